@@ -52,12 +52,228 @@ pub struct CreateUserArgs {
     pub password: Option<String>,
 }
 
+/// Account name or IP address named by the `ban` and `unban` subcommands.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+#[group(required = true, multiple = false)]
+pub struct BanTargetArgs {
+    /// Account name to match.
+    #[arg(long)]
+    pub username: Option<String>,
+    /// Client IP address to match.
+    #[arg(long)]
+    pub address: Option<String>,
+}
+
+/// Arguments for the `ban` administrative subcommand.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+pub struct BanArgs {
+    /// Account name or address to ban.
+    #[command(flatten)]
+    pub target: BanTargetArgs,
+    /// Note recorded with the ban.
+    #[arg(long)]
+    pub reason: Option<String>,
+    /// Lift the ban after this many seconds; unset bans until `unban`.
+    #[arg(long)]
+    pub expires_in_secs: Option<u64>,
+}
+
+/// Arguments for the `init` administrative subcommand.
+///
+/// Anything left unset is asked for on a terminal, or takes its default
+/// with `--non-interactive`.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+pub struct InitArgs {
+    /// Configuration file to write; defaults to `.mxd.toml`.
+    #[arg(long)]
+    pub config_path: Option<String>,
+    /// Address the server listens on; defaults to `0.0.0.0:5500`.
+    #[arg(long)]
+    pub bind: Option<String>,
+    /// Database connection string or path; defaults to `mxd.db`.
+    #[arg(long)]
+    pub database: Option<String>,
+    /// Name of the first administrator account; defaults to `admin`.
+    #[arg(long)]
+    pub admin_username: Option<String>,
+    /// Password of the first administrator account.
+    #[serde(skip_serializing)]
+    #[arg(long)]
+    pub admin_password: Option<String>,
+    /// Generate a self-signed TLS certificate and key beside the
+    /// configuration file.
+    #[arg(long)]
+    pub tls: bool,
+    /// Take the defaults instead of asking for anything left unset.
+    #[arg(long)]
+    pub non_interactive: bool,
+    /// Replace an existing configuration file.
+    #[arg(long)]
+    pub force: bool,
+}
+
 /// CLI subcommands exposed by `mxd`.
 #[derive(Subcommand, Deserialize, Serialize, Debug, Clone)]
 pub enum Commands {
+    /// Write a configuration file, prepare the database, and create the
+    /// first administrator account.
+    #[command(name = "init")]
+    Init(InitArgs),
     /// Create a new user account.
     #[command(name = "create-user")]
     CreateUser(CreateUserArgs),
+    /// Ban an account name or IP address.
+    #[command(name = "ban")]
+    Ban(BanArgs),
+    /// Remove the ban on an account name or IP address.
+    #[command(name = "unban")]
+    Unban(BanTargetArgs),
+    /// List recorded bans, including expired ones.
+    #[command(name = "list-bans")]
+    ListBans,
+    /// Report on user accounts.
+    #[command(name = "users", subcommand)]
+    Users(UsersCommand),
+    /// Apply pending database migrations without starting the server.
+    #[command(name = "migrate")]
+    Migrate(MigrateArgs),
+    /// Look after the database.
+    #[command(name = "db", subcommand)]
+    Db(DbCommand),
+    /// Look after the news database.
+    #[command(name = "news", subcommand)]
+    News(NewsCommand),
+    /// Manage the file area.
+    #[command(name = "files", subcommand)]
+    Files(FilesCommand),
+}
+
+/// Arguments for the `migrate` administrative subcommand.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+pub struct MigrateArgs {
+    /// Build indexes concurrently and backfill in batches, so a large
+    /// `PostgreSQL` database stays writable while it upgrades.
+    #[arg(long)]
+    pub online: bool,
+}
+
+/// Subcommands of `db`.
+#[derive(Subcommand, Deserialize, Serialize, Debug, Clone)]
+pub enum DbCommand {
+    /// Reclaim space, refresh planner statistics, and rebuild the indexes
+    /// searches use.
+    #[command(name = "maintain")]
+    Maintain,
+}
+
+/// Subcommands of `news`.
+#[derive(Subcommand, Deserialize, Serialize, Debug, Clone)]
+pub enum NewsCommand {
+    /// Check and repair the links that thread news articles together.
+    #[command(name = "fsck")]
+    Fsck(NewsFsckArgs),
+    /// Show or change which categories an account gets digests of.
+    #[command(name = "digest")]
+    Digest(NewsDigestArgs),
+    /// List the news roots clients may select at login, or add one.
+    #[command(name = "roots")]
+    Roots(NewsRootsArgs),
+}
+
+/// Arguments for the `news fsck` administrative subcommand.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+pub struct NewsFsckArgs {
+    /// Report broken links without repairing them.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Arguments for the `news digest` administrative subcommand.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+pub struct NewsDigestArgs {
+    /// Account whose digest to show or change.
+    #[arg(long)]
+    pub username: String,
+    /// Comma-separated category paths, such as `General,Tech/Rust`, to
+    /// subscribe the account to, replacing any it already follows.
+    #[arg(long, conflicts_with = "off")]
+    pub categories: Option<String>,
+    /// Address to email digests to; without one they are delivered at login.
+    #[arg(long, requires = "categories")]
+    pub email: Option<String>,
+    /// Stop sending the account digests.
+    #[arg(long)]
+    pub off: bool,
+}
+
+/// Arguments for the `news roots` administrative subcommand.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+pub struct NewsRootsArgs {
+    /// Name of a new root to add before listing.
+    #[arg(long)]
+    pub add: Option<String>,
+}
+
+/// Subcommands of `files`.
+#[derive(Subcommand, Deserialize, Serialize, Debug, Clone)]
+pub enum FilesCommand {
+    /// Make a folder a drop box, whose contents only users with View Drop
+    /// Boxes may list or download.
+    #[command(name = "drop-box")]
+    DropBox(DropBoxArgs),
+}
+
+/// Arguments for the `files drop-box` administrative subcommand.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+pub struct DropBoxArgs {
+    /// Folder path, such as `Uploads` or `Docs/Submissions`.
+    #[arg(long)]
+    pub path: String,
+    /// Make the folder an ordinary folder again.
+    #[arg(long)]
+    pub off: bool,
+}
+
+/// Subcommands of `users`.
+#[derive(Subcommand, Deserialize, Serialize, Debug, Clone)]
+pub enum UsersCommand {
+    /// Report the bytes and files each account has transferred.
+    #[command(name = "stats")]
+    Stats,
+    /// Show or change an account's download credits.
+    #[command(name = "credits")]
+    Credits(CreditsArgs),
+    /// Show or change an account's storage quota.
+    #[command(name = "quota")]
+    Quota(QuotaArgs),
+}
+
+/// Arguments for the `users credits` administrative subcommand.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+pub struct CreditsArgs {
+    /// Account whose credits to show or change.
+    #[arg(long)]
+    pub username: String,
+    /// Credits, in bytes, to add; a negative value removes credits.
+    #[arg(long, allow_hyphen_values = true, conflicts_with = "set")]
+    pub add: Option<i64>,
+    /// Replace the balance with this many credits.
+    #[arg(long)]
+    pub set: Option<i64>,
+}
+
+/// Arguments for the `users quota` administrative subcommand.
+#[derive(Args, Deserialize, Serialize, Default, Debug, Clone)]
+pub struct QuotaArgs {
+    /// Account whose quota to show or change.
+    #[arg(long)]
+    pub username: String,
+    /// Limit the account to storing this many bytes of file content.
+    #[arg(long, value_parser = clap::value_parser!(i64).range(0..), conflicts_with = "clear")]
+    pub set: Option<i64>,
+    /// Remove the account's quota.
+    #[arg(long)]
+    pub clear: bool,
 }
 
 /// Runtime configuration shared by all binaries.
@@ -69,7 +285,8 @@ pub enum Commands {
 #[derive(Args, OrthoConfig, Serialize, Deserialize, Default, Debug, Clone)]
 #[ortho_config(prefix = "MXD_")]
 pub struct AppConfig {
-    /// Server bind address.
+    /// Server bind address, or a comma-separated list of addresses to listen
+    /// on together.
     #[ortho_config(default = "0.0.0.0:5500".to_owned())]
     #[arg(long)]
     pub bind: String,
@@ -80,6 +297,17 @@ pub struct AppConfig {
     /// Optional migration timeout in seconds.
     #[arg(long)]
     pub migration_timeout_secs: Option<u64>,
+    /// Most database connections held open at once; defaults to 10.
+    #[arg(long)]
+    pub db_pool_size: Option<u32>,
+    /// Seconds a request waits for a free database connection before it
+    /// fails with error 24; defaults to 30.
+    #[arg(long)]
+    pub db_connection_timeout_secs: Option<u64>,
+    /// Seconds an unused database connection stays open before it is
+    /// closed; defaults to 600.
+    #[arg(long)]
+    pub db_idle_timeout_secs: Option<u64>,
     /// Argon2 memory cost parameter.
     #[ortho_config(default = DEFAULT_ARGON2_M_COST)]
     #[arg(long)]
@@ -92,6 +320,263 @@ pub struct AppConfig {
     #[ortho_config(default = DEFAULT_ARGON2_P_COST)]
     #[arg(long)]
     pub argon2_p_cost: u32,
+    /// Password verifications run at once; defaults to one per CPU core.
+    #[arg(long)]
+    pub hashing_concurrency: Option<usize>,
+    /// Password verifications allowed to wait before logins are shed;
+    /// defaults to 64.
+    #[arg(long)]
+    pub hashing_queue_limit: Option<usize>,
+    /// Tokio worker threads; defaults to one per CPU core.
+    #[arg(long)]
+    pub worker_threads: Option<usize>,
+    /// Upper bound on Tokio's blocking thread pool; defaults to 512.
+    #[arg(long)]
+    pub max_blocking_threads: Option<usize>,
+    /// Scheduler ticks between I/O and timer polls; defaults to 61.
+    #[arg(long)]
+    pub event_interval: Option<u32>,
+    /// Prefix queries with a `/* trace_id=... */` comment naming the
+    /// transaction that issued them.
+    #[ortho_config(default = false)]
+    #[arg(long)]
+    pub sql_trace_comments: bool,
+    /// How to answer unsupported transaction types: `error` (the default),
+    /// `ignore`, or `disconnect`.
+    #[arg(long)]
+    pub unknown_transactions: Option<String>,
+    /// Unsupported transactions a connection may send before the
+    /// `disconnect` policy closes it; defaults to 8.
+    #[arg(long)]
+    pub unknown_transaction_limit: Option<u32>,
+    /// Text file clients must accept after login before they go online.
+    #[arg(long)]
+    pub agreement_path: Option<String>,
+    /// Image file served to clients that request the server banner.
+    #[arg(long)]
+    pub banner_path: Option<String>,
+    /// Server rules shown to clients that ask for help.
+    #[arg(long)]
+    pub server_rules: Option<String>,
+    /// How to reach the server's operators, shown alongside the rules.
+    #[arg(long)]
+    pub server_contact: Option<String>,
+    /// What may be uploaded and shared, shown alongside the rules.
+    #[arg(long)]
+    pub server_file_policy: Option<String>,
+    /// Refuse every news request, for servers that do not offer news.
+    #[ortho_config(default = false)]
+    #[arg(long)]
+    pub disable_news: bool,
+    /// Refuse every file request, for servers that do not offer files.
+    #[ortho_config(default = false)]
+    #[arg(long)]
+    pub disable_files: bool,
+    /// Refuse public chat, for servers that do not offer it.
+    #[ortho_config(default = false)]
+    #[arg(long)]
+    pub disable_chat: bool,
+    /// Also serve the legacy runtime on this address from the Wireframe
+    /// server, sharing its database, to compare the two during migration.
+    #[arg(long)]
+    pub legacy_bind: Option<String>,
+    /// Seconds a connection may go without sending a transaction before it
+    /// is closed; unset keeps idle connections open.
+    #[arg(long)]
+    pub idle_timeout_secs: Option<u64>,
+    /// Most clients connected at once; unset leaves the number unlimited.
+    #[arg(long)]
+    pub max_connections: Option<usize>,
+    /// Most clients connected at once from one IP address; unset leaves the
+    /// number unlimited.
+    #[arg(long)]
+    pub max_connections_per_ip: Option<usize>,
+    /// Seconds a fragmented request may take to arrive in full before the
+    /// connection is closed; defaults to 30.
+    #[arg(long)]
+    pub reassembly_timeout_secs: Option<u64>,
+    /// Seconds to wait for more of a frame a client is sending before the
+    /// connection is closed; defaults to 5.
+    #[arg(long)]
+    pub read_timeout_secs: Option<u64>,
+    /// Seconds to wait for a client to accept a frame before giving up on
+    /// it; defaults to 3.
+    #[arg(long)]
+    pub write_timeout_secs: Option<u64>,
+    /// Most fields one request may carry; defaults to 4096.
+    #[arg(long)]
+    pub max_param_count: Option<u16>,
+    /// PEM certificate chain for accepting clients over TLS; set together
+    /// with `tls_key`.
+    #[arg(long)]
+    pub tls_cert: Option<String>,
+    /// PEM private key for `tls_cert`.
+    #[arg(long)]
+    pub tls_key: Option<String>,
+    /// Accept TLS clients without negotiating ALPN, as "Hotline over TLS"
+    /// proxies expect.
+    #[ortho_config(default = false)]
+    #[arg(long)]
+    pub tls_no_alpn: bool,
+    /// Seconds between pings sent to measure each client's round-trip time;
+    /// unset turns pings off.
+    #[arg(long)]
+    pub ping_interval_secs: Option<u64>,
+    /// Pings in a row a client that answers pings may miss before it is
+    /// disconnected; defaults to 3.
+    #[arg(long)]
+    pub ping_miss_limit: Option<u32>,
+    /// How to treat clients that XOR their text: `permissive` (the default)
+    /// translates it, `strict` disconnects them, and `disabled` never looks.
+    #[arg(long)]
+    pub compat_policy: Option<String>,
+    /// Clear a user's away message (automatic response) as soon as they
+    /// send a request other than a keep-alive or a user info update.
+    #[ortho_config(default = false)]
+    #[arg(long)]
+    pub clear_away_on_activity: bool,
+    /// Restrict downloads: `off` (the default), `ratio`, or `credits`.
+    #[arg(long)]
+    pub download_policy: Option<String>,
+    /// Bytes a user may download for each byte uploaded under the `ratio`
+    /// policy; defaults to 1.
+    #[arg(long)]
+    pub download_ratio: Option<u32>,
+    /// Bytes a user may download under the `ratio` policy before uploading
+    /// anything; defaults to 0.
+    #[arg(long)]
+    pub download_free_bytes: Option<u64>,
+    /// Comma-separated privilege names, such as `DISCONNECT_USER`, whose
+    /// holders are exempt from the download policy.
+    #[arg(long)]
+    pub download_exempt_privileges: Option<String>,
+    /// Failed logins an address or account may make before it is locked
+    /// out; defaults to 5, and 0 turns lockouts off.
+    #[arg(long)]
+    pub login_failure_limit: Option<u32>,
+    /// Seconds the first lockout lasts; each further failure doubles it.
+    /// Defaults to 30.
+    #[arg(long)]
+    pub login_lockout_secs: Option<u64>,
+    /// Longest a lockout may last, in seconds; defaults to 3600.
+    #[arg(long)]
+    pub login_lockout_max_secs: Option<u64>,
+    /// Seconds the session token issued at login stays valid for logging in
+    /// again without the password; unset issues no tokens.
+    #[arg(long)]
+    pub session_resume_secs: Option<u64>,
+    /// Requests a second each connection may sustain; unset or zero leaves
+    /// connections unlimited.
+    #[arg(long)]
+    pub rate_limit_per_sec: Option<u32>,
+    /// Requests a connection may send in a burst; defaults to
+    /// `rate_limit_per_sec`.
+    #[arg(long)]
+    pub rate_limit_burst: Option<u32>,
+    /// Requests a second all connections from one address may sustain
+    /// together; unset or zero leaves addresses unlimited.
+    #[arg(long)]
+    pub address_rate_limit_per_sec: Option<u32>,
+    /// Requests the connections from one address may send in a burst;
+    /// defaults to `address_rate_limit_per_sec`.
+    #[arg(long)]
+    pub address_rate_limit_burst: Option<u32>,
+    /// Comma-separated file-area folder paths, such as `Uploads,Docs/Specs`,
+    /// to snapshot into read-only archive folders.
+    #[arg(long)]
+    pub archive_folders: Option<String>,
+    /// Seconds between snapshots of each archived folder; defaults to 86400.
+    #[arg(long)]
+    pub archive_interval_secs: Option<u64>,
+    /// Snapshots kept for each archived folder; defaults to 7.
+    #[arg(long)]
+    pub archive_keep: Option<u32>,
+    /// Transfers allowed to run at once across the server; unset or zero
+    /// means no limit.
+    #[arg(long)]
+    pub max_transfers: Option<usize>,
+    /// Transfers allowed to run at once for one account; unset or zero means
+    /// no limit.
+    #[arg(long)]
+    pub max_transfers_per_user: Option<usize>,
+    /// Bytes one file upload may send; unset or zero means no limit.
+    #[arg(long)]
+    pub max_upload_bytes: Option<u64>,
+    /// Seconds the Wireframe server lets running file transfers finish after
+    /// a shutdown signal before it exits; defaults to 10.
+    #[arg(long)]
+    pub shutdown_grace_secs: Option<u64>,
+    /// Where file content is stored: a directory path or `file://` URL, or
+    /// `s3://bucket/prefix` when built with the `s3` feature.
+    #[arg(long)]
+    pub storage_url: Option<String>,
+    /// Seconds between scheduled database maintenance runs; unset leaves
+    /// maintenance to `mxd db maintain`.
+    #[arg(long)]
+    pub maintenance_interval_secs: Option<u64>,
+    /// UTC time range, such as `02:00-05:00`, in which scheduled maintenance
+    /// may start; unset allows any time.
+    #[arg(long)]
+    pub maintenance_window: Option<String>,
+    /// Transactions per minute above which scheduled maintenance waits for
+    /// a quieter moment; defaults to 30.
+    #[arg(long)]
+    pub maintenance_max_activity: Option<u64>,
+    /// Check and repair the links that thread news articles together before
+    /// accepting connections.
+    #[ortho_config(default = false)]
+    #[arg(long)]
+    pub news_fsck_on_startup: bool,
+    /// Seconds between builds of the news digests accounts opted in to;
+    /// defaults to 3600.
+    #[arg(long)]
+    pub news_digest_interval_secs: Option<u64>,
+    /// Sendmail-compatible program, such as `/usr/sbin/sendmail`, used to
+    /// email digests to accounts with an address; unset delivers every
+    /// digest at login.
+    #[arg(long)]
+    pub news_digest_sendmail: Option<String>,
+    /// `From` address of emailed digests; unset leaves it to the sendmail
+    /// program.
+    #[arg(long)]
+    pub news_digest_from: Option<String>,
+    /// Bytes above which a news article body is sent over the transfer port
+    /// to clients that ask for it; unset sends every body inline.
+    #[arg(long)]
+    pub news_transfer_threshold: Option<u32>,
+    /// Where session start and stop records are sent: a file path, an
+    /// `http://` URL, or `syslog`; unset keeps no records.
+    #[arg(long)]
+    pub accounting_sink: Option<String>,
+    /// Address of the debug HTTP server serving CPU and heap profiles, such
+    /// as `127.0.0.1:6060`; needs a build with the `profiling` feature.
+    #[arg(long)]
+    pub profiling_bind: Option<String>,
+    /// Address of the HTTP server answering `/healthz` and `/readyz`, such
+    /// as `127.0.0.1:8080`; unset serves no probes.
+    #[arg(long)]
+    pub health_bind: Option<String>,
+    /// Where logs are written: `stderr` (the default), a file such as
+    /// `file:/var/log/mxd.log`, or, on Unix, `syslog`, optionally with a
+    /// facility such as `syslog:local0`.
+    #[arg(long)]
+    pub log_target: Option<String>,
+    /// When the log file is rotated by time: `hourly`, `daily`, or `never`
+    /// (the default).
+    #[arg(long)]
+    pub log_rotation: Option<String>,
+    /// Size in bytes the log file is rotated before exceeding; unset never
+    /// rotates by size.
+    #[arg(long)]
+    pub log_max_bytes: Option<u64>,
+    /// Rotated log files kept beside the active one; defaults to 7.
+    #[arg(long)]
+    pub log_max_files: Option<u16>,
+    /// Payload limits for chosen transaction types, such as
+    /// `SendChat=4096,PostNewsArticle=4194304`; other types accept up to
+    /// 1 MiB.
+    #[arg(long)]
+    pub payload_limits: Option<String>,
 }
 
 /// Top-level CLI entry point consumed by binaries.
@@ -0,0 +1,154 @@
+//! Derive macros for reading request structs from transaction parameters.
+//!
+//! `#[derive(TransactionParams)]` implements
+//! `mxd_proto::transaction::TransactionParams` for a struct with named
+//! fields. Every field names the parameter it is read from:
+//!
+//! ```ignore
+//! #[derive(TransactionParams)]
+//! struct DeleteArticleRequest {
+//!     #[param(NewsPath)]
+//!     path: String,
+//!     #[param(NewsArticleId)]
+//!     article_id: i32,
+//!     #[param(NewsRecursiveDelete)]
+//!     recursive: bool,
+//! }
+//! ```
+//!
+//! The field's type decides how the value is decoded and whether it may be
+//! left out, through its `ParamValue` implementation. A modifier may follow
+//! the field id: `default` uses the type's default when the parameter is
+//! absent, and `omit_blank` reads an `Option` that treats a zero or empty
+//! value as absent. Errors point at the attribute or field at fault; the
+//! cases are kept in `tests/ui`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, quote_spanned};
+use syn::{Data, DeriveInput, Field, Fields, Ident, Type, parse_macro_input, spanned::Spanned};
+
+/// Implement `TransactionParams` from the `#[param(...)]` attribute on each
+/// field.
+#[proc_macro_derive(TransactionParams, attributes(param))]
+pub fn derive_transaction_params(input: TokenStream) -> TokenStream {
+    let parsed = parse_macro_input!(input as DeriveInput);
+    expand(&parsed)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// What a field holds when its parameter is absent.
+enum Presence {
+    /// Whatever the type's `ParamValue` implementation decides.
+    Required,
+    /// The type's default.
+    Default,
+    /// `None`, as it is for zero or empty values.
+    OmitBlank,
+}
+
+/// A parsed `#[param(...)]` attribute.
+struct ParamAttr {
+    field_id: Ident,
+    presence: Presence,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "TransactionParams can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(
+            data.fields.span(),
+            "TransactionParams needs named fields",
+        ));
+    };
+    let inits = fields
+        .named
+        .iter()
+        .map(field_init)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::mxd_proto::transaction::TransactionParams
+            for #name #ty_generics #where_clause
+        {
+            fn from_params<V: ::core::convert::AsRef<[u8]>, S: ::std::hash::BuildHasher>(
+                params: &::std::collections::HashMap<
+                    ::mxd_proto::field_id::FieldId,
+                    ::std::vec::Vec<V>,
+                    S,
+                >,
+            ) -> ::core::result::Result<Self, ::mxd_proto::transaction::TransactionError> {
+                ::core::result::Result::Ok(Self { #(#inits,)* })
+            }
+        }
+    })
+}
+
+fn field_init(member: &Field) -> syn::Result<TokenStream2> {
+    let name = member
+        .ident
+        .as_ref()
+        .ok_or_else(|| syn::Error::new(member.span(), "expected a named field"))?;
+    let ParamAttr { field_id, presence } = param_attr(member)?;
+    let read = match presence {
+        Presence::Required => quote!(param),
+        Presence::Default => quote!(param_or_default),
+        Presence::OmitBlank if is_option(&member.ty) => quote!(param_unless_blank),
+        Presence::OmitBlank => {
+            return Err(syn::Error::new(
+                member.ty.span(),
+                "`omit_blank` needs an `Option` field",
+            ));
+        }
+    };
+    let field = quote_spanned!(field_id.span()=> ::mxd_proto::field_id::FieldId::#field_id);
+    let ty = &member.ty;
+    Ok(quote_spanned! {ty.span()=>
+        #name: ::mxd_proto::transaction::#read(params, #field)?
+    })
+}
+
+/// Whether `ty` is spelled as an `Option`.
+fn is_option(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "Option")
+}
+
+fn param_attr(member: &Field) -> syn::Result<ParamAttr> {
+    let attr = member
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("param"))
+        .ok_or_else(|| syn::Error::new(member.span(), "missing #[param(FieldId)] attribute"))?;
+    let mut named = None;
+    let mut presence = Presence::Required;
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("default") {
+            presence = Presence::Default;
+        } else if meta.path.is_ident("omit_blank") {
+            presence = Presence::OmitBlank;
+        } else if let Some(ident) = meta.path.get_ident()
+            && named.is_none()
+        {
+            named = Some(ident.clone());
+        } else {
+            return Err(meta.error("expected one FieldId variant, `default`, or `omit_blank`"));
+        }
+        Ok(())
+    })?;
+    let field_id =
+        named.ok_or_else(|| syn::Error::new(attr.span(), "#[param] needs a FieldId variant"))?;
+    Ok(ParamAttr { field_id, presence })
+}
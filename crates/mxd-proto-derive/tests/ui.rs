@@ -0,0 +1,13 @@
+//! Compile-time checks for `#[derive(TransactionParams)]`.
+//!
+//! Each file under `tests/ui/pass` must build and run; each under
+//! `tests/ui/fail` must be rejected with the diagnostic recorded beside it.
+//! Run with `TRYBUILD=overwrite` to record new diagnostics after changing a
+//! message.
+
+#[test]
+fn derive_accepts_and_rejects_request_structs() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/pass/*.rs");
+    cases.compile_fail("tests/ui/fail/*.rs");
+}
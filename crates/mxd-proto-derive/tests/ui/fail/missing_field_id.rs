@@ -0,0 +1,9 @@
+use mxd_proto::transaction::TransactionParams;
+
+#[derive(TransactionParams)]
+struct Request {
+    #[param(default)]
+    flags: i32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use mxd_proto::transaction::TransactionParams;
+
+#[derive(TransactionParams)]
+struct Request {
+    #[param(NewsPath)]
+    path: String,
+    name: String,
+}
+
+fn main() {}
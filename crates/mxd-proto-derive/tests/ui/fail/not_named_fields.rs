@@ -0,0 +1,11 @@
+use mxd_proto::transaction::TransactionParams;
+
+#[derive(TransactionParams)]
+struct Request(String);
+
+#[derive(TransactionParams)]
+enum Choice {
+    Path(String),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use mxd_proto::transaction::TransactionParams;
+
+#[derive(TransactionParams)]
+struct Request {
+    #[param(NewsArticleId, omit_blank)]
+    parent_id: i32,
+}
+
+fn main() {}
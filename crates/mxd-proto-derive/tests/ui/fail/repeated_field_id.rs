@@ -0,0 +1,9 @@
+use mxd_proto::transaction::TransactionParams;
+
+#[derive(TransactionParams)]
+struct Request {
+    #[param(NewsPath, FilePath)]
+    path: String,
+}
+
+fn main() {}
@@ -0,0 +1,46 @@
+//! A repeatable parameter yields its first value, two members may read the
+//! same parameter as different types, and a parameter that may not repeat
+//! is refused when it does.
+
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use mxd_proto::{
+    field_id::FieldId,
+    transaction::{TransactionError, TransactionParams, encode_params},
+};
+
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
+struct Request {
+    #[param(FileName)]
+    name: String,
+    #[param(FileName)]
+    raw_name: Vec<u8>,
+    #[param(UserId, default)]
+    user_id: u32,
+}
+
+fn main() -> Result<(), TransactionError> {
+    let sent = encode_params::<&[u8]>(&[
+        (FieldId::FileName, b"first".as_slice()),
+        (FieldId::FileName, b"second".as_slice()),
+    ])?;
+    assert_eq!(
+        Request::from_payload(&sent)?,
+        Request {
+            name: "first".to_owned(),
+            raw_name: b"first".to_vec(),
+            user_id: 0,
+        }
+    );
+
+    let repeated = encode_params::<&[u8]>(&[
+        (FieldId::FileName, b"first".as_slice()),
+        (FieldId::UserId, &7_u16.to_be_bytes()),
+        (FieldId::UserId, &9_u16.to_be_bytes()),
+    ])?;
+    assert!(matches!(
+        Request::from_payload(&repeated),
+        Err(TransactionError::DuplicateField(103))
+    ));
+    Ok(())
+}
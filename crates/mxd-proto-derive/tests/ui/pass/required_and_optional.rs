@@ -0,0 +1,43 @@
+//! Required, optional, defaulted, and blank-skipping members.
+
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use mxd_proto::{
+    field_id::FieldId,
+    transaction::{TransactionError, TransactionParams, encode_params},
+};
+
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
+struct Request {
+    #[param(NewsPath)]
+    path: String,
+    #[param(FilePath)]
+    folder: Option<Vec<u8>>,
+    #[param(NewsArticleFlags, default)]
+    flags: i32,
+    #[param(NewsArticleId, omit_blank)]
+    parent_id: Option<i32>,
+}
+
+fn main() -> Result<(), TransactionError> {
+    let sent = encode_params::<&[u8]>(&[
+        (FieldId::NewsPath, b"General".as_slice()),
+        (FieldId::NewsArticleId, &0_i32.to_be_bytes()),
+    ])?;
+    assert_eq!(
+        Request::from_payload(&sent)?,
+        Request {
+            path: "General".to_owned(),
+            folder: None,
+            flags: 0,
+            parent_id: None,
+        }
+    );
+
+    let missing = encode_params::<&[u8]>(&[(FieldId::FilePath, &[0, 0])])?;
+    assert!(matches!(
+        Request::from_payload(&missing),
+        Err(TransactionError::MissingField(FieldId::NewsPath))
+    ));
+    Ok(())
+}
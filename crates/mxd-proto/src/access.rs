@@ -0,0 +1,234 @@
+//! Access each transaction type requires before its handler runs.
+//!
+//! [`ACCESS_TABLE`] is the single declaration of who may send which request.
+//! The server's dispatcher consults [`required_access`] before invoking a
+//! handler, so handlers only refine the check where the privilege depends on
+//! the target, such as Delete File needing Delete Folder for folders. The
+//! verification crate checks the table against its model's request
+//! catalogue.
+
+use crate::{privileges::Privileges, transaction_type::TransactionType};
+
+/// Session state a transaction type requires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// Any connection may send the transaction.
+    Open,
+    /// The connection must have logged in.
+    Authenticated,
+    /// The connection must have logged in and accepted any agreement.
+    Online,
+    /// The connection must have logged in and hold every listed privilege.
+    Privilege(Privileges),
+    /// The connection must be online and hold every listed privilege.
+    OnlinePrivilege(Privileges),
+}
+
+impl Access {
+    /// Whether the transaction is refused before login.
+    #[must_use]
+    pub const fn requires_authentication(self) -> bool { !matches!(self, Self::Open) }
+
+    /// Whether the transaction is refused until the agreement is accepted.
+    #[must_use]
+    pub const fn requires_online(self) -> bool {
+        matches!(self, Self::Online | Self::OnlinePrivilege(_))
+    }
+
+    /// Privileges the transaction requires; empty when none are needed.
+    #[must_use]
+    pub const fn privileges(self) -> Privileges {
+        match self {
+            Self::Privilege(privileges) | Self::OnlinePrivilege(privileges) => privileges,
+            Self::Open | Self::Authenticated | Self::Online => Privileges::empty(),
+        }
+    }
+}
+
+/// Access required by each client request the server handles.
+///
+/// Transaction types absent from the table, such as server pushes and
+/// unknown identifiers, are [`Access::Open`]; the dispatcher answers them
+/// without running a handler.
+pub const ACCESS_TABLE: &[(TransactionType, Access)] = &[
+    (TransactionType::Login, Access::Open),
+    (TransactionType::KeepAlive, Access::Open),
+    // The rules are meant to be read before deciding to log in.
+    (TransactionType::GetServerRules, Access::Open),
+    (TransactionType::Agreed, Access::Authenticated),
+    (TransactionType::SetClientUserInfo, Access::Authenticated),
+    (TransactionType::Logout, Access::Authenticated),
+    (TransactionType::DownloadBanner, Access::Authenticated),
+    // Deleting a bundle or category needs a privilege that depends on what
+    // the path names, so the handler checks it after the lookup.
+    (TransactionType::DeleteNewsItem, Access::Authenticated),
+    // Search covers files and news, each behind its own privilege, so the
+    // handler searches only the parts the session may read.
+    (TransactionType::Search, Access::Authenticated),
+    // Deleting or moving needs the file or folder privilege for the entry's
+    // kind, so the handler checks it once the entry has been found.
+    (TransactionType::DeleteFile, Access::Authenticated),
+    (TransactionType::MoveFile, Access::Authenticated),
+    // Anyone may read the details of an entry they can see. Renaming and
+    // commenting need kind-specific privileges the handler checks.
+    (TransactionType::GetFileInfo, Access::Authenticated),
+    (TransactionType::SetFileInfo, Access::Authenticated),
+    (TransactionType::GetUserNameList, Access::Online),
+    (
+        TransactionType::SendChat,
+        Access::OnlinePrivilege(Privileges::SEND_CHAT),
+    ),
+    (
+        TransactionType::SendInstantMsg,
+        Access::OnlinePrivilege(Privileges::SEND_PRIVATE_MESSAGE),
+    ),
+    (
+        TransactionType::UserBroadcast,
+        Access::OnlinePrivilege(Privileges::BROADCAST),
+    ),
+    (
+        TransactionType::DisconnectUser,
+        Access::OnlinePrivilege(Privileges::DISCONNECT_USER),
+    ),
+    (
+        TransactionType::GetClientInfoText,
+        Access::Privilege(Privileges::GET_CLIENT_INFO),
+    ),
+    (
+        TransactionType::GetFileNameList,
+        Access::Privilege(Privileges::DOWNLOAD_FILE),
+    ),
+    (
+        TransactionType::DownloadFile,
+        Access::Privilege(Privileges::DOWNLOAD_FILE),
+    ),
+    (
+        TransactionType::UploadFile,
+        Access::Privilege(Privileges::UPLOAD_FILE),
+    ),
+    (
+        TransactionType::NewsCategoryNameList,
+        Access::Privilege(Privileges::NEWS_READ_ARTICLE),
+    ),
+    (
+        TransactionType::NewsArticleNameList,
+        Access::Privilege(Privileges::NEWS_READ_ARTICLE),
+    ),
+    (
+        TransactionType::NewsArticleData,
+        Access::Privilege(Privileges::NEWS_READ_ARTICLE),
+    ),
+    (
+        TransactionType::PostNewsArticle,
+        Access::Privilege(Privileges::NEWS_POST_ARTICLE),
+    ),
+    (
+        TransactionType::DeleteNewsArticle,
+        Access::Privilege(Privileges::NEWS_DELETE_ARTICLE),
+    ),
+    (
+        TransactionType::NewNewsFolder,
+        Access::Privilege(Privileges::NEWS_CREATE_FOLDER),
+    ),
+    (
+        TransactionType::NewNewsCategory,
+        Access::Privilege(Privileges::NEWS_CREATE_CATEGORY),
+    ),
+    (
+        TransactionType::GetAccounts,
+        Access::Privilege(Privileges::OPEN_USER),
+    ),
+    (
+        TransactionType::GetUser,
+        Access::Privilege(Privileges::OPEN_USER),
+    ),
+    (
+        TransactionType::NewUser,
+        Access::Privilege(Privileges::CREATE_USER),
+    ),
+    (
+        TransactionType::DeleteUser,
+        Access::Privilege(Privileges::DELETE_USER),
+    ),
+    (
+        TransactionType::SetUser,
+        Access::Privilege(Privileges::MODIFY_USER),
+    ),
+];
+
+/// Access required to send a transaction of type `ty`.
+#[must_use]
+pub fn required_access(ty: TransactionType) -> Access {
+    ACCESS_TABLE
+        .iter()
+        .find(|(listed, _)| *listed == ty)
+        .map_or(Access::Open, |&(_, access)| access)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for the access table.
+    use rstest::rstest;
+
+    use super::*;
+
+    #[test]
+    fn table_lists_each_type_once() {
+        for (index, (ty, _)) in ACCESS_TABLE.iter().enumerate() {
+            let repeats = ACCESS_TABLE[index + 1..]
+                .iter()
+                .filter(|(other, _)| other == ty)
+                .count();
+            assert_eq!(repeats, 0, "{ty} is listed more than once");
+        }
+    }
+
+    #[rstest]
+    #[case(TransactionType::Login, Access::Open)]
+    #[case(TransactionType::Agreed, Access::Authenticated)]
+    #[case(TransactionType::DeleteFile, Access::Authenticated)]
+    #[case(TransactionType::MoveFile, Access::Authenticated)]
+    #[case(TransactionType::GetFileInfo, Access::Authenticated)]
+    #[case(TransactionType::SetFileInfo, Access::Authenticated)]
+    #[case(TransactionType::GetUserNameList, Access::Online)]
+    #[case(
+        TransactionType::SendChat,
+        Access::OnlinePrivilege(Privileges::SEND_CHAT)
+    )]
+    #[case(
+        TransactionType::NewsArticleData,
+        Access::Privilege(Privileges::NEWS_READ_ARTICLE)
+    )]
+    #[case(TransactionType::ChatMsg, Access::Open)]
+    #[case(TransactionType::Other(9999), Access::Open)]
+    fn looks_up_required_access(#[case] ty: TransactionType, #[case] expected: Access) {
+        assert_eq!(required_access(ty), expected);
+    }
+
+    #[rstest]
+    #[case(Access::Open, false, false, Privileges::empty())]
+    #[case(Access::Authenticated, true, false, Privileges::empty())]
+    #[case(Access::Online, true, true, Privileges::empty())]
+    #[case(
+        Access::Privilege(Privileges::DELETE_USER),
+        true,
+        false,
+        Privileges::DELETE_USER
+    )]
+    #[case(
+        Access::OnlinePrivilege(Privileges::BROADCAST),
+        true,
+        true,
+        Privileges::BROADCAST
+    )]
+    fn describes_requirements(
+        #[case] access: Access,
+        #[case] authenticated: bool,
+        #[case] online: bool,
+        #[case] privileges: Privileges,
+    ) {
+        assert_eq!(access.requires_authentication(), authenticated);
+        assert_eq!(access.requires_online(), online);
+        assert_eq!(access.privileges(), privileges);
+    }
+}
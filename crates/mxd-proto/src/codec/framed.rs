@@ -10,15 +10,18 @@
 //! ```rust,ignore
 //! use tokio::net::TcpStream;
 //! use tokio_util::codec::Framed;
-//! use mxd::wireframe::codec::HotlineCodec;
+//! use mxd_proto::codec::HotlineCodec;
 //!
 //! async fn handle_connection(stream: TcpStream) {
 //!     let mut framed = Framed::new(stream, HotlineCodec::new());
 //!     // Use framed.next() and framed.send() for frame I/O
 //! }
 //! ```
+//!
+//! A fragmented transaction must arrive in full within the codec's reassembly
+//! age limit; see [`crate::transaction::reassembly`].
 
-use std::io;
+use std::{io, time::Duration};
 
 use bytes::{BufMut, BytesMut};
 use tokio_util::codec::{Decoder, Encoder};
@@ -28,8 +31,11 @@ use crate::transaction::{
     FrameHeader,
     HEADER_LEN,
     MAX_FRAME_DATA,
+    MAX_NEGOTIATED_FRAME_DATA,
     MAX_PAYLOAD_SIZE,
+    PayloadLimits,
     TransactionError,
+    reassembly::{DEFAULT_REASSEMBLY_TIMEOUT, Reassembly},
 };
 
 /// Tokio codec for Hotline transaction framing.
@@ -52,10 +58,38 @@ use crate::transaction::{
 /// | total_size  | 12     | 4    | Total payload size across fragments   |
 /// | data_size   | 16     | 4    | Payload size in this frame (≤32 KiB)  |
 /// | payload     | 20     | var  | Frame payload (`data_size` bytes)     |
-#[derive(Debug, Default)]
+///
+/// Peers that negotiated larger frames raise the 32 KiB limit with
+/// [`HotlineCodec::set_max_frame_data`].
+///
+/// Transactions are held to the default [`PayloadLimits`] unless
+/// [`HotlineCodec::set_payload_limits`] gives the codec its own.
+///
+/// Partial transactions older than [`DEFAULT_REASSEMBLY_TIMEOUT`], or the
+/// limit given to [`HotlineCodec::set_max_reassembly_age`], are abandoned
+/// with an [`io::ErrorKind::TimedOut`] error wrapping
+/// [`TransactionError::ReassemblyTimeout`].
+#[derive(Debug)]
 pub struct HotlineCodec {
     /// State for multi-fragment reassembly.
     reassembly: Option<ReassemblyState>,
+    /// Largest data size accepted in, and written to, one frame.
+    max_frame_data: usize,
+    /// Longest time a fragmented transaction may take to arrive in full.
+    max_reassembly_age: Duration,
+    /// Largest payload accepted for each transaction type.
+    limits: PayloadLimits,
+}
+
+impl Default for HotlineCodec {
+    fn default() -> Self {
+        Self {
+            reassembly: None,
+            max_frame_data: MAX_FRAME_DATA,
+            max_reassembly_age: DEFAULT_REASSEMBLY_TIMEOUT,
+            limits: PayloadLimits::new(),
+        }
+    }
 }
 
 /// State for reassembling multi-fragment transactions.
@@ -65,6 +99,8 @@ struct ReassemblyState {
     first_header: FrameHeader,
     /// Accumulated payload bytes.
     payload: Vec<u8>,
+    /// Registration that ages the partial transaction.
+    tracker: Reassembly,
 }
 
 impl HotlineCodec {
@@ -72,6 +108,72 @@ impl HotlineCodec {
     #[must_use]
     pub fn new() -> Self { Self::default() }
 
+    /// Create a codec whose frames carry up to `max_frame_data` bytes.
+    #[must_use]
+    pub fn with_max_frame_data(max_frame_data: usize) -> Self {
+        let mut codec = Self::default();
+        codec.set_max_frame_data(max_frame_data);
+        codec
+    }
+
+    /// Change the largest data size accepted in, and written to, one frame.
+    ///
+    /// The value is clamped to `1..=MAX_NEGOTIATED_FRAME_DATA`; values above
+    /// [`MAX_FRAME_DATA`] suit only peers that agreed to larger frames.
+    pub const fn set_max_frame_data(&mut self, max_frame_data: usize) {
+        self.max_frame_data = if max_frame_data == 0 {
+            1
+        } else if max_frame_data > MAX_NEGOTIATED_FRAME_DATA {
+            MAX_NEGOTIATED_FRAME_DATA
+        } else {
+            max_frame_data
+        };
+    }
+
+    /// Return the largest data size accepted in, and written to, one frame.
+    #[must_use]
+    pub const fn max_frame_data(&self) -> usize { self.max_frame_data }
+
+    /// Change how long a fragmented transaction may take to arrive in full.
+    ///
+    /// The limit applies to reassemblies started after the change.
+    pub const fn set_max_reassembly_age(&mut self, max_age: Duration) {
+        self.max_reassembly_age = max_age;
+    }
+
+    /// Hold transactions to `limits` instead of the default limits.
+    pub fn set_payload_limits(&mut self, limits: PayloadLimits) { self.limits = limits; }
+
+    /// Return how long ago the partial transaction being reassembled began,
+    /// or `None` when no reassembly is in progress.
+    #[must_use]
+    pub fn reassembly_age(&self) -> Option<Duration> {
+        self.reassembly.as_ref().map(|state| state.tracker.age())
+    }
+
+    /// Abandon the partial transaction if it has outlived its age limit.
+    ///
+    /// [`Decoder::decode`] checks this whenever bytes arrive; owners can also
+    /// call it on a timer to catch clients that stop sending altogether.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`io::ErrorKind::TimedOut`] error wrapping
+    /// [`TransactionError::ReassemblyTimeout`] once the reassembly is too old.
+    pub fn check_reassembly_age(&mut self) -> Result<(), io::Error> {
+        let expired = self
+            .reassembly
+            .as_ref()
+            .and_then(|state| state.tracker.remaining().err());
+        match expired {
+            Some(error) => {
+                self.reassembly = None;
+                Err(io::Error::new(io::ErrorKind::TimedOut, error))
+            }
+            None => Ok(()),
+        }
+    }
+
     fn finalize_transaction(
         header: FrameHeader,
         payload: Vec<u8>,
@@ -130,7 +232,10 @@ impl Decoder for HotlineCodec {
     type Item = HotlineTransaction;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        let Some((header, payload)) = super::take_hotline_frame(src)? else {
+        self.check_reassembly_age()?;
+        let Some((header, payload)) =
+            super::take_hotline_frame_limited(src, self.max_frame_data, &self.limits)?
+        else {
             return Ok(None);
         };
 
@@ -152,6 +257,7 @@ impl Decoder for HotlineCodec {
             self.reassembly = Some(ReassemblyState {
                 first_header: header,
                 payload,
+                tracker: Reassembly::begin(self.max_reassembly_age),
             });
             return Ok(None);
         }
@@ -177,9 +283,30 @@ impl Encoder<HotlineTransaction> for HotlineCodec {
     type Error = io::Error;
 
     fn encode(&mut self, item: HotlineTransaction, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        let (header, payload) = item.into_parts();
+        self.encode_frames(item.header(), item.payload(), dst)
+    }
+}
 
-        // Validate
+impl HotlineCodec {
+    /// Write `payload` to `dst` as one or more frames under `header`.
+    ///
+    /// The payload is borrowed and copied once, straight into `dst`, which
+    /// grows once to hold every frame. Callers that keep `dst` for the life
+    /// of a connection, as [`tokio_util::codec::Framed`] does, therefore
+    /// encode without allocating once the buffer has grown to their largest
+    /// transaction.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`io::ErrorKind::InvalidInput`] when the header has flags set,
+    /// the payload exceeds [`MAX_PAYLOAD_SIZE`], or the header's
+    /// `total_size` disagrees with the payload's length.
+    pub fn encode_frames(
+        &self,
+        header: &FrameHeader,
+        payload: &[u8],
+        dst: &mut BytesMut,
+    ) -> io::Result<()> {
         if header.flags != 0 {
             return Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
@@ -192,38 +319,30 @@ impl Encoder<HotlineTransaction> for HotlineCodec {
                 TransactionError::PayloadTooLarge.to_string(),
             ));
         }
+        if usize::try_from(header.total_size).ok() != Some(payload.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                TransactionError::SizeMismatch.to_string(),
+            ));
+        }
 
-        // Handle empty payload
+        let frames = payload.len().div_ceil(self.max_frame_data).max(1);
+        dst.reserve(frames * HEADER_LEN + payload.len());
+        let mut frame_header = header.clone();
+        let mut header_bytes = [0u8; HEADER_LEN];
         if payload.is_empty() {
-            let mut frame_header = header;
             frame_header.data_size = 0;
-            let mut header_bytes = [0u8; HEADER_LEN];
             frame_header.write_bytes(&mut header_bytes);
-            dst.reserve(HEADER_LEN);
             dst.put_slice(&header_bytes);
             return Ok(());
         }
-
-        // Fragment if needed
-        let mut offset = 0usize;
-        while offset < payload.len() {
-            let end = (offset + MAX_FRAME_DATA).min(payload.len());
-            // Offset starts at 0, increments by chunk size, and end is capped at payload.len().
-            let chunk = payload.get(offset..end).ok_or_else(|| {
-                io::Error::new(io::ErrorKind::InvalidInput, "payload chunk out of bounds")
-            })?;
-            let mut frame_header = header.clone();
+        for chunk in payload.chunks(self.max_frame_data) {
             frame_header.data_size = u32::try_from(chunk.len())
                 .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "chunk too large"))?;
-
-            dst.reserve(HEADER_LEN + chunk.len());
-            let mut header_bytes = [0u8; HEADER_LEN];
             frame_header.write_bytes(&mut header_bytes);
             dst.put_slice(&header_bytes);
             dst.put_slice(chunk);
-            offset = end;
         }
-
         Ok(())
     }
 }
@@ -5,7 +5,7 @@ use rstest::rstest;
 use super::*;
 use crate::{
     field_id::FieldId,
-    wireframe::test_helpers::{fragmented_transaction_bytes, transaction_bytes},
+    test_support::{fragmented_transaction_bytes, transaction_bytes},
 };
 fn prepare_reassembly_buffer(
     codec: &mut HotlineCodec,
@@ -240,3 +240,129 @@ fn rejects_incomplete_reassembly_at_eof() {
 
     assert!(err.to_string().contains("incomplete transaction frame"));
 }
+
+fn large_transaction() -> HotlineTransaction {
+    let text = vec![b'x'; MAX_FRAME_DATA + 1024];
+    HotlineTransaction::request_from_params(105, 5, &[(FieldId::Data, text)]).expect("transaction")
+}
+
+#[rstest]
+#[case::default_limit(HotlineCodec::new(), 2)]
+#[case::negotiated_limit(HotlineCodec::with_max_frame_data(2 * MAX_FRAME_DATA), 1)]
+fn fragments_by_frame_limit(#[case] mut codec: HotlineCodec, #[case] frames: usize) {
+    let tx = large_transaction();
+    let payload_len = tx.payload().len();
+    let mut buf = BytesMut::new();
+
+    codec.encode(tx, &mut buf).expect("encode should succeed");
+
+    assert_eq!(buf.len(), frames * HEADER_LEN + payload_len);
+}
+
+#[rstest]
+fn reused_buffers_encode_without_growing() {
+    let codec = HotlineCodec::new();
+    let tx = large_transaction();
+    let mut buf = BytesMut::new();
+    codec
+        .encode_frames(tx.header(), tx.payload(), &mut buf)
+        .expect("encode should succeed");
+    let encoded = buf.to_vec();
+    buf.clear();
+    let capacity = buf.capacity();
+
+    codec
+        .encode_frames(tx.header(), tx.payload(), &mut buf)
+        .expect("encode should succeed");
+
+    assert!(capacity >= encoded.len());
+    assert_eq!(buf.capacity(), capacity);
+    assert_eq!(buf.as_ref(), encoded.as_slice());
+}
+
+#[rstest]
+#[case::short(-1)]
+#[case::long(1)]
+fn refuses_headers_whose_total_size_disagrees(#[case] skew: i64) {
+    let codec = HotlineCodec::new();
+    let tx = large_transaction();
+    let mut header = tx.header().clone();
+    header.total_size = u32::try_from(i64::from(header.total_size) + skew).expect("size");
+    let mut buf = BytesMut::new();
+
+    let error = codec
+        .encode_frames(&header, tx.payload(), &mut buf)
+        .expect_err("mismatched size refused");
+
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    assert!(buf.is_empty());
+}
+
+#[rstest]
+fn decodes_negotiated_frames_only_when_raised() {
+    let mut writer = HotlineCodec::with_max_frame_data(2 * MAX_FRAME_DATA);
+    let tx = large_transaction();
+    let payload = tx.payload().to_vec();
+    let mut encoded = BytesMut::new();
+    writer
+        .encode(tx, &mut encoded)
+        .expect("encode should succeed");
+
+    let mut legacy = HotlineCodec::new();
+    assert!(legacy.decode(&mut encoded.clone()).is_err());
+
+    let mut negotiated = HotlineCodec::new();
+    negotiated.set_max_frame_data(2 * MAX_FRAME_DATA);
+    let decoded = negotiated
+        .decode(&mut encoded)
+        .expect("decode should succeed")
+        .expect("should produce transaction");
+    assert_eq!(decoded.payload(), payload.as_slice());
+}
+
+#[rstest]
+#[case(0, 1)]
+#[case(MAX_FRAME_DATA, MAX_FRAME_DATA)]
+#[case(usize::MAX, MAX_NEGOTIATED_FRAME_DATA)]
+fn clamps_frame_limit(#[case] requested: usize, #[case] expected: usize) {
+    assert_eq!(
+        HotlineCodec::with_max_frame_data(requested).max_frame_data(),
+        expected
+    );
+}
+
+#[rstest]
+#[tokio::test(start_paused = true)]
+async fn abandons_reassemblies_past_their_age_limit() {
+    let mut codec = HotlineCodec::new();
+    codec.set_max_reassembly_age(Duration::from_secs(5));
+    let header = FrameHeader {
+        flags: 0,
+        is_reply: 0,
+        ty: 107,
+        id: 9,
+        error: 0,
+        total_size: 8,
+        data_size: 4,
+    };
+    let mut buf = BytesMut::from(&transaction_bytes(&header, &[0u8; 4])[..]);
+    assert!(codec.decode(&mut buf).expect("first fragment").is_none());
+
+    tokio::time::advance(Duration::from_secs(3)).await;
+    assert_eq!(codec.reassembly_age(), Some(Duration::from_secs(3)));
+    codec.check_reassembly_age().expect("still within limit");
+
+    tokio::time::advance(Duration::from_secs(2)).await;
+    let err = codec
+        .check_reassembly_age()
+        .expect_err("reassembly should expire");
+
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    assert!(matches!(
+        err.into_inner()
+            .and_then(|inner| inner.downcast::<TransactionError>().ok())
+            .map(|inner| *inner),
+        Some(TransactionError::ReassemblyTimeout)
+    ));
+    assert_eq!(codec.reassembly_age(), None);
+}
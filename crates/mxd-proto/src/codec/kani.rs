@@ -1,7 +1,12 @@
 //! Kani harnesses for Hotline transaction framing invariants.
 
 use super::{for_each_fragment_range, validate_header};
-use crate::transaction::{MAX_FRAME_DATA, MAX_PAYLOAD_SIZE, kani_support::any_frame_header};
+use crate::transaction::{
+    MAX_FRAME_DATA,
+    MAX_PAYLOAD_SIZE,
+    PayloadLimits,
+    kani_support::any_frame_header,
+};
 
 const _: () = assert!(MAX_PAYLOAD_SIZE <= u32::MAX as usize);
 const _: () = assert!(MAX_FRAME_DATA <= u32::MAX as usize);
@@ -26,7 +31,7 @@ fn kani_validate_header_matches_predicate() {
         && !(header.data_size == 0 && header.total_size > 0);
 
     kani::assert(
-        validate_header(&header).is_ok() == expected_ok,
+        validate_header(&header, MAX_FRAME_DATA, &PayloadLimits::new()).is_ok() == expected_ok,
         "header validation matches predicate",
     );
 }
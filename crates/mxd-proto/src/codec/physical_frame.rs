@@ -4,14 +4,34 @@ use std::io;
 
 use bytes::{Buf, BytesMut};
 
-use crate::transaction::{FrameHeader, HEADER_LEN};
+use crate::transaction::{FrameHeader, HEADER_LEN, MAX_FRAME_DATA, PayloadLimits};
 
 /// Try to read one complete physical Hotline frame from `src`.
 ///
 /// Returns `Ok(None)` when more bytes are required, or the validated header
-/// plus payload chunk once a full frame is available.
-pub(crate) fn take_hotline_frame(
+/// plus payload chunk once a full frame is available. The header is held to
+/// the default [`PayloadLimits`].
+///
+/// # Errors
+///
+/// Returns [`io::ErrorKind::InvalidData`] if the header violates the framing
+/// limits.
+pub fn take_hotline_frame(src: &mut BytesMut) -> Result<Option<(FrameHeader, Vec<u8>)>, io::Error> {
+    take_hotline_frame_limited(src, MAX_FRAME_DATA, &PayloadLimits::new())
+}
+
+/// Like [`take_hotline_frame`], but accept frames carrying up to
+/// `max_frame_data` bytes, as negotiated with the peer, and transactions up
+/// to the limit for their type in `limits`.
+///
+/// # Errors
+///
+/// Returns [`io::ErrorKind::InvalidData`] if the header violates the framing
+/// limits.
+pub fn take_hotline_frame_limited(
     src: &mut BytesMut,
+    max_frame_data: usize,
+    limits: &PayloadLimits,
 ) -> Result<Option<(FrameHeader, Vec<u8>)>, io::Error> {
     if src.len() < HEADER_LEN {
         return Ok(None);
@@ -25,7 +45,7 @@ pub(crate) fn take_hotline_frame(
         .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid header length"))?;
     let header = FrameHeader::from_bytes(header_bytes);
 
-    super::validate_header(&header)
+    super::validate_header(&header, max_frame_data, limits)
         .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
 
     let data_size = usize::try_from(header.data_size)
@@ -1,14 +1,10 @@
-//! Unit tests for the Wireframe transaction codec.
+//! Unit tests for the bincode transaction codec.
 
-use std::io::Cursor;
-
-use bincode::{config, encode_to_vec};
+use bincode::{borrow_decode_from_slice, config, encode_to_vec};
 use rstest::rstest;
-use tokio::io::BufReader;
-use wireframe::preamble::read_preamble;
 
 use super::*;
-use crate::wireframe::test_helpers::transaction_bytes;
+use crate::{test_support::transaction_bytes, transaction_type::TransactionType};
 
 fn hotline_config() -> impl bincode::config::Config {
     config::standard()
@@ -16,6 +12,13 @@ fn hotline_config() -> impl bincode::config::Config {
         .with_fixed_int_encoding()
 }
 
+/// Decode one transaction from `bytes`, returning it with any bytes left
+/// unread.
+fn decode(bytes: &[u8]) -> Result<(HotlineTransaction, &[u8]), DecodeError> {
+    let (tx, read) = borrow_decode_from_slice(bytes, hotline_config())?;
+    Ok((tx, bytes.get(read..).unwrap_or_default()))
+}
+
 fn file_list_header(is_reply: u8, payload_len: u32) -> FrameHeader {
     FrameHeader {
         flags: 0,
@@ -40,13 +43,10 @@ fn assert_encode_error(tx: &HotlineTransaction, expected_msg: &str) {
 
 /// Assert that decoding a transaction with the given header and payload fails
 /// with an error message containing the expected substring.
-async fn assert_decode_error(header: FrameHeader, payload: Vec<u8>, expected_msg: &str) {
-    let bytes = transaction_bytes(&header, &payload);
-    let mut reader = BufReader::new(Cursor::new(bytes));
+fn assert_decode_error(header: &FrameHeader, payload: &[u8], expected_msg: &str) {
+    let bytes = transaction_bytes(header, payload);
 
-    let err = read_preamble::<_, HotlineTransaction>(&mut reader)
-        .await
-        .expect_err("decode must fail");
+    let err = decode(&bytes).expect_err("decode must fail");
 
     assert!(
         err.to_string().contains(expected_msg),
@@ -57,8 +57,7 @@ async fn assert_decode_error(header: FrameHeader, payload: Vec<u8>, expected_msg
 #[rstest]
 #[case(20, 20)] // Single frame with payload
 #[case(0, 0)] // Empty payload
-#[tokio::test]
-async fn decodes_valid_single_frame(#[case] total: u32, #[case] data: u32) {
+fn decodes_valid_single_frame(#[case] total: u32, #[case] data: u32) {
     let header = FrameHeader {
         flags: 0,
         is_reply: 0,
@@ -70,11 +69,8 @@ async fn decodes_valid_single_frame(#[case] total: u32, #[case] data: u32) {
     };
     let payload = vec![0u8; total as usize];
     let bytes = transaction_bytes(&header, &payload);
-    let mut reader = BufReader::new(Cursor::new(bytes));
 
-    let (tx, leftover) = read_preamble::<_, HotlineTransaction>(&mut reader)
-        .await
-        .expect("transaction must decode");
+    let (tx, leftover) = decode(&bytes).expect("transaction must decode");
 
     assert!(leftover.is_empty());
     assert_eq!(tx.header().total_size, total);
@@ -84,18 +80,14 @@ async fn decodes_valid_single_frame(#[case] total: u32, #[case] data: u32) {
 #[rstest]
 #[case(0)]
 #[case(1)]
-#[tokio::test]
-async fn decodes_opaque_file_list_payload(#[case] is_reply: u8) {
+fn decodes_opaque_file_list_payload(#[case] is_reply: u8) {
     let payload = b"\x00\xffnot-a-param-block".to_vec();
     let payload_len = u32::try_from(payload.len()).expect("payload length fits in u32");
     let header = file_list_header(is_reply, payload_len);
     if is_reply == 0 {
         let bytes = transaction_bytes(&header, &payload);
-        let mut reader = BufReader::new(Cursor::new(bytes));
 
-        let (tx, leftover) = read_preamble::<_, HotlineTransaction>(&mut reader)
-            .await
-            .expect("file-list request should decode");
+        let (tx, leftover) = decode(&bytes).expect("file-list request should decode");
 
         assert!(leftover.is_empty());
         assert_eq!(tx.header().ty, header.ty);
@@ -114,8 +106,7 @@ async fn decodes_opaque_file_list_payload(#[case] is_reply: u8) {
 #[rstest]
 #[case(10, 20, "data size exceeds total")]
 #[case(100, 0, "data size is zero but total size is non-zero")]
-#[tokio::test]
-async fn rejects_invalid_length_combinations(
+fn rejects_invalid_length_combinations(
     #[case] total: u32,
     #[case] data: u32,
     #[case] expected_msg: &str,
@@ -130,7 +121,7 @@ async fn rejects_invalid_length_combinations(
         data_size: data,
     };
     let payload = vec![0u8; data as usize];
-    assert_decode_error(header, payload, expected_msg).await;
+    assert_decode_error(&header, &payload, expected_msg);
 }
 
 #[rstest]
@@ -173,13 +164,38 @@ async fn rejects_invalid_length_combinations(
     vec![0u8; MAX_FRAME_DATA + 1],
     "data size exceeds maximum"
 )]
-#[tokio::test]
-async fn rejects_invalid_headers(
+fn rejects_invalid_headers(
     #[case] header: FrameHeader,
     #[case] payload: Vec<u8>,
     #[case] expected_msg: &str,
 ) {
-    assert_decode_error(header, payload, expected_msg).await;
+    assert_decode_error(&header, &payload, expected_msg);
+}
+
+#[rstest]
+fn enforces_the_limit_for_the_transaction_type() {
+    let ty = 4000;
+    let limits = PayloadLimits::new().with_limit(TransactionType::from(ty), 8);
+    let header = |size: u32| FrameHeader {
+        flags: 0,
+        is_reply: 0,
+        ty,
+        id: 1,
+        error: 0,
+        total_size: size,
+        data_size: size,
+    };
+
+    let mut within = bytes::BytesMut::from(transaction_bytes(&header(8), &[0; 8]).as_slice());
+    let (_, payload) = take_hotline_frame_limited(&mut within, MAX_FRAME_DATA, &limits)
+        .expect("payload within the limit decodes")
+        .expect("frame is complete");
+    assert_eq!(payload.len(), 8);
+
+    let mut over = bytes::BytesMut::from(transaction_bytes(&header(9), &[0; 9]).as_slice());
+    let err = take_hotline_frame_limited(&mut over, MAX_FRAME_DATA, &limits)
+        .expect_err("payload over the limit is refused");
+    assert!(err.to_string().contains("total size exceeds maximum"));
 }
 
 #[rstest]
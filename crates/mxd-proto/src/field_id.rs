@@ -0,0 +1,314 @@
+//! Field identifiers used within transaction payloads.
+//!
+//! Each `FieldId` corresponds to a specific parameter or data value defined by
+//! the Hotline protocol. They are used when encoding and decoding transaction
+//! parameters.
+//!
+//! The `field_registry!` invocation below is the single source of truth for
+//! known identifiers: it generates the enum, both conversion directions, and
+//! the display names. Identifiers missing from the registry decode to
+//! [`FieldId::Unknown`], which keeps the raw value so proxied or future-client
+//! transactions survive a decode/encode round trip unchanged.
+
+use std::{
+    hash::{Hash, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use crate::transaction_type::{FILE_NAME_LIST_ID, USER_NAME_LIST_ID};
+
+/// Generate [`FieldId`] and its lookup tables from `Variant = id` entries.
+macro_rules! field_registry {
+    ($($(#[$meta:meta])* $variant:ident = $id:tt,)+) => {
+        /// Field identifiers for transaction parameters.
+        ///
+        /// Each variant represents a specific parameter type used in the Hotline
+        /// protocol's transaction payloads. Equality and hashing use the raw wire
+        /// value, so `FieldId::Unknown(102)` and `FieldId::Name` compare equal.
+        #[derive(Debug, Clone, Copy)]
+        pub enum FieldId {
+            $($(#[$meta])* $variant,)+
+            /// Field id absent from the registry; the raw value is preserved.
+            Unknown(u16),
+        }
+
+        impl FieldId {
+            /// Every field identifier known to the registry.
+            pub const KNOWN: &'static [Self] = &[$(Self::$variant),+];
+
+            /// Resolve a raw wire value to its canonical identifier.
+            #[must_use]
+            pub const fn from_raw(raw: u16) -> Self {
+                match raw {
+                    $($id => Self::$variant,)+
+                    other => Self::Unknown(other),
+                }
+            }
+
+            /// Return the raw wire value for this identifier.
+            #[must_use]
+            pub const fn raw(self) -> u16 {
+                match self {
+                    $(Self::$variant => $id,)+
+                    Self::Unknown(raw) => raw,
+                }
+            }
+
+            /// Return the registry name, or `None` for unknown identifiers.
+            #[must_use]
+            pub const fn name(self) -> Option<&'static str> {
+                match self {
+                    $(Self::$variant => Some(stringify!($variant)),)+
+                    Self::Unknown(_) => None,
+                }
+            }
+        }
+    };
+}
+
+field_registry! {
+    /// Text explaining why a request failed, sent with error replies.
+    ErrorText = 100,
+    /// User-visible nickname.
+    Name = 102,
+    /// Login name for an account.
+    Login = 105,
+    /// Password for an account.
+    Password = 106,
+    /// Reference number a client quotes on the file-transfer port.
+    ReferenceNumber = 107,
+    /// Size in bytes of the data waiting on the file-transfer port.
+    TransferSize = 108,
+    /// User identifier.
+    UserId = 103,
+    /// User icon identifier.
+    IconId = 104,
+    /// Chat options; `1` marks an emote (`/me`) line.
+    ChatOptions = 109,
+    /// User access bitmap.
+    UserAccess = 110,
+    /// User list colour or status flags.
+    UserFlags = 112,
+    /// Connection option flags.
+    Options = 113,
+    /// Main chat subject.
+    ChatSubject = 115,
+    /// Place in the server's transfer queue, or zero once the transfer runs.
+    WaitingCount = 116,
+    /// Client version information.
+    Version = 160,
+    /// Banner identifier used for HTTP banner retrieval.
+    BannerId = 161,
+    /// Server name string returned during login.
+    ServerName = 162,
+    /// Server clock at login, as epoch milliseconds (mxd extension).
+    ServerTime = 163,
+    /// Server UTC offset in seconds east of UTC (mxd extension).
+    ServerUtcOffset = 164,
+    /// Search result entry: kind, article id, location and name (mxd
+    /// extension).
+    SearchResult = 165,
+    /// Largest frame data size, in bytes, the sender accepts (mxd
+    /// extension).
+    MaxFrameData = 166,
+    /// CRC-32 of the rest of the payload, on connections that negotiated
+    /// checksums (mxd extension).
+    PayloadChecksum = 167,
+    /// Server rules or help section: a `u16` kind followed by its text (mxd
+    /// extension).
+    HelpSection = 168,
+    /// Session resumption token: sent at login, empty or holding an earlier
+    /// token, to ask for one, and presented instead of a password to log in
+    /// again (mxd extension).
+    SessionToken = 169,
+    /// News root a login selects for the session's news transactions (mxd
+    /// extension).
+    NewsRoot = 170,
+    /// Set on Get News Article Data by a client that will fetch a long body
+    /// from the transfer port (mxd extension).
+    NewsDataTransfer = 171,
+    /// Set on Get News Category Name List by a client that wants each
+    /// entry's most recent post time, which the reply then sends after every
+    /// news category entry as epoch milliseconds (mxd extension).
+    NewsActivity = 172,
+    /// Generic data payload (often message text).
+    Data = 101,
+    /// Name of a news category to create.
+    NewsCategoryName = 322,
+    /// News category list entry returned by the server.
+    NewsCategory = 323,
+    /// News article list entry returned by the server.
+    NewsArticle = 321,
+    /// Article identifier in requests.
+    NewsArticleId = 326,
+    /// Data flavour for article content.
+    NewsDataFlavor = 327,
+    /// Article title field.
+    NewsTitle = 328,
+    /// Article poster field.
+    NewsPoster = 329,
+    /// Article post date.
+    NewsDate = 330,
+    /// Previous article id for navigation.
+    NewsPrevId = 331,
+    /// Next article id for navigation.
+    NewsNextId = 332,
+    /// Article data payload.
+    NewsArticleData = 333,
+    /// Article flags field (field 334).
+    ///
+    /// Protocol semantics: indicates whether a post is locked or is an
+    /// announcement type. Typically 0 for normal posts. Flag values are not
+    /// strictly defined in the protocol specification; implementations may
+    /// vary.
+    NewsArticleFlags = 334,
+    /// Parent article id field.
+    NewsParentId = 335,
+    /// First child article id field.
+    NewsFirstChildId = 336,
+    /// Non-zero when deleting an article should also delete its replies.
+    NewsRecursiveDelete = 337,
+    /// Path within the news hierarchy.
+    NewsPath = 325,
+    /// File name entry.
+    FileName = FILE_NAME_LIST_ID,
+    /// Name of the file or folder a file request targets.
+    FileItemName = 201,
+    /// Folder path containing the targeted file or folder.
+    FilePath = 202,
+    /// Classic Mac OS type code as a string.
+    FileTypeString = 205,
+    /// Classic Mac OS creator code as a string.
+    FileCreatorString = 206,
+    /// File size in bytes.
+    FileSize = 207,
+    /// File creation date.
+    FileCreateDate = 208,
+    /// File modification date.
+    FileModifyDate = 209,
+    /// User-editable file or folder comment.
+    FileComment = 210,
+    /// Replacement name for a file or folder.
+    FileNewName = 211,
+    /// Destination folder path for a move or alias.
+    FileNewPath = 212,
+    /// Four-byte type code; `fldr` marks a folder.
+    FileType = 213,
+    /// Packed user-list entry containing id, icon, flags, and name.
+    UserNameWithInfo = USER_NAME_LIST_ID,
+    /// Text quoted from an earlier private message.
+    QuotingMsg = 214,
+    /// Automatic response text.
+    AutoResponse = 215,
+}
+
+impl FieldId {
+    /// Return `true` when the identifier is present in the registry.
+    #[must_use]
+    pub const fn is_known(self) -> bool { self.name().is_some() }
+
+    /// Return the canonical form of this identifier.
+    ///
+    /// `Unknown` values whose raw id is registered resolve to the named
+    /// variant.
+    #[must_use]
+    pub const fn canonical(self) -> Self { Self::from_raw(self.raw()) }
+}
+
+impl PartialEq for FieldId {
+    fn eq(&self, other: &Self) -> bool { self.raw() == other.raw() }
+}
+
+impl Eq for FieldId {}
+
+impl Hash for FieldId {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.raw().hash(state); }
+}
+
+impl From<u16> for FieldId {
+    fn from(v: u16) -> Self { Self::from_raw(v) }
+}
+
+impl From<FieldId> for u16 {
+    fn from(f: FieldId) -> Self { f.raw() }
+}
+
+impl std::fmt::Display for FieldId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "Unknown({})", self.raw()),
+        }
+    }
+}
+
+/// One bit per possible field id, packed into 64-bit words.
+const SEEN_WORDS: usize = 1 << 10;
+
+/// Bitset of unknown field ids already reported by [`note_unknown_field`].
+static SEEN_UNKNOWN: [AtomicU64; SEEN_WORDS] = [const { AtomicU64::new(0) }; SEEN_WORDS];
+
+/// Record an unknown field id observed while decoding a payload.
+///
+/// Logs the id the first time it is seen by this process so operators can
+/// discover protocol extensions in use. Known ids are ignored.
+pub fn note_unknown_field(field: FieldId) {
+    if let FieldId::Unknown(raw) = field.canonical()
+        && mark_seen(raw)
+    {
+        tracing::info!(field_id = raw, "first sighting of unknown field id");
+    }
+}
+
+/// Set the bit for `raw`, returning `true` if it was previously clear.
+fn mark_seen(raw: u16) -> bool {
+    let bit = 1u64 << (raw & 0x3f);
+    SEEN_UNKNOWN
+        .get(usize::from(raw >> 6))
+        .is_some_and(|word| word.fetch_or(bit, Ordering::Relaxed) & bit == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Registry invariants for field identifiers.
+
+    use std::collections::HashSet;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn registry_round_trips_every_known_id() {
+        for field in FieldId::KNOWN {
+            assert_eq!(FieldId::from(u16::from(*field)).name(), field.name());
+        }
+    }
+
+    #[rstest]
+    fn registry_ids_are_unique() {
+        let raws: HashSet<u16> = FieldId::KNOWN.iter().map(|field| field.raw()).collect();
+        assert_eq!(raws.len(), FieldId::KNOWN.len());
+    }
+
+    #[rstest]
+    fn unknown_ids_preserve_raw_value() {
+        let field = FieldId::from(4242);
+        assert!(matches!(field, FieldId::Unknown(4242)));
+        assert_eq!(u16::from(field), 4242);
+        assert_eq!(field.to_string(), "Unknown(4242)");
+    }
+
+    #[rstest]
+    fn unknown_alias_equals_registered_variant() {
+        assert_eq!(FieldId::Unknown(102), FieldId::Name);
+        assert!(matches!(FieldId::Unknown(102).canonical(), FieldId::Name));
+    }
+
+    #[rstest]
+    fn mark_seen_reports_first_sighting_only() {
+        assert!(mark_seen(65_001));
+        assert!(!mark_seen(65_001));
+        assert!(mark_seen(65_002));
+    }
+}
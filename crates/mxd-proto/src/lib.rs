@@ -0,0 +1,30 @@
+//! Hotline protocol types and framing for mxd.
+//!
+//! This crate holds the parts of the wire protocol that do not depend on the
+//! server: the handshake, the 20-byte transaction frame and its parameter
+//! block, field and transaction identifiers, user access privileges and the
+//! access each transaction requires, and codecs that reassemble fragmented
+//! transactions. Client tools, bridges, and the fuzz harness can depend on it
+//! without pulling in Diesel or the server runtime. The `mxd` crate
+//! re-exports each module at its original path.
+
+#![cfg_attr(test, expect(clippy::unwrap_used, reason = "test code can panic"))]
+#![cfg_attr(
+    test,
+    expect(clippy::indexing_slicing, reason = "test code with known bounds")
+)]
+
+// Lets `#[derive(TransactionParams)]` name this crate as `::mxd_proto` in
+// its own tests.
+#[cfg(test)]
+extern crate self as mxd_proto;
+
+pub mod access;
+pub mod codec;
+pub mod field_id;
+pub mod privileges;
+pub mod protocol;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+pub mod transaction;
+pub mod transaction_type;
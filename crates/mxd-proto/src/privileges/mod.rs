@@ -3,7 +3,9 @@
 //! Field 110 (User Access) contains a bitmap representing the privileges
 //! granted to a user account. Each bit corresponds to a specific operation
 //! that the user may or may not be allowed to perform. See `docs/protocol.md`
-//! for the full specification.
+//! for the full specification, and [`wire`] for the byte layout of the field.
+
+pub mod wire;
 
 use bitflags::bitflags;
 
@@ -98,8 +100,9 @@ impl Privileges {
     ///
     /// Grants basic read/download access and communication privileges without
     /// administrative capabilities. This matches typical regular user
-    /// permissions. TODO(task 5.1): Load privileges from user account in
-    /// database rather than using this default.
+    /// permissions. mxd stores it for accounts created without an explicit
+    /// access bitmap; its migrations use the same value as the column
+    /// default.
     #[must_use]
     pub const fn default_user() -> Self {
         Self::from_bits_truncate(
@@ -0,0 +1,91 @@
+//! Hotline access bytes: the encoding of [`Privileges`] in field 110.
+//!
+//! The field is eight bytes long. Privilege bit 0 is the most significant
+//! bit of the first byte, bit 7 the least significant bit of the first byte,
+//! bit 8 the most significant bit of the second byte, and so on. That is the
+//! reverse of the bit order [`Privileges`] uses in memory, so encoding
+//! reverses the bits of the `u64` and writes it big-endian.
+#![expect(
+    clippy::big_endian_bytes,
+    reason = "Hotline access bytes are big-endian"
+)]
+
+use thiserror::Error;
+
+use super::Privileges;
+
+/// Length in bytes of the access bitmap in field 110.
+pub const ACCESS_LEN: usize = 8;
+
+/// Field 110 held a bitmap of the wrong length.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error("access bitmap must be {ACCESS_LEN} bytes, got {0}")]
+pub struct AccessLengthError(pub usize);
+
+/// Encode `privileges` as Hotline access bytes.
+#[must_use]
+pub const fn encode(privileges: Privileges) -> [u8; ACCESS_LEN] {
+    privileges.bits().reverse_bits().to_be_bytes()
+}
+
+/// Decode Hotline access bytes, ignoring bits no privilege is defined for.
+///
+/// # Errors
+///
+/// Returns [`AccessLengthError`] when `bytes` is not [`ACCESS_LEN`] bytes
+/// long.
+pub fn decode(bytes: &[u8]) -> Result<Privileges, AccessLengthError> {
+    let raw: [u8; ACCESS_LEN] = bytes
+        .try_into()
+        .map_err(|_| AccessLengthError(bytes.len()))?;
+    Ok(Privileges::from_bits_truncate(
+        u64::from_be_bytes(raw).reverse_bits(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for access byte encoding.
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(Privileges::DELETE_FILE, [0x80, 0, 0, 0, 0, 0, 0, 0])]
+    #[case(Privileges::DOWNLOAD_FILE, [0x20, 0, 0, 0, 0, 0, 0, 0])]
+    #[case(Privileges::READ_CHAT, [0, 0x40, 0, 0, 0, 0, 0, 0])]
+    #[case(Privileges::BROADCAST, [0, 0, 0, 0, 0x80, 0, 0, 0])]
+    #[case(Privileges::NEWS_DELETE_FOLDER, [0, 0, 0, 0, 0x04, 0, 0, 0])]
+    #[case(Privileges::admin(), [0xff, 0xff, 0xff, 0xff, 0xfc, 0, 0, 0])]
+    fn encodes_bit_zero_as_most_significant(
+        #[case] privileges: Privileges,
+        #[case] expected: [u8; ACCESS_LEN],
+    ) {
+        assert_eq!(encode(privileges), expected);
+    }
+
+    #[rstest]
+    #[case(Privileges::empty())]
+    #[case(Privileges::default_user())]
+    #[case(Privileges::admin())]
+    #[case(Privileges::CREATE_USER | Privileges::NEWS_CREATE_CATEGORY)]
+    fn round_trips(#[case] privileges: Privileges) {
+        assert_eq!(decode(&encode(privileges)), Ok(privileges));
+    }
+
+    #[test]
+    fn ignores_undefined_bits() {
+        assert_eq!(
+            decode(&[0, 0, 0, 0, 0x03, 0xff, 0xff, 0xff]),
+            Ok(Privileges::empty())
+        );
+    }
+
+    #[rstest]
+    #[case(&[])]
+    #[case(&[0xff; 4])]
+    #[case(&[0; 9])]
+    fn rejects_wrong_lengths(#[case] bytes: &[u8]) {
+        assert_eq!(decode(bytes), Err(AccessLengthError(bytes.len())));
+    }
+}
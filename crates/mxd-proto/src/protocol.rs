@@ -30,6 +30,8 @@ pub const HANDSHAKE_ERR_INVALID: u32 = 1;
 pub const HANDSHAKE_ERR_UNSUPPORTED_VERSION: u32 = 2;
 /// Error code when the handshake times out.
 pub const HANDSHAKE_ERR_TIMEOUT: u32 = 3;
+/// Error code when the server has no room for another connection.
+pub const HANDSHAKE_ERR_SERVER_FULL: u32 = 4;
 
 /// Timeout for reading the client handshake.
 pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
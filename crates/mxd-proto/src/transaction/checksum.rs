@@ -0,0 +1,88 @@
+//! Payload checksums for lossy links (mxd extension).
+//!
+//! Peers that agree on checksums at login end every parameter block with a
+//! `PayloadChecksum` (167) field: a big-endian CRC-32 (IEEE) of the parameter
+//! block without that field, its count included. The checksum covers the
+//! payload as it travels, after any XOR or character set encoding, so a
+//! receiver verifies it before decoding anything else.
+
+use super::{TransactionError, decode_params, encode_params, read_u32};
+use crate::field_id::FieldId;
+
+/// Add a `PayloadChecksum` field to `payload`, replacing any existing one.
+///
+/// # Errors
+///
+/// Returns an error if `payload` is not a valid parameter block.
+#[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+pub fn append_checksum(payload: &[u8]) -> Result<Vec<u8>, TransactionError> {
+    let mut params = decode_params(payload)?;
+    params.retain(|(field, _)| *field != FieldId::PayloadChecksum);
+    let checksum = crc32fast::hash(&encode_params(&params)?);
+    params.push((FieldId::PayloadChecksum, checksum.to_be_bytes().to_vec()));
+    encode_params(&params)
+}
+
+/// Check the `PayloadChecksum` field of `payload` and return the payload
+/// without it, or `None` when the payload carries no checksum.
+///
+/// # Errors
+///
+/// Returns [`TransactionError::ChecksumMismatch`] when the checksum does not
+/// match, or another error if `payload` is not a valid parameter block.
+pub fn verify_checksum(payload: &[u8]) -> Result<Option<Vec<u8>>, TransactionError> {
+    let mut params = decode_params(payload)?;
+    let Some(index) = params
+        .iter()
+        .position(|(field, _)| *field == FieldId::PayloadChecksum)
+    else {
+        return Ok(None);
+    };
+    let (_, expected) = params.remove(index);
+    let body = encode_params(&params)?;
+    if read_u32(&expected).ok() != Some(crc32fast::hash(&body)) {
+        return Err(TransactionError::ChecksumMismatch);
+    }
+    Ok(Some(body))
+}
+
+#[cfg(test)]
+mod tests {
+    //! Appending and verifying payload checksums.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn payload() -> Vec<u8> {
+        encode_params(&[(FieldId::Data, b"hello".as_slice())]).expect("payload encodes")
+    }
+
+    #[rstest]
+    fn checksummed_payloads_verify_and_strip() {
+        let checked = append_checksum(&payload()).expect("append");
+
+        assert_eq!(verify_checksum(&checked).expect("verify"), Some(payload()));
+        assert_eq!(append_checksum(&checked).expect("append again"), checked);
+    }
+
+    #[rstest]
+    fn payloads_without_checksum_are_left_alone() {
+        assert_eq!(verify_checksum(&payload()).expect("verify"), None);
+    }
+
+    #[rstest]
+    fn corrupted_payloads_are_rejected() {
+        let mut checked = append_checksum(&payload()).expect("append");
+        let hello = checked
+            .iter()
+            .position(|&byte| byte == b'h')
+            .expect("text present");
+        checked[hello] = b'j';
+
+        assert!(matches!(
+            verify_checksum(&checked),
+            Err(TransactionError::ChecksumMismatch)
+        ));
+    }
+}
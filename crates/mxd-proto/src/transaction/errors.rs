@@ -24,12 +24,22 @@ pub enum TransactionError {
     /// Buffer is too short to contain the expected data.
     #[error("buffer too short")]
     ShortBuffer,
+    /// A parameter block declares more fields than the configured cap.
+    #[error("too many parameters: {0}")]
+    TooManyParams(u16),
     /// A required parameter field is missing.
     #[error("missing field {0:?}")]
     MissingField(crate::field_id::FieldId),
     /// A parameter value could not be parsed (e.g. invalid UTF-8 or wrong size).
     #[error("invalid param value for field {0:?}")]
     InvalidParamValue(crate::field_id::FieldId),
+    /// The payload does not match its `PayloadChecksum` (167) field.
+    #[error("payload checksum mismatch")]
+    ChecksumMismatch,
+    /// A fragmented transaction was still incomplete when its age limit
+    /// passed.
+    #[error("reassembly exceeded age limit")]
+    ReassemblyTimeout,
     /// I/O error occurred during read or write.
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
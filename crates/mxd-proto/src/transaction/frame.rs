@@ -13,11 +13,11 @@ use tokio::{
 
 use super::{
     HEADER_LEN,
-    IO_TIMEOUT,
     MAX_FRAME_DATA,
-    MAX_PAYLOAD_SIZE,
+    READ_TIMEOUT,
     errors::TransactionError,
-    params::validate_payload,
+    limits::PayloadLimits,
+    params::validate_payload_parts,
 };
 
 async fn io_with_timeout<F, T>(timeout_dur: Duration, operation: F) -> Result<T, TransactionError>
@@ -162,40 +162,59 @@ pub struct Transaction {
     pub payload: Vec<u8>,
 }
 
-/// Parse a transaction from a single frame of bytes.
+/// Parse a transaction from a single frame of bytes under the default
+/// [`PayloadLimits`].
+///
+/// # Errors
+/// Returns the same errors as [`parse_transaction_limited`].
+#[must_use = "handle the result"]
+pub fn parse_transaction(buf: &[u8]) -> Result<Transaction, TransactionError> {
+    parse_transaction_limited(buf, &PayloadLimits::new())
+}
+
+/// Parse a transaction from a single frame of bytes under `limits`.
 ///
 /// # Errors
 /// Returns an error if:
 /// - The buffer is too short for a transaction header
-/// - The payload exceeds `MAX_PAYLOAD_SIZE`
+/// - The payload exceeds the largest limit in `limits`
+/// - The payload declares more parameters than `limits` allows
 /// - The frame is malformed or fails validation
+#[must_use = "handle the result"]
+pub fn parse_transaction_limited(
+    buf: &[u8],
+    limits: &PayloadLimits,
+) -> Result<Transaction, TransactionError> {
+    let (header, payload) = parse_transaction_ref(buf, limits)?;
+    Ok(Transaction {
+        header,
+        payload: payload.to_vec(),
+    })
+}
+
+/// Parse a transaction from a single frame of bytes under `limits`,
+/// borrowing its payload from `buf` rather than copying it.
 ///
-/// # Panics
-/// Cannot panic; the internal slice conversion is guarded by an earlier length check.
+/// # Errors
+/// Returns the same errors as [`parse_transaction_limited`].
 #[must_use = "handle the result"]
-#[expect(clippy::indexing_slicing, reason = "length is checked before slicing")]
-pub fn parse_transaction(buf: &[u8]) -> Result<Transaction, TransactionError> {
-    if buf.len() < HEADER_LEN {
-        return Err(TransactionError::SizeMismatch);
-    }
-    debug_assert!(buf.len() >= HEADER_LEN, "buffer too short for header");
-    let header_slice = buf
-        .get(0..HEADER_LEN)
+pub fn parse_transaction_ref<'a>(
+    buf: &'a [u8],
+    limits: &PayloadLimits,
+) -> Result<(FrameHeader, &'a [u8]), TransactionError> {
+    let (header_slice, payload) = buf
+        .split_first_chunk::<HEADER_LEN>()
         .ok_or(TransactionError::SizeMismatch)?;
-    let hdr: &[u8; HEADER_LEN] = header_slice
-        .try_into()
-        .map_err(|_| TransactionError::SizeMismatch)?;
-    let header = FrameHeader::from_bytes(hdr);
-    if header.total_size as usize > MAX_PAYLOAD_SIZE {
+    let header = FrameHeader::from_bytes(header_slice);
+    if header.total_size as usize > limits.largest() {
         return Err(TransactionError::PayloadTooLarge);
     }
-    if buf.len() != HEADER_LEN + header.total_size as usize {
+    if payload.len() != header.total_size as usize {
         return Err(TransactionError::SizeMismatch);
     }
-    let payload = buf[HEADER_LEN..].to_vec();
-    let tx = Transaction { header, payload };
-    validate_payload(&tx)?;
-    Ok(tx)
+    limits.check_params(payload)?;
+    validate_payload_parts(&header, payload)?;
+    Ok((header, payload))
 }
 
 impl Transaction {
@@ -251,4 +270,4 @@ pub(super) async fn read_stream_chunk<R: AsyncRead + Unpin>(
     read_timeout_exact(rdr, buf, timeout_dur).await
 }
 
-pub(super) const fn default_timeout() -> Duration { IO_TIMEOUT }
+pub(super) const fn default_timeout() -> Duration { READ_TIMEOUT }
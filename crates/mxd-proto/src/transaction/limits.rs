@@ -0,0 +1,152 @@
+//! Payload limits per transaction type.
+//!
+//! Requests of different types carry very different amounts of data: a chat
+//! line needs a few hundred bytes while a news article or an account list may
+//! need far more. Every decoder checks a transaction's declared `total_size`
+//! against the [`PayloadLimits`] it was given before buffering any of it, and
+//! rejects larger ones with [`TransactionError::PayloadTooLarge`]. Types
+//! without a limit of their own keep [`MAX_PAYLOAD_SIZE`]. Decoders built
+//! without limits use [`PayloadLimits::default`]; servers hand their own to
+//! the decoders of each connection.
+
+use std::collections::BTreeMap;
+
+use super::{
+    FrameHeader,
+    MAX_PAYLOAD_SIZE,
+    TransactionError,
+    param_limit::{DEFAULT_MAX_PARAM_COUNT, check_param_count},
+    read_u16,
+};
+use crate::transaction_type::TransactionType;
+
+/// Largest limit any transaction type may be given.
+///
+/// Limits are buffering budgets, so this bounds the memory one connection
+/// may hold for a single request.
+pub const MAX_PAYLOAD_LIMIT: usize = 16 * 1024 * 1024; // 16 MiB
+
+/// Largest payload accepted for each transaction type, and the most
+/// parameters one payload may declare.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PayloadLimits {
+    by_type: BTreeMap<u16, usize>,
+    max_params: usize,
+}
+
+impl Default for PayloadLimits {
+    fn default() -> Self { Self::new() }
+}
+
+impl PayloadLimits {
+    /// Limits giving every type [`MAX_PAYLOAD_SIZE`] and
+    /// [`DEFAULT_MAX_PARAM_COUNT`] parameters.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            by_type: BTreeMap::new(),
+            max_params: DEFAULT_MAX_PARAM_COUNT,
+        }
+    }
+
+    /// Give `ty` its own limit, clamped to `0..=MAX_PAYLOAD_LIMIT`.
+    #[must_use]
+    pub fn with_limit(mut self, ty: TransactionType, limit: usize) -> Self {
+        self.by_type
+            .insert(u16::from(ty), limit.min(MAX_PAYLOAD_LIMIT));
+        self
+    }
+
+    /// Accept at most `max_params` parameters in one payload.
+    #[must_use]
+    pub const fn with_max_params(mut self, max_params: usize) -> Self {
+        self.max_params = max_params;
+        self
+    }
+
+    /// Return the largest payload accepted for `ty`.
+    #[must_use]
+    pub fn limit(&self, ty: TransactionType) -> usize {
+        self.by_type
+            .get(&u16::from(ty))
+            .copied()
+            .unwrap_or(MAX_PAYLOAD_SIZE)
+    }
+
+    /// Return the largest payload accepted for any type, which bounds how
+    /// much a connection buffers for one transaction.
+    #[must_use]
+    pub fn largest(&self) -> usize {
+        self.by_type
+            .values()
+            .copied()
+            .fold(MAX_PAYLOAD_SIZE, usize::max)
+    }
+
+    /// Return the most parameters one payload may declare.
+    #[must_use]
+    pub const fn max_params(&self) -> usize { self.max_params }
+
+    /// Reject a transaction declaring more than its type's [`limit`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::PayloadTooLarge`] when `header.total_size`
+    /// exceeds the limit.
+    ///
+    /// [`limit`]: Self::limit
+    pub fn check_header(&self, header: &FrameHeader) -> Result<(), TransactionError> {
+        if header.total_size as usize > self.limit(TransactionType::from(header.ty)) {
+            return Err(TransactionError::PayloadTooLarge);
+        }
+        Ok(())
+    }
+
+    /// Reject a parameter block declaring more than [`max_params`] fields,
+    /// before any of them is read.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::TooManyParams`] when the declared count
+    /// exceeds the cap.
+    ///
+    /// [`max_params`]: Self::max_params
+    pub fn check_params(&self, payload: &[u8]) -> Result<(), TransactionError> {
+        let Some(count) = payload.get(..2) else {
+            return Ok(());
+        };
+        check_param_count(read_u16(count)?, self.max_params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Looking up and clamping per-type limits.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn types_without_a_limit_keep_the_default() {
+        let limits = PayloadLimits::new().with_limit(TransactionType::SendChat, 4096);
+
+        assert_eq!(limits.limit(TransactionType::SendChat), 4096);
+        assert_eq!(
+            limits.limit(TransactionType::PostNewsArticle),
+            MAX_PAYLOAD_SIZE
+        );
+        assert_eq!(limits.largest(), MAX_PAYLOAD_SIZE);
+    }
+
+    #[rstest]
+    fn raised_limits_stop_at_the_ceiling() {
+        let limits = PayloadLimits::new().with_limit(TransactionType::PostNewsArticle, usize::MAX);
+
+        assert_eq!(
+            limits.limit(TransactionType::PostNewsArticle),
+            MAX_PAYLOAD_LIMIT
+        );
+        assert_eq!(limits.largest(), MAX_PAYLOAD_LIMIT);
+    }
+}
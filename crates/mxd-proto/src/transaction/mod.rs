@@ -8,31 +8,49 @@
 
 use std::time::Duration;
 
+pub mod checksum;
 pub mod errors;
 pub mod frame;
 #[cfg(kani)]
 pub(crate) mod kani_support;
+pub mod limits;
+pub mod param_limit;
 pub mod params;
 pub mod reader;
+pub mod reassembly;
 pub mod writer;
 
+pub use checksum::{append_checksum, verify_checksum};
 pub use errors::TransactionError;
 pub use frame::{
     FrameHeader,
     Transaction,
     parse_transaction,
+    parse_transaction_limited,
+    parse_transaction_ref,
     read_u16,
     read_u32,
     write_u16,
     write_u32,
 };
+pub use limits::{MAX_PAYLOAD_LIMIT, PayloadLimits};
+pub use mxd_proto_derive::TransactionParams;
+pub use param_limit::DEFAULT_MAX_PARAM_COUNT;
 pub use params::{
+    ParamValue,
+    ReplyParams,
+    TransactionParams,
     decode_params,
+    decode_params_borrowed,
     decode_params_map,
+    decode_params_map_borrowed,
     encode_params,
     first_param_i32,
     first_param_string,
     first_param_u32,
+    param,
+    param_or_default,
+    param_unless_blank,
     required_param_i32,
     required_param_string,
     required_param_u32,
@@ -45,16 +63,25 @@ pub use reader::{
     TransactionReader,
     TransactionStreamReader,
 };
+pub use reassembly::{DEFAULT_REASSEMBLY_TIMEOUT, ReassemblySnapshot, reassembly_stats};
 pub use writer::TransactionWriter;
 
 /// Length of a transaction frame header in bytes.
 pub const HEADER_LEN: usize = 20;
 /// Maximum allowed payload size for a buffered transaction.
 ///
-/// Streaming readers and writers may be configured with larger limits when
-/// handling file transfers or other large payloads.
+/// Incoming transactions are held to their type's [`PayloadLimits::limit`],
+/// which defaults to this. Streaming readers and writers may be configured with
+/// larger limits when handling file transfers or other large payloads.
 pub const MAX_PAYLOAD_SIZE: usize = 1024 * 1024; // 1 MiB
 /// Maximum data size per frame when writing.
 pub const MAX_FRAME_DATA: usize = 32 * 1024; // 32 KiB
-/// Default I/O timeout when reading or writing transactions.
-pub const IO_TIMEOUT: Duration = Duration::from_secs(5);
+/// Largest per-frame data size a peer may negotiate with the mxd
+/// `MaxFrameData` (166) login field; one frame never needs to exceed a
+/// buffered payload.
+pub const MAX_NEGOTIATED_FRAME_DATA: usize = MAX_PAYLOAD_SIZE;
+/// Default timeout for each read of a transaction frame.
+pub const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default timeout for each write of a transaction frame; shorter than
+/// [`READ_TIMEOUT`] so a peer that stops reading is given up on first.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(3);
@@ -0,0 +1,23 @@
+//! Cap on the number of parameters in one payload.
+//!
+//! A parameter block declares its own field count, and each field may be as
+//! small as four bytes, so a 1 MiB payload can claim tens of thousands of
+//! fields. Decoders given [`PayloadLimits`](super::PayloadLimits) reject
+//! blocks declaring more than its [`max_params`](super::PayloadLimits::max_params)
+//! fields with [`TransactionError::TooManyParams`] before reading any of them.
+//! The cap defaults to [`DEFAULT_MAX_PARAM_COUNT`].
+
+use super::TransactionError;
+
+/// Parameter cap used by [`PayloadLimits::new`](super::PayloadLimits::new).
+///
+/// Large enough for replies listing every file in a busy folder.
+pub const DEFAULT_MAX_PARAM_COUNT: usize = 4096;
+
+/// Reject a parameter block declaring more than `max_params` fields.
+pub(super) fn check_param_count(count: u16, max_params: usize) -> Result<(), TransactionError> {
+    if usize::from(count) > max_params {
+        return Err(TransactionError::TooManyParams(count));
+    }
+    Ok(())
+}
@@ -0,0 +1,248 @@
+//! Typed extraction of request structs from parameter blocks.
+//!
+//! A request struct derives [`TransactionParams`] and names the field each of
+//! its members is read from; the derive calls [`param`],
+//! [`param_or_default`], or [`param_unless_blank`] for each one. How a value
+//! is decoded, and whether it may be absent, follows from the member's type
+//! through [`ParamValue`], so every request reports a missing or malformed
+//! parameter with the same [`TransactionError`].
+
+use std::{collections::HashMap, hash::BuildHasher};
+
+use super::{decode_params_map_borrowed, first_value, parse_protocol_u32};
+use crate::{field_id::FieldId, transaction::TransactionError};
+
+/// A request that can be read from a decoded parameter block.
+///
+/// Implement it with `#[derive(TransactionParams)]`.
+pub trait TransactionParams: Sized {
+    /// Read the request from a parameter map.
+    ///
+    /// # Errors
+    /// Returns [`TransactionError::MissingField`] when a required parameter
+    /// is absent, or [`TransactionError::InvalidParamValue`] when one does
+    /// not decode as its member's type.
+    fn from_params<V: AsRef<[u8]>, S: BuildHasher>(
+        params: &HashMap<FieldId, Vec<V>, S>,
+    ) -> Result<Self, TransactionError>;
+
+    /// Decode `payload` and read the request from it without copying the
+    /// parameters it does not keep.
+    ///
+    /// # Errors
+    /// Returns an error if the payload does not decode or, as for
+    /// [`Self::from_params`], a parameter is missing or malformed.
+    fn from_payload(payload: &[u8]) -> Result<Self, TransactionError> {
+        Self::from_params(&decode_params_map_borrowed(payload)?)
+    }
+}
+
+/// A type a single parameter decodes to.
+pub trait ParamValue: Sized {
+    /// Decode the first `field` parameter, given as `None` when it was not
+    /// sent.
+    ///
+    /// # Errors
+    /// Returns [`TransactionError::MissingField`] when the type needs a value
+    /// and none was sent, or [`TransactionError::InvalidParamValue`] when the
+    /// bytes do not decode.
+    fn decode_param(field: FieldId, value: Option<&[u8]>) -> Result<Self, TransactionError>;
+
+    /// Whether the value means the same as leaving the parameter out.
+    fn is_blank(&self) -> bool;
+}
+
+impl ParamValue for String {
+    fn decode_param(field: FieldId, value: Option<&[u8]>) -> Result<Self, TransactionError> {
+        let bytes = value.ok_or(TransactionError::MissingField(field))?;
+        std::str::from_utf8(bytes)
+            .map(str::to_owned)
+            .map_err(|_| TransactionError::InvalidParamValue(field))
+    }
+
+    fn is_blank(&self) -> bool { self.is_empty() }
+}
+
+impl ParamValue for Vec<u8> {
+    fn decode_param(field: FieldId, value: Option<&[u8]>) -> Result<Self, TransactionError> {
+        value
+            .map(<[u8]>::to_vec)
+            .ok_or(TransactionError::MissingField(field))
+    }
+
+    fn is_blank(&self) -> bool { self.is_empty() }
+}
+
+impl ParamValue for i32 {
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    fn decode_param(field: FieldId, value: Option<&[u8]>) -> Result<Self, TransactionError> {
+        let bytes = value.ok_or(TransactionError::MissingField(field))?;
+        let arr: [u8; 4] = bytes
+            .try_into()
+            .map_err(|_| TransactionError::InvalidParamValue(field))?;
+        Ok(Self::from_be_bytes(arr))
+    }
+
+    fn is_blank(&self) -> bool { *self == 0 }
+}
+
+/// Accepts the 16-bit and 32-bit encodings clients use interchangeably.
+impl ParamValue for u32 {
+    fn decode_param(field: FieldId, value: Option<&[u8]>) -> Result<Self, TransactionError> {
+        parse_protocol_u32(value.ok_or(TransactionError::MissingField(field))?, field)
+    }
+
+    fn is_blank(&self) -> bool { *self == 0 }
+}
+
+/// A flag: set by any non-zero 16-bit or 32-bit value, clear when absent.
+impl ParamValue for bool {
+    fn decode_param(field: FieldId, value: Option<&[u8]>) -> Result<Self, TransactionError> {
+        Ok(value
+            .map(|bytes| parse_protocol_u32(bytes, field))
+            .transpose()?
+            .is_some_and(|flag| flag != 0))
+    }
+
+    fn is_blank(&self) -> bool { !*self }
+}
+
+/// An optional parameter: `None` when absent, decoded as `T` otherwise.
+impl<T: ParamValue> ParamValue for Option<T> {
+    fn decode_param(field: FieldId, value: Option<&[u8]>) -> Result<Self, TransactionError> {
+        value
+            .map(|bytes| T::decode_param(field, Some(bytes)))
+            .transpose()
+    }
+
+    fn is_blank(&self) -> bool { self.as_ref().is_none_or(ParamValue::is_blank) }
+}
+
+/// Read the first `field` parameter as `T`.
+///
+/// # Errors
+/// Returns an error if `T` rejects the value or its absence.
+#[must_use = "handle the result"]
+pub fn param<T: ParamValue, V: AsRef<[u8]>, S: BuildHasher>(
+    params: &HashMap<FieldId, Vec<V>, S>,
+    field: FieldId,
+) -> Result<T, TransactionError> {
+    T::decode_param(field, first_value(params, field))
+}
+
+/// Read the first `field` parameter as `T`, or `T`'s default when absent.
+///
+/// # Errors
+/// Returns [`TransactionError::InvalidParamValue`] if the value does not
+/// decode.
+#[must_use = "handle the result"]
+pub fn param_or_default<T: ParamValue + Default, V: AsRef<[u8]>, S: BuildHasher>(
+    params: &HashMap<FieldId, Vec<V>, S>,
+    field: FieldId,
+) -> Result<T, TransactionError> {
+    Ok(param::<Option<T>, V, S>(params, field)?.unwrap_or_default())
+}
+
+/// Read the first `field` parameter as `T`, treating a zero or empty value
+/// like an absent one.
+///
+/// # Errors
+/// Returns [`TransactionError::InvalidParamValue`] if the value does not
+/// decode.
+#[must_use = "handle the result"]
+pub fn param_unless_blank<T: ParamValue, V: AsRef<[u8]>, S: BuildHasher>(
+    params: &HashMap<FieldId, Vec<V>, S>,
+    field: FieldId,
+) -> Result<Option<T>, TransactionError> {
+    Ok(param::<Option<T>, V, S>(params, field)?.filter(|value| !value.is_blank()))
+}
+
+#[cfg(test)]
+#[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+mod tests {
+    //! Reading derived requests from parameter blocks.
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::transaction::{TransactionParams, encode_params};
+
+    #[derive(Debug, PartialEq, Eq, TransactionParams)]
+    struct Sample {
+        #[param(NewsPath)]
+        path: String,
+        #[param(NewsArticleId, omit_blank)]
+        parent_id: Option<i32>,
+        #[param(NewsArticleFlags, default)]
+        flags: i32,
+        #[param(NewsRecursiveDelete)]
+        recursive: bool,
+        #[param(FilePath)]
+        folder: Option<Vec<u8>>,
+    }
+
+    #[rstest]
+    fn reads_every_member() {
+        let payload = encode_params::<&[u8]>(&[
+            (FieldId::NewsPath, b"General".as_slice()),
+            (FieldId::NewsArticleId, &7_i32.to_be_bytes()),
+            (FieldId::NewsArticleFlags, &2_i32.to_be_bytes()),
+            (FieldId::NewsRecursiveDelete, &1_u16.to_be_bytes()),
+            (FieldId::FilePath, &[0, 0]),
+        ])
+        .expect("payload encodes");
+
+        assert_eq!(
+            Sample::from_payload(&payload).expect("sample reads"),
+            Sample {
+                path: "General".to_owned(),
+                parent_id: Some(7),
+                flags: 2,
+                recursive: true,
+                folder: Some(vec![0, 0]),
+            }
+        );
+    }
+
+    #[rstest]
+    fn absent_and_blank_parameters_fall_back() {
+        let payload = encode_params::<&[u8]>(&[
+            (FieldId::NewsPath, b"General".as_slice()),
+            (FieldId::NewsArticleId, &0_i32.to_be_bytes()),
+        ])
+        .expect("payload encodes");
+
+        assert_eq!(
+            Sample::from_payload(&payload).expect("sample reads"),
+            Sample {
+                path: "General".to_owned(),
+                parent_id: None,
+                flags: 0,
+                recursive: false,
+                folder: None,
+            }
+        );
+    }
+
+    #[rstest]
+    #[case::missing(&[], TransactionError::MissingField(FieldId::NewsPath))]
+    #[case::not_utf8(
+        &[(FieldId::NewsPath, &[0xff][..])],
+        TransactionError::InvalidParamValue(FieldId::NewsPath)
+    )]
+    #[case::short_integer(
+        &[(FieldId::NewsPath, &b"General"[..]), (FieldId::NewsArticleFlags, &[0, 1][..])],
+        TransactionError::InvalidParamValue(FieldId::NewsArticleFlags)
+    )]
+    fn rejects_missing_or_malformed_parameters(
+        #[case] params: &[(FieldId, &[u8])],
+        #[case] expected: TransactionError,
+    ) {
+        let payload = encode_params(params).expect("payload encodes");
+
+        assert_eq!(
+            Sample::from_payload(&payload).map_err(|error| error.to_string()),
+            Err(expected.to_string())
+        );
+    }
+}
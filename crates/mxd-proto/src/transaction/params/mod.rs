@@ -3,11 +3,30 @@
 //! The payload for most transactions is a list of parameters, each keyed by a
 //! 16-bit [`FieldId`]. This module validates and serializes that parameter
 //! structure.
+//!
+//! Decoding comes in two flavours. [`decode_params_borrowed`] and
+//! [`decode_params_map_borrowed`] return slices of the payload and allocate
+//! nothing per field, so request parsing on hot paths uses them.
+//! [`decode_params`] copies each value for callers that edit the block and
+//! encode it again. The `first_param_*` and `required_param_*` helpers accept
+//! any of the resulting maps.
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::BuildHasher,
+};
 
 use super::{FrameHeader, Transaction, errors::TransactionError, read_u16};
-use crate::{field_id::FieldId, transaction_type::TransactionType};
+use crate::{
+    field_id::{FieldId, note_unknown_field},
+    transaction_type::TransactionType,
+};
+
+mod extract;
+mod reply;
+
+pub use extract::{ParamValue, TransactionParams, param, param_or_default, param_unless_blank};
+pub use reply::ReplyParams;
 
 /// Determine whether duplicate instances of the given field id are permitted.
 const fn duplicate_allowed(fid: FieldId, context: DuplicateContext) -> bool {
@@ -69,11 +88,11 @@ fn iter_params(
     if buf.len() < 2 {
         return Err(TransactionError::SizeMismatch);
     }
-    let param_count = read_u16(&buf[0..2])? as usize;
+    let param_count = read_u16(&buf[0..2])?;
     Ok(ParamIter {
         buf,
         offset: 2,
-        remaining: param_count,
+        remaining: usize::from(param_count),
         seen: HashSet::new(),
         error: None,
         duplicate_context,
@@ -126,6 +145,7 @@ impl Iterator for ParamIter<'_> {
             return None;
         }
         let fid = FieldId::from(field_id);
+        note_unknown_field(fid);
         if let Err(e) = check_duplicate(fid, &mut self.seen, self.duplicate_context) {
             self.error = Some(e);
             return None;
@@ -186,40 +206,71 @@ pub fn validate_payload_parts(
     iter.finish(payload.len())
 }
 
-/// Decode the parameter block into a vector of field id/value pairs.
+/// Decode the parameter block into field id/value pairs that borrow from
+/// `buf`.
 ///
 /// # Errors
 /// Returns an error if the buffer cannot be parsed.
 #[must_use = "handle the result"]
-#[expect(
-    clippy::indexing_slicing,
-    reason = "bounds are validated by iter_params"
-)]
-pub fn decode_params(buf: &[u8]) -> Result<Vec<(FieldId, Vec<u8>)>, TransactionError> {
+pub fn decode_params_borrowed(buf: &[u8]) -> Result<Vec<(FieldId, &[u8])>, TransactionError> {
     if buf.is_empty() {
         return Ok(Vec::new());
     }
     let mut iter = iter_params(buf, DuplicateContext::DECODE_ONLY)?;
     let mut params = Vec::new();
     for (fid, start, len) in &mut iter {
-        params.push((fid, buf[start..start + len].to_vec()));
+        let value = buf
+            .get(start..start + len)
+            .ok_or(TransactionError::SizeMismatch)?;
+        params.push((fid, value));
     }
     iter.finish(buf.len())?;
     Ok(params)
 }
 
+/// Decode the parameter block into a vector of field id/value pairs.
+///
+/// Each value is copied; prefer [`decode_params_borrowed`] when the payload
+/// outlives the parameters.
+///
+/// # Errors
+/// Returns an error if the buffer cannot be parsed.
+#[must_use = "handle the result"]
+pub fn decode_params(buf: &[u8]) -> Result<Vec<(FieldId, Vec<u8>)>, TransactionError> {
+    Ok(decode_params_borrowed(buf)?
+        .into_iter()
+        .map(|(fid, value)| (fid, value.to_vec()))
+        .collect())
+}
+
+/// Decode the parameter block into a map keyed by `FieldId` whose values
+/// borrow from `buf`.
+///
+/// # Errors
+/// Returns an error if the buffer cannot be parsed.
+#[must_use = "handle the result"]
+pub fn decode_params_map_borrowed(
+    buf: &[u8],
+) -> Result<HashMap<FieldId, Vec<&[u8]>>, TransactionError> {
+    Ok(group_params(decode_params_borrowed(buf)?))
+}
+
 /// Decode the parameter block into a map keyed by `FieldId`.
 ///
 /// # Errors
 /// Returns an error if the buffer cannot be parsed.
 #[must_use = "handle the result"]
 pub fn decode_params_map(buf: &[u8]) -> Result<HashMap<FieldId, Vec<Vec<u8>>>, TransactionError> {
-    let params = decode_params(buf)?;
-    let mut map: HashMap<FieldId, Vec<Vec<u8>>> = HashMap::new();
+    Ok(group_params(decode_params(buf)?))
+}
+
+/// Group decoded pairs by field, keeping repeated values in payload order.
+fn group_params<V>(params: Vec<(FieldId, V)>) -> HashMap<FieldId, Vec<V>> {
+    let mut map: HashMap<FieldId, Vec<V>> = HashMap::new();
     for (fid, value) in params {
         map.entry(fid).or_default().push(value);
     }
-    Ok(map)
+    map
 }
 
 /// Build a parameter block from field id/data pairs.
@@ -256,11 +307,11 @@ pub fn encode_params<T: AsRef<[u8]>>(params: &[(FieldId, T)]) -> Result<Vec<u8>,
 /// Retrieve the first value for `field` from a parameter map.
 ///
 /// Returns `None` if the field is absent.
-fn first_value<S: std::hash::BuildHasher>(
-    map: &HashMap<FieldId, Vec<Vec<u8>>, S>,
+fn first_value<V: AsRef<[u8]>, S: BuildHasher>(
+    map: &HashMap<FieldId, Vec<V>, S>,
     field: FieldId,
 ) -> Option<&[u8]> {
-    map.get(&field).and_then(|v| v.first()).map(Vec::as_slice)
+    map.get(&field).and_then(|v| v.first()).map(AsRef::as_ref)
 }
 
 /// Return the first value for `field` in a parameter map as a `String`.
@@ -272,8 +323,8 @@ fn first_value<S: std::hash::BuildHasher>(
 /// Returns [`TransactionError::InvalidParamValue`] if the parameter value is
 /// not valid UTF-8.
 #[must_use = "handle the result"]
-pub fn first_param_string<S: std::hash::BuildHasher>(
-    map: &HashMap<FieldId, Vec<Vec<u8>>, S>,
+pub fn first_param_string<V: AsRef<[u8]>, S: BuildHasher>(
+    map: &HashMap<FieldId, Vec<V>, S>,
     field: FieldId,
 ) -> Result<Option<String>, TransactionError> {
     match first_value(map, field) {
@@ -292,8 +343,8 @@ pub fn first_param_string<S: std::hash::BuildHasher>(
 /// Returns [`TransactionError::MissingField`] if the field is absent, or
 /// [`TransactionError::InvalidParamValue`] if the value is not valid UTF-8.
 #[must_use = "handle the result"]
-pub fn required_param_string<S: std::hash::BuildHasher>(
-    map: &HashMap<FieldId, Vec<Vec<u8>>, S>,
+pub fn required_param_string<V: AsRef<[u8]>, S: BuildHasher>(
+    map: &HashMap<FieldId, Vec<V>, S>,
     field: FieldId,
 ) -> Result<String, TransactionError> {
     first_param_string(map, field)?.ok_or(TransactionError::MissingField(field))
@@ -306,8 +357,8 @@ pub fn required_param_string<S: std::hash::BuildHasher>(
 /// [`TransactionError::InvalidParamValue`] if the value cannot be parsed as `i32`.
 #[must_use = "handle the result"]
 #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
-pub fn required_param_i32<S: std::hash::BuildHasher>(
-    map: &HashMap<FieldId, Vec<Vec<u8>>, S>,
+pub fn required_param_i32<V: AsRef<[u8]>, S: BuildHasher>(
+    map: &HashMap<FieldId, Vec<V>, S>,
     field: FieldId,
 ) -> Result<i32, TransactionError> {
     let bytes = first_value(map, field).ok_or(TransactionError::MissingField(field))?;
@@ -327,8 +378,8 @@ pub fn required_param_i32<S: std::hash::BuildHasher>(
 /// parsed as `i32`.
 #[must_use = "handle the result"]
 #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
-pub fn first_param_i32<S: std::hash::BuildHasher>(
-    map: &HashMap<FieldId, Vec<Vec<u8>>, S>,
+pub fn first_param_i32<V: AsRef<[u8]>, S: BuildHasher>(
+    map: &HashMap<FieldId, Vec<V>, S>,
     field: FieldId,
 ) -> Result<Option<i32>, TransactionError> {
     match first_value(map, field) {
@@ -350,8 +401,8 @@ pub fn first_param_i32<S: std::hash::BuildHasher>(
 /// [`TransactionError::InvalidParamValue`] if the value cannot be parsed as a
 /// 16-bit or 32-bit big-endian unsigned integer.
 #[must_use = "handle the result"]
-pub fn required_param_u32<S: std::hash::BuildHasher>(
-    map: &HashMap<FieldId, Vec<Vec<u8>>, S>,
+pub fn required_param_u32<V: AsRef<[u8]>, S: BuildHasher>(
+    map: &HashMap<FieldId, Vec<V>, S>,
     field: FieldId,
 ) -> Result<u32, TransactionError> {
     first_param_u32(map, field)?.ok_or(TransactionError::MissingField(field))
@@ -364,8 +415,8 @@ pub fn required_param_u32<S: std::hash::BuildHasher>(
 /// Returns [`TransactionError::InvalidParamValue`] if the value length is not
 /// two or four bytes.
 #[must_use = "handle the result"]
-pub fn first_param_u32<S: std::hash::BuildHasher>(
-    map: &HashMap<FieldId, Vec<Vec<u8>>, S>,
+pub fn first_param_u32<V: AsRef<[u8]>, S: BuildHasher>(
+    map: &HashMap<FieldId, Vec<V>, S>,
     field: FieldId,
 ) -> Result<Option<u32>, TransactionError> {
     match first_value(map, field) {
@@ -397,3 +448,70 @@ fn parse_protocol_u32_exact(bytes: &[u8], field: FieldId) -> Result<u32, Transac
         .map_err(|_| TransactionError::InvalidParamValue(field))?;
     Ok(u32::from_be_bytes(arr))
 }
+
+#[cfg(test)]
+mod tests {
+    //! Borrowed, shared, and copied decoding of parameter blocks.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn sample_block() -> Vec<u8> {
+        encode_params::<&[u8]>(&[
+            (FieldId::FileName, b"hello".as_slice()),
+            (FieldId::UserId, &[0, 7]),
+            (FieldId::FileName, b"again".as_slice()),
+        ])
+        .expect("block encodes")
+    }
+
+    #[rstest]
+    fn borrowed_values_point_into_the_payload() {
+        let block = sample_block();
+        let params = decode_params_borrowed(&block).expect("block decodes");
+
+        let owned = decode_params(&block).expect("block decodes");
+        assert_eq!(
+            params,
+            owned
+                .iter()
+                .map(|(fid, value)| (*fid, value.as_slice()))
+                .collect::<Vec<_>>()
+        );
+        let range = block.as_ptr_range();
+        assert!(
+            params
+                .iter()
+                .all(|(_, value)| range.contains(&value.as_ptr()))
+        );
+    }
+
+    #[rstest]
+    fn borrowed_maps_read_like_owned_ones() {
+        let block = sample_block();
+        let borrowed = decode_params_map_borrowed(&block).expect("block decodes");
+        let owned = decode_params_map(&block).expect("block decodes");
+
+        assert_eq!(
+            first_param_string(&borrowed, FieldId::FileName).expect("utf-8"),
+            first_param_string(&owned, FieldId::FileName).expect("utf-8")
+        );
+        assert_eq!(
+            required_param_u32(&borrowed, FieldId::UserId).expect("user id"),
+            7
+        );
+        assert_eq!(borrowed.get(&FieldId::FileName).map(Vec::len), Some(2));
+    }
+
+    #[rstest]
+    fn truncated_blocks_are_rejected() {
+        let mut block = sample_block();
+        block.truncate(block.len() - 1);
+
+        assert!(matches!(
+            decode_params_borrowed(&block),
+            Err(TransactionError::SizeMismatch)
+        ));
+    }
+}
@@ -0,0 +1,241 @@
+//! Fluent construction of reply parameter blocks.
+//!
+//! Handlers describe a reply field by field with typed setters instead of
+//! assembling `(FieldId, Vec<u8>)` pairs and big-endian byte arrays by hand.
+//! Sizes are checked as fields are added: a value longer than the 16-bit
+//! length prefix allows, more fields than the 16-bit count allows, or a block
+//! larger than [`MAX_PAYLOAD_SIZE`] is remembered and reported when the block
+//! is finished, so a chain of setters never needs to stop for errors.
+
+use super::encode_params;
+use crate::{
+    field_id::FieldId,
+    transaction::{MAX_PAYLOAD_SIZE, TransactionError},
+};
+
+/// Bytes the parameter count occupies at the start of a block.
+const COUNT_LEN: usize = 2;
+/// Bytes each field's identifier and length prefix occupy.
+const FIELD_HEADER_LEN: usize = 4;
+
+/// Size problem found while adding fields; reported by the finishers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Oversize {
+    /// A value did not fit the 16-bit length prefix.
+    Value(FieldId),
+    /// The block outgrew the 16-bit count or [`MAX_PAYLOAD_SIZE`].
+    Block,
+}
+
+/// Builder for the parameter block of a reply.
+///
+/// ```
+/// use mxd_proto::{field_id::FieldId, transaction::params::ReplyParams};
+///
+/// let payload = ReplyParams::new()
+///     .string(FieldId::NewsTitle, "Welcome")
+///     .i32(FieldId::NewsArticleFlags, 0)
+///     .encode()
+///     .expect("reply fits");
+/// assert_eq!(payload.get(..2), Some([0, 2].as_slice()));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[must_use = "finish the reply with `encode` or `finish`"]
+pub struct ReplyParams {
+    params: Vec<(FieldId, Vec<u8>)>,
+    encoded_len: usize,
+    oversize: Option<Oversize>,
+}
+
+impl Default for ReplyParams {
+    fn default() -> Self { Self::new() }
+}
+
+impl ReplyParams {
+    /// Start an empty reply.
+    pub const fn new() -> Self {
+        Self {
+            params: Vec::new(),
+            encoded_len: COUNT_LEN,
+            oversize: None,
+        }
+    }
+
+    /// Add `value` as UTF-8 text.
+    pub fn string(self, field: FieldId, value: &str) -> Self { self.blob(field, value.as_bytes()) }
+
+    /// Add `value` as a big-endian 16-bit integer.
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    pub fn i16(self, field: FieldId, value: i16) -> Self { self.blob(field, value.to_be_bytes()) }
+
+    /// Add `value` as a big-endian 32-bit integer.
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    pub fn i32(self, field: FieldId, value: i32) -> Self { self.blob(field, value.to_be_bytes()) }
+
+    /// Add `value` as a big-endian unsigned 32-bit integer, as sizes and
+    /// counts travel.
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    pub fn u32(self, field: FieldId, value: u32) -> Self { self.blob(field, value.to_be_bytes()) }
+
+    /// Add a timestamp given as milliseconds since the Unix epoch in UTC,
+    /// sent as a big-endian 64-bit integer.
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    pub fn timestamp(self, field: FieldId, millis: i64) -> Self {
+        self.blob(field, millis.to_be_bytes())
+    }
+
+    /// Add `value` unchanged.
+    pub fn blob(mut self, field: FieldId, value: impl Into<Vec<u8>>) -> Self {
+        let bytes = value.into();
+        if u16::try_from(bytes.len()).is_err() {
+            self.oversize.get_or_insert(Oversize::Value(field));
+        }
+        self.encoded_len = self
+            .encoded_len
+            .saturating_add(FIELD_HEADER_LEN)
+            .saturating_add(bytes.len());
+        self.params.push((field, bytes));
+        if self.params.len() > usize::from(u16::MAX) || self.encoded_len > MAX_PAYLOAD_SIZE {
+            self.oversize.get_or_insert(Oversize::Block);
+        }
+        self
+    }
+
+    /// Apply `set` to `value` when there is one, for fields a reply may
+    /// leave out.
+    ///
+    /// ```
+    /// use mxd_proto::{field_id::FieldId, transaction::params::ReplyParams};
+    ///
+    /// let poster: Option<&str> = None;
+    /// let reply = ReplyParams::new().optional(poster, |reply, name| {
+    ///     reply.string(FieldId::NewsPoster, name)
+    /// });
+    /// assert!(reply.is_empty());
+    /// ```
+    pub fn optional<T>(self, value: Option<T>, set: impl FnOnce(Self, T) -> Self) -> Self {
+        match value {
+            Some(present) => set(self, present),
+            None => self,
+        }
+    }
+
+    /// Number of fields added so far.
+    #[must_use]
+    pub const fn len(&self) -> usize { self.params.len() }
+
+    /// Whether no fields have been added.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool { self.params.is_empty() }
+
+    /// The fields added so far, in order.
+    #[must_use]
+    pub fn as_slice(&self) -> &[(FieldId, Vec<u8>)] { &self.params }
+
+    /// Return the fields as `(FieldId, value)` pairs.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::InvalidParamValue`] naming the first value
+    /// too long for its length prefix, or
+    /// [`TransactionError::PayloadTooLarge`] when the block has too many
+    /// fields or exceeds [`MAX_PAYLOAD_SIZE`].
+    pub fn finish(self) -> Result<Vec<(FieldId, Vec<u8>)>, TransactionError> {
+        match self.oversize {
+            None => Ok(self.params),
+            Some(Oversize::Value(field)) => Err(TransactionError::InvalidParamValue(field)),
+            Some(Oversize::Block) => Err(TransactionError::PayloadTooLarge),
+        }
+    }
+
+    /// Encode the fields as a parameter block.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`ReplyParams::finish`].
+    pub fn encode(self) -> Result<Vec<u8>, TransactionError> { encode_params(&self.finish()?) }
+}
+
+impl Extend<(FieldId, Vec<u8>)> for ReplyParams {
+    fn extend<I: IntoIterator<Item = (FieldId, Vec<u8>)>>(&mut self, iter: I) {
+        for (field, value) in iter {
+            *self = std::mem::take(self).blob(field, value);
+        }
+    }
+}
+
+impl FromIterator<(FieldId, Vec<u8>)> for ReplyParams {
+    fn from_iter<I: IntoIterator<Item = (FieldId, Vec<u8>)>>(iter: I) -> Self {
+        let mut reply = Self::new();
+        reply.extend(iter);
+        reply
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Building and size-checking reply parameter blocks.
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::transaction::decode_params;
+
+    #[rstest]
+    fn typed_setters_encode_big_endian_values() {
+        let payload = ReplyParams::new()
+            .string(FieldId::NewsTitle, "Hi")
+            .i16(FieldId::NewsArticleFlags, -2)
+            .i32(FieldId::NewsArticleId, 7)
+            .timestamp(FieldId::NewsDate, 2_000)
+            .blob(FieldId::Data, [1, 2, 3])
+            .encode()
+            .expect("reply fits");
+
+        assert_eq!(
+            decode_params(&payload).expect("payload decodes"),
+            vec![
+                (FieldId::NewsTitle, b"Hi".to_vec()),
+                (FieldId::NewsArticleFlags, vec![0xff, 0xfe]),
+                (FieldId::NewsArticleId, vec![0, 0, 0, 7]),
+                (FieldId::NewsDate, vec![0, 0, 0, 0, 0, 0, 0x07, 0xd0]),
+                (FieldId::Data, vec![1, 2, 3]),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn optional_fields_are_left_out_when_absent() {
+        let reply = ReplyParams::new()
+            .optional(Some(3), |reply, id| reply.i32(FieldId::NewsPrevId, id))
+            .optional(None, |reply, id| reply.i32(FieldId::NewsNextId, id));
+
+        assert_eq!(
+            reply.as_slice(),
+            [(FieldId::NewsPrevId, vec![0, 0, 0, 3])].as_slice()
+        );
+    }
+
+    #[rstest]
+    fn oversized_values_name_their_field() {
+        let reply = ReplyParams::new()
+            .blob(FieldId::Data, vec![0; usize::from(u16::MAX) + 1])
+            .string(FieldId::NewsTitle, "after");
+
+        assert!(matches!(
+            reply.encode(),
+            Err(TransactionError::InvalidParamValue(FieldId::Data))
+        ));
+    }
+
+    #[rstest]
+    fn oversized_blocks_are_refused() {
+        let chunk = vec![0; usize::from(u16::MAX)];
+        let reply: ReplyParams = std::iter::repeat_n((FieldId::FileName, chunk), 17).collect();
+
+        assert!(matches!(
+            reply.finish(),
+            Err(TransactionError::PayloadTooLarge)
+        ));
+    }
+}
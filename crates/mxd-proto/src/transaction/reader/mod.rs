@@ -3,7 +3,9 @@
 //! [`TransactionReader`] assembles entire payloads into memory for small
 //! request/response frames. [`TransactionStreamReader`] exposes an incremental
 //! interface that yields payload fragments without buffering the full message,
-//! enabling large file transfers to be processed safely.
+//! enabling large file transfers to be processed safely. Both abandon a
+//! fragmented transaction that is still incomplete after its reassembly age
+//! limit.
 
 #[cfg(kani)]
 mod kani;
@@ -17,18 +19,21 @@ pub use self::streaming::{StreamingTransaction, TransactionFragment, Transaction
 use self::streaming::{
     StreamingTransactionInit,
     build_streaming_transaction,
+    read_continuation,
     validate_continuation_frame,
     validate_first_frame,
 };
 use super::{
     FrameHeader,
-    IO_TIMEOUT,
-    MAX_PAYLOAD_SIZE,
+    READ_TIMEOUT,
     Transaction,
     errors::TransactionError,
     frame::read_frame,
+    limits::PayloadLimits,
     params::validate_payload,
+    reassembly::{DEFAULT_REASSEMBLY_TIMEOUT, Reassembly},
 };
+use crate::transaction_type::TransactionType;
 
 /// Check whether a continuation frame header matches the first frame header.
 pub(crate) const fn headers_match(first: &FrameHeader, next: &FrameHeader) -> bool {
@@ -49,7 +54,9 @@ pub(crate) const fn headers_match(first: &FrameHeader, next: &FrameHeader) -> bo
 pub struct TransactionReader<R> {
     reader: R,
     timeout: Duration,
-    max_payload: usize,
+    max_payload: Option<usize>,
+    limits: PayloadLimits,
+    max_reassembly_age: Duration,
 }
 
 /// Validate the first frame header of a transaction.
@@ -93,14 +100,16 @@ where
     pub fn new(reader: R) -> Self {
         Self {
             reader,
-            timeout: IO_TIMEOUT,
-            max_payload: MAX_PAYLOAD_SIZE,
+            timeout: READ_TIMEOUT,
+            max_payload: None,
+            limits: PayloadLimits::new(),
+            max_reassembly_age: DEFAULT_REASSEMBLY_TIMEOUT,
         }
     }
 
     /// Set the I/O timeout for frame reads.
     ///
-    /// Defaults to [`IO_TIMEOUT`].
+    /// Defaults to [`READ_TIMEOUT`].
     #[must_use]
     pub const fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
@@ -109,35 +118,68 @@ where
 
     /// Set the maximum buffered payload size.
     ///
-    /// Defaults to [`MAX_PAYLOAD_SIZE`]. Transactions declaring a larger
-    /// `total_size` will be rejected with [`TransactionError::PayloadTooLarge`].
+    /// Defaults to the limit for each transaction's type in the reader's
+    /// [`PayloadLimits`]. Transactions declaring a larger `total_size` will be
+    /// rejected with [`TransactionError::PayloadTooLarge`].
     #[must_use]
     pub const fn with_max_payload(mut self, max_payload: usize) -> Self {
-        self.max_payload = max_payload;
+        self.max_payload = Some(max_payload);
+        self
+    }
+
+    /// Hold transactions to `limits` instead of [`PayloadLimits::new`].
+    ///
+    /// A payload declaring more parameters than `limits` allows is rejected
+    /// with [`TransactionError::TooManyParams`] before any is read.
+    #[must_use]
+    pub fn with_payload_limits(mut self, limits: PayloadLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set how long a fragmented transaction may take to arrive in full.
+    ///
+    /// Defaults to [`DEFAULT_REASSEMBLY_TIMEOUT`]. Older transactions are
+    /// abandoned with [`TransactionError::ReassemblyTimeout`].
+    #[must_use]
+    pub const fn with_max_reassembly_age(mut self, max_age: Duration) -> Self {
+        self.max_reassembly_age = max_age;
         self
     }
 
+    /// Borrow the underlying reader, for example to drain it on close.
+    #[must_use]
+    pub const fn get_mut(&mut self) -> &mut R { &mut self.reader }
+
     /// Read the next complete transaction from the underlying reader.
     ///
     /// # Errors
     /// Returns an error if the stream does not contain a valid transaction.
     #[must_use = "handle the result"]
     pub async fn read_transaction(&mut self) -> Result<Transaction, TransactionError> {
-        let (mut header, mut payload) =
-            read_frame(&mut self.reader, self.timeout, self.max_payload).await?;
-        validate_first_header(&header, self.max_payload)?;
+        let ceiling = self.max_payload.unwrap_or_else(|| self.limits.largest());
+        let (mut header, mut payload) = read_frame(&mut self.reader, self.timeout, ceiling).await?;
+        let max_payload = self
+            .max_payload
+            .unwrap_or_else(|| self.limits.limit(TransactionType::from(header.ty)));
+        validate_first_header(&header, max_payload)?;
 
         let mut remaining = header.total_size - header.data_size;
-        while remaining > 0 {
-            let (next_hdr, chunk) =
-                read_frame(&mut self.reader, self.timeout, self.max_payload).await?;
-            validate_continuation_frame(&header, &next_hdr, remaining)?;
-            payload.extend_from_slice(&chunk);
-            remaining -= next_hdr.data_size;
+        if remaining > 0 {
+            let reassembly = Reassembly::begin(self.max_reassembly_age);
+            while remaining > 0 {
+                let (next_hdr, chunk) =
+                    read_continuation(&mut self.reader, &reassembly, self.timeout, max_payload)
+                        .await?;
+                validate_continuation_frame(&header, &next_hdr, remaining)?;
+                payload.extend_from_slice(&chunk);
+                remaining -= next_hdr.data_size;
+            }
         }
 
         header.data_size = header.total_size;
         let tx = Transaction { header, payload };
+        self.limits.check_params(&tx.payload)?;
         validate_payload(&tx)?;
         Ok(tx)
     }
@@ -149,8 +191,9 @@ where
     pub async fn read_streaming_transaction(
         &mut self,
     ) -> Result<StreamingTransaction<'_, R>, TransactionError> {
+        let max_total = self.max_payload.unwrap_or_else(|| self.limits.largest());
         let (first_hdr, first_chunk, remaining) =
-            validate_first_frame(&mut self.reader, self.timeout, self.max_payload).await?;
+            validate_first_frame(&mut self.reader, self.timeout, max_total).await?;
         Ok(build_streaming_transaction(
             &mut self.reader,
             StreamingTransactionInit {
@@ -158,7 +201,8 @@ where
                 first_chunk,
                 remaining,
                 timeout: self.timeout,
-                max_total: self.max_payload,
+                max_total,
+                max_age: self.max_reassembly_age,
             },
         ))
     }
@@ -169,14 +213,61 @@ mod tests {
     //! Tests for this module.
     use std::io::Cursor;
 
-    use tokio::io::BufReader;
+    use tokio::io::{AsyncWriteExt, BufReader, DuplexStream};
 
     use super::*;
-    use crate::wireframe::test_helpers::{
+    use crate::test_support::{
         fragmented_transaction_bytes,
         mismatched_continuation_bytes,
+        transaction_bytes,
     };
 
+    /// Open a stream whose peer sent the first half of a two-fragment
+    /// transaction and then stalled; keep the returned peer alive.
+    async fn stalled_stream() -> (DuplexStream, DuplexStream) {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let header = FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: 107,
+            id: 3,
+            error: 0,
+            total_size: 8,
+            data_size: 4,
+        };
+        client
+            .write_all(&transaction_bytes(&header, &[0u8; 4]))
+            .await
+            .expect("write first fragment");
+        (client, server)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn abandons_stalled_buffered_reassembly() {
+        let (_client, server) = stalled_stream().await;
+        let mut reader =
+            TransactionReader::new(server).with_max_reassembly_age(Duration::from_secs(2));
+
+        let err = reader.read_transaction().await.expect_err("should expire");
+
+        assert!(matches!(err, TransactionError::ReassemblyTimeout));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn abandons_stalled_streaming_reassembly() {
+        let (_client, server) = stalled_stream().await;
+        let mut reader =
+            TransactionStreamReader::new(server).with_max_reassembly_age(Duration::from_secs(2));
+        let mut stream = reader.start_transaction().await.expect("stream");
+        let _first = stream.next_fragment().await.expect("first fragment");
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(stream.reassembly_age(), Some(Duration::from_secs(1)));
+        let err = stream.next_fragment().await.expect_err("should expire");
+
+        assert!(matches!(err, TransactionError::ReassemblyTimeout));
+    }
+
     #[tokio::test]
     async fn streams_large_fragmented_payload() {
         let total = 2 * 1024 * 1024usize;
@@ -2,6 +2,8 @@
 //!
 //! These types enable incremental processing of transaction payloads without
 //! buffering the entire content in memory, suitable for file transfers.
+//! Continuation frames must arrive before the transaction's reassembly age
+//! limit passes; see [`crate::transaction::reassembly`].
 
 use std::time::Duration;
 
@@ -13,6 +15,7 @@ use crate::transaction::{
     MAX_PAYLOAD_SIZE,
     errors::TransactionError,
     frame::{default_timeout, read_frame},
+    reassembly::{DEFAULT_REASSEMBLY_TIMEOUT, Reassembly},
 };
 
 /// Initialization data for constructing a `StreamingTransaction`.
@@ -22,6 +25,7 @@ pub(super) struct StreamingTransactionInit {
     pub remaining: u32,
     pub timeout: Duration,
     pub max_total: usize,
+    pub max_age: Duration,
 }
 
 /// Validate and read the first frame of a streaming transaction.
@@ -52,9 +56,24 @@ pub(super) fn build_streaming_transaction<R: AsyncRead + Unpin>(
         offset: 0,
         pending_first: Some(init.first_chunk),
         max_total: init.max_total,
+        reassembly: (init.remaining > 0).then(|| Reassembly::begin(init.max_age)),
     }
 }
 
+/// Read the next continuation frame, abandoning the transaction once
+/// `reassembly` outlives its age limit.
+pub(super) async fn read_continuation<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    reassembly: &Reassembly,
+    timeout: Duration,
+    max_total: usize,
+) -> Result<(FrameHeader, Vec<u8>), TransactionError> {
+    let window = reassembly.remaining()?;
+    tokio::time::timeout(window, read_frame(reader, timeout, max_total))
+        .await
+        .unwrap_or_else(|_| Err(reassembly.expire()))
+}
+
 /// Validate a continuation frame against the first frame header.
 pub(super) const fn validate_continuation_frame(
     first: &FrameHeader,
@@ -100,6 +119,7 @@ pub struct StreamingTransaction<'a, R> {
     offset: u32,
     pending_first: Option<Vec<u8>>,
     max_total: usize,
+    reassembly: Option<Reassembly>,
 }
 
 impl<R> StreamingTransaction<'_, R>
@@ -110,12 +130,21 @@ where
     #[must_use]
     pub const fn header(&self) -> &FrameHeader { &self.first_header }
 
+    /// Return how long ago the first fragment arrived, or `None` once the
+    /// payload is complete.
+    #[must_use]
+    pub fn reassembly_age(&self) -> Option<Duration> {
+        self.reassembly.as_ref().map(Reassembly::age)
+    }
+
     /// Yield the next fragment in the sequence.
     ///
     /// Returns `Ok(None)` once the final fragment has been returned.
     ///
     /// # Errors
-    /// Returns an error if framing invariants are violated.
+    /// Returns an error if framing invariants are violated, or
+    /// [`TransactionError::ReassemblyTimeout`] if the remaining fragments do
+    /// not arrive within the reassembly age limit.
     pub async fn next_fragment(&mut self) -> Result<Option<TransactionFragment>, TransactionError> {
         if let Some(first) = self.pending_first.take() {
             let is_last = self.remaining == 0;
@@ -129,16 +158,20 @@ where
             return Ok(Some(fragment));
         }
 
-        if self.remaining == 0 {
+        let Some(reassembly) = self.reassembly.as_ref() else {
             return Ok(None);
-        }
+        };
 
-        let (next_hdr, chunk) = read_frame(self.reader, self.timeout, self.max_total).await?;
+        let (next_hdr, chunk) =
+            read_continuation(self.reader, reassembly, self.timeout, self.max_total).await?;
         validate_continuation_frame(&self.first_header, &next_hdr, self.remaining)?;
 
         let offset = self.offset;
         self.remaining -= next_hdr.data_size;
         self.offset += next_hdr.data_size;
+        if self.remaining == 0 {
+            self.reassembly = None;
+        }
 
         Ok(Some(TransactionFragment {
             header: next_hdr,
@@ -159,6 +192,7 @@ pub struct TransactionStreamReader<R> {
     reader: R,
     timeout: Duration,
     max_total: usize,
+    max_reassembly_age: Duration,
 }
 
 impl<R> TransactionStreamReader<R>
@@ -175,12 +209,13 @@ where
             reader,
             timeout: default_timeout(),
             max_total: MAX_PAYLOAD_SIZE,
+            max_reassembly_age: DEFAULT_REASSEMBLY_TIMEOUT,
         }
     }
 
     /// Set the I/O timeout for streaming reads.
     ///
-    /// Defaults to [`IO_TIMEOUT`](crate::transaction::IO_TIMEOUT).
+    /// Defaults to [`READ_TIMEOUT`](crate::transaction::READ_TIMEOUT).
     #[must_use]
     pub const fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
@@ -198,6 +233,15 @@ where
         self
     }
 
+    /// Set how long a fragmented transaction may take to arrive in full.
+    ///
+    /// Defaults to [`DEFAULT_REASSEMBLY_TIMEOUT`].
+    #[must_use]
+    pub const fn with_max_reassembly_age(mut self, max_age: Duration) -> Self {
+        self.max_reassembly_age = max_age;
+        self
+    }
+
     /// Start reading the next transaction from the underlying reader.
     ///
     /// # Errors
@@ -215,6 +259,7 @@ where
                 remaining,
                 timeout: self.timeout,
                 max_total: self.max_total,
+                max_age: self.max_reassembly_age,
             },
         ))
     }
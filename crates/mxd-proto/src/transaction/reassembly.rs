@@ -0,0 +1,142 @@
+//! Age limits and process-wide statistics for multi-fragment reassembly.
+//!
+//! A client that stalls after the first fragment of a transaction leaves a
+//! partial payload buffered until its connection closes. [`HotlineCodec`] and
+//! the transaction readers therefore start a [`Reassembly`] for every
+//! fragmented transaction and abandon it with
+//! [`TransactionError::ReassemblyTimeout`] once it is older than its limit,
+//! [`DEFAULT_REASSEMBLY_TIMEOUT`] unless configured otherwise. Every
+//! reassembly in progress is registered here until it completes or is
+//! dropped, so [`reassembly_stats`] can report how many there are, how old
+//! the oldest is, and how many were abandoned.
+//!
+//! [`HotlineCodec`]: crate::codec::HotlineCodec
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Mutex,
+        PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+use super::TransactionError;
+
+/// Age after which a partial transaction is abandoned when no limit is set.
+pub const DEFAULT_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+static ABORTED: AtomicU64 = AtomicU64::new(0);
+
+static ACTIVE: Mutex<BTreeMap<u64, Instant>> = Mutex::new(BTreeMap::new());
+
+/// Point-in-time view of reassembly across the process.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReassemblySnapshot {
+    /// Partial transactions waiting for more fragments.
+    pub in_progress: usize,
+    /// Age of the oldest partial transaction, if any.
+    pub oldest: Option<Duration>,
+    /// Reassemblies abandoned for exceeding their age limit.
+    pub aborted: u64,
+}
+
+/// Read the current reassembly statistics.
+#[must_use]
+pub fn reassembly_stats() -> ReassemblySnapshot {
+    let active = ACTIVE.lock().unwrap_or_else(PoisonError::into_inner);
+    ReassemblySnapshot {
+        in_progress: active.len(),
+        oldest: active.values().min().map(Instant::elapsed),
+        aborted: ABORTED.load(Ordering::Relaxed),
+    }
+}
+
+/// One partial transaction, registered until it is dropped.
+#[derive(Debug)]
+pub(crate) struct Reassembly {
+    id: u64,
+    started: Instant,
+    max_age: Duration,
+}
+
+impl Reassembly {
+    /// Start tracking a partial transaction that may live for `max_age`.
+    pub(crate) fn begin(max_age: Duration) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        ACTIVE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(id, started);
+        Self {
+            id,
+            started,
+            max_age,
+        }
+    }
+
+    /// Return how long ago the first fragment arrived.
+    pub(crate) fn age(&self) -> Duration { self.started.elapsed() }
+
+    /// Return how long the reassembly may still wait for fragments.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::ReassemblyTimeout`], counting the abort,
+    /// once the age limit has passed.
+    pub(crate) fn remaining(&self) -> Result<Duration, TransactionError> {
+        match self.max_age.checked_sub(self.age()) {
+            Some(left) if !left.is_zero() => Ok(left),
+            _ => Err(self.expire()),
+        }
+    }
+
+    /// Count this reassembly as abandoned and return the error to report.
+    pub(crate) fn expire(&self) -> TransactionError {
+        ABORTED.fetch_add(1, Ordering::Relaxed);
+        TransactionError::ReassemblyTimeout
+    }
+}
+
+impl Drop for Reassembly {
+    fn drop(&mut self) {
+        ACTIVE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Registering, ageing, and expiring reassemblies.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn reassemblies_age_and_expire() {
+        let reassembly = Reassembly::begin(Duration::from_secs(10));
+        let aborted = reassembly_stats().aborted;
+        assert!(reassembly_stats().in_progress >= 1);
+
+        tokio::time::advance(Duration::from_secs(4)).await;
+        assert_eq!(reassembly.remaining().ok(), Some(Duration::from_secs(6)));
+        assert!(reassembly_stats().oldest >= Some(Duration::from_secs(4)));
+
+        tokio::time::advance(Duration::from_secs(6)).await;
+        assert!(matches!(
+            reassembly.remaining(),
+            Err(TransactionError::ReassemblyTimeout)
+        ));
+        assert!(reassembly_stats().aborted > aborted);
+    }
+}
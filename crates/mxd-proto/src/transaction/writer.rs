@@ -14,10 +14,11 @@ use tokio::{
 
 use super::{
     FrameHeader,
-    IO_TIMEOUT,
     MAX_FRAME_DATA,
+    MAX_NEGOTIATED_FRAME_DATA,
     MAX_PAYLOAD_SIZE,
     Transaction,
+    WRITE_TIMEOUT,
     errors::TransactionError,
     frame::{read_stream_chunk, write_frame},
     params::validate_payload,
@@ -68,13 +69,15 @@ where
     pub fn new(writer: W) -> Self {
         Self {
             writer,
-            timeout: IO_TIMEOUT,
+            timeout: WRITE_TIMEOUT,
             max_frame: MAX_FRAME_DATA,
             max_payload: MAX_PAYLOAD_SIZE,
         }
     }
 
-    /// Override the I/O timeout used for writes.
+    /// Override the timeout for each write.
+    ///
+    /// Defaults to [`WRITE_TIMEOUT`].
     #[must_use]
     pub const fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
@@ -83,14 +86,16 @@ where
 
     /// Override the maximum frame size used for fragmentation.
     ///
-    /// The value is clamped to `1..=MAX_FRAME_DATA` to prevent infinite loops
-    /// (if zero) and to ensure frames are accepted by readers.
+    /// The value is clamped to `1..=MAX_NEGOTIATED_FRAME_DATA` to prevent
+    /// infinite loops (if zero) and oversized frames. Values above
+    /// [`MAX_FRAME_DATA`] are accepted only by readers that negotiated larger
+    /// frames.
     #[must_use]
     pub const fn with_max_frame(mut self, max_frame: usize) -> Self {
         self.max_frame = if max_frame == 0 {
             1
-        } else if max_frame > MAX_FRAME_DATA {
-            MAX_FRAME_DATA
+        } else if max_frame > MAX_NEGOTIATED_FRAME_DATA {
+            MAX_NEGOTIATED_FRAME_DATA
         } else {
             max_frame
         };
@@ -136,6 +141,19 @@ where
         Ok(())
     }
 
+    /// Flush buffered bytes and shut down the write half of the stream.
+    ///
+    /// # Errors
+    /// Returns an error if flushing or shutting down fails or times out.
+    #[must_use = "handle the result"]
+    pub async fn shutdown(&mut self) -> Result<(), TransactionError> {
+        self.flush_timeout().await?;
+        timeout(self.timeout, self.writer.shutdown())
+            .await
+            .map_err(|_| TransactionError::Timeout)??;
+        Ok(())
+    }
+
     /// Write a payload from a reader, emitting fragments incrementally.
     ///
     /// The caller must set `header.total_size` to the total byte count that
@@ -181,7 +199,7 @@ where
                 .await
                 .map_err(map_eof_to_size_mismatch)?;
             write_frame(&mut self.writer, header.clone(), chunk, self.timeout).await?;
-            // Defensive: to_read <= max_frame <= MAX_FRAME_DATA < u32::MAX but
+            // Defensive: to_read <= max_frame <= MAX_NEGOTIATED_FRAME_DATA < u32::MAX but
             // we keep the check to avoid panics.
             sent += u32::try_from(to_read).map_err(|_| TransactionError::PayloadTooLarge)?;
         }
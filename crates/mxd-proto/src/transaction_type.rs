@@ -0,0 +1,391 @@
+//! Enumeration of supported transaction types.
+//!
+//! Each variant corresponds to a Hotline protocol transaction identifier used
+//! for client/server communication.
+/// Transaction type identifier for server message pushes.
+pub const SERVER_MSG_ID: u16 = 104;
+/// Transaction type identifier for public chat requests.
+pub const SEND_CHAT_ID: u16 = 105;
+/// Transaction type identifier for chat message pushes.
+pub const CHAT_MSG_ID: u16 = 106;
+/// Transaction type identifier for private message requests.
+pub const SEND_INSTANT_MSG_ID: u16 = 108;
+/// Transaction type identifier for requests to disconnect another user.
+pub const DISCONNECT_USER_ID: u16 = 110;
+/// Transaction type identifier for server disconnect notices.
+pub const DISCONNECT_MSG_ID: u16 = 111;
+/// Transaction type identifier for file name list requests.
+pub const FILE_NAME_LIST_ID: u16 = 200;
+/// Transaction type identifier for file download requests.
+pub const DOWNLOAD_FILE_ID: u16 = 202;
+/// Transaction type identifier for file upload requests.
+pub const UPLOAD_FILE_ID: u16 = 203;
+/// Transaction type identifier for file deletion requests.
+pub const DELETE_FILE_ID: u16 = 204;
+/// Transaction type identifier for file metadata requests.
+pub const GET_FILE_INFO_ID: u16 = 206;
+/// Transaction type identifier for file metadata updates.
+pub const SET_FILE_INFO_ID: u16 = 207;
+/// Transaction type identifier for file move requests.
+pub const MOVE_FILE_ID: u16 = 208;
+/// Transaction type identifier for queued transfer position pushes.
+pub const DOWNLOAD_INFO_ID: u16 = 211;
+/// Transaction type identifier for banner download requests.
+pub const DOWNLOAD_BANNER_ID: u16 = 212;
+/// Transaction type identifier for user name list requests.
+pub const USER_NAME_LIST_ID: u16 = 300;
+/// Transaction type identifier for notify-change-user transactions.
+pub const NOTIFY_CHANGE_USER_ID: u16 = 301;
+/// Transaction type identifier for notify-delete-user transactions.
+pub const NOTIFY_DELETE_USER_ID: u16 = 302;
+/// Transaction type identifier for get-client-info transactions.
+pub const GET_CLIENT_INFO_TEXT_ID: u16 = 303;
+/// Transaction type identifier for set-client-user-info transactions.
+pub const SET_CLIENT_USER_INFO_ID: u16 = 304;
+/// Transaction type identifier for account list requests.
+pub const GET_ACCOUNTS_ID: u16 = 348;
+/// Transaction type identifier for account creation requests.
+pub const NEW_USER_ID: u16 = 350;
+/// Transaction type identifier for account deletion requests.
+pub const DELETE_USER_ID: u16 = 351;
+/// Transaction type identifier for account detail requests.
+pub const GET_USER_ID: u16 = 352;
+/// Transaction type identifier for account modification requests.
+pub const SET_USER_ID: u16 = 353;
+/// Transaction type identifier for administrator broadcasts.
+pub const USER_BROADCAST_ID: u16 = 355;
+/// Transaction type identifier for connection keep-alive requests.
+pub const KEEP_ALIVE_ID: u16 = 500;
+/// Transaction type identifier for logout requests.
+///
+/// Hotline defines no logout transaction, so this mxd extension uses an
+/// identifier well above the range Hotline assigns.
+pub const LOGOUT_ID: u16 = 3000;
+/// Transaction type identifier for combined file and news searches, an mxd
+/// extension numbered after [`LOGOUT_ID`].
+pub const SEARCH_ID: u16 = 3001;
+/// Transaction type identifier for fetching the server's rules and help, an
+/// mxd extension numbered after [`SEARCH_ID`].
+pub const SERVER_RULES_ID: u16 = 3002;
+
+/// Transaction types supported by the Hotline protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionType {
+    /// Server error response.
+    Error,
+    /// Server push delivering a private or administrative message.
+    ServerMsg,
+    /// Client request to post a line to public chat.
+    SendChat,
+    /// Server push delivering a public chat line.
+    ChatMsg,
+    /// User login request.
+    Login,
+    /// Client request to send a private message to another user.
+    SendInstantMsg,
+    /// Server agreement/banner display.
+    Agreement,
+    /// Privileged request to end another user's connection.
+    DisconnectUser,
+    /// Server notice sent just before it closes the connection.
+    DisconnectMsg,
+    /// Client has accepted the agreement.
+    Agreed,
+    /// Request for the list of available files.
+    GetFileNameList,
+    /// Request to download a file over the transfer port.
+    DownloadFile,
+    /// Request to upload a file over the transfer port.
+    UploadFile,
+    /// Request to delete a file or empty folder.
+    DeleteFile,
+    /// Request for a file or folder's metadata.
+    GetFileInfo,
+    /// Request to rename a file or folder or change its comment.
+    SetFileInfo,
+    /// Request to move a file or folder to another folder.
+    MoveFile,
+    /// Server push telling a client where its queued transfer stands.
+    DownloadInfo,
+    /// Request to download the server's banner image.
+    DownloadBanner,
+    /// Request the list of logged-in users.
+    GetUserNameList,
+    /// Server notification that a user's public data changed.
+    NotifyChangeUser,
+    /// Server notification that a user left the online roster.
+    NotifyDeleteUser,
+    /// Request another user's public info text.
+    GetClientInfoText,
+    /// Update the current session's public user info.
+    SetClientUserInfo,
+    /// Privileged request for every login account (Hotline's List Users).
+    GetAccounts,
+    /// Privileged request to create a login account.
+    NewUser,
+    /// Privileged request to delete a login account.
+    DeleteUser,
+    /// Privileged request for one login account's details.
+    GetUser,
+    /// Privileged request to change a login account's name or password.
+    SetUser,
+    /// User access privileges response.
+    UserAccess,
+    /// Administrator announcement sent to every connected client.
+    UserBroadcast,
+    /// Request for news category names.
+    NewsCategoryNameList,
+    /// Request for news article names within a category.
+    NewsArticleNameList,
+    /// Request for a specific news article's content.
+    NewsArticleData,
+    /// Request to post a new news article.
+    PostNewsArticle,
+    /// Request to delete a news bundle or category and its contents.
+    DeleteNewsItem,
+    /// Request to create a news bundle (folder).
+    NewNewsFolder,
+    /// Request to create a news category.
+    NewNewsCategory,
+    /// Request to delete a news article, optionally with its replies.
+    DeleteNewsArticle,
+    /// Client heartbeat that keeps an otherwise quiet connection open.
+    KeepAlive,
+    /// End the login while keeping the connection open (mxd extension).
+    Logout,
+    /// Search file names and news articles in one request (mxd extension).
+    Search,
+    /// Request the server's rules, contact details, and file policy (mxd
+    /// extension).
+    GetServerRules,
+    /// Any other transaction type not explicitly handled.
+    Other(u16),
+}
+
+impl TransactionType {
+    /// Return true if this transaction type may include a payload.
+    #[must_use]
+    pub const fn allows_payload(self) -> bool {
+        !matches!(
+            self,
+            Self::GetFileNameList
+                | Self::DownloadBanner
+                | Self::GetUserNameList
+                | Self::GetAccounts
+                | Self::Logout
+                | Self::GetServerRules
+        )
+    }
+
+    /// Return `true` when a non-empty payload should be rejected outright.
+    #[must_use]
+    pub const fn rejects_payload(self, payload_is_empty: bool) -> bool {
+        if payload_is_empty {
+            return false;
+        }
+        match self {
+            // Clients may send a folder path (field 202), and SynHX sends a
+            // bare binary `DATA_DIR` block for `/ls`. Accept the payload and
+            // let the parser decide which folder to list.
+            Self::GetFileNameList => false,
+            _ => !self.allows_payload(),
+        }
+    }
+
+    /// Return `true` when request payload bytes should bypass decode attempts.
+    #[must_use]
+    pub const fn bypass_payload_decode(self) -> bool {
+        matches!(self, Self::GetFileNameList) || !self.allows_payload()
+    }
+
+    /// Look up a named type by the name [`Display`](std::fmt::Display)
+    /// writes for it, such as `SendChat`.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMED
+            .iter()
+            .copied()
+            .find(|ty| ty.name() == Some(name))
+    }
+}
+
+/// Generate [`TransactionType::NAMED`] and [`TransactionType::name`] from the
+/// named variants, so display and lookup share one list of names.
+macro_rules! transaction_names {
+    ($($variant:ident,)+) => {
+        impl TransactionType {
+            /// Every transaction type with a name of its own.
+            pub const NAMED: &'static [Self] = &[$(Self::$variant),+];
+
+            /// Return the variant's name, or `None` for [`Self::Other`].
+            #[must_use]
+            pub const fn name(self) -> Option<&'static str> {
+                match self {
+                    $(Self::$variant => Some(stringify!($variant)),)+
+                    Self::Other(_) => None,
+                }
+            }
+        }
+    };
+}
+
+transaction_names! {
+    Error,
+    ServerMsg,
+    SendChat,
+    ChatMsg,
+    Login,
+    SendInstantMsg,
+    Agreement,
+    DisconnectUser,
+    DisconnectMsg,
+    Agreed,
+    GetFileNameList,
+    DownloadFile,
+    UploadFile,
+    DeleteFile,
+    GetFileInfo,
+    SetFileInfo,
+    MoveFile,
+    DownloadInfo,
+    DownloadBanner,
+    GetUserNameList,
+    NotifyChangeUser,
+    NotifyDeleteUser,
+    GetClientInfoText,
+    SetClientUserInfo,
+    GetAccounts,
+    NewUser,
+    DeleteUser,
+    GetUser,
+    SetUser,
+    UserAccess,
+    UserBroadcast,
+    NewsCategoryNameList,
+    NewsArticleNameList,
+    NewsArticleData,
+    PostNewsArticle,
+    DeleteNewsItem,
+    NewNewsFolder,
+    NewNewsCategory,
+    DeleteNewsArticle,
+    KeepAlive,
+    Logout,
+    Search,
+    GetServerRules,
+}
+
+impl From<u16> for TransactionType {
+    fn from(v: u16) -> Self {
+        match v {
+            100 => Self::Error,
+            SERVER_MSG_ID => Self::ServerMsg,
+            SEND_CHAT_ID => Self::SendChat,
+            CHAT_MSG_ID => Self::ChatMsg,
+            107 => Self::Login,
+            SEND_INSTANT_MSG_ID => Self::SendInstantMsg,
+            109 => Self::Agreement,
+            DISCONNECT_USER_ID => Self::DisconnectUser,
+            DISCONNECT_MSG_ID => Self::DisconnectMsg,
+            121 => Self::Agreed,
+            FILE_NAME_LIST_ID => Self::GetFileNameList,
+            DOWNLOAD_FILE_ID => Self::DownloadFile,
+            UPLOAD_FILE_ID => Self::UploadFile,
+            DELETE_FILE_ID => Self::DeleteFile,
+            GET_FILE_INFO_ID => Self::GetFileInfo,
+            SET_FILE_INFO_ID => Self::SetFileInfo,
+            MOVE_FILE_ID => Self::MoveFile,
+            DOWNLOAD_INFO_ID => Self::DownloadInfo,
+            DOWNLOAD_BANNER_ID => Self::DownloadBanner,
+            USER_NAME_LIST_ID => Self::GetUserNameList,
+            NOTIFY_CHANGE_USER_ID => Self::NotifyChangeUser,
+            NOTIFY_DELETE_USER_ID => Self::NotifyDeleteUser,
+            GET_CLIENT_INFO_TEXT_ID => Self::GetClientInfoText,
+            SET_CLIENT_USER_INFO_ID => Self::SetClientUserInfo,
+            GET_ACCOUNTS_ID => Self::GetAccounts,
+            NEW_USER_ID => Self::NewUser,
+            DELETE_USER_ID => Self::DeleteUser,
+            GET_USER_ID => Self::GetUser,
+            SET_USER_ID => Self::SetUser,
+            354 => Self::UserAccess,
+            USER_BROADCAST_ID => Self::UserBroadcast,
+            370 => Self::NewsCategoryNameList,
+            371 => Self::NewsArticleNameList,
+            400 => Self::NewsArticleData,
+            410 => Self::PostNewsArticle,
+            380 => Self::DeleteNewsItem,
+            381 => Self::NewNewsFolder,
+            382 => Self::NewNewsCategory,
+            411 => Self::DeleteNewsArticle,
+            KEEP_ALIVE_ID => Self::KeepAlive,
+            LOGOUT_ID => Self::Logout,
+            SEARCH_ID => Self::Search,
+            SERVER_RULES_ID => Self::GetServerRules,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl From<TransactionType> for u16 {
+    fn from(t: TransactionType) -> Self {
+        match t {
+            TransactionType::Error => 100,
+            TransactionType::ServerMsg => SERVER_MSG_ID,
+            TransactionType::SendChat => SEND_CHAT_ID,
+            TransactionType::ChatMsg => CHAT_MSG_ID,
+            TransactionType::Login => 107,
+            TransactionType::SendInstantMsg => SEND_INSTANT_MSG_ID,
+            TransactionType::Agreement => 109,
+            TransactionType::DisconnectUser => DISCONNECT_USER_ID,
+            TransactionType::DisconnectMsg => DISCONNECT_MSG_ID,
+            TransactionType::Agreed => 121,
+            TransactionType::GetFileNameList => FILE_NAME_LIST_ID,
+            TransactionType::DownloadFile => DOWNLOAD_FILE_ID,
+            TransactionType::UploadFile => UPLOAD_FILE_ID,
+            TransactionType::DeleteFile => DELETE_FILE_ID,
+            TransactionType::GetFileInfo => GET_FILE_INFO_ID,
+            TransactionType::SetFileInfo => SET_FILE_INFO_ID,
+            TransactionType::MoveFile => MOVE_FILE_ID,
+            TransactionType::DownloadInfo => DOWNLOAD_INFO_ID,
+            TransactionType::DownloadBanner => DOWNLOAD_BANNER_ID,
+            TransactionType::GetUserNameList => USER_NAME_LIST_ID,
+            TransactionType::NotifyChangeUser => NOTIFY_CHANGE_USER_ID,
+            TransactionType::NotifyDeleteUser => NOTIFY_DELETE_USER_ID,
+            TransactionType::GetClientInfoText => GET_CLIENT_INFO_TEXT_ID,
+            TransactionType::SetClientUserInfo => SET_CLIENT_USER_INFO_ID,
+            TransactionType::GetAccounts => GET_ACCOUNTS_ID,
+            TransactionType::NewUser => NEW_USER_ID,
+            TransactionType::DeleteUser => DELETE_USER_ID,
+            TransactionType::GetUser => GET_USER_ID,
+            TransactionType::SetUser => SET_USER_ID,
+            TransactionType::UserAccess => 354,
+            TransactionType::UserBroadcast => USER_BROADCAST_ID,
+            TransactionType::NewsCategoryNameList => 370,
+            TransactionType::NewsArticleNameList => 371,
+            TransactionType::NewsArticleData => 400,
+            TransactionType::PostNewsArticle => 410,
+            TransactionType::DeleteNewsItem => 380,
+            TransactionType::NewNewsFolder => 381,
+            TransactionType::NewNewsCategory => 382,
+            TransactionType::DeleteNewsArticle => 411,
+            TransactionType::KeepAlive => KEEP_ALIVE_ID,
+            TransactionType::Logout => LOGOUT_ID,
+            TransactionType::Search => SEARCH_ID,
+            TransactionType::GetServerRules => SERVER_RULES_ID,
+            TransactionType::Other(v) => v,
+        }
+    }
+}
+
+impl std::fmt::Display for TransactionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Self::Other(v) = self {
+            return write!(f, "Other({v})");
+        }
+        f.write_str(self.name().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+#[path = "transaction_type_tests.rs"]
+mod tests;
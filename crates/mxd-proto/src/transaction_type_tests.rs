@@ -0,0 +1,159 @@
+//! Tests for `TransactionType` payload-policy helpers across explicit and
+//! table-driven cases.
+
+use rstest::rstest;
+
+use super::TransactionType;
+
+const ALL_TRANSACTION_TYPES: [TransactionType; 44] = [
+    TransactionType::Error,
+    TransactionType::ServerMsg,
+    TransactionType::SendChat,
+    TransactionType::ChatMsg,
+    TransactionType::Login,
+    TransactionType::SendInstantMsg,
+    TransactionType::Agreement,
+    TransactionType::DisconnectUser,
+    TransactionType::DisconnectMsg,
+    TransactionType::Agreed,
+    TransactionType::GetFileNameList,
+    TransactionType::DownloadFile,
+    TransactionType::UploadFile,
+    TransactionType::DeleteFile,
+    TransactionType::GetFileInfo,
+    TransactionType::SetFileInfo,
+    TransactionType::MoveFile,
+    TransactionType::DownloadInfo,
+    TransactionType::DownloadBanner,
+    TransactionType::GetUserNameList,
+    TransactionType::NotifyChangeUser,
+    TransactionType::NotifyDeleteUser,
+    TransactionType::GetClientInfoText,
+    TransactionType::SetClientUserInfo,
+    TransactionType::GetAccounts,
+    TransactionType::NewUser,
+    TransactionType::DeleteUser,
+    TransactionType::GetUser,
+    TransactionType::SetUser,
+    TransactionType::UserAccess,
+    TransactionType::UserBroadcast,
+    TransactionType::NewsCategoryNameList,
+    TransactionType::NewsArticleNameList,
+    TransactionType::NewsArticleData,
+    TransactionType::PostNewsArticle,
+    TransactionType::DeleteNewsItem,
+    TransactionType::NewNewsFolder,
+    TransactionType::NewNewsCategory,
+    TransactionType::DeleteNewsArticle,
+    TransactionType::KeepAlive,
+    TransactionType::Logout,
+    TransactionType::Search,
+    TransactionType::GetServerRules,
+    TransactionType::Other(999),
+];
+
+#[rstest]
+#[case(TransactionType::GetFileNameList, false, false)]
+#[case(TransactionType::NewsArticleData, false, false)]
+#[case(TransactionType::DownloadBanner, false, true)]
+#[case(TransactionType::GetUserNameList, false, true)]
+#[case(TransactionType::GetAccounts, false, true)]
+#[case(TransactionType::GetUser, false, false)]
+#[case(TransactionType::Logout, false, true)]
+#[case(TransactionType::GetServerRules, false, true)]
+#[case(TransactionType::NotifyChangeUser, false, false)]
+#[case(TransactionType::NotifyDeleteUser, false, false)]
+#[case(TransactionType::GetClientInfoText, false, false)]
+#[case(TransactionType::SetClientUserInfo, false, false)]
+fn rejects_payload_matches_expected_policy(
+    #[case] transaction_type: TransactionType,
+    #[case] expected_for_empty_payload: bool,
+    #[case] expected_for_non_empty_payload: bool,
+) {
+    assert_eq!(
+        transaction_type.rejects_payload(true),
+        expected_for_empty_payload
+    );
+    assert_eq!(
+        transaction_type.rejects_payload(false),
+        expected_for_non_empty_payload
+    );
+}
+
+#[rstest]
+#[case(TransactionType::Error, false)]
+#[case(TransactionType::ServerMsg, false)]
+#[case(TransactionType::SendChat, false)]
+#[case(TransactionType::ChatMsg, false)]
+#[case(TransactionType::Login, false)]
+#[case(TransactionType::SendInstantMsg, false)]
+#[case(TransactionType::Agreement, false)]
+#[case(TransactionType::DisconnectUser, false)]
+#[case(TransactionType::DisconnectMsg, false)]
+#[case(TransactionType::Agreed, false)]
+#[case(TransactionType::GetFileNameList, true)]
+#[case(TransactionType::DownloadFile, false)]
+#[case(TransactionType::UploadFile, false)]
+#[case(TransactionType::DeleteFile, false)]
+#[case(TransactionType::GetFileInfo, false)]
+#[case(TransactionType::SetFileInfo, false)]
+#[case(TransactionType::MoveFile, false)]
+#[case(TransactionType::DownloadInfo, false)]
+#[case(TransactionType::DownloadBanner, true)]
+#[case(TransactionType::GetUserNameList, true)]
+#[case(TransactionType::NotifyChangeUser, false)]
+#[case(TransactionType::NotifyDeleteUser, false)]
+#[case(TransactionType::GetClientInfoText, false)]
+#[case(TransactionType::SetClientUserInfo, false)]
+#[case(TransactionType::GetAccounts, true)]
+#[case(TransactionType::NewUser, false)]
+#[case(TransactionType::DeleteUser, false)]
+#[case(TransactionType::GetUser, false)]
+#[case(TransactionType::SetUser, false)]
+#[case(TransactionType::UserAccess, false)]
+#[case(TransactionType::UserBroadcast, false)]
+#[case(TransactionType::NewsCategoryNameList, false)]
+#[case(TransactionType::NewsArticleNameList, false)]
+#[case(TransactionType::NewsArticleData, false)]
+#[case(TransactionType::PostNewsArticle, false)]
+#[case(TransactionType::DeleteNewsItem, false)]
+#[case(TransactionType::NewNewsFolder, false)]
+#[case(TransactionType::NewNewsCategory, false)]
+#[case(TransactionType::DeleteNewsArticle, false)]
+#[case(TransactionType::KeepAlive, false)]
+#[case(TransactionType::Logout, true)]
+#[case(TransactionType::Search, false)]
+#[case(TransactionType::GetServerRules, true)]
+#[case(TransactionType::Other(999), false)]
+fn bypass_payload_decode_matches_transaction_policy(
+    #[case] transaction_type: TransactionType,
+    #[case] expected: bool,
+) {
+    assert!(
+        ALL_TRANSACTION_TYPES.contains(&transaction_type),
+        "missing coverage entry for {transaction_type:?}"
+    );
+    assert_eq!(
+        transaction_type.bypass_payload_decode(),
+        expected,
+        "unexpected bypass policy for {transaction_type:?}"
+    );
+}
+
+#[rstest]
+fn names_round_trip_through_display() {
+    assert_eq!(
+        TransactionType::NAMED.len(),
+        ALL_TRANSACTION_TYPES.len() - 1
+    );
+    for transaction_type in ALL_TRANSACTION_TYPES {
+        let expected = match transaction_type {
+            TransactionType::Other(_) => None,
+            named => Some(named),
+        };
+        assert_eq!(
+            TransactionType::from_name(&transaction_type.to_string()),
+            expected
+        );
+    }
+}
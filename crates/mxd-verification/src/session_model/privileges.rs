@@ -1,8 +1,8 @@
 //! Privilege bit constants for the session gating model.
 //!
-//! These constants mirror the values in `src/privileges.rs` and must be kept
-//! in sync manually. Any drift indicates a synchronization failure that must
-//! be corrected before verification results can be trusted.
+//! These constants mirror the values in `mxd_proto::privileges`. The
+//! `access_catalogue` integration test compares them, so drift fails the
+//! build's tests rather than silently weakening verification results.
 //!
 //! The constants are defined as raw `u64` values rather than using bitflags
 //! to keep this crate dependency-light.
@@ -42,7 +42,7 @@ pub const GET_CLIENT_INFO: u64 = 1 << 24;
 
 /// Default privileges for a standard authenticated user.
 ///
-/// This composite matches `Privileges::default_user()` in `src/privileges.rs`.
+/// This composite matches `Privileges::default_user()` in `mxd_proto`.
 pub const DEFAULT_USER_PRIVILEGES: u64 = DOWNLOAD_FILE
     | READ_CHAT
     | SEND_CHAT
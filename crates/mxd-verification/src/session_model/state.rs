@@ -79,6 +79,23 @@ impl RequestType {
         }
     }
 
+    /// Returns the Hotline transaction identifier this request class stands
+    /// for.
+    ///
+    /// The `access_catalogue` test uses it to check the model against the
+    /// server's access table.
+    #[must_use]
+    pub const fn transaction_id(self) -> u16 {
+        match self {
+            Self::Ping => 500,
+            Self::GetUserInfo => 300,
+            Self::GetFileList => 200,
+            Self::GetNewsCategories => 370,
+            Self::PostNewsArticle => 410,
+            Self::GetClientInfo => 303,
+        }
+    }
+
     /// Returns `true` if this request requires authentication.
     ///
     /// Ping does not require authentication; all other requests do.
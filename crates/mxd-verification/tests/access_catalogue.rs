@@ -0,0 +1,68 @@
+//! Checks the session model's request catalogue against the access table the
+//! server's dispatcher enforces.
+//!
+//! The model abstracts transactions into [`RequestType`] classes with their
+//! own privilege requirements. If those drift from `mxd_proto::access`, the
+//! model would verify rules the server does not apply, so each class is
+//! compared with the table entry for the transaction it stands for.
+
+use mxd_proto::{
+    access::required_access,
+    privileges::Privileges,
+    transaction_type::TransactionType,
+};
+use mxd_verification::session_model::{
+    privileges::{
+        ADMIN_PRIVILEGES,
+        CHANGE_OWN_PASSWORD,
+        CREATE_USER,
+        DEFAULT_USER_PRIVILEGES,
+        DISCONNECT_USER,
+        DOWNLOAD_FILE,
+        GET_CLIENT_INFO,
+        NEWS_POST_ARTICLE,
+        NEWS_READ_ARTICLE,
+        READ_CHAT,
+        SEND_CHAT,
+        SEND_PRIVATE_MESSAGE,
+        SHOW_IN_LIST,
+    },
+    state::RequestType,
+};
+use rstest::rstest;
+
+#[rstest]
+fn catalogue_matches_access_table() {
+    for &request in RequestType::all() {
+        let ty = TransactionType::from(request.transaction_id());
+        let access = required_access(ty);
+        assert_eq!(
+            access.requires_authentication(),
+            request.requires_authentication(),
+            "{request:?} ({ty}) authentication differs from the access table"
+        );
+        assert_eq!(
+            access.privileges().bits(),
+            request.required_privilege(),
+            "{request:?} ({ty}) privilege differs from the access table"
+        );
+    }
+}
+
+#[rstest]
+#[case(DOWNLOAD_FILE, Privileges::DOWNLOAD_FILE)]
+#[case(READ_CHAT, Privileges::READ_CHAT)]
+#[case(SEND_CHAT, Privileges::SEND_CHAT)]
+#[case(SHOW_IN_LIST, Privileges::SHOW_IN_LIST)]
+#[case(CREATE_USER, Privileges::CREATE_USER)]
+#[case(CHANGE_OWN_PASSWORD, Privileges::CHANGE_OWN_PASSWORD)]
+#[case(SEND_PRIVATE_MESSAGE, Privileges::SEND_PRIVATE_MESSAGE)]
+#[case(NEWS_READ_ARTICLE, Privileges::NEWS_READ_ARTICLE)]
+#[case(NEWS_POST_ARTICLE, Privileges::NEWS_POST_ARTICLE)]
+#[case(DISCONNECT_USER, Privileges::DISCONNECT_USER)]
+#[case(GET_CLIENT_INFO, Privileges::GET_CLIENT_INFO)]
+#[case(DEFAULT_USER_PRIVILEGES, Privileges::default_user())]
+#[case(ADMIN_PRIVILEGES, Privileges::admin())]
+fn model_privileges_match_protocol_bits(#[case] model: u64, #[case] protocol: Privileges) {
+    assert_eq!(model, protocol.bits());
+}
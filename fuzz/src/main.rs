@@ -7,7 +7,7 @@ unsafe extern "C" {
 }
 use std::io::{self, Read};
 
-use mxd::transaction::{HEADER_LEN, MAX_PAYLOAD_SIZE, parse_transaction};
+use mxd_proto::transaction::{HEADER_LEN, MAX_PAYLOAD_SIZE, parse_transaction};
 
 fn main() {
     // Allocate a buffer up to the maximum frame size so we don't grow
@@ -0,0 +1,10 @@
+ALTER TABLE file_nodes
+    DROP COLUMN IF EXISTS creator_code,
+    DROP COLUMN IF EXISTS type_code;
+
+ALTER TABLE files
+    DROP COLUMN IF EXISTS updated_at,
+    DROP COLUMN IF EXISTS created_at,
+    DROP COLUMN IF EXISTS comment,
+    DROP COLUMN IF EXISTS creator_code,
+    DROP COLUMN IF EXISTS type_code;
@@ -0,0 +1,15 @@
+ALTER TABLE files
+    ADD COLUMN type_code TEXT NOT NULL DEFAULT '',
+    ADD COLUMN creator_code TEXT NOT NULL DEFAULT '',
+    ADD COLUMN comment TEXT,
+    ADD COLUMN created_at TIMESTAMP,
+    ADD COLUMN updated_at TIMESTAMP;
+
+UPDATE files
+SET
+    created_at = COALESCE(created_at, CURRENT_TIMESTAMP),
+    updated_at = COALESCE(updated_at, CURRENT_TIMESTAMP);
+
+ALTER TABLE file_nodes
+    ADD COLUMN type_code TEXT NOT NULL DEFAULT '',
+    ADD COLUMN creator_code TEXT NOT NULL DEFAULT '';
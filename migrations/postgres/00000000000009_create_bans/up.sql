@@ -0,0 +1,9 @@
+CREATE TABLE bans (
+    id INTEGER PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    kind TEXT NOT NULL CHECK (kind IN ('username', 'address')),
+    target TEXT NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP,
+    UNIQUE (kind, target)
+);
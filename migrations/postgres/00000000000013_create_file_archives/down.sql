@@ -0,0 +1,23 @@
+-- Remove the snapshot copies so object keys are unique again.
+WITH RECURSIVE archived(id) AS (
+    SELECT container_id FROM file_archives
+    UNION ALL
+    SELECT n.id FROM file_nodes n JOIN archived a ON n.parent_id = a.id
+)
+DELETE FROM resource_permissions
+WHERE resource_type = 'file_node' AND resource_id IN (SELECT id FROM archived);
+
+WITH RECURSIVE archived(id) AS (
+    SELECT container_id FROM file_archives
+    UNION ALL
+    SELECT n.id FROM file_nodes n JOIN archived a ON n.parent_id = a.id
+)
+DELETE FROM file_nodes WHERE id IN (SELECT id FROM archived);
+
+DROP TABLE file_archives;
+
+DROP INDEX idx_file_nodes_object_key;
+
+CREATE UNIQUE INDEX idx_file_nodes_object_key
+    ON file_nodes(object_key)
+    WHERE object_key IS NOT NULL;
@@ -0,0 +1,2 @@
+-- NULL leaves an account's stored file content unlimited.
+ALTER TABLE users ADD COLUMN quota_bytes BIGINT;
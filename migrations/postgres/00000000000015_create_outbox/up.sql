@@ -0,0 +1,12 @@
+-- Notifications written in the same transaction as the change they report,
+-- and delivered to clients afterwards.
+CREATE TABLE outbox_events (
+    id INTEGER PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    event TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMP
+);
+
+CREATE INDEX idx_outbox_events_pending
+    ON outbox_events(id)
+    WHERE delivered_at IS NULL;
@@ -0,0 +1,6 @@
+-- The newest outbox event each account has been sent, so an event retried
+-- after a crash is not shown to the same account twice.
+CREATE TABLE outbox_cursors (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    last_event_id INTEGER NOT NULL
+);
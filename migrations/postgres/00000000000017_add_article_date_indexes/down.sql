@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_articles_posted;
+DROP INDEX IF EXISTS idx_articles_category_posted;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS news_digest_categories;
+DROP TABLE IF EXISTS news_digests;
@@ -0,0 +1,16 @@
+-- Accounts that opted in to news digests: where to email them, if anywhere,
+-- when the account last caught up on news, and the digest waiting for its
+-- next login.
+CREATE TABLE news_digests (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    email TEXT,
+    last_visit_at TIMESTAMP NOT NULL,
+    pending TEXT
+);
+
+-- The news categories each digest covers.
+CREATE TABLE news_digest_categories (
+    user_id INTEGER NOT NULL REFERENCES news_digests(user_id) ON DELETE CASCADE,
+    category_id INTEGER NOT NULL REFERENCES news_categories(id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, category_id)
+);
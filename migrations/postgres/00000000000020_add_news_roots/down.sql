@@ -0,0 +1,14 @@
+DROP INDEX idx_categories_root_name_unique;
+CREATE UNIQUE INDEX idx_categories_root_name_unique
+    ON news_categories(name)
+    WHERE bundle_id IS NULL;
+
+DROP INDEX idx_bundles_root_name_unique;
+CREATE UNIQUE INDEX idx_bundles_root_name_unique
+    ON news_bundles(name)
+    WHERE parent_bundle_id IS NULL;
+
+ALTER TABLE news_categories DROP COLUMN root_id;
+ALTER TABLE news_bundles DROP COLUMN root_id;
+
+DROP TABLE IF EXISTS news_roots;
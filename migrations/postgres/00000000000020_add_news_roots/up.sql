@@ -0,0 +1,23 @@
+-- Separate news trees, one per community board. Root 0 is the primary root
+-- every server has; each bundle and category records the root it belongs to.
+CREATE TABLE news_roots (
+    id INTEGER PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY (MINVALUE 0),
+    name TEXT NOT NULL UNIQUE
+);
+
+INSERT INTO news_roots (id, name) VALUES (0, 'main');
+SELECT setval(pg_get_serial_sequence('news_roots', 'id'), 1, false);
+
+ALTER TABLE news_bundles ADD COLUMN root_id INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE news_categories ADD COLUMN root_id INTEGER NOT NULL DEFAULT 0;
+
+-- Top-level names need only be unique within their root.
+DROP INDEX idx_bundles_root_name_unique;
+CREATE UNIQUE INDEX idx_bundles_root_name_unique
+    ON news_bundles(root_id, name)
+    WHERE parent_bundle_id IS NULL;
+
+DROP INDEX idx_categories_root_name_unique;
+CREATE UNIQUE INDEX idx_categories_root_name_unique
+    ON news_categories(root_id, name)
+    WHERE bundle_id IS NULL;
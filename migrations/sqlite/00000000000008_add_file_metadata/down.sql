@@ -0,0 +1,8 @@
+ALTER TABLE file_nodes DROP COLUMN creator_code;
+ALTER TABLE file_nodes DROP COLUMN type_code;
+
+ALTER TABLE files DROP COLUMN updated_at;
+ALTER TABLE files DROP COLUMN created_at;
+ALTER TABLE files DROP COLUMN comment;
+ALTER TABLE files DROP COLUMN creator_code;
+ALTER TABLE files DROP COLUMN type_code;
@@ -0,0 +1,15 @@
+ALTER TABLE files ADD COLUMN type_code TEXT NOT NULL DEFAULT '';
+ALTER TABLE files ADD COLUMN creator_code TEXT NOT NULL DEFAULT '';
+ALTER TABLE files ADD COLUMN comment TEXT;
+-- SQLite cannot add a column whose default is CURRENT_TIMESTAMP, so the
+-- timestamps stay nullable and existing rows are backfilled here.
+ALTER TABLE files ADD COLUMN created_at DATETIME;
+ALTER TABLE files ADD COLUMN updated_at DATETIME;
+
+UPDATE files
+SET
+    created_at = COALESCE(created_at, CURRENT_TIMESTAMP),
+    updated_at = COALESCE(updated_at, CURRENT_TIMESTAMP);
+
+ALTER TABLE file_nodes ADD COLUMN type_code TEXT NOT NULL DEFAULT '';
+ALTER TABLE file_nodes ADD COLUMN creator_code TEXT NOT NULL DEFAULT '';
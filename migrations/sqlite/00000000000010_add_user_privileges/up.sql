@@ -0,0 +1,3 @@
+-- Existing accounts keep the privileges every login received before the
+-- column existed: Privileges::default_user() in mxd-proto.
+ALTER TABLE users ADD COLUMN privileges BIGINT NOT NULL DEFAULT 20719108;
@@ -0,0 +1,4 @@
+CREATE TABLE download_credits (
+    user_id INTEGER PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    credits BIGINT NOT NULL DEFAULT 0
+);
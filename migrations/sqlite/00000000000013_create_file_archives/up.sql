@@ -0,0 +1,14 @@
+CREATE TABLE file_archives (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source_id INTEGER UNIQUE REFERENCES file_nodes(id) ON DELETE SET NULL,
+    container_id INTEGER NOT NULL UNIQUE REFERENCES file_nodes(id) ON DELETE CASCADE,
+    last_snapshot_at DATETIME
+);
+
+-- Snapshot copies share their originals' stored content, so one object key
+-- may now back several file nodes.
+DROP INDEX idx_file_nodes_object_key;
+
+CREATE INDEX idx_file_nodes_object_key
+    ON file_nodes(object_key)
+    WHERE object_key IS NOT NULL;
@@ -0,0 +1,2 @@
+CREATE INDEX idx_articles_category_posted ON news_articles(category_id, posted_at);
+CREATE INDEX idx_articles_posted ON news_articles(posted_at, id);
@@ -0,0 +1,2 @@
+DROP TABLE news_digest_categories;
+DROP TABLE news_digests;
@@ -0,0 +1,9 @@
+-- Single-use tokens a reconnecting client presents instead of its password,
+-- each valid until expires_at.
+CREATE TABLE session_tokens (
+    token TEXT PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_session_tokens_expires_at ON session_tokens(expires_at);
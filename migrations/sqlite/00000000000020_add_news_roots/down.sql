@@ -0,0 +1,13 @@
+DROP INDEX idx_news_categories_unique;
+CREATE UNIQUE INDEX idx_news_categories_unique
+    ON news_categories(name, IFNULL(bundle_id, -1));
+
+DROP INDEX idx_bundles_root_name_unique;
+CREATE UNIQUE INDEX idx_bundles_root_name_unique
+    ON news_bundles(name)
+    WHERE parent_bundle_id IS NULL;
+
+ALTER TABLE news_categories DROP COLUMN root_id;
+ALTER TABLE news_bundles DROP COLUMN root_id;
+
+DROP TABLE IF EXISTS news_roots;
@@ -1,18 +1,41 @@
 //! Binary entry point for the Wireframe-based server.
 //!
 //! The runtime logic lives in `mxd::server::wireframe`, so this binary only
-//! delegates to the shared library code.
+//! loads configuration, installs the log subscriber it names, builds a Tokio
+//! runtime sized from it, and delegates to the shared library code.
 
 use std::process::ExitCode;
 
-use tokio::runtime::Builder;
+use mxd::server::{
+    load_cli,
+    logging::init_logging,
+    runtime::build_runtime,
+    wireframe::run_with_cli,
+};
+
+// Heap profiles come from jemalloc's sampling profiler, so profiling builds
+// allocate through it.
+#[cfg(feature = "profiling")]
+#[global_allocator]
+static ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 #[expect(
     clippy::print_stderr,
     reason = "error output is appropriate for main binary"
 )]
 fn main() -> ExitCode {
-    let runtime = match Builder::new_multi_thread().enable_all().build() {
+    let cli = match load_cli() {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("mxd-wireframe-server failed to load configuration: {err:#}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Err(err) = init_logging(&cli.config) {
+        eprintln!("mxd-wireframe-server failed to start logging: {err:#}");
+        return ExitCode::FAILURE;
+    }
+    let runtime = match build_runtime(&cli.config) {
         Ok(runtime) => runtime,
         Err(err) => {
             eprintln!("mxd-wireframe-server failed to build runtime: {err:#}");
@@ -21,7 +44,7 @@ fn main() -> ExitCode {
     };
 
     runtime.block_on(async {
-        match mxd::server::wireframe::run().await {
+        match run_with_cli(cli).await {
             Ok(()) => ExitCode::SUCCESS,
             Err(err) => {
                 eprintln!("mxd-wireframe-server failed: {err:#}");
@@ -0,0 +1,95 @@
+//! Account inspection: Get User (352) and the account list (348).
+//!
+//! Both replies describe an account with the same four fields: the name
+//! (102), the login (105) with every byte inverted as Hotline clients
+//! expect, a placeholder password (106), and the access bitmap (110) in the
+//! [`wire`] layout. Accounts do not yet store a full name, so the login
+//! doubles as the name. The placeholder is the value Set User sends back to
+//! keep the current password, so the stored hash never leaves the server.
+
+use super::{Command, CommandError, ERR_ACCOUNT_NOT_FOUND};
+use crate::{
+    db::{DbPool, acquire, get_user_by_name, list_users},
+    field_id::FieldId,
+    header_util::reply_header,
+    login::account_privileges,
+    models::User,
+    privileges::wire,
+    transaction::{FrameHeader, Transaction, TransactionError, TransactionParams, encode_params},
+};
+
+/// Password field value standing in for the stored password.
+const PASSWORD_PLACEHOLDER: &[u8] = &[0];
+
+/// Parameters of Get User (352).
+#[derive(TransactionParams)]
+struct GetUserParams {
+    #[param(Login)]
+    login: String,
+}
+
+/// Parse a Get User payload, which names the account in field 105.
+pub(super) fn parse_get_user_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let GetUserParams { login } = GetUserParams::from_payload(payload)?;
+    if login.is_empty() {
+        return Err(TransactionError::InvalidParamValue(FieldId::Login));
+    }
+    Ok(Command::GetUser { header, login })
+}
+
+impl Command {
+    pub(super) async fn process_get_user(
+        pool: &DbPool,
+        header: &FrameHeader,
+        login: &str,
+    ) -> Result<Transaction, CommandError> {
+        let mut conn = acquire(pool, header.ty).await?;
+        let Some(account) = get_user_by_name(&mut conn, login).await? else {
+            return Ok(Transaction {
+                header: reply_header(header, ERR_ACCOUNT_NOT_FOUND, 0),
+                payload: Vec::new(),
+            });
+        };
+        let payload = encode_params(&account_fields(&account))?;
+        Ok(Transaction {
+            header: reply_header(header, 0, payload.len()),
+            payload,
+        })
+    }
+
+    pub(super) async fn process_get_accounts(
+        pool: &DbPool,
+        header: &FrameHeader,
+    ) -> Result<Transaction, CommandError> {
+        let mut conn = acquire(pool, header.ty).await?;
+        let accounts = list_users(&mut conn).await?;
+        let entries = accounts
+            .iter()
+            .map(|account| {
+                encode_params(&account_fields(account)).map(|data| (FieldId::Data, data))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let payload = encode_params(&entries)?;
+        Ok(Transaction {
+            header: reply_header(header, 0, payload.len()),
+            payload,
+        })
+    }
+}
+
+/// Fields describing `account` in Get User and account list replies.
+fn account_fields(account: &User) -> [(FieldId, Vec<u8>); 4] {
+    let login = account.username.as_bytes();
+    [
+        (FieldId::Name, login.to_vec()),
+        (FieldId::Login, login.iter().map(|byte| !byte).collect()),
+        (FieldId::Password, PASSWORD_PLACEHOLDER.to_vec()),
+        (
+            FieldId::UserAccess,
+            wire::encode(account_privileges(account)).to_vec(),
+        ),
+    ]
+}
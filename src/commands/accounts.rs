@@ -0,0 +1,378 @@
+//! Account administration: New User (350), Delete User (351), and Set User
+//! (353).
+//!
+//! Accounts are keyed by login name. New passwords are hashed on the server's
+//! [`HashingPool`], so they use the same Argon2 parameters as the
+//! `create-user` subcommand. The access bitmap (110) is stored with the
+//! account, but only when the administrator holds every privilege it grants,
+//! so nobody can hand out, or take for themselves, more than they have. The
+//! full name (102) that clients send alongside is accepted but not stored,
+//! because accounts do not yet persist names.
+
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel_async::{AsyncConnection, pooled_connection::bb8::RunError};
+use tracing::{info, warn};
+
+use super::{
+    Command,
+    CommandError,
+    ERR_ACCOUNT_EXISTS,
+    ERR_ACCOUNT_IN_USE,
+    ERR_ACCOUNT_NOT_FOUND,
+    ERR_INSUFFICIENT_PRIVILEGES,
+    ERR_SERVER_BUSY,
+    handlers::empty_success_reply,
+    parsing::first_param_bytes,
+};
+use crate::{
+    db::{
+        DbConnection,
+        DbPool,
+        UserUpdate,
+        acquire,
+        create_user,
+        delete_user,
+        get_user_by_name,
+        revoke_session_tokens,
+        set_privileges,
+        update_user,
+    },
+    field_id::FieldId,
+    handler::Session,
+    hashing::{HashingError, HashingPool},
+    header_util::reply_header,
+    models::NewUser,
+    privileges::{Privileges, wire},
+    transaction::{
+        FrameHeader,
+        Transaction,
+        TransactionError,
+        decode_params_map_borrowed,
+        first_param_string,
+        required_param_string,
+    },
+    transaction_type::TransactionType,
+};
+
+/// Password field value Set User sends when the password is unchanged.
+const UNCHANGED_PASSWORD: &[u8] = &[0];
+
+/// An account administration request.
+#[derive(Debug, PartialEq, Eq)]
+pub enum AccountRequest {
+    /// Create an account (350).
+    Create {
+        /// Login name of the new account.
+        login: String,
+        /// Plain-text password to hash and store.
+        password: String,
+        /// Privileges to store, or `None` for the default user set.
+        privileges: Option<Privileges>,
+    },
+    /// Delete an account (351).
+    Delete {
+        /// Login name of the account to remove.
+        login: String,
+    },
+    /// Rename an account or change its password (353).
+    Update {
+        /// Current login name of the account.
+        login: String,
+        /// Replacement login name, when renaming.
+        new_login: Option<String>,
+        /// Replacement plain-text password, or `None` to keep the current
+        /// one.
+        password: Option<String>,
+        /// Replacement privileges, or `None` to keep the current ones.
+        privileges: Option<Privileges>,
+    },
+}
+
+impl AccountRequest {
+    /// Login name of the account the request targets.
+    #[must_use]
+    pub fn login(&self) -> &str {
+        match self {
+            Self::Create { login, .. } | Self::Delete { login } | Self::Update { login, .. } => {
+                login
+            }
+        }
+    }
+
+    /// Privileges the request would store on the account, if any.
+    const fn granted(&self) -> Option<Privileges> {
+        match self {
+            Self::Create { privileges, .. } | Self::Update { privileges, .. } => *privileges,
+            Self::Delete { .. } => None,
+        }
+    }
+}
+
+/// Parse a New User, Delete User, or Set User payload.
+///
+/// Set User renames an account when field 101 carries its current login; the
+/// login field then holds the new name. A password field holding a single
+/// zero byte keeps the current password, and an absent one clears it, as the
+/// Hotline client does when the password box is emptied. An access bitmap
+/// (110) that is not eight bytes long is rejected.
+pub(super) fn parse_account_params(
+    ty: TransactionType,
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let params = decode_params_map_borrowed(payload)?;
+    let mut login = required_param_string(&params, FieldId::Login)?;
+    if login.is_empty() {
+        return Err(TransactionError::InvalidParamValue(FieldId::Login));
+    }
+    let privileges = first_param_bytes(&params, FieldId::UserAccess)
+        .map(|raw| wire::decode(&raw))
+        .transpose()
+        .map_err(|_| TransactionError::InvalidParamValue(FieldId::UserAccess))?;
+    let req = match ty {
+        TransactionType::NewUser => AccountRequest::Create {
+            login,
+            password: first_param_string(&params, FieldId::Password)?.unwrap_or_default(),
+            privileges,
+        },
+        TransactionType::DeleteUser => AccountRequest::Delete { login },
+        _ => {
+            let password = match first_param_bytes(&params, FieldId::Password) {
+                Some(raw) if raw == UNCHANGED_PASSWORD => None,
+                Some(raw) => Some(
+                    String::from_utf8(raw)
+                        .map_err(|_| TransactionError::InvalidParamValue(FieldId::Password))?,
+                ),
+                None => Some(String::new()),
+            };
+            let mut new_login = None;
+            if let Some(current) = first_param_string(&params, FieldId::Data)?
+                .filter(|current| !current.is_empty() && *current != login)
+            {
+                new_login = Some(std::mem::replace(&mut login, current));
+            }
+            AccountRequest::Update {
+                login,
+                new_login,
+                password,
+                privileges,
+            }
+        }
+    };
+    Ok(Command::ManageAccount { header, req })
+}
+
+/// The database and hashing pool an account change goes through.
+#[derive(Clone, Copy)]
+pub(super) struct AccountStore<'a> {
+    /// Database connection pool.
+    pub pool: &'a DbPool,
+    /// Pool hashing new passwords.
+    pub hashing: &'a HashingPool,
+}
+
+impl Command {
+    pub(super) async fn process_manage_account(
+        store: AccountStore<'_>,
+        session: &Session,
+        header: &FrameHeader,
+        req: &AccountRequest,
+    ) -> Result<Transaction, CommandError> {
+        match apply_request(store, session, header, req).await {
+            Ok(()) => {}
+            Err(AccountError::Reply(error)) => return Ok(error_reply(header, error)),
+            Err(AccountError::Command(error)) => return Err(error),
+        }
+        info!(
+            user_id = ?session.user_id,
+            login = req.login(),
+            ty = %TransactionType::from(header.ty),
+            "account changed"
+        );
+        Ok(empty_success_reply(header))
+    }
+}
+
+/// Why an account change failed: a protocol error for the caller, or a
+/// server-side failure.
+enum AccountError {
+    Reply(u32),
+    Command(CommandError),
+}
+
+impl From<RunError> for AccountError {
+    fn from(error: RunError) -> Self { Self::Command(error.into()) }
+}
+
+impl From<DieselError> for AccountError {
+    fn from(error: DieselError) -> Self { Self::Command(error.into()) }
+}
+
+async fn apply_request(
+    store: AccountStore<'_>,
+    session: &Session,
+    header: &FrameHeader,
+    req: &AccountRequest,
+) -> Result<(), AccountError> {
+    ensure_grantable(session, req)?;
+    match req {
+        AccountRequest::Create {
+            login,
+            password,
+            privileges,
+        } => {
+            let hashed = hash(store.hashing, password).await?;
+            let mut conn = acquire(store.pool, header.ty).await?;
+            create_account(&mut conn, login, &hashed, *privileges).await
+        }
+        AccountRequest::Delete { login } => delete_account(store.pool, header, login).await,
+        AccountRequest::Update {
+            login,
+            new_login,
+            password,
+            privileges,
+        } => {
+            let hashed = match password {
+                Some(plain) => Some(hash(store.hashing, plain).await?),
+                None => None,
+            };
+            let update = UserUpdate {
+                username: new_login.as_deref(),
+                password: hashed.as_deref(),
+            };
+            let mut conn = acquire(store.pool, header.ty).await?;
+            update_account(&mut conn, login, &update, *privileges).await
+        }
+    }
+}
+
+async fn create_account(
+    conn: &mut DbConnection,
+    login: &str,
+    hashed: &str,
+    privileges: Option<Privileges>,
+) -> Result<(), AccountError> {
+    let new_user = NewUser {
+        username: login,
+        password: hashed,
+    };
+    // An account must never exist with the default privileges in place of
+    // the ones requested.
+    conn.transaction::<_, AccountError, _>(async |tx_conn| {
+        create_user(tx_conn, &new_user)
+            .await
+            .map_err(constraint_error)?;
+        if let Some(granted) = privileges {
+            set_privileges(tx_conn, login, granted).await?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+async fn delete_account(
+    pool: &DbPool,
+    header: &FrameHeader,
+    login: &str,
+) -> Result<(), AccountError> {
+    let mut conn = acquire(pool, header.ty).await?;
+    match delete_user(&mut conn, login)
+        .await
+        .map_err(constraint_error)?
+    {
+        0 => Err(AccountError::Reply(ERR_ACCOUNT_NOT_FOUND)),
+        _ => Ok(()),
+    }
+}
+
+async fn update_account(
+    conn: &mut DbConnection,
+    login: &str,
+    update: &UserUpdate<'_>,
+    privileges: Option<Privileges>,
+) -> Result<(), AccountError> {
+    conn.transaction::<_, AccountError, _>(async |tx_conn| {
+        apply_update(tx_conn, login, update, privileges).await
+    })
+    .await
+}
+
+async fn apply_update(
+    conn: &mut DbConnection,
+    login: &str,
+    update: &UserUpdate<'_>,
+    privileges: Option<Privileges>,
+) -> Result<(), AccountError> {
+    let changed = if update.is_empty() {
+        usize::from(get_user_by_name(conn, login).await?.is_some())
+    } else {
+        update_user(conn, login, update)
+            .await
+            .map_err(constraint_error)?
+    };
+    if changed == 0 {
+        return Err(AccountError::Reply(ERR_ACCOUNT_NOT_FOUND));
+    }
+    let current = update.username.unwrap_or(login);
+    if let Some(granted) = privileges {
+        set_privileges(conn, current, granted).await?;
+    }
+    // Tokens were handed out against the old password.
+    if update.password.is_some()
+        && let Some(account) = get_user_by_name(conn, current).await?
+    {
+        revoke_session_tokens(conn, account.id).await?;
+    }
+    Ok(())
+}
+
+/// Refuse to store an access bitmap granting a privilege `session` does not
+/// hold itself.
+fn ensure_grantable(session: &Session, req: &AccountRequest) -> Result<(), AccountError> {
+    match req.granted() {
+        Some(granted) if !session.privileges.contains(granted) => {
+            warn!(
+                user_id = ?session.user_id,
+                login = req.login(),
+                withheld = ?granted - session.privileges,
+                "account change refused: grants privileges the administrator lacks"
+            );
+            Err(AccountError::Reply(ERR_INSUFFICIENT_PRIVILEGES))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Hash `password` on `hashing`, shedding the request when the pool is
+/// saturated.
+async fn hash(hashing: &HashingPool, password: &str) -> Result<String, AccountError> {
+    match hashing.hash(password.to_owned()).await {
+        Ok(hashed) => Ok(hashed),
+        Err(HashingError::Saturated) => {
+            warn!("account change shed: password hashing saturated");
+            Err(AccountError::Reply(ERR_SERVER_BUSY))
+        }
+        Err(error) => Err(AccountError::Command(error.into())),
+    }
+}
+
+/// Report login clashes and accounts still referenced by other records to
+/// the caller rather than as server failures.
+fn constraint_error(error: DieselError) -> AccountError {
+    match error {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+            AccountError::Reply(ERR_ACCOUNT_EXISTS)
+        }
+        DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _) => {
+            AccountError::Reply(ERR_ACCOUNT_IN_USE)
+        }
+        other => other.into(),
+    }
+}
+
+fn error_reply(header: &FrameHeader, error: u32) -> Transaction {
+    Transaction {
+        header: reply_header(header, error, 0),
+        payload: Vec::new(),
+    }
+}
@@ -0,0 +1,79 @@
+//! Away messages: the automatic response users set with Set Client User Info
+//! (304).
+//!
+//! While a session has an away message, the user list flags it as away and
+//! private messages to it are answered with the message. Operators can set
+//! `clear_away_on_activity` so that the first request the user makes
+//! afterwards, other than a keep-alive or a user info update, clears it
+//! again; the choice is read from the server's
+//! [`ServerSettings`](crate::server::settings::ServerSettings).
+
+use tracing::debug;
+
+use super::{Command, CommandContext, CommandError, handlers::push_with_retry_to_peers};
+use crate::{presence::build_notify_change_user, transaction_type::TransactionType};
+
+/// Return whether a request of type `ty` shows the user is back.
+///
+/// Keep-alives are sent by idle clients, and the login, agreement, user info
+/// and logout requests set or reset the away message themselves.
+const fn counts_as_activity(ty: TransactionType) -> bool {
+    !matches!(
+        ty,
+        TransactionType::KeepAlive
+            | TransactionType::Login
+            | TransactionType::Agreed
+            | TransactionType::SetClientUserInfo
+            | TransactionType::Logout
+    )
+}
+
+impl Command {
+    /// Clear the session's away message when the request shows the user is
+    /// back, telling peers the user-list flag changed.
+    pub(super) async fn return_from_away(
+        context: &mut CommandContext<'_>,
+        ty: TransactionType,
+    ) -> Result<(), CommandError> {
+        if !context.settings.clear_away_on_activity
+            || !counts_as_activity(ty)
+            || !context.session.clear_away()
+        {
+            return Ok(());
+        }
+        debug!(peer = %context.peer, %ty, "away message cleared by activity");
+        let Some(snapshot) = context
+            .presence_connection_id
+            .and_then(|connection_id| context.session.presence_snapshot(connection_id))
+        else {
+            return Ok(());
+        };
+        let upsert = context.presence.upsert(snapshot)?;
+        if upsert.peer_ids.is_empty() {
+            return Ok(());
+        }
+        let notification = build_notify_change_user(&upsert.snapshot)?;
+        push_with_retry_to_peers(context.messaging, &upsert.peer_ids, notification).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for classifying requests as activity.
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(TransactionType::SendChat, true)]
+    #[case(TransactionType::SendInstantMsg, true)]
+    #[case(TransactionType::GetFileNameList, true)]
+    #[case(TransactionType::KeepAlive, false)]
+    #[case(TransactionType::SetClientUserInfo, false)]
+    #[case(TransactionType::Agreed, false)]
+    #[case(TransactionType::Logout, false)]
+    fn classifies_activity(#[case] ty: TransactionType, #[case] expected: bool) {
+        assert_eq!(counts_as_activity(ty), expected);
+    }
+}
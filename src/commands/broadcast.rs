@@ -0,0 +1,32 @@
+//! Administrator broadcast command handling.
+
+use tracing::{info, warn};
+
+use super::{Command, CommandContext, CommandError, handlers::empty_success_reply};
+use crate::{
+    server::{broadcast::build_broadcast_msg, outbound::OutboundPriority},
+    transaction::FrameHeader,
+};
+
+impl Command {
+    pub(super) async fn process_broadcast(
+        context: CommandContext<'_>,
+        header: &FrameHeader,
+        text: String,
+    ) -> Result<(), CommandError> {
+        let CommandContext {
+            peer,
+            session,
+            transport,
+            messaging,
+            ..
+        } = context;
+        let message = build_broadcast_msg(&text)?;
+        transport.send_reply(empty_success_reply(header))?;
+        info!(%peer, user_id = ?session.user_id, "broadcasting announcement");
+        if let Err(error) = messaging.broadcast(message, OutboundPriority::High).await {
+            warn!(?error, "broadcast delivery failed");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+//! Public chat command handling.
+
+use super::{Command, CommandContext, CommandError, handlers::empty_success_reply};
+use crate::{
+    server::chat::{ChatLine, broadcast_chat, build_chat_msg},
+    transaction::FrameHeader,
+};
+
+impl Command {
+    pub(super) async fn process_send_chat(
+        context: CommandContext<'_>,
+        header: &FrameHeader,
+        text: String,
+        emote: bool,
+    ) -> Result<(), CommandError> {
+        let CommandContext {
+            session,
+            transport,
+            messaging,
+            presence,
+            ..
+        } = context;
+        let line = ChatLine {
+            speaker: session.display_name.clone(),
+            text,
+            emote,
+        };
+        let message = build_chat_msg(&line)?;
+        transport.send_reply(empty_success_reply(header))?;
+        broadcast_chat(messaging, presence, &message).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+//! Get Client Info Text command handling.
+
+use super::{Command, CommandContext, CommandError, ERR_INTERNAL_SERVER};
+use crate::{
+    db::{acquire, get_transfer_stats, get_user_by_id},
+    header_util::reply_header,
+    presence::build_client_info_text_reply,
+    server::client_info::ClientInfo,
+    transaction::{FrameHeader, Transaction},
+};
+
+impl Command {
+    pub(super) async fn process_get_client_info_text(
+        context: CommandContext<'_>,
+        header: &FrameHeader,
+        target_user_id: i32,
+    ) -> Result<(), CommandError> {
+        let CommandContext {
+            pool,
+            transport,
+            presence,
+            ..
+        } = context;
+        let reply = match presence.snapshot_for_user_id(target_user_id) {
+            Some(snapshot) => {
+                let details = presence.connection_details(snapshot.connection_id);
+                let mut conn = acquire(&pool, header.ty).await?;
+                let recorded = get_transfer_stats(&mut conn, snapshot.account_id).await?;
+                let info = ClientInfo::from_presence(&snapshot, details.as_ref())
+                    .add_recorded_transfers(recorded);
+                build_client_info_text_reply(header, &info.name, &info.render())?
+            }
+            None => {
+                let mut conn = acquire(&pool, header.ty).await?;
+                match get_user_by_id(&mut conn, target_user_id).await? {
+                    Some(user) => build_client_info_text_reply(header, &user.username, "")?,
+                    None => Transaction {
+                        header: reply_header(header, ERR_INTERNAL_SERVER, 0),
+                        payload: Vec::new(),
+                    },
+                }
+            }
+        };
+        transport.send_reply(reply)?;
+        Ok(())
+    }
+}
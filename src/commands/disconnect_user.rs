@@ -0,0 +1,171 @@
+//! Disconnect User command handling.
+
+use std::net::{IpAddr, SocketAddr};
+
+use tracing::{info, warn};
+
+use super::{
+    Command,
+    CommandContext,
+    CommandError,
+    ERR_INSUFFICIENT_PRIVILEGES,
+    ERR_INTERNAL_SERVER,
+    ERR_USER_NOT_ONLINE,
+    handlers::empty_success_reply,
+};
+use crate::{
+    db::{BanTarget, DbConnection, acquire, create_ban, get_user_by_id, revoke_session_tokens},
+    header_util::reply_header,
+    presence::{PresenceRegistry, PresenceSnapshot},
+    server::{
+        bans::{BAN_REASON, BanLength, ban_clock},
+        disconnect::KICK_REASON,
+        outbound::{OutboundError, OutboundMessaging},
+    },
+    transaction::{FrameHeader, Transaction},
+};
+
+/// Parameters for disconnecting another user.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DisconnectUserRequest {
+    pub(crate) target_user_id: i32,
+    pub(crate) reason: Option<String>,
+    pub(crate) ban: Option<BanLength>,
+}
+
+impl Command {
+    pub(super) async fn process_disconnect_user(
+        context: CommandContext<'_>,
+        header: &FrameHeader,
+        req: DisconnectUserRequest,
+    ) -> Result<(), CommandError> {
+        let CommandContext {
+            peer,
+            pool,
+            session,
+            transport,
+            messaging,
+            presence,
+            ..
+        } = context;
+        let target = match disconnect_target(header, presence, req.target_user_id) {
+            Ok(target) => target,
+            Err(reply) => {
+                transport.send_reply(reply)?;
+                return Ok(());
+            }
+        };
+        // Banning revokes the account's session tokens as well, so either
+        // way the user must log in with their password to come back.
+        let mut conn = acquire(&pool, header.ty).await?;
+        if let Some(length) = req.ban {
+            let address = presence
+                .connection_details(target.connection_id)
+                .map(|details| details.address);
+            ban_target(&mut conn, target.account_id, address, length).await?;
+        } else {
+            revoke_session_tokens(&mut conn, target.account_id).await?;
+        }
+        drop(conn);
+        let fallback = if req.ban.is_some() {
+            BAN_REASON
+        } else {
+            KICK_REASON
+        };
+        let reason = req.reason.filter(|text| !text.is_empty());
+        let notice = reason.as_deref().unwrap_or(fallback);
+        let error = end_connection(messaging, peer, &target, notice).await;
+        if error == 0 {
+            info!(
+                %peer,
+                user_id = ?session.user_id,
+                target_user_id = req.target_user_id,
+                ban = ?req.ban,
+                "disconnected user"
+            );
+            transport.send_reply(empty_success_reply(header))?;
+        } else {
+            transport.send_reply(error_reply(header, error))?;
+        }
+        Ok(())
+    }
+}
+
+/// Ban the account with database identifier `account_id`, and the address
+/// its connection came from, for `length`.
+///
+/// Banning the address as well stops the user logging straight back in
+/// under another account. An account deleted since it logged in is skipped
+/// with a warning, as is a connection the runtime attached no address to;
+/// the connection is still closed.
+async fn ban_target(
+    conn: &mut DbConnection,
+    account_id: i32,
+    address: Option<IpAddr>,
+    length: BanLength,
+) -> Result<(), CommandError> {
+    let expires_at = length.expires_at(ban_clock());
+    match get_user_by_id(conn, account_id).await? {
+        Some(account) => {
+            let target = BanTarget::Username(account.username);
+            create_ban(conn, &target, None, expires_at).await?;
+        }
+        None => warn!(account_id, "cannot ban an account that no longer exists"),
+    }
+    match address {
+        Some(addr) => {
+            let target = BanTarget::Address(addr);
+            create_ban(conn, &target, None, expires_at).await?;
+        }
+        None => warn!(
+            account_id,
+            "cannot ban the address of an unattached connection"
+        ),
+    }
+    Ok(())
+}
+
+/// Find the presence entry for `target_user_id` and check it may be
+/// disconnected, or build the error reply explaining why not.
+fn disconnect_target(
+    header: &FrameHeader,
+    presence: &PresenceRegistry,
+    target_user_id: i32,
+) -> Result<PresenceSnapshot, Transaction> {
+    let target = presence
+        .snapshot_for_user_id(target_user_id)
+        .ok_or_else(|| error_reply(header, ERR_USER_NOT_ONLINE))?;
+    if target.cannot_be_disconnected {
+        return Err(error_reply(header, ERR_INSUFFICIENT_PRIVILEGES));
+    }
+    Ok(target)
+}
+
+/// Ask the runtime to end the target's connection, returning the error code
+/// to report to the caller.
+async fn end_connection(
+    messaging: &dyn OutboundMessaging,
+    peer: SocketAddr,
+    target: &PresenceSnapshot,
+    notice: &str,
+) -> u32 {
+    let Some(controller) = messaging.controller() else {
+        warn!(%peer, "runtime cannot disconnect other connections");
+        return ERR_INTERNAL_SERVER;
+    };
+    match controller.disconnect(target.connection_id, notice).await {
+        Ok(()) => 0,
+        Err(OutboundError::TargetUnavailable) => ERR_USER_NOT_ONLINE,
+        Err(error) => {
+            warn!(?error, %peer, "disconnect user failed");
+            ERR_INTERNAL_SERVER
+        }
+    }
+}
+
+fn error_reply(header: &FrameHeader, error: u32) -> Transaction {
+    Transaction {
+        header: reply_header(header, error, 0),
+        payload: Vec::new(),
+    }
+}
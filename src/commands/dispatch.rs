@@ -0,0 +1,311 @@
+//! Routing of parsed commands to their handlers.
+//!
+//! Before any handler runs, a request belonging to a disabled subsystem is
+//! refused, the session is checked against the access the
+//! [`ACCESS_TABLE`](crate::access::ACCESS_TABLE) declares for the
+//! transaction type, and a permitted request may bring an away user back.
+//! Presence and messaging commands need the outbound adapters, and file
+//! transfers need the connection's presence details, so they are handled
+//! with the full [`CommandContext`]; the rest only need the pool and session
+//! and have their single reply forwarded to the transport.
+
+use std::sync::Arc;
+
+use super::{
+    Command,
+    CommandContext,
+    CommandError,
+    ERR_FEATURE_DISABLED,
+    accounts::AccountStore,
+    instant_msg::InstantMsgRequest,
+    privilege_error_reply,
+    search::enabled_scope,
+};
+use crate::{
+    access::required_access,
+    db::DbPool,
+    file_handlers,
+    handler::Session,
+    header_util::reply_header,
+    news_handlers,
+    server::{outbound::OutboundTransport, settings::ServerSettings, subsystems::Subsystems},
+    transaction::{FrameHeader, Transaction},
+    transaction_type::TransactionType,
+};
+
+impl Command {
+    pub(super) async fn dispatch(
+        self,
+        mut context: CommandContext<'_>,
+    ) -> Result<(), CommandError> {
+        if self.refuse_disabled(context.settings.subsystems, context.transport)?
+            || self.refuse_access(context.session, context.transport)?
+        {
+            return Ok(());
+        }
+        if let Some(header) = self.checked_header() {
+            Self::return_from_away(&mut context, TransactionType::from(header.ty)).await?;
+        }
+        match self {
+            Self::Login { .. }
+            | Self::GetUserNameList { .. }
+            | Self::GetClientInfoText { .. }
+            | Self::SetClientUserInfo { .. }
+            | Self::Agreed { .. } => self.process_presence_command(context).await,
+            Self::Logout { header } => Self::process_logout(context, &header).await,
+            Self::SendChat {
+                header,
+                text,
+                emote,
+            } => Self::process_send_chat(context, &header, text, emote).await,
+            Self::Broadcast { header, text } => {
+                Self::process_broadcast(context, &header, text).await
+            }
+            Self::DisconnectUser { header, req } => {
+                Self::process_disconnect_user(context, &header, req).await
+            }
+            Self::SendInstantMsg {
+                header,
+                target_user_id,
+                options,
+                text,
+                quoting,
+            } => {
+                let request = InstantMsgRequest {
+                    target_user_id,
+                    options,
+                    text,
+                    quoting,
+                };
+                Self::process_send_instant_msg(context, &header, request).await
+            }
+            Self::DownloadFile { header, req } => {
+                Self::process_download_file(context, &header, &req).await
+            }
+            Self::UploadFile { header, req } => {
+                Self::process_upload_file(context, &header, &req).await
+            }
+            Self::Unknown { header } => Self::process_unknown(context, &header),
+            command => {
+                let CommandContext {
+                    pool,
+                    session,
+                    transport,
+                    settings,
+                    ..
+                } = context;
+                let reply = command.execute(pool, session, settings).await?;
+                transport.send_reply(reply)?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Reply with [`ERR_FEATURE_DISABLED`] if this command belongs to a
+    /// subsystem the server has switched off, returning whether it was
+    /// refused.
+    fn refuse_disabled(
+        &self,
+        subsystems: Subsystems,
+        transport: &mut dyn OutboundTransport,
+    ) -> Result<bool, CommandError> {
+        let Some(header) = self.checked_header() else {
+            return Ok(false);
+        };
+        if subsystems.serves(TransactionType::from(header.ty)) {
+            return Ok(false);
+        }
+        transport.send_reply(Transaction {
+            header: reply_header(header, ERR_FEATURE_DISABLED, 0),
+            payload: Vec::new(),
+        })?;
+        Ok(true)
+    }
+
+    /// Reply with an error if the session lacks the access the table
+    /// requires for this command, returning whether it was refused.
+    fn refuse_access(
+        &self,
+        session: &Session,
+        transport: &mut dyn OutboundTransport,
+    ) -> Result<bool, CommandError> {
+        let Some(header) = self.checked_header() else {
+            return Ok(false);
+        };
+        let access = required_access(TransactionType::from(header.ty));
+        let Err(error) = session.require_access(access) else {
+            return Ok(false);
+        };
+        transport.send_reply(privilege_error_reply(header, error))?;
+        Ok(true)
+    }
+
+    /// Header of a command whose access is checked before dispatch.
+    ///
+    /// Malformed payloads are answered with an error whatever the session's
+    /// state, so they have no header to check.
+    pub(super) const fn checked_header(&self) -> Option<&FrameHeader> {
+        match self {
+            Self::Login { req } => Some(&req.header),
+            Self::Logout { header }
+            | Self::GetUserNameList { header }
+            | Self::GetClientInfoText { header, .. }
+            | Self::SetClientUserInfo { header, .. }
+            | Self::Agreed { header, .. }
+            | Self::DownloadBanner { header }
+            | Self::GetServerRules { header }
+            | Self::KeepAlive { header }
+            | Self::SendChat { header, .. }
+            | Self::Broadcast { header, .. }
+            | Self::DisconnectUser { header, .. }
+            | Self::GetUser { header, .. }
+            | Self::GetAccounts { header }
+            | Self::ManageAccount { header, .. }
+            | Self::SendInstantMsg { header, .. }
+            | Self::GetFileNameList { header, .. }
+            | Self::DownloadFile { header, .. }
+            | Self::UploadFile { header, .. }
+            | Self::DeleteFile { header, .. }
+            | Self::GetFileInfo { header, .. }
+            | Self::SetFileInfo { header, .. }
+            | Self::MoveFile { header, .. }
+            | Self::GetNewsCategoryNameList { header, .. }
+            | Self::GetNewsArticleNameList { header, .. }
+            | Self::GetNewsArticleData { header, .. }
+            | Self::PostNewsArticle { header, .. }
+            | Self::DeleteNewsArticle { header, .. }
+            | Self::ManageNewsStructure { header, .. }
+            | Self::Search { header, .. }
+            | Self::Unknown { header } => Some(header),
+            Self::InvalidPayload { .. } => None,
+        }
+    }
+
+    async fn process_presence_command(
+        self,
+        context: CommandContext<'_>,
+    ) -> Result<(), CommandError> {
+        match self {
+            Self::Login { req } => Self::process_login_with_presence(context, req).await,
+            Self::GetUserNameList { header } => Self::process_get_user_name_list(context, &header),
+            Self::GetClientInfoText {
+                header,
+                target_user_id,
+            } => Self::process_get_client_info_text(context, &header, target_user_id).await,
+            Self::SetClientUserInfo { header, update } => {
+                Self::process_set_client_user_info(context, header, update).await
+            }
+            Self::Agreed { header, update } => Self::process_agreed(context, header, update).await,
+            _ => Err(CommandError::Invariant(
+                "non-presence command passed to presence dispatcher",
+            )),
+        }
+    }
+
+    async fn execute(
+        self,
+        pool: DbPool,
+        session: &mut Session,
+        settings: &ServerSettings,
+    ) -> Result<Transaction, CommandError> {
+        match self {
+            Self::GetFileNameList { header, path } => {
+                file_handlers::process_get_file_name_list(&pool, session, &header, path.as_deref())
+                    .await
+            }
+            Self::DeleteFile { header, req } => {
+                let store = file_handlers::FileStore {
+                    pool: &pool,
+                    storage: settings.storage.as_ref(),
+                };
+                file_handlers::process_delete_file(store, session, &header, &req).await
+            }
+            Self::GetFileInfo { header, req } => {
+                file_handlers::process_get_file_info(&pool, session, &header, &req).await
+            }
+            Self::SetFileInfo { header, req } => {
+                file_handlers::process_set_file_info(&pool, session, &header, &req).await
+            }
+            Self::MoveFile { header, req } => {
+                file_handlers::process_move_file(&pool, session, &header, &req).await
+            }
+            Self::GetNewsCategoryNameList {
+                header,
+                path,
+                with_activity,
+            } => {
+                let listing = news_handlers::CategoryListing {
+                    root: session.news_root,
+                    path,
+                    encoding: session.news_listing,
+                    with_activity,
+                };
+                Ok(news_handlers::process_category_name_list(pool, header, listing).await)
+            }
+            Self::GetNewsArticleNameList { header, path } => {
+                let root = session.news_root;
+                Ok(news_handlers::process_article_name_list(pool, header, root, path).await)
+            }
+            Self::GetNewsArticleData { header, req } => {
+                let query = news_handlers::ArticleDataQuery {
+                    root: session.news_root,
+                    req,
+                    transfers: Arc::clone(&settings.transfers),
+                    threshold: settings.news_transfer_threshold,
+                };
+                Ok(news_handlers::process_article_data(pool, header, query).await)
+            }
+            Self::PostNewsArticle { header, req } => {
+                let root = session.news_root;
+                Ok(news_handlers::process_post_article(pool, header, root, req).await)
+            }
+            Self::DeleteNewsArticle { header, req } => {
+                let root = session.news_root;
+                Ok(news_handlers::process_delete_article(pool, header, root, req).await)
+            }
+            Self::ManageNewsStructure { header, req } => {
+                Ok(news_handlers::process_news_structure(pool, session, header, req).await)
+            }
+            Self::GetUser { header, login } => Self::process_get_user(&pool, &header, &login).await,
+            Self::GetAccounts { header } => Self::process_get_accounts(&pool, &header).await,
+            Self::ManageAccount { header, req } => {
+                let store = AccountStore {
+                    pool: &pool,
+                    hashing: &settings.hashing,
+                };
+                Self::process_manage_account(store, session, &header, &req).await
+            }
+            Self::Search { header, mut req } => {
+                // Parts the server has switched off are never searched.
+                req.scope &= enabled_scope(settings.subsystems);
+                Self::process_search(&pool, session, &header, &req).await
+            }
+            Self::DownloadBanner { header } => Self::process_download_banner(&header, settings),
+            Self::GetServerRules { header } => {
+                Self::process_get_server_rules(&header, &settings.rules)
+            }
+            Self::KeepAlive { header } => Ok(Self::process_keep_alive(&header)),
+            Self::Login { .. }
+            | Self::Logout { .. }
+            | Self::GetUserNameList { .. }
+            | Self::GetClientInfoText { .. }
+            | Self::SetClientUserInfo { .. }
+            | Self::Agreed { .. } => Err(CommandError::Invariant(
+                "presence command should be handled before execute",
+            )),
+            Self::SendChat { .. }
+            | Self::SendInstantMsg { .. }
+            | Self::Broadcast { .. }
+            | Self::DisconnectUser { .. } => Err(CommandError::Invariant(
+                "messaging command should be handled before execute",
+            )),
+            Self::DownloadFile { .. } | Self::UploadFile { .. } => Err(CommandError::Invariant(
+                "file transfer command should be handled before execute",
+            )),
+            Self::Unknown { .. } => Err(CommandError::Invariant(
+                "unknown command should be handled before execute",
+            )),
+            Self::InvalidPayload { header } => Ok(Self::process_invalid_payload(header)),
+        }
+    }
+}
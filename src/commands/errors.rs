@@ -0,0 +1,182 @@
+//! Protocol error codes and the command processing error type.
+
+use diesel_async::pooled_connection::bb8::RunError;
+use thiserror::Error;
+
+use crate::{
+    field_id::FieldId,
+    handler::PrivilegeError,
+    hashing::HashingError,
+    header_util::reply_header,
+    server::outbound::OutboundError,
+    transaction::{FrameHeader, Transaction, TransactionError, encode_params},
+};
+
+/// Error code used when authentication is required but not present.
+pub const ERR_NOT_AUTHENTICATED: u32 = 1;
+/// Error code used when a request includes an unexpected payload.
+pub const ERR_INVALID_PAYLOAD: u32 = 2;
+/// Error code used for unexpected server-side failures.
+pub const ERR_INTERNAL_SERVER: u32 = 3;
+/// Error code used when the user lacks the required privilege.
+pub const ERR_INSUFFICIENT_PRIVILEGES: u32 = 4;
+/// Error code used when the requested news path is malformed.
+pub const NEWS_ERR_PATH_UNSUPPORTED: u32 = 5;
+/// Error code used when a news article cannot be found.
+pub const NEWS_ERR_ARTICLE_NOT_FOUND: u32 = 6;
+/// Error code used when a private message names a user who is not online.
+pub const ERR_USER_NOT_ONLINE: u32 = 7;
+/// Error code used when the server sheds a request under load.
+pub const ERR_SERVER_BUSY: u32 = 8;
+/// Error code used when a file request names a folder path mxd cannot serve.
+pub const FILE_ERR_PATH_UNSUPPORTED: u32 = 9;
+/// Error code used when a file or folder cannot be found.
+pub const FILE_ERR_NOT_FOUND: u32 = 10;
+/// Error code used when a rename or move collides with an existing name.
+pub const FILE_ERR_NAME_TAKEN: u32 = 11;
+/// Error code used when deleting a folder that still has contents.
+pub const FILE_ERR_FOLDER_NOT_EMPTY: u32 = 12;
+/// Error code used when a well-formed news path names no bundle or category.
+pub const NEWS_ERR_PATH_NOT_FOUND: u32 = 13;
+/// Error code used when a news bundle or category name is already in use.
+pub const NEWS_ERR_NAME_TAKEN: u32 = 14;
+/// Error code used when a banned account or address tries to log in.
+pub const ERR_BANNED: u32 = 15;
+/// Error code used when creating or renaming an account to a login in use.
+pub const ERR_ACCOUNT_EXISTS: u32 = 16;
+/// Error code used when an account administration request names no account.
+pub const ERR_ACCOUNT_NOT_FOUND: u32 = 17;
+/// Error code used when an account cannot be deleted because other records
+/// still refer to it.
+pub const ERR_ACCOUNT_IN_USE: u32 = 18;
+/// Error code used when repeated failed logins have temporarily locked out
+/// the account or address.
+pub const ERR_LOGIN_LOCKED: u32 = 19;
+/// Error code used when a file request would change an archive snapshot.
+pub const FILE_ERR_READ_ONLY: u32 = 20;
+/// Error code used when an upload would take the account past its storage
+/// quota.
+pub const FILE_ERR_QUOTA_EXCEEDED: u32 = 21;
+/// Error code used when a request fails its payload checksum; the client may
+/// resend it.
+pub const ERR_CHECKSUM_MISMATCH: u32 = 22;
+/// Error code used when a client sends requests faster than its rate limit
+/// allows; the client may retry later.
+pub const ERR_RATE_LIMITED: u32 = 23;
+/// Error code used when no database connection frees up within the pool's
+/// connection timeout; the client may retry later.
+pub const ERR_DATABASE_BUSY: u32 = 24;
+/// Error code used when a request belongs to a subsystem the server has
+/// switched off.
+pub const ERR_FEATURE_DISABLED: u32 = 25;
+/// Error code used when the download policy forbids a download; the reply
+/// explains why in its error text.
+pub const FILE_ERR_DOWNLOAD_REFUSED: u32 = 26;
+
+/// Errors that can occur while processing commands.
+#[derive(Debug, Error)]
+pub enum CommandError {
+    /// A database query failed.
+    #[error("database error: {0}")]
+    Database(#[from] diesel::result::Error),
+    /// Connection pool access failed.
+    #[error("pool error: {0}")]
+    Pool(#[from] RunError),
+    /// Transaction parsing or encoding failed.
+    #[error("transaction error: {0}")]
+    Transaction(#[from] TransactionError),
+    /// Privilege checks failed unexpectedly.
+    #[error("privilege error: {0}")]
+    Privilege(#[from] PrivilegeError),
+    /// Command processing invariants were violated.
+    #[error("invariant violation: {0}")]
+    Invariant(&'static str),
+    /// Password verification could not run.
+    #[error("hashing error: {0}")]
+    Hashing(#[from] HashingError),
+    /// Outbound transport failed to deliver a reply.
+    #[error("outbound transport error: {0}")]
+    Outbound(#[from] OutboundError),
+}
+
+impl CommandError {
+    /// Protocol error code reported to the client for this failure.
+    #[must_use]
+    pub const fn error_code(&self) -> u32 {
+        match self {
+            Self::Transaction(error) => transaction_error_code(error),
+            Self::Pool(error) => pool_error_code(error),
+            _ => ERR_INTERNAL_SERVER,
+        }
+    }
+
+    /// Text reported to the client alongside [`Self::error_code`], if any.
+    ///
+    /// Only malformed requests are explained; server-side failures stay
+    /// opaque so database and hashing details never reach clients.
+    #[must_use]
+    pub fn error_text(&self) -> Option<String> {
+        match self {
+            Self::Transaction(error) if transaction_error_code(error) != ERR_INTERNAL_SERVER => {
+                Some(error.to_string())
+            }
+            _ => None,
+        }
+    }
+
+    /// Build the error reply answering `request`, carrying
+    /// [`Self::error_text`] in an `ErrorText` (100) field when there is one.
+    ///
+    /// Both runtimes send this reply for requests that fail to parse or
+    /// process, so a failure is reported the same way whichever runtime
+    /// serves the client.
+    #[must_use]
+    pub fn reply_to(&self, request: &FrameHeader) -> Transaction {
+        let payload = self
+            .error_text()
+            .and_then(|text| encode_params(&[(FieldId::ErrorText, text.as_bytes())]).ok())
+            .unwrap_or_default();
+        Transaction {
+            header: reply_header(request, self.error_code(), payload.len()),
+            payload,
+        }
+    }
+}
+
+/// Protocol error code reported for a request that could not check out a
+/// database connection.
+///
+/// An exhausted pool reports [`ERR_DATABASE_BUSY`] so the client may retry;
+/// a connection that failed to open is a server-side failure.
+#[must_use]
+pub const fn pool_error_code(error: &RunError) -> u32 {
+    match error {
+        RunError::TimedOut => ERR_DATABASE_BUSY,
+        RunError::User(_) => ERR_INTERNAL_SERVER,
+    }
+}
+
+/// Protocol error code reported for a request that failed with `error`.
+///
+/// Malformed frames and parameters are the client's fault and report
+/// [`ERR_INVALID_PAYLOAD`]; a corrupt checksum reports
+/// [`ERR_CHECKSUM_MISMATCH`] so the client may resend; anything else is a
+/// server-side failure.
+#[must_use]
+pub const fn transaction_error_code(error: &TransactionError) -> u32 {
+    match error {
+        TransactionError::InvalidFlags
+        | TransactionError::PayloadTooLarge
+        | TransactionError::SizeMismatch
+        | TransactionError::HeaderMismatch
+        | TransactionError::DuplicateField(_)
+        | TransactionError::ShortBuffer
+        | TransactionError::TooManyParams(_)
+        | TransactionError::MissingField(_)
+        | TransactionError::InvalidParamValue(_) => ERR_INVALID_PAYLOAD,
+        TransactionError::ChecksumMismatch => ERR_CHECKSUM_MISMATCH,
+        TransactionError::ReassemblyTimeout
+        | TransactionError::Io(_)
+        | TransactionError::Timeout => ERR_INTERNAL_SERVER,
+    }
+}
@@ -0,0 +1,80 @@
+//! Download File (202) and Upload File (203) command handling.
+//!
+//! The file handlers file the transfer, but the transfer port finishes it
+//! after the reply has gone. These commands therefore hand over the
+//! connection's transfer tally, so the port can count the file against the
+//! connection that asked for it, and the connection's identifier, so the
+//! transfer queue can tell it when a queued transfer moves up.
+
+use std::sync::Arc;
+
+use super::{Command, CommandContext, CommandError};
+use crate::{
+    file_handlers::{self, DownloadFileRequest, TransferContext, UploadFileRequest},
+    presence::PresenceRegistry,
+    server::{outbound::OutboundConnectionId, transfer_stats::TransferTally},
+    transaction::FrameHeader,
+};
+
+impl Command {
+    pub(super) async fn process_download_file(
+        context: CommandContext<'_>,
+        header: &FrameHeader,
+        req: &DownloadFileRequest,
+    ) -> Result<(), CommandError> {
+        let CommandContext {
+            pool,
+            session,
+            transport,
+            presence,
+            presence_connection_id,
+            settings,
+            ..
+        } = context;
+        let transfer = TransferContext {
+            pool: &pool,
+            settings,
+            connection: presence_connection_id,
+            tally: transfer_tally(presence, presence_connection_id),
+        };
+        let reply = file_handlers::process_download_file(transfer, session, header, req).await?;
+        transport.send_reply(reply)?;
+        Ok(())
+    }
+
+    pub(super) async fn process_upload_file(
+        context: CommandContext<'_>,
+        header: &FrameHeader,
+        req: &UploadFileRequest,
+    ) -> Result<(), CommandError> {
+        let CommandContext {
+            pool,
+            session,
+            transport,
+            presence,
+            presence_connection_id,
+            settings,
+            ..
+        } = context;
+        let transfer = TransferContext {
+            pool: &pool,
+            settings,
+            connection: presence_connection_id,
+            tally: transfer_tally(presence, presence_connection_id),
+        };
+        let reply = file_handlers::process_upload_file(transfer, session, header, req).await?;
+        transport.send_reply(reply)?;
+        Ok(())
+    }
+}
+
+/// Return the tally counting the connection's transfers, if the runtime
+/// attached one.
+fn transfer_tally(
+    presence: &PresenceRegistry,
+    connection: Option<OutboundConnectionId>,
+) -> Option<Arc<TransferTally>> {
+    connection
+        .and_then(|connection_id| presence.connection_details(connection_id))
+        .map(|details| details.transfers)
+}
@@ -3,10 +3,10 @@
 //! This module implements the per-command processing logic invoked by
 //! `Command::process` and centralizes reply construction shared across handlers.
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc, time::Instant};
 
 use tokio::time::{Duration, sleep};
-use tracing::warn;
+use tracing::{debug, info, warn};
 
 use super::{
     Command,
@@ -14,72 +14,34 @@ use super::{
     CommandError,
     ERR_INTERNAL_SERVER,
     ERR_INVALID_PAYLOAD,
+    FILE_ERR_NOT_FOUND,
     UserInfoUpdate,
-    check_privilege_and_run,
-    privilege_error_reply,
+    unknown::{UNKNOWN_TRANSACTION_DISCONNECT_REASON, UnknownAction, record_unknown_transaction},
 };
 use crate::{
-    db::{DbPool, get_user_by_id},
+    db::DbPool,
     field_id::FieldId,
-    handler::PrivilegeError,
     header_util::reply_header,
-    login::{LoginRequest, handle_login},
+    login::{LoginOrigin, LoginRequest, handle_login},
     presence::{
         PresenceRegistry,
-        build_client_info_text_reply,
+        PresenceSnapshot,
         build_notify_change_user,
         build_user_name_list_reply,
     },
-    privileges::Privileges,
-    server::outbound::{OutboundMessaging, OutboundPriority, OutboundTarget, OutboundTransport},
+    server::{
+        duplicate_login::{audit_duplicate_login, build_logged_in_elsewhere_msg},
+        news_digest::take_login_digest,
+        outbound::{OutboundMessaging, OutboundPriority, OutboundTarget, OutboundTransport},
+        rules::ServerRules,
+        settings::ServerSettings,
+        subsystems::Subsystem,
+        transfer_port::{PendingTransfer, TransferKind},
+    },
     transaction::{FrameHeader, Transaction, encode_params},
 };
 
 impl Command {
-    pub(super) async fn process_login(
-        peer: SocketAddr,
-        pool: DbPool,
-        session: &mut crate::handler::Session,
-        req: LoginRequest,
-    ) -> Result<Transaction, CommandError> {
-        handle_login(peer, session, pool, req).await
-    }
-
-    pub(super) async fn process_get_file_name_list(
-        pool: DbPool,
-        session: &mut crate::handler::Session,
-        header: FrameHeader,
-    ) -> Result<Transaction, CommandError> {
-        let header_reply = header.clone();
-        let session_ref = &*session;
-        check_privilege_and_run(
-            session_ref,
-            &header,
-            Privileges::DOWNLOAD_FILE,
-            || async move {
-                let Some(uid) = session_ref.user_id else {
-                    tracing::error!("authenticated session missing user id in file list handler");
-                    return Err(CommandError::Invariant(
-                        "authenticated session missing user id",
-                    ));
-                };
-                let mut conn = pool.get().await?;
-                let files =
-                    crate::db::list_visible_root_file_nodes_for_user(&mut conn, uid).await?;
-                let params: Vec<(FieldId, &[u8])> = files
-                    .iter()
-                    .map(|f| (FieldId::FileName, f.name.as_bytes()))
-                    .collect();
-                let payload = encode_params(&params)?;
-                Ok(Transaction {
-                    header: reply_header(&header_reply, 0, payload.len()),
-                    payload,
-                })
-            },
-        )
-        .await
-    }
-
     pub(super) async fn process_login_with_presence(
         context: CommandContext<'_>,
         req: LoginRequest,
@@ -92,13 +54,19 @@ impl Command {
             messaging,
             presence,
             presence_connection_id,
+            settings,
         } = context;
         let presence_context = PresenceContext {
             transport,
             messaging,
             presence,
         };
-        let reply = handle_login(peer, session, pool, req).await?;
+        let origin = LoginOrigin {
+            peer,
+            connection: presence_connection_id,
+            settings,
+        };
+        let reply = handle_login(origin, session, pool.clone(), req).await?;
         presence_context.transport.send_reply(reply)?;
         let Some(connection_id) = presence_connection_id else {
             return Ok(());
@@ -107,12 +75,13 @@ impl Command {
             return Ok(());
         };
         build_notify_change_user(&snapshot)?;
-        let upsert = presence_context.presence.upsert(snapshot)?;
-        if upsert.peer_ids.is_empty() {
-            return Ok(());
+        publish_snapshot(&presence_context, peer, snapshot).await?;
+        // Digests wait unread while news is switched off.
+        if let Some(user_id) = session.user_id
+            && settings.subsystems.is_enabled(Subsystem::News)
+        {
+            deliver_login_digest(&pool, messaging, connection_id, user_id).await;
         }
-        let notification = build_notify_change_user(&upsert.snapshot)?;
-        push_with_retry_to_peers(presence_context.messaging, &upsert.peer_ids, notification).await;
         Ok(())
     }
 
@@ -121,67 +90,22 @@ impl Command {
         header: &FrameHeader,
     ) -> Result<(), CommandError> {
         let CommandContext {
-            session,
             transport,
             presence,
             ..
         } = context;
-        if !session.is_online() {
-            transport.send_reply(privilege_error_reply(
-                header,
-                PrivilegeError::NotAuthenticated,
-            ))?;
-            return Ok(());
-        }
         let reply = build_user_name_list_reply(header, &presence.online_snapshots())?;
         transport.send_reply(reply)?;
         Ok(())
     }
 
-    pub(super) async fn process_get_client_info_text(
-        context: CommandContext<'_>,
-        header: FrameHeader,
-        target_user_id: i32,
-    ) -> Result<(), CommandError> {
-        let CommandContext {
-            pool,
-            session,
-            transport,
-            presence,
-            ..
-        } = context;
-        let header_reply = header.clone();
-        let reply = check_privilege_and_run(
-            session,
-            &header,
-            Privileges::GET_CLIENT_INFO,
-            || async move {
-                if let Some(snapshot) = presence.snapshot_for_user_id(target_user_id) {
-                    return build_client_info_text_reply(&header_reply, &snapshot.display_name, "")
-                        .map_err(CommandError::from);
-                }
-                let mut conn = pool.get().await?;
-                match get_user_by_id(&mut conn, target_user_id).await? {
-                    Some(user) => build_client_info_text_reply(&header_reply, &user.username, "")
-                        .map_err(CommandError::from),
-                    None => Ok(Transaction {
-                        header: reply_header(&header_reply, ERR_INTERNAL_SERVER, 0),
-                        payload: Vec::new(),
-                    }),
-                }
-            },
-        )
-        .await?;
-        transport.send_reply(reply)?;
-        Ok(())
-    }
-
     pub(super) async fn process_set_client_user_info(
         context: CommandContext<'_>,
         header: FrameHeader,
         update: UserInfoUpdate,
     ) -> Result<(), CommandError> {
         let CommandContext {
+            peer,
             session,
             transport,
             messaging,
@@ -194,13 +118,6 @@ impl Command {
             messaging,
             presence,
         };
-        if let Err(error) = session.require_authenticated() {
-            presence_context
-                .transport
-                .send_reply(privilege_error_reply(&header, error))?;
-            return Ok(());
-        }
-
         apply_user_info_update(session, update);
         let maybe_snapshot = presence_connection_id
             .and_then(|connection_id| session.presence_snapshot(connection_id));
@@ -214,13 +131,66 @@ impl Command {
         let Some(snapshot) = maybe_snapshot else {
             return Ok(());
         };
-        let upsert = presence_context.presence.upsert(snapshot)?;
-        if upsert.peer_ids.is_empty() {
-            return Ok(());
+        publish_snapshot(&presence_context, peer, snapshot).await
+    }
+
+    /// Accept the server agreement, then apply the user details sent with
+    /// it exactly as Set Client User Info (304) would.
+    pub(super) async fn process_agreed(
+        mut context: CommandContext<'_>,
+        header: FrameHeader,
+        update: UserInfoUpdate,
+    ) -> Result<(), CommandError> {
+        if context.session.accept_agreement() {
+            info!(peer = %context.peer, "server agreement accepted");
+        }
+        Self::process_set_client_user_info(context, header, update).await
+    }
+
+    /// File the configured banner image for the transfer port and reply
+    /// with its reference number (107) and size (108).
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    pub(super) fn process_download_banner(
+        header: &FrameHeader,
+        settings: &ServerSettings,
+    ) -> Result<Transaction, CommandError> {
+        let Some(banner) = settings.agreement.banner() else {
+            return Ok(Transaction {
+                header: reply_header(header, FILE_ERR_NOT_FOUND, 0),
+                payload: Vec::new(),
+            });
+        };
+        let size = u32::try_from(banner.len())
+            .map_err(|_| CommandError::Invariant("banner exceeds transfer size"))?;
+        let reference = settings.transfers.register(PendingTransfer::new(
+            TransferKind::Banner,
+            Arc::from(banner),
+            Instant::now(),
+        ));
+        let payload = encode_params(&[
+            (FieldId::ReferenceNumber, reference.to_be_bytes()),
+            (FieldId::TransferSize, size.to_be_bytes()),
+        ])?;
+        Ok(Transaction {
+            header: reply_header(header, 0, payload.len()),
+            payload,
+        })
+    }
+
+    /// Answer with the configured server rules and help sections.
+    pub(super) fn process_get_server_rules(
+        header: &FrameHeader,
+        rules: &ServerRules,
+    ) -> Result<Transaction, CommandError> {
+        Ok(rules.reply(header)?)
+    }
+
+    /// Acknowledge a keep-alive; the runtime has already noted the activity.
+    pub(super) fn process_keep_alive(header: &FrameHeader) -> Transaction {
+        Transaction {
+            header: reply_header(header, 0, 0),
+            payload: Vec::new(),
         }
-        let notification = build_notify_change_user(&upsert.snapshot)?;
-        push_with_retry_to_peers(presence_context.messaging, &upsert.peer_ids, notification).await;
-        Ok(())
     }
 
     #[expect(
@@ -234,24 +204,53 @@ impl Command {
         }
     }
 
-    #[expect(
-        clippy::needless_pass_by_value,
-        reason = "signature required by Command.process dispatch"
-    )]
-    pub(super) fn process_unknown(peer: SocketAddr, header: FrameHeader) -> Transaction {
-        handle_unknown(peer, &header)
+    pub(super) fn process_unknown(
+        context: CommandContext<'_>,
+        header: &FrameHeader,
+    ) -> Result<(), CommandError> {
+        let CommandContext {
+            peer,
+            session,
+            transport,
+            settings,
+            ..
+        } = context;
+        session.unknown_transactions = session.unknown_transactions.saturating_add(1);
+        let total = record_unknown_transaction(header.ty);
+        if total == 1 {
+            info!(%peer, ty = header.ty, "first sighting of unknown transaction type");
+        } else {
+            debug!(%peer, ty = header.ty, total, "unknown transaction");
+        }
+        let reply = match settings
+            .unknown_transactions
+            .action(session.unknown_transactions)
+        {
+            UnknownAction::Acknowledge => empty_success_reply(header),
+            UnknownAction::Reject => unknown_error_reply(header),
+            UnknownAction::RejectAndDisconnect => {
+                warn!(
+                    %peer,
+                    count = session.unknown_transactions,
+                    "disconnecting client after repeated unknown transactions"
+                );
+                session.disconnect_reason = Some(UNKNOWN_TRANSACTION_DISCONNECT_REASON);
+                unknown_error_reply(header)
+            }
+        };
+        transport.send_reply(reply)?;
+        Ok(())
     }
 }
 
-fn handle_unknown(peer: SocketAddr, header: &FrameHeader) -> Transaction {
-    tracing::warn!(%peer, ty = %header.ty, "unknown transaction");
+fn unknown_error_reply(header: &FrameHeader) -> Transaction {
     Transaction {
         header: reply_header(header, ERR_INTERNAL_SERVER, 0),
         payload: Vec::new(),
     }
 }
 
-fn empty_success_reply(header: &FrameHeader) -> Transaction {
+pub(super) fn empty_success_reply(header: &FrameHeader) -> Transaction {
     Transaction {
         header: reply_header(header, 0, 0),
         payload: Vec::new(),
@@ -271,14 +270,60 @@ fn apply_user_info_update(session: &mut crate::handler::Session, update: UserInf
     }
     if final_flags.has_auto_response() {
         if let Some(auto_response) = update.auto_response {
-            session.auto_response = Some(auto_response);
+            session.auto_response = Some(auto_response).filter(|text| !text.is_empty());
         }
     } else {
         session.auto_response = None;
     }
 }
 
-async fn push_with_retry_to_peers(
+/// Store `snapshot` in the presence registry and tell the peers it
+/// concerns.
+///
+/// Online peers learn of the change with Notify Change User (301). When the
+/// snapshot brings a second session of an account online, the sessions it
+/// already had are told it logged in elsewhere and the login is audited.
+async fn publish_snapshot(
+    context: &PresenceContext<'_>,
+    peer: SocketAddr,
+    snapshot: PresenceSnapshot,
+) -> Result<(), CommandError> {
+    let upsert = context.presence.upsert(snapshot)?;
+    if !upsert.earlier_sessions.is_empty() {
+        audit_duplicate_login(&upsert.snapshot, peer.ip(), &upsert.earlier_sessions);
+        let earlier_ids: Vec<_> = upsert
+            .earlier_sessions
+            .iter()
+            .map(|session| session.connection_id)
+            .collect();
+        let notice = build_logged_in_elsewhere_msg()?;
+        push_with_retry_to_peers(context.messaging, &earlier_ids, notice).await;
+    }
+    if upsert.peer_ids.is_empty() {
+        return Ok(());
+    }
+    let notification = build_notify_change_user(&upsert.snapshot)?;
+    push_with_retry_to_peers(context.messaging, &upsert.peer_ids, notification).await;
+    Ok(())
+}
+
+/// Push the news digest waiting for `user_id` to its new session, if any.
+///
+/// A digest that cannot be read is logged rather than failing the login.
+async fn deliver_login_digest(
+    pool: &DbPool,
+    messaging: &dyn OutboundMessaging,
+    connection_id: crate::server::outbound::OutboundConnectionId,
+    user_id: i32,
+) {
+    match take_login_digest(pool, user_id).await {
+        Ok(Some(digest)) => push_with_retry_to_peer(messaging, connection_id, digest).await,
+        Ok(None) => {}
+        Err(error) => warn!(%error, user_id, "failed to read news digest"),
+    }
+}
+
+pub(super) async fn push_with_retry_to_peers(
     messaging: &dyn OutboundMessaging,
     connection_ids: &[crate::server::outbound::OutboundConnectionId],
     message: Transaction,
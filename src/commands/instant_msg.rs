@@ -0,0 +1,119 @@
+//! Private instant message command handling.
+//!
+//! When the recipient has an away message set, it is sent back to the sender
+//! as an automatic response from the recipient, quoting the message.
+
+use tracing::warn;
+
+use super::{
+    Command,
+    CommandContext,
+    CommandError,
+    ERR_USER_NOT_ONLINE,
+    handlers::empty_success_reply,
+};
+use crate::{
+    header_util::reply_header,
+    presence::PresenceSnapshot,
+    server::{
+        instant_msg::{InstantMessage, MSG_OPTION_AUTOMATIC_RESPONSE, build_server_msg},
+        outbound::{OutboundConnectionId, OutboundMessaging, OutboundPriority, OutboundTarget},
+    },
+    transaction::{FrameHeader, Transaction},
+};
+
+/// Parameters of a Send Instant Message (108) request.
+pub(super) struct InstantMsgRequest {
+    pub(super) target_user_id: i32,
+    pub(super) options: u32,
+    pub(super) text: String,
+    pub(super) quoting: Option<String>,
+}
+
+impl Command {
+    pub(super) async fn process_send_instant_msg(
+        context: CommandContext<'_>,
+        header: &FrameHeader,
+        request: InstantMsgRequest,
+    ) -> Result<(), CommandError> {
+        let CommandContext {
+            session,
+            transport,
+            messaging,
+            presence,
+            presence_connection_id,
+            ..
+        } = context;
+        let Some(sender_id) = session.user_id else {
+            return Err(CommandError::Invariant("online session missing user id"));
+        };
+        let Some(recipient) = presence.snapshot_for_user_id(request.target_user_id) else {
+            transport.send_reply(Transaction {
+                header: reply_header(header, ERR_USER_NOT_ONLINE, 0),
+                payload: Vec::new(),
+            })?;
+            return Ok(());
+        };
+
+        let auto_response = presence_connection_id.zip(automatic_response(&recipient, &request));
+        let message = build_server_msg(&InstantMessage {
+            sender_id,
+            sender_name: session.display_name.clone(),
+            options: request.options,
+            text: request.text,
+            quoting: request.quoting,
+        })?;
+        transport.send_reply(empty_success_reply(header))?;
+        deliver(
+            messaging,
+            recipient.connection_id,
+            message,
+            "private message",
+        )
+        .await;
+        if let Some((sender_connection_id, away_message)) = auto_response {
+            let reply = build_server_msg(&away_message)?;
+            deliver(messaging, sender_connection_id, reply, "automatic response").await;
+        }
+        Ok(())
+    }
+}
+
+/// The reply an away `recipient` sends back automatically: their away
+/// message, quoting the message that prompted it. Automatic responses are
+/// not answered, so two away users cannot keep replying to each other.
+fn automatic_response(
+    recipient: &PresenceSnapshot,
+    request: &InstantMsgRequest,
+) -> Option<InstantMessage> {
+    if request.options == MSG_OPTION_AUTOMATIC_RESPONSE {
+        return None;
+    }
+    let text = recipient.auto_response.clone()?;
+    Some(InstantMessage {
+        sender_id: recipient.user_id,
+        sender_name: recipient.display_name.clone(),
+        options: MSG_OPTION_AUTOMATIC_RESPONSE,
+        text,
+        quoting: Some(request.text.clone()),
+    })
+}
+
+async fn deliver(
+    messaging: &dyn OutboundMessaging,
+    connection_id: OutboundConnectionId,
+    message: Transaction,
+    kind: &str,
+) {
+    let target = OutboundTarget::Connection(connection_id);
+    if let Err(error) = messaging
+        .push(target, message, OutboundPriority::High)
+        .await
+    {
+        warn!(
+            ?error,
+            target = connection_id.as_u64(),
+            "{kind} delivery failed"
+        );
+    }
+}
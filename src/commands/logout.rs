@@ -0,0 +1,55 @@
+//! Logout (mxd extension): end the login while keeping the connection open.
+//!
+//! The session returns to the state of a fresh connection, so the client can
+//! log in again, possibly as another account, without reconnecting. Peers see
+//! the user leave exactly as they would on disconnect. The account's session
+//! tokens are revoked, so logging back in takes the password.
+
+use tracing::info;
+
+use super::{
+    Command,
+    CommandContext,
+    CommandError,
+    handlers::{empty_success_reply, push_with_retry_to_peers},
+};
+use crate::{
+    db::{acquire, revoke_session_tokens},
+    presence::build_notify_delete_user,
+    transaction::FrameHeader,
+};
+
+impl Command {
+    pub(super) async fn process_logout(
+        context: CommandContext<'_>,
+        header: &FrameHeader,
+    ) -> Result<(), CommandError> {
+        let CommandContext {
+            peer,
+            pool,
+            session,
+            transport,
+            messaging,
+            presence,
+            presence_connection_id,
+            ..
+        } = context;
+        let user_id = session.user_id;
+        if let Some(account_id) = user_id {
+            let mut conn = acquire(&pool, header.ty).await?;
+            revoke_session_tokens(&mut conn, account_id).await?;
+        }
+        session.invalidate();
+        transport.send_reply(empty_success_reply(header))?;
+        info!(%peer, ?user_id, "logged out");
+        let Some(removal) = presence_connection_id.and_then(|id| presence.withdraw(id)) else {
+            return Ok(());
+        };
+        if removal.remaining_peer_ids.is_empty() {
+            return Ok(());
+        }
+        let notification = build_notify_delete_user(removal.departed.user_id)?;
+        push_with_retry_to_peers(messaging, &removal.remaining_peer_ids, notification).await;
+        Ok(())
+    }
+}
@@ -5,68 +5,99 @@
 //! the connection handler to drive database operations and build reply
 //! transactions.
 
-use std::net::SocketAddr;
-
+mod account_info;
+mod accounts;
+mod away;
+mod broadcast;
+mod chat;
+mod client_info;
+mod disconnect_user;
+mod dispatch;
+mod errors;
+mod file_transfer;
 mod handlers;
+mod instant_msg;
+mod logout;
 mod parsing;
+mod search;
 mod support;
+mod unknown;
 
-use diesel_async::pooled_connection::bb8::RunError;
+pub use accounts::AccountRequest;
+pub use disconnect_user::DisconnectUserRequest;
+pub use errors::{
+    CommandError,
+    ERR_ACCOUNT_EXISTS,
+    ERR_ACCOUNT_IN_USE,
+    ERR_ACCOUNT_NOT_FOUND,
+    ERR_BANNED,
+    ERR_CHECKSUM_MISMATCH,
+    ERR_DATABASE_BUSY,
+    ERR_FEATURE_DISABLED,
+    ERR_INSUFFICIENT_PRIVILEGES,
+    ERR_INTERNAL_SERVER,
+    ERR_INVALID_PAYLOAD,
+    ERR_LOGIN_LOCKED,
+    ERR_NOT_AUTHENTICATED,
+    ERR_RATE_LIMITED,
+    ERR_SERVER_BUSY,
+    ERR_USER_NOT_ONLINE,
+    FILE_ERR_DOWNLOAD_REFUSED,
+    FILE_ERR_FOLDER_NOT_EMPTY,
+    FILE_ERR_NAME_TAKEN,
+    FILE_ERR_NOT_FOUND,
+    FILE_ERR_PATH_UNSUPPORTED,
+    FILE_ERR_QUOTA_EXCEEDED,
+    FILE_ERR_READ_ONLY,
+    NEWS_ERR_ARTICLE_NOT_FOUND,
+    NEWS_ERR_NAME_TAKEN,
+    NEWS_ERR_PATH_NOT_FOUND,
+    NEWS_ERR_PATH_UNSUPPORTED,
+    pool_error_code,
+    transaction_error_code,
+};
 use parsing::parse_command;
+pub use search::{
+    SEARCH_RESULT_LIMIT,
+    SearchRequest,
+    SearchResultKind,
+    SearchScope,
+    encode_search_result,
+};
 pub use support::ProcessContext;
-pub(crate) use support::{
-    CommandContext,
-    UserInfoUpdate,
-    check_privilege_and_run,
-    privilege_error_reply,
+pub(crate) use support::{CommandContext, UserInfoUpdate, privilege_error_reply};
+pub use unknown::{
+    DEFAULT_UNKNOWN_TRANSACTION_LIMIT,
+    UNKNOWN_TRANSACTION_DISCONNECT_REASON,
+    UnknownPolicyError,
+    UnknownTransactionPolicy,
+    unknown_transaction_counts,
 };
-use thiserror::Error;
 
 use crate::{
-    db::DbPool,
-    handler::PrivilegeError,
+    db::with_query_trace,
+    file_handlers::{
+        DeleteFileRequest,
+        DownloadFileRequest,
+        FileInfoRequest,
+        MoveFileRequest,
+        SetFileInfoRequest,
+        UploadFileRequest,
+    },
     login::LoginRequest,
-    news_handlers::{self, ArticleDataRequest, PostArticleRequest},
-    server::outbound::OutboundError,
-    transaction::{FrameHeader, Transaction, TransactionError},
+    news_handlers::{
+        ArticleDataRequest,
+        DeleteArticleRequest,
+        NewsStructureRequest,
+        PostArticleRequest,
+    },
+    server::{
+        outbound::OutboundError,
+        transaction_span::{command_span, timed},
+    },
+    transaction::{FrameHeader, PayloadLimits, Transaction, TransactionError},
 };
 
-/// Error code used when authentication is required but not present.
-pub const ERR_NOT_AUTHENTICATED: u32 = 1;
-/// Error code used when a request includes an unexpected payload.
-pub const ERR_INVALID_PAYLOAD: u32 = 2;
-/// Error code used for unexpected server-side failures.
-pub const ERR_INTERNAL_SERVER: u32 = 3;
-/// Error code used when the user lacks the required privilege.
-pub const ERR_INSUFFICIENT_PRIVILEGES: u32 = 4;
-/// Error code used when the requested news path is unsupported.
-pub const NEWS_ERR_PATH_UNSUPPORTED: u32 = 5;
-/// Error code used when a news article cannot be found.
-pub const NEWS_ERR_ARTICLE_NOT_FOUND: u32 = 6;
-
-/// Errors that can occur while processing commands.
-#[derive(Debug, Error)]
-pub enum CommandError {
-    /// A database query failed.
-    #[error("database error: {0}")]
-    Database(#[from] diesel::result::Error),
-    /// Connection pool access failed.
-    #[error("pool error: {0}")]
-    Pool(#[from] RunError),
-    /// Transaction parsing or encoding failed.
-    #[error("transaction error: {0}")]
-    Transaction(#[from] TransactionError),
-    /// Privilege checks failed unexpectedly.
-    #[error("privilege error: {0}")]
-    Privilege(#[from] PrivilegeError),
-    /// Command processing invariants were violated.
-    #[error("invariant violation: {0}")]
-    Invariant(&'static str),
-    /// Outbound transport failed to deliver a reply.
-    #[error("outbound transport error: {0}")]
-    Outbound(#[from] OutboundError),
-}
-
 /// High-level command representation parsed from incoming transactions.
 ///
 /// Commands encapsulate the parameters and type information needed to
@@ -78,6 +109,11 @@ pub enum Command {
         /// Login request containing credentials and header.
         req: LoginRequest,
     },
+    /// End the login while keeping the connection open.
+    Logout {
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
     /// Request for the list of online users.
     GetUserNameList {
         /// Transaction frame header.
@@ -97,8 +133,129 @@ pub enum Command {
         /// Requested metadata changes.
         update: UserInfoUpdate,
     },
-    /// Request for the list of available files.
+    /// Acceptance of the server agreement, with the client's user details.
+    Agreed {
+        /// Transaction frame header.
+        header: FrameHeader,
+        /// Metadata sent alongside the acceptance.
+        update: UserInfoUpdate,
+    },
+    /// Request for the server banner image.
+    DownloadBanner {
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Request for the server rules and help sections (mxd extension).
+    GetServerRules {
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Client heartbeat, answered with an empty reply.
+    KeepAlive {
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Post a line to public chat.
+    SendChat {
+        /// Transaction frame header.
+        header: FrameHeader,
+        /// Chat text to deliver.
+        text: String,
+        /// Whether the line is an emote rather than speech.
+        emote: bool,
+    },
+    /// Announce a message to every connected client.
+    Broadcast {
+        /// Transaction frame header.
+        header: FrameHeader,
+        /// Announcement text.
+        text: String,
+    },
+    /// End another user's connection.
+    DisconnectUser {
+        /// Transaction frame header.
+        header: FrameHeader,
+        /// Target, notice text, and requested ban.
+        req: DisconnectUserRequest,
+    },
+    /// Request for an account's name, login, and access bitmap.
+    GetUser {
+        /// Transaction frame header.
+        header: FrameHeader,
+        /// Login name of the account to describe.
+        login: String,
+    },
+    /// Request for every account on the server.
+    GetAccounts {
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Create, delete, or modify a login account.
+    ManageAccount {
+        /// Transaction frame header.
+        header: FrameHeader,
+        /// Account administration request.
+        req: AccountRequest,
+    },
+    /// Send a private message to another online user.
+    SendInstantMsg {
+        /// Transaction frame header.
+        header: FrameHeader,
+        /// Recipient user id.
+        target_user_id: i32,
+        /// Message options (field 113).
+        options: u32,
+        /// Message text.
+        text: String,
+        /// Quoted text from an earlier message.
+        quoting: Option<String>,
+    },
+    /// Request for the contents of a folder in the file area.
     GetFileNameList {
+        /// Encoded folder path (field 202); `None` lists the root.
+        path: Option<Vec<u8>>,
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Request to download a file over the transfer port.
+    DownloadFile {
+        /// Target name and folder path.
+        req: DownloadFileRequest,
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Request to upload a file over the transfer port.
+    UploadFile {
+        /// New file's name, folder path, and size.
+        req: UploadFileRequest,
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Request to delete a file or empty folder.
+    DeleteFile {
+        /// Target name and folder path.
+        req: DeleteFileRequest,
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Request for a file or folder's metadata.
+    GetFileInfo {
+        /// Target name and folder path.
+        req: FileInfoRequest,
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Request to rename a file or folder or change its comment.
+    SetFileInfo {
+        /// Target, replacement name, and replacement comment.
+        req: SetFileInfoRequest,
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Request to move a file or folder to another folder.
+    MoveFile {
+        /// Target and destination folder path.
+        req: MoveFileRequest,
         /// Transaction frame header.
         header: FrameHeader,
     },
@@ -106,6 +263,9 @@ pub enum Command {
     GetNewsCategoryNameList {
         /// News hierarchy path (optional for root).
         path: Option<String>,
+        /// Whether to follow each entry with its latest post time (field
+        /// 172).
+        with_activity: bool,
         /// Transaction frame header.
         header: FrameHeader,
     },
@@ -118,10 +278,8 @@ pub enum Command {
     },
     /// Request for a specific news article's content.
     GetNewsArticleData {
-        /// News category path.
-        path: String,
-        /// Article identifier.
-        article_id: i32,
+        /// Article lookup request containing path, id, and transfer flag.
+        req: ArticleDataRequest,
         /// Transaction frame header.
         header: FrameHeader,
     },
@@ -132,6 +290,27 @@ pub enum Command {
         /// Transaction frame header.
         header: FrameHeader,
     },
+    /// Request to delete a news article.
+    DeleteNewsArticle {
+        /// Category path, article identifier, and recursion flag.
+        req: DeleteArticleRequest,
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Request to create or delete a news bundle or category.
+    ManageNewsStructure {
+        /// Operation, target path, and name.
+        req: NewsStructureRequest,
+        /// Transaction frame header.
+        header: FrameHeader,
+    },
+    /// Search file names and news articles.
+    Search {
+        /// Transaction frame header.
+        header: FrameHeader,
+        /// Search text and scope.
+        req: SearchRequest,
+    },
     /// Request contained a payload when none was expected. The server
     /// responds with [`crate::commands::ERR_INVALID_PAYLOAD`].
     InvalidPayload {
@@ -146,11 +325,17 @@ pub enum Command {
 }
 
 impl Command {
-    /// Convert a [`Transaction`] into a [`Command`].
+    /// Convert a [`Transaction`] into a [`Command`], refusing payloads that
+    /// exceed `limits`.
     ///
     /// # Errors
     /// Returns an error if required parameters are missing or cannot be parsed.
-    pub fn from_transaction(tx: Transaction) -> Result<Self, TransactionError> { parse_command(tx) }
+    pub fn from_transaction(
+        tx: Transaction,
+        limits: &PayloadLimits,
+    ) -> Result<Self, TransactionError> {
+        parse_command(tx, limits)
+    }
 
     /// Execute the command using the provided context.
     ///
@@ -164,6 +349,7 @@ impl Command {
             session,
             presence,
             presence_connection_id,
+            settings,
         } = context;
         let mut transport = crate::server::outbound::ReplyBuffer::new();
         let messaging = crate::server::outbound::NoopOutboundMessaging;
@@ -175,6 +361,7 @@ impl Command {
             messaging: &messaging,
             presence,
             presence_connection_id,
+            settings,
         })
         .await?;
         transport
@@ -184,6 +371,10 @@ impl Command {
 
     /// Execute the command using outbound transport and messaging adapters.
     ///
+    /// The command runs in a `command` span and under [`with_query_trace`],
+    /// so traced queries carry the transaction's trace identifier when the
+    /// server's settings enable SQL trace comments.
+    ///
     /// # Errors
     /// Returns an error if database access fails or the command cannot be
     /// handled.
@@ -191,82 +382,9 @@ impl Command {
         self,
         context: CommandContext<'_>,
     ) -> Result<(), CommandError> {
-        match self {
-            Self::Login { .. }
-            | Self::GetUserNameList { .. }
-            | Self::GetClientInfoText { .. }
-            | Self::SetClientUserInfo { .. } => self.process_presence_command(context).await,
-            command => {
-                let CommandContext {
-                    peer,
-                    pool,
-                    session,
-                    transport,
-                    ..
-                } = context;
-                let reply = command.execute(peer, pool, session).await?;
-                transport.send_reply(reply)?;
-                Ok(())
-            }
-        }
-    }
-
-    async fn process_presence_command(
-        self,
-        context: CommandContext<'_>,
-    ) -> Result<(), CommandError> {
-        match self {
-            Self::Login { req } => Self::process_login_with_presence(context, req).await,
-            Self::GetUserNameList { header } => Self::process_get_user_name_list(context, &header),
-            Self::GetClientInfoText {
-                header,
-                target_user_id,
-            } => Self::process_get_client_info_text(context, header, target_user_id).await,
-            Self::SetClientUserInfo { header, update } => {
-                Self::process_set_client_user_info(context, header, update).await
-            }
-            _ => Err(CommandError::Invariant(
-                "non-presence command passed to presence dispatcher",
-            )),
-        }
-    }
-
-    async fn execute(
-        self,
-        peer: SocketAddr,
-        pool: DbPool,
-        session: &mut crate::handler::Session,
-    ) -> Result<Transaction, CommandError> {
-        match self {
-            Self::Login { req } => Self::process_login(peer, pool, session, req).await,
-            Self::GetFileNameList { header } => {
-                Self::process_get_file_name_list(pool, session, header).await
-            }
-            Self::GetNewsCategoryNameList { header, path } => {
-                news_handlers::process_category_name_list(pool, session, header, path).await
-            }
-            Self::GetNewsArticleNameList { header, path } => {
-                news_handlers::process_article_name_list(pool, session, header, path).await
-            }
-            Self::GetNewsArticleData {
-                header,
-                path,
-                article_id,
-            } => {
-                let req = ArticleDataRequest { path, article_id };
-                news_handlers::process_article_data(pool, session, header, req).await
-            }
-            Self::PostNewsArticle { header, req } => {
-                news_handlers::process_post_article(pool, session, header, req).await
-            }
-            Self::GetUserNameList { .. }
-            | Self::GetClientInfoText { .. }
-            | Self::SetClientUserInfo { .. } => Err(CommandError::Invariant(
-                "presence command should be handled before execute",
-            )),
-            Self::InvalidPayload { header } => Ok(Self::process_invalid_payload(header)),
-            Self::Unknown { header } => Ok(Self::process_unknown(peer, header)),
-        }
+        let span = command_span(context.peer, self.checked_header());
+        let trace_sql = context.settings.sql_trace_comments;
+        timed(span, with_query_trace(trace_sql, self.dispatch(context))).await
     }
 }
 
@@ -0,0 +1,85 @@
+//! Parsing of file area requests.
+
+use super::first_param_bytes;
+use crate::{
+    commands::Command,
+    field_id::FieldId,
+    file_handlers::{
+        DeleteFileRequest,
+        DownloadFileRequest,
+        FileInfoRequest,
+        MoveFileRequest,
+        SetFileInfoRequest,
+        UploadFileRequest,
+    },
+    transaction::{FrameHeader, TransactionError, TransactionParams, decode_params_map_borrowed},
+};
+
+pub(super) fn parse_get_file_name_list_params(payload: &[u8], header: FrameHeader) -> Command {
+    // SynHX sends a bare `DATA_DIR` block rather than a parameter list for
+    // `/ls`, so a payload that does not decode as parameters lists the root.
+    let path = decode_params_map_borrowed(payload)
+        .ok()
+        .and_then(|params| first_param_bytes(&params, FieldId::FilePath));
+    Command::GetFileNameList { path, header }
+}
+
+pub(super) fn parse_download_file_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    Ok(Command::DownloadFile {
+        req: DownloadFileRequest::from_payload(payload)?,
+        header,
+    })
+}
+
+pub(super) fn parse_upload_file_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    Ok(Command::UploadFile {
+        req: UploadFileRequest::from_payload(payload)?,
+        header,
+    })
+}
+
+pub(super) fn parse_delete_file_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    Ok(Command::DeleteFile {
+        req: DeleteFileRequest::from_payload(payload)?,
+        header,
+    })
+}
+
+pub(super) fn parse_move_file_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    Ok(Command::MoveFile {
+        req: MoveFileRequest::from_payload(payload)?,
+        header,
+    })
+}
+
+pub(super) fn parse_get_file_info_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    Ok(Command::GetFileInfo {
+        req: FileInfoRequest::from_payload(payload)?,
+        header,
+    })
+}
+
+pub(super) fn parse_set_file_info_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    Ok(Command::SetFileInfo {
+        req: SetFileInfoRequest::from_payload(payload)?,
+        header,
+    })
+}
@@ -0,0 +1,468 @@
+//! Transaction-to-command parsing helpers.
+//!
+//! Every parser reads its parameters through a struct deriving
+//! [`TransactionParams`]. Rules that span fields, such as login's
+//! password-or-token check, and conversions to narrower types are applied to
+//! the struct afterwards.
+
+mod files;
+
+use std::collections::HashMap;
+
+use self::files::{
+    parse_delete_file_params,
+    parse_download_file_params,
+    parse_get_file_info_params,
+    parse_get_file_name_list_params,
+    parse_move_file_params,
+    parse_set_file_info_params,
+    parse_upload_file_params,
+};
+use super::{
+    Command,
+    DisconnectUserRequest,
+    UserInfoUpdate,
+    account_info::parse_get_user_params,
+    accounts::parse_account_params,
+    search::parse_search_params,
+};
+use crate::{
+    connection_flags::ConnectionFlags,
+    field_id::FieldId,
+    login::LoginRequest,
+    news_handlers::{
+        ArticleDataRequest,
+        DeleteArticleRequest,
+        NewsStructureRequest,
+        PostArticleRequest,
+    },
+    server::{bans::BanLength, chat::CHAT_OPTION_EMOTE, instant_msg::MSG_OPTION_USER},
+    transaction::{FrameHeader, PayloadLimits, Transaction, TransactionError, TransactionParams},
+    transaction_type::TransactionType,
+};
+
+/// Parsed login credentials extracted from transaction parameters.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct LoginCredentials {
+    /// Username for authentication.
+    pub(super) username: String,
+    /// Password for authentication; empty when only a resume token was sent.
+    pub(super) password: String,
+    /// Session token presented to resume an earlier login.
+    pub(super) resume_token: Option<String>,
+    /// News root the client selected.
+    pub(super) news_root: Option<i32>,
+}
+
+/// Parameters of Login (107) as sent.
+#[derive(TransactionParams)]
+struct LoginParams {
+    #[param(Login)]
+    username: String,
+    #[param(SessionToken)]
+    resume_token: Option<String>,
+    #[param(Password)]
+    password: Option<String>,
+    #[param(NewsRoot)]
+    news_root: Option<i32>,
+}
+
+/// Extract username, password, and any session token or news root from
+/// login payload parameters.
+///
+/// The password may be left out when a session token is present.
+pub(super) fn parse_login_params(payload: &[u8]) -> Result<LoginCredentials, TransactionError> {
+    let LoginParams {
+        username,
+        resume_token,
+        password,
+        news_root,
+    } = LoginParams::from_payload(payload)?;
+    let checked_password = match password {
+        Some(sent) => sent,
+        None if resume_token.is_some() => String::new(),
+        None => return Err(TransactionError::MissingField(FieldId::Password)),
+    };
+    Ok(LoginCredentials {
+        username,
+        password: checked_password,
+        resume_token,
+        news_root,
+    })
+}
+
+/// Convert a parsed transaction into a high-level command.
+///
+/// Payloads larger than the limit `limits` sets for their type are refused
+/// with [`TransactionError::PayloadTooLarge`], and those declaring more
+/// parameters than it allows with [`TransactionError::TooManyParams`],
+/// whichever path built them.
+pub(super) fn parse_command(
+    tx: Transaction,
+    limits: &PayloadLimits,
+) -> Result<Command, TransactionError> {
+    let ty = TransactionType::from(tx.header.ty);
+    if tx.payload.len() > limits.limit(ty) {
+        return Err(TransactionError::PayloadTooLarge);
+    }
+    limits.check_params(&tx.payload)?;
+    if ty.rejects_payload(tx.payload.is_empty()) {
+        return Ok(Command::InvalidPayload { header: tx.header });
+    }
+    match ty {
+        TransactionType::Login => {
+            let creds = parse_login_params(&tx.payload)?;
+            Ok(Command::Login {
+                req: LoginRequest {
+                    username: creds.username,
+                    password: creds.password,
+                    resume_token: creds.resume_token,
+                    news_root: creds.news_root,
+                    header: tx.header,
+                },
+            })
+        }
+        TransactionType::Logout => Ok(Command::Logout { header: tx.header }),
+        TransactionType::GetUserNameList => Ok(Command::GetUserNameList { header: tx.header }),
+        TransactionType::GetClientInfoText => {
+            parse_get_client_info_text_params(&tx.payload, tx.header)
+        }
+        TransactionType::SetClientUserInfo => Ok(Command::SetClientUserInfo {
+            update: parse_user_info_update(&tx.payload)?,
+            header: tx.header,
+        }),
+        TransactionType::Agreed => Ok(Command::Agreed {
+            update: parse_user_info_update(&tx.payload)?,
+            header: tx.header,
+        }),
+        TransactionType::DownloadBanner => Ok(Command::DownloadBanner { header: tx.header }),
+        TransactionType::GetServerRules => Ok(Command::GetServerRules { header: tx.header }),
+        TransactionType::KeepAlive => Ok(Command::KeepAlive { header: tx.header }),
+        TransactionType::SendChat => parse_send_chat_params(&tx.payload, tx.header),
+        TransactionType::SendInstantMsg => parse_send_instant_msg_params(&tx.payload, tx.header),
+        TransactionType::UserBroadcast => parse_broadcast_params(&tx.payload, tx.header),
+        TransactionType::DisconnectUser => parse_disconnect_user_params(&tx.payload, tx.header),
+        TransactionType::GetUser => parse_get_user_params(&tx.payload, tx.header),
+        TransactionType::GetAccounts => Ok(Command::GetAccounts { header: tx.header }),
+        TransactionType::NewUser | TransactionType::DeleteUser | TransactionType::SetUser => {
+            parse_account_params(ty, &tx.payload, tx.header)
+        }
+        TransactionType::GetFileNameList => {
+            Ok(parse_get_file_name_list_params(&tx.payload, tx.header))
+        }
+        TransactionType::DownloadFile => parse_download_file_params(&tx.payload, tx.header),
+        TransactionType::UploadFile => parse_upload_file_params(&tx.payload, tx.header),
+        TransactionType::DeleteFile => parse_delete_file_params(&tx.payload, tx.header),
+        TransactionType::GetFileInfo => parse_get_file_info_params(&tx.payload, tx.header),
+        TransactionType::SetFileInfo => parse_set_file_info_params(&tx.payload, tx.header),
+        TransactionType::MoveFile => parse_move_file_params(&tx.payload, tx.header),
+        TransactionType::NewsCategoryNameList => {
+            parse_news_category_name_list_params(&tx.payload, tx.header)
+        }
+        TransactionType::NewsArticleNameList => {
+            parse_news_article_name_list_params(&tx.payload, tx.header)
+        }
+        TransactionType::NewsArticleData => parse_news_article_data_params(&tx.payload, tx.header),
+        TransactionType::PostNewsArticle => parse_post_news_article_params(&tx.payload, tx.header),
+        TransactionType::DeleteNewsArticle => {
+            parse_delete_news_article_params(&tx.payload, tx.header)
+        }
+        TransactionType::DeleteNewsItem
+        | TransactionType::NewNewsFolder
+        | TransactionType::NewNewsCategory => {
+            parse_news_structure_params(ty, &tx.payload, tx.header)
+        }
+        TransactionType::Search => parse_search_params(&tx.payload, tx.header),
+        _ => Ok(Command::Unknown { header: tx.header }),
+    }
+}
+
+/// Return the raw bytes of the first `field` parameter, if present.
+pub(super) fn first_param_bytes<V: AsRef<[u8]>>(
+    params: &HashMap<FieldId, Vec<V>>,
+    field: FieldId,
+) -> Option<Vec<u8>> {
+    params
+        .get(&field)
+        .and_then(|values| values.first())
+        .map(|value| value.as_ref().to_vec())
+}
+
+/// A category listing request: a news path that may be left out to mean the
+/// root, and whether the client asked for post times.
+#[derive(TransactionParams)]
+struct CategoryListParams {
+    #[param(NewsPath)]
+    path: Option<String>,
+    #[param(NewsActivity)]
+    with_activity: bool,
+}
+
+/// A news path that must be sent.
+#[derive(TransactionParams)]
+struct RequiredNewsPath {
+    #[param(NewsPath)]
+    path: String,
+}
+
+fn parse_news_category_name_list_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let CategoryListParams {
+        path,
+        with_activity,
+    } = CategoryListParams::from_payload(payload)?;
+    Ok(Command::GetNewsCategoryNameList {
+        path,
+        with_activity,
+        header,
+    })
+}
+
+fn parse_news_article_name_list_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let RequiredNewsPath { path } = RequiredNewsPath::from_payload(payload)?;
+    Ok(Command::GetNewsArticleNameList { path, header })
+}
+
+fn parse_news_article_data_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    Ok(Command::GetNewsArticleData {
+        req: ArticleDataRequest::from_payload(payload)?,
+        header,
+    })
+}
+
+/// The user a Get Client Info Text (303) request asks about.
+#[derive(TransactionParams)]
+struct TargetUser {
+    #[param(UserId)]
+    user_id: u32,
+}
+
+/// Convert a user id as sent into the signed id sessions use.
+fn target_user_id(user_id: u32) -> Result<i32, TransactionError> {
+    i32::try_from(user_id).map_err(|_| TransactionError::InvalidParamValue(FieldId::UserId))
+}
+
+fn parse_get_client_info_text_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let TargetUser { user_id } = TargetUser::from_payload(payload)?;
+    Ok(Command::GetClientInfoText {
+        header,
+        target_user_id: target_user_id(user_id)?,
+    })
+}
+
+/// Parameters of Agreed (121) and Set Client User Info (304) as sent.
+#[derive(TransactionParams)]
+struct UserInfoParams {
+    #[param(Name)]
+    name: Option<String>,
+    #[param(IconId)]
+    icon_id: Option<u32>,
+    #[param(Options)]
+    options: Option<u32>,
+    #[param(AutoResponse)]
+    auto_response: Option<String>,
+}
+
+/// Read the user details carried by Agreed (121) and Set Client User Info
+/// (304).
+fn parse_user_info_update(payload: &[u8]) -> Result<UserInfoUpdate, TransactionError> {
+    let UserInfoParams {
+        name,
+        icon_id,
+        options,
+        auto_response,
+    } = UserInfoParams::from_payload(payload)?;
+    Ok(UserInfoUpdate {
+        display_name: name.as_deref().and_then(normalise_nickname),
+        icon_id: icon_id
+            .map(u16::try_from)
+            .transpose()
+            .map_err(|_| TransactionError::InvalidParamValue(FieldId::IconId))?,
+        options: options
+            .map(u8::try_from)
+            .transpose()
+            .map_err(|_| TransactionError::InvalidParamValue(FieldId::Options))?
+            .map(ConnectionFlags::from_bits_truncate),
+        auto_response,
+    })
+}
+
+/// Strip control characters and surrounding whitespace from a requested
+/// nickname, returning `None` when nothing is left.
+///
+/// Clients send a blank name when only the icon or options change, and a
+/// nickname containing line breaks could forge lines in other users' chat.
+fn normalise_nickname(raw: &str) -> Option<String> {
+    let cleaned: String = raw.chars().filter(|c| !c.is_control()).collect();
+    let trimmed = cleaned.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_owned())
+}
+
+/// Parameters of Send Chat (105).
+#[derive(TransactionParams)]
+struct ChatParams {
+    #[param(Data)]
+    text: String,
+    #[param(ChatOptions)]
+    options: Option<u32>,
+}
+
+fn parse_send_chat_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let ChatParams { text, options } = ChatParams::from_payload(payload)?;
+    Ok(Command::SendChat {
+        header,
+        text,
+        emote: options == Some(CHAT_OPTION_EMOTE),
+    })
+}
+
+/// Parameters of User Broadcast (355).
+#[derive(TransactionParams)]
+struct BroadcastParams {
+    #[param(Data)]
+    text: String,
+}
+
+fn parse_broadcast_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let BroadcastParams { text } = BroadcastParams::from_payload(payload)?;
+    Ok(Command::Broadcast { header, text })
+}
+
+/// Parameters of Disconnect User (110) as sent.
+#[derive(TransactionParams)]
+struct DisconnectParams {
+    #[param(UserId)]
+    user_id: u32,
+    #[param(Data)]
+    reason: Option<String>,
+    #[param(Options)]
+    options: Option<u32>,
+}
+
+fn parse_disconnect_user_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let DisconnectParams {
+        user_id,
+        reason,
+        options,
+    } = DisconnectParams::from_payload(payload)?;
+    Ok(Command::DisconnectUser {
+        header,
+        req: DisconnectUserRequest {
+            target_user_id: target_user_id(user_id)?,
+            reason,
+            ban: options.and_then(BanLength::from_options),
+        },
+    })
+}
+
+/// Parameters of Send Instant Message (108) as sent.
+#[derive(TransactionParams)]
+struct InstantMsgParams {
+    #[param(UserId)]
+    user_id: u32,
+    #[param(Options)]
+    options: Option<u32>,
+    #[param(Data, default)]
+    text: String,
+    #[param(QuotingMsg)]
+    quoting: Option<String>,
+}
+
+fn parse_send_instant_msg_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let InstantMsgParams {
+        user_id,
+        options,
+        text,
+        quoting,
+    } = InstantMsgParams::from_payload(payload)?;
+    Ok(Command::SendInstantMsg {
+        header,
+        target_user_id: target_user_id(user_id)?,
+        options: options.unwrap_or(MSG_OPTION_USER),
+        text,
+        quoting,
+    })
+}
+
+fn parse_post_news_article_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    Ok(Command::PostNewsArticle {
+        req: PostArticleRequest::from_payload(payload)?,
+        header,
+    })
+}
+
+fn parse_delete_news_article_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    Ok(Command::DeleteNewsArticle {
+        req: DeleteArticleRequest::from_payload(payload)?,
+        header,
+    })
+}
+
+/// Parameters of New News Folder (381).
+#[derive(TransactionParams)]
+struct NewFolderParams {
+    #[param(NewsPath)]
+    parent: Option<String>,
+    #[param(FileItemName)]
+    name: String,
+}
+
+/// Parameters of New News Category (382).
+#[derive(TransactionParams)]
+struct NewCategoryParams {
+    #[param(NewsPath)]
+    parent: Option<String>,
+    #[param(NewsCategoryName)]
+    name: String,
+}
+
+fn parse_news_structure_params(
+    ty: TransactionType,
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let req = match ty {
+        TransactionType::NewNewsFolder => {
+            let NewFolderParams { parent, name } = NewFolderParams::from_payload(payload)?;
+            NewsStructureRequest::NewFolder { parent, name }
+        }
+        TransactionType::NewNewsCategory => {
+            let NewCategoryParams { parent, name } = NewCategoryParams::from_payload(payload)?;
+            NewsStructureRequest::NewCategory { parent, name }
+        }
+        _ => {
+            let RequiredNewsPath { path } = RequiredNewsPath::from_payload(payload)?;
+            NewsStructureRequest::DeleteItem { path }
+        }
+    };
+    Ok(Command::ManageNewsStructure { req, header })
+}
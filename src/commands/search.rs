@@ -0,0 +1,272 @@
+//! Search (mxd extension): find files and news articles in one request.
+//!
+//! The request carries the search text in field 101 and, optionally, a scope
+//! in field 113: bit 0 searches the file area and bit 1 searches news, with
+//! no bits meaning both. The reply holds one field 165 per hit, files first,
+//! each encoded as a big-endian `u16` kind, a `u32` article id (zero for
+//! files), then the location and name as `u16`-length-prefixed strings. The
+//! location is the slash-separated folder or category path.
+//!
+//! Files need Download File and news needs Read Article; parts the session
+//! cannot read are left out, and a request for nothing it may read is
+//! refused. Parts whose subsystem the server has switched off are left out
+//! too. File hits respect the same grants as folder listings, so a drop box's
+//! contents are found only by sessions holding View Drop Boxes.
+
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use bitflags::bitflags;
+
+use super::{Command, CommandError, ERR_FEATURE_DISABLED, privilege_error_reply};
+use crate::{
+    db::{DbPool, FileSearcher, acquire, search_articles, search_file_nodes},
+    field_id::FieldId,
+    handler::{PrivilegeError, Session},
+    header_util::reply_header,
+    privileges::Privileges,
+    server::subsystems::{Subsystem, Subsystems},
+    transaction::{FrameHeader, Transaction, TransactionError, TransactionParams, encode_params},
+};
+
+/// Most hits returned from each part of a search.
+pub const SEARCH_RESULT_LIMIT: usize = 100;
+
+bitflags! {
+    /// Parts of the server a search covers (field 113).
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct SearchScope: u32 {
+        /// File and folder names.
+        const FILES = 1;
+        /// News article titles and bodies.
+        const NEWS = 1 << 1;
+    }
+}
+
+/// What a search hit names (the first element of field 165).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u16)]
+pub enum SearchResultKind {
+    /// A file.
+    File = 1,
+    /// A folder.
+    Folder = 2,
+    /// A news article.
+    Article = 3,
+}
+
+/// Parameters for a search.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SearchRequest {
+    /// Text to look for.
+    pub query: String,
+    /// Parts of the server to search.
+    pub scope: SearchScope,
+}
+
+/// Parameters of Search as sent.
+#[derive(TransactionParams)]
+struct SearchParams {
+    #[param(Data)]
+    text: String,
+    #[param(Options, omit_blank)]
+    scope: Option<u32>,
+}
+
+/// Parse a Search payload: text in field 101 and an optional scope in field
+/// 113.
+pub(super) fn parse_search_params(
+    payload: &[u8],
+    header: FrameHeader,
+) -> Result<Command, TransactionError> {
+    let SearchParams { text, scope: bits } = SearchParams::from_payload(payload)?;
+    let query = text.trim().to_owned();
+    if query.is_empty() {
+        return Err(TransactionError::InvalidParamValue(FieldId::Data));
+    }
+    let scope = match bits {
+        None => SearchScope::all(),
+        Some(requested) => SearchScope::from_bits(requested)
+            .ok_or(TransactionError::InvalidParamValue(FieldId::Options))?,
+    };
+    Ok(Command::Search {
+        header,
+        req: SearchRequest { query, scope },
+    })
+}
+
+/// Encode one search hit as a field 165 value.
+///
+/// # Errors
+///
+/// Returns [`TransactionError::InvalidParamValue`] if the location or name
+/// is longer than 65535 bytes.
+pub fn encode_search_result(
+    kind: SearchResultKind,
+    article_id: u32,
+    location: &str,
+    name: &str,
+) -> Result<Vec<u8>, TransactionError> {
+    let mut entry =
+        Vec::with_capacity(location.len().saturating_add(name.len()).saturating_add(10));
+    entry.extend_from_slice(&(kind as u16).to_be_bytes());
+    entry.extend_from_slice(&article_id.to_be_bytes());
+    for text in [location, name] {
+        let len = u16::try_from(text.len())
+            .map_err(|_| TransactionError::InvalidParamValue(FieldId::SearchResult))?;
+        entry.extend_from_slice(&len.to_be_bytes());
+        entry.extend_from_slice(text.as_bytes());
+    }
+    Ok(entry)
+}
+
+/// Parts of the server whose subsystem is switched on in `subsystems`.
+pub(super) fn enabled_scope(subsystems: Subsystems) -> SearchScope {
+    let mut scope = SearchScope::empty();
+    scope.set(SearchScope::FILES, subsystems.is_enabled(Subsystem::Files));
+    scope.set(SearchScope::NEWS, subsystems.is_enabled(Subsystem::News));
+    scope
+}
+
+impl Command {
+    pub(super) async fn process_search(
+        pool: &DbPool,
+        session: &Session,
+        header: &FrameHeader,
+        req: &SearchRequest,
+    ) -> Result<Transaction, CommandError> {
+        let scope = req.scope;
+        if scope.is_empty() {
+            return Ok(Transaction {
+                header: reply_header(header, ERR_FEATURE_DISABLED, 0),
+                payload: Vec::new(),
+            });
+        }
+        let files =
+            scope.contains(SearchScope::FILES) && session.has_privilege(Privileges::DOWNLOAD_FILE);
+        let news = scope.contains(SearchScope::NEWS)
+            && session.has_privilege(Privileges::NEWS_READ_ARTICLE);
+        if !files && !news {
+            let needed = if scope.contains(SearchScope::FILES) {
+                Privileges::DOWNLOAD_FILE
+            } else {
+                Privileges::NEWS_READ_ARTICLE
+            };
+            return Ok(privilege_error_reply(
+                header,
+                PrivilegeError::InsufficientPrivileges(needed),
+            ));
+        }
+        let user_id = session.user_id.ok_or(CommandError::Invariant(
+            "authenticated session missing user id",
+        ))?;
+        let mut conn = acquire(pool, header.ty).await?;
+        let mut entries = Vec::new();
+        if files {
+            let searcher = FileSearcher {
+                user_id,
+                view_drop_boxes: session.has_privilege(Privileges::VIEW_DROP_BOXES),
+            };
+            for hit in
+                search_file_nodes(&mut conn, searcher, &req.query, SEARCH_RESULT_LIMIT).await?
+            {
+                let kind = if hit.is_folder {
+                    SearchResultKind::Folder
+                } else {
+                    SearchResultKind::File
+                };
+                let entry = encode_search_result(kind, 0, &hit.folder, &hit.name)?;
+                entries.push((FieldId::SearchResult, entry));
+            }
+        }
+        if news {
+            for hit in search_articles(
+                &mut conn,
+                session.news_root,
+                &req.query,
+                SEARCH_RESULT_LIMIT,
+            )
+            .await?
+            {
+                let article_id = u32::try_from(hit.article_id).unwrap_or_default();
+                let entry = encode_search_result(
+                    SearchResultKind::Article,
+                    article_id,
+                    &hit.category,
+                    &hit.title,
+                )?;
+                entries.push((FieldId::SearchResult, entry));
+            }
+        }
+        let payload = encode_params(&entries)?;
+        Ok(Transaction {
+            header: reply_header(header, 0, payload.len()),
+            payload,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for search request parsing and result encoding.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn header() -> FrameHeader {
+        FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: crate::transaction_type::SEARCH_ID,
+            id: 1,
+            error: 0,
+            total_size: 0,
+            data_size: 0,
+        }
+    }
+
+    #[rstest]
+    #[case::both_by_default(None, Some(SearchScope::all()))]
+    #[case::zero_means_both(Some(0), Some(SearchScope::all()))]
+    #[case::files_only(Some(1), Some(SearchScope::FILES))]
+    #[case::news_only(Some(2), Some(SearchScope::NEWS))]
+    #[case::unknown_bits(Some(4), None)]
+    fn parses_search_scope(#[case] options: Option<u32>, #[case] expected: Option<SearchScope>) {
+        let mut params = vec![(FieldId::Data, b" guide ".to_vec())];
+        if let Some(bits) = options {
+            params.push((FieldId::Options, bits.to_be_bytes().to_vec()));
+        }
+        let payload = encode_params(&params).expect("encode params");
+
+        let scope = match parse_search_params(&payload, header()) {
+            Ok(Command::Search { req, .. }) => {
+                assert_eq!(req.query, "guide");
+                Some(req.scope)
+            }
+            _ => None,
+        };
+
+        assert_eq!(scope, expected);
+    }
+
+    #[rstest]
+    fn refuses_blank_search_text() {
+        let payload = encode_params(&[(FieldId::Data, b"  ".to_vec())]).expect("encode params");
+        assert!(matches!(
+            parse_search_params(&payload, header()),
+            Err(TransactionError::InvalidParamValue(FieldId::Data))
+        ));
+    }
+
+    #[rstest]
+    fn encodes_typed_result_entries() {
+        let entry = encode_search_result(SearchResultKind::Article, 7, "General/Chat", "Hello")
+            .expect("encode entry");
+
+        let mut expected = vec![0, 3, 0, 0, 0, 7, 0, 12];
+        expected.extend_from_slice(b"General/Chat");
+        expected.extend_from_slice(&[0, 5]);
+        expected.extend_from_slice(b"Hello");
+        assert_eq!(entry, expected);
+    }
+}
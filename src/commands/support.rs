@@ -1,16 +1,16 @@
 //! Shared command support types and privilege helpers.
 
-use std::future::Future;
-
-use super::{CommandError, ERR_INSUFFICIENT_PRIVILEGES, ERR_NOT_AUTHENTICATED};
+use super::{ERR_INSUFFICIENT_PRIVILEGES, ERR_NOT_AUTHENTICATED};
 use crate::{
     connection_flags::ConnectionFlags,
     db::DbPool,
     handler::PrivilegeError,
     header_util::reply_header,
     presence::PresenceRegistry,
-    privileges::Privileges,
-    server::outbound::{OutboundConnectionId, OutboundMessaging, OutboundTransport},
+    server::{
+        outbound::{OutboundConnectionId, OutboundMessaging, OutboundTransport},
+        settings::ServerSettings,
+    },
     transaction::{FrameHeader, Transaction},
 };
 
@@ -30,6 +30,8 @@ pub(crate) struct CommandContext<'a> {
     pub presence: &'a PresenceRegistry,
     /// Adapter-owned connection identifier for presence snapshots.
     pub presence_connection_id: Option<OutboundConnectionId>,
+    /// Settings of the server that accepted the connection.
+    pub settings: &'a ServerSettings,
 }
 
 /// Execution context for command processing without external outbound adapters.
@@ -44,6 +46,8 @@ pub struct ProcessContext<'a> {
     pub presence: &'a PresenceRegistry,
     /// Adapter-owned connection identifier for presence snapshots.
     pub presence_connection_id: Option<OutboundConnectionId>,
+    /// Settings of the server that accepted the connection.
+    pub settings: &'a ServerSettings,
 }
 
 /// User-visible metadata updates accepted by `121` and `304`.
@@ -70,20 +74,3 @@ pub(crate) fn privilege_error_reply(header: &FrameHeader, err: PrivilegeError) -
         payload: Vec::new(),
     }
 }
-
-/// Check privileges and run a handler, mapping failures to error replies.
-pub(crate) async fn check_privilege_and_run<F, Fut>(
-    session: &crate::handler::Session,
-    header: &FrameHeader,
-    privilege: Privileges,
-    handler: F,
-) -> Result<Transaction, CommandError>
-where
-    F: FnOnce() -> Fut,
-    Fut: Future<Output = Result<Transaction, CommandError>>,
-{
-    if let Err(error) = session.require_privilege(privilege) {
-        return Ok(privilege_error_reply(header, error));
-    }
-    handler().await
-}
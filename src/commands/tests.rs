@@ -6,7 +6,11 @@ use super::{
     parsing::{LoginCredentials, parse_login_params},
     *,
 };
-use crate::{field_id::FieldId, transaction::encode_params, transaction_type::TransactionType};
+use crate::{
+    field_id::FieldId,
+    transaction::{PayloadLimits, encode_params},
+    transaction_type::TransactionType,
+};
 
 /// Returns valid login parameters for testing.
 fn valid_login_payload() -> Vec<u8> {
@@ -98,6 +102,33 @@ fn parse_login_params_ignores_extra_fields() {
     assert_valid_credentials(&result);
 }
 
+#[test]
+fn parse_login_params_accepts_a_token_instead_of_a_password() {
+    let params: Vec<(FieldId, &[u8])> = vec![
+        (FieldId::Login, b"alice"),
+        (FieldId::SessionToken, b"0123abcd"),
+    ];
+    let payload = encode_params(&params).expect("payload encodes");
+    let result = parse_login_params(&payload).expect("should parse");
+    assert_eq!(result.username, "alice");
+    assert_eq!(result.password, "");
+    assert_eq!(result.resume_token.as_deref(), Some("0123abcd"));
+}
+
+#[expect(clippy::big_endian_bytes, reason = "network protocol")]
+#[test]
+fn parse_login_params_reads_the_selected_news_root() {
+    let mut params: Vec<(FieldId, Vec<u8>)> = vec![
+        (FieldId::Login, b"alice".to_vec()),
+        (FieldId::Password, b"secret".to_vec()),
+    ];
+    params.push((FieldId::NewsRoot, 2i32.to_be_bytes().to_vec()));
+    let payload = encode_params(&params).expect("payload encodes");
+    let result = parse_login_params(&payload).expect("should parse");
+    assert_valid_credentials(&result);
+    assert_eq!(result.news_root, Some(2));
+}
+
 #[test]
 fn parse_login_params_rejects_malformed_payload() {
     // Payload too short to contain the parameter count (needs at least 2 bytes)
@@ -121,7 +152,339 @@ fn get_file_name_list_accepts_client_directory_payload() {
         payload: vec![0xca, 0x00, 0x02, 0x00, 0x01],
     };
 
-    let command = Command::from_transaction(transaction).expect("command should parse");
+    let command = Command::from_transaction(transaction, &PayloadLimits::new())
+        .expect("command should parse");
+
+    assert!(matches!(
+        command,
+        Command::GetFileNameList { path: None, .. }
+    ));
+}
+
+#[test]
+fn payloads_over_the_type_limit_are_refused() {
+    let ty = TransactionType::SendChat;
+    let limits = PayloadLimits::new().with_limit(ty, 4);
+    let transaction = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: ty.into(),
+            id: 9,
+            error: 0,
+            total_size: 5,
+            data_size: 5,
+        },
+        payload: vec![0; 5],
+    };
+
+    assert!(matches!(
+        Command::from_transaction(transaction, &limits),
+        Err(TransactionError::PayloadTooLarge)
+    ));
+}
+
+#[test]
+fn get_file_name_list_reads_folder_path() {
+    let folder = crate::file_handlers::encode_file_path(&["Docs"]).expect("path encodes");
+    let payload =
+        encode_params(&[(FieldId::FilePath, folder.as_slice())]).expect("payload encodes");
+    let transaction = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::GetFileNameList.into(),
+            id: 8,
+            error: 0,
+            total_size: u32::try_from(payload.len()).expect("payload fits"),
+            data_size: u32::try_from(payload.len()).expect("payload fits"),
+        },
+        payload,
+    };
+
+    let command = Command::from_transaction(transaction, &PayloadLimits::new())
+        .expect("command should parse");
+
+    assert!(matches!(
+        command,
+        Command::GetFileNameList { path: Some(ref bytes), .. } if *bytes == folder
+    ));
+}
+
+#[expect(clippy::big_endian_bytes, reason = "network protocol")]
+#[rstest]
+#[case::speech(None, false)]
+#[case::emote(Some(1u16), true)]
+#[case::other_option(Some(2u16), false)]
+fn send_chat_parses_text_and_emote_option(#[case] options: Option<u16>, #[case] emote: bool) {
+    let option_bytes = options.map(u16::to_be_bytes);
+    let mut params: Vec<(FieldId, &[u8])> = vec![(FieldId::Data, b"hello")];
+    if let Some(bytes) = option_bytes.as_ref() {
+        params.push((FieldId::ChatOptions, bytes.as_slice()));
+    }
+    let payload = encode_params(&params).expect("payload encodes");
+    let transaction = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::SendChat.into(),
+            id: 8,
+            error: 0,
+            total_size: u32::try_from(payload.len()).expect("payload fits"),
+            data_size: u32::try_from(payload.len()).expect("payload fits"),
+        },
+        payload,
+    };
+
+    let command = Command::from_transaction(transaction, &PayloadLimits::new())
+        .expect("command should parse");
+
+    assert!(matches!(
+        command,
+        Command::SendChat { text, emote: parsed, .. } if text == "hello" && parsed == emote
+    ));
+}
+
+#[expect(clippy::big_endian_bytes, reason = "network protocol")]
+#[rstest]
+fn send_instant_msg_parses_target_and_defaults_options() {
+    let target = 7u16.to_be_bytes();
+    let params: Vec<(FieldId, &[u8])> = vec![
+        (FieldId::UserId, target.as_slice()),
+        (FieldId::Data, b"psst"),
+    ];
+    let payload = encode_params(&params).expect("payload encodes");
+    let transaction = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::SendInstantMsg.into(),
+            id: 9,
+            error: 0,
+            total_size: u32::try_from(payload.len()).expect("payload fits"),
+            data_size: u32::try_from(payload.len()).expect("payload fits"),
+        },
+        payload,
+    };
+
+    let command = Command::from_transaction(transaction, &PayloadLimits::new())
+        .expect("command should parse");
+
+    assert!(matches!(
+        command,
+        Command::SendInstantMsg { target_user_id: 7, options: 1, text, quoting: None, .. }
+            if text == "psst"
+    ));
+}
+
+#[rstest]
+fn set_file_info_treats_blank_new_name_as_unchanged() {
+    let params: Vec<(FieldId, &[u8])> = vec![
+        (FieldId::FileItemName, b"readme.txt"),
+        (FieldId::FileNewName, b""),
+        (FieldId::FileComment, b"Start here"),
+    ];
+    let payload = encode_params(&params).expect("payload encodes");
+    let transaction = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::SetFileInfo.into(),
+            id: 10,
+            error: 0,
+            total_size: u32::try_from(payload.len()).expect("payload fits"),
+            data_size: u32::try_from(payload.len()).expect("payload fits"),
+        },
+        payload,
+    };
+
+    let command = Command::from_transaction(transaction, &PayloadLimits::new())
+        .expect("command should parse");
+
+    let Command::SetFileInfo { req, .. } = command else {
+        panic!("expected SetFileInfo, got {command:?}");
+    };
+    assert_eq!(
+        req,
+        SetFileInfoRequest {
+            name: "readme.txt".to_owned(),
+            path: None,
+            new_name: None,
+            comment: Some("Start here".to_owned()),
+        }
+    );
+}
+
+#[test]
+fn move_file_reads_destination_path() {
+    let destination = crate::file_handlers::encode_file_path(&["Archive"]).expect("path encodes");
+    let params: Vec<(FieldId, &[u8])> = vec![
+        (FieldId::FileItemName, b"guide.txt"),
+        (FieldId::FileNewPath, destination.as_slice()),
+    ];
+    let payload = encode_params(&params).expect("payload encodes");
+    let transaction = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::MoveFile.into(),
+            id: 11,
+            error: 0,
+            total_size: u32::try_from(payload.len()).expect("payload fits"),
+            data_size: u32::try_from(payload.len()).expect("payload fits"),
+        },
+        payload,
+    };
+
+    let command = Command::from_transaction(transaction, &PayloadLimits::new())
+        .expect("command should parse");
+
+    let Command::MoveFile { req, .. } = command else {
+        panic!("expected MoveFile, got {command:?}");
+    };
+    assert_eq!(
+        req,
+        MoveFileRequest {
+            name: "guide.txt".to_owned(),
+            path: None,
+            new_path: Some(destination),
+        }
+    );
+}
+
+#[rstest]
+#[case::reply(Some(7_i32.to_be_bytes()), Some(7))]
+#[case::zero_parent(Some(0_i32.to_be_bytes()), None)]
+#[case::no_parent(None, None)]
+fn post_news_article_reads_parent_id(
+    #[case] parent: Option<[u8; 4]>,
+    #[case] expected: Option<i32>,
+) {
+    let mut params: Vec<(FieldId, &[u8])> = vec![
+        (FieldId::NewsPath, b"General"),
+        (FieldId::NewsTitle, b"Re: Hello"),
+        (FieldId::NewsDataFlavor, b"text/plain"),
+        (FieldId::NewsArticleData, b"Agreed"),
+    ];
+    if let Some(bytes) = parent.as_ref() {
+        params.push((FieldId::NewsArticleId, bytes));
+    }
+    let payload = encode_params(&params).expect("payload encodes");
+    let transaction = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::PostNewsArticle.into(),
+            id: 12,
+            error: 0,
+            total_size: u32::try_from(payload.len()).expect("payload fits"),
+            data_size: u32::try_from(payload.len()).expect("payload fits"),
+        },
+        payload,
+    };
+
+    let command = Command::from_transaction(transaction, &PayloadLimits::new())
+        .expect("command should parse");
 
-    assert!(matches!(command, Command::GetFileNameList { .. }));
+    let Command::PostNewsArticle { req, .. } = command else {
+        panic!("expected PostNewsArticle, got {command:?}");
+    };
+    assert_eq!(req.parent_id, expected);
+}
+
+#[rstest]
+#[case::short_flag(Some(1_u16.to_be_bytes().to_vec()), true)]
+#[case::long_flag(Some(1_u32.to_be_bytes().to_vec()), true)]
+#[case::cleared_flag(Some(0_u16.to_be_bytes().to_vec()), false)]
+#[case::no_flag(None, false)]
+fn delete_news_article_reads_recursive_flag(#[case] flag: Option<Vec<u8>>, #[case] expected: bool) {
+    let article_id = 3_i32.to_be_bytes();
+    let mut params: Vec<(FieldId, &[u8])> = vec![
+        (FieldId::NewsPath, b"General"),
+        (FieldId::NewsArticleId, &article_id),
+    ];
+    if let Some(bytes) = flag.as_deref() {
+        params.push((FieldId::NewsRecursiveDelete, bytes));
+    }
+    let payload = encode_params(&params).expect("payload encodes");
+    let transaction = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::DeleteNewsArticle.into(),
+            id: 13,
+            error: 0,
+            total_size: u32::try_from(payload.len()).expect("payload fits"),
+            data_size: u32::try_from(payload.len()).expect("payload fits"),
+        },
+        payload,
+    };
+
+    let command = Command::from_transaction(transaction, &PayloadLimits::new())
+        .expect("command should parse");
+
+    let Command::DeleteNewsArticle { req, .. } = command else {
+        panic!("expected DeleteNewsArticle, got {command:?}");
+    };
+    assert_eq!(
+        req,
+        DeleteArticleRequest {
+            path: "General".to_owned(),
+            article_id: 3,
+            recursive: expected,
+        }
+    );
+}
+
+#[rstest]
+#[case::flag_set(Some(1_u16.to_be_bytes().to_vec()), true)]
+#[case::flag_cleared(Some(0_u16.to_be_bytes().to_vec()), false)]
+#[case::no_flag(None, false)]
+fn get_news_article_data_reads_transfer_flag(
+    #[case] flag: Option<Vec<u8>>,
+    #[case] expected: bool,
+) {
+    let article_id = 3_i32.to_be_bytes();
+    let mut params: Vec<(FieldId, &[u8])> = vec![
+        (FieldId::NewsPath, b"General"),
+        (FieldId::NewsArticleId, &article_id),
+    ];
+    if let Some(bytes) = flag.as_deref() {
+        params.push((FieldId::NewsDataTransfer, bytes));
+    }
+    let payload = encode_params(&params).expect("payload encodes");
+    let transaction = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::NewsArticleData.into(),
+            id: 14,
+            error: 0,
+            total_size: u32::try_from(payload.len()).expect("payload fits"),
+            data_size: u32::try_from(payload.len()).expect("payload fits"),
+        },
+        payload,
+    };
+
+    let command = Command::from_transaction(transaction, &PayloadLimits::new())
+        .expect("command should parse");
+
+    let Command::GetNewsArticleData { req, .. } = command else {
+        panic!("expected GetNewsArticleData, got {command:?}");
+    };
+    assert_eq!(
+        req,
+        ArticleDataRequest {
+            path: "General".to_owned(),
+            article_id: 3,
+            accepts_transfer: expected,
+        }
+    );
+}
+
+#[rstest]
+fn exhausted_pool_reports_database_busy() {
+    let error = CommandError::from(diesel_async::pooled_connection::bb8::RunError::TimedOut);
+    assert_eq!(error.error_code(), ERR_DATABASE_BUSY);
+    assert!(error.error_text().is_none());
 }
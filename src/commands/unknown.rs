@@ -0,0 +1,197 @@
+//! Telemetry and policy for transaction types mxd does not implement.
+//!
+//! Every unknown transaction bumps a process-wide counter for its type, so
+//! operators can see which client features are being asked for without the
+//! log filling up on a chatty client: only the first sighting of each type is
+//! logged at info level. What the client gets back is governed by
+//! [`UnknownTransactionPolicy`], which each server reads at startup and hands
+//! to its connections in its
+//! [`ServerSettings`](crate::server::settings::ServerSettings).
+
+use std::{
+    collections::BTreeMap,
+    num::NonZeroU32,
+    sync::{Mutex, PoisonError},
+};
+
+use thiserror::Error;
+
+use crate::server::AppConfig;
+
+/// Unknown transactions a connection may send before the `disconnect` policy
+/// closes it, unless configured otherwise.
+pub const DEFAULT_UNKNOWN_TRANSACTION_LIMIT: NonZeroU32 = NonZeroU32::MIN.saturating_add(7);
+
+/// Reason given in the Disconnect Message sent by the `disconnect` policy.
+pub const UNKNOWN_TRANSACTION_DISCONNECT_REASON: &str = "Too many unsupported requests";
+
+static COUNTS: Mutex<BTreeMap<u16, u64>> = Mutex::new(BTreeMap::new());
+
+/// How mxd answers transaction types it does not implement.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownTransactionPolicy {
+    /// Acknowledge with an empty reply that carries no error.
+    Ignore,
+    /// Reply with the generic server error (code 3).
+    #[default]
+    Error,
+    /// Reply with the generic server error, and close the connection once it
+    /// has sent `limit` unknown transactions.
+    Disconnect {
+        /// Unknown transactions tolerated per connection.
+        limit: NonZeroU32,
+    },
+}
+
+/// Errors raised while reading the policy from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum UnknownPolicyError {
+    /// `unknown_transactions` named no known policy.
+    #[error("unknown_transactions must be ignore, error, or disconnect, not {0:?}")]
+    InvalidMode(String),
+    /// `unknown_transaction_limit` was zero.
+    #[error("unknown_transaction_limit must be greater than zero")]
+    ZeroLimit,
+}
+
+/// What to do with one unknown transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum UnknownAction {
+    /// Send an empty success reply.
+    Acknowledge,
+    /// Send an error reply.
+    Reject,
+    /// Send an error reply, then close the connection.
+    RejectAndDisconnect,
+}
+
+impl UnknownTransactionPolicy {
+    /// Read the policy from `config`.
+    ///
+    /// An unset `unknown_transactions` selects [`Self::Error`], and an unset
+    /// `unknown_transaction_limit` selects
+    /// [`DEFAULT_UNKNOWN_TRANSACTION_LIMIT`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`UnknownPolicyError`] for an unrecognised mode or a zero
+    /// limit.
+    pub fn from_config(config: &AppConfig) -> Result<Self, UnknownPolicyError> {
+        let limit = config
+            .unknown_transaction_limit
+            .map_or(Ok(DEFAULT_UNKNOWN_TRANSACTION_LIMIT), |raw| {
+                NonZeroU32::new(raw).ok_or(UnknownPolicyError::ZeroLimit)
+            })?;
+        match config.unknown_transactions.as_deref().map(str::trim) {
+            None | Some("error") => Ok(Self::Error),
+            Some("ignore") => Ok(Self::Ignore),
+            Some("disconnect") => Ok(Self::Disconnect { limit }),
+            Some(other) => Err(UnknownPolicyError::InvalidMode(other.to_owned())),
+        }
+    }
+
+    /// Decide how to answer the `seen`th unknown transaction on a connection.
+    pub(crate) const fn action(self, seen: u32) -> UnknownAction {
+        match self {
+            Self::Ignore => UnknownAction::Acknowledge,
+            Self::Error => UnknownAction::Reject,
+            Self::Disconnect { limit } if seen >= limit.get() => UnknownAction::RejectAndDisconnect,
+            Self::Disconnect { .. } => UnknownAction::Reject,
+        }
+    }
+}
+
+/// Return how many unknown transactions of each type this process has seen,
+/// ordered by type.
+#[must_use]
+pub fn unknown_transaction_counts() -> Vec<(u16, u64)> {
+    COUNTS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .map(|(&ty, &count)| (ty, count))
+        .collect()
+}
+
+/// Count one unknown transaction of type `ty`, returning the new total.
+pub(crate) fn record_unknown_transaction(ty: u16) -> u64 {
+    let mut counts = COUNTS.lock().unwrap_or_else(PoisonError::into_inner);
+    let count = counts.entry(ty).or_insert(0);
+    *count = count.saturating_add(1);
+    *count
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for unknown-transaction policy parsing and decisions.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn limit(raw: u32) -> NonZeroU32 { NonZeroU32::new(raw).expect("non-zero limit") }
+
+    #[rstest]
+    #[case::unset(None, None, Ok(UnknownTransactionPolicy::Error))]
+    #[case::error(Some("error"), None, Ok(UnknownTransactionPolicy::Error))]
+    #[case::ignore(Some("ignore"), None, Ok(UnknownTransactionPolicy::Ignore))]
+    #[case::disconnect_default(
+        Some("disconnect"),
+        None,
+        Ok(UnknownTransactionPolicy::Disconnect { limit: DEFAULT_UNKNOWN_TRANSACTION_LIMIT })
+    )]
+    #[case::disconnect_limit(
+        Some("disconnect"),
+        Some(3),
+        Ok(UnknownTransactionPolicy::Disconnect { limit: limit(3) })
+    )]
+    #[case::invalid(Some("drop"), None, Err(UnknownPolicyError::InvalidMode("drop".to_owned())))]
+    #[case::zero_limit(Some("disconnect"), Some(0), Err(UnknownPolicyError::ZeroLimit))]
+    fn parses_policy_from_config(
+        #[case] mode: Option<&str>,
+        #[case] raw_limit: Option<u32>,
+        #[case] expected: Result<UnknownTransactionPolicy, UnknownPolicyError>,
+    ) {
+        let config = AppConfig {
+            unknown_transactions: mode.map(str::to_owned),
+            unknown_transaction_limit: raw_limit,
+            ..AppConfig::default()
+        };
+        assert_eq!(UnknownTransactionPolicy::from_config(&config), expected);
+    }
+
+    #[rstest]
+    #[case::ignore(UnknownTransactionPolicy::Ignore, 100, UnknownAction::Acknowledge)]
+    #[case::error(UnknownTransactionPolicy::Error, 100, UnknownAction::Reject)]
+    #[case::below_limit(
+        UnknownTransactionPolicy::Disconnect { limit: limit(3) },
+        2,
+        UnknownAction::Reject
+    )]
+    #[case::at_limit(
+        UnknownTransactionPolicy::Disconnect { limit: limit(3) },
+        3,
+        UnknownAction::RejectAndDisconnect
+    )]
+    fn decides_action_from_count(
+        #[case] policy: UnknownTransactionPolicy,
+        #[case] seen: u32,
+        #[case] expected: UnknownAction,
+    ) {
+        assert_eq!(policy.action(seen), expected);
+    }
+
+    #[test]
+    fn counts_unknown_transactions_per_type() {
+        // Use a type no other test sends so parallel tests cannot interfere.
+        let ty = 65_001;
+        let first = record_unknown_transaction(ty);
+        let second = record_unknown_transaction(ty);
+        assert_eq!(second, first + 1);
+        assert!(
+            unknown_transaction_counts()
+                .iter()
+                .any(|&(seen_ty, count)| seen_ty == ty && count >= second)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Deleting news articles and repairing the links around them.
+//!
+//! Articles form two kinds of chain: siblings are linked through
+//! `prev_article_id`/`next_article_id`, and a parent names its first reply
+//! through `first_child_article_id`. A delete rewires every link that points at
+//! the removed rows before deleting them, because `PostgreSQL` enforces the
+//! self-referencing foreign keys while `SQLite` connections do not. Doing the
+//! repair in application code keeps both backends identical without a
+//! migration.
+
+use diesel::{OptionalExtension, prelude::*, result::Error as DieselError};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+use super::news_lock::lock_category;
+#[cfg(feature = "sqlite")]
+use super::news_lock::serialise_news_writes;
+use super::{
+    categories::category_id_from_path,
+    connection::{DbConnection, TracedQueryDsl},
+    paths::{NewsPath, PathLookupError},
+};
+use crate::models::Article;
+
+/// Delete article `article_id` from the category at `path`.
+///
+/// With `recursive`, the article's replies and all of their replies are
+/// deleted as well. Otherwise the replies move up a level: they take the
+/// article's place in its sibling chain and adopt its parent. Returns the
+/// number of articles removed, or `Ok(None)` when the category holds no
+/// article `article_id`. Deletes and posts in one category are serialised,
+/// so a post never links after an article being removed.
+///
+/// # Errors
+/// Returns an error if the path is invalid or a query fails.
+#[must_use = "handle the result"]
+pub async fn delete_article(
+    conn: &mut DbConnection,
+    path: impl Into<NewsPath<'_>>,
+    article_id: i32,
+    recursive: bool,
+) -> Result<Option<usize>, PathLookupError> {
+    use crate::schema::news_articles::dsl as a;
+
+    let news_path = path.into();
+    #[cfg(feature = "sqlite")]
+    let _serialised = serialise_news_writes().await;
+    conn.transaction::<_, PathLookupError, _>(async |tx_conn| {
+        let cat_id = category_id_from_path(tx_conn, news_path).await?;
+        #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+        lock_category(tx_conn, cat_id).await?;
+        let Some(article) = a::news_articles
+            .filter(a::category_id.eq(cat_id))
+            .filter(a::id.eq(article_id))
+            .limit(1)
+            .traced()
+            .get_result::<Article>(tx_conn)
+            .await
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        let removed = if recursive {
+            let subtree = collect_subtree(tx_conn, article.id).await?;
+            splice_out(tx_conn, &article, None).await?;
+            diesel::delete(a::news_articles.filter(a::id.eq_any(subtree)))
+                .traced()
+                .execute(tx_conn)
+                .await?
+        } else {
+            let replies = reply_span(tx_conn, &article).await?;
+            splice_out(tx_conn, &article, replies).await?;
+            diesel::delete(a::news_articles.filter(a::id.eq(article.id)))
+                .traced()
+                .execute(tx_conn)
+                .await?
+        };
+        Ok(Some(removed))
+    })
+    .await
+}
+
+/// Return the article and every transitive reply beneath it.
+async fn collect_subtree(conn: &mut DbConnection, root: i32) -> Result<Vec<i32>, DieselError> {
+    use crate::schema::news_articles::dsl as a;
+
+    let mut ids = vec![root];
+    let mut frontier = vec![root];
+    while !frontier.is_empty() {
+        let children = a::news_articles
+            .filter(a::parent_article_id.eq_any(&frontier))
+            .select(a::id)
+            .traced()
+            .load::<i32>(conn)
+            .await?;
+        ids.extend_from_slice(&children);
+        frontier = children;
+    }
+    Ok(ids)
+}
+
+/// Return the first and last direct replies of `article`, if it has any.
+async fn reply_span(
+    conn: &mut DbConnection,
+    article: &Article,
+) -> Result<Option<(i32, i32)>, DieselError> {
+    use crate::schema::news_articles::dsl as a;
+
+    let Some(first) = article.first_child_article_id else {
+        return Ok(None);
+    };
+    let last = a::news_articles
+        .filter(a::parent_article_id.eq(article.id))
+        .filter(a::next_article_id.is_null())
+        .select(a::id)
+        .first::<i32>(conn)
+        .await
+        .optional()?
+        .unwrap_or(first);
+    Ok(Some((first, last)))
+}
+
+/// Unlink `article` from its sibling chain and its parent.
+///
+/// When `replies` names the article's first and last replies, that run of
+/// replies is re-parented and spliced into the gap; otherwise the article's
+/// neighbours are linked to each other. Links are repaired by searching for
+/// rows that point at the article rather than trusting its own `prev`/`next`
+/// columns, so a chain that was already inconsistent cannot leave a dangling
+/// reference behind.
+async fn splice_out(
+    conn: &mut DbConnection,
+    article: &Article,
+    replies: Option<(i32, i32)>,
+) -> Result<(), DieselError> {
+    use crate::schema::news_articles::dsl as a;
+
+    // `head` follows the article's previous sibling and `tail` precedes its
+    // next sibling once the article is gone.
+    let (head, tail) = match replies {
+        Some((first, last)) => {
+            diesel::update(a::news_articles.filter(a::parent_article_id.eq(article.id)))
+                .set(a::parent_article_id.eq(article.parent_article_id))
+                .traced()
+                .execute(conn)
+                .await?;
+            diesel::update(a::news_articles.filter(a::id.eq(first)))
+                .set(a::prev_article_id.eq(article.prev_article_id))
+                .traced()
+                .execute(conn)
+                .await?;
+            diesel::update(a::news_articles.filter(a::id.eq(last)))
+                .set(a::next_article_id.eq(article.next_article_id))
+                .traced()
+                .execute(conn)
+                .await?;
+            (Some(first), Some(last))
+        }
+        None => (article.next_article_id, article.prev_article_id),
+    };
+    diesel::update(a::news_articles.filter(a::next_article_id.eq(article.id)))
+        .set(a::next_article_id.eq(head))
+        .traced()
+        .execute(conn)
+        .await?;
+    diesel::update(a::news_articles.filter(a::first_child_article_id.eq(article.id)))
+        .set(a::first_child_article_id.eq(head))
+        .traced()
+        .execute(conn)
+        .await?;
+    diesel::update(a::news_articles.filter(a::prev_article_id.eq(article.id)))
+        .set(a::prev_article_id.eq(tail))
+        .traced()
+        .execute(conn)
+        .await?;
+    Ok(())
+}
@@ -6,7 +6,16 @@ use diesel_async::{AsyncConnection, RunQueryDsl};
 
 #[cfg(all(feature = "sqlite", not(feature = "returning_clauses_for_sqlite_3_35")))]
 use super::insert::fetch_last_insert_rowid;
-use super::{categories::category_id_from_path, connection::DbConnection, paths::PathLookupError};
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+use super::news_lock::lock_category;
+#[cfg(feature = "sqlite")]
+use super::news_lock::serialise_news_writes;
+use super::{
+    categories::category_id_from_path,
+    connection::DbConnection,
+    outbox::{OutboxEvent, enqueue_outbox_event},
+    paths::{NewsPath, PathLookupError},
+};
 
 /// Retrieve a single article by path and identifier.
 ///
@@ -15,7 +24,7 @@ use super::{categories::category_id_from_path, connection::DbConnection, paths::
 #[must_use = "handle the result"]
 pub async fn get_article(
     conn: &mut DbConnection,
-    path: &str,
+    path: impl Into<NewsPath<'_>>,
     article_id: i32,
 ) -> Result<Option<crate::models::Article>, PathLookupError> {
     use crate::schema::news_articles::dsl as a;
@@ -29,6 +38,26 @@ pub async fn get_article(
     Ok(found)
 }
 
+/// Return the body of article `article_id`, or `None` when there is no such
+/// article. An article without a body has an empty one.
+///
+/// # Errors
+/// Returns an error if the query fails.
+#[must_use = "handle the result"]
+pub async fn get_article_body(
+    conn: &mut DbConnection,
+    article_id: i32,
+) -> QueryResult<Option<String>> {
+    use crate::schema::news_articles::dsl as a;
+    let body = a::news_articles
+        .find(article_id)
+        .select(a::data)
+        .first::<Option<String>>(conn)
+        .await
+        .optional()?;
+    Ok(body.map(Option::unwrap_or_default))
+}
+
 /// List the titles of all root-level articles within a category.
 ///
 /// # Errors
@@ -36,7 +65,7 @@ pub async fn get_article(
 #[must_use = "handle the result"]
 pub async fn list_article_titles(
     conn: &mut DbConnection,
-    path: &str,
+    path: impl Into<NewsPath<'_>>,
 ) -> Result<Vec<String>, PathLookupError> {
     use crate::schema::news_articles::dsl as a;
     let cat_id = category_id_from_path(conn, path).await?;
@@ -51,7 +80,7 @@ pub async fn list_article_titles(
     Ok(titles)
 }
 
-/// Parameters required to create a new root article.
+/// Parameters required to create a new root article or reply.
 pub struct CreateRootArticleParams<'a> {
     /// Article title.
     pub title: &'a str,
@@ -65,34 +94,130 @@ pub struct CreateRootArticleParams<'a> {
 
 /// Create a new root article in the specified category path.
 ///
+/// An [`OutboxEvent::ArticlePosted`] is written in the same transaction.
+/// Concurrent posts to one category are serialised, so each links after the
+/// one before it.
+///
 /// # Errors
 /// Returns an error if the path is invalid or the insertion fails.
 #[must_use = "handle the result"]
 pub async fn create_root_article(
     conn: &mut DbConnection,
-    path: &str,
+    path: impl Into<NewsPath<'_>>,
     params: CreateRootArticleParams<'_>,
 ) -> Result<i32, PathLookupError> {
+    let news_path = path.into();
+    #[cfg(feature = "sqlite")]
+    let _serialised = serialise_news_writes().await;
     conn.transaction::<_, PathLookupError, _>(async |tx_conn| {
-        let cat_id = category_id_from_path(tx_conn, path).await?;
-        let last = get_last_root_article_id(tx_conn, cat_id).await?;
-        let inserted = insert_new_article(tx_conn, cat_id, last, &params).await?;
+        let cat_id = category_id_from_path(tx_conn, news_path).await?;
+        #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+        lock_category(tx_conn, cat_id).await?;
+        let last = get_last_article_id(tx_conn, cat_id, None).await?;
+        let placement = ArticlePlacement {
+            category_id: cat_id,
+            parent: None,
+            prev: last,
+        };
+        let inserted = insert_new_article(tx_conn, placement, &params).await?;
         if let Some(prev) = last {
             link_prev_to_new(tx_conn, prev, inserted).await?;
         }
+        enqueue_posted(tx_conn, news_path.path, inserted, &params).await?;
         Ok(inserted)
     })
     .await
 }
 
-async fn get_last_root_article_id(
+/// Create a reply to article `parent_id` in the specified category path.
+///
+/// The reply becomes the parent's last child: it is linked after the previous
+/// last reply through `prev_article_id`/`next_article_id`, or recorded as the
+/// parent's `first_child_article_id` when it is the first reply, and an
+/// [`OutboxEvent::ArticlePosted`] is written in the same transaction. Posts
+/// are serialised per category as for [`create_root_article`]. Returns
+/// `Ok(None)` when the category holds no article `parent_id`.
+///
+/// # Errors
+/// Returns an error if the path is invalid or the insertion fails.
+#[must_use = "handle the result"]
+pub async fn create_reply_article(
+    conn: &mut DbConnection,
+    path: impl Into<NewsPath<'_>>,
+    parent_id: i32,
+    params: CreateRootArticleParams<'_>,
+) -> Result<Option<i32>, PathLookupError> {
+    use crate::schema::news_articles::dsl as a;
+    let news_path = path.into();
+    #[cfg(feature = "sqlite")]
+    let _serialised = serialise_news_writes().await;
+    conn.transaction::<_, PathLookupError, _>(async |tx_conn| {
+        let cat_id = category_id_from_path(tx_conn, news_path).await?;
+        #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+        lock_category(tx_conn, cat_id).await?;
+        let parent = a::news_articles
+            .filter(a::category_id.eq(cat_id))
+            .filter(a::id.eq(parent_id))
+            .select(a::id)
+            .first::<i32>(tx_conn)
+            .await
+            .optional()?;
+        if parent.is_none() {
+            return Ok(None);
+        }
+        let last = get_last_article_id(tx_conn, cat_id, Some(parent_id)).await?;
+        let placement = ArticlePlacement {
+            category_id: cat_id,
+            parent: Some(parent_id),
+            prev: last,
+        };
+        let inserted = insert_new_article(tx_conn, placement, &params).await?;
+        if let Some(prev) = last {
+            link_prev_to_new(tx_conn, prev, inserted).await?;
+        } else {
+            diesel::update(a::news_articles.filter(a::id.eq(parent_id)))
+                .set(a::first_child_article_id.eq(inserted))
+                .execute(tx_conn)
+                .await?;
+        }
+        enqueue_posted(tx_conn, news_path.path, inserted, &params).await?;
+        Ok(Some(inserted))
+    })
+    .await
+}
+
+async fn enqueue_posted(
+    conn: &mut DbConnection,
+    path: &str,
+    article_id: i32,
+    params: &CreateRootArticleParams<'_>,
+) -> Result<(), PathLookupError> {
+    let event = OutboxEvent::ArticlePosted {
+        path: path.to_owned(),
+        article_id,
+        title: params.title.to_owned(),
+    };
+    enqueue_outbox_event(conn, &event)
+        .await
+        .map_err(PathLookupError::Diesel)
+}
+
+/// Find the newest article among the children of `parent`, or among the root
+/// articles of the category when `parent` is `None`.
+async fn get_last_article_id(
     conn: &mut DbConnection,
     cat_id: i32,
+    parent: Option<i32>,
 ) -> Result<Option<i32>, PathLookupError> {
     use crate::schema::news_articles::dsl as a;
-    a::news_articles
+    let siblings = a::news_articles
         .filter(a::category_id.eq(cat_id))
-        .filter(a::parent_article_id.is_null())
+        .into_boxed();
+    let filtered = match parent {
+        Some(parent_id) => siblings.filter(a::parent_article_id.eq(parent_id)),
+        None => siblings.filter(a::parent_article_id.is_null()),
+    };
+    filtered
         .order(a::id.desc())
         .select(a::id)
         .first::<i32>(conn)
@@ -101,18 +226,27 @@ async fn get_last_root_article_id(
         .map_err(PathLookupError::Diesel)
 }
 
+/// Where a new article sits in its category's thread.
+#[derive(Clone, Copy)]
+struct ArticlePlacement {
+    category_id: i32,
+    /// Article being replied to; `None` for a root article.
+    parent: Option<i32>,
+    /// Sibling the new article is linked after.
+    prev: Option<i32>,
+}
+
 async fn insert_new_article(
     conn: &mut DbConnection,
-    cat_id: i32,
-    prev: Option<i32>,
+    placement: ArticlePlacement,
     params: &CreateRootArticleParams<'_>,
 ) -> Result<i32, PathLookupError> {
     use crate::schema::news_articles::dsl as a;
     let now = Utc::now().naive_utc();
     let article = crate::models::NewArticle {
-        category_id: cat_id,
-        parent_article_id: None,
-        prev_article_id: prev,
+        category_id: placement.category_id,
+        parent_article_id: placement.parent,
+        prev_article_id: placement.prev,
         next_article_id: None,
         first_child_article_id: None,
         title: params.title,
@@ -0,0 +1,165 @@
+//! Ban list persistence.
+//!
+//! A ban matches either an account name or a peer IP address. Addresses are
+//! stored in canonical form so an IPv4 client reaching a dual-stack listener
+//! through an IPv4-mapped IPv6 address matches a ban on its IPv4 address.
+
+use std::{fmt, net::IpAddr};
+
+use chrono::NaiveDateTime;
+use diesel::{prelude::*, result::QueryResult};
+use diesel_async::RunQueryDsl;
+
+use super::connection::DbConnection;
+use crate::models::{Ban, NewBan};
+
+/// Stored `kind` for bans matching an account name.
+const KIND_USERNAME: &str = "username";
+/// Stored `kind` for bans matching a peer IP address.
+const KIND_ADDRESS: &str = "address";
+
+/// What a ban matches.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BanTarget {
+    /// An account name, compared exactly.
+    Username(String),
+    /// A peer IP address.
+    Address(IpAddr),
+}
+
+impl BanTarget {
+    /// Stored `kind` column value for this target.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Username(_) => KIND_USERNAME,
+            Self::Address(_) => KIND_ADDRESS,
+        }
+    }
+
+    /// Stored `target` column value for this target.
+    #[must_use]
+    pub fn value(&self) -> String {
+        match self {
+            Self::Username(name) => name.clone(),
+            Self::Address(addr) => addr.to_canonical().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for BanTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind(), self.value())
+    }
+}
+
+/// Record a ban, replacing the reason and expiry of any existing ban on the
+/// same target.
+///
+/// Banning an account name also revokes the account's session tokens, so
+/// the ban outlives any resumption the account was promised.
+///
+/// # Errors
+/// Returns any error produced by the queries.
+#[must_use = "handle the result"]
+pub async fn create_ban(
+    conn: &mut DbConnection,
+    target: &BanTarget,
+    reason: Option<&str>,
+    expires_at: Option<NaiveDateTime>,
+) -> QueryResult<usize> {
+    use crate::schema::{bans::dsl as b, session_tokens::dsl as t, users::dsl as u};
+    let value = target.value();
+    let ban = NewBan {
+        kind: target.kind(),
+        target: &value,
+        reason,
+        expires_at,
+    };
+    conn.transaction::<_, diesel::result::Error, _>(async |tx_conn| {
+        if let BanTarget::Username(name) = target {
+            let account = u::users.filter(u::username.eq(name)).select(u::id);
+            diesel::delete(t::session_tokens.filter(t::user_id.eq_any(account)))
+                .execute(tx_conn)
+                .await?;
+        }
+        diesel::insert_into(b::bans)
+            .values(&ban)
+            .on_conflict((b::kind, b::target))
+            .do_update()
+            .set(&ban)
+            .execute(tx_conn)
+            .await
+    })
+    .await
+}
+
+/// Delete the ban on `target`, returning the number of rows removed.
+///
+/// # Errors
+/// Returns any error produced by the delete query.
+#[must_use = "handle the result"]
+pub async fn remove_ban(conn: &mut DbConnection, target: &BanTarget) -> QueryResult<usize> {
+    use crate::schema::bans::dsl as b;
+    diesel::delete(
+        b::bans
+            .filter(b::kind.eq(target.kind()))
+            .filter(b::target.eq(target.value())),
+    )
+    .execute(conn)
+    .await
+}
+
+/// List every recorded ban, including expired ones, oldest first.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn list_bans(conn: &mut DbConnection) -> QueryResult<Vec<Ban>> {
+    use crate::schema::bans::dsl as b;
+    b::bans.order(b::id.asc()).load::<Ban>(conn).await
+}
+
+/// Find the ban on `target` that is still in force at `now`.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn find_active_ban(
+    conn: &mut DbConnection,
+    target: &BanTarget,
+    now: NaiveDateTime,
+) -> QueryResult<Option<Ban>> {
+    use crate::schema::bans::dsl as b;
+    b::bans
+        .filter(b::kind.eq(target.kind()))
+        .filter(b::target.eq(target.value()))
+        .filter(b::expires_at.is_null().or(b::expires_at.gt(now)))
+        .first::<Ban>(conn)
+        .await
+        .optional()
+}
+
+/// List address bans still in force at `now` with their expiries.
+///
+/// Rows whose target does not parse as an IP address are skipped.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn list_active_address_bans(
+    conn: &mut DbConnection,
+    now: NaiveDateTime,
+) -> QueryResult<Vec<(IpAddr, Option<NaiveDateTime>)>> {
+    use crate::schema::bans::dsl as b;
+    let rows = b::bans
+        .filter(b::kind.eq(KIND_ADDRESS))
+        .filter(b::expires_at.is_null().or(b::expires_at.gt(now)))
+        .select((b::target, b::expires_at))
+        .load::<(String, Option<NaiveDateTime>)>(conn)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(target, expires_at)| Some((target.parse().ok()?, expires_at)))
+        .collect())
+}
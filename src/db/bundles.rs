@@ -1,9 +1,13 @@
 //! Bundle helpers and shared path resolution types.
 
+use std::collections::HashMap;
+
 use cfg_if::cfg_if;
+use chrono::NaiveDateTime;
 use diesel::{
     OptionalExtension,
     QueryableByName,
+    dsl::{count_star, max},
     prelude::*,
     result::QueryResult,
     sql_query,
@@ -15,16 +19,17 @@ use diesel_async::RunQueryDsl;
 use super::insert::fetch_last_insert_rowid;
 use super::{
     connection::DbConnection,
-    paths::{PathLookupError, normalize_lookup_result, parse_path_segments},
+    news_roots::require_news_root,
+    paths::{NewsPath, PathLookupError, normalize_lookup_result, parse_path_segments},
 };
 use crate::{
     models::{Bundle, Category},
     news_path::{BUNDLE_BODY_SQL, BUNDLE_STEP_SQL, build_path_cte_with_conn},
 };
 
-async fn bundle_id_from_path(
+pub(super) async fn bundle_id_from_path(
     conn: &mut DbConnection,
-    path: &str,
+    news_path: impl Into<NewsPath<'_>>,
 ) -> Result<Option<i32>, PathLookupError> {
     #[derive(QueryableByName)]
     struct BunId {
@@ -32,11 +37,14 @@ async fn bundle_id_from_path(
         id: Option<i32>,
     }
 
+    let NewsPath { root, path } = news_path.into();
     let Some((json, len)) = parse_path_segments(path, true)? else {
         return Ok(None);
     };
 
-    let step = sql_query(BUNDLE_STEP_SQL).bind::<Text, _>(json.clone());
+    let step = sql_query(BUNDLE_STEP_SQL)
+        .bind::<Text, _>(json)
+        .bind::<Integer, _>(root);
     let len_i32: i32 = i32::try_from(len).map_err(|_| PathLookupError::InvalidPath)?;
     let body = sql_query(BUNDLE_BODY_SQL).bind::<Integer, _>(len_i32);
 
@@ -45,49 +53,174 @@ async fn bundle_id_from_path(
     normalize_lookup_result(res.and_then(|b| b.id), true)
 }
 
-/// List bundle and category names located at the given path.
+/// Kind of node surfaced by a news category listing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewsEntryKind {
+    /// A bundle that groups further bundles or categories.
+    Bundle,
+    /// A category that holds articles.
+    Category,
+}
+
+/// Typed entry returned by [`list_names_at_path`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewsListingRow {
+    /// Whether the entry is a bundle or a category.
+    pub kind: NewsEntryKind,
+    /// Display name of the entry.
+    pub name: String,
+    /// Number of items beneath the entry.
+    ///
+    /// Bundles count their direct child bundles and categories; categories
+    /// count their articles.
+    pub count: u32,
+    /// Timestamp of the most recent article in a category.
+    ///
+    /// Always `None` for bundles and for categories without articles.
+    pub last_posted_at: Option<NaiveDateTime>,
+}
+
+/// List bundles and categories located at the given path.
+///
+/// An empty path lists the top level of the path's root. Bundles are
+/// returned first, followed by categories; each group is ordered by name.
+/// Every row carries its kind and item count so replies can distinguish
+/// folders from article groups.
 ///
 /// # Errors
-/// Returns an error if the path is invalid or the query fails.
+/// Returns an error if the path or root is invalid or the query fails.
 #[must_use = "handle the result"]
 pub async fn list_names_at_path(
     conn: &mut DbConnection,
-    path: Option<&str>,
-) -> Result<Vec<String>, PathLookupError> {
+    path: impl Into<NewsPath<'_>>,
+) -> Result<Vec<NewsListingRow>, PathLookupError> {
     use crate::schema::{news_bundles::dsl as b, news_categories::dsl as c};
-    let bundle_id = if let Some(p) = path {
-        bundle_id_from_path(conn, p).await?
-    } else {
-        None
-    };
-    let mut names: Vec<String> = apply_parent_filter(
+    let news_path = path.into();
+    let bundle_id = bundle_id_from_path(conn, news_path).await?;
+    if bundle_id.is_none() {
+        require_news_root(conn, news_path.root).await?;
+    }
+    let bundles = apply_parent_filter(
         b::news_bundles.into_boxed(),
         bundle_id,
         |q, id| q.filter(b::parent_bundle_id.eq(id)),
-        |q| q.filter(b::parent_bundle_id.is_null()),
+        |q| {
+            q.filter(b::parent_bundle_id.is_null())
+                .filter(b::root_id.eq(news_path.root))
+        },
     )
     .order(b::name.asc())
     .load::<Bundle>(conn)
-    .await?
-    .into_iter()
-    .map(|b| b.name)
-    .collect();
-    let mut cats: Vec<String> = apply_parent_filter(
+    .await?;
+    let cats = apply_parent_filter(
         c::news_categories.into_boxed(),
         bundle_id,
         |q, id| q.filter(c::bundle_id.eq(id)),
-        |q| q.filter(c::bundle_id.is_null()),
+        |q| {
+            q.filter(c::bundle_id.is_null())
+                .filter(c::root_id.eq(news_path.root))
+        },
     )
     .order(c::name.asc())
     .load::<Category>(conn)
-    .await?
-    .into_iter()
-    .map(|c| c.name)
-    .collect();
-    names.append(&mut cats);
-    Ok(names)
+    .await?;
+
+    let bundle_ids: Vec<i32> = bundles.iter().map(|bun| bun.id).collect();
+    let category_ids: Vec<i32> = cats.iter().map(|cat| cat.id).collect();
+    let child_counts = bundle_child_counts(conn, &bundle_ids).await?;
+    let activity = category_activity(conn, &category_ids).await?;
+
+    let mut rows: Vec<NewsListingRow> = bundles
+        .into_iter()
+        .map(|bun| NewsListingRow {
+            kind: NewsEntryKind::Bundle,
+            count: child_counts.get(&bun.id).copied().unwrap_or(0),
+            name: bun.name,
+            last_posted_at: None,
+        })
+        .collect();
+    rows.extend(cats.into_iter().map(|cat| {
+        let stats = activity.get(&cat.id).copied().unwrap_or_default();
+        NewsListingRow {
+            kind: NewsEntryKind::Category,
+            name: cat.name,
+            count: stats.count,
+            last_posted_at: stats.last_posted_at,
+        }
+    }));
+    Ok(rows)
 }
 
+/// Count the direct child bundles and categories of each bundle in `ids`.
+async fn bundle_child_counts(
+    conn: &mut DbConnection,
+    ids: &[i32],
+) -> QueryResult<HashMap<i32, u32>> {
+    use crate::schema::{news_bundles::dsl as b, news_categories::dsl as c};
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let sub_bundles: Vec<(Option<i32>, i64)> = b::news_bundles
+        .filter(b::parent_bundle_id.eq_any(ids.to_vec()))
+        .group_by(b::parent_bundle_id)
+        .select((b::parent_bundle_id, count_star()))
+        .load(conn)
+        .await?;
+    let categories: Vec<(Option<i32>, i64)> = c::news_categories
+        .filter(c::bundle_id.eq_any(ids.to_vec()))
+        .group_by(c::bundle_id)
+        .select((c::bundle_id, count_star()))
+        .load(conn)
+        .await?;
+    let mut counts = HashMap::new();
+    for (parent, count) in sub_bundles.into_iter().chain(categories) {
+        if let Some(id) = parent {
+            let entry = counts.entry(id).or_insert(0u32);
+            *entry = entry.saturating_add(clamp_count(count));
+        }
+    }
+    Ok(counts)
+}
+
+/// Article totals and latest post time for a single category.
+#[derive(Clone, Copy, Debug, Default)]
+struct CategoryActivity {
+    count: u32,
+    last_posted_at: Option<NaiveDateTime>,
+}
+
+/// Summarise article activity for each category in `ids`.
+///
+/// Uses one grouped aggregate query so listings stay a fixed number of round
+/// trips regardless of how many categories are returned.
+async fn category_activity(
+    conn: &mut DbConnection,
+    ids: &[i32],
+) -> QueryResult<HashMap<i32, CategoryActivity>> {
+    use crate::schema::news_articles::dsl as a;
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let stats: Vec<(i32, i64, Option<NaiveDateTime>)> = a::news_articles
+        .filter(a::category_id.eq_any(ids.to_vec()))
+        .group_by(a::category_id)
+        .select((a::category_id, count_star(), max(a::posted_at)))
+        .load(conn)
+        .await?;
+    Ok(stats
+        .into_iter()
+        .map(|(id, count, last_posted_at)| {
+            let activity = CategoryActivity {
+                count: clamp_count(count),
+                last_posted_at,
+            };
+            (id, activity)
+        })
+        .collect())
+}
+
+fn clamp_count(count: i64) -> u32 { u32::try_from(count.max(0)).unwrap_or(u32::MAX) }
+
 fn apply_parent_filter<Q, FSome, FNone>(
     query: Q,
     parent: Option<i32>,
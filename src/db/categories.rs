@@ -13,7 +13,7 @@ use diesel_async::RunQueryDsl;
 use super::insert::fetch_last_insert_rowid;
 use super::{
     connection::DbConnection,
-    paths::{PathLookupError, normalize_lookup_result, parse_path_segments},
+    paths::{NewsPath, PathLookupError, normalize_lookup_result, parse_path_segments},
 };
 use crate::news_path::{CATEGORY_BODY_SQL, CATEGORY_STEP_SQL, build_path_cte_with_conn};
 
@@ -57,7 +57,7 @@ pub async fn create_category(
 
 pub(super) async fn category_id_from_path(
     conn: &mut DbConnection,
-    path: &str,
+    news_path: impl Into<NewsPath<'_>>,
 ) -> Result<i32, PathLookupError> {
     #[derive(QueryableByName)]
     struct CatId {
@@ -65,6 +65,7 @@ pub(super) async fn category_id_from_path(
         id: i32,
     }
 
+    let NewsPath { root, path } = news_path.into();
     let Some((json, len)) = parse_path_segments(path, false)? else {
         return Err(PathLookupError::InvalidPath);
     };
@@ -72,7 +73,9 @@ pub(super) async fn category_id_from_path(
     if len == 0 {
         return Err(PathLookupError::InvalidPath);
     }
-    let step = sql_query(CATEGORY_STEP_SQL).bind::<Text, _>(json);
+    let step = sql_query(CATEGORY_STEP_SQL)
+        .bind::<Text, _>(json)
+        .bind::<Integer, _>(root);
     let len_minus_one: i32 = i32::try_from(len - 1).map_err(|_| PathLookupError::InvalidPath)?;
     let trimmed = path.trim_matches('/');
     let final_segment = trimmed
@@ -82,10 +85,11 @@ pub(super) async fn category_id_from_path(
         .to_owned();
     let body = sql_query(CATEGORY_BODY_SQL)
         .bind::<Text, _>(final_segment)
+        .bind::<Integer, _>(root)
         .bind::<Integer, _>(len_minus_one);
 
     let query = build_path_cte_with_conn(conn, step, body);
     let res: Option<CatId> = query.get_result(conn).await.optional()?;
     let maybe_id = normalize_lookup_result(res.map(|c| c.id), true)?;
-    maybe_id.ok_or(PathLookupError::InvalidPath)
+    maybe_id.ok_or(PathLookupError::NotFound)
 }
@@ -1,10 +1,26 @@
 //! Connection and pool helpers for database access.
+//!
+//! This module also holds the query-instrumentation wrapper [`Traced`]. When
+//! a server enables SQL trace comments, [`with_query_trace`] gives each inbound
+//! transaction a fresh [`QueryTraceId`], records it on a tracing span, and
+//! makes it visible to every query wrapped with [`TracedQueryDsl::traced`]
+//! while the transaction runs. Those queries start with a
+//! `/* trace_id=... */` comment, so `PostgreSQL` slow-query logs can be
+//! matched to server traces.
+
+use std::{fmt, num::NonZeroU32, time::Duration};
 
 use cfg_if::cfg_if;
+use diesel::{
+    backend::Backend as DieselBackend,
+    query_builder::{AstPass, Query, QueryFragment, QueryId},
+    result::QueryResult,
+};
 use diesel_async::pooled_connection::{AsyncDieselConnectionManager, PoolError, bb8::Pool};
 #[cfg(feature = "sqlite")]
 use diesel_async::sync_connection_wrapper::SyncConnectionWrapper;
 use diesel_migrations::{EmbeddedMigrations, embed_migrations};
+use tracing::{Instrument, debug_span};
 
 cfg_if! {
     if #[cfg(all(feature = "sqlite", feature = "postgres", not(feature = "lint")))] {
@@ -35,10 +51,37 @@ cfg_if! {
     }
 }
 
+/// Size and timeouts of the database connection pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolSettings {
+    /// Most connections open at once.
+    pub max_size: NonZeroU32,
+    /// How long a checkout waits for a free connection before failing with
+    /// `RunError::TimedOut`; must be greater than zero.
+    pub connection_timeout: Duration,
+    /// How long an unused connection stays open, if limited.
+    pub idle_timeout: Option<Duration>,
+}
+
+impl PoolSettings {
+    /// bb8's own defaults: ten connections, a 30 second checkout timeout, and
+    /// idle connections closed after ten minutes.
+    pub const DEFAULT: Self = Self {
+        max_size: NonZeroU32::MIN.saturating_add(9),
+        connection_timeout: Duration::from_secs(30),
+        idle_timeout: Some(Duration::from_secs(600)),
+    };
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self { Self::DEFAULT }
+}
+
 /// Create a pooled connection to the configured database.
 ///
 /// Asynchronously establishes a database connection pool for the configured
-/// backend, returning any pool initialization failure to the caller.
+/// backend with [`PoolSettings::DEFAULT`], returning any pool initialization
+/// failure to the caller.
 ///
 /// # Examples
 ///
@@ -54,6 +97,168 @@ cfg_if! {
 /// # Errors
 /// Returns any error reported by the underlying connection pool builder.
 pub async fn establish_pool(database_url: &str) -> Result<DbPool, PoolError> {
+    establish_pool_with(database_url, &PoolSettings::DEFAULT).await
+}
+
+/// Create a pooled connection to the configured database, sized and timed
+/// by `settings`.
+///
+/// # Errors
+/// Returns any error reported by the underlying connection pool builder.
+pub async fn establish_pool_with(
+    database_url: &str,
+    settings: &PoolSettings,
+) -> Result<DbPool, PoolError> {
     let config = AsyncDieselConnectionManager::<DbConnection>::new(database_url);
-    Pool::builder().build(config).await
+    Pool::builder()
+        .max_size(settings.max_size.get())
+        .connection_timeout(settings.connection_timeout)
+        .idle_timeout(settings.idle_timeout)
+        .build(config)
+        .await
+}
+
+tokio::task_local! {
+    static QUERY_TRACE_ID: QueryTraceId;
+}
+
+/// Identifier tying the queries of one transaction to its tracing span.
+///
+/// Identifiers are 16 lowercase hexadecimal digits, so they can be embedded
+/// in an SQL comment without escaping.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueryTraceId(String);
+
+impl QueryTraceId {
+    /// Generate a random identifier.
+    #[must_use]
+    pub fn generate() -> Self { Self(format!("{:016x}", rand::random::<u64>())) }
+
+    /// Borrow the identifier as text.
+    #[must_use]
+    pub const fn as_str(&self) -> &str { self.0.as_str() }
+}
+
+impl fmt::Display for QueryTraceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(&self.0) }
+}
+
+/// Run `fut` on behalf of one inbound transaction.
+///
+/// When `enabled`, `fut` runs inside a `transaction` span carrying a fresh
+/// [`QueryTraceId`], and traced queries it issues are prefixed with that
+/// identifier. Otherwise `fut` runs unchanged.
+pub async fn with_query_trace<F: Future>(enabled: bool, fut: F) -> F::Output {
+    if !enabled {
+        return fut.await;
+    }
+    let trace_id = QueryTraceId::generate();
+    let span = debug_span!("transaction", trace_id = %trace_id);
+    QUERY_TRACE_ID.scope(trace_id, fut).instrument(span).await
+}
+
+/// Return the trace identifier of the transaction being served, if any.
+#[must_use]
+pub fn current_query_trace_id() -> Option<QueryTraceId> {
+    QUERY_TRACE_ID.try_with(Clone::clone).ok()
+}
+
+/// Query wrapper that prefixes the SQL with the current trace identifier.
+///
+/// The identifier is captured when the wrapper is built. Without one, the
+/// wrapped query's SQL and statement caching are unchanged; with one, the
+/// statement is not cached because its text is unique to the transaction.
+#[derive(Clone, Debug)]
+pub struct Traced<Q> {
+    query: Q,
+    trace_id: Option<QueryTraceId>,
+}
+
+impl<Q> Traced<Q> {
+    /// Wrap `query`, tagging it with `trace_id` when one is given.
+    #[must_use]
+    pub const fn new(query: Q, trace_id: Option<QueryTraceId>) -> Self { Self { query, trace_id } }
+}
+
+impl<Q, DB> QueryFragment<DB> for Traced<Q>
+where
+    DB: DieselBackend,
+    Q: QueryFragment<DB>,
+{
+    fn walk_ast<'b>(&'b self, mut out: AstPass<'_, 'b, DB>) -> QueryResult<()> {
+        if let Some(trace_id) = &self.trace_id {
+            out.unsafe_to_cache_prepared();
+            out.push_sql("/* trace_id=");
+            out.push_sql(trace_id.as_str());
+            out.push_sql(" */ ");
+        }
+        self.query.walk_ast(out.reborrow())
+    }
+}
+
+impl<Q: QueryId> QueryId for Traced<Q> {
+    type QueryId = Q::QueryId;
+
+    const HAS_STATIC_QUERY_ID: bool = Q::HAS_STATIC_QUERY_ID;
+}
+
+impl<Q: Query> Query for Traced<Q> {
+    type SqlType = Q::SqlType;
+}
+
+/// Adds [`traced`](TracedQueryDsl::traced) to every query.
+pub trait TracedQueryDsl: Sized {
+    /// Tag this query with the trace identifier of the current transaction.
+    ///
+    /// Call it last, immediately before running the query.
+    #[must_use]
+    fn traced(self) -> Traced<Self> { Traced::new(self, current_query_trace_id()) }
+}
+
+impl<Q> TracedQueryDsl for Q {}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for SQL trace comments.
+    use diesel::{debug_query, prelude::*};
+    use rstest::rstest;
+
+    use super::*;
+    use crate::schema::users::dsl as u;
+
+    #[rstest]
+    fn untraced_queries_are_unchanged() {
+        let query = u::users.filter(u::id.eq(1)).select(u::username);
+        let plain = debug_query::<Backend, _>(&query).to_string();
+        let wrapped = Traced::new(query, None);
+        assert_eq!(debug_query::<Backend, _>(&wrapped).to_string(), plain);
+    }
+
+    #[rstest]
+    fn traced_queries_start_with_the_trace_comment() {
+        let trace_id = QueryTraceId::generate();
+        let query = u::users.filter(u::id.eq(1)).select(u::username);
+        let wrapped = Traced::new(query, Some(trace_id.clone()));
+        let sql = debug_query::<Backend, _>(&wrapped).to_string();
+        assert!(sql.starts_with(&format!("/* trace_id={trace_id} */ SELECT")));
+    }
+
+    #[rstest]
+    fn trace_ids_are_hexadecimal() {
+        let trace_id = QueryTraceId::generate();
+        assert_eq!(trace_id.as_str().len(), 16);
+        assert!(trace_id.as_str().chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn trace_ids_are_scoped_to_the_transaction() {
+        assert!(current_query_trace_id().is_none());
+        let trace_id = QueryTraceId::generate();
+        let seen = QUERY_TRACE_ID
+            .scope(trace_id.clone(), async { ().traced().trace_id })
+            .await;
+        assert_eq!(seen, Some(trace_id));
+        assert!(current_query_trace_id().is_none());
+    }
 }
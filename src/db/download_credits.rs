@@ -0,0 +1,67 @@
+//! Download credit balances.
+//!
+//! Under the `credits` download policy each download spends credits, one per
+//! byte, and administrators top balances up with the `users credits`
+//! subcommand. An account without a row has no credits.
+
+use diesel::{prelude::*, result::QueryResult, upsert::excluded};
+use diesel_async::RunQueryDsl;
+
+use super::connection::DbConnection;
+
+/// Return the credits held by `user_id`, or zero when it has none.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn get_download_credits(conn: &mut DbConnection, user_id: i32) -> QueryResult<i64> {
+    use crate::schema::download_credits::dsl as c;
+    let credits = c::download_credits
+        .filter(c::user_id.eq(user_id))
+        .select(c::credits)
+        .first::<i64>(conn)
+        .await
+        .optional()?;
+    Ok(credits.unwrap_or_default())
+}
+
+/// Add `delta` credits to `user_id`'s balance; a negative `delta` spends
+/// them.
+///
+/// # Errors
+/// Returns any error produced by the upsert.
+#[must_use = "handle the result"]
+pub async fn adjust_download_credits(
+    conn: &mut DbConnection,
+    user_id: i32,
+    delta: i64,
+) -> QueryResult<usize> {
+    use crate::schema::download_credits::dsl as c;
+    diesel::insert_into(c::download_credits)
+        .values((c::user_id.eq(user_id), c::credits.eq(delta)))
+        .on_conflict(c::user_id)
+        .do_update()
+        .set(c::credits.eq(c::credits + excluded(c::credits)))
+        .execute(conn)
+        .await
+}
+
+/// Replace `user_id`'s balance with `credits`.
+///
+/// # Errors
+/// Returns any error produced by the upsert.
+#[must_use = "handle the result"]
+pub async fn set_download_credits(
+    conn: &mut DbConnection,
+    user_id: i32,
+    credits: i64,
+) -> QueryResult<usize> {
+    use crate::schema::download_credits::dsl as c;
+    diesel::insert_into(c::download_credits)
+        .values((c::user_id.eq(user_id), c::credits.eq(credits)))
+        .on_conflict(c::user_id)
+        .do_update()
+        .set(c::credits.eq(credits))
+        .execute(conn)
+        .await
+}
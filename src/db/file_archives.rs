@@ -0,0 +1,381 @@
+//! Read-only snapshots of file-area folders.
+//!
+//! A snapshot copies a folder's file nodes, and the Download File grants on
+//! each, into a dated folder inside the source's archive folder, a sibling
+//! named `<source> Archive`. Copies keep their originals' object keys, so a
+//! snapshot duplicates metadata only; deleting the live file leaves the
+//! archived copy readable. Everything inside an archive folder is read-only
+//! to clients, which [`is_archived_node`] lets handlers check.
+
+use chrono::NaiveDateTime;
+use diesel::{OptionalExtension, prelude::*, result::Error as DieselError};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use thiserror::Error;
+
+use super::{
+    connection::{DbConnection, TracedQueryDsl},
+    files::{RESOURCE_TYPE_FILE_NODE, create_file_node, get_file_node, list_child_file_nodes},
+};
+use crate::models::{FileNode, FileNodeKind, NewFileNode, NewResourcePermission};
+
+/// Deepest folder nesting walked when checking for an archive ancestor.
+const MAX_ARCHIVE_DEPTH: usize = 256;
+
+/// Errors raised while taking a snapshot.
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    /// The node to snapshot does not exist or is not a folder.
+    #[error("snapshot source is not a folder")]
+    NotAFolder,
+    /// Another entry already uses the archive folder's name.
+    #[error("an entry named {0:?} is in the way of the archive folder")]
+    ContainerNameTaken(String),
+    /// A database query failed.
+    #[error(transparent)]
+    Diesel(#[from] DieselError),
+}
+
+/// What a snapshot run did.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    /// A new snapshot folder was created.
+    Taken {
+        /// Name of the dated snapshot folder.
+        name: String,
+        /// Older snapshots removed to stay within the retention limit.
+        pruned: usize,
+    },
+    /// A snapshot with the same timestamp already exists.
+    AlreadyTaken,
+}
+
+/// Name of the archive folder kept beside `source_name`.
+#[must_use]
+pub fn archive_folder_name(source_name: &str) -> String { format!("{source_name} Archive") }
+
+/// Name of the snapshot folder taken at `taken_at`, such as
+/// `2026-10-16 1430`. Names sort in the order the snapshots were taken.
+#[must_use]
+pub fn snapshot_folder_name(taken_at: NaiveDateTime) -> String {
+    taken_at.format("%Y-%m-%d %H%M").to_string()
+}
+
+/// Snapshot folder `source_id` as it stands at `taken_at`, keeping at most
+/// `keep` snapshots of it.
+///
+/// The archive folder is created on the first snapshot, with the source's
+/// grants. Archive folders found inside the source are not copied.
+///
+/// # Errors
+/// Returns [`ArchiveError::NotAFolder`] when `source_id` names no folder,
+/// [`ArchiveError::ContainerNameTaken`] when an unrelated entry has the
+/// archive folder's name, or any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn snapshot_folder(
+    conn: &mut DbConnection,
+    source_id: i32,
+    taken_at: NaiveDateTime,
+    keep: usize,
+) -> Result<SnapshotOutcome, ArchiveError> {
+    conn.transaction::<_, ArchiveError, _>(async |tx_conn| {
+        let source = get_file_node(tx_conn, source_id)
+            .await?
+            .filter(|node| node.kind == FileNodeKind::Folder.as_str())
+            .ok_or(ArchiveError::NotAFolder)?;
+        let container_id = ensure_archive_folder(tx_conn, &source).await?;
+        let name = snapshot_folder_name(taken_at);
+        if child_named(tx_conn, container_id, &name).await?.is_some() {
+            return Ok(SnapshotOutcome::AlreadyTaken);
+        }
+        let snapshot_id = create_folder(tx_conn, &name, container_id, source.creator_id).await?;
+        copy_grants(tx_conn, source.id, snapshot_id).await?;
+        copy_tree(tx_conn, source.id, snapshot_id).await?;
+        {
+            use crate::schema::file_archives::dsl as a;
+            diesel::update(a::file_archives.filter(a::container_id.eq(container_id)))
+                .set(a::last_snapshot_at.eq(taken_at))
+                .traced()
+                .execute(tx_conn)
+                .await?;
+        }
+        let pruned = prune_snapshots(tx_conn, container_id, keep).await?;
+        Ok(SnapshotOutcome::Taken { name, pruned })
+    })
+    .await
+}
+
+/// Return when folder `source_id` was last snapshotted, or `None` if it
+/// never has been.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn last_snapshot_at(
+    conn: &mut DbConnection,
+    source_id: i32,
+) -> QueryResult<Option<NaiveDateTime>> {
+    use crate::schema::file_archives::dsl as a;
+
+    Ok(a::file_archives
+        .filter(a::source_id.eq(source_id))
+        .select(a::last_snapshot_at)
+        .traced()
+        .get_result::<Option<NaiveDateTime>>(conn)
+        .await
+        .optional()?
+        .flatten())
+}
+
+/// Report whether `node_id` is an archive folder or lies inside one.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn is_archived_node(conn: &mut DbConnection, node_id: i32) -> QueryResult<bool> {
+    use crate::schema::{file_archives::dsl as a, file_nodes::dsl as f};
+
+    let containers = a::file_archives
+        .select(a::container_id)
+        .traced()
+        .load::<i32>(conn)
+        .await?;
+    if containers.is_empty() {
+        return Ok(false);
+    }
+    let mut current = Some(node_id);
+    for _ in 0..MAX_ARCHIVE_DEPTH {
+        let Some(id) = current else {
+            return Ok(false);
+        };
+        if containers.contains(&id) {
+            return Ok(true);
+        }
+        current = f::file_nodes
+            .filter(f::id.eq(id))
+            .select(f::parent_id)
+            .traced()
+            .get_result::<Option<i32>>(conn)
+            .await
+            .optional()?
+            .flatten();
+    }
+    Ok(false)
+}
+
+async fn ensure_archive_folder(
+    conn: &mut DbConnection,
+    source: &FileNode,
+) -> Result<i32, ArchiveError> {
+    use crate::schema::file_archives::dsl as a;
+
+    if let Some(container_id) = a::file_archives
+        .filter(a::source_id.eq(source.id))
+        .select(a::container_id)
+        .traced()
+        .get_result::<i32>(conn)
+        .await
+        .optional()?
+    {
+        return Ok(container_id);
+    }
+    let name = archive_folder_name(&source.name);
+    let siblings = list_child_file_nodes(conn, source.parent_id).await?;
+    if siblings.iter().any(|node| node.name == name) {
+        return Err(ArchiveError::ContainerNameTaken(name));
+    }
+    let container_id = create_node(conn, &name, source.parent_id, source.creator_id).await?;
+    diesel::insert_into(a::file_archives)
+        .values((a::source_id.eq(source.id), a::container_id.eq(container_id)))
+        .traced()
+        .execute(conn)
+        .await?;
+    copy_grants(conn, source.id, container_id).await?;
+    Ok(container_id)
+}
+
+async fn child_named(
+    conn: &mut DbConnection,
+    parent_id: i32,
+    name: &str,
+) -> QueryResult<Option<i32>> {
+    use crate::schema::file_nodes::dsl as f;
+
+    f::file_nodes
+        .filter(f::parent_id.eq(parent_id))
+        .filter(f::name.eq(name))
+        .select(f::id)
+        .traced()
+        .get_result::<i32>(conn)
+        .await
+        .optional()
+}
+
+async fn create_folder(
+    conn: &mut DbConnection,
+    name: &str,
+    parent_id: i32,
+    creator_id: i32,
+) -> QueryResult<i32> {
+    create_node(conn, name, Some(parent_id), creator_id).await
+}
+
+async fn create_node(
+    conn: &mut DbConnection,
+    name: &str,
+    parent_id: Option<i32>,
+    creator_id: i32,
+) -> QueryResult<i32> {
+    create_file_node(
+        conn,
+        &NewFileNode {
+            kind: FileNodeKind::Folder.as_str(),
+            name,
+            parent_id,
+            alias_target_id: None,
+            object_key: None,
+            size: None,
+            comment: None,
+            is_dropbox: false,
+            creator_id,
+        },
+    )
+    .await
+}
+
+/// Copy the children of `from` beneath `to`, depth first, skipping archive
+/// folders.
+async fn copy_tree(conn: &mut DbConnection, from: i32, to: i32) -> QueryResult<()> {
+    use crate::schema::file_archives::dsl as a;
+
+    let containers = a::file_archives
+        .select(a::container_id)
+        .traced()
+        .load::<i32>(conn)
+        .await?;
+    let mut pending = vec![(from, to)];
+    while let Some((source_parent, copy_parent)) = pending.pop() {
+        for child in list_child_file_nodes(conn, Some(source_parent)).await? {
+            if containers.contains(&child.id) {
+                continue;
+            }
+            let copy_id = copy_node(conn, &child, copy_parent).await?;
+            copy_grants(conn, child.id, copy_id).await?;
+            if child.kind == FileNodeKind::Folder.as_str() {
+                pending.push((child.id, copy_id));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn copy_node(conn: &mut DbConnection, node: &FileNode, parent_id: i32) -> QueryResult<i32> {
+    use crate::schema::file_nodes::dsl as f;
+
+    let copy_id = create_file_node(
+        conn,
+        &NewFileNode {
+            kind: &node.kind,
+            name: &node.name,
+            parent_id: Some(parent_id),
+            alias_target_id: node.alias_target_id,
+            object_key: node.object_key.as_deref(),
+            size: node.size,
+            comment: node.comment.as_deref(),
+            is_dropbox: node.is_dropbox,
+            creator_id: node.creator_id,
+        },
+    )
+    .await?;
+    diesel::update(f::file_nodes.filter(f::id.eq(copy_id)))
+        .set((
+            f::type_code.eq(&node.type_code),
+            f::creator_code.eq(&node.creator_code),
+            f::created_at.eq(node.created_at),
+            f::updated_at.eq(node.updated_at),
+        ))
+        .traced()
+        .execute(conn)
+        .await?;
+    Ok(copy_id)
+}
+
+/// Grant on node `to` every permission granted on node `from`.
+pub(super) async fn copy_grants(conn: &mut DbConnection, from: i32, to: i32) -> QueryResult<()> {
+    use crate::schema::resource_permissions::dsl as rp;
+
+    let grants = rp::resource_permissions
+        .filter(rp::resource_type.eq(RESOURCE_TYPE_FILE_NODE))
+        .filter(rp::resource_id.eq(from))
+        .select((rp::principal_type, rp::principal_id, rp::permission_id))
+        .traced()
+        .load::<(String, i32, i32)>(conn)
+        .await?;
+    for (principal_type, principal_id, permission_id) in &grants {
+        diesel::insert_into(rp::resource_permissions)
+            .values(&NewResourcePermission {
+                resource_type: RESOURCE_TYPE_FILE_NODE,
+                resource_id: to,
+                principal_type,
+                principal_id: *principal_id,
+                permission_id: *permission_id,
+            })
+            .on_conflict_do_nothing()
+            .traced()
+            .execute(conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Remove all but the newest `keep` snapshots in `container_id`, returning
+/// how many were removed.
+async fn prune_snapshots(
+    conn: &mut DbConnection,
+    container_id: i32,
+    keep: usize,
+) -> QueryResult<usize> {
+    let snapshots = list_child_file_nodes(conn, Some(container_id)).await?;
+    let excess = snapshots.len().saturating_sub(keep);
+    for snapshot in snapshots.iter().take(excess) {
+        delete_subtree(conn, snapshot.id).await?;
+    }
+    Ok(excess)
+}
+
+/// Delete `root_id` and everything beneath it, with their grants.
+///
+/// Stored content is left alone: the live files, or other snapshots, may
+/// still use the same object keys.
+async fn delete_subtree(conn: &mut DbConnection, root_id: i32) -> QueryResult<()> {
+    use crate::schema::{file_nodes::dsl as f, resource_permissions::dsl as rp};
+
+    let mut doomed = vec![root_id];
+    let mut next = 0;
+    while let Some(&parent_id) = doomed.get(next) {
+        let children = f::file_nodes
+            .filter(f::parent_id.eq(parent_id))
+            .select(f::id)
+            .traced()
+            .load::<i32>(conn)
+            .await?;
+        doomed.extend(children);
+        next += 1;
+    }
+    diesel::delete(
+        rp::resource_permissions
+            .filter(rp::resource_type.eq(RESOURCE_TYPE_FILE_NODE))
+            .filter(rp::resource_id.eq_any(&doomed)),
+    )
+    .traced()
+    .execute(conn)
+    .await?;
+    // Delete children before their parents so the self-reference never
+    // dangles, whether or not the backend enforces it.
+    for node_id in doomed.iter().rev() {
+        diesel::delete(f::file_nodes.filter(f::id.eq(node_id)))
+            .traced()
+            .execute(conn)
+            .await?;
+    }
+    Ok(())
+}
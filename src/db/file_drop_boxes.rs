@@ -0,0 +1,70 @@
+//! Drop boxes: folders users may deposit files into but not read back.
+//!
+//! A folder whose `is_dropbox` flag is set hides its contents, and the
+//! contents of every folder beneath it, from users without
+//! [`crate::privileges::Privileges::VIEW_DROP_BOXES`]. The flag lives on the
+//! folder alone, so [`is_in_drop_box`] walks up from a node to find it.
+
+use diesel::{OptionalExtension, prelude::*, result::QueryResult};
+use diesel_async::RunQueryDsl;
+
+use super::{
+    connection::{DbConnection, TracedQueryDsl},
+    files::{FileNodeLookupError, resolve_file_node_path},
+};
+use crate::models::FileNodeKind;
+
+/// Report whether `node_id` is a drop box or lies inside one.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn is_in_drop_box(conn: &mut DbConnection, node_id: i32) -> QueryResult<bool> {
+    use crate::schema::file_nodes::dsl as f;
+
+    let mut current = Some(node_id);
+    while let Some(id) = current {
+        let Some((is_dropbox, parent_id)) = f::file_nodes
+            .filter(f::id.eq(id))
+            .select((f::is_dropbox, f::parent_id))
+            .traced()
+            .get_result::<(bool, Option<i32>)>(conn)
+            .await
+            .optional()?
+        else {
+            return Ok(false);
+        };
+        if is_dropbox {
+            return Ok(true);
+        }
+        current = parent_id;
+    }
+    Ok(false)
+}
+
+/// Turn the drop-box flag of the folder at `path` on or off, returning
+/// whether a folder was found there.
+///
+/// # Errors
+/// Returns an error if the path is malformed or if a query fails.
+#[must_use = "handle the result"]
+pub async fn set_drop_box(
+    conn: &mut DbConnection,
+    path: &str,
+    is_dropbox: bool,
+) -> Result<bool, FileNodeLookupError> {
+    use crate::schema::file_nodes::dsl as f;
+
+    let Some(node) = resolve_file_node_path(conn, path).await? else {
+        return Ok(false);
+    };
+    if node.kind != FileNodeKind::Folder.as_str() {
+        return Ok(false);
+    }
+    diesel::update(f::file_nodes.filter(f::id.eq(node.id)))
+        .set(f::is_dropbox.eq(is_dropbox))
+        .traced()
+        .execute(conn)
+        .await?;
+    Ok(true)
+}
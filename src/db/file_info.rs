@@ -0,0 +1,287 @@
+//! File metadata lookups for Get File Info and Set File Info.
+//!
+//! The root file listing merges top-level `file_nodes` visible through
+//! `resource_permissions` with legacy `files` rows visible through `file_acl`.
+//! Metadata lookups resolve root names the same way: a visible file node wins,
+//! and legacy rows answer for names the hierarchy does not hold. Below the root
+//! only `file_nodes` exist.
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::{AsChangeset, OptionalExtension, Queryable, prelude::*, result::QueryResult};
+use diesel_async::RunQueryDsl;
+
+use super::{
+    connection::DbConnection,
+    file_listing::{find_visible_child_node, find_visible_folder},
+    files::{
+        DOWNLOAD_FILE_PERMISSION_CODE,
+        FileNodeLookupError,
+        PRINCIPAL_GROUP,
+        PRINCIPAL_USER,
+        RESOURCE_TYPE_FILE_NODE,
+    },
+};
+use crate::{
+    models::{FileNode, FileNodeKind},
+    schema::{file_nodes, files},
+};
+
+/// Metadata reported for a file or folder.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileInfo {
+    /// Name shown in the file listing.
+    pub name: String,
+    /// Whether the entry is a folder.
+    pub is_folder: bool,
+    /// Size in bytes; zero for folders.
+    pub size: i64,
+    /// Classic Mac OS type code, or empty when unknown.
+    pub type_code: String,
+    /// Classic Mac OS creator code, or empty when unknown.
+    pub creator_code: String,
+    /// User-editable comment.
+    pub comment: Option<String>,
+    /// Creation timestamp, when recorded.
+    pub created_at: Option<NaiveDateTime>,
+    /// Last modification timestamp, when recorded.
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Table holding a visible entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileInfoSource {
+    /// Row in `file_nodes` with the given identifier.
+    Node(i32),
+    /// Row in the legacy `files` table with the given identifier.
+    Legacy(i32),
+}
+
+/// A visible entry together with where it is stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VisibleFileInfo {
+    /// Table and row that hold the entry.
+    pub source: FileInfoSource,
+    /// Metadata reported to clients.
+    pub info: FileInfo,
+}
+
+/// Changes requested for a file or folder.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FileInfoUpdate<'a> {
+    /// Replacement name, if renaming.
+    pub new_name: Option<&'a str>,
+    /// Replacement comment, if editing it.
+    pub comment: Option<&'a str>,
+}
+
+#[derive(AsChangeset)]
+#[diesel(table_name = file_nodes)]
+struct NodeChanges<'a> {
+    name: Option<&'a str>,
+    comment: Option<&'a str>,
+    updated_at: NaiveDateTime,
+}
+
+#[derive(AsChangeset)]
+#[diesel(table_name = files)]
+struct LegacyChanges<'a> {
+    name: Option<&'a str>,
+    comment: Option<&'a str>,
+    updated_at: NaiveDateTime,
+}
+
+#[derive(Queryable)]
+struct LegacyFileRow {
+    id: i32,
+    name: String,
+    size: i64,
+    type_code: String,
+    creator_code: String,
+    comment: Option<String>,
+    created_at: Option<NaiveDateTime>,
+    updated_at: Option<NaiveDateTime>,
+}
+
+impl From<FileNode> for VisibleFileInfo {
+    fn from(node: FileNode) -> Self {
+        Self {
+            source: FileInfoSource::Node(node.id),
+            info: FileInfo {
+                is_folder: node.kind == FileNodeKind::Folder.as_str(),
+                name: node.name,
+                size: node.size.unwrap_or(0),
+                type_code: node.type_code,
+                creator_code: node.creator_code,
+                comment: node.comment,
+                created_at: Some(node.created_at),
+                updated_at: Some(node.updated_at),
+            },
+        }
+    }
+}
+
+impl From<LegacyFileRow> for VisibleFileInfo {
+    fn from(row: LegacyFileRow) -> Self {
+        Self {
+            source: FileInfoSource::Legacy(row.id),
+            info: FileInfo {
+                name: row.name,
+                is_folder: false,
+                size: row.size,
+                type_code: row.type_code,
+                creator_code: row.creator_code,
+                comment: row.comment,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            },
+        }
+    }
+}
+
+/// Find the root-level entry called `name` that `user_id` may see.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn find_visible_root_file_info(
+    conn: &mut DbConnection,
+    user_id: i32,
+    name: &str,
+) -> QueryResult<Option<VisibleFileInfo>> {
+    if let Some(node) = visible_root_node(conn, user_id, name).await? {
+        return Ok(Some(node.into()));
+    }
+    let legacy = visible_legacy_file(conn, user_id, name).await?;
+    Ok(legacy.map(Into::into))
+}
+
+/// Find the entry called `name` in the folder at `folder` that `user_id` may
+/// see.
+///
+/// An empty `folder` addresses the root. Below the root the folder itself must
+/// also be visible.
+///
+/// # Errors
+/// Returns an error if the folder path is malformed or if the lookup fails.
+#[must_use = "handle the result"]
+pub async fn find_visible_file_info(
+    conn: &mut DbConnection,
+    user_id: i32,
+    folder: &[String],
+    name: &str,
+) -> Result<Option<VisibleFileInfo>, FileNodeLookupError> {
+    if folder.is_empty() {
+        return Ok(find_visible_root_file_info(conn, user_id, name).await?);
+    }
+    let Some(parent) = find_visible_folder(conn, user_id, folder).await? else {
+        return Ok(None);
+    };
+    let child = find_visible_child_node(conn, user_id, parent.id, name).await?;
+    Ok(child.map(Into::into))
+}
+
+/// Apply `update` to the entry identified by `source`.
+///
+/// The modification timestamp is refreshed even when `update` changes
+/// nothing else.
+///
+/// # Errors
+/// Returns any error produced by the database, including unique-constraint
+/// violations when the new name is already taken.
+#[must_use = "handle the result"]
+pub async fn update_file_info(
+    conn: &mut DbConnection,
+    source: FileInfoSource,
+    update: FileInfoUpdate<'_>,
+) -> QueryResult<()> {
+    let now = Utc::now().naive_utc();
+    match source {
+        FileInfoSource::Node(id) => {
+            diesel::update(file_nodes::table.filter(file_nodes::id.eq(id)))
+                .set(&NodeChanges {
+                    name: update.new_name,
+                    comment: update.comment,
+                    updated_at: now,
+                })
+                .execute(conn)
+                .await?;
+        }
+        FileInfoSource::Legacy(id) => {
+            diesel::update(files::table.filter(files::id.eq(id)))
+                .set(&LegacyChanges {
+                    name: update.new_name,
+                    comment: update.comment,
+                    updated_at: now,
+                })
+                .execute(conn)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn visible_root_node(
+    conn: &mut DbConnection,
+    user_id: i32,
+    name: &str,
+) -> QueryResult<Option<FileNode>> {
+    use crate::schema::{
+        file_nodes::dsl as f,
+        permissions::dsl as p,
+        resource_permissions::dsl as rp,
+        user_groups::dsl as ug,
+    };
+
+    let group_ids = ug::user_groups
+        .filter(ug::user_id.eq(user_id))
+        .select(ug::group_id);
+
+    f::file_nodes
+        .inner_join(
+            rp::resource_permissions.on(rp::resource_type
+                .eq(RESOURCE_TYPE_FILE_NODE)
+                .and(rp::resource_id.eq(f::id))),
+        )
+        .inner_join(p::permissions.on(p::id.eq(rp::permission_id)))
+        .filter(f::parent_id.is_null())
+        .filter(f::name.eq(name))
+        .filter(p::code.eq(DOWNLOAD_FILE_PERMISSION_CODE))
+        .filter(
+            rp::principal_type
+                .eq(PRINCIPAL_USER)
+                .and(rp::principal_id.eq(user_id))
+                .or(rp::principal_type
+                    .eq(PRINCIPAL_GROUP)
+                    .and(rp::principal_id.eq_any(group_ids))),
+        )
+        .select(file_nodes::all_columns)
+        .first::<FileNode>(conn)
+        .await
+        .optional()
+}
+
+async fn visible_legacy_file(
+    conn: &mut DbConnection,
+    user_id: i32,
+    name: &str,
+) -> QueryResult<Option<LegacyFileRow>> {
+    use crate::schema::{file_acl::dsl as acl, files::dsl as lf};
+
+    lf::files
+        .inner_join(acl::file_acl.on(acl::file_id.eq(lf::id)))
+        .filter(acl::user_id.eq(user_id))
+        .filter(lf::name.eq(name))
+        .select((
+            lf::id,
+            lf::name,
+            lf::size,
+            lf::type_code,
+            lf::creator_code,
+            lf::comment,
+            lf::created_at,
+            lf::updated_at,
+        ))
+        .first::<LegacyFileRow>(conn)
+        .await
+        .optional()
+}
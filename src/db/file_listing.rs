@@ -0,0 +1,165 @@
+//! Folder lookups and listings below the root of the file area.
+//!
+//! Legacy `files` rows have no parent, so everything beneath the root comes
+//! from `file_nodes`. Folders are resolved with the recursive path CTE in
+//! [`super::file_path`], and both the folder and each child must be granted
+//! to the user through `resource_permissions`, as at the root.
+
+use diesel::{OptionalExtension, prelude::*, result::QueryResult};
+use diesel_async::RunQueryDsl;
+use tracing::debug;
+
+use super::{
+    connection::{DbConnection, TracedQueryDsl},
+    files::{
+        DOWNLOAD_FILE_PERMISSION_CODE,
+        FileNodeLookupError,
+        PRINCIPAL_GROUP,
+        PRINCIPAL_USER,
+        RESOURCE_TYPE_FILE_NODE,
+        resolve_file_node_path,
+    },
+};
+use crate::models::{FileNode, FileNodeKind, VisibleFileNode};
+
+/// Resolve `segments` to a folder that `user_id` may see.
+///
+/// Returns `Ok(None)` when no node lives at the path, the node is not a
+/// folder, or the user has no Download File grant for it.
+///
+/// # Errors
+/// Returns an error if the path is malformed or if the lookup fails.
+#[must_use = "handle the result"]
+pub async fn find_visible_folder(
+    conn: &mut DbConnection,
+    user_id: i32,
+    segments: &[String],
+) -> Result<Option<FileNode>, FileNodeLookupError> {
+    let path = segments.join("/");
+    let Some(node) = resolve_file_node_path(conn, &path).await? else {
+        return Ok(None);
+    };
+    if node.kind != FileNodeKind::Folder.as_str() {
+        return Ok(None);
+    }
+    let visible = is_file_node_visible(conn, user_id, node.id).await?;
+    Ok(visible.then_some(node))
+}
+
+/// Find the child of folder `parent_id` called `name` that `user_id` may see.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn find_visible_child_node(
+    conn: &mut DbConnection,
+    user_id: i32,
+    parent_id: i32,
+    name: &str,
+) -> QueryResult<Option<FileNode>> {
+    use crate::schema::file_nodes::dsl as f;
+
+    let Some(node) = f::file_nodes
+        .filter(f::parent_id.eq(parent_id))
+        .filter(f::name.eq(name))
+        .limit(1)
+        .traced()
+        .get_result::<FileNode>(conn)
+        .await
+        .optional()?
+    else {
+        return Ok(None);
+    };
+    let visible = is_file_node_visible(conn, user_id, node.id).await?;
+    Ok(visible.then_some(node))
+}
+
+/// List the children of folder `parent_id` that `user_id` may see.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn list_visible_child_file_nodes_for_user(
+    conn: &mut DbConnection,
+    user_id: i32,
+    parent_id: i32,
+) -> QueryResult<Vec<VisibleFileNode>> {
+    use crate::schema::{
+        file_nodes::dsl as f,
+        permissions::dsl as p,
+        resource_permissions::dsl as rp,
+        user_groups::dsl as ug,
+    };
+
+    let group_ids = ug::user_groups
+        .filter(ug::user_id.eq(user_id))
+        .select(ug::group_id);
+
+    let visible = f::file_nodes
+        .inner_join(
+            rp::resource_permissions.on(rp::resource_type
+                .eq(RESOURCE_TYPE_FILE_NODE)
+                .and(rp::resource_id.eq(f::id))),
+        )
+        .inner_join(p::permissions.on(p::id.eq(rp::permission_id)))
+        .filter(f::parent_id.eq(parent_id))
+        .filter(p::code.eq(DOWNLOAD_FILE_PERMISSION_CODE))
+        .filter(
+            rp::principal_type
+                .eq(PRINCIPAL_USER)
+                .and(rp::principal_id.eq(user_id))
+                .or(rp::principal_type
+                    .eq(PRINCIPAL_GROUP)
+                    .and(rp::principal_id.eq_any(group_ids))),
+        )
+        .select((f::id, f::name, f::kind))
+        .distinct()
+        .order(f::name.asc())
+        .traced()
+        .load::<VisibleFileNode>(conn)
+        .await?;
+    debug!(
+        user_id,
+        parent_id,
+        file_count = visible.len(),
+        "folder visibility query completed"
+    );
+    Ok(visible)
+}
+
+pub(super) async fn is_file_node_visible(
+    conn: &mut DbConnection,
+    user_id: i32,
+    node_id: i32,
+) -> QueryResult<bool> {
+    use crate::schema::{
+        permissions::dsl as p,
+        resource_permissions::dsl as rp,
+        user_groups::dsl as ug,
+    };
+
+    let group_ids = ug::user_groups
+        .filter(ug::user_id.eq(user_id))
+        .select(ug::group_id);
+
+    let grant = rp::resource_permissions
+        .inner_join(p::permissions.on(p::id.eq(rp::permission_id)))
+        .filter(rp::resource_type.eq(RESOURCE_TYPE_FILE_NODE))
+        .filter(rp::resource_id.eq(node_id))
+        .filter(p::code.eq(DOWNLOAD_FILE_PERMISSION_CODE))
+        .filter(
+            rp::principal_type
+                .eq(PRINCIPAL_USER)
+                .and(rp::principal_id.eq(user_id))
+                .or(rp::principal_type
+                    .eq(PRINCIPAL_GROUP)
+                    .and(rp::principal_id.eq_any(group_ids))),
+        )
+        .select(rp::permission_id)
+        .limit(1)
+        .traced()
+        .get_result::<i32>(conn)
+        .await
+        .optional()?;
+    Ok(grant.is_some())
+}
@@ -0,0 +1,219 @@
+//! Deleting and moving entries in the file area.
+//!
+//! Each operation runs in one database transaction. Grants and aliases that
+//! point at a deleted node are removed explicitly rather than through foreign
+//! key cascades, because `SQLite` connections do not enforce them by default.
+//! File content is addressed by a stable object key, so a move only rewrites
+//! `parent_id`; a delete hands the released key back to the caller once no
+//! archived copy still uses it.
+
+use chrono::Utc;
+use diesel::{OptionalExtension, prelude::*, result::Error as DieselError};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use thiserror::Error;
+
+use super::{
+    connection::{DbConnection, TracedQueryDsl},
+    file_info::FileInfoSource,
+    files::RESOURCE_TYPE_FILE_NODE,
+};
+
+/// Errors raised while deleting or moving an entry.
+#[derive(Debug, Error)]
+pub enum FileMutationError {
+    /// The folder still has children.
+    #[error("folder is not empty")]
+    FolderNotEmpty,
+    /// The destination lies inside the folder being moved.
+    #[error("cannot move a folder into itself")]
+    MoveIntoSelf,
+    /// The folder already holds an entry of that name.
+    #[error("name already taken")]
+    NameTaken,
+    /// A database query failed.
+    #[error(transparent)]
+    Diesel(#[from] DieselError),
+}
+
+/// Delete the entry identified by `source`.
+///
+/// Folders must be empty. Deleting a file node also removes any aliases that
+/// target it. Returns the object key of the removed file content, if any, so
+/// the caller can release it from storage.
+///
+/// # Errors
+/// Returns [`FileMutationError::FolderNotEmpty`] for a folder with children,
+/// or any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn delete_file_entry(
+    conn: &mut DbConnection,
+    source: FileInfoSource,
+) -> Result<Option<String>, FileMutationError> {
+    conn.transaction::<_, FileMutationError, _>(async |tx_conn| match source {
+        FileInfoSource::Node(id) => delete_node(tx_conn, id).await,
+        FileInfoSource::Legacy(id) => delete_legacy_file(tx_conn, id).await,
+    })
+    .await
+}
+
+/// Move file node `node_id` into folder `new_parent`, or to the root when
+/// `new_parent` is `None`.
+///
+/// # Errors
+/// Returns [`FileMutationError::MoveIntoSelf`] when `new_parent` is the node
+/// or one of its descendants, or any error produced by the database,
+/// including unique-constraint violations when the destination already holds
+/// an entry with the same name.
+#[must_use = "handle the result"]
+pub async fn move_file_node(
+    conn: &mut DbConnection,
+    node_id: i32,
+    new_parent: Option<i32>,
+) -> Result<(), FileMutationError> {
+    use crate::schema::file_nodes::dsl as f;
+
+    conn.transaction::<_, FileMutationError, _>(async |tx_conn| {
+        if let Some(parent_id) = new_parent
+            && is_same_or_descendant(tx_conn, parent_id, node_id).await?
+        {
+            return Err(FileMutationError::MoveIntoSelf);
+        }
+        diesel::update(f::file_nodes.filter(f::id.eq(node_id)))
+            .set((
+                f::parent_id.eq(new_parent),
+                f::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .traced()
+            .execute(tx_conn)
+            .await?;
+        Ok(())
+    })
+    .await
+}
+
+async fn delete_node(
+    conn: &mut DbConnection,
+    node_id: i32,
+) -> Result<Option<String>, FileMutationError> {
+    use crate::schema::file_nodes::dsl as f;
+
+    let children = f::file_nodes
+        .filter(f::parent_id.eq(node_id))
+        .count()
+        .traced()
+        .get_result::<i64>(conn)
+        .await?;
+    if children > 0 {
+        return Err(FileMutationError::FolderNotEmpty);
+    }
+    let aliases = f::file_nodes
+        .filter(f::alias_target_id.eq(node_id))
+        .select(f::id)
+        .traced()
+        .load::<i32>(conn)
+        .await?;
+    for alias_id in aliases {
+        remove_node_row(conn, alias_id).await?;
+    }
+    let object_key = f::file_nodes
+        .filter(f::id.eq(node_id))
+        .select(f::object_key)
+        .limit(1)
+        .traced()
+        .get_result::<Option<String>>(conn)
+        .await
+        .optional()?
+        .flatten();
+    remove_node_row(conn, node_id).await?;
+    // Archive snapshots share their originals' content, so the key is only
+    // released once no node refers to it.
+    let Some(key) = object_key else {
+        return Ok(None);
+    };
+    let still_used = f::file_nodes
+        .filter(f::object_key.eq(&key))
+        .count()
+        .traced()
+        .get_result::<i64>(conn)
+        .await?;
+    Ok((still_used == 0).then_some(key))
+}
+
+async fn remove_node_row(conn: &mut DbConnection, node_id: i32) -> Result<(), DieselError> {
+    use crate::schema::{
+        file_archives::dsl as a,
+        file_nodes::dsl as f,
+        resource_permissions::dsl as rp,
+    };
+
+    diesel::delete(
+        rp::resource_permissions
+            .filter(rp::resource_type.eq(RESOURCE_TYPE_FILE_NODE))
+            .filter(rp::resource_id.eq(node_id)),
+    )
+    .traced()
+    .execute(conn)
+    .await?;
+    // Snapshots of a deleted folder stay in its archive folder.
+    diesel::update(a::file_archives.filter(a::source_id.eq(node_id)))
+        .set(a::source_id.eq(None::<i32>))
+        .traced()
+        .execute(conn)
+        .await?;
+    diesel::delete(f::file_nodes.filter(f::id.eq(node_id)))
+        .traced()
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn delete_legacy_file(
+    conn: &mut DbConnection,
+    file_id: i32,
+) -> Result<Option<String>, FileMutationError> {
+    use crate::schema::{file_acl::dsl as acl, files::dsl as lf};
+
+    let object_key = lf::files
+        .filter(lf::id.eq(file_id))
+        .select(lf::object_key)
+        .limit(1)
+        .traced()
+        .get_result::<String>(conn)
+        .await
+        .optional()?;
+    diesel::delete(acl::file_acl.filter(acl::file_id.eq(file_id)))
+        .traced()
+        .execute(conn)
+        .await?;
+    diesel::delete(lf::files.filter(lf::id.eq(file_id)))
+        .traced()
+        .execute(conn)
+        .await?;
+    Ok(object_key)
+}
+
+/// Walk up from `start` and report whether `node_id` is on the way to the root.
+async fn is_same_or_descendant(
+    conn: &mut DbConnection,
+    start: i32,
+    node_id: i32,
+) -> Result<bool, DieselError> {
+    use crate::schema::file_nodes::dsl as f;
+
+    let mut current = Some(start);
+    while let Some(id) = current {
+        if id == node_id {
+            return Ok(true);
+        }
+        current = f::file_nodes
+            .filter(f::id.eq(id))
+            .select(f::parent_id)
+            .limit(1)
+            .traced()
+            .get_result::<Option<i32>>(conn)
+            .await
+            .optional()?
+            .flatten();
+    }
+    Ok(false)
+}
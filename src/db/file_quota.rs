@@ -0,0 +1,52 @@
+//! Accounting of the file content each account stores.
+//!
+//! An account's usage is the total size of the files it created. Archive
+//! snapshots copy file nodes without copying their content, so nodes sharing
+//! an `object_key` are counted once; deleting a file frees its bytes once no
+//! snapshot lists it either.
+
+use diesel::{prelude::*, result::QueryResult};
+use diesel_async::RunQueryDsl;
+
+use super::connection::DbConnection;
+use crate::models::FileNodeKind;
+
+/// Return the bytes of file content `user_id` has uploaded and not deleted.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn stored_bytes_for_user(conn: &mut DbConnection, user_id: i32) -> QueryResult<u64> {
+    use crate::schema::file_nodes::dsl as f;
+    let stored: Vec<(Option<String>, Option<i64>)> = f::file_nodes
+        .filter(f::creator_id.eq(user_id))
+        .filter(f::kind.eq(FileNodeKind::File.as_str()))
+        .filter(f::object_key.is_not_null())
+        .select((f::object_key, f::size))
+        .distinct()
+        .load(conn)
+        .await?;
+    Ok(stored
+        .into_iter()
+        .filter_map(|(_, size)| size)
+        .map(|size| u64::try_from(size).unwrap_or_default())
+        .fold(0, u64::saturating_add))
+}
+
+/// Replace the quota of the user named `name`, returning the number of rows
+/// changed; `None` removes the limit.
+///
+/// # Errors
+/// Returns any error produced by the update query.
+#[must_use = "handle the result"]
+pub async fn set_quota_bytes(
+    conn: &mut DbConnection,
+    name: &str,
+    quota_bytes: Option<i64>,
+) -> QueryResult<usize> {
+    use crate::schema::users::dsl as u;
+    diesel::update(u::users.filter(u::username.eq(name)))
+        .set(u::quota_bytes.eq(quota_bytes))
+        .execute(conn)
+        .await
+}
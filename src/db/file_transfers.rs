@@ -0,0 +1,185 @@
+//! File content lookups for Download File and new entries from Upload File.
+//!
+//! A download reads the object key of the entry the client named, following
+//! an alias to its target. A finished upload becomes a file node in one
+//! transaction: the node inherits its folder's grants, as archive snapshots
+//! do, and the uploader may always see what they uploaded.
+
+use chrono::Utc;
+use diesel::{OptionalExtension, prelude::*, result::QueryResult};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+
+use super::{
+    connection::{DbConnection, TracedQueryDsl},
+    file_archives::copy_grants,
+    file_info::FileInfoSource,
+    file_mutations::FileMutationError,
+    files::{
+        PRINCIPAL_USER,
+        RESOURCE_TYPE_FILE_NODE,
+        create_file_node,
+        download_file_permission,
+        grant_resource_permission,
+        resolve_alias_target,
+        seed_permission,
+    },
+};
+use crate::models::{FileNodeKind, NewFileNode, NewResourcePermission};
+
+/// A file whose content has been stored and which now needs an entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UploadedFile<'a> {
+    /// Folder receiving the file, or `None` for the root.
+    pub parent_id: Option<i32>,
+    /// Name of the new entry.
+    pub name: &'a str,
+    /// Key the content was stored under.
+    pub object_key: &'a str,
+    /// Size of the content in bytes.
+    pub size: i64,
+    /// Classic Mac OS type code from the upload.
+    pub type_code: &'a str,
+    /// Classic Mac OS creator code from the upload.
+    pub creator_code: &'a str,
+    /// Comment sent with the upload.
+    pub comment: Option<&'a str>,
+    /// Account that uploaded the file.
+    pub creator_id: i32,
+}
+
+/// Return the object key holding the content of the entry at `source`.
+///
+/// Aliases answer with their target's key. Folders, and files whose content
+/// was never stored, have none.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn file_object_key(
+    conn: &mut DbConnection,
+    source: FileInfoSource,
+) -> QueryResult<Option<String>> {
+    use crate::schema::{file_nodes::dsl as f, files::dsl as lf};
+
+    match source {
+        FileInfoSource::Node(id) => {
+            if let Some(target) = resolve_alias_target(conn, id).await? {
+                return Ok(target.object_key);
+            }
+            Ok(f::file_nodes
+                .filter(f::id.eq(id))
+                .select(f::object_key)
+                .traced()
+                .get_result::<Option<String>>(conn)
+                .await
+                .optional()?
+                .flatten())
+        }
+        FileInfoSource::Legacy(id) => lf::files
+            .filter(lf::id.eq(id))
+            .select(lf::object_key)
+            .traced()
+            .get_result::<String>(conn)
+            .await
+            .optional(),
+    }
+}
+
+/// Report whether the folder `parent_id`, or the root when `None`, already
+/// holds an entry called `name`, whether or not it is visible.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn is_file_name_taken(
+    conn: &mut DbConnection,
+    parent_id: Option<i32>,
+    name: &str,
+) -> QueryResult<bool> {
+    use crate::schema::{file_nodes::dsl as f, files::dsl as lf};
+
+    let nodes = match parent_id {
+        Some(id) => f::file_nodes.filter(f::parent_id.eq(id)).into_boxed(),
+        None => f::file_nodes.filter(f::parent_id.is_null()).into_boxed(),
+    };
+    let in_hierarchy = nodes
+        .filter(f::name.eq(name))
+        .count()
+        .traced()
+        .get_result::<i64>(conn)
+        .await?;
+    if in_hierarchy > 0 || parent_id.is_some() {
+        return Ok(in_hierarchy > 0);
+    }
+    let legacy = lf::files
+        .filter(lf::name.eq(name))
+        .count()
+        .traced()
+        .get_result::<i64>(conn)
+        .await?;
+    Ok(legacy > 0)
+}
+
+/// Create the entry for an uploaded file and return its identifier.
+///
+/// The entry is granted everything its folder is, and the uploader is
+/// granted Download File on it.
+///
+/// # Errors
+/// Returns [`FileMutationError::NameTaken`] if an entry of that name
+/// appeared while the content was being received, or any error produced by
+/// the database.
+#[must_use = "handle the result"]
+pub async fn create_uploaded_file(
+    conn: &mut DbConnection,
+    upload: &UploadedFile<'_>,
+) -> Result<i32, FileMutationError> {
+    use crate::schema::file_nodes::dsl as f;
+
+    conn.transaction::<_, FileMutationError, _>(async |tx_conn| {
+        if is_file_name_taken(tx_conn, upload.parent_id, upload.name).await? {
+            return Err(FileMutationError::NameTaken);
+        }
+        let node_id = create_file_node(
+            tx_conn,
+            &NewFileNode {
+                kind: FileNodeKind::File.as_str(),
+                name: upload.name,
+                parent_id: upload.parent_id,
+                alias_target_id: None,
+                object_key: Some(upload.object_key),
+                size: Some(upload.size),
+                comment: upload.comment,
+                is_dropbox: false,
+                creator_id: upload.creator_id,
+            },
+        )
+        .await?;
+        diesel::update(f::file_nodes.filter(f::id.eq(node_id)))
+            .set((
+                f::type_code.eq(upload.type_code),
+                f::creator_code.eq(upload.creator_code),
+                f::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .traced()
+            .execute(tx_conn)
+            .await?;
+        if let Some(parent_id) = upload.parent_id {
+            copy_grants(tx_conn, parent_id, node_id).await?;
+        }
+        let permission_id = seed_permission(tx_conn, &download_file_permission()).await?;
+        grant_resource_permission(
+            tx_conn,
+            &NewResourcePermission {
+                resource_type: RESOURCE_TYPE_FILE_NODE,
+                resource_id: node_id,
+                principal_type: PRINCIPAL_USER,
+                principal_id: upload.creator_id,
+                permission_id,
+            },
+        )
+        .await?;
+        Ok(node_id)
+    })
+    .await
+}
@@ -31,10 +31,10 @@ use crate::models::{
     NewUserGroup,
     VisibleFileNode,
 };
-const RESOURCE_TYPE_FILE_NODE: &str = "file_node";
-const PRINCIPAL_USER: &str = "user";
-const PRINCIPAL_GROUP: &str = "group";
-const DOWNLOAD_FILE_PERMISSION_CODE: i32 = 2;
+pub(super) const RESOURCE_TYPE_FILE_NODE: &str = "file_node";
+pub(super) const PRINCIPAL_USER: &str = "user";
+pub(super) const PRINCIPAL_GROUP: &str = "group";
+pub(super) const DOWNLOAD_FILE_PERMISSION_CODE: i32 = 2;
 const DOWNLOAD_FILE_PERMISSION_NAME: &str = "download_file";
 const DOWNLOAD_FILE_PERMISSION_DESCRIPTION: &str = "List or download a file node";
 
@@ -0,0 +1,67 @@
+//! Routine database maintenance.
+//!
+//! A maintenance run reclaims space left by deleted rows, refreshes the
+//! statistics the query planner relies on, and rebuilds the indexes of the
+//! tables searches scan. The statements differ per backend: `SQLite` runs
+//! `VACUUM` and `ANALYZE`, while `PostgreSQL` runs `VACUUM (ANALYZE)`. Only
+//! the tables searches scan are reindexed, because `REINDEX` blocks writes to
+//! each table while it runs.
+
+use std::time::{Duration, Instant};
+
+use cfg_if::cfg_if;
+use diesel::{result::QueryResult, sql_query};
+use diesel_async::RunQueryDsl;
+
+use super::connection::DbConnection;
+
+cfg_if! {
+    if #[cfg(feature = "sqlite")] {
+        const STATEMENTS: &[&str] = &[
+            "VACUUM",
+            "ANALYZE",
+            "REINDEX file_nodes",
+            "REINDEX news_articles",
+        ];
+    } else {
+        const STATEMENTS: &[&str] = &[
+            "VACUUM (ANALYZE)",
+            "REINDEX TABLE file_nodes",
+            "REINDEX TABLE news_articles",
+        ];
+    }
+}
+
+/// One statement of a maintenance run and how long it took.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaintenanceStep {
+    /// SQL statement that ran.
+    pub statement: &'static str,
+    /// Time the statement took.
+    pub elapsed: Duration,
+}
+
+/// Return the statements [`run_maintenance`] executes, in order.
+#[must_use]
+pub const fn maintenance_statements() -> &'static [&'static str] { STATEMENTS }
+
+/// Run each maintenance statement in turn, stopping at the first failure.
+///
+/// The connection must not be inside a transaction, since neither backend
+/// allows `VACUUM` there.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn run_maintenance(conn: &mut DbConnection) -> QueryResult<Vec<MaintenanceStep>> {
+    let mut steps = Vec::with_capacity(STATEMENTS.len());
+    for &statement in STATEMENTS {
+        let started = Instant::now();
+        sql_query(statement).execute(conn).await?;
+        steps.push(MaintenanceStep {
+            statement,
+            elapsed: started.elapsed(),
+        });
+    }
+    Ok(steps)
+}
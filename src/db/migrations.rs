@@ -13,6 +13,13 @@ use tracing::info;
 
 use super::connection::{DbConnection, MIGRATIONS};
 
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+#[path = "online_migrations.rs"]
+mod online;
+
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+pub use self::online::{OnlinePlan, OnlineStep, online_plans, run_online_migrations};
+
 #[derive(Debug)]
 struct MigrationHarnessError(Box<dyn StdError + Send + Sync>);
 
@@ -367,3 +374,44 @@ pub async fn apply_migrations(
 #[cfg(test)]
 #[path = "migrations_tests.rs"]
 mod tests;
+
+/// Report whether any embedded migration has yet to be applied.
+///
+/// # Errors
+///
+/// Returns any error raised while reading the applied migrations.
+#[cfg(feature = "sqlite")]
+#[must_use = "handle the result"]
+pub async fn migrations_pending(conn: &mut DbConnection, _database_url: &str) -> QueryResult<bool> {
+    conn.spawn_blocking(|inner| {
+        inner
+            .has_pending_migration(MIGRATIONS)
+            .map_err(wrap_harness_error)
+    })
+    .await
+}
+
+/// Report whether any embedded migration has yet to be applied.
+///
+/// The migration harness needs a synchronous connection, so this opens one
+/// to `url` on a blocking thread, as [`run_migrations`] does.
+///
+/// # Errors
+///
+/// Returns any error raised while connecting or reading the applied
+/// migrations.
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+#[must_use = "handle the result"]
+pub async fn migrations_pending(conn: &mut DbConnection, url: &str) -> QueryResult<bool> {
+    let _ = conn;
+    let owned_url = url.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let mut pg_conn =
+            diesel::pg::PgConnection::establish(&owned_url).map_err(wrap_connection_error)?;
+        pg_conn
+            .has_pending_migration(MIGRATIONS)
+            .map_err(wrap_harness_error)
+    })
+    .await
+    .map_err(wrap_executor_error)?
+}
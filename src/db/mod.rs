@@ -4,34 +4,114 @@
 //! running embedded migrations, auditing backend capabilities, and executing
 //! application queries grouped by domain concerns.
 
+mod article_mutations;
 mod articles;
 mod audit;
+mod bans;
 mod bundles;
 mod categories;
 mod connection;
+mod download_credits;
+mod file_archives;
+mod file_drop_boxes;
+mod file_info;
+mod file_listing;
+mod file_mutations;
 mod file_path;
+mod file_quota;
+mod file_transfers;
 mod files;
 mod insert;
+mod maintenance;
 mod migrations;
+mod news_digests;
+mod news_linkage;
+mod news_lock;
+mod news_roots;
+mod news_structure;
+mod outbox;
 mod paths;
+mod pool_metrics;
 
 #[cfg(test)]
 mod schema_alignment_tests;
+mod search;
+mod session_tokens;
 
 #[cfg(test)]
 mod tests;
 
+mod transfer_stats;
 mod users;
 
 #[cfg(feature = "postgres")]
 pub use self::audit::audit_postgres_features;
 #[cfg(feature = "sqlite")]
 pub use self::audit::audit_sqlite_features;
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+pub use self::migrations::{OnlinePlan, OnlineStep, online_plans, run_online_migrations};
 pub use self::{
-    articles::{CreateRootArticleParams, create_root_article, get_article, list_article_titles},
-    bundles::{create_bundle, list_names_at_path},
+    article_mutations::delete_article,
+    articles::{
+        CreateRootArticleParams,
+        create_reply_article,
+        create_root_article,
+        get_article,
+        get_article_body,
+        list_article_titles,
+    },
+    bans::{
+        BanTarget,
+        create_ban,
+        find_active_ban,
+        list_active_address_bans,
+        list_bans,
+        remove_ban,
+    },
+    bundles::{NewsEntryKind, NewsListingRow, create_bundle, list_names_at_path},
     categories::create_category,
-    connection::{Backend, DbConnection, DbPool, MIGRATIONS, establish_pool},
+    connection::{
+        Backend,
+        DbConnection,
+        DbPool,
+        MIGRATIONS,
+        PoolSettings,
+        QueryTraceId,
+        Traced,
+        TracedQueryDsl,
+        current_query_trace_id,
+        establish_pool,
+        establish_pool_with,
+        with_query_trace,
+    },
+    download_credits::{adjust_download_credits, get_download_credits, set_download_credits},
+    file_archives::{
+        ArchiveError,
+        SnapshotOutcome,
+        archive_folder_name,
+        is_archived_node,
+        last_snapshot_at,
+        snapshot_folder,
+        snapshot_folder_name,
+    },
+    file_drop_boxes::{is_in_drop_box, set_drop_box},
+    file_info::{
+        FileInfo,
+        FileInfoSource,
+        FileInfoUpdate,
+        VisibleFileInfo,
+        find_visible_file_info,
+        find_visible_root_file_info,
+        update_file_info,
+    },
+    file_listing::{
+        find_visible_child_node,
+        find_visible_folder,
+        list_visible_child_file_nodes_for_user,
+    },
+    file_mutations::{FileMutationError, delete_file_entry, move_file_node},
+    file_quota::{set_quota_bytes, stored_bytes_for_user},
+    file_transfers::{UploadedFile, create_uploaded_file, file_object_key, is_file_name_taken},
     files::{
         FileNodeLookupError,
         add_user_to_group,
@@ -46,7 +126,87 @@ pub use self::{
         resolve_file_node_path,
         seed_permission,
     },
-    migrations::{apply_migrations, run_migrations},
-    paths::PathLookupError,
-    users::{create_user, get_user_by_id, get_user_by_name},
+    maintenance::{MaintenanceStep, maintenance_statements, run_maintenance},
+    migrations::{apply_migrations, migrations_pending, run_migrations},
+    news_digests::{
+        DigestArticle,
+        DigestSubscription,
+        NewsDigestSubscriber,
+        get_news_digest,
+        mark_digest_sent,
+        new_digest_articles,
+        news_digest_subscribers,
+        store_pending_digest,
+        subscribe_news_digest,
+        take_pending_digest,
+        unsubscribe_news_digest,
+    },
+    news_linkage::{
+        ArticleLinks,
+        LinkField,
+        LinkRepair,
+        check_news_linkage,
+        plan_link_repairs,
+        repair_news_linkage,
+    },
+    news_roots::{
+        NewsRoot,
+        PRIMARY_NEWS_ROOT,
+        create_news_root,
+        list_news_roots,
+        news_root_exists,
+    },
+    news_structure::{
+        NewsDeletion,
+        NewsItem,
+        NewsStructureError,
+        create_news_bundle,
+        create_news_category,
+        delete_news_item,
+        find_news_item,
+    },
+    outbox::{
+        OutboxEvent,
+        OutboxRecord,
+        advance_outbox_cursor,
+        enqueue_outbox_event,
+        mark_outbox_delivered,
+        pending_outbox_events,
+        prune_outbox,
+    },
+    paths::{NewsPath, PathLookupError},
+    pool_metrics::{
+        PoolMetricsSnapshot,
+        SLOW_ACQUIRE_THRESHOLD,
+        acquire,
+        log_pool_metrics,
+        pool_metrics,
+    },
+    search::{
+        ArticleSearchHit,
+        FileSearchHit,
+        FileSearcher,
+        like_pattern,
+        search_articles,
+        search_file_nodes,
+    },
+    session_tokens::{issue_session_token, redeem_session_token, revoke_session_tokens},
+    transfer_stats::{
+        TransferStats,
+        get_transfer_stats,
+        list_transfer_stats,
+        record_transfer_stats,
+    },
+    users::{
+        UserUpdate,
+        create_user,
+        decode_privileges,
+        delete_user,
+        encode_privileges,
+        get_user_by_id,
+        get_user_by_name,
+        list_users,
+        set_privileges,
+        update_user,
+    },
 };
@@ -0,0 +1,310 @@
+//! Per-account news digest preferences and pending digests.
+//!
+//! An account opts in with [`subscribe_news_digest`], naming the categories
+//! it follows and, optionally, an email address. The server's digest job
+//! lists each subscriber's [`new_digest_articles`] since it last caught up,
+//! then either emails them and calls [`mark_digest_sent`] or stores the text
+//! with [`store_pending_digest`] for [`take_pending_digest`] to hand over at
+//! the next login. Rows are removed explicitly by
+//! [`unsubscribe_news_digest`] rather than through `ON DELETE CASCADE`,
+//! which `SQLite` connections do not enforce by default.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use diesel::{prelude::*, result::QueryResult, upsert::excluded};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+
+use super::connection::{DbConnection, TracedQueryDsl};
+
+/// An account that opted in to news digests.
+#[derive(Queryable, Clone, Debug, PartialEq, Eq)]
+pub struct NewsDigestSubscriber {
+    /// The subscribed account.
+    pub user_id: i32,
+    /// Address digests are emailed to; `None` delivers them at login.
+    pub email: Option<String>,
+    /// When the account last caught up; later articles are new to it.
+    pub last_visit_at: NaiveDateTime,
+}
+
+/// An article listed in a digest.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigestArticle {
+    /// Slash-separated path of the article's category.
+    pub category: String,
+    /// Article title.
+    pub title: String,
+    /// Name the article was posted under, if recorded.
+    pub poster: Option<String>,
+    /// When the article was posted.
+    pub posted_at: NaiveDateTime,
+}
+
+/// What an account chooses when it subscribes to news digests.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DigestSubscription<'a> {
+    /// Categories the account follows.
+    pub category_ids: &'a [i32],
+    /// Address digests are emailed to; `None` delivers them at login.
+    pub email: Option<&'a str>,
+}
+
+/// Subscribe `user_id` to digests as described by `subscription`, replacing
+/// any earlier choice of categories and email address.
+///
+/// A new subscriber's digests start with articles posted after `now`.
+///
+/// # Errors
+/// Returns any error produced by the queries.
+#[must_use = "handle the result"]
+pub async fn subscribe_news_digest(
+    conn: &mut DbConnection,
+    user_id: i32,
+    subscription: DigestSubscription<'_>,
+    now: NaiveDateTime,
+) -> QueryResult<()> {
+    use crate::schema::{news_digest_categories::dsl as c, news_digests::dsl as d};
+    let DigestSubscription {
+        category_ids,
+        email,
+    } = subscription;
+    conn.transaction::<_, diesel::result::Error, _>(async |tx_conn| {
+        diesel::insert_into(d::news_digests)
+            .values((
+                d::user_id.eq(user_id),
+                d::email.eq(email),
+                d::last_visit_at.eq(now),
+            ))
+            .on_conflict(d::user_id)
+            .do_update()
+            .set(d::email.eq(excluded(d::email)))
+            .traced()
+            .execute(tx_conn)
+            .await?;
+        diesel::delete(c::news_digest_categories.filter(c::user_id.eq(user_id)))
+            .traced()
+            .execute(tx_conn)
+            .await?;
+        if category_ids.is_empty() {
+            return Ok(());
+        }
+        let rows: Vec<_> = category_ids
+            .iter()
+            .map(|&category_id| (c::user_id.eq(user_id), c::category_id.eq(category_id)))
+            .collect();
+        diesel::insert_into(c::news_digest_categories)
+            .values(&rows)
+            .traced()
+            .execute(tx_conn)
+            .await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Stop sending digests to `user_id`, returning whether it was subscribed.
+///
+/// # Errors
+/// Returns any error produced by the deletes.
+#[must_use = "handle the result"]
+pub async fn unsubscribe_news_digest(conn: &mut DbConnection, user_id: i32) -> QueryResult<bool> {
+    use crate::schema::{news_digest_categories::dsl as c, news_digests::dsl as d};
+    conn.transaction::<_, diesel::result::Error, _>(async |tx_conn| {
+        diesel::delete(c::news_digest_categories.filter(c::user_id.eq(user_id)))
+            .traced()
+            .execute(tx_conn)
+            .await?;
+        let removed = diesel::delete(d::news_digests.filter(d::user_id.eq(user_id)))
+            .traced()
+            .execute(tx_conn)
+            .await?;
+        Ok(removed > 0)
+    })
+    .await
+}
+
+/// Return the digest subscription of `user_id`, if any.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn get_news_digest(
+    conn: &mut DbConnection,
+    user_id: i32,
+) -> QueryResult<Option<NewsDigestSubscriber>> {
+    use crate::schema::news_digests::dsl as d;
+    d::news_digests
+        .filter(d::user_id.eq(user_id))
+        .select((d::user_id, d::email, d::last_visit_at))
+        .traced()
+        .first(conn)
+        .await
+        .optional()
+}
+
+/// Return every digest subscriber.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn news_digest_subscribers(
+    conn: &mut DbConnection,
+) -> QueryResult<Vec<NewsDigestSubscriber>> {
+    use crate::schema::news_digests::dsl as d;
+    d::news_digests
+        .order(d::user_id.asc())
+        .select((d::user_id, d::email, d::last_visit_at))
+        .traced()
+        .load(conn)
+        .await
+}
+
+/// Return up to `limit` articles in the categories `subscriber` follows that
+/// were posted after its `last_visit_at` and no later than `until`, oldest
+/// first, together with how many there are in all.
+///
+/// # Errors
+/// Returns any error produced by the queries.
+#[must_use = "handle the result"]
+pub async fn new_digest_articles(
+    conn: &mut DbConnection,
+    subscriber: &NewsDigestSubscriber,
+    until: NaiveDateTime,
+    limit: i64,
+) -> QueryResult<(Vec<DigestArticle>, i64)> {
+    use crate::schema::{news_articles::dsl as a, news_digest_categories::dsl as c};
+    let user_id = subscriber.user_id;
+    let since = subscriber.last_visit_at;
+    let new_articles = || {
+        a::news_articles
+            .inner_join(c::news_digest_categories.on(c::category_id.eq(a::category_id)))
+            .filter(c::user_id.eq(user_id))
+            .filter(a::posted_at.gt(since))
+            .filter(a::posted_at.le(until))
+    };
+    let total = new_articles().count().traced().get_result(conn).await?;
+    let rows: Vec<(i32, String, Option<String>, NaiveDateTime)> = new_articles()
+        .order((a::posted_at.asc(), a::id.asc()))
+        .select((a::category_id, a::title, a::poster, a::posted_at))
+        .limit(limit)
+        .traced()
+        .load(conn)
+        .await?;
+    let paths = category_paths(conn).await?;
+    let articles = rows
+        .into_iter()
+        .map(|(category_id, title, poster, posted_at)| DigestArticle {
+            category: paths.get(&category_id).cloned().unwrap_or_default(),
+            title,
+            poster,
+            posted_at,
+        })
+        .collect();
+    Ok((articles, total))
+}
+
+/// Map every category to its slash-separated path.
+async fn category_paths(conn: &mut DbConnection) -> QueryResult<HashMap<i32, String>> {
+    use crate::schema::{news_bundles::dsl as b, news_categories::dsl as c};
+    let bundles: HashMap<i32, (Option<i32>, String)> = b::news_bundles
+        .select((b::id, b::parent_bundle_id, b::name))
+        .traced()
+        .load::<(i32, Option<i32>, String)>(conn)
+        .await?
+        .into_iter()
+        .map(|(id, parent, name)| (id, (parent, name)))
+        .collect();
+    let categories: Vec<(i32, Option<i32>, String)> = c::news_categories
+        .select((c::id, c::bundle_id, c::name))
+        .traced()
+        .load(conn)
+        .await?;
+    Ok(categories
+        .into_iter()
+        .map(|(id, bundle, name)| {
+            let mut segments = vec![name];
+            let mut parent = bundle;
+            // The depth bound stops a corrupt parent cycle from looping.
+            for _ in 0..bundles.len() {
+                let Some((next, bundle_name)) =
+                    parent.and_then(|parent_id| bundles.get(&parent_id))
+                else {
+                    break;
+                };
+                segments.push(bundle_name.clone());
+                parent = *next;
+            }
+            segments.reverse();
+            (id, segments.join("/"))
+        })
+        .collect())
+}
+
+/// Store `text` as the digest awaiting `user_id`'s next login, replacing
+/// any earlier one.
+///
+/// # Errors
+/// Returns any error produced by the update.
+#[must_use = "handle the result"]
+pub async fn store_pending_digest(
+    conn: &mut DbConnection,
+    user_id: i32,
+    text: &str,
+) -> QueryResult<usize> {
+    use crate::schema::news_digests::dsl as d;
+    diesel::update(d::news_digests.filter(d::user_id.eq(user_id)))
+        .set(d::pending.eq(text))
+        .traced()
+        .execute(conn)
+        .await
+}
+
+/// Record that `user_id` was sent every article posted up to `at`.
+///
+/// # Errors
+/// Returns any error produced by the update.
+#[must_use = "handle the result"]
+pub async fn mark_digest_sent(
+    conn: &mut DbConnection,
+    user_id: i32,
+    at: NaiveDateTime,
+) -> QueryResult<usize> {
+    use crate::schema::news_digests::dsl as d;
+    diesel::update(d::news_digests.filter(d::user_id.eq(user_id)))
+        .set((d::last_visit_at.eq(at), d::pending.eq(None::<String>)))
+        .traced()
+        .execute(conn)
+        .await
+}
+
+/// Clear and return the digest awaiting `user_id`, recording the login at
+/// `now` as its latest visit.
+///
+/// Returns `None` when the account is not subscribed or nothing is waiting.
+///
+/// # Errors
+/// Returns any error produced by the queries.
+#[must_use = "handle the result"]
+pub async fn take_pending_digest(
+    conn: &mut DbConnection,
+    user_id: i32,
+    now: NaiveDateTime,
+) -> QueryResult<Option<String>> {
+    use crate::schema::news_digests::dsl as d;
+    conn.transaction::<_, diesel::result::Error, _>(async |tx_conn| {
+        let row: Option<Option<String>> = d::news_digests
+            .filter(d::user_id.eq(user_id))
+            .select(d::pending)
+            .traced()
+            .first(tx_conn)
+            .await
+            .optional()?;
+        let Some(pending) = row else {
+            return Ok(None);
+        };
+        mark_digest_sent(tx_conn, user_id, now).await?;
+        Ok(pending)
+    })
+    .await
+}
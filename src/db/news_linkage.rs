@@ -0,0 +1,352 @@
+//! Checking and repairing the links between news articles.
+//!
+//! Siblings, meaning the root articles of a category or the replies to one
+//! article, form a chain through `prev_article_id`/`next_article_id`, and a
+//! parent names its first reply through `first_child_article_id`. Posting
+//! sets a new article's `prev_article_id` and its predecessor's
+//! `next_article_id` in separate statements, so a database restored from a
+//! crash or written by another tool can hold a half-linked chain.
+//!
+//! [`plan_link_repairs`] rebuilds each chain: it starts from the parent's
+//! first reply, or from a sibling with no valid predecessor, and follows
+//! `next_article_id` while it leads to an unvisited sibling. Siblings the walk
+//! misses are appended in posting order. Every stored link that differs from
+//! the rebuilt chain becomes a [`LinkRepair`].
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use diesel::{prelude::*, result::QueryResult};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+
+use super::connection::{DbConnection, TracedQueryDsl};
+
+/// The link columns of one article.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Queryable)]
+pub struct ArticleLinks {
+    /// Article identifier.
+    pub id: i32,
+    /// Category holding the article.
+    pub category_id: i32,
+    /// Article replied to, or `None` for a root article.
+    pub parent_article_id: Option<i32>,
+    /// Previous sibling.
+    pub prev_article_id: Option<i32>,
+    /// Next sibling.
+    pub next_article_id: Option<i32>,
+    /// First reply.
+    pub first_child_article_id: Option<i32>,
+}
+
+/// Which link column a repair rewrites.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkField {
+    /// `prev_article_id`.
+    Prev,
+    /// `next_article_id`.
+    Next,
+    /// `first_child_article_id`.
+    FirstChild,
+}
+
+impl LinkField {
+    /// Name of the column.
+    #[must_use]
+    pub const fn column(self) -> &'static str {
+        match self {
+            Self::Prev => "prev_article_id",
+            Self::Next => "next_article_id",
+            Self::FirstChild => "first_child_article_id",
+        }
+    }
+}
+
+/// One link rewritten, or to be rewritten, by a repair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkRepair {
+    /// Article whose column changes.
+    pub article_id: i32,
+    /// Column that changes.
+    pub field: LinkField,
+    /// Value found.
+    pub found: Option<i32>,
+    /// Value the chain requires.
+    pub expected: Option<i32>,
+}
+
+impl fmt::Display for LinkRepair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show =
+            |value: Option<i32>| value.map_or_else(|| "NULL".to_owned(), |id| id.to_string());
+        write!(
+            f,
+            "article {} {}: {} -> {}",
+            self.article_id,
+            self.field.column(),
+            show(self.found),
+            show(self.expected)
+        )
+    }
+}
+
+/// Work out the repairs that make every sibling chain and first-reply link
+/// in `articles` consistent.
+#[must_use]
+pub fn plan_link_repairs(articles: &[ArticleLinks]) -> Vec<LinkRepair> {
+    let by_id: BTreeMap<i32, &ArticleLinks> = articles.iter().map(|row| (row.id, row)).collect();
+    let mut groups: BTreeMap<(i32, Option<i32>), Vec<i32>> = BTreeMap::new();
+    for row in articles {
+        groups
+            .entry((row.category_id, row.parent_article_id))
+            .or_default()
+            .push(row.id);
+    }
+    let mut first_children = BTreeMap::new();
+    let mut repairs = Vec::new();
+    for ((_, parent), members) in &groups {
+        let order = rebuild_chain(&by_id, *parent, members);
+        if let (Some(parent_id), Some(&first)) = (parent, order.first()) {
+            first_children.insert(*parent_id, first);
+        }
+        repairs.extend(chain_repairs(&by_id, &order));
+    }
+    for row in articles {
+        let expected = first_children.get(&row.id).copied();
+        push_if_changed(&mut repairs, row, LinkField::FirstChild, expected);
+    }
+    repairs
+}
+
+/// Return the sibling-link repairs that make stored links follow `order`.
+fn chain_repairs(by_id: &BTreeMap<i32, &ArticleLinks>, order: &[i32]) -> Vec<LinkRepair> {
+    let mut repairs = Vec::new();
+    for (index, id) in order.iter().enumerate() {
+        let Some(row) = by_id.get(id) else {
+            continue;
+        };
+        let prev = index.checked_sub(1).and_then(|at| order.get(at)).copied();
+        let next = order.get(index.saturating_add(1)).copied();
+        push_if_changed(&mut repairs, row, LinkField::Prev, prev);
+        push_if_changed(&mut repairs, row, LinkField::Next, next);
+    }
+    repairs
+}
+
+/// Order the `members` of one sibling group, which are sorted by id.
+fn rebuild_chain(
+    by_id: &BTreeMap<i32, &ArticleLinks>,
+    parent: Option<i32>,
+    members: &[i32],
+) -> Vec<i32> {
+    let is_member = |id: &i32| members.binary_search(id).is_ok();
+    let head = parent
+        .and_then(|parent_id| by_id.get(&parent_id))
+        .and_then(|row| row.first_child_article_id)
+        .filter(is_member)
+        .or_else(|| {
+            members.iter().copied().find(|id| {
+                by_id
+                    .get(id)
+                    .and_then(|row| row.prev_article_id)
+                    .is_none_or(|prev| !is_member(&prev))
+            })
+        });
+    let mut visited = BTreeSet::new();
+    let mut order = Vec::with_capacity(members.len());
+    let mut current = head;
+    while let Some(id) = current.filter(|id| is_member(id) && !visited.contains(id)) {
+        visited.insert(id);
+        order.push(id);
+        current = by_id.get(&id).and_then(|row| row.next_article_id);
+    }
+    order.extend(members.iter().filter(|id| !visited.contains(*id)));
+    order
+}
+
+/// Record a repair when `row`'s stored `field` differs from `expected`.
+fn push_if_changed(
+    repairs: &mut Vec<LinkRepair>,
+    row: &ArticleLinks,
+    field: LinkField,
+    expected: Option<i32>,
+) {
+    let found = match field {
+        LinkField::Prev => row.prev_article_id,
+        LinkField::Next => row.next_article_id,
+        LinkField::FirstChild => row.first_child_article_id,
+    };
+    if found != expected {
+        repairs.push(LinkRepair {
+            article_id: row.id,
+            field,
+            found,
+            expected,
+        });
+    }
+}
+
+async fn load_article_links(conn: &mut DbConnection) -> QueryResult<Vec<ArticleLinks>> {
+    use crate::schema::news_articles::dsl as a;
+    a::news_articles
+        .select((
+            a::id,
+            a::category_id,
+            a::parent_article_id,
+            a::prev_article_id,
+            a::next_article_id,
+            a::first_child_article_id,
+        ))
+        .order(a::id.asc())
+        .traced()
+        .load::<ArticleLinks>(conn)
+        .await
+}
+
+/// Report the link repairs the news articles need, without making them.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn check_news_linkage(conn: &mut DbConnection) -> QueryResult<Vec<LinkRepair>> {
+    Ok(plan_link_repairs(&load_article_links(conn).await?))
+}
+
+/// Make the link repairs the news articles need, in one transaction, and
+/// return them.
+///
+/// # Errors
+/// Returns any error produced by the queries; no repair is kept then.
+#[must_use = "handle the result"]
+pub async fn repair_news_linkage(conn: &mut DbConnection) -> QueryResult<Vec<LinkRepair>> {
+    use crate::schema::news_articles::dsl as a;
+    conn.transaction::<_, diesel::result::Error, _>(async |tx_conn| {
+        let repairs = plan_link_repairs(&load_article_links(tx_conn).await?);
+        for repair in &repairs {
+            let target = a::news_articles.filter(a::id.eq(repair.article_id));
+            let update = diesel::update(target);
+            match repair.field {
+                LinkField::Prev => {
+                    update
+                        .set(a::prev_article_id.eq(repair.expected))
+                        .traced()
+                        .execute(tx_conn)
+                        .await?
+                }
+                LinkField::Next => {
+                    update
+                        .set(a::next_article_id.eq(repair.expected))
+                        .traced()
+                        .execute(tx_conn)
+                        .await?
+                }
+                LinkField::FirstChild => {
+                    update
+                        .set(a::first_child_article_id.eq(repair.expected))
+                        .traced()
+                        .execute(tx_conn)
+                        .await?
+                }
+            };
+        }
+        Ok(repairs)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for planning link repairs.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    /// Links of article `id`: its parent, previous and next siblings, and
+    /// first child.
+    fn article(id: i32, [parent, prev, next, first_child]: [Option<i32>; 4]) -> ArticleLinks {
+        ArticleLinks {
+            id,
+            category_id: 1,
+            parent_article_id: parent,
+            prev_article_id: prev,
+            next_article_id: next,
+            first_child_article_id: first_child,
+        }
+    }
+
+    fn repair(
+        article_id: i32,
+        field: LinkField,
+        found: Option<i32>,
+        expected: Option<i32>,
+    ) -> LinkRepair {
+        LinkRepair {
+            article_id,
+            field,
+            found,
+            expected,
+        }
+    }
+
+    #[rstest]
+    fn consistent_threads_need_no_repair() {
+        let articles = [
+            article(1, [None, None, Some(2), Some(3)]),
+            article(2, [None, Some(1), None, None]),
+            article(3, [Some(1), None, Some(4), None]),
+            article(4, [Some(1), Some(3), None, None]),
+        ];
+        assert!(plan_link_repairs(&articles).is_empty());
+    }
+
+    #[rstest]
+    fn half_linked_post_is_joined_to_its_predecessor() {
+        // Article 2 was inserted but article 1's next link was never set.
+        let articles = [
+            article(1, [None, None, None, None]),
+            article(2, [None, Some(1), None, None]),
+        ];
+        assert_eq!(
+            plan_link_repairs(&articles),
+            [repair(1, LinkField::Next, None, Some(2))]
+        );
+    }
+
+    #[rstest]
+    fn first_reply_link_is_set_and_cleared() {
+        let articles = [
+            article(1, [None, None, Some(2), None]),
+            article(2, [None, Some(1), None, Some(9)]),
+            article(3, [Some(1), None, None, None]),
+        ];
+        assert_eq!(
+            plan_link_repairs(&articles),
+            [
+                repair(1, LinkField::FirstChild, None, Some(3)),
+                repair(2, LinkField::FirstChild, Some(9), None),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn cycles_are_broken_and_kept_in_chain_order() {
+        // A spliced chain 1 -> 3 -> 2 whose last link loops back to 1.
+        let articles = [
+            article(1, [None, None, Some(3), None]),
+            article(2, [None, Some(3), Some(1), None]),
+            article(3, [None, Some(1), Some(2), None]),
+        ];
+        assert_eq!(
+            plan_link_repairs(&articles),
+            [repair(2, LinkField::Next, Some(1), None)]
+        );
+    }
+
+    #[rstest]
+    fn repairs_describe_the_change() {
+        let text = repair(4, LinkField::Prev, Some(2), None).to_string();
+        assert_eq!(text, "article 4 prev_article_id: 2 -> NULL");
+    }
+}
@@ -0,0 +1,48 @@
+//! Serialising writes to a category's article chains.
+//!
+//! Posting reads the newest sibling and links the new article after it, so two
+//! posts to the same category at once could both link after the same article
+//! and break the chain. `PostgreSQL` takes a transaction-scoped advisory lock
+//! per category with [`lock_category`], which is released when the
+//! transaction ends. `SQLite` allows one writer at a time anyway, so news
+//! writers queue on one process-wide mutex from [`serialise_news_writes`]
+//! instead of failing with a busy database.
+
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+use diesel::{result::QueryResult, sql_types::Integer};
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+use diesel_async::RunQueryDsl;
+#[cfg(feature = "sqlite")]
+use tokio::sync::{Mutex, MutexGuard};
+
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+use super::connection::DbConnection;
+
+/// First key of the advisory locks taken for news categories; the category
+/// id is the second. Spells `mxdN` so the locks are easy to spot in
+/// `pg_locks`.
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+const NEWS_LOCK_CLASS: i32 = 0x6d78_644e;
+
+#[cfg(feature = "sqlite")]
+static NEWS_WRITES: Mutex<()> = Mutex::const_new(());
+
+/// Block other writers to category `cat_id` until the current transaction
+/// ends.
+///
+/// # Errors
+/// Returns any error raised while taking the lock.
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+pub(super) async fn lock_category(conn: &mut DbConnection, cat_id: i32) -> QueryResult<()> {
+    diesel::sql_query("SELECT pg_advisory_xact_lock($1, $2)")
+        .bind::<Integer, _>(NEWS_LOCK_CLASS)
+        .bind::<Integer, _>(cat_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Wait until no other news write is running; hold the guard until the
+/// transaction has committed.
+#[cfg(feature = "sqlite")]
+pub(super) async fn serialise_news_writes() -> MutexGuard<'static, ()> { NEWS_WRITES.lock().await }
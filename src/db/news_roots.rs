@@ -0,0 +1,87 @@
+//! News roots: separate news trees served side by side.
+//!
+//! Every bundle and category belongs to one root, and paths resolve within
+//! the root a session selected at login. The primary root,
+//! [`PRIMARY_NEWS_ROOT`], always exists and serves clients that select
+//! none; operators add a root per community board with
+//! [`create_news_root`]. Nested items share their parent's root, so a path
+//! can never lead from one root into another.
+
+use diesel::{prelude::*, result::QueryResult};
+use diesel_async::RunQueryDsl;
+
+use super::{
+    connection::{DbConnection, TracedQueryDsl},
+    paths::PathLookupError,
+};
+
+/// Identifier of the root every server has.
+pub const PRIMARY_NEWS_ROOT: i32 = 0;
+
+/// A news root and its name.
+#[derive(Queryable, Clone, Debug, PartialEq, Eq)]
+pub struct NewsRoot {
+    /// Root identifier clients select at login.
+    pub id: i32,
+    /// Name operators know the root by.
+    pub name: String,
+}
+
+/// Add a root called `name` and return its identifier.
+///
+/// # Errors
+/// Returns any error produced by the insert, including a unique violation
+/// when the name is taken.
+#[must_use = "handle the result"]
+pub async fn create_news_root(conn: &mut DbConnection, name: &str) -> QueryResult<i32> {
+    use crate::schema::news_roots::dsl as r;
+    diesel::insert_into(r::news_roots)
+        .values(r::name.eq(name))
+        .traced()
+        .execute(conn)
+        .await?;
+    r::news_roots
+        .filter(r::name.eq(name))
+        .select(r::id)
+        .traced()
+        .first(conn)
+        .await
+}
+
+/// List every root in identifier order.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn list_news_roots(conn: &mut DbConnection) -> QueryResult<Vec<NewsRoot>> {
+    use crate::schema::news_roots::dsl as r;
+    r::news_roots.order(r::id.asc()).traced().load(conn).await
+}
+
+/// Return whether root `id` exists.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn news_root_exists(conn: &mut DbConnection, id: i32) -> QueryResult<bool> {
+    use crate::schema::news_roots::dsl as r;
+    diesel::select(diesel::dsl::exists(r::news_roots.find(id)))
+        .traced()
+        .get_result(conn)
+        .await
+}
+
+/// Fail with [`PathLookupError::NotFound`] unless root `id` exists.
+///
+/// Paths below the top level need no check, since they only resolve through
+/// bundles the root already holds.
+pub(super) async fn require_news_root(
+    conn: &mut DbConnection,
+    id: i32,
+) -> Result<(), PathLookupError> {
+    if id == PRIMARY_NEWS_ROOT || news_root_exists(conn, id).await? {
+        Ok(())
+    } else {
+        Err(PathLookupError::NotFound)
+    }
+}
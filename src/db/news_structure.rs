@@ -0,0 +1,271 @@
+//! Creating and deleting news bundles and categories.
+//!
+//! Bundles and categories share one namespace per parent bundle, or per news
+//! root at the top level, because a path segment could otherwise name
+//! either. Deletes remove the whole subtree
+//! with explicit statements, articles first and bundles last, rather than
+//! relying on the `ON DELETE CASCADE` foreign keys, which `SQLite`
+//! connections do not enforce by default.
+
+use chrono::Utc;
+use diesel::{prelude::*, result::Error as DieselError};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use thiserror::Error;
+
+use super::{
+    bundles::{bundle_id_from_path, create_bundle},
+    categories::{category_id_from_path, create_category},
+    connection::{DbConnection, TracedQueryDsl},
+    news_roots::require_news_root,
+    paths::{NewsPath, PathLookupError, parse_path_segments},
+};
+use crate::models::{NewBundle, NewCategory};
+
+/// A bundle or category located by [`find_news_item`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewsItem {
+    /// A bundle and its identifier.
+    Bundle(i32),
+    /// A category and its identifier.
+    Category(i32),
+}
+
+/// Rows removed by [`delete_news_item`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NewsDeletion {
+    /// Bundles removed, including the target when it is a bundle.
+    pub bundles: usize,
+    /// Categories removed, including the target when it is a category.
+    pub categories: usize,
+    /// Articles removed from those categories.
+    pub articles: usize,
+}
+
+/// Errors raised while creating a bundle or category.
+#[derive(Debug, Error)]
+pub enum NewsStructureError {
+    /// The name is empty or cannot be used as a path segment.
+    #[error("invalid news item name")]
+    InvalidName,
+    /// The parent bundle already holds a bundle or category with this name.
+    #[error("news item name already in use")]
+    NameTaken,
+    /// The parent path could not be resolved, or a query failed.
+    #[error(transparent)]
+    Path(#[from] PathLookupError),
+}
+
+impl From<DieselError> for NewsStructureError {
+    fn from(err: DieselError) -> Self { Self::Path(err.into()) }
+}
+
+/// Create bundle `name` inside the bundle at `parent_path`.
+///
+/// An empty `parent_path` creates the bundle at the top level of its root.
+/// Returns the new bundle's identifier.
+///
+/// # Errors
+/// Returns [`NewsStructureError::InvalidName`] or
+/// [`NewsStructureError::NameTaken`] for unusable names, and
+/// [`NewsStructureError::Path`] when the parent cannot be resolved or a query
+/// fails.
+#[must_use = "handle the result"]
+pub async fn create_news_bundle(
+    conn: &mut DbConnection,
+    parent_path: impl Into<NewsPath<'_>>,
+    name: &str,
+) -> Result<i32, NewsStructureError> {
+    let target = parent_path.into();
+    validate_name(name)?;
+    conn.transaction::<_, NewsStructureError, _>(async |tx_conn| {
+        let parent = parent_bundle(tx_conn, target).await?;
+        ensure_name_free(tx_conn, target.root, parent, name).await?;
+        let bundle = NewBundle {
+            parent_bundle_id: parent,
+            name,
+            guid: None,
+            created_at: Some(Utc::now().naive_utc()),
+            root_id: target.root,
+        };
+        Ok(create_bundle(tx_conn, &bundle).await?)
+    })
+    .await
+}
+
+/// Create category `name` inside the bundle at `parent_path`.
+///
+/// An empty `parent_path` creates the category at the top level of its root.
+/// Returns the new category's identifier.
+///
+/// # Errors
+/// Returns the same errors as [`create_news_bundle`].
+#[must_use = "handle the result"]
+pub async fn create_news_category(
+    conn: &mut DbConnection,
+    parent_path: impl Into<NewsPath<'_>>,
+    name: &str,
+) -> Result<i32, NewsStructureError> {
+    let target = parent_path.into();
+    validate_name(name)?;
+    conn.transaction::<_, NewsStructureError, _>(async |tx_conn| {
+        let parent = parent_bundle(tx_conn, target).await?;
+        ensure_name_free(tx_conn, target.root, parent, name).await?;
+        let category = NewCategory {
+            name,
+            bundle_id: parent,
+            guid: None,
+            add_sn: None,
+            delete_sn: None,
+            created_at: Some(Utc::now().naive_utc()),
+            root_id: target.root,
+        };
+        Ok(create_category(tx_conn, &category).await?)
+    })
+    .await
+}
+
+/// Resolve `path` to the bundle or category it names.
+///
+/// Bundles are tried first; the root itself is not an item.
+///
+/// # Errors
+/// Returns [`PathLookupError::InvalidPath`] for a malformed or empty path,
+/// [`PathLookupError::NotFound`] when nothing matches, or a query error.
+#[must_use = "handle the result"]
+pub async fn find_news_item(
+    conn: &mut DbConnection,
+    path: impl Into<NewsPath<'_>>,
+) -> Result<NewsItem, PathLookupError> {
+    let news_path = path.into();
+    parse_path_segments(news_path.path, false)?;
+    match bundle_id_from_path(conn, news_path).await {
+        Ok(Some(id)) => Ok(NewsItem::Bundle(id)),
+        Ok(None) => Err(PathLookupError::InvalidPath),
+        Err(PathLookupError::NotFound) => Ok(NewsItem::Category(
+            category_id_from_path(conn, news_path).await?,
+        )),
+        Err(err) => Err(err),
+    }
+}
+
+/// Delete `item` together with everything beneath it.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn delete_news_item(
+    conn: &mut DbConnection,
+    item: NewsItem,
+) -> Result<NewsDeletion, DieselError> {
+    use crate::schema::{
+        news_articles::dsl as a,
+        news_bundles::dsl as b,
+        news_categories::dsl as c,
+    };
+
+    conn.transaction::<_, DieselError, _>(async |tx_conn| {
+        let (bundle_ids, category_ids) = match item {
+            NewsItem::Bundle(id) => {
+                let bundle_ids = collect_bundles(tx_conn, id).await?;
+                let category_ids = c::news_categories
+                    .filter(c::bundle_id.eq_any(&bundle_ids))
+                    .select(c::id)
+                    .traced()
+                    .load::<i32>(tx_conn)
+                    .await?;
+                (bundle_ids, category_ids)
+            }
+            NewsItem::Category(id) => (Vec::new(), vec![id]),
+        };
+        let articles =
+            diesel::delete(a::news_articles.filter(a::category_id.eq_any(&category_ids)))
+                .traced()
+                .execute(tx_conn)
+                .await?;
+        let categories = diesel::delete(c::news_categories.filter(c::id.eq_any(&category_ids)))
+            .traced()
+            .execute(tx_conn)
+            .await?;
+        let bundles = diesel::delete(b::news_bundles.filter(b::id.eq_any(&bundle_ids)))
+            .traced()
+            .execute(tx_conn)
+            .await?;
+        Ok(NewsDeletion {
+            bundles,
+            categories,
+            articles,
+        })
+    })
+    .await
+}
+
+fn validate_name(name: &str) -> Result<(), NewsStructureError> {
+    if name.is_empty() || name.contains('/') || name.chars().any(char::is_control) {
+        Err(NewsStructureError::InvalidName)
+    } else {
+        Ok(())
+    }
+}
+
+async fn parent_bundle(
+    conn: &mut DbConnection,
+    parent_path: NewsPath<'_>,
+) -> Result<Option<i32>, PathLookupError> {
+    let parent = bundle_id_from_path(conn, parent_path).await?;
+    if parent.is_none() {
+        require_news_root(conn, parent_path.root).await?;
+    }
+    Ok(parent)
+}
+
+/// Fail with [`NewsStructureError::NameTaken`] when bundle `parent`, or the
+/// top level of `root` when there is no parent, already holds a bundle or
+/// category called `name`.
+async fn ensure_name_free(
+    conn: &mut DbConnection,
+    root: i32,
+    parent: Option<i32>,
+    name: &str,
+) -> Result<(), NewsStructureError> {
+    use crate::schema::{news_bundles::dsl as b, news_categories::dsl as c};
+
+    let mut bundles = b::news_bundles.filter(b::name.eq(name)).into_boxed();
+    let mut categories = c::news_categories.filter(c::name.eq(name)).into_boxed();
+    if let Some(id) = parent {
+        bundles = bundles.filter(b::parent_bundle_id.eq(id));
+        categories = categories.filter(c::bundle_id.eq(id));
+    } else {
+        bundles = bundles
+            .filter(b::parent_bundle_id.is_null())
+            .filter(b::root_id.eq(root));
+        categories = categories
+            .filter(c::bundle_id.is_null())
+            .filter(c::root_id.eq(root));
+    }
+    let taken = bundles.count().traced().get_result::<i64>(conn).await?
+        + categories.count().traced().get_result::<i64>(conn).await?;
+    if taken > 0 {
+        Err(NewsStructureError::NameTaken)
+    } else {
+        Ok(())
+    }
+}
+
+/// Return the bundle and every bundle nested beneath it.
+async fn collect_bundles(conn: &mut DbConnection, root: i32) -> Result<Vec<i32>, DieselError> {
+    use crate::schema::news_bundles::dsl as b;
+
+    let mut ids = vec![root];
+    let mut frontier = vec![root];
+    while !frontier.is_empty() {
+        let children = b::news_bundles
+            .filter(b::parent_bundle_id.eq_any(&frontier))
+            .select(b::id)
+            .traced()
+            .load::<i32>(conn)
+            .await?;
+        ids.extend_from_slice(&children);
+        frontier = children;
+    }
+    Ok(ids)
+}
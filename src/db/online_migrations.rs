@@ -0,0 +1,271 @@
+//! Online migrations for `PostgreSQL`.
+//!
+//! Diesel runs each embedded migration inside a transaction, so an index
+//! build or bulk `UPDATE` holds its locks until the whole migration commits.
+//! On a server with millions of articles that stalls posting for the length
+//! of the upgrade. A migration listed in [`online_plans`] can instead be
+//! applied by [`run_online_migrations`], which runs its [`OnlineStep`]s
+//! outside any transaction and then records the migration as applied.
+//! Migrations without a plan run as usual.
+//!
+//! A plan must leave the schema exactly as the migration's `up.sql` would,
+//! so servers upgraded either way end up alike.
+
+use std::time::Duration;
+
+use diesel::{
+    Connection,
+    RunQueryDsl,
+    dsl::sql,
+    migration::Migration,
+    pg::{Pg, PgConnection},
+    result::QueryResult,
+    sql_query,
+    sql_types::{Bool, Text},
+};
+use diesel_migrations::MigrationHarness;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+use super::{
+    MIGRATIONS,
+    ensure_migrations_not_cancelled,
+    run_with_migration_timeout,
+    wrap_connection_error,
+    wrap_executor_error,
+    wrap_harness_error,
+};
+
+/// Work that replaces a migration's `up.sql` when it is applied online.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnlineStep {
+    /// Build an index with `CREATE INDEX CONCURRENTLY`, which lets writes
+    /// continue while it runs.
+    ConcurrentIndex {
+        /// Index name.
+        name: &'static str,
+        /// Everything after the name, such as `ON news_articles(posted_at)`.
+        definition: &'static str,
+    },
+    /// Update rows in batches, each committed on its own, until none match
+    /// `pending`.
+    ///
+    /// The table must have an `id` key, and `set` must make `pending` false
+    /// for every row it touches.
+    Backfill {
+        /// Table to update.
+        table: &'static str,
+        /// Assignments for the `SET` clause.
+        set: &'static str,
+        /// Condition matching rows still to be updated.
+        pending: &'static str,
+        /// Rows updated per batch.
+        batch_size: u32,
+    },
+}
+
+/// Online steps for one embedded migration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OnlinePlan {
+    /// Version of the migration the plan replaces.
+    pub version: &'static str,
+    /// Steps run in order in place of `up.sql`.
+    pub steps: &'static [OnlineStep],
+}
+
+const PLANS: &[OnlinePlan] = &[OnlinePlan {
+    version: "00000000000017",
+    steps: &[
+        OnlineStep::ConcurrentIndex {
+            name: "idx_articles_category_posted",
+            definition: "ON news_articles(category_id, posted_at)",
+        },
+        OnlineStep::ConcurrentIndex {
+            name: "idx_articles_posted",
+            definition: "ON news_articles(posted_at, id)",
+        },
+    ],
+}];
+
+/// Return the migrations that can be applied online, in version order.
+#[must_use]
+pub const fn online_plans() -> &'static [OnlinePlan] { PLANS }
+
+fn online_plan(version: &str) -> Option<&'static OnlinePlan> {
+    PLANS.iter().find(|plan| plan.version == version)
+}
+
+/// Run pending embedded migrations, applying those with an online plan
+/// without holding long locks.
+///
+/// Unlike [`super::run_migrations`], the watchdog only runs when
+/// `timeout_secs` is set, since building an index on a large table may
+/// take far longer than the default allows.
+///
+/// # Errors
+///
+/// Returns any error produced by Diesel while running migrations, or a
+/// wrapped timeout error when the watchdog cancels the work.
+#[must_use = "handle the result"]
+pub async fn run_online_migrations(
+    database_url: &str,
+    timeout_secs: Option<u64>,
+) -> QueryResult<()> {
+    let url = database_url.to_owned();
+    let token = CancellationToken::new();
+    let migration_token = token.clone();
+    let work = tokio::task::spawn_blocking(move || establish_and_migrate(&url, &migration_token));
+    let joined = match timeout_secs.filter(|seconds| *seconds > 0) {
+        Some(seconds) => {
+            run_with_migration_timeout(Duration::from_secs(seconds), token, work).await?
+        }
+        None => work.await,
+    };
+    joined.map_err(wrap_executor_error)?
+}
+
+fn establish_and_migrate(url: &str, token: &CancellationToken) -> QueryResult<()> {
+    let mut conn = PgConnection::establish(url).map_err(wrap_connection_error)?;
+    info!("applying pending migrations online");
+    loop {
+        ensure_migrations_not_cancelled(token)?;
+        let pending = conn
+            .pending_migrations(MIGRATIONS)
+            .map_err(wrap_harness_error)?;
+        let Some(next) = pending.first() else {
+            return Ok(());
+        };
+        apply_next(&mut conn, next.as_ref(), token)?;
+    }
+}
+
+fn apply_next(
+    conn: &mut PgConnection,
+    migration: &dyn Migration<Pg>,
+    token: &CancellationToken,
+) -> QueryResult<()> {
+    let version = migration.name().version().to_string();
+    let Some(plan) = online_plan(&version) else {
+        info!(migration = %migration.name(), "applying migration");
+        conn.run_migration(migration).map_err(wrap_harness_error)?;
+        return Ok(());
+    };
+    info!(migration = %migration.name(), "applying migration online");
+    for step in plan.steps {
+        run_step(conn, step, token)?;
+    }
+    sql_query("INSERT INTO __diesel_schema_migrations (version) VALUES ($1)")
+        .bind::<Text, _>(&version)
+        .execute(conn)?;
+    Ok(())
+}
+
+fn run_step(
+    conn: &mut PgConnection,
+    step: &OnlineStep,
+    token: &CancellationToken,
+) -> QueryResult<()> {
+    match *step {
+        OnlineStep::ConcurrentIndex { name, definition } => {
+            build_index_concurrently(conn, name, definition)
+        }
+        OnlineStep::Backfill {
+            table,
+            set,
+            pending,
+            batch_size,
+        } => {
+            let statement = backfill_statement(table, set, pending, batch_size);
+            backfill(conn, table, &statement, token)
+        }
+    }
+}
+
+fn backfill(
+    conn: &mut PgConnection,
+    table: &str,
+    statement: &str,
+    token: &CancellationToken,
+) -> QueryResult<()> {
+    let mut total = 0;
+    loop {
+        ensure_migrations_not_cancelled(token)?;
+        let updated = sql_query(statement).execute(conn)?;
+        if updated == 0 {
+            info!(table, total, "backfill finished");
+            return Ok(());
+        }
+        total += updated;
+        debug!(table, total, "backfilled batch");
+    }
+}
+
+fn build_index_concurrently(
+    conn: &mut PgConnection,
+    name: &str,
+    definition: &str,
+) -> QueryResult<()> {
+    // An interrupted concurrent build leaves an invalid index behind, which
+    // `IF NOT EXISTS` would otherwise keep.
+    let invalid = diesel::select(
+        sql::<Bool>(
+            "EXISTS (SELECT 1 FROM pg_index i JOIN pg_class c ON c.oid = i.indexrelid WHERE \
+             c.relname = ",
+        )
+        .bind::<Text, _>(name)
+        .sql(" AND NOT i.indisvalid)"),
+    )
+    .get_result::<bool>(conn)?;
+    if invalid {
+        info!(
+            index = name,
+            "dropping invalid index left by an earlier build"
+        );
+        sql_query(format!("DROP INDEX CONCURRENTLY IF EXISTS {name}")).execute(conn)?;
+    }
+    info!(index = name, "building index concurrently");
+    sql_query(format!(
+        "CREATE INDEX CONCURRENTLY IF NOT EXISTS {name} {definition}"
+    ))
+    .execute(conn)?;
+    Ok(())
+}
+
+fn backfill_statement(table: &str, set: &str, pending: &str, batch_size: u32) -> String {
+    format!(
+        "UPDATE {table} SET {set} WHERE id IN (SELECT id FROM {table} WHERE {pending} LIMIT \
+         {batch_size})"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    //! Checking online plans against the embedded migrations.
+
+    use diesel::migration::MigrationSource;
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn every_plan_replaces_an_embedded_migration() {
+        let migrations = MigrationSource::<Pg>::migrations(&MIGRATIONS).expect("migrations");
+        let versions: Vec<String> = migrations
+            .iter()
+            .map(|migration| migration.name().version().to_string())
+            .collect();
+        for plan in online_plans() {
+            assert!(versions.iter().any(|version| version == plan.version));
+            assert!(!plan.steps.is_empty());
+        }
+    }
+
+    #[rstest]
+    fn backfills_update_one_batch_of_pending_rows() {
+        assert_eq!(
+            backfill_statement("news_articles", "flags = 0", "flags IS NULL", 500),
+            "UPDATE news_articles SET flags = 0 WHERE id IN (SELECT id FROM news_articles WHERE \
+             flags IS NULL LIMIT 500)"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+//! The transactional outbox for client notifications.
+//!
+//! A change that clients should hear about writes an [`OutboxEvent`] with
+//! [`enqueue_outbox_event`] inside the transaction that makes the change, so
+//! the notification is stored exactly when the change commits. The server's
+//! dispatcher later reads [`pending_outbox_events`], delivers them, and calls
+//! [`mark_outbox_delivered`]. A crash between delivery and marking delivers the
+//! event again on restart, so delivery is at least once.
+//!
+//! Each account also has a delivery cursor, the newest event it has been
+//! sent. The dispatcher moves it with [`advance_outbox_cursor`] before pushing
+//! to the account's sessions, so a retried event, or one delivered before the
+//! user reconnected, is not shown to the same account twice.
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::{prelude::*, result::QueryResult};
+use diesel_async::RunQueryDsl;
+use serde::{Deserialize, Serialize};
+
+use super::connection::{DbConnection, TracedQueryDsl};
+
+/// A notification waiting in the outbox.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OutboxEvent {
+    /// A news article or reply was posted.
+    ArticlePosted {
+        /// Category path the article was posted in.
+        path: String,
+        /// Identifier of the new article.
+        article_id: i32,
+        /// Title of the new article.
+        title: String,
+    },
+}
+
+/// A stored outbox row.
+#[derive(Clone, Debug, PartialEq, Eq, Queryable)]
+pub struct OutboxRecord {
+    /// Row identifier; events are delivered in this order.
+    pub id: i32,
+    /// The event as stored, in JSON.
+    pub event: String,
+    /// When the event was written.
+    pub created_at: NaiveDateTime,
+}
+
+impl OutboxRecord {
+    /// Decode the stored event.
+    ///
+    /// # Errors
+    /// Returns an error if the row does not hold a known event.
+    pub fn decode(&self) -> serde_json::Result<OutboxEvent> { serde_json::from_str(&self.event) }
+}
+
+/// Write `event` to the outbox; call this inside the transaction making the
+/// change it reports.
+///
+/// # Errors
+/// Returns any error produced by the insert.
+#[must_use = "handle the result"]
+pub async fn enqueue_outbox_event(conn: &mut DbConnection, event: &OutboxEvent) -> QueryResult<()> {
+    use crate::schema::outbox_events::dsl as o;
+    let encoded = serde_json::to_string(event)
+        .map_err(|error| diesel::result::Error::SerializationError(Box::new(error)))?;
+    diesel::insert_into(o::outbox_events)
+        .values(o::event.eq(encoded))
+        .traced()
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Return up to `limit` undelivered events, oldest first.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn pending_outbox_events(
+    conn: &mut DbConnection,
+    limit: i64,
+) -> QueryResult<Vec<OutboxRecord>> {
+    use crate::schema::outbox_events::dsl as o;
+    o::outbox_events
+        .filter(o::delivered_at.is_null())
+        .order(o::id.asc())
+        .select((o::id, o::event, o::created_at))
+        .limit(limit)
+        .traced()
+        .load(conn)
+        .await
+}
+
+/// Record the events `ids` as delivered, returning how many were marked.
+///
+/// # Errors
+/// Returns any error produced by the update.
+#[must_use = "handle the result"]
+pub async fn mark_outbox_delivered(conn: &mut DbConnection, ids: &[i32]) -> QueryResult<usize> {
+    use crate::schema::outbox_events::dsl as o;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    diesel::update(o::outbox_events.filter(o::id.eq_any(ids)))
+        .set(o::delivered_at.eq(Utc::now().naive_utc()))
+        .traced()
+        .execute(conn)
+        .await
+}
+
+/// Delete events delivered before `before`, returning how many were removed.
+///
+/// # Errors
+/// Returns any error produced by the delete.
+#[must_use = "handle the result"]
+pub async fn prune_outbox(conn: &mut DbConnection, before: NaiveDateTime) -> QueryResult<usize> {
+    use crate::schema::outbox_events::dsl as o;
+    diesel::delete(o::outbox_events.filter(o::delivered_at.lt(before)))
+        .traced()
+        .execute(conn)
+        .await
+}
+
+/// Move the delivery cursor of `user_id` to `event_id`, returning whether it
+/// moved; `false` means the account was already sent this event or a later
+/// one.
+///
+/// # Errors
+/// Returns any error produced by the queries.
+#[must_use = "handle the result"]
+pub async fn advance_outbox_cursor(
+    conn: &mut DbConnection,
+    user_id: i32,
+    event_id: i32,
+) -> QueryResult<bool> {
+    use crate::schema::outbox_cursors::dsl as c;
+    let moved = diesel::update(
+        c::outbox_cursors
+            .filter(c::user_id.eq(user_id))
+            .filter(c::last_event_id.lt(event_id)),
+    )
+    .set(c::last_event_id.eq(event_id))
+    .traced()
+    .execute(conn)
+    .await?;
+    if moved > 0 {
+        return Ok(true);
+    }
+    let inserted = diesel::insert_into(c::outbox_cursors)
+        .values((c::user_id.eq(user_id), c::last_event_id.eq(event_id)))
+        .on_conflict_do_nothing()
+        .traced()
+        .execute(conn)
+        .await?;
+    Ok(inserted > 0)
+}
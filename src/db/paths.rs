@@ -2,14 +2,41 @@
 
 use thiserror::Error;
 
+use super::news_roots::PRIMARY_NEWS_ROOT;
 use crate::news_path::prepare_path;
 
+/// A news path and the root it starts from.
+///
+/// A bare `&str` converts to a path in the primary root.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NewsPath<'a> {
+    /// Root the path is resolved in.
+    pub root: i32,
+    /// Slash-separated bundle and category names; empty for the root's top
+    /// level.
+    pub path: &'a str,
+}
+
+impl<'a> NewsPath<'a> {
+    /// Name `path` within `root`.
+    #[must_use]
+    pub const fn new(root: i32, path: &'a str) -> Self { Self { root, path } }
+}
+
+impl<'a> From<&'a str> for NewsPath<'a> {
+    fn from(path: &'a str) -> Self { Self::new(PRIMARY_NEWS_ROOT, path) }
+}
+
 /// Errors that can occur when resolving news paths.
 #[derive(Debug, Error)]
 pub enum PathLookupError {
-    /// The provided news path is invalid or malformed.
-    #[error("invalid news path")]
+    /// The news path is syntactically malformed, for example because it has
+    /// an empty segment.
+    #[error("malformed news path")]
     InvalidPath,
+    /// The news path is well formed but names no bundle or category.
+    #[error("news path not found")]
+    NotFound,
     /// A database query error occurred.
     #[error(transparent)]
     Diesel(#[from] diesel::result::Error),
@@ -19,10 +46,16 @@ pub enum PathLookupError {
 }
 
 /// Parse a news path into JSON segments, enforcing empty-path rules.
+///
+/// Leading and trailing slashes are ignored. Empty inner segments and control
+/// characters make the path malformed.
 pub fn parse_path_segments(
     path: &str,
     allow_empty: bool,
 ) -> Result<Option<(String, usize)>, PathLookupError> {
+    if !is_well_formed(path) {
+        return Err(PathLookupError::InvalidPath);
+    }
     let Some((json, len)) = prepare_path(path)? else {
         return if allow_empty {
             Ok(None)
@@ -39,8 +72,16 @@ pub const fn normalize_lookup_result(
     require_match: bool,
 ) -> Result<Option<i32>, PathLookupError> {
     if require_match && id.is_none() {
-        Err(PathLookupError::InvalidPath)
+        Err(PathLookupError::NotFound)
     } else {
         Ok(id)
     }
 }
+
+fn is_well_formed(path: &str) -> bool {
+    let trimmed = path.trim_matches('/');
+    trimmed.is_empty()
+        || trimmed
+            .split('/')
+            .all(|segment| !segment.is_empty() && !segment.chars().any(char::is_control))
+}
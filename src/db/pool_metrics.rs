@@ -0,0 +1,131 @@
+//! Connection pool statistics and slow-acquisition warnings.
+//!
+//! bb8 tracks how many connections it holds and how often callers had to wait
+//! for one. [`pool_metrics`] copies those counters into a plain snapshot, and
+//! [`acquire`] wraps `Pool::get` so that a wait longer than
+//! [`SLOW_ACQUIRE_THRESHOLD`] is logged with the transaction type that was
+//! blocked. A run of those warnings means the pool is too small for the load
+//! or a handler is holding connections across slow work.
+
+use std::time::{Duration, Instant};
+
+use diesel_async::pooled_connection::bb8::{PooledConnection, RunError};
+use tracing::{info, warn};
+
+use super::connection::{DbConnection, DbPool};
+use crate::transaction_type::TransactionType;
+
+/// Pool waits longer than this are logged as warnings.
+pub const SLOW_ACQUIRE_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// Point-in-time copy of the pool's bb8 state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolMetricsSnapshot {
+    /// Connections currently open, idle or in use.
+    pub connections: u32,
+    /// Open connections not checked out.
+    pub idle_connections: u32,
+    /// Acquisitions served without waiting.
+    pub gets_direct: u64,
+    /// Acquisitions that had to wait for a connection.
+    pub gets_waited: u64,
+    /// Acquisitions that gave up after the pool's connection timeout.
+    pub gets_timed_out: u64,
+    /// Total time acquisitions spent waiting.
+    pub total_wait: Duration,
+    /// Connections opened over the pool's lifetime.
+    pub connections_created: u64,
+}
+
+/// Read the current statistics for `pool`.
+#[must_use]
+pub fn pool_metrics(pool: &DbPool) -> PoolMetricsSnapshot {
+    let state = pool.state();
+    let stats = state.statistics;
+    PoolMetricsSnapshot {
+        connections: state.connections,
+        idle_connections: state.idle_connections,
+        gets_direct: stats.get_direct,
+        gets_waited: stats.get_waited,
+        gets_timed_out: stats.get_timed_out,
+        total_wait: stats.get_wait_time,
+        connections_created: stats.connections_created,
+    }
+}
+
+/// Log the current statistics for `pool` at info level.
+pub fn log_pool_metrics(pool: &DbPool) {
+    let stats = pool_metrics(pool);
+    info!(
+        connections = stats.connections,
+        idle = stats.idle_connections,
+        gets_direct = stats.gets_direct,
+        gets_waited = stats.gets_waited,
+        gets_timed_out = stats.gets_timed_out,
+        total_wait_ms = duration_millis(stats.total_wait),
+        connections_created = stats.connections_created,
+        "database pool statistics"
+    );
+}
+
+/// Check out a connection for a handler serving transaction type `ty`.
+///
+/// Behaves like `pool.get()` but warns when the wait exceeds
+/// [`SLOW_ACQUIRE_THRESHOLD`], naming the blocked transaction type.
+///
+/// # Errors
+/// Returns any error reported by the pool, including acquisition timeouts.
+pub async fn acquire(
+    pool: &DbPool,
+    ty: impl Into<TransactionType>,
+) -> Result<PooledConnection<'_, DbConnection>, RunError> {
+    let started = Instant::now();
+    let result = pool.get().await;
+    let waited = started.elapsed();
+    if waited > SLOW_ACQUIRE_THRESHOLD {
+        let stats = pool_metrics(pool);
+        warn!(
+            transaction = %ty.into(),
+            waited_ms = duration_millis(waited),
+            connections = stats.connections,
+            idle = stats.idle_connections,
+            "slow database pool acquisition"
+        );
+    }
+    result
+}
+
+fn duration_millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for pool statistics snapshots.
+    use rstest::rstest;
+    use test_util::{AnyError, build_test_db, setup_files_db};
+    use tokio::runtime::Builder;
+
+    use super::*;
+
+    #[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+    #[rstest]
+    fn acquisitions_are_counted() -> Result<(), AnyError> {
+        let rt = Builder::new_current_thread().enable_all().build()?;
+        let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+            return Ok(());
+        };
+        let pool = test_db.pool();
+        let before = pool_metrics(&pool);
+
+        rt.block_on(async {
+            let _conn = acquire(&pool, TransactionType::GetFileNameList).await?;
+            Ok::<_, RunError>(())
+        })?;
+
+        let after = pool_metrics(&pool);
+        assert!(after.connections >= 1);
+        assert!(after.gets_direct + after.gets_waited > before.gets_direct + before.gets_waited);
+        Ok(())
+    }
+}
@@ -102,7 +102,15 @@ async fn assert_postgres_bundle_schema(conn: &mut DbConnection) -> TestResult<()
     )
     .await?;
     anyhow::ensure!(
-        bundle_columns == vec!["id", "parent_bundle_id", "name", "guid", "created_at"],
+        bundle_columns
+            == vec![
+                "id",
+                "parent_bundle_id",
+                "name",
+                "guid",
+                "created_at",
+                "root_id"
+            ],
         "unexpected news_bundles columns: {bundle_columns:?}"
     );
 
@@ -155,7 +163,8 @@ async fn assert_postgres_category_schema(conn: &mut DbConnection) -> TestResult<
                 "guid",
                 "add_sn",
                 "delete_sn",
-                "created_at"
+                "created_at",
+                "root_id"
             ],
         "unexpected news_categories columns: {category_columns:?}"
     );
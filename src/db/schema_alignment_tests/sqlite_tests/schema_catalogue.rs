@@ -65,7 +65,15 @@ async fn assert_sqlite_bundle_schema(conn: &mut DbConnection) -> TestResult<()>
     )
     .await?;
     anyhow::ensure!(
-        bundle_columns == vec!["id", "parent_bundle_id", "name", "guid", "created_at"],
+        bundle_columns
+            == vec![
+                "id",
+                "parent_bundle_id",
+                "name",
+                "guid",
+                "created_at",
+                "root_id"
+            ],
         "unexpected SQLite news_bundles columns: {bundle_columns:?}"
     );
 
@@ -118,7 +126,8 @@ async fn assert_sqlite_news_schema(conn: &mut DbConnection) -> TestResult<()> {
                 "guid",
                 "add_sn",
                 "delete_sn",
-                "created_at"
+                "created_at",
+                "root_id"
             ],
         "unexpected SQLite news_categories columns: {category_columns:?}"
     );
@@ -0,0 +1,262 @@
+//! Name and text search across the file area and the news tree.
+//!
+//! Both searches are case-insensitive substring matches. File nodes match on
+//! their name and are returned only when the user may see both the node and
+//! the folder holding it, the same grants a listing of that folder checks.
+//! Nodes inside a drop box are returned only to sessions that may view drop
+//! boxes, as only those may list them.
+//! Articles match on their title or body within one news root and carry no
+//! grants of their own, so the caller decides whether the session may read
+//! news at all.
+
+use std::collections::BTreeMap;
+
+use diesel::{prelude::*, result::QueryResult};
+use diesel_async::RunQueryDsl;
+
+use super::{
+    connection::{DbConnection, TracedQueryDsl},
+    file_drop_boxes::is_in_drop_box,
+    file_listing::is_file_node_visible,
+    files::{
+        DOWNLOAD_FILE_PERMISSION_CODE,
+        PRINCIPAL_GROUP,
+        PRINCIPAL_USER,
+        RESOURCE_TYPE_FILE_NODE,
+        get_file_node,
+    },
+};
+use crate::models::{Bundle, Category, FileNodeKind};
+
+diesel::define_sql_function! {
+    /// SQL `LOWER`, available on every supported backend.
+    fn lower(text: diesel::sql_types::Text) -> diesel::sql_types::Text;
+}
+
+/// A file node whose name matched a search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileSearchHit {
+    /// Slash-separated path of the folder holding the node; empty for the
+    /// root.
+    pub folder: String,
+    /// Node name.
+    pub name: String,
+    /// Whether the node is a folder.
+    pub is_folder: bool,
+}
+
+/// Who is searching the file area, and whether they may see into drop boxes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileSearcher {
+    /// Account whose grants decide which nodes are returned.
+    pub user_id: i32,
+    /// Whether nodes inside drop boxes are returned; see [`is_in_drop_box`].
+    pub view_drop_boxes: bool,
+}
+
+/// A news article whose title or body matched a search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArticleSearchHit {
+    /// Slash-separated path of the category holding the article.
+    pub category: String,
+    /// Article identifier.
+    pub article_id: i32,
+    /// Article title.
+    pub title: String,
+}
+
+/// Build a `LIKE` pattern matching `query` anywhere in lowercased text, with
+/// `\` escaping the wildcards.
+#[must_use]
+pub fn like_pattern(query: &str) -> String {
+    let lowered = query.to_lowercase();
+    let mut pattern = String::with_capacity(lowered.len().saturating_add(2));
+    pattern.push('%');
+    for ch in lowered.chars() {
+        if matches!(ch, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(ch);
+    }
+    pattern.push('%');
+    pattern
+}
+
+/// Find up to `limit` file nodes named like `query` that `searcher` may
+/// reach, ordered by name.
+///
+/// Unless the searcher may view drop boxes, nodes inside a drop box are left
+/// out; see [`is_in_drop_box`].
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn search_file_nodes(
+    conn: &mut DbConnection,
+    searcher: FileSearcher,
+    query: &str,
+    limit: usize,
+) -> QueryResult<Vec<FileSearchHit>> {
+    use crate::schema::{
+        file_nodes::dsl as f,
+        permissions::dsl as p,
+        resource_permissions::dsl as rp,
+        user_groups::dsl as ug,
+    };
+
+    let user_id = searcher.user_id;
+    let group_ids = ug::user_groups
+        .filter(ug::user_id.eq(user_id))
+        .select(ug::group_id);
+    let candidates = f::file_nodes
+        .inner_join(
+            rp::resource_permissions.on(rp::resource_type
+                .eq(RESOURCE_TYPE_FILE_NODE)
+                .and(rp::resource_id.eq(f::id))),
+        )
+        .inner_join(p::permissions.on(p::id.eq(rp::permission_id)))
+        .filter(lower(f::name).like(like_pattern(query)).escape('\\'))
+        .filter(p::code.eq(DOWNLOAD_FILE_PERMISSION_CODE))
+        .filter(
+            rp::principal_type
+                .eq(PRINCIPAL_USER)
+                .and(rp::principal_id.eq(user_id))
+                .or(rp::principal_type
+                    .eq(PRINCIPAL_GROUP)
+                    .and(rp::principal_id.eq_any(group_ids))),
+        )
+        .select((f::id, f::name, f::kind, f::parent_id))
+        .distinct()
+        .order((f::name.asc(), f::id.asc()))
+        .traced()
+        .load::<(i32, String, String, Option<i32>)>(conn)
+        .await?;
+
+    // Several hits often share a folder, so each folder is checked once.
+    let mut folders: BTreeMap<i32, Option<String>> = BTreeMap::new();
+    let mut hits = Vec::new();
+    for (_, name, kind, parent_id) in candidates {
+        if hits.len() >= limit {
+            break;
+        }
+        let visible = match parent_id {
+            None => Some(String::new()),
+            Some(parent) => {
+                if let Some(cached) = folders.get(&parent) {
+                    cached.clone()
+                } else {
+                    let path = if searcher.view_drop_boxes || !is_in_drop_box(conn, parent).await? {
+                        visible_folder_path(conn, user_id, parent).await?
+                    } else {
+                        None
+                    };
+                    folders.insert(parent, path.clone());
+                    path
+                }
+            }
+        };
+        if let Some(folder) = visible {
+            hits.push(FileSearchHit {
+                folder,
+                name,
+                is_folder: kind == FileNodeKind::Folder.as_str(),
+            });
+        }
+    }
+    Ok(hits)
+}
+
+/// Return the path of folder `folder_id` when `user_id` may see it.
+async fn visible_folder_path(
+    conn: &mut DbConnection,
+    user_id: i32,
+    folder_id: i32,
+) -> QueryResult<Option<String>> {
+    if !is_file_node_visible(conn, user_id, folder_id).await? {
+        return Ok(None);
+    }
+    let mut segments = Vec::new();
+    let mut next = Some(folder_id);
+    while let Some(id) = next {
+        let Some(node) = get_file_node(conn, id).await? else {
+            return Ok(None);
+        };
+        next = node.parent_id;
+        segments.push(node.name);
+    }
+    segments.reverse();
+    Ok(Some(segments.join("/")))
+}
+
+/// Find up to `limit` articles in news root `root` whose title or body
+/// contains `query`, newest first.
+///
+/// # Errors
+/// Returns any error produced by the database.
+#[must_use = "handle the result"]
+pub async fn search_articles(
+    conn: &mut DbConnection,
+    root: i32,
+    query: &str,
+    limit: usize,
+) -> QueryResult<Vec<ArticleSearchHit>> {
+    use crate::schema::{news_articles::dsl as a, news_categories::dsl as c};
+
+    let pattern = like_pattern(query);
+    let in_root = c::news_categories.filter(c::root_id.eq(root)).select(c::id);
+    let rows = a::news_articles
+        .filter(a::category_id.eq_any(in_root))
+        .filter(
+            lower(a::title).like(&pattern).escape('\\').or(a::data
+                .is_not_null()
+                .and(lower(a::data.assume_not_null()).like(&pattern).escape('\\'))),
+        )
+        .order((a::posted_at.desc(), a::id.desc()))
+        .limit(i64::try_from(limit).unwrap_or(i64::MAX))
+        .select((a::id, a::category_id, a::title))
+        .traced()
+        .load::<(i32, i32, String)>(conn)
+        .await?;
+
+    let mut categories: BTreeMap<i32, String> = BTreeMap::new();
+    let mut hits = Vec::with_capacity(rows.len());
+    for (article_id, category_id, title) in rows {
+        let category = if let Some(cached) = categories.get(&category_id) {
+            cached.clone()
+        } else {
+            let path = category_path(conn, category_id).await?;
+            categories.insert(category_id, path.clone());
+            path
+        };
+        hits.push(ArticleSearchHit {
+            category,
+            article_id,
+            title,
+        });
+    }
+    Ok(hits)
+}
+
+/// Return the slash-separated path of category `category_id`.
+async fn category_path(conn: &mut DbConnection, category_id: i32) -> QueryResult<String> {
+    use crate::schema::{news_bundles::dsl as b, news_categories::dsl as c};
+
+    let category = c::news_categories
+        .find(category_id)
+        .traced()
+        .get_result::<Category>(conn)
+        .await?;
+    let mut segments = vec![category.name];
+    let mut next = category.bundle_id;
+    while let Some(id) = next {
+        let bundle = b::news_bundles
+            .find(id)
+            .traced()
+            .get_result::<Bundle>(conn)
+            .await?;
+        next = bundle.parent_bundle_id;
+        segments.push(bundle.name);
+    }
+    segments.reverse();
+    Ok(segments.join("/"))
+}
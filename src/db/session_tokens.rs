@@ -0,0 +1,95 @@
+//! Session resumption tokens.
+//!
+//! A login that asks for one is handed a random token with
+//! [`issue_session_token`]. A client that loses its connection may log in
+//! again with the token instead of its password; [`redeem_session_token`]
+//! accepts each token once, before it expires, and only for the account it
+//! was issued to. Logging out, a password change, a kick, or a ban revokes
+//! the account's tokens with [`revoke_session_tokens`]. Only a SHA-256
+//! digest of each token is stored, so the table alone cannot be used to log
+//! in. Expired tokens are pruned whenever a new one is issued.
+
+use chrono::NaiveDateTime;
+use diesel::{prelude::*, result::QueryResult};
+use diesel_async::RunQueryDsl;
+use sha2::{Digest, Sha256};
+
+use super::connection::DbConnection;
+
+/// Hex-encoded SHA-256 digest of `token`, as stored in `session_tokens`.
+fn token_digest(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .map(|nibble| char::from_digit(u32::from(nibble), 16).unwrap_or('0'))
+        .collect()
+}
+
+/// Store a new token for `user_id` that stays valid until `expires_at`,
+/// pruning tokens that expired by `now`, and return it.
+///
+/// # Errors
+/// Returns any error produced by the queries.
+#[must_use = "handle the result"]
+pub async fn issue_session_token(
+    conn: &mut DbConnection,
+    user_id: i32,
+    now: NaiveDateTime,
+    expires_at: NaiveDateTime,
+) -> QueryResult<String> {
+    use crate::schema::session_tokens::dsl as t;
+    let token = format!("{:032x}", rand::random::<u128>());
+    diesel::delete(t::session_tokens.filter(t::expires_at.le(now)))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(t::session_tokens)
+        .values((
+            t::token.eq(token_digest(&token)),
+            t::user_id.eq(user_id),
+            t::expires_at.eq(expires_at),
+        ))
+        .execute(conn)
+        .await?;
+    Ok(token)
+}
+
+/// Use up `token` if it was issued to `user_id` and has not expired by
+/// `now`, returning whether it was accepted.
+///
+/// A token issued to another account is left alone, so naming the wrong
+/// account cannot burn someone else's token.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn redeem_session_token(
+    conn: &mut DbConnection,
+    user_id: i32,
+    token: &str,
+    now: NaiveDateTime,
+) -> QueryResult<bool> {
+    use crate::schema::session_tokens::dsl as t;
+    // Only the redemption that removes the row wins when two clients present
+    // the same token at once.
+    let removed = diesel::delete(
+        t::session_tokens
+            .filter(t::token.eq(token_digest(token)))
+            .filter(t::user_id.eq(user_id))
+            .filter(t::expires_at.gt(now)),
+    )
+    .execute(conn)
+    .await?;
+    Ok(removed > 0)
+}
+
+/// Remove every token issued to `user_id`, returning how many were removed.
+///
+/// # Errors
+/// Returns any error produced by the delete query.
+#[must_use = "handle the result"]
+pub async fn revoke_session_tokens(conn: &mut DbConnection, user_id: i32) -> QueryResult<usize> {
+    use crate::schema::session_tokens::dsl as t;
+    diesel::delete(t::session_tokens.filter(t::user_id.eq(user_id)))
+        .execute(conn)
+        .await
+}
@@ -0,0 +1,154 @@
+//! News article deletion tests (`SQLite`).
+//!
+//! Each test checks that the sibling and parent links left behind point only
+//! at surviving articles.
+
+use anyhow::anyhow;
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, migrated_conn, seed_root_category};
+use crate::{
+    db::{
+        CreateRootArticleParams,
+        create_reply_article,
+        create_root_article,
+        delete_article,
+        get_article,
+    },
+    models::Article,
+};
+
+const fn params(title: &'static str) -> CreateRootArticleParams<'static> {
+    CreateRootArticleParams {
+        title,
+        flags: 0,
+        data_flavor: "text/plain",
+        data: "body",
+    }
+}
+
+async fn reply(conn: &mut DbConnection, parent: i32, title: &'static str) -> Result<i32, AnyError> {
+    create_reply_article(conn, "/General", parent, params(title))
+        .await?
+        .ok_or_else(|| anyhow!("reply {title} not created"))
+}
+
+async fn fetch(conn: &mut DbConnection, id: i32) -> Result<Article, AnyError> {
+    get_article(conn, "/General", id)
+        .await?
+        .ok_or_else(|| anyhow!("article {id} missing"))
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_delete_middle_root_relinks_neighbours(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let first = create_root_article(&mut conn, "/General", params("One")).await?;
+    let middle = create_root_article(&mut conn, "/General", params("Two")).await?;
+    let last = create_root_article(&mut conn, "/General", params("Three")).await?;
+
+    let removed = delete_article(&mut conn, "/General", middle, false).await?;
+    assert_eq!(removed, Some(1));
+    assert!(get_article(&mut conn, "/General", middle).await?.is_none());
+    assert_eq!(fetch(&mut conn, first).await?.next_article_id, Some(last));
+    assert_eq!(fetch(&mut conn, last).await?.prev_article_id, Some(first));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_delete_promotes_replies_in_place(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let root = create_root_article(&mut conn, "/General", params("Hello")).await?;
+    let before = reply(&mut conn, root, "Before").await?;
+    let doomed = reply(&mut conn, root, "Doomed").await?;
+    let after = reply(&mut conn, root, "After").await?;
+    let child_a = reply(&mut conn, doomed, "Child A").await?;
+    let child_b = reply(&mut conn, doomed, "Child B").await?;
+
+    let removed = delete_article(&mut conn, "/General", doomed, false).await?;
+    assert_eq!(removed, Some(1));
+    let promoted_a = fetch(&mut conn, child_a).await?;
+    let promoted_b = fetch(&mut conn, child_b).await?;
+    assert_eq!(promoted_a.parent_article_id, Some(root));
+    assert_eq!(promoted_a.prev_article_id, Some(before));
+    assert_eq!(promoted_b.parent_article_id, Some(root));
+    assert_eq!(promoted_b.next_article_id, Some(after));
+    assert_eq!(
+        fetch(&mut conn, before).await?.next_article_id,
+        Some(child_a)
+    );
+    assert_eq!(
+        fetch(&mut conn, after).await?.prev_article_id,
+        Some(child_b)
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_delete_first_reply_updates_parent(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let root = create_root_article(&mut conn, "/General", params("Hello")).await?;
+    let first = reply(&mut conn, root, "First").await?;
+    let second = reply(&mut conn, root, "Second").await?;
+
+    delete_article(&mut conn, "/General", first, false).await?;
+    assert_eq!(
+        fetch(&mut conn, root).await?.first_child_article_id,
+        Some(second)
+    );
+    assert_eq!(fetch(&mut conn, second).await?.prev_article_id, None);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_recursive_delete_removes_subtree(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let root = create_root_article(&mut conn, "/General", params("Hello")).await?;
+    let child = reply(&mut conn, root, "Child").await?;
+    let grandchild = reply(&mut conn, child, "Grandchild").await?;
+    let survivor = create_root_article(&mut conn, "/General", params("Survivor")).await?;
+
+    let removed = delete_article(&mut conn, "/General", root, true).await?;
+    assert_eq!(removed, Some(3));
+    assert!(get_article(&mut conn, "/General", child).await?.is_none());
+    assert!(
+        get_article(&mut conn, "/General", grandchild)
+            .await?
+            .is_none()
+    );
+    assert_eq!(fetch(&mut conn, survivor).await?.prev_article_id, None);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_delete_missing_article_returns_none(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let removed = delete_article(&mut conn, "/General", 999, true).await?;
+    assert_eq!(removed, None);
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Threaded reply tests for news articles (`SQLite`).
+//!
+//! Replies hang off their parent through `first_child_article_id` and are
+//! chained to one another through `prev_article_id`/`next_article_id`, apart
+//! from the category's root articles.
+
+use anyhow::anyhow;
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, migrated_conn, seed_root_category};
+use crate::db::{CreateRootArticleParams, create_reply_article, create_root_article, get_article};
+
+const fn params(title: &'static str) -> CreateRootArticleParams<'static> {
+    CreateRootArticleParams {
+        title,
+        flags: 0,
+        data_flavor: "text/plain",
+        data: "body",
+    }
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_replies_thread_under_their_parent(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let root_id = create_root_article(&mut conn, "/General", params("Hello")).await?;
+    let first_reply = create_reply_article(&mut conn, "/General", root_id, params("Re: Hello"))
+        .await?
+        .ok_or_else(|| anyhow!("first reply not created"))?;
+    let second_reply = create_reply_article(&mut conn, "/General", root_id, params("Re: Re"))
+        .await?
+        .ok_or_else(|| anyhow!("second reply not created"))?;
+
+    let root = get_article(&mut conn, "/General", root_id)
+        .await?
+        .ok_or_else(|| anyhow!("root missing"))?;
+    let first = get_article(&mut conn, "/General", first_reply)
+        .await?
+        .ok_or_else(|| anyhow!("first reply missing"))?;
+    let second = get_article(&mut conn, "/General", second_reply)
+        .await?
+        .ok_or_else(|| anyhow!("second reply missing"))?;
+    assert_eq!(root.first_child_article_id, Some(first_reply));
+    assert_eq!(root.next_article_id, None);
+    assert_eq!(first.parent_article_id, Some(root_id));
+    assert_eq!(first.prev_article_id, None);
+    assert_eq!(first.next_article_id, Some(second_reply));
+    assert_eq!(second.parent_article_id, Some(root_id));
+    assert_eq!(second.prev_article_id, Some(first_reply));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_replies_do_not_join_the_root_chain(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let root_id = create_root_article(&mut conn, "/General", params("Hello")).await?;
+    create_reply_article(&mut conn, "/General", root_id, params("Re: Hello"))
+        .await?
+        .ok_or_else(|| anyhow!("reply not created"))?;
+    let next_root = create_root_article(&mut conn, "/General", params("Second")).await?;
+
+    let root = get_article(&mut conn, "/General", root_id)
+        .await?
+        .ok_or_else(|| anyhow!("root missing"))?;
+    assert_eq!(root.next_article_id, Some(next_root));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_reply_to_missing_parent_is_rejected(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let created = create_reply_article(&mut conn, "/General", 999, params("Orphan")).await?;
+    assert_eq!(created, None);
+    Ok(())
+}
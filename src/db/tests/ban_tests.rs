@@ -0,0 +1,100 @@
+//! Ban list persistence tests (`SQLite`).
+
+use std::net::IpAddr;
+
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, create_account, migrated_conn};
+use crate::db::{
+    BanTarget,
+    create_ban,
+    find_active_ban,
+    issue_session_token,
+    list_active_address_bans,
+    list_bans,
+    redeem_session_token,
+    remove_ban,
+};
+
+fn now() -> NaiveDateTime { Utc::now().naive_utc() }
+
+fn alice() -> BanTarget { BanTarget::Username("alice".to_owned()) }
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_ban_applies_until_it_expires(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let expiry = now() + TimeDelta::minutes(30);
+    create_ban(&mut conn, &alice(), Some("spam"), Some(expiry)).await?;
+
+    let ban = find_active_ban(&mut conn, &alice(), now()).await?;
+    assert_eq!(ban.and_then(|found| found.reason), Some("spam".to_owned()));
+    let later = expiry + TimeDelta::seconds(1);
+    assert!(find_active_ban(&mut conn, &alice(), later).await?.is_none());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_reban_replaces_existing_entry(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let past = now() - TimeDelta::minutes(1);
+    create_ban(&mut conn, &alice(), None, Some(past)).await?;
+    create_ban(&mut conn, &alice(), Some("again"), None).await?;
+
+    let bans = list_bans(&mut conn).await?;
+    assert_eq!(bans.len(), 1);
+    assert!(find_active_ban(&mut conn, &alice(), now()).await?.is_some());
+    assert_eq!(remove_ban(&mut conn, &alice()).await?, 1);
+    assert!(list_bans(&mut conn).await?.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_address_bans_match_mapped_ipv4(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let plain: IpAddr = "192.0.2.7".parse()?;
+    let mapped: IpAddr = "::ffff:192.0.2.7".parse()?;
+    create_ban(&mut conn, &BanTarget::Address(plain), None, None).await?;
+
+    let found = find_active_ban(&mut conn, &BanTarget::Address(mapped), now()).await?;
+    assert_eq!(found.map(|ban| ban.target), Some("192.0.2.7".to_owned()));
+    let other = BanTarget::Username("192.0.2.7".to_owned());
+    assert!(find_active_ban(&mut conn, &other, now()).await?.is_none());
+    create_ban(&mut conn, &other, None, None).await?;
+    let addresses = list_active_address_bans(&mut conn, now()).await?;
+    assert_eq!(addresses, vec![(plain, None)]);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_banning_an_account_revokes_its_session_tokens(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice_id = create_account(&mut conn, "alice").await?;
+    let bob_id = create_account(&mut conn, "bob").await?;
+    let expires_at = now() + TimeDelta::hours(1);
+    let alice_token = issue_session_token(&mut conn, alice_id, now(), expires_at).await?;
+    let bob_token = issue_session_token(&mut conn, bob_id, now(), expires_at).await?;
+
+    create_ban(&mut conn, &alice(), None, None).await?;
+
+    assert!(!redeem_session_token(&mut conn, alice_id, &alice_token, now()).await?);
+    assert!(redeem_session_token(&mut conn, bob_id, &bob_token, now()).await?);
+    Ok(())
+}
@@ -0,0 +1,182 @@
+//! Archive snapshot tests (`SQLite`).
+
+use chrono::{NaiveDate, NaiveDateTime};
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, migrated_conn};
+use crate::{
+    db::{
+        FileInfoSource,
+        SnapshotOutcome,
+        create_file_node,
+        create_user,
+        delete_file_entry,
+        download_file_permission,
+        get_user_by_name,
+        grant_resource_permission,
+        is_archived_node,
+        last_snapshot_at,
+        list_child_file_nodes,
+        resolve_file_node_path,
+        seed_permission,
+        snapshot_folder,
+        stored_bytes_for_user,
+    },
+    models::{FileNodeKind, NewFileNode, NewResourcePermission, NewUser},
+};
+
+struct ArchiveFixture {
+    docs_id: i32,
+    guide_id: i32,
+}
+
+async fn seed_docs(conn: &mut DbConnection) -> Result<ArchiveFixture, AnyError> {
+    create_user(
+        conn,
+        &NewUser {
+            username: "alice",
+            password: "hash",
+        },
+    )
+    .await?;
+    let alice = get_user_by_name(conn, "alice")
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("user not found"))?;
+    let permission_id = seed_permission(conn, &download_file_permission()).await?;
+    let docs_id = create_file_node(
+        conn,
+        &NewFileNode {
+            kind: FileNodeKind::Folder.as_str(),
+            name: "Docs",
+            parent_id: None,
+            alias_target_id: None,
+            object_key: None,
+            size: None,
+            comment: None,
+            is_dropbox: false,
+            creator_id: alice.id,
+        },
+    )
+    .await?;
+    let guide_id = create_file_node(
+        conn,
+        &NewFileNode {
+            kind: FileNodeKind::File.as_str(),
+            name: "guide.txt",
+            parent_id: Some(docs_id),
+            alias_target_id: None,
+            object_key: Some("objects/guide"),
+            size: Some(42),
+            comment: Some("Read me"),
+            is_dropbox: false,
+            creator_id: alice.id,
+        },
+    )
+    .await?;
+    for resource_id in [docs_id, guide_id] {
+        grant_resource_permission(
+            conn,
+            &NewResourcePermission {
+                resource_type: "file_node",
+                resource_id,
+                principal_type: "user",
+                principal_id: alice.id,
+                permission_id,
+            },
+        )
+        .await?;
+    }
+    Ok(ArchiveFixture { docs_id, guide_id })
+}
+
+fn at(day: u32) -> Result<NaiveDateTime, AnyError> {
+    NaiveDate::from_ymd_opt(2026, 10, day)
+        .and_then(|date| date.and_hms_opt(14, 30, 0))
+        .ok_or_else(|| anyhow::anyhow!("invalid date"))
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_snapshot_copies_tree_into_read_only_archive(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let ArchiveFixture { docs_id, guide_id } = seed_docs(&mut conn).await?;
+
+    let outcome = snapshot_folder(&mut conn, docs_id, at(16)?, 2).await?;
+    assert_eq!(
+        outcome,
+        SnapshotOutcome::Taken {
+            name: "2026-10-16 1430".to_owned(),
+            pruned: 0,
+        }
+    );
+    assert_eq!(
+        snapshot_folder(&mut conn, docs_id, at(16)?, 2).await?,
+        SnapshotOutcome::AlreadyTaken
+    );
+    assert_eq!(last_snapshot_at(&mut conn, docs_id).await?, Some(at(16)?));
+
+    let copy = resolve_file_node_path(&mut conn, "Docs Archive/2026-10-16 1430/guide.txt")
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("archived copy missing"))?;
+    assert_eq!(copy.object_key.as_deref(), Some("objects/guide"));
+    assert_eq!(copy.comment.as_deref(), Some("Read me"));
+    assert!(is_archived_node(&mut conn, copy.id).await?);
+    assert!(!is_archived_node(&mut conn, guide_id).await?);
+
+    // The live file's content stays in use by the archived copy.
+    let released = delete_file_entry(&mut conn, FileInfoSource::Node(guide_id)).await?;
+    assert_eq!(released, None);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_snapshots_beyond_the_limit_are_pruned(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let ArchiveFixture { docs_id, .. } = seed_docs(&mut conn).await?;
+
+    for day in 14..=16 {
+        snapshot_folder(&mut conn, docs_id, at(day)?, 2).await?;
+    }
+
+    let archive = resolve_file_node_path(&mut conn, "Docs Archive")
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("archive folder missing"))?;
+    let names: Vec<String> = list_child_file_nodes(&mut conn, Some(archive.id))
+        .await?
+        .into_iter()
+        .map(|node| node.name)
+        .collect();
+    assert_eq!(names, ["2026-10-15 1430", "2026-10-16 1430"]);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_snapshot_copies_do_not_count_against_quotas(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let ArchiveFixture { docs_id, guide_id } = seed_docs(&mut conn).await?;
+    let alice = get_user_by_name(&mut conn, "alice")
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("user not found"))?;
+    assert_eq!(stored_bytes_for_user(&mut conn, alice.id).await?, 42);
+
+    snapshot_folder(&mut conn, docs_id, at(16)?, 2).await?;
+    assert_eq!(stored_bytes_for_user(&mut conn, alice.id).await?, 42);
+
+    // The snapshot still holds the content, so deleting the live file frees
+    // nothing.
+    delete_file_entry(&mut conn, FileInfoSource::Node(guide_id)).await?;
+    assert_eq!(stored_bytes_for_user(&mut conn, alice.id).await?, 42);
+    Ok(())
+}
@@ -13,16 +13,40 @@ use rstest::{fixture, rstest};
 #[cfg(feature = "sqlite")]
 use test_util::AnyError;
 
+#[cfg(feature = "sqlite")]
+mod article_delete_tests;
+#[cfg(feature = "sqlite")]
+mod article_reply_tests;
+#[cfg(feature = "sqlite")]
+mod ban_tests;
+#[cfg(feature = "sqlite")]
+mod file_archive_tests;
 #[cfg(any(feature = "sqlite", feature = "postgres"))]
 mod file_node_tests;
 #[cfg(feature = "sqlite")]
+mod news_concurrency_tests;
+#[cfg(feature = "sqlite")]
+mod news_digest_tests;
+#[cfg(feature = "sqlite")]
+mod news_linkage_tests;
+#[cfg(feature = "sqlite")]
+mod news_root_tests;
+#[cfg(feature = "sqlite")]
+mod news_structure_tests;
+#[cfg(feature = "sqlite")]
+mod outbox_tests;
+#[cfg(feature = "sqlite")]
 mod permission_tests;
 #[cfg(feature = "postgres")]
 mod permission_tests_postgres;
 #[cfg(feature = "postgres")]
 mod postgres_file_node_tests;
 #[cfg(feature = "sqlite")]
+mod session_token_tests;
+#[cfg(feature = "sqlite")]
 mod sqlite_file_node_tests;
+#[cfg(feature = "sqlite")]
+mod transfer_stats_tests;
 
 #[cfg(feature = "sqlite")]
 use super::*;
@@ -70,6 +94,45 @@ async fn test_create_and_get_user(#[future] migrated_conn: Result<DbConnection,
     assert_eq!(fetched.password, "hash");
 }
 
+#[cfg(feature = "sqlite")]
+#[rstest]
+#[tokio::test]
+async fn new_users_get_default_privileges_until_set(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) {
+    use crate::privileges::Privileges;
+
+    let mut conn = migrated_conn
+        .await
+        .expect("failed to create migrated test database");
+    let new_user = NewUser {
+        username: "alice",
+        password: "hash",
+    };
+    create_user(&mut conn, &new_user)
+        .await
+        .expect("failed to create user");
+    let created = get_user_by_name(&mut conn, "alice")
+        .await
+        .expect("lookup failed")
+        .expect("user not found");
+    // The migration's column default must match the protocol crate's set.
+    assert_eq!(
+        decode_privileges(created.privileges),
+        Privileges::default_user()
+    );
+
+    let granted = Privileges::admin() - Privileges::CANNOT_BE_DISCONNECTED;
+    assert_eq!(set_privileges(&mut conn, "alice", granted).await, Ok(1));
+    assert_eq!(set_privileges(&mut conn, "nobody", granted).await, Ok(0));
+    let updated = get_user_by_name(&mut conn, "alice")
+        .await
+        .expect("lookup failed")
+        .expect("user not found");
+    assert_eq!(decode_privileges(updated.privileges), granted);
+    assert_eq!(encode_privileges(granted), updated.privileges);
+}
+
 // basic smoke test for migrations and insertion
 #[cfg(feature = "sqlite")]
 #[rstest]
@@ -83,6 +146,7 @@ async fn test_create_bundle_and_category(#[future] migrated_conn: Result<DbConne
         name: "Bundle",
         guid: None,
         created_at: None,
+        root_id: PRIMARY_NEWS_ROOT,
     };
     let _ = create_bundle(&mut conn, &bun)
         .await
@@ -94,15 +158,30 @@ async fn test_create_bundle_and_category(#[future] migrated_conn: Result<DbConne
         add_sn: None,
         delete_sn: None,
         created_at: None,
+        root_id: PRIMARY_NEWS_ROOT,
     };
     create_category(&mut conn, &cat)
         .await
         .expect("failed to create category");
-    let _names = list_names_at_path(&mut conn, None)
+    let _names = list_names_at_path(&mut conn, "")
         .await
         .expect("failed to list names");
 }
 
+/// Create an account called `username` and return its id.
+#[cfg(feature = "sqlite")]
+async fn create_account(conn: &mut DbConnection, username: &str) -> Result<i32, AnyError> {
+    let new_user = NewUser {
+        username,
+        password: "hash",
+    };
+    create_user(conn, &new_user).await?;
+    let user = get_user_by_name(conn, username)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("user not found"))?;
+    Ok(user.id)
+}
+
 #[cfg(feature = "sqlite")]
 async fn seed_root_category(conn: &mut DbConnection, name: &'static str) -> Result<(), AnyError> {
     let cat = NewCategory {
@@ -112,6 +191,7 @@ async fn seed_root_category(conn: &mut DbConnection, name: &'static str) -> Resu
         add_sn: None,
         delete_sn: None,
         created_at: None,
+        root_id: PRIMARY_NEWS_ROOT,
     };
     create_category(conn, &cat).await?;
     Ok(())
@@ -120,7 +200,7 @@ async fn seed_root_category(conn: &mut DbConnection, name: &'static str) -> Resu
 #[cfg(feature = "sqlite")]
 #[rstest]
 #[tokio::test]
-async fn test_list_names_invalid_path(#[future] migrated_conn: Result<DbConnection, AnyError>) {
+async fn test_list_names_missing_path(#[future] migrated_conn: Result<DbConnection, AnyError>) {
     let mut conn = migrated_conn
         .await
         .expect("failed to create migrated test database");
@@ -130,13 +210,32 @@ async fn test_list_names_invalid_path(#[future] migrated_conn: Result<DbConnecti
         name: "RootBundle",
         guid: None,
         created_at: None,
+        root_id: PRIMARY_NEWS_ROOT,
     };
     create_bundle(&mut conn, &bun)
         .await
         .expect("failed to create bundle");
-    let err = list_names_at_path(&mut conn, Some("/missing"))
+    let err = list_names_at_path(&mut conn, "/missing")
+        .await
+        .expect_err("expected missing path error");
+    assert!(matches!(err, PathLookupError::NotFound));
+}
+
+#[cfg(feature = "sqlite")]
+#[rstest]
+#[case("/Root//General")]
+#[case("Root/\u{7}")]
+#[tokio::test]
+async fn test_list_names_malformed_path(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+    #[case] path: &str,
+) {
+    let mut conn = migrated_conn
+        .await
+        .expect("failed to create migrated test database");
+    let err = list_names_at_path(&mut conn, path)
         .await
-        .expect_err("expected invalid path error");
+        .expect_err("expected malformed path error");
     assert!(matches!(err, PathLookupError::InvalidPath));
 }
 
@@ -235,6 +334,92 @@ async fn test_create_root_article_invalid_path(
     };
     let err = create_root_article(&mut conn, "/missing", params)
         .await
-        .expect_err("expected invalid path failure");
-    assert!(matches!(err, PathLookupError::InvalidPath));
+        .expect_err("expected missing path failure");
+    assert!(matches!(err, PathLookupError::NotFound));
+}
+
+#[cfg(feature = "sqlite")]
+#[rstest]
+#[tokio::test]
+async fn test_list_names_reports_kinds_counts_and_activity(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) {
+    let mut conn = migrated_conn
+        .await
+        .expect("failed to create migrated test database");
+    let bundle_id = create_bundle(
+        &mut conn,
+        &NewBundle {
+            parent_bundle_id: None,
+            name: "Bundle",
+            guid: None,
+            created_at: None,
+            root_id: PRIMARY_NEWS_ROOT,
+        },
+    )
+    .await
+    .expect("failed to create bundle");
+    create_category(
+        &mut conn,
+        &NewCategory {
+            name: "Inner",
+            bundle_id: Some(bundle_id),
+            guid: None,
+            add_sn: None,
+            delete_sn: None,
+            created_at: None,
+            root_id: PRIMARY_NEWS_ROOT,
+        },
+    )
+    .await
+    .expect("failed to create nested category");
+    seed_root_category(&mut conn, "General")
+        .await
+        .expect("failed to seed category");
+    for title in ["First", "Second"] {
+        let params = CreateRootArticleParams {
+            title,
+            flags: 0,
+            data_flavor: "text/plain",
+            data: "body",
+        };
+        create_root_article(&mut conn, "/General", params)
+            .await
+            .expect("failed to create article");
+    }
+
+    let rows = list_names_at_path(&mut conn, "")
+        .await
+        .expect("failed to list names");
+
+    let summary: Vec<_> = rows
+        .iter()
+        .map(|row| (row.kind, row.name.as_str(), row.count))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (NewsEntryKind::Bundle, "Bundle", 1),
+            (NewsEntryKind::Category, "General", 2),
+        ]
+    );
+    let latest: Vec<_> = rows
+        .iter()
+        .map(|row| row.last_posted_at.is_some())
+        .collect();
+    assert_eq!(latest, vec![false, true]);
+}
+
+#[cfg(feature = "sqlite")]
+#[rstest]
+#[tokio::test]
+async fn maintenance_runs_every_statement(#[future] migrated_conn: Result<DbConnection, AnyError>) {
+    let mut conn = migrated_conn
+        .await
+        .expect("failed to create migrated test database");
+    let steps = run_maintenance(&mut conn)
+        .await
+        .expect("maintenance failed");
+    let statements: Vec<_> = steps.iter().map(|step| step.statement).collect();
+    assert_eq!(statements, maintenance_statements());
 }
@@ -0,0 +1,97 @@
+//! Concurrent news posting tests (`SQLite`).
+
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use rstest::rstest;
+use tempfile::TempDir;
+use test_util::AnyError;
+
+use super::seed_root_category;
+use crate::db::{
+    CreateRootArticleParams,
+    apply_migrations,
+    check_news_linkage,
+    create_reply_article,
+    create_root_article,
+    establish_pool,
+};
+
+const POSTERS: usize = 16;
+
+const fn params(title: &'static str) -> CreateRootArticleParams<'static> {
+    CreateRootArticleParams {
+        title,
+        flags: 0,
+        data_flavor: "text/plain",
+        data: "body",
+    }
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_posts_form_one_chain() -> Result<(), AnyError> {
+    use crate::schema::news_articles::dsl as a;
+
+    let dir = TempDir::new()?;
+    let database = dir.path().join("news.db").display().to_string();
+    let pool = establish_pool(&database).await?;
+    let first = {
+        let mut conn = pool.get().await?;
+        // Readers such as pool checkouts would otherwise find the file
+        // locked while a post commits.
+        diesel::sql_query("PRAGMA journal_mode = WAL")
+            .execute(&mut conn)
+            .await?;
+        apply_migrations(&mut conn, &database, None).await?;
+        seed_root_category(&mut conn, "General").await?;
+        create_root_article(&mut conn, "/General", params("First")).await?
+    };
+
+    // Every poster adds a root article and a reply to the first article, each
+    // on its own pooled connection.
+    let posters = (0..POSTERS).map(|_| {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let mut conn = pool.get().await?;
+            create_root_article(&mut conn, "/General", params("Root")).await?;
+            create_reply_article(&mut conn, "/General", first, params("Re: First")).await?;
+            Ok::<_, AnyError>(())
+        })
+    });
+    for poster in futures_util::future::join_all(posters).await {
+        poster??;
+    }
+
+    let mut conn = pool.get().await?;
+    assert!(check_news_linkage(&mut conn).await?.is_empty());
+
+    let links = a::news_articles
+        .select((
+            a::id,
+            a::parent_article_id,
+            a::prev_article_id,
+            a::next_article_id,
+        ))
+        .order(a::id.asc())
+        .load::<(i32, Option<i32>, Option<i32>, Option<i32>)>(&mut conn)
+        .await?;
+    for parent in [None, Some(first)] {
+        let siblings: Vec<_> = links.iter().filter(|link| link.1 == parent).collect();
+        let expected = if parent.is_none() {
+            POSTERS + 1
+        } else {
+            POSTERS
+        };
+        assert_eq!(siblings.len(), expected);
+        // Exactly one head and one tail, and every other link points at a
+        // distinct neighbour, so the siblings form a single chain.
+        assert_eq!(siblings.iter().filter(|link| link.2.is_none()).count(), 1);
+        assert_eq!(siblings.iter().filter(|link| link.3.is_none()).count(), 1);
+        let mut nexts: Vec<_> = siblings.iter().filter_map(|link| link.3).collect();
+        nexts.sort_unstable();
+        nexts.dedup();
+        assert_eq!(nexts.len(), expected - 1);
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! News digest subscription tests (`SQLite`).
+
+use anyhow::anyhow;
+use chrono::{TimeDelta, Utc};
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, migrated_conn};
+use crate::{
+    db::{
+        CreateRootArticleParams,
+        DigestSubscription,
+        NewsDigestSubscriber,
+        create_news_bundle,
+        create_news_category,
+        create_root_article,
+        create_user,
+        get_news_digest,
+        get_user_by_name,
+        new_digest_articles,
+        store_pending_digest,
+        subscribe_news_digest,
+        take_pending_digest,
+        unsubscribe_news_digest,
+    },
+    models::NewUser,
+};
+
+const fn params(title: &'static str) -> CreateRootArticleParams<'static> {
+    CreateRootArticleParams {
+        title,
+        flags: 0,
+        data_flavor: "text/plain",
+        data: "body",
+    }
+}
+
+async fn seed_alice(conn: &mut DbConnection) -> Result<i32, AnyError> {
+    create_user(
+        conn,
+        &NewUser {
+            username: "alice",
+            password: "hash",
+        },
+    )
+    .await?;
+    let user = get_user_by_name(conn, "alice")
+        .await?
+        .ok_or_else(|| anyhow!("user not found"))?;
+    Ok(user.id)
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_digests_list_new_articles_in_followed_categories(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = seed_alice(&mut conn).await?;
+    create_news_bundle(&mut conn, "", "Tech").await?;
+    let rust = create_news_category(&mut conn, "Tech", "Rust").await?;
+    create_news_category(&mut conn, "", "General").await?;
+    let before = Utc::now().naive_utc() - TimeDelta::minutes(1);
+    let subscription = DigestSubscription {
+        category_ids: &[rust],
+        email: None,
+    };
+    subscribe_news_digest(&mut conn, alice, subscription, before).await?;
+    create_root_article(&mut conn, "/Tech/Rust", params("Editions")).await?;
+    create_root_article(&mut conn, "/Tech/Rust", params("Lints")).await?;
+    create_root_article(&mut conn, "/General", params("Elsewhere")).await?;
+
+    let until = Utc::now().naive_utc() + TimeDelta::minutes(1);
+    let subscriber = get_news_digest(&mut conn, alice)
+        .await?
+        .ok_or_else(|| anyhow!("not subscribed"))?;
+    let (articles, total) = new_digest_articles(&mut conn, &subscriber, until, 1).await?;
+    assert_eq!(total, 2);
+    let first = articles.first().ok_or_else(|| anyhow!("no articles"))?;
+    assert_eq!(articles.len(), 1);
+    assert_eq!(first.category, "Tech/Rust");
+    assert_eq!(first.title, "Editions");
+
+    let caught_up = NewsDigestSubscriber {
+        last_visit_at: until,
+        ..subscriber
+    };
+    let (later, later_total) = new_digest_articles(&mut conn, &caught_up, until, 10).await?;
+    assert!(later.is_empty());
+    assert_eq!(later_total, 0);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_pending_digests_are_taken_once(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = seed_alice(&mut conn).await?;
+    let now = Utc::now().naive_utc();
+    assert_eq!(take_pending_digest(&mut conn, alice, now).await?, None);
+
+    let subscription = DigestSubscription {
+        category_ids: &[],
+        email: Some("alice@example.org"),
+    };
+    subscribe_news_digest(&mut conn, alice, subscription, now).await?;
+    assert_eq!(
+        store_pending_digest(&mut conn, alice, "News digest").await?,
+        1
+    );
+    let login = now + TimeDelta::hours(1);
+    assert_eq!(
+        take_pending_digest(&mut conn, alice, login).await?,
+        Some("News digest".to_owned())
+    );
+    assert_eq!(take_pending_digest(&mut conn, alice, login).await?, None);
+    let digest = get_news_digest(&mut conn, alice)
+        .await?
+        .ok_or_else(|| anyhow!("not subscribed"))?;
+    assert_eq!(digest.last_visit_at, login);
+    assert_eq!(digest.email.as_deref(), Some("alice@example.org"));
+
+    assert!(unsubscribe_news_digest(&mut conn, alice).await?);
+    assert!(!unsubscribe_news_digest(&mut conn, alice).await?);
+    assert_eq!(get_news_digest(&mut conn, alice).await?, None);
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! News thread link repair tests (`SQLite`).
+
+use anyhow::anyhow;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, migrated_conn, seed_root_category};
+use crate::db::{
+    CreateRootArticleParams,
+    LinkField,
+    LinkRepair,
+    check_news_linkage,
+    create_reply_article,
+    create_root_article,
+    get_article,
+    repair_news_linkage,
+};
+
+const fn params(title: &'static str) -> CreateRootArticleParams<'static> {
+    CreateRootArticleParams {
+        title,
+        flags: 0,
+        data_flavor: "text/plain",
+        data: "body",
+    }
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_broken_links_are_reported_then_repaired(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    use crate::schema::news_articles::dsl as a;
+
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let first = create_root_article(&mut conn, "/General", params("First")).await?;
+    let second = create_root_article(&mut conn, "/General", params("Second")).await?;
+    let reply = create_reply_article(&mut conn, "/General", first, params("Re: First"))
+        .await?
+        .ok_or_else(|| anyhow!("reply not created"))?;
+    assert!(check_news_linkage(&mut conn).await?.is_empty());
+
+    // Leave the chain as a crash between posting statements would.
+    diesel::update(a::news_articles.filter(a::id.eq(first)))
+        .set((
+            a::next_article_id.eq(None::<i32>),
+            a::first_child_article_id.eq(None::<i32>),
+        ))
+        .execute(&mut conn)
+        .await?;
+
+    let expected = [
+        LinkRepair {
+            article_id: first,
+            field: LinkField::Next,
+            found: None,
+            expected: Some(second),
+        },
+        LinkRepair {
+            article_id: first,
+            field: LinkField::FirstChild,
+            found: None,
+            expected: Some(reply),
+        },
+    ];
+    assert_eq!(check_news_linkage(&mut conn).await?, expected);
+    assert_eq!(repair_news_linkage(&mut conn).await?, expected);
+    assert!(check_news_linkage(&mut conn).await?.is_empty());
+
+    let repaired = get_article(&mut conn, "/General", first)
+        .await?
+        .ok_or_else(|| anyhow!("article missing"))?;
+    assert_eq!(repaired.next_article_id, Some(second));
+    assert_eq!(repaired.first_child_article_id, Some(reply));
+    Ok(())
+}
@@ -0,0 +1,130 @@
+//! News root isolation tests (`SQLite`).
+
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, migrated_conn};
+use crate::db::{
+    CreateRootArticleParams,
+    NewsItem,
+    NewsPath,
+    NewsRoot,
+    NewsStructureError,
+    PRIMARY_NEWS_ROOT,
+    PathLookupError,
+    create_news_bundle,
+    create_news_category,
+    create_news_root,
+    create_root_article,
+    find_news_item,
+    list_article_titles,
+    list_names_at_path,
+    list_news_roots,
+    news_root_exists,
+    search_articles,
+};
+
+async fn names_in(conn: &mut DbConnection, root: i32) -> Result<Vec<String>, AnyError> {
+    let rows = list_names_at_path(conn, NewsPath::new(root, "")).await?;
+    Ok(rows.into_iter().map(|row| row.name).collect())
+}
+
+const fn post(title: &'static str) -> CreateRootArticleParams<'static> {
+    CreateRootArticleParams {
+        title,
+        flags: 0,
+        data_flavor: "text/plain",
+        data: "body",
+    }
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn roots_are_listed_after_the_primary_root(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let games = create_news_root(&mut conn, "games").await?;
+
+    assert_ne!(games, PRIMARY_NEWS_ROOT);
+    assert!(news_root_exists(&mut conn, games).await?);
+    assert!(!news_root_exists(&mut conn, games + 1).await?);
+    assert_eq!(
+        list_news_roots(&mut conn).await?,
+        vec![
+            NewsRoot {
+                id: PRIMARY_NEWS_ROOT,
+                name: "main".to_owned(),
+            },
+            NewsRoot {
+                id: games,
+                name: "games".to_owned(),
+            },
+        ]
+    );
+    assert!(create_news_root(&mut conn, "games").await.is_err());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn roots_keep_their_trees_apart(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let games = create_news_root(&mut conn, "games").await?;
+    create_news_bundle(&mut conn, "", "Lobby").await?;
+    create_news_category(&mut conn, "/Lobby", "General").await?;
+    let lobby = create_news_bundle(&mut conn, NewsPath::new(games, ""), "Lobby").await?;
+    let general = create_news_category(&mut conn, NewsPath::new(games, "Lobby"), "General").await?;
+    create_news_category(&mut conn, NewsPath::new(games, ""), "Scores").await?;
+
+    assert_eq!(names_in(&mut conn, PRIMARY_NEWS_ROOT).await?, vec!["Lobby"]);
+    assert_eq!(names_in(&mut conn, games).await?, vec!["Lobby", "Scores"]);
+    assert_eq!(
+        find_news_item(&mut conn, NewsPath::new(games, "/Lobby")).await?,
+        NewsItem::Bundle(lobby)
+    );
+    assert_eq!(
+        find_news_item(&mut conn, NewsPath::new(games, "/Lobby/General")).await?,
+        NewsItem::Category(general)
+    );
+    assert!(matches!(
+        find_news_item(&mut conn, "/Scores").await,
+        Err(PathLookupError::NotFound)
+    ));
+
+    let in_games = NewsPath::new(games, "Lobby/General");
+    create_root_article(&mut conn, in_games, post("Speedrun")).await?;
+    create_root_article(&mut conn, "Lobby/General", post("Welcome")).await?;
+    assert_eq!(
+        list_article_titles(&mut conn, in_games).await?,
+        vec!["Speedrun"]
+    );
+    let hits = search_articles(&mut conn, games, "e", 10).await?;
+    let titles: Vec<_> = hits.iter().map(|hit| hit.title.as_str()).collect();
+    assert_eq!(titles, vec!["Speedrun"]);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn unknown_roots_are_not_found(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let missing = NewsPath::new(7, "");
+
+    assert!(matches!(
+        list_names_at_path(&mut conn, missing).await,
+        Err(PathLookupError::NotFound)
+    ));
+    assert!(matches!(
+        create_news_bundle(&mut conn, missing, "Lobby").await,
+        Err(NewsStructureError::Path(PathLookupError::NotFound))
+    ));
+    Ok(())
+}
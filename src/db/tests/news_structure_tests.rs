@@ -0,0 +1,167 @@
+//! News bundle and category management tests (`SQLite`).
+
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, migrated_conn};
+use crate::db::{
+    CreateRootArticleParams,
+    NewsDeletion,
+    NewsEntryKind,
+    NewsItem,
+    NewsStructureError,
+    PathLookupError,
+    create_news_bundle,
+    create_news_category,
+    create_root_article,
+    delete_news_item,
+    find_news_item,
+    list_names_at_path,
+};
+
+async fn names_at(conn: &mut DbConnection, path: &str) -> Result<Vec<String>, AnyError> {
+    let rows = list_names_at_path(conn, path).await?;
+    Ok(rows.into_iter().map(|row| row.name).collect())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_creates_nested_bundle_and_category(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let outer = create_news_bundle(&mut conn, "", "Outer").await?;
+    let inner = create_news_bundle(&mut conn, "/Outer", "Inner").await?;
+    let cat = create_news_category(&mut conn, "Outer/Inner", "General").await?;
+
+    assert_eq!(names_at(&mut conn, "/Outer").await?, vec!["Inner"]);
+    let rows = list_names_at_path(&mut conn, "/Outer/Inner").await?;
+    let kinds: Vec<_> = rows.iter().map(|row| row.kind).collect();
+    assert_eq!(kinds, vec![NewsEntryKind::Category]);
+    assert_eq!(
+        find_news_item(&mut conn, "/Outer").await?,
+        NewsItem::Bundle(outer)
+    );
+    assert_eq!(
+        find_news_item(&mut conn, "/Outer/Inner").await?,
+        NewsItem::Bundle(inner)
+    );
+    assert_eq!(
+        find_news_item(&mut conn, "/Outer/Inner/General").await?,
+        NewsItem::Category(cat)
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::bundle_then_category(true)]
+#[case::category_then_bundle(false)]
+#[tokio::test]
+async fn test_rejects_names_already_used_by_either_kind(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+    #[case] bundle_first: bool,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let result = if bundle_first {
+        create_news_bundle(&mut conn, "", "News").await?;
+        create_news_category(&mut conn, "", "News").await
+    } else {
+        create_news_category(&mut conn, "", "News").await?;
+        create_news_bundle(&mut conn, "", "News").await
+    };
+    let err = result.expect_err("duplicate name accepted");
+    assert!(matches!(err, NewsStructureError::NameTaken));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::empty("")]
+#[case::separator("a/b")]
+#[case::control("bad\u{7}")]
+#[tokio::test]
+async fn test_rejects_unusable_names(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+    #[case] name: &str,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let err = create_news_category(&mut conn, "", name)
+        .await
+        .expect_err("unusable name accepted");
+    assert!(matches!(err, NewsStructureError::InvalidName));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_create_under_missing_parent_fails(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let err = create_news_bundle(&mut conn, "/Missing", "Child")
+        .await
+        .expect_err("missing parent accepted");
+    assert!(matches!(
+        err,
+        NewsStructureError::Path(PathLookupError::NotFound)
+    ));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_deleting_bundle_removes_subtree(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    create_news_bundle(&mut conn, "", "Outer").await?;
+    create_news_bundle(&mut conn, "/Outer", "Inner").await?;
+    create_news_category(&mut conn, "/Outer", "Top").await?;
+    create_news_category(&mut conn, "/Outer/Inner", "Deep").await?;
+    create_news_category(&mut conn, "", "Kept").await?;
+    let params = CreateRootArticleParams {
+        title: "Post",
+        flags: 0,
+        data_flavor: "text/plain",
+        data: "body",
+    };
+    create_root_article(&mut conn, "/Outer/Inner/Deep", params).await?;
+
+    let item = find_news_item(&mut conn, "/Outer").await?;
+    let removed = delete_news_item(&mut conn, item).await?;
+    assert_eq!(
+        removed,
+        NewsDeletion {
+            bundles: 2,
+            categories: 2,
+            articles: 1,
+        }
+    );
+    assert_eq!(names_at(&mut conn, "").await?, vec!["Kept"]);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::root("/", PathLookupError::InvalidPath)]
+#[case::missing("/Nowhere", PathLookupError::NotFound)]
+#[tokio::test]
+async fn test_find_rejects_root_and_missing_paths(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+    #[case] path: &str,
+    #[case] expected: PathLookupError,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let err = find_news_item(&mut conn, path)
+        .await
+        .expect_err("lookup should fail");
+    assert_eq!(
+        std::mem::discriminant(&err),
+        std::mem::discriminant(&expected)
+    );
+    Ok(())
+}
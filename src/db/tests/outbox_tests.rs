@@ -0,0 +1,164 @@
+//! Transactional outbox tests (`SQLite`).
+
+use anyhow::anyhow;
+use chrono::{TimeDelta, Utc};
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, create_account, migrated_conn, seed_root_category};
+use crate::db::{
+    CreateRootArticleParams,
+    OutboxEvent,
+    OutboxRecord,
+    advance_outbox_cursor,
+    create_reply_article,
+    create_root_article,
+    mark_outbox_delivered,
+    pending_outbox_events,
+    prune_outbox,
+};
+
+const fn params(title: &'static str) -> CreateRootArticleParams<'static> {
+    CreateRootArticleParams {
+        title,
+        flags: 0,
+        data_flavor: "text/plain",
+        data: "body",
+    }
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_posts_write_outbox_events(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let root = create_root_article(&mut conn, "/General", params("Hello")).await?;
+    let reply = create_reply_article(&mut conn, "/General", root, params("Re: Hello"))
+        .await?
+        .ok_or_else(|| anyhow!("reply not created"))?;
+
+    let pending = pending_outbox_events(&mut conn, 10).await?;
+    let events = pending
+        .iter()
+        .map(OutboxRecord::decode)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        events,
+        vec![
+            OutboxEvent::ArticlePosted {
+                path: "/General".to_owned(),
+                article_id: root,
+                title: "Hello".to_owned(),
+            },
+            OutboxEvent::ArticlePosted {
+                path: "/General".to_owned(),
+                article_id: reply,
+                title: "Re: Hello".to_owned(),
+            },
+        ]
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_failed_posts_write_no_outbox_event(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let missing = create_reply_article(&mut conn, "/General", 99, params("Orphan")).await?;
+
+    assert!(missing.is_none());
+    assert!(pending_outbox_events(&mut conn, 10).await?.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_delivered_events_leave_the_queue(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    create_root_article(&mut conn, "/General", params("One")).await?;
+    create_root_article(&mut conn, "/General", params("Two")).await?;
+    let pending = pending_outbox_events(&mut conn, 1).await?;
+    assert_eq!(pending.len(), 1);
+
+    let ids: Vec<i32> = pending.iter().map(|record| record.id).collect();
+    assert_eq!(mark_outbox_delivered(&mut conn, &ids).await?, 1);
+    assert_eq!(pending_outbox_events(&mut conn, 10).await?.len(), 1);
+
+    let later = Utc::now().naive_utc() + TimeDelta::minutes(1);
+    assert_eq!(prune_outbox(&mut conn, later).await?, 1);
+    assert_eq!(pending_outbox_events(&mut conn, 10).await?.len(), 1);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_cursor_blocks_redelivery_after_crash_before_ack(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    seed_root_category(&mut conn, "General").await?;
+    let alice = create_account(&mut conn, "alice").await?;
+    create_root_article(&mut conn, "/General", params("Hello")).await?;
+    let event = pending_outbox_events(&mut conn, 1)
+        .await?
+        .first()
+        .map(|record| record.id)
+        .ok_or_else(|| anyhow!("no pending event"))?;
+
+    // First pass: the cursor moves and the push goes out, then the server
+    // stops before the event is marked delivered.
+    assert!(advance_outbox_cursor(&mut conn, alice, event).await?);
+
+    // After the restart the event is still pending, but the cursor refuses a
+    // second delivery to the same account.
+    assert_eq!(pending_outbox_events(&mut conn, 10).await?.len(), 1);
+    assert!(!advance_outbox_cursor(&mut conn, alice, event).await?);
+    assert_eq!(mark_outbox_delivered(&mut conn, &[event]).await?, 1);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_cursor_is_per_account(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = create_account(&mut conn, "alice").await?;
+    let bob = create_account(&mut conn, "bob").await?;
+
+    // The server stopped after alice's cursor moved but before bob's, so bob
+    // still receives the retried event.
+    assert!(advance_outbox_cursor(&mut conn, alice, 1).await?);
+    assert!(advance_outbox_cursor(&mut conn, bob, 1).await?);
+    assert!(!advance_outbox_cursor(&mut conn, bob, 1).await?);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_cursor_only_moves_forward(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = create_account(&mut conn, "alice").await?;
+
+    assert!(advance_outbox_cursor(&mut conn, alice, 5).await?);
+    assert!(!advance_outbox_cursor(&mut conn, alice, 3).await?);
+    assert!(!advance_outbox_cursor(&mut conn, alice, 5).await?);
+    assert!(advance_outbox_cursor(&mut conn, alice, 6).await?);
+    Ok(())
+}
@@ -0,0 +1,104 @@
+//! Session resumption token tests (`SQLite`).
+
+use chrono::{NaiveDateTime, TimeDelta};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, create_account, migrated_conn};
+use crate::db::{delete_user, issue_session_token, redeem_session_token};
+
+fn noon() -> NaiveDateTime {
+    NaiveDateTime::parse_from_str("2026-01-01 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap_or_default()
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn tokens_are_redeemed_once(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = create_account(&mut conn, "alice").await?;
+    let now = noon();
+    let token = issue_session_token(&mut conn, alice, now, now + TimeDelta::minutes(5)).await?;
+
+    assert_eq!(token.len(), 32);
+    assert!(!redeem_session_token(&mut conn, alice, "unknown", now).await?);
+    assert!(redeem_session_token(&mut conn, alice, &token, now).await?);
+    assert!(!redeem_session_token(&mut conn, alice, &token, now).await?);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn tokens_only_work_for_their_account(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = create_account(&mut conn, "alice").await?;
+    let bob = create_account(&mut conn, "bob").await?;
+    let now = noon();
+    let token = issue_session_token(&mut conn, alice, now, now + TimeDelta::minutes(5)).await?;
+
+    assert!(!redeem_session_token(&mut conn, bob, &token, now).await?);
+    assert!(redeem_session_token(&mut conn, alice, &token, now).await?);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn tokens_are_stored_as_digests(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    use crate::schema::session_tokens::dsl as t;
+
+    let mut conn = migrated_conn.await?;
+    let alice = create_account(&mut conn, "alice").await?;
+    let now = noon();
+    let token = issue_session_token(&mut conn, alice, now, now + TimeDelta::minutes(5)).await?;
+
+    let stored: Vec<String> = t::session_tokens.select(t::token).load(&mut conn).await?;
+    assert_eq!(stored.len(), 1);
+    assert!(
+        stored
+            .iter()
+            .all(|digest| digest.len() == 64 && *digest != token)
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn expired_tokens_are_refused(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = create_account(&mut conn, "alice").await?;
+    let now = noon();
+    let expires_at = now + TimeDelta::minutes(5);
+    let token = issue_session_token(&mut conn, alice, now, expires_at).await?;
+
+    assert!(!redeem_session_token(&mut conn, alice, &token, expires_at).await?);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn deleting_the_account_revokes_its_tokens(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = create_account(&mut conn, "alice").await?;
+    let now = noon();
+    let token = issue_session_token(&mut conn, alice, now, now + TimeDelta::minutes(5)).await?;
+
+    assert_eq!(delete_user(&mut conn, "alice").await?, 1);
+    assert!(!redeem_session_token(&mut conn, alice, &token, now).await?);
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Transfer statistics and download credit persistence tests (`SQLite`).
+
+use rstest::rstest;
+use test_util::AnyError;
+
+use super::{DbConnection, create_account, migrated_conn};
+use crate::db::{
+    TransferStats,
+    adjust_download_credits,
+    get_download_credits,
+    get_transfer_stats,
+    list_transfer_stats,
+    record_transfer_stats,
+    set_download_credits,
+};
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_recorded_batches_accumulate(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = create_account(&mut conn, "alice").await?;
+    assert!(get_transfer_stats(&mut conn, alice).await?.is_empty());
+
+    let upload = TransferStats {
+        bytes_uploaded: 1_000,
+        uploads: 1,
+        ..TransferStats::default()
+    };
+    let download = TransferStats {
+        bytes_downloaded: 4_096,
+        downloads: 2,
+        ..TransferStats::default()
+    };
+    record_transfer_stats(&mut conn, alice, &upload).await?;
+    record_transfer_stats(&mut conn, alice, &upload).await?;
+    record_transfer_stats(&mut conn, alice, &download).await?;
+
+    assert_eq!(
+        get_transfer_stats(&mut conn, alice).await?,
+        TransferStats {
+            bytes_uploaded: 2_000,
+            bytes_downloaded: 4_096,
+            uploads: 2,
+            downloads: 2,
+        }
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_report_lists_every_account(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let bob = create_account(&mut conn, "bob").await?;
+    create_account(&mut conn, "alice").await?;
+    let delta = TransferStats {
+        bytes_downloaded: 512,
+        downloads: 1,
+        ..TransferStats::default()
+    };
+    record_transfer_stats(&mut conn, bob, &delta).await?;
+
+    assert_eq!(
+        list_transfer_stats(&mut conn).await?,
+        vec![
+            ("alice".to_owned(), TransferStats::default()),
+            ("bob".to_owned(), delta),
+        ]
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[tokio::test]
+async fn test_download_credits_adjust_and_reset(
+    #[future] migrated_conn: Result<DbConnection, AnyError>,
+) -> Result<(), AnyError> {
+    let mut conn = migrated_conn.await?;
+    let alice = create_account(&mut conn, "alice").await?;
+    assert_eq!(get_download_credits(&mut conn, alice).await?, 0);
+
+    adjust_download_credits(&mut conn, alice, 5_000).await?;
+    adjust_download_credits(&mut conn, alice, -1_500).await?;
+    assert_eq!(get_download_credits(&mut conn, alice).await?, 3_500);
+
+    set_download_credits(&mut conn, alice, 100).await?;
+    assert_eq!(get_download_credits(&mut conn, alice).await?, 100);
+    Ok(())
+}
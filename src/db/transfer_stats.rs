@@ -0,0 +1,127 @@
+//! Per-account transfer statistics.
+//!
+//! Each account has at most one row of running totals. Sessions count their
+//! transfers in memory and add them here in batches, so a row only changes
+//! when a batch is recorded; an account without a row has transferred
+//! nothing.
+
+use diesel::{prelude::*, result::QueryResult, upsert::excluded};
+use diesel_async::RunQueryDsl;
+
+use super::connection::DbConnection;
+
+/// Bytes and files an account has transferred.
+#[derive(Queryable, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferStats {
+    /// Bytes the account has uploaded.
+    pub bytes_uploaded: i64,
+    /// Bytes the account has downloaded.
+    pub bytes_downloaded: i64,
+    /// Completed uploads.
+    pub uploads: i64,
+    /// Completed downloads.
+    pub downloads: i64,
+}
+
+impl TransferStats {
+    /// Return `true` when nothing has been transferred.
+    #[must_use]
+    pub fn is_empty(&self) -> bool { *self == Self::default() }
+
+    /// Add `other` to these totals, saturating rather than wrapping.
+    #[must_use]
+    pub const fn saturating_add(self, other: Self) -> Self {
+        Self {
+            bytes_uploaded: self.bytes_uploaded.saturating_add(other.bytes_uploaded),
+            bytes_downloaded: self.bytes_downloaded.saturating_add(other.bytes_downloaded),
+            uploads: self.uploads.saturating_add(other.uploads),
+            downloads: self.downloads.saturating_add(other.downloads),
+        }
+    }
+}
+
+/// Add `delta` to the totals stored for `user_id`, creating the row on the
+/// account's first recorded transfer.
+///
+/// # Errors
+/// Returns any error produced by the upsert.
+#[must_use = "handle the result"]
+pub async fn record_transfer_stats(
+    conn: &mut DbConnection,
+    user_id: i32,
+    delta: &TransferStats,
+) -> QueryResult<usize> {
+    use crate::schema::transfer_stats::dsl as t;
+    diesel::insert_into(t::transfer_stats)
+        .values((
+            t::user_id.eq(user_id),
+            t::bytes_uploaded.eq(delta.bytes_uploaded),
+            t::bytes_downloaded.eq(delta.bytes_downloaded),
+            t::uploads.eq(delta.uploads),
+            t::downloads.eq(delta.downloads),
+        ))
+        .on_conflict(t::user_id)
+        .do_update()
+        .set((
+            t::bytes_uploaded.eq(t::bytes_uploaded + excluded(t::bytes_uploaded)),
+            t::bytes_downloaded.eq(t::bytes_downloaded + excluded(t::bytes_downloaded)),
+            t::uploads.eq(t::uploads + excluded(t::uploads)),
+            t::downloads.eq(t::downloads + excluded(t::downloads)),
+        ))
+        .execute(conn)
+        .await
+}
+
+/// Return the recorded totals for `user_id`, or zeroes when it has none.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn get_transfer_stats(
+    conn: &mut DbConnection,
+    user_id: i32,
+) -> QueryResult<TransferStats> {
+    use crate::schema::transfer_stats::dsl as t;
+    let stats = t::transfer_stats
+        .filter(t::user_id.eq(user_id))
+        .select((
+            t::bytes_uploaded,
+            t::bytes_downloaded,
+            t::uploads,
+            t::downloads,
+        ))
+        .first::<TransferStats>(conn)
+        .await
+        .optional()?;
+    Ok(stats.unwrap_or_default())
+}
+
+/// List every account's login with its recorded totals, ordered by login.
+///
+/// # Errors
+/// Returns any error produced by the query.
+#[must_use = "handle the result"]
+pub async fn list_transfer_stats(
+    conn: &mut DbConnection,
+) -> QueryResult<Vec<(String, TransferStats)>> {
+    use crate::schema::{transfer_stats::dsl as t, users::dsl as u};
+    let rows = u::users
+        .left_join(t::transfer_stats)
+        .select((
+            u::username,
+            (
+                t::bytes_uploaded,
+                t::bytes_downloaded,
+                t::uploads,
+                t::downloads,
+            )
+                .nullable(),
+        ))
+        .order(u::username.asc())
+        .load::<(String, Option<TransferStats>)>(conn)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(username, stats)| (username, stats.unwrap_or_default()))
+        .collect())
+}
@@ -1,9 +1,21 @@
 //! User record helpers.
 
 use diesel::{prelude::*, result::QueryResult};
-use diesel_async::RunQueryDsl;
+use diesel_async::{AsyncConnection, RunQueryDsl};
 
 use super::connection::DbConnection;
+use crate::privileges::Privileges;
+
+/// Decode a `users.privileges` value, ignoring bits no privilege is defined
+/// for.
+#[must_use]
+pub const fn decode_privileges(column: i64) -> Privileges {
+    Privileges::from_bits_truncate(column.cast_unsigned())
+}
+
+/// Encode `privileges` for the `users.privileges` column.
+#[must_use]
+pub const fn encode_privileges(privileges: Privileges) -> i64 { privileges.bits().cast_signed() }
 
 /// Look up a user record by username.
 ///
@@ -43,6 +55,19 @@ pub async fn get_user_by_id(
         .optional()
 }
 
+/// List every user record, ordered by username.
+///
+/// # Errors
+/// Returns any error produced by the underlying database query.
+#[must_use = "handle the result"]
+pub async fn list_users(conn: &mut DbConnection) -> QueryResult<Vec<crate::models::User>> {
+    use crate::schema::users::dsl::{username, users};
+    users
+        .order(username.asc())
+        .load::<crate::models::User>(conn)
+        .await
+}
+
 /// Insert a new user record.
 ///
 /// # Errors
@@ -55,3 +80,82 @@ pub async fn create_user(
     use crate::schema::users::dsl::users;
     diesel::insert_into(users).values(user).execute(conn).await
 }
+
+/// Changes to apply to an existing user record.
+#[derive(AsChangeset, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[diesel(table_name = crate::schema::users)]
+pub struct UserUpdate<'a> {
+    /// Replacement login name, if renaming the account.
+    pub username: Option<&'a str>,
+    /// Replacement password hash, if changing the password.
+    pub password: Option<&'a str>,
+}
+
+impl UserUpdate<'_> {
+    /// Return `true` when the update would change nothing.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool { self.username.is_none() && self.password.is_none() }
+}
+
+/// Apply `update` to the user named `name`, returning the number of rows
+/// changed.
+///
+/// # Errors
+/// Returns any error produced by the update query, including a query builder
+/// error when `update` is empty.
+#[must_use = "handle the result"]
+pub async fn update_user(
+    conn: &mut DbConnection,
+    name: &str,
+    update: &UserUpdate<'_>,
+) -> QueryResult<usize> {
+    use crate::schema::users::dsl::{username, users};
+    diesel::update(users.filter(username.eq(name)))
+        .set(update)
+        .execute(conn)
+        .await
+}
+
+/// Replace the privileges of the user named `name`, returning the number of
+/// rows changed.
+///
+/// # Errors
+/// Returns any error produced by the update query.
+#[must_use = "handle the result"]
+pub async fn set_privileges(
+    conn: &mut DbConnection,
+    name: &str,
+    privileges: Privileges,
+) -> QueryResult<usize> {
+    use crate::schema::users::dsl::{privileges as privileges_column, username, users};
+    diesel::update(users.filter(username.eq(name)))
+        .set(privileges_column.eq(encode_privileges(privileges)))
+        .execute(conn)
+        .await
+}
+
+/// Delete the user named `name` and its session tokens, returning the
+/// number of users removed.
+///
+/// # Errors
+/// Returns any error produced by the deletion queries, including a foreign
+/// key violation while file entries still name the user as their creator.
+#[must_use = "handle the result"]
+pub async fn delete_user(conn: &mut DbConnection, name: &str) -> QueryResult<usize> {
+    use crate::schema::{
+        session_tokens::dsl as t,
+        users::dsl::{id, username, users},
+    };
+    conn.transaction::<_, diesel::result::Error, _>(async |tx_conn| {
+        // `SQLite` does not cascade the delete, and a later account may be
+        // given the same id.
+        let account = users.filter(username.eq(name)).select(id);
+        diesel::delete(t::session_tokens.filter(t::user_id.eq_any(account)))
+            .execute(tx_conn)
+            .await?;
+        diesel::delete(users.filter(username.eq(name)))
+            .execute(tx_conn)
+            .await
+    })
+    .await
+}
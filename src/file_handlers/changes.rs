@@ -0,0 +1,185 @@
+//! Delete File (204) and Move File (208).
+
+use std::sync::Arc;
+
+use tracing::{info, warn};
+
+use super::{
+    FileHandlerError,
+    encode_reply,
+    ensure_entry_readable,
+    ensure_writable,
+    file_error_reply,
+    find_entry,
+    folder_segments,
+    session_user_id,
+};
+use crate::{
+    commands::CommandError,
+    db::{DbPool, FileInfoSource, acquire, delete_file_entry, find_visible_folder, move_file_node},
+    handler::Session,
+    privileges::Privileges,
+    storage::Storage,
+    transaction::{FrameHeader, ReplyParams, Transaction, TransactionParams},
+    transaction_type::TransactionType,
+};
+
+/// Parameters for deleting a file or folder.
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
+pub struct DeleteFileRequest {
+    #[param(FileItemName)]
+    pub(crate) name: String,
+    #[param(FilePath)]
+    pub(crate) path: Option<Vec<u8>>,
+}
+
+/// Parameters for moving a file or folder to another folder.
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
+pub struct MoveFileRequest {
+    #[param(FileItemName)]
+    pub(crate) name: String,
+    #[param(FilePath)]
+    pub(crate) path: Option<Vec<u8>>,
+    #[param(FileNewPath)]
+    pub(crate) new_path: Option<Vec<u8>>,
+}
+
+/// The database and content backend a delete removes an entry from.
+#[derive(Clone, Copy)]
+pub struct FileStore<'a> {
+    /// Database connection pool.
+    pub pool: &'a DbPool,
+    /// Backend holding file content, if the server configured one.
+    pub storage: Option<&'a Arc<dyn Storage>>,
+}
+
+const fn delete_privilege(is_folder: bool) -> Privileges {
+    if is_folder {
+        Privileges::DELETE_FOLDER
+    } else {
+        Privileges::DELETE_FILE
+    }
+}
+
+const fn move_privilege(is_folder: bool) -> Privileges {
+    if is_folder {
+        Privileges::MOVE_FOLDER
+    } else {
+        Privileges::MOVE_FILE
+    }
+}
+
+/// Handle Delete File commands once the dispatcher has checked access.
+///
+/// Files need Delete File and folders need Delete Folder. Folders must be
+/// empty, entries inside an archive cannot be deleted, and entries inside a
+/// drop box need View Drop Boxes as well.
+///
+/// # Errors
+/// Returns an error if the authenticated session has no user id.
+pub async fn process_delete_file(
+    store: FileStore<'_>,
+    session: &Session,
+    header: &FrameHeader,
+    req: &DeleteFileRequest,
+) -> Result<Transaction, CommandError> {
+    let user_id = session_user_id(session)?;
+    Ok(
+        match delete_entry(store.pool, session, user_id, req).await {
+            Ok(released) => {
+                if let Some(object_key) = released {
+                    info!(user_id, %object_key, "file content released by delete");
+                    release_content(store.storage, &object_key).await;
+                }
+                encode_reply(header, ReplyParams::new())
+            }
+            Err(err) => file_error_reply(header, err),
+        },
+    )
+}
+
+/// Handle Move File commands once the dispatcher has checked access.
+///
+/// Files need Move File and folders need Move Folder. An absent destination
+/// path moves the entry to the root. Nothing moves into or out of an
+/// archive, and only holders of View Drop Boxes may move anything out of a
+/// drop box.
+///
+/// # Errors
+/// Returns an error if the authenticated session has no user id.
+pub async fn process_move_file(
+    pool: &DbPool,
+    session: &Session,
+    header: &FrameHeader,
+    req: &MoveFileRequest,
+) -> Result<Transaction, CommandError> {
+    let user_id = session_user_id(session)?;
+    Ok(match move_entry(pool, session, user_id, req).await {
+        Ok(()) => encode_reply(header, ReplyParams::new()),
+        Err(err) => file_error_reply(header, err),
+    })
+}
+
+async fn delete_entry(
+    pool: &DbPool,
+    session: &Session,
+    user_id: i32,
+    req: &DeleteFileRequest,
+) -> Result<Option<String>, FileHandlerError> {
+    let folder = folder_segments(req.path.as_deref())?;
+    let mut conn = acquire(pool, TransactionType::DeleteFile).await?;
+    let found = find_entry(&mut conn, user_id, &folder, &req.name).await?;
+    ensure_entry_readable(&mut conn, session, found.source).await?;
+    session
+        .require_privilege(delete_privilege(found.info.is_folder))
+        .map_err(FileHandlerError::Privilege)?;
+    ensure_writable(&mut conn, found.source).await?;
+    Ok(delete_file_entry(&mut conn, found.source).await?)
+}
+
+/// Remove released content from the storage backend. The entry is already
+/// gone, so a failure only leaves an orphaned object behind and is logged.
+async fn release_content(storage: Option<&Arc<dyn Storage>>, object_key: &str) {
+    let Some(backend) = storage else {
+        return;
+    };
+    if let Err(error) = backend.delete(object_key).await {
+        warn!(%object_key, %error, "released file content not removed from storage");
+    }
+}
+
+async fn move_entry(
+    pool: &DbPool,
+    session: &Session,
+    user_id: i32,
+    req: &MoveFileRequest,
+) -> Result<(), FileHandlerError> {
+    let folder = folder_segments(req.path.as_deref())?;
+    let destination = folder_segments(req.new_path.as_deref())?;
+    let mut conn = acquire(pool, TransactionType::MoveFile).await?;
+    let found = find_entry(&mut conn, user_id, &folder, &req.name).await?;
+    ensure_entry_readable(&mut conn, session, found.source).await?;
+    session
+        .require_privilege(move_privilege(found.info.is_folder))
+        .map_err(FileHandlerError::Privilege)?;
+    ensure_writable(&mut conn, found.source).await?;
+    let FileInfoSource::Node(node_id) = found.source else {
+        // Legacy entries live only at the root and cannot enter folders.
+        return if destination.is_empty() {
+            Ok(())
+        } else {
+            Err(FileHandlerError::PathUnsupported)
+        };
+    };
+    let new_parent = if destination.is_empty() {
+        None
+    } else {
+        let parent = find_visible_folder(&mut conn, user_id, &destination)
+            .await?
+            .ok_or(FileHandlerError::NotFound)?;
+        ensure_writable(&mut conn, FileInfoSource::Node(parent.id)).await?;
+        Some(parent.id)
+    };
+    move_file_node(&mut conn, node_id, new_parent).await?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+//! Get File Name List (200) for the root and nested folders.
+
+use super::{
+    FileHandlerError,
+    encode_reply,
+    ensure_readable,
+    file_error_reply,
+    folder_segments,
+    session_user_id,
+};
+use crate::{
+    commands::CommandError,
+    db::{
+        DbPool,
+        acquire,
+        find_visible_folder,
+        list_visible_child_file_nodes_for_user,
+        list_visible_root_file_nodes_for_user,
+    },
+    field_id::FieldId,
+    handler::Session,
+    models::VisibleFileNode,
+    transaction::{FrameHeader, ReplyParams, Transaction},
+    transaction_type::TransactionType,
+};
+
+/// Handle Get File Name List commands once the dispatcher has checked access.
+///
+/// Without a path, or with an empty one, the root listing merges file nodes
+/// and legacy files. A path names a folder whose visible children are listed;
+/// the contents of a drop box need View Drop Boxes.
+///
+/// # Errors
+/// Returns an error if the authenticated session has no user id.
+pub async fn process_get_file_name_list(
+    pool: &DbPool,
+    session: &Session,
+    header: &FrameHeader,
+    path: Option<&[u8]>,
+) -> Result<Transaction, CommandError> {
+    let user_id = session_user_id(session)?;
+    Ok(match list_folder(pool, session, user_id, path).await {
+        Ok(entries) => encode_reply(header, name_params(&entries)),
+        Err(err) => file_error_reply(header, err),
+    })
+}
+
+async fn list_folder(
+    pool: &DbPool,
+    session: &Session,
+    user_id: i32,
+    path: Option<&[u8]>,
+) -> Result<Vec<VisibleFileNode>, FileHandlerError> {
+    let segments = folder_segments(path)?;
+    let mut conn = acquire(pool, TransactionType::GetFileNameList).await?;
+    if segments.is_empty() {
+        return Ok(list_visible_root_file_nodes_for_user(&mut conn, user_id).await?);
+    }
+    let folder = find_visible_folder(&mut conn, user_id, &segments)
+        .await?
+        .ok_or(FileHandlerError::NotFound)?;
+    ensure_readable(&mut conn, session, folder.id).await?;
+    Ok(list_visible_child_file_nodes_for_user(&mut conn, user_id, folder.id).await?)
+}
+
+fn name_params(entries: &[VisibleFileNode]) -> ReplyParams {
+    entries.iter().fold(ReplyParams::new(), |reply, entry| {
+        reply.string(FieldId::FileName, &entry.name)
+    })
+}
@@ -0,0 +1,474 @@
+//! File-area command helpers and database operations.
+//!
+//! These helpers implement Get File Name List (200), Download File (202),
+//! Upload File (203), Delete File (204), Get File Info (206), Set File Info
+//! (207), and Move File (208), keeping file-related transactions and
+//! database access grouped together as [`crate::news_handlers`] does for
+//! news. Renaming is part of Set File Info; Hotline has no separate rename
+//! transaction. Archive snapshots are read-only: changing anything inside
+//! one fails with [`FILE_ERR_READ_ONLY`]. Drop boxes accept deposits from
+//! anyone allowed to upload, but only holders of
+//! [`Privileges::VIEW_DROP_BOXES`] may list them, download from them, or
+//! inspect, change, delete, or move anything inside them.
+
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel_async::pooled_connection::bb8::RunError;
+use tracing::{error, warn};
+
+use crate::{
+    commands::{
+        CommandError,
+        ERR_INTERNAL_SERVER,
+        ERR_INVALID_PAYLOAD,
+        FILE_ERR_DOWNLOAD_REFUSED,
+        FILE_ERR_FOLDER_NOT_EMPTY,
+        FILE_ERR_NAME_TAKEN,
+        FILE_ERR_NOT_FOUND,
+        FILE_ERR_PATH_UNSUPPORTED,
+        FILE_ERR_QUOTA_EXCEEDED,
+        FILE_ERR_READ_ONLY,
+        pool_error_code,
+        privilege_error_reply,
+    },
+    db::{
+        DbConnection,
+        DbPool,
+        FileInfo,
+        FileInfoSource,
+        FileInfoUpdate,
+        FileMutationError,
+        FileNodeLookupError,
+        VisibleFileInfo,
+        acquire,
+        find_visible_file_info,
+        is_archived_node,
+        is_in_drop_box,
+        update_file_info,
+    },
+    field_id::FieldId,
+    handler::{PrivilegeError, Session},
+    header_util::reply_header,
+    privileges::Privileges,
+    server::{
+        download_policy::{DownloadCheckError, DownloadRefusal},
+        storage_quota::QuotaExceeded,
+    },
+    storage::StorageError,
+    transaction::{FrameHeader, ReplyParams, Transaction, TransactionParams},
+    transaction_type::TransactionType,
+    wire_time::optional_timestamp_millis,
+};
+
+mod changes;
+mod listing;
+mod path;
+mod transfer;
+
+pub use changes::{
+    DeleteFileRequest,
+    FileStore,
+    MoveFileRequest,
+    process_delete_file,
+    process_move_file,
+};
+pub use listing::process_get_file_name_list;
+pub use path::{decode_file_path, encode_file_path};
+pub use transfer::{
+    DownloadFileRequest,
+    TransferContext,
+    UploadFileRequest,
+    process_download_file,
+    process_upload_file,
+};
+
+/// Four-byte type code reported for folders.
+pub const FOLDER_TYPE_CODE: &str = "fldr";
+
+/// Parameters for retrieving a file or folder's metadata.
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
+pub struct FileInfoRequest {
+    #[param(FileItemName)]
+    pub(crate) name: String,
+    #[param(FilePath)]
+    pub(crate) path: Option<Vec<u8>>,
+}
+
+/// Parameters for renaming a file or folder or changing its comment.
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
+pub struct SetFileInfoRequest {
+    #[param(FileItemName)]
+    pub(crate) name: String,
+    #[param(FilePath)]
+    pub(crate) path: Option<Vec<u8>>,
+    /// New name; clients leave it blank when only the comment changes.
+    #[param(FileNewName, omit_blank)]
+    pub(crate) new_name: Option<String>,
+    #[param(FileComment)]
+    pub(crate) comment: Option<String>,
+}
+
+impl SetFileInfoRequest {
+    /// Privileges needed to apply this request to a file or folder.
+    fn required_privileges(&self, is_folder: bool) -> Privileges {
+        let mut required = Privileges::empty();
+        if self.new_name.is_some() {
+            required |= if is_folder {
+                Privileges::RENAME_FOLDER
+            } else {
+                Privileges::RENAME_FILE
+            };
+        }
+        if self.comment.is_some() {
+            required |= if is_folder {
+                Privileges::SET_FOLDER_COMMENT
+            } else {
+                Privileges::SET_FILE_COMMENT
+            };
+        }
+        required
+    }
+
+    fn to_update(&self) -> FileInfoUpdate<'_> {
+        FileInfoUpdate {
+            new_name: self.new_name.as_deref(),
+            comment: self.comment.as_deref(),
+        }
+    }
+}
+
+enum FileHandlerError {
+    PathUnsupported,
+    InvalidPath,
+    NotFound,
+    InvalidName,
+    NameTaken,
+    FolderNotEmpty,
+    MoveIntoSelf,
+    ReadOnly,
+    NoStorage,
+    Untransferable,
+    DownloadRefused(DownloadRefusal),
+    QuotaExceeded(QuotaExceeded),
+    UploadTooLarge(u64),
+    Privilege(PrivilegeError),
+    Pool(RunError),
+    Database(DieselError),
+    Storage(StorageError),
+}
+
+impl From<DieselError> for FileHandlerError {
+    fn from(err: DieselError) -> Self {
+        match err {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => Self::NameTaken,
+            other => Self::Database(other),
+        }
+    }
+}
+
+impl From<RunError> for FileHandlerError {
+    fn from(err: RunError) -> Self { Self::Pool(err) }
+}
+
+impl From<StorageError> for FileHandlerError {
+    fn from(err: StorageError) -> Self { Self::Storage(err) }
+}
+
+impl From<DownloadCheckError> for FileHandlerError {
+    fn from(err: DownloadCheckError) -> Self {
+        match err {
+            DownloadCheckError::Refused(refusal) => Self::DownloadRefused(refusal),
+            DownloadCheckError::Database(source) => source.into(),
+        }
+    }
+}
+
+impl From<FileNodeLookupError> for FileHandlerError {
+    fn from(err: FileNodeLookupError) -> Self {
+        match err {
+            FileNodeLookupError::InvalidPath => Self::InvalidPath,
+            FileNodeLookupError::Diesel(source) => Self::Database(source),
+            FileNodeLookupError::Serde(source) => {
+                Self::Database(DieselError::SerializationError(Box::new(source)))
+            }
+        }
+    }
+}
+
+impl From<FileMutationError> for FileHandlerError {
+    fn from(err: FileMutationError) -> Self {
+        match err {
+            FileMutationError::FolderNotEmpty => Self::FolderNotEmpty,
+            FileMutationError::MoveIntoSelf => Self::MoveIntoSelf,
+            FileMutationError::NameTaken => Self::NameTaken,
+            FileMutationError::Diesel(source) => source.into(),
+        }
+    }
+}
+
+/// Handle Get File Info commands once the dispatcher has checked access.
+///
+/// # Errors
+/// Returns an error if the authenticated session has no user id.
+pub async fn process_get_file_info(
+    pool: &DbPool,
+    session: &Session,
+    header: &FrameHeader,
+    req: &FileInfoRequest,
+) -> Result<Transaction, CommandError> {
+    let user_id = session_user_id(session)?;
+    Ok(match fetch_file_info(pool, session, user_id, req).await {
+        Ok(found) => encode_reply(header, file_info_params(&found.info)),
+        Err(err) => file_error_reply(header, err),
+    })
+}
+
+/// Handle Set File Info commands once the dispatcher has checked access.
+///
+/// Renaming needs Rename File or Rename Folder, and changing the comment needs
+/// Set File Comment or Set Folder Comment, depending on the entry's kind.
+///
+/// # Errors
+/// Returns an error if the authenticated session has no user id.
+pub async fn process_set_file_info(
+    pool: &DbPool,
+    session: &Session,
+    header: &FrameHeader,
+    req: &SetFileInfoRequest,
+) -> Result<Transaction, CommandError> {
+    let user_id = session_user_id(session)?;
+    Ok(match apply_file_info(pool, session, user_id, req).await {
+        Ok(()) => encode_reply(header, ReplyParams::new()),
+        Err(err) => file_error_reply(header, err),
+    })
+}
+
+fn session_user_id(session: &Session) -> Result<i32, CommandError> {
+    session.user_id.ok_or_else(|| {
+        error!("authenticated session missing user id in file handler");
+        CommandError::Invariant("authenticated session missing user id")
+    })
+}
+
+async fn fetch_file_info(
+    pool: &DbPool,
+    session: &Session,
+    user_id: i32,
+    req: &FileInfoRequest,
+) -> Result<VisibleFileInfo, FileHandlerError> {
+    let folder = folder_segments(req.path.as_deref())?;
+    let mut conn = acquire(pool, TransactionType::GetFileInfo).await?;
+    let found = find_entry(&mut conn, user_id, &folder, &req.name).await?;
+    ensure_entry_readable(&mut conn, session, found.source).await?;
+    Ok(found)
+}
+
+async fn apply_file_info(
+    pool: &DbPool,
+    session: &Session,
+    user_id: i32,
+    req: &SetFileInfoRequest,
+) -> Result<(), FileHandlerError> {
+    let folder = folder_segments(req.path.as_deref())?;
+    if req
+        .new_name
+        .as_deref()
+        .is_some_and(|name| !is_valid_name(name))
+    {
+        return Err(FileHandlerError::InvalidName);
+    }
+    let mut conn = acquire(pool, TransactionType::SetFileInfo).await?;
+    let found = find_entry(&mut conn, user_id, &folder, &req.name).await?;
+    ensure_entry_readable(&mut conn, session, found.source).await?;
+    session
+        .require_privilege(req.required_privileges(found.info.is_folder))
+        .map_err(FileHandlerError::Privilege)?;
+    ensure_writable(&mut conn, found.source).await?;
+    Ok(update_file_info(&mut conn, found.source, req.to_update()).await?)
+}
+
+/// Decode an optional field 202 path; an absent path addresses the root.
+fn folder_segments(path: Option<&[u8]>) -> Result<Vec<String>, FileHandlerError> {
+    path.map_or_else(
+        || Ok(Vec::new()),
+        |bytes| decode_file_path(bytes).ok_or(FileHandlerError::InvalidPath),
+    )
+}
+
+/// Look up the visible entry `name` inside `folder`.
+async fn find_entry(
+    conn: &mut DbConnection,
+    user_id: i32,
+    folder: &[String],
+    name: &str,
+) -> Result<VisibleFileInfo, FileHandlerError> {
+    find_visible_file_info(conn, user_id, folder, name)
+        .await?
+        .ok_or(FileHandlerError::NotFound)
+}
+
+/// Refuse changes to archive snapshots; see [`crate::db::is_archived_node`].
+async fn ensure_writable(
+    conn: &mut DbConnection,
+    source: FileInfoSource,
+) -> Result<(), FileHandlerError> {
+    match source {
+        FileInfoSource::Node(node_id) if is_archived_node(conn, node_id).await? => {
+            Err(FileHandlerError::ReadOnly)
+        }
+        FileInfoSource::Node(_) | FileInfoSource::Legacy(_) => Ok(()),
+    }
+}
+
+/// Refuse to reveal what a drop box holds, or send a file out of one, unless
+/// `session` holds View Drop Boxes; see [`crate::db::is_in_drop_box`].
+async fn ensure_readable(
+    conn: &mut DbConnection,
+    session: &Session,
+    node_id: i32,
+) -> Result<(), FileHandlerError> {
+    if !is_in_drop_box(conn, node_id).await? {
+        return Ok(());
+    }
+    session
+        .require_privilege(Privileges::VIEW_DROP_BOXES)
+        .map_err(FileHandlerError::Privilege)
+}
+
+/// Apply [`ensure_readable`] to a looked-up entry. Legacy entries live at
+/// the root, outside every drop box.
+async fn ensure_entry_readable(
+    conn: &mut DbConnection,
+    session: &Session,
+    source: FileInfoSource,
+) -> Result<(), FileHandlerError> {
+    match source {
+        FileInfoSource::Node(node_id) => ensure_readable(conn, session, node_id).await,
+        FileInfoSource::Legacy(_) => Ok(()),
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', ':']) && !name.chars().any(char::is_control)
+}
+
+/// Pad or truncate a stored type code to the four bytes field 213 carries.
+fn four_char_code(code: &str) -> [u8; 4] {
+    let mut out = [b' '; 4];
+    for (slot, byte) in out.iter_mut().zip(code.bytes()) {
+        *slot = byte;
+    }
+    out
+}
+
+fn file_info_params(info: &FileInfo) -> ReplyParams {
+    let type_code = if info.is_folder {
+        FOLDER_TYPE_CODE
+    } else {
+        info.type_code.as_str()
+    };
+    let size = u32::try_from(info.size.max(0)).unwrap_or(u32::MAX);
+    ReplyParams::new()
+        .string(FieldId::FileItemName, &info.name)
+        .string(FieldId::FileTypeString, type_code)
+        .string(FieldId::FileCreatorString, &info.creator_code)
+        .blob(FieldId::FileType, four_char_code(type_code))
+        .timestamp(
+            FieldId::FileCreateDate,
+            optional_timestamp_millis(info.created_at),
+        )
+        .timestamp(
+            FieldId::FileModifyDate,
+            optional_timestamp_millis(info.updated_at),
+        )
+        .u32(FieldId::FileSize, size)
+        .optional(info.comment.as_deref(), |reply, comment| {
+            reply.string(FieldId::FileComment, comment)
+        })
+}
+
+fn encode_reply(header: &FrameHeader, params: ReplyParams) -> Transaction {
+    match params.encode() {
+        Ok(payload) => Transaction {
+            header: reply_header(header, 0, payload.len()),
+            payload,
+        },
+        Err(e) => {
+            error!(%e, "failed to encode file reply");
+            error_reply(header, ERR_INTERNAL_SERVER)
+        }
+    }
+}
+
+fn file_error_reply(header: &FrameHeader, err: FileHandlerError) -> Transaction {
+    match err {
+        FileHandlerError::PathUnsupported => {
+            tracing::debug!("legacy file entries cannot be moved into folders");
+            error_reply(header, FILE_ERR_PATH_UNSUPPORTED)
+        }
+        FileHandlerError::InvalidPath => error_reply(header, ERR_INVALID_PAYLOAD),
+        FileHandlerError::NotFound => error_reply(header, FILE_ERR_NOT_FOUND),
+        FileHandlerError::InvalidName => error_reply(header, ERR_INVALID_PAYLOAD),
+        FileHandlerError::NameTaken => error_reply(header, FILE_ERR_NAME_TAKEN),
+        FileHandlerError::FolderNotEmpty => error_reply(header, FILE_ERR_FOLDER_NOT_EMPTY),
+        FileHandlerError::MoveIntoSelf => error_reply(header, ERR_INVALID_PAYLOAD),
+        FileHandlerError::ReadOnly => error_reply(header, FILE_ERR_READ_ONLY),
+        FileHandlerError::NoStorage => {
+            error!("file content requested but no storage backend is configured");
+            error_reply(header, ERR_INTERNAL_SERVER)
+        }
+        FileHandlerError::Untransferable => {
+            warn!("file too large to send in a flattened file");
+            error_reply(header, ERR_INTERNAL_SERVER)
+        }
+        FileHandlerError::DownloadRefused(refusal) => {
+            error_text_reply(header, FILE_ERR_DOWNLOAD_REFUSED, &refusal.to_string())
+        }
+        FileHandlerError::QuotaExceeded(refusal) => {
+            error_text_reply(header, FILE_ERR_QUOTA_EXCEEDED, &refusal.to_string())
+        }
+        FileHandlerError::UploadTooLarge(cap) => error_text_reply(
+            header,
+            FILE_ERR_QUOTA_EXCEEDED,
+            &format!("Upload refused: files on this server may be at most {cap} bytes."),
+        ),
+        FileHandlerError::Privilege(err) => privilege_error_reply(header, err),
+        FileHandlerError::Pool(err) => {
+            error!(%err, "failed to get database connection");
+            error_reply(header, pool_error_code(&err))
+        }
+        FileHandlerError::Database(err) => {
+            error!(%err, "file database error");
+            error_reply(header, ERR_INTERNAL_SERVER)
+        }
+        FileHandlerError::Storage(StorageError::NotFound(key)) => {
+            warn!(%key, "file content missing from storage");
+            error_reply(header, FILE_ERR_NOT_FOUND)
+        }
+        FileHandlerError::Storage(err) => {
+            error!(%err, "file storage error");
+            error_reply(header, ERR_INTERNAL_SERVER)
+        }
+    }
+}
+
+fn error_reply(header: &FrameHeader, code: u32) -> Transaction {
+    Transaction {
+        header: reply_header(header, code, 0),
+        payload: Vec::new(),
+    }
+}
+
+/// Build an error reply explaining itself in an `ErrorText` (100) field.
+fn error_text_reply(header: &FrameHeader, code: u32, text: &str) -> Transaction {
+    let payload = ReplyParams::new()
+        .string(FieldId::ErrorText, text)
+        .encode()
+        .unwrap_or_default();
+    Transaction {
+        header: reply_header(header, code, payload.len()),
+        payload,
+    }
+}
+
+#[cfg(test)]
+mod tests;
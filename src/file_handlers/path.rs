@@ -0,0 +1,54 @@
+//! Hotline file path (field 202) encoding.
+//!
+//! A path is a big-endian `u16` segment count followed by one record per
+//! folder: a reserved `u16`, a `u8` name length, and the name bytes. An empty
+//! field, or a count of zero, addresses the root of the file area.
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use super::is_valid_name;
+
+/// Decode a field 202 path into folder names, outermost first.
+///
+/// Returns `None` when the bytes are truncated, carry trailing data, or hold a
+/// name that is not UTF-8 or could not name a file-area entry.
+#[must_use]
+pub fn decode_file_path(bytes: &[u8]) -> Option<Vec<String>> {
+    if bytes.is_empty() {
+        return Some(Vec::new());
+    }
+    let (count, mut rest) = split_u16(bytes)?;
+    let mut segments = Vec::with_capacity(usize::from(count));
+    for _ in 0..count {
+        let (_reserved, after_reserved) = split_u16(rest)?;
+        let (&len, after_len) = after_reserved.split_first()?;
+        let (raw, after_name) = after_len.split_at_checked(usize::from(len))?;
+        let name = std::str::from_utf8(raw).ok()?;
+        if !is_valid_name(name) {
+            return None;
+        }
+        segments.push(name.to_owned());
+        rest = after_name;
+    }
+    rest.is_empty().then_some(segments)
+}
+
+/// Encode folder names, outermost first, as a field 202 path.
+///
+/// Returns `None` if there are more than `u16::MAX` segments or a name is
+/// longer than 255 bytes.
+#[must_use]
+pub fn encode_file_path(segments: &[&str]) -> Option<Vec<u8>> {
+    let count = u16::try_from(segments.len()).ok()?;
+    let mut buf = count.to_be_bytes().to_vec();
+    for segment in segments {
+        let len = u8::try_from(segment.len()).ok()?;
+        buf.extend_from_slice(&[0, 0, len]);
+        buf.extend_from_slice(segment.as_bytes());
+    }
+    Some(buf)
+}
+
+fn split_u16(bytes: &[u8]) -> Option<(u16, &[u8])> {
+    let (head, rest) = bytes.split_first_chunk::<2>()?;
+    Some((u16::from_be_bytes(*head), rest))
+}
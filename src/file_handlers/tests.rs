@@ -0,0 +1,153 @@
+//! Unit tests for file handler helpers.
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use chrono::DateTime;
+use rstest::{fixture, rstest};
+
+use super::*;
+
+/// Returns a plain file's metadata with sensible defaults for testing.
+#[fixture]
+fn text_file() -> FileInfo {
+    FileInfo {
+        name: "readme.txt".to_string(),
+        is_folder: false,
+        size: 12,
+        type_code: "TEXT".to_string(),
+        creator_code: "ttxt".to_string(),
+        comment: None,
+        created_at: DateTime::from_timestamp(2_000, 0).map(|dt| dt.naive_utc()),
+        updated_at: None,
+    }
+}
+
+fn field(params: &ReplyParams, id: FieldId) -> Option<&[u8]> {
+    params
+        .as_slice()
+        .iter()
+        .find(|(field_id, _)| *field_id == id)
+        .map(|(_, value)| value.as_slice())
+}
+
+#[rstest]
+#[case(None, Some(vec![]))]
+#[case(Some(&[][..]), Some(vec![]))]
+#[case(Some(&[0, 0][..]), Some(vec![]))]
+#[case(Some(&[0, 1, 0, 0, 3, b'a', b'b', b'c'][..]), Some(vec!["abc"]))]
+#[case(Some(&[0, 1, 0][..]), None)]
+fn folder_segments_default_to_root(
+    #[case] path: Option<&[u8]>,
+    #[case] expected: Option<Vec<&str>>,
+) {
+    let decoded = folder_segments(path).ok();
+    let owned = expected.map(|names| names.into_iter().map(str::to_owned).collect::<Vec<_>>());
+    assert_eq!(decoded, owned);
+}
+
+#[rstest]
+#[case("notes.txt", true)]
+#[case("", false)]
+#[case("a/b", false)]
+#[case("a:b", false)]
+#[case("tab\there", false)]
+fn validates_new_names(#[case] name: &str, #[case] valid: bool) {
+    assert_eq!(is_valid_name(name), valid);
+}
+
+#[rstest]
+#[case(false, Privileges::RENAME_FILE | Privileges::SET_FILE_COMMENT)]
+#[case(true, Privileges::RENAME_FOLDER | Privileges::SET_FOLDER_COMMENT)]
+fn required_privileges_follow_entry_kind(#[case] is_folder: bool, #[case] expected: Privileges) {
+    let req = SetFileInfoRequest {
+        name: "docs".to_string(),
+        path: None,
+        new_name: Some("manuals".to_string()),
+        comment: Some(String::new()),
+    };
+    assert_eq!(req.required_privileges(is_folder), expected);
+}
+
+#[rstest]
+fn comment_only_update_needs_comment_privilege() {
+    let req = SetFileInfoRequest {
+        name: "readme.txt".to_string(),
+        path: None,
+        new_name: None,
+        comment: Some("Start here".to_string()),
+    };
+    assert_eq!(req.required_privileges(false), Privileges::SET_FILE_COMMENT);
+}
+
+#[rstest]
+fn file_params_carry_codes_dates_and_size(text_file: FileInfo) {
+    let params = file_info_params(&text_file);
+
+    assert_eq!(
+        field(&params, FieldId::FileItemName),
+        Some(&b"readme.txt"[..])
+    );
+    assert_eq!(field(&params, FieldId::FileTypeString), Some(&b"TEXT"[..]));
+    assert_eq!(
+        field(&params, FieldId::FileCreatorString),
+        Some(&b"ttxt"[..])
+    );
+    assert_eq!(field(&params, FieldId::FileType), Some(&b"TEXT"[..]));
+    assert_eq!(
+        field(&params, FieldId::FileSize),
+        Some(&12u32.to_be_bytes()[..])
+    );
+    assert_eq!(
+        field(&params, FieldId::FileCreateDate),
+        Some(&2_000_000i64.to_be_bytes()[..])
+    );
+    assert_eq!(field(&params, FieldId::FileModifyDate), Some(&[0; 8][..]));
+    assert_eq!(field(&params, FieldId::FileComment), None);
+}
+
+#[rstest]
+fn folder_params_report_folder_type(text_file: FileInfo) {
+    let folder = FileInfo {
+        is_folder: true,
+        size: 0,
+        type_code: String::new(),
+        comment: Some("Shared docs".to_string()),
+        ..text_file
+    };
+
+    let params = file_info_params(&folder);
+
+    assert_eq!(field(&params, FieldId::FileTypeString), Some(&b"fldr"[..]));
+    assert_eq!(field(&params, FieldId::FileType), Some(&b"fldr"[..]));
+    assert_eq!(
+        field(&params, FieldId::FileComment),
+        Some(&b"Shared docs"[..])
+    );
+}
+
+#[rstest]
+fn blank_type_codes_are_space_padded() {
+    assert_eq!(four_char_code(""), *b"    ");
+    assert_eq!(four_char_code("APPLE"), *b"APPL");
+}
+
+#[rstest]
+#[case(&[][..], Some(vec![]))]
+#[case(&[0, 0][..], Some(vec![]))]
+#[case(&[0, 1, 0, 0, 4, b'D', b'o', b'c', b's'][..], Some(vec!["Docs"]))]
+#[case(&[0, 1, 0, 0, 4, b'D', b'o'][..], None)]
+#[case(&[0, 0, 9][..], None)]
+#[case(&[0, 1, 0, 0, 3, b'a', b'/', b'b'][..], None)]
+#[case(&[0, 1, 0, 0, 0][..], None)]
+fn decodes_file_paths(#[case] bytes: &[u8], #[case] expected: Option<Vec<&str>>) {
+    let owned = expected.map(|names| names.into_iter().map(str::to_owned).collect::<Vec<_>>());
+    assert_eq!(decode_file_path(bytes), owned);
+}
+
+#[rstest]
+fn file_paths_round_trip() {
+    let encoded = encode_file_path(&["Docs", "Archive"]).expect("path encodes");
+    assert_eq!(
+        decode_file_path(&encoded),
+        Some(vec!["Docs".to_owned(), "Archive".to_owned()])
+    );
+}
@@ -0,0 +1,327 @@
+//! Download File (202) and Upload File (203).
+//!
+//! Neither transaction carries file content. A download files the entry's
+//! stored content with the transfer registry and replies with the reference
+//! the client claims it with on the transfer port; an upload files where the
+//! content will go, and the transfer port stores it and enters the file once
+//! the client has sent it. Both need a storage backend. The transfer port
+//! adds each finished file to the requesting connection's tally. Downloads
+//! are checked against the download rules here, so a refusal reaches the
+//! client as an error reply, and again when the transfer starts. Uploads
+//! larger than `max_upload_bytes` are refused here, and uploads are checked
+//! against the account's storage quota here and again before their content
+//! is stored. Both are
+//! admitted to the transfer queue as they are filed, and the reply's
+//! Waiting Count (116) gives the transfer's place in it.
+
+use std::{sync::Arc, time::Instant};
+
+use super::{
+    FileHandlerError,
+    encode_reply,
+    ensure_entry_readable,
+    ensure_writable,
+    file_error_reply,
+    find_entry,
+    folder_segments,
+    four_char_code,
+    is_valid_name,
+    session_user_id,
+};
+use crate::{
+    commands::CommandError,
+    db::{
+        DbPool,
+        FileInfo,
+        FileInfoSource,
+        acquire,
+        file_object_key,
+        find_visible_folder,
+        is_file_name_taken,
+    },
+    field_id::FieldId,
+    handler::Session,
+    server::{
+        download_policy::{DownloadRequest, check_download},
+        flat_file::{FlatFileInfo, encode_flat_file_prefix},
+        outbound::OutboundConnectionId,
+        settings::ServerSettings,
+        storage_quota::check_upload_quota,
+        transfer_port::{Downloader, PendingTransfer, TransferKind, TransferSource, UploadTarget},
+        transfer_stats::TransferTally,
+        transfers::{Admission, TransferDirection, TransferRequest},
+    },
+    transaction::{FrameHeader, ReplyParams, Transaction, TransactionParams},
+    transaction_type::TransactionType,
+};
+
+/// Parameters for downloading a file.
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
+pub struct DownloadFileRequest {
+    #[param(FileItemName)]
+    pub(crate) name: String,
+    #[param(FilePath)]
+    pub(crate) path: Option<Vec<u8>>,
+}
+
+/// Parameters for uploading a file.
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
+pub struct UploadFileRequest {
+    #[param(FileItemName)]
+    pub(crate) name: String,
+    #[param(FilePath)]
+    pub(crate) path: Option<Vec<u8>>,
+    /// Bytes the client will send on the transfer port.
+    #[param(TransferSize)]
+    pub(crate) size: u32,
+}
+
+/// The server and connection a transfer is filed for.
+#[derive(Clone)]
+pub struct TransferContext<'a> {
+    /// Pool the file tree is read through.
+    pub pool: &'a DbPool,
+    /// Settings of the server, naming its storage backend, download rules,
+    /// and transfer registry.
+    pub settings: &'a ServerSettings,
+    /// Connection told of the transfer's place in the queue, when the
+    /// runtime can push to it.
+    pub connection: Option<OutboundConnectionId>,
+    /// Tally the transfer is counted in once it completes.
+    pub tally: Option<Arc<TransferTally>>,
+}
+
+/// A transfer filed with the registry and admitted to the queue.
+struct FiledTransfer {
+    reference: u32,
+    admission: Admission,
+}
+
+/// A download filed with the transfer registry.
+struct FiledDownload {
+    filed: FiledTransfer,
+    transfer_size: u32,
+    file_size: u32,
+}
+
+/// Handle Download File commands once the dispatcher has checked access.
+///
+/// Replies with the flattened file's length (108), the content's size
+/// (207), the reference to claim it with (107), and its place in the
+/// transfer queue (116). Folders cannot be downloaded this way, and
+/// drop-box contents need View Drop Boxes. A download the download rules
+/// forbid is refused with
+/// [`FILE_ERR_DOWNLOAD_REFUSED`](crate::commands::FILE_ERR_DOWNLOAD_REFUSED)
+/// and the reason in field 100. The download is counted in the context's
+/// tally once it has been sent.
+///
+/// # Errors
+/// Returns an error if the authenticated session has no user id.
+pub async fn process_download_file(
+    context: TransferContext<'_>,
+    session: &Session,
+    header: &FrameHeader,
+    req: &DownloadFileRequest,
+) -> Result<Transaction, CommandError> {
+    let user_id = session_user_id(session)?;
+    Ok(match file_download(context, session, user_id, req).await {
+        Ok(download) => encode_reply(
+            header,
+            ReplyParams::new()
+                .u32(FieldId::TransferSize, download.transfer_size)
+                .u32(FieldId::FileSize, download.file_size)
+                .u32(FieldId::ReferenceNumber, download.filed.reference)
+                .u32(
+                    FieldId::WaitingCount,
+                    download.filed.admission.waiting_count(),
+                ),
+        ),
+        Err(err) => file_error_reply(header, err),
+    })
+}
+
+/// Handle Upload File commands once the dispatcher has checked access.
+///
+/// The folder must be visible and writable, and must not already hold an
+/// entry of that name. Replies with the reference to send the file under
+/// (107) and its place in the transfer queue (116). An upload whose
+/// transfer size exceeds `max_upload_bytes` or would take the account past
+/// its storage quota is refused with
+/// [`FILE_ERR_QUOTA_EXCEEDED`](crate::commands::FILE_ERR_QUOTA_EXCEEDED) and
+/// the reason in field 100. The upload is counted in the context's tally
+/// once it has been entered.
+///
+/// # Errors
+/// Returns an error if the authenticated session has no user id.
+pub async fn process_upload_file(
+    context: TransferContext<'_>,
+    session: &Session,
+    header: &FrameHeader,
+    req: &UploadFileRequest,
+) -> Result<Transaction, CommandError> {
+    let user_id = session_user_id(session)?;
+    Ok(match file_upload(context, user_id, req).await {
+        Ok(filed) => encode_reply(
+            header,
+            ReplyParams::new()
+                .u32(FieldId::ReferenceNumber, filed.reference)
+                .u32(FieldId::WaitingCount, filed.admission.waiting_count()),
+        ),
+        Err(err) => file_error_reply(header, err),
+    })
+}
+
+async fn file_download(
+    context: TransferContext<'_>,
+    session: &Session,
+    user_id: i32,
+    req: &DownloadFileRequest,
+) -> Result<FiledDownload, FileHandlerError> {
+    let settings = context.settings;
+    let backend = settings
+        .storage
+        .clone()
+        .ok_or(FileHandlerError::NoStorage)?;
+    let folder = folder_segments(req.path.as_deref())?;
+    let mut conn = acquire(context.pool, TransactionType::DownloadFile).await?;
+    let found = find_entry(&mut conn, user_id, &folder, &req.name).await?;
+    if found.info.is_folder {
+        return Err(FileHandlerError::NotFound);
+    }
+    ensure_entry_readable(&mut conn, session, found.source).await?;
+    let key = file_object_key(&mut conn, found.source)
+        .await?
+        .ok_or(FileHandlerError::NotFound)?;
+    let file_size =
+        u32::try_from(backend.size(&key).await?).map_err(|_| FileHandlerError::Untransferable)?;
+    let download = DownloadRequest {
+        user_id,
+        privileges: session.privileges,
+        size: u64::from(file_size),
+    };
+    check_download(&mut conn, settings.download_rules, download).await?;
+    let prefix = encode_flat_file_prefix(&flat_file_info(found.info), file_size)
+        .map_err(|_| FileHandlerError::Untransferable)?;
+    let transfer_size = u32::try_from(prefix.len())
+        .ok()
+        .and_then(|len| len.checked_add(file_size))
+        .ok_or(FileHandlerError::Untransferable)?;
+    let filed = file_transfer(
+        PendingTransfer::from_source(
+            TransferKind::File,
+            TransferSource::Stored {
+                backend,
+                key,
+                prefix: Arc::from(prefix),
+                downloader: Downloader {
+                    pool: context.pool.clone(),
+                    user_id,
+                    privileges: session.privileges,
+                    rules: settings.download_rules,
+                },
+            },
+            Instant::now(),
+        ),
+        user_id,
+        TransferDirection::Download,
+        context,
+    );
+    Ok(FiledDownload {
+        filed,
+        transfer_size,
+        file_size,
+    })
+}
+
+async fn file_upload(
+    context: TransferContext<'_>,
+    user_id: i32,
+    req: &UploadFileRequest,
+) -> Result<FiledTransfer, FileHandlerError> {
+    let backend = context
+        .settings
+        .storage
+        .clone()
+        .ok_or(FileHandlerError::NoStorage)?;
+    if !is_valid_name(&req.name) {
+        return Err(FileHandlerError::InvalidName);
+    }
+    let folder = folder_segments(req.path.as_deref())?;
+    let mut conn = acquire(context.pool, TransactionType::UploadFile).await?;
+    let parent_id = if folder.is_empty() {
+        None
+    } else {
+        let parent = find_visible_folder(&mut conn, user_id, &folder)
+            .await?
+            .ok_or(FileHandlerError::NotFound)?;
+        ensure_writable(&mut conn, FileInfoSource::Node(parent.id)).await?;
+        Some(parent.id)
+    };
+    if is_file_name_taken(&mut conn, parent_id, &req.name).await? {
+        return Err(FileHandlerError::NameTaken);
+    }
+    if let Some(cap) = context.settings.transfers.manager().limits().max_upload
+        && u64::from(req.size) > cap.get()
+    {
+        return Err(FileHandlerError::UploadTooLarge(cap.get()));
+    }
+    check_upload_quota(&mut conn, user_id, u64::from(req.size))
+        .await?
+        .map_err(FileHandlerError::QuotaExceeded)?;
+    Ok(file_transfer(
+        PendingTransfer::from_source(
+            TransferKind::Upload,
+            TransferSource::Upload(UploadTarget {
+                pool: context.pool.clone(),
+                backend,
+                user_id,
+                parent_id,
+                name: req.name.clone(),
+                size: req.size,
+            }),
+            Instant::now(),
+        ),
+        user_id,
+        TransferDirection::Upload,
+        context,
+    ))
+}
+
+/// File `transfer` with the server's registry, counted in the context's
+/// tally, and admit it to the transfer queue.
+fn file_transfer(
+    transfer: PendingTransfer,
+    user_id: i32,
+    direction: TransferDirection,
+    context: TransferContext<'_>,
+) -> FiledTransfer {
+    let TransferContext {
+        settings,
+        connection,
+        tally,
+        ..
+    } = context;
+    let registry = &settings.transfers;
+    let reference = registry.register(transfer.counted_in(tally));
+    let admission = registry.manager().admit(TransferRequest {
+        user_id,
+        reference,
+        direction,
+        connection,
+    });
+    FiledTransfer {
+        reference,
+        admission,
+    }
+}
+
+fn flat_file_info(info: FileInfo) -> FlatFileInfo {
+    FlatFileInfo {
+        type_code: four_char_code(&info.type_code),
+        creator_code: four_char_code(&info.creator_code),
+        name: info.name,
+        created_at: info.created_at,
+        modified_at: info.updated_at,
+        comment: info.comment,
+    }
+}
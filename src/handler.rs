@@ -2,45 +2,21 @@
 //!
 //! The handler owns per-client [`Session`] state and dispatches incoming
 //! transactions to [`Command`] processors.
-use std::{
-    error::Error,
-    fmt,
-    net::SocketAddr,
-    sync::{
-        Arc,
-        atomic::{AtomicU64, Ordering},
-    },
-};
+use std::{error::Error, fmt};
 
-use argon2::Argon2;
+pub use context::Context;
 
 use crate::{
+    access::Access,
     commands::{Command, CommandError, ProcessContext},
     connection_flags::ConnectionFlags,
-    db::DbPool,
-    presence::{PresenceRegistry, PresenceSnapshot, SessionPhase},
+    news_handlers::NewsListingEncoding,
+    presence::{PresenceSnapshot, SessionPhase, user_flags},
     privileges::Privileges,
     server::outbound::OutboundConnectionId,
-    transaction::{Transaction, parse_transaction},
+    transaction::{Transaction, parse_transaction_limited},
 };
 
-static NEXT_LEGACY_PRESENCE_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
-
-/// Per-connection context used by `handle_request`.
-#[derive(Clone)]
-pub struct Context {
-    /// Remote peer socket address.
-    pub peer: SocketAddr,
-    /// Database connection pool.
-    pub pool: DbPool,
-    /// Shared Argon2 instance for password hashing.
-    pub argon2: Arc<Argon2<'static>>,
-    /// Shared presence registry for legacy request processing.
-    pub presence: Arc<PresenceRegistry>,
-    /// Adapter-owned identifier used when publishing this connection's presence.
-    pub presence_connection_id: OutboundConnectionId,
-}
-
 /// Session state for a single connection.
 ///
 /// Tracks authentication status, privileges, and user preferences. The session
@@ -52,8 +28,11 @@ pub struct Session {
     pub user_id: Option<i32>,
     /// User access privileges from the Hotline protocol.
     ///
-    /// Populated on successful login; empty until authenticated.
+    /// Populated on successful login; empty until authenticated and, when the
+    /// server has an agreement, until the client accepts it.
     pub privileges: Privileges,
+    /// Privileges held back until the client accepts the server agreement.
+    pub pending_privileges: Privileges,
     /// Connection lifecycle state for protocol visibility.
     pub phase: SessionPhase,
     /// Session-visible nickname.
@@ -64,8 +43,28 @@ pub struct Session {
     ///
     /// Set during login/agreement and can be updated via `SetClientUserInfo`.
     pub connection_flags: ConnectionFlags,
-    /// Automatic response text associated with the current session.
+    /// Away message sent back to users who message this session.
+    ///
+    /// Only set while [`ConnectionFlags::AUTOMATIC_RESPONSE`] is; the user
+    /// list shows the session as away meanwhile.
     pub auto_response: Option<String>,
+    /// Encoding applied to news category listing entries.
+    ///
+    /// Selected by the wireframe adapter from client compatibility metadata;
+    /// defaults to bare names.
+    pub news_listing: NewsListingEncoding,
+    /// News root that paths in news transactions resolve in.
+    ///
+    /// Chosen at login; the primary root unless the client selected another.
+    pub news_root: i32,
+    /// Unknown transaction types this connection has sent.
+    pub unknown_transactions: u32,
+    /// Set by a handler that wants the connection closed once its reply has
+    /// been sent; the runtime sends this reason in a Disconnect Message.
+    pub disconnect_reason: Option<&'static str>,
+    /// Set by login when the server agreement must be shown; the runtime
+    /// sends the Show Agreement push after the reply and clears the flag.
+    pub show_agreement: bool,
 }
 
 /// Error returned when a privilege check fails.
@@ -148,25 +147,91 @@ impl Session {
         }
     }
 
+    /// Require the session state `access` describes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PrivilegeError::NotAuthenticated`] if the session is not
+    /// logged in, or not yet online when `access` requires it, or
+    /// [`PrivilegeError::InsufficientPrivileges`] if a privilege is missing.
+    pub const fn require_access(&self, access: Access) -> Result<(), PrivilegeError> {
+        if !access.requires_authentication() {
+            return Ok(());
+        }
+        if access.requires_online() && !self.is_online() {
+            return Err(PrivilegeError::NotAuthenticated);
+        }
+        self.require_privilege(access.privileges())
+    }
+
     /// Update the authenticated account details after a successful login.
+    ///
+    /// Accounts without [`Privileges::NO_AGREEMENT`] wait in
+    /// [`SessionPhase::PendingAgreement`] with their privileges held back
+    /// until [`Self::accept_agreement`] is called.
     pub fn apply_login(&mut self, user_id: i32, username: &str, privileges: Privileges) {
         self.user_id = Some(user_id);
-        self.privileges = privileges;
         username.clone_into(&mut self.display_name);
         self.icon_id = 0;
         self.auto_response = None;
         self.connection_flags = ConnectionFlags::default();
-        self.phase = if self.requires_agreement() {
-            SessionPhase::PendingAgreement
+        if privileges.contains(Privileges::NO_AGREEMENT) {
+            self.privileges = privileges;
+            self.pending_privileges = Privileges::empty();
+            self.phase = SessionPhase::Online;
         } else {
-            SessionPhase::Online
-        };
+            self.privileges = Privileges::empty();
+            self.pending_privileges = privileges;
+            self.phase = SessionPhase::PendingAgreement;
+        }
+    }
+
+    /// Drop the session's login, returning it to the state of a fresh
+    /// connection.
+    ///
+    /// Logout calls this, as do the runtimes before closing a connection a
+    /// handler asked to drop, so nothing queued behind that request runs
+    /// with the departing account's privileges. The news listing encoding
+    /// and unknown-transaction count describe the client rather than the
+    /// account, so they are kept.
+    pub fn invalidate(&mut self) {
+        self.user_id = None;
+        self.privileges = Privileges::empty();
+        self.pending_privileges = Privileges::empty();
+        self.phase = SessionPhase::Unauthenticated;
+        self.display_name.clear();
+        self.icon_id = 0;
+        self.connection_flags = ConnectionFlags::default();
+        self.auto_response = None;
+        self.show_agreement = false;
     }
 
-    /// Return whether the account must complete agreement before going online.
+    /// Take the reason a handler gave for closing the connection,
+    /// invalidating the session when there is one.
+    pub fn take_disconnect_reason(&mut self) -> Option<&'static str> {
+        let reason = self.disconnect_reason.take()?;
+        self.invalidate();
+        Some(reason)
+    }
+
+    /// Return whether the session is waiting for the client to accept the
+    /// server agreement.
     #[must_use]
     pub const fn requires_agreement(&self) -> bool {
-        !self.privileges.contains(Privileges::NO_AGREEMENT)
+        matches!(self.phase, SessionPhase::PendingAgreement)
+    }
+
+    /// Grant the held-back privileges and bring the session online.
+    ///
+    /// Returns `false`, changing nothing, when no agreement was pending.
+    pub const fn accept_agreement(&mut self) -> bool {
+        if !self.requires_agreement() {
+            return false;
+        }
+        self.privileges = self.pending_privileges;
+        self.pending_privileges = Privileges::empty();
+        self.phase = SessionPhase::Online;
+        true
     }
 
     /// Return whether the session should appear in the public user list.
@@ -175,9 +240,21 @@ impl Session {
         self.is_online() && self.privileges.contains(Privileges::SHOW_IN_LIST)
     }
 
+    /// Return whether the session has an away message set.
+    #[must_use]
+    pub const fn is_away(&self) -> bool { self.auto_response.is_some() }
+
+    /// Clear the away message and the automatic-response option, returning
+    /// whether one was set.
+    pub fn clear_away(&mut self) -> bool {
+        self.connection_flags
+            .remove(ConnectionFlags::AUTOMATIC_RESPONSE);
+        self.auto_response.take().is_some()
+    }
+
     /// Return the packed user-list colour/status flags for this session.
     #[must_use]
-    pub fn presence_flags(&self) -> u16 { if self.is_presence_admin() { 2 } else { 0 } }
+    pub fn presence_flags(&self) -> u16 { user_flags(self.is_away(), self.is_presence_admin()) }
 
     /// Build a public presence snapshot when the session is online and visible.
     #[must_use]
@@ -192,9 +269,12 @@ impl Session {
         Some(PresenceSnapshot {
             connection_id,
             user_id,
+            account_id: user_id,
             display_name: self.display_name.clone(),
             icon_id: self.icon_id,
             status_flags: self.presence_flags(),
+            cannot_be_disconnected: self.privileges.contains(Privileges::CANNOT_BE_DISCONNECTED),
+            auto_response: self.auto_response.clone(),
         })
     }
 
@@ -210,42 +290,6 @@ impl Session {
     }
 }
 
-impl Context {
-    /// Create a new connection context.
-    #[must_use]
-    pub fn new(peer: SocketAddr, pool: DbPool, argon2: Arc<Argon2<'static>>) -> Self {
-        Self {
-            peer,
-            pool,
-            argon2,
-            presence: Arc::new(PresenceRegistry::default()),
-            presence_connection_id: next_legacy_presence_connection_id(),
-        }
-    }
-
-    /// Create a new connection context with shared presence.
-    #[must_use]
-    pub fn with_presence(
-        peer: SocketAddr,
-        pool: DbPool,
-        argon2: Arc<Argon2<'static>>,
-        presence: Arc<PresenceRegistry>,
-    ) -> Self {
-        Self {
-            peer,
-            pool,
-            argon2,
-            presence,
-            presence_connection_id: next_legacy_presence_connection_id(),
-        }
-    }
-}
-
-fn next_legacy_presence_connection_id() -> OutboundConnectionId {
-    let id = NEXT_LEGACY_PRESENCE_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
-    OutboundConnectionId::new(id)
-}
-
 /// Parse and handle a single request frame without performing network I/O.
 ///
 /// # Errors
@@ -256,18 +300,23 @@ pub async fn handle_request(
     session: &mut Session,
     frame: &[u8],
 ) -> Result<Transaction, CommandError> {
-    let tx = parse_transaction(frame)?;
-    let cmd = Command::from_transaction(tx)?;
+    let limits = &ctx.settings.transport.payload_limits;
+    let tx = parse_transaction_limited(frame, limits)?;
+    let cmd = Command::from_transaction(tx, limits)?;
     cmd.process(ProcessContext {
         peer: ctx.peer,
         pool: ctx.pool.clone(),
         session,
         presence: ctx.presence.as_ref(),
         presence_connection_id: Some(ctx.presence_connection_id),
+        settings: &ctx.settings,
     })
     .await
 }
 
+#[path = "handler_context.rs"]
+mod context;
+
 #[cfg(test)]
 #[path = "handler_tests.rs"]
 mod tests;
@@ -0,0 +1,101 @@
+//! Per-connection context handed to request processing.
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use argon2::Argon2;
+use tracing::debug;
+
+use crate::{
+    db::DbPool,
+    presence::PresenceRegistry,
+    server::{idle::ActivityClock, outbound::OutboundConnectionId, settings::ServerSettings},
+};
+
+static NEXT_LEGACY_PRESENCE_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Per-connection context used by `handle_request`.
+#[derive(Clone)]
+pub struct Context {
+    /// Remote peer socket address.
+    pub peer: SocketAddr,
+    /// Database connection pool.
+    pub pool: DbPool,
+    /// Shared Argon2 instance for password hashing.
+    pub argon2: Arc<Argon2<'static>>,
+    /// Shared presence registry for legacy request processing.
+    pub presence: Arc<PresenceRegistry>,
+    /// Adapter-owned identifier used when publishing this connection's presence.
+    pub presence_connection_id: OutboundConnectionId,
+    /// Settings of the server that accepted the connection.
+    pub settings: Arc<ServerSettings>,
+}
+
+impl Context {
+    /// Create a new connection context.
+    #[must_use]
+    pub fn new(peer: SocketAddr, pool: DbPool, argon2: Arc<Argon2<'static>>) -> Self {
+        Self {
+            peer,
+            pool,
+            argon2,
+            presence: Arc::new(PresenceRegistry::default()),
+            presence_connection_id: next_legacy_presence_connection_id(),
+            settings: Arc::default(),
+        }
+    }
+
+    /// Create a new connection context with shared presence.
+    #[must_use]
+    pub fn with_presence(
+        peer: SocketAddr,
+        pool: DbPool,
+        argon2: Arc<Argon2<'static>>,
+        presence: Arc<PresenceRegistry>,
+    ) -> Self {
+        Self {
+            peer,
+            pool,
+            argon2,
+            presence,
+            presence_connection_id: next_legacy_presence_connection_id(),
+            settings: Arc::default(),
+        }
+    }
+
+    /// Hand the connection the settings of the server that accepted it in
+    /// place of the defaults.
+    #[must_use]
+    pub fn with_settings(self, settings: Arc<ServerSettings>) -> Self { Self { settings, ..self } }
+
+    /// Record this connection's address and activity clock in the shared
+    /// presence registry for Get Client Info Text (303).
+    pub fn attach_presence(&self, activity: Arc<ActivityClock>) {
+        self.presence
+            .attach_connection(self.presence_connection_id, self.peer.ip(), activity);
+    }
+
+    /// Remove this connection from the shared presence registry.
+    ///
+    /// Runtimes call this once the connection ends so departed users stop
+    /// appearing in user-name lists. Calling it for a connection that never
+    /// came online is a no-op.
+    pub fn release_presence(&self) {
+        if let Some(removal) = self.presence.remove(self.presence_connection_id) {
+            debug!(
+                user_id = removal.departed.user_id,
+                "released legacy presence entry"
+            );
+        }
+    }
+}
+
+fn next_legacy_presence_connection_id() -> OutboundConnectionId {
+    let id = NEXT_LEGACY_PRESENCE_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+    OutboundConnectionId::new(id)
+}
@@ -1,7 +1,10 @@
 //! Unit tests for connection-level session handling.
 
 use super::*;
-use crate::wireframe::test_helpers::dummy_pool;
+use crate::{
+    presence::{USER_FLAG_ADMIN, USER_FLAG_AWAY},
+    wireframe::test_helpers::dummy_pool,
+};
 
 #[tokio::test]
 async fn context_carries_shared_argon2_reference() {
@@ -135,6 +138,132 @@ fn session_require_authenticated_succeeds_when_logged_in() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn login_without_no_agreement_holds_privileges_until_accepted() {
+    let mut session = Session::default();
+    session.apply_login(7, "alice", Privileges::default_user());
+    assert_eq!(session.phase, SessionPhase::PendingAgreement);
+    assert!(session.requires_agreement());
+    assert!(session.privileges.is_empty());
+    assert_eq!(
+        session.require_privilege(Privileges::DOWNLOAD_FILE),
+        Err(PrivilegeError::InsufficientPrivileges(
+            Privileges::DOWNLOAD_FILE
+        ))
+    );
+
+    assert!(session.accept_agreement());
+    assert!(session.is_online());
+    assert_eq!(session.privileges, Privileges::default_user());
+    assert!(session.pending_privileges.is_empty());
+    assert!(!session.accept_agreement());
+}
+
+#[test]
+fn login_with_no_agreement_goes_online_immediately() {
+    let mut session = Session::default();
+    let privileges = Privileges::default_user() | Privileges::NO_AGREEMENT;
+    session.apply_login(7, "alice", privileges);
+    assert!(session.is_online());
+    assert_eq!(session.privileges, privileges);
+    assert!(!session.accept_agreement());
+}
+
+#[test]
+fn invalidate_drops_login_but_keeps_connection_state() {
+    let mut session = Session {
+        news_listing: NewsListingEncoding::Hotline18,
+        unknown_transactions: 2,
+        ..Session::default()
+    };
+    session.apply_login(
+        7,
+        "alice",
+        Privileges::default_user() | Privileges::NO_AGREEMENT,
+    );
+    session.auto_response = Some("away".to_owned());
+
+    session.invalidate();
+
+    assert!(!session.is_authenticated());
+    assert_eq!(session.phase, SessionPhase::Unauthenticated);
+    assert!(session.privileges.is_empty());
+    assert!(session.display_name.is_empty());
+    assert!(session.auto_response.is_none());
+    assert_eq!(session.news_listing, NewsListingEncoding::Hotline18);
+    assert_eq!(session.unknown_transactions, 2);
+}
+
+#[test]
+fn take_disconnect_reason_invalidates_only_when_set() {
+    let mut session = Session::default();
+    session.apply_login(
+        7,
+        "alice",
+        Privileges::default_user() | Privileges::NO_AGREEMENT,
+    );
+    assert_eq!(session.take_disconnect_reason(), None);
+    assert!(session.is_online());
+
+    session.disconnect_reason = Some("kicked");
+    assert_eq!(session.take_disconnect_reason(), Some("kicked"));
+    assert!(!session.is_authenticated());
+    assert_eq!(session.disconnect_reason, None);
+}
+
+#[test]
+fn away_message_flags_user_until_cleared() {
+    let mut session = Session::default();
+    session.apply_login(
+        7,
+        "alice",
+        Privileges::default_user() | Privileges::NO_AGREEMENT,
+    );
+    session.connection_flags = ConnectionFlags::AUTOMATIC_RESPONSE;
+    session.auto_response = Some("back soon".to_owned());
+    assert_eq!(session.presence_flags(), USER_FLAG_AWAY);
+    let snapshot = session
+        .presence_snapshot(OutboundConnectionId::new(3))
+        .expect("online session is listed");
+    assert_eq!(snapshot.auto_response.as_deref(), Some("back soon"));
+
+    session.privileges |= Privileges::BROADCAST;
+    assert_eq!(session.presence_flags(), USER_FLAG_AWAY | USER_FLAG_ADMIN);
+    assert!(session.clear_away());
+    assert!(!session.clear_away());
+    assert_eq!(session.presence_flags(), USER_FLAG_ADMIN);
+    assert!(!session.connection_flags.has_auto_response());
+}
+
+#[test]
+fn session_require_access_checks_phase_then_privileges() {
+    let mut session = Session::default();
+    assert_eq!(session.require_access(Access::Open), Ok(()));
+    assert_eq!(
+        session.require_access(Access::Authenticated),
+        Err(PrivilegeError::NotAuthenticated)
+    );
+
+    session.apply_login(7, "alice", Privileges::SEND_CHAT);
+    assert_eq!(session.require_access(Access::Authenticated), Ok(()));
+    assert_eq!(
+        session.require_access(Access::Online),
+        Err(PrivilegeError::NotAuthenticated)
+    );
+
+    assert!(session.accept_agreement());
+    assert_eq!(
+        session.require_access(Access::OnlinePrivilege(Privileges::SEND_CHAT)),
+        Ok(())
+    );
+    assert_eq!(
+        session.require_access(Access::Privilege(Privileges::BROADCAST)),
+        Err(PrivilegeError::InsufficientPrivileges(
+            Privileges::BROADCAST
+        ))
+    );
+}
+
 #[test]
 fn privilege_error_display_not_authenticated() {
     let err = PrivilegeError::NotAuthenticated;
@@ -146,3 +275,32 @@ fn privilege_error_display_insufficient_privileges() {
     let err = PrivilegeError::InsufficientPrivileges(Privileges::DOWNLOAD_FILE);
     assert!(err.to_string().contains("insufficient privileges"));
 }
+
+#[tokio::test]
+async fn release_presence_removes_connection_from_shared_registry() {
+    let presence = Arc::new(PresenceRegistry::default());
+    let ctx = Context::with_presence(
+        "127.0.0.1:9004".parse().expect("loopback"),
+        dummy_pool(),
+        Arc::new(Argon2::default()),
+        Arc::clone(&presence),
+    );
+    presence
+        .upsert(PresenceSnapshot {
+            connection_id: ctx.presence_connection_id,
+            user_id: 0,
+            account_id: 0,
+            display_name: "alice".to_owned(),
+            icon_id: 7,
+            status_flags: 0,
+            cannot_be_disconnected: false,
+            auto_response: None,
+        })
+        .expect("presence upsert");
+    assert_eq!(presence.online_snapshots().len(), 1);
+
+    ctx.release_presence();
+    ctx.release_presence();
+
+    assert!(presence.online_snapshots().is_empty());
+}
@@ -0,0 +1,314 @@
+//! Bounded offload of password verification to Tokio's blocking pool.
+//!
+//! Argon2 is deliberately expensive, and a login storm that verifies hashes
+//! on async worker threads stalls every connection sharing those workers.
+//! [`HashingPool`] moves verification onto `spawn_blocking`, caps how many
+//! verifications run at once with a semaphore, and bounds how many may wait
+//! for a slot. Requests beyond that bound fail fast with
+//! [`HashingError::Saturated`] so the login handler can shed load instead of
+//! queueing without limit.
+//!
+//! Account administration hashes new passwords through the same pool with
+//! [`HashingPool::hash`], using the Argon2 parameters from the configuration.
+//! Login asks [`HashingPool::needs_rehash`] whether a stored hash predates
+//! those parameters and, once the password verifies, stores a fresh one.
+//!
+//! Each server builds its pool with [`HashingPool::from_config`] and keeps it
+//! in its settings, so servers in one process never share permits or queues.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    thread::available_parallelism,
+    time::{Duration, Instant},
+};
+
+use argon2::{Argon2, password_hash::Error as PasswordHashError};
+use thiserror::Error;
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    task::JoinError,
+};
+
+use crate::{
+    server::{AppConfig, admin::argon2_from_config},
+    users::{hash_password, needs_rehash, verify_password},
+};
+
+/// Default number of verifications allowed to wait for a slot.
+pub const DEFAULT_HASHING_QUEUE_LIMIT: usize = 64;
+
+/// Errors raised while verifying a password on the hashing pool.
+#[derive(Debug, Error)]
+pub enum HashingError {
+    /// Too many verifications are already waiting for a slot.
+    #[error("password hashing queue is saturated")]
+    Saturated,
+    /// The blocking hashing task panicked or was cancelled.
+    #[error("password hashing task failed: {0}")]
+    Task(#[from] JoinError),
+    /// Argon2 could not hash the password.
+    #[error("password hashing failed: {0}")]
+    Hash(#[from] PasswordHashError),
+}
+
+/// Point-in-time counters for a [`HashingPool`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HashingMetricsSnapshot {
+    /// Operations currently waiting for a slot.
+    pub queued: usize,
+    /// Verifications and hashes that have finished.
+    pub completed: u64,
+    /// Operations rejected because the queue was full.
+    pub rejected: u64,
+    /// Total time operations have spent waiting for a slot.
+    pub total_wait: Duration,
+}
+
+/// Semaphore-gated password verification and hashing on Tokio's blocking
+/// pool.
+#[derive(Debug)]
+pub struct HashingPool {
+    argon2: Argon2<'static>,
+    permits: Arc<Semaphore>,
+    queue_limit: usize,
+    queued: AtomicUsize,
+    completed: AtomicU64,
+    rejected: AtomicU64,
+    wait_micros: AtomicU64,
+}
+
+impl HashingPool {
+    /// Create a pool running at most `concurrency` verifications at once with
+    /// up to `queue_limit` more waiting. New passwords are hashed with the
+    /// default Argon2 parameters.
+    #[must_use]
+    pub fn new(concurrency: NonZeroUsize, queue_limit: usize) -> Self {
+        Self::with_argon2(Argon2::default(), concurrency, queue_limit)
+    }
+
+    fn with_argon2(argon2: Argon2<'static>, concurrency: NonZeroUsize, queue_limit: usize) -> Self {
+        Self {
+            argon2,
+            permits: Arc::new(Semaphore::new(concurrency.get())),
+            queue_limit,
+            queued: AtomicUsize::new(0),
+            completed: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            wait_micros: AtomicU64::new(0),
+        }
+    }
+
+    /// Create a pool sized from the hashing options in `config`, hashing new
+    /// passwords with its Argon2 parameters.
+    ///
+    /// Concurrency defaults to the number of available CPU cores and the
+    /// queue limit to [`DEFAULT_HASHING_QUEUE_LIMIT`].
+    #[must_use]
+    pub fn from_config(config: &AppConfig) -> Self {
+        let concurrency = config
+            .hashing_concurrency
+            .and_then(NonZeroUsize::new)
+            .unwrap_or_else(default_concurrency);
+        let queue_limit = config
+            .hashing_queue_limit
+            .unwrap_or(DEFAULT_HASHING_QUEUE_LIMIT);
+        let argon2 = argon2_from_config(config).unwrap_or_else(|error| {
+            tracing::warn!(%error, "invalid Argon2 parameters; hashing with defaults");
+            Argon2::default()
+        });
+        Self::with_argon2(argon2, concurrency, queue_limit)
+    }
+
+    /// Verify `password` against the stored `hash` on the blocking pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HashingError::Saturated`] when the wait queue is full, or
+    /// [`HashingError::Task`] if the blocking task fails.
+    pub async fn verify(&self, hash: String, password: String) -> Result<bool, HashingError> {
+        self.run_blocking(move || verify_password(&hash, &password))
+            .await
+    }
+
+    /// Hash a new `password` on the blocking pool with the configured Argon2
+    /// parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HashingError::Saturated`] when the wait queue is full,
+    /// [`HashingError::Task`] if the blocking task fails, or
+    /// [`HashingError::Hash`] if Argon2 rejects the input.
+    pub async fn hash(&self, password: String) -> Result<String, HashingError> {
+        let argon2 = self.argon2.clone();
+        Ok(self
+            .run_blocking(move || hash_password(&argon2, &password))
+            .await??)
+    }
+
+    /// Report whether `hash` was made with other Argon2 parameters than
+    /// [`Self::hash`] uses.
+    #[must_use]
+    pub fn needs_rehash(&self, hash: &str) -> bool { needs_rehash(&self.argon2, hash) }
+
+    async fn run_blocking<T, F>(&self, work: F) -> Result<T, HashingError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = match Arc::clone(&self.permits).try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => self.wait_for_permit().await?,
+        };
+
+        let output = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            work()
+        })
+        .await?;
+        self.completed.fetch_add(1, Ordering::Relaxed);
+        Ok(output)
+    }
+
+    /// Read the current counter values.
+    #[must_use]
+    pub fn metrics(&self) -> HashingMetricsSnapshot {
+        HashingMetricsSnapshot {
+            queued: self.queued.load(Ordering::Relaxed),
+            completed: self.completed.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            total_wait: Duration::from_micros(self.wait_micros.load(Ordering::Relaxed)),
+        }
+    }
+
+    async fn wait_for_permit(&self) -> Result<OwnedSemaphorePermit, HashingError> {
+        let slot = self.enter_queue()?;
+        let started = Instant::now();
+        let permit = Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .map_err(|_| HashingError::Saturated)?;
+        drop(slot);
+        let waited = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+        self.wait_micros.fetch_add(waited, Ordering::Relaxed);
+        Ok(permit)
+    }
+
+    fn enter_queue(&self) -> Result<QueueSlot<'_>, HashingError> {
+        let admitted = self
+            .queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < self.queue_limit).then_some(queued + 1)
+            })
+            .is_ok();
+        if admitted {
+            Ok(QueueSlot { pool: self })
+        } else {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            Err(HashingError::Saturated)
+        }
+    }
+}
+
+/// Queue position released when the waiter obtains a permit or is dropped.
+struct QueueSlot<'a> {
+    pool: &'a HashingPool,
+}
+
+impl Drop for QueueSlot<'_> {
+    fn drop(&mut self) { self.pool.queued.fetch_sub(1, Ordering::AcqRel); }
+}
+
+fn default_concurrency() -> NonZeroUsize { available_parallelism().unwrap_or(NonZeroUsize::MIN) }
+
+impl Default for HashingPool {
+    /// A pool sized and parameterised as the default configuration asks.
+    fn default() -> Self { Self::from_config(&AppConfig::default()) }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for hashing pool admission and metrics.
+    use argon2::Argon2;
+    use rstest::rstest;
+    use tokio::runtime::Runtime;
+
+    use super::*;
+    use crate::users::hash_password;
+
+    const fn one() -> NonZeroUsize { NonZeroUsize::MIN }
+
+    #[rstest]
+    fn verifies_on_blocking_pool() {
+        let pool = HashingPool::new(one(), 4);
+        let hash = hash_password(&Argon2::default(), "secret").expect("hash password");
+        let rt = Runtime::new().expect("runtime");
+
+        let matched = rt
+            .block_on(pool.verify(hash, "secret".to_owned()))
+            .expect("verify");
+
+        assert!(matched);
+        let metrics = pool.metrics();
+        assert_eq!(metrics.completed, 1);
+        assert_eq!(metrics.queued, 0);
+    }
+
+    #[rstest]
+    fn hashes_new_passwords_with_configured_parameters() {
+        let config = AppConfig {
+            argon2_t_cost: 1,
+            ..AppConfig::default()
+        };
+        let pool = HashingPool::from_config(&config);
+        let rt = Runtime::new().expect("runtime");
+
+        let hash = rt.block_on(pool.hash("secret".to_owned())).expect("hash");
+
+        assert!(hash.contains("t=1"), "{hash}");
+        assert!(verify_password(&hash, "secret"));
+        assert_eq!(pool.metrics().completed, 1);
+    }
+
+    #[rstest]
+    fn sheds_load_when_queue_is_full() {
+        let pool = HashingPool::new(one(), 0);
+        let _busy = Arc::clone(&pool.permits)
+            .try_acquire_owned()
+            .expect("only permit");
+        let rt = Runtime::new().expect("runtime");
+
+        let result = rt.block_on(pool.verify("hash".to_owned(), "secret".to_owned()));
+
+        assert!(matches!(result, Err(HashingError::Saturated)));
+        assert_eq!(pool.metrics().rejected, 1);
+    }
+
+    #[rstest]
+    fn queue_slot_is_released_on_drop() {
+        let pool = HashingPool::new(one(), 1);
+
+        let slot = pool.enter_queue().expect("first waiter admitted");
+        assert!(matches!(pool.enter_queue(), Err(HashingError::Saturated)));
+        drop(slot);
+
+        assert!(pool.enter_queue().is_ok());
+    }
+
+    #[rstest]
+    fn config_overrides_defaults() {
+        let config = AppConfig {
+            hashing_concurrency: Some(2),
+            hashing_queue_limit: Some(8),
+            ..AppConfig::default()
+        };
+
+        let pool = HashingPool::from_config(&config);
+
+        assert_eq!(pool.permits.available_permits(), 2);
+        assert_eq!(pool.queue_limit, 8);
+    }
+}
@@ -39,8 +39,9 @@ cfg_if::cfg_if! {
 pub mod commands;
 pub mod connection_flags;
 pub mod db;
-pub mod field_id;
+pub mod file_handlers;
 pub mod handler;
+pub mod hashing;
 pub mod header_util;
 pub mod login;
 pub mod models;
@@ -48,6 +49,7 @@ pub mod news_handlers;
 pub(crate) mod news_path;
 mod presence;
 pub use presence::{
+    AccountSession,
     PresenceRegistry,
     PresenceRemoval,
     PresenceSnapshot,
@@ -58,11 +60,12 @@ pub use presence::{
     build_notify_delete_user,
     build_user_name_list_reply,
 };
-pub mod privileges;
-pub mod protocol;
 pub mod schema;
 pub mod server;
-pub mod transaction;
-pub mod transaction_type;
+pub mod storage;
 pub mod users;
+pub mod wire_time;
 pub mod wireframe;
+
+/// Protocol modules from [`mxd_proto`], re-exported at their original paths.
+pub use mxd_proto::{access, field_id, privileges, protocol, transaction, transaction_type};
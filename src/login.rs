@@ -2,7 +2,18 @@
 //!
 //! Validates user credentials against the database and updates session state
 //! on success. Login attempts are logged and rejected with appropriate error
-//! codes when validation fails.
+//! codes when validation fails. A successful login whose stored hash was made
+//! with other Argon2 parameters than the configured ones replaces the hash,
+//! so operators can strengthen the parameters without resetting passwords.
+//! Repeated failures lock the address or account out for a while; see
+//! [`crate::server::login_throttle`]. When sessions may be resumed, a login
+//! may present the session token from an earlier one instead of the
+//! password, and each successful login that asks for one is handed a new
+//! token; see
+//! [`crate::server::session_resume`]. A resumed session takes over the
+//! account's running and queued transfers, so their Download Info (211)
+//! pushes reach the connection it arrived on. A login may also select the news root
+//! the session reads and posts in; an unknown root refuses the login.
 
 #![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
 #![expect(
@@ -10,18 +21,47 @@
     reason = "login flow requires multiple validation steps"
 )]
 
-use std::net::SocketAddr;
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
 
-use tracing::{info, warn};
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use tracing::{debug, info, warn};
 
 use crate::{
-    commands::CommandError,
-    db::{DbPool, get_user_by_name},
+    commands::{
+        CommandError,
+        ERR_BANNED,
+        ERR_LOGIN_LOCKED,
+        ERR_SERVER_BUSY,
+        NEWS_ERR_PATH_NOT_FOUND,
+    },
+    db::{
+        DbPool,
+        PRIMARY_NEWS_ROOT,
+        UserUpdate,
+        acquire,
+        decode_privileges,
+        get_user_by_name,
+        issue_session_token,
+        news_root_exists,
+        redeem_session_token,
+        update_user,
+    },
     field_id::FieldId,
+    hashing::{HashingError, HashingPool},
     header_util::reply_header,
+    models::{Ban, User},
     privileges::Privileges,
+    server::{
+        bans::{BAN_REASON, find_login_ban},
+        login_throttle::LoginThrottle,
+        outbound::OutboundConnectionId,
+        settings::ServerSettings,
+    },
     transaction::{FrameHeader, Transaction, encode_params},
-    users::verify_password,
+    wire_time::{server_clock_params, server_now},
 };
 
 /// Parameters for a login request containing credentials and protocol header.
@@ -31,38 +71,139 @@ pub struct LoginRequest {
     pub username: String,
     /// Password for authentication.
     pub password: String,
+    /// Session token from an earlier login, accepted instead of the
+    /// password. Present, even empty, it asks for a new token.
+    pub resume_token: Option<String>,
+    /// News root the session should use; `None` selects the primary root.
+    pub news_root: Option<i32>,
     /// Transaction frame header.
     pub header: FrameHeader,
 }
 
+/// Privileges granted to `account` when it logs in.
+///
+/// These are the privileges stored with the account. Get User reports the
+/// same value so admin clients see what a login would grant.
+#[must_use]
+pub(crate) const fn account_privileges(account: &User) -> Privileges {
+    decode_privileges(account.privileges)
+}
+
+/// Where a login request arrived.
+#[derive(Clone, Copy)]
+pub(crate) struct LoginOrigin<'a> {
+    /// Remote peer address.
+    pub peer: SocketAddr,
+    /// Connection the login arrived on, when the runtime can push to it.
+    pub connection: Option<OutboundConnectionId>,
+    /// Settings of the server that accepted the connection.
+    pub settings: &'a ServerSettings,
+}
+
 /// Handle a user login request.
 ///
+/// Logins from a locked-out address or to a locked-out account are refused
+/// with [`ERR_LOGIN_LOCKED`] before the database is consulted; see
+/// [`LoginThrottle`]. Selecting a news root that does not exist is refused
+/// with [`NEWS_ERR_PATH_NOT_FOUND`]. A session resumed with a token rebinds
+/// the account's transfers to the connection the login arrived on.
+///
 /// # Errors
 /// Returns an error if database access fails or credentials are invalid.
 #[must_use = "handle the result"]
 pub(crate) async fn handle_login(
-    peer: SocketAddr,
+    origin: LoginOrigin<'_>,
     session: &mut crate::handler::Session,
     pool: DbPool,
     req: LoginRequest,
 ) -> Result<Transaction, CommandError> {
-    let mut conn = pool.get().await?;
+    let LoginOrigin {
+        peer,
+        connection,
+        settings,
+    } = origin;
+    let throttle = &settings.login_throttle;
+    if let Some(remaining) = throttle.locked_for(peer.ip(), &req.username, Instant::now()) {
+        return Ok(refuse_locked_out(peer, &req, remaining));
+    }
+    let mut conn = acquire(&pool, req.header.ty).await?;
+    if let Some(ban) = find_login_ban(&mut conn, &req.username, peer.ip()).await? {
+        return Ok(refuse_banned(peer, session, &req, &ban));
+    }
+    let news_root = match req.news_root {
+        Some(root) if !news_root_exists(&mut conn, root).await? => {
+            return Ok(refuse_unknown_news_root(peer, &req, root));
+        }
+        root => root.unwrap_or(PRIMARY_NEWS_ROOT),
+    };
     let user = get_user_by_name(&mut conn, &req.username).await?;
+    // A token only resumes the account it was issued to; any other token,
+    // or one presented while resumption is off, falls back to the password.
+    let resumed = match (&user, req.resume_token.as_deref(), settings.resume_window) {
+        (Some(u), Some(token), Some(_)) if !token.is_empty() => {
+            redeem_session_token(&mut conn, u.id, token, Utc::now().naive_utc()).await?
+        }
+        _ => false,
+    };
+    // Release the connection before waiting on the hashing pool.
+    drop(conn);
     let (error, payload) = if let Some(u) = user {
-        if verify_password(&u.password, &req.password) {
-            // Apply the current server policy until account-level privilege
-            // persistence exists.
-            let privileges = Privileges::default_user() | Privileges::NO_AGREEMENT;
+        let mut privileges = account_privileges(&u);
+        // Accounts skip the agreement step only when the server has none to
+        // show.
+        if settings.agreement.text().is_none() {
+            privileges |= Privileges::NO_AGREEMENT;
+        }
+        let stale_hash = !resumed && settings.hashing.needs_rehash(&u.password);
+        let verified = if resumed {
+            true
+        } else {
+            match settings
+                .hashing
+                .verify(u.password, req.password.clone())
+                .await
+            {
+                Ok(verified) => verified,
+                Err(HashingError::Saturated) => {
+                    warn!(%peer, username = %req.username, "login shed: password hashing saturated");
+                    return Ok(Transaction {
+                        header: reply_header(&req.header, ERR_SERVER_BUSY, 0),
+                        payload: Vec::new(),
+                    });
+                }
+                Err(error) => return Err(error.into()),
+            }
+        };
+        if verified {
+            if stale_hash {
+                upgrade_password_hash(&settings.hashing, &pool, &req, &u.username).await;
+            }
+            if resumed {
+                info!(%peer, username = %u.username, "session resumed with token");
+                if let Some(connection) = connection {
+                    let moved = settings.transfers.manager().rebind(u.id, connection);
+                    debug!(%peer, moved, "transfers rebound to resumed session");
+                }
+            }
+            throttle.record_success(&u.username);
+            // Clients ask for a token by sending field 169, empty when they
+            // have none to present.
+            let token = match (&req.resume_token, settings.resume_window) {
+                (Some(_), Some(window)) => {
+                    issue_resume_token(&pool, &req.header, u.id, window).await
+                }
+                _ => None,
+            };
+            session.news_root = news_root;
             session.apply_login(u.id, &u.username, privileges);
-            let params = encode_params(&[(
-                FieldId::Version,
-                &crate::protocol::CLIENT_VERSION.to_be_bytes(),
-            )])?;
-            (0u32, params)
+            session.show_agreement = session.requires_agreement();
+            (0u32, login_reply_params(token)?)
         } else {
+            record_failed_login(throttle, peer, Some(&u.username));
             (1u32, Vec::new())
         }
     } else {
+        record_failed_login(throttle, peer, None);
         (1u32, Vec::new())
     };
     let reply = Transaction {
@@ -77,75 +218,139 @@ pub(crate) async fn handle_login(
     Ok(reply)
 }
 
-#[cfg(test)]
-mod tests {
-    //! Behavioural coverage for login edge cases.
-
-    use std::net::SocketAddr;
-
-    use anyhow::anyhow;
-    use test_util::{AnyError, DatabaseUrl, build_test_db, with_db};
-    use tokio::runtime::Runtime;
-
-    use super::{LoginRequest, handle_login};
-    use crate::{
-        db::create_user,
-        handler::Session,
-        models::NewUser,
-        transaction::FrameHeader,
-        transaction_type::TransactionType,
-    };
+/// Return the payload of a successful login reply, which carries
+/// `resume_token` when one was issued.
+fn login_reply_params(resume_token: Option<String>) -> Result<Vec<u8>, CommandError> {
+    let mut reply_params = vec![(
+        FieldId::Version,
+        crate::protocol::CLIENT_VERSION.to_be_bytes().to_vec(),
+    )];
+    reply_params.extend(server_clock_params(&server_now()));
+    if let Some(token) = resume_token {
+        reply_params.push((FieldId::SessionToken, token.into_bytes()));
+    }
+    Ok(encode_params(&reply_params)?)
+}
 
-    fn setup_user_with_invalid_hash(db: DatabaseUrl) -> Result<(), AnyError> {
-        with_db(db, |conn| {
-            Box::pin(async move {
-                let new_user = NewUser {
-                    username: "alice",
-                    password: "not-a-valid-hash",
-                };
-                create_user(conn, &new_user).await?;
-                Ok(())
-            })
-        })
+/// Issue a session token for `user_id` that stays valid for `window`.
+///
+/// The login succeeds either way: a token that cannot be stored is logged
+/// and left out of the reply.
+async fn issue_resume_token(
+    pool: &DbPool,
+    header: &FrameHeader,
+    user_id: i32,
+    window: Duration,
+) -> Option<String> {
+    match store_session_token(pool, header, user_id, window).await {
+        Ok(token) => Some(token),
+        Err(error) => {
+            warn!(%error, user_id, "session token not issued");
+            None
+        }
     }
+}
 
-    #[serial_test::file_serial(postgres_embedded_setup)]
-    #[test]
-    fn handle_login_rejects_invalid_password_hashes() -> Result<(), AnyError> {
-        let rt = Runtime::new()?;
-        let Some(db) = build_test_db(&rt, setup_user_with_invalid_hash)? else {
-            return Ok(());
-        };
-        let mut session = Session::default();
-        let peer: SocketAddr = "127.0.0.1:12345".parse()?;
-        let req = LoginRequest {
-            username: "alice".to_string(),
-            password: "secret".to_string(),
-            header: FrameHeader {
-                flags: 0,
-                is_reply: 0,
-                ty: TransactionType::Login.into(),
-                id: 1,
-                error: 0,
-                total_size: 0,
-                data_size: 0,
-            },
-        };
+async fn store_session_token(
+    pool: &DbPool,
+    header: &FrameHeader,
+    user_id: i32,
+    window: Duration,
+) -> Result<String, CommandError> {
+    let now = Utc::now().naive_utc();
+    let expires_at = TimeDelta::from_std(window)
+        .ok()
+        .and_then(|delta| now.checked_add_signed(delta))
+        .unwrap_or(NaiveDateTime::MAX);
+    let mut conn = acquire(pool, header.ty).await?;
+    Ok(issue_session_token(&mut conn, user_id, now, expires_at).await?)
+}
 
-        let reply = rt.block_on(handle_login(peer, &mut session, db.pool(), req))?;
+/// Count a failed login from `peer` in `throttle`, and against `account`
+/// when the login named one, logging any lockout it starts.
+fn record_failed_login(throttle: &LoginThrottle, peer: SocketAddr, account: Option<&str>) {
+    if let Some(lockout) = throttle.record_failure(peer.ip(), account, Instant::now()) {
+        warn!(
+            %peer,
+            ?account,
+            lockout_secs = lockout.as_secs(),
+            "login locked out after repeated failures"
+        );
+    }
+}
 
-        if reply.header.error != 1 {
-            return Err(anyhow!(
-                "expected error code 1 for invalid hash, got {}",
-                reply.header.error
-            ));
-        }
-        if session.user_id.is_some() {
-            return Err(anyhow!("session should remain unauthenticated"));
-        }
-        if session.is_online() {
-            return Err(anyhow!("session should not become online"));
-        }
-        Ok(())
+/// Replace `username`'s stored hash with one made with the configured
+/// Argon2 parameters.
+///
+/// The login succeeds either way: a failure is logged and leaves the old
+/// hash in place for the next login to replace.
+async fn upgrade_password_hash(
+    hashing: &HashingPool,
+    pool: &DbPool,
+    req: &LoginRequest,
+    username: &str,
+) {
+    match store_fresh_hash(hashing, pool, req, username).await {
+        Ok(()) => info!(username, "password rehashed with current Argon2 parameters"),
+        Err(error) => warn!(%error, username, "password rehash failed"),
+    }
+}
+
+async fn store_fresh_hash(
+    hashing: &HashingPool,
+    pool: &DbPool,
+    req: &LoginRequest,
+    username: &str,
+) -> Result<(), CommandError> {
+    let hashed = hashing.hash(req.password.clone()).await?;
+    let update = UserUpdate {
+        username: None,
+        password: Some(&hashed),
+    };
+    let mut conn = acquire(pool, req.header.ty).await?;
+    update_user(&mut conn, username, &update).await?;
+    Ok(())
+}
+
+/// Reply to a login from a locked-out account or address; the connection
+/// stays open so the client can retry once the lockout expires.
+fn refuse_locked_out(peer: SocketAddr, req: &LoginRequest, remaining: Duration) -> Transaction {
+    warn!(
+        %peer,
+        username = %req.username,
+        retry_after_secs = remaining.as_secs(),
+        "login refused: locked out"
+    );
+    Transaction {
+        header: reply_header(&req.header, ERR_LOGIN_LOCKED, 0),
+        payload: Vec::new(),
+    }
+}
+
+/// Reply to a login from a banned account or address and ask the runtime to
+/// close the connection with [`BAN_REASON`].
+fn refuse_banned(
+    peer: SocketAddr,
+    session: &mut crate::handler::Session,
+    req: &LoginRequest,
+    ban: &Ban,
+) -> Transaction {
+    warn!(%peer, username = %req.username, ban_id = ban.id, "login refused: banned");
+    session.disconnect_reason = Some(BAN_REASON);
+    Transaction {
+        header: reply_header(&req.header, ERR_BANNED, 0),
+        payload: Vec::new(),
+    }
+}
+
+fn refuse_unknown_news_root(peer: SocketAddr, req: &LoginRequest, root: i32) -> Transaction {
+    warn!(%peer, username = %req.username, news_root = root, "login refused: unknown news root");
+    Transaction {
+        header: reply_header(&req.header, NEWS_ERR_PATH_NOT_FOUND, 0),
+        payload: Vec::new(),
     }
 }
+
+#[cfg(test)]
+#[path = "login_tests.rs"]
+mod tests;
@@ -0,0 +1,146 @@
+//! Logins that resume an earlier session with a token.
+
+use std::{net::SocketAddr, time::Duration};
+
+use anyhow::anyhow;
+use test_util::{AnyError, build_test_db};
+use tokio::runtime::Runtime;
+
+use super::{LoginOrigin, LoginRequest, handle_login, login_as, origin, setup_weak_account};
+use crate::{
+    field_id::FieldId,
+    handler::Session,
+    server::{
+        outbound::OutboundConnectionId,
+        settings::ServerSettings,
+        transfers::{TransferDirection, TransferRequest},
+    },
+    transaction::{decode_params_map, first_param_string},
+};
+
+#[serial_test::file_serial(postgres_embedded_setup)]
+#[test]
+fn handle_login_resumes_sessions_with_single_use_tokens() -> Result<(), AnyError> {
+    let rt = Runtime::new()?;
+    let Some(db) = build_test_db(&rt, |db| setup_weak_account(db, "carol"))? else {
+        return Ok(());
+    };
+    let settings = resumable();
+    let peer: SocketAddr = "192.0.2.81:12345".parse()?;
+    let login = |req: LoginRequest| {
+        let mut session = Session::default();
+        let reply = rt.block_on(handle_login(
+            origin(peer, &settings),
+            &mut session,
+            db.pool(),
+            req,
+        ))?;
+        let params = decode_params_map(&reply.payload)?;
+        let token = first_param_string(&params, FieldId::SessionToken)?;
+        Ok::<_, AnyError>((reply.header.error, session.user_id, token))
+    };
+    let resume = |token: &str| LoginRequest {
+        resume_token: Some(token.to_owned()),
+        ..login_as("carol", "")
+    };
+
+    let (_, _, unasked) = login(login_as("carol", "secret"))?;
+    if unasked.is_some() {
+        return Err(anyhow!("a login that asked for no token was given one"));
+    }
+    let (empty_error, ..) = login(resume(""))?;
+    if empty_error != 1 {
+        return Err(anyhow!("an empty token must not stand in for the password"));
+    }
+    let asking = LoginRequest {
+        resume_token: Some(String::new()),
+        ..login_as("carol", "secret")
+    };
+    let (login_error, _, first) = login(asking)?;
+    let first = first.ok_or_else(|| anyhow!("login {login_error} issued no token"))?;
+    let (error, user_id, second) = login(resume(&first))?;
+    if error != 0 || user_id.is_none() || second.is_none() {
+        return Err(anyhow!("token login failed with error {error}"));
+    }
+    if second.as_deref() == Some(first.as_str()) {
+        return Err(anyhow!("resumed login must be given a new token"));
+    }
+    let (reuse_error, reused_user, _) = login(resume(&first))?;
+    if reuse_error != 1 || reused_user.is_some() {
+        return Err(anyhow!("a used token must not log in again"));
+    }
+    Ok(())
+}
+
+#[serial_test::file_serial(postgres_embedded_setup)]
+#[test]
+fn handle_login_rebinds_transfers_to_a_resumed_session() -> Result<(), AnyError> {
+    let rt = Runtime::new()?;
+    let Some(db) = build_test_db(&rt, |db| setup_weak_account(db, "erin"))? else {
+        return Ok(());
+    };
+    let settings = resumable();
+    let peer: SocketAddr = "192.0.2.83:12345".parse()?;
+    let (dropped, resumed) = (OutboundConnectionId::new(1), OutboundConnectionId::new(2));
+    let asking = LoginRequest {
+        resume_token: Some(String::new()),
+        ..login_as("erin", "secret")
+    };
+    let mut session = Session::default();
+    let reply = rt.block_on(handle_login(
+        LoginOrigin {
+            connection: Some(dropped),
+            ..origin(peer, &settings)
+        },
+        &mut session,
+        db.pool(),
+        asking,
+    ))?;
+    let token = first_param_string(&decode_params_map(&reply.payload)?, FieldId::SessionToken)?
+        .ok_or_else(|| anyhow!("login {} issued no token", reply.header.error))?;
+    let user_id = session
+        .user_id
+        .ok_or_else(|| anyhow!("login did not authenticate"))?;
+    let reference = 0x3048_0001;
+    let _ = settings.transfers.manager().admit(TransferRequest {
+        user_id,
+        reference,
+        direction: TransferDirection::Download,
+        connection: Some(dropped),
+    });
+
+    let resume = LoginRequest {
+        resume_token: Some(token),
+        ..login_as("erin", "")
+    };
+    let mut resumed_session = Session::default();
+    let outcome = rt.block_on(handle_login(
+        LoginOrigin {
+            connection: Some(resumed),
+            ..origin(peer, &settings)
+        },
+        &mut resumed_session,
+        db.pool(),
+        resume,
+    ));
+    let rebound = settings
+        .transfers
+        .manager()
+        .request(reference)
+        .and_then(|request| request.connection);
+    if outcome?.header.error != 0 {
+        return Err(anyhow!("token login failed"));
+    }
+    if rebound != Some(resumed) {
+        return Err(anyhow!("transfer still bound to {rebound:?}"));
+    }
+    Ok(())
+}
+
+/// Settings of a server that lets sessions be resumed.
+fn resumable() -> ServerSettings {
+    ServerSettings {
+        resume_window: Some(Duration::from_secs(300)),
+        ..ServerSettings::default()
+    }
+}
@@ -0,0 +1,306 @@
+//! Behavioural coverage for login edge cases.
+
+use std::net::SocketAddr;
+
+use anyhow::anyhow;
+use test_util::{AnyError, DatabaseUrl, build_test_db, with_db};
+use tokio::runtime::Runtime;
+
+use super::{
+    BAN_REASON,
+    ERR_BANNED,
+    ERR_LOGIN_LOCKED,
+    LoginOrigin,
+    LoginRequest,
+    NEWS_ERR_PATH_NOT_FOUND,
+    handle_login,
+};
+use crate::{
+    db::{BanTarget, create_ban, create_news_root, create_user, get_user_by_name},
+    handler::Session,
+    models::NewUser,
+    server::settings::ServerSettings,
+    transaction::FrameHeader,
+    transaction_type::TransactionType,
+    users::{hash_password, verify_password},
+};
+
+/// Hash of "secret" made with a lower time cost than the server uses.
+fn weak_hash() -> Result<String, AnyError> {
+    let params = argon2::Params::new(8, 1, 1, None).map_err(|error| anyhow!("{error}"))?;
+    let argon2 = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params);
+    hash_password(&argon2, "secret").map_err(|error| anyhow!("{error}"))
+}
+
+fn setup_user_with_weak_hash(db: DatabaseUrl) -> Result<(), AnyError> {
+    setup_weak_account(db, "alice")
+}
+
+fn setup_weak_account(db: DatabaseUrl, username: &'static str) -> Result<(), AnyError> {
+    let hashed = weak_hash()?;
+    with_db(db, |conn| {
+        Box::pin(async move {
+            let new_user = NewUser {
+                username,
+                password: &hashed,
+            };
+            create_user(conn, &new_user).await?;
+            Ok(())
+        })
+    })
+}
+
+fn setup_user_with_invalid_hash(db: DatabaseUrl) -> Result<(), AnyError> {
+    with_db(db, |conn| {
+        Box::pin(async move {
+            let new_user = NewUser {
+                username: "alice",
+                password: "not-a-valid-hash",
+            };
+            create_user(conn, &new_user).await?;
+            Ok(())
+        })
+    })
+}
+
+fn setup_banned_address(db: DatabaseUrl) -> Result<(), AnyError> {
+    with_db(db, |conn| {
+        Box::pin(async move {
+            let new_user = NewUser {
+                username: "alice",
+                password: "not-a-valid-hash",
+            };
+            create_user(conn, &new_user).await?;
+            let address = BanTarget::Address("127.0.0.1".parse()?);
+            create_ban(conn, &address, Some("flooding"), None).await?;
+            Ok(())
+        })
+    })
+}
+
+/// A login from `peer` to a server with `settings`, on a connection the
+/// runtime cannot push to.
+const fn origin(peer: SocketAddr, settings: &ServerSettings) -> LoginOrigin<'_> {
+    LoginOrigin {
+        peer,
+        connection: None,
+        settings,
+    }
+}
+
+fn alice_login() -> LoginRequest { login_as("alice", "secret") }
+
+fn login_as(username: &str, password: &str) -> LoginRequest {
+    LoginRequest {
+        username: username.to_string(),
+        password: password.to_string(),
+        resume_token: None,
+        news_root: None,
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::Login.into(),
+            id: 1,
+            error: 0,
+            total_size: 0,
+            data_size: 0,
+        },
+    }
+}
+
+#[serial_test::file_serial(postgres_embedded_setup)]
+#[test]
+fn handle_login_rejects_invalid_password_hashes() -> Result<(), AnyError> {
+    let rt = Runtime::new()?;
+    let Some(db) = build_test_db(&rt, setup_user_with_invalid_hash)? else {
+        return Ok(());
+    };
+    let mut session = Session::default();
+    let settings = ServerSettings::default();
+    let peer: SocketAddr = "127.0.0.1:12345".parse()?;
+
+    let reply = rt.block_on(handle_login(
+        origin(peer, &settings),
+        &mut session,
+        db.pool(),
+        alice_login(),
+    ))?;
+
+    if reply.header.error != 1 {
+        return Err(anyhow!(
+            "expected error code 1 for invalid hash, got {}",
+            reply.header.error
+        ));
+    }
+    if session.user_id.is_some() {
+        return Err(anyhow!("session should remain unauthenticated"));
+    }
+    if session.is_online() {
+        return Err(anyhow!("session should not become online"));
+    }
+    Ok(())
+}
+
+#[serial_test::file_serial(postgres_embedded_setup)]
+#[test]
+fn handle_login_refuses_banned_address() -> Result<(), AnyError> {
+    let rt = Runtime::new()?;
+    let Some(db) = build_test_db(&rt, setup_banned_address)? else {
+        return Ok(());
+    };
+    let mut session = Session::default();
+    let settings = ServerSettings::default();
+    let peer: SocketAddr = "127.0.0.1:12345".parse()?;
+
+    let reply = rt.block_on(handle_login(
+        origin(peer, &settings),
+        &mut session,
+        db.pool(),
+        alice_login(),
+    ))?;
+
+    if reply.header.error != ERR_BANNED {
+        return Err(anyhow!("expected ban error, got {}", reply.header.error));
+    }
+    if session.disconnect_reason != Some(BAN_REASON) {
+        return Err(anyhow!("banned login should close the connection"));
+    }
+    if session.user_id.is_some() {
+        return Err(anyhow!("session should remain unauthenticated"));
+    }
+    Ok(())
+}
+
+#[serial_test::file_serial(postgres_embedded_setup)]
+#[test]
+fn handle_login_rehashes_passwords_with_stale_parameters() -> Result<(), AnyError> {
+    let rt = Runtime::new()?;
+    let Some(db) = build_test_db(&rt, setup_user_with_weak_hash)? else {
+        return Ok(());
+    };
+    let mut session = Session::default();
+    let settings = ServerSettings::default();
+    let peer: SocketAddr = "127.0.0.1:12345".parse()?;
+
+    let reply = rt.block_on(handle_login(
+        origin(peer, &settings),
+        &mut session,
+        db.pool(),
+        alice_login(),
+    ))?;
+    if reply.header.error != 0 {
+        return Err(anyhow!("login failed with error {}", reply.header.error));
+    }
+
+    let stored = rt.block_on(async {
+        let mut conn = db.pool().get().await?;
+        get_user_by_name(&mut conn, "alice")
+            .await?
+            .ok_or_else(|| anyhow!("alice disappeared"))
+    })?;
+    if settings.hashing.needs_rehash(&stored.password) {
+        return Err(anyhow!("stale hash was kept: {}", stored.password));
+    }
+    if !verify_password(&stored.password, "secret") {
+        return Err(anyhow!("rehashed password no longer verifies"));
+    }
+    Ok(())
+}
+
+#[serial_test::file_serial(postgres_embedded_setup)]
+#[test]
+fn handle_login_locks_out_after_repeated_failures() -> Result<(), AnyError> {
+    let rt = Runtime::new()?;
+    let Some(db) = build_test_db(&rt, |db| setup_weak_account(db, "mallory"))? else {
+        return Ok(());
+    };
+    let settings = ServerSettings::default();
+    let peer: SocketAddr = "192.0.2.80:12345".parse()?;
+
+    for attempt in 1..=5 {
+        let mut session = Session::default();
+        let reply = rt.block_on(handle_login(
+            origin(peer, &settings),
+            &mut session,
+            db.pool(),
+            login_as("mallory", "guess"),
+        ))?;
+        if reply.header.error != 1 {
+            return Err(anyhow!(
+                "attempt {attempt}: expected error 1, got {}",
+                reply.header.error
+            ));
+        }
+    }
+
+    let mut session = Session::default();
+    let reply = rt.block_on(handle_login(
+        origin(peer, &settings),
+        &mut session,
+        db.pool(),
+        login_as("mallory", "secret"),
+    ))?;
+    if reply.header.error != ERR_LOGIN_LOCKED {
+        return Err(anyhow!(
+            "expected lockout error, got {}",
+            reply.header.error
+        ));
+    }
+    if session.user_id.is_some() {
+        return Err(anyhow!("locked-out login must not authenticate"));
+    }
+    if session.disconnect_reason.is_some() {
+        return Err(anyhow!("locked-out login must keep the connection open"));
+    }
+    Ok(())
+}
+
+#[serial_test::file_serial(postgres_embedded_setup)]
+#[test]
+fn handle_login_selects_the_requested_news_root() -> Result<(), AnyError> {
+    let rt = Runtime::new()?;
+    let Some(db) = build_test_db(&rt, |db| {
+        setup_weak_account(db.clone(), "dave")?;
+        with_db(db, |conn| {
+            Box::pin(async move {
+                create_news_root(conn, "games").await?;
+                Ok(())
+            })
+        })
+    })?
+    else {
+        return Ok(());
+    };
+    let settings = ServerSettings::default();
+    let peer: SocketAddr = "192.0.2.82:12345".parse()?;
+    let login = |news_root: Option<i32>| {
+        let mut session = Session::default();
+        let req = LoginRequest {
+            news_root,
+            ..login_as("dave", "secret")
+        };
+        let reply = rt.block_on(handle_login(
+            origin(peer, &settings),
+            &mut session,
+            db.pool(),
+            req,
+        ))?;
+        Ok::<_, AnyError>((reply.header.error, session))
+    };
+
+    let (error, selected) = login(Some(1))?;
+    if error != 0 || selected.news_root != 1 {
+        return Err(anyhow!(
+            "login to root 1 gave error {error}, root {}",
+            selected.news_root
+        ));
+    }
+    let (refusal, refused) = login(Some(99))?;
+    if refusal != NEWS_ERR_PATH_NOT_FOUND || refused.user_id.is_some() {
+        return Err(anyhow!("login to an unknown root gave error {refusal}"));
+    }
+    Ok(())
+}
+
+#[path = "login_resume_tests.rs"]
+mod resume;
@@ -1,15 +1,23 @@
 //! Binary entry point for the legacy TCP server.
 //!
 //! All runtime logic lives in `mxd::server`, allowing future binaries to re-use
-//! the same domain modules and configuration plumbing.
+//! the same domain modules and configuration plumbing. Configuration is loaded
+//! before the Tokio runtime starts so its thread pools can be sized from it, and
+//! the log subscriber, whose target it names, is installed straight after so
+//! every later step is recorded.
 
 use anyhow::{Context, Result};
-use tokio::runtime::Builder;
+use mxd::server::{load_cli, logging::init_logging, run_with_cli, runtime::build_runtime};
+
+// Heap profiles come from jemalloc's sampling profiler, so profiling builds
+// allocate through it.
+#[cfg(feature = "profiling")]
+#[global_allocator]
+static ALLOCATOR: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 fn main() -> Result<()> {
-    let runtime = Builder::new_multi_thread()
-        .enable_all()
-        .build()
-        .context("failed to build Tokio runtime")?;
-    runtime.block_on(async { mxd::server::run().await })
+    let cli = load_cli()?;
+    init_logging(&cli.config)?;
+    let runtime = build_runtime(&cli.config).context("failed to build Tokio runtime")?;
+    runtime.block_on(run_with_cli(cli))
 }
@@ -9,6 +9,7 @@ use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::schema::{
+    bans,
     file_nodes,
     groups,
     permissions,
@@ -26,9 +27,17 @@ pub struct User {
     pub username: String,
     /// Hashed password.
     pub password: String,
+    /// Privilege bitmask; decode it with [`crate::db::decode_privileges`].
+    pub privileges: i64,
+    /// Most bytes of file content the account may store, or `None` for no
+    /// limit.
+    pub quota_bytes: Option<i64>,
 }
 
 /// Parameters for creating a new user account.
+///
+/// The account receives [`crate::privileges::Privileges::default_user`] until
+/// [`crate::db::set_privileges`] changes it.
 #[derive(Insertable, Deserialize)]
 #[diesel(table_name = crate::schema::users)]
 pub struct NewUser<'a> {
@@ -55,6 +64,8 @@ pub struct Category {
     pub delete_sn: Option<i32>,
     /// Creation timestamp.
     pub created_at: Option<NaiveDateTime>,
+    /// News root the category belongs to.
+    pub root_id: i32,
 }
 
 /// Parameters for creating a new news category.
@@ -73,6 +84,9 @@ pub struct NewCategory<'a> {
     pub delete_sn: Option<i32>,
     /// Creation timestamp.
     pub created_at: Option<NaiveDateTime>,
+    /// News root the category belongs to; must match the parent bundle's.
+    #[serde(default)]
+    pub root_id: i32,
 }
 
 /// Represents a news bundle (grouping of categories) in the database.
@@ -88,6 +102,8 @@ pub struct Bundle {
     pub guid: Option<String>,
     /// Creation timestamp.
     pub created_at: Option<NaiveDateTime>,
+    /// News root the bundle belongs to.
+    pub root_id: i32,
 }
 
 /// Parameters for creating a new news bundle.
@@ -102,6 +118,9 @@ pub struct NewBundle<'a> {
     pub guid: Option<String>,
     /// Creation timestamp.
     pub created_at: Option<NaiveDateTime>,
+    /// News root the bundle belongs to; must match the parent bundle's.
+    #[serde(default)]
+    pub root_id: i32,
 }
 
 /// Represents a news article stored in the database.
@@ -282,6 +301,10 @@ pub struct FileNode {
     pub created_at: NaiveDateTime,
     /// Timestamp when the node was last updated.
     pub updated_at: NaiveDateTime,
+    /// Classic Mac OS type code, or empty when unknown.
+    pub type_code: String,
+    /// Classic Mac OS creator code, or empty when unknown.
+    pub creator_code: String,
 }
 
 /// Parameters for inserting a new file node.
@@ -334,3 +357,35 @@ pub struct NewResourcePermission<'a> {
     /// Permission being granted on the resource.
     pub permission_id: i32,
 }
+
+/// Represents a ban list entry stored in the database.
+#[derive(Queryable, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Ban {
+    /// Unique ban identifier.
+    pub id: i32,
+    /// What the ban matches (`username` or `address`).
+    pub kind: String,
+    /// Username or canonical IP address being banned.
+    pub target: String,
+    /// Operator note explaining the ban.
+    pub reason: Option<String>,
+    /// Timestamp when the ban was recorded.
+    pub created_at: NaiveDateTime,
+    /// Timestamp after which the ban no longer applies, or `None` when it
+    /// never expires.
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+/// Parameters for recording a ban.
+#[derive(Insertable, AsChangeset)]
+#[diesel(table_name = bans, treat_none_as_null = true)]
+pub struct NewBan<'a> {
+    /// What the ban matches (`username` or `address`).
+    pub kind: &'a str,
+    /// Username or canonical IP address being banned.
+    pub target: &'a str,
+    /// Operator note explaining the ban.
+    pub reason: Option<&'a str>,
+    /// Timestamp after which the ban no longer applies.
+    pub expires_at: Option<NaiveDateTime>,
+}
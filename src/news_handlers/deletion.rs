@@ -0,0 +1,41 @@
+//! Delete News Article (411).
+
+use super::{NewsHandlerError, run_news_tx};
+use crate::{
+    db::{DbPool, NewsPath, delete_article},
+    transaction::{FrameHeader, ReplyParams, Transaction, TransactionParams},
+};
+
+/// Parameters for deleting a news article.
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
+pub struct DeleteArticleRequest {
+    #[param(NewsPath)]
+    pub(crate) path: String,
+    #[param(NewsArticleId)]
+    pub(crate) article_id: i32,
+    /// Delete the article's replies too, rather than promoting them.
+    #[param(NewsRecursiveDelete)]
+    pub(crate) recursive: bool,
+}
+
+/// Delete a news article in news root `root`, promoting or removing its
+/// replies.
+pub async fn process_delete_article(
+    pool: DbPool,
+    header: FrameHeader,
+    root: i32,
+    req: DeleteArticleRequest,
+) -> Transaction {
+    run_news_tx(pool, header, move |conn| {
+        Box::pin(async move {
+            let path = NewsPath::new(root, &req.path);
+            let removed = delete_article(conn, path, req.article_id, req.recursive)
+                .await
+                .map_err(NewsHandlerError::Path)?
+                .ok_or(NewsHandlerError::ArticleNotFound)?;
+            tracing::debug!(article_id = req.article_id, removed, "news article deleted");
+            Ok(ReplyParams::new())
+        })
+    })
+    .await
+}
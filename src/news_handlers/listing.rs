@@ -0,0 +1,153 @@
+//! Client-specific encodings for news category listings.
+//!
+//! Hotline client generations disagree on the shape of the field 323 entries
+//! returned by Get News Category Name List (370). `SynHX` and unidentified
+//! clients accept bare names, clients that predate Hotline 1.8.5 expect a
+//! single type byte ahead of each name, and Hotline 1.8.5 and later expect a
+//! two-byte type code, a two-byte item count, and a length-prefixed name. The
+//! wireframe adapter selects the variant via `ClientCompatibility` and stores
+//! it on the [`Session`](crate::handler::Session) so the domain handler can
+//! encode entries without knowing about client detection.
+//!
+//! No client family's entry has room for a post time, so a client that wants
+//! one asks with [`FieldId::NewsActivity`] (172). Each field 323 entry is then
+//! followed by a field 172 holding the entry's most recent post time, leaving
+//! the entries themselves in the layout the client expects.
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use super::{NewsHandlerError, run_news_tx};
+use crate::{
+    db::{DbPool, NewsEntryKind, NewsListingRow, NewsPath, list_names_at_path},
+    field_id::FieldId,
+    transaction::{FrameHeader, ReplyParams, Transaction},
+    wire_time::optional_timestamp_millis,
+};
+
+/// Type code identifying a bundle entry.
+const BUNDLE_TYPE: u8 = 2;
+/// Type code identifying a category entry.
+const CATEGORY_TYPE: u8 = 3;
+/// Largest name length representable by the one-byte length prefix.
+const MAX_NAME_LEN: usize = u8::MAX as usize;
+
+/// Wire encoding applied to news category listing entries.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NewsListingEncoding {
+    /// Bare UTF-8 names, one per field 323 value.
+    ///
+    /// Used for `SynHX` and for clients that have not reported a login
+    /// version, matching the behaviour of earlier releases.
+    #[default]
+    Names,
+    /// A one-byte type code followed by the UTF-8 name.
+    ///
+    /// Used for Hotline clients that report a login version below 151.
+    Hotline15,
+    /// A big-endian `u16` type code, a big-endian `u16` item count, a one-byte
+    /// name length, and the UTF-8 name.
+    ///
+    /// Bundles report their direct children and categories report their
+    /// article totals; counts above `u16::MAX` saturate.
+    ///
+    /// Used for Hotline 1.8.5 and 1.9 clients.
+    Hotline18,
+}
+
+impl NewsListingEncoding {
+    /// Encode listing rows as field 323 reply parameters, following each
+    /// with its most recent post time in field 172 when `with_activity` is
+    /// set. Bundles, and categories nobody has posted in, report zero.
+    #[must_use]
+    pub fn encode_entries(self, rows: &[NewsListingRow], with_activity: bool) -> ReplyParams {
+        rows.iter().fold(ReplyParams::new(), |reply, row| {
+            let last_posted = with_activity.then(|| optional_timestamp_millis(row.last_posted_at));
+            reply
+                .blob(FieldId::NewsCategory, self.encode_entry(row))
+                .optional(last_posted, |with_entry, millis| {
+                    with_entry.timestamp(FieldId::NewsActivity, millis)
+                })
+        })
+    }
+
+    fn encode_entry(self, row: &NewsListingRow) -> Vec<u8> {
+        match self {
+            Self::Names => row.name.as_bytes().to_vec(),
+            Self::Hotline15 => {
+                let mut entry = Vec::with_capacity(1 + row.name.len());
+                entry.push(type_byte(row.kind));
+                entry.extend_from_slice(row.name.as_bytes());
+                entry
+            }
+            Self::Hotline18 => {
+                let name = truncated_name(&row.name);
+                let name_len = u8::try_from(name.len()).unwrap_or(u8::MAX);
+                let count = u16::try_from(row.count).unwrap_or(u16::MAX);
+                let mut entry = Vec::with_capacity(5 + name.len());
+                entry.extend_from_slice(&u16::from(type_byte(row.kind)).to_be_bytes());
+                entry.extend_from_slice(&count.to_be_bytes());
+                entry.push(name_len);
+                entry.extend_from_slice(name);
+                entry
+            }
+        }
+    }
+}
+
+const fn type_byte(kind: NewsEntryKind) -> u8 {
+    match kind {
+        NewsEntryKind::Bundle => BUNDLE_TYPE,
+        NewsEntryKind::Category => CATEGORY_TYPE,
+    }
+}
+
+/// Clamp `name` to the one-byte length prefix without splitting a UTF-8
+/// sequence.
+fn truncated_name(name: &str) -> &[u8] {
+    if name.len() <= MAX_NAME_LEN {
+        return name.as_bytes();
+    }
+    let end = (0..=MAX_NAME_LEN)
+        .rev()
+        .find(|idx| name.is_char_boundary(*idx))
+        .unwrap_or(0);
+    name.as_bytes().get(..end).unwrap_or_default()
+}
+
+/// What a Get News Category Name List request asks for, and how to encode
+/// the reply.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CategoryListing {
+    /// News root the session reads.
+    pub root: i32,
+    /// Bundle to list; `None` lists the root.
+    pub path: Option<String>,
+    /// Entry layout the session's client expects.
+    pub encoding: NewsListingEncoding,
+    /// Whether the client asked for post times in field 172.
+    pub with_activity: bool,
+}
+
+/// Retrieve the bundles and categories at a path in the session's listing
+/// encoding.
+pub async fn process_category_name_list(
+    pool: DbPool,
+    header: FrameHeader,
+    listing: CategoryListing,
+) -> Transaction {
+    run_news_tx(pool, header, move |conn| {
+        Box::pin(async move {
+            let path = listing.path.as_deref().unwrap_or_default();
+            let rows = list_names_at_path(conn, NewsPath::new(listing.root, path))
+                .await
+                .map_err(NewsHandlerError::Path)?;
+            Ok(listing
+                .encoding
+                .encode_entries(&rows, listing.with_activity))
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+#[path = "listing_tests.rs"]
+mod tests;
@@ -0,0 +1,132 @@
+//! Unit tests for news category listing encodings.
+
+use chrono::DateTime;
+use rstest::{fixture, rstest};
+
+use super::*;
+
+#[fixture]
+fn rows() -> Vec<NewsListingRow> {
+    vec![
+        NewsListingRow {
+            kind: NewsEntryKind::Bundle,
+            name: "Bundle".to_owned(),
+            count: 1,
+            last_posted_at: None,
+        },
+        NewsListingRow {
+            kind: NewsEntryKind::Category,
+            name: "General".to_owned(),
+            count: 4,
+            last_posted_at: DateTime::from_timestamp(2, 0).map(|posted| posted.naive_utc()),
+        },
+    ]
+}
+
+#[rstest]
+#[case::names(NewsListingEncoding::Names, &[b"Bundle".as_slice(), b"General".as_slice()])]
+#[case::hotline_15(
+    NewsListingEncoding::Hotline15,
+    &[b"\x02Bundle".as_slice(), b"\x03General".as_slice()]
+)]
+#[case::hotline_18(
+    NewsListingEncoding::Hotline18,
+    &[
+        b"\x00\x02\x00\x01\x06Bundle".as_slice(),
+        b"\x00\x03\x00\x04\x07General".as_slice(),
+    ]
+)]
+fn encodes_entries_per_client_family(
+    rows: Vec<NewsListingRow>,
+    #[case] encoding: NewsListingEncoding,
+    #[case] expected: &[&[u8]],
+) {
+    let params = encoding.encode_entries(&rows, false);
+
+    let fields: Vec<FieldId> = params.as_slice().iter().map(|(field, _)| *field).collect();
+    assert_eq!(fields, vec![FieldId::NewsCategory; 2]);
+    let values: Vec<&[u8]> = params
+        .as_slice()
+        .iter()
+        .map(|(_, value)| value.as_slice())
+        .collect();
+    assert_eq!(values, expected);
+}
+
+#[rstest]
+fn hotline_18_truncates_long_names_on_char_boundary() {
+    let name = format!("{}é", "a".repeat(254));
+    let row = NewsListingRow {
+        kind: NewsEntryKind::Category,
+        name,
+        count: 0,
+        last_posted_at: None,
+    };
+
+    let params = NewsListingEncoding::Hotline18.encode_entries(&[row], false);
+
+    let Some((_, entry)) = params.as_slice().first() else {
+        panic!("expected one encoded entry");
+    };
+    assert_eq!(entry.get(4), Some(&254));
+    assert_eq!(entry.len(), 5 + 254);
+}
+
+#[rstest]
+fn hotline_18_saturates_large_counts() {
+    let row = NewsListingRow {
+        kind: NewsEntryKind::Category,
+        name: "Busy".to_owned(),
+        count: 70_000,
+        last_posted_at: None,
+    };
+
+    let params = NewsListingEncoding::Hotline18.encode_entries(&[row], false);
+
+    let Some((_, entry)) = params.as_slice().first() else {
+        panic!("expected one encoded entry");
+    };
+    assert_eq!(entry.get(2..4), Some([0xff, 0xff].as_slice()));
+}
+
+#[rstest]
+#[case::names(NewsListingEncoding::Names)]
+#[case::hotline_15(NewsListingEncoding::Hotline15)]
+#[case::hotline_18(NewsListingEncoding::Hotline18)]
+fn activity_follows_each_entry_when_asked(
+    rows: Vec<NewsListingRow>,
+    #[case] encoding: NewsListingEncoding,
+) {
+    let plain = encoding.encode_entries(&rows, false);
+    let params = encoding.encode_entries(&rows, true);
+
+    let fields: Vec<FieldId> = params.as_slice().iter().map(|(field, _)| *field).collect();
+    assert_eq!(
+        fields,
+        vec![
+            FieldId::NewsCategory,
+            FieldId::NewsActivity,
+            FieldId::NewsCategory,
+            FieldId::NewsActivity,
+        ]
+    );
+    let entries: Vec<&Vec<u8>> = params
+        .as_slice()
+        .iter()
+        .step_by(2)
+        .map(|(_, value)| value)
+        .collect();
+    let plain_entries: Vec<&Vec<u8>> = plain.as_slice().iter().map(|(_, value)| value).collect();
+    assert_eq!(entries, plain_entries);
+    let times: Vec<&[u8]> = params
+        .as_slice()
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .map(|(_, value)| value.as_slice())
+        .collect();
+    assert_eq!(
+        times,
+        vec![[0u8; 8].as_slice(), 2_000i64.to_be_bytes().as_slice()]
+    );
+}
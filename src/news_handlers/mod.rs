@@ -2,70 +2,86 @@
 //!
 //! These helpers keep news-related transactions and database access logic
 //! grouped together for reuse by command processing.
-#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use std::{sync::Arc, time::Instant};
 
 use futures_util::future::BoxFuture;
-use tracing::error;
 
 use crate::{
-    commands::{
-        CommandError,
-        ERR_INTERNAL_SERVER,
-        NEWS_ERR_ARTICLE_NOT_FOUND,
-        NEWS_ERR_PATH_UNSUPPORTED,
-        check_privilege_and_run,
-    },
     db::{
         CreateRootArticleParams,
         DbConnection,
         DbPool,
+        NewsPath,
         PathLookupError,
+        create_reply_article,
         create_root_article,
         get_article,
         list_article_titles,
-        list_names_at_path,
     },
     field_id::FieldId,
-    handler::Session,
-    header_util::reply_header,
     models::Article,
-    privileges::Privileges,
-    transaction::{FrameHeader, Transaction, encode_params},
+    server::{
+        news_transfer::transfers_body,
+        transfer_port::{PendingTransfer, TransferRegistry},
+    },
+    transaction::{FrameHeader, ReplyParams, Transaction, TransactionParams},
+    wire_time::timestamp_millis,
 };
 
-/// Macro to reduce boilerplate in privilege-checked news handlers.
-///
-/// Wraps a handler invocation with privilege checking, automatically cloning
-/// the header and handling the async closure boilerplate required by
-/// `check_privilege_and_run`.
-macro_rules! with_privilege_check {
-    ($pool:expr, $session:expr, $header:expr, $privilege:expr, $handler:expr $(, $args:expr)*) => {{
-        let reply_header = $header.clone();
-        let pool = $pool;
-        check_privilege_and_run(
-            $session,
-            &$header,
-            $privilege,
-            || async move { Ok($handler(pool, reply_header, $($args),*).await) },
-        )
-        .await
-    }};
-}
+mod deletion;
+mod listing;
+mod reply;
+mod structure;
+
+pub use deletion::{DeleteArticleRequest, process_delete_article};
+pub use listing::{CategoryListing, NewsListingEncoding, process_category_name_list};
+use reply::{NewsHandlerError, run_news_tx};
+pub use structure::{NewsStructureRequest, process_news_structure};
 
 /// Parameters for retrieving a news article's data.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
 pub struct ArticleDataRequest {
+    #[param(NewsPath)]
     pub(crate) path: String,
+    #[param(NewsArticleId)]
     pub(crate) article_id: i32,
-}
-
-/// Parameters for posting a new news article.
-#[derive(Debug, PartialEq, Eq)]
+    /// Whether the client can fetch a long body from the transfer port.
+    #[param(NewsDataTransfer)]
+    pub(crate) accepts_transfer: bool,
+}
+
+/// Which article a Get News Article Data request reads, and where a long
+/// body goes in place of the reply.
+#[derive(Debug)]
+pub struct ArticleDataQuery {
+    /// News root the session reads.
+    pub root: i32,
+    /// The request as the client sent it.
+    pub req: ArticleDataRequest,
+    /// Registry a long body is filed with.
+    pub transfers: Arc<TransferRegistry>,
+    /// Longest body sent inline to clients that accept transfers, or `None`
+    /// to send every body inline.
+    pub threshold: Option<u32>,
+}
+
+/// Parameters for posting a new news article or a reply to one.
+#[derive(Debug, PartialEq, Eq, TransactionParams)]
 pub struct PostArticleRequest {
+    #[param(NewsPath)]
     pub(crate) path: String,
+    /// Article being replied to; `None` posts a new thread. Clients send
+    /// parent id 0, or omit field 326, for a new thread.
+    #[param(NewsArticleId, omit_blank)]
+    pub(crate) parent_id: Option<i32>,
+    #[param(NewsTitle)]
     pub(crate) title: String,
+    #[param(NewsArticleFlags, default)]
     pub(crate) flags: i32,
+    #[param(NewsDataFlavor)]
     pub(crate) data_flavor: String,
+    #[param(NewsArticleData)]
     pub(crate) data: String,
 }
 
@@ -84,107 +100,15 @@ impl PostArticleRequest {
     }
 }
 
-enum NewsHandlerError {
-    Path(PathLookupError),
-    ArticleNotFound,
-}
-
-/// Handle news category listing commands after privilege checks.
-///
-/// # Errors
-/// Returns an error if privilege checks or database operations fail.
-pub async fn process_category_name_list(
-    pool: DbPool,
-    session: &Session,
-    header: FrameHeader,
-    path: Option<String>,
-) -> Result<Transaction, CommandError> {
-    with_privilege_check!(
-        pool,
-        session,
-        header,
-        Privileges::NEWS_READ_ARTICLE,
-        handle_category_list,
-        path
-    )
-}
-
-/// Handle news article title listing commands after privilege checks.
-///
-/// # Errors
-/// Returns an error if privilege checks or database operations fail.
+/// Retrieve the titles of articles in a category of news root `root`.
 pub async fn process_article_name_list(
     pool: DbPool,
-    session: &Session,
     header: FrameHeader,
+    root: i32,
     path: String,
-) -> Result<Transaction, CommandError> {
-    with_privilege_check!(
-        pool,
-        session,
-        header,
-        Privileges::NEWS_READ_ARTICLE,
-        handle_article_titles,
-        path
-    )
-}
-
-/// Handle news article data commands after privilege checks.
-///
-/// # Errors
-/// Returns an error if privilege checks or database operations fail.
-pub async fn process_article_data(
-    pool: DbPool,
-    session: &Session,
-    header: FrameHeader,
-    req: ArticleDataRequest,
-) -> Result<Transaction, CommandError> {
-    with_privilege_check!(
-        pool,
-        session,
-        header,
-        Privileges::NEWS_READ_ARTICLE,
-        handle_article_data,
-        req
-    )
-}
-
-/// Handle news article creation commands after privilege checks.
-///
-/// # Errors
-/// Returns an error if privilege checks or database operations fail.
-pub async fn process_post_article(
-    pool: DbPool,
-    session: &Session,
-    header: FrameHeader,
-    req: PostArticleRequest,
-) -> Result<Transaction, CommandError> {
-    with_privilege_check!(
-        pool,
-        session,
-        header,
-        Privileges::NEWS_POST_ARTICLE,
-        handle_post_article,
-        req
-    )
-}
-
-/// Retrieve the list of category names for a given news path.
-async fn handle_category_list(
-    pool: DbPool,
-    header: FrameHeader,
-    path: Option<String>,
 ) -> Transaction {
-    handle_list(pool, header, FieldId::NewsCategory, move |conn| {
-        Box::pin(async move { list_names_at_path(conn, path.as_deref()).await })
-    })
-    .await
-}
-
-/// Retrieve the titles of articles in a news category.
-async fn handle_article_titles(pool: DbPool, header: FrameHeader, path: String) -> Transaction {
     handle_list(pool, header, FieldId::NewsArticle, move |conn| {
-        Box::pin(async move { list_article_titles(conn, &path).await })
+        Box::pin(async move { list_article_titles(conn, NewsPath::new(root, &path)).await })
     })
     .await
 }
@@ -198,203 +122,119 @@ where
     run_news_tx(pool, header, move |conn| {
         Box::pin(async move {
             let names = fetch(conn).await.map_err(NewsHandlerError::Path)?;
-            let params = names
+            Ok(names
                 .into_iter()
-                .map(|name| (field, name.into_bytes()))
-                .collect();
-            Ok(params)
+                .fold(ReplyParams::new(), |reply, name| reply.string(field, &name)))
         })
     })
     .await
 }
 
-/// Retrieve a specific news article's data.
-async fn handle_article_data(
+/// Retrieve a specific news article's data from the query's news root.
+///
+/// When the client accepts it and the body is longer than the query's
+/// threshold, the body is filed with its transfer registry and the reply
+/// carries its reference number (107) and size (108) in place of the data.
+pub async fn process_article_data(
     pool: DbPool,
     header: FrameHeader,
-    req: ArticleDataRequest,
+    query: ArticleDataQuery,
 ) -> Transaction {
+    let ArticleDataQuery {
+        root,
+        req,
+        transfers,
+        threshold,
+    } = query;
+    let transfer_pool = pool.clone();
     run_news_tx(pool, header, move |conn| {
         Box::pin(async move {
-            let maybe_article = get_article(conn, &req.path, req.article_id)
+            let path = NewsPath::new(root, &req.path);
+            let maybe_article = get_article(conn, path, req.article_id)
                 .await
                 .map_err(NewsHandlerError::Path)?;
             let Some(found_article) = maybe_article else {
                 return Err(NewsHandlerError::ArticleNotFound);
             };
-            Ok(article_to_params(&found_article))
+            let reply = article_header_params(&found_article);
+            let data = found_article.data.as_deref();
+            let transfer_size = data
+                .map(str::len)
+                .filter(|&len| req.accepts_transfer && transfers_body(threshold, len))
+                .and_then(|len| u32::try_from(len).ok());
+            Ok(match transfer_size {
+                Some(size) => {
+                    let reference = transfers.register(PendingTransfer::article(
+                        transfer_pool,
+                        found_article.id,
+                        Instant::now(),
+                    ));
+                    reply
+                        .u32(FieldId::ReferenceNumber, reference)
+                        .u32(FieldId::TransferSize, size)
+                }
+                None => reply.optional(data, |params, body| {
+                    params.string(FieldId::NewsArticleData, body)
+                }),
+            })
         })
     })
     .await
 }
 
-/// Create a new root article under the provided path.
-async fn handle_post_article(
+/// Create a new root article, or a reply when a parent is given, under the
+/// provided path in news root `root`.
+pub async fn process_post_article(
     pool: DbPool,
     header: FrameHeader,
+    root: i32,
     req: PostArticleRequest,
 ) -> Transaction {
     run_news_tx(pool, header, move |conn| {
         Box::pin(async move {
-            let id = create_root_article(conn, &req.path, req.to_db_params())
-                .await
-                .map_err(NewsHandlerError::Path)?;
-            Ok(vec![(FieldId::NewsArticleId, id.to_be_bytes().to_vec())])
+            let params = req.to_db_params();
+            let path = NewsPath::new(root, &req.path);
+            let id = match req.parent_id {
+                Some(parent_id) => create_reply_article(conn, path, parent_id, params)
+                    .await
+                    .map_err(NewsHandlerError::Path)?
+                    .ok_or(NewsHandlerError::ArticleNotFound)?,
+                None => create_root_article(conn, path, params)
+                    .await
+                    .map_err(NewsHandlerError::Path)?,
+            };
+            Ok(ReplyParams::new().i32(FieldId::NewsArticleId, id))
         })
     })
     .await
 }
 
-/// Push an optional i32 field as big-endian bytes if present.
-fn push_optional_i32(params: &mut Vec<(FieldId, Vec<u8>)>, field: FieldId, value: Option<i32>) {
-    if let Some(v) = value {
-        params.push((field, v.to_be_bytes().to_vec()));
-    }
-}
-
-/// Push an optional string field as bytes if present.
-fn push_optional_str(params: &mut Vec<(FieldId, Vec<u8>)>, field: FieldId, value: Option<&str>) {
-    if let Some(s) = value {
-        params.push((field, s.as_bytes().to_vec()));
-    }
-}
-
-fn article_to_params(article: &Article) -> Vec<(FieldId, Vec<u8>)> {
-    let mut params: Vec<(FieldId, Vec<u8>)> = Vec::new();
-    params.push((FieldId::NewsTitle, article.title.as_bytes().to_vec()));
-    push_optional_str(&mut params, FieldId::NewsPoster, article.poster.as_deref());
-    params.push((
-        FieldId::NewsDate,
-        article
-            .posted_at
-            .and_utc()
-            .timestamp_millis()
-            .to_be_bytes()
-            .to_vec(),
-    ));
-    push_optional_i32(&mut params, FieldId::NewsPrevId, article.prev_article_id);
-    push_optional_i32(&mut params, FieldId::NewsNextId, article.next_article_id);
-    push_optional_i32(
-        &mut params,
-        FieldId::NewsParentId,
-        article.parent_article_id,
-    );
-    push_optional_i32(
-        &mut params,
-        FieldId::NewsFirstChildId,
-        article.first_child_article_id,
-    );
-    params.push((
-        FieldId::NewsArticleFlags,
-        article.flags.to_be_bytes().to_vec(),
-    ));
-    params.push((
-        FieldId::NewsDataFlavor,
-        article
-            .data_flavor
-            .as_deref()
-            .unwrap_or("text/plain")
-            .as_bytes()
-            .to_vec(),
-    ));
-    push_optional_str(
-        &mut params,
-        FieldId::NewsArticleData,
-        article.data.as_deref(),
-    );
-    params
-}
-
-/// Helper to execute a news database operation and build a reply transaction.
-async fn run_news_tx<F>(pool: DbPool, header: FrameHeader, op: F) -> Transaction
-where
-    for<'c> F: FnOnce(
-            &'c mut DbConnection,
-        ) -> BoxFuture<'c, Result<Vec<(FieldId, Vec<u8>)>, NewsHandlerError>>
-        + Send
-        + 'static,
-{
-    let result = match pool.get().await {
-        Ok(mut conn) => op(&mut conn).await,
-        Err(err) => return pool_error_reply(&header, err),
-    };
-    handle_news_result(&header, result)
-}
-
-fn handle_news_result(
-    header: &FrameHeader,
-    result: Result<Vec<(FieldId, Vec<u8>)>, NewsHandlerError>,
-) -> Transaction {
-    match result {
-        Ok(params) => encode_reply(header, &params),
-        Err(err) => news_error_reply(header, err),
-    }
-}
-
-fn pool_error_reply<E: std::fmt::Display>(header: &FrameHeader, err: E) -> Transaction {
-    error!(%err, "failed to get database connection");
-    internal_error_reply(header)
-}
-
-fn encode_reply(header: &FrameHeader, params: &[(FieldId, Vec<u8>)]) -> Transaction {
-    match encode_params(params) {
-        Ok(payload) => Transaction {
-            header: reply_header(header, 0, payload.len()),
-            payload,
-        },
-        Err(e) => {
-            error!(%e, "failed to encode news reply");
-            internal_error_reply(header)
-        }
-    }
-}
-
-fn news_error_reply(header: &FrameHeader, err: NewsHandlerError) -> Transaction {
-    match err {
-        NewsHandlerError::ArticleNotFound => article_not_found_reply(header),
-        NewsHandlerError::Path(path_err) => path_error_reply(header, path_err),
-    }
-}
-
-fn article_not_found_reply(header: &FrameHeader) -> Transaction {
-    Transaction {
-        header: reply_header(header, NEWS_ERR_ARTICLE_NOT_FOUND, 0),
-        payload: Vec::new(),
-    }
-}
-
-fn path_error_reply(header: &FrameHeader, err: PathLookupError) -> Transaction {
-    match err {
-        PathLookupError::InvalidPath => {
-            tracing::debug!("invalid news path requested");
-            unsupported_path_reply(header)
-        }
-        PathLookupError::Diesel(e) => logged_internal_error(header, "database error", e),
-        PathLookupError::Serde(e) => logged_internal_error(header, "serialization error", e),
-    }
-}
-
-fn unsupported_path_reply(header: &FrameHeader) -> Transaction {
-    Transaction {
-        header: reply_header(header, NEWS_ERR_PATH_UNSUPPORTED, 0),
-        payload: Vec::new(),
-    }
-}
-
-fn logged_internal_error<E: std::fmt::Display>(
-    header: &FrameHeader,
-    context: &str,
-    err: E,
-) -> Transaction {
-    error!(%err, context, "news handler error");
-    internal_error_reply(header)
+/// Encode everything about `article` except its body.
+fn article_header_params(article: &Article) -> ReplyParams {
+    ReplyParams::new()
+        .string(FieldId::NewsTitle, &article.title)
+        .optional(article.poster.as_deref(), |reply, poster| {
+            reply.string(FieldId::NewsPoster, poster)
+        })
+        .timestamp(FieldId::NewsDate, timestamp_millis(article.posted_at))
+        .optional(article.prev_article_id, |reply, id| {
+            reply.i32(FieldId::NewsPrevId, id)
+        })
+        .optional(article.next_article_id, |reply, id| {
+            reply.i32(FieldId::NewsNextId, id)
+        })
+        .optional(article.parent_article_id, |reply, id| {
+            reply.i32(FieldId::NewsParentId, id)
+        })
+        .optional(article.first_child_article_id, |reply, id| {
+            reply.i32(FieldId::NewsFirstChildId, id)
+        })
+        .i32(FieldId::NewsArticleFlags, article.flags)
+        .string(
+            FieldId::NewsDataFlavor,
+            article.data_flavor.as_deref().unwrap_or("text/plain"),
+        )
 }
 
-fn internal_error_reply(header: &FrameHeader) -> Transaction {
-    Transaction {
-        header: reply_header(header, ERR_INTERNAL_SERVER, 0),
-        payload: Vec::new(),
-    }
-}
 #[cfg(test)]
 mod tests;
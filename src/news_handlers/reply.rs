@@ -0,0 +1,119 @@
+//! Reply construction and error mapping shared by the news handlers.
+
+use futures_util::future::BoxFuture;
+use tracing::error;
+
+use crate::{
+    commands::{
+        ERR_INSUFFICIENT_PRIVILEGES,
+        ERR_INTERNAL_SERVER,
+        NEWS_ERR_ARTICLE_NOT_FOUND,
+        NEWS_ERR_NAME_TAKEN,
+        NEWS_ERR_PATH_NOT_FOUND,
+        NEWS_ERR_PATH_UNSUPPORTED,
+    },
+    db::{DbConnection, DbPool, PathLookupError, acquire},
+    header_util::reply_header,
+    transaction::{FrameHeader, ReplyParams, Transaction},
+};
+
+/// Failures a news handler turns into an error reply.
+pub(super) enum NewsHandlerError {
+    /// The path was malformed or missing, or a query failed.
+    Path(PathLookupError),
+    /// No article with the requested id exists in the category.
+    ArticleNotFound,
+    /// A bundle or category name cannot be used as a path segment.
+    InvalidName,
+    /// The parent bundle already holds an item with the requested name.
+    NameTaken,
+    /// The session lacks the privilege the target item requires.
+    Forbidden,
+}
+
+/// Helper to execute a news database operation and build a reply transaction.
+pub(super) async fn run_news_tx<F>(pool: DbPool, header: FrameHeader, op: F) -> Transaction
+where
+    for<'c> F: FnOnce(&'c mut DbConnection) -> BoxFuture<'c, Result<ReplyParams, NewsHandlerError>>
+        + Send
+        + 'static,
+{
+    let result = match acquire(&pool, header.ty).await {
+        Ok(mut conn) => op(&mut conn).await,
+        Err(err) => return pool_error_reply(&header, err),
+    };
+    handle_news_result(&header, result)
+}
+
+fn handle_news_result(
+    header: &FrameHeader,
+    result: Result<ReplyParams, NewsHandlerError>,
+) -> Transaction {
+    match result {
+        Ok(params) => encode_reply(header, params),
+        Err(err) => news_error_reply(header, err),
+    }
+}
+
+fn pool_error_reply<E: std::fmt::Display>(header: &FrameHeader, err: E) -> Transaction {
+    error!(%err, "failed to get database connection");
+    internal_error_reply(header)
+}
+
+fn encode_reply(header: &FrameHeader, params: ReplyParams) -> Transaction {
+    match params.encode() {
+        Ok(payload) => Transaction {
+            header: reply_header(header, 0, payload.len()),
+            payload,
+        },
+        Err(e) => {
+            error!(%e, "failed to encode news reply");
+            internal_error_reply(header)
+        }
+    }
+}
+
+fn news_error_reply(header: &FrameHeader, err: NewsHandlerError) -> Transaction {
+    match err {
+        NewsHandlerError::ArticleNotFound => error_reply(header, NEWS_ERR_ARTICLE_NOT_FOUND),
+        NewsHandlerError::InvalidName => error_reply(header, NEWS_ERR_PATH_UNSUPPORTED),
+        NewsHandlerError::NameTaken => error_reply(header, NEWS_ERR_NAME_TAKEN),
+        NewsHandlerError::Forbidden => error_reply(header, ERR_INSUFFICIENT_PRIVILEGES),
+        NewsHandlerError::Path(path_err) => path_error_reply(header, path_err),
+    }
+}
+
+pub(super) fn path_error_reply(header: &FrameHeader, err: PathLookupError) -> Transaction {
+    match err {
+        PathLookupError::InvalidPath => {
+            tracing::debug!("malformed news path requested");
+            error_reply(header, NEWS_ERR_PATH_UNSUPPORTED)
+        }
+        PathLookupError::NotFound => {
+            tracing::debug!("news path not found");
+            error_reply(header, NEWS_ERR_PATH_NOT_FOUND)
+        }
+        PathLookupError::Diesel(e) => logged_internal_error(header, "database error", e),
+        PathLookupError::Serde(e) => logged_internal_error(header, "serialization error", e),
+    }
+}
+
+fn error_reply(header: &FrameHeader, code: u32) -> Transaction {
+    Transaction {
+        header: reply_header(header, code, 0),
+        payload: Vec::new(),
+    }
+}
+
+fn logged_internal_error<E: std::fmt::Display>(
+    header: &FrameHeader,
+    context: &str,
+    err: E,
+) -> Transaction {
+    error!(%err, context, "news handler error");
+    internal_error_reply(header)
+}
+
+fn internal_error_reply(header: &FrameHeader) -> Transaction {
+    error_reply(header, ERR_INTERNAL_SERVER)
+}
@@ -0,0 +1,163 @@
+//! Delete News Item (380), New News Folder (381), and New News Category (382).
+
+use super::{NewsHandlerError, run_news_tx};
+use crate::{
+    db::{
+        DbConnection,
+        DbPool,
+        NewsEntryKind,
+        NewsItem,
+        NewsPath,
+        NewsStructureError,
+        create_news_bundle,
+        create_news_category,
+        delete_news_item,
+        find_news_item,
+    },
+    handler::Session,
+    privileges::Privileges,
+    transaction::{FrameHeader, ReplyParams, Transaction},
+};
+
+/// Parameters for changing the shape of the news hierarchy.
+#[derive(Debug, PartialEq, Eq)]
+pub enum NewsStructureRequest {
+    /// Create a bundle inside `parent`, or at the top level when it is absent.
+    NewFolder {
+        /// Path of the parent bundle.
+        parent: Option<String>,
+        /// Name of the new bundle.
+        name: String,
+    },
+    /// Create a category inside `parent`, or at the top level when it is absent.
+    NewCategory {
+        /// Path of the parent bundle.
+        parent: Option<String>,
+        /// Name of the new category.
+        name: String,
+    },
+    /// Delete the bundle or category at `path` and everything beneath it.
+    DeleteItem {
+        /// Path of the item to delete.
+        path: String,
+    },
+}
+
+/// Handle news structure commands once the dispatcher has checked access.
+///
+/// The dispatcher checks New News Folder or New News Category before a
+/// create. Deleting needs Delete News Folder or Delete News Category
+/// depending on what the path names, so that check runs once the item has
+/// been looked up.
+pub async fn process_news_structure(
+    pool: DbPool,
+    session: &Session,
+    header: FrameHeader,
+    req: NewsStructureRequest,
+) -> Transaction {
+    match req {
+        NewsStructureRequest::NewFolder { parent, name } => {
+            let item = NewItem {
+                kind: NewsEntryKind::Bundle,
+                root: session.news_root,
+                parent,
+                name,
+            };
+            handle_create(pool, header, item).await
+        }
+        NewsStructureRequest::NewCategory { parent, name } => {
+            let item = NewItem {
+                kind: NewsEntryKind::Category,
+                root: session.news_root,
+                parent,
+                name,
+            };
+            handle_create(pool, header, item).await
+        }
+        NewsStructureRequest::DeleteItem { path } => {
+            handle_delete(pool, header, session, path).await
+        }
+    }
+}
+
+/// A bundle or category to create under the session's news root.
+struct NewItem {
+    kind: NewsEntryKind,
+    root: i32,
+    /// Path of the parent bundle; `None` for the top level.
+    parent: Option<String>,
+    name: String,
+}
+
+async fn handle_create(pool: DbPool, header: FrameHeader, item: NewItem) -> Transaction {
+    let NewItem {
+        kind,
+        root,
+        parent,
+        name,
+    } = item;
+    run_news_tx(pool, header, move |conn| {
+        Box::pin(async move {
+            let target = NewsPath::new(root, parent.as_deref().unwrap_or_default());
+            let id = create_item(conn, kind, target, &name)
+                .await
+                .map_err(structure_error)?;
+            tracing::debug!(?kind, id, %name, "news item created");
+            Ok(ReplyParams::new())
+        })
+    })
+    .await
+}
+
+async fn create_item(
+    conn: &mut DbConnection,
+    kind: NewsEntryKind,
+    parent: NewsPath<'_>,
+    name: &str,
+) -> Result<i32, NewsStructureError> {
+    match kind {
+        NewsEntryKind::Bundle => create_news_bundle(conn, parent, name).await,
+        NewsEntryKind::Category => create_news_category(conn, parent, name).await,
+    }
+}
+
+async fn handle_delete(
+    pool: DbPool,
+    header: FrameHeader,
+    session: &Session,
+    path: String,
+) -> Transaction {
+    let root = session.news_root;
+    let granted = session.privileges;
+    run_news_tx(pool, header, move |conn| {
+        Box::pin(async move {
+            let item = find_news_item(conn, NewsPath::new(root, &path))
+                .await
+                .map_err(NewsHandlerError::Path)?;
+            if !granted.contains(delete_privilege(item)) {
+                return Err(NewsHandlerError::Forbidden);
+            }
+            let removed = delete_news_item(conn, item)
+                .await
+                .map_err(|err| NewsHandlerError::Path(err.into()))?;
+            tracing::debug!(?item, ?removed, "news item deleted");
+            Ok(ReplyParams::new())
+        })
+    })
+    .await
+}
+
+const fn delete_privilege(item: NewsItem) -> Privileges {
+    match item {
+        NewsItem::Bundle(_) => Privileges::NEWS_DELETE_FOLDER,
+        NewsItem::Category(_) => Privileges::NEWS_DELETE_CATEGORY,
+    }
+}
+
+fn structure_error(err: NewsStructureError) -> NewsHandlerError {
+    match err {
+        NewsStructureError::InvalidName => NewsHandlerError::InvalidName,
+        NewsStructureError::NameTaken => NewsHandlerError::NameTaken,
+        NewsStructureError::Path(path_err) => NewsHandlerError::Path(path_err),
+    }
+}
@@ -1,14 +1,16 @@
 //! Unit tests for news handler helpers.
 
-use rstest::fixture;
+use rstest::{fixture, rstest};
 
-use super::*;
+use super::{reply::path_error_reply, *};
+use crate::commands::{NEWS_ERR_PATH_NOT_FOUND, NEWS_ERR_PATH_UNSUPPORTED};
 
 /// Returns a `PostArticleRequest` with sensible default values for testing.
 #[fixture]
 fn default_post_article_request() -> PostArticleRequest {
     PostArticleRequest {
         path: "/news".to_string(),
+        parent_id: None,
         title: "Test Article".to_string(),
         flags: 0,
         data_flavor: "text/plain".to_string(),
@@ -70,3 +72,21 @@ fn post_article_request_to_db_params_excludes_path() {
     assert_eq!(params.data_flavor, "text/plain");
     assert_eq!(params.data, "Test content");
 }
+
+#[rstest]
+#[case::malformed(PathLookupError::InvalidPath, NEWS_ERR_PATH_UNSUPPORTED)]
+#[case::missing(PathLookupError::NotFound, NEWS_ERR_PATH_NOT_FOUND)]
+fn path_errors_map_to_distinct_codes(#[case] err: PathLookupError, #[case] expected: u32) {
+    let header = FrameHeader {
+        flags: 0,
+        is_reply: 0,
+        ty: 370,
+        id: 7,
+        error: 0,
+        total_size: 0,
+        data_size: 0,
+    };
+    let reply = path_error_reply(&header, err);
+    assert_eq!(reply.header.error, expected);
+    assert_eq!(reply.header.id, 7);
+}
@@ -9,7 +9,9 @@
 // The `$jt` argument specifies the join type (`"JOIN"` or `"LEFT JOIN"`) used
 // when linking the current tree node to the `news_bundles` table. The resulting
 // SQL selects the next bundle in the path by comparing the segment at
-// `tree.idx` to the bundle name.
+// `tree.idx` to the bundle name. Top-level bundles are further restricted to
+// the news root bound after the path segments; nested bundles share their
+// parent's root.
 cfg_if::cfg_if! {
     if #[cfg(feature = "postgres")] {
         macro_rules! step_sql {
@@ -21,16 +23,17 @@ cfg_if::cfg_if! {
                     "  ON seg.idx::int = tree.idx + 1\n",
                     $jt,
                     " news_bundles b ON b.name = seg.value AND\n  ((tree.id IS NULL AND \
-                     b.parent_bundle_id IS NULL) OR b.parent_bundle_id = tree.id::int)"
+                     b.parent_bundle_id IS NULL AND b.root_id = $2) OR b.parent_bundle_id = \
+                     tree.id::int)"
                 )
             };
         }
 
-        pub(crate) const BUNDLE_BODY_SQL: &str = "SELECT id FROM tree WHERE idx = $2";
+        pub(crate) const BUNDLE_BODY_SQL: &str = "SELECT id FROM tree WHERE idx = $3";
         pub(crate) const CATEGORY_BODY_SQL: &str = concat!(
             "SELECT c.id AS id \n",
             "FROM news_categories c \n",
-            "WHERE c.name = $2 AND c.bundle_id IS NOT DISTINCT FROM (SELECT id FROM tree WHERE idx = $3)"
+            "WHERE c.name = $3 AND c.root_id = $4 AND c.bundle_id IS NOT DISTINCT FROM (SELECT id FROM tree WHERE idx = $5)"
         );
     } else {
         macro_rules! step_sql {
@@ -41,7 +44,7 @@ cfg_if::cfg_if! {
                     "JOIN json_each(?) seg ON seg.key = tree.idx\n",
                     $jt,
                     " news_bundles b ON b.name = seg.value AND\n  ((tree.id IS NULL AND \
-                     b.parent_bundle_id IS NULL) OR b.parent_bundle_id = tree.id)"
+                     b.parent_bundle_id IS NULL AND b.root_id = ?) OR b.parent_bundle_id = tree.id)"
                 )
             };
         }
@@ -50,7 +53,7 @@ cfg_if::cfg_if! {
         pub(crate) const CATEGORY_BODY_SQL: &str = concat!(
             "SELECT c.id AS id \n",
             "FROM news_categories c \n",
-            "WHERE c.name = ? AND c.bundle_id IS (SELECT id FROM tree WHERE idx = ?)"
+            "WHERE c.name = ? AND c.root_id = ? AND c.bundle_id IS (SELECT id FROM tree WHERE idx = ?)"
         );
     }
 }
@@ -136,16 +139,18 @@ FROM tree
 JOIN json_array_elements_text({source}::json) WITH ORDINALITY seg(value, idx)
   ON seg.idx::int = tree.idx + 1
 {join_type} news_bundles b ON b.name = seg.value AND
-  ((tree.id IS NULL AND b.parent_bundle_id IS NULL) OR b.parent_bundle_id = tree.id::int)";
+  ((tree.id IS NULL AND b.parent_bundle_id IS NULL AND b.root_id = {root}) OR b.parent_bundle_id = tree.id::int)";
             sql.replace("{source}", "$1")
+                .replace("{root}", "$2")
                 .replace("{join_type}", join_type)
         } else {
             let sql = r"SELECT tree.idx + 1 AS idx, b.id AS id
 FROM tree
 JOIN json_each({source}) seg ON seg.key = tree.idx
 {join_type} news_bundles b ON b.name = seg.value AND
-  ((tree.id IS NULL AND b.parent_bundle_id IS NULL) OR b.parent_bundle_id = tree.id)";
+  ((tree.id IS NULL AND b.parent_bundle_id IS NULL AND b.root_id = {root}) OR b.parent_bundle_id = tree.id)";
             sql.replace("{source}", "?")
+                .replace("{root}", "?")
                 .replace("{join_type}", join_type)
         }
     }
@@ -177,7 +182,7 @@ JOIN json_each({source}) seg ON seg.key = tree.idx
     #[test]
     fn bundle_body_sql_matches_expected() {
         let expected = if cfg!(feature = "postgres") {
-            "SELECT id FROM tree WHERE idx = $2"
+            "SELECT id FROM tree WHERE idx = $3"
         } else {
             "SELECT id FROM tree WHERE idx = ?"
         };
@@ -187,11 +192,11 @@ JOIN json_each({source}) seg ON seg.key = tree.idx
     #[test]
     fn category_body_sql_matches_expected() {
         let expected = if cfg!(feature = "postgres") {
-            "SELECT c.id AS id \nFROM news_categories c \nWHERE c.name = $2 AND c.bundle_id IS NOT \
-             DISTINCT FROM (SELECT id FROM tree WHERE idx = $3)"
+            "SELECT c.id AS id \nFROM news_categories c \nWHERE c.name = $3 AND c.root_id = $4 AND \
+             c.bundle_id IS NOT DISTINCT FROM (SELECT id FROM tree WHERE idx = $5)"
         } else {
-            "SELECT c.id AS id \nFROM news_categories c \nWHERE c.name = ? AND c.bundle_id IS \
-             (SELECT id FROM tree WHERE idx = ?)"
+            "SELECT c.id AS id \nFROM news_categories c \nWHERE c.name = ? AND c.root_id = ? AND \
+             c.bundle_id IS (SELECT id FROM tree WHERE idx = ?)"
         };
         assert_eq!(CATEGORY_BODY_SQL, expected);
     }
@@ -0,0 +1,459 @@
+//! Runtime presence state and payload helpers.
+//!
+//! This module keeps the server's online-user view in one place so handlers can
+//! build consistent `300`, `301`, `302`, and `303` transactions without
+//! re-encoding presence state in multiple branches.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::Notify;
+
+use crate::{
+    server::{
+        accounting::{AccountingQueue, SessionRecord},
+        idle::ActivityClock,
+        outbound::OutboundConnectionId,
+        ping::PingTracker,
+        transfer_stats::TransferTally,
+    },
+    transaction::TransactionError,
+};
+
+mod wire;
+
+use self::wire::invalid_field_300;
+pub use self::wire::{
+    USER_FLAG_ADMIN,
+    USER_FLAG_AWAY,
+    build_client_info_text_reply,
+    build_notify_change_user,
+    build_notify_delete_user,
+    build_user_name_list_reply,
+    server_notification,
+    user_flags,
+};
+
+/// A connection's visibility within the presence lifecycle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SessionPhase {
+    /// The connection has not authenticated yet.
+    #[default]
+    Unauthenticated,
+    /// The connection authenticated but must still complete agreement flow.
+    PendingAgreement,
+    /// The connection is fully online and may appear in presence lists.
+    Online,
+}
+
+/// Presence data shared with other clients.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PresenceSnapshot {
+    /// Outbound connection identifier used for push delivery.
+    pub connection_id: OutboundConnectionId,
+    /// Protocol-visible identifier assigned to this active presence session.
+    pub user_id: i32,
+    /// Database identifier of the account the session logged in with.
+    pub account_id: i32,
+    /// Session-visible nickname.
+    pub display_name: String,
+    /// Session-visible icon identifier.
+    pub icon_id: u16,
+    /// Packed status flags used by Hotline user-list clients.
+    pub status_flags: u16,
+    /// Whether the session holds the "Cannot be disconnected" privilege.
+    pub cannot_be_disconnected: bool,
+    /// Away message sent back to users who message this session, if set.
+    pub auto_response: Option<String>,
+}
+
+/// Per-connection facts reported by Get Client Info Text (303).
+///
+/// Runtimes attach these when a connection is accepted; the registry stamps
+/// the login time when the connection first comes online.
+#[derive(Clone, Debug)]
+pub struct ConnectionDetails {
+    /// Peer IP address.
+    pub address: IpAddr,
+    /// Time the connection first appeared online, if it has.
+    pub logged_in_at: Option<DateTime<Utc>>,
+    /// Clock recording the connection's most recent transaction.
+    pub activity: Arc<ActivityClock>,
+    /// Transfers made by the current login and not yet recorded.
+    pub transfers: Arc<TransferTally>,
+    /// Ping state, when the runtime pings the connection.
+    pub pings: Option<Arc<PingTracker>>,
+}
+
+/// What a departing connection's details say about the session it ends.
+struct EndedSession {
+    address: IpAddr,
+    logged_in_at: Option<DateTime<Utc>>,
+    transfers: Arc<TransferTally>,
+}
+
+impl From<ConnectionDetails> for EndedSession {
+    fn from(details: ConnectionDetails) -> Self {
+        Self {
+            address: details.address,
+            logged_in_at: details.logged_in_at,
+            transfers: details.transfers,
+        }
+    }
+}
+
+/// Result of removing a connection from the registry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PresenceRemoval {
+    /// Snapshot that was removed, if the connection was online.
+    pub departed: PresenceSnapshot,
+    /// Remaining online peers after removal.
+    pub remaining_peer_ids: Vec<OutboundConnectionId>,
+}
+
+/// Result of inserting or updating a presence snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PresenceUpsert {
+    /// Snapshot stored in the registry, including its session-unique presence ID.
+    pub snapshot: PresenceSnapshot,
+    /// Other online peers that should receive update notifications.
+    pub peer_ids: Vec<OutboundConnectionId>,
+    /// Sessions of the same account that were already online, when the
+    /// snapshot brought its connection online; empty for updates.
+    pub earlier_sessions: Vec<AccountSession>,
+}
+
+/// An online session of an account, as seen by a later login to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountSession {
+    /// Outbound connection identifier of the session.
+    pub connection_id: OutboundConnectionId,
+    /// Peer IP address, when the runtime attached one.
+    pub address: Option<IpAddr>,
+}
+
+/// Shared runtime registry of online presence snapshots.
+#[derive(Debug, Default)]
+pub struct PresenceRegistry {
+    state: Mutex<PresenceState>,
+    retired: Notify,
+    accounting: Arc<AccountingQueue>,
+}
+
+/// Transfer tally awaiting a write, with the account it belongs to.
+pub type AccountTally = (i32, Arc<TransferTally>);
+
+#[derive(Debug, Default)]
+struct PresenceState {
+    snapshots: HashMap<OutboundConnectionId, PresenceSnapshot>,
+    details: HashMap<OutboundConnectionId, ConnectionDetails>,
+    retired_tallies: Vec<AccountTally>,
+    next_presence_id: u16,
+}
+
+impl PresenceRegistry {
+    /// Create an empty registry that reports sessions to `accounting`.
+    #[must_use]
+    pub fn with_accounting(accounting: Arc<AccountingQueue>) -> Self {
+        Self {
+            accounting,
+            ..Self::default()
+        }
+    }
+
+    /// Insert or replace a connection snapshot and return peer targets.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::InvalidParamValue`] when all field-300 user
+    /// ID values are already assigned to active sessions.
+    pub fn upsert(
+        &self,
+        mut snapshot: PresenceSnapshot,
+    ) -> Result<PresenceUpsert, TransactionError> {
+        let mut guard = self.lock_state();
+        let connection_id = snapshot.connection_id;
+        snapshot.user_id = assigned_presence_id(&mut guard, connection_id)?;
+        let started = guard
+            .details
+            .get_mut(&connection_id)
+            .filter(|details| details.logged_in_at.is_none())
+            .map(|details| {
+                let now = Utc::now();
+                details.logged_in_at = Some(now);
+                (details.address, now)
+            });
+        let earlier_sessions = if guard.snapshots.contains_key(&connection_id) {
+            Vec::new()
+        } else {
+            account_sessions(&guard, snapshot.account_id)
+        };
+        guard.snapshots.insert(connection_id, snapshot.clone());
+        let peer_ids = peer_ids_from_guard(&guard.snapshots, Some(connection_id));
+        drop(guard);
+        if let Some((address, at)) = started {
+            self.accounting
+                .record(SessionRecord::start(&snapshot, address, at));
+        }
+        Ok(PresenceUpsert {
+            snapshot,
+            peer_ids,
+            earlier_sessions,
+        })
+    }
+
+    /// Remove a connection snapshot if it was online.
+    ///
+    /// Any attached [`ConnectionDetails`] are dropped as well, and the
+    /// session's transfer tally is queued for recording.
+    #[must_use]
+    pub fn remove(&self, connection_id: OutboundConnectionId) -> Option<PresenceRemoval> {
+        let mut guard = self.lock_state();
+        let ended = guard.details.remove(&connection_id).map(EndedSession::from);
+        self.end_session(guard, connection_id, ended)
+    }
+
+    /// Take a connection offline while it stays connected, as logout does.
+    ///
+    /// Attached [`ConnectionDetails`] are kept, but their login time is
+    /// cleared so the next login stamps a fresh one, and their transfer
+    /// tally is queued for recording and replaced so the next login counts
+    /// from zero.
+    #[must_use]
+    pub fn withdraw(&self, connection_id: OutboundConnectionId) -> Option<PresenceRemoval> {
+        let mut guard = self.lock_state();
+        let ended = guard
+            .details
+            .get_mut(&connection_id)
+            .map(|details| EndedSession {
+                address: details.address,
+                logged_in_at: details.logged_in_at.take(),
+                transfers: std::mem::take(&mut details.transfers),
+            });
+        self.end_session(guard, connection_id, ended)
+    }
+
+    /// Take `connection_id`'s snapshot, then announce its departure and
+    /// record the session's end once the lock is released.
+    fn end_session(
+        &self,
+        mut guard: MutexGuard<'_, PresenceState>,
+        connection_id: OutboundConnectionId,
+        ended: Option<EndedSession>,
+    ) -> Option<PresenceRemoval> {
+        let tally = ended.as_ref().map(|session| Arc::clone(&session.transfers));
+        let removal = take_snapshot(&mut guard, connection_id, tally);
+        drop(guard);
+        self.announce_retired(removal.as_ref());
+        if let Some(departed) = removal.as_ref().map(|gone| &gone.departed)
+            && let Some(session) = ended
+            && let Some(started) = session.logged_in_at
+        {
+            self.accounting.record(SessionRecord::stop(
+                departed,
+                session.address,
+                started..Utc::now(),
+                session.transfers.session_bytes(),
+            ));
+        }
+        removal
+    }
+
+    /// Return the tallies of departed sessions and of every online session,
+    /// ready to be recorded.
+    ///
+    /// Departed sessions' tallies are handed over once; online sessions'
+    /// tallies stay attached and are returned on every call.
+    #[must_use]
+    pub fn transfer_tallies(&self) -> Vec<AccountTally> {
+        let mut guard = self.lock_state();
+        let mut tallies = std::mem::take(&mut guard.retired_tallies);
+        tallies.extend(guard.snapshots.values().filter_map(|snapshot| {
+            let details = guard.details.get(&snapshot.connection_id)?;
+            Some((snapshot.account_id, Arc::clone(&details.transfers)))
+        }));
+        tallies
+    }
+
+    /// Hand back tallies a failed write could not record.
+    ///
+    /// Tallies still attached to a connection are skipped, because
+    /// [`Self::transfer_tallies`] returns them anyway.
+    pub fn requeue_transfers(&self, tallies: impl IntoIterator<Item = AccountTally>) {
+        let mut guard = self.lock_state();
+        for (account_id, tally) in tallies {
+            let attached = guard
+                .details
+                .values()
+                .any(|details| Arc::ptr_eq(&details.transfers, &tally));
+            if !attached {
+                guard.retired_tallies.push((account_id, tally));
+            }
+        }
+    }
+
+    /// Wait until a session leaves with transfers to record.
+    pub async fn transfers_retired(&self) { self.retired.notified().await; }
+
+    fn announce_retired(&self, removal: Option<&PresenceRemoval>) {
+        if removal.is_some() {
+            self.retired.notify_one();
+        }
+    }
+
+    /// Return all currently online snapshots in deterministic order.
+    #[must_use]
+    pub fn online_snapshots(&self) -> Vec<PresenceSnapshot> {
+        let guard = self.lock_state();
+        sorted_snapshots(guard.snapshots.values().cloned().collect())
+    }
+
+    /// Look up the presence snapshot for the given user identifier.
+    ///
+    /// If multiple connections are registered under the same `user_id` (for
+    /// example, duplicate logins), the snapshot with the numerically lowest
+    /// `connection_id` is returned. Returns `None` if the user is not currently
+    /// online.
+    #[must_use]
+    pub fn snapshot_for_user_id(&self, user_id: i32) -> Option<PresenceSnapshot> {
+        let guard = self.lock_state();
+        guard
+            .snapshots
+            .values()
+            .filter(|snapshot| snapshot.user_id == user_id)
+            .min_by_key(|snapshot| snapshot.connection_id.as_u64())
+            .cloned()
+    }
+
+    /// Record the address and activity clock of a newly accepted connection.
+    pub fn attach_connection(
+        &self,
+        connection_id: OutboundConnectionId,
+        address: IpAddr,
+        activity: Arc<ActivityClock>,
+    ) {
+        let details = ConnectionDetails {
+            address,
+            logged_in_at: None,
+            activity,
+            transfers: Arc::default(),
+            pings: None,
+        };
+        self.lock_state().details.insert(connection_id, details);
+    }
+
+    /// Record the ping state of an attached connection.
+    pub fn attach_pings(&self, connection_id: OutboundConnectionId, pings: Arc<PingTracker>) {
+        if let Some(details) = self.lock_state().details.get_mut(&connection_id) {
+            details.pings = Some(pings);
+        }
+    }
+
+    /// Look up the details attached to `connection_id`.
+    #[must_use]
+    pub fn connection_details(
+        &self,
+        connection_id: OutboundConnectionId,
+    ) -> Option<ConnectionDetails> {
+        self.lock_state().details.get(&connection_id).cloned()
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, PresenceState> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Remove the snapshot for `connection_id`, queueing `tally` for recording
+/// against the departing account.
+fn take_snapshot(
+    state: &mut PresenceState,
+    connection_id: OutboundConnectionId,
+    tally: Option<Arc<TransferTally>>,
+) -> Option<PresenceRemoval> {
+    let departed = state.snapshots.remove(&connection_id)?;
+    if let Some(retired) = tally {
+        state.retired_tallies.push((departed.account_id, retired));
+    }
+    let remaining_peer_ids = peer_ids_from_guard(&state.snapshots, None);
+    Some(PresenceRemoval {
+        departed,
+        remaining_peer_ids,
+    })
+}
+
+/// Return the online sessions of `account_id`, oldest connection first.
+fn account_sessions(state: &PresenceState, account_id: i32) -> Vec<AccountSession> {
+    let mut sessions: Vec<_> = state
+        .snapshots
+        .values()
+        .filter(|snapshot| snapshot.account_id == account_id)
+        .map(|snapshot| AccountSession {
+            connection_id: snapshot.connection_id,
+            address: state
+                .details
+                .get(&snapshot.connection_id)
+                .map(|details| details.address),
+        })
+        .collect();
+    sessions.sort_by_key(|session| session.connection_id.as_u64());
+    sessions
+}
+
+fn sorted_snapshots(mut snapshots: Vec<PresenceSnapshot>) -> Vec<PresenceSnapshot> {
+    snapshots.sort_by_key(|snapshot| (snapshot.user_id, snapshot.connection_id.as_u64()));
+    snapshots
+}
+
+fn peer_ids_from_guard(
+    guard: &HashMap<OutboundConnectionId, PresenceSnapshot>,
+    excluded_id: Option<OutboundConnectionId>,
+) -> Vec<OutboundConnectionId> {
+    let mut peer_ids: Vec<_> = guard
+        .values()
+        .filter_map(|snapshot| {
+            if excluded_id == Some(snapshot.connection_id) {
+                None
+            } else {
+                Some(snapshot.connection_id)
+            }
+        })
+        .collect();
+    peer_ids.sort_by_key(|connection_id| connection_id.as_u64());
+    peer_ids
+}
+
+fn assigned_presence_id(
+    state: &mut PresenceState,
+    connection_id: OutboundConnectionId,
+) -> Result<i32, TransactionError> {
+    if let Some(snapshot) = state.snapshots.get(&connection_id) {
+        return Ok(snapshot.user_id);
+    }
+    next_available_presence_id(state)
+        .map(i32::from)
+        .ok_or_else(invalid_field_300)
+}
+
+fn next_available_presence_id(state: &mut PresenceState) -> Option<u16> {
+    let active_ids: HashSet<u16> = state
+        .snapshots
+        .values()
+        .filter_map(|snapshot| u16::try_from(snapshot.user_id).ok())
+        .collect();
+    for _ in 0..u16::MAX {
+        state.next_presence_id = state.next_presence_id.wrapping_add(1).max(1);
+        if !active_ids.contains(&state.next_presence_id) {
+            return Some(state.next_presence_id);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests;
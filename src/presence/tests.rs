@@ -1,15 +1,24 @@
 //! Tests for presence registry and payload helpers.
 
+use rstest::rstest;
+
 use super::*;
-use crate::{field_id::FieldId, transaction::decode_params};
+use crate::{
+    field_id::FieldId,
+    transaction::{FrameHeader, decode_params},
+    transaction_type::TransactionType,
+};
 
 fn snapshot(connection_id: u64, user_id: i32, display_name: &str) -> PresenceSnapshot {
     PresenceSnapshot {
         connection_id: OutboundConnectionId::new(connection_id),
         user_id,
+        account_id: user_id,
         display_name: display_name.to_owned(),
         icon_id: 0,
         status_flags: 0,
+        cannot_be_disconnected: false,
+        auto_response: None,
     }
 }
 
@@ -84,6 +93,33 @@ fn registry_assigns_unique_presence_ids_for_duplicate_account_logins() {
     assert_eq!(registry.online_snapshots().len(), 2);
 }
 
+#[test]
+fn registry_reports_earlier_sessions_of_the_same_account() {
+    let registry = PresenceRegistry::default();
+    let address = IpAddr::from([192, 0, 2, 8]);
+    registry.attach_connection(OutboundConnectionId::new(1), address, Arc::default());
+    registry
+        .upsert(snapshot(1, 42, "alice"))
+        .expect("first login");
+    registry
+        .upsert(snapshot(3, 9, "bob"))
+        .expect("other account");
+
+    let second = registry
+        .upsert(snapshot(2, 42, "alice"))
+        .expect("second login");
+    let update = registry.upsert(snapshot(2, 42, "Alice")).expect("rename");
+
+    assert_eq!(
+        second.earlier_sessions,
+        vec![AccountSession {
+            connection_id: OutboundConnectionId::new(1),
+            address: Some(address),
+        }]
+    );
+    assert!(update.earlier_sessions.is_empty());
+}
+
 #[test]
 fn registry_preserves_presence_id_when_session_updates() {
     let (registry, first) = registry_with_first_alice_upsert().expect("insert first alice login");
@@ -117,6 +153,98 @@ fn registry_remove_returns_remaining_peers() {
     );
 }
 
+#[test]
+fn registry_stamps_login_time_and_drops_details_on_remove() {
+    let registry = PresenceRegistry::default();
+    let connection_id = OutboundConnectionId::new(1);
+    let address: IpAddr = "192.0.2.7".parse().expect("address");
+    registry.attach_connection(connection_id, address, Arc::new(ActivityClock::new()));
+
+    let attached = registry.connection_details(connection_id).expect("details");
+    assert_eq!(attached.address, address);
+    assert!(attached.logged_in_at.is_none());
+
+    registry
+        .upsert(snapshot(1, 1, "alice"))
+        .expect("insert alice");
+    let first_login = registry
+        .connection_details(connection_id)
+        .and_then(|details| details.logged_in_at);
+    assert!(first_login.is_some());
+    registry
+        .upsert(snapshot(1, 1, "Alice A."))
+        .expect("rename alice");
+    let after_update = registry
+        .connection_details(connection_id)
+        .and_then(|details| details.logged_in_at);
+    assert_eq!(after_update, first_login);
+
+    let _ = registry.remove(connection_id);
+    assert!(registry.connection_details(connection_id).is_none());
+}
+
+#[test]
+fn registry_withdraw_keeps_details_and_clears_login_time() {
+    let registry = PresenceRegistry::default();
+    let connection_id = OutboundConnectionId::new(1);
+    let address: IpAddr = "192.0.2.7".parse().expect("address");
+    registry.attach_connection(connection_id, address, Arc::new(ActivityClock::new()));
+    registry
+        .upsert(snapshot(1, 1, "alice"))
+        .expect("insert alice");
+    registry.upsert(snapshot(2, 2, "bob")).expect("insert bob");
+
+    let withdrawn = registry.withdraw(connection_id).expect("alice was online");
+
+    assert_eq!(withdrawn.departed.display_name, "alice");
+    assert_eq!(
+        withdrawn.remaining_peer_ids,
+        vec![OutboundConnectionId::new(2)]
+    );
+    let details = registry.connection_details(connection_id).expect("details");
+    assert_eq!(details.address, address);
+    assert!(details.logged_in_at.is_none());
+    assert!(registry.withdraw(connection_id).is_none());
+}
+
+#[test]
+fn registry_hands_over_departed_tallies_once() {
+    let registry = PresenceRegistry::default();
+    let address: IpAddr = "192.0.2.7".parse().expect("address");
+    for id in [1, 2] {
+        let connection_id = OutboundConnectionId::new(id);
+        registry.attach_connection(connection_id, address, Arc::new(ActivityClock::new()));
+    }
+    registry
+        .upsert(snapshot(1, 1, "alice"))
+        .expect("insert alice");
+    registry.upsert(snapshot(2, 2, "bob")).expect("insert bob");
+    let alice = registry
+        .connection_details(OutboundConnectionId::new(1))
+        .expect("details")
+        .transfers;
+    alice.record_download(64);
+
+    let _ = registry.withdraw(OutboundConnectionId::new(1));
+    let fresh = registry
+        .connection_details(OutboundConnectionId::new(1))
+        .expect("details")
+        .transfers;
+    assert!(!Arc::ptr_eq(&fresh, &alice));
+    let _ = registry.remove(OutboundConnectionId::new(2));
+
+    let accounts: Vec<i32> = registry
+        .transfer_tallies()
+        .into_iter()
+        .map(|(account_id, _)| account_id)
+        .collect();
+    assert_eq!(accounts, vec![1, 2]);
+    assert!(registry.transfer_tallies().is_empty());
+
+    registry.requeue_transfers([(1, alice), (1, fresh)]);
+    assert_eq!(registry.transfer_tallies().len(), 1);
+}
+
 #[test]
 fn notify_change_user_uses_server_initiated_transaction_id() {
     let mut snapshot = snapshot(1, 7, "alice");
@@ -164,3 +292,16 @@ fn user_name_list_reply_contains_repeated_field_300_entries() {
     assert_eq!(reply.header.is_reply, 1);
     assert_eq!(reply.header.id, 44);
 }
+
+#[rstest]
+#[case(false, false, 0)]
+#[case(true, false, 1)]
+#[case(false, true, 2)]
+#[case(true, true, 3)]
+fn user_flags_pack_away_and_admin_bits(
+    #[case] away: bool,
+    #[case] admin: bool,
+    #[case] expected: u16,
+) {
+    assert_eq!(user_flags(away, admin), expected);
+}
@@ -0,0 +1,160 @@
+//! Presence payload encoding for the `300`–`303` transactions.
+//!
+//! Field 300 packs a user's ID, icon, status flags, and nickname into one
+//! SynHX-compatible record; field 112 carries the same flags on their own in
+//! Notify Change User (301).
+
+use super::PresenceSnapshot;
+use crate::{
+    field_id::FieldId,
+    header_util::reply_header,
+    transaction::{FrameHeader, Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// User-list flag bit marking a user as away.
+pub const USER_FLAG_AWAY: u16 = 1 << 0;
+/// User-list flag bit marking a user as an administrator.
+pub const USER_FLAG_ADMIN: u16 = 1 << 1;
+
+/// Pack the user-list flags for a user who is `away` and/or an `admin`.
+#[must_use]
+pub const fn user_flags(away: bool, admin: bool) -> u16 {
+    let away_bit = if away { USER_FLAG_AWAY } else { 0 };
+    let admin_bit = if admin { USER_FLAG_ADMIN } else { 0 };
+    away_bit | admin_bit
+}
+
+impl PresenceSnapshot {
+    /// Encode the SynHX-compatible packed field-300 payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::InvalidParamValue`] if the user ID or
+    /// display name length cannot be represented in the field-300 wire format.
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    pub fn encode_user_name_with_info(&self) -> Result<Vec<u8>, TransactionError> {
+        let mut payload = Vec::with_capacity(8 + self.display_name.len());
+        payload.extend_from_slice(&field_300_user_id(self.user_id)?.to_be_bytes());
+        payload.extend_from_slice(&self.icon_id.to_be_bytes());
+        payload.extend_from_slice(&self.status_flags.to_be_bytes());
+        payload.extend_from_slice(&field_300_name_len(self.display_name.len())?.to_be_bytes());
+        payload.extend_from_slice(self.display_name.as_bytes());
+        Ok(payload)
+    }
+
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    fn notify_change_fields(&self) -> [(FieldId, Vec<u8>); 4] {
+        [
+            (FieldId::UserId, self.user_id.to_be_bytes().to_vec()),
+            (FieldId::IconId, self.icon_id.to_be_bytes().to_vec()),
+            (FieldId::UserFlags, self.status_flags.to_be_bytes().to_vec()),
+            (FieldId::Name, self.display_name.as_bytes().to_vec()),
+        ]
+    }
+}
+
+/// Build a `300` reply with repeated field-300 entries.
+///
+/// # Errors
+///
+/// Returns an encoding error if the payload would exceed protocol limits.
+pub fn build_user_name_list_reply(
+    header: &FrameHeader,
+    snapshots: &[PresenceSnapshot],
+) -> Result<Transaction, TransactionError> {
+    let params: Vec<(FieldId, Vec<u8>)> = snapshots
+        .iter()
+        .map(|snapshot| {
+            snapshot
+                .encode_user_name_with_info()
+                .map(|payload| (FieldId::UserNameWithInfo, payload))
+        })
+        .collect::<Result<_, _>>()?;
+    let payload = encode_params(&params)?;
+    Ok(Transaction {
+        header: reply_header(header, 0, payload.len()),
+        payload,
+    })
+}
+
+/// Build a `301` notification transaction.
+///
+/// # Errors
+///
+/// Returns an encoding error if the payload would exceed protocol limits.
+pub fn build_notify_change_user(
+    snapshot: &PresenceSnapshot,
+) -> Result<Transaction, TransactionError> {
+    let payload = encode_params(&snapshot.notify_change_fields())?;
+    Ok(server_notification(
+        TransactionType::NotifyChangeUser,
+        payload,
+    ))
+}
+
+/// Build a `302` notification transaction.
+///
+/// # Errors
+///
+/// Returns an encoding error if the payload would exceed protocol limits.
+pub fn build_notify_delete_user(user_id: i32) -> Result<Transaction, TransactionError> {
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    let payload = encode_params(&[(FieldId::UserId, user_id.to_be_bytes())])?;
+    Ok(server_notification(
+        TransactionType::NotifyDeleteUser,
+        payload,
+    ))
+}
+
+/// Build a `303` reply transaction.
+///
+/// # Errors
+///
+/// Returns an encoding error if the payload would exceed protocol limits.
+pub fn build_client_info_text_reply(
+    header: &FrameHeader,
+    display_name: &str,
+    info_text: &str,
+) -> Result<Transaction, TransactionError> {
+    let payload = encode_params(&[
+        (FieldId::Name, display_name.as_bytes()),
+        (FieldId::Data, info_text.as_bytes()),
+    ])?;
+    Ok(Transaction {
+        header: reply_header(header, 0, payload.len()),
+        payload,
+    })
+}
+
+/// Wrap `payload` in an unsolicited server-to-client transaction header.
+pub(crate) fn server_notification(
+    transaction_type: TransactionType,
+    payload: Vec<u8>,
+) -> Transaction {
+    let payload_len = u32::try_from(payload.len()).unwrap_or(u32::MAX);
+    Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: transaction_type.into(),
+            id: 0,
+            error: 0,
+            total_size: payload_len,
+            data_size: payload_len,
+        },
+        payload,
+    }
+}
+
+pub(super) const fn invalid_field_300() -> TransactionError {
+    TransactionError::InvalidParamValue(FieldId::UserNameWithInfo)
+}
+
+fn field_300_user_id(value: i32) -> Result<u16, TransactionError> {
+    u16::try_from(value).map_err(|_| invalid_field_300())
+}
+
+fn field_300_name_len(value: usize) -> Result<u16, TransactionError> {
+    u16::try_from(value).map_err(|_| invalid_field_300())
+}
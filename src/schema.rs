@@ -10,6 +10,8 @@ diesel::table! {
         id -> Integer,
         username -> Text,
         password -> Text,
+        privileges -> BigInt,
+        quota_bytes -> Nullable<BigInt>,
     }
 }
 
@@ -20,6 +22,7 @@ diesel::table! {
         name -> Text,
         guid -> Nullable<Text>,
         created_at -> Nullable<Timestamp>,
+        root_id -> Integer,
     }
 }
 
@@ -32,6 +35,14 @@ diesel::table! {
         add_sn -> Nullable<Integer>,
         delete_sn -> Nullable<Integer>,
         created_at -> Nullable<Timestamp>,
+        root_id -> Integer,
+    }
+}
+
+diesel::table! {
+    news_roots (id) {
+        id -> Integer,
+        name -> Text,
     }
 }
 
@@ -41,6 +52,11 @@ diesel::table! {
         name -> Text,
         object_key -> Text,
         size -> BigInt,
+        type_code -> Text,
+        creator_code -> Text,
+        comment -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
     }
 }
 
@@ -58,6 +74,8 @@ diesel::table! {
         creator_id -> Integer,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        type_code -> Text,
+        creator_code -> Text,
     }
 }
 
@@ -131,6 +149,83 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    bans (id) {
+        id -> Integer,
+        kind -> Text,
+        target -> Text,
+        reason -> Nullable<Text>,
+        created_at -> Timestamp,
+        expires_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    download_credits (user_id) {
+        user_id -> Integer,
+        credits -> BigInt,
+    }
+}
+
+diesel::table! {
+    file_archives (id) {
+        id -> Integer,
+        source_id -> Nullable<Integer>,
+        container_id -> Integer,
+        last_snapshot_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    transfer_stats (user_id) {
+        user_id -> Integer,
+        bytes_uploaded -> BigInt,
+        bytes_downloaded -> BigInt,
+        uploads -> BigInt,
+        downloads -> BigInt,
+    }
+}
+
+diesel::table! {
+    outbox_events (id) {
+        id -> Integer,
+        event -> Text,
+        created_at -> Timestamp,
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    outbox_cursors (user_id) {
+        user_id -> Integer,
+        last_event_id -> Integer,
+    }
+}
+
+diesel::table! {
+    news_digests (user_id) {
+        user_id -> Integer,
+        email -> Nullable<Text>,
+        last_visit_at -> Timestamp,
+        pending -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    news_digest_categories (user_id, category_id) {
+        user_id -> Integer,
+        category_id -> Integer,
+    }
+}
+
+diesel::table! {
+    session_tokens (token) {
+        token -> Text,
+        user_id -> Integer,
+        expires_at -> Timestamp,
+    }
+}
+
 diesel::joinable!(file_nodes -> users (creator_id));
 diesel::joinable!(file_acl -> files (file_id));
 diesel::joinable!(file_acl -> users (user_id));
@@ -141,17 +236,34 @@ diesel::joinable!(user_groups -> groups (group_id));
 diesel::joinable!(user_groups -> users (user_id));
 diesel::joinable!(user_permissions -> permissions (permission_id));
 diesel::joinable!(user_permissions -> users (user_id));
+diesel::joinable!(transfer_stats -> users (user_id));
+diesel::joinable!(download_credits -> users (user_id));
+diesel::joinable!(outbox_cursors -> users (user_id));
+diesel::joinable!(news_digests -> users (user_id));
+diesel::joinable!(news_digest_categories -> news_digests (user_id));
+diesel::joinable!(news_digest_categories -> news_categories (category_id));
+diesel::joinable!(session_tokens -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    bans,
+    download_credits,
     file_acl,
+    file_archives,
     file_nodes,
     files,
     groups,
     news_articles,
     news_bundles,
     news_categories,
+    news_digest_categories,
+    news_digests,
+    news_roots,
+    outbox_cursors,
+    outbox_events,
     permissions,
     resource_permissions,
+    session_tokens,
+    transfer_stats,
     user_groups,
     user_permissions,
     users
@@ -0,0 +1,303 @@
+//! Accept-loop protection against file descriptor exhaustion.
+//!
+//! When the process runs out of descriptors, `accept()` fails immediately
+//! with `EMFILE` or `ENFILE` while the pending connection stays in the listen
+//! backlog, so a naive loop spins at full CPU and logs the same error
+//! thousands of times a second. [`AcceptGuard`] classifies those failures,
+//! hands the caller an exponentially growing pause, and keeps one spare
+//! descriptor in reserve. Releasing the reserve lets the loop accept and
+//! immediately close one pending connection, so that client sees a closed
+//! connection rather than hanging in the backlog.
+//!
+//! Wireframe runs its own accept loop, so its listeners cannot report their
+//! failures here. A watcher instead calls [`AcceptGuard::probe`] on a timer
+//! and sheds through a duplicate of the listener.
+
+use std::{
+    cmp::min,
+    fs::File,
+    io,
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use futures_util::FutureExt;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+use super::NetworkRuntime;
+
+/// First pause applied after descriptor exhaustion.
+pub const PAUSE_INITIAL: Duration = Duration::from_millis(10);
+
+/// Longest pause applied while descriptors remain exhausted.
+pub const PAUSE_MAX: Duration = Duration::from_secs(1);
+
+/// Raw OS error codes reporting descriptor exhaustion (`EMFILE`, `ENFILE`).
+#[cfg(unix)]
+const FD_EXHAUSTION_CODES: [i32; 2] = [24, 23];
+/// Raw OS error codes reporting descriptor exhaustion (`WSAEMFILE`).
+#[cfg(windows)]
+const FD_EXHAUSTION_CODES: [i32; 1] = [10024];
+#[cfg(not(any(unix, windows)))]
+const FD_EXHAUSTION_CODES: [i32; 0] = [];
+
+/// Path opened to hold the reserve descriptor.
+#[cfg(windows)]
+const RESERVE_PATH: &str = "NUL";
+#[cfg(not(windows))]
+const RESERVE_PATH: &str = "/dev/null";
+
+/// Return `true` when `error` reports that the process or system has no free
+/// file descriptors.
+#[must_use]
+pub fn is_fd_exhaustion(error: &io::Error) -> bool {
+    error
+        .raw_os_error()
+        .is_some_and(|code| FD_EXHAUSTION_CODES.contains(&code))
+}
+
+/// Counters describing accept-loop health.
+#[derive(Debug, Default)]
+pub struct AcceptMetrics {
+    accepted: AtomicU64,
+    errors: AtomicU64,
+    fd_exhaustions: AtomicU64,
+    shed: AtomicU64,
+}
+
+/// Point-in-time copy of [`AcceptMetrics`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AcceptMetricsSnapshot {
+    /// Connections accepted and handed to a handler.
+    pub accepted: u64,
+    /// Accept calls that failed for any reason.
+    pub errors: u64,
+    /// Failures caused by descriptor exhaustion.
+    pub fd_exhaustions: u64,
+    /// Pending connections closed using the reserve descriptor.
+    pub shed: u64,
+}
+
+impl AcceptMetrics {
+    /// Read the current counter values.
+    #[must_use]
+    pub fn snapshot(&self) -> AcceptMetricsSnapshot {
+        AcceptMetricsSnapshot {
+            accepted: self.accepted.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            fd_exhaustions: self.fd_exhaustions.load(Ordering::Relaxed),
+            shed: self.shed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Count a connection accepted by a loop that has no [`AcceptGuard`].
+    pub fn record_accepted(&self) { Self::bump(&self.accepted); }
+
+    fn bump(counter: &AtomicU64) { counter.fetch_add(1, Ordering::Relaxed); }
+}
+
+/// Log the final accept counters for `runtime` once its listeners stop.
+pub fn log_accept_metrics(runtime: NetworkRuntime, metrics: &AcceptMetrics) {
+    let stats = metrics.snapshot();
+    info!(
+        runtime = runtime.label(),
+        accepted = stats.accepted,
+        errors = stats.errors,
+        fd_exhaustions = stats.fd_exhaustions,
+        shed = stats.shed,
+        "accept loop stopped"
+    );
+}
+
+/// Per-listener state for surviving descriptor exhaustion.
+#[derive(Debug)]
+pub struct AcceptGuard {
+    metrics: Arc<AcceptMetrics>,
+    reserve: Option<File>,
+    next_pause: Duration,
+    exhausted: bool,
+}
+
+impl AcceptGuard {
+    /// Create a guard recording into `metrics` and open the reserve
+    /// descriptor.
+    #[must_use]
+    pub fn new(metrics: Arc<AcceptMetrics>) -> Self {
+        Self {
+            metrics,
+            reserve: open_reserve(),
+            next_pause: PAUSE_INITIAL,
+            exhausted: false,
+        }
+    }
+
+    /// Shared counters updated by this guard.
+    #[must_use]
+    pub const fn metrics(&self) -> &Arc<AcceptMetrics> { &self.metrics }
+
+    /// Record a successful accept, ending any exhaustion episode.
+    pub fn record_accepted(&mut self) {
+        self.metrics.record_accepted();
+        self.record_recovered();
+    }
+
+    /// End any exhaustion episode without counting an accept, for loops
+    /// whose connections are counted further on.
+    pub fn record_recovered(&mut self) {
+        if self.exhausted {
+            self.exhausted = false;
+            info!("file descriptors available again; accepting connections");
+        }
+        self.next_pause = PAUSE_INITIAL;
+        if self.reserve.is_none() {
+            self.reserve = open_reserve();
+        }
+    }
+
+    /// Record a failed accept.
+    ///
+    /// Returns how long the caller should stop accepting when the failure was
+    /// caused by descriptor exhaustion, or `None` for other errors. The
+    /// operator warning is logged once per exhaustion episode.
+    pub fn record_error(&mut self, error: &io::Error) -> Option<Duration> {
+        AcceptMetrics::bump(&self.metrics.errors);
+        if !is_fd_exhaustion(error) {
+            warn!(%error, "accept failed");
+            return None;
+        }
+        Some(self.record_exhaustion(error))
+    }
+
+    /// Check for descriptor exhaustion without accepting, for listeners
+    /// whose accept loop is owned elsewhere.
+    ///
+    /// Opens and closes one spare descriptor. Returns the pause before the
+    /// next probe when none was free, as [`Self::record_error`] does, and
+    /// otherwise ends any exhaustion episode and returns `None`.
+    pub fn probe(&mut self) -> Option<Duration> {
+        match File::open(RESERVE_PATH) {
+            Ok(_) => {
+                self.record_recovered();
+                None
+            }
+            Err(error) if is_fd_exhaustion(&error) => Some(self.record_exhaustion(&error)),
+            Err(error) => {
+                warn!(%error, "failed to probe for free file descriptors");
+                None
+            }
+        }
+    }
+
+    fn record_exhaustion(&mut self, error: &io::Error) -> Duration {
+        AcceptMetrics::bump(&self.metrics.fd_exhaustions);
+        if !self.exhausted {
+            self.exhausted = true;
+            warn!(
+                %error,
+                "out of file descriptors; pausing accepts. Raise the open file \
+                 limit (ulimit -n or LimitNOFILE) or reduce the connection count"
+            );
+        }
+        let pause = self.next_pause;
+        self.next_pause = min(pause.saturating_mul(2), PAUSE_MAX);
+        pause
+    }
+
+    /// Close one pending connection using the reserve descriptor.
+    ///
+    /// The reserve is released, one ready connection is accepted and dropped,
+    /// and the reserve is reopened. Does nothing when the reserve is already
+    /// spent or no connection is ready.
+    pub fn shed_pending(&mut self, listener: &TcpListener) {
+        if self.reserve.take().is_none() {
+            return;
+        }
+        if let Some(Ok((socket, peer))) = listener.accept().now_or_never() {
+            drop(socket);
+            self.record_shed(peer);
+        }
+        self.reserve = open_reserve();
+    }
+
+    fn record_shed(&self, peer: SocketAddr) {
+        AcceptMetrics::bump(&self.metrics.shed);
+        warn!(%peer, "closed pending connection while out of file descriptors");
+    }
+}
+
+fn open_reserve() -> Option<File> {
+    File::open(RESERVE_PATH)
+        .inspect_err(|error| warn!(%error, "failed to open reserve file descriptor"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for descriptor exhaustion classification and pacing.
+    use rstest::rstest;
+
+    use super::*;
+
+    fn exhaustion() -> io::Error {
+        let code = FD_EXHAUSTION_CODES.first().copied().expect("platform code");
+        io::Error::from_raw_os_error(code)
+    }
+
+    #[rstest]
+    fn classifies_descriptor_exhaustion() {
+        assert!(is_fd_exhaustion(&exhaustion()));
+        assert!(!is_fd_exhaustion(&io::Error::from(
+            io::ErrorKind::ConnectionAborted
+        )));
+    }
+
+    #[rstest]
+    fn pauses_grow_until_capped() {
+        let mut guard = AcceptGuard::new(Arc::default());
+
+        let pauses: Vec<_> = (0..10)
+            .filter_map(|_| guard.record_error(&exhaustion()))
+            .collect();
+
+        assert_eq!(pauses.first(), Some(&PAUSE_INITIAL));
+        assert!(pauses.windows(2).all(|pair| pair.first() <= pair.last()));
+        assert_eq!(pauses.last(), Some(&PAUSE_MAX));
+    }
+
+    #[rstest]
+    fn accept_resets_pause_and_counts() {
+        let mut guard = AcceptGuard::new(Arc::default());
+        guard.record_error(&exhaustion());
+        guard.record_error(&exhaustion());
+        guard.record_error(&io::Error::from(io::ErrorKind::ConnectionReset));
+
+        guard.record_accepted();
+
+        assert_eq!(guard.record_error(&exhaustion()), Some(PAUSE_INITIAL));
+        assert_eq!(
+            guard.metrics().snapshot(),
+            AcceptMetricsSnapshot {
+                accepted: 1,
+                errors: 4,
+                fd_exhaustions: 3,
+                shed: 0,
+            }
+        );
+    }
+
+    #[rstest]
+    fn probe_finds_free_descriptors() {
+        let mut guard = AcceptGuard::new(Arc::default());
+        guard.record_error(&exhaustion());
+
+        assert_eq!(guard.probe(), None);
+
+        assert_eq!(guard.record_error(&exhaustion()), Some(PAUSE_INITIAL));
+        assert_eq!(guard.metrics().snapshot().accepted, 0);
+    }
+}
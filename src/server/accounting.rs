@@ -0,0 +1,414 @@
+//! Session accounting records.
+//!
+//! With `accounting_sink` set, the presence registry reports each session
+//! as it comes online and again as it leaves, in the manner of RADIUS
+//! accounting start and stop records. A record is one JSON object naming the
+//! session, account, nickname, and address; stop records add how long the
+//! session lasted and the file transfer bytes it moved. The task started by
+//! [`start_session_accounting`] opens the server's [`AccountingQueue`] and
+//! delivers its records to the configured sink:
+//!
+//! - a path or `file://` URL, which gets one line per record appended;
+//! - an `http://` URL, which receives each record as a JSON `POST`;
+//! - `syslog`, the local syslog daemon's `/dev/log` socket, or `syslog:///path` for another socket
+//!   (Unix only).
+//!
+//! Records wait in a bounded queue so a slow sink never holds up a login.
+//! Records that find the queue full, or that the sink refuses, are dropped
+//! with a warning.
+
+use std::{net::IpAddr, ops::Range, path::PathBuf, sync::OnceLock};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use thiserror::Error;
+use tokio::{fs::File, io::AsyncWriteExt, sync::mpsc, task::JoinHandle};
+use tracing::{info, warn};
+
+#[cfg(unix)]
+use super::syslog::{Facility, Severity, syslog_message};
+use super::{AppConfig, http, syslog::DEFAULT_SYSLOG_SOCKET, transfer_stats::SessionBytes};
+use crate::presence::PresenceSnapshot;
+
+/// Records held while the sink catches up.
+pub const ACCOUNTING_QUEUE_LEN: usize = 1024;
+
+/// Where session records are delivered.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccountingSink {
+    /// A file records are appended to.
+    File(PathBuf),
+    /// An HTTP endpoint each record is posted to.
+    Webhook {
+        /// `host:port` to connect to.
+        authority: String,
+        /// Request path, starting with `/`.
+        path: String,
+    },
+    /// A syslog daemon's datagram socket.
+    Syslog(PathBuf),
+}
+
+/// Errors raised while reading the accounting sink from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AccountingSinkError {
+    /// `accounting_sink` is empty or names an unknown scheme.
+    #[error("unsupported accounting_sink {0:?}")]
+    Unsupported(String),
+    /// `accounting_sink` is an `https://` URL.
+    #[error("accounting_sink cannot use https; post to a local relay over http")]
+    Https,
+    /// `accounting_sink` names syslog on a platform without Unix sockets.
+    #[error("accounting_sink syslog needs a Unix platform")]
+    SyslogUnavailable,
+}
+
+impl AccountingSink {
+    /// Parse a sink: a path or `file://` URL, an `http://` URL, `syslog`, or
+    /// `syslog:///path/to/socket`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AccountingSinkError`] for an empty path, an `https://` or
+    /// other unknown URL, an `http://` URL without a host, or syslog off
+    /// Unix.
+    pub fn parse(sink: &str) -> Result<Self, AccountingSinkError> {
+        let unsupported = || AccountingSinkError::Unsupported(sink.to_owned());
+        if sink == "syslog" || sink.starts_with("syslog://") {
+            if cfg!(not(unix)) {
+                return Err(AccountingSinkError::SyslogUnavailable);
+            }
+            let socket = sink
+                .strip_prefix("syslog://")
+                .filter(|socket| !socket.is_empty())
+                .unwrap_or(DEFAULT_SYSLOG_SOCKET);
+            return Ok(Self::Syslog(PathBuf::from(socket)));
+        }
+        if sink.starts_with("https://") {
+            return Err(AccountingSinkError::Https);
+        }
+        if let Some(rest) = sink.strip_prefix("http://") {
+            let (host, path) = rest
+                .find('/')
+                .map_or((rest, "/"), |slash| rest.split_at(slash));
+            if host.is_empty() {
+                return Err(unsupported());
+            }
+            let has_port = host
+                .rsplit_once(':')
+                .is_some_and(|(_, port)| !port.ends_with(']'));
+            let authority = if has_port {
+                host.to_owned()
+            } else {
+                format!("{host}:80")
+            };
+            return Ok(Self::Webhook {
+                authority,
+                path: path.to_owned(),
+            });
+        }
+        let file = sink.strip_prefix("file://").unwrap_or(sink);
+        if file.is_empty() || file.contains("://") {
+            return Err(unsupported());
+        }
+        Ok(Self::File(PathBuf::from(file)))
+    }
+
+    /// Read the sink from `config`; `None` keeps no records.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AccountingSinkError`] when `accounting_sink` cannot be
+    /// parsed.
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>, AccountingSinkError> {
+        config
+            .accounting_sink
+            .as_deref()
+            .map(Self::parse)
+            .transpose()
+    }
+}
+
+/// Whether a record marks a session's start or its end.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionEvent {
+    /// The session came online.
+    Start,
+    /// The session logged out or disconnected.
+    Stop,
+}
+
+/// One accounting record.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SessionRecord {
+    /// Whether the session started or stopped.
+    pub event: SessionEvent,
+    /// Identifier shared by a session's start and stop records.
+    pub session_id: u64,
+    /// Database identifier of the account.
+    pub account_id: i32,
+    /// Nickname the session used.
+    pub nickname: String,
+    /// Peer IP address.
+    pub address: IpAddr,
+    /// When the session came online.
+    pub started_at: String,
+    /// Seconds the session lasted; stop records only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u64>,
+    /// File bytes uploaded during the session; stop records only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_uploaded: Option<u64>,
+    /// File bytes downloaded during the session; stop records only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_downloaded: Option<u64>,
+}
+
+impl SessionRecord {
+    /// Build the start record of `session`, which came online from
+    /// `address` at `started_at`.
+    #[must_use]
+    pub fn start(session: &PresenceSnapshot, address: IpAddr, started_at: DateTime<Utc>) -> Self {
+        Self {
+            event: SessionEvent::Start,
+            session_id: session.connection_id.as_u64(),
+            account_id: session.account_id,
+            nickname: session.display_name.clone(),
+            address,
+            started_at: started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            duration_secs: None,
+            bytes_uploaded: None,
+            bytes_downloaded: None,
+        }
+    }
+
+    /// Build the stop record of `session`, which was online from `address`
+    /// for `online` and moved `bytes`.
+    #[must_use]
+    pub fn stop(
+        session: &PresenceSnapshot,
+        address: IpAddr,
+        online: Range<DateTime<Utc>>,
+        bytes: SessionBytes,
+    ) -> Self {
+        let Range {
+            start: started_at,
+            end: ended_at,
+        } = online;
+        let lasted = (ended_at - started_at).num_seconds();
+        Self {
+            event: SessionEvent::Stop,
+            duration_secs: Some(u64::try_from(lasted).unwrap_or_default()),
+            bytes_uploaded: Some(bytes.uploaded),
+            bytes_downloaded: Some(bytes.downloaded),
+            ..Self::start(session, address, started_at)
+        }
+    }
+}
+
+/// One server's queue of session records awaiting its sink.
+///
+/// The queue drops every record until [`start_session_accounting`] opens it.
+#[derive(Debug, Default)]
+pub struct AccountingQueue {
+    sender: OnceLock<mpsc::Sender<SessionRecord>>,
+}
+
+impl AccountingQueue {
+    /// Queue `record` for the sink; does nothing while accounting is off.
+    pub fn record(&self, record: SessionRecord) {
+        let Some(sender) = self.sender.get() else {
+            return;
+        };
+        if let Err(error) = sender.try_send(record) {
+            warn!(%error, "session accounting record dropped");
+        }
+    }
+}
+
+/// Open `sink` and deliver the records sent to `queue` until the returned
+/// task is aborted, or return `None` when accounting is off.
+///
+/// # Errors
+///
+/// Returns an error if the record file cannot be opened, the syslog socket
+/// cannot be created, or `queue` is already open.
+pub async fn start_session_accounting(
+    sink: Option<&AccountingSink>,
+    queue: &AccountingQueue,
+) -> Result<Option<JoinHandle<()>>> {
+    let Some(sink) = sink else {
+        return Ok(None);
+    };
+    let mut writer = SinkWriter::open(sink).await?;
+    let (sender, mut records) = mpsc::channel(ACCOUNTING_QUEUE_LEN);
+    if queue.sender.set(sender).is_err() {
+        bail!("session accounting is already running");
+    }
+    info!(?sink, "recording session accounting");
+    Ok(Some(tokio::spawn(async move {
+        while let Some(record) = records.recv().await {
+            if let Err(error) = writer.write(&record).await {
+                warn!(%error, event = ?record.event, "session accounting record not delivered");
+            }
+        }
+    })))
+}
+
+/// An opened sink.
+enum SinkWriter {
+    File(File),
+    Webhook {
+        authority: String,
+        path: String,
+    },
+    #[cfg(unix)]
+    Syslog {
+        socket: tokio::net::UnixDatagram,
+        path: PathBuf,
+    },
+}
+
+impl SinkWriter {
+    async fn open(sink: &AccountingSink) -> Result<Self> {
+        match sink {
+            AccountingSink::File(path) => {
+                let file = tokio::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .await
+                    .with_context(|| format!("failed to open {}", path.display()))?;
+                Ok(Self::File(file))
+            }
+            AccountingSink::Webhook { authority, path } => Ok(Self::Webhook {
+                authority: authority.clone(),
+                path: path.clone(),
+            }),
+            #[cfg(unix)]
+            AccountingSink::Syslog(path) => Ok(Self::Syslog {
+                socket: tokio::net::UnixDatagram::unbound()
+                    .context("failed to create syslog socket")?,
+                path: path.clone(),
+            }),
+            #[cfg(not(unix))]
+            AccountingSink::Syslog(_) => Err(AccountingSinkError::SyslogUnavailable.into()),
+        }
+    }
+
+    async fn write(&mut self, record: &SessionRecord) -> Result<()> {
+        let json = serde_json::to_string(record)?;
+        match self {
+            Self::File(file) => {
+                file.write_all(format!("{json}\n").as_bytes()).await?;
+                file.flush().await?;
+            }
+            Self::Webhook { authority, path } => {
+                let status =
+                    http::post(authority, path, "application/json", json.as_bytes()).await?;
+                if !(200..300).contains(&status) {
+                    bail!("webhook answered {status}");
+                }
+            }
+            #[cfg(unix)]
+            Self::Syslog { socket, path } => {
+                socket
+                    .send_to(
+                        syslog_message(Facility::AuthPriv, Severity::Info, &json).as_bytes(),
+                        &*path,
+                    )
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Parsing sinks and encoding records.
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::server::outbound::OutboundConnectionId;
+
+    fn alice() -> PresenceSnapshot {
+        PresenceSnapshot {
+            connection_id: OutboundConnectionId::new(7),
+            user_id: 1,
+            account_id: 3,
+            display_name: "alice".to_owned(),
+            icon_id: 0,
+            status_flags: 0,
+            cannot_be_disconnected: false,
+            auto_response: None,
+        }
+    }
+
+    fn noon() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z")
+            .map(|time| time.with_timezone(&Utc))
+            .expect("valid time")
+    }
+
+    #[rstest]
+    #[case::path("/var/log/mxd/sessions.log", Ok(AccountingSink::File("/var/log/mxd/sessions.log".into())))]
+    #[case::file_url("file:///tmp/sessions", Ok(AccountingSink::File("/tmp/sessions".into())))]
+    #[case::webhook("http://billing:8080/mxd", Ok(AccountingSink::Webhook {
+        authority: "billing:8080".to_owned(),
+        path: "/mxd".to_owned(),
+    }))]
+    #[case::default_port("http://billing", Ok(AccountingSink::Webhook {
+        authority: "billing:80".to_owned(),
+        path: "/".to_owned(),
+    }))]
+    #[case::ipv6("http://[::1]/mxd", Ok(AccountingSink::Webhook {
+        authority: "[::1]:80".to_owned(),
+        path: "/mxd".to_owned(),
+    }))]
+    #[case::syslog("syslog", Ok(AccountingSink::Syslog(DEFAULT_SYSLOG_SOCKET.into())))]
+    #[case::syslog_socket("syslog:///run/log", Ok(AccountingSink::Syslog("/run/log".into())))]
+    #[case::https("https://billing/mxd", Err(AccountingSinkError::Https))]
+    #[case::no_host("http:///mxd", Err(AccountingSinkError::Unsupported("http:///mxd".to_owned())))]
+    #[case::empty("", Err(AccountingSinkError::Unsupported(String::new())))]
+    #[case::other_scheme("ftp://billing", Err(AccountingSinkError::Unsupported("ftp://billing".to_owned())))]
+    fn parses_sinks(
+        #[case] sink: &str,
+        #[case] expected: Result<AccountingSink, AccountingSinkError>,
+    ) {
+        assert_eq!(AccountingSink::parse(sink), expected);
+    }
+
+    #[rstest]
+    fn start_records_omit_session_totals() {
+        let record = SessionRecord::start(&alice(), "192.0.2.1".parse().expect("address"), noon());
+
+        assert_eq!(
+            serde_json::to_string(&record).expect("record encodes"),
+            r#"{"event":"start","session_id":7,"account_id":3,"nickname":"alice","address":"192.0.2.1","started_at":"2026-01-01T12:00:00Z"}"#
+        );
+    }
+
+    #[rstest]
+    fn stop_records_carry_duration_and_bytes() {
+        let ended_at = noon() + chrono::TimeDelta::seconds(90);
+        let bytes = SessionBytes {
+            uploaded: 10,
+            downloaded: 20,
+        };
+        let record = SessionRecord::stop(
+            &alice(),
+            "192.0.2.1".parse().expect("address"),
+            noon()..ended_at,
+            bytes,
+        );
+
+        assert_eq!(record.event, SessionEvent::Stop);
+        assert_eq!(record.duration_secs, Some(90));
+        assert_eq!(record.bytes_uploaded, Some(10));
+        assert_eq!(record.bytes_downloaded, Some(20));
+    }
+}
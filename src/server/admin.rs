@@ -1,24 +1,77 @@
 //! Administrative command handlers shared across server runtimes.
 //!
-//! These helpers keep user-management workflows available regardless of the
-//! selected networking adapter. The functions remain free of transport
-//! dependencies so both the legacy Tokio loop and the Wireframe runtime can
-//! reuse them.
+//! These helpers keep user-management and ban-list workflows available
+//! regardless of the selected networking adapter. The functions remain free
+//! of transport dependencies so both the legacy Tokio loop and the Wireframe
+//! runtime can reuse them.
 
 #![expect(
     clippy::print_stdout,
     reason = "intentional user output for CLI commands"
 )]
 
+use std::net::IpAddr;
+
 use anyhow::{Context, Result, anyhow};
 use argon2::{Algorithm, Argon2, ParamsBuilder, Version};
+use chrono::{NaiveDateTime, TimeDelta, Utc};
 use diesel_async::AsyncConnection;
 use ortho_config::load_and_merge_subcommand_for;
 
-use super::{AppConfig, Commands, CreateUserArgs};
+use super::{
+    AppConfig,
+    BanArgs,
+    BanTargetArgs,
+    Commands,
+    CreateUserArgs,
+    CreditsArgs,
+    DbCommand,
+    DropBoxArgs,
+    FilesCommand,
+    MigrateArgs,
+    NewsCommand,
+    NewsDigestArgs,
+    NewsFsckArgs,
+    QuotaArgs,
+    UsersCommand,
+    bans::ban_clock,
+    init::run_init,
+    news_digest::is_email_address,
+    news_fsck::log_link_repairs,
+    news_roots::run_news_roots,
+};
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+use crate::db::run_online_migrations;
 use crate::{
-    db::{DbConnection, apply_migrations, create_user},
-    models,
+    db::{
+        BanTarget,
+        DbConnection,
+        DigestSubscription,
+        NewsDigestSubscriber,
+        NewsItem,
+        TransferStats,
+        adjust_download_credits,
+        apply_migrations,
+        check_news_linkage,
+        create_ban,
+        create_user,
+        find_news_item,
+        get_download_credits,
+        get_news_digest,
+        get_user_by_name,
+        list_bans,
+        list_transfer_stats,
+        remove_ban,
+        repair_news_linkage,
+        run_maintenance,
+        set_download_credits,
+        set_drop_box,
+        set_quota_bytes,
+        stored_bytes_for_user,
+        subscribe_news_digest,
+        unsubscribe_news_digest,
+    },
+    models::{self, Ban},
     users::hash_password,
 };
 
@@ -29,6 +82,7 @@ use crate::{
 /// Propagates failures from configuration merging or database operations.
 pub async fn run_command(command: Commands, cfg: &AppConfig) -> Result<()> {
     match command {
+        Commands::Init(args) => run_init(args, cfg).await,
         Commands::CreateUser(args) => {
             let cli_args = args;
             let mut merged = load_and_merge_subcommand_for::<CreateUserArgs>(&cli_args)?;
@@ -38,6 +92,18 @@ pub async fn run_command(command: Commands, cfg: &AppConfig) -> Result<()> {
             }
             run_create_user(merged, cfg).await
         }
+        Commands::Ban(args) => run_ban(args, cfg).await,
+        Commands::Unban(args) => run_unban(args, cfg).await,
+        Commands::ListBans => run_list_bans(cfg).await,
+        Commands::Users(UsersCommand::Stats) => run_users_stats(cfg).await,
+        Commands::Users(UsersCommand::Credits(args)) => run_users_credits(args, cfg).await,
+        Commands::Users(UsersCommand::Quota(args)) => run_users_quota(args, cfg).await,
+        Commands::Migrate(args) => run_migrate(args, cfg).await,
+        Commands::Db(DbCommand::Maintain) => run_db_maintain(cfg).await,
+        Commands::News(NewsCommand::Fsck(args)) => run_news_fsck(args, cfg).await,
+        Commands::News(NewsCommand::Digest(args)) => run_news_digest(args, cfg).await,
+        Commands::News(NewsCommand::Roots(args)) => run_news_roots(args, cfg).await,
+        Commands::Files(FilesCommand::DropBox(args)) => run_files_drop_box(args, cfg).await,
     }
 }
 
@@ -71,8 +137,7 @@ async fn run_create_user(args: CreateUserArgs, cfg: &AppConfig) -> Result<()> {
         username: &username,
         password: &hashed,
     };
-    let mut conn = DbConnection::establish(&cfg.database).await?;
-    apply_migrations(&mut conn, &cfg.database, cfg.migration_timeout_secs).await?;
+    let mut conn = open_database(cfg).await?;
     create_user(&mut conn, &new_user)
         .await
         .with_context(|| format!("failed to create user '{username}'"))?;
@@ -80,48 +145,277 @@ async fn run_create_user(args: CreateUserArgs, cfg: &AppConfig) -> Result<()> {
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    //! Tests for this module.
-    use rstest::rstest;
-
-    use super::*;
-
-    #[rstest]
-    fn argon2_respects_cli_overrides() {
-        let cfg = AppConfig {
-            argon2_m_cost: 1024,
-            argon2_t_cost: 5,
-            argon2_p_cost: 3,
-            ..AppConfig::default()
+async fn run_ban(args: BanArgs, cfg: &AppConfig) -> Result<()> {
+    let target = ban_target(args.target)?;
+    let expires_at = ban_expiry(ban_clock(), args.expires_in_secs)?;
+    let mut conn = open_database(cfg).await?;
+    create_ban(&mut conn, &target, args.reason.as_deref(), expires_at)
+        .await
+        .with_context(|| format!("failed to ban {target}"))?;
+    println!("Banned {target}");
+    Ok(())
+}
+
+async fn run_unban(args: BanTargetArgs, cfg: &AppConfig) -> Result<()> {
+    let target = ban_target(args)?;
+    let mut conn = open_database(cfg).await?;
+    let removed = remove_ban(&mut conn, &target)
+        .await
+        .with_context(|| format!("failed to unban {target}"))?;
+    if removed == 0 {
+        return Err(anyhow!("no ban recorded for {target}"));
+    }
+    println!("Unbanned {target}");
+    Ok(())
+}
+
+async fn run_list_bans(cfg: &AppConfig) -> Result<()> {
+    let mut conn = open_database(cfg).await?;
+    let bans = list_bans(&mut conn).await.context("failed to list bans")?;
+    let now = ban_clock();
+    for ban in &bans {
+        println!("{}", describe_ban(ban, now));
+    }
+    Ok(())
+}
+
+async fn run_users_stats(cfg: &AppConfig) -> Result<()> {
+    let mut conn = open_database(cfg).await?;
+    let report = list_transfer_stats(&mut conn)
+        .await
+        .context("failed to list transfer statistics")?;
+    println!("user\tuploaded\tuploads\tdownloaded\tdownloads");
+    for (username, stats) in &report {
+        println!("{}", describe_transfer_stats(username, stats));
+    }
+    Ok(())
+}
+
+async fn run_users_credits(args: CreditsArgs, cfg: &AppConfig) -> Result<()> {
+    let username = args.username;
+    let mut conn = open_database(cfg).await?;
+    let user = get_user_by_name(&mut conn, &username)
+        .await
+        .with_context(|| format!("failed to look up user '{username}'"))?
+        .ok_or_else(|| anyhow!("no user named '{username}'"))?;
+    let changed = match (args.set, args.add) {
+        (Some(credits), _) => set_download_credits(&mut conn, user.id, credits).await,
+        (None, Some(delta)) => adjust_download_credits(&mut conn, user.id, delta).await,
+        (None, None) => Ok(0),
+    };
+    changed.with_context(|| format!("failed to change download credits for '{username}'"))?;
+    let credits = get_download_credits(&mut conn, user.id)
+        .await
+        .with_context(|| format!("failed to read download credits for '{username}'"))?;
+    println!("{username} has {credits} download credits");
+    Ok(())
+}
+
+async fn run_users_quota(args: QuotaArgs, cfg: &AppConfig) -> Result<()> {
+    let username = args.username;
+    let mut conn = open_database(cfg).await?;
+    if args.set.is_some() || args.clear {
+        let changed = set_quota_bytes(&mut conn, &username, args.set)
+            .await
+            .with_context(|| format!("failed to change the quota for '{username}'"))?;
+        if changed == 0 {
+            return Err(anyhow!("no user named '{username}'"));
+        }
+    }
+    let user = get_user_by_name(&mut conn, &username)
+        .await
+        .with_context(|| format!("failed to look up user '{username}'"))?
+        .ok_or_else(|| anyhow!("no user named '{username}'"))?;
+    let stored = stored_bytes_for_user(&mut conn, user.id)
+        .await
+        .with_context(|| format!("failed to count stored bytes for '{username}'"))?;
+    println!("{}", describe_quota(&username, stored, user.quota_bytes));
+    Ok(())
+}
+
+async fn run_db_maintain(cfg: &AppConfig) -> Result<()> {
+    let mut conn = open_database(cfg).await?;
+    let steps = run_maintenance(&mut conn)
+        .await
+        .context("database maintenance failed")?;
+    for step in &steps {
+        println!("{}\t{} ms", step.statement, step.elapsed.as_millis());
+    }
+    Ok(())
+}
+
+async fn run_news_fsck(args: NewsFsckArgs, cfg: &AppConfig) -> Result<()> {
+    let mut conn = open_database(cfg).await?;
+    let repairs = if args.dry_run {
+        check_news_linkage(&mut conn).await
+    } else {
+        repair_news_linkage(&mut conn).await
+    }
+    .context("failed to check news article links")?;
+    for repair in &repairs {
+        println!("{repair}");
+    }
+    if !args.dry_run {
+        log_link_repairs(&repairs);
+    }
+    let verb = if args.dry_run { "needed" } else { "made" };
+    println!("{} repairs {verb}", repairs.len());
+    Ok(())
+}
+
+async fn run_news_digest(args: NewsDigestArgs, cfg: &AppConfig) -> Result<()> {
+    let username = args.username;
+    let mut conn = open_database(cfg).await?;
+    let user = get_user_by_name(&mut conn, &username)
+        .await
+        .with_context(|| format!("failed to look up user '{username}'"))?
+        .ok_or_else(|| anyhow!("no user named '{username}'"))?;
+    let email = args.email.as_deref();
+    if let Some(address) = email.filter(|address| !is_email_address(address)) {
+        return Err(anyhow!("'{address}' is not an email address"));
+    }
+    let changed = if args.off {
+        unsubscribe_news_digest(&mut conn, user.id).await.map(drop)
+    } else if let Some(paths) = args.categories.as_deref() {
+        let category_ids = digest_categories(&mut conn, paths).await?;
+        let now = Utc::now().naive_utc();
+        let subscription = DigestSubscription {
+            category_ids: &category_ids,
+            email,
         };
+        subscribe_news_digest(&mut conn, user.id, subscription, now).await
+    } else {
+        Ok(())
+    };
+    changed.with_context(|| format!("failed to change the news digest for '{username}'"))?;
+    let digest = get_news_digest(&mut conn, user.id)
+        .await
+        .with_context(|| format!("failed to read the news digest for '{username}'"))?;
+    println!("{}", describe_news_digest(&username, digest.as_ref()));
+    Ok(())
+}
+
+/// Resolve a comma-separated list of category paths to their identifiers.
+async fn digest_categories(conn: &mut DbConnection, paths: &str) -> Result<Vec<i32>> {
+    let mut category_ids = Vec::new();
+    for path in paths
+        .split(',')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+    {
+        match find_news_item(conn, path).await {
+            Ok(NewsItem::Category(id)) => category_ids.push(id),
+            Ok(NewsItem::Bundle(_)) => return Err(anyhow!("'{path}' is a bundle, not a category")),
+            Err(error) => return Err(anyhow!("no news category at '{path}': {error}")),
+        }
+    }
+    if category_ids.is_empty() {
+        return Err(anyhow!("no news categories named"));
+    }
+    Ok(category_ids)
+}
 
-        let argon2 = argon2_from_config(&cfg).expect("argon2");
-
-        let params = argon2.params();
-        assert_eq!(params.m_cost(), cfg.argon2_m_cost);
-        assert_eq!(params.t_cost(), cfg.argon2_t_cost);
-        assert_eq!(params.p_cost(), cfg.argon2_p_cost);
-    }
-
-    #[rstest]
-    #[case(None, Some("password".into()), "missing username")]
-    #[case(Some("user".into()), None, "missing password")]
-    #[tokio::test]
-    async fn run_command_rejects_missing_fields(
-        #[case] username: Option<String>,
-        #[case] password: Option<String>,
-        #[case] expected: &str,
-    ) {
-        let cfg = AppConfig::default();
-        let args = CreateUserArgs { username, password };
-
-        // Validate missing-field checks directly to avoid process-wide
-        // environment/config merge races during `cargo test` parallel runs.
-        let err = run_create_user(args, &cfg)
+async fn run_files_drop_box(args: DropBoxArgs, cfg: &AppConfig) -> Result<()> {
+    let path = args.path;
+    let mut conn = open_database(cfg).await?;
+    let found = set_drop_box(&mut conn, &path, !args.off)
+        .await
+        .with_context(|| format!("failed to change folder '{path}'"))?;
+    if !found {
+        return Err(anyhow!("no folder at '{path}'"));
+    }
+    if args.off {
+        println!("{path} is no longer a drop box");
+    } else {
+        println!("{path} is now a drop box");
+    }
+    Ok(())
+}
+
+async fn run_migrate(args: MigrateArgs, cfg: &AppConfig) -> Result<()> {
+    if args.online {
+        #[cfg(feature = "sqlite")]
+        return Err(anyhow!("online migrations need PostgreSQL"));
+        #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+        run_online_migrations(&cfg.database, cfg.migration_timeout_secs)
             .await
-            .expect_err("command must fail");
+            .context("online migration failed")?;
+    } else {
+        open_database(cfg).await?;
+    }
+    println!("Database is up to date");
+    Ok(())
+}
+
+pub(super) async fn open_database(cfg: &AppConfig) -> Result<DbConnection> {
+    let mut conn = DbConnection::establish(&cfg.database).await?;
+    apply_migrations(&mut conn, &cfg.database, cfg.migration_timeout_secs).await?;
+    Ok(conn)
+}
+
+fn ban_target(args: BanTargetArgs) -> Result<BanTarget> {
+    match (args.username, args.address) {
+        (Some(username), None) => Ok(BanTarget::Username(username)),
+        (None, Some(address)) => address
+            .parse::<IpAddr>()
+            .map(BanTarget::Address)
+            .with_context(|| format!("invalid address '{address}'")),
+        _ => Err(anyhow!("supply exactly one of --username or --address")),
+    }
+}
+
+fn ban_expiry(now: NaiveDateTime, expires_in_secs: Option<u64>) -> Result<Option<NaiveDateTime>> {
+    let Some(secs) = expires_in_secs else {
+        return Ok(None);
+    };
+    i64::try_from(secs)
+        .ok()
+        .and_then(TimeDelta::try_seconds)
+        .and_then(|lifetime| now.checked_add_signed(lifetime))
+        .map(Some)
+        .ok_or_else(|| anyhow!("expires_in_secs {secs} is too large"))
+}
+
+fn describe_ban(ban: &Ban, now: NaiveDateTime) -> String {
+    let status = match ban.expires_at {
+        None => "permanent".to_owned(),
+        Some(at) if at <= now => format!("expired {at}"),
+        Some(at) => format!("until {at}"),
+    };
+    let reason = ban.reason.as_deref().unwrap_or("-");
+    format!(
+        "{}\t{} {}\t{status}\t{reason}",
+        ban.id, ban.kind, ban.target
+    )
+}
+
+/// One `users stats` row: bytes and completed transfers in each direction.
+fn describe_transfer_stats(username: &str, stats: &TransferStats) -> String {
+    format!(
+        "{username}\t{}\t{}\t{}\t{}",
+        stats.bytes_uploaded, stats.uploads, stats.bytes_downloaded, stats.downloads
+    )
+}
 
-        assert!(err.to_string().contains(expected));
+/// One `news digest` line: whether and how the account gets digests.
+fn describe_news_digest(username: &str, digest: Option<&NewsDigestSubscriber>) -> String {
+    match digest {
+        None => format!("{username} gets no news digest"),
+        Some(NewsDigestSubscriber {
+            email: Some(email), ..
+        }) => format!("{username} gets news digests by email to {email}"),
+        Some(_) => format!("{username} gets news digests at login"),
     }
 }
+
+/// One `users quota` line: bytes stored against the account's limit.
+fn describe_quota(username: &str, stored: u64, quota_bytes: Option<i64>) -> String {
+    match quota_bytes {
+        Some(quota) => format!("{username} stores {stored} bytes of a {quota}-byte quota"),
+        None => format!("{username} stores {stored} bytes with no quota"),
+    }
+}
+
+#[cfg(test)]
+#[path = "admin_tests.rs"]
+mod tests;
@@ -0,0 +1,136 @@
+//! Unit tests for administrative command helpers.
+
+use rstest::rstest;
+
+use super::*;
+
+#[rstest]
+fn argon2_respects_cli_overrides() {
+    let cfg = AppConfig {
+        argon2_m_cost: 1024,
+        argon2_t_cost: 5,
+        argon2_p_cost: 3,
+        ..AppConfig::default()
+    };
+
+    let argon2 = argon2_from_config(&cfg).expect("argon2");
+
+    let params = argon2.params();
+    assert_eq!(params.m_cost(), cfg.argon2_m_cost);
+    assert_eq!(params.t_cost(), cfg.argon2_t_cost);
+    assert_eq!(params.p_cost(), cfg.argon2_p_cost);
+}
+
+#[rstest]
+#[case(None, Some("password".into()), "missing username")]
+#[case(Some("user".into()), None, "missing password")]
+#[tokio::test]
+async fn run_command_rejects_missing_fields(
+    #[case] username: Option<String>,
+    #[case] password: Option<String>,
+    #[case] expected: &str,
+) {
+    let cfg = AppConfig::default();
+    let args = CreateUserArgs { username, password };
+
+    // Validate missing-field checks directly to avoid process-wide
+    // environment/config merge races during `cargo test` parallel runs.
+    let err = run_create_user(args, &cfg)
+        .await
+        .expect_err("command must fail");
+
+    assert!(err.to_string().contains(expected));
+}
+
+#[rstest]
+#[case(Some("alice"), None, Some(BanTarget::Username("alice".to_owned())))]
+#[case(None, Some("::ffff:192.0.2.9"), Some(BanTarget::Address("::ffff:192.0.2.9".parse().expect("address"))))]
+#[case(None, Some("not-an-address"), None)]
+#[case(None, None, None)]
+fn ban_target_requires_one_valid_target(
+    #[case] username: Option<&str>,
+    #[case] address: Option<&str>,
+    #[case] expected: Option<BanTarget>,
+) {
+    let args = BanTargetArgs {
+        username: username.map(str::to_owned),
+        address: address.map(str::to_owned),
+    };
+
+    assert_eq!(ban_target(args).ok(), expected);
+}
+
+#[rstest]
+fn ban_expiry_is_relative_to_now() {
+    let now = ban_clock();
+
+    assert_eq!(ban_expiry(now, None).expect("no expiry"), None);
+    assert_eq!(
+        ban_expiry(now, Some(90)).expect("expiry"),
+        Some(now + TimeDelta::seconds(90))
+    );
+    assert!(ban_expiry(now, Some(u64::MAX)).is_err());
+}
+
+#[rstest]
+fn describe_ban_reports_expiry_state() {
+    let now = ban_clock();
+    let mut ban = Ban {
+        id: 3,
+        kind: "username".to_owned(),
+        target: "alice".to_owned(),
+        reason: Some("spam".to_owned()),
+        created_at: now,
+        expires_at: None,
+    };
+    assert_eq!(
+        describe_ban(&ban, now),
+        "3\tusername alice\tpermanent\tspam"
+    );
+
+    ban.expires_at = Some(now);
+    ban.reason = None;
+    assert!(describe_ban(&ban, now).contains("\texpired "));
+    assert!(describe_ban(&ban, now).ends_with("\t-"));
+}
+
+#[rstest]
+fn describe_transfer_stats_lists_both_directions() {
+    let stats = TransferStats {
+        bytes_uploaded: 400,
+        bytes_downloaded: 1_000,
+        uploads: 1,
+        downloads: 3,
+    };
+
+    assert_eq!(
+        describe_transfer_stats("alice", &stats),
+        "alice\t400\t1\t1000\t3"
+    );
+}
+
+#[rstest]
+#[case(None, "alice stores 512 bytes with no quota")]
+#[case(Some(4_096), "alice stores 512 bytes of a 4096-byte quota")]
+fn describe_quota_reports_the_limit(#[case] quota_bytes: Option<i64>, #[case] expected: &str) {
+    assert_eq!(describe_quota("alice", 512, quota_bytes), expected);
+}
+
+#[rstest]
+#[case(None, "alice gets no news digest")]
+#[case(Some(None), "alice gets news digests at login")]
+#[case(
+    Some(Some("alice@example.org")),
+    "alice gets news digests by email to alice@example.org"
+)]
+fn describe_news_digest_reports_delivery(
+    #[case] email: Option<Option<&str>>,
+    #[case] expected: &str,
+) {
+    let digest = email.map(|email| NewsDigestSubscriber {
+        user_id: 1,
+        email: email.map(str::to_owned),
+        last_visit_at: NaiveDateTime::default(),
+    });
+    assert_eq!(describe_news_digest("alice", digest.as_ref()), expected);
+}
@@ -0,0 +1,325 @@
+//! Server agreement and banner offered to clients after login.
+//!
+//! Operators name an agreement text file with `agreement_path` and a banner
+//! image with `banner_path`. When a storage backend is configured the banner is
+//! read from it, with `banner_path` as its object key, so every server sharing
+//! the store serves the same image. Both are read once at startup by
+//! [`ServerAgreement::from_config`] and kept in the server's
+//! [`ServerSettings`](super::settings::ServerSettings). While an agreement is
+//! configured, a login leaves the session waiting for acceptance: the runtime
+//! follows the login reply with a Show Agreement push (109), and the account's
+//! privileges are only granted once the client answers with Agreed (121).
+
+use std::{fs, io, mem, path::PathBuf};
+
+use thiserror::Error;
+
+use crate::{
+    field_id::FieldId,
+    handler::Session,
+    presence::server_notification,
+    server::AppConfig,
+    storage::{Storage, StorageError},
+    transaction::{Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// Agreement text and banner image served to clients.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerAgreement {
+    text: Option<Vec<u8>>,
+    banner: Option<Vec<u8>>,
+}
+
+/// Errors raised while loading the agreement or banner files.
+#[derive(Debug, Error)]
+pub enum AgreementError {
+    /// A configured file could not be read.
+    #[error("failed to read {kind} file {}: {source}", .path.display())]
+    Read {
+        /// Which file was being read.
+        kind: &'static str,
+        /// Path that failed.
+        path: PathBuf,
+        /// Underlying I/O error.
+        source: io::Error,
+    },
+    /// The banner could not be read from the storage backend.
+    #[error("failed to read banner object {key} from storage: {source}")]
+    Storage {
+        /// Object key that failed.
+        key: String,
+        /// Underlying storage error.
+        source: StorageError,
+    },
+    /// A configured file does not fit in a single transaction field.
+    #[error("{kind} file {} is larger than {} bytes", .path.display(), u16::MAX)]
+    TooLarge {
+        /// Which file was being read.
+        kind: &'static str,
+        /// Path that was too large.
+        path: PathBuf,
+    },
+}
+
+impl ServerAgreement {
+    /// Build an agreement from in-memory content.
+    ///
+    /// Empty content is treated as absent.
+    #[must_use]
+    pub fn new(text: Option<Vec<u8>>, banner: Option<Vec<u8>>) -> Self {
+        Self {
+            text: text.filter(|bytes| !bytes.is_empty()),
+            banner: banner.filter(|bytes| !bytes.is_empty()),
+        }
+    }
+
+    /// Read the files named by `agreement_path` and `banner_path`, taking
+    /// the banner from `storage` when a backend is given.
+    ///
+    /// Unset paths leave the matching content absent.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AgreementError`] if a configured file cannot be read or is
+    /// too large to send in one field.
+    pub async fn from_config(
+        config: &AppConfig,
+        storage: Option<&dyn Storage>,
+    ) -> Result<Self, AgreementError> {
+        let text = read_optional("agreement", config.agreement_path.as_deref())?;
+        let banner = match (storage, config.banner_path.as_deref()) {
+            (Some(backend), Some(key)) => Some(read_stored_banner(backend, key).await?),
+            (None, path) => read_optional("banner", path)?,
+            (Some(_), None) => None,
+        };
+        Ok(Self::new(text, banner))
+    }
+
+    /// Agreement text clients must accept, if one is configured.
+    #[must_use]
+    pub fn text(&self) -> Option<&[u8]> { self.text.as_deref() }
+
+    /// Banner image served by Download Banner (212), if one is configured.
+    #[must_use]
+    pub fn banner(&self) -> Option<&[u8]> { self.banner.as_deref() }
+
+    /// Build the Show Agreement push, or `None` when no agreement is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an encoding error if the text exceeds protocol limits.
+    pub fn show_agreement(&self) -> Result<Option<Transaction>, TransactionError> {
+        let Some(text) = self.text() else {
+            return Ok(None);
+        };
+        let payload = encode_params(&[(FieldId::Data, text)])?;
+        Ok(Some(server_notification(
+            TransactionType::Agreement,
+            payload,
+        )))
+    }
+}
+
+/// Return the Show Agreement push owed to `session`, clearing its
+/// [`Session::show_agreement`] flag.
+///
+/// Runtimes call this after writing each reply, and send the push when one is
+/// returned.
+///
+/// # Errors
+///
+/// Returns an encoding error if the agreement text exceeds protocol limits.
+pub fn take_agreement_push(
+    session: &mut Session,
+    agreement: &ServerAgreement,
+) -> Result<Option<Transaction>, TransactionError> {
+    if !mem::take(&mut session.show_agreement) {
+        return Ok(None);
+    }
+    agreement.show_agreement()
+}
+
+fn read_optional(
+    kind: &'static str,
+    path: Option<&str>,
+) -> Result<Option<Vec<u8>>, AgreementError> {
+    let Some(configured) = path else {
+        return Ok(None);
+    };
+    let file = PathBuf::from(configured);
+    let bytes = fs::read(&file).map_err(|source| AgreementError::Read {
+        kind,
+        path: file.clone(),
+        source,
+    })?;
+    if bytes.len() > usize::from(u16::MAX) {
+        return Err(AgreementError::TooLarge { kind, path: file });
+    }
+    Ok(Some(bytes))
+}
+
+async fn read_stored_banner(backend: &dyn Storage, key: &str) -> Result<Vec<u8>, AgreementError> {
+    let bytes = backend
+        .get(key)
+        .await
+        .map_err(|source| AgreementError::Storage {
+            key: key.to_owned(),
+            source,
+        })?;
+    if bytes.len() > usize::from(u16::MAX) {
+        return Err(AgreementError::TooLarge {
+            kind: "banner",
+            path: PathBuf::from(key),
+        });
+    }
+    Ok(bytes.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    //! Loading agreement content from configuration.
+
+    use std::io::Write;
+
+    use bytes::Bytes;
+    use rstest::rstest;
+    use tempfile::NamedTempFile;
+
+    use super::*;
+    use crate::{storage::LocalStorage, transaction::decode_params};
+
+    fn temp_file(contents: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().expect("temp file");
+        file.write_all(contents).expect("write temp file");
+        file
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn unset_paths_load_nothing() {
+        let agreement = ServerAgreement::from_config(&AppConfig::default(), None)
+            .await
+            .expect("load");
+        assert_eq!(agreement, ServerAgreement::default());
+        assert!(agreement.show_agreement().expect("encode").is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn reads_configured_files() {
+        let text = temp_file(b"Be excellent to each other.");
+        let banner = temp_file(b"GIF89a");
+        let config = AppConfig {
+            agreement_path: Some(text.path().display().to_string()),
+            banner_path: Some(banner.path().display().to_string()),
+            ..AppConfig::default()
+        };
+        let agreement = ServerAgreement::from_config(&config, None)
+            .await
+            .expect("load");
+        assert_eq!(agreement.text(), Some(&b"Be excellent to each other."[..]));
+        assert_eq!(agreement.banner(), Some(&b"GIF89a"[..]));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn empty_agreement_file_counts_as_absent() {
+        let text = temp_file(b"");
+        let config = AppConfig {
+            agreement_path: Some(text.path().display().to_string()),
+            ..AppConfig::default()
+        };
+        let agreement = ServerAgreement::from_config(&config, None)
+            .await
+            .expect("load");
+        assert!(agreement.text().is_none());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn rejects_missing_and_oversized_files() {
+        let missing = AppConfig {
+            agreement_path: Some("/nonexistent/mxd-agreement.txt".to_owned()),
+            ..AppConfig::default()
+        };
+        assert!(matches!(
+            ServerAgreement::from_config(&missing, None).await,
+            Err(AgreementError::Read {
+                kind: "agreement",
+                ..
+            })
+        ));
+        let huge = temp_file(&vec![0u8; usize::from(u16::MAX) + 1]);
+        let oversized = AppConfig {
+            banner_path: Some(huge.path().display().to_string()),
+            ..AppConfig::default()
+        };
+        assert!(matches!(
+            ServerAgreement::from_config(&oversized, None).await,
+            Err(AgreementError::TooLarge { kind: "banner", .. })
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn reads_the_banner_from_storage() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let backend =
+            LocalStorage::open(dir.path().to_str().expect("utf-8 path")).expect("store opens");
+        backend
+            .put("branding/banner.gif", Bytes::from_static(b"GIF89a"))
+            .await
+            .expect("banner stored");
+        let config = AppConfig {
+            banner_path: Some("branding/banner.gif".to_owned()),
+            ..AppConfig::default()
+        };
+        let agreement = ServerAgreement::from_config(&config, Some(&backend))
+            .await
+            .expect("load");
+        assert_eq!(agreement.banner(), Some(&b"GIF89a"[..]));
+
+        let missing = AppConfig {
+            banner_path: Some("branding/missing.gif".to_owned()),
+            ..AppConfig::default()
+        };
+        assert!(matches!(
+            ServerAgreement::from_config(&missing, Some(&backend)).await,
+            Err(AgreementError::Storage { .. })
+        ));
+    }
+
+    #[rstest]
+    fn show_agreement_carries_text_in_data_field() {
+        let agreement = ServerAgreement::new(Some(b"Rules".to_vec()), None);
+        let push = agreement
+            .show_agreement()
+            .expect("encode")
+            .expect("agreement push");
+        assert_eq!(push.header.ty, u16::from(TransactionType::Agreement));
+        assert_eq!(push.header.is_reply, 0);
+        let params = decode_params(&push.payload).expect("decode");
+        assert_eq!(params, vec![(FieldId::Data, b"Rules".to_vec())]);
+    }
+
+    #[rstest]
+    fn agreement_push_is_taken_once() {
+        let agreement = ServerAgreement::new(Some(b"Rules".to_vec()), None);
+        let mut session = Session {
+            show_agreement: true,
+            ..Session::default()
+        };
+        assert!(
+            take_agreement_push(&mut session, &agreement)
+                .expect("encode")
+                .is_some()
+        );
+        assert!(!session.show_agreement);
+        assert!(
+            take_agreement_push(&mut session, &agreement)
+                .expect("encode")
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! Scheduled archive snapshots of file-area folders.
+//!
+//! Operators list folders in `archive_folders`. Each is snapshotted into its
+//! read-only `<folder> Archive` sibling once every `archive_interval_secs`,
+//! keeping the newest `archive_keep` snapshots; see
+//! [`crate::db::snapshot_folder`].
+//! The scheduler wakes every [`ARCHIVE_CHECK_INTERVAL`] and compares each
+//! folder's last snapshot time, stored in the database, with the interval,
+//! so restarting the server neither skips nor repeats a snapshot.
+
+use std::{num::NonZeroUsize, time::Duration};
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use thiserror::Error;
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{info, warn};
+
+use super::{
+    AppConfig,
+    chaos::{ChaosPoint, chaos_point},
+};
+use crate::db::{
+    DbPool,
+    SnapshotOutcome,
+    last_snapshot_at,
+    resolve_file_node_path,
+    snapshot_folder,
+};
+
+/// Time between snapshots of a folder when none is configured.
+pub const DEFAULT_ARCHIVE_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// Snapshots kept per folder when no limit is configured.
+pub const DEFAULT_ARCHIVE_KEEP: NonZeroUsize = NonZeroUsize::MIN.saturating_add(6);
+
+/// How often the scheduler checks for folders due a snapshot.
+pub const ARCHIVE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Which folders to snapshot, how often, and how many snapshots to keep.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveSchedule {
+    /// Slash-separated paths of the folders to snapshot.
+    pub folders: Vec<String>,
+    /// Time between snapshots of each folder.
+    pub interval: Duration,
+    /// Snapshots kept for each folder.
+    pub keep: NonZeroUsize,
+}
+
+/// Errors raised while reading the archive schedule from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ArchiveScheduleError {
+    /// `archive_interval_secs` was zero.
+    #[error("archive_interval_secs must be greater than zero")]
+    ZeroInterval,
+    /// `archive_keep` was zero.
+    #[error("archive_keep must be greater than zero")]
+    ZeroKeep,
+}
+
+impl ArchiveSchedule {
+    /// Read the schedule from `config`; `None` when no folders are listed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ArchiveScheduleError`] for a zero interval or retention
+    /// limit.
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>, ArchiveScheduleError> {
+        let interval = config
+            .archive_interval_secs
+            .map_or(DEFAULT_ARCHIVE_INTERVAL, Duration::from_secs);
+        if interval.is_zero() {
+            return Err(ArchiveScheduleError::ZeroInterval);
+        }
+        let keep = match config.archive_keep {
+            None => DEFAULT_ARCHIVE_KEEP,
+            Some(raw) => usize::try_from(raw)
+                .ok()
+                .and_then(NonZeroUsize::new)
+                .ok_or(ArchiveScheduleError::ZeroKeep)?,
+        };
+        let folders: Vec<String> = config
+            .archive_folders
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(|path| path.trim().trim_matches('/'))
+            .filter(|path| !path.is_empty())
+            .map(str::to_owned)
+            .collect();
+        if folders.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            folders,
+            interval,
+            keep,
+        }))
+    }
+
+    /// Return whether a folder last snapshotted at `last` is due another
+    /// snapshot at `now`.
+    #[must_use]
+    pub fn is_due(&self, last: Option<NaiveDateTime>, now: NaiveDateTime) -> bool {
+        let interval = TimeDelta::from_std(self.interval).unwrap_or(TimeDelta::MAX);
+        last.is_none_or(|at| now.signed_duration_since(at) >= interval)
+    }
+}
+
+/// Snapshot the folders in `schedule` whenever they fall due, until the
+/// returned task is aborted, or return `None` when no folders are scheduled.
+///
+/// Failures are logged and retried at the next check.
+#[must_use]
+pub fn start_archive_snapshots(
+    pool: DbPool,
+    schedule: Option<ArchiveSchedule>,
+) -> Option<JoinHandle<()>> {
+    let schedule = schedule?;
+    Some(tokio::spawn(async move {
+        loop {
+            chaos_point(ChaosPoint::ArchiveSnapshot).await;
+            for folder in &schedule.folders {
+                if let Err(error) = snapshot_if_due(&pool, &schedule, folder).await {
+                    warn!(%error, folder, "archive snapshot failed");
+                }
+            }
+            sleep(ARCHIVE_CHECK_INTERVAL).await;
+        }
+    }))
+}
+
+async fn snapshot_if_due(pool: &DbPool, schedule: &ArchiveSchedule, folder: &str) -> Result<()> {
+    let mut conn = pool.get().await?;
+    let Some(node) = resolve_file_node_path(&mut conn, folder).await? else {
+        warn!(folder, "archive folder not found");
+        return Ok(());
+    };
+    let now = Utc::now().naive_utc();
+    if !schedule.is_due(last_snapshot_at(&mut conn, node.id).await?, now) {
+        return Ok(());
+    }
+    match snapshot_folder(&mut conn, node.id, now, schedule.keep.get()).await? {
+        SnapshotOutcome::Taken { name, pruned } => {
+            info!(folder, snapshot = %name, pruned, "archive snapshot taken");
+        }
+        SnapshotOutcome::AlreadyTaken => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for archive schedule parsing.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn schedule(folders: &[&str], interval_secs: u64, keep: usize) -> ArchiveSchedule {
+        ArchiveSchedule {
+            folders: folders.iter().map(|&folder| folder.to_owned()).collect(),
+            interval: Duration::from_secs(interval_secs),
+            keep: NonZeroUsize::new(keep).expect("non-zero keep"),
+        }
+    }
+
+    #[rstest]
+    #[case::unset(None, None, None, Ok(None))]
+    #[case::blank(Some(" , "), None, None, Ok(None))]
+    #[case::defaults(Some("Uploads"), None, None, Ok(Some(schedule(&["Uploads"], 86_400, 7))))]
+    #[case::custom(
+        Some(" /Uploads/ , Docs/Specs,"),
+        Some(3_600),
+        Some(3),
+        Ok(Some(schedule(&["Uploads", "Docs/Specs"], 3_600, 3)))
+    )]
+    #[case::zero_interval(
+        Some("Uploads"),
+        Some(0),
+        None,
+        Err(ArchiveScheduleError::ZeroInterval)
+    )]
+    #[case::zero_keep(Some("Uploads"), None, Some(0), Err(ArchiveScheduleError::ZeroKeep))]
+    fn parses_schedule_from_config(
+        #[case] folders: Option<&str>,
+        #[case] interval_secs: Option<u64>,
+        #[case] keep: Option<u32>,
+        #[case] expected: Result<Option<ArchiveSchedule>, ArchiveScheduleError>,
+    ) {
+        let config = AppConfig {
+            archive_folders: folders.map(str::to_owned),
+            archive_interval_secs: interval_secs,
+            archive_keep: keep,
+            ..AppConfig::default()
+        };
+        assert_eq!(ArchiveSchedule::from_config(&config), expected);
+    }
+
+    #[rstest]
+    #[case::never_taken(None, true)]
+    #[case::recent(Some(59), false)]
+    #[case::due(Some(60), true)]
+    fn snapshots_fall_due_after_the_interval(
+        #[case] minutes_ago: Option<i64>,
+        #[case] expected: bool,
+    ) {
+        let now = Utc::now().naive_utc();
+        let last = minutes_ago.map(|minutes| now - TimeDelta::minutes(minutes));
+        assert_eq!(schedule(&["Uploads"], 3_600, 1).is_due(last, now), expected);
+    }
+}
@@ -0,0 +1,257 @@
+//! Ban list enforcement shared by both runtimes.
+//!
+//! Bans live in the database so the `ban` and `unban` subcommands and the
+//! Disconnect User transaction all act on the same list. Login queries the
+//! database for bans on the account name and the peer address. The accept
+//! path cannot afford a database round trip per connection, so each server
+//! keeps a copy of the address bans in its settings, loaded at startup and
+//! refreshed every [`BAN_REFRESH_INTERVAL`], and refuses banned peers before
+//! completing the handshake.
+
+use std::{
+    collections::BTreeMap,
+    net::IpAddr,
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, TimeDelta, Utc};
+use diesel::result::QueryResult;
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{debug, warn};
+
+use crate::{
+    db::{BanTarget, DbConnection, DbPool, find_active_ban, list_active_address_bans},
+    models::Ban,
+    server::chaos::{ChaosPoint, chaos_point},
+};
+
+/// Reason sent in the Disconnect Message to a banned client.
+pub const BAN_REASON: &str = "You are banned from this server.";
+
+/// How long a temporary ban set through Disconnect User lasts.
+pub const TEMPORARY_BAN: TimeDelta = TimeDelta::minutes(30);
+
+/// How often the runtimes reload address bans from the database.
+pub const BAN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Disconnect User (110) option asking for a temporary ban.
+pub const BAN_OPTION_TEMPORARY: u32 = 1;
+
+/// Disconnect User (110) option asking for a permanent ban.
+pub const BAN_OPTION_PERMANENT: u32 = 2;
+
+/// Ban requested alongside a Disconnect User transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BanLength {
+    /// Lift the ban after [`TEMPORARY_BAN`].
+    Temporary,
+    /// Keep the ban until an operator removes it.
+    Permanent,
+}
+
+impl BanLength {
+    /// Decode the Options field (113) of a Disconnect User request.
+    ///
+    /// Returns `None` for a plain disconnect.
+    #[must_use]
+    pub const fn from_options(options: u32) -> Option<Self> {
+        match options {
+            BAN_OPTION_TEMPORARY => Some(Self::Temporary),
+            BAN_OPTION_PERMANENT => Some(Self::Permanent),
+            _ => None,
+        }
+    }
+
+    /// Expiry for a ban of this length starting at `now`, or `None` when
+    /// the ban never expires.
+    #[must_use]
+    pub fn expires_at(self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Self::Temporary => now.checked_add_signed(TEMPORARY_BAN),
+            Self::Permanent => None,
+        }
+    }
+}
+
+/// Current time as compared against ban expiries.
+#[must_use]
+pub fn ban_clock() -> NaiveDateTime { Utc::now().naive_utc() }
+
+/// Address bans cached for the accept path: banned addresses mapped to
+/// their expiry, or `None` for permanent bans.
+#[derive(Debug, Default)]
+pub struct AddressBans {
+    bans: RwLock<BTreeMap<IpAddr, Option<NaiveDateTime>>>,
+}
+
+impl AddressBans {
+    /// Replace the cached ban list.
+    pub fn replace(&self, bans: BTreeMap<IpAddr, Option<NaiveDateTime>>) {
+        *self.bans.write().unwrap_or_else(PoisonError::into_inner) = bans;
+    }
+
+    /// Return `true` when connections from `addr` should be refused.
+    #[must_use]
+    pub fn is_banned(&self, addr: IpAddr) -> bool {
+        let bans = self.bans.read().unwrap_or_else(PoisonError::into_inner);
+        bans.get(&addr.to_canonical())
+            .is_some_and(|expiry| expiry.is_none_or(|at| at > ban_clock()))
+    }
+
+    /// Reload the cached ban list from the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns any error raised while acquiring a connection or querying
+    /// bans.
+    pub async fn refresh(&self, pool: &DbPool) -> Result<usize> {
+        let mut conn = pool.get().await?;
+        let bans = list_active_address_bans(&mut conn, ban_clock()).await?;
+        let count = bans.len();
+        self.replace(bans.into_iter().collect());
+        Ok(count)
+    }
+}
+
+/// Load `bans` from the database, then keep reloading them in the
+/// background until the returned task is aborted.
+///
+/// A failed load is logged and leaves the previous list in place, so a
+/// database outage degrades to admitting connections that login would
+/// still refuse.
+pub async fn start_ban_refresh(pool: DbPool, bans: Arc<AddressBans>) -> JoinHandle<()> {
+    reload(&pool, &bans).await;
+    tokio::spawn(async move {
+        loop {
+            sleep(BAN_REFRESH_INTERVAL).await;
+            reload(&pool, &bans).await;
+        }
+    })
+}
+
+async fn reload(pool: &DbPool, bans: &AddressBans) {
+    chaos_point(ChaosPoint::BanRefresh).await;
+    match bans.refresh(pool).await {
+        Ok(count) => debug!(count, "reloaded address bans"),
+        Err(error) => warn!(%error, "failed to reload address bans"),
+    }
+}
+
+/// Find a ban in force against `username` or `addr`, checking the account
+/// first.
+///
+/// # Errors
+///
+/// Returns any error produced by the ban list queries.
+pub async fn find_login_ban(
+    conn: &mut DbConnection,
+    username: &str,
+    addr: IpAddr,
+) -> QueryResult<Option<Ban>> {
+    let now = ban_clock();
+    let account = BanTarget::Username(username.to_owned());
+    if let Some(ban) = find_active_ban(conn, &account, now).await? {
+        return Ok(Some(ban));
+    }
+    find_active_ban(conn, &BanTarget::Address(addr), now).await
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for Disconnect User ban options and the address ban cache.
+    use rstest::rstest;
+    use test_util::{AnyError, build_test_db};
+    use tokio::runtime::Runtime;
+
+    use super::*;
+    use crate::{
+        db::create_ban,
+        server::chaos::{ChaosPoint, ChaosSettings, chaos_delays, enable_chaos},
+    };
+
+    #[rstest]
+    #[case(0, None)]
+    #[case(BAN_OPTION_TEMPORARY, Some(BanLength::Temporary))]
+    #[case(BAN_OPTION_PERMANENT, Some(BanLength::Permanent))]
+    #[case(3, None)]
+    fn decodes_disconnect_options(#[case] options: u32, #[case] expected: Option<BanLength>) {
+        assert_eq!(BanLength::from_options(options), expected);
+    }
+
+    #[rstest]
+    fn only_temporary_bans_expire() {
+        let now = ban_clock();
+        assert_eq!(
+            BanLength::Temporary.expires_at(now),
+            Some(now + TEMPORARY_BAN)
+        );
+        assert_eq!(BanLength::Permanent.expires_at(now), None);
+    }
+
+    #[rstest]
+    fn address_cache_honours_expiry_and_mapped_addresses() {
+        let permanent: IpAddr = "192.0.2.1".parse().expect("address");
+        let lapsed: IpAddr = "192.0.2.2".parse().expect("address");
+        let mapped: IpAddr = "::ffff:192.0.2.1".parse().expect("address");
+        let past = ban_clock() - TimeDelta::minutes(1);
+        let bans = AddressBans::default();
+        bans.replace(BTreeMap::from([(permanent, None), (lapsed, Some(past))]));
+
+        assert!(bans.is_banned(permanent));
+        assert!(bans.is_banned(mapped));
+        assert!(!bans.is_banned(lapsed));
+        bans.replace(BTreeMap::new());
+        assert!(!bans.is_banned(permanent));
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(4)]
+    #[serial_test::serial(chaos)]
+    #[serial_test::file_serial(postgres_embedded_setup)]
+    fn bans_are_enforced_after_delayed_refreshes(
+        #[case] refreshers: usize,
+    ) -> Result<(), AnyError> {
+        let rt = Runtime::new()?;
+        let Some(db) = build_test_db(&rt, |_| Ok(()))? else {
+            return Ok(());
+        };
+        let pool = db.pool();
+        let banned: IpAddr = "198.51.100.7".parse()?;
+        let bans = Arc::new(AddressBans::default());
+        let before = chaos_delays(ChaosPoint::BanRefresh);
+        let chaos = enable_chaos(ChaosSettings {
+            max_jitter: Duration::from_millis(20),
+        });
+
+        rt.block_on(async {
+            let refreshes: Vec<_> = (0..refreshers)
+                .map(|_| {
+                    let pool = pool.clone();
+                    let bans = Arc::clone(&bans);
+                    tokio::spawn(async move {
+                        for _ in 0..3 {
+                            reload(&pool, &bans).await;
+                        }
+                    })
+                })
+                .collect();
+            let mut conn = pool.get().await?;
+            create_ban(&mut conn, &BanTarget::Address(banned), None, None).await?;
+            for refresh in refreshes {
+                refresh.await?;
+            }
+            // A refresh that read the list before the ban was committed may
+            // land last; the next refresh must still pick the ban up.
+            reload(&pool, &bans).await;
+            Ok::<_, AnyError>(())
+        })?;
+        drop(chaos);
+
+        assert!(bans.is_banned(banned));
+        assert!(chaos_delays(ChaosPoint::BanRefresh) > before);
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Listener addresses named by the `bind` and `legacy_bind` settings.
+//!
+//! Both settings take a comma-separated list, so one server can listen on
+//! IPv4 and IPv6 at once, for example `0.0.0.0:5500,[::]:5500`. Each entry is
+//! a socket address or a resolvable `host:port`; a host name binds every
+//! address it resolves to. Both runtimes run one acceptor per address, and
+//! each address gets its own transfer port one above it.
+//!
+//! IPv6 listeners are bound with `IPV6_V6ONLY` set, so an IPv6 wildcard does
+//! not also claim the IPv4 port and the two can be listed side by side.
+
+use std::net::{SocketAddr, TcpListener as StdTcpListener, ToSocketAddrs};
+
+use anyhow::{Context, Result, anyhow};
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::net::TcpListener;
+
+/// Pending connections each listener queues before refusing more.
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// Split a bind setting into its trimmed, non-empty entries.
+pub fn bind_entries(bind: &str) -> impl Iterator<Item = &str> {
+    bind.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+}
+
+/// Parse every entry of `target` as a socket address, resolving host names
+/// if needed.
+///
+/// Duplicates are dropped, keeping the order in which addresses first
+/// appear.
+///
+/// # Errors
+///
+/// Returns an error if `target` names no addresses or any entry is neither
+/// an address nor a resolvable `host:port` pair.
+pub fn parse_bind_addr(target: &str) -> Result<Vec<SocketAddr>> {
+    let mut addrs = Vec::new();
+    for entry in bind_entries(target) {
+        let resolved =
+            parse_entry(entry).with_context(|| format!("invalid bind address '{entry}'"))?;
+        for addr in resolved {
+            if !addrs.contains(&addr) {
+                addrs.push(addr);
+            }
+        }
+    }
+    if addrs.is_empty() {
+        return Err(anyhow!(
+            "invalid bind address '{target}': no addresses given"
+        ));
+    }
+    Ok(addrs)
+}
+
+fn parse_entry(entry: &str) -> Result<Vec<SocketAddr>> {
+    if let Ok(addr) = entry.parse() {
+        return Ok(vec![addr]);
+    }
+    let resolved: Vec<SocketAddr> = entry
+        .to_socket_addrs()
+        .with_context(|| format!("failed to resolve '{entry}'"))?
+        .collect();
+    if resolved.is_empty() {
+        return Err(anyhow!("failed to resolve '{entry}'"));
+    }
+    Ok(resolved)
+}
+
+/// Bind a non-blocking standard listener on `addr`, IPv6-only for IPv6
+/// addresses.
+///
+/// # Errors
+///
+/// Returns an error if the socket cannot be created or bound.
+pub fn bind_std_listener(addr: SocketAddr) -> Result<StdTcpListener> {
+    let bind = || -> std::io::Result<StdTcpListener> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        if addr.is_ipv6() {
+            socket.set_only_v6(true)?;
+        }
+        // Match tokio's `TcpListener::bind`, which lets restarts reuse ports
+        // still in TIME_WAIT.
+        #[cfg(unix)]
+        socket.set_reuse_address(true)?;
+        socket.bind(&addr.into())?;
+        socket.listen(LISTEN_BACKLOG)?;
+        socket.set_nonblocking(true)?;
+        Ok(socket.into())
+    };
+    bind().with_context(|| format!("failed to bind {addr}"))
+}
+
+/// Bind one listener for every address in the `bind` setting `target`.
+///
+/// # Errors
+///
+/// Returns an error if `target` cannot be parsed or any address cannot be
+/// bound.
+pub fn bind_listeners(target: &str) -> Result<Vec<TcpListener>> {
+    parse_bind_addr(target)?
+        .into_iter()
+        .map(|addr| Ok(TcpListener::from_std(bind_std_listener(addr)?)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    //! Parsing bind lists and binding dual-stack listeners.
+
+    use std::net::Ipv6Addr;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("127.0.0.1:6000", &["127.0.0.1:6000"])]
+    #[case("[::1]:7000", &["[::1]:7000"])]
+    #[case("0.0.0.0:5500, [::]:5500", &["0.0.0.0:5500", "[::]:5500"])]
+    #[case("127.0.0.1:6000,,127.0.0.1:6000", &["127.0.0.1:6000"])]
+    fn parses_socket_addrs(#[case] bind: &str, #[case] expected: &[&str]) {
+        let addrs = parse_bind_addr(bind).expect("bind");
+        let rendered: Vec<String> = addrs.iter().map(ToString::to_string).collect();
+        assert_eq!(rendered, expected);
+    }
+
+    #[rstest]
+    #[case("invalid")]
+    #[case("127.0.0.1")]
+    #[case("127.0.0.1:6000,nonsense")]
+    #[case(" , ")]
+    fn rejects_invalid_addrs(#[case] bind: &str) {
+        let err = parse_bind_addr(bind).expect_err("must fail");
+        assert!(err.to_string().contains("invalid bind address"));
+    }
+
+    #[rstest]
+    fn resolves_hostnames() {
+        let addrs = parse_bind_addr("localhost:6010").expect("bind");
+        assert!(!addrs.is_empty());
+        assert!(
+            addrs
+                .iter()
+                .all(|addr| addr.ip().is_loopback() && addr.port() == 6010)
+        );
+    }
+
+    #[rstest]
+    fn binds_ipv4_and_ipv6_wildcards_on_the_same_port() {
+        let v4 = bind_std_listener("0.0.0.0:0".parse().expect("address")).expect("ipv4");
+        let port = v4.local_addr().expect("address").port();
+        let v6_addr = SocketAddr::from((Ipv6Addr::UNSPECIFIED, port));
+        // Hosts without IPv6 cannot create the second socket at all.
+        if Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP)).is_err() {
+            return;
+        }
+        let v6 = bind_std_listener(v6_addr).expect("ipv6 beside ipv4");
+        assert_eq!(v6.local_addr().expect("address").port(), port);
+    }
+}
@@ -0,0 +1,46 @@
+//! Administrator broadcasts.
+//!
+//! User Broadcast (355) lets a session holding [`Privileges::BROADCAST`]
+//! announce something to everyone connected, such as a maintenance window.
+//! The text is delivered as a Server Message (104) without a sender, which
+//! clients display as an administrator message. Unlike chat it goes to every
+//! connection in the runtime's outbound registry, including clients that have
+//! not finished logging in.
+//!
+//! [`Privileges::BROADCAST`]: crate::privileges::Privileges::BROADCAST
+
+use crate::{
+    field_id::FieldId,
+    presence::server_notification,
+    transaction::{Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// Build the `104` push that delivers a broadcast `text` to each client.
+///
+/// # Errors
+///
+/// Returns an encoding error if the text exceeds protocol limits.
+pub fn build_broadcast_msg(text: &str) -> Result<Transaction, TransactionError> {
+    let payload = encode_params(&[(FieldId::Data, text.as_bytes())])?;
+    Ok(server_notification(TransactionType::ServerMsg, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for broadcast message construction.
+    use rstest::rstest;
+
+    use super::*;
+    use crate::transaction::decode_params;
+
+    #[rstest]
+    fn broadcast_carries_only_the_text() {
+        let push = build_broadcast_msg("Back in five").expect("build broadcast");
+
+        assert_eq!(push.header.ty, u16::from(TransactionType::ServerMsg));
+        assert_eq!(push.header.is_reply, 0);
+        let params = decode_params(&push.payload).expect("decode broadcast");
+        assert_eq!(params, vec![(FieldId::Data, b"Back in five".to_vec())]);
+    }
+}
@@ -0,0 +1,143 @@
+//! Chaos mode for background jobs and outbound deliveries.
+//!
+//! The background jobs (ban refresh, archive snapshots, scheduled
+//! maintenance, and outbox dispatch) and every outbound push pass a
+//! [`ChaosPoint`] through [`chaos_point`] before they act. In normal builds
+//! that returns at once. Unit tests and builds with the `test-support`
+//! feature can call `enable_chaos`, after which each point first waits a
+//! random delay of up to the configured jitter. Tests use it to check that
+//! these subsystems stay correct when their work runs late and out of step:
+//! pushes are never lost and bans are always enforced in the end.
+
+use std::time::Duration;
+
+use tokio::time::sleep;
+
+/// A place where chaos mode may delay work.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChaosPoint {
+    /// Reloading the address ban list.
+    BanRefresh,
+    /// Checking whether an archive snapshot is due.
+    ArchiveSnapshot,
+    /// Running scheduled database maintenance.
+    Maintenance,
+    /// Delivering a batch of outbox events.
+    OutboxDispatch,
+    /// Queueing a frame for a client connection.
+    OutboundPush,
+}
+
+/// Wait the chaos delay for `point`, if chaos mode is enabled.
+pub async fn chaos_point(point: ChaosPoint) {
+    if let Some(delay) = draw_delay(point) {
+        sleep(delay).await;
+    }
+}
+
+#[cfg(not(any(test, feature = "test-support")))]
+const fn draw_delay(_point: ChaosPoint) -> Option<Duration> { None }
+
+#[cfg(any(test, feature = "test-support"))]
+use self::switch::draw_delay;
+#[cfg(any(test, feature = "test-support"))]
+pub use self::switch::{ChaosGuard, ChaosSettings, chaos_delays, enable_chaos};
+
+#[cfg(any(test, feature = "test-support"))]
+mod switch {
+    //! The process-wide chaos switch.
+
+    use std::{
+        sync::{
+            PoisonError,
+            RwLock,
+            atomic::{AtomicU64, Ordering},
+        },
+        time::Duration,
+    };
+
+    use super::ChaosPoint;
+
+    static CHAOS: RwLock<Option<ChaosSettings>> = RwLock::new(None);
+
+    static DELAYS: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+
+    /// How chaos mode delays work.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct ChaosSettings {
+        /// Longest delay added at any [`ChaosPoint`].
+        pub max_jitter: Duration,
+    }
+
+    /// Keeps chaos mode enabled; dropping it turns chaos mode off.
+    #[derive(Debug)]
+    #[must_use = "chaos mode is turned off when the guard is dropped"]
+    pub struct ChaosGuard(());
+
+    impl Drop for ChaosGuard {
+        fn drop(&mut self) { *CHAOS.write().unwrap_or_else(PoisonError::into_inner) = None; }
+    }
+
+    /// Delay work at every [`ChaosPoint`] by up to `settings.max_jitter`
+    /// until the returned guard is dropped.
+    ///
+    /// The switch is process-wide, so it also delays work started by tests
+    /// running alongside; keep the jitter small.
+    pub fn enable_chaos(settings: ChaosSettings) -> ChaosGuard {
+        *CHAOS.write().unwrap_or_else(PoisonError::into_inner) = Some(settings);
+        ChaosGuard(())
+    }
+
+    /// Return how many delays chaos mode has added at `point`.
+    #[must_use]
+    pub fn chaos_delays(point: ChaosPoint) -> u64 {
+        DELAYS
+            .get(slot(point))
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn draw_delay(point: ChaosPoint) -> Option<Duration> {
+        let settings = (*CHAOS.read().unwrap_or_else(PoisonError::into_inner))?;
+        if let Some(count) = DELAYS.get(slot(point)) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+        let max_nanos = u64::try_from(settings.max_jitter.as_nanos()).unwrap_or(u64::MAX);
+        Some(Duration::from_nanos(rand::random_range(0..=max_nanos)))
+    }
+
+    const fn slot(point: ChaosPoint) -> usize {
+        match point {
+            ChaosPoint::BanRefresh => 0,
+            ChaosPoint::ArchiveSnapshot => 1,
+            ChaosPoint::Maintenance => 2,
+            ChaosPoint::OutboxDispatch => 3,
+            ChaosPoint::OutboundPush => 4,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Drawing delays while chaos mode is on.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[serial_test::serial(chaos)]
+    fn delays_stay_within_the_jitter() {
+        let max_jitter = Duration::from_millis(3);
+        let guard = enable_chaos(ChaosSettings { max_jitter });
+        let before = chaos_delays(ChaosPoint::Maintenance);
+
+        for _ in 0..100 {
+            let delay = draw_delay(ChaosPoint::Maintenance).expect("chaos is on");
+            assert!(delay <= max_jitter);
+        }
+        assert!(chaos_delays(ChaosPoint::Maintenance) >= before + 100);
+
+        drop(guard);
+        assert_eq!(draw_delay(ChaosPoint::Maintenance), None);
+    }
+}
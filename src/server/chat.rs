@@ -0,0 +1,195 @@
+//! Public chat formatting and fan-out.
+//!
+//! Send Chat (105) requests are rendered as classic Hotline chat lines and
+//! delivered as Chat Message (106) pushes to every online session through the
+//! runtime's [`OutboundMessaging`] adapter. The sender receives its own line as
+//! well, because Hotline clients only display chat once the server echoes it.
+//! Private chat rooms (field 114) are not supported yet, so every line goes to
+//! the public room.
+
+use tracing::warn;
+
+use crate::{
+    field_id::FieldId,
+    presence::{PresenceRegistry, server_notification},
+    server::outbound::{OutboundMessaging, OutboundPriority, OutboundTarget},
+    transaction::{Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// Chat options (field 109) value marking an emote line.
+pub const CHAT_OPTION_EMOTE: u32 = 1;
+
+/// Column width the speaker's nickname is padded or truncated to.
+const SPEAKER_WIDTH: usize = 13;
+
+/// A public chat line posted by an online session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChatLine {
+    /// Nickname of the session that posted the line.
+    pub speaker: String,
+    /// Message text as sent by the client.
+    pub text: String,
+    /// Whether the line is an emote (`/me`) rather than speech.
+    pub emote: bool,
+}
+
+impl ChatLine {
+    /// Render the line the way Hotline clients display it.
+    ///
+    /// Speech is prefixed with the nickname right-aligned in a 13-column
+    /// gutter; emotes are prefixed with `***` and the full nickname.
+    #[must_use]
+    pub fn render(&self) -> String {
+        if self.emote {
+            format!("\r*** {} {}", self.speaker, self.text)
+        } else {
+            format!(
+                "\r{speaker:>width$.width$}:  {text}",
+                speaker = self.speaker,
+                width = SPEAKER_WIDTH,
+                text = self.text,
+            )
+        }
+    }
+}
+
+/// Build a `106` push carrying the rendered chat line.
+///
+/// # Errors
+///
+/// Returns an encoding error if the payload would exceed protocol limits.
+pub fn build_chat_msg(line: &ChatLine) -> Result<Transaction, TransactionError> {
+    let rendered = line.render();
+    let payload = encode_params(&[(FieldId::Data, rendered.as_bytes())])?;
+    Ok(server_notification(TransactionType::ChatMsg, payload))
+}
+
+/// Push a chat message to every online session.
+///
+/// Delivery failures are logged per recipient so one slow or departed
+/// connection does not stop the line reaching everyone else.
+pub async fn broadcast_chat(
+    messaging: &dyn OutboundMessaging,
+    presence: &PresenceRegistry,
+    message: &Transaction,
+) {
+    for snapshot in presence.online_snapshots() {
+        let target = OutboundTarget::Connection(snapshot.connection_id);
+        if let Err(error) = messaging
+            .push(target, message.clone(), OutboundPriority::High)
+            .await
+        {
+            warn!(
+                ?error,
+                target = snapshot.connection_id.as_u64(),
+                "chat delivery failed"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for chat rendering and fan-out.
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use rstest::rstest;
+    use tokio::runtime::Runtime;
+
+    use super::*;
+    use crate::{
+        presence::PresenceSnapshot,
+        server::outbound::{OutboundConnectionId, OutboundError},
+        transaction::decode_params,
+    };
+
+    #[derive(Default)]
+    struct RecordingMessaging {
+        pushed: Mutex<Vec<OutboundTarget>>,
+    }
+
+    #[async_trait]
+    impl OutboundMessaging for RecordingMessaging {
+        async fn push(
+            &self,
+            target: OutboundTarget,
+            _message: Transaction,
+            _priority: OutboundPriority,
+        ) -> Result<(), OutboundError> {
+            self.pushed.lock().expect("recording lock").push(target);
+            Ok(())
+        }
+
+        async fn broadcast(
+            &self,
+            _message: Transaction,
+            _priority: OutboundPriority,
+        ) -> Result<(), OutboundError> {
+            Err(OutboundError::MessagingUnavailable)
+        }
+    }
+
+    fn line(speaker: &str, text: &str, emote: bool) -> ChatLine {
+        ChatLine {
+            speaker: speaker.to_owned(),
+            text: text.to_owned(),
+            emote,
+        }
+    }
+
+    #[rstest]
+    #[case::speech(line("alice", "hello", false), "\r        alice:  hello")]
+    #[case::long_name(line("bartholomew-the-third", "hi", false), "\rbartholomew-t:  hi")]
+    #[case::emote(line("alice", "waves", true), "\r*** alice waves")]
+    fn renders_hotline_chat_lines(#[case] line: ChatLine, #[case] expected: &str) {
+        assert_eq!(line.render(), expected);
+    }
+
+    #[rstest]
+    fn chat_msg_carries_rendered_line() {
+        let message = build_chat_msg(&line("alice", "hello", false)).expect("build chat");
+
+        assert_eq!(message.header.ty, u16::from(TransactionType::ChatMsg));
+        assert_eq!(message.header.is_reply, 0);
+        let params = decode_params(&message.payload).expect("decode chat");
+        assert_eq!(
+            params,
+            vec![(FieldId::Data, b"\r        alice:  hello".to_vec())]
+        );
+    }
+
+    #[rstest]
+    fn broadcast_reaches_every_online_session() {
+        let presence = PresenceRegistry::default();
+        for (id, name) in [(1, "alice"), (2, "bob")] {
+            presence
+                .upsert(PresenceSnapshot {
+                    connection_id: OutboundConnectionId::new(id),
+                    user_id: 0,
+                    account_id: 0,
+                    display_name: name.to_owned(),
+                    icon_id: 0,
+                    status_flags: 0,
+                    cannot_be_disconnected: false,
+                    auto_response: None,
+                })
+                .expect("insert snapshot");
+        }
+        let messaging = RecordingMessaging::default();
+        let message = build_chat_msg(&line("alice", "hello", false)).expect("build chat");
+        let rt = Runtime::new().expect("runtime");
+
+        rt.block_on(broadcast_chat(&messaging, &presence, &message));
+
+        let pushed = messaging.pushed.lock().expect("recording lock").clone();
+        assert_eq!(
+            pushed,
+            vec![
+                OutboundTarget::Connection(OutboundConnectionId::new(1)),
+                OutboundTarget::Connection(OutboundConnectionId::new(2)),
+            ]
+        );
+    }
+}
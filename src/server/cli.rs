@@ -11,12 +11,26 @@ use argon2::Params;
 use clap::{CommandFactory, Parser};
 pub use cli_defs::{
     AppConfig,
+    BanArgs,
+    BanTargetArgs,
     Cli,
     Commands,
     CreateUserArgs,
+    CreditsArgs,
     DEFAULT_ARGON2_M_COST,
     DEFAULT_ARGON2_P_COST,
     DEFAULT_ARGON2_T_COST,
+    DbCommand,
+    DropBoxArgs,
+    FilesCommand,
+    InitArgs,
+    MigrateArgs,
+    NewsCommand,
+    NewsDigestArgs,
+    NewsFsckArgs,
+    NewsRootsArgs,
+    QuotaArgs,
+    UsersCommand,
 };
 
 const _: () = {
@@ -161,6 +175,20 @@ mod tests {
         });
     }
 
+    #[rstest]
+    fn runtime_tuning_loads_from_env() {
+        Jail::expect_with(|j| {
+            j.set_env("MXD_WORKER_THREADS", "2");
+            j.set_env("MXD_MAX_BLOCKING_THREADS", "16");
+            j.set_env("MXD_EVENT_INTERVAL", "31");
+            let cfg = AppConfig::load_from_iter(["mxd"]).expect("load");
+            assert_eq!(cfg.worker_threads, Some(2));
+            assert_eq!(cfg.max_blocking_threads, Some(16));
+            assert_eq!(cfg.event_interval, Some(31));
+            Ok(())
+        });
+    }
+
     #[rstest]
     fn loads_from_dotfile() {
         Jail::expect_with(|j| {
@@ -0,0 +1,194 @@
+//! Get Client Info Text (303) report.
+//!
+//! Hotline clients show the info text verbatim in a "Get Info" window, so the
+//! server renders a short `\r`-separated report from the presence registry:
+//! the user's address, when they logged in, how long they have been idle,
+//! their latest ping round-trip time, how much their account has transferred,
+//! and their active file transfers.
+//! Facts the registry does not hold, such as the
+//! address of a connection no runtime attached, are omitted rather than
+//! guessed.
+
+use std::{fmt::Write as _, net::IpAddr, time::Duration};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    db::TransferStats,
+    presence::{ConnectionDetails, PresenceSnapshot},
+};
+
+/// Facts about an online user reported by Get Client Info Text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientInfo {
+    /// Session-visible nickname.
+    pub name: String,
+    /// Peer IP address.
+    pub address: Option<IpAddr>,
+    /// Time the user came online.
+    pub logged_in_at: Option<DateTime<Utc>>,
+    /// Time since the user's most recent transaction.
+    pub idle: Option<Duration>,
+    /// Round-trip time of the connection's latest answered ping.
+    pub round_trip: Option<Duration>,
+    /// Bytes and files the account has transferred.
+    pub transferred: Option<TransferStats>,
+    /// One description per active file transfer.
+    pub transfers: Vec<String>,
+}
+
+impl ClientInfo {
+    /// Gather the report for `snapshot` from its attached connection details.
+    ///
+    /// Only the session's unrecorded transfers are known here; callers add
+    /// the account's recorded totals with [`Self::add_recorded_transfers`].
+    #[must_use]
+    pub fn from_presence(snapshot: &PresenceSnapshot, details: Option<&ConnectionDetails>) -> Self {
+        Self {
+            name: snapshot.display_name.clone(),
+            address: details.map(|found| found.address),
+            logged_in_at: details.and_then(|found| found.logged_in_at),
+            idle: details.map(|found| found.activity.idle_for()),
+            round_trip: details
+                .and_then(|found| found.pings.as_ref())
+                .and_then(|pings| pings.round_trip()),
+            transferred: details.map(|found| found.transfers.pending()),
+            transfers: Vec::new(),
+        }
+    }
+
+    /// Add the account's recorded transfer totals to the report.
+    #[must_use]
+    pub fn add_recorded_transfers(mut self, recorded: TransferStats) -> Self {
+        let pending = self.transferred.unwrap_or_default();
+        self.transferred = Some(recorded.saturating_add(pending));
+        self
+    }
+
+    /// Render the report as Hotline info text.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut text = format!("Name: {}", self.name);
+        if let Some(address) = self.address {
+            let _ = write!(text, "\rAddress: {address}");
+        }
+        if let Some(at) = self.logged_in_at {
+            let _ = write!(text, "\rLogged in: {}", at.format("%Y-%m-%d %H:%M:%S UTC"));
+        }
+        if let Some(idle) = self.idle {
+            let _ = write!(text, "\rIdle: {}", format_idle(idle));
+        }
+        if let Some(round_trip) = self.round_trip {
+            let _ = write!(text, "\rLatency: {} ms", round_trip.as_millis());
+        }
+        if let Some(stats) = self.transferred {
+            let _ = write!(
+                text,
+                "\rUploaded: {} bytes ({} files)\rDownloaded: {} bytes ({} files)",
+                stats.bytes_uploaded, stats.uploads, stats.bytes_downloaded, stats.downloads
+            );
+        }
+        if self.transfers.is_empty() {
+            text.push_str("\rTransfers: none");
+        } else {
+            text.push_str("\rTransfers:");
+            for transfer in &self.transfers {
+                let _ = write!(text, "\r  {transfer}");
+            }
+        }
+        text
+    }
+}
+
+/// Format `idle` as hours, minutes, and seconds.
+fn format_idle(idle: Duration) -> String {
+    let secs = idle.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for client info rendering.
+    use chrono::TimeZone;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn info() -> ClientInfo {
+        ClientInfo {
+            name: "alice".to_owned(),
+            address: None,
+            logged_in_at: None,
+            idle: None,
+            round_trip: None,
+            transferred: None,
+            transfers: Vec::new(),
+        }
+    }
+
+    #[rstest]
+    #[case(Duration::ZERO, "0:00:00")]
+    #[case(Duration::from_secs(312), "0:05:12")]
+    #[case(Duration::from_secs(90_061), "25:01:01")]
+    fn formats_idle_time(#[case] idle: Duration, #[case] expected: &str) {
+        assert_eq!(format_idle(idle), expected);
+    }
+
+    #[rstest]
+    fn omits_unknown_facts() {
+        assert_eq!(info().render(), "Name: alice\rTransfers: none");
+    }
+
+    #[rstest]
+    fn recorded_transfers_add_to_pending() {
+        let pending = TransferStats {
+            bytes_downloaded: 100,
+            downloads: 1,
+            ..TransferStats::default()
+        };
+        let recorded = TransferStats {
+            bytes_downloaded: 900,
+            downloads: 3,
+            ..TransferStats::default()
+        };
+        let report = ClientInfo {
+            transferred: Some(pending),
+            ..info()
+        }
+        .add_recorded_transfers(recorded);
+
+        assert_eq!(
+            report.transferred,
+            Some(TransferStats {
+                bytes_downloaded: 1_000,
+                downloads: 4,
+                ..TransferStats::default()
+            })
+        );
+    }
+
+    #[rstest]
+    fn renders_every_known_fact() {
+        let report = ClientInfo {
+            address: "192.0.2.7".parse().ok(),
+            logged_in_at: Utc.with_ymd_and_hms(2026, 10, 16, 9, 30, 0).single(),
+            idle: Some(Duration::from_secs(65)),
+            round_trip: Some(Duration::from_millis(42)),
+            transferred: Some(TransferStats {
+                bytes_uploaded: 2_048,
+                bytes_downloaded: 512,
+                uploads: 2,
+                downloads: 1,
+            }),
+            transfers: vec!["download report.pdf (40%)".to_owned()],
+            ..info()
+        };
+
+        assert_eq!(
+            report.render(),
+            "Name: alice\rAddress: 192.0.2.7\rLogged in: 2026-10-16 09:30:00 UTC\rIdle: \
+             0:01:05\rLatency: 42 ms\rUploaded: 2048 bytes (2 files)\rDownloaded: 512 bytes (1 \
+             files)\rTransfers:\r  download report.pdf (40%)"
+        );
+    }
+}
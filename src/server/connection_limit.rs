@@ -0,0 +1,242 @@
+//! Limits on how many clients may be connected at once.
+//!
+//! Every connection holds a [`ConnectionSlot`] from the handshake until it
+//! closes. A server's [`ConnectionLimiter`] hands one out only while the
+//! server is below
+//! `max_connections` in total and the peer's address is below
+//! `max_connections_per_ip`, so a flood of connections is turned away at the
+//! handshake, answered with
+//! [`HANDSHAKE_ERR_SERVER_FULL`](crate::protocol::HANDSHAKE_ERR_SERVER_FULL),
+//! before it can claim database connections or memory. Both limits are off
+//! unless configured. Each server counts its own connections, and both
+//! runtimes in dual-runtime mode share one limiter.
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use thiserror::Error;
+
+use super::AppConfig;
+
+/// Most connections allowed in total and from one address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionLimits {
+    /// Most connections open at once, if limited.
+    pub total: Option<NonZeroUsize>,
+    /// Most connections open at once from one address, if limited.
+    pub per_address: Option<NonZeroUsize>,
+}
+
+/// Errors raised while reading the connection limits from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConnectionLimitError {
+    /// `max_connections` was zero.
+    #[error("max_connections must be greater than zero")]
+    ZeroTotal,
+    /// `max_connections_per_ip` was zero.
+    #[error("max_connections_per_ip must be greater than zero")]
+    ZeroPerAddress,
+}
+
+impl ConnectionLimits {
+    /// No limits.
+    pub const OFF: Self = Self {
+        total: None,
+        per_address: None,
+    };
+
+    /// Read the limits from `config`; each one left unset is off.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConnectionLimitError`] when either limit is zero.
+    pub fn from_config(config: &AppConfig) -> Result<Self, ConnectionLimitError> {
+        Ok(Self {
+            total: limit(config.max_connections).ok_or(ConnectionLimitError::ZeroTotal)?,
+            per_address: limit(config.max_connections_per_ip)
+                .ok_or(ConnectionLimitError::ZeroPerAddress)?,
+        })
+    }
+
+    fn admits(self, counts: &ConnectionCounts, address: IpAddr) -> bool {
+        let below = |limit: Option<NonZeroUsize>, count: usize| {
+            limit.is_none_or(|limit| count < limit.get())
+        };
+        below(self.total, counts.total)
+            && below(
+                self.per_address,
+                counts
+                    .per_address
+                    .get(&address)
+                    .copied()
+                    .unwrap_or_default(),
+            )
+    }
+}
+
+/// Return `Some(None)` when unset, or `None` for zero.
+fn limit(value: Option<usize>) -> Option<Option<NonZeroUsize>> {
+    match value {
+        None => Some(None),
+        Some(raw) => NonZeroUsize::new(raw).map(Some),
+    }
+}
+
+#[derive(Debug, Default)]
+struct ConnectionCounts {
+    total: usize,
+    per_address: HashMap<IpAddr, usize>,
+}
+
+/// Connection limits and counts for one server.
+#[derive(Debug, Default)]
+pub struct ConnectionLimiter {
+    limits: ConnectionLimits,
+    counts: Mutex<ConnectionCounts>,
+}
+
+impl ConnectionLimiter {
+    /// Create a limiter applying `limits`.
+    #[must_use]
+    pub fn new(limits: ConnectionLimits) -> Self {
+        Self {
+            limits,
+            counts: Mutex::default(),
+        }
+    }
+
+    /// Claim a slot for a connection from `address`, or return `None` when
+    /// the server or the address is at its limit.
+    #[must_use]
+    pub fn admit(self: &Arc<Self>, address: IpAddr) -> Option<ConnectionSlot> {
+        let mut counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        if !self.limits.admits(&counts, address) {
+            return None;
+        }
+        counts.total = counts.total.saturating_add(1);
+        let count = counts.per_address.entry(address).or_default();
+        *count = count.saturating_add(1);
+        Some(ConnectionSlot(Arc::new(HeldSlot {
+            limiter: Arc::clone(self),
+            address,
+        })))
+    }
+
+    fn release(&self, address: IpAddr) {
+        let mut counts = self.counts.lock().unwrap_or_else(PoisonError::into_inner);
+        counts.total = counts.total.saturating_sub(1);
+        if let Some(count) = counts.per_address.get_mut(&address) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                counts.per_address.remove(&address);
+            }
+        }
+    }
+}
+
+/// A connection's place within the limits, given back when the last clone
+/// is dropped.
+#[derive(Clone, Debug)]
+pub struct ConnectionSlot(Arc<HeldSlot>);
+
+impl PartialEq for ConnectionSlot {
+    fn eq(&self, other: &Self) -> bool { Arc::ptr_eq(&self.0, &other.0) }
+}
+
+impl Eq for ConnectionSlot {}
+
+#[derive(Debug)]
+struct HeldSlot {
+    limiter: Arc<ConnectionLimiter>,
+    address: IpAddr,
+}
+
+impl Drop for HeldSlot {
+    fn drop(&mut self) { self.limiter.release(self.address); }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for connection limit parsing and slot accounting.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn limits(total: Option<usize>, per_address: Option<usize>) -> ConnectionLimits {
+        ConnectionLimits {
+            total: total.and_then(NonZeroUsize::new),
+            per_address: per_address.and_then(NonZeroUsize::new),
+        }
+    }
+
+    #[rstest]
+    #[case(None, None, Ok(ConnectionLimits::OFF))]
+    #[case(Some(100), Some(4), Ok(limits(Some(100), Some(4))))]
+    #[case(Some(0), None, Err(ConnectionLimitError::ZeroTotal))]
+    #[case(None, Some(0), Err(ConnectionLimitError::ZeroPerAddress))]
+    fn parses_limits(
+        #[case] total: Option<usize>,
+        #[case] per_address: Option<usize>,
+        #[case] expected: Result<ConnectionLimits, ConnectionLimitError>,
+    ) {
+        let config = AppConfig {
+            max_connections: total,
+            max_connections_per_ip: per_address,
+            ..AppConfig::default()
+        };
+        assert_eq!(ConnectionLimits::from_config(&config), expected);
+    }
+
+    #[rstest]
+    fn slots_are_limited_per_address_and_returned_on_drop() {
+        let limiter = Arc::new(ConnectionLimiter::new(limits(None, Some(2))));
+        let address = IpAddr::from([192, 0, 2, 60]);
+        let other = IpAddr::from([192, 0, 2, 61]);
+
+        let first = limiter.admit(address);
+        let second = limiter.admit(address);
+        let third = limiter.admit(address);
+        let elsewhere = limiter.admit(other);
+        drop(first);
+        let after_drop = limiter.admit(address);
+
+        assert!(second.is_some());
+        assert!(third.is_none());
+        assert!(elsewhere.is_some());
+        assert!(after_drop.is_some());
+    }
+
+    #[rstest]
+    fn clones_share_one_slot() {
+        let address = IpAddr::from([192, 0, 2, 62]);
+        let limiter = Arc::new(ConnectionLimiter::new(limits(None, Some(1))));
+
+        let slot = limiter.admit(address).expect("first slot");
+        let copy = slot.clone();
+        drop(slot);
+        let while_held = limiter.admit(address);
+        drop(copy);
+        let after_release = limiter.admit(address);
+
+        assert!(while_held.is_none());
+        assert!(after_release.is_some());
+    }
+
+    #[rstest]
+    fn servers_count_their_own_connections() {
+        let address = IpAddr::from([192, 0, 2, 63]);
+        let one = Arc::new(ConnectionLimiter::new(limits(Some(1), None)));
+        let other = Arc::new(ConnectionLimiter::new(limits(Some(1), None)));
+
+        let held = one.admit(address);
+
+        assert!(held.is_some());
+        assert!(one.admit(address).is_none());
+        assert!(other.admit(address).is_some());
+    }
+}
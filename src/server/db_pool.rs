@@ -0,0 +1,104 @@
+//! Database pool sizing and timeouts.
+//!
+//! Operators size the pool with `db_pool_size` and bound how long a request
+//! waits for a connection with `db_connection_timeout_secs`, and how long an
+//! unused connection stays open with `db_idle_timeout_secs`. Each runtime
+//! reads them with [`pool_settings_from_config`] when it opens the pool.
+//! Unset options keep [`PoolSettings::DEFAULT`]. A request that times out
+//! waiting for a connection is answered with
+//! [`ERR_DATABASE_BUSY`](crate::commands::ERR_DATABASE_BUSY) rather than a
+//! generic internal error, so clients can tell an exhausted pool apart from a
+//! failure.
+
+use std::{num::NonZeroU32, time::Duration};
+
+use thiserror::Error;
+
+use super::AppConfig;
+use crate::db::PoolSettings;
+
+/// Errors raised while reading the pool settings from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PoolSettingsError {
+    /// `db_pool_size` was zero.
+    #[error("db_pool_size must be greater than zero")]
+    ZeroSize,
+    /// `db_connection_timeout_secs` was zero.
+    #[error("db_connection_timeout_secs must be greater than zero")]
+    ZeroConnectionTimeout,
+    /// `db_idle_timeout_secs` was zero.
+    #[error("db_idle_timeout_secs must be greater than zero")]
+    ZeroIdleTimeout,
+}
+
+/// Read the pool settings from `config`, keeping the default for each
+/// option left unset.
+///
+/// # Errors
+///
+/// Returns [`PoolSettingsError`] when any option is zero.
+pub fn pool_settings_from_config(config: &AppConfig) -> Result<PoolSettings, PoolSettingsError> {
+    let defaults = PoolSettings::DEFAULT;
+    let max_size = match config.db_pool_size {
+        None => defaults.max_size,
+        Some(size) => NonZeroU32::new(size).ok_or(PoolSettingsError::ZeroSize)?,
+    };
+    let connection_timeout = match config.db_connection_timeout_secs {
+        None => defaults.connection_timeout,
+        Some(0) => return Err(PoolSettingsError::ZeroConnectionTimeout),
+        Some(secs) => Duration::from_secs(secs),
+    };
+    let idle_timeout = match config.db_idle_timeout_secs {
+        None => defaults.idle_timeout,
+        Some(0) => return Err(PoolSettingsError::ZeroIdleTimeout),
+        Some(secs) => Some(Duration::from_secs(secs)),
+    };
+    Ok(PoolSettings {
+        max_size,
+        connection_timeout,
+        idle_timeout,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the pool settings from configuration.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn config(size: Option<u32>, connect: Option<u64>, idle: Option<u64>) -> AppConfig {
+        AppConfig {
+            db_pool_size: size,
+            db_connection_timeout_secs: connect,
+            db_idle_timeout_secs: idle,
+            ..AppConfig::default()
+        }
+    }
+
+    #[rstest]
+    fn unset_options_keep_the_defaults() {
+        assert_eq!(
+            pool_settings_from_config(&config(None, None, None)),
+            Ok(PoolSettings::DEFAULT)
+        );
+    }
+
+    #[rstest]
+    fn reads_each_option() {
+        let settings = pool_settings_from_config(&config(Some(32), Some(5), Some(120)))
+            .expect("valid settings");
+        assert_eq!(settings.max_size.get(), 32);
+        assert_eq!(settings.connection_timeout, Duration::from_secs(5));
+        assert_eq!(settings.idle_timeout, Some(Duration::from_secs(120)));
+    }
+
+    #[rstest]
+    #[case(config(Some(0), None, None), PoolSettingsError::ZeroSize)]
+    #[case(config(None, Some(0), None), PoolSettingsError::ZeroConnectionTimeout)]
+    #[case(config(None, None, Some(0)), PoolSettingsError::ZeroIdleTimeout)]
+    fn rejects_zero(#[case] config: AppConfig, #[case] expected: PoolSettingsError) {
+        assert_eq!(pool_settings_from_config(&config), Err(expected));
+    }
+}
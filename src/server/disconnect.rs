@@ -0,0 +1,115 @@
+//! Graceful connection teardown shared by both runtimes.
+//!
+//! When the server ends a connection itself, dropping the socket outright
+//! loses frames that are still queued and leaves the client reporting a bare
+//! "connection lost". Both runtimes instead flush pending output, send a
+//! Disconnect Message (111) carrying the reason, half-close the write side,
+//! and drain inbound bytes for a short window. The drain matters because
+//! closing a socket with unread input makes the kernel send a reset, which can
+//! discard the notice before the client reads it.
+
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncRead, AsyncReadExt},
+    time::timeout,
+};
+use tracing::debug;
+
+use crate::{
+    field_id::FieldId,
+    presence::server_notification,
+    transaction::{Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// Reason sent to clients when the server stops.
+pub const SHUTDOWN_REASON: &str = "The server is shutting down.";
+
+/// Reason sent to a user disconnected by an administrator who gave none.
+pub const KICK_REASON: &str = "You have been disconnected by an administrator.";
+
+/// How long a closing connection keeps reading after its write side closes.
+pub const DRAIN_WINDOW: Duration = Duration::from_millis(500);
+
+/// Build a `111` notice carrying `reason` in field 101.
+///
+/// # Errors
+///
+/// Returns an encoding error if the payload would exceed protocol limits.
+pub fn build_disconnect_msg(reason: &str) -> Result<Transaction, TransactionError> {
+    let payload = encode_params(&[(FieldId::Data, reason.as_bytes())])?;
+    Ok(server_notification(TransactionType::DisconnectMsg, payload))
+}
+
+/// Read and discard inbound bytes until EOF, a read error, or `window`
+/// elapses.
+pub async fn drain_inbound<R>(reader: &mut R, window: Duration)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut scratch = [0u8; 1024];
+    let drained = timeout(window, async {
+        while let Ok(read) = reader.read(&mut scratch).await {
+            if read == 0 {
+                break;
+            }
+        }
+    })
+    .await;
+    if drained.is_err() {
+        debug!(?window, "peer still sending when drain window elapsed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for disconnect notices and inbound draining.
+    use rstest::rstest;
+    use tokio::{io::AsyncWriteExt, runtime::Runtime, time::Instant};
+
+    use super::*;
+    use crate::transaction::decode_params;
+
+    #[rstest]
+    fn disconnect_msg_carries_reason() {
+        let notice = build_disconnect_msg(SHUTDOWN_REASON).expect("build notice");
+
+        assert_eq!(notice.header.ty, u16::from(TransactionType::DisconnectMsg));
+        assert_eq!(notice.header.is_reply, 0);
+        let params = decode_params(&notice.payload).expect("decode notice");
+        assert_eq!(
+            params,
+            vec![(FieldId::Data, SHUTDOWN_REASON.as_bytes().to_vec())]
+        );
+    }
+
+    #[rstest]
+    fn drain_stops_at_peer_eof() {
+        let rt = Runtime::new().expect("runtime");
+        rt.block_on(async {
+            let (mut local, mut remote) = tokio::io::duplex(64);
+            remote.write_all(b"late bytes").await.expect("write");
+            drop(remote);
+            let started = Instant::now();
+
+            drain_inbound(&mut local, Duration::from_secs(5)).await;
+
+            assert!(started.elapsed() < Duration::from_secs(5));
+        });
+    }
+
+    #[rstest]
+    fn drain_gives_up_after_window() {
+        let rt = Runtime::new().expect("runtime");
+        rt.block_on(async {
+            let (mut local, _remote) = tokio::io::duplex(64);
+            let window = Duration::from_millis(20);
+            let started = Instant::now();
+
+            drain_inbound(&mut local, window).await;
+
+            assert!(started.elapsed() >= window);
+        });
+    }
+}
@@ -0,0 +1,460 @@
+//! Ratio and credit limits on downloads.
+//!
+//! Operators choose a [`DownloadPolicy`] with `download_policy`. Under
+//! `ratio`, a user may download `download_ratio` bytes for every byte they
+//! have uploaded, plus `download_free_bytes`; under `credits`, each download
+//! needs as many credits as it has bytes, and administrators grant credits
+//! with the `users credits` subcommand. Holders of any privilege named in
+//! `download_exempt_privileges` are never limited.
+//!
+//! Each server keeps its rules in its
+//! [`ServerSettings`](super::settings::ServerSettings). Download File asks
+//! [`check_download`] before filing a transfer, and the transfer port asks
+//! [`spend_download`] again before sending the file, which also spends the
+//! credits it costs. Both load the user's [`DownloadStanding`] and consult
+//! [`DownloadRules::check`]; a refusal's message is meant for the client's
+//! error text.
+
+use std::num::NonZeroU32;
+
+use diesel::result::QueryResult;
+use diesel_async::AsyncConnection;
+use thiserror::Error;
+
+use super::AppConfig;
+use crate::{
+    db::{
+        DbConnection,
+        TransferStats,
+        adjust_download_credits,
+        get_download_credits,
+        get_transfer_stats,
+    },
+    privileges::Privileges,
+};
+
+/// How downloads are limited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DownloadPolicy {
+    /// Downloads are not limited.
+    #[default]
+    Off,
+    /// Downloads are limited by how much the user has uploaded.
+    Ratio {
+        /// Bytes the user may download per byte uploaded.
+        ratio: NonZeroU32,
+        /// Bytes the user may download before uploading anything.
+        free_bytes: u64,
+    },
+    /// Each download spends one credit per byte.
+    Credits,
+}
+
+/// The download policy together with the privileges exempt from it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DownloadRules {
+    /// How downloads are limited.
+    pub policy: DownloadPolicy,
+    /// Users holding any of these privileges are not limited.
+    pub exempt: Privileges,
+}
+
+/// A download of `size` bytes asked for by `user_id`, holding
+/// `privileges`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DownloadRequest {
+    /// Account downloading the file.
+    pub user_id: i32,
+    /// Privileges the account holds.
+    pub privileges: Privileges,
+    /// Size of the requested file.
+    pub size: u64,
+}
+
+/// Errors raised while reading the download rules from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DownloadPolicyError {
+    /// `download_policy` named no known policy.
+    #[error("download_policy must be off, ratio, or credits, not {0:?}")]
+    InvalidMode(String),
+    /// `download_ratio` was zero.
+    #[error("download_ratio must be greater than zero")]
+    ZeroRatio,
+    /// `download_exempt_privileges` named an unknown privilege.
+    #[error("download_exempt_privileges names unknown privilege {0:?}")]
+    UnknownPrivilege(String),
+}
+
+/// Errors raised while deciding whether a download may go ahead.
+#[derive(Debug, Error)]
+pub enum DownloadCheckError {
+    /// The policy forbids the download.
+    #[error(transparent)]
+    Refused(#[from] DownloadRefusal),
+    /// Loading the user's standing or spending credits failed.
+    #[error(transparent)]
+    Database(#[from] diesel::result::Error),
+}
+
+/// What a user has transferred and the credits they hold.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DownloadStanding {
+    /// Recorded transfer totals.
+    pub transferred: TransferStats,
+    /// Download credits held.
+    pub credits: i64,
+}
+
+/// Why a download was refused; the message is shown to the user.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum DownloadRefusal {
+    /// The download would take the user past their upload ratio.
+    #[error(
+        "Download refused: your uploads allow {allowed} bytes of downloads and you have used \
+         {downloaded}, so this {size}-byte file does not fit. Upload files to download more."
+    )]
+    RatioExceeded {
+        /// Bytes the user's uploads allow them to download in total.
+        allowed: u64,
+        /// Bytes the user has already downloaded.
+        downloaded: u64,
+        /// Size of the requested file.
+        size: u64,
+    },
+    /// The user holds fewer credits than the file has bytes.
+    #[error(
+        "Download refused: this file needs {size} credits and you have {credits}. Ask an \
+         administrator for more credits."
+    )]
+    InsufficientCredits {
+        /// Credits the user holds.
+        credits: i64,
+        /// Size of the requested file.
+        size: u64,
+    },
+}
+
+impl DownloadRules {
+    /// Rules that never limit downloads.
+    pub const OFF: Self = Self {
+        policy: DownloadPolicy::Off,
+        exempt: Privileges::empty(),
+    };
+
+    /// Read the rules from `config`.
+    ///
+    /// An unset `download_policy` selects [`DownloadPolicy::Off`], an unset
+    /// `download_ratio` allows one byte per byte uploaded, and an unset
+    /// `download_free_bytes` allows nothing before the first upload.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DownloadPolicyError`] for an unrecognised mode, a zero
+    /// ratio, or an unknown exempt privilege.
+    pub fn from_config(config: &AppConfig) -> Result<Self, DownloadPolicyError> {
+        let ratio = config.download_ratio.map_or(Ok(NonZeroU32::MIN), |raw| {
+            NonZeroU32::new(raw).ok_or(DownloadPolicyError::ZeroRatio)
+        })?;
+        let policy = match config.download_policy.as_deref().map(str::trim) {
+            None | Some("off") => DownloadPolicy::Off,
+            Some("ratio") => DownloadPolicy::Ratio {
+                ratio,
+                free_bytes: config.download_free_bytes.unwrap_or_default(),
+            },
+            Some("credits") => DownloadPolicy::Credits,
+            Some(other) => return Err(DownloadPolicyError::InvalidMode(other.to_owned())),
+        };
+        let exempt = parse_privileges(config.download_exempt_privileges.as_deref())?;
+        Ok(Self { policy, exempt })
+    }
+
+    /// Report whether the rules limit a user holding `privileges` at all.
+    #[must_use]
+    pub const fn limits(&self, privileges: Privileges) -> bool {
+        !matches!(self.policy, DownloadPolicy::Off) && !privileges.intersects(self.exempt)
+    }
+
+    /// Decide whether a user holding `privileges`, with `standing`, may
+    /// download a file of `size` bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`DownloadRefusal`] to show the user when the policy
+    /// forbids the download.
+    pub fn check(
+        &self,
+        privileges: Privileges,
+        standing: &DownloadStanding,
+        size: u64,
+    ) -> Result<(), DownloadRefusal> {
+        if privileges.intersects(self.exempt) {
+            return Ok(());
+        }
+        match self.policy {
+            DownloadPolicy::Off => Ok(()),
+            DownloadPolicy::Ratio { ratio, free_bytes } => {
+                let uploaded = to_bytes(standing.transferred.bytes_uploaded);
+                let downloaded = to_bytes(standing.transferred.bytes_downloaded);
+                let allowed = uploaded
+                    .saturating_mul(u64::from(ratio.get()))
+                    .saturating_add(free_bytes);
+                if downloaded.saturating_add(size) > allowed {
+                    return Err(DownloadRefusal::RatioExceeded {
+                        allowed,
+                        downloaded,
+                        size,
+                    });
+                }
+                Ok(())
+            }
+            DownloadPolicy::Credits => {
+                if to_bytes(standing.credits) < size {
+                    return Err(DownloadRefusal::InsufficientCredits {
+                        credits: standing.credits,
+                        size,
+                    });
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Parse a comma-separated list of privilege names, such as
+/// `DISCONNECT_USER, CANNOT_BE_DISCONNECTED`.
+fn parse_privileges(names: Option<&str>) -> Result<Privileges, DownloadPolicyError> {
+    let mut privileges = Privileges::empty();
+    for name in names.unwrap_or_default().split(',').map(str::trim) {
+        if name.is_empty() {
+            continue;
+        }
+        let privilege = Privileges::from_name(&name.to_ascii_uppercase())
+            .ok_or_else(|| DownloadPolicyError::UnknownPrivilege(name.to_owned()))?;
+        privileges |= privilege;
+    }
+    Ok(privileges)
+}
+
+fn to_bytes(value: i64) -> u64 { u64::try_from(value).unwrap_or_default() }
+
+/// Load the recorded transfers and credits of `user_id`.
+///
+/// Transfers the user's sessions have made since the last flush are not
+/// included.
+///
+/// # Errors
+/// Returns any error produced by the queries.
+#[must_use = "handle the result"]
+pub async fn download_standing(
+    conn: &mut DbConnection,
+    user_id: i32,
+) -> QueryResult<DownloadStanding> {
+    Ok(DownloadStanding {
+        transferred: get_transfer_stats(conn, user_id).await?,
+        credits: get_download_credits(conn, user_id).await?,
+    })
+}
+
+/// Check `download` against `rules`.
+///
+/// Users the rules do not limit are allowed without a database read.
+///
+/// # Errors
+/// Returns [`DownloadCheckError::Refused`] when the rules forbid the
+/// download, or any error produced by the queries.
+#[must_use = "handle the result"]
+pub async fn check_download(
+    conn: &mut DbConnection,
+    rules: DownloadRules,
+    download: DownloadRequest,
+) -> Result<(), DownloadCheckError> {
+    let DownloadRequest {
+        user_id,
+        privileges,
+        size,
+    } = download;
+    if !rules.limits(privileges) {
+        return Ok(());
+    }
+    let standing = download_standing(conn, user_id).await?;
+    rules.check(privileges, &standing, size)?;
+    Ok(())
+}
+
+/// Check a download as [`check_download`] does and, under the credits
+/// policy, spend the credits it costs in the same database transaction.
+///
+/// # Errors
+/// Returns [`DownloadCheckError::Refused`] when the rules forbid the
+/// download, or any error produced by the queries.
+#[must_use = "handle the result"]
+pub async fn spend_download(
+    conn: &mut DbConnection,
+    rules: DownloadRules,
+    download: DownloadRequest,
+) -> Result<(), DownloadCheckError> {
+    let DownloadRequest {
+        user_id,
+        privileges,
+        size,
+    } = download;
+    if !rules.limits(privileges) {
+        return Ok(());
+    }
+    conn.transaction::<_, DownloadCheckError, _>(async |tx_conn| {
+        let standing = download_standing(tx_conn, user_id).await?;
+        rules.check(privileges, &standing, size)?;
+        if matches!(rules.policy, DownloadPolicy::Credits) {
+            let cost = i64::try_from(size).unwrap_or(i64::MAX);
+            adjust_download_credits(tx_conn, user_id, -cost).await?;
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for download policy parsing and decisions.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn ratio(raw: u32) -> NonZeroU32 { NonZeroU32::new(raw).expect("non-zero ratio") }
+
+    fn standing(uploaded: i64, downloaded: i64, credits: i64) -> DownloadStanding {
+        DownloadStanding {
+            transferred: TransferStats {
+                bytes_uploaded: uploaded,
+                bytes_downloaded: downloaded,
+                ..TransferStats::default()
+            },
+            credits,
+        }
+    }
+
+    #[rstest]
+    #[case::unset(None, None, Ok(DownloadPolicy::Off))]
+    #[case::off(Some("off"), Some(5), Ok(DownloadPolicy::Off))]
+    #[case::ratio_default(
+        Some("ratio"),
+        None,
+        Ok(DownloadPolicy::Ratio { ratio: NonZeroU32::MIN, free_bytes: 0 })
+    )]
+    #[case::ratio(Some("ratio"), Some(3), Ok(DownloadPolicy::Ratio { ratio: ratio(3), free_bytes: 0 }))]
+    #[case::credits(Some("credits"), None, Ok(DownloadPolicy::Credits))]
+    #[case::invalid(Some("quota"), None, Err(DownloadPolicyError::InvalidMode("quota".to_owned())))]
+    #[case::zero_ratio(Some("ratio"), Some(0), Err(DownloadPolicyError::ZeroRatio))]
+    fn parses_policy_from_config(
+        #[case] mode: Option<&str>,
+        #[case] raw_ratio: Option<u32>,
+        #[case] expected: Result<DownloadPolicy, DownloadPolicyError>,
+    ) {
+        let config = AppConfig {
+            download_policy: mode.map(str::to_owned),
+            download_ratio: raw_ratio,
+            ..AppConfig::default()
+        };
+        assert_eq!(
+            DownloadRules::from_config(&config).map(|rules| rules.policy),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::empty("", Ok(Privileges::empty()))]
+    #[case::names(
+        "disconnect_user, CANNOT_BE_DISCONNECTED",
+        Ok(Privileges::DISCONNECT_USER | Privileges::CANNOT_BE_DISCONNECTED)
+    )]
+    #[case::unknown(
+        "DOWNLOAD_FILE,LEECH",
+        Err(DownloadPolicyError::UnknownPrivilege("LEECH".to_owned()))
+    )]
+    fn parses_exempt_privileges(
+        #[case] names: &str,
+        #[case] expected: Result<Privileges, DownloadPolicyError>,
+    ) {
+        let config = AppConfig {
+            download_exempt_privileges: Some(names.to_owned()),
+            ..AppConfig::default()
+        };
+        assert_eq!(
+            DownloadRules::from_config(&config).map(|rules| rules.exempt),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case::off(DownloadPolicy::Off, standing(0, 0, 0), 1_000, true)]
+    #[case::free_allowance(
+        DownloadPolicy::Ratio { ratio: ratio(2), free_bytes: 100 },
+        standing(0, 0, 0),
+        100,
+        true
+    )]
+    #[case::within_ratio(
+        DownloadPolicy::Ratio { ratio: ratio(2), free_bytes: 0 },
+        standing(500, 600, 0),
+        400,
+        true
+    )]
+    #[case::past_ratio(
+        DownloadPolicy::Ratio { ratio: ratio(2), free_bytes: 0 },
+        standing(500, 600, 0),
+        401,
+        false
+    )]
+    #[case::enough_credits(DownloadPolicy::Credits, standing(0, 0, 50), 50, true)]
+    #[case::too_few_credits(DownloadPolicy::Credits, standing(0, 0, 49), 50, false)]
+    #[case::negative_credits(DownloadPolicy::Credits, standing(0, 0, -5), 0, true)]
+    fn checks_downloads_against_policy(
+        #[case] policy: DownloadPolicy,
+        #[case] standing: DownloadStanding,
+        #[case] size: u64,
+        #[case] allowed: bool,
+    ) {
+        let rules = DownloadRules {
+            policy,
+            exempt: Privileges::empty(),
+        };
+        assert_eq!(
+            rules
+                .check(Privileges::DOWNLOAD_FILE, &standing, size)
+                .is_ok(),
+            allowed
+        );
+    }
+
+    #[rstest]
+    fn exempt_privileges_bypass_the_policy() {
+        let rules = DownloadRules {
+            policy: DownloadPolicy::Credits,
+            exempt: Privileges::DISCONNECT_USER,
+        };
+        let broke = standing(0, 0, 0);
+
+        assert!(rules.check(Privileges::DISCONNECT_USER, &broke, 10).is_ok());
+        assert_eq!(
+            rules.check(Privileges::DOWNLOAD_FILE, &broke, 10),
+            Err(DownloadRefusal::InsufficientCredits {
+                credits: 0,
+                size: 10
+            })
+        );
+    }
+
+    #[rstest]
+    fn refusal_explains_the_shortfall() {
+        let refusal = DownloadRefusal::InsufficientCredits {
+            credits: 3,
+            size: 10,
+        };
+        assert!(
+            refusal
+                .to_string()
+                .contains("needs 10 credits and you have 3")
+        );
+    }
+}
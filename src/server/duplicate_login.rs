@@ -0,0 +1,77 @@
+//! Notice to sessions whose account logs in again elsewhere.
+//!
+//! An account may be online from several places at once. When another login
+//! to it comes online, each session already online receives a Server Message
+//! (104) saying so, which lets a user notice a login they did not make, and
+//! the login is recorded under [`AUDIT_TARGET`] with every address involved.
+
+use std::net::IpAddr;
+
+use tracing::info;
+
+use super::logging::AUDIT_TARGET;
+use crate::{
+    field_id::FieldId,
+    presence::{AccountSession, PresenceSnapshot, server_notification},
+    transaction::{Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// Text sent to the sessions an account already had online.
+pub const LOGGED_IN_ELSEWHERE: &str = "Your account has just logged in from another location.";
+
+/// Build the `104` push telling a session its account logged in elsewhere.
+///
+/// # Errors
+///
+/// Returns an encoding error if the message exceeds protocol limits.
+pub fn build_logged_in_elsewhere_msg() -> Result<Transaction, TransactionError> {
+    let payload = encode_params(&[(FieldId::Data, LOGGED_IN_ELSEWHERE.as_bytes())])?;
+    Ok(server_notification(TransactionType::ServerMsg, payload))
+}
+
+/// Record in the audit log that `snapshot` came online from `address` while
+/// `earlier` sessions of the same account were online.
+pub fn audit_duplicate_login(
+    snapshot: &PresenceSnapshot,
+    address: IpAddr,
+    earlier: &[AccountSession],
+) {
+    let earlier_addresses: Vec<String> = earlier
+        .iter()
+        .map(|session| {
+            session
+                .address
+                .map_or_else(|| "unknown".to_owned(), |ip| ip.to_string())
+        })
+        .collect();
+    info!(
+        target: AUDIT_TARGET,
+        account_id = snapshot.account_id,
+        %address,
+        earlier_addresses = %earlier_addresses.join(", "),
+        "account logged in from a second location"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for the logged-in-elsewhere notice.
+    use rstest::rstest;
+
+    use super::*;
+    use crate::transaction::decode_params;
+
+    #[rstest]
+    fn notice_carries_only_the_text() {
+        let push = build_logged_in_elsewhere_msg().expect("build notice");
+
+        assert_eq!(push.header.ty, u16::from(TransactionType::ServerMsg));
+        assert_eq!(push.header.is_reply, 0);
+        let params = decode_params(&push.payload).expect("decode notice");
+        assert_eq!(
+            params,
+            vec![(FieldId::Data, LOGGED_IN_ELSEWHERE.as_bytes().to_vec())]
+        );
+    }
+}
@@ -0,0 +1,310 @@
+//! Hotline "flattened file" objects sent over the transfer port.
+//!
+//! Downloads and uploads do not send a file's bytes alone. They wrap them in
+//! a flattened file object: a 24-byte `FILP` header giving the fork count,
+//! then each fork behind a 16-byte header naming its type and size. The
+//! `INFO` fork carries the file's type and creator codes, dates, name, and
+//! comment; the `DATA` fork carries the content. Classic Mac OS clients may
+//! also send a `MACR` resource fork, which mxd does not store and skips.
+//! [`read_flat_file_header`] and [`read_fork_header`] let the transfer port
+//! walk an upload fork by fork, streaming the data fork to storage instead of
+//! holding it; [`read_flat_file`] reads a whole object for small ones.
+//! Dates use the same epoch-millisecond encoding as every other timestamp
+//! mxd sends; see [`crate::wire_time`].
+
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use std::io;
+
+use chrono::NaiveDateTime;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::wire_time::{decode_timestamp, encode_optional_timestamp};
+
+/// Tag opening every flattened file object.
+pub const FLAT_FILE_MAGIC: [u8; 4] = *b"FILP";
+
+/// Format version mxd writes and accepts.
+pub const FLAT_FILE_VERSION: u16 = 1;
+
+/// Length of the object header in bytes.
+pub const FLAT_FILE_HEADER_LEN: usize = 24;
+
+/// Length of each fork header in bytes.
+pub const FORK_HEADER_LEN: usize = 16;
+
+const INFO_FORK: [u8; 4] = *b"INFO";
+const DATA_FORK: [u8; 4] = *b"DATA";
+const PLATFORM: [u8; 4] = *b"AMAC";
+
+/// Bytes of the information fork before the name: platform, type, creator,
+/// flags, platform flags, 32 reserved bytes, two dates, and the name's
+/// script and length.
+const INFO_FIXED_LEN: usize = 72;
+
+/// Longest information fork accepted: the fixed fields, then a name and a
+/// comment of up to `u16::MAX` bytes each, behind their lengths.
+const MAX_INFO_FORK_LEN: u32 = 72 + 65_535 + 2 + 65_535;
+
+/// Metadata carried in the information fork.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlatFileInfo {
+    /// File name.
+    pub name: String,
+    /// Classic Mac OS type code.
+    pub type_code: [u8; 4],
+    /// Classic Mac OS creator code.
+    pub creator_code: [u8; 4],
+    /// Creation time, when known.
+    pub created_at: Option<NaiveDateTime>,
+    /// Last modification time, when known.
+    pub modified_at: Option<NaiveDateTime>,
+    /// File comment.
+    pub comment: Option<String>,
+}
+
+/// A flattened file read from a client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlatFile {
+    /// Metadata from the information fork.
+    pub info: FlatFileInfo,
+    /// Content of the data fork.
+    pub data: Vec<u8>,
+}
+
+/// Which fork a fork header opens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForkKind {
+    /// The `INFO` fork.
+    Info,
+    /// The `DATA` fork.
+    Data,
+    /// Any other fork, such as a `MACR` resource fork.
+    Other,
+}
+
+/// Header preceding each fork's bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ForkHeader {
+    /// Which fork follows.
+    pub kind: ForkKind,
+    /// Length of the fork in bytes.
+    pub len: u32,
+}
+
+/// Errors raised while writing or reading flattened files.
+#[derive(Debug, Error)]
+pub enum FlatFileError {
+    /// The object did not open with `FILP` version 1.
+    #[error("not a flattened file object")]
+    BadMagic,
+    /// The information fork was shorter than its fields.
+    #[error("flattened file information fork is truncated")]
+    BadInfo,
+    /// The object had no data fork.
+    #[error("flattened file has no data fork")]
+    MissingData,
+    /// A name, comment, or fork was too long for its length field.
+    #[error("flattened file field is too long")]
+    TooLong,
+    /// Reading the object failed or it ended early.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Encode the header, information fork, and data fork header preceding
+/// `data_len` bytes of content.
+///
+/// # Errors
+///
+/// Returns [`FlatFileError::TooLong`] if the name or comment does not fit
+/// its length field.
+pub fn encode_flat_file_prefix(
+    info: &FlatFileInfo,
+    data_len: u32,
+) -> Result<Vec<u8>, FlatFileError> {
+    let info_fork = encode_info_fork(info)?;
+    let info_len = u32::try_from(info_fork.len()).map_err(|_| FlatFileError::TooLong)?;
+    let mut out = Vec::with_capacity(FLAT_FILE_HEADER_LEN + 2 * FORK_HEADER_LEN + info_fork.len());
+    out.extend_from_slice(&FLAT_FILE_MAGIC);
+    out.extend_from_slice(&FLAT_FILE_VERSION.to_be_bytes());
+    out.extend_from_slice(&[0; 16]);
+    out.extend_from_slice(&2u16.to_be_bytes());
+    out.extend_from_slice(&fork_header(INFO_FORK, info_len));
+    out.extend_from_slice(&info_fork);
+    out.extend_from_slice(&fork_header(DATA_FORK, data_len));
+    Ok(out)
+}
+
+/// Read a flattened file from `reader`, keeping its information and data
+/// forks and skipping any others.
+///
+/// # Errors
+///
+/// Returns an error if the header is wrong, a fork is malformed, there is
+/// no data fork, or the stream ends early.
+pub async fn read_flat_file<R>(reader: &mut R) -> Result<FlatFile, FlatFileError>
+where
+    R: AsyncRead + Unpin,
+{
+    let forks = read_flat_file_header(reader).await?;
+    let mut info = FlatFileInfo::default();
+    let mut data = None;
+    for _ in 0..forks {
+        let fork = read_fork_header(reader).await?;
+        match fork.kind {
+            ForkKind::Info => info = read_info_fork(reader, fork.len).await?,
+            ForkKind::Data => data = Some(read_fork(reader, fork.len).await?),
+            ForkKind::Other => {
+                read_fork(reader, fork.len).await?;
+            }
+        }
+    }
+    Ok(FlatFile {
+        info,
+        data: data.ok_or(FlatFileError::MissingData)?,
+    })
+}
+
+/// Read the object header from `reader` and return how many forks follow.
+///
+/// # Errors
+///
+/// Returns [`FlatFileError::BadMagic`] unless the object opens with `FILP`
+/// version 1, or an error if the stream ends early.
+pub async fn read_flat_file_header<R>(reader: &mut R) -> Result<u16, FlatFileError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut header = [0u8; FLAT_FILE_HEADER_LEN];
+    reader.read_exact(&mut header).await?;
+    let [m0, m1, m2, m3, v0, v1, rest @ ..] = header;
+    if [m0, m1, m2, m3] != FLAT_FILE_MAGIC || u16::from_be_bytes([v0, v1]) != FLAT_FILE_VERSION {
+        return Err(FlatFileError::BadMagic);
+    }
+    let [.., c0, c1] = rest;
+    Ok(u16::from_be_bytes([c0, c1]))
+}
+
+/// Read the header of the next fork; its `len` bytes follow in `reader`.
+///
+/// # Errors
+///
+/// Returns an error if the stream ends early.
+pub async fn read_fork_header<R>(reader: &mut R) -> Result<ForkHeader, FlatFileError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut fork = [0u8; FORK_HEADER_LEN];
+    reader.read_exact(&mut fork).await?;
+    let [t0, t1, t2, t3, .., s0, s1, s2, s3] = fork;
+    let kind = match [t0, t1, t2, t3] {
+        INFO_FORK => ForkKind::Info,
+        DATA_FORK => ForkKind::Data,
+        _ => ForkKind::Other,
+    };
+    Ok(ForkHeader {
+        kind,
+        len: u32::from_be_bytes([s0, s1, s2, s3]),
+    })
+}
+
+/// Read and decode an information fork of `len` bytes.
+///
+/// # Errors
+///
+/// Returns [`FlatFileError::TooLong`] for a fork longer than any valid one,
+/// [`FlatFileError::BadInfo`] for a truncated one, or an error if the stream
+/// ends early.
+pub async fn read_info_fork<R>(reader: &mut R, len: u32) -> Result<FlatFileInfo, FlatFileError>
+where
+    R: AsyncRead + Unpin,
+{
+    if len > MAX_INFO_FORK_LEN {
+        return Err(FlatFileError::TooLong);
+    }
+    decode_info_fork(&read_fork(reader, len).await?)
+}
+
+async fn read_fork<R>(reader: &mut R, len: u32) -> Result<Vec<u8>, FlatFileError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut body = Vec::new();
+    reader.take(u64::from(len)).read_to_end(&mut body).await?;
+    if body.len() != usize::try_from(len).map_err(|_| FlatFileError::TooLong)? {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(body)
+}
+
+const fn fork_header(kind: [u8; 4], len: u32) -> [u8; FORK_HEADER_LEN] {
+    let [t0, t1, t2, t3] = kind;
+    let [s0, s1, s2, s3] = len.to_be_bytes();
+    [t0, t1, t2, t3, 0, 0, 0, 0, 0, 0, 0, 0, s0, s1, s2, s3]
+}
+
+fn encode_info_fork(info: &FlatFileInfo) -> Result<Vec<u8>, FlatFileError> {
+    let name_len = u16::try_from(info.name.len()).map_err(|_| FlatFileError::TooLong)?;
+    let comment = info.comment.as_deref().unwrap_or_default();
+    let comment_len = u16::try_from(comment.len()).map_err(|_| FlatFileError::TooLong)?;
+    let mut fork = Vec::with_capacity(INFO_FIXED_LEN + info.name.len() + 2 + comment.len());
+    fork.extend_from_slice(&PLATFORM);
+    fork.extend_from_slice(&info.type_code);
+    fork.extend_from_slice(&info.creator_code);
+    fork.extend_from_slice(&[0; 8 + 32]);
+    fork.extend_from_slice(&encode_optional_timestamp(info.created_at));
+    fork.extend_from_slice(&encode_optional_timestamp(info.modified_at));
+    fork.extend_from_slice(&[0; 2]);
+    fork.extend_from_slice(&name_len.to_be_bytes());
+    fork.extend_from_slice(info.name.as_bytes());
+    fork.extend_from_slice(&comment_len.to_be_bytes());
+    fork.extend_from_slice(comment.as_bytes());
+    Ok(fork)
+}
+
+/// Split the next `N` bytes off the front of `bytes`.
+fn take<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N], FlatFileError> {
+    let (head, tail) = bytes
+        .split_first_chunk::<N>()
+        .ok_or(FlatFileError::BadInfo)?;
+    *bytes = tail;
+    Ok(*head)
+}
+
+fn decode_info_fork(fork: &[u8]) -> Result<FlatFileInfo, FlatFileError> {
+    let mut rest = fork;
+    take::<4>(&mut rest)?;
+    let type_code = take::<4>(&mut rest)?;
+    let creator_code = take::<4>(&mut rest)?;
+    take::<{ 8 + 32 }>(&mut rest)?;
+    let created = take::<8>(&mut rest)?;
+    let modified = take::<8>(&mut rest)?;
+    take::<2>(&mut rest)?;
+    let name_len = u16::from_be_bytes(take(&mut rest)?);
+    let (name, after_name) = rest
+        .split_at_checked(usize::from(name_len))
+        .ok_or(FlatFileError::BadInfo)?;
+    let comment = match after_name.split_first_chunk::<2>() {
+        Some((len, tail)) => {
+            let text = tail
+                .get(..usize::from(u16::from_be_bytes(*len)))
+                .ok_or(FlatFileError::BadInfo)?;
+            Some(String::from_utf8_lossy(text).into_owned()).filter(|comment| !comment.is_empty())
+        }
+        None => None,
+    };
+    Ok(FlatFileInfo {
+        name: String::from_utf8_lossy(name).into_owned(),
+        type_code,
+        creator_code,
+        created_at: decode_timestamp(&created).filter(|_| created != [0; 8]),
+        modified_at: decode_timestamp(&modified).filter(|_| modified != [0; 8]),
+        comment,
+    })
+}
+
+#[cfg(test)]
+#[path = "flat_file_tests.rs"]
+mod tests;
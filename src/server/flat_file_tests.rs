@@ -0,0 +1,93 @@
+//! Writing and reading flattened file objects.
+
+use rstest::rstest;
+
+use super::*;
+
+fn sample_info() -> FlatFileInfo {
+    FlatFileInfo {
+        name: "guide.txt".to_owned(),
+        type_code: *b"TEXT",
+        creator_code: *b"ttxt",
+        created_at: chrono::DateTime::from_timestamp(1_700_000_000, 0).map(|dt| dt.naive_utc()),
+        modified_at: None,
+        comment: Some("Read me first".to_owned()),
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn written_objects_read_back() {
+    let info = sample_info();
+    let mut object = encode_flat_file_prefix(&info, 5).expect("prefix encodes");
+    object.extend_from_slice(b"hello");
+
+    let file = read_flat_file(&mut object.as_slice())
+        .await
+        .expect("object reads");
+
+    assert_eq!(file.info, info);
+    assert_eq!(file.data, b"hello");
+}
+
+#[rstest]
+#[tokio::test]
+async fn forks_are_read_one_at_a_time() {
+    let mut object = encode_flat_file_prefix(&sample_info(), 5).expect("prefix encodes");
+    object.extend_from_slice(b"hello");
+    let mut reader = object.as_slice();
+
+    assert_eq!(read_flat_file_header(&mut reader).await.expect("header"), 2);
+    let info = read_fork_header(&mut reader).await.expect("info header");
+    assert_eq!(info.kind, ForkKind::Info);
+    let decoded = read_info_fork(&mut reader, info.len)
+        .await
+        .expect("info fork");
+    assert_eq!(decoded, sample_info());
+    let data = read_fork_header(&mut reader).await.expect("data header");
+    assert_eq!(
+        data,
+        ForkHeader {
+            kind: ForkKind::Data,
+            len: 5,
+        }
+    );
+    assert_eq!(reader, b"hello");
+}
+
+#[rstest]
+#[tokio::test]
+async fn oversized_information_forks_are_refused() {
+    let result = read_info_fork(&mut [0u8; 0].as_slice(), u32::MAX).await;
+
+    assert!(matches!(result, Err(FlatFileError::TooLong)));
+}
+
+#[rstest]
+#[tokio::test]
+async fn resource_forks_are_skipped() {
+    let mut object = encode_flat_file_prefix(&sample_info(), 2).expect("prefix encodes");
+    object.extend_from_slice(b"hi");
+    object.extend_from_slice(&fork_header(*b"MACR", 3));
+    object.extend_from_slice(b"rsc");
+    if let Some(count) = object.get_mut(22..24) {
+        count.copy_from_slice(&3u16.to_be_bytes());
+    }
+
+    let file = read_flat_file(&mut object.as_slice())
+        .await
+        .expect("object reads");
+
+    assert_eq!(file.data, b"hi");
+}
+
+#[rstest]
+#[case::wrong_magic(b"FILQ\0\x01".to_vec())]
+#[case::truncated(encode_flat_file_prefix(&sample_info(), 10).expect("prefix encodes"))]
+#[tokio::test]
+async fn malformed_objects_are_refused(#[case] object: Vec<u8>) {
+    let mut padded = object;
+    padded.resize(padded.len().max(FLAT_FILE_HEADER_LEN), 0);
+
+    assert!(read_flat_file(&mut padded.as_slice()).await.is_err());
+}
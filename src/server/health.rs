@@ -0,0 +1,330 @@
+//! HTTP health and readiness probes.
+//!
+//! With `health_bind` set, both runtimes serve plain HTTP endpoints, so
+//! orchestrators and test harnesses can tell when the server is usable
+//! without scraping its output:
+//!
+//! - `/healthz` answers `200 OK` while the process is serving at all.
+//! - `/readyz` answers `200 OK` once a pooled database connection can be checked out and no
+//!   embedded migration is pending, and `503 Service Unavailable` otherwise. The body lists each
+//!   check, one per line.
+//! - `/metrics` reports the [`AcceptMetrics`] counters, one `name value` line each, in the
+//!   Prometheus text format.
+//!
+//! The legacy runtime starts the probes before it migrates, so `/readyz`
+//! reports the migrations as pending until startup finishes. The server has
+//! no authentication, so `health_bind` should name a loopback or otherwise
+//! private address.
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use thiserror::Error;
+use tokio::{task::JoinHandle, time::timeout};
+use tracing::info;
+
+use super::{
+    AppConfig,
+    accept::{AcceptMetrics, AcceptMetricsSnapshot},
+    http::{self, Response, parse_get},
+};
+use crate::db::{DbPool, migrations_pending};
+
+/// Longest each readiness check waits on the database.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Errors raised while validating the health probe options.
+#[derive(Debug, Error)]
+pub enum HealthConfigError {
+    /// `health_bind` is not a socket address.
+    #[error("invalid health_bind '{0}': expected an address such as 127.0.0.1:8080")]
+    InvalidBind(String),
+}
+
+/// Read the health probe address from `config`, or `None` when it is unset.
+///
+/// # Errors
+///
+/// Returns an error if `health_bind` is not a socket address.
+pub fn health_bind_from_config(
+    config: &AppConfig,
+) -> Result<Option<SocketAddr>, HealthConfigError> {
+    let Some(bind) = config.health_bind.as_deref() else {
+        return Ok(None);
+    };
+    bind.trim()
+        .parse()
+        .map(Some)
+        .map_err(|_| HealthConfigError::InvalidBind(bind.to_owned()))
+}
+
+/// Start serving the probes for `pool` and the counters in `accepts` on
+/// `bind` until the returned task is aborted, or return `None` when no probe
+/// address is configured. `database` is the connection string the pool was
+/// opened with.
+///
+/// # Errors
+///
+/// Returns an error if the probe address cannot be bound.
+pub fn start_health_server(
+    bind: Option<SocketAddr>,
+    pool: &DbPool,
+    database: &str,
+    accepts: Arc<AcceptMetrics>,
+) -> Result<Option<JoinHandle<()>>> {
+    let Some(addr) = bind else {
+        return Ok(None);
+    };
+    let probe = Probe::new(pool.clone(), database);
+    let handle = http::spawn(addr, "health", move |head| {
+        let request_probe = probe.clone();
+        let accepted = accepts.snapshot();
+        async move {
+            match parse_request(&head) {
+                Ok(Endpoint::Live) => Response::ok("text/plain; charset=utf-8", b"ok\n".to_vec()),
+                Ok(Endpoint::Ready) => request_probe.check().await.response(),
+                Ok(Endpoint::Metrics) => metrics_response(accepted),
+                Err(response) => response,
+            }
+        }
+    })?;
+    info!(%addr, "serving health probes");
+    Ok(Some(handle))
+}
+
+/// Probe a request asks for.
+#[derive(Debug, PartialEq, Eq)]
+enum Endpoint {
+    /// Whether the process is serving.
+    Live,
+    /// Whether the server can handle clients.
+    Ready,
+    /// Accept-loop counters.
+    Metrics,
+}
+
+fn parse_request(head: &str) -> Result<Endpoint, Response> {
+    match parse_get(head)?.0 {
+        "/healthz" => Ok(Endpoint::Live),
+        "/readyz" => Ok(Endpoint::Ready),
+        "/metrics" => Ok(Endpoint::Metrics),
+        _ => Err(Response::error("404 Not Found", "unknown probe")),
+    }
+}
+
+fn metrics_response(accepts: AcceptMetricsSnapshot) -> Response {
+    let body = format!(
+        "mxd_accept_accepted_total {}\nmxd_accept_errors_total \
+         {}\nmxd_accept_fd_exhaustions_total {}\nmxd_accept_shed_total {}\n",
+        accepts.accepted, accepts.errors, accepts.fd_exhaustions, accepts.shed
+    );
+    Response::ok(
+        "text/plain; version=0.0.4; charset=utf-8",
+        body.into_bytes(),
+    )
+}
+
+/// Outcome of one readiness check.
+#[derive(Debug, PartialEq, Eq)]
+enum Check {
+    /// The check passed with this status.
+    Passed(&'static str),
+    /// The check failed for this reason.
+    Failed(String),
+}
+
+/// Outcome of every readiness check.
+#[derive(Debug, PartialEq, Eq)]
+struct Report {
+    database: Check,
+    migrations: Check,
+}
+
+impl Report {
+    fn unreachable(reason: String) -> Self {
+        Self {
+            database: Check::Failed(format!("unreachable: {reason}")),
+            migrations: Check::Failed("unknown: database unreachable".to_owned()),
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        matches!(
+            (&self.database, &self.migrations),
+            (Check::Passed(_), Check::Passed(_))
+        )
+    }
+
+    fn response(&self) -> Response {
+        let line = |name: &str, check: &Check| match check {
+            Check::Passed(status) => format!("{name}: {status}\n"),
+            Check::Failed(reason) => format!("{name}: {reason}\n"),
+        };
+        let body = format!(
+            "{}{}",
+            line("database", &self.database),
+            line("migrations", &self.migrations)
+        );
+        Response {
+            status: if self.is_ready() {
+                "200 OK"
+            } else {
+                "503 Service Unavailable"
+            },
+            content_type: "text/plain; charset=utf-8",
+            body: body.into_bytes(),
+        }
+    }
+}
+
+/// Database state shared by every readiness request.
+#[derive(Clone)]
+struct Probe {
+    pool: DbPool,
+    database: Arc<str>,
+    /// Set once no migration was pending; migrations are never unapplied
+    /// while the server runs, so later checks skip the query.
+    migrated: Arc<AtomicBool>,
+}
+
+impl Probe {
+    fn new(pool: DbPool, database: &str) -> Self {
+        Self {
+            pool,
+            database: Arc::from(database),
+            migrated: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    async fn check(&self) -> Report {
+        // bb8 validates a connection as it is checked out, so a checkout
+        // proves the database answers.
+        let mut conn = match timeout(CHECK_TIMEOUT, self.pool.get()).await {
+            Ok(Ok(conn)) => conn,
+            Ok(Err(error)) => return Report::unreachable(error.to_string()),
+            Err(_) => return Report::unreachable("timed out".to_owned()),
+        };
+        let migrations = if self.migrated.load(Ordering::Relaxed) {
+            Check::Passed("applied")
+        } else {
+            match timeout(CHECK_TIMEOUT, migrations_pending(&mut conn, &self.database)).await {
+                Ok(Ok(false)) => {
+                    self.migrated.store(true, Ordering::Relaxed);
+                    Check::Passed("applied")
+                }
+                Ok(Ok(true)) => Check::Failed("pending".to_owned()),
+                Ok(Err(error)) => Check::Failed(format!("unknown: {error}")),
+                Err(_) => Check::Failed("unknown: timed out".to_owned()),
+            }
+        };
+        Report {
+            database: Check::Passed("ok"),
+            migrations,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Validating the probe options and answering probes.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, Some(None))]
+    #[case(Some("127.0.0.1:8080"), Some(Some("127.0.0.1:8080")))]
+    #[case(Some(" [::1]:8080 "), Some(Some("[::1]:8080")))]
+    #[case(Some("localhost"), None)]
+    fn parses_the_probe_address(
+        #[case] bind: Option<&str>,
+        #[case] expected: Option<Option<&str>>,
+    ) {
+        let config = AppConfig {
+            health_bind: bind.map(str::to_owned),
+            ..AppConfig::default()
+        };
+        let parsed = health_bind_from_config(&config)
+            .ok()
+            .map(|addr| addr.map(|addr| addr.to_string()));
+        assert_eq!(parsed.as_ref().map(Option::as_deref), expected);
+    }
+
+    #[rstest]
+    #[case("GET /healthz HTTP/1.1\r\n\r\n", Ok(Endpoint::Live))]
+    #[case("GET /readyz?verbose=1 HTTP/1.1\r\n\r\n", Ok(Endpoint::Ready))]
+    #[case("GET /metrics HTTP/1.1\r\n\r\n", Ok(Endpoint::Metrics))]
+    #[case("GET /status HTTP/1.1\r\n\r\n", Err("404 Not Found"))]
+    #[case("HEAD /readyz HTTP/1.1\r\n\r\n", Err("405 Method Not Allowed"))]
+    fn routes_probes(#[case] head: &str, #[case] expected: Result<Endpoint, &str>) {
+        assert_eq!(
+            parse_request(head).map_err(|response| response.status),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn metrics_list_each_accept_counter() {
+        let response = metrics_response(AcceptMetricsSnapshot {
+            accepted: 5,
+            errors: 2,
+            fd_exhaustions: 1,
+            shed: 1,
+        });
+        assert_eq!(response.status, "200 OK");
+        assert_eq!(
+            String::from_utf8(response.body).expect("utf-8"),
+            "mxd_accept_accepted_total 5\nmxd_accept_errors_total \
+             2\nmxd_accept_fd_exhaustions_total 1\nmxd_accept_shed_total 1\n"
+        );
+    }
+
+    #[rstest]
+    fn failed_checks_are_unavailable() {
+        let response = Report::unreachable("connection refused".to_owned()).response();
+        assert_eq!(response.status, "503 Service Unavailable");
+        assert_eq!(
+            String::from_utf8(response.body).expect("utf-8"),
+            "database: unreachable: connection refused\nmigrations: unknown: database \
+             unreachable\n"
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[rstest]
+    #[tokio::test]
+    async fn ready_once_migrations_are_applied() {
+        use crate::db::{apply_migrations, establish_pool};
+
+        let dir = tempfile::TempDir::new().expect("tempdir");
+        let database = dir.path().join("health.db").display().to_string();
+        let pool = establish_pool(&database).await.expect("pool");
+        let probe = Probe::new(pool.clone(), &database);
+
+        let report = probe.check().await;
+        assert_eq!(report.database, Check::Passed("ok"));
+        assert_eq!(report.migrations, Check::Failed("pending".to_owned()));
+        assert_eq!(report.response().status, "503 Service Unavailable");
+
+        let mut conn = pool.get().await.expect("connection");
+        apply_migrations(&mut conn, &database, None)
+            .await
+            .expect("migrate");
+        drop(conn);
+
+        let report = probe.check().await;
+        assert!(report.is_ready());
+        assert_eq!(
+            String::from_utf8(report.response().body).expect("utf-8"),
+            "database: ok\nmigrations: applied\n"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+//! Minimal HTTP/1.1 plumbing shared by the operator endpoints.
+//!
+//! The profiling and health servers answer one `GET` per connection and close
+//! it after the reply, which is all their clients (`curl`, `pprof`, and
+//! orchestrator probes) need. [`spawn`] runs the accept loop and hands each
+//! request head to the endpoint's responder. [`post`] is the matching client
+//! side, used to deliver session accounting records to a webhook.
+
+use std::{future::Future, io, net::SocketAddr, time::Duration};
+
+use anyhow::{Context, Result};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+use tracing::{debug, warn};
+
+use super::{accept::PAUSE_INITIAL, bind::bind_std_listener};
+
+/// Largest request head read before the request is refused.
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+
+/// Time a client has to send its request head, and again to take the reply.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// HTTP reply written back to the client.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct Response {
+    pub(super) status: &'static str,
+    pub(super) content_type: &'static str,
+    pub(super) body: Vec<u8>,
+}
+
+impl Response {
+    pub(super) const fn ok(content_type: &'static str, body: Vec<u8>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    pub(super) fn error(status: &'static str, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{message}\n").into_bytes(),
+        }
+    }
+
+    pub(super) fn to_bytes(&self) -> Vec<u8> {
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        );
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+/// Split a `GET` request head into its path and query string.
+///
+/// # Errors
+///
+/// Returns the reply refusing a malformed request line or another method.
+pub(super) fn parse_get(head: &str) -> Result<(&str, &str), Response> {
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Err(Response::error("400 Bad Request", "malformed request line"));
+    };
+    if method != "GET" {
+        return Err(Response::error(
+            "405 Method Not Allowed",
+            "only GET is supported",
+        ));
+    }
+    Ok(target.split_once('?').unwrap_or((target, "")))
+}
+
+/// Bind `addr` and answer each request with `respond` until the returned task
+/// is aborted. `name` labels the server in logs and errors.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound.
+pub(super) fn spawn<F, Fut>(
+    addr: SocketAddr,
+    name: &'static str,
+    respond: F,
+) -> Result<JoinHandle<()>>
+where
+    F: Fn(String) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Response> + Send + 'static,
+{
+    let listener = TcpListener::from_std(bind_std_listener(addr)?)
+        .with_context(|| format!("failed to bind {name} server on {addr}"))?;
+    Ok(tokio::spawn(serve(listener, name, respond)))
+}
+
+async fn serve<F, Fut>(listener: TcpListener, name: &'static str, respond: F)
+where
+    F: Fn(String) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Response> + Send + 'static,
+{
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let handler = respond.clone();
+                tokio::spawn(async move {
+                    if let Err(error) = answer(stream, handler).await {
+                        debug!(%peer, %error, server = name, "HTTP request failed");
+                    }
+                });
+            }
+            Err(error) => {
+                warn!(%error, server = name, "HTTP listener accept failed");
+                sleep(PAUSE_INITIAL).await;
+            }
+        }
+    }
+}
+
+async fn answer<F, Fut>(mut stream: TcpStream, respond: F) -> io::Result<()>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Response>,
+{
+    let head = timeout(REQUEST_TIMEOUT, read_head(&mut stream))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "request head timed out"))??;
+    let response = respond(head).await;
+    let reply = async {
+        stream.write_all(&response.to_bytes()).await?;
+        stream.shutdown().await
+    };
+    timeout(REQUEST_TIMEOUT, reply)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "reply timed out"))?
+}
+
+/// Send `body` to `path` on `authority` (`host:port`) as a `POST` and return
+/// the reply's status code.
+///
+/// The request asks the server to close the connection, so only the reply
+/// head is read.
+///
+/// # Errors
+///
+/// Returns an error if the server cannot be reached, does not answer within
+/// [`REQUEST_TIMEOUT`], or sends a malformed status line.
+pub(super) async fn post(
+    authority: &str,
+    path: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<u16> {
+    let exchange = async {
+        let mut stream = TcpStream::connect(authority).await?;
+        let head = format!(
+            "POST {path} HTTP/1.1\r\nHost: {authority}\r\nContent-Type: \
+             {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body).await?;
+        parse_status(&read_head(&mut stream).await?)
+    };
+    timeout(REQUEST_TIMEOUT, exchange)
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "HTTP request timed out"))?
+}
+
+/// Read the status code from a reply head.
+fn parse_status(head: &str) -> io::Result<u16> {
+    head.lines()
+        .next()
+        .and_then(|status_line| status_line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed status line"))
+}
+
+async fn read_head(stream: &mut TcpStream) -> io::Result<String> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        if head.len() > MAX_REQUEST_HEAD {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request head too large",
+            ));
+        }
+        let read = stream.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+        head.extend_from_slice(buf.get(..read).unwrap_or_default());
+    }
+    Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    //! Parsing request lines and encoding replies.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("GET /readyz HTTP/1.1\r\n\r\n", ("/readyz", ""))]
+    #[case("GET /debug/pprof/profile?seconds=5 HTTP/1.1\r\n\r\n", ("/debug/pprof/profile", "seconds=5"))]
+    fn splits_the_target(#[case] head: &str, #[case] expected: (&str, &str)) {
+        assert_eq!(parse_get(head), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("POST /readyz HTTP/1.1\r\n\r\n", "405 Method Not Allowed")]
+    #[case("", "400 Bad Request")]
+    fn refuses_other_requests(#[case] head: &str, #[case] status: &str) {
+        let refused = parse_get(head).expect_err("must be refused");
+        assert_eq!(refused.status, status);
+    }
+
+    #[rstest]
+    #[case("HTTP/1.1 204 No Content\r\n\r\n", Some(204))]
+    #[case("HTTP/1.0 500 Internal Server Error\r\n", Some(500))]
+    #[case("", None)]
+    #[case("garbage", None)]
+    fn reads_the_status_code(#[case] head: &str, #[case] expected: Option<u16>) {
+        assert_eq!(parse_status(head).ok(), expected);
+    }
+
+    #[rstest]
+    fn replies_carry_their_length() {
+        let bytes = Response::ok("image/svg+xml", b"<svg/>".to_vec()).to_bytes();
+        let text = String::from_utf8(bytes).expect("utf-8");
+        assert!(text.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(text.contains("Content-Length: 6\r\n"));
+        assert!(text.ends_with("\r\n\r\n<svg/>"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stalled_readers_time_out() {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("listener binds");
+        let addr = listener.local_addr().expect("listener address");
+        let mut client = TcpStream::connect(addr).await.expect("client connects");
+        let (stream, _) = listener.accept().await.expect("server accepts");
+        client
+            .write_all(b"GET / HTTP/1.1\r\n\r\n")
+            .await
+            .expect("request sends");
+
+        // The client never reads, so a reply larger than the socket buffers
+        // cannot be written.
+        let result = answer(stream, |_| async {
+            Response::ok("application/octet-stream", vec![0; 32 * 1024 * 1024])
+        })
+        .await;
+
+        assert_eq!(
+            result.map_err(|error| error.kind()),
+            Err(io::ErrorKind::TimedOut)
+        );
+    }
+}
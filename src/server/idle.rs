@@ -0,0 +1,182 @@
+//! Idle connection reaping.
+//!
+//! Operators set `idle_timeout_secs` to close connections that send no
+//! transactions for that long. Clients that are merely quiet stay connected
+//! by sending Connection Keep Alive (500), which is answered like any other
+//! request and so resets the timer. Each server keeps the window in its
+//! [`TransportSettings`](super::settings::TransportSettings) and hands it to
+//! every connection it accepts; reaped clients receive a Disconnect Message
+//! carrying [`IDLE_DISCONNECT_REASON`].
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use thiserror::Error;
+use tokio::time::{Instant, sleep_until};
+
+use super::AppConfig;
+
+/// Reason given in the Disconnect Message sent to an idle client.
+pub const IDLE_DISCONNECT_REASON: &str = "Disconnected for inactivity";
+
+/// Stand-in deadline for windows too long to represent as an [`Instant`].
+const NEVER: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+
+/// Errors raised while reading the idle timeout from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IdleTimeoutError {
+    /// `idle_timeout_secs` was zero.
+    #[error("idle_timeout_secs must be greater than zero")]
+    Zero,
+}
+
+/// Read the idle window from `config`; `None` disables reaping.
+///
+/// # Errors
+///
+/// Returns [`IdleTimeoutError::Zero`] for a zero timeout.
+pub fn idle_timeout_from_config(config: &AppConfig) -> Result<Option<Duration>, IdleTimeoutError> {
+    match config.idle_timeout_secs {
+        None => Ok(None),
+        Some(0) => Err(IdleTimeoutError::Zero),
+        Some(secs) => Ok(Some(Duration::from_secs(secs))),
+    }
+}
+
+/// Time of a connection's most recent transaction, shared between the code
+/// that handles transactions and the task that watches for idleness.
+#[derive(Debug)]
+pub struct ActivityClock {
+    origin: Instant,
+    last_millis: AtomicU64,
+}
+
+impl Default for ActivityClock {
+    fn default() -> Self { Self::new() }
+}
+
+impl ActivityClock {
+    /// Start a clock that counts the connection as active now.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            last_millis: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a transaction from the client.
+    pub fn touch(&self) {
+        let elapsed = u64::try_from(self.origin.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.last_millis.fetch_max(elapsed, Ordering::Relaxed);
+    }
+
+    /// Time since the connection's most recent transaction.
+    #[must_use]
+    pub fn idle_for(&self) -> Duration {
+        let last = Duration::from_millis(self.last_millis.load(Ordering::Relaxed));
+        self.origin.elapsed().saturating_sub(last)
+    }
+
+    /// Instant at which the connection counts as idle under `window`.
+    #[must_use]
+    pub fn deadline(&self, window: Duration) -> Instant {
+        let last = Duration::from_millis(self.last_millis.load(Ordering::Relaxed));
+        self.origin
+            .checked_add(last.saturating_add(window))
+            .unwrap_or_else(|| Instant::now() + NEVER)
+    }
+}
+
+/// Resolve once `clock` has seen no activity for `window`.
+///
+/// Activity recorded while waiting pushes the deadline back, so this only
+/// returns for a connection that really has gone quiet.
+pub async fn wait_until_idle(window: Duration, clock: &ActivityClock) {
+    loop {
+        let deadline = clock.deadline(window);
+        if deadline <= Instant::now() {
+            return;
+        }
+        sleep_until(deadline).await;
+    }
+}
+
+/// Like [`wait_until_idle`], but never resolves when `window` is `None`.
+pub async fn idle_expired(window: Option<Duration>, clock: &ActivityClock) {
+    match window {
+        Some(limit) => wait_until_idle(limit, clock).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the idle window and tracking activity.
+
+    use rstest::rstest;
+    use test_util::CLOCK_JUMPS;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, Ok(None))]
+    #[case(Some(0), Err(IdleTimeoutError::Zero))]
+    #[case(Some(300), Ok(Some(Duration::from_secs(300))))]
+    fn reads_idle_timeout(
+        #[case] secs: Option<u64>,
+        #[case] expected: Result<Option<Duration>, IdleTimeoutError>,
+    ) {
+        let config = AppConfig {
+            idle_timeout_secs: secs,
+            ..AppConfig::default()
+        };
+        assert_eq!(idle_timeout_from_config(&config), expected);
+    }
+
+    #[rstest]
+    fn touch_pushes_the_deadline_back() {
+        let clock = ActivityClock::new();
+        let window = Duration::from_secs(60);
+        let first = clock.deadline(window);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(clock.deadline(window), first);
+        let quiet = clock.idle_for();
+        assert!(quiet >= Duration::from_millis(5));
+        clock.touch();
+        assert!(clock.deadline(window) >= first + Duration::from_millis(5));
+        assert!(clock.idle_for() < quiet);
+    }
+
+    #[tokio::test]
+    async fn quiet_connection_expires() {
+        let clock = ActivityClock::new();
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            idle_expired(Some(Duration::from_millis(10)), &clock),
+        )
+        .await
+        .expect("idle window should elapse");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_windows_survive_clock_jumps() {
+        let window = Duration::from_secs(300);
+        for jump in CLOCK_JUMPS {
+            let clock = ActivityClock::new();
+            tokio::time::advance(jump.forward()).await;
+
+            let expired_now =
+                tokio::time::timeout(Duration::ZERO, idle_expired(Some(window), &clock))
+                    .await
+                    .is_ok();
+            assert_eq!(expired_now, jump.passes(window), "{jump:?}");
+            assert!(clock.deadline(Duration::MAX) > Instant::now(), "{jump:?}");
+            tokio::time::timeout(window, idle_expired(Some(window), &clock))
+                .await
+                .expect("idle window should elapse");
+        }
+    }
+}
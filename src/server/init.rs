@@ -0,0 +1,304 @@
+//! First-start setup behind `mxd init`.
+//!
+//! [`run_init`] settles the listen address, database, and first
+//! administrator from the `init` flags, asking on a terminal for anything
+//! left unset. It then migrates the database and creates the account with
+//! every privilege, through the same helpers as `migrate` and `create-user`,
+//! optionally writes a self-signed TLS certificate beside the configuration
+//! file, and finally writes `.mxd.toml`, so later runs start with those
+//! settings. The configuration file is written last and an administrator
+//! left behind by an earlier attempt is updated rather than created again,
+//! so a failed run can be repeated without `--force`. On a terminal the
+//! password is read without echo.
+
+mod prompt;
+
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use diesel_async::AsyncConnection;
+
+pub use self::prompt::{LinePrompt, Prompt, TerminalPrompt};
+use super::{
+    AppConfig,
+    InitArgs,
+    admin::{argon2_from_config, open_database},
+};
+use crate::{
+    db::{UserUpdate, create_user, get_user_by_name, set_privileges, update_user},
+    models::NewUser,
+    privileges::Privileges,
+    users::hash_password,
+};
+
+/// Configuration file written when `--config-path` is not given.
+pub const DEFAULT_CONFIG_PATH: &str = ".mxd.toml";
+/// Listen address offered when `--bind` is not given.
+pub const DEFAULT_BIND: &str = "0.0.0.0:5500";
+/// Database offered when `--database` is not given.
+pub const DEFAULT_DATABASE: &str = "mxd.db";
+/// Administrator name offered when `--admin-username` is not given.
+pub const DEFAULT_ADMIN_USERNAME: &str = "admin";
+
+const CERT_FILE: &str = "mxd-cert.pem";
+const KEY_FILE: &str = "mxd-key.pem";
+
+/// Answers `init` works from once every question is settled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InitPlan {
+    /// Configuration file to write.
+    pub config_path: PathBuf,
+    /// Address the server listens on.
+    pub bind: String,
+    /// Database connection string or path.
+    pub database: String,
+    /// Name of the first administrator account.
+    pub admin_username: String,
+    /// Password of the first administrator account.
+    pub admin_password: String,
+    /// Whether to generate a self-signed TLS certificate.
+    pub tls: bool,
+}
+
+impl InitPlan {
+    /// Settle every answer from `args`, asking `prompt` for the rest or
+    /// taking the defaults when there is no prompt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an answer cannot be read, or if the administrator
+    /// password is missing and there is no prompt to ask for it.
+    pub fn resolve(args: InitArgs, mut prompt: Option<&mut dyn Prompt>) -> Result<Self> {
+        let mut answer = |given: Option<String>, question: &str, default: Option<&str>| {
+            if let Some(value) = given {
+                return Ok(value);
+            }
+            match (prompt.as_deref_mut(), default) {
+                (Some(asker), _) => asker.ask(question, default),
+                (None, Some(suggested)) => Ok(suggested.to_owned()),
+                (None, None) => Err(anyhow!("{question} is required; pass it as a flag")),
+            }
+        };
+        let config_path = answer(
+            args.config_path,
+            "Configuration file",
+            Some(DEFAULT_CONFIG_PATH),
+        )?;
+        let bind = answer(args.bind, "Listen address", Some(DEFAULT_BIND))?;
+        let database = answer(args.database, "Database", Some(DEFAULT_DATABASE))?;
+        let admin_username = answer(
+            args.admin_username,
+            "Administrator name",
+            Some(DEFAULT_ADMIN_USERNAME),
+        )?;
+        let admin_password = match (args.admin_password, prompt.as_deref_mut()) {
+            (Some(password), _) => password,
+            (None, Some(asker)) => asker.ask_secret("Administrator password")?,
+            (None, None) => bail!("Administrator password is required; pass it as a flag"),
+        };
+        let tls = match prompt {
+            Some(asker) if !args.tls => asker.confirm("Generate a self-signed TLS certificate?")?,
+            _ => args.tls,
+        };
+        Ok(Self {
+            config_path: PathBuf::from(config_path),
+            bind,
+            database,
+            admin_username,
+            admin_password,
+            tls,
+        })
+    }
+}
+
+/// Run `mxd init`.
+///
+/// Questions are asked only when standard input is a terminal and
+/// `--non-interactive` is not set.
+///
+/// # Errors
+///
+/// Returns an error if the configuration file already exists without
+/// `--force`, or if any step of the setup fails.
+#[expect(
+    clippy::print_stdout,
+    reason = "intentional user output for CLI commands"
+)]
+pub async fn run_init(args: InitArgs, cfg: &AppConfig) -> Result<()> {
+    let interactive = !args.non_interactive && io::stdin().is_terminal();
+    let force = args.force;
+    let plan = if interactive {
+        let mut prompt = TerminalPrompt::new();
+        InitPlan::resolve(args, Some(&mut prompt))?
+    } else {
+        InitPlan::resolve(args, None)?
+    };
+    apply_plan(&plan, cfg, force).await?;
+    println!(
+        "Wrote {}; start the server from this directory to use it",
+        plan.config_path.display()
+    );
+    Ok(())
+}
+
+/// Carry out `plan`: prepare the database and administrator, write any
+/// certificate, then write the configuration file.
+///
+/// # Errors
+///
+/// Returns an error if the configuration file already exists and `force`
+/// is false, or if any step fails.
+pub async fn apply_plan(plan: &InitPlan, cfg: &AppConfig, force: bool) -> Result<()> {
+    if !force && plan.config_path.exists() {
+        bail!(
+            "{} already exists; pass --force to replace it",
+            plan.config_path.display()
+        );
+    }
+    let db_cfg = AppConfig {
+        database: plan.database.clone(),
+        ..cfg.clone()
+    };
+    create_admin(plan, &db_cfg).await?;
+    let tls = if plan.tls {
+        Some(write_certificate(&plan.config_path, &plan.bind)?)
+    } else {
+        None
+    };
+    let text = render_config(plan, tls.as_ref());
+    fs::write(&plan.config_path, text)
+        .with_context(|| format!("failed to write {}", plan.config_path.display()))
+}
+
+/// Create the administrator with every privilege, or, when an earlier run
+/// already created an account of that name, replace its password and
+/// privileges so the run can be repeated.
+async fn create_admin(plan: &InitPlan, cfg: &AppConfig) -> Result<()> {
+    let argon2 = argon2_from_config(cfg)?;
+    let hashed = hash_password(&argon2, &plan.admin_password)?;
+    let username = plan.admin_username.as_str();
+    let mut conn = open_database(cfg).await?;
+    conn.transaction::<_, anyhow::Error, _>(async |tx_conn| {
+        if get_user_by_name(tx_conn, username).await?.is_some() {
+            let update = UserUpdate {
+                username: None,
+                password: Some(&hashed),
+            };
+            update_user(tx_conn, username, &update).await?;
+        } else {
+            create_user(
+                tx_conn,
+                &NewUser {
+                    username,
+                    password: &hashed,
+                },
+            )
+            .await
+            .with_context(|| format!("failed to create user '{username}'"))?;
+        }
+        set_privileges(tx_conn, username, Privileges::all()).await?;
+        Ok(())
+    })
+    .await
+}
+
+/// Paths of a generated certificate and its key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TlsFiles {
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+fn write_certificate(config_path: &Path, bind: &str) -> Result<TlsFiles> {
+    let dir = config_path.parent().unwrap_or_else(|| Path::new(""));
+    let files = TlsFiles {
+        cert: dir.join(CERT_FILE),
+        key: dir.join(KEY_FILE),
+    };
+    let generated = rcgen::generate_simple_self_signed(certificate_names(bind))
+        .context("failed to generate TLS certificate")?;
+    fs::write(&files.cert, generated.cert.pem())
+        .with_context(|| format!("failed to write {}", files.cert.display()))?;
+    write_private(&files.key, generated.signing_key.serialize_pem().as_bytes())
+        .with_context(|| format!("failed to write {}", files.key.display()))?;
+    Ok(files)
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> { fs::write(path, contents) }
+
+/// Names the certificate covers: `localhost` and every specific host in
+/// `bind`.
+fn certificate_names(bind: &str) -> Vec<String> {
+    let mut names = vec!["localhost".to_owned()];
+    for address in bind.split(',') {
+        let host = address
+            .trim()
+            .rsplit_once(':')
+            .map_or(address.trim(), |(host, _)| host)
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        if !matches!(host, "" | "0.0.0.0" | "::") && !names.iter().any(|name| name == host) {
+            names.push(host.to_owned());
+        }
+    }
+    names
+}
+
+fn render_config(plan: &InitPlan, tls: Option<&TlsFiles>) -> String {
+    let mut lines = vec![
+        "# Written by `mxd init`.".to_owned(),
+        format!("bind = {}", toml_string(&plan.bind)),
+        format!("database = {}", toml_string(&plan.database)),
+    ];
+    if let Some(files) = tls {
+        lines.push(format!(
+            "tls_cert = {}",
+            toml_string(&files.cert.display().to_string())
+        ));
+        lines.push(format!(
+            "tls_key = {}",
+            toml_string(&files.key.display().to_string())
+        ));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Quote `value` as a TOML basic string.
+fn toml_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len().saturating_add(2));
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04X}", u32::from(ch))),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+#[path = "init_tests.rs"]
+mod tests;
@@ -0,0 +1,114 @@
+//! Questions `mxd init` asks for settings missing from its flags.
+
+use std::io::{self, BufRead, Write};
+
+use anyhow::{Context, Result, bail};
+
+/// Source of answers for settings missing from the `init` flags.
+pub trait Prompt {
+    /// Ask `question`, returning `default` when the answer is blank.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the answer cannot be read.
+    fn ask(&mut self, question: &str, default: Option<&str>) -> Result<String>;
+
+    /// Ask for a secret with no default, such as a password.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the answer cannot be read.
+    fn ask_secret(&mut self, question: &str) -> Result<String>;
+
+    /// Ask a yes-or-no `question` whose blank answer is no.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the answer cannot be read.
+    fn confirm(&mut self, question: &str) -> Result<bool>;
+}
+
+/// [`Prompt`] reading answers line by line from `input`.
+///
+/// Secrets are read like any other answer, so they are echoed when `input`
+/// is a terminal; [`TerminalPrompt`] reads them without echo.
+pub struct LinePrompt<R, W> {
+    input: R,
+    pub(super) output: W,
+}
+
+impl<R: BufRead, W: Write> LinePrompt<R, W> {
+    /// Ask questions on `output` and read the answers from `input`.
+    pub const fn new(input: R, output: W) -> Self { Self { input, output } }
+
+    fn read_answer(&mut self, question: &str) -> Result<String> {
+        write!(self.output, "{question}")?;
+        self.output.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line)? == 0 {
+            bail!("input ended before init finished");
+        }
+        Ok(line.trim().to_owned())
+    }
+}
+
+impl<R: BufRead, W: Write> Prompt for LinePrompt<R, W> {
+    fn ask(&mut self, question: &str, default: Option<&str>) -> Result<String> {
+        loop {
+            let answer = match default {
+                Some(suggested) => self.read_answer(&format!("{question} [{suggested}]: "))?,
+                None => self.read_answer(&format!("{question}: "))?,
+            };
+            match (answer.is_empty(), default) {
+                (false, _) => return Ok(answer),
+                (true, Some(suggested)) => return Ok(suggested.to_owned()),
+                (true, None) => writeln!(self.output, "An answer is required.")?,
+            }
+        }
+    }
+
+    fn ask_secret(&mut self, question: &str) -> Result<String> { self.ask(question, None) }
+
+    fn confirm(&mut self, question: &str) -> Result<bool> {
+        let answer = self.read_answer(&format!("{question} [y/N]: "))?;
+        Ok(matches!(answer.to_ascii_lowercase().as_str(), "y" | "yes"))
+    }
+}
+
+/// [`Prompt`] for an interactive terminal, reading secrets without echo.
+pub struct TerminalPrompt {
+    lines: LinePrompt<io::StdinLock<'static>, io::Stdout>,
+}
+
+impl TerminalPrompt {
+    /// Ask questions on standard output and read answers from standard input.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            lines: LinePrompt::new(io::stdin().lock(), io::stdout()),
+        }
+    }
+}
+
+impl Default for TerminalPrompt {
+    fn default() -> Self { Self::new() }
+}
+
+impl Prompt for TerminalPrompt {
+    fn ask(&mut self, question: &str, default: Option<&str>) -> Result<String> {
+        self.lines.ask(question, default)
+    }
+
+    fn ask_secret(&mut self, question: &str) -> Result<String> {
+        loop {
+            let answer = rpassword::prompt_password(format!("{question}: "))
+                .context("failed to read from the terminal")?;
+            if !answer.is_empty() {
+                return Ok(answer);
+            }
+            writeln!(self.lines.output, "An answer is required.")?;
+        }
+    }
+
+    fn confirm(&mut self, question: &str) -> Result<bool> { self.lines.confirm(question) }
+}
@@ -0,0 +1,176 @@
+//! Unit tests for `mxd init`.
+
+use std::io::Cursor;
+
+use rstest::rstest;
+use tempfile::TempDir;
+
+use super::*;
+
+fn answers(input: &str) -> LinePrompt<Cursor<Vec<u8>>, Vec<u8>> {
+    LinePrompt::new(Cursor::new(input.as_bytes().to_vec()), Vec::new())
+}
+
+#[rstest]
+fn blank_answers_take_the_defaults() {
+    // Every blank line takes the default, but the password is asked again.
+    let mut prompt = answers("\n\n\n\n\nsecret\ny\n");
+    let plan = InitPlan::resolve(InitArgs::default(), Some(&mut prompt)).expect("resolve");
+
+    assert_eq!(
+        plan,
+        InitPlan {
+            config_path: PathBuf::from(DEFAULT_CONFIG_PATH),
+            bind: DEFAULT_BIND.to_owned(),
+            database: DEFAULT_DATABASE.to_owned(),
+            admin_username: DEFAULT_ADMIN_USERNAME.to_owned(),
+            admin_password: "secret".to_owned(),
+            tls: true,
+        }
+    );
+    let shown = String::from_utf8(prompt.output).expect("utf-8");
+    assert!(shown.contains("An answer is required."));
+}
+
+#[rstest]
+fn flags_are_not_asked_again() {
+    let args = InitArgs {
+        bind: Some("127.0.0.1:6500".to_owned()),
+        admin_password: Some("secret".to_owned()),
+        tls: true,
+        ..InitArgs::default()
+    };
+    let mut prompt = answers("\n\nsysop\n");
+    let plan = InitPlan::resolve(args, Some(&mut prompt)).expect("resolve");
+
+    assert_eq!(plan.bind, "127.0.0.1:6500");
+    assert_eq!(plan.admin_username, "sysop");
+    assert!(plan.tls);
+}
+
+#[rstest]
+fn non_interactive_runs_need_a_password() {
+    let err = InitPlan::resolve(InitArgs::default(), None).expect_err("must fail");
+    assert!(err.to_string().contains("Administrator password"));
+
+    let args = InitArgs {
+        admin_password: Some("secret".to_owned()),
+        ..InitArgs::default()
+    };
+    let plan = InitPlan::resolve(args, None).expect("resolve");
+    assert_eq!(plan.database, DEFAULT_DATABASE);
+    assert!(!plan.tls);
+}
+
+#[rstest]
+fn ended_input_is_an_error() {
+    let mut prompt = answers("");
+    assert!(InitPlan::resolve(InitArgs::default(), Some(&mut prompt)).is_err());
+}
+
+#[rstest]
+#[case("0.0.0.0:5500", &["localhost"])]
+#[case("chat.example.org:5500", &["localhost", "chat.example.org"])]
+#[case("[::1]:5500, 192.0.2.4:5500", &["localhost", "::1", "192.0.2.4"])]
+fn certificate_covers_specific_hosts(#[case] bind: &str, #[case] expected: &[&str]) {
+    assert_eq!(certificate_names(bind), expected);
+}
+
+#[rstest]
+fn config_values_are_quoted() {
+    assert_eq!(
+        toml_string(r#"C:\mxd "data".db"#),
+        r#""C:\\mxd \"data\".db""#
+    );
+    assert_eq!(toml_string("a\u{1}b"), r#""a\u0001b""#);
+}
+
+#[rstest]
+fn rendered_config_names_the_certificate() {
+    let plan = InitPlan::resolve(
+        InitArgs {
+            admin_password: Some("secret".to_owned()),
+            ..InitArgs::default()
+        },
+        None,
+    )
+    .expect("resolve");
+    let tls = TlsFiles {
+        cert: PathBuf::from(CERT_FILE),
+        key: PathBuf::from(KEY_FILE),
+    };
+
+    assert_eq!(
+        render_config(&plan, Some(&tls)),
+        "# Written by `mxd init`.\nbind = \"0.0.0.0:5500\"\ndatabase = \"mxd.db\"\ntls_cert = \
+         \"mxd-cert.pem\"\ntls_key = \"mxd-key.pem\"\n"
+    );
+}
+
+#[rstest]
+#[tokio::test]
+async fn existing_config_is_kept_without_force() {
+    let dir = TempDir::new().expect("tempdir");
+    let config_path = dir.path().join(DEFAULT_CONFIG_PATH);
+    fs::write(&config_path, "bind = \"127.0.0.1:5500\"\n").expect("write");
+    let plan = InitPlan {
+        config_path: config_path.clone(),
+        bind: DEFAULT_BIND.to_owned(),
+        database: DEFAULT_DATABASE.to_owned(),
+        admin_username: DEFAULT_ADMIN_USERNAME.to_owned(),
+        admin_password: "secret".to_owned(),
+        tls: false,
+    };
+
+    let err = apply_plan(&plan, &AppConfig::default(), false)
+        .await
+        .expect_err("must refuse");
+
+    assert!(err.to_string().contains("--force"));
+    assert_eq!(
+        fs::read_to_string(&config_path).expect("read"),
+        "bind = \"127.0.0.1:5500\"\n"
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[rstest]
+#[tokio::test]
+async fn failed_run_can_be_repeated_without_force() {
+    use crate::{
+        db::{DbConnection, decode_privileges},
+        users::verify_password,
+    };
+
+    let dir = TempDir::new().expect("tempdir");
+    let database = dir.path().join("mxd.db").display().to_string();
+    let config_path = dir.path().join(DEFAULT_CONFIG_PATH);
+    let mut plan = InitPlan {
+        // The missing directory makes the final write fail after the
+        // administrator has been created.
+        config_path: dir.path().join("missing").join(DEFAULT_CONFIG_PATH),
+        bind: DEFAULT_BIND.to_owned(),
+        database: database.clone(),
+        admin_username: DEFAULT_ADMIN_USERNAME.to_owned(),
+        admin_password: "first".to_owned(),
+        tls: false,
+    };
+    apply_plan(&plan, &AppConfig::default(), false)
+        .await
+        .expect_err("config write must fail");
+
+    plan.config_path.clone_from(&config_path);
+    plan.admin_password = "second".to_owned();
+    apply_plan(&plan, &AppConfig::default(), false)
+        .await
+        .expect("rerun");
+
+    assert!(config_path.exists());
+    let mut conn = DbConnection::establish(&database).await.expect("connect");
+    let admin = get_user_by_name(&mut conn, DEFAULT_ADMIN_USERNAME)
+        .await
+        .expect("query")
+        .expect("administrator");
+    assert!(verify_password(&admin.password, "second"));
+    assert_eq!(decode_privileges(admin.privileges), Privileges::all());
+}
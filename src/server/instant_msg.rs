@@ -0,0 +1,103 @@
+//! Private instant message delivery.
+//!
+//! Send Instant Message (108) requests are relayed to one online session as a
+//! Server Message (104) push through the runtime's [`OutboundMessaging`]
+//! adapter. Hotline has no store-and-forward mail, so a message addressed to a
+//! user who is not online is refused rather than queued.
+
+use crate::{
+    field_id::FieldId,
+    presence::server_notification,
+    transaction::{Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// Options (field 113) value for an ordinary user message.
+pub const MSG_OPTION_USER: u32 = 1;
+/// Options (field 113) value for an automatic response to a message.
+pub const MSG_OPTION_AUTOMATIC_RESPONSE: u32 = 4;
+
+/// A private message from one online session to another.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstantMessage {
+    /// User identifier of the sender.
+    pub sender_id: i32,
+    /// Nickname of the sender.
+    pub sender_name: String,
+    /// Options value supplied by the sender (field 113).
+    pub options: u32,
+    /// Message text.
+    pub text: String,
+    /// Quoted text from an earlier message, if any (field 214).
+    pub quoting: Option<String>,
+}
+
+/// Build a `104` push delivering `message` to its recipient.
+///
+/// # Errors
+///
+/// Returns an encoding error if the payload would exceed protocol limits.
+#[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+pub fn build_server_msg(message: &InstantMessage) -> Result<Transaction, TransactionError> {
+    let sender_id = message.sender_id.to_be_bytes();
+    let options = message.options.to_be_bytes();
+    let mut params: Vec<(FieldId, &[u8])> = vec![
+        (FieldId::UserId, sender_id.as_slice()),
+        (FieldId::Name, message.sender_name.as_bytes()),
+        (FieldId::Options, options.as_slice()),
+        (FieldId::Data, message.text.as_bytes()),
+    ];
+    if let Some(quoting) = &message.quoting {
+        params.push((FieldId::QuotingMsg, quoting.as_bytes()));
+    }
+    let payload = encode_params(&params)?;
+    Ok(server_notification(TransactionType::ServerMsg, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for Server Message construction.
+    use rstest::rstest;
+
+    use super::*;
+    use crate::transaction::decode_params;
+
+    fn message(quoting: Option<&str>) -> InstantMessage {
+        InstantMessage {
+            sender_id: 7,
+            sender_name: "alice".to_owned(),
+            options: MSG_OPTION_USER,
+            text: "hello".to_owned(),
+            quoting: quoting.map(str::to_owned),
+        }
+    }
+
+    #[rstest]
+    fn server_msg_carries_sender_and_text() {
+        let push = build_server_msg(&message(None)).expect("build message");
+
+        assert_eq!(push.header.ty, u16::from(TransactionType::ServerMsg));
+        assert_eq!(push.header.is_reply, 0);
+        let params = decode_params(&push.payload).expect("decode message");
+        assert_eq!(
+            params,
+            vec![
+                (FieldId::UserId, vec![0, 0, 0, 7]),
+                (FieldId::Name, b"alice".to_vec()),
+                (FieldId::Options, vec![0, 0, 0, 1]),
+                (FieldId::Data, b"hello".to_vec()),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn server_msg_includes_quoted_text() {
+        let push = build_server_msg(&message(Some("earlier"))).expect("build message");
+
+        let params = decode_params(&push.payload).expect("decode message");
+        assert_eq!(
+            params.last(),
+            Some(&(FieldId::QuotingMsg, b"earlier".to_vec()))
+        );
+    }
+}
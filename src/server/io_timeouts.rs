@@ -0,0 +1,104 @@
+//! Read and write timeouts for client connections.
+//!
+//! A client may pause mid-request on a slow link, so reads get the longer
+//! allowance; a client that stops draining its socket only holds up the
+//! server, so writes give up sooner. Operators override the defaults with
+//! `read_timeout_secs` and `write_timeout_secs`.
+//!
+//! The legacy runtime hands both to each connection's transaction reader and
+//! writer. The Wireframe runtime bounds the gap between fragments of one
+//! request by the read timeout (see [`crate::wireframe::codec`]), and bounds
+//! how long a push may wait for room in a connection's outbound queue by the
+//! write timeout.
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+use super::AppConfig;
+use crate::transaction::{READ_TIMEOUT, WRITE_TIMEOUT};
+
+/// How long a connection may stall while reading or writing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IoTimeouts {
+    /// Longest wait for the next bytes of a frame the client is sending.
+    pub read: Duration,
+    /// Longest wait for a frame to be accepted for sending to the client.
+    pub write: Duration,
+}
+
+/// Errors raised while reading the timeouts from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum IoTimeoutError {
+    /// `read_timeout_secs` was zero.
+    #[error("read_timeout_secs must be greater than zero")]
+    ZeroRead,
+    /// `write_timeout_secs` was zero.
+    #[error("write_timeout_secs must be greater than zero")]
+    ZeroWrite,
+}
+
+impl IoTimeouts {
+    /// [`READ_TIMEOUT`] and [`WRITE_TIMEOUT`].
+    pub const DEFAULT: Self = Self {
+        read: READ_TIMEOUT,
+        write: WRITE_TIMEOUT,
+    };
+
+    /// Read the timeouts from `config`, defaulting each one left unset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either timeout is zero.
+    pub fn from_config(config: &AppConfig) -> Result<Self, IoTimeoutError> {
+        Ok(Self {
+            read: seconds(config.read_timeout_secs, READ_TIMEOUT)
+                .ok_or(IoTimeoutError::ZeroRead)?,
+            write: seconds(config.write_timeout_secs, WRITE_TIMEOUT)
+                .ok_or(IoTimeoutError::ZeroWrite)?,
+        })
+    }
+}
+
+fn seconds(value: Option<u64>, default: Duration) -> Option<Duration> {
+    match value {
+        None => Some(default),
+        Some(0) => None,
+        Some(secs) => Some(Duration::from_secs(secs)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the connection timeouts from configuration.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, None, Ok(IoTimeouts::DEFAULT))]
+    #[case(Some(60), Some(2), Ok(IoTimeouts {
+        read: Duration::from_secs(60),
+        write: Duration::from_secs(2),
+    }))]
+    #[case(Some(0), None, Err(IoTimeoutError::ZeroRead))]
+    #[case(None, Some(0), Err(IoTimeoutError::ZeroWrite))]
+    fn reads_io_timeouts(
+        #[case] read: Option<u64>,
+        #[case] write: Option<u64>,
+        #[case] expected: Result<IoTimeouts, IoTimeoutError>,
+    ) {
+        let config = AppConfig {
+            read_timeout_secs: read,
+            write_timeout_secs: write,
+            ..AppConfig::default()
+        };
+        assert_eq!(IoTimeouts::from_config(&config), expected);
+    }
+
+    #[rstest]
+    fn reads_get_longer_than_writes_by_default() {
+        assert!(IoTimeouts::DEFAULT.read > IoTimeouts::DEFAULT.write);
+    }
+}
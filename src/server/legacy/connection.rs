@@ -0,0 +1,242 @@
+//! Per-connection lifecycle for the legacy runtime.
+//!
+//! Each accepted socket completes a TLS handshake when TLS is configured (see
+//! [`crate::server::tls`]), performs the Hotline handshake, then processes
+//! framed transactions until the peer disconnects, a handler asks for the
+//! connection to be dropped, the connection sits idle past
+//! `idle_timeout_secs`, or the server shuts down. In all but the first case
+//! the connection is closed gracefully as described in
+//! [`crate::server::disconnect`].
+
+use std::{io, sync::Arc, time::Instant};
+
+use anyhow::Result;
+use tokio::{
+    io::{self as tokio_io, AsyncRead, AsyncReadExt, AsyncWrite},
+    net::TcpStream,
+    sync::watch,
+    time::timeout,
+};
+use tracing::{debug, warn};
+
+use crate::{
+    handler::{Context as HandlerContext, Session, handle_request},
+    protocol,
+    server::{
+        NetworkRuntime,
+        agreement::take_agreement_push,
+        disconnect::{DRAIN_WINDOW, SHUTDOWN_REASON, build_disconnect_msg, drain_inbound},
+        idle::{ActivityClock, IDLE_DISCONNECT_REASON, idle_expired},
+        metrics::runtime_metrics,
+        rate_limit::{ConnectionRateLimiter, throttled_reply},
+        tls::accept_tls,
+        transaction_span::{request_span, timed},
+    },
+    transaction::{Transaction, TransactionError, TransactionReader, TransactionWriter},
+};
+
+/// How the transaction loop ended without an error.
+enum LoopExit {
+    /// The peer closed its side of the connection.
+    PeerClosed,
+    /// The server asked every connection to stop.
+    Shutdown,
+    /// A handler asked for this connection to be closed.
+    Kicked(&'static str),
+}
+
+/// Handles a single client connection, performing handshake and processing transactions.
+///
+/// A connection that was not `admitted` within the connection limits is
+/// refused with [`protocol::HANDSHAKE_ERR_SERVER_FULL`] once it sends its
+/// handshake.
+pub(super) async fn handle_client(
+    socket: TcpStream,
+    ctx: HandlerContext,
+    admitted: bool,
+    shutdown: &mut watch::Receiver<bool>,
+) -> Result<()> {
+    match ctx.settings.transport.tls.clone() {
+        Some(acceptor) => {
+            let stream = accept_tls(&acceptor, socket).await?;
+            serve_stream(stream, ctx, admitted, shutdown).await
+        }
+        None => serve_stream(socket, ctx, admitted, shutdown).await,
+    }
+}
+
+/// Run the Hotline session over `stream`, which may be plain or TLS.
+async fn serve_stream<S>(
+    stream: S,
+    ctx: HandlerContext,
+    admitted: bool,
+    shutdown: &mut watch::Receiver<bool>,
+) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite,
+{
+    let (mut reader, mut writer) = tokio_io::split(stream);
+
+    if !perform_handshake(&mut reader, &mut writer, admitted).await? {
+        return Ok(());
+    }
+
+    let result = serve_transactions(reader, writer, &ctx, shutdown).await;
+    ctx.release_presence();
+    result
+}
+
+/// Process framed transactions until the peer disconnects or shutdown is
+/// signalled.
+async fn serve_transactions<R, W>(
+    reader: R,
+    writer: W,
+    ctx: &HandlerContext,
+    shutdown: &mut watch::Receiver<bool>,
+) -> Result<()>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let transport = &ctx.settings.transport;
+    let mut tx_reader = TransactionReader::new(reader)
+        .with_timeout(transport.io_timeouts.read)
+        .with_max_reassembly_age(transport.reassembly_timeout)
+        .with_payload_limits(transport.payload_limits.clone());
+    let mut tx_writer = TransactionWriter::new(writer).with_timeout(transport.io_timeouts.write);
+    let mut session = Session::default();
+    let idle_window = transport.idle_timeout;
+    let activity = Arc::new(ActivityClock::new());
+    let limiter = ConnectionRateLimiter::new(Arc::clone(&ctx.settings.rate_limits), ctx.peer.ip());
+    ctx.attach_presence(Arc::clone(&activity));
+    let exit = loop {
+        tokio::select! {
+            tx = tx_reader.read_transaction() => match tx {
+                Ok(tx) => {
+                    activity.touch();
+                    let resp = respond(ctx, &mut session, &limiter, &tx).await;
+                    tx_writer.write_transaction(&resp).await?;
+                    runtime_metrics(NetworkRuntime::Legacy).record_reply(resp.header.error);
+                    if let Some(push) = take_agreement_push(&mut session, &ctx.settings.agreement)? {
+                        tx_writer.write_transaction(&push).await?;
+                    }
+                    if let Some(reason) = session.take_disconnect_reason() {
+                        break LoopExit::Kicked(reason);
+                    }
+                }
+                Err(TransactionError::Io(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    // Remote closed the connection, end session gracefully
+                    break LoopExit::PeerClosed;
+                }
+                Err(e) => return Err(e.into()),
+            },
+            _ = shutdown.changed() => {
+                break LoopExit::Shutdown;
+            }
+            () = idle_expired(idle_window, &activity) => {
+                debug!(peer = %ctx.peer, "closing idle connection");
+                break LoopExit::Kicked(IDLE_DISCONNECT_REASON);
+            }
+        }
+    };
+    match exit {
+        LoopExit::PeerClosed => {}
+        LoopExit::Shutdown => {
+            close_gracefully(&mut tx_reader, &mut tx_writer, SHUTDOWN_REASON).await;
+        }
+        LoopExit::Kicked(reason) => {
+            close_gracefully(&mut tx_reader, &mut tx_writer, reason).await;
+        }
+    }
+    Ok(())
+}
+
+/// Handle one request, answering a failure with the same error reply the
+/// Wireframe runtime sends (see [`crate::commands::CommandError::reply_to`]).
+///
+/// A request over the connection's rate limit is refused without being
+/// handled, as the Wireframe middleware does.
+async fn respond(
+    ctx: &HandlerContext,
+    session: &mut Session,
+    limiter: &ConnectionRateLimiter,
+    tx: &Transaction,
+) -> Transaction {
+    if !limiter.admit(&tx.header, Instant::now()) {
+        debug!(peer = %ctx.peer, ty = tx.header.ty, id = tx.header.id, "request rate limited");
+        return throttled_reply(&tx.header);
+    }
+    let frame = tx.to_bytes();
+    let span = request_span(NetworkRuntime::Legacy, ctx.peer, &frame);
+    match timed(span, handle_request(ctx, session, &frame)).await {
+        Ok(reply) => reply,
+        Err(error) => {
+            warn!(
+                peer = %ctx.peer,
+                ty = tx.header.ty,
+                id = tx.header.id,
+                error_code = error.error_code(),
+                %error,
+                "request failed"
+            );
+            error.reply_to(&tx.header)
+        }
+    }
+}
+
+/// Send a Disconnect Message, half-close the write side, and drain input.
+///
+/// Every step is best-effort: the connection is going away regardless, so
+/// failures are logged rather than reported.
+async fn close_gracefully<R, W>(
+    reader: &mut TransactionReader<R>,
+    writer: &mut TransactionWriter<W>,
+    reason: &str,
+) where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    match build_disconnect_msg(reason) {
+        Ok(notice) => {
+            if let Err(error) = writer.write_transaction(&notice).await {
+                debug!(%error, "failed to send disconnect notice");
+            }
+        }
+        Err(error) => debug!(%error, "failed to encode disconnect notice"),
+    }
+    if let Err(error) = writer.shutdown().await {
+        debug!(%error, "failed to half-close connection");
+    }
+    drain_inbound(reader.get_mut(), DRAIN_WINDOW).await;
+}
+
+/// Read the client handshake and answer it, returning whether the session
+/// may go on to transactions.
+async fn perform_handshake<R, W>(reader: &mut R, writer: &mut W, admitted: bool) -> Result<bool>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut buf = [0u8; protocol::HANDSHAKE_LEN];
+    match timeout(protocol::HANDSHAKE_TIMEOUT, reader.read_exact(&mut buf)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => {
+            if e.kind() == io::ErrorKind::UnexpectedEof {
+                return Ok(false);
+            }
+            return Err(e.into());
+        }
+        Err(_) => {
+            protocol::write_handshake_reply(writer, protocol::HANDSHAKE_ERR_TIMEOUT).await?;
+            return Ok(false);
+        }
+    }
+
+    let code = match protocol::parse_handshake(&buf) {
+        Ok(_) if admitted => protocol::HANDSHAKE_OK,
+        Ok(_) => protocol::HANDSHAKE_ERR_SERVER_FULL,
+        Err(err) => protocol::handshake_error_code(&err),
+    };
+    protocol::write_handshake_reply(writer, code).await?;
+    Ok(code == protocol::HANDSHAKE_OK)
+}
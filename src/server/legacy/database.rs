@@ -0,0 +1,65 @@
+//! Database preparation for the legacy server runtime.
+
+use anyhow::{Context, Result};
+use diesel_async::pooled_connection::PoolError;
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+use tracing::warn;
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+use url::Url;
+
+use crate::db::{DbPool, PoolSettings, apply_migrations, establish_pool_with};
+
+/// Determine whether the supplied connection string targets Postgres.
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+pub(super) fn is_postgres_url(s: &str) -> bool {
+    match Url::parse(s) {
+        Ok(u) => matches!(u.scheme(), "postgres" | "postgresql"),
+        Err(err) => {
+            warn!(
+                target = "server::legacy",
+                "invalid database url '{s}': {err}"
+            );
+            false
+        }
+    }
+}
+
+pub(super) async fn create_pool(
+    database: &str,
+    settings: &PoolSettings,
+) -> Result<DbPool, PoolError> {
+    #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+    if is_postgres_url(database) {
+        return establish_pool_with(database, settings).await;
+    }
+    establish_pool_with(database, settings).await
+}
+
+/// Prepares the pooled database for serving.
+///
+/// Audits database-specific features and applies any pending migrations.
+///
+/// # Arguments
+///
+/// * `pool` - The connection pool opened for `database`.
+/// * `database` - The database connection string or file path.
+/// * `migration_timeout_secs` - Optional limit on how long migrations may run.
+///
+/// # Returns
+///
+/// An error if the audit or a migration fails.
+pub(super) async fn prepare_database(
+    pool: &DbPool,
+    database: &str,
+    migration_timeout_secs: Option<u64>,
+) -> Result<()> {
+    let mut conn = pool.get().await.context("failed to get db connection")?;
+    #[cfg(feature = "sqlite")]
+    crate::db::audit_sqlite_features(&mut conn).await?;
+    #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+    if is_postgres_url(database) {
+        crate::db::audit_postgres_features(&mut conn).await?;
+    }
+    apply_migrations(&mut conn, database, migration_timeout_secs).await?;
+    Ok(())
+}
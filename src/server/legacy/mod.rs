@@ -5,14 +5,6 @@
 //! adapter) without duplicating code.
 
 #![expect(clippy::shadow_reuse, reason = "intentional shadowing in async blocks")]
-#![expect(
-    clippy::print_stdout,
-    reason = "intentional console output for server status"
-)]
-#![expect(
-    clippy::print_stderr,
-    reason = "intentional error output for diagnostics"
-)]
 #![expect(
     clippy::integer_division_remainder_used,
     reason = "tokio::select! macro usage"
@@ -22,33 +14,56 @@
     reason = "shutdown signal send is fire-and-forget"
 )]
 
+mod connection;
+mod database;
+
 use std::{io, net::SocketAddr, sync::Arc};
 
-use anyhow::{Context, Result};
+use anyhow::Result;
 use argon2::Argon2;
-use diesel_async::pooled_connection::PoolError;
+use futures_util::future::join_all;
 use tokio::{
-    io::{self as tokio_io, AsyncReadExt},
     net::{TcpListener, TcpStream},
     sync::watch,
     task::JoinSet,
-    time::timeout,
+    time::sleep,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+#[cfg(all(
+    feature = "postgres",
+    not(feature = "sqlite"),
+    any(test, feature = "test-support")
+))]
+use self::database::is_postgres_url;
+use self::{
+    connection::handle_client,
+    database::{create_pool, prepare_database},
 };
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-use tracing::warn;
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-use url::Url;
-
 use super::{
+    NetworkRuntime,
+    accept::{AcceptGuard, AcceptMetrics, log_accept_metrics},
     admin,
+    bans::start_ban_refresh,
+    bind::bind_listeners,
     cli::{AppConfig, ResolvedCli},
+    connection_limit::ConnectionSlot,
+    db_pool::pool_settings_from_config,
+    health::start_health_server,
+    logging::announce_listening,
+    metrics::{log_runtime_metrics, runtime_metrics},
+    news_fsck::repair_news_on_startup,
+    settings::ServerSettings,
+    shutdown::shutdown_signal,
+    tasks::BackgroundTasks,
+    transfer_port::start_transfer_port,
+    transfer_stats::TransferStatsFlusher,
 };
 use crate::{
-    db::{DbPool, apply_migrations, establish_pool},
-    handler::{Context as HandlerContext, Session, handle_request},
+    db::{DbPool, log_pool_metrics},
+    handler::Context as HandlerContext,
     presence::PresenceRegistry,
-    protocol,
-    transaction::{TransactionError, TransactionReader, TransactionWriter},
 };
 
 /// Shared server resources passed to connection handlers.
@@ -64,21 +79,44 @@ pub struct ServerResources {
     pub argon2: Arc<Argon2<'static>>,
     /// Shared presence registry for legacy connection handlers.
     pub presence: Arc<PresenceRegistry>,
+    /// Settings the server hands to each connection.
+    pub settings: Arc<ServerSettings>,
 }
 
 /// Shared server resources passed to connection handlers.
 #[derive(Clone)]
 #[cfg(not(feature = "test-support"))]
-struct ServerResources {
+pub(crate) struct ServerResources {
     pool: DbPool,
     argon2: Arc<Argon2<'static>>,
     presence: Arc<PresenceRegistry>,
+    settings: Arc<ServerSettings>,
+}
+
+impl ServerResources {
+    /// Bundle a server's shared state with a fresh presence registry.
+    pub(crate) fn new(
+        pool: DbPool,
+        argon2: Arc<Argon2<'static>>,
+        settings: Arc<ServerSettings>,
+    ) -> Self {
+        let accounting = Arc::clone(&settings.accounting);
+        Self {
+            pool,
+            argon2,
+            presence: Arc::new(PresenceRegistry::with_accounting(accounting)),
+            settings,
+        }
+    }
 }
 
 /// An accepted TCP connection with its peer address.
 struct AcceptedConnection {
     socket: TcpStream,
     peer: SocketAddr,
+    /// Place within the connection limits; `None` when the server was full
+    /// and the handshake must be refused.
+    slot: Option<ConnectionSlot>,
 }
 
 /// Parse CLI arguments and execute the requested action.
@@ -108,105 +146,118 @@ pub async fn dispatch(cli: ResolvedCli) -> Result<()> {
 /// Returns any failure reported while seeding the database pool, binding the
 /// socket, or handling inbound connections.
 pub async fn run_daemon(cfg: AppConfig) -> Result<()> {
-    let bind = cfg.bind.clone();
     let database = cfg.database.clone();
     let migration_timeout_secs = cfg.migration_timeout_secs;
+    let pool_settings = pool_settings_from_config(&cfg)?;
 
     // Build the Argon2 instance once so it can be shared by all worker tasks.
     let argon2 = Arc::new(admin::argon2_from_config(&cfg)?);
-
-    let pool = setup_database(&database, migration_timeout_secs).await?;
-
-    let listener = TcpListener::bind(&bind).await?;
-    println!("mxd listening on {bind}");
-
-    accept_connections(listener, pool, argon2).await
-}
-
-/// Determine whether the supplied connection string targets Postgres.
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-fn is_postgres_url(s: &str) -> bool {
-    match Url::parse(s) {
-        Ok(u) => matches!(u.scheme(), "postgres" | "postgresql"),
-        Err(err) => {
-            warn!(
-                target = "server::legacy",
-                "invalid database url '{s}': {err}"
-            );
-            false
-        }
+    let settings = Arc::new(super::configure_process(&cfg).await?);
+
+    let pool = create_pool(&database, &pool_settings).await?;
+    let metrics = Arc::new(AcceptMetrics::default());
+    let mut tasks = BackgroundTasks::default();
+    // The probes start before migrating, so `/readyz` reports startup.
+    tasks.extend(start_health_server(
+        settings.background.health_bind,
+        &pool,
+        &database,
+        Arc::clone(&metrics),
+    )?);
+    if let Err(error) = prepare_database(&pool, &database, migration_timeout_secs).await {
+        tasks.abort_all();
+        return Err(error);
+    }
+    repair_news_on_startup(&pool, &cfg).await;
+
+    let listeners = bind_listeners(&cfg.bind)?;
+    for listener in &listeners {
+        let addr = listener.local_addr()?;
+        announce_listening("mxd", &addr);
+        // The legacy runtime does not drain transfers; the port is aborted
+        // with the other background tasks once the listeners stop.
+        tasks.extend(
+            start_transfer_port(addr, CancellationToken::new(), Arc::clone(&settings)).await,
+        );
     }
+
+    let bans = Arc::clone(&settings.address_bans);
+    tasks.extend([start_ban_refresh(pool.clone(), bans).await]);
+    tasks.start_configured(&pool, &settings).await?;
+    let resources = ServerResources::new(pool, argon2, settings);
+    let result = accept_connections(listeners, resources, Arc::clone(&metrics)).await;
+    tasks.abort_all();
+    log_accept_metrics(NetworkRuntime::Legacy, &metrics);
+    result
 }
 
 #[cfg(any(test, feature = "test-support"))]
 mod test_helpers;
 
-async fn create_pool(database: &str) -> Result<DbPool, PoolError> {
-    #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-    if is_postgres_url(database) {
-        return establish_pool(database).await;
-    }
-    establish_pool(database).await
-}
-
-/// Sets up the database connection pool and runs migrations.
+/// Serve legacy connections from every listener until a shutdown signal
+/// arrives.
 ///
-/// Establishes a connection pool for the specified database, audits database-specific features,
-/// and applies any pending migrations. Returns the initialized connection pool on success.
-///
-/// # Arguments
-///
-/// * `database` - The database connection string or file path.
-///
-/// # Returns
-///
-/// A result containing the initialized database connection pool, or an error if setup fails.
-async fn setup_database(database: &str, migration_timeout_secs: Option<u64>) -> Result<DbPool> {
-    let pool: DbPool = create_pool(database).await?;
-    {
-        let mut conn = pool.get().await.context("failed to get db connection")?;
-        #[cfg(feature = "sqlite")]
-        crate::db::audit_sqlite_features(&mut conn).await?;
-        #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-        if is_postgres_url(database) {
-            crate::db::audit_postgres_features(&mut conn).await?;
-        }
-        apply_migrations(&mut conn, database, migration_timeout_secs).await?;
-    }
-    Ok(pool)
-}
-
-async fn accept_connections(
-    listener: TcpListener,
-    pool: DbPool,
-    argon2: Arc<Argon2<'static>>,
+/// Each listener runs its own accept loop, but all of them share `resources`
+/// and its presence registry, so users connected over different addresses see
+/// each other. The Wireframe server's dual-runtime mode also calls this.
+/// Accepts are counted in `metrics`, which the caller serves and logs.
+pub(crate) async fn accept_connections(
+    listeners: Vec<TcpListener>,
+    resources: ServerResources,
+    metrics: Arc<AcceptMetrics>,
 ) -> Result<()> {
     let (shutdown_tx, shutdown_rx) = watch::channel(false);
-    let mut join_set = JoinSet::new();
-    let shutdown = shutdown_signal();
-    tokio::pin!(shutdown);
-    let resources = ServerResources {
-        pool,
-        argon2,
-        presence: Arc::new(PresenceRegistry::default()),
+    let transfer_stats =
+        TransferStatsFlusher::start(resources.pool.clone(), Arc::clone(&resources.presence));
+    let acceptors = join_all(listeners.into_iter().map(|listener| {
+        let guard = AcceptGuard::new(Arc::clone(&metrics));
+        accept_loop(listener, resources.clone(), shutdown_rx.clone(), guard)
+    }));
+    let stop = async {
+        shutdown_signal().await;
+        info!("shutdown signal received");
+        // notify every accept loop and connection task to shut down
+        let _ = shutdown_tx.send(true);
     };
+    tokio::join!(stop, acceptors);
+
+    transfer_stats.stop().await;
+    log_runtime_metrics(NetworkRuntime::Legacy);
+    log_pool_metrics(&resources.pool);
+    Ok(())
+}
 
+/// Accept connections from `listener` until `shutdown_rx` reports shutdown,
+/// then wait for the connections it started to close.
+async fn accept_loop(
+    listener: TcpListener,
+    resources: ServerResources,
+    mut shutdown_rx: watch::Receiver<bool>,
+    mut guard: AcceptGuard,
+) {
+    let mut join_set = JoinSet::new();
     loop {
         tokio::select! {
-            () = &mut shutdown => {
-                println!("shutdown signal received");
-                break;
-            }
-            res = listener.accept() => {
-                handle_accept_result(res, &resources, &shutdown_rx, &mut join_set);
-            }
+            _ = shutdown_rx.changed() => break,
+            res = listener.accept() => match res {
+                Err(error) => {
+                    let Some(pause) = guard.record_error(&error) else {
+                        continue;
+                    };
+                    guard.shed_pending(&listener);
+                    tokio::select! {
+                        _ = shutdown_rx.changed() => break,
+                        () = sleep(pause) => {}
+                    }
+                }
+                accepted => {
+                    guard.record_accepted();
+                    handle_accept_result(accepted, &resources, &shutdown_rx, &mut join_set);
+                }
+            },
         }
     }
-
-    // notify all tasks to shut down
-    let _ = shutdown_tx.send(true);
     await_spawned_tasks(&mut join_set).await;
-    Ok(())
 }
 
 /// Spawn a client handler task for the accepted connection.
@@ -218,10 +269,19 @@ fn handle_accept_result(
 ) {
     match res {
         Ok((socket, peer)) => {
-            let conn = AcceptedConnection { socket, peer };
+            if resources.settings.address_bans.is_banned(peer.ip()) {
+                // Dropping the socket before the handshake refuses the peer.
+                info!(%peer, "refused connection from banned address");
+                return;
+            }
+            let slot = resources.settings.connections.admit(peer.ip());
+            if slot.is_none() {
+                info!(%peer, "refusing connection: connection limit reached");
+            }
+            let conn = AcceptedConnection { socket, peer, slot };
             spawn_client_handler(conn, resources.clone(), shutdown_rx.clone(), join_set);
         }
-        Err(e) => eprintln!("accept error: {e}"),
+        Err(error) => warn!(%error, "accept error"),
     }
 }
 
@@ -231,130 +291,32 @@ fn spawn_client_handler(
     mut shutdown_rx: watch::Receiver<bool>,
     join_set: &mut JoinSet<()>,
 ) {
+    runtime_metrics(NetworkRuntime::Legacy).record_connection();
     let ctx = HandlerContext::with_presence(
         conn.peer,
         resources.pool,
         resources.argon2,
         resources.presence,
-    );
+    )
+    .with_settings(resources.settings);
     join_set.spawn(async move {
-        if let Err(e) = handle_client(conn.socket, ctx, &mut shutdown_rx).await {
-            eprintln!("connection error from {}: {e}", conn.peer);
+        let admitted = conn.slot.is_some();
+        let served = handle_client(conn.socket, ctx, admitted, &mut shutdown_rx).await;
+        if let Err(error) = served {
+            warn!(peer = %conn.peer, %error, "connection error");
         }
+        drop(conn.slot);
     });
 }
 
 async fn await_spawned_tasks(join_set: &mut JoinSet<()>) {
     while let Some(res) = join_set.join_next().await {
-        if let Err(e) = res {
-            eprintln!("task error: {e}");
+        if let Err(error) = res {
+            error!(%error, "connection task failed");
         }
     }
 }
 
-/// Handles a single client connection, performing handshake and processing transactions.
-async fn handle_client(
-    socket: TcpStream,
-    ctx: HandlerContext,
-    shutdown: &mut watch::Receiver<bool>,
-) -> Result<()> {
-    let (mut reader, mut writer) = tokio_io::split(socket);
-
-    perform_handshake(&mut reader, &mut writer).await?;
-
-    let mut tx_reader = TransactionReader::new(reader);
-    let mut tx_writer = TransactionWriter::new(writer);
-    let mut session = Session::default();
-    loop {
-        tokio::select! {
-            tx = tx_reader.read_transaction() => match tx {
-                Ok(tx) => {
-                    let frame = tx.to_bytes();
-                    let resp = handle_request(&ctx, &mut session, &frame)
-                        .await
-                        .map_err(|e| anyhow::anyhow!(e))?;
-                    tx_writer.write_transaction(&resp).await?;
-                }
-                Err(TransactionError::Io(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
-                    // Remote closed the connection, end session gracefully
-                    break;
-                }
-                Err(e) => return Err(e.into()),
-            },
-            _ = shutdown.changed() => {
-                break;
-            }
-        }
-    }
-    Ok(())
-}
-
-async fn perform_handshake<R, W>(reader: &mut R, writer: &mut W) -> Result<()>
-where
-    R: tokio::io::AsyncRead + Unpin,
-    W: tokio::io::AsyncWrite + Unpin,
-{
-    let mut buf = [0u8; protocol::HANDSHAKE_LEN];
-    match timeout(protocol::HANDSHAKE_TIMEOUT, reader.read_exact(&mut buf)).await {
-        Ok(Ok(_)) => {}
-        Ok(Err(e)) => {
-            if e.kind() == io::ErrorKind::UnexpectedEof {
-                return Ok(());
-            }
-            return Err(e.into());
-        }
-        Err(_) => {
-            protocol::write_handshake_reply(writer, protocol::HANDSHAKE_ERR_TIMEOUT).await?;
-            return Ok(());
-        }
-    }
-
-    match protocol::parse_handshake(&buf) {
-        Ok(_) => protocol::write_handshake_reply(writer, protocol::HANDSHAKE_OK).await?,
-        Err(err) => {
-            let code = protocol::handshake_error_code(&err);
-            protocol::write_handshake_reply(writer, code).await?;
-            return Ok(());
-        }
-    }
-
-    Ok(())
-}
-
-/// Waits for a shutdown signal, completing when termination is requested.
-async fn shutdown_signal() {
-    #[cfg(unix)]
-    {
-        use tokio::signal::unix::{SignalKind, signal};
-        match signal(SignalKind::terminate()) {
-            Ok(mut term) => {
-                tokio::select! {
-                    res = tokio::signal::ctrl_c() => {
-                        if let Err(err) = res {
-                            eprintln!("failed to listen for Ctrl-C: {err}");
-                        }
-                    },
-                    _ = term.recv() => {},
-                }
-            }
-            Err(err) => {
-                eprintln!("failed to install SIGTERM handler: {err}");
-                wait_for_ctrl_c().await;
-            }
-        }
-    }
-    #[cfg(not(unix))]
-    {
-        wait_for_ctrl_c().await;
-    }
-}
-
-async fn wait_for_ctrl_c() {
-    if let Err(err) = tokio::signal::ctrl_c().await {
-        eprintln!("failed to listen for Ctrl-C: {err}");
-    }
-}
-
 #[cfg(feature = "test-support")]
 pub mod test_support {
     //! Expose legacy server internals exclusively for integration tests
@@ -1,7 +1,7 @@
 //! Unit tests for legacy server helpers, ensuring internal behaviours remain
 //! stable without requiring the external binary.
 
-use std::sync::Arc;
+use std::{num::NonZeroU32, sync::Arc};
 
 use anyhow::Result;
 use argon2::Argon2;
@@ -15,7 +15,15 @@ use tokio::{
 };
 
 use super::{ServerResources, handle_accept_result, test_helpers};
-use crate::{presence::PresenceRegistry, protocol};
+use crate::{
+    commands::{ERR_INTERNAL_SERVER, ERR_INVALID_PAYLOAD, UnknownTransactionPolicy},
+    field_id::FieldId,
+    presence::PresenceRegistry,
+    protocol,
+    server::settings::ServerSettings,
+    transaction::{FrameHeader, Transaction, TransactionReader, TransactionWriter, decode_params},
+    transaction_type::TransactionType,
+};
 
 #[cfg(all(feature = "postgres", not(feature = "sqlite")))]
 #[rstest]
@@ -38,6 +46,7 @@ async fn handle_accept_result_shares_argon2_between_clients() -> Result<()> {
         pool,
         argon2: Arc::clone(&argon2),
         presence: Arc::new(PresenceRegistry::default()),
+        settings: Arc::default(),
     };
     // resources holds one clone; count is now strong_before + 1
     let after_resources = Arc::strong_count(&argon2);
@@ -75,3 +84,156 @@ async fn handle_accept_result_shares_argon2_between_clients() -> Result<()> {
 
     Ok(())
 }
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[tokio::test]
+async fn shutdown_sends_disconnect_notice_then_closes() -> Result<()> {
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut join_set = JoinSet::new();
+    let resources = ServerResources {
+        pool: test_helpers::dummy_pool(),
+        argon2: Arc::new(Argon2::default()),
+        presence: Arc::new(PresenceRegistry::default()),
+        settings: Arc::default(),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let mut client = TcpStream::connect(listener.local_addr()?).await?;
+    handle_accept_result(
+        listener.accept().await,
+        &resources,
+        &shutdown_rx,
+        &mut join_set,
+    );
+    client.write_all(&test_helpers::handshake_frame()).await?;
+    let mut reply = [0u8; protocol::REPLY_LEN];
+    client.read_exact(&mut reply).await?;
+
+    shutdown_tx
+        .send(true)
+        .expect("shutdown receivers should remain until broadcast");
+
+    let mut reader = TransactionReader::new(&mut client);
+    let notice = reader.read_transaction().await?;
+    assert_eq!(notice.header.ty, u16::from(TransactionType::DisconnectMsg));
+    let mut rest = Vec::new();
+    client.read_to_end(&mut rest).await?;
+    assert!(rest.is_empty(), "write side should close after the notice");
+    while let Some(result) = join_set.join_next().await {
+        result.expect("client handler task");
+    }
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[tokio::test]
+async fn repeated_unknown_transactions_disconnect_under_policy() -> Result<()> {
+    let limit = NonZeroU32::new(2).expect("non-zero limit");
+    let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut join_set = JoinSet::new();
+    let resources = ServerResources {
+        pool: test_helpers::dummy_pool(),
+        argon2: Arc::new(Argon2::default()),
+        presence: Arc::new(PresenceRegistry::default()),
+        settings: Arc::new(ServerSettings {
+            unknown_transactions: UnknownTransactionPolicy::Disconnect { limit },
+            ..ServerSettings::default()
+        }),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let mut client = TcpStream::connect(listener.local_addr()?).await?;
+    handle_accept_result(
+        listener.accept().await,
+        &resources,
+        &shutdown_rx,
+        &mut join_set,
+    );
+    client.write_all(&test_helpers::handshake_frame()).await?;
+    let mut reply = [0u8; protocol::REPLY_LEN];
+    client.read_exact(&mut reply).await?;
+
+    let (read_half, write_half) = client.into_split();
+    let mut reader = TransactionReader::new(read_half);
+    let mut writer = TransactionWriter::new(write_half);
+    for id in 1..=2 {
+        let request = Transaction {
+            header: FrameHeader {
+                flags: 0,
+                is_reply: 0,
+                ty: 9_999,
+                id,
+                error: 0,
+                total_size: 0,
+                data_size: 0,
+            },
+            payload: Vec::new(),
+        };
+        writer.write_transaction(&request).await?;
+        let answer = reader.read_transaction().await?;
+        assert_eq!(answer.header.id, id);
+        assert_eq!(answer.header.error, ERR_INTERNAL_SERVER);
+    }
+    let notice = reader.read_transaction().await?;
+    assert_eq!(notice.header.ty, u16::from(TransactionType::DisconnectMsg));
+    drop(writer);
+    drop(reader);
+    while let Some(result) = join_set.join_next().await {
+        result.expect("client handler task");
+    }
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[tokio::test]
+async fn malformed_requests_get_an_error_reply_and_keep_the_connection() -> Result<()> {
+    let (_shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut join_set = JoinSet::new();
+    let resources = ServerResources {
+        pool: test_helpers::dummy_pool(),
+        argon2: Arc::new(Argon2::default()),
+        presence: Arc::new(PresenceRegistry::default()),
+        settings: Arc::default(),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let mut client = TcpStream::connect(listener.local_addr()?).await?;
+    handle_accept_result(
+        listener.accept().await,
+        &resources,
+        &shutdown_rx,
+        &mut join_set,
+    );
+    client.write_all(&test_helpers::handshake_frame()).await?;
+    let mut reply = [0u8; protocol::REPLY_LEN];
+    client.read_exact(&mut reply).await?;
+
+    let (read_half, write_half) = client.into_split();
+    let mut reader = TransactionReader::new(read_half);
+    let mut writer = TransactionWriter::new(write_half);
+    let login_without_fields = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: u16::from(TransactionType::Login),
+            id: 7,
+            error: 0,
+            total_size: 0,
+            data_size: 0,
+        },
+        payload: Vec::new(),
+    };
+    for _ in 0..2 {
+        writer.write_transaction(&login_without_fields).await?;
+        let answer = reader.read_transaction().await?;
+        assert_eq!(answer.header.id, 7);
+        assert_eq!(answer.header.error, ERR_INVALID_PAYLOAD);
+        assert_eq!(
+            decode_params(&answer.payload)?,
+            vec![(FieldId::ErrorText, b"missing field Login".to_vec())]
+        );
+    }
+    drop(writer);
+    drop(reader);
+    while let Some(result) = join_set.join_next().await {
+        result.expect("client handler task");
+    }
+    Ok(())
+}
@@ -0,0 +1,344 @@
+//! Log files that rotate themselves.
+//!
+//! Small deployments often run without logrotate, so the `file:<path>` log
+//! target manages its own files. [`RotatingFile`] appends to `path` and, once
+//! the file would grow past `log_max_bytes` or a new hour or day (UTC) begins
+//! under `log_rotation`, renames it to `path.1`, shifting older files along
+//! to `path.2` and so on. Only `log_max_files` rotated files are kept; the
+//! oldest is deleted as the next one takes its place.
+
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use thiserror::Error;
+use tracing_subscriber::fmt::MakeWriter;
+
+use super::AppConfig;
+
+/// Rotated files kept when `log_max_files` is unset.
+pub const DEFAULT_LOG_MAX_FILES: u16 = 7;
+
+/// Calendar period after which a log file is rotated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RotationPeriod {
+    /// At the start of every UTC hour.
+    Hourly,
+    /// At midnight UTC.
+    Daily,
+}
+
+impl RotationPeriod {
+    const fn secs(self) -> u64 {
+        match self {
+            Self::Hourly => 60 * 60,
+            Self::Daily => 24 * 60 * 60,
+        }
+    }
+
+    /// Number of the period `time` falls in, counted from the Unix epoch.
+    fn index(self, time: SystemTime) -> u64 {
+        time.duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+            .div_euclid(self.secs())
+    }
+}
+
+/// When a log file is rotated and how many rotated files are kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// Rotate when a new period begins; `None` never rotates by time.
+    pub period: Option<RotationPeriod>,
+    /// Rotate before the file grows past this many bytes; `None` never
+    /// rotates by size.
+    pub max_bytes: Option<u64>,
+    /// Rotated files kept beside the active one.
+    pub max_files: u16,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            period: None,
+            max_bytes: None,
+            max_files: DEFAULT_LOG_MAX_FILES,
+        }
+    }
+}
+
+/// Errors raised while reading the rotation policy from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LogRotationError {
+    /// `log_rotation` named no known period.
+    #[error("unknown log_rotation {0:?}; expected hourly, daily, or never")]
+    UnknownPeriod(String),
+    /// `log_max_bytes` was zero.
+    #[error("log_max_bytes must be greater than zero")]
+    ZeroMaxBytes,
+    /// `log_max_files` was zero.
+    #[error("log_max_files must be greater than zero")]
+    ZeroMaxFiles,
+}
+
+impl RotationPolicy {
+    /// Read the policy from `config`. Unset, files are never rotated and
+    /// [`DEFAULT_LOG_MAX_FILES`] would be kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LogRotationError`] for an unknown period or a zero limit.
+    pub fn from_config(config: &AppConfig) -> Result<Self, LogRotationError> {
+        let period = match config.log_rotation.as_deref() {
+            None | Some("never") => None,
+            Some("hourly") => Some(RotationPeriod::Hourly),
+            Some("daily") => Some(RotationPeriod::Daily),
+            Some(other) => return Err(LogRotationError::UnknownPeriod(other.to_owned())),
+        };
+        if config.log_max_bytes == Some(0) {
+            return Err(LogRotationError::ZeroMaxBytes);
+        }
+        let max_files = match config.log_max_files {
+            None => DEFAULT_LOG_MAX_FILES,
+            Some(0) => return Err(LogRotationError::ZeroMaxFiles),
+            Some(count) => count,
+        };
+        Ok(Self {
+            period,
+            max_bytes: config.log_max_bytes,
+            max_files,
+        })
+    }
+
+    /// Whether any of the rotation options is set.
+    #[must_use]
+    pub const fn is_configured(config: &AppConfig) -> bool {
+        config.log_rotation.is_some()
+            || config.log_max_bytes.is_some()
+            || config.log_max_files.is_some()
+    }
+}
+
+/// Log file appended to by every event and rotated under a
+/// [`RotationPolicy`].
+#[derive(Debug)]
+pub struct RotatingFile {
+    path: PathBuf,
+    policy: RotationPolicy,
+    active: Mutex<ActiveFile>,
+}
+
+/// The file currently written and what rotation needs to know about it.
+#[derive(Debug)]
+struct ActiveFile {
+    file: File,
+    len: u64,
+    period: Option<u64>,
+}
+
+impl RotatingFile {
+    /// Open `path` for appending, creating it if needed.
+    ///
+    /// An existing file keeps its contents and is rotated when it is next
+    /// due, judged by its size and when it was last written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or inspected.
+    pub fn open(path: &Path, policy: RotationPolicy) -> io::Result<Self> {
+        let file = open_append(path)?;
+        let metadata = file.metadata()?;
+        let written = metadata.modified().unwrap_or_else(|_| SystemTime::now());
+        Ok(Self {
+            path: path.to_owned(),
+            policy,
+            active: Mutex::new(ActiveFile {
+                file,
+                len: metadata.len(),
+                period: policy.period.map(|period| period.index(written)),
+            }),
+        })
+    }
+
+    /// Append `buf`, rotating first if it is due at `now`.
+    fn write_at(&self, buf: &[u8], now: SystemTime) -> io::Result<()> {
+        let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+        let len = u64::try_from(buf.len()).unwrap_or(u64::MAX);
+        let period = self.policy.period.map(|period| period.index(now));
+        let oversized = self
+            .policy
+            .max_bytes
+            .is_some_and(|max| active.len > 0 && active.len.saturating_add(len) > max);
+        if oversized || period != active.period {
+            active.file.flush()?;
+            self.rotate()?;
+            *active = ActiveFile {
+                file: open_append(&self.path)?,
+                len: 0,
+                period,
+            };
+        }
+        active.file.write_all(buf)?;
+        active.len = active.len.saturating_add(len);
+        Ok(())
+    }
+
+    /// Shift `path.N` to `path.N+1`, dropping the oldest, and move the
+    /// active file to `path.1`.
+    fn rotate(&self) -> io::Result<()> {
+        let keep = self.policy.max_files;
+        ignore_missing(fs::remove_file(numbered(&self.path, keep)))?;
+        for index in (1..keep).rev() {
+            ignore_missing(fs::rename(
+                numbered(&self.path, index),
+                numbered(&self.path, index + 1),
+            ))?;
+        }
+        fs::rename(&self.path, numbered(&self.path, 1))
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// `path` with `.index` appended to its file name.
+fn numbered(path: &Path, index: u16) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{index}"));
+    PathBuf::from(name)
+}
+
+/// Treat a file that is already gone as removed or moved.
+fn ignore_missing(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        other => other,
+    }
+}
+
+impl<'a> MakeWriter<'a> for RotatingFile {
+    type Writer = RotatingFileWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer { RotatingFileWriter { file: self } }
+}
+
+/// Handle writing one event to a [`RotatingFile`].
+#[derive(Debug)]
+pub struct RotatingFileWriter<'a> {
+    file: &'a RotatingFile,
+}
+
+impl Write for RotatingFileWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write_at(buf, SystemTime::now())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file
+            .active
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .file
+            .flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Rotation by size and period, retention, and reading the policy.
+
+    use std::time::Duration;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    const DAY: Duration = Duration::from_hours(24);
+
+    fn read(path: &Path) -> String { fs::read_to_string(path).expect("log file reads") }
+
+    #[rstest]
+    fn rotates_by_size_and_keeps_the_newest_files() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("mxd.log");
+        let policy = RotationPolicy {
+            period: None,
+            max_bytes: Some(6),
+            max_files: 2,
+        };
+        let log = RotatingFile::open(&path, policy).expect("log opens");
+
+        for line in ["one\n", "two\n", "three\n", "four\n"] {
+            log.write_at(line.as_bytes(), UNIX_EPOCH)
+                .expect("line writes");
+        }
+
+        assert_eq!(read(&path), "four\n");
+        assert_eq!(read(&numbered(&path, 1)), "three\n");
+        assert_eq!(read(&numbered(&path, 2)), "two\n");
+        assert!(!numbered(&path, 3).exists());
+    }
+
+    #[rstest]
+    fn rotates_when_a_new_period_begins() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("mxd.log");
+        let policy = RotationPolicy {
+            period: Some(RotationPeriod::Daily),
+            ..RotationPolicy::default()
+        };
+        let log = RotatingFile::open(&path, policy).expect("log opens");
+        let today = SystemTime::now();
+
+        log.write_at(b"morning\n", today).expect("line writes");
+        log.write_at(b"evening\n", today).expect("line writes");
+        log.write_at(b"tomorrow\n", today + DAY)
+            .expect("line writes");
+
+        assert_eq!(read(&path), "tomorrow\n");
+        assert_eq!(read(&numbered(&path, 1)), "morning\nevening\n");
+    }
+
+    #[rstest]
+    #[case::unset(None, None, None, Ok(RotationPolicy::default()))]
+    #[case::daily(
+        Some("daily"),
+        Some(1024),
+        Some(3),
+        Ok(RotationPolicy {
+            period: Some(RotationPeriod::Daily),
+            max_bytes: Some(1024),
+            max_files: 3,
+        })
+    )]
+    #[case::unknown(
+        Some("weekly"),
+        None,
+        None,
+        Err(LogRotationError::UnknownPeriod("weekly".to_owned()))
+    )]
+    #[case::zero_bytes(None, Some(0), None, Err(LogRotationError::ZeroMaxBytes))]
+    #[case::zero_files(None, None, Some(0), Err(LogRotationError::ZeroMaxFiles))]
+    fn reads_rotation_policy(
+        #[case] rotation: Option<&str>,
+        #[case] max_bytes: Option<u64>,
+        #[case] max_files: Option<u16>,
+        #[case] expected: Result<RotationPolicy, LogRotationError>,
+    ) {
+        let config = AppConfig {
+            log_rotation: rotation.map(str::to_owned),
+            log_max_bytes: max_bytes,
+            log_max_files: max_files,
+            ..AppConfig::default()
+        };
+
+        assert_eq!(RotationPolicy::from_config(&config), expected);
+    }
+}
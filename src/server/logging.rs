@@ -0,0 +1,281 @@
+//! Console and log output for the server binaries.
+//!
+//! Runtime status and diagnostics are `tracing` events, so a single
+//! subscriber decides where they are written and which are kept.
+//! [`init_logging`] installs that subscriber: it writes to stderr, to the
+//! local syslog daemon, or to a self-rotating file when `log_target` says so
+//! (see [`LogTarget`] and [`super::log_file`]), and filters with `RUST_LOG`,
+//! defaulting to [`DEFAULT_LOG_FILTER`]. The one line still printed directly is the stdout banner
+//! from [`announce_listening`], kept so an operator at the console sees that the server is up
+//! whatever the filter says. Builds with the `profiling` feature also feed `tokio-console` (see
+//! [`super::profiling`]).
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Result, anyhow};
+use thiserror::Error;
+use tracing::{Subscriber, info, warn};
+#[cfg(any(not(unix), not(feature = "profiling")))]
+use tracing_subscriber::layer::Identity;
+use tracing_subscriber::{
+    EnvFilter,
+    Layer,
+    fmt,
+    layer::SubscriberExt,
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+};
+
+#[cfg(unix)]
+use super::syslog::{DEFAULT_SYSLOG_SOCKET, SyslogWriter};
+use super::{
+    AppConfig,
+    log_file::{LogRotationError, RotatingFile, RotationPolicy},
+    syslog::Facility,
+};
+
+/// Filter applied when `RUST_LOG` is unset or invalid.
+pub const DEFAULT_LOG_FILTER: &str = "info";
+
+/// `tracing` target of events recording changes the server made to stored
+/// data on its own, such as repaired news links, and logins worth a second
+/// look, such as an account logging in from a second location; select them
+/// with `RUST_LOG=mxd::audit=info`.
+pub const AUDIT_TARGET: &str = "mxd::audit";
+
+/// Where log output is written.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LogTarget {
+    /// Standard error, the default.
+    #[default]
+    Stderr,
+    /// The local syslog daemon, under the given facility (Unix only).
+    Syslog(Facility),
+    /// A file the server rotates itself.
+    File(PathBuf),
+}
+
+/// Errors raised while reading the log target from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LogTargetError {
+    /// `log_target` names neither stderr, syslog, nor a file.
+    #[error("unknown log_target {0:?}; expected stderr, syslog, syslog:<facility>, or file:<path>")]
+    Unknown(String),
+    /// `log_target` names syslog on a platform without Unix sockets.
+    #[error("log_target syslog needs a Unix platform")]
+    SyslogUnavailable,
+    /// Rotation options were set without a file target.
+    #[error("log_rotation, log_max_bytes, and log_max_files need a file: log_target")]
+    RotationWithoutFile,
+    /// The rotation options were invalid.
+    #[error(transparent)]
+    Rotation(#[from] LogRotationError),
+}
+
+impl LogTarget {
+    /// Parse `stderr`, `file:<path>`, `syslog`, or `syslog:<facility>`,
+    /// where the facility is `daemon` (the default), `user`, or `local0` to
+    /// `local7`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LogTargetError`] for an unknown target or facility, an
+    /// empty path, or syslog off Unix.
+    pub fn parse(target: &str) -> Result<Self, LogTargetError> {
+        let unknown = || LogTargetError::Unknown(target.to_owned());
+        if target == "stderr" {
+            return Ok(Self::Stderr);
+        }
+        if let Some(path) = target.strip_prefix("file:") {
+            return if path.is_empty() {
+                Err(unknown())
+            } else {
+                Ok(Self::File(PathBuf::from(path)))
+            };
+        }
+        let facility = match target.strip_prefix("syslog") {
+            Some("") => Facility::Daemon,
+            Some(rest) => rest
+                .strip_prefix(':')
+                .and_then(Facility::parse)
+                .ok_or_else(unknown)?,
+            None => return Err(unknown()),
+        };
+        if cfg!(not(unix)) {
+            return Err(LogTargetError::SyslogUnavailable);
+        }
+        Ok(Self::Syslog(facility))
+    }
+
+    /// Read the target from `config`, defaulting to stderr.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LogTargetError`] when `log_target` cannot be parsed, or
+    /// when rotation options are set for a target other than a file.
+    pub fn from_config(config: &AppConfig) -> Result<Self, LogTargetError> {
+        let target = config
+            .log_target
+            .as_deref()
+            .map_or(Ok(Self::Stderr), Self::parse)?;
+        if RotationPolicy::is_configured(config) && !matches!(target, Self::File(_)) {
+            return Err(LogTargetError::RotationWithoutFile);
+        }
+        Ok(target)
+    }
+}
+
+/// Install the process-wide `tracing` subscriber, writing to the target
+/// `config` names.
+///
+/// # Errors
+///
+/// Returns an error if `log_target` or the rotation options are invalid, the
+/// syslog socket cannot be reached, the log file cannot be opened, or a
+/// global subscriber is already installed.
+pub fn init_logging(config: &AppConfig) -> Result<()> {
+    let target = LogTarget::from_config(config)?;
+    let file = file_layer(&target, RotationPolicy::from_config(config)?)?;
+    // The filter applies to log output only: `tokio-console` needs the
+    // runtime's trace-level events whatever `RUST_LOG` says.
+    let stderr = (target == LogTarget::Stderr).then(|| {
+        fmt::layer()
+            .with_writer(io::stderr)
+            .with_filter(log_filter())
+    });
+    tracing_subscriber::registry()
+        .with(console_layer())
+        .with(stderr)
+        .with(syslog_layer(&target)?)
+        .with(file)
+        .try_init()
+        .map_err(|err| anyhow!("failed to install log subscriber: {err}"))
+}
+
+/// Filter from `RUST_LOG`, or [`DEFAULT_LOG_FILTER`] when it is unset or
+/// invalid.
+fn log_filter() -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER))
+}
+
+/// Layer sending events to the syslog daemon when `target` asks for it.
+///
+/// The daemon stamps each message itself, so the layer writes neither a
+/// timestamp nor colour codes.
+#[cfg(unix)]
+fn syslog_layer<S>(target: &LogTarget) -> Result<Option<impl Layer<S>>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let &LogTarget::Syslog(facility) = target else {
+        return Ok(None);
+    };
+    let writer = SyslogWriter::open(DEFAULT_SYSLOG_SOCKET.as_ref(), facility)
+        .map_err(|err| anyhow!("failed to reach syslog at {DEFAULT_SYSLOG_SOCKET}: {err}"))?;
+    Ok(Some(
+        fmt::layer()
+            .with_ansi(false)
+            .without_time()
+            .with_writer(writer)
+            .with_filter(log_filter()),
+    ))
+}
+
+/// [`LogTarget::parse`] refuses syslog off Unix, so there is nothing to add.
+#[cfg(not(unix))]
+const fn syslog_layer(_target: &LogTarget) -> Result<Option<Identity>> { Ok(None) }
+
+/// Layer appending events to a rotating file when `target` names one.
+///
+/// Lines keep their timestamps, since nothing else stamps them, but carry no
+/// colour codes.
+fn file_layer<S>(target: &LogTarget, policy: RotationPolicy) -> Result<Option<impl Layer<S>>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let LogTarget::File(path) = target else {
+        return Ok(None);
+    };
+    let file = RotatingFile::open(path, policy)
+        .map_err(|err| anyhow!("failed to open log file {}: {err}", path.display()))?;
+    Ok(Some(
+        fmt::layer()
+            .with_ansi(false)
+            .with_writer(file)
+            .with_filter(log_filter()),
+    ))
+}
+
+/// Layer serving task data to `tokio-console`.
+#[cfg(feature = "profiling")]
+fn console_layer<S>() -> Option<impl Layer<S>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    Some(console_subscriber::spawn())
+}
+
+/// Without the `profiling` feature there is no console to feed.
+#[cfg(not(feature = "profiling"))]
+const fn console_layer() -> Option<Identity> { None }
+
+/// Report that `program` is accepting connections on `addr`.
+///
+/// Emits a `listening` event and prints the human banner to stdout, flushing
+/// it so piped output shows the line straight away.
+#[expect(
+    clippy::print_stdout,
+    reason = "the listening banner is meant for the operator's console"
+)]
+pub fn announce_listening(program: &str, addr: &impl Display) {
+    info!(program, %addr, "listening");
+    println!("{program} listening on {addr}");
+    if let Err(error) = io::stdout().flush() {
+        warn!(%error, "failed to flush stdout");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the log target.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::stderr("stderr", Ok(LogTarget::Stderr))]
+    #[case::syslog("syslog", Ok(LogTarget::Syslog(Facility::Daemon)))]
+    #[case::facility("syslog:user", Ok(LogTarget::Syslog(Facility::User)))]
+    #[case::bad_facility("syslog:kern", Err(LogTargetError::Unknown("syslog:kern".to_owned())))]
+    #[case::no_colon("syslogd", Err(LogTargetError::Unknown("syslogd".to_owned())))]
+    #[case::file("file:/var/log/mxd.log", Ok(LogTarget::File("/var/log/mxd.log".into())))]
+    #[case::empty_file("file:", Err(LogTargetError::Unknown("file:".to_owned())))]
+    #[case::unknown("journal", Err(LogTargetError::Unknown("journal".to_owned())))]
+    fn parses_log_targets(
+        #[case] target: &str,
+        #[case] expected: Result<LogTarget, LogTargetError>,
+    ) {
+        assert_eq!(LogTarget::parse(target), expected);
+    }
+
+    #[rstest]
+    #[case::stderr(None, Err(LogTargetError::RotationWithoutFile))]
+    #[case::file(Some("file:mxd.log"), Ok(LogTarget::File("mxd.log".into())))]
+    fn rotation_needs_a_file_target(
+        #[case] target: Option<&str>,
+        #[case] expected: Result<LogTarget, LogTargetError>,
+    ) {
+        let config = AppConfig {
+            log_target: target.map(str::to_owned),
+            log_rotation: Some("daily".to_owned()),
+            ..AppConfig::default()
+        };
+
+        assert_eq!(LogTarget::from_config(&config), expected);
+    }
+}
@@ -0,0 +1,351 @@
+//! Brute-force protection for Login (107).
+//!
+//! Failed logins are counted per peer address and per existing account.
+//! Once either count reaches `login_failure_limit`, further logins from that
+//! address or to that account are refused with
+//! [`ERR_LOGIN_LOCKED`](crate::commands::ERR_LOGIN_LOCKED) until the lockout
+//! expires, without checking the password. The first lockout lasts
+//! `login_lockout_secs`, and each failure after it doubles the next one, up
+//! to `login_lockout_max_secs`. A successful login clears the account's
+//! count; an address's count is forgotten once it has been quiet for the
+//! longest lockout, so logging in to one account cannot reset the guesses
+//! made against another.
+//!
+//! Each server keeps one [`LoginThrottle`], built from the configured
+//! [`LockoutPolicy`], in its
+//! [`ServerSettings`](super::settings::ServerSettings); login consults it
+//! through the settings of the connection it arrived on.
+
+use std::{
+    collections::BTreeMap,
+    net::IpAddr,
+    num::NonZeroU32,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+use super::AppConfig;
+
+/// Failed logins allowed before the first lockout when none is configured.
+pub const DEFAULT_LOGIN_FAILURE_LIMIT: NonZeroU32 = NonZeroU32::MIN.saturating_add(4);
+
+/// Length of the first lockout when none is configured.
+pub const DEFAULT_LOGIN_LOCKOUT: Duration = Duration::from_secs(30);
+
+/// Longest lockout when none is configured.
+pub const DEFAULT_LOGIN_LOCKOUT_MAX: Duration = Duration::from_secs(60 * 60);
+
+/// Stand-in deadline for lockouts too long to represent as an [`Instant`].
+const NEVER: Duration = Duration::from_secs(60 * 60 * 24 * 365);
+
+/// When failed logins lead to a lockout, and for how long.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    /// Failures that trigger the first lockout.
+    pub failure_limit: NonZeroU32,
+    /// Length of the first lockout.
+    pub lockout: Duration,
+    /// Longest a lockout may last.
+    pub max_lockout: Duration,
+}
+
+/// Errors raised while reading the lockout policy from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LockoutPolicyError {
+    /// `login_lockout_secs` was zero.
+    #[error("login_lockout_secs must be greater than zero")]
+    ZeroLockout,
+    /// `login_lockout_max_secs` was shorter than `login_lockout_secs`.
+    #[error("login_lockout_max_secs must not be less than login_lockout_secs")]
+    MaxBelowLockout,
+}
+
+impl LockoutPolicy {
+    /// The policy used when no option is set.
+    pub const DEFAULT: Self = Self {
+        failure_limit: DEFAULT_LOGIN_FAILURE_LIMIT,
+        lockout: DEFAULT_LOGIN_LOCKOUT,
+        max_lockout: DEFAULT_LOGIN_LOCKOUT_MAX,
+    };
+
+    /// Read the policy from `config`; `None` turns lockouts off.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LockoutPolicyError`] for a zero lockout or a maximum
+    /// shorter than the first lockout.
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>, LockoutPolicyError> {
+        let failure_limit = match config.login_failure_limit {
+            None => DEFAULT_LOGIN_FAILURE_LIMIT,
+            Some(raw) => match NonZeroU32::new(raw) {
+                Some(limit) => limit,
+                None => return Ok(None),
+            },
+        };
+        let lockout = config
+            .login_lockout_secs
+            .map_or(DEFAULT_LOGIN_LOCKOUT, Duration::from_secs);
+        if lockout.is_zero() {
+            return Err(LockoutPolicyError::ZeroLockout);
+        }
+        let max_lockout = config
+            .login_lockout_max_secs
+            .map_or(DEFAULT_LOGIN_LOCKOUT_MAX.max(lockout), Duration::from_secs);
+        if max_lockout < lockout {
+            return Err(LockoutPolicyError::MaxBelowLockout);
+        }
+        Ok(Some(Self {
+            failure_limit,
+            lockout,
+            max_lockout,
+        }))
+    }
+
+    /// Return how long to lock out after `failures` consecutive failures,
+    /// or `None` while they are below the limit.
+    #[must_use]
+    pub fn lockout_after(&self, failures: u32) -> Option<Duration> {
+        let excess = failures.checked_sub(self.failure_limit.get())?;
+        let factor = 1_u32.checked_shl(excess).unwrap_or(u32::MAX);
+        Some(self.lockout.saturating_mul(factor).min(self.max_lockout))
+    }
+}
+
+/// What a failure count is kept against.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum ThrottleKey {
+    Address(IpAddr),
+    Account(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Failures {
+    count: u32,
+    last: Instant,
+    locked_until: Option<Instant>,
+}
+
+impl Failures {
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.locked_until
+            .map(|until| until.saturating_duration_since(now))
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+/// Failed-login counts for every address and account, with the policy that
+/// turns them into lockouts.
+#[derive(Debug)]
+pub struct LoginThrottle {
+    policy: Option<LockoutPolicy>,
+    failures: Mutex<BTreeMap<ThrottleKey, Failures>>,
+}
+
+impl LoginThrottle {
+    /// Create a throttle with no recorded failures.
+    #[must_use]
+    pub const fn new(policy: Option<LockoutPolicy>) -> Self {
+        Self {
+            policy,
+            failures: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Return how much longer a login from `address` to `username` is
+    /// locked out, or `None` when it may proceed.
+    #[must_use]
+    pub fn locked_for(&self, address: IpAddr, username: &str, now: Instant) -> Option<Duration> {
+        self.policy?;
+        let failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        let by_address = failures
+            .get(&ThrottleKey::Address(address))
+            .and_then(|entry| entry.remaining(now));
+        let by_account = failures
+            .get(&ThrottleKey::Account(username.to_owned()))
+            .and_then(|entry| entry.remaining(now));
+        by_address.max(by_account)
+    }
+
+    /// Count a failed login from `address`, and against `account` when it
+    /// names an existing account, returning the lockout it starts, if any.
+    pub fn record_failure(
+        &self,
+        address: IpAddr,
+        account: Option<&str>,
+        now: Instant,
+    ) -> Option<Duration> {
+        let policy = self.policy?;
+        let mut failures = self.failures.lock().unwrap_or_else(PoisonError::into_inner);
+        failures.retain(|_, entry| {
+            entry.remaining(now).is_some()
+                || now.saturating_duration_since(entry.last) < policy.max_lockout
+        });
+        let keys = std::iter::once(ThrottleKey::Address(address))
+            .chain(account.map(|name| ThrottleKey::Account(name.to_owned())));
+        let mut started = None;
+        for key in keys {
+            let entry = failures.entry(key).or_insert(Failures {
+                count: 0,
+                last: now,
+                locked_until: None,
+            });
+            entry.count = entry.count.saturating_add(1);
+            entry.last = now;
+            if let Some(lockout) = policy.lockout_after(entry.count) {
+                entry.locked_until = Some(
+                    now.checked_add(lockout)
+                        .unwrap_or_else(|| Instant::now() + NEVER),
+                );
+                started = started.max(Some(lockout));
+            }
+        }
+        started
+    }
+
+    /// Clear the failures counted against `account` after it logs in.
+    pub fn record_success(&self, account: &str) {
+        self.failures
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&ThrottleKey::Account(account.to_owned()));
+    }
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self { Self::new(Some(LockoutPolicy::DEFAULT)) }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for lockout policy parsing and failure counting.
+
+    use rstest::rstest;
+    use test_util::CLOCK_JUMPS;
+
+    use super::*;
+
+    fn policy(limit: u32, lockout_secs: u64, max_secs: u64) -> LockoutPolicy {
+        LockoutPolicy {
+            failure_limit: NonZeroU32::new(limit).expect("non-zero limit"),
+            lockout: Duration::from_secs(lockout_secs),
+            max_lockout: Duration::from_secs(max_secs),
+        }
+    }
+
+    fn address() -> IpAddr { IpAddr::from([192, 0, 2, 7]) }
+
+    #[rstest]
+    #[case::unset(None, None, None, Ok(Some(LockoutPolicy::DEFAULT)))]
+    #[case::disabled(Some(0), None, None, Ok(None))]
+    #[case::custom(Some(3), Some(10), Some(80), Ok(Some(policy(3, 10, 80))))]
+    #[case::long_first_lockout(Some(3), Some(7_200), None, Ok(Some(policy(3, 7_200, 7_200))))]
+    #[case::zero_lockout(None, Some(0), None, Err(LockoutPolicyError::ZeroLockout))]
+    #[case::max_too_short(None, Some(60), Some(30), Err(LockoutPolicyError::MaxBelowLockout))]
+    fn parses_policy_from_config(
+        #[case] limit: Option<u32>,
+        #[case] lockout_secs: Option<u64>,
+        #[case] max_secs: Option<u64>,
+        #[case] expected: Result<Option<LockoutPolicy>, LockoutPolicyError>,
+    ) {
+        let config = AppConfig {
+            login_failure_limit: limit,
+            login_lockout_secs: lockout_secs,
+            login_lockout_max_secs: max_secs,
+            ..AppConfig::default()
+        };
+        assert_eq!(LockoutPolicy::from_config(&config), expected);
+    }
+
+    #[rstest]
+    #[case(2, None)]
+    #[case(3, Some(10))]
+    #[case(4, Some(20))]
+    #[case(6, Some(80))]
+    #[case(7, Some(100))]
+    #[case(u32::MAX, Some(100))]
+    fn backs_off_exponentially(#[case] failures: u32, #[case] expected_secs: Option<u64>) {
+        assert_eq!(
+            policy(3, 10, 100).lockout_after(failures),
+            expected_secs.map(Duration::from_secs)
+        );
+    }
+
+    #[rstest]
+    fn locks_out_the_address_after_repeated_failures() {
+        let throttle = LoginThrottle::new(Some(policy(2, 10, 100)));
+        let now = Instant::now();
+
+        assert_eq!(throttle.record_failure(address(), None, now), None);
+        assert_eq!(
+            throttle.record_failure(address(), None, now),
+            Some(Duration::from_secs(10))
+        );
+
+        assert_eq!(
+            throttle.locked_for(address(), "anyone", now + Duration::from_secs(4)),
+            Some(Duration::from_secs(6))
+        );
+        assert_eq!(
+            throttle.locked_for(address(), "anyone", now + Duration::from_secs(10)),
+            None
+        );
+        assert_eq!(
+            throttle.record_failure(address(), None, now + Duration::from_secs(10)),
+            Some(Duration::from_secs(20))
+        );
+    }
+
+    #[rstest]
+    fn success_clears_only_the_account() {
+        let throttle = LoginThrottle::new(Some(policy(1, 10, 100)));
+        let now = Instant::now();
+        throttle.record_failure(address(), Some("alice"), now);
+
+        throttle.record_success("alice");
+
+        let elsewhere = IpAddr::from([198, 51, 100, 1]);
+        assert_eq!(throttle.locked_for(elsewhere, "alice", now), None);
+        assert!(throttle.locked_for(address(), "bob", now).is_some());
+    }
+
+    #[rstest]
+    fn forgets_quiet_addresses() {
+        let throttle = LoginThrottle::new(Some(policy(2, 10, 100)));
+        let now = Instant::now();
+        throttle.record_failure(address(), None, now);
+
+        let later = now + Duration::from_secs(100);
+        assert_eq!(throttle.record_failure(address(), None, later), None);
+    }
+
+    #[rstest]
+    fn lockouts_survive_clock_jumps() {
+        let lockout = Duration::from_secs(10);
+        for jump in CLOCK_JUMPS {
+            let throttle = LoginThrottle::new(Some(policy(1, 10, 100)));
+            let now = Instant::now();
+            throttle.record_failure(address(), None, now);
+
+            let later = jump.apply(now);
+            let remaining = throttle.locked_for(address(), "anyone", later);
+            if jump.passes(lockout) {
+                assert_eq!(remaining, None, "{jump:?}");
+            } else {
+                assert!(remaining.is_some_and(|left| left >= lockout), "{jump:?}");
+            }
+            throttle.record_failure(address(), None, later);
+        }
+    }
+
+    #[rstest]
+    fn disabled_policy_never_locks() {
+        let throttle = LoginThrottle::new(None);
+        let now = Instant::now();
+        for _ in 0..10 {
+            assert_eq!(throttle.record_failure(address(), Some("alice"), now), None);
+        }
+        assert_eq!(throttle.locked_for(address(), "alice", now), None);
+    }
+}
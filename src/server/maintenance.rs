@@ -0,0 +1,321 @@
+//! Scheduled database maintenance.
+//!
+//! Setting `maintenance_interval_secs` makes the server run
+//! [`crate::db::run_maintenance`] itself, so operators need not schedule
+//! `mxd db maintain` externally. The scheduler wakes every
+//! [`MAINTENANCE_CHECK_INTERVAL`]; a due run waits while the time lies outside
+//! `maintenance_window` or while clients answered more than
+//! `maintenance_max_activity` transactions per minute since the previous
+//! check, because `VACUUM` and `REINDEX` block writers while they run. The
+//! time of the last run is kept in memory, so the first run after a restart
+//! falls one interval after startup.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{NaiveTime, Utc};
+use thiserror::Error;
+use tokio::{
+    task::JoinHandle,
+    time::{Instant, sleep},
+};
+use tracing::{debug, info, warn};
+
+use super::{
+    AppConfig,
+    NetworkRuntime,
+    chaos::{ChaosPoint, chaos_point},
+    metrics::runtime_metrics,
+};
+use crate::db::{DbPool, run_maintenance};
+
+/// Transactions per minute above which a due run is deferred when no limit
+/// is configured.
+pub const DEFAULT_MAINTENANCE_MAX_ACTIVITY: u64 = 30;
+
+/// How often the scheduler checks whether maintenance is due.
+pub const MAINTENANCE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Daily UTC time range in which scheduled maintenance may start.
+///
+/// A window whose end precedes its start, such as `23:00-02:00`, spans
+/// midnight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaintenanceWindow {
+    /// First time of day a run may start.
+    pub start: NaiveTime,
+    /// Time of day after which no run may start.
+    pub end: NaiveTime,
+}
+
+impl MaintenanceWindow {
+    /// Return whether `time` lies within the window.
+    #[must_use]
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// How often to run maintenance, when, and how quiet the server must be.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaintenanceSchedule {
+    /// Time between runs.
+    pub interval: Duration,
+    /// Time range in which runs may start; `None` allows any time.
+    pub window: Option<MaintenanceWindow>,
+    /// Transactions per minute above which a due run is deferred.
+    pub max_activity: u64,
+}
+
+/// What the scheduler does at one check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaintenanceDecision {
+    /// The interval has not elapsed since the last run.
+    NotDue,
+    /// A run is due but the time lies outside the window.
+    OutsideWindow,
+    /// A run is due but clients are too busy.
+    Busy,
+    /// Run maintenance now.
+    Run,
+}
+
+/// Errors raised while reading the maintenance schedule from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MaintenanceScheduleError {
+    /// `maintenance_interval_secs` was zero.
+    #[error("maintenance_interval_secs must be greater than zero")]
+    ZeroInterval,
+    /// `maintenance_window` was not of the form `HH:MM-HH:MM`.
+    #[error("maintenance_window '{0}' must look like 02:00-05:00")]
+    InvalidWindow(String),
+}
+
+impl MaintenanceSchedule {
+    /// Read the schedule from `config`; `None` when no interval is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MaintenanceScheduleError`] for a zero interval or a
+    /// malformed window.
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>, MaintenanceScheduleError> {
+        let Some(secs) = config.maintenance_interval_secs else {
+            return Ok(None);
+        };
+        if secs == 0 {
+            return Err(MaintenanceScheduleError::ZeroInterval);
+        }
+        let window = config
+            .maintenance_window
+            .as_deref()
+            .map(parse_window)
+            .transpose()?;
+        Ok(Some(Self {
+            interval: Duration::from_secs(secs),
+            window,
+            max_activity: config
+                .maintenance_max_activity
+                .unwrap_or(DEFAULT_MAINTENANCE_MAX_ACTIVITY),
+        }))
+    }
+
+    /// Decide whether to run, given the time since the last run, the UTC time
+    /// of day, and the transactions answered per minute since the last check.
+    #[must_use]
+    pub fn decide(
+        &self,
+        since_last: Duration,
+        now: NaiveTime,
+        per_minute: u64,
+    ) -> MaintenanceDecision {
+        if since_last < self.interval {
+            MaintenanceDecision::NotDue
+        } else if self.window.is_some_and(|window| !window.contains(now)) {
+            MaintenanceDecision::OutsideWindow
+        } else if per_minute > self.max_activity {
+            MaintenanceDecision::Busy
+        } else {
+            MaintenanceDecision::Run
+        }
+    }
+}
+
+fn parse_window(raw: &str) -> Result<MaintenanceWindow, MaintenanceScheduleError> {
+    let invalid = || MaintenanceScheduleError::InvalidWindow(raw.to_owned());
+    let (start, end) = raw.split_once('-').ok_or_else(invalid)?;
+    let parse = |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| invalid());
+    Ok(MaintenanceWindow {
+        start: parse(start)?,
+        end: parse(end)?,
+    })
+}
+
+/// Run maintenance whenever `schedule` falls due and the server is quiet,
+/// until the returned task is aborted, or return `None` when no schedule is
+/// set.
+///
+/// A failed run is logged and retried after another interval.
+#[must_use]
+pub fn start_scheduled_maintenance(
+    pool: DbPool,
+    schedule: Option<MaintenanceSchedule>,
+) -> Option<JoinHandle<()>> {
+    let schedule = schedule?;
+    Some(tokio::spawn(async move {
+        let mut last_run = Instant::now();
+        let mut last_transactions = transactions_answered();
+        loop {
+            sleep(MAINTENANCE_CHECK_INTERVAL).await;
+            let transactions = transactions_answered();
+            // Checks are a minute apart, so the difference is a per-minute rate.
+            let per_minute = transactions.saturating_sub(last_transactions);
+            last_transactions = transactions;
+            if run_if_due(&pool, &schedule, last_run.elapsed(), per_minute).await {
+                last_run = Instant::now();
+            }
+        }
+    }))
+}
+
+/// Run maintenance if `schedule` allows it now, returning whether it ran.
+async fn run_if_due(
+    pool: &DbPool,
+    schedule: &MaintenanceSchedule,
+    since_last: Duration,
+    per_minute: u64,
+) -> bool {
+    match schedule.decide(since_last, Utc::now().time(), per_minute) {
+        MaintenanceDecision::NotDue | MaintenanceDecision::OutsideWindow => false,
+        MaintenanceDecision::Busy => {
+            debug!(per_minute, "database maintenance deferred while busy");
+            false
+        }
+        MaintenanceDecision::Run => {
+            chaos_point(ChaosPoint::Maintenance).await;
+            if let Err(error) = maintain(pool).await {
+                warn!(%error, "scheduled database maintenance failed");
+            }
+            true
+        }
+    }
+}
+
+/// Transactions answered by both runtimes since the process started.
+fn transactions_answered() -> u64 {
+    [NetworkRuntime::Legacy, NetworkRuntime::Wireframe]
+        .into_iter()
+        .map(|runtime| runtime_metrics(runtime).snapshot().transactions)
+        .sum()
+}
+
+async fn maintain(pool: &DbPool) -> Result<()> {
+    let mut conn = pool.get().await?;
+    let started = Instant::now();
+    let steps = run_maintenance(&mut conn).await?;
+    info!(
+        statements = steps.len(),
+        elapsed_ms = started.elapsed().as_millis(),
+        "scheduled database maintenance finished"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for maintenance schedule parsing and deferral.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn time(raw: &str) -> NaiveTime { NaiveTime::parse_from_str(raw, "%H:%M").expect("time") }
+
+    fn window(start: &str, end: &str) -> MaintenanceWindow {
+        MaintenanceWindow {
+            start: time(start),
+            end: time(end),
+        }
+    }
+
+    #[rstest]
+    #[case::unset(None, None, None, Ok(None))]
+    #[case::defaults(
+        Some(3_600),
+        None,
+        None,
+        Ok(Some(MaintenanceSchedule {
+            interval: Duration::from_secs(3_600),
+            window: None,
+            max_activity: DEFAULT_MAINTENANCE_MAX_ACTIVITY,
+        }))
+    )]
+    #[case::custom(
+        Some(86_400),
+        Some(" 23:30 - 02:00 "),
+        Some(5),
+        Ok(Some(MaintenanceSchedule {
+            interval: Duration::from_secs(86_400),
+            window: Some(window("23:30", "02:00")),
+            max_activity: 5,
+        }))
+    )]
+    #[case::zero_interval(Some(0), None, None, Err(MaintenanceScheduleError::ZeroInterval))]
+    #[case::bad_window(
+        Some(60),
+        Some("tonight"),
+        None,
+        Err(MaintenanceScheduleError::InvalidWindow("tonight".to_owned()))
+    )]
+    fn parses_schedule_from_config(
+        #[case] interval_secs: Option<u64>,
+        #[case] window_range: Option<&str>,
+        #[case] max_activity: Option<u64>,
+        #[case] expected: Result<Option<MaintenanceSchedule>, MaintenanceScheduleError>,
+    ) {
+        let config = AppConfig {
+            maintenance_interval_secs: interval_secs,
+            maintenance_window: window_range.map(str::to_owned),
+            maintenance_max_activity: max_activity,
+            ..AppConfig::default()
+        };
+        assert_eq!(MaintenanceSchedule::from_config(&config), expected);
+    }
+
+    #[rstest]
+    #[case::inside(("02:00", "05:00"), "03:15", true)]
+    #[case::at_end(("02:00", "05:00"), "05:00", false)]
+    #[case::across_midnight_late(("23:00", "02:00"), "23:30", true)]
+    #[case::across_midnight_early(("23:00", "02:00"), "01:59", true)]
+    #[case::across_midnight_outside(("23:00", "02:00"), "12:00", false)]
+    fn windows_may_span_midnight(
+        #[case] bounds: (&str, &str),
+        #[case] at: &str,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(window(bounds.0, bounds.1).contains(time(at)), expected);
+    }
+
+    #[rstest]
+    #[case::not_due(59, "03:00", 0, MaintenanceDecision::NotDue)]
+    #[case::outside_window(60, "12:00", 0, MaintenanceDecision::OutsideWindow)]
+    #[case::busy(60, "03:00", 11, MaintenanceDecision::Busy)]
+    #[case::quiet(60, "03:00", 10, MaintenanceDecision::Run)]
+    fn due_runs_wait_for_the_window_and_quiet(
+        #[case] minutes_since_last: u64,
+        #[case] now: &str,
+        #[case] per_minute: u64,
+        #[case] expected: MaintenanceDecision,
+    ) {
+        let schedule = MaintenanceSchedule {
+            interval: Duration::from_secs(3_600),
+            window: Some(window("02:00", "05:00")),
+            max_activity: 10,
+        };
+        let since_last = Duration::from_secs(minutes_since_last * 60);
+        assert_eq!(schedule.decide(since_last, time(now), per_minute), expected);
+    }
+}
@@ -0,0 +1,175 @@
+//! Per-runtime traffic counters.
+//!
+//! Each networking runtime counts the connections it serves and the
+//! transactions it answers, labelled by [`NetworkRuntime`]. In dual-runtime
+//! mode both stacks share one database, so these counters are what an
+//! operator compares when judging the Wireframe runtime against the legacy
+//! one. Round-trip times measured by server pings (see [`super::ping`]) are
+//! counted in a histogram with the bounds in [`RTT_BUCKETS_MS`].
+//! [`log_runtime_metrics`] reports a runtime's counters when it stops, along
+//! with the process-wide reassembly statistics from
+//! [`crate::transaction::reassembly_stats`].
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use tracing::info;
+
+use super::NetworkRuntime;
+use crate::transaction::reassembly_stats;
+
+static LEGACY: RuntimeMetrics = RuntimeMetrics::new();
+
+static WIREFRAME: RuntimeMetrics = RuntimeMetrics::new();
+
+/// Upper bounds, in milliseconds, of the round-trip time histogram buckets.
+/// One more bucket counts everything slower than the last bound.
+pub const RTT_BUCKETS_MS: [u64; 6] = [10, 50, 100, 250, 500, 1_000];
+
+/// Buckets in the round-trip time histogram.
+pub const RTT_BUCKET_COUNT: usize = RTT_BUCKETS_MS.len() + 1;
+
+/// Traffic counters for one networking runtime.
+#[derive(Debug, Default)]
+pub struct RuntimeMetrics {
+    connections: AtomicU64,
+    transactions: AtomicU64,
+    error_replies: AtomicU64,
+    round_trips: [AtomicU64; RTT_BUCKET_COUNT],
+}
+
+/// Point-in-time copy of [`RuntimeMetrics`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RuntimeMetricsSnapshot {
+    /// Client connections handed to the runtime.
+    pub connections: u64,
+    /// Transactions answered.
+    pub transactions: u64,
+    /// Replies that carried a non-zero error code.
+    pub error_replies: u64,
+    /// Answered pings per round-trip time bucket.
+    pub round_trips: [u64; RTT_BUCKET_COUNT],
+}
+
+impl RuntimeMetrics {
+    /// Create a set of zeroed counters.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            connections: AtomicU64::new(0),
+            transactions: AtomicU64::new(0),
+            error_replies: AtomicU64::new(0),
+            round_trips: [const { AtomicU64::new(0) }; RTT_BUCKET_COUNT],
+        }
+    }
+
+    /// Count one client connection.
+    pub fn record_connection(&self) { self.connections.fetch_add(1, Ordering::Relaxed); }
+
+    /// Count one answered transaction whose reply carried `error`.
+    pub fn record_reply(&self, error: u32) {
+        self.transactions.fetch_add(1, Ordering::Relaxed);
+        if error != 0 {
+            self.error_replies.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Count one answered ping that took `round_trip`.
+    pub fn record_round_trip(&self, round_trip: Duration) {
+        let millis = u64::try_from(round_trip.as_millis()).unwrap_or(u64::MAX);
+        let bucket = RTT_BUCKETS_MS
+            .iter()
+            .position(|bound| millis <= *bound)
+            .unwrap_or(RTT_BUCKETS_MS.len());
+        if let Some(counter) = self.round_trips.get(bucket) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Read the current counter values.
+    #[must_use]
+    pub fn snapshot(&self) -> RuntimeMetricsSnapshot {
+        RuntimeMetricsSnapshot {
+            connections: self.connections.load(Ordering::Relaxed),
+            transactions: self.transactions.load(Ordering::Relaxed),
+            error_replies: self.error_replies.load(Ordering::Relaxed),
+            round_trips: self
+                .round_trips
+                .each_ref()
+                .map(|counter| counter.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Return the process-wide counters for `runtime`.
+#[must_use]
+pub const fn runtime_metrics(runtime: NetworkRuntime) -> &'static RuntimeMetrics {
+    match runtime {
+        NetworkRuntime::Legacy => &LEGACY,
+        NetworkRuntime::Wireframe => &WIREFRAME,
+    }
+}
+
+/// Log the counters for `runtime`, labelled with its name, and the
+/// reassembly statistics.
+pub fn log_runtime_metrics(runtime: NetworkRuntime) {
+    let stats = runtime_metrics(runtime).snapshot();
+    let reassembly = reassembly_stats();
+    info!(
+        runtime = runtime.label(),
+        connections = stats.connections,
+        transactions = stats.transactions,
+        error_replies = stats.error_replies,
+        round_trips = ?stats.round_trips,
+        partial_transactions = reassembly.in_progress,
+        oldest_partial = ?reassembly.oldest,
+        reassemblies_aborted = reassembly.aborted,
+        "runtime statistics"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    //! Counting connections and replies.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn counts_replies_and_errors() {
+        let metrics = RuntimeMetrics::new();
+        metrics.record_connection();
+        metrics.record_reply(0);
+        metrics.record_reply(4);
+        assert_eq!(
+            metrics.snapshot(),
+            RuntimeMetricsSnapshot {
+                connections: 1,
+                transactions: 2,
+                error_replies: 1,
+                round_trips: [0; RTT_BUCKET_COUNT],
+            }
+        );
+    }
+
+    #[rstest]
+    fn buckets_round_trips_by_their_upper_bound() {
+        let metrics = RuntimeMetrics::new();
+        metrics.record_round_trip(Duration::from_millis(4));
+        metrics.record_round_trip(Duration::from_millis(10));
+        metrics.record_round_trip(Duration::from_millis(120));
+        metrics.record_round_trip(Duration::from_secs(3));
+        assert_eq!(metrics.snapshot().round_trips, [2, 0, 0, 1, 0, 0, 1]);
+    }
+
+    #[rstest]
+    fn runtimes_have_separate_counters() {
+        assert!(!std::ptr::eq(
+            runtime_metrics(NetworkRuntime::Legacy),
+            runtime_metrics(NetworkRuntime::Wireframe)
+        ));
+    }
+}
@@ -6,20 +6,108 @@
 //! feature flag, allowing the bespoke frame handler to be disabled without
 //! touching domain or admin flows.
 
+pub mod accept;
+pub mod accounting;
 pub mod admin;
+pub mod agreement;
+pub mod archives;
+pub mod bans;
+pub mod bind;
+pub mod broadcast;
+pub mod chaos;
+pub mod chat;
 pub mod cli;
+pub mod client_info;
+pub mod connection_limit;
+pub mod db_pool;
+pub mod disconnect;
+pub mod download_policy;
+pub mod duplicate_login;
+pub mod flat_file;
+pub mod health;
+mod http;
+pub mod idle;
+pub mod init;
+pub mod instant_msg;
+pub mod io_timeouts;
 #[cfg(feature = "legacy-networking")]
 pub mod legacy;
+pub mod log_file;
+pub mod logging;
+pub mod login_throttle;
+pub mod maintenance;
+pub mod metrics;
+pub mod news_digest;
+pub mod news_fsck;
+pub mod news_roots;
+pub mod news_transfer;
 pub mod outbound;
+pub mod outbox;
+pub mod param_limit;
+pub mod payload_limits;
+pub mod ping;
+pub mod profiling;
+pub mod rate_limit;
+pub mod reassembly;
+pub mod rules;
+pub mod runtime;
+pub mod session_resume;
+pub mod settings;
+pub mod shutdown;
+pub mod storage_quota;
+pub mod subsystems;
+pub mod summary;
+pub mod syslog;
+pub mod tasks;
+pub mod tls;
+pub mod transaction_span;
+pub mod transfer_port;
+pub mod transfer_stats;
+pub mod transfers;
 pub mod wireframe;
 
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 pub use admin::run_command;
+use agreement::ServerAgreement;
 use anyhow::Result;
-pub use cli::{AppConfig, Cli, Commands, CreateUserArgs, ResolvedCli, load_cli};
+pub use cli::{
+    AppConfig,
+    BanArgs,
+    BanTargetArgs,
+    Cli,
+    Commands,
+    CreateUserArgs,
+    CreditsArgs,
+    DbCommand,
+    DropBoxArgs,
+    FilesCommand,
+    InitArgs,
+    MigrateArgs,
+    NewsCommand,
+    NewsDigestArgs,
+    NewsFsckArgs,
+    NewsRootsArgs,
+    QuotaArgs,
+    ResolvedCli,
+    UsersCommand,
+    load_cli,
+};
+use connection_limit::{ConnectionLimiter, ConnectionLimits};
+use download_policy::DownloadRules;
 #[cfg(feature = "legacy-networking")]
 pub use legacy::run_daemon;
+use login_throttle::{LockoutPolicy, LoginThrottle};
+use rate_limit::{RateLimitPolicy, RateLimiter};
+use rules::ServerRules;
+use session_resume::resume_window_from_config;
+use settings::{BackgroundSettings, ServerSettings, TransportSettings};
+use subsystems::Subsystems;
+use summary::{log_config_summary, summarise};
+use transfer_port::TransferRegistry;
+use transfers::TransferLimits;
+
+use crate::{hashing::HashingPool, storage::open_storage};
 
 /// Track which networking runtime the crate is compiled to use.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -42,6 +130,17 @@ pub const fn active_runtime() -> NetworkRuntime {
     }
 }
 
+impl NetworkRuntime {
+    /// Lower-case name used to label logs and metrics.
+    #[must_use]
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Legacy => "legacy",
+            Self::Wireframe => "wireframe",
+        }
+    }
+}
+
 impl FromStr for NetworkRuntime {
     type Err = String;
 
@@ -55,6 +154,52 @@ impl FromStr for NetworkRuntime {
     }
 }
 
+/// Read the settings both runtimes take from `config`, then log the
+/// effective configuration with a warning for each risky combination.
+///
+/// Nothing is installed process-wide: the runtime hands the returned
+/// settings to its connections, so servers in one process stay independent.
+///
+/// # Errors
+///
+/// Returns an error if an option is invalid, or if the TLS certificate,
+/// storage backend, agreement, or banner cannot be loaded.
+pub(crate) async fn configure_process(config: &AppConfig) -> Result<ServerSettings> {
+    let connection_limits = ConnectionLimits::from_config(config)?;
+    let transport = TransportSettings::from_config(config)?;
+    let download_rules = DownloadRules::from_config(config)?;
+    let lockout_policy = LockoutPolicy::from_config(config)?;
+    let resume_window = resume_window_from_config(config)?;
+    let rate_limits = RateLimitPolicy::from_config(config)?;
+    let background = BackgroundSettings::from_config(config)?;
+    let storage = open_storage(config)?;
+    let agreement = ServerAgreement::from_config(config, storage.as_deref()).await?;
+    let rules = ServerRules::from_config(config)?;
+    let summary = summarise(config, &agreement)?;
+    log_config_summary(&summary);
+    Ok(ServerSettings {
+        connections: Arc::new(ConnectionLimiter::new(connection_limits)),
+        address_bans: Arc::default(),
+        transport,
+        rate_limits: Arc::new(RateLimiter::new(rate_limits)),
+        subsystems: Subsystems::from_config(config),
+        unknown_transactions: summary.unknown_transactions,
+        clear_away_on_activity: config.clear_away_on_activity,
+        sql_trace_comments: config.sql_trace_comments,
+        agreement,
+        rules,
+        login_throttle: LoginThrottle::new(lockout_policy),
+        hashing: HashingPool::from_config(config),
+        resume_window,
+        storage,
+        download_rules,
+        transfers: Arc::new(TransferRegistry::new(TransferLimits::from_config(config))),
+        news_transfer_threshold: config.news_transfer_threshold,
+        background,
+        accounting: Arc::default(),
+    })
+}
+
 /// Parse CLI arguments and execute the requested command or daemon.
 ///
 /// # Errors
@@ -115,4 +260,11 @@ mod tests {
     fn rejects_unknown_runtime() {
         assert!("unknown".parse::<NetworkRuntime>().is_err());
     }
+
+    #[rstest]
+    #[case(NetworkRuntime::Legacy)]
+    #[case(NetworkRuntime::Wireframe)]
+    fn labels_parse_back(#[case] runtime: NetworkRuntime) {
+        assert_eq!(runtime.label().parse::<NetworkRuntime>(), Ok(runtime));
+    }
 }
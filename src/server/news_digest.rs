@@ -0,0 +1,419 @@
+//! Scheduled news digests for accounts that opted in.
+//!
+//! Operators subscribe an account with `mxd news digest`, naming the
+//! categories it follows and, optionally, an email address (see
+//! [`crate::db::subscribe_news_digest`]). Every `news_digest_interval_secs`
+//! the job started by [`start_news_digests`] lists, for each subscriber, the
+//! articles posted in those categories since the account last caught up.
+//! When `news_digest_sendmail` is set and the account has an address, the
+//! digest is emailed through that program and the account counts as caught
+//! up. Otherwise the digest waits in the database and the Wireframe runtime
+//! sends it as a Server Message (104) at the account's next login, through
+//! [`take_login_digest`]; logging in also counts as catching up. The legacy
+//! runtime cannot push to clients, so digests wait for a Wireframe login.
+
+use std::{fmt::Write as _, path::PathBuf, process::Stdio, time::Duration};
+
+use anyhow::{Context, Result, bail};
+use chrono::{NaiveDateTime, Utc};
+use thiserror::Error;
+use tokio::{io::AsyncWriteExt, process::Command, task::JoinHandle, time::sleep};
+use tracing::{debug, warn};
+
+use super::{
+    AppConfig,
+    subsystems::{Subsystem, Subsystems},
+};
+use crate::{
+    db::{
+        DbConnection,
+        DbPool,
+        DigestArticle,
+        NewsDigestSubscriber,
+        mark_digest_sent,
+        new_digest_articles,
+        news_digest_subscribers,
+        store_pending_digest,
+        take_pending_digest,
+    },
+    field_id::FieldId,
+    presence::server_notification,
+    transaction::{Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// Time between digest builds when none is configured.
+pub const DEFAULT_DIGEST_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Most articles listed in one digest; the rest are only counted.
+pub const DIGEST_MAX_ARTICLES: i64 = 50;
+
+/// How long the sendmail program may take to accept one digest.
+pub const SENDMAIL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often digests are built and how they may be emailed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigestSchedule {
+    /// Time between digest builds.
+    pub interval: Duration,
+    /// Program used to email digests, if any.
+    pub mailer: Option<DigestMailer>,
+}
+
+/// A sendmail-compatible program and the sender it should use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DigestMailer {
+    /// Program run with `-t -i`, reading the message on standard input.
+    pub program: PathBuf,
+    /// `From` address, if one is configured.
+    pub from: Option<String>,
+}
+
+/// Errors raised while reading the digest schedule from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DigestScheduleError {
+    /// `news_digest_interval_secs` was zero.
+    #[error("news_digest_interval_secs must be greater than zero")]
+    ZeroInterval,
+    /// `news_digest_from` was set without `news_digest_sendmail`.
+    #[error("news_digest_from needs news_digest_sendmail")]
+    FromWithoutSendmail,
+    /// `news_digest_from` is not a usable address.
+    #[error("news_digest_from is not a valid email address")]
+    InvalidFrom,
+}
+
+impl Default for DigestSchedule {
+    fn default() -> Self {
+        Self {
+            interval: DEFAULT_DIGEST_INTERVAL,
+            mailer: None,
+        }
+    }
+}
+
+impl DigestSchedule {
+    /// Read the schedule from `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DigestScheduleError`] for a zero interval or an unusable
+    /// sender.
+    pub fn from_config(config: &AppConfig) -> Result<Self, DigestScheduleError> {
+        let interval = config
+            .news_digest_interval_secs
+            .map_or(DEFAULT_DIGEST_INTERVAL, Duration::from_secs);
+        if interval.is_zero() {
+            return Err(DigestScheduleError::ZeroInterval);
+        }
+        let from = config.news_digest_from.clone();
+        if from
+            .as_deref()
+            .is_some_and(|address| !is_email_address(address))
+        {
+            return Err(DigestScheduleError::InvalidFrom);
+        }
+        let mailer = match (config.news_digest_sendmail.as_deref(), from) {
+            (Some(program), from) => Some(DigestMailer {
+                program: PathBuf::from(program),
+                from,
+            }),
+            (None, Some(_)) => return Err(DigestScheduleError::FromWithoutSendmail),
+            (None, None) => None,
+        };
+        Ok(Self { interval, mailer })
+    }
+}
+
+/// Return whether `address` looks like an email address that is safe to
+/// put in a message header.
+#[must_use]
+pub fn is_email_address(address: &str) -> bool {
+    address.contains('@') && !address.chars().any(|c| c.is_control() || c.is_whitespace())
+}
+
+/// Build digests every `schedule` interval until the returned task is
+/// aborted.
+///
+/// A failed build is logged and retried at the next interval. Nothing is
+/// built when `subsystems` has news switched off.
+#[must_use]
+pub fn start_news_digests(
+    pool: DbPool,
+    subsystems: Subsystems,
+    schedule: DigestSchedule,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if !subsystems.is_enabled(Subsystem::News) {
+            return;
+        }
+        loop {
+            sleep(schedule.interval).await;
+            match build_digests(&pool, &schedule).await {
+                Ok(built) if built > 0 => debug!(built, "news digests built"),
+                Ok(_) => {}
+                Err(error) => warn!(%error, "news digest build failed"),
+            }
+        }
+    })
+}
+
+/// Build and deliver a digest for every subscriber with new articles,
+/// returning how many were built.
+///
+/// A subscriber whose digest cannot be delivered is logged and tried again
+/// at the next build.
+///
+/// # Errors
+///
+/// Returns an error if the database cannot be reached.
+pub async fn build_digests(pool: &DbPool, schedule: &DigestSchedule) -> Result<usize> {
+    let mut conn = pool.get().await?;
+    let now = Utc::now().naive_utc();
+    let mut built = 0_usize;
+    for subscriber in news_digest_subscribers(&mut conn).await? {
+        match deliver_digest(&mut conn, schedule, &subscriber, now).await {
+            Ok(true) => built = built.saturating_add(1),
+            Ok(false) => {}
+            Err(error) => {
+                warn!(%error, user_id = subscriber.user_id, "news digest delivery failed");
+            }
+        }
+    }
+    Ok(built)
+}
+
+/// Email or store the digest of articles new to `subscriber` up to `now`,
+/// returning `false` when there are none.
+async fn deliver_digest(
+    conn: &mut DbConnection,
+    schedule: &DigestSchedule,
+    subscriber: &NewsDigestSubscriber,
+    now: NaiveDateTime,
+) -> Result<bool> {
+    let user_id = subscriber.user_id;
+    let (articles, total) = new_digest_articles(conn, subscriber, now, DIGEST_MAX_ARTICLES).await?;
+    if total == 0 {
+        return Ok(false);
+    }
+    let text = build_digest_text(&articles, total);
+    match (&schedule.mailer, subscriber.email.as_deref()) {
+        (Some(mailer), Some(to)) => {
+            email_digest(mailer, to, &text).await?;
+            mark_digest_sent(conn, user_id, now).await?;
+        }
+        _ => {
+            store_pending_digest(conn, user_id, &text).await?;
+        }
+    }
+    Ok(true)
+}
+
+/// Render a digest listing `articles` out of `total` new ones, one line
+/// each, separated by carriage returns as Hotline clients expect.
+#[must_use]
+pub fn build_digest_text(articles: &[DigestArticle], total: i64) -> String {
+    let noun = if total == 1 { "article" } else { "articles" };
+    let mut text = format!("News digest: {total} new {noun}");
+    for article in articles {
+        let posted = article.posted_at.format("%Y-%m-%d %H:%M");
+        let _ = write!(text, "\r{posted} {}: {}", article.category, article.title);
+        if let Some(poster) = &article.poster {
+            let _ = write!(text, " ({poster})");
+        }
+    }
+    let shown = i64::try_from(articles.len()).unwrap_or(i64::MAX);
+    if total > shown {
+        let _ = write!(text, "\r...and {} more", total.saturating_sub(shown));
+    }
+    text
+}
+
+/// Build the Server Message (104) push carrying digest `text`.
+///
+/// # Errors
+///
+/// Returns an encoding error if the text exceeds protocol limits.
+pub fn build_digest_msg(text: &str) -> Result<Transaction, TransactionError> {
+    let payload = encode_params(&[(FieldId::Data, text.as_bytes())])?;
+    Ok(server_notification(TransactionType::ServerMsg, payload))
+}
+
+/// Clear the digest waiting for `user_id` and return it as a push, or
+/// `None` when nothing is waiting.
+///
+/// The login counts as catching up, so later digests start from now.
+///
+/// # Errors
+///
+/// Returns an error if the database cannot be reached or the digest cannot
+/// be encoded.
+pub async fn take_login_digest(pool: &DbPool, user_id: i32) -> Result<Option<Transaction>> {
+    let mut conn = pool.get().await?;
+    let now = Utc::now().naive_utc();
+    let Some(text) = take_pending_digest(&mut conn, user_id, now).await? else {
+        return Ok(None);
+    };
+    Ok(Some(build_digest_msg(&text)?))
+}
+
+/// Hand `text` to the sendmail program addressed to `to`.
+///
+/// Writing the message and waiting for the program share one
+/// [`SENDMAIL_TIMEOUT`]; a program still running when it expires is killed.
+async fn email_digest(mailer: &DigestMailer, to: &str, text: &str) -> Result<()> {
+    if !is_email_address(to) {
+        bail!("invalid digest address {to:?}");
+    }
+    let from = mailer
+        .from
+        .as_ref()
+        .map(|from| format!("From: {from}\n"))
+        .unwrap_or_default();
+    let body = text.replace('\r', "\n");
+    let message = format!("{from}To: {to}\nSubject: News digest\n\n{body}\n");
+    let mut child = Command::new(&mailer.program)
+        .args(["-t", "-i"])
+        .stdin(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to run {}", mailer.program.display()))?;
+    let delivery = async {
+        let mut stdin = child
+            .stdin
+            .take()
+            .context("sendmail has no standard input")?;
+        stdin.write_all(message.as_bytes()).await?;
+        drop(stdin);
+        Ok::<_, anyhow::Error>(child.wait().await?)
+    };
+    let Ok(delivered) = tokio::time::timeout(SENDMAIL_TIMEOUT, delivery).await else {
+        child.kill().await.context("failed to stop sendmail")?;
+        bail!("sendmail timed out");
+    };
+    let status = delivered?;
+    if !status.success() {
+        bail!("sendmail exited with {status}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for digest schedule parsing and rendering.
+
+    use std::os::unix::fs::PermissionsExt;
+
+    use chrono::NaiveDate;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn config(interval_secs: Option<u64>, sendmail: Option<&str>, from: Option<&str>) -> AppConfig {
+        AppConfig {
+            news_digest_interval_secs: interval_secs,
+            news_digest_sendmail: sendmail.map(str::to_owned),
+            news_digest_from: from.map(str::to_owned),
+            ..AppConfig::default()
+        }
+    }
+
+    #[rstest]
+    fn defaults_to_hourly_digests_at_login() {
+        assert_eq!(
+            DigestSchedule::from_config(&AppConfig::default()),
+            Ok(DigestSchedule::default())
+        );
+    }
+
+    #[rstest]
+    fn reads_the_mailer() {
+        let schedule = DigestSchedule::from_config(&config(
+            Some(600),
+            Some("/usr/sbin/sendmail"),
+            Some("news@example.org"),
+        ));
+        assert_eq!(
+            schedule,
+            Ok(DigestSchedule {
+                interval: Duration::from_secs(600),
+                mailer: Some(DigestMailer {
+                    program: PathBuf::from("/usr/sbin/sendmail"),
+                    from: Some("news@example.org".to_owned()),
+                }),
+            })
+        );
+    }
+
+    #[rstest]
+    #[case::zero_interval(Some(0), None, None, DigestScheduleError::ZeroInterval)]
+    #[case::from_alone(
+        None,
+        None,
+        Some("news@example.org"),
+        DigestScheduleError::FromWithoutSendmail
+    )]
+    #[case::header_injection(
+        None,
+        Some("sendmail"),
+        Some("news@example.org\nBcc: x@example.org"),
+        DigestScheduleError::InvalidFrom
+    )]
+    fn rejects_invalid_settings(
+        #[case] interval_secs: Option<u64>,
+        #[case] sendmail: Option<&str>,
+        #[case] from: Option<&str>,
+        #[case] expected: DigestScheduleError,
+    ) {
+        assert_eq!(
+            DigestSchedule::from_config(&config(interval_secs, sendmail, from)),
+            Err(expected)
+        );
+    }
+
+    #[rstest]
+    fn digests_list_articles_and_count_the_rest() {
+        let posted_at = NaiveDate::from_ymd_opt(2026, 10, 17)
+            .and_then(|date| date.and_hms_opt(9, 30, 0))
+            .expect("valid timestamp");
+        let article = DigestArticle {
+            category: "Tech/Rust".to_owned(),
+            title: "Editions".to_owned(),
+            poster: Some("alice".to_owned()),
+            posted_at,
+        };
+
+        assert_eq!(
+            build_digest_text(&[article], 3),
+            "News digest: 3 new articles\r2026-10-17 09:30 Tech/Rust: Editions (alice)\r...and 2 \
+             more"
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stalled_mailers_are_stopped() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let program = dir.path().join("sendmail");
+        std::fs::write(&program, "#!/bin/sh\nexec sleep 600\n").expect("script writes");
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755))
+            .expect("script is executable");
+        let mailer = DigestMailer {
+            program,
+            from: None,
+        };
+        // Larger than a pipe buffer, so the write itself stalls.
+        let text = "x".repeat(1024 * 1024);
+
+        let error = email_digest(&mailer, "alice@example.org", &text)
+            .await
+            .expect_err("stalled mailer must time out");
+
+        assert_eq!(error.to_string(), "sendmail timed out");
+    }
+
+    #[rstest]
+    fn digests_travel_as_server_messages() {
+        let push = build_digest_msg("News digest: 1 new article").expect("encode");
+        assert_eq!(push.header.ty, u16::from(TransactionType::ServerMsg));
+    }
+}
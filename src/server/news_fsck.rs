@@ -0,0 +1,49 @@
+//! Repairing news thread links at startup.
+//!
+//! With `news_fsck_on_startup` set, both runtimes call
+//! [`repair_news_on_startup`] before accepting connections, so a chain left
+//! half-linked by a crash is whole again before anyone reads it. Each repair
+//! is recorded under [`AUDIT_TARGET`]. The `news fsck` subcommand runs the
+//! same pass on demand.
+
+use anyhow::Result;
+use tracing::{info, warn};
+
+use super::{AppConfig, logging::AUDIT_TARGET};
+use crate::db::{DbPool, LinkRepair, repair_news_linkage};
+
+/// Record each of `repairs` in the audit log.
+pub fn log_link_repairs(repairs: &[LinkRepair]) {
+    for repair in repairs {
+        info!(
+            target: AUDIT_TARGET,
+            article_id = repair.article_id,
+            column = repair.field.column(),
+            found = ?repair.found,
+            expected = ?repair.expected,
+            "repaired news article link"
+        );
+    }
+}
+
+/// Repair news thread links when `config` asks for it.
+///
+/// A failed pass is logged and the server starts anyway, since it only
+/// leaves the threads as they were.
+pub async fn repair_news_on_startup(pool: &DbPool, config: &AppConfig) {
+    if !config.news_fsck_on_startup {
+        return;
+    }
+    match repair(pool).await {
+        Ok(repairs) => {
+            log_link_repairs(&repairs);
+            info!(repairs = repairs.len(), "news thread check finished");
+        }
+        Err(error) => warn!(%error, "news thread check failed"),
+    }
+}
+
+async fn repair(pool: &DbPool) -> Result<Vec<LinkRepair>> {
+    let mut conn = pool.get().await?;
+    Ok(repair_news_linkage(&mut conn).await?)
+}
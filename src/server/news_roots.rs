@@ -0,0 +1,39 @@
+//! The `news roots` administrative subcommand.
+//!
+//! Each news root is a separate tree of bundles and categories, such as one
+//! board per community sharing a server. Clients pick a root at login with
+//! [`FieldId::NewsRoot`](crate::field_id::FieldId::NewsRoot) and stay in the
+//! primary root otherwise. [`run_news_roots`] adds roots and lists them with
+//! the identifiers clients select them by.
+
+#![expect(
+    clippy::print_stdout,
+    reason = "intentional user output for CLI commands"
+)]
+
+use anyhow::{Context, Result};
+
+use super::{AppConfig, NewsRootsArgs, admin::open_database};
+use crate::db::{NewsRoot, create_news_root, list_news_roots};
+
+/// Add the root named by `--add`, if any, then list every root.
+///
+/// # Errors
+///
+/// Returns an error when the database cannot be opened, the name is taken,
+/// or the roots cannot be listed.
+pub async fn run_news_roots(args: NewsRootsArgs, cfg: &AppConfig) -> Result<()> {
+    let mut conn = open_database(cfg).await?;
+    if let Some(name) = args.add.as_deref() {
+        create_news_root(&mut conn, name)
+            .await
+            .with_context(|| format!("failed to add news root '{name}'"))?;
+    }
+    let roots = list_news_roots(&mut conn)
+        .await
+        .context("failed to list news roots")?;
+    for NewsRoot { id, name } in &roots {
+        println!("{id}\t{name}");
+    }
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! Sending long news article bodies over the transfer port.
+//!
+//! With `news_transfer_threshold` set, a Get News Article Data (400) request
+//! carrying [`FieldId::NewsDataTransfer`](crate::field_id::FieldId::NewsDataTransfer)
+//! for an article whose body is longer than the threshold is answered without
+//! the body. The reply names a transfer reference (107) and size (108)
+//! instead, and the client fetches the body from the transfer port, so a long
+//! body never holds up the transaction connection. Clients that do not ask
+//! are always answered inline, as is everyone when the option is unset. The
+//! threshold is kept in the server's
+//! [`ServerSettings`](super::settings::ServerSettings).
+
+/// Whether a body of `len` bytes goes over the transfer port under
+/// `threshold`; `None` sends every body inline.
+#[must_use]
+pub fn transfers_body(threshold: Option<u32>, len: usize) -> bool {
+    threshold.is_some_and(|limit| len > usize::try_from(limit).unwrap_or(usize::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    //! Deciding which bodies use the transfer port.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::unset(None, 1_000_000, false)]
+    #[case::at_threshold(Some(4), 4, false)]
+    #[case::above_threshold(Some(4), 5, true)]
+    #[case::zero_threshold(Some(0), 1, true)]
+    #[case::empty_body(Some(0), 0, false)]
+    fn bodies_above_the_threshold_use_the_transfer_port(
+        #[case] threshold: Option<u32>,
+        #[case] len: usize,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(transfers_body(threshold, len), expected);
+    }
+}
@@ -124,6 +124,27 @@ pub trait OutboundMessaging: Send + Sync {
         message: Transaction,
         priority: OutboundPriority,
     ) -> Result<(), OutboundError>;
+
+    /// Return the controller for ending other connections, when the runtime
+    /// offers one.
+    fn controller(&self) -> Option<&dyn ConnectionController> { None }
+}
+
+/// Control channel from the command layer back to live connections.
+#[async_trait]
+pub trait ConnectionController: Send + Sync {
+    /// Send `target` a Disconnect Message carrying `reason` and take it
+    /// offline.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutboundError::TargetUnavailable`] if the connection has
+    /// already gone.
+    async fn disconnect(
+        &self,
+        target: OutboundConnectionId,
+        reason: &str,
+    ) -> Result<(), OutboundError>;
 }
 
 /// In-memory reply buffer used by adapters that need to return a reply value.
@@ -258,4 +279,9 @@ mod tests {
 
         assert_eq!(err, OutboundError::MessagingUnavailable);
     }
+
+    #[rstest]
+    fn noop_messaging_offers_no_controller() {
+        assert!(NoopOutboundMessaging.controller().is_none());
+    }
 }
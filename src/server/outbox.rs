@@ -0,0 +1,203 @@
+//! Delivering outbox notifications to connected clients.
+//!
+//! Changes that clients should hear about write an [`OutboxEvent`] in their
+//! own transaction (see [`crate::db::enqueue_outbox_event`]). The Wireframe
+//! runtime starts [`start_outbox_dispatcher`], which every
+//! [`OUTBOX_POLL_INTERVAL`] pushes pending events to every online session,
+//! oldest first, and then marks them delivered. Events left pending by a crash
+//! are retried after the restart, but each account's delivery cursor (see
+//! [`crate::db::advance_outbox_cursor`]) is moved before its sessions are
+//! pushed to, so an account that reconnects is never sent the same event
+//! twice. A crash between moving the cursor and pushing loses that notice for
+//! the account instead: delivery is at most once per account. Delivered events
+//! are deleted after
+//! [`OUTBOX_RETENTION`]. The legacy runtime cannot push to clients, so events
+//! posted through it wait for the Wireframe server.
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use chrono::{TimeDelta, Utc};
+use tokio::{task::JoinHandle, time::sleep};
+use tracing::{debug, warn};
+
+use super::{
+    chaos::{ChaosPoint, chaos_point},
+    outbound::{OutboundConnectionId, OutboundMessaging, OutboundPriority, OutboundTarget},
+};
+use crate::{
+    db::{
+        DbConnection,
+        DbPool,
+        OutboxEvent,
+        OutboxRecord,
+        advance_outbox_cursor,
+        mark_outbox_delivered,
+        pending_outbox_events,
+        prune_outbox,
+    },
+    field_id::FieldId,
+    presence::{PresenceRegistry, server_notification},
+    transaction::{Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// How often the dispatcher looks for pending events.
+pub const OUTBOX_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Events delivered in one pass.
+pub const OUTBOX_BATCH_SIZE: i64 = 100;
+
+/// How long delivered events are kept before they are deleted.
+pub const OUTBOX_RETENTION: TimeDelta = TimeDelta::days(1);
+
+/// Build the Server Message (104) push announcing `event`.
+///
+/// # Errors
+///
+/// Returns an encoding error if the text exceeds protocol limits.
+pub fn build_outbox_push(event: &OutboxEvent) -> Result<Transaction, TransactionError> {
+    let text = match event {
+        OutboxEvent::ArticlePosted { path, title, .. } => {
+            format!("New news article in {path}: {title}")
+        }
+    };
+    let payload = encode_params(&[(FieldId::Data, text.as_bytes())])?;
+    Ok(server_notification(TransactionType::ServerMsg, payload))
+}
+
+/// Deliver pending outbox events until the returned task is aborted.
+///
+/// A failed pass is logged and retried at the next poll.
+#[must_use]
+pub fn start_outbox_dispatcher(
+    pool: DbPool,
+    messaging: Arc<dyn OutboundMessaging>,
+    presence: Arc<PresenceRegistry>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            sleep(OUTBOX_POLL_INTERVAL).await;
+            chaos_point(ChaosPoint::OutboxDispatch).await;
+            if let Err(error) = dispatch_pending(&pool, messaging.as_ref(), &presence).await {
+                warn!(%error, "outbox dispatch failed");
+            }
+        }
+    })
+}
+
+/// Deliver one batch of pending events and mark them, returning how many
+/// were handled.
+///
+/// # Errors
+///
+/// Returns an error if the database cannot be reached; events handled before
+/// the failure stay pending and are retried, skipping accounts already sent
+/// them.
+pub async fn dispatch_pending(
+    pool: &DbPool,
+    messaging: &dyn OutboundMessaging,
+    presence: &PresenceRegistry,
+) -> Result<usize> {
+    let mut conn = pool.get().await?;
+    let pending = pending_outbox_events(&mut conn, OUTBOX_BATCH_SIZE).await?;
+    let mut handled = Vec::with_capacity(pending.len());
+    for record in &pending {
+        match outbox_push(record) {
+            Ok(push) => {
+                let targets = claim_recipients(&mut conn, presence, record.id).await?;
+                push_to(messaging, targets, &push).await;
+            }
+            // An event that cannot be decoded or encoded never will be, so it
+            // is marked rather than retried forever.
+            Err(error) => warn!(%error, id = record.id, "dropping undeliverable outbox event"),
+        }
+        handled.push(record.id);
+    }
+    mark_outbox_delivered(&mut conn, &handled).await?;
+    if !handled.is_empty() {
+        debug!(events = handled.len(), "outbox events delivered");
+        if let Some(before) = Utc::now().naive_utc().checked_sub_signed(OUTBOX_RETENTION) {
+            prune_outbox(&mut conn, before).await?;
+        }
+    }
+    Ok(handled.len())
+}
+
+fn outbox_push(record: &OutboxRecord) -> Result<Transaction> {
+    Ok(build_outbox_push(&record.decode()?)?)
+}
+
+/// Move the delivery cursor of every online account that has not yet been
+/// sent event `event_id`, returning the sessions of those accounts.
+async fn claim_recipients(
+    conn: &mut DbConnection,
+    presence: &PresenceRegistry,
+    event_id: i32,
+) -> Result<Vec<OutboundConnectionId>> {
+    let mut accounts: BTreeMap<i32, Vec<OutboundConnectionId>> = BTreeMap::new();
+    for snapshot in presence.online_snapshots() {
+        accounts
+            .entry(snapshot.account_id)
+            .or_default()
+            .push(snapshot.connection_id);
+    }
+    let mut recipients = Vec::new();
+    for (account_id, connections) in accounts {
+        if advance_outbox_cursor(conn, account_id, event_id).await? {
+            recipients.extend(connections);
+        }
+    }
+    Ok(recipients)
+}
+
+/// Push `message` to each of `targets`, logging failures per recipient.
+async fn push_to(
+    messaging: &dyn OutboundMessaging,
+    targets: Vec<OutboundConnectionId>,
+    message: &Transaction,
+) {
+    for connection_id in targets {
+        let target = OutboundTarget::Connection(connection_id);
+        if let Err(error) = messaging
+            .push(target, message.clone(), OutboundPriority::Low)
+            .await
+        {
+            warn!(
+                ?error,
+                target = connection_id.as_u64(),
+                "outbox delivery failed"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for outbox push construction.
+    use rstest::rstest;
+
+    use super::*;
+    use crate::transaction::decode_params;
+
+    #[rstest]
+    fn posted_articles_are_announced_with_their_category() {
+        let event = OutboxEvent::ArticlePosted {
+            path: "General".to_owned(),
+            article_id: 7,
+            title: "Welcome".to_owned(),
+        };
+
+        let push = build_outbox_push(&event).expect("build push");
+
+        assert_eq!(push.header.ty, u16::from(TransactionType::ServerMsg));
+        let params = decode_params(&push.payload).expect("decode push");
+        assert_eq!(
+            params,
+            vec![(
+                FieldId::Data,
+                b"New news article in General: Welcome".to_vec()
+            )]
+        );
+    }
+}
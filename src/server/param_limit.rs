@@ -0,0 +1,59 @@
+//! Parameter count cap for incoming payloads.
+//!
+//! Operators bound how many fields one request may declare with
+//! `max_param_count`. The cap travels in each server's
+//! [`TransportSettings`](super::settings::TransportSettings) alongside the
+//! payload limits, so both runtimes refuse larger parameter blocks with
+//! [`crate::transaction::TransactionError::TooManyParams`] before decoding
+//! them.
+
+use thiserror::Error;
+
+use super::AppConfig;
+use crate::transaction::DEFAULT_MAX_PARAM_COUNT;
+
+/// Errors raised while reading the parameter cap from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParamLimitError {
+    /// `max_param_count` was zero.
+    #[error("max_param_count must be greater than zero")]
+    Zero,
+}
+
+/// Read the parameter cap from `config`, defaulting to
+/// [`DEFAULT_MAX_PARAM_COUNT`].
+///
+/// # Errors
+///
+/// Returns [`ParamLimitError::Zero`] for a zero cap.
+pub fn param_limit_from_config(config: &AppConfig) -> Result<usize, ParamLimitError> {
+    match config.max_param_count {
+        None => Ok(DEFAULT_MAX_PARAM_COUNT),
+        Some(0) => Err(ParamLimitError::Zero),
+        Some(limit) => Ok(usize::from(limit)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the parameter cap from configuration.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, Ok(DEFAULT_MAX_PARAM_COUNT))]
+    #[case(Some(0), Err(ParamLimitError::Zero))]
+    #[case(Some(64), Ok(64))]
+    fn reads_param_limit(
+        #[case] limit: Option<u16>,
+        #[case] expected: Result<usize, ParamLimitError>,
+    ) {
+        let config = AppConfig {
+            max_param_count: limit,
+            ..AppConfig::default()
+        };
+        assert_eq!(param_limit_from_config(&config), expected);
+    }
+}
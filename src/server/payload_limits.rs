@@ -0,0 +1,122 @@
+//! Per-type payload limits for incoming transactions.
+//!
+//! Operators give chosen transaction types their own payload limit with
+//! `payload_limits`, a comma-separated list such as
+//! `SendChat=4096, PostNewsArticle=4194304`. Types are named as they appear
+//! in logs or by number; the others keep
+//! [`MAX_PAYLOAD_SIZE`](crate::transaction::MAX_PAYLOAD_SIZE).
+//! The limits travel in each server's
+//! [`TransportSettings`](super::settings::TransportSettings), so both runtimes
+//! refuse larger requests with
+//! [`crate::transaction::TransactionError::PayloadTooLarge`] before buffering
+//! them.
+
+use thiserror::Error;
+
+use super::AppConfig;
+use crate::{
+    transaction::{MAX_PAYLOAD_LIMIT, PayloadLimits},
+    transaction_type::TransactionType,
+};
+
+/// Errors raised while reading payload limits from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PayloadLimitsError {
+    /// An entry was not of the form `Type=bytes`.
+    #[error("payload limit {0:?} must look like Type=bytes")]
+    Malformed(String),
+    /// An entry named no known transaction type.
+    #[error("unknown transaction type {0:?} in payload_limits")]
+    UnknownType(String),
+    /// An entry's limit was not a byte count.
+    #[error("payload limit for {0} must be a number of bytes")]
+    InvalidSize(String),
+    /// An entry's limit exceeded [`MAX_PAYLOAD_LIMIT`].
+    #[error("payload limit for {0} exceeds the {MAX_PAYLOAD_LIMIT}-byte maximum")]
+    TooLarge(String),
+}
+
+/// Read the per-type payload limits from `config`.
+///
+/// # Errors
+///
+/// Returns a [`PayloadLimitsError`] for a malformed entry, an unknown type,
+/// or a limit that is not a byte count or exceeds [`MAX_PAYLOAD_LIMIT`].
+pub fn payload_limits_from_config(config: &AppConfig) -> Result<PayloadLimits, PayloadLimitsError> {
+    let mut limits = PayloadLimits::new();
+    for entry in config
+        .payload_limits
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+    {
+        if entry.is_empty() {
+            continue;
+        }
+        let (raw_name, raw_size) = entry
+            .split_once('=')
+            .ok_or_else(|| PayloadLimitsError::Malformed(entry.to_owned()))?;
+        let (name, size) = (raw_name.trim(), raw_size.trim());
+        let ty = TransactionType::from_name(name)
+            .or_else(|| name.parse::<u16>().ok().map(TransactionType::from))
+            .ok_or_else(|| PayloadLimitsError::UnknownType(name.to_owned()))?;
+        let limit = size
+            .parse::<usize>()
+            .map_err(|_| PayloadLimitsError::InvalidSize(name.to_owned()))?;
+        if limit > MAX_PAYLOAD_LIMIT {
+            return Err(PayloadLimitsError::TooLarge(name.to_owned()));
+        }
+        limits = limits.with_limit(ty, limit);
+    }
+    Ok(limits)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading payload limits from configuration.
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::transaction::MAX_PAYLOAD_SIZE;
+
+    fn read(limits: Option<&str>) -> Result<PayloadLimits, PayloadLimitsError> {
+        let config = AppConfig {
+            payload_limits: limits.map(str::to_owned),
+            ..AppConfig::default()
+        };
+        payload_limits_from_config(&config)
+    }
+
+    #[rstest]
+    fn unset_limits_keep_the_default() {
+        let limits = read(None).expect("limits read");
+
+        assert_eq!(limits, PayloadLimits::new());
+        assert_eq!(limits.limit(TransactionType::SendChat), MAX_PAYLOAD_SIZE);
+    }
+
+    #[rstest]
+    fn reads_types_by_name_or_number() {
+        let limits =
+            read(Some("SendChat=4096, 3001 = 8192,PostNewsArticle=4194304")).expect("limits read");
+
+        assert_eq!(limits.limit(TransactionType::SendChat), 4096);
+        assert_eq!(limits.limit(TransactionType::Search), 8192);
+        assert_eq!(limits.limit(TransactionType::PostNewsArticle), 4_194_304);
+        assert_eq!(limits.limit(TransactionType::Login), MAX_PAYLOAD_SIZE);
+    }
+
+    #[rstest]
+    #[case::malformed("SendChat", PayloadLimitsError::Malformed("SendChat".to_owned()))]
+    #[case::unknown("Shout=10", PayloadLimitsError::UnknownType("Shout".to_owned()))]
+    #[case::not_bytes("SendChat=4k", PayloadLimitsError::InvalidSize("SendChat".to_owned()))]
+    #[case::too_large(
+        "SendChat=999999999",
+        PayloadLimitsError::TooLarge("SendChat".to_owned())
+    )]
+    fn rejects_invalid_entries(#[case] limits: &str, #[case] expected: PayloadLimitsError) {
+        assert_eq!(read(Some(limits)), Err(expected));
+    }
+}
@@ -0,0 +1,251 @@
+//! Server-initiated pings and round-trip times.
+//!
+//! With `ping_interval_secs` set, the Wireframe runtime sends each connection
+//! a Connection Keep Alive (500) request every interval, numbered by the
+//! connection's [`PingTracker`]. Clients that understand it answer with a
+//! reply carrying the same id, and the time between the two is the
+//! connection's round-trip time. The latest one is shown in Get Client Info
+//! Text (303) and each one is counted in the runtime's histogram (see
+//! [`super::metrics`]).
+//!
+//! A client that has answered a ping and then misses `ping_miss_limit` in a
+//! row is disconnected with [`PING_DISCONNECT_REASON`], well before the idle
+//! timeout would notice it has gone. A client that answers none of its first
+//! `ping_miss_limit` pings is taken not to support them; it is pinged no more
+//! and left to the idle timeout. The legacy runtime does not send pings.
+
+use std::{
+    num::NonZeroU32,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use thiserror::Error;
+use tokio::time::Instant;
+
+use super::AppConfig;
+use crate::{
+    presence::server_notification,
+    transaction::Transaction,
+    transaction_type::TransactionType,
+};
+
+/// Consecutive unanswered pings allowed when none is configured.
+pub const DEFAULT_PING_MISS_LIMIT: NonZeroU32 = NonZeroU32::MIN.saturating_add(2);
+
+/// Reason given in the Disconnect Message sent to a client that stopped
+/// answering pings.
+pub const PING_DISCONNECT_REASON: &str = "Disconnected: not answering pings";
+
+/// How often connections are pinged, and how many misses end one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PingPolicy {
+    /// Time between pings.
+    pub interval: Duration,
+    /// Consecutive unanswered pings that end a connection.
+    pub miss_limit: NonZeroU32,
+}
+
+/// Errors raised while reading the ping policy from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PingPolicyError {
+    /// `ping_interval_secs` was zero.
+    #[error("ping_interval_secs must be greater than zero")]
+    ZeroInterval,
+    /// `ping_miss_limit` was zero.
+    #[error("ping_miss_limit must be greater than zero")]
+    ZeroMissLimit,
+}
+
+impl PingPolicy {
+    /// Read the policy from `config`; `None` turns pings off.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PingPolicyError`] for a zero interval or miss limit.
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>, PingPolicyError> {
+        let Some(secs) = config.ping_interval_secs else {
+            return Ok(None);
+        };
+        if secs == 0 {
+            return Err(PingPolicyError::ZeroInterval);
+        }
+        let miss_limit = match config.ping_miss_limit {
+            None => DEFAULT_PING_MISS_LIMIT,
+            Some(raw) => NonZeroU32::new(raw).ok_or(PingPolicyError::ZeroMissLimit)?,
+        };
+        Ok(Some(Self {
+            interval: Duration::from_secs(secs),
+            miss_limit,
+        }))
+    }
+}
+
+/// Build the Connection Keep Alive (500) request sent as ping `id`.
+#[must_use]
+pub fn build_ping(id: u32) -> Transaction {
+    let mut ping = server_notification(TransactionType::KeepAlive, Vec::new());
+    ping.header.id = id;
+    ping
+}
+
+/// What a connection's pinger does next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PingStep {
+    /// Send the ping with this id.
+    Send(u32),
+    /// Stop pinging a client that has never answered.
+    Stop,
+    /// Disconnect a client that answered before but no longer does.
+    Evict,
+}
+
+/// Ping state of one connection, shared between its pinger, the code that
+/// reads its replies, and the presence registry.
+#[derive(Debug, Default)]
+pub struct PingTracker {
+    state: Mutex<PingState>,
+}
+
+#[derive(Debug, Default)]
+struct PingState {
+    last_id: u32,
+    outstanding: Option<(u32, Instant)>,
+    missed: u32,
+    answered: bool,
+    round_trip: Option<Duration>,
+}
+
+impl PingTracker {
+    /// Decide the next step, counting the previous ping as missed if it is
+    /// still unanswered.
+    #[must_use]
+    pub fn next_step(&self, miss_limit: NonZeroU32) -> PingStep {
+        let mut state = self.lock_state();
+        if state.outstanding.take().is_some() {
+            state.missed = state.missed.saturating_add(1);
+        }
+        if state.missed >= miss_limit.get() {
+            return if state.answered {
+                PingStep::Evict
+            } else {
+                PingStep::Stop
+            };
+        }
+        let id = state.last_id.wrapping_add(1);
+        state.last_id = id;
+        state.outstanding = Some((id, Instant::now()));
+        PingStep::Send(id)
+    }
+
+    /// Record the client's reply to ping `id`, returning the round-trip time
+    /// when it answers the ping in flight.
+    #[must_use]
+    pub fn answer(&self, id: u32) -> Option<Duration> {
+        let mut state = self.lock_state();
+        let (expected, sent_at) = state.outstanding?;
+        if expected != id {
+            return None;
+        }
+        let round_trip = sent_at.elapsed();
+        state.outstanding = None;
+        state.missed = 0;
+        state.answered = true;
+        state.round_trip = Some(round_trip);
+        Some(round_trip)
+    }
+
+    /// Round-trip time of the most recently answered ping.
+    #[must_use]
+    pub fn round_trip(&self) -> Option<Duration> { self.lock_state().round_trip }
+
+    fn lock_state(&self) -> MutexGuard<'_, PingState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the ping policy and tracking answers.
+
+    use rstest::rstest;
+    use test_util::CLOCK_JUMPS;
+
+    use super::*;
+
+    const LIMIT: NonZeroU32 = NonZeroU32::MIN.saturating_add(1);
+
+    #[rstest]
+    #[case(None, None, Ok(None))]
+    #[case(Some(0), None, Err(PingPolicyError::ZeroInterval))]
+    #[case(Some(30), Some(0), Err(PingPolicyError::ZeroMissLimit))]
+    #[case(Some(30), None, Ok(Some(PingPolicy {
+        interval: Duration::from_secs(30),
+        miss_limit: DEFAULT_PING_MISS_LIMIT,
+    })))]
+    fn reads_ping_policy(
+        #[case] interval: Option<u64>,
+        #[case] misses: Option<u32>,
+        #[case] expected: Result<Option<PingPolicy>, PingPolicyError>,
+    ) {
+        let config = AppConfig {
+            ping_interval_secs: interval,
+            ping_miss_limit: misses,
+            ..AppConfig::default()
+        };
+        assert_eq!(PingPolicy::from_config(&config), expected);
+    }
+
+    #[rstest]
+    fn pings_are_keep_alive_requests() {
+        let ping = build_ping(7);
+        assert_eq!(ping.header.ty, u16::from(TransactionType::KeepAlive));
+        assert_eq!(ping.header.is_reply, 0);
+        assert_eq!(ping.header.id, 7);
+    }
+
+    #[rstest]
+    fn answers_record_the_round_trip() {
+        let tracker = PingTracker::default();
+        let PingStep::Send(id) = tracker.next_step(LIMIT) else {
+            panic!("first step should send a ping");
+        };
+        assert_eq!(tracker.answer(id.wrapping_add(1)), None);
+        let round_trip = tracker.answer(id).expect("ping in flight");
+        assert_eq!(tracker.round_trip(), Some(round_trip));
+        assert_eq!(tracker.answer(id), None);
+    }
+
+    #[rstest]
+    fn silent_clients_stop_being_pinged() {
+        let tracker = PingTracker::default();
+        assert!(matches!(tracker.next_step(LIMIT), PingStep::Send(_)));
+        assert!(matches!(tracker.next_step(LIMIT), PingStep::Send(_)));
+        assert_eq!(tracker.next_step(LIMIT), PingStep::Stop);
+        assert_eq!(tracker.round_trip(), None);
+    }
+
+    #[rstest]
+    fn answering_clients_are_evicted_after_missed_pings() {
+        let tracker = PingTracker::default();
+        let PingStep::Send(id) = tracker.next_step(LIMIT) else {
+            panic!("first step should send a ping");
+        };
+        assert!(tracker.answer(id).is_some());
+        assert!(matches!(tracker.next_step(LIMIT), PingStep::Send(_)));
+        assert!(matches!(tracker.next_step(LIMIT), PingStep::Send(_)));
+        assert_eq!(tracker.next_step(LIMIT), PingStep::Evict);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn round_trips_follow_clock_jumps() {
+        for jump in CLOCK_JUMPS {
+            let tracker = PingTracker::default();
+            let PingStep::Send(id) = tracker.next_step(LIMIT) else {
+                panic!("first step should send a ping");
+            };
+            tokio::time::advance(jump.forward()).await;
+            assert_eq!(tracker.answer(id), Some(jump.forward()), "{jump:?}");
+        }
+    }
+}
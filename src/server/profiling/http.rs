@@ -0,0 +1,224 @@
+//! Debug HTTP server for the profiling endpoints.
+//!
+//! Requests are served one per connection by `server::http`:
+//!
+//! - `/debug/pprof/profile?seconds=N` samples every thread's CPU use for `N` seconds and returns a
+//!   pprof protobuf for `pprof` or `go tool pprof`.
+//! - `/debug/pprof/flamegraph?seconds=N` renders the same sample as an SVG flame graph.
+//! - `/debug/pprof/heap` returns jemalloc's heap profile as a gzipped pprof protobuf.
+//!
+//! `seconds` defaults to [`DEFAULT_SECONDS`] and may be at most
+//! [`MAX_SECONDS`]. Only one CPU profile runs at a time. The server has no
+//! authentication, so `profiling_bind` should name a loopback or otherwise
+//! private address.
+
+use std::{net::SocketAddr, time::Duration};
+
+use anyhow::Result;
+use pprof::{ProfilerGuardBuilder, protos::Message};
+use tokio::{
+    sync::Mutex,
+    task::{JoinHandle, spawn_blocking},
+};
+use tracing::info;
+
+use crate::server::http::{self, Response, parse_get};
+
+/// Seconds a CPU profile samples for when the request does not say.
+const DEFAULT_SECONDS: u64 = 30;
+
+/// Longest CPU profile a request may ask for, in seconds.
+const MAX_SECONDS: u64 = 300;
+
+/// CPU samples taken per second; odd so sampling does not fall into step
+/// with periodic work.
+const SAMPLE_FREQUENCY: i32 = 99;
+
+/// Held while a CPU profile runs; the sampler is process-wide.
+static CPU_PROFILE: Mutex<()> = Mutex::const_new(());
+
+/// Profile a request asks for.
+#[derive(Debug, PartialEq, Eq)]
+enum Endpoint {
+    /// CPU samples over the given window, in the given format.
+    Cpu(CpuFormat, Duration),
+    /// The current jemalloc heap profile.
+    Heap,
+}
+
+/// Encoding of a CPU profile.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CpuFormat {
+    /// pprof protobuf.
+    Pprof,
+    /// SVG flame graph.
+    Flamegraph,
+}
+
+/// Bind `addr` and serve profiles until the returned task is aborted.
+///
+/// # Errors
+///
+/// Returns an error if the address cannot be bound.
+pub(super) fn start(addr: SocketAddr) -> Result<JoinHandle<()>> {
+    let handle = http::spawn(addr, "profiling", |head| async move {
+        match parse_request(&head) {
+            Ok(endpoint) => respond(endpoint).await,
+            Err(response) => response,
+        }
+    })?;
+    info!(%addr, "serving profiles");
+    Ok(handle)
+}
+
+fn parse_request(head: &str) -> Result<Endpoint, Response> {
+    let (path, query) = parse_get(head)?;
+    match path {
+        "/debug/pprof/profile" => Ok(Endpoint::Cpu(CpuFormat::Pprof, sample_window(query)?)),
+        "/debug/pprof/flamegraph" => {
+            Ok(Endpoint::Cpu(CpuFormat::Flamegraph, sample_window(query)?))
+        }
+        "/debug/pprof/heap" => Ok(Endpoint::Heap),
+        _ => Err(Response::error("404 Not Found", "unknown profile")),
+    }
+}
+
+fn sample_window(query: &str) -> Result<Duration, Response> {
+    let Some(value) = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("seconds="))
+    else {
+        return Ok(Duration::from_secs(DEFAULT_SECONDS));
+    };
+    match value.parse::<u64>() {
+        Ok(seconds) if (1..=MAX_SECONDS).contains(&seconds) => Ok(Duration::from_secs(seconds)),
+        _ => Err(Response::error(
+            "400 Bad Request",
+            &format!("seconds must be between 1 and {MAX_SECONDS}"),
+        )),
+    }
+}
+
+async fn respond(endpoint: Endpoint) -> Response {
+    match endpoint {
+        Endpoint::Cpu(format, window) => profile_cpu(format, window).await,
+        Endpoint::Heap => profile_heap().await,
+    }
+}
+
+async fn profile_cpu(format: CpuFormat, window: Duration) -> Response {
+    let Ok(_running) = CPU_PROFILE.try_lock() else {
+        return Response::error("409 Conflict", "a CPU profile is already running");
+    };
+    // The sampler installs a signal handler and is driven from a plain
+    // thread, so it never holds up the runtime's workers.
+    let sampled = spawn_blocking(move || sample_cpu(format, window)).await;
+    match sampled {
+        Ok(Ok(response)) => response,
+        Ok(Err(error)) => Response::error("500 Internal Server Error", &error),
+        Err(error) => Response::error("500 Internal Server Error", &error.to_string()),
+    }
+}
+
+fn sample_cpu(format: CpuFormat, window: Duration) -> Result<Response, String> {
+    let guard = ProfilerGuardBuilder::default()
+        .frequency(SAMPLE_FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|error| error.to_string())?;
+    std::thread::sleep(window);
+    let report = guard.report().build().map_err(|error| error.to_string())?;
+    let mut body = Vec::new();
+    match format {
+        CpuFormat::Pprof => {
+            let profile = report.pprof().map_err(|error| error.to_string())?;
+            profile
+                .encode(&mut body)
+                .map_err(|error| error.to_string())?;
+            Ok(Response::ok("application/octet-stream", body))
+        }
+        CpuFormat::Flamegraph => {
+            report
+                .flamegraph(&mut body)
+                .map_err(|error| error.to_string())?;
+            Ok(Response::ok("image/svg+xml", body))
+        }
+    }
+}
+
+async fn profile_heap() -> Response {
+    let Some(shared) = jemalloc_pprof::PROF_CTL.as_ref() else {
+        return Response::error(
+            "503 Service Unavailable",
+            "jemalloc heap profiling is unavailable",
+        );
+    };
+    let mut control = shared.lock().await;
+    if !control.activated() {
+        return Response::error(
+            "503 Service Unavailable",
+            "heap profiling is off; start the server with MALLOC_CONF=prof:true,prof_active:true",
+        );
+    }
+    match control.dump_pprof() {
+        Ok(body) => Response::ok("application/octet-stream", body),
+        Err(error) => Response::error("500 Internal Server Error", &error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Routing profiling requests.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("GET /debug/pprof/profile HTTP/1.1\r\n\r\n", CpuFormat::Pprof, 30)]
+    #[case(
+        "GET /debug/pprof/profile?seconds=5 HTTP/1.1\r\n\r\n",
+        CpuFormat::Pprof,
+        5
+    )]
+    #[case(
+        "GET /debug/pprof/flamegraph?debug=1&seconds=300 HTTP/1.1\r\n\r\n",
+        CpuFormat::Flamegraph,
+        300
+    )]
+    fn routes_cpu_profiles(#[case] head: &str, #[case] format: CpuFormat, #[case] seconds: u64) {
+        assert_eq!(
+            parse_request(head),
+            Ok(Endpoint::Cpu(format, Duration::from_secs(seconds)))
+        );
+    }
+
+    #[rstest]
+    fn routes_heap_profiles() {
+        assert_eq!(
+            parse_request("GET /debug/pprof/heap HTTP/1.1\r\n\r\n"),
+            Ok(Endpoint::Heap)
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "GET /debug/pprof/profile?seconds=0 HTTP/1.1\r\n\r\n",
+        "400 Bad Request"
+    )]
+    #[case(
+        "GET /debug/pprof/profile?seconds=301 HTTP/1.1\r\n\r\n",
+        "400 Bad Request"
+    )]
+    #[case(
+        "GET /debug/pprof/profile?seconds=x HTTP/1.1\r\n\r\n",
+        "400 Bad Request"
+    )]
+    #[case("POST /debug/pprof/heap HTTP/1.1\r\n\r\n", "405 Method Not Allowed")]
+    #[case("GET /metrics HTTP/1.1\r\n\r\n", "404 Not Found")]
+    #[case("", "400 Bad Request")]
+    fn refuses_other_requests(#[case] head: &str, #[case] status: &str) {
+        let refused = parse_request(head).expect_err("must be refused");
+        assert_eq!(refused.status, status);
+    }
+}
@@ -0,0 +1,118 @@
+//! Profiling hooks for performance investigations on production-like hosts.
+//!
+//! Builds with the `profiling` feature carry everything an investigation
+//! needs, so nobody has to ship a custom binary to look at a slow server:
+//!
+//! - [`crate::server::logging::init_logging`] installs a `tokio-console` layer, which serves task
+//!   data on `127.0.0.1:6669` (override with `TOKIO_CONSOLE_BIND`). Task data needs the build to
+//!   set `RUSTFLAGS="--cfg tokio_unstable"` as well.
+//! - The server binaries allocate through jemalloc, so heap profiles can be taken once the server
+//!   starts with `MALLOC_CONF=prof:true,prof_active:true`. The library leaves the choice of
+//!   allocator to the binary that links it.
+//! - With `profiling_bind` set, [`start_profiling_server`] serves CPU and heap profiles over HTTP.
+//!   Both runtimes start it alongside their other background tasks.
+//!
+//! Without the feature none of this is compiled in, and setting
+//! `profiling_bind` is a startup error rather than a silent no-op.
+
+#[cfg(feature = "profiling")]
+mod http;
+
+use std::net::SocketAddr;
+
+use anyhow::Result;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+
+use super::AppConfig;
+
+/// Errors raised while validating the profiling options.
+#[derive(Debug, Error)]
+pub enum ProfilingConfigError {
+    /// `profiling_bind` is not a socket address.
+    #[error("invalid profiling_bind '{0}': expected an address such as 127.0.0.1:6060")]
+    InvalidBind(String),
+    /// `profiling_bind` is set but the build cannot serve profiles.
+    #[error("profiling_bind is set but this build lacks the profiling feature")]
+    Unsupported,
+}
+
+/// Read the profiling server address from `config`, or `None` when it is
+/// unset.
+///
+/// # Errors
+///
+/// Returns an error if `profiling_bind` is not a socket address or the build
+/// lacks the `profiling` feature.
+pub fn profiling_bind_from_config(
+    config: &AppConfig,
+) -> Result<Option<SocketAddr>, ProfilingConfigError> {
+    let Some(bind) = config.profiling_bind.as_deref() else {
+        return Ok(None);
+    };
+    if !cfg!(feature = "profiling") {
+        return Err(ProfilingConfigError::Unsupported);
+    }
+    bind.trim()
+        .parse()
+        .map(Some)
+        .map_err(|_| ProfilingConfigError::InvalidBind(bind.to_owned()))
+}
+
+/// Start serving profiles on `bind` until the returned task is aborted, or
+/// return `None` when no profiling server is configured.
+///
+/// # Errors
+///
+/// Returns an error if the profiling address cannot be bound.
+pub fn start_profiling_server(bind: Option<SocketAddr>) -> Result<Option<JoinHandle<()>>> {
+    #[cfg(feature = "profiling")]
+    if let Some(addr) = bind {
+        return http::start(addr).map(Some);
+    }
+    #[cfg(not(feature = "profiling"))]
+    let _ = bind;
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Validating the profiling options.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn profiling_is_off_by_default() {
+        let bind = profiling_bind_from_config(&AppConfig::default()).expect("no profiling");
+        assert!(bind.is_none());
+    }
+
+    #[cfg(feature = "profiling")]
+    #[rstest]
+    #[case("127.0.0.1:6060", Some("127.0.0.1:6060"))]
+    #[case("[::1]:6060", Some("[::1]:6060"))]
+    #[case("localhost", None)]
+    fn parses_the_profiling_address(#[case] bind: &str, #[case] expected: Option<&str>) {
+        let config = AppConfig {
+            profiling_bind: Some(bind.to_owned()),
+            ..AppConfig::default()
+        };
+        let parsed = profiling_bind_from_config(&config).ok().flatten();
+        assert_eq!(parsed.map(|addr| addr.to_string()).as_deref(), expected);
+    }
+
+    #[cfg(not(feature = "profiling"))]
+    #[rstest]
+    fn profiling_bind_needs_the_feature() {
+        let config = AppConfig {
+            profiling_bind: Some("127.0.0.1:6060".to_owned()),
+            ..AppConfig::default()
+        };
+        assert!(matches!(
+            profiling_bind_from_config(&config),
+            Err(ProfilingConfigError::Unsupported)
+        ));
+    }
+}
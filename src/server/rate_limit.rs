@@ -0,0 +1,313 @@
+//! Request rate limiting per connection and per address.
+//!
+//! Each connection draws one token per request from its own bucket, and
+//! from a bucket shared by every connection from the same address, so
+//! neither one flooding client nor many connections from one host can queue
+//! unbounded database work. A request that finds either bucket empty is
+//! answered with [`ERR_RATE_LIMITED`](crate::commands::ERR_RATE_LIMITED)
+//! without being processed. Buckets refill at `rate_limit_per_sec` and
+//! `address_rate_limit_per_sec` tokens a second and hold at most their
+//! burst; both limits are off unless configured.
+//!
+//! Each server holds one [`RateLimiter`] built from its [`RateLimitPolicy`],
+//! which keeps the address buckets, and hands it to every connection it
+//! accepts; the connection checks requests against its own
+//! [`ConnectionRateLimiter`].
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    num::NonZeroU32,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+use super::AppConfig;
+use crate::{
+    commands::ERR_RATE_LIMITED,
+    header_util::reply_header,
+    transaction::{FrameHeader, Transaction},
+};
+
+/// Sustained rate and burst for one kind of bucket.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Tokens added each second.
+    pub per_sec: NonZeroU32,
+    /// Most tokens the bucket holds.
+    pub burst: NonZeroU32,
+}
+
+impl RateLimit {
+    fn interval(self) -> Duration { Duration::from_secs(1) / self.per_sec.get() }
+
+    fn tolerance(self) -> Duration { self.interval() * (self.burst.get() - 1) }
+}
+
+/// Limits applied to every connection and to every address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    /// Bucket for each connection, if limited.
+    pub connection: Option<RateLimit>,
+    /// Bucket shared by the connections from one address, if limited.
+    pub address: Option<RateLimit>,
+}
+
+/// Errors raised while reading the rate limits from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RateLimitError {
+    /// `rate_limit_burst` was zero or set without `rate_limit_per_sec`.
+    #[error("rate_limit_burst must be positive and needs rate_limit_per_sec")]
+    ConnectionBurst,
+    /// `address_rate_limit_burst` was zero or set without
+    /// `address_rate_limit_per_sec`.
+    #[error("address_rate_limit_burst must be positive and needs address_rate_limit_per_sec")]
+    AddressBurst,
+}
+
+impl RateLimitPolicy {
+    /// No limits.
+    pub const OFF: Self = Self {
+        connection: None,
+        address: None,
+    };
+
+    /// Read the limits from `config`; a rate left unset or zero turns that
+    /// limit off, and a burst left unset defaults to the rate.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RateLimitError`] for a zero burst or a burst without a
+    /// rate.
+    pub fn from_config(config: &AppConfig) -> Result<Self, RateLimitError> {
+        Ok(Self {
+            connection: rate_limit(config.rate_limit_per_sec, config.rate_limit_burst)
+                .ok_or(RateLimitError::ConnectionBurst)?,
+            address: rate_limit(
+                config.address_rate_limit_per_sec,
+                config.address_rate_limit_burst,
+            )
+            .ok_or(RateLimitError::AddressBurst)?,
+        })
+    }
+}
+
+/// Return `Some(None)` for no limit, or `None` for an invalid burst.
+fn rate_limit(per_sec: Option<u32>, burst: Option<u32>) -> Option<Option<RateLimit>> {
+    let Some(rate) = per_sec.and_then(NonZeroU32::new) else {
+        return burst.is_none().then_some(None);
+    };
+    let capacity = match burst {
+        None => rate,
+        Some(raw) => NonZeroU32::new(raw)?,
+    };
+    Some(Some(RateLimit {
+        per_sec: rate,
+        burst: capacity,
+    }))
+}
+
+/// Token bucket kept as the time it next drains empty.
+///
+/// Each request pushes that time one refill interval later; a request that
+/// would push it more than a burst's worth past now finds the bucket empty.
+#[derive(Clone, Copy, Debug)]
+struct TokenBucket {
+    empty_at: Instant,
+}
+
+impl TokenBucket {
+    const fn new(now: Instant) -> Self { Self { empty_at: now } }
+
+    fn take(&mut self, limit: RateLimit, now: Instant) -> bool {
+        let start = self.empty_at.max(now);
+        if start.saturating_duration_since(now) > limit.tolerance() {
+            return false;
+        }
+        self.empty_at = start + limit.interval();
+        true
+    }
+
+    fn is_full(&self, now: Instant) -> bool { self.empty_at <= now }
+}
+
+/// Rate limits shared by every connection one server accepts.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    policy: RateLimitPolicy,
+    addresses: Mutex<HashMap<IpAddr, TokenBucket>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter applying `policy`.
+    #[must_use]
+    pub fn new(policy: RateLimitPolicy) -> Self {
+        Self {
+            policy,
+            addresses: Mutex::default(),
+        }
+    }
+
+    fn admit_address(&self, address: IpAddr, limit: RateLimit, now: Instant) -> bool {
+        let mut buckets = self
+            .addresses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !buckets.contains_key(&address) {
+            // Full buckets carry no state, so they are forgotten while adding
+            // another rather than on a timer.
+            buckets.retain(|_, bucket| !bucket.is_full(now));
+        }
+        buckets
+            .entry(address)
+            .or_insert(TokenBucket::new(now))
+            .take(limit, now)
+    }
+}
+
+/// Rate limiter for one connection's requests.
+#[derive(Debug)]
+pub struct ConnectionRateLimiter {
+    limits: Arc<RateLimiter>,
+    address: IpAddr,
+    bucket: Mutex<Option<TokenBucket>>,
+}
+
+impl ConnectionRateLimiter {
+    /// Create a limiter for a connection from `address` to the server
+    /// applying `limits`.
+    #[must_use]
+    pub const fn new(limits: Arc<RateLimiter>, address: IpAddr) -> Self {
+        Self {
+            limits,
+            address,
+            bucket: Mutex::new(None),
+        }
+    }
+
+    /// Take a token for `request`, returning `false` when the connection or
+    /// its address has run out.
+    ///
+    /// Replies, such as answers to server pings, are always admitted. The
+    /// address bucket is only charged once the connection's own bucket
+    /// admits the request, so one flooding connection cannot starve others
+    /// from the same host of more than its own allowance.
+    pub fn admit(&self, request: &FrameHeader, now: Instant) -> bool {
+        if request.is_reply != 0 {
+            return true;
+        }
+        let policy = self.limits.policy;
+        if let Some(limit) = policy.connection {
+            let mut bucket = self.bucket.lock().unwrap_or_else(PoisonError::into_inner);
+            if !bucket.get_or_insert(TokenBucket::new(now)).take(limit, now) {
+                return false;
+            }
+        }
+        policy
+            .address
+            .is_none_or(|limit| self.limits.admit_address(self.address, limit, now))
+    }
+}
+
+/// Build the reply refusing `request` because its sender is over its rate.
+#[must_use]
+pub fn throttled_reply(request: &FrameHeader) -> Transaction {
+    Transaction {
+        header: reply_header(request, ERR_RATE_LIMITED, 0),
+        payload: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for rate limit parsing and token buckets.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn limit(per_sec: u32, burst: u32) -> RateLimit {
+        RateLimit {
+            per_sec: NonZeroU32::new(per_sec).expect("non-zero rate"),
+            burst: NonZeroU32::new(burst).expect("non-zero burst"),
+        }
+    }
+
+    #[rstest]
+    #[case::unset(None, None, Some(None))]
+    #[case::zero_rate(Some(0), None, Some(None))]
+    #[case::default_burst(Some(10), None, Some(Some(limit(10, 10))))]
+    #[case::custom_burst(Some(10), Some(40), Some(Some(limit(10, 40))))]
+    #[case::zero_burst(Some(10), Some(0), None)]
+    #[case::burst_without_rate(None, Some(5), None)]
+    fn parses_limits(
+        #[case] per_sec: Option<u32>,
+        #[case] burst: Option<u32>,
+        #[case] expected: Option<Option<RateLimit>>,
+    ) {
+        assert_eq!(rate_limit(per_sec, burst), expected);
+    }
+
+    #[rstest]
+    fn rejects_an_address_burst_without_a_rate() {
+        let config = AppConfig {
+            address_rate_limit_burst: Some(5),
+            ..AppConfig::default()
+        };
+        assert_eq!(
+            RateLimitPolicy::from_config(&config),
+            Err(RateLimitError::AddressBurst)
+        );
+    }
+
+    #[rstest]
+    #[case(1, 1)]
+    #[case(5, 5)]
+    #[case(2, 20)]
+    fn buckets_admit_a_burst_then_refill(#[case] per_sec: u32, #[case] burst: u32) {
+        let limit = limit(per_sec, burst);
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(now);
+
+        for _ in 0..burst {
+            assert!(bucket.take(limit, now));
+        }
+        assert!(!bucket.take(limit, now));
+
+        let refilled = now + limit.interval();
+        assert!(bucket.take(limit, refilled));
+        assert!(!bucket.take(limit, refilled));
+        assert!(bucket.is_full(now + limit.interval() * (burst + 1)));
+    }
+
+    #[rstest]
+    fn connections_from_one_address_share_its_bucket() {
+        let limits = Arc::new(RateLimiter::new(RateLimitPolicy {
+            connection: Some(limit(100, 3)),
+            address: Some(limit(1, 4)),
+        }));
+        let address = IpAddr::from([192, 0, 2, 40]);
+        let first = ConnectionRateLimiter::new(Arc::clone(&limits), address);
+        let second = ConnectionRateLimiter::new(limits, address);
+        let now = Instant::now();
+        let request = FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: 500,
+            id: 1,
+            error: 0,
+            total_size: 0,
+            data_size: 0,
+        };
+
+        let admitted = (0..4)
+            .map(|_| first.admit(&request, now))
+            .chain((0..4).map(|_| second.admit(&request, now)))
+            .filter(|admitted| *admitted)
+            .count();
+
+        assert_eq!(admitted, 4);
+    }
+}
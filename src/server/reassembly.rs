@@ -0,0 +1,65 @@
+//! Age limit for fragmented requests.
+//!
+//! A client that sends the first fragment of a transaction and then stalls
+//! leaves a partial payload buffered. Operators bound how long a fragmented
+//! request may take to arrive in full with `reassembly_timeout_secs`; the
+//! legacy runtime gives each connection's transaction reader that limit, and
+//! connections that exceed it are closed with
+//! [`crate::transaction::TransactionError::ReassemblyTimeout`]. Counts and
+//! ages of partial transactions are logged with the runtime statistics (see
+//! [`super::metrics::log_runtime_metrics`]).
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+use super::AppConfig;
+use crate::transaction::DEFAULT_REASSEMBLY_TIMEOUT;
+
+/// Errors raised while reading the reassembly limit from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ReassemblyTimeoutError {
+    /// `reassembly_timeout_secs` was zero.
+    #[error("reassembly_timeout_secs must be greater than zero")]
+    Zero,
+}
+
+/// Read the reassembly limit from `config`, defaulting to
+/// [`DEFAULT_REASSEMBLY_TIMEOUT`].
+///
+/// # Errors
+///
+/// Returns [`ReassemblyTimeoutError::Zero`] for a zero limit.
+pub fn reassembly_timeout_from_config(
+    config: &AppConfig,
+) -> Result<Duration, ReassemblyTimeoutError> {
+    match config.reassembly_timeout_secs {
+        None => Ok(DEFAULT_REASSEMBLY_TIMEOUT),
+        Some(0) => Err(ReassemblyTimeoutError::Zero),
+        Some(secs) => Ok(Duration::from_secs(secs)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the reassembly limit from configuration.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, Ok(DEFAULT_REASSEMBLY_TIMEOUT))]
+    #[case(Some(0), Err(ReassemblyTimeoutError::Zero))]
+    #[case(Some(45), Ok(Duration::from_secs(45)))]
+    fn reads_reassembly_timeout(
+        #[case] secs: Option<u64>,
+        #[case] expected: Result<Duration, ReassemblyTimeoutError>,
+    ) {
+        let config = AppConfig {
+            reassembly_timeout_secs: secs,
+            ..AppConfig::default()
+        };
+        assert_eq!(reassembly_timeout_from_config(&config), expected);
+    }
+}
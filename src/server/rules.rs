@@ -0,0 +1,226 @@
+//! Server rules and help sections served on request.
+//!
+//! Operators write the rules, a contact line, and the file policy in the
+//! configuration as `server_rules`, `server_contact`, and `server_file_policy`.
+//! [`ServerRules::from_config`] reads them once at startup into the server's
+//! [`ServerSettings`](super::settings::ServerSettings). Get Server Rules (3002)
+//! answers with one [`FieldId::HelpSection`] per configured section, so a
+//! client can lay them out in a help window, together with the same sections
+//! rendered as plain text in [`FieldId::Data`] for clients that only know how
+//! to show a block of text.
+
+use thiserror::Error;
+
+use crate::{
+    field_id::FieldId,
+    header_util::reply_header,
+    server::AppConfig,
+    transaction::{FrameHeader, Transaction, TransactionError, encode_params},
+};
+
+/// What a help section describes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HelpSectionKind {
+    /// Conduct expected of users.
+    Rules,
+    /// How to reach the operators.
+    Contact,
+    /// What may be uploaded and shared.
+    FilePolicy,
+}
+
+impl HelpSectionKind {
+    /// Code sent ahead of the section text in [`FieldId::HelpSection`].
+    #[must_use]
+    pub const fn code(self) -> u16 {
+        match self {
+            Self::Rules => 1,
+            Self::Contact => 2,
+            Self::FilePolicy => 3,
+        }
+    }
+
+    /// Heading used for the section in the plain-text rendering.
+    #[must_use]
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::Rules => "Rules",
+            Self::Contact => "Contact",
+            Self::FilePolicy => "File policy",
+        }
+    }
+}
+
+/// Errors raised while reading the server rules from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ServerRulesError {
+    /// The sections do not fit in a single transaction field.
+    #[error(
+        "server_rules, server_contact, and server_file_policy exceed {} bytes together",
+        u16::MAX
+    )]
+    TooLarge,
+}
+
+/// Help sections served to clients, in the order they are shown.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerRules {
+    sections: Vec<(HelpSectionKind, String)>,
+}
+
+impl ServerRules {
+    /// Build the rules from in-memory sections.
+    ///
+    /// Line endings become the carriage returns Hotline clients expect, and
+    /// sections left empty are dropped.
+    #[must_use]
+    pub fn new(sections: impl IntoIterator<Item = (HelpSectionKind, String)>) -> Self {
+        Self {
+            sections: sections
+                .into_iter()
+                .map(|(kind, text)| (kind, normalise_lines(&text)))
+                .filter(|(_, text)| !text.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Read the sections from `server_rules`, `server_contact`, and
+    /// `server_file_policy`; unset options are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ServerRulesError::TooLarge`] when the plain-text rendering
+    /// would not fit in one field.
+    pub fn from_config(config: &AppConfig) -> Result<Self, ServerRulesError> {
+        let rules = Self::new(
+            [
+                (HelpSectionKind::Rules, &config.server_rules),
+                (HelpSectionKind::Contact, &config.server_contact),
+                (HelpSectionKind::FilePolicy, &config.server_file_policy),
+            ]
+            .into_iter()
+            .filter_map(|(kind, text)| text.clone().map(|text| (kind, text))),
+        );
+        // Every section is shorter than the rendering, so this bounds both.
+        if rules.plain_text().len() > usize::from(u16::MAX) {
+            return Err(ServerRulesError::TooLarge);
+        }
+        Ok(rules)
+    }
+
+    /// Configured sections in the order they are shown.
+    #[must_use]
+    pub fn sections(&self) -> &[(HelpSectionKind, String)] { &self.sections }
+
+    /// Render every section under its heading as one block of text.
+    #[must_use]
+    pub fn plain_text(&self) -> String {
+        self.sections
+            .iter()
+            .map(|(kind, text)| format!("{}\r{text}", kind.title()))
+            .collect::<Vec<_>>()
+            .join("\r\r")
+    }
+
+    /// Build the Get Server Rules reply to `header`.
+    ///
+    /// A server without rules answers with no fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an encoding error if a section exceeds protocol limits.
+    #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+    pub fn reply(&self, header: &FrameHeader) -> Result<Transaction, TransactionError> {
+        let mut params: Vec<(FieldId, Vec<u8>)> = self
+            .sections
+            .iter()
+            .map(|(kind, text)| {
+                let mut value = kind.code().to_be_bytes().to_vec();
+                value.extend_from_slice(text.as_bytes());
+                (FieldId::HelpSection, value)
+            })
+            .collect();
+        if !params.is_empty() {
+            params.push((FieldId::Data, self.plain_text().into_bytes()));
+        }
+        let payload = encode_params(&params)?;
+        Ok(Transaction {
+            header: reply_header(header, 0, payload.len()),
+            payload,
+        })
+    }
+}
+
+fn normalise_lines(text: &str) -> String { text.trim().replace("\r\n", "\r").replace('\n', "\r") }
+
+#[cfg(test)]
+mod tests {
+    //! Reading server rules from configuration and encoding the reply.
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::{transaction::decode_params, transaction_type::TransactionType};
+
+    fn request() -> FrameHeader {
+        FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: TransactionType::GetServerRules.into(),
+            id: 7,
+            error: 0,
+            total_size: 0,
+            data_size: 0,
+        }
+    }
+
+    #[rstest]
+    fn unset_options_serve_no_sections() {
+        let rules = ServerRules::from_config(&AppConfig::default()).expect("load");
+        assert!(rules.sections().is_empty());
+        let reply = rules.reply(&request()).expect("encode");
+        assert_eq!(reply.header.error, 0);
+        assert!(decode_params(&reply.payload).expect("decode").is_empty());
+    }
+
+    #[rstest]
+    fn sections_are_encoded_with_a_plain_text_fallback() {
+        let config = AppConfig {
+            server_rules: Some("Be kind.\nNo spam.\n".to_owned()),
+            server_file_policy: Some("Uploads go in /Incoming.".to_owned()),
+            server_contact: Some("  ".to_owned()),
+            ..AppConfig::default()
+        };
+        let rules = ServerRules::from_config(&config).expect("load");
+        let reply = rules.reply(&request()).expect("encode");
+        let params = decode_params(&reply.payload).expect("decode");
+
+        assert_eq!(reply.header.id, 7);
+        assert_eq!(
+            params,
+            vec![
+                (FieldId::HelpSection, b"\x00\x01Be kind.\rNo spam.".to_vec()),
+                (
+                    FieldId::HelpSection,
+                    b"\x00\x03Uploads go in /Incoming.".to_vec()
+                ),
+                (
+                    FieldId::Data,
+                    b"Rules\rBe kind.\rNo spam.\r\rFile policy\rUploads go in /Incoming.".to_vec()
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn rejects_sections_too_large_for_one_field() {
+        let config = AppConfig {
+            server_rules: Some("x".repeat(usize::from(u16::MAX))),
+            ..AppConfig::default()
+        };
+        assert_eq!(
+            ServerRules::from_config(&config),
+            Err(ServerRulesError::TooLarge)
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Tokio runtime construction for the server binaries.
+//!
+//! The runtime has to exist before any configuration-dependent async code
+//! runs, so binaries load [`AppConfig`] synchronously, build the runtime here,
+//! and only then enter the server. Unset options keep Tokio's defaults, which
+//! suit most hosts; small VPS deployments typically lower the worker and
+//! blocking thread counts, while busy hosts may raise them.
+
+use thiserror::Error;
+use tokio::runtime::{Builder, Runtime};
+
+use super::AppConfig;
+
+/// Errors raised while building the Tokio runtime.
+#[derive(Debug, Error)]
+pub enum RuntimeTuningError {
+    /// A tuning option was set to zero, which Tokio does not accept.
+    #[error("{0} must be greater than zero")]
+    Zero(&'static str),
+    /// Tokio failed to start the runtime.
+    #[error("failed to build Tokio runtime: {0}")]
+    Build(#[from] std::io::Error),
+}
+
+/// Validated runtime tuning options taken from [`AppConfig`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RuntimeTuning {
+    /// Number of worker threads, or `None` for one per CPU core.
+    pub worker_threads: Option<usize>,
+    /// Maximum number of blocking threads, or `None` for Tokio's default.
+    pub max_blocking_threads: Option<usize>,
+    /// Scheduler ticks between event polls, or `None` for Tokio's default.
+    pub event_interval: Option<u32>,
+}
+
+impl RuntimeTuning {
+    /// Extract and validate tuning options from `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RuntimeTuningError::Zero`] when any option is set to zero.
+    pub fn from_config(config: &AppConfig) -> Result<Self, RuntimeTuningError> {
+        Ok(Self {
+            worker_threads: non_zero(config.worker_threads, "worker_threads")?,
+            max_blocking_threads: non_zero(config.max_blocking_threads, "max_blocking_threads")?,
+            event_interval: non_zero(config.event_interval, "event_interval")?,
+        })
+    }
+
+    /// Build a multi-threaded runtime with these options applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RuntimeTuningError::Build`] if Tokio cannot start the
+    /// runtime.
+    pub fn build(self) -> Result<Runtime, RuntimeTuningError> {
+        let mut builder = Builder::new_multi_thread();
+        builder.enable_all();
+        if let Some(threads) = self.worker_threads {
+            builder.worker_threads(threads);
+        }
+        if let Some(threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(threads);
+        }
+        if let Some(ticks) = self.event_interval {
+            builder.event_interval(ticks);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Build the server runtime using the tuning options in `config`.
+///
+/// # Errors
+///
+/// Returns an error if an option is invalid or Tokio cannot start.
+pub fn build_runtime(config: &AppConfig) -> Result<Runtime, RuntimeTuningError> {
+    RuntimeTuning::from_config(config)?.build()
+}
+
+fn non_zero<T>(value: Option<T>, name: &'static str) -> Result<Option<T>, RuntimeTuningError>
+where
+    T: Default + PartialEq,
+{
+    match value {
+        Some(inner) if inner == T::default() => Err(RuntimeTuningError::Zero(name)),
+        other => Ok(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for runtime tuning validation and application.
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn unset_options_keep_tokio_defaults() {
+        let tuning = RuntimeTuning::from_config(&AppConfig::default()).expect("valid tuning");
+
+        assert_eq!(tuning, RuntimeTuning::default());
+    }
+
+    #[rstest]
+    #[case::workers(AppConfig { worker_threads: Some(0), ..AppConfig::default() }, "worker_threads")]
+    #[case::blocking(
+        AppConfig { max_blocking_threads: Some(0), ..AppConfig::default() },
+        "max_blocking_threads"
+    )]
+    #[case::interval(AppConfig { event_interval: Some(0), ..AppConfig::default() }, "event_interval")]
+    fn rejects_zero_values(#[case] config: AppConfig, #[case] expected: &str) {
+        let error = RuntimeTuning::from_config(&config).expect_err("zero should be rejected");
+
+        assert!(matches!(error, RuntimeTuningError::Zero(name) if name == expected));
+    }
+
+    #[rstest]
+    fn applies_worker_thread_count() {
+        let config = AppConfig {
+            worker_threads: Some(2),
+            max_blocking_threads: Some(4),
+            event_interval: Some(31),
+            ..AppConfig::default()
+        };
+
+        let runtime = build_runtime(&config).expect("runtime builds");
+
+        assert_eq!(runtime.metrics().num_workers(), 2);
+    }
+}
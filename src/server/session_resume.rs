@@ -0,0 +1,66 @@
+//! Session resumption window.
+//!
+//! With `session_resume_secs` set, a successful login that carries a
+//! [`FieldId::SessionToken`](crate::field_id::FieldId::SessionToken), empty
+//! when the client has none yet, is answered with a token that stays valid
+//! for that long. A client whose connection drops may log in again with the
+//! token in place of its password and gets the same account and privileges
+//! back. Each token works once; the login it admits hands out a fresh one.
+//! Logging out, a password change, a kick, or a ban revokes the account's
+//! tokens. Unset, no tokens are issued and tokens presented at login are ignored.
+//! The window is kept in the server's
+//! [`ServerSettings`](super::settings::ServerSettings).
+
+use std::time::Duration;
+
+use thiserror::Error;
+
+use super::AppConfig;
+
+/// Errors raised while reading the resumption window from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SessionResumeError {
+    /// `session_resume_secs` was zero.
+    #[error("session_resume_secs must be greater than zero")]
+    Zero,
+}
+
+/// Read how long session tokens stay valid from `config`; `None` issues no
+/// tokens.
+///
+/// # Errors
+///
+/// Returns [`SessionResumeError::Zero`] for a zero window.
+pub fn resume_window_from_config(
+    config: &AppConfig,
+) -> Result<Option<Duration>, SessionResumeError> {
+    match config.session_resume_secs {
+        None => Ok(None),
+        Some(0) => Err(SessionResumeError::Zero),
+        Some(secs) => Ok(Some(Duration::from_secs(secs))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the resumption window.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, Ok(None))]
+    #[case(Some(0), Err(SessionResumeError::Zero))]
+    #[case(Some(120), Ok(Some(Duration::from_secs(120))))]
+    fn reads_resume_window(
+        #[case] secs: Option<u64>,
+        #[case] expected: Result<Option<Duration>, SessionResumeError>,
+    ) {
+        let config = AppConfig {
+            session_resume_secs: secs,
+            ..AppConfig::default()
+        };
+        assert_eq!(resume_window_from_config(&config), expected);
+    }
+}
@@ -0,0 +1,202 @@
+//! Settings each server is handed rather than reading from process-wide
+//! state.
+//!
+//! [`configure_process`](super::configure_process) reads them from
+//! configuration and returns them in a [`ServerSettings`], which the runtime
+//! passes to every connection it accepts and each connection hands to the
+//! commands it runs. Two servers in one process, such as those a test suite
+//! starts, therefore never see each other's settings.
+
+use std::{fmt, net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use tokio_rustls::TlsAcceptor;
+
+use super::{
+    AppConfig,
+    accounting::{AccountingQueue, AccountingSink},
+    agreement::ServerAgreement,
+    archives::ArchiveSchedule,
+    bans::AddressBans,
+    connection_limit::ConnectionLimiter,
+    download_policy::DownloadRules,
+    health::health_bind_from_config,
+    idle::idle_timeout_from_config,
+    io_timeouts::IoTimeouts,
+    login_throttle::LoginThrottle,
+    maintenance::MaintenanceSchedule,
+    news_digest::DigestSchedule,
+    param_limit::param_limit_from_config,
+    payload_limits::payload_limits_from_config,
+    ping::PingPolicy,
+    profiling::profiling_bind_from_config,
+    rate_limit::RateLimiter,
+    reassembly::reassembly_timeout_from_config,
+    rules::ServerRules,
+    subsystems::Subsystems,
+    tls::tls_acceptor_from_config,
+    transfer_port::TransferRegistry,
+};
+use crate::{
+    commands::UnknownTransactionPolicy,
+    hashing::HashingPool,
+    storage::Storage,
+    transaction::{DEFAULT_REASSEMBLY_TIMEOUT, PayloadLimits},
+    wireframe::compat::XorPolicy,
+};
+
+/// Settings and shared limits for one server.
+#[derive(Debug, Default)]
+pub struct ServerSettings {
+    /// Connection limits, and the connections counted against them.
+    pub connections: Arc<ConnectionLimiter>,
+    /// Address bans refused at accept, reloaded from the database.
+    pub address_bans: Arc<AddressBans>,
+    /// How each accepted connection is carried and kept alive.
+    pub transport: TransportSettings,
+    /// Request rate limits shared by the server's connections.
+    pub rate_limits: Arc<RateLimiter>,
+    /// Subsystems the server serves.
+    pub subsystems: Subsystems,
+    /// How transaction types mxd does not implement are answered.
+    pub unknown_transactions: UnknownTransactionPolicy,
+    /// Whether a user's first request after setting an away message clears
+    /// it.
+    pub clear_away_on_activity: bool,
+    /// Whether queries carry a `/* trace_id=... */` comment naming the
+    /// transaction that issued them.
+    pub sql_trace_comments: bool,
+    /// Agreement and banner offered to clients after login.
+    pub agreement: ServerAgreement,
+    /// Rules and help sections served by Get Server Rules (3002).
+    pub rules: ServerRules,
+    /// Failed logins counted against addresses and accounts.
+    pub login_throttle: LoginThrottle,
+    /// Pool verifying and hashing passwords off the async workers.
+    pub hashing: HashingPool,
+    /// How long session tokens stay valid, or `None` when sessions cannot be
+    /// resumed.
+    pub resume_window: Option<Duration>,
+    /// Backend holding file content, or `None` when file content is not
+    /// managed.
+    pub storage: Option<Arc<dyn Storage>>,
+    /// Ratio and credit limits on downloads.
+    pub download_rules: DownloadRules,
+    /// Transfers waiting on the transfer port, and the queue admitting
+    /// file transfers.
+    pub transfers: Arc<TransferRegistry>,
+    /// Longest news article body sent inline to clients that accept
+    /// transfers, or `None` to send every body inline.
+    pub news_transfer_threshold: Option<u32>,
+    /// Schedules and addresses of the server's background tasks.
+    pub background: BackgroundSettings,
+    /// Session records waiting for the accounting sink.
+    pub accounting: Arc<AccountingQueue>,
+}
+
+/// What a server's background tasks do and where they listen.
+#[derive(Clone, Debug, Default)]
+pub struct BackgroundSettings {
+    /// Folders snapshotted into archives, or `None` to take no snapshots.
+    pub archives: Option<ArchiveSchedule>,
+    /// When database maintenance runs, or `None` to run none.
+    pub maintenance: Option<MaintenanceSchedule>,
+    /// How often news digests are built and how they are emailed.
+    pub digests: DigestSchedule,
+    /// Where session accounting records go, or `None` to keep none.
+    pub accounting_sink: Option<AccountingSink>,
+    /// Address serving CPU and heap profiles, if any.
+    pub profiling_bind: Option<SocketAddr>,
+    /// Address serving the health probes, if any.
+    pub health_bind: Option<SocketAddr>,
+}
+
+impl BackgroundSettings {
+    /// Read the background task settings from `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a schedule, the accounting sink, or a probe or
+    /// profiling address is invalid.
+    pub fn from_config(config: &AppConfig) -> Result<Self> {
+        Ok(Self {
+            archives: ArchiveSchedule::from_config(config)?,
+            maintenance: MaintenanceSchedule::from_config(config)?,
+            digests: DigestSchedule::from_config(config)?,
+            accounting_sink: AccountingSink::from_config(config)?,
+            profiling_bind: profiling_bind_from_config(config)?,
+            health_bind: health_bind_from_config(config)?,
+        })
+    }
+}
+
+/// How a server carries its connections and decides they have gone.
+#[derive(Clone)]
+pub struct TransportSettings {
+    /// Acceptor wrapping client and transfer connections in TLS, or `None`
+    /// to serve plain TCP.
+    pub tls: Option<TlsAcceptor>,
+    /// Read and write timeouts for client connections.
+    pub io_timeouts: IoTimeouts,
+    /// Longest a fragmented request may take to arrive in full.
+    pub reassembly_timeout: Duration,
+    /// How long a connection may send nothing before it is closed, or
+    /// `None` to keep quiet connections open.
+    pub idle_timeout: Option<Duration>,
+    /// How often connections are pinged, or `None` to send no pings.
+    pub ping: Option<PingPolicy>,
+    /// Whether XOR-obfuscated clients are accepted.
+    pub xor: XorPolicy,
+    /// Largest payload and most parameters accepted in one request.
+    pub payload_limits: PayloadLimits,
+}
+
+impl TransportSettings {
+    /// Read the transport settings from `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a timeout, the ping policy, a payload limit, or the
+    /// parameter cap is invalid, the XOR policy is unknown, or the TLS
+    /// certificate cannot be loaded.
+    pub fn from_config(config: &AppConfig) -> Result<Self> {
+        Ok(Self {
+            tls: tls_acceptor_from_config(config)?,
+            io_timeouts: IoTimeouts::from_config(config)?,
+            reassembly_timeout: reassembly_timeout_from_config(config)?,
+            idle_timeout: idle_timeout_from_config(config)?,
+            ping: PingPolicy::from_config(config)?,
+            xor: XorPolicy::from_config(config)?,
+            payload_limits: payload_limits_from_config(config)?
+                .with_max_params(param_limit_from_config(config)?),
+        })
+    }
+}
+
+impl Default for TransportSettings {
+    fn default() -> Self {
+        Self {
+            tls: None,
+            io_timeouts: IoTimeouts::DEFAULT,
+            reassembly_timeout: DEFAULT_REASSEMBLY_TIMEOUT,
+            idle_timeout: None,
+            ping: None,
+            xor: XorPolicy::default(),
+            payload_limits: PayloadLimits::new(),
+        }
+    }
+}
+
+impl fmt::Debug for TransportSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransportSettings")
+            .field("tls", &self.tls.is_some())
+            .field("io_timeouts", &self.io_timeouts)
+            .field("reassembly_timeout", &self.reassembly_timeout)
+            .field("idle_timeout", &self.idle_timeout)
+            .field("ping", &self.ping)
+            .field("xor", &self.xor)
+            .field("payload_limits", &self.payload_limits)
+            .finish()
+    }
+}
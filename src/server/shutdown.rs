@@ -0,0 +1,79 @@
+//! Shutdown signals and the grace period shared by both runtimes.
+//!
+//! Both runtimes stop on Ctrl+C, or on `SIGTERM` on Unix, through
+//! [`shutdown_signal`]. The Wireframe runtime then gives file transfers
+//! already running on the transfer port up to `shutdown_grace_secs` to finish
+//! (see [`super::transfer_port::TransferRegistry::drain`]) before it exits.
+
+use std::time::Duration;
+
+use tracing::warn;
+
+use super::AppConfig;
+
+/// Grace period for in-flight transfers when none is configured.
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// Read the grace period from `config`; zero exits without waiting.
+#[must_use]
+pub fn shutdown_grace(config: &AppConfig) -> Duration {
+    config
+        .shutdown_grace_secs
+        .map_or(DEFAULT_SHUTDOWN_GRACE, Duration::from_secs)
+}
+
+/// Wait for a shutdown signal, completing when termination is requested.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    wait_for_terminate_or_ctrl_c().await;
+    #[cfg(not(unix))]
+    wait_for_ctrl_c().await;
+}
+
+#[cfg(unix)]
+#[expect(
+    clippy::integer_division_remainder_used,
+    reason = "tokio::select! macro usage"
+)]
+async fn wait_for_terminate_or_ctrl_c() {
+    use tokio::signal::unix::{SignalKind, signal};
+    match signal(SignalKind::terminate()) {
+        Ok(mut term) => {
+            tokio::select! {
+                () = wait_for_ctrl_c() => {},
+                _ = term.recv() => {},
+            }
+        }
+        Err(error) => {
+            warn!(%error, "failed to install SIGTERM handler");
+            wait_for_ctrl_c().await;
+        }
+    }
+}
+
+async fn wait_for_ctrl_c() {
+    if let Err(error) = tokio::signal::ctrl_c().await {
+        warn!(%error, "failed to listen for Ctrl-C");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the shutdown grace period from configuration.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, DEFAULT_SHUTDOWN_GRACE)]
+    #[case(Some(0), Duration::ZERO)]
+    #[case(Some(30), Duration::from_secs(30))]
+    fn reads_shutdown_grace(#[case] secs: Option<u64>, #[case] expected: Duration) {
+        let config = AppConfig {
+            shutdown_grace_secs: secs,
+            ..AppConfig::default()
+        };
+        assert_eq!(shutdown_grace(&config), expected);
+    }
+}
@@ -0,0 +1,121 @@
+//! Per-account storage quotas.
+//!
+//! An account's `quota_bytes` caps the file content it may store, counted by
+//! [`crate::db::stored_bytes_for_user`]; accounts without a quota are not
+//! limited. The upload handler asks [`check_upload_quota`] before accepting a
+//! file and answers a refusal with
+//! [`crate::commands::FILE_ERR_QUOTA_EXCEEDED`], using the refusal's message
+//! as the client's error text. The transfer port asks again, with the data
+//! fork's length, before it stores the file's content, so uploads filed side
+//! by side cannot overrun it.
+
+use diesel::result::QueryResult;
+use thiserror::Error;
+
+use crate::db::{DbConnection, get_user_by_id, stored_bytes_for_user};
+
+/// Why an upload was refused; the message is shown to the user.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error(
+    "Upload refused: your quota is {quota} bytes and you store {stored}, so this {size}-byte file \
+     does not fit. Delete files to upload more."
+)]
+pub struct QuotaExceeded {
+    /// Bytes the account may store.
+    pub quota: u64,
+    /// Bytes the account already stores.
+    pub stored: u64,
+    /// Size of the upload.
+    pub size: u64,
+}
+
+/// Decide whether an account with `quota_bytes`, already storing `stored`
+/// bytes, may upload `size` more.
+///
+/// # Errors
+///
+/// Returns [`QuotaExceeded`] when the upload would not fit.
+pub fn check_quota(quota_bytes: Option<i64>, stored: u64, size: u64) -> Result<(), QuotaExceeded> {
+    let Some(raw_quota) = quota_bytes else {
+        return Ok(());
+    };
+    let quota = u64::try_from(raw_quota).unwrap_or_default();
+    if stored.saturating_add(size) > quota {
+        return Err(QuotaExceeded {
+            quota,
+            stored,
+            size,
+        });
+    }
+    Ok(())
+}
+
+/// Decide whether account `user_id` may upload a file of `size` bytes,
+/// loading what the account already stores only when it has a quota.
+///
+/// # Errors
+///
+/// Returns any error produced by the account or usage query; the inner
+/// result is the decision.
+#[must_use = "handle the result"]
+pub async fn check_upload_quota(
+    conn: &mut DbConnection,
+    user_id: i32,
+    size: u64,
+) -> QueryResult<Result<(), QuotaExceeded>> {
+    let quota_bytes = get_user_by_id(conn, user_id)
+        .await?
+        .and_then(|user| user.quota_bytes);
+    if quota_bytes.is_none() {
+        return Ok(Ok(()));
+    }
+    let stored = stored_bytes_for_user(conn, user_id).await?;
+    Ok(check_quota(quota_bytes, stored, size))
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for quota decisions.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::unlimited(None, 10_000, 10_000, Ok(()))]
+    #[case::fits(Some(1_000), 600, 400, Ok(()))]
+    #[case::exceeds(
+        Some(1_000),
+        600,
+        401,
+        Err(QuotaExceeded { quota: 1_000, stored: 600, size: 401 })
+    )]
+    #[case::negative_quota(
+        Some(-1),
+        0,
+        1,
+        Err(QuotaExceeded { quota: 0, stored: 0, size: 1 })
+    )]
+    fn uploads_must_fit_the_quota(
+        #[case] quota_bytes: Option<i64>,
+        #[case] stored: u64,
+        #[case] size: u64,
+        #[case] expected: Result<(), QuotaExceeded>,
+    ) {
+        assert_eq!(check_quota(quota_bytes, stored, size), expected);
+    }
+
+    #[rstest]
+    fn refusals_explain_the_shortfall() {
+        let refusal = QuotaExceeded {
+            quota: 1_000,
+            stored: 600,
+            size: 401,
+        };
+        assert!(
+            refusal
+                .to_string()
+                .contains("quota is 1000 bytes and you store 600")
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Switches that turn whole subsystems off for a deployment.
+//!
+//! Operators running a file-only or news-only server set `disable_news`,
+//! `disable_files`, or `disable_chat`. [`Subsystems::from_config`] reads the
+//! switches at startup into the server's
+//! [`ServerSettings`](super::settings::ServerSettings).
+//! The command dispatcher refuses every request belonging to a disabled
+//! subsystem with
+//! [`ERR_FEATURE_DISABLED`](crate::commands::ERR_FEATURE_DISABLED) before the
+//! access check, so no handler for it ever runs, and Search leaves out the
+//! parts that are switched off.
+
+use super::AppConfig;
+use crate::transaction_type::TransactionType;
+
+/// A group of transactions that can be switched off together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Subsystem {
+    /// News bundles, categories, and articles.
+    News,
+    /// The file area.
+    Files,
+    /// Public chat.
+    Chat,
+}
+
+impl Subsystem {
+    /// Subsystem a transaction type belongs to, or `None` for transactions
+    /// that are always served.
+    #[must_use]
+    pub const fn of(ty: TransactionType) -> Option<Self> {
+        match ty {
+            TransactionType::NewsCategoryNameList
+            | TransactionType::NewsArticleNameList
+            | TransactionType::NewsArticleData
+            | TransactionType::PostNewsArticle
+            | TransactionType::DeleteNewsItem
+            | TransactionType::NewNewsFolder
+            | TransactionType::NewNewsCategory
+            | TransactionType::DeleteNewsArticle => Some(Self::News),
+            TransactionType::GetFileNameList
+            | TransactionType::DownloadFile
+            | TransactionType::UploadFile
+            | TransactionType::DeleteFile
+            | TransactionType::GetFileInfo
+            | TransactionType::SetFileInfo
+            | TransactionType::MoveFile => Some(Self::Files),
+            TransactionType::SendChat => Some(Self::Chat),
+            _ => None,
+        }
+    }
+}
+
+/// Which subsystems a deployment serves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Subsystems {
+    /// Whether news is served.
+    pub news: bool,
+    /// Whether the file area is served.
+    pub files: bool,
+    /// Whether public chat is served.
+    pub chat: bool,
+}
+
+impl Default for Subsystems {
+    fn default() -> Self { Self::ALL }
+}
+
+impl Subsystems {
+    /// Every subsystem enabled.
+    pub const ALL: Self = Self {
+        news: true,
+        files: true,
+        chat: true,
+    };
+
+    /// Read the `disable_*` switches from `config`.
+    #[must_use]
+    pub const fn from_config(config: &AppConfig) -> Self {
+        Self {
+            news: !config.disable_news,
+            files: !config.disable_files,
+            chat: !config.disable_chat,
+        }
+    }
+
+    /// Whether `subsystem` is served.
+    #[must_use]
+    pub const fn is_enabled(self, subsystem: Subsystem) -> bool {
+        match subsystem {
+            Subsystem::News => self.news,
+            Subsystem::Files => self.files,
+            Subsystem::Chat => self.chat,
+        }
+    }
+
+    /// Whether a request of type `ty` is served.
+    #[must_use]
+    pub const fn serves(self, ty: TransactionType) -> bool {
+        match Subsystem::of(ty) {
+            Some(subsystem) => self.is_enabled(subsystem),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the switches and classifying transactions.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn everything_is_served_by_default() {
+        let subsystems = Subsystems::from_config(&AppConfig::default());
+        assert_eq!(subsystems, Subsystems::ALL);
+        assert!(subsystems.serves(TransactionType::SendChat));
+    }
+
+    #[rstest]
+    #[case(TransactionType::NewsArticleData, false)]
+    #[case(TransactionType::GetFileNameList, true)]
+    #[case(TransactionType::SendChat, false)]
+    #[case(TransactionType::SendInstantMsg, true)]
+    #[case(TransactionType::Search, true)]
+    fn disabled_subsystems_are_not_served(#[case] ty: TransactionType, #[case] served: bool) {
+        let config = AppConfig {
+            disable_news: true,
+            disable_chat: true,
+            ..AppConfig::default()
+        };
+        assert_eq!(Subsystems::from_config(&config).serves(ty), served);
+    }
+}
@@ -0,0 +1,280 @@
+//! Startup summary of the effective configuration.
+//!
+//! [`summarise`] is the one place that inspects an [`AppConfig`] on the
+//! operator's behalf: it resolves unset options to the defaults the server
+//! will actually use and flags risky combinations as [`ConfigWarning`]s. Both
+//! runtimes log the result with [`log_config_summary`] during startup, so the
+//! log shows what a server is running with, whatever mix of flags, files, and
+//! environment variables produced it.
+
+use std::{fmt, net::SocketAddr};
+
+use tracing::{info, warn};
+
+use super::{
+    AppConfig,
+    NetworkRuntime,
+    active_runtime,
+    agreement::ServerAgreement,
+    bind::bind_entries,
+    login_throttle::DEFAULT_LOGIN_FAILURE_LIMIT,
+};
+use crate::{
+    commands::{UnknownPolicyError, UnknownTransactionPolicy},
+    hashing::DEFAULT_HASHING_QUEUE_LIMIT,
+};
+
+/// Effective startup configuration, with defaults resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigSummary {
+    /// Networking runtime compiled into this binary.
+    pub runtime: NetworkRuntime,
+    /// Listener bind address as configured.
+    pub bind: String,
+    /// Second listener serving the legacy runtime in dual-runtime mode.
+    pub legacy_bind: Option<String>,
+    /// Database backend compiled into this binary.
+    pub backend: &'static str,
+    /// Whether client connections are encrypted with TLS.
+    pub tls: bool,
+    /// Whether users must accept an agreement before going online.
+    pub agreement: bool,
+    /// Whether a banner image is served.
+    pub banner: bool,
+    /// Logins that may wait for password verification before more are shed.
+    pub login_queue_limit: usize,
+    /// How unsupported transactions are answered.
+    pub unknown_transactions: UnknownTransactionPolicy,
+    /// Seconds without a transaction before a connection is closed, if
+    /// idle connections are reaped.
+    pub idle_timeout_secs: Option<u64>,
+    /// Whether activity clears a user's away message.
+    pub clear_away_on_activity: bool,
+    /// How downloads are limited: `off`, `ratio`, or `credits`.
+    pub download_policy: String,
+    /// Failed logins before an address or account is locked out; zero when
+    /// lockouts are off.
+    pub login_failure_limit: u32,
+    /// Folders snapshotted into archive folders, as configured.
+    pub archive_folders: Option<String>,
+    /// Whether queries carry transaction trace comments.
+    pub sql_trace_comments: bool,
+    /// Risky combinations found in the configuration.
+    pub warnings: Vec<ConfigWarning>,
+}
+
+/// A configuration combination the operator should know is risky.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigWarning {
+    /// The server listens on every interface without TLS, so credentials and
+    /// messages cross the network in the clear.
+    PlaintextWildcardBind,
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PlaintextWildcardBind => f.write_str(
+                "listening on every interface without TLS; credentials and messages are sent in \
+                 the clear",
+            ),
+        }
+    }
+}
+
+/// Summarise `config` as this binary will run it.
+///
+/// `agreement` is the content loaded from the configured agreement and
+/// banner files.
+///
+/// # Errors
+///
+/// Returns [`UnknownPolicyError`] if the unknown-transaction options are
+/// invalid.
+pub fn summarise(
+    config: &AppConfig,
+    agreement: &ServerAgreement,
+) -> Result<ConfigSummary, UnknownPolicyError> {
+    let tls = config.tls_cert.is_some() && config.tls_key.is_some();
+    let mut warnings = Vec::new();
+    let wildcard = bind_entries(&config.bind)
+        .chain(
+            config
+                .legacy_bind
+                .as_deref()
+                .into_iter()
+                .flat_map(bind_entries),
+        )
+        .any(binds_every_interface);
+    if !tls && wildcard {
+        warnings.push(ConfigWarning::PlaintextWildcardBind);
+    }
+    Ok(ConfigSummary {
+        runtime: active_runtime(),
+        bind: config.bind.clone(),
+        legacy_bind: config.legacy_bind.clone(),
+        backend: if cfg!(feature = "sqlite") {
+            "sqlite"
+        } else {
+            "postgres"
+        },
+        tls,
+        agreement: agreement.text().is_some(),
+        banner: agreement.banner().is_some(),
+        login_queue_limit: config
+            .hashing_queue_limit
+            .unwrap_or(DEFAULT_HASHING_QUEUE_LIMIT),
+        unknown_transactions: UnknownTransactionPolicy::from_config(config)?,
+        idle_timeout_secs: config.idle_timeout_secs,
+        clear_away_on_activity: config.clear_away_on_activity,
+        download_policy: config
+            .download_policy
+            .clone()
+            .unwrap_or_else(|| "off".to_owned()),
+        login_failure_limit: config
+            .login_failure_limit
+            .unwrap_or(DEFAULT_LOGIN_FAILURE_LIMIT.get()),
+        archive_folders: config.archive_folders.clone(),
+        sql_trace_comments: config.sql_trace_comments,
+        warnings,
+    })
+}
+
+/// Log `summary` as one `effective configuration` event, followed by a
+/// warning for each risky combination.
+pub fn log_config_summary(summary: &ConfigSummary) {
+    info!(
+        runtime = ?summary.runtime,
+        bind = %summary.bind,
+        legacy_bind = ?summary.legacy_bind,
+        backend = summary.backend,
+        tls = summary.tls,
+        agreement = summary.agreement,
+        banner = summary.banner,
+        login_queue_limit = summary.login_queue_limit,
+        unknown_transactions = ?summary.unknown_transactions,
+        idle_timeout_secs = ?summary.idle_timeout_secs,
+        clear_away_on_activity = summary.clear_away_on_activity,
+        download_policy = %summary.download_policy,
+        login_failure_limit = summary.login_failure_limit,
+        archive_folders = ?summary.archive_folders,
+        sql_trace_comments = summary.sql_trace_comments,
+        "effective configuration"
+    );
+    for warning in &summary.warnings {
+        warn!(?warning, "{warning}");
+    }
+}
+
+/// Return whether one bind entry names the unspecified address, `0.0.0.0` or
+/// `::`.
+///
+/// Host names are resolved later by the runtimes and are not treated as
+/// wildcards here.
+fn binds_every_interface(bind: &str) -> bool {
+    bind.parse::<SocketAddr>()
+        .is_ok_and(|addr| addr.ip().is_unspecified())
+}
+
+#[cfg(test)]
+mod tests {
+    //! Resolving defaults and flagging risky configurations.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn config_binding(bind: &str) -> AppConfig {
+        AppConfig {
+            bind: bind.to_owned(),
+            ..AppConfig::default()
+        }
+    }
+
+    #[rstest]
+    #[case("0.0.0.0:5500", true)]
+    #[case("[::]:5500", true)]
+    #[case("127.0.0.1:5500", false)]
+    #[case("192.0.2.10:5500", false)]
+    #[case("localhost:5500", false)]
+    #[case("127.0.0.1:5500,[::]:5500", true)]
+    fn warns_about_plaintext_wildcard_binds(#[case] bind: &str, #[case] warned: bool) {
+        let summary =
+            summarise(&config_binding(bind), &ServerAgreement::default()).expect("summary");
+        assert_eq!(
+            summary
+                .warnings
+                .contains(&ConfigWarning::PlaintextWildcardBind),
+            warned
+        );
+    }
+
+    #[rstest]
+    fn tls_silences_the_wildcard_warning() {
+        let config = AppConfig {
+            tls_cert: Some("cert.pem".to_owned()),
+            tls_key: Some("key.pem".to_owned()),
+            ..config_binding("0.0.0.0:5500")
+        };
+        let summary = summarise(&config, &ServerAgreement::default()).expect("summary");
+        assert!(summary.tls);
+        assert!(summary.warnings.is_empty());
+    }
+
+    #[rstest]
+    fn warns_about_wildcard_legacy_listener() {
+        let config = AppConfig {
+            legacy_bind: Some("0.0.0.0:5600".to_owned()),
+            ..config_binding("127.0.0.1:5500")
+        };
+        let summary = summarise(&config, &ServerAgreement::default()).expect("summary");
+        assert_eq!(summary.legacy_bind.as_deref(), Some("0.0.0.0:5600"));
+        assert_eq!(summary.warnings, vec![ConfigWarning::PlaintextWildcardBind]);
+    }
+
+    #[rstest]
+    fn resolves_defaults() {
+        let summary = summarise(
+            &config_binding("127.0.0.1:5500"),
+            &ServerAgreement::default(),
+        )
+        .expect("summary");
+        assert_eq!(summary.runtime, active_runtime());
+        assert!(!summary.tls);
+        assert!(!summary.agreement);
+        assert!(!summary.banner);
+        assert_eq!(summary.login_queue_limit, DEFAULT_HASHING_QUEUE_LIMIT);
+        assert!(!summary.clear_away_on_activity);
+        assert_eq!(summary.download_policy, "off");
+        assert_eq!(
+            summary.login_failure_limit,
+            DEFAULT_LOGIN_FAILURE_LIMIT.get()
+        );
+        assert_eq!(summary.archive_folders, None);
+        assert_eq!(
+            summary.unknown_transactions,
+            UnknownTransactionPolicy::Error
+        );
+        assert!(summary.warnings.is_empty());
+    }
+
+    #[rstest]
+    fn reports_agreement_and_banner() {
+        let agreement = ServerAgreement::new(Some(b"Rules".to_vec()), Some(b"GIF89a".to_vec()));
+        let summary = summarise(&config_binding("127.0.0.1:5500"), &agreement).expect("summary");
+        assert!(summary.agreement);
+        assert!(summary.banner);
+    }
+
+    #[rstest]
+    fn rejects_invalid_unknown_transaction_policy() {
+        let config = AppConfig {
+            unknown_transactions: Some("shrug".to_owned()),
+            ..config_binding("127.0.0.1:5500")
+        };
+        assert_eq!(
+            summarise(&config, &ServerAgreement::default()),
+            Err(UnknownPolicyError::InvalidMode("shrug".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Messages for the local syslog daemon.
+//!
+//! The [`super::logging`] syslog target and the [`super::accounting`] syslog
+//! sink both send datagrams to the daemon's Unix socket in the traditional
+//! local format, `<PRI>mxd[pid]: message`, where `PRI` combines a
+//! [`Facility`] and a [`Severity`]. The daemon adds the timestamp and host.
+
+#[cfg(unix)]
+use std::{
+    io::{self, Write},
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+};
+
+use tracing::Level;
+#[cfg(unix)]
+use tracing::Metadata;
+#[cfg(unix)]
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Socket the local syslog daemon listens on.
+pub const DEFAULT_SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Kind of program a message comes from, as syslog daemons route them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Facility {
+    /// `user`: generic user-level messages.
+    User,
+    /// `daemon`: system daemons; the default for server logs.
+    Daemon,
+    /// `authpriv`: private security and accounting messages.
+    AuthPriv,
+    /// `local0` to `local7`, reserved for site use.
+    Local(LocalFacility),
+}
+
+/// One of the eight `localN` facilities.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalFacility(u8);
+
+impl Facility {
+    /// Look up a facility by its syslog name, such as `daemon` or `local3`.
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "user" => Some(Self::User),
+            "daemon" => Some(Self::Daemon),
+            "authpriv" => Some(Self::AuthPriv),
+            _ => name
+                .strip_prefix("local")
+                .and_then(|digit| digit.parse::<u8>().ok())
+                .filter(|index| *index <= 7)
+                .map(|index| Self::Local(LocalFacility(index))),
+        }
+    }
+
+    /// Numeric facility code.
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::User => 1,
+            Self::Daemon => 3,
+            Self::AuthPriv => 10,
+            Self::Local(LocalFacility(index)) => 16 + index,
+        }
+    }
+}
+
+/// How urgent a message is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// `err`: error conditions.
+    Error,
+    /// `warning`: warning conditions.
+    Warning,
+    /// `info`: informational messages.
+    Info,
+    /// `debug`: debug-level messages.
+    Debug,
+}
+
+impl Severity {
+    /// Numeric severity code.
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Error => 3,
+            Self::Warning => 4,
+            Self::Info => 6,
+            Self::Debug => 7,
+        }
+    }
+}
+
+/// `tracing` levels map onto the nearest severity; `TRACE` and `DEBUG` both
+/// become `debug`.
+impl From<Level> for Severity {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::ERROR => Self::Error,
+            Level::WARN => Self::Warning,
+            Level::INFO => Self::Info,
+            _ => Self::Debug,
+        }
+    }
+}
+
+/// Wrap `body` in a message the local syslog daemon accepts.
+#[must_use]
+pub fn syslog_message(facility: Facility, severity: Severity, body: &str) -> String {
+    let priority = u16::from(facility.code()) * 8 + u16::from(severity.code());
+    format!("<{priority}>mxd[{}]: {body}", std::process::id())
+}
+
+/// `tracing` writer that sends each formatted event to the syslog daemon
+/// with the severity of the event's level.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct SyslogWriter {
+    socket: UnixDatagram,
+    path: PathBuf,
+    facility: Facility,
+}
+
+#[cfg(unix)]
+impl SyslogWriter {
+    /// Prepare to send messages to the daemon listening on `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if nothing exists at `path` or the socket cannot be
+    /// created.
+    pub fn open(path: &Path, facility: Facility) -> io::Result<Self> {
+        std::fs::metadata(path)?;
+        Ok(Self {
+            socket: UnixDatagram::unbound()?,
+            path: path.to_owned(),
+            facility,
+        })
+    }
+}
+
+#[cfg(unix)]
+impl<'a> MakeWriter<'a> for SyslogWriter {
+    type Writer = SyslogLine<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer { SyslogLine::new(self, Severity::Info) }
+
+    fn make_writer_for(&'a self, meta: &Metadata<'_>) -> Self::Writer {
+        SyslogLine::new(self, Severity::from(*meta.level()))
+    }
+}
+
+/// One event being formatted; it is sent when dropped.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct SyslogLine<'a> {
+    writer: &'a SyslogWriter,
+    severity: Severity,
+    line: Vec<u8>,
+}
+
+#[cfg(unix)]
+impl<'a> SyslogLine<'a> {
+    const fn new(writer: &'a SyslogWriter, severity: Severity) -> Self {
+        Self {
+            writer,
+            severity,
+            line: Vec::new(),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Write for SyslogLine<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.line.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+#[cfg(unix)]
+impl Drop for SyslogLine<'_> {
+    fn drop(&mut self) {
+        if self.line.is_empty() {
+            return;
+        }
+        let text = String::from_utf8_lossy(&self.line);
+        let message = syslog_message(self.writer.facility, self.severity, text.trim_end());
+        // A log line the daemon refuses has nowhere else to be reported.
+        let _unsent = self
+            .writer
+            .socket
+            .send_to(message.as_bytes(), &self.writer.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Facility names, priorities, and delivery to a syslog socket.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::daemon("daemon", Some(Facility::Daemon))]
+    #[case::authpriv("authpriv", Some(Facility::AuthPriv))]
+    #[case::local7("local7", Some(Facility::Local(LocalFacility(7))))]
+    #[case::local8("local8", None)]
+    #[case::unknown("kern", None)]
+    fn parses_facility_names(#[case] name: &str, #[case] expected: Option<Facility>) {
+        assert_eq!(Facility::parse(name), expected);
+    }
+
+    #[rstest]
+    #[case::accounting(Facility::AuthPriv, Severity::Info, "<86>")]
+    #[case::daemon_error(Facility::Daemon, Severity::Error, "<27>")]
+    #[case::local0_debug(Facility::Local(LocalFacility(0)), Severity::Debug, "<135>")]
+    fn messages_carry_the_priority(
+        #[case] facility: Facility,
+        #[case] severity: Severity,
+        #[case] prefix: &str,
+    ) {
+        let message = syslog_message(facility, severity, "hello");
+
+        assert!(message.starts_with(&format!("{prefix}mxd[")));
+        assert!(message.ends_with("]: hello"));
+    }
+
+    #[rstest]
+    #[case::error(Level::ERROR, Severity::Error)]
+    #[case::warn(Level::WARN, Severity::Warning)]
+    #[case::info(Level::INFO, Severity::Info)]
+    #[case::trace(Level::TRACE, Severity::Debug)]
+    fn maps_levels_to_severities(#[case] level: Level, #[case] expected: Severity) {
+        assert_eq!(Severity::from(level), expected);
+    }
+
+    #[cfg(unix)]
+    #[rstest]
+    fn lines_reach_the_socket_when_dropped() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("log");
+        let daemon = UnixDatagram::bind(&path).expect("bind syslog socket");
+        let writer = SyslogWriter::open(&path, Facility::Daemon).expect("open writer");
+
+        let mut line = writer.make_writer();
+        line.write_all(b"server started\n").expect("line buffers");
+        drop(line);
+
+        let mut received = [0u8; 256];
+        let len = daemon.recv(&mut received).expect("message arrives");
+        let message = String::from_utf8_lossy(received.get(..len).expect("length"));
+        assert!(message.starts_with("<30>mxd["));
+        assert!(message.ends_with("]: server started"));
+    }
+}
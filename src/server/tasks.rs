@@ -0,0 +1,74 @@
+//! Background tasks that run alongside a server runtime.
+//!
+//! Both runtimes start periodic jobs, such as ban refreshes, archive
+//! snapshots, maintenance, and outbox delivery, before accepting connections,
+//! and stop them all once the listener returns. [`BackgroundTasks`] collects
+//! their handles so a new job needs one line to start and none to stop.
+
+use anyhow::Result;
+use tokio::task::JoinHandle;
+
+use super::{
+    accounting::start_session_accounting,
+    archives::start_archive_snapshots,
+    maintenance::start_scheduled_maintenance,
+    news_digest::start_news_digests,
+    profiling::start_profiling_server,
+    settings::ServerSettings,
+};
+use crate::db::DbPool;
+
+/// Handles of the background tasks a runtime has started.
+#[derive(Debug, Default)]
+pub struct BackgroundTasks {
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl BackgroundTasks {
+    /// Start the jobs that `settings` schedules against `pool`, with the
+    /// profiling server and session accounting when they are configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the profiling address cannot be bound or the
+    /// accounting sink cannot be opened.
+    pub async fn start_configured(
+        &mut self,
+        pool: &DbPool,
+        settings: &ServerSettings,
+    ) -> Result<()> {
+        let background = &settings.background;
+        self.extend(start_archive_snapshots(
+            pool.clone(),
+            background.archives.clone(),
+        ));
+        self.extend(start_scheduled_maintenance(
+            pool.clone(),
+            background.maintenance.clone(),
+        ));
+        self.extend([start_news_digests(
+            pool.clone(),
+            settings.subsystems,
+            background.digests.clone(),
+        )]);
+        self.extend(start_profiling_server(background.profiling_bind)?);
+        self.extend(
+            start_session_accounting(background.accounting_sink.as_ref(), &settings.accounting)
+                .await?,
+        );
+        Ok(())
+    }
+
+    /// Abort every collected task.
+    pub fn abort_all(self) {
+        for handle in self.handles {
+            handle.abort();
+        }
+    }
+}
+
+impl Extend<JoinHandle<()>> for BackgroundTasks {
+    fn extend<I: IntoIterator<Item = JoinHandle<()>>>(&mut self, iter: I) {
+        self.handles.extend(iter);
+    }
+}
@@ -0,0 +1,217 @@
+//! Optional TLS termination for client connections.
+//!
+//! Setting both `tls_cert` and `tls_key` makes each runtime accept clients over
+//! TLS instead of plain TCP; the Hotline handshake and transactions then run
+//! inside the encrypted stream unchanged. [`configure_process`] keeps the
+//! acceptor in each server's
+//! [`TransportSettings`](super::settings::TransportSettings). The legacy
+//! runtime wraps each accepted socket itself. Wireframe owns its listener, so
+//! the Wireframe runtime terminates TLS in front of a loopback listener. The
+//! front end admits each client before its TLS handshake and records the
+//! forwarded connection here with the client's address and [`ConnectionSlot`];
+//! the handshake hook looks it up with [`forwarded_client`] and refuses
+//! loopback connections the front end did not relay. The transfer port wraps
+//! its connections in the same acceptor.
+//!
+//! The server offers the [`HOTLINE_ALPN`] protocol id, which refuses clients
+//! that ask only for something else. `tls_no_alpn` disables ALPN entirely for
+//! "Hotline over TLS" proxies that send their own protocol list.
+//!
+//! [`configure_process`]: super::configure_process
+
+use std::{
+    collections::HashMap,
+    io,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
+
+use thiserror::Error;
+use tokio::{net::TcpStream, time::timeout};
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        self,
+        ServerConfig,
+        crypto::ring,
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    },
+    server::TlsStream,
+};
+
+use super::{AppConfig, connection_limit::ConnectionSlot};
+use crate::protocol::HANDSHAKE_TIMEOUT;
+
+/// ALPN protocol id offered to TLS clients unless `tls_no_alpn` is set.
+pub const HOTLINE_ALPN: &[u8] = b"hotline";
+
+static FORWARDED_PEERS: LazyLock<Mutex<HashMap<SocketAddr, ForwardedClient>>> =
+    LazyLock::new(Mutex::default);
+
+/// Client admitted by the TLS front end and relayed to the Wireframe
+/// listener.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForwardedClient {
+    /// The client's own address.
+    pub address: SocketAddr,
+    /// Connection slot the front end took for the client.
+    pub slot: ConnectionSlot,
+}
+
+/// Errors raised while loading the TLS configuration.
+#[derive(Debug, Error)]
+pub enum TlsConfigError {
+    /// Only one of `tls_cert` and `tls_key` was set.
+    #[error("tls_cert and tls_key must be set together")]
+    Incomplete,
+    /// The certificate chain could not be read.
+    #[error("failed to read TLS certificate {path}: {source}")]
+    Certificate {
+        /// File named by `tls_cert`.
+        path: PathBuf,
+        /// Underlying PEM error.
+        source: rustls::pki_types::pem::Error,
+    },
+    /// The private key could not be read.
+    #[error("failed to read TLS key {path}: {source}")]
+    Key {
+        /// File named by `tls_key`.
+        path: PathBuf,
+        /// Underlying PEM error.
+        source: rustls::pki_types::pem::Error,
+    },
+    /// rustls refused the certificate and key.
+    #[error("invalid TLS certificate or key: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+/// Build a TLS acceptor from `config`, or `None` when TLS is not configured.
+///
+/// # Errors
+///
+/// Returns an error if only one of the certificate and key is set, either
+/// file cannot be read, or rustls rejects them.
+pub fn tls_acceptor_from_config(config: &AppConfig) -> Result<Option<TlsAcceptor>, TlsConfigError> {
+    let (cert, key) = match (&config.tls_cert, &config.tls_key) {
+        (None, None) => return Ok(None),
+        (Some(cert), Some(key)) => (PathBuf::from(cert), PathBuf::from(key)),
+        _ => return Err(TlsConfigError::Incomplete),
+    };
+    let chain = CertificateDer::pem_file_iter(&cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|source| TlsConfigError::Certificate {
+            path: cert.clone(),
+            source,
+        })?;
+    let private_key = PrivateKeyDer::from_pem_file(&key)
+        .map_err(|source| TlsConfigError::Key { path: key, source })?;
+    // Name the provider: other dependencies may enable a second rustls
+    // backend, which leaves no unambiguous process default.
+    let mut server_config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(chain, private_key)?;
+    if !config.tls_no_alpn {
+        server_config.alpn_protocols = vec![HOTLINE_ALPN.to_vec()];
+    }
+    Ok(Some(TlsAcceptor::from(Arc::new(server_config))))
+}
+
+/// Complete the TLS handshake on `stream` within the Hotline handshake
+/// timeout.
+///
+/// # Errors
+///
+/// Returns an error if the handshake fails or times out.
+pub async fn accept_tls(
+    acceptor: &TlsAcceptor,
+    stream: TcpStream,
+) -> io::Result<TlsStream<TcpStream>> {
+    timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "TLS handshake timed out"))?
+}
+
+/// Record that connections from `forwarder` carry traffic for `client`.
+///
+/// The record is removed when the returned guard is dropped.
+#[must_use = "the record is removed when the guard is dropped"]
+pub fn forward_peer(forwarder: SocketAddr, client: ForwardedClient) -> ForwardedPeer {
+    FORWARDED_PEERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(forwarder, client);
+    ForwardedPeer(forwarder)
+}
+
+/// Return the client the TLS front end forwards through `peer`, or `None`
+/// when the front end did not open that connection.
+#[must_use]
+pub fn forwarded_client(peer: SocketAddr) -> Option<ForwardedClient> {
+    FORWARDED_PEERS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&peer)
+        .cloned()
+}
+
+/// Registration made by [`forward_peer`].
+#[derive(Debug)]
+pub struct ForwardedPeer(SocketAddr);
+
+impl Drop for ForwardedPeer {
+    fn drop(&mut self) {
+        FORWARDED_PEERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Loading TLS settings and resolving forwarded peers.
+
+    use rstest::rstest;
+
+    use super::*;
+    use crate::server::connection_limit::ConnectionLimiter;
+
+    #[rstest]
+    fn tls_is_off_by_default() {
+        let acceptor = tls_acceptor_from_config(&AppConfig::default()).expect("no TLS");
+        assert!(acceptor.is_none());
+    }
+
+    #[rstest]
+    #[case(Some("cert.pem"), None)]
+    #[case(None, Some("key.pem"))]
+    fn tls_needs_both_files(#[case] cert: Option<&str>, #[case] key: Option<&str>) {
+        let config = AppConfig {
+            tls_cert: cert.map(str::to_owned),
+            tls_key: key.map(str::to_owned),
+            ..AppConfig::default()
+        };
+        assert!(matches!(
+            tls_acceptor_from_config(&config),
+            Err(TlsConfigError::Incomplete)
+        ));
+    }
+
+    #[rstest]
+    fn forwarded_peers_resolve_until_dropped() {
+        let forwarder: SocketAddr = "127.0.0.1:40001".parse().expect("address");
+        let address: SocketAddr = "203.0.113.9:5500".parse().expect("address");
+        let slot = Arc::new(ConnectionLimiter::default())
+            .admit(address.ip())
+            .expect("slot");
+        let client = ForwardedClient { address, slot };
+
+        let record = forward_peer(forwarder, client.clone());
+        assert_eq!(forwarded_client(forwarder), Some(client));
+
+        drop(record);
+        assert_eq!(forwarded_client(forwarder), None);
+    }
+}
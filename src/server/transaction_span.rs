@@ -0,0 +1,89 @@
+//! Tracing spans around inbound transactions.
+//!
+//! Both runtimes serve each request frame inside a `request` span carrying
+//! the runtime, peer, transaction type, and transaction ID, and the command
+//! it decodes to runs inside a nested `command` span. [`timed`] records how
+//! long either took in the span's `latency_us` field and logs a
+//! `transaction finished` event at debug level, so a slow request can be
+//! found by its ID in the logs and everything it logged read in context.
+
+use std::{future::Future, net::SocketAddr, time::Instant};
+
+use tracing::{Instrument, Span, debug, debug_span, field::Empty, info_span};
+
+use super::NetworkRuntime;
+use crate::transaction::{FrameHeader, HEADER_LEN};
+
+/// Span covering one request frame from receipt to reply.
+///
+/// `ty` and `id` stay empty when the frame is too short to hold a header.
+#[must_use]
+pub fn request_span(runtime: NetworkRuntime, peer: SocketAddr, frame: &[u8]) -> Span {
+    let span = info_span!(
+        "request",
+        runtime = runtime.label(),
+        %peer,
+        ty = Empty,
+        id = Empty,
+        latency_us = Empty,
+    );
+    if let Some(header) = frame.first_chunk::<HEADER_LEN>() {
+        record_header(&span, &FrameHeader::from_bytes(header));
+    }
+    span
+}
+
+/// Span covering the execution of one decoded command.
+#[must_use]
+pub fn command_span(peer: SocketAddr, header: Option<&FrameHeader>) -> Span {
+    let span = debug_span!(
+        "command",
+        %peer,
+        ty = Empty,
+        id = Empty,
+        latency_us = Empty,
+    );
+    if let Some(frame) = header {
+        record_header(&span, frame);
+    }
+    span
+}
+
+fn record_header(span: &Span, header: &FrameHeader) {
+    span.record("ty", header.ty);
+    span.record("id", header.id);
+}
+
+/// Run `fut` inside `span`, then record its latency on the span.
+pub async fn timed<F: Future>(span: Span, fut: F) -> F::Output {
+    let started = Instant::now();
+    let output = fut.instrument(span.clone()).await;
+    let latency_us = u64::try_from(started.elapsed().as_micros()).unwrap_or(u64::MAX);
+    span.record("latency_us", latency_us);
+    span.in_scope(|| debug!(latency_us, "transaction finished"));
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    //! Recording transaction latency.
+
+    use futures_util::FutureExt;
+    use rstest::rstest;
+
+    use super::*;
+    use crate::wireframe::test_helpers::tracing::capture_single_event;
+
+    #[rstest]
+    fn finished_transactions_log_their_latency() {
+        let peer: SocketAddr = "127.0.0.1:5500".parse().expect("address");
+        let event = capture_single_event(|| {
+            let span = request_span(NetworkRuntime::Legacy, peer, &[0; HEADER_LEN]);
+            timed(span, async {}).now_or_never().expect("ready");
+        });
+
+        assert_eq!(event.level(), tracing::Level::DEBUG);
+        assert_eq!(event.message(), Some("transaction finished"));
+        assert!(event.field("latency_us").is_some());
+    }
+}
@@ -0,0 +1,655 @@
+//! Hotline file-transfer port and the transfers waiting on it.
+//!
+//! Transactions that move bulk data, such as Download Banner (212), Download
+//! File (202), and long news article bodies, do not carry it inline. The
+//! handler files the data, or where to read it from, with the server's
+//! [`TransferRegistry`] and replies with its reference number (field 107)
+//! and size (field 108). The client then connects to the transfer port, one
+//! above the transaction port, and opens with a 16-byte `HTXF` handshake
+//! naming the reference. The server sends the data and closes the
+//! connection. When TLS is configured the port speaks it too, with the same
+//! acceptor as the transaction port. Upload File (203) runs the other way: after the handshake the
+//! client sends a flattened file, which is written to the storage backend,
+//! if it still fits the uploader's storage quota, and then entered in its
+//! folder. A reference can be claimed once, and
+//! unclaimed references lapse after [`TRANSFER_CLAIM_TIMEOUT`]. Transfers
+//! being served are tracked so a stopping server can let them finish with
+//! [`TransferRegistry::drain`]; the port stops accepting as soon as shutdown
+//! begins, so the drain only waits for transfers already under way. File downloads and uploads are
+//! admitted through the transfer queue in [`super::transfers`]: a queued one is held after its
+//! handshake until its turn, and gives up its place when it ends. They are
+//! added to the requesting connection's [`TransferTally`] once they
+//! complete. File content is streamed in chunks, never held whole, and a
+//! transfer that stalls for [`TRANSFER_IDLE_TIMEOUT`] is dropped; see
+//! [`content`].
+
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+mod content;
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use diesel_async::pooled_connection::bb8::RunError;
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::{debug, info, warn};
+
+use self::content::{idle, receive_upload, send_object};
+use super::{
+    accept::PAUSE_INITIAL,
+    download_policy::{DownloadCheckError, DownloadRequest, DownloadRules, spend_download},
+    flat_file::FlatFileError,
+    settings::ServerSettings,
+    storage_quota::QuotaExceeded,
+    tls::accept_tls,
+    transfer_stats::TransferTally,
+    transfers::{Admission, TransferLimits, TransferManager},
+};
+use crate::{
+    db::{DbPool, FileMutationError, acquire, get_article_body},
+    privileges::Privileges,
+    storage::{Storage, StorageError},
+    transaction_type::TransactionType,
+};
+
+/// Protocol tag opening every transfer handshake.
+pub const HTXF_MAGIC: [u8; 4] = *b"HTXF";
+
+/// Length of the transfer handshake in bytes.
+pub const HTXF_HANDSHAKE_LEN: usize = 16;
+
+/// How long a client has to send its handshake after connecting.
+pub const HTXF_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a transfer may wait on the client, or the client on it, before
+/// it is dropped.
+pub const TRANSFER_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a reference stays claimable after the reply announcing it.
+pub const TRANSFER_CLAIM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What a pending transfer delivers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferKind {
+    /// The server banner, for Download Banner (212).
+    Banner,
+    /// A news article body, for Get News Article Data (400).
+    ArticleData,
+    /// A stored file, for Download File (202).
+    File,
+    /// A file sent by the client, for Upload File (203).
+    Upload,
+}
+
+/// Where the bytes of a pending transfer come from.
+#[derive(Clone)]
+pub enum TransferSource {
+    /// Bytes already in memory.
+    Inline(Arc<[u8]>),
+    /// The body of a news article, read from the database once the transfer
+    /// is claimed so waiting references hold no copy of it.
+    Article {
+        /// Pool the body is read through.
+        pool: DbPool,
+        /// Article whose body is sent.
+        article_id: i32,
+    },
+    /// A file's content in the storage backend, sent as a flattened file
+    /// behind `prefix`.
+    Stored {
+        /// Backend holding the content.
+        backend: Arc<dyn Storage>,
+        /// Object key of the content.
+        key: String,
+        /// Flattened file header and information fork sent before the
+        /// content.
+        prefix: Arc<[u8]>,
+        /// Account the file is sent to.
+        downloader: Downloader,
+    },
+    /// A file the client will send, and where it goes.
+    Upload(UploadTarget),
+}
+
+/// The account a stored file is sent to, whose download is checked against
+/// the download rules again, and paid for, when the transfer starts.
+#[derive(Clone)]
+pub struct Downloader {
+    /// Pool the account's standing is read and charged through.
+    pub pool: DbPool,
+    /// Account downloading the file.
+    pub user_id: i32,
+    /// Privileges the account held when it asked for the file.
+    pub privileges: Privileges,
+    /// Download rules of the server the file was asked for from.
+    pub rules: DownloadRules,
+}
+
+/// Where an uploaded file is stored and entered.
+#[derive(Clone)]
+pub struct UploadTarget {
+    /// Pool the new entry is written through.
+    pub pool: DbPool,
+    /// Backend the content is written to.
+    pub backend: Arc<dyn Storage>,
+    /// Account uploading the file.
+    pub user_id: i32,
+    /// Folder receiving the file, or `None` for the root.
+    pub parent_id: Option<i32>,
+    /// Name of the new entry.
+    pub name: String,
+    /// Most bytes the client may send.
+    pub size: u32,
+}
+
+impl fmt::Debug for TransferSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inline(data) => f.debug_tuple("Inline").field(&data.len()).finish(),
+            Self::Article { article_id, .. } => f
+                .debug_struct("Article")
+                .field("article_id", article_id)
+                .finish_non_exhaustive(),
+            Self::Stored {
+                key,
+                prefix,
+                downloader,
+                ..
+            } => f
+                .debug_struct("Stored")
+                .field("key", key)
+                .field("prefix", &prefix.len())
+                .field("user_id", &downloader.user_id)
+                .finish_non_exhaustive(),
+            Self::Upload(target) => f
+                .debug_struct("Upload")
+                .field("user_id", &target.user_id)
+                .field("parent_id", &target.parent_id)
+                .field("name", &target.name)
+                .field("size", &target.size)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Data waiting for a client to claim it on the transfer port.
+#[derive(Clone, Debug)]
+pub struct PendingTransfer {
+    /// What the data is.
+    pub kind: TransferKind,
+    /// Where the bytes sent to the client come from.
+    pub source: TransferSource,
+    issued: Instant,
+    tally: Option<Arc<TransferTally>>,
+}
+
+impl PendingTransfer {
+    /// File `data` as a transfer of `kind` issued at `now`.
+    #[must_use]
+    pub fn new(kind: TransferKind, data: Arc<[u8]>, now: Instant) -> Self {
+        Self {
+            kind,
+            source: TransferSource::Inline(data),
+            issued: now,
+            tally: None,
+        }
+    }
+
+    /// File the body of `article_id`, read through `pool` when claimed, as a
+    /// transfer issued at `now`.
+    #[must_use]
+    pub fn article(pool: DbPool, article_id: i32, now: Instant) -> Self {
+        Self {
+            kind: TransferKind::ArticleData,
+            source: TransferSource::Article { pool, article_id },
+            issued: now,
+            tally: None,
+        }
+    }
+
+    /// File a transfer of `kind` reading from or writing to `source`,
+    /// issued at `now`.
+    #[must_use]
+    pub const fn from_source(kind: TransferKind, source: TransferSource, now: Instant) -> Self {
+        Self {
+            kind,
+            source,
+            issued: now,
+            tally: None,
+        }
+    }
+
+    /// Count the file this transfer moves in `tally` once it completes.
+    #[must_use]
+    pub fn counted_in(mut self, tally: Option<Arc<TransferTally>>) -> Self {
+        self.tally = tally;
+        self
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.issued) >= TRANSFER_CLAIM_TIMEOUT
+    }
+}
+
+/// Transfers announced to clients but not yet claimed, by reference number,
+/// with the queue admitting the files among them and the transfers being
+/// served.
+#[derive(Debug)]
+pub struct TransferRegistry {
+    pending: Mutex<BTreeMap<u32, PendingTransfer>>,
+    manager: TransferManager,
+    in_flight: TaskTracker,
+}
+
+impl TransferRegistry {
+    /// Create an empty registry whose queue applies `limits`.
+    #[must_use]
+    pub fn new(limits: TransferLimits) -> Self {
+        Self {
+            pending: Mutex::new(BTreeMap::new()),
+            manager: TransferManager::new(limits),
+            in_flight: TaskTracker::new(),
+        }
+    }
+
+    /// Wait up to `grace` for transfers being served to finish, returning
+    /// `false` if some were still running when it elapsed.
+    pub async fn drain(&self, grace: Duration) -> bool { drain(&self.in_flight, grace).await }
+
+    /// Return the queue admitting this registry's file transfers.
+    #[must_use]
+    pub const fn manager(&self) -> &TransferManager { &self.manager }
+
+    /// File `transfer` and return the reference number a client claims it
+    /// with.
+    ///
+    /// References are random and non-zero, so one client cannot guess
+    /// another's.
+    pub fn register(&self, transfer: PendingTransfer) -> u32 {
+        let now = transfer.issued;
+        let mut pending = self.lock_pending();
+        let lapsed = take_lapsed(&mut pending, now);
+        let reference = loop {
+            let candidate = rand::random::<u32>();
+            if candidate != 0 && !pending.contains_key(&candidate) {
+                break candidate;
+            }
+        };
+        pending.insert(reference, transfer);
+        drop(pending);
+        self.release_all(lapsed);
+        reference
+    }
+
+    /// Remove and return the transfer filed under `reference`, unless it
+    /// has lapsed.
+    pub fn claim(&self, reference: u32, now: Instant) -> Option<PendingTransfer> {
+        let claimed = self.lock_pending().remove(&reference)?;
+        if claimed.is_expired(now) {
+            self.manager.release(reference);
+            return None;
+        }
+        Some(claimed)
+    }
+
+    /// Drop transfers that lapsed unclaimed by `now`, giving up their places
+    /// in the transfer queue.
+    pub fn release_lapsed(&self, now: Instant) {
+        let lapsed = take_lapsed(&mut self.lock_pending(), now);
+        self.release_all(lapsed);
+    }
+
+    fn release_all(&self, references: Vec<u32>) {
+        for reference in references {
+            self.manager.release(reference);
+        }
+    }
+
+    fn lock_pending(&self) -> MutexGuard<'_, BTreeMap<u32, PendingTransfer>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Remove the transfers in `pending` that have lapsed by `now`, returning
+/// their references.
+fn take_lapsed(pending: &mut BTreeMap<u32, PendingTransfer>, now: Instant) -> Vec<u32> {
+    let lapsed: Vec<u32> = pending
+        .iter()
+        .filter(|(_, entry)| entry.is_expired(now))
+        .map(|(reference, _)| *reference)
+        .collect();
+    pending.retain(|_, entry| !entry.is_expired(now));
+    lapsed
+}
+
+/// A claimed transfer's place in the transfer queue, given up when the
+/// transfer ends, however it ends.
+struct TransferSlot<'a> {
+    manager: &'a TransferManager,
+    reference: u32,
+}
+
+impl Drop for TransferSlot<'_> {
+    fn drop(&mut self) { self.manager.release(self.reference); }
+}
+
+/// Wait until transfer `reference` is no longer queued.
+///
+/// Places held by transfers that were filed but never claimed are only
+/// released when they lapse, so a wait that hears nothing for a claim
+/// timeout prunes them.
+async fn wait_for_turn(registry: &TransferRegistry, reference: u32) {
+    let manager = registry.manager();
+    loop {
+        let turn = manager.turn_changed();
+        if !matches!(manager.admission(reference), Some(Admission::Queued { .. })) {
+            return;
+        }
+        if timeout(TRANSFER_CLAIM_TIMEOUT, turn).await.is_err() {
+            registry.release_lapsed(Instant::now());
+        }
+    }
+}
+
+impl Default for TransferRegistry {
+    fn default() -> Self { Self::new(TransferLimits::UNLIMITED) }
+}
+
+/// Errors raised while serving a transfer connection.
+#[derive(Debug, Error)]
+pub enum TransferPortError {
+    /// The handshake did not open with `HTXF`.
+    #[error("transfer handshake has the wrong protocol tag")]
+    BadMagic,
+    /// The handshake named no pending transfer.
+    #[error("unknown or expired transfer reference {0}")]
+    UnknownReference(u32),
+    /// The client sent no handshake in time.
+    #[error("transfer handshake timed out")]
+    Timeout,
+    /// The connection made no progress for [`TRANSFER_IDLE_TIMEOUT`].
+    #[error("transfer stalled")]
+    Stalled,
+    /// The transaction port is the highest port, leaving none above it.
+    #[error("no transfer port above {0}")]
+    NoPortAbove(SocketAddr),
+    /// The article a transfer names was deleted after it was announced.
+    #[error("article {0} no longer exists")]
+    ArticleGone(i32),
+    /// No database connection was available to read the transfer's data.
+    #[error(transparent)]
+    Pool(#[from] RunError),
+    /// Reading the transfer's data from the database failed.
+    #[error(transparent)]
+    Database(#[from] diesel::result::Error),
+    /// Reading or writing the file's content failed.
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    /// The client's upload was not a well-formed flattened file.
+    #[error(transparent)]
+    FlatFile(#[from] FlatFileError),
+    /// The uploaded file could not be entered in its folder.
+    #[error(transparent)]
+    Entry(#[from] FileMutationError),
+    /// The download rules refused the file, or checking them failed.
+    #[error(transparent)]
+    Download(#[from] DownloadCheckError),
+    /// The uploaded file would take its account past its storage quota.
+    #[error(transparent)]
+    Quota(#[from] QuotaExceeded),
+    /// Reading or writing the connection failed.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Handshake a client sends on connecting to the transfer port.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HtxfHandshake {
+    /// Reference number from the transaction reply.
+    pub reference: u32,
+    /// Bytes the client will upload, or zero for a download.
+    pub data_size: u32,
+}
+
+impl HtxfHandshake {
+    /// Decode a handshake.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransferPortError::BadMagic`] unless `bytes` opens with
+    /// [`HTXF_MAGIC`].
+    pub fn parse(bytes: [u8; HTXF_HANDSHAKE_LEN]) -> Result<Self, TransferPortError> {
+        let [m0, m1, m2, m3, r0, r1, r2, r3, s0, s1, s2, s3, ..] = bytes;
+        if [m0, m1, m2, m3] != HTXF_MAGIC {
+            return Err(TransferPortError::BadMagic);
+        }
+        Ok(Self {
+            reference: u32::from_be_bytes([r0, r1, r2, r3]),
+            data_size: u32::from_be_bytes([s0, s1, s2, s3]),
+        })
+    }
+}
+
+/// Return the transfer port address for a transaction port bound at `bind`.
+///
+/// # Errors
+///
+/// Returns [`TransferPortError::NoPortAbove`] when `bind` uses port 65535.
+pub fn transfer_addr(bind: SocketAddr) -> Result<SocketAddr, TransferPortError> {
+    let port = bind
+        .port()
+        .checked_add(1)
+        .ok_or(TransferPortError::NoPortAbove(bind))?;
+    Ok(SocketAddr::new(bind.ip(), port))
+}
+
+/// Serve the transfers filed with the registry in `settings` on the port
+/// above the transaction port bound at `bind` until `stop` is cancelled or
+/// the returned task is aborted, over TLS when `settings` configure it.
+///
+/// Cancelling `stop` closes the port at once; transfers it already accepted
+/// keep running until they finish or [`TransferRegistry::drain`] gives up on
+/// them.
+/// A port that cannot be bound is logged and skipped: the server still runs,
+/// but clients cannot fetch transfers.
+pub async fn start_transfer_port(
+    bind: SocketAddr,
+    stop: CancellationToken,
+    settings: Arc<ServerSettings>,
+) -> Option<JoinHandle<()>> {
+    match bind_transfer_port(bind).await {
+        Ok(listener) => {
+            if let Ok(addr) = listener.local_addr() {
+                info!(%addr, "transfer port listening");
+            }
+            Some(tokio::spawn(accept_transfers(listener, stop, settings)))
+        }
+        Err(error) => {
+            warn!(%error, "transfer port unavailable; files and banners cannot be transferred");
+            None
+        }
+    }
+}
+
+async fn bind_transfer_port(bind: SocketAddr) -> Result<TcpListener, TransferPortError> {
+    Ok(TcpListener::bind(transfer_addr(bind)?).await?)
+}
+
+#[expect(
+    clippy::integer_division_remainder_used,
+    reason = "tokio::select! macro usage"
+)]
+async fn accept_transfers(
+    listener: TcpListener,
+    stop: CancellationToken,
+    settings: Arc<ServerSettings>,
+) {
+    loop {
+        let accepted = tokio::select! {
+            biased;
+            () = stop.cancelled() => {
+                debug!("transfer port closed for shutdown");
+                return;
+            }
+            accepted = listener.accept() => accepted,
+        };
+        let (stream, peer) = match accepted {
+            Ok(accepted) => accepted,
+            Err(error) => {
+                warn!(%error, "transfer port accept failed");
+                sleep(PAUSE_INITIAL).await;
+                continue;
+            }
+        };
+        if settings.address_bans.is_banned(peer.ip()) {
+            continue;
+        }
+        let served = Arc::clone(&settings);
+        settings.transfers.in_flight.spawn(async move {
+            match serve_connection(stream, &served, Instant::now()).await {
+                Ok(kind) => debug!(%peer, ?kind, "transfer sent"),
+                Err(error) => warn!(%peer, %error, "transfer failed"),
+            }
+        });
+    }
+}
+
+/// Serve the transfer from the registry in `settings` on an accepted
+/// connection, inside a TLS session when `settings` configure one.
+async fn serve_connection(
+    mut stream: TcpStream,
+    settings: &ServerSettings,
+    now: Instant,
+) -> Result<TransferKind, TransferPortError> {
+    let registry = &settings.transfers;
+    match &settings.transport.tls {
+        Some(acceptor) => {
+            let mut session = accept_tls(acceptor, stream).await?;
+            serve_transfer(&mut session, registry, now).await
+        }
+        None => serve_transfer(&mut stream, registry, now).await,
+    }
+}
+
+async fn drain(tracker: &TaskTracker, grace: Duration) -> bool {
+    let running = tracker.len();
+    if running == 0 {
+        return true;
+    }
+    info!(running, ?grace, "waiting for transfers to finish");
+    tracker.close();
+    let drained = timeout(grace, tracker.wait()).await.is_ok();
+    tracker.reopen();
+    drained
+}
+
+/// Read a handshake from `stream`, send or receive the transfer it names,
+/// and close the write side.
+///
+/// # Errors
+///
+/// Returns an error if the handshake is late or malformed, names no pending
+/// transfer, or the connection fails.
+pub async fn serve_transfer<S>(
+    stream: &mut S,
+    registry: &TransferRegistry,
+    now: Instant,
+) -> Result<TransferKind, TransferPortError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut bytes = [0u8; HTXF_HANDSHAKE_LEN];
+    timeout(HTXF_HANDSHAKE_TIMEOUT, stream.read_exact(&mut bytes))
+        .await
+        .map_err(|_| TransferPortError::Timeout)??;
+    let handshake = HtxfHandshake::parse(bytes)?;
+    let transfer = registry
+        .claim(handshake.reference, now)
+        .ok_or(TransferPortError::UnknownReference(handshake.reference))?;
+    let _slot = TransferSlot {
+        manager: registry.manager(),
+        reference: handshake.reference,
+    };
+    wait_for_turn(registry, handshake.reference).await;
+    let moved = match &transfer.source {
+        TransferSource::Inline(data) => {
+            idle(stream.write_all(data)).await?;
+            None
+        }
+        TransferSource::Article { pool, article_id } => {
+            let body = read_article_body(pool, *article_id).await?;
+            idle(stream.write_all(body.as_bytes())).await?;
+            None
+        }
+        TransferSource::Stored {
+            backend,
+            key,
+            prefix,
+            downloader,
+        } => {
+            let size = backend.size(key).await?;
+            settle_download(downloader, size).await?;
+            let mut content = backend.open(key).await?;
+            idle(stream.write_all(prefix)).await?;
+            Some(FileMoved::Downloaded(
+                send_object(stream, &mut content).await?,
+            ))
+        }
+        TransferSource::Upload(target) => {
+            Some(FileMoved::Uploaded(receive_upload(stream, target).await?))
+        }
+    };
+    idle(stream.shutdown()).await?;
+    if let (Some(tally), Some(file)) = (&transfer.tally, moved) {
+        match file {
+            FileMoved::Downloaded(bytes) => tally.record_download(bytes),
+            FileMoved::Uploaded(bytes) => tally.record_upload(bytes),
+        }
+    }
+    Ok(transfer.kind)
+}
+
+/// A file a finished transfer moved, and its content's size.
+enum FileMoved {
+    Downloaded(u64),
+    Uploaded(u64),
+}
+
+fn byte_count(len: usize) -> u64 { u64::try_from(len).unwrap_or(u64::MAX) }
+
+async fn read_article_body(pool: &DbPool, article_id: i32) -> Result<String, TransferPortError> {
+    let mut conn = acquire(pool, TransactionType::NewsArticleData).await?;
+    get_article_body(&mut conn, article_id)
+        .await?
+        .ok_or(TransferPortError::ArticleGone(article_id))
+}
+
+/// Check a download of `size` bytes against the download rules once more
+/// and spend the credits it costs.
+async fn settle_download(downloader: &Downloader, size: u64) -> Result<(), TransferPortError> {
+    let mut conn = acquire(&downloader.pool, TransactionType::DownloadFile).await?;
+    let download = DownloadRequest {
+        user_id: downloader.user_id,
+        privileges: downloader.privileges,
+        size,
+    };
+    spend_download(&mut conn, downloader.rules, download).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "transfer_port_tests.rs"]
+mod tests;
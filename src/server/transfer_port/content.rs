@@ -0,0 +1,204 @@
+//! File content moved over the transfer port a chunk at a time.
+//!
+//! Neither direction holds a whole file. A download is copied from its
+//! [`ObjectReader`] to the client, and an upload's data fork from the client
+//! to an [`ObjectWriter`], at most [`CHUNK_LEN`] bytes at a time. Every read
+//! and write on the connection must make progress within
+//! [`TRANSFER_IDLE_TIMEOUT`], so a stalled client cannot hold its transfer,
+//! and its place in the transfer queue, indefinitely.
+
+use std::{future::Future, io};
+
+use bytes::BytesMut;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    time::timeout,
+};
+use tracing::{info, warn};
+
+use super::{TRANSFER_IDLE_TIMEOUT, TransferPortError, UploadTarget, byte_count};
+use crate::{
+    db::{DbPool, UploadedFile, acquire, create_uploaded_file},
+    server::{
+        flat_file::{
+            FlatFileError,
+            FlatFileInfo,
+            ForkKind,
+            read_flat_file_header,
+            read_fork_header,
+            read_info_fork,
+        },
+        storage_quota::check_upload_quota,
+    },
+    storage::{ObjectReader, ObjectWriter},
+    transaction_type::TransactionType,
+};
+
+/// Most bytes read or written in one step.
+const CHUNK_LEN: usize = 64 * 1024;
+
+/// Wait for `io`, failing with [`TransferPortError::Stalled`] if it does not
+/// complete within [`TRANSFER_IDLE_TIMEOUT`].
+pub(super) async fn idle<T, E>(
+    io: impl Future<Output = Result<T, E>>,
+) -> Result<T, TransferPortError>
+where
+    TransferPortError: From<E>,
+{
+    Ok(timeout(TRANSFER_IDLE_TIMEOUT, io)
+        .await
+        .map_err(|_| TransferPortError::Stalled)??)
+}
+
+/// Copy `content` to `stream`, returning how many bytes were sent.
+pub(super) async fn send_object<S>(
+    stream: &mut S,
+    content: &mut ObjectReader,
+) -> Result<u64, TransferPortError>
+where
+    S: AsyncWrite + Unpin,
+{
+    let mut buffer = vec![0u8; CHUNK_LEN];
+    let mut sent = 0u64;
+    loop {
+        let read = idle(content.read(&mut buffer)).await?;
+        let Some(chunk) = buffer.get(..read).filter(|chunk| !chunk.is_empty()) else {
+            return Ok(sent);
+        };
+        idle(stream.write_all(chunk)).await?;
+        sent = sent.saturating_add(byte_count(read));
+    }
+}
+
+/// Store the flattened file the client sends and enter it in its folder,
+/// returning the size of its content. Content stored for a file that is not
+/// entered is removed again.
+pub(super) async fn receive_upload<S>(
+    stream: &mut S,
+    target: &UploadTarget,
+) -> Result<u64, TransferPortError>
+where
+    S: AsyncRead + Unpin,
+{
+    let key = format!("uploads/{:032x}", rand::random::<u128>());
+    let mut body = (&mut *stream).take(u64::from(target.size));
+    let received = store_and_enter(&mut body, target, &key).await;
+    if received.is_err()
+        && let Err(cleanup) = target.backend.delete(&key).await
+    {
+        warn!(%key, error = %cleanup, "unentered upload not removed from storage");
+    }
+    received
+}
+
+async fn store_and_enter<R>(
+    body: &mut R,
+    target: &UploadTarget,
+    key: &str,
+) -> Result<u64, TransferPortError>
+where
+    R: AsyncRead + Unpin,
+{
+    let forks = idle(read_flat_file_header(body)).await?;
+    let mut file_info = FlatFileInfo::default();
+    let mut stored = None;
+    for _ in 0..forks {
+        let fork = idle(read_fork_header(body)).await?;
+        match fork.kind {
+            ForkKind::Info => file_info = idle(read_info_fork(body, fork.len)).await?,
+            ForkKind::Data if stored.is_none() => {
+                stored = Some(store_data(body, target, key, fork.len).await?);
+            }
+            ForkKind::Data | ForkKind::Other => copy_fork(body, fork.len, None).await?,
+        }
+    }
+    let received = stored.ok_or(FlatFileError::MissingData)?;
+    let size = i64::try_from(received).unwrap_or(i64::MAX);
+    let type_code = code_string(file_info.type_code);
+    let creator_code = code_string(file_info.creator_code);
+    let upload = UploadedFile {
+        parent_id: target.parent_id,
+        name: &target.name,
+        object_key: key,
+        size,
+        type_code: &type_code,
+        creator_code: &creator_code,
+        comment: file_info.comment.as_deref(),
+        creator_id: target.user_id,
+    };
+    enter_upload(&target.pool, &upload).await?;
+    info!(user_id = target.user_id, name = %target.name, %key, size, "file uploaded");
+    Ok(received)
+}
+
+/// Check a data fork of `len` bytes against the uploader's storage quota,
+/// then stream it from `body` to storage under `key`.
+async fn store_data<R>(
+    body: &mut R,
+    target: &UploadTarget,
+    key: &str,
+    len: u32,
+) -> Result<u64, TransferPortError>
+where
+    R: AsyncRead + Unpin,
+{
+    let size = u64::from(len);
+    ensure_quota(&target.pool, target.user_id, size).await?;
+    let mut writer = target.backend.create(key).await?;
+    if let Err(error) = copy_fork(body, len, Some(writer.as_mut())).await {
+        if let Err(cleanup) = writer.abort().await {
+            warn!(%key, error = %cleanup, "partial upload not removed from storage");
+        }
+        return Err(error);
+    }
+    writer.finish().await?;
+    Ok(size)
+}
+
+/// Read a fork's `len` bytes from `body` a chunk at a time, handing each to
+/// `writer`, or discarding it when there is none.
+async fn copy_fork<R>(
+    body: &mut R,
+    len: u32,
+    mut writer: Option<&mut dyn ObjectWriter>,
+) -> Result<(), TransferPortError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut remaining = u64::from(len);
+    while remaining > 0 {
+        let capacity = usize::try_from(remaining).map_or(CHUNK_LEN, |left| left.min(CHUNK_LEN));
+        let mut chunk = BytesMut::zeroed(capacity);
+        let read = idle(body.read(&mut chunk)).await?;
+        if read == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        chunk.truncate(read);
+        remaining = remaining.saturating_sub(byte_count(read));
+        if let Some(sink) = writer.as_deref_mut() {
+            sink.write(chunk.freeze()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Refuse an upload of `size` bytes that no longer fits its account's
+/// storage quota.
+async fn ensure_quota(pool: &DbPool, user_id: i32, size: u64) -> Result<(), TransferPortError> {
+    let mut conn = acquire(pool, TransactionType::UploadFile).await?;
+    check_upload_quota(&mut conn, user_id, size).await??;
+    Ok(())
+}
+
+async fn enter_upload(pool: &DbPool, upload: &UploadedFile<'_>) -> Result<(), TransferPortError> {
+    let mut conn = acquire(pool, TransactionType::UploadFile).await?;
+    create_uploaded_file(&mut conn, upload).await?;
+    Ok(())
+}
+
+/// Read a four-byte type or creator code, dropping trailing padding.
+fn code_string(code: [u8; 4]) -> String {
+    String::from_utf8_lossy(&code)
+        .trim_end_matches(['\0', ' '])
+        .to_owned()
+}
@@ -0,0 +1,206 @@
+//! Tests for transfer references, the `HTXF` handshake, TLS, and draining.
+
+use rstest::rstest;
+use test_util::{CLOCK_JUMPS, TlsFixture};
+use tokio::io::duplex;
+
+use super::*;
+use crate::server::{AppConfig, settings::TransportSettings, tls::tls_acceptor_from_config};
+
+fn handshake(magic: [u8; 4], reference: u32) -> Vec<u8> {
+    let mut bytes = magic.to_vec();
+    bytes.extend_from_slice(&reference.to_be_bytes());
+    bytes.extend_from_slice(&[0; 8]);
+    bytes
+}
+
+fn banner(now: Instant) -> PendingTransfer {
+    PendingTransfer::new(TransferKind::Banner, Arc::from(&b"GIF89a"[..]), now)
+}
+
+#[rstest]
+fn references_are_claimed_once() {
+    let registry = TransferRegistry::default();
+    let now = Instant::now();
+    let reference = registry.register(banner(now));
+
+    assert_ne!(reference, 0);
+    assert!(registry.claim(reference, now).is_some());
+    assert!(registry.claim(reference, now).is_none());
+}
+
+#[rstest]
+fn references_lapse() {
+    let registry = TransferRegistry::default();
+    let now = Instant::now();
+    let reference = registry.register(banner(now));
+
+    assert!(
+        registry
+            .claim(reference, now + TRANSFER_CLAIM_TIMEOUT)
+            .is_none()
+    );
+}
+
+#[rstest]
+fn claims_survive_clock_jumps() {
+    for jump in CLOCK_JUMPS {
+        let registry = TransferRegistry::default();
+        let now = Instant::now();
+        let reference = registry.register(banner(now));
+
+        let claimed = registry.claim(reference, jump.apply(now)).is_some();
+        assert_eq!(claimed, !jump.passes(TRANSFER_CLAIM_TIMEOUT), "{jump:?}");
+    }
+}
+
+#[rstest]
+#[case::next_port("127.0.0.1:5500", Some("127.0.0.1:5501"))]
+#[case::highest_port("127.0.0.1:65535", None)]
+fn transfer_port_is_one_above(#[case] bind: &str, #[case] expected: Option<&str>) {
+    let bind: SocketAddr = bind.parse().expect("bind address");
+    let expected = expected.map(|addr| addr.parse::<SocketAddr>().expect("expected address"));
+    assert_eq!(transfer_addr(bind).ok(), expected);
+}
+
+#[rstest]
+#[tokio::test]
+async fn serves_the_claimed_transfer() {
+    let registry = TransferRegistry::default();
+    let now = Instant::now();
+    let reference = registry.register(banner(now));
+    let (mut client, mut server) = duplex(64);
+
+    client
+        .write_all(&handshake(HTXF_MAGIC, reference))
+        .await
+        .expect("write handshake");
+    let kind = serve_transfer(&mut server, &registry, now)
+        .await
+        .expect("serve transfer");
+    let mut received = Vec::new();
+    client
+        .read_to_end(&mut received)
+        .await
+        .expect("read transfer");
+
+    assert_eq!(kind, TransferKind::Banner);
+    assert_eq!(received, b"GIF89a");
+}
+
+#[rstest]
+#[case::bad_magic(*b"HTTP", true)]
+#[case::unknown_reference(HTXF_MAGIC, false)]
+#[tokio::test]
+async fn refuses_bad_handshakes(#[case] magic: [u8; 4], #[case] expect_bad_magic: bool) {
+    let registry = TransferRegistry::default();
+    let (mut client, mut server) = duplex(64);
+    client
+        .write_all(&handshake(magic, 7))
+        .await
+        .expect("write handshake");
+
+    let error = serve_transfer(&mut server, &registry, Instant::now())
+        .await
+        .expect_err("handshake refused");
+
+    assert_eq!(
+        matches!(error, TransferPortError::BadMagic),
+        expect_bad_magic
+    );
+    assert_eq!(
+        matches!(error, TransferPortError::UnknownReference(7)),
+        !expect_bad_magic
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn transfers_the_client_stops_reading_are_dropped() {
+    let registry = TransferRegistry::default();
+    let now = Instant::now();
+    let data = Arc::from(&[0u8; 4 * HTXF_HANDSHAKE_LEN][..]);
+    let reference = registry.register(PendingTransfer::new(TransferKind::Banner, data, now));
+    let (mut client, mut server) = duplex(HTXF_HANDSHAKE_LEN);
+    client
+        .write_all(&handshake(HTXF_MAGIC, reference))
+        .await
+        .expect("write handshake");
+
+    let error = serve_transfer(&mut server, &registry, now)
+        .await
+        .expect_err("stalled transfer dropped");
+
+    assert!(matches!(error, TransferPortError::Stalled));
+    drop(client);
+}
+
+#[tokio::test(start_paused = true)]
+async fn draining_waits_for_running_transfers_up_to_the_grace_period() {
+    let tracker = TaskTracker::new();
+    assert!(drain(&tracker, Duration::ZERO).await);
+
+    tracker.spawn(tokio::time::sleep(Duration::from_secs(5)));
+    assert!(!drain(&tracker, Duration::from_secs(1)).await);
+    assert!(drain(&tracker, Duration::from_secs(10)).await);
+    assert!(!tracker.is_closed());
+}
+
+#[rstest]
+#[tokio::test]
+async fn accepting_stops_when_shutdown_begins() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local address");
+    let stop = CancellationToken::new();
+    let settings = Arc::new(ServerSettings::default());
+    let acceptor = tokio::spawn(accept_transfers(listener, stop.clone(), settings));
+
+    stop.cancel();
+    timeout(Duration::from_secs(5), acceptor)
+        .await
+        .expect("accept loop ends")
+        .expect("accept task");
+
+    assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+}
+
+#[rstest]
+#[tokio::test]
+async fn serves_transfers_over_tls_when_configured() {
+    let fixture = TlsFixture::new().expect("TLS material");
+    let config = AppConfig {
+        tls_cert: Some(fixture.cert_path().display().to_string()),
+        tls_key: Some(fixture.key_path().display().to_string()),
+        ..AppConfig::default()
+    };
+    let tls = tls_acceptor_from_config(&config).expect("TLS settings");
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local address");
+    let stop = CancellationToken::new();
+    let settings = Arc::new(ServerSettings {
+        transport: TransportSettings {
+            tls,
+            ..TransportSettings::default()
+        },
+        ..ServerSettings::default()
+    });
+    let reference = settings.transfers.register(banner(Instant::now()));
+    let acceptor = tokio::spawn(accept_transfers(listener, stop.clone(), settings));
+
+    let received = tokio::task::spawn_blocking(move || {
+        use std::io::{Read, Write};
+
+        let mut client = fixture.connect(addr).expect("TLS handshake");
+        client
+            .write_all(&handshake(HTXF_MAGIC, reference))
+            .expect("write handshake");
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).expect("read transfer");
+        received
+    })
+    .await
+    .expect("client task");
+    stop.cancel();
+    acceptor.await.expect("accept task");
+
+    assert_eq!(received, b"GIF89a");
+}
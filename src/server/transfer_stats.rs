@@ -0,0 +1,263 @@
+//! Per-user transfer statistics.
+//!
+//! Each connection counts the bytes and files it transfers in a
+//! [`TransferTally`] attached to its presence details. The tallies are
+//! added to the account's totals in the database every
+//! [`TRANSFER_STATS_FLUSH_INTERVAL`], as soon as a session logs out or
+//! disconnects, and once more when the server stops, so the totals survive
+//! restarts without a database write per transfer. Get Client Info Text
+//! (303) and the `users stats` subcommand report the totals.
+
+use std::{
+    iter,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use tokio::{sync::Notify, task::JoinHandle, time::sleep};
+use tracing::{debug, warn};
+
+use crate::{
+    db::{DbPool, TransferStats, record_transfer_stats},
+    presence::PresenceRegistry,
+};
+
+/// How often the runtimes add online sessions' tallies to the database.
+pub const TRANSFER_STATS_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Transfers a connection has made since its tally was last flushed.
+#[derive(Debug, Default)]
+pub struct TransferTally {
+    bytes_uploaded: AtomicU64,
+    bytes_downloaded: AtomicU64,
+    uploads: AtomicU64,
+    downloads: AtomicU64,
+    session_uploaded: AtomicU64,
+    session_downloaded: AtomicU64,
+}
+
+/// Bytes a login has transferred, flushed or not.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SessionBytes {
+    /// Bytes uploaded to the server.
+    pub uploaded: u64,
+    /// Bytes downloaded from the server.
+    pub downloaded: u64,
+}
+
+impl TransferTally {
+    /// Count a completed upload of `bytes`.
+    pub fn record_upload(&self, bytes: u64) {
+        self.bytes_uploaded.fetch_add(bytes, Ordering::Relaxed);
+        self.uploads.fetch_add(1, Ordering::Relaxed);
+        self.session_uploaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Count a completed download of `bytes`.
+    pub fn record_download(&self, bytes: u64) {
+        self.bytes_downloaded.fetch_add(bytes, Ordering::Relaxed);
+        self.downloads.fetch_add(1, Ordering::Relaxed);
+        self.session_downloaded.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Return every byte counted since the tally was created; flushes do
+    /// not reset these.
+    #[must_use]
+    pub fn session_bytes(&self) -> SessionBytes {
+        SessionBytes {
+            uploaded: self.session_uploaded.load(Ordering::Relaxed),
+            downloaded: self.session_downloaded.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Return the transfers not yet flushed.
+    #[must_use]
+    pub fn pending(&self) -> TransferStats {
+        TransferStats {
+            bytes_uploaded: to_column(self.bytes_uploaded.load(Ordering::Relaxed)),
+            bytes_downloaded: to_column(self.bytes_downloaded.load(Ordering::Relaxed)),
+            uploads: to_column(self.uploads.load(Ordering::Relaxed)),
+            downloads: to_column(self.downloads.load(Ordering::Relaxed)),
+        }
+    }
+
+    /// Return the transfers not yet flushed and reset the tally.
+    pub fn take(&self) -> TransferStats {
+        TransferStats {
+            bytes_uploaded: to_column(self.bytes_uploaded.swap(0, Ordering::Relaxed)),
+            bytes_downloaded: to_column(self.bytes_downloaded.swap(0, Ordering::Relaxed)),
+            uploads: to_column(self.uploads.swap(0, Ordering::Relaxed)),
+            downloads: to_column(self.downloads.swap(0, Ordering::Relaxed)),
+        }
+    }
+
+    /// Put back totals taken by a flush that failed.
+    pub fn restore(&self, stats: &TransferStats) {
+        self.bytes_uploaded
+            .fetch_add(from_column(stats.bytes_uploaded), Ordering::Relaxed);
+        self.bytes_downloaded
+            .fetch_add(from_column(stats.bytes_downloaded), Ordering::Relaxed);
+        self.uploads
+            .fetch_add(from_column(stats.uploads), Ordering::Relaxed);
+        self.downloads
+            .fetch_add(from_column(stats.downloads), Ordering::Relaxed);
+    }
+}
+
+fn to_column(value: u64) -> i64 { i64::try_from(value).unwrap_or(i64::MAX) }
+
+fn from_column(value: i64) -> u64 { u64::try_from(value).unwrap_or_default() }
+
+/// Add every pending tally in `presence` to the database, returning the
+/// number of accounts updated.
+///
+/// When a write fails, the tallies not yet written are handed back to
+/// `presence` so the next flush retries them.
+///
+/// # Errors
+///
+/// Returns the first error raised while acquiring a connection or writing
+/// totals.
+pub async fn flush_transfer_stats(pool: &DbPool, presence: &PresenceRegistry) -> Result<usize> {
+    let tallies = presence.transfer_tallies();
+    if tallies.iter().all(|(_, tally)| tally.pending().is_empty()) {
+        return Ok(0);
+    }
+    let mut conn = match pool.get().await {
+        Ok(conn) => conn,
+        Err(error) => {
+            presence.requeue_transfers(tallies);
+            return Err(error.into());
+        }
+    };
+    let mut remaining = tallies.into_iter();
+    let mut failure = None;
+    let mut flushed = 0;
+    for (account_id, tally) in remaining.by_ref() {
+        let delta = tally.take();
+        if delta.is_empty() {
+            continue;
+        }
+        if let Err(error) = record_transfer_stats(&mut conn, account_id, &delta).await {
+            tally.restore(&delta);
+            failure = Some((account_id, tally, error));
+            break;
+        }
+        flushed += 1;
+    }
+    if let Some((account_id, tally, error)) = failure {
+        presence.requeue_transfers(iter::once((account_id, tally)).chain(remaining));
+        return Err(error.into());
+    }
+    Ok(flushed)
+}
+
+/// Background task that keeps transfer totals in the database current.
+#[derive(Debug)]
+pub struct TransferStatsFlusher {
+    stop: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+impl TransferStatsFlusher {
+    /// Flush tallies from `presence` periodically and whenever a session
+    /// leaves, until [`Self::stop`] is called.
+    #[must_use]
+    pub fn start(pool: DbPool, presence: Arc<PresenceRegistry>) -> Self {
+        let stop = Arc::new(Notify::new());
+        let stopped = Arc::clone(&stop);
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    () = stopped.notified() => break,
+                    () = sleep(TRANSFER_STATS_FLUSH_INTERVAL) => {}
+                    () = presence.transfers_retired() => {}
+                }
+                flush(&pool, &presence).await;
+            }
+            flush(&pool, &presence).await;
+        });
+        Self { stop, task }
+    }
+
+    /// Flush the remaining tallies and stop the task.
+    pub async fn stop(self) {
+        self.stop.notify_one();
+        if let Err(error) = self.task.await {
+            warn!(%error, "transfer statistics task failed");
+        }
+    }
+}
+
+async fn flush(pool: &DbPool, presence: &PresenceRegistry) {
+    match flush_transfer_stats(pool, presence).await {
+        Ok(0) => {}
+        Ok(accounts) => debug!(accounts, "recorded transfer statistics"),
+        Err(error) => warn!(%error, "failed to record transfer statistics"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for transfer tallies.
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn take_resets_and_restore_puts_back() {
+        let tally = TransferTally::default();
+        tally.record_upload(1_000);
+        tally.record_download(300);
+        tally.record_download(200);
+
+        let taken = tally.take();
+        assert_eq!(
+            taken,
+            TransferStats {
+                bytes_uploaded: 1_000,
+                bytes_downloaded: 500,
+                uploads: 1,
+                downloads: 2,
+            }
+        );
+        assert!(tally.pending().is_empty());
+
+        tally.record_upload(24);
+        tally.restore(&taken);
+        assert_eq!(tally.pending().bytes_uploaded, 1_024);
+        assert_eq!(tally.pending().uploads, 2);
+    }
+
+    #[rstest]
+    fn session_bytes_survive_flushes() {
+        let tally = TransferTally::default();
+        tally.record_upload(1_000);
+        tally.record_download(500);
+
+        let taken = tally.take();
+        tally.restore(&taken);
+        let _flushed = tally.take();
+        tally.record_download(24);
+
+        assert_eq!(
+            tally.session_bytes(),
+            SessionBytes {
+                uploaded: 1_000,
+                downloaded: 524,
+            }
+        );
+    }
+
+    #[rstest]
+    fn saturates_rather_than_wrapping() {
+        let tally = TransferTally::default();
+        tally.record_download(u64::MAX);
+
+        assert_eq!(tally.pending().bytes_downloaded, i64::MAX);
+    }
+}
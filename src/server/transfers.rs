@@ -0,0 +1,443 @@
+//! Concurrency limits and queueing for file transfers.
+//!
+//! Every upload and download a client asks for is admitted through the
+//! [`TransferManager`] of the server's transfer registry. A transfer starts at
+//! once while fewer than `max_transfers` are running in total and its account
+//! has fewer than `max_transfers_per_user` running; otherwise it joins a
+//! first-come queue. When a transfer finishes, queued transfers that now fit
+//! are started, in order, and every transfer still waiting learns its new
+//! place. An account at its own limit does not hold up the accounts queued
+//! behind it. The limits also cap a single upload at `max_upload_bytes`, which
+//! the upload handler checks before filing the transfer.
+//!
+//! Queue places reach clients through the Waiting Count field (116): the
+//! transfer reply carries it, and [`download_info`] builds the Download Info
+//! (211) push that reports each later change. The Wireframe runtime hands the
+//! manager the messaging those pushes go through with
+//! [`TransferManager::set_messaging`]; the legacy runtime cannot push, so its
+//! clients only learn their place from the reply. A queued transfer claimed on
+//! the transfer port waits there for [`TransferManager::turn_changed`] to show
+//! it running. Banner and news article transfers are not counted. A session
+//! resumed with a token takes over its account's transfers through
+//! [`TransferManager::rebind`], so later queue changes reach the new
+//! connection.
+
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    mem,
+    num::{NonZeroU64, NonZeroUsize},
+    sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock},
+};
+
+use tokio::{
+    runtime::Handle,
+    sync::{Notify, futures::Notified},
+};
+use tracing::debug;
+
+use super::{
+    AppConfig,
+    outbound::{OutboundConnectionId, OutboundMessaging, OutboundPriority, OutboundTarget},
+};
+use crate::{
+    field_id::FieldId,
+    presence::server_notification,
+    transaction::{Transaction, TransactionError, encode_params},
+    transaction_type::TransactionType,
+};
+
+/// Which way a transfer moves data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferDirection {
+    /// Server to client.
+    Download,
+    /// Client to server.
+    Upload,
+}
+
+/// How many transfers may run at once, and how large an upload may be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferLimits {
+    /// Transfers running across the server, or `None` for no limit.
+    pub total: Option<NonZeroUsize>,
+    /// Transfers running for one account, or `None` for no limit.
+    pub per_user: Option<NonZeroUsize>,
+    /// Bytes one upload may send, or `None` for no limit.
+    pub max_upload: Option<NonZeroU64>,
+}
+
+impl TransferLimits {
+    /// No limits: every transfer starts at once and uploads may be any size.
+    pub const UNLIMITED: Self = Self {
+        total: None,
+        per_user: None,
+        max_upload: None,
+    };
+
+    /// Read the limits from `config`; an unset or zero option means no
+    /// limit.
+    #[must_use]
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            total: config.max_transfers.and_then(NonZeroUsize::new),
+            per_user: config.max_transfers_per_user.and_then(NonZeroUsize::new),
+            max_upload: config.max_upload_bytes.and_then(NonZeroU64::new),
+        }
+    }
+}
+
+/// A transfer asking to run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferRequest {
+    /// Account the transfer belongs to.
+    pub user_id: i32,
+    /// Reference number the client quotes on the transfer port.
+    pub reference: u32,
+    /// Which way the data moves.
+    pub direction: TransferDirection,
+    /// Connection that asked for the transfer and hears of queue changes.
+    pub connection: Option<OutboundConnectionId>,
+}
+
+/// Where a transfer stands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Admission {
+    /// The transfer may run now.
+    Active,
+    /// The transfer waits behind `ahead` others.
+    Queued {
+        /// Transfers queued ahead of this one.
+        ahead: usize,
+    },
+}
+
+impl Admission {
+    /// Value for the Waiting Count field (116): zero once the transfer may
+    /// run.
+    #[must_use]
+    pub fn waiting_count(self) -> u32 {
+        match self {
+            Self::Active => 0,
+            Self::Queued { ahead } => u32::try_from(ahead.saturating_add(1)).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+/// A queued transfer whose place changed, to be reported to its client.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueueUpdate {
+    /// The transfer.
+    pub request: TransferRequest,
+    /// Its new place.
+    pub admission: Admission,
+}
+
+/// Running and queued counts, for logs and metrics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransferCounts {
+    /// Transfers running now.
+    pub active: usize,
+    /// Transfers waiting to start.
+    pub queued: usize,
+}
+
+#[derive(Debug)]
+struct TransferQueue {
+    active: Vec<TransferRequest>,
+    waiting: VecDeque<TransferRequest>,
+}
+
+impl TransferQueue {
+    fn fits(&self, limits: TransferLimits, user_id: i32) -> bool {
+        let total_ok = limits
+            .total
+            .is_none_or(|limit| self.active.len() < limit.get());
+        let user_ok = limits.per_user.is_none_or(|limit| {
+            self.active
+                .iter()
+                .filter(|running| running.user_id == user_id)
+                .count()
+                < limit.get()
+        });
+        total_ok && user_ok
+    }
+
+    /// Start every waiting transfer that now fits, in queue order, and
+    /// report every transfer whose place changed. Transfers at or beyond
+    /// index `shifted_from` have already moved up because an earlier one left
+    /// the queue.
+    fn promote(&mut self, limits: TransferLimits, shifted_from: usize) -> Vec<QueueUpdate> {
+        let mut updates = Vec::new();
+        let mut still_waiting = VecDeque::with_capacity(self.waiting.len());
+        let mut moved = false;
+        for (index, request) in mem::take(&mut self.waiting).into_iter().enumerate() {
+            if self.fits(limits, request.user_id) {
+                self.active.push(request);
+                updates.push(QueueUpdate {
+                    request,
+                    admission: Admission::Active,
+                });
+                moved = true;
+            } else {
+                if moved || index >= shifted_from {
+                    updates.push(QueueUpdate {
+                        request,
+                        admission: Admission::Queued {
+                            ahead: still_waiting.len(),
+                        },
+                    });
+                }
+                still_waiting.push_back(request);
+            }
+        }
+        self.waiting = still_waiting;
+        updates
+    }
+}
+
+/// Running and queued transfers with the limits that govern them.
+pub struct TransferManager {
+    limits: RwLock<TransferLimits>,
+    queue: Mutex<TransferQueue>,
+    turns: Notify,
+    messaging: RwLock<Option<Arc<dyn OutboundMessaging>>>,
+}
+
+impl TransferManager {
+    /// Create a manager with no transfers.
+    #[must_use]
+    pub const fn new(limits: TransferLimits) -> Self {
+        Self {
+            limits: RwLock::new(limits),
+            queue: Mutex::new(TransferQueue {
+                active: Vec::new(),
+                waiting: VecDeque::new(),
+            }),
+            turns: Notify::const_new(),
+            messaging: RwLock::new(None),
+        }
+    }
+
+    /// Replace the limits. Transfers already running are not stopped, but
+    /// queued transfers that now fit are started and reported.
+    #[must_use = "report queue updates to their clients"]
+    pub fn set_limits(&self, limits: TransferLimits) -> Vec<QueueUpdate> {
+        *self.limits.write().unwrap_or_else(PoisonError::into_inner) = limits;
+        let updates = self.lock_queue().promote(limits, usize::MAX);
+        self.turns.notify_waiters();
+        updates
+    }
+
+    /// Return the limits in force.
+    #[must_use]
+    pub fn limits(&self) -> TransferLimits {
+        *self.limits.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_queue(&self) -> MutexGuard<'_, TransferQueue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Start `request` if it fits within the limits, or queue it.
+    #[must_use = "tell the client whether its transfer is queued"]
+    pub fn admit(&self, request: TransferRequest) -> Admission {
+        let limits = self.limits();
+        let mut queue = self.lock_queue();
+        // Waiting transfers are all blocked by a limit, so a request that
+        // fits skips no one entitled to run before it.
+        if queue.fits(limits, request.user_id) {
+            queue.active.push(request);
+            return Admission::Active;
+        }
+        let ahead = queue.waiting.len();
+        queue.waiting.push_back(request);
+        Admission::Queued { ahead }
+    }
+
+    /// Remove the running or queued transfer `reference`, start whatever
+    /// now fits, and report every transfer whose place changed.
+    #[must_use = "report queue updates to their clients"]
+    pub fn finish(&self, reference: u32) -> Vec<QueueUpdate> {
+        let limits = self.limits();
+        let mut queue = self.lock_queue();
+        queue
+            .active
+            .retain(|running| running.reference != reference);
+        let shifted_from = queue
+            .waiting
+            .iter()
+            .position(|waiting| waiting.reference == reference)
+            .unwrap_or(usize::MAX);
+        queue
+            .waiting
+            .retain(|waiting| waiting.reference != reference);
+        let updates = queue.promote(limits, shifted_from);
+        drop(queue);
+        self.turns.notify_waiters();
+        updates
+    }
+
+    /// Return a future that resolves the next time queued transfers may
+    /// have started.
+    ///
+    /// The future hears every change made after it is created, so callers
+    /// take it before checking [`Self::admission`] and cannot miss their
+    /// turn in between.
+    pub fn turn_changed(&self) -> Notified<'_> { self.turns.notified() }
+
+    /// Return where transfer `reference` stands, or `None` if it is neither
+    /// running nor queued.
+    #[must_use]
+    pub fn admission(&self, reference: u32) -> Option<Admission> {
+        let queue = self.lock_queue();
+        if queue
+            .active
+            .iter()
+            .any(|running| running.reference == reference)
+        {
+            return Some(Admission::Active);
+        }
+        queue
+            .waiting
+            .iter()
+            .position(|waiting| waiting.reference == reference)
+            .map(|ahead| Admission::Queued { ahead })
+    }
+
+    /// Return the running or queued request for transfer `reference`.
+    #[must_use]
+    pub fn request(&self, reference: u32) -> Option<TransferRequest> {
+        let queue = self.lock_queue();
+        queue
+            .active
+            .iter()
+            .chain(&queue.waiting)
+            .find(|request| request.reference == reference)
+            .copied()
+    }
+
+    /// Point every running and queued transfer of `user_id` at
+    /// `connection` and return how many moved.
+    ///
+    /// A resumed session calls this so the queue changes meant for the
+    /// connection it replaced reach it instead.
+    pub fn rebind(&self, user_id: i32, connection: OutboundConnectionId) -> usize {
+        let mut queue = self.lock_queue();
+        let TransferQueue { active, waiting } = &mut *queue;
+        let mut moved = 0;
+        for request in active.iter_mut().chain(waiting.iter_mut()) {
+            if request.user_id == user_id && request.connection != Some(connection) {
+                request.connection = Some(connection);
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// Count running and queued transfers.
+    #[must_use]
+    pub fn counts(&self) -> TransferCounts {
+        let queue = self.lock_queue();
+        TransferCounts {
+            active: queue.active.len(),
+            queued: queue.waiting.len(),
+        }
+    }
+
+    /// Send Download Info (211) pushes through `messaging`; `None`, for
+    /// runtimes that cannot push, sends none.
+    pub fn set_messaging(&self, messaging: Option<Arc<dyn OutboundMessaging>>) {
+        *self
+            .messaging
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = messaging;
+    }
+
+    /// Finish transfer `reference` and report the transfers that moved in
+    /// the background.
+    ///
+    /// Called when a transfer ends, however it ends, and when an unclaimed
+    /// one lapses; unknown references change nothing.
+    pub fn release(&self, reference: u32) {
+        let updates = self.finish(reference);
+        if updates.is_empty() {
+            return;
+        }
+        let installed = self
+            .messaging
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        let Some(messaging) = installed else {
+            return;
+        };
+        if let Ok(handle) = Handle::try_current() {
+            handle.spawn(report_queue_updates(messaging, updates));
+        }
+    }
+}
+
+impl fmt::Debug for TransferManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransferManager")
+            .field("limits", &self.limits())
+            .field("counts", &self.counts())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Tell each transfer's client where it now stands with a Download Info
+/// (211) push through `messaging`. Failed pushes are logged and skipped.
+async fn report_queue_updates(messaging: Arc<dyn OutboundMessaging>, updates: Vec<QueueUpdate>) {
+    for update in updates {
+        let Some(connection) = update.request.connection else {
+            continue;
+        };
+        let reference = update.request.reference;
+        let result = match download_info(reference, update.admission) {
+            Ok(push) => {
+                messaging
+                    .push(
+                        OutboundTarget::Connection(connection),
+                        push,
+                        OutboundPriority::High,
+                    )
+                    .await
+            }
+            Err(error) => {
+                debug!(%error, reference, "download info not encoded");
+                continue;
+            }
+        };
+        if let Err(error) = result {
+            debug!(%error, reference, "download info not delivered");
+        }
+    }
+}
+
+/// Build the Download Info (211) push telling a client where transfer
+/// `reference` now stands.
+///
+/// # Errors
+///
+/// Returns an encoding error if the parameters cannot be encoded.
+pub fn download_info(
+    reference: u32,
+    admission: Admission,
+) -> Result<Transaction, TransactionError> {
+    let payload = encode_params(&[
+        (FieldId::ReferenceNumber, reference.to_be_bytes()),
+        (
+            FieldId::WaitingCount,
+            admission.waiting_count().to_be_bytes(),
+        ),
+    ])?;
+    Ok(server_notification(TransactionType::DownloadInfo, payload))
+}
+
+#[cfg(test)]
+#[path = "transfers_tests.rs"]
+mod tests;
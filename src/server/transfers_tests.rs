@@ -0,0 +1,186 @@
+//! Unit tests for transfer admission and queueing.
+
+use rstest::rstest;
+
+use super::*;
+use crate::transaction::decode_params;
+
+fn limits(total: usize, per_user: usize) -> TransferLimits {
+    TransferLimits {
+        total: NonZeroUsize::new(total),
+        per_user: NonZeroUsize::new(per_user),
+        max_upload: None,
+    }
+}
+
+fn request(user_id: i32, reference: u32) -> TransferRequest {
+    TransferRequest {
+        user_id,
+        reference,
+        direction: TransferDirection::Download,
+        connection: None,
+    }
+}
+
+#[rstest]
+#[case::unset(None, None, TransferLimits::UNLIMITED)]
+#[case::zero_means_unlimited(Some(0), Some(0), TransferLimits::UNLIMITED)]
+#[case::both(Some(8), Some(2), limits(8, 2))]
+fn reads_limits_from_config(
+    #[case] total: Option<usize>,
+    #[case] per_user: Option<usize>,
+    #[case] expected: TransferLimits,
+) {
+    let config = AppConfig {
+        max_transfers: total,
+        max_transfers_per_user: per_user,
+        ..AppConfig::default()
+    };
+    assert_eq!(TransferLimits::from_config(&config), expected);
+}
+
+#[rstest]
+#[case::unset(None, None)]
+#[case::zero_means_unlimited(Some(0), None)]
+#[case::set(Some(1_048_576), NonZeroU64::new(1_048_576))]
+fn reads_the_upload_cap_from_config(
+    #[case] bytes: Option<u64>,
+    #[case] expected: Option<NonZeroU64>,
+) {
+    let config = AppConfig {
+        max_upload_bytes: bytes,
+        ..AppConfig::default()
+    };
+    assert_eq!(TransferLimits::from_config(&config).max_upload, expected);
+}
+
+#[rstest]
+fn transfers_beyond_the_total_limit_queue_in_order() {
+    let manager = TransferManager::new(limits(1, 0));
+
+    assert_eq!(manager.admit(request(1, 10)), Admission::Active);
+    assert_eq!(
+        manager.admit(request(2, 20)),
+        Admission::Queued { ahead: 0 }
+    );
+    assert_eq!(
+        manager.admit(request(3, 30)),
+        Admission::Queued { ahead: 1 }
+    );
+
+    let updates = manager.finish(10);
+    assert_eq!(
+        updates,
+        [
+            QueueUpdate {
+                request: request(2, 20),
+                admission: Admission::Active,
+            },
+            QueueUpdate {
+                request: request(3, 30),
+                admission: Admission::Queued { ahead: 0 },
+            },
+        ]
+    );
+    assert_eq!(
+        manager.counts(),
+        TransferCounts {
+            active: 1,
+            queued: 1,
+        }
+    );
+}
+
+#[rstest]
+fn a_user_at_their_limit_does_not_block_others() {
+    let manager = TransferManager::new(limits(2, 1));
+
+    assert_eq!(manager.admit(request(1, 10)), Admission::Active);
+    assert_eq!(
+        manager.admit(request(1, 11)),
+        Admission::Queued { ahead: 0 }
+    );
+    assert_eq!(manager.admit(request(2, 20)), Admission::Active);
+    assert_eq!(
+        manager.admit(request(3, 30)),
+        Admission::Queued { ahead: 1 }
+    );
+
+    // User 3 starts ahead of user 1, who is still at their own limit.
+    assert_eq!(
+        manager.finish(20),
+        [QueueUpdate {
+            request: request(3, 30),
+            admission: Admission::Active,
+        }]
+    );
+    assert_eq!(manager.admission(11), Some(Admission::Queued { ahead: 0 }));
+    assert_eq!(manager.admission(20), None);
+}
+
+#[rstest]
+fn raising_the_limits_starts_queued_transfers() {
+    let manager = TransferManager::new(limits(1, 0));
+    let _ = manager.admit(request(1, 10));
+    let _ = manager.admit(request(2, 20));
+
+    assert_eq!(
+        manager.set_limits(TransferLimits::UNLIMITED),
+        [QueueUpdate {
+            request: request(2, 20),
+            admission: Admission::Active,
+        }]
+    );
+}
+
+#[rstest]
+fn cancelling_a_queued_transfer_moves_later_ones_up() {
+    let manager = TransferManager::new(limits(1, 0));
+    let _ = manager.admit(request(1, 10));
+    let _ = manager.admit(request(2, 20));
+    let _ = manager.admit(request(3, 30));
+
+    assert_eq!(
+        manager.finish(20),
+        [QueueUpdate {
+            request: request(3, 30),
+            admission: Admission::Queued { ahead: 0 },
+        }]
+    );
+    assert_eq!(manager.admission(30), Some(Admission::Queued { ahead: 0 }));
+}
+
+#[rstest]
+fn rebinding_moves_only_the_accounts_transfers() {
+    let manager = TransferManager::new(limits(1, 0));
+    let _ = manager.admit(request(1, 10));
+    let _ = manager.admit(request(2, 20));
+    let _ = manager.admit(request(1, 11));
+    let resumed = OutboundConnectionId::new(7);
+
+    assert_eq!(manager.rebind(1, resumed), 2);
+    assert_eq!(manager.rebind(1, resumed), 0);
+
+    let connection = |reference| manager.request(reference).and_then(|r| r.connection);
+    assert_eq!(connection(10), Some(resumed));
+    assert_eq!(connection(11), Some(resumed));
+    assert_eq!(connection(20), None);
+}
+
+#[rstest]
+#[case::active(Admission::Active, 0)]
+#[case::first_in_line(Admission::Queued { ahead: 0 }, 1)]
+#[case::third_in_line(Admission::Queued { ahead: 2 }, 3)]
+fn download_info_reports_the_waiting_count(#[case] admission: Admission, #[case] expected: u32) {
+    let tx = download_info(42, admission).expect("download info");
+    let params = decode_params(&tx.payload).expect("decode params");
+
+    assert_eq!(tx.header.ty, u16::from(TransactionType::DownloadInfo));
+    assert_eq!(
+        params,
+        [
+            (FieldId::ReferenceNumber, 42u32.to_be_bytes().to_vec()),
+            (FieldId::WaitingCount, expected.to_be_bytes().to_vec()),
+        ]
+    );
+}
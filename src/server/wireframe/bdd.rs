@@ -52,14 +52,19 @@ fn then_success(world: &BootstrapWorld) {
     assert_step_ok!(outcome.as_ref().map(|_| ()).map_err(ToString::to_string));
 }
 
-#[then("the resolved bind address is \"{bind}\"")]
+#[then("the resolved bind addresses are \"{bind}\"")]
 fn then_matches_bind(world: &BootstrapWorld, bind: String) {
     let outcome_ref = world.outcome.borrow();
     let Some(outcome) = outcome_ref.as_ref() else {
         panic!("bootstrap not executed");
     };
     let bootstrap = assert_step_ok!(outcome.as_ref().map_err(ToString::to_string));
-    assert_eq!(bootstrap.bind_addr.to_string(), bind);
+    let resolved: Vec<String> = bootstrap
+        .bind_addrs
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(resolved.join(", "), bind);
 }
 
 #[then("bootstrap fails with message \"{message}\"")]
@@ -79,4 +84,7 @@ fn then_failure(world: &BootstrapWorld, message: String) {
 fn accepts_bind(world: BootstrapWorld) { let _ = world; }
 
 #[scenario(path = "tests/features/wireframe_server.feature", index = 1)]
+fn accepts_dual_stack_bind(world: BootstrapWorld) { let _ = world; }
+
+#[scenario(path = "tests/features/wireframe_server.feature", index = 2)]
 fn rejects_bind(world: BootstrapWorld) { let _ = world; }
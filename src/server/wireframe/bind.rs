@@ -0,0 +1,57 @@
+//! Accept pacing and descriptor exhaustion for the Wireframe listener.
+//!
+//! Wireframe's accept loop backs off on its own but cannot report why an
+//! accept failed. [`watch_descriptors`] keeps a duplicate of each listener and
+//! probes for free descriptors beside it, so the Wireframe runtime warns the
+//! operator, sheds pending connections, and counts exhaustion in
+//! [`AcceptMetrics`] as the legacy runtime does.
+
+use std::{net::TcpListener as StdTcpListener, sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use tokio::{net::TcpListener, task::JoinHandle, time::sleep};
+use wireframe::server::BackoffConfig;
+
+use crate::server::accept::{AcceptGuard, AcceptMetrics, PAUSE_INITIAL, PAUSE_MAX};
+
+/// Time between descriptor probes while descriptors are available.
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pace wireframe's own accept-failure backoff with the bounds used by
+/// [`crate::server::accept`] in the legacy runtime.
+pub(super) const fn accept_backoff() -> BackoffConfig {
+    BackoffConfig {
+        initial_delay: PAUSE_INITIAL,
+        max_delay: PAUSE_MAX,
+    }
+}
+
+/// Watch for descriptor exhaustion on behalf of the Wireframe server serving
+/// `listener`, recording into `metrics`, until the returned task is aborted.
+///
+/// # Errors
+///
+/// Returns an error if the listener cannot be duplicated.
+pub(super) fn watch_descriptors(
+    listener: &StdTcpListener,
+    metrics: Arc<AcceptMetrics>,
+) -> Result<JoinHandle<()>> {
+    let listener = listener
+        .try_clone()
+        .and_then(TcpListener::from_std)
+        .context("failed to duplicate listener for descriptor watch")?;
+    Ok(tokio::spawn(watch(listener, AcceptGuard::new(metrics))))
+}
+
+async fn watch(listener: TcpListener, mut guard: AcceptGuard) {
+    loop {
+        let pause = match guard.probe() {
+            Some(pause) => {
+                guard.shed_pending(&listener);
+                pause
+            }
+            None => PROBE_INTERVAL,
+        };
+        sleep(pause).await;
+    }
+}
@@ -4,18 +4,22 @@
 //! one in-flight logical transaction per connection, matching the legacy
 //! sequential framing model. All three budget dimensions therefore collapse to
 //! the same logical transaction envelope: a normalized 20-byte header plus the
-//! maximum 1 MiB assembled payload.
+//! largest payload the server's limits let any transaction type carry.
 
 use std::num::NonZeroUsize;
 
 use wireframe::app::{BudgetBytes, MemoryBudgets};
 
-use crate::wireframe::message_assembly::HOTLINE_LOGICAL_MESSAGE_BYTES;
+use crate::{
+    transaction::PayloadLimits,
+    wireframe::message_assembly::hotline_logical_message_bytes,
+};
 
-/// Build the explicit Wireframe memory budgets for the Hotline adapter.
+/// Build the explicit Wireframe memory budgets for the Hotline adapter under
+/// `limits`.
 #[must_use]
-pub(crate) const fn explicit_memory_budgets() -> MemoryBudgets {
-    let logical_message_bytes = non_zero(HOTLINE_LOGICAL_MESSAGE_BYTES);
+pub(crate) fn explicit_memory_budgets(limits: &PayloadLimits) -> MemoryBudgets {
+    let logical_message_bytes = non_zero(hotline_logical_message_bytes(limits));
     let budget = BudgetBytes::new(logical_message_bytes);
     MemoryBudgets::new(budget, budget, budget)
 }
@@ -37,25 +41,26 @@ mod tests {
 
     #[test]
     fn budgets_match_one_full_hotline_logical_message() {
-        let budgets = explicit_memory_budgets();
+        let limits = PayloadLimits::new();
+        let budgets = explicit_memory_budgets(&limits);
 
         assert_eq!(
             budgets.bytes_per_message().as_usize(),
-            HOTLINE_LOGICAL_MESSAGE_BYTES
+            hotline_logical_message_bytes(&limits)
         );
         assert_eq!(
             budgets.bytes_per_connection().as_usize(),
-            HOTLINE_LOGICAL_MESSAGE_BYTES
+            hotline_logical_message_bytes(&limits)
         );
         assert_eq!(
             budgets.bytes_in_flight().as_usize(),
-            HOTLINE_LOGICAL_MESSAGE_BYTES
+            hotline_logical_message_bytes(&limits)
         );
     }
 
     #[test]
     fn budgets_preserve_non_zero_and_relation_invariants() {
-        let budgets = explicit_memory_budgets();
+        let budgets = explicit_memory_budgets(&PayloadLimits::new());
 
         assert!(budgets.bytes_per_message().as_usize() > 0);
         assert_eq!(
@@ -0,0 +1,146 @@
+//! Dual-runtime mode for migrating from the legacy runtime.
+//!
+//! When `legacy_bind` is set, the Wireframe server also runs the legacy
+//! accept loop on that address. Both listeners share the database pool and
+//! password hasher, so the same accounts, files, and news are served by both
+//! stacks while operators compare them through the `runtime` label on
+//! [`crate::server::metrics`] events. They also share one [`ServerSettings`],
+//! so a host's requests count against one rate limit whichever listener
+//! carries them. Presence and chat stay per-runtime: users only see peers
+//! connected through the same listener. Accept counters are shared, so the
+//! health endpoint reports the process as a whole.
+
+use std::sync::Arc;
+
+use thiserror::Error;
+use tokio::task::JoinHandle;
+
+use super::AppResources;
+use crate::server::{AppConfig, accept::AcceptMetrics};
+
+/// Reasons dual-runtime mode cannot start.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub(super) enum DualRuntimeError {
+    /// The binary was built without the legacy runtime.
+    #[error("legacy_bind requires a build with the legacy-networking feature")]
+    Unsupported,
+    /// Both runtimes were asked to listen on the same address.
+    #[error("legacy_bind must differ from bind ({0})")]
+    SameAddress(String),
+}
+
+/// Check that `config` asks for a dual-runtime setup this binary can run.
+///
+/// Overlap between address lists is left to the operating system, which
+/// refuses the second bind.
+pub(super) fn validate(config: &AppConfig) -> Result<(), DualRuntimeError> {
+    let Some(legacy_bind) = config.legacy_bind.as_deref() else {
+        return Ok(());
+    };
+    if !cfg!(feature = "legacy-networking") {
+        return Err(DualRuntimeError::Unsupported);
+    }
+    if legacy_bind == config.bind {
+        return Err(DualRuntimeError::SameAddress(legacy_bind.to_owned()));
+    }
+    Ok(())
+}
+
+/// Bind the legacy listeners named by `legacy_bind`, if any, and serve them
+/// on a background task until shutdown with the pool, hasher, and settings
+/// from `resources`, counting accepts in `metrics`.
+///
+/// # Errors
+///
+/// Returns an error if the legacy address cannot be bound.
+#[cfg(feature = "legacy-networking")]
+pub(super) async fn spawn_legacy_listener(
+    config: &AppConfig,
+    resources: &AppResources,
+    metrics: &Arc<AcceptMetrics>,
+) -> anyhow::Result<Option<JoinHandle<anyhow::Result<()>>>> {
+    use anyhow::Context;
+
+    use crate::server::{
+        bind::bind_listeners,
+        legacy::{ServerResources, accept_connections},
+        logging::announce_listening,
+    };
+
+    let Some(bind) = config.legacy_bind.as_deref() else {
+        return Ok(None);
+    };
+    let listeners = bind_listeners(bind)
+        .with_context(|| format!("failed to bind legacy listener to {bind}"))?;
+    for listener in &listeners {
+        announce_listening("mxd", &listener.local_addr()?);
+    }
+    let legacy = ServerResources::new(
+        resources.pool.clone(),
+        Arc::clone(&resources.argon2),
+        Arc::clone(&resources.settings),
+    );
+    let task = accept_connections(listeners, legacy, Arc::clone(metrics));
+    Ok(Some(tokio::spawn(task)))
+}
+
+/// Without the legacy runtime there is never a second listener; [`validate`]
+/// has already rejected `legacy_bind`.
+#[cfg(not(feature = "legacy-networking"))]
+#[expect(
+    clippy::unused_async,
+    reason = "matches the signature of the legacy-networking variant"
+)]
+pub(super) async fn spawn_legacy_listener(
+    _config: &AppConfig,
+    _resources: &AppResources,
+    _metrics: &Arc<AcceptMetrics>,
+) -> anyhow::Result<Option<JoinHandle<anyhow::Result<()>>>> {
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    //! Validating dual-runtime configuration.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    fn config(legacy_bind: Option<&str>) -> AppConfig {
+        AppConfig {
+            bind: "127.0.0.1:5500".to_owned(),
+            legacy_bind: legacy_bind.map(str::to_owned),
+            ..AppConfig::default()
+        }
+    }
+
+    #[rstest]
+    fn single_runtime_needs_no_checks() {
+        assert_eq!(validate(&config(None)), Ok(()));
+    }
+
+    #[cfg(feature = "legacy-networking")]
+    #[rstest]
+    fn accepts_distinct_legacy_address() {
+        assert_eq!(validate(&config(Some("127.0.0.1:5600"))), Ok(()));
+    }
+
+    #[cfg(feature = "legacy-networking")]
+    #[rstest]
+    fn rejects_shared_address() {
+        assert_eq!(
+            validate(&config(Some("127.0.0.1:5500"))),
+            Err(DualRuntimeError::SameAddress("127.0.0.1:5500".to_owned()))
+        );
+    }
+
+    #[cfg(not(feature = "legacy-networking"))]
+    #[rstest]
+    fn rejects_legacy_bind_without_legacy_runtime() {
+        assert_eq!(
+            validate(&config(Some("127.0.0.1:5600"))),
+            Err(DualRuntimeError::Unsupported)
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! One Wireframe listener per bind address.
+//!
+//! A [`wireframe::server::WireframeServer`] serves a single listener, so the
+//! bootstrap builds one server for each address in `bind`, all sharing the
+//! same app factory and presence registry, and runs them against one shutdown
+//! future. Each address gets a TLS front end when TLS is configured, its own
+//! startup banner, and its own transfer port, served over TLS too when it is
+//! configured. Every public accept loop, the
+//! TLS front end's or Wireframe's own through a descriptor watch, records
+//! into one shared [`AcceptMetrics`].
+
+use std::{
+    net::{SocketAddr, TcpListener as StdTcpListener},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+
+use super::{bind::watch_descriptors, tls_front::TlsFront};
+use crate::{
+    server::{
+        accept::AcceptMetrics,
+        bind::bind_std_listener,
+        logging::announce_listening,
+        settings::ServerSettings,
+        tasks::BackgroundTasks,
+        transfer_port::start_transfer_port,
+    },
+    wireframe::handshake::Admission,
+};
+
+/// State every listener shares, whichever address it serves.
+#[derive(Clone, Copy)]
+pub(super) struct ListenerShared<'a> {
+    /// Settings handed to each connection, including the connection limits.
+    pub(super) settings: &'a Arc<ServerSettings>,
+    /// Accept counters every public accept loop records into.
+    pub(super) metrics: &'a Arc<AcceptMetrics>,
+    /// Cancelled to close the transfer ports.
+    pub(super) stop: &'a CancellationToken,
+}
+
+/// Bind every address in `binds`, handing each listener to `serve` to build
+/// a bound server that reports its local address. `serve` is told how the
+/// listener admits clients against the connection limits in
+/// `shared.settings`: directly, or through a TLS front end that admits them
+/// for it.
+///
+/// Background work for each address, TLS relays or descriptor watches and
+/// transfer ports, is added to `tasks`; the transfer ports close when
+/// `shared.stop` is cancelled.
+///
+/// # Errors
+///
+/// Returns the first error raised while binding an address or building its
+/// server.
+pub(super) async fn bind_all<T>(
+    binds: &[SocketAddr],
+    tasks: &mut BackgroundTasks,
+    shared: ListenerShared<'_>,
+    mut serve: impl FnMut(StdTcpListener, Admission) -> Result<(T, SocketAddr)>,
+) -> Result<Vec<T>> {
+    let ListenerShared {
+        settings,
+        metrics,
+        stop,
+    } = shared;
+    let mut servers = Vec::with_capacity(binds.len());
+    for &public in binds {
+        let (listen_addr, front) = TlsFront::bind(public, settings.transport.tls.clone()).await?;
+        let listener = bind_std_listener(listen_addr)?;
+        let admission = if front.is_none() {
+            tasks.extend([watch_descriptors(&listener, Arc::clone(metrics))?]);
+            Admission::Direct(Arc::clone(settings))
+        } else {
+            Admission::Relayed
+        };
+        let (server, local) = serve(listener, admission)?;
+        let addr = TlsFront::start(front, local, tasks, shared)?;
+        announce_listening("mxd-wireframe-server", &addr);
+        tasks.extend(start_transfer_port(addr, stop.clone(), Arc::clone(settings)).await);
+        servers.push(server);
+    }
+    Ok(servers)
+}
@@ -13,7 +13,15 @@
 //! 2. Creates a shared Argon2 instance for password hashing
 //! 3. Builds a `WireframeServer` with Hotline preamble hooks
 //! 4. Registers the Hotline frame codec and routes
-//! 5. Binds and runs the server
+//! 5. Binds and runs one server per `bind` address, plus the legacy listener when `legacy_bind`
+//!    asks for dual-runtime mode; with TLS configured, Wireframe binds loopback behind a TLS front
+//!    end
+//!
+//! Hotline frames bypass wireframe's message serializer because the
+//! transaction middleware decodes and encodes them itself. The serializer is
+//! still a type parameter of [`HotlineApp`], so protocol experiments (such as
+//! a JSON debug transport) can start the same routing stack through
+//! [`run_daemon_with_serializer`] without touching the Hotline codec.
 //!
 //! [`HotlineFrameCodec`]: crate::wireframe::codec::HotlineFrameCodec
 
@@ -21,38 +29,62 @@
     clippy::shadow_reuse,
     reason = "intentional shadowing for server building"
 )]
-#![expect(
-    clippy::print_stdout,
-    reason = "intentional console output for server status"
-)]
 
+mod bind;
 mod budgets;
+mod dual;
+mod listeners;
+mod shutdown;
+mod tls_front;
 
 use std::{
-    io::{self, Write},
-    net::{Ipv4Addr, SocketAddr, SocketAddrV4, ToSocketAddrs},
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
     sync::Arc,
 };
 
 use anyhow::{Context, Result, anyhow};
 use argon2::Argon2;
+use futures_util::{FutureExt, future::try_join_all};
 use thiserror::Error;
 use tokio::sync::Mutex as TokioMutex;
-use tracing::warn;
+use tracing::info;
 use wireframe::{
     app::{Envelope, Handler, WireframeApp},
-    serializer::BincodeSerializer,
+    serializer::{BincodeSerializer, Serializer},
     server::WireframeServer,
 };
 
+use self::{
+    bind::accept_backoff,
+    shutdown::{ShutdownController, notify_then_stop},
+};
 use super::{AppConfig, ResolvedCli, load_cli};
 use crate::{
-    db::{DbPool, establish_pool},
+    db::{DbPool, establish_pool_with, log_pool_metrics},
     handler::Session,
     presence::PresenceRegistry,
     protocol,
-    server::admin,
+    server::{
+        NetworkRuntime,
+        accept::{AcceptMetrics, log_accept_metrics},
+        admin,
+        bans::start_ban_refresh,
+        bind::parse_bind_addr,
+        connection_limit::ConnectionSlot,
+        db_pool::pool_settings_from_config,
+        health::start_health_server,
+        idle::ActivityClock,
+        metrics::{log_runtime_metrics, runtime_metrics},
+        news_fsck::repair_news_on_startup,
+        outbox::start_outbox_dispatcher,
+        ping::PingTracker,
+        settings::{ServerSettings, TransportSettings},
+        shutdown::shutdown_grace,
+        tasks::BackgroundTasks,
+        transfer_stats::TransferStatsFlusher,
+    },
     wireframe::{
+        closer::SocketCloser,
         codec::HotlineFrameCodec,
         compat::XorCompatibility,
         compat_policy::ClientCompatibility,
@@ -72,7 +104,16 @@ use crate::{
     },
 };
 
-type HotlineApp = WireframeApp<BincodeSerializer, (), Envelope, HotlineFrameCodec>;
+/// Wireframe application serving Hotline transactions with serializer `S`.
+pub type HotlineApp<S = BincodeSerializer> = WireframeApp<S, (), Envelope, HotlineFrameCodec>;
+
+/// Serializers that can back a [`HotlineApp`].
+///
+/// Blanket-implemented for every wireframe [`Serializer`] that can be built
+/// with [`Default`] once per connection.
+pub trait HotlineSerializer: Serializer + Default + Send + Sync + 'static {}
+
+impl<S> HotlineSerializer for S where S: Serializer + Default + Send + Sync + 'static {}
 
 #[derive(Debug, Error)]
 enum AppFactoryError {
@@ -80,6 +121,8 @@ enum AppFactoryError {
     MissingHandshakeContext,
     #[error("peer address missing in app factory")]
     MissingPeerAddress,
+    #[error("server is shutting down")]
+    ShuttingDown,
     #[error("failed to build wireframe application")]
     BuildApplication(#[source] anyhow::Error),
 }
@@ -110,136 +153,223 @@ pub async fn run_with_cli(cli: ResolvedCli) -> Result<()> {
 }
 
 async fn run_daemon(config: AppConfig) -> Result<()> {
+    run_daemon_with_serializer::<BincodeSerializer>(config).await
+}
+
+/// Run the Wireframe daemon with a caller-chosen message serializer.
+///
+/// Routing, middleware, and the Hotline codec are identical to the default
+/// runtime; only the serializer type carried by each [`HotlineApp`] changes.
+///
+/// # Errors
+///
+/// Returns any error raised while preparing the database or binding the
+/// Wireframe listener.
+pub async fn run_daemon_with_serializer<S: HotlineSerializer>(config: AppConfig) -> Result<()> {
     let bootstrap = WireframeBootstrap::prepare(config)?;
-    bootstrap.run().await
+    bootstrap.run::<S>().await
 }
 
 #[derive(Clone, Debug)]
 struct WireframeBootstrap {
-    bind_addr: SocketAddr,
+    bind_addrs: Vec<SocketAddr>,
     config: Arc<AppConfig>,
 }
 
 impl WireframeBootstrap {
     fn prepare(config: AppConfig) -> Result<Self> {
-        let bind_addr = parse_bind_addr(&config.bind)?;
+        let bind_addrs = parse_bind_addr(&config.bind)?;
+        dual::validate(&config)?;
         Ok(Self {
-            bind_addr,
+            bind_addrs,
             config: Arc::new(config),
         })
     }
 
-    async fn run(self) -> Result<()> {
-        let Self { bind_addr, config } = self;
-        println!("mxd-wireframe-server using database {}", config.database);
-        println!("mxd-wireframe-server binding to {}", config.bind);
+    async fn run<S: HotlineSerializer>(self) -> Result<()> {
+        let Self { bind_addrs, config } = self;
+        info!(database = %config.database, bind = %config.bind, "starting wireframe server");
 
-        let pool = establish_pool(&config.database)
+        let pool_settings = pool_settings_from_config(&config)?;
+        let pool = establish_pool_with(&config.database, &pool_settings)
             .await
             .context("failed to establish database pool")?;
         let argon2 = Arc::new(admin::argon2_from_config(&config)?);
+        let settings = Arc::new(super::configure_process(&config).await?);
+        repair_news_on_startup(&pool, &config).await;
+        let mut tasks = BackgroundTasks::default();
+        let bans = Arc::clone(&settings.address_bans);
+        tasks.extend([start_ban_refresh(pool.clone(), bans).await]);
+        tasks.start_configured(&pool, &settings).await?;
+        let accept_metrics = Arc::new(AcceptMetrics::default());
+        tasks.extend(start_health_server(
+            settings.background.health_bind,
+            &pool,
+            &config.database,
+            Arc::clone(&accept_metrics),
+        )?);
 
         let outbound_registry = Arc::new(WireframeOutboundRegistry::default());
-        let presence = Arc::new(PresenceRegistry::default());
-        validate_app_factory(&pool, &argon2, &outbound_registry, &presence)
-            .context("failed to validate wireframe app factory")?;
-        let app_factory = {
-            let pool = pool.clone();
-            let argon2 = Arc::clone(&argon2);
-            let outbound_registry = Arc::clone(&outbound_registry);
-            let presence = Arc::clone(&presence);
-            move || build_app_for_connection(&pool, &argon2, &outbound_registry, &presence)
+        let presence = Arc::new(PresenceRegistry::with_accounting(Arc::clone(
+            &settings.accounting,
+        )));
+        let resources = AppResources {
+            pool: pool.clone(),
+            argon2,
+            outbound_registry: Arc::clone(&outbound_registry),
+            presence: Arc::clone(&presence),
+            settings: Arc::clone(&settings),
         };
-
-        let server = WireframeServer::new(app_factory).with_preamble::<HotlinePreamble>();
-        let server = handshake::install(server, protocol::HANDSHAKE_TIMEOUT);
-        let server = server
-            .bind(bind_addr)
-            .context("failed to bind wireframe server")?;
-        let addr = server
-            .local_addr()
-            .ok_or_else(|| anyhow!("failed to get local address"))?;
-
-        announce_listening(addr);
-
-        server.run().await.context("wireframe server terminated")?;
+        validate_app_factory::<S>(&resources)
+            .context("failed to validate wireframe app factory")?;
+        let transfer_stats = TransferStatsFlusher::start(pool.clone(), Arc::clone(&presence));
+        settings
+            .transfers
+            .manager()
+            .set_messaging(Some(Arc::clone(&outbound_registry)));
+        tasks.extend([start_outbox_dispatcher(
+            pool.clone(),
+            Arc::clone(&outbound_registry),
+            Arc::clone(&presence),
+        )]);
+        let shutdown = ShutdownController::default();
+        let app_factory = shutdown.guard({
+            let resources = resources.clone();
+            let accept_metrics = Arc::clone(&accept_metrics);
+            move || {
+                accept_metrics.record_accepted();
+                build_app_for_connection::<S>(&resources)
+            }
+        });
+
+        let servers = listeners::bind_all(
+            &bind_addrs,
+            &mut tasks,
+            listeners::ListenerShared {
+                settings: &settings,
+                metrics: &accept_metrics,
+                stop: &shutdown.token(),
+            },
+            |listener, admission| {
+                let server =
+                    WireframeServer::new(app_factory.clone()).with_preamble::<HotlinePreamble>();
+                let server = handshake::install(server, protocol::HANDSHAKE_TIMEOUT, admission)
+                    .accept_backoff(accept_backoff())
+                    .bind_existing_listener(listener)
+                    .context("failed to bind wireframe server")?;
+                let addr = server
+                    .local_addr()
+                    .ok_or_else(|| anyhow!("failed to get local address"))?;
+                Ok((server, addr))
+            },
+        )
+        .await?;
+        let legacy = dual::spawn_legacy_listener(&config, &resources, &accept_metrics).await?;
+
+        // Every listener stops on the same signal; the shared future runs the
+        // drain once, whichever server polls it first.
+        let stop = notify_then_stop(
+            shutdown,
+            outbound_registry,
+            Arc::clone(&settings.transfers),
+            shutdown_grace(&config),
+        )
+        .boxed()
+        .shared();
+        try_join_all(
+            servers
+                .into_iter()
+                .map(|server| server.run_with_shutdown(stop.clone())),
+        )
+        .await
+        .context("wireframe server terminated")?;
+        tasks.abort_all();
+        transfer_stats.stop().await;
+        log_runtime_metrics(NetworkRuntime::Wireframe);
+        if let Some(legacy) = legacy {
+            legacy.await.context("legacy listener task failed")??;
+        }
+        log_accept_metrics(NetworkRuntime::Wireframe, &accept_metrics);
+        log_pool_metrics(&pool);
         Ok(())
     }
 }
 
-fn announce_listening(addr: SocketAddr) {
-    println!("mxd-wireframe-server listening on {addr}");
-    // Explicit flush ensures the message reaches piped stdout immediately,
-    // which is critical for test harness readiness detection.
-    if let Err(error) = io::stdout().flush() {
-        warn!(%error, "failed to flush stdout");
-    }
+/// Shared state every connection's app is built from.
+#[derive(Clone)]
+struct AppResources {
+    pool: DbPool,
+    argon2: Arc<Argon2<'static>>,
+    outbound_registry: Arc<WireframeOutboundRegistry>,
+    presence: Arc<PresenceRegistry>,
+    settings: Arc<ServerSettings>,
 }
 
-fn build_app_for_connection(
-    pool: &DbPool,
-    argon2: &Arc<Argon2<'static>>,
-    outbound_registry: &Arc<WireframeOutboundRegistry>,
-    presence: &Arc<PresenceRegistry>,
-) -> std::result::Result<HotlineApp, AppFactoryError> {
-    try_build_app(pool, argon2, outbound_registry, presence)
+fn build_app_for_connection<S: HotlineSerializer>(
+    resources: &AppResources,
+) -> std::result::Result<HotlineApp<S>, AppFactoryError> {
+    runtime_metrics(NetworkRuntime::Wireframe).record_connection();
+    try_build_app(resources)
 }
 
-fn try_build_app(
-    pool: &DbPool,
-    argon2: &Arc<Argon2<'static>>,
-    outbound_registry: &Arc<WireframeOutboundRegistry>,
-    presence: &Arc<PresenceRegistry>,
-) -> std::result::Result<HotlineApp, AppFactoryError> {
-    let build_context = build_app_context(pool, argon2, outbound_registry, presence)?;
+fn try_build_app<S: HotlineSerializer>(
+    resources: &AppResources,
+) -> std::result::Result<HotlineApp<S>, AppFactoryError> {
+    let build_context = build_app_context(resources)?;
     map_build_application_result(build_app(build_context))
 }
 
-fn build_app_context<'a>(
-    pool: &'a DbPool,
-    argon2: &'a Arc<Argon2<'static>>,
-    outbound_registry: &'a Arc<WireframeOutboundRegistry>,
-    presence: &'a Arc<PresenceRegistry>,
-) -> std::result::Result<AppBuildContext<'a>, AppFactoryError> {
+fn build_app_context(
+    resources: &AppResources,
+) -> std::result::Result<AppBuildContext<'_>, AppFactoryError> {
     // Missing connection context indicates handshake setup failed; abort the
     // connection rather than running without routing state. Returning a
     // degraded app would accept traffic with broken routing and state.
     let context = take_current_context().ok_or(AppFactoryError::MissingHandshakeContext)?;
-    let (handshake, peer) = context.into_parts();
+    let (handshake, peer, slot, closer) = context.into_parts();
     let peer = peer.ok_or(AppFactoryError::MissingPeerAddress)?;
-    let compat = Arc::new(XorCompatibility::from_handshake(&handshake));
+    let compat = Arc::new(XorCompatibility::from_handshake(
+        &handshake,
+        resources.settings.transport.xor,
+    ));
     let client_compat = Arc::new(ClientCompatibility::from_handshake(&handshake));
     Ok(AppBuildContext {
-        pool,
-        argon2,
-        outbound_registry,
-        presence,
+        resources,
         peer,
+        slot,
+        closer,
         compat,
         client_compat,
     })
 }
 
 struct AppBuildContext<'a> {
-    pool: &'a DbPool,
-    argon2: &'a Arc<Argon2<'static>>,
-    outbound_registry: &'a Arc<WireframeOutboundRegistry>,
-    presence: &'a Arc<PresenceRegistry>,
+    resources: &'a AppResources,
     peer: SocketAddr,
+    slot: Option<ConnectionSlot>,
+    closer: Option<Arc<SocketCloser>>,
     compat: Arc<XorCompatibility>,
     client_compat: Arc<ClientCompatibility>,
 }
 
-fn build_app(context: AppBuildContext<'_>) -> wireframe::app::Result<HotlineApp> {
+fn build_app<S: HotlineSerializer>(
+    context: AppBuildContext<'_>,
+) -> wireframe::app::Result<HotlineApp<S>> {
     let AppBuildContext {
-        pool,
-        argon2,
-        outbound_registry,
-        presence,
+        resources,
         peer,
+        slot,
+        closer,
         compat,
         client_compat,
     } = context;
+    let AppResources {
+        pool,
+        argon2,
+        outbound_registry,
+        presence,
+        settings,
+    } = resources;
     let outbound_id = outbound_registry.allocate_id();
     let session = Arc::new(TokioMutex::new(Session::default()));
     let outbound_connection = Arc::new(WireframeOutboundConnection::new_with_runtime_handle(
@@ -248,18 +378,32 @@ fn build_app(context: AppBuildContext<'_>) -> wireframe::app::Result<HotlineApp>
         Arc::clone(presence),
         Some(tokio::runtime::Handle::current()),
     ));
-    let outbound_messaging = WireframeOutboundMessaging::new(Arc::clone(&outbound_connection));
-    let router = WireframeRouter::new(Arc::clone(&compat), client_compat);
+    let activity = Arc::new(ActivityClock::new());
+    presence.attach_connection(outbound_id, peer.ip(), Arc::clone(&activity));
+    let pings = Arc::new(PingTracker::default());
+    presence.attach_pings(outbound_id, Arc::clone(&pings));
+    let transport = &settings.transport;
+    watch_connection(&outbound_connection, &activity, &pings, transport);
+    let outbound_messaging = WireframeOutboundMessaging::new(Arc::clone(&outbound_connection))
+        .with_write_timeout(transport.io_timeouts.write);
+    let codec = HotlineFrameCodec::with_frame_limit(client_compat.frame_limit())
+        .with_read_timeout(transport.io_timeouts.read)
+        .with_payload_limits(transport.payload_limits.clone());
     let protocol = HotlineProtocol::new(
         pool.clone(),
         Arc::clone(argon2),
         outbound_connection,
         Arc::clone(&compat),
-    );
+    )
+    .with_client_compat(Arc::clone(&client_compat))
+    .with_closer(closer);
+    let router = WireframeRouter::new(Arc::clone(&compat), client_compat);
 
-    let app = HotlineApp::default()
+    // Swapping the codec resets fragmentation, so it goes first.
+    let app = HotlineApp::<S>::default()
+        .with_codec(codec)
         .fragmentation(None)
-        .memory_budgets(budgets::explicit_memory_budgets())
+        .memory_budgets(budgets::explicit_memory_budgets(&transport.payload_limits))
         .with_message_assembler(HotlineMessageAssembler::new())
         .with_protocol(protocol)
         .wrap(TransactionMiddleware::new(TransactionMiddlewareConfig {
@@ -270,6 +414,10 @@ fn build_app(context: AppBuildContext<'_>) -> wireframe::app::Result<HotlineApp>
             messaging: Arc::new(outbound_messaging),
             presence: Arc::clone(presence),
             presence_connection_id: outbound_id,
+            activity,
+            pings,
+            settings: Arc::clone(settings),
+            slot,
         }))?;
 
     let handler = routing_placeholder_handler();
@@ -279,31 +427,40 @@ fn build_app(context: AppBuildContext<'_>) -> wireframe::app::Result<HotlineApp>
         .try_fold(app, |app, id| app.route(*id, handler.clone()))
 }
 
-fn map_build_application_result(
-    result: wireframe::app::Result<HotlineApp>,
-) -> std::result::Result<HotlineApp, AppFactoryError> {
+/// Start the idle reaper and pinger `transport` asks for on `connection`.
+fn watch_connection(
+    connection: &Arc<WireframeOutboundConnection>,
+    activity: &Arc<ActivityClock>,
+    pings: &Arc<PingTracker>,
+    transport: &TransportSettings,
+) {
+    if let Some(window) = transport.idle_timeout {
+        connection.spawn_idle_reaper(Arc::clone(activity), window);
+    }
+    if let Some(policy) = transport.ping {
+        connection.spawn_pinger(Arc::clone(pings), policy);
+    }
+}
+
+fn map_build_application_result<S: HotlineSerializer>(
+    result: wireframe::app::Result<HotlineApp<S>>,
+) -> std::result::Result<HotlineApp<S>, AppFactoryError> {
     result.map_err(|e| AppFactoryError::BuildApplication(anyhow!("wireframe error: {e}")))
 }
 
-fn validate_app_factory(
-    pool: &DbPool,
-    argon2: &Arc<Argon2<'static>>,
-    outbound_registry: &Arc<WireframeOutboundRegistry>,
-    presence: &Arc<PresenceRegistry>,
-) -> Result<()> {
+fn validate_app_factory<S: HotlineSerializer>(resources: &AppResources) -> Result<()> {
     let peer = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0));
     let build_context = AppBuildContext {
-        pool,
-        argon2,
-        outbound_registry,
-        presence,
+        resources,
         peer,
+        slot: None,
+        closer: None,
         compat: Arc::new(XorCompatibility::disabled()),
         client_compat: Arc::new(ClientCompatibility::from_handshake(
             &HandshakeMetadata::default(),
         )),
     };
-    build_app(build_context)
+    build_app::<S>(build_context)
         .map_err(|e| anyhow!("failed to build wireframe application: {e}"))
         .context("failed to register routes or middleware")?;
     Ok(())
@@ -314,22 +471,6 @@ fn routing_placeholder_handler() -> Handler<Envelope> {
     Arc::new(|_: &Envelope| Box::pin(async {}))
 }
 
-fn parse_bind_addr(target: &str) -> Result<SocketAddr> {
-    target
-        .parse()
-        .or_else(|_| resolve_hostname(target))
-        .with_context(|| format!("invalid bind address '{target}'"))
-}
-
-fn resolve_hostname(target: &str) -> Result<SocketAddr> {
-    let mut addrs = target
-        .to_socket_addrs()
-        .with_context(|| format!("failed to resolve '{target}'"))?;
-    addrs
-        .next()
-        .ok_or_else(|| anyhow!("failed to resolve '{target}'"))
-}
-
 #[cfg(test)]
 mod tests;
 
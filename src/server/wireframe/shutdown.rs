@@ -0,0 +1,119 @@
+//! Stopping the Wireframe listener on a shutdown signal.
+//!
+//! Once Ctrl+C or `SIGTERM` arrives, the [`ShutdownController`] makes the app
+//! factory refuse new connections and closes the transfer ports, every
+//! connection is sent a Disconnect
+//! Message with [`SHUTDOWN_REASON`], and transfers already running on the
+//! transfer port get the configured grace period to finish. Only then does
+//! the shutdown future resolve and wireframe stop its workers.
+
+use std::{sync::Arc, time::Duration};
+
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use super::AppFactoryError;
+use crate::{
+    server::{
+        disconnect::{DRAIN_WINDOW, SHUTDOWN_REASON},
+        shutdown::shutdown_signal,
+        transfer_port::TransferRegistry,
+    },
+    wireframe::outbound::WireframeOutboundRegistry,
+};
+
+/// Shared flag telling the app factory that the server is stopping.
+#[derive(Clone, Debug, Default)]
+pub(super) struct ShutdownController {
+    stopping: CancellationToken,
+}
+
+impl ShutdownController {
+    /// Returns true once new connections should be refused.
+    pub(super) fn is_stopping(&self) -> bool { self.stopping.is_cancelled() }
+
+    /// Token cancelled when the server starts stopping, for background
+    /// listeners such as the transfer ports.
+    pub(super) fn token(&self) -> CancellationToken { self.stopping.clone() }
+
+    /// Wrap the app `factory` so it refuses new connections once stopping.
+    ///
+    /// One guarded factory is shared by every listener, so all of them stop
+    /// admitting clients together.
+    pub(super) fn guard<A>(
+        &self,
+        factory: impl Fn() -> Result<A, AppFactoryError> + Clone + Send + Sync + 'static,
+    ) -> impl Fn() -> Result<A, AppFactoryError> + Clone + Send + Sync + 'static {
+        let controller = self.clone();
+        move || {
+            if controller.is_stopping() {
+                return Err(AppFactoryError::ShuttingDown);
+            }
+            factory()
+        }
+    }
+
+    /// Refuse new connections and transfers, tell connected clients the
+    /// server is going away, and wait for running transfers until `grace`
+    /// elapses.
+    ///
+    /// Each connection receives its Disconnect Message behind its queued
+    /// frames; the drain window, which overlaps the transfer grace period,
+    /// gives connection actors time to flush them before wireframe cancels
+    /// the workers and drops the sockets.
+    pub(super) async fn stop(
+        &self,
+        outbound_registry: &WireframeOutboundRegistry,
+        transfers: &TransferRegistry,
+        grace: Duration,
+    ) {
+        self.stopping.cancel();
+        outbound_registry.notify_disconnect(SHUTDOWN_REASON).await;
+        let ((), drained) = tokio::join!(tokio::time::sleep(DRAIN_WINDOW), transfers.drain(grace));
+        if !drained {
+            warn!(
+                ?grace,
+                "transfers still running when the shutdown grace period ended"
+            );
+        }
+    }
+}
+
+/// Resolve once a shutdown signal arrives and `controller` has stopped the
+/// server.
+pub(super) async fn notify_then_stop(
+    controller: ShutdownController,
+    outbound_registry: Arc<WireframeOutboundRegistry>,
+    transfers: Arc<TransferRegistry>,
+    grace: Duration,
+) {
+    shutdown_signal().await;
+    info!("shutdown signal received");
+    controller.stop(&outbound_registry, &transfers, grace).await;
+}
+
+#[cfg(test)]
+mod tests {
+    //! Stopping the server through the controller.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[tokio::test(start_paused = true)]
+    async fn stopping_refuses_new_connections() {
+        let controller = ShutdownController::default();
+        let factory_view = controller.clone();
+        let transfers = controller.token();
+        let registry = WireframeOutboundRegistry::default();
+        assert!(!factory_view.is_stopping());
+
+        controller
+            .stop(&registry, &TransferRegistry::default(), Duration::ZERO)
+            .await;
+
+        assert!(factory_view.is_stopping());
+        assert!(transfers.is_cancelled());
+    }
+}
@@ -34,39 +34,36 @@ fn bound_config() -> AppConfig {
     }
 }
 
-#[rstest]
-#[case("127.0.0.1:6000")]
-#[case("[::1]:7000")]
-fn parses_socket_addrs(#[case] bind: &str) {
-    let addr = parse_bind_addr(bind).expect("bind");
-    assert_eq!(addr.to_string(), bind);
-}
-
-#[rstest]
-#[case("invalid")]
-#[case("127.0.0.1")]
-fn rejects_invalid_addrs(#[case] bind: &str) {
-    let err = parse_bind_addr(bind).expect_err("must fail");
-    assert!(err.to_string().contains("invalid bind address"));
-}
-
-#[rstest]
-fn resolves_hostnames() {
-    let addr = parse_bind_addr("localhost:6010").expect("bind");
-    assert!(addr.ip().is_loopback());
-    assert_eq!(addr.port(), 6010);
-}
-
 #[rstest]
 fn bootstrap_captures_bind(bound_config: AppConfig) {
     let bootstrap = WireframeBootstrap::prepare(bound_config).expect("bootstrap");
     assert_eq!(
-        bootstrap.bind_addr,
-        "127.0.0.1:7777".parse().expect("valid socket address")
+        bootstrap.bind_addrs,
+        vec!["127.0.0.1:7777".parse().expect("valid socket address")]
     );
     assert_eq!(bootstrap.config.bind, "127.0.0.1:7777");
 }
 
+#[rstest]
+fn bootstrap_captures_every_bind_address() {
+    let config = AppConfig {
+        bind: "127.0.0.1:7777,[::1]:7777".to_string(),
+        ..AppConfig::default()
+    };
+    let bootstrap = WireframeBootstrap::prepare(config).expect("bootstrap");
+    assert_eq!(bootstrap.bind_addrs.len(), 2);
+}
+
+fn test_resources() -> AppResources {
+    AppResources {
+        pool: dummy_pool(),
+        argon2: Arc::new(Argon2::default()),
+        outbound_registry: Arc::new(WireframeOutboundRegistry::default()),
+        presence: Arc::new(PresenceRegistry::default()),
+        settings: Arc::new(ServerSettings::default()),
+    }
+}
+
 fn run_factory_with_stored_context(
     stored: Option<ConnectionContext>,
 ) -> std::result::Result<HotlineApp, AppFactoryError> {
@@ -79,10 +76,7 @@ fn run_factory_with_stored_context_and_assertions<F>(
 where
     F: FnOnce(),
 {
-    let pool = dummy_pool();
-    let argon2 = Arc::new(Argon2::default());
-    let outbound_registry = Arc::new(WireframeOutboundRegistry::default());
-    let presence = Arc::new(PresenceRegistry::default());
+    let resources = test_resources();
     let runtime = match Builder::new_current_thread().enable_all().build() {
         Ok(runtime) => runtime,
         Err(err) => return Err(AppFactoryError::BuildApplication(err.into())),
@@ -95,7 +89,7 @@ where
                 let _ = take_current_context();
             }
         }
-        let app = build_app_for_connection(&pool, &argon2, &outbound_registry, &presence);
+        let app = build_app_for_connection(&resources);
         assertions();
         app
     }))
@@ -137,7 +131,9 @@ fn app_factory_rejects_missing_peer_address() {
 #[rstest]
 fn app_factory_wraps_build_application_errors() {
     let duplicate_route = FALLBACK_ROUTE_ID;
-    let result = map_build_application_result(Err(WireframeError::DuplicateRoute(duplicate_route)));
+    let result = map_build_application_result::<BincodeSerializer>(Err(
+        WireframeError::DuplicateRoute(duplicate_route),
+    ));
 
     let Err(AppFactoryError::BuildApplication(error)) = result else {
         panic!("wireframe builder errors must be wrapped in AppFactoryError::BuildApplication");
@@ -155,3 +151,19 @@ fn app_factory_wraps_build_application_errors() {
         "wrapped error should preserve the wireframe failure details"
     );
 }
+
+#[rstest]
+fn app_factory_validates_with_explicit_serializer() {
+    let runtime = Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("runtime");
+
+    let result =
+        runtime.block_on(async { validate_app_factory::<BincodeSerializer>(&test_resources()) });
+
+    assert!(
+        result.is_ok(),
+        "explicit serializer should build: {result:?}"
+    );
+}
@@ -0,0 +1,238 @@
+//! TLS front end for the Wireframe listener.
+//!
+//! Wireframe accepts plain TCP streams, so when TLS is configured the
+//! Wireframe listener binds an ephemeral loopback port and [`TlsFront`] takes
+//! the public address instead. Each client's TLS session ends here and its
+//! plaintext is relayed to the loopback listener.
+//!
+//! Clients are admitted as they are accepted, before any TLS work: addresses
+//! the server's settings ban are dropped, each client takes its slot from the
+//! server's [`ConnectionLimiter`], and at most
+//! [`MAX_PENDING_HANDSHAKES`] handshakes run at once. A client refused at this
+//! point is closed without a reply, since no TLS session exists to carry one.
+//! The relay's local address is recorded with [`forward_peer`], together with
+//! the client's address and slot, for the lifetime of the connection. The
+//! handshake hook takes the admission from there and refuses loopback
+//! connections with no record, so nothing reaches the plaintext listener
+//! without passing through this front end.
+//!
+//! [`ConnectionLimiter`]: crate::server::connection_limit::ConnectionLimiter
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+
+use anyhow::{Context, Result};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{OwnedSemaphorePermit, Semaphore},
+    task::JoinSet,
+    time::sleep,
+};
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, info};
+
+use super::listeners::ListenerShared;
+use crate::server::{
+    accept::AcceptGuard,
+    bind::bind_std_listener,
+    settings::ServerSettings,
+    tasks::BackgroundTasks,
+    tls::{ForwardedClient, accept_tls, forward_peer},
+};
+
+/// Most TLS handshakes in progress at once; clients beyond this are refused
+/// until one completes.
+const MAX_PENDING_HANDSHAKES: usize = 128;
+
+/// Public TLS listener relaying to the Wireframe listener.
+pub(super) struct TlsFront {
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+}
+
+impl TlsFront {
+    /// Bind `public` when `tls` holds an acceptor.
+    ///
+    /// Returns the address the Wireframe listener should bind: `public` itself
+    /// for plain TCP, or an ephemeral loopback port behind the front end.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be bound.
+    pub(super) async fn bind(
+        public: SocketAddr,
+        tls: Option<TlsAcceptor>,
+    ) -> Result<(SocketAddr, Option<Self>)> {
+        let Some(acceptor) = tls else {
+            return Ok((public, None));
+        };
+        let listener = TcpListener::from_std(bind_std_listener(public)?)
+            .with_context(|| format!("failed to bind TLS listener on {public}"))?;
+        Ok((
+            Self::backend_addr(public),
+            Some(Self { listener, acceptor }),
+        ))
+    }
+
+    /// Start relaying to the Wireframe listener at `backend`, admitting
+    /// clients against the bans and connection limits in `shared.settings`
+    /// and recording accepts into `shared.metrics`, and return the address
+    /// clients connect to.
+    ///
+    /// Without a front end this is `backend` itself.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the public listener's address cannot be read.
+    pub(super) fn start(
+        front: Option<Self>,
+        backend: SocketAddr,
+        tasks: &mut BackgroundTasks,
+        shared: ListenerShared<'_>,
+    ) -> Result<SocketAddr> {
+        let Some(tls) = front else {
+            return Ok(backend);
+        };
+        let public = tls
+            .listener
+            .local_addr()
+            .context("failed to get TLS listener address")?;
+        let guard = AcceptGuard::new(Arc::clone(shared.metrics));
+        let settings = Arc::clone(shared.settings);
+        tasks.extend([tokio::spawn(relay_clients(tls, backend, settings, guard))]);
+        Ok(public)
+    }
+
+    const fn backend_addr(public: SocketAddr) -> SocketAddr {
+        let ip = match public {
+            SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        };
+        SocketAddr::new(ip, 0)
+    }
+}
+
+/// Accept TLS clients until the task is aborted, relaying each admitted
+/// client to `backend`.
+///
+/// Relays run in a set owned by this task, so aborting it ends them too.
+async fn relay_clients(
+    front: TlsFront,
+    backend: SocketAddr,
+    settings: Arc<ServerSettings>,
+    mut guard: AcceptGuard,
+) {
+    let handshakes = Arc::new(Semaphore::new(MAX_PENDING_HANDSHAKES));
+    let mut relays = JoinSet::new();
+    loop {
+        match front.listener.accept().await {
+            Ok((stream, peer)) => {
+                // The app factory counts the connection once it reaches
+                // Wireframe.
+                guard.record_recovered();
+                while relays.try_join_next().is_some() {}
+                if let Some(admitted) = admit(peer, &settings, &handshakes) {
+                    relays.spawn(relay(front.acceptor.clone(), stream, admitted, backend));
+                }
+            }
+            Err(error) => {
+                if let Some(pause) = guard.record_error(&error) {
+                    guard.shed_pending(&front.listener);
+                    sleep(pause).await;
+                }
+            }
+        }
+    }
+}
+
+/// Client accepted by the front end, holding its connection slot and a
+/// place among the pending handshakes.
+struct Admitted {
+    client: ForwardedClient,
+    handshake: OwnedSemaphorePermit,
+}
+
+/// Admit the client at `peer`, or return `None` when `settings` ban it, its
+/// connection limits are reached, or too many handshakes are pending.
+fn admit(
+    peer: SocketAddr,
+    settings: &ServerSettings,
+    handshakes: &Arc<Semaphore>,
+) -> Option<Admitted> {
+    if settings.address_bans.is_banned(peer.ip()) {
+        info!(%peer, "refused connection from banned address");
+        return None;
+    }
+    let Some(slot) = settings.connections.admit(peer.ip()) else {
+        info!(%peer, "refusing connection: connection limit reached");
+        return None;
+    };
+    let Ok(handshake) = Arc::clone(handshakes).try_acquire_owned() else {
+        info!(%peer, "refusing connection: too many pending TLS handshakes");
+        return None;
+    };
+    Some(Admitted {
+        client: ForwardedClient {
+            address: peer,
+            slot,
+        },
+        handshake,
+    })
+}
+
+async fn relay(acceptor: TlsAcceptor, stream: TcpStream, admitted: Admitted, backend: SocketAddr) {
+    let peer = admitted.client.address;
+    if let Err(error) = try_relay(&acceptor, stream, admitted, backend).await {
+        debug!(%peer, %error, "TLS relay closed");
+    }
+}
+
+async fn try_relay(
+    acceptor: &TlsAcceptor,
+    stream: TcpStream,
+    admitted: Admitted,
+    backend: SocketAddr,
+) -> io::Result<()> {
+    let Admitted { client, handshake } = admitted;
+    let mut tls = accept_tls(acceptor, stream).await?;
+    drop(handshake);
+    let mut upstream = TcpStream::connect(backend).await?;
+    let _forwarded = forward_peer(upstream.local_addr()?, client);
+    tokio::io::copy_bidirectional(&mut tls, &mut upstream).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    //! Choosing the loopback address behind the front end and admitting
+    //! clients.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("0.0.0.0:5500", "127.0.0.1:0")]
+    #[case("[::]:5500", "[::1]:0")]
+    fn backend_binds_loopback_of_the_same_family(#[case] public: &str, #[case] backend: &str) {
+        let public: SocketAddr = public.parse().expect("address");
+        let expected: SocketAddr = backend.parse().expect("address");
+        assert_eq!(TlsFront::backend_addr(public), expected);
+    }
+
+    #[rstest]
+    fn admission_waits_for_a_free_handshake() {
+        let peer: SocketAddr = "203.0.113.9:5500".parse().expect("address");
+        let settings = ServerSettings::default();
+        let handshakes = Arc::new(Semaphore::new(1));
+
+        let first = admit(peer, &settings, &handshakes).expect("first client admitted");
+        assert!(admit(peer, &settings, &handshakes).is_none());
+
+        drop(first.handshake);
+        assert!(admit(peer, &settings, &handshakes).is_some());
+    }
+}
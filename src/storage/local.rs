@@ -0,0 +1,239 @@
+//! Storage backend rooted at a local directory.
+
+use std::{io, sync::Arc};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use camino::{Utf8Path, Utf8PathBuf};
+use cap_std::{ambient_authority, fs_utf8::Dir};
+use tokio::{fs::File, io::AsyncWriteExt, task::spawn_blocking};
+
+use super::{ObjectReader, ObjectWriter, Storage, StorageError, validate_key};
+
+/// Objects stored as files beneath a root directory.
+///
+/// The root is opened as a capability, so no key can reach outside it even
+/// through symlinks. Each key's parent directories are created on write. A
+/// new object is written to a hidden partial file beside its key and renamed
+/// into place once finished.
+#[derive(Clone, Debug)]
+pub struct LocalStorage {
+    root: Arc<Dir>,
+}
+
+impl LocalStorage {
+    /// Open the directory at `root`, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the directory cannot be created or opened.
+    pub fn open(root: &str) -> Result<Self, StorageError> {
+        Dir::create_ambient_dir_all(root, ambient_authority())?;
+        let dir = Dir::open_ambient_dir(root, ambient_authority())?;
+        Ok(Self {
+            root: Arc::new(dir),
+        })
+    }
+
+    async fn run<T, F>(&self, key: &str, op: F) -> Result<T, StorageError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Dir, &camino::Utf8Path) -> io::Result<T> + Send + 'static,
+    {
+        let path = validate_key(key)?.to_owned();
+        let root = Arc::clone(&self.root);
+        let result = spawn_blocking(move || op(&root, &path))
+            .await
+            .map_err(io::Error::other)?;
+        result.map_err(|error| match error.kind() {
+            io::ErrorKind::NotFound => StorageError::NotFound(key.to_owned()),
+            _ => StorageError::Io(error),
+        })
+    }
+}
+
+/// Return the hidden file a new object at `path` is written to.
+fn partial_path(path: &Utf8Path) -> Utf8PathBuf {
+    let name = path.file_name().unwrap_or_default();
+    path.with_file_name(format!(".{name}.{:016x}.partial", rand::random::<u64>()))
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn open(&self, key: &str) -> Result<ObjectReader, StorageError> {
+        let file = self
+            .run(key, |root, path| {
+                root.open(path).map(cap_std::fs_utf8::File::into_std)
+            })
+            .await?;
+        Ok(Box::pin(File::from_std(file)))
+    }
+
+    async fn create(&self, key: &str) -> Result<Box<dyn ObjectWriter>, StorageError> {
+        let (file, path, partial) = self
+            .run(key, |root, path| {
+                if let Some(parent) = path.parent().filter(|parent| !parent.as_str().is_empty()) {
+                    root.create_dir_all(parent)?;
+                }
+                let partial = partial_path(path);
+                let file = root.create(&partial)?.into_std();
+                Ok((file, path.to_owned(), partial))
+            })
+            .await?;
+        Ok(Box::new(LocalWriter {
+            root: Arc::clone(&self.root),
+            path,
+            partial,
+            file: File::from_std(file),
+        }))
+    }
+
+    async fn size(&self, key: &str) -> Result<u64, StorageError> {
+        self.run(key, |root, path| root.metadata(path).map(|meta| meta.len()))
+            .await
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match self.run(key, |root, path| root.remove_file(path)).await {
+            Err(StorageError::NotFound(_)) => Ok(()),
+            other => other,
+        }
+    }
+}
+
+/// Object being written to its partial file.
+struct LocalWriter {
+    root: Arc<Dir>,
+    path: Utf8PathBuf,
+    partial: Utf8PathBuf,
+    file: File,
+}
+
+#[async_trait]
+impl ObjectWriter for LocalWriter {
+    async fn write(&mut self, chunk: Bytes) -> Result<(), StorageError> {
+        self.file.write_all(&chunk).await?;
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> Result<(), StorageError> {
+        let Self {
+            root,
+            path,
+            partial,
+            mut file,
+        } = *self;
+        file.flush().await?;
+        file.sync_all().await?;
+        drop(file);
+        spawn_blocking(move || root.rename(&partial, &root, &path))
+            .await
+            .map_err(io::Error::other)??;
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) -> Result<(), StorageError> {
+        let Self {
+            root,
+            partial,
+            file,
+            ..
+        } = *self;
+        drop(file);
+        spawn_blocking(move || root.remove_file(&partial))
+            .await
+            .map_err(io::Error::other)??;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for the local storage backend.
+
+    use rstest::rstest;
+    use tempfile::TempDir;
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    fn open(dir: &TempDir) -> LocalStorage {
+        LocalStorage::open(dir.path().to_str().expect("utf-8 temp dir")).expect("open storage")
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn stores_reads_and_deletes_objects() {
+        let dir = TempDir::new().expect("temp dir");
+        let storage = open(&dir);
+
+        storage
+            .put("objects/ab/guide", Bytes::from_static(b"hello"))
+            .await
+            .expect("put");
+        assert_eq!(storage.size("objects/ab/guide").await.expect("size"), 5);
+        assert_eq!(
+            storage.get("objects/ab/guide").await.expect("get"),
+            Bytes::from_static(b"hello")
+        );
+
+        storage.delete("objects/ab/guide").await.expect("delete");
+        storage
+            .delete("objects/ab/guide")
+            .await
+            .expect("delete again");
+        assert!(matches!(
+            storage.get("objects/ab/guide").await,
+            Err(StorageError::NotFound(_))
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn objects_appear_only_once_finished() {
+        let dir = TempDir::new().expect("temp dir");
+        let storage = open(&dir);
+
+        let mut writer = storage.create("uploads/guide").await.expect("create");
+        writer
+            .write(Bytes::from_static(b"hel"))
+            .await
+            .expect("write");
+        writer
+            .write(Bytes::from_static(b"lo"))
+            .await
+            .expect("write");
+        assert!(matches!(
+            storage.size("uploads/guide").await,
+            Err(StorageError::NotFound(_))
+        ));
+        writer.finish().await.expect("finish");
+        let mut content = Vec::new();
+        storage
+            .open("uploads/guide")
+            .await
+            .expect("open")
+            .read_to_end(&mut content)
+            .await
+            .expect("read");
+        assert_eq!(content, b"hello");
+
+        let abandoned = storage.create("uploads/draft").await.expect("create");
+        abandoned.abort().await.expect("abort");
+        let entries = std::fs::read_dir(dir.path().join("uploads"))
+            .expect("list uploads")
+            .count();
+        assert_eq!(entries, 1, "only the finished object remains");
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn refuses_keys_outside_the_root() {
+        let dir = TempDir::new().expect("temp dir");
+        let storage = open(&dir);
+
+        let result = storage.put("../escape", Bytes::from_static(b"x")).await;
+
+        assert!(matches!(result, Err(StorageError::InvalidKey(_))));
+    }
+}
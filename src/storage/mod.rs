@@ -0,0 +1,278 @@
+//! Backends holding file content.
+//!
+//! File metadata lives in the database; the bytes live in a [`Storage`]
+//! backend under each entry's `object_key`. The backend is chosen by
+//! `storage_url`: a `file://` URL or plain path names a local directory, and
+//! an `s3://bucket/prefix` URL names an S3-compatible bucket when the `s3`
+//! feature is enabled. Object keys are relative, slash-separated paths, so
+//! the same key works with either backend and content can be copied between
+//! them unchanged.
+//!
+//! File content is streamed: [`Storage::open`] reads an object a chunk at a
+//! time and [`Storage::create`] writes one through an [`ObjectWriter`], which
+//! the local backend backs with a hidden partial file and the S3 backend with
+//! a multipart upload. [`Storage::get`] and [`Storage::put`] wrap them for
+//! small objects such as banners.
+
+use std::{pin::Pin, sync::Arc};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use camino::Utf8Path;
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tracing::debug;
+
+use crate::server::AppConfig;
+
+mod local;
+#[cfg(feature = "s3")]
+mod s3;
+
+pub use local::LocalStorage;
+#[cfg(feature = "s3")]
+pub use s3::S3Storage;
+
+/// Errors raised by storage backends.
+#[derive(Debug, Error)]
+pub enum StorageError {
+    /// The object key is empty, absolute, or steps outside the store.
+    #[error("invalid object key '{0}'")]
+    InvalidKey(String),
+    /// No object is stored under the key.
+    #[error("no object stored under '{0}'")]
+    NotFound(String),
+    /// `storage_url` names a scheme this build cannot serve.
+    #[error("unsupported storage URL '{0}'")]
+    UnsupportedUrl(String),
+    /// The local filesystem failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// The object store failed.
+    #[cfg(feature = "s3")]
+    #[error(transparent)]
+    ObjectStore(#[from] object_store::Error),
+}
+
+/// Content of a stored object, read as it is needed.
+pub type ObjectReader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// A new object, written a chunk at a time.
+///
+/// Nothing appears under the key until [`ObjectWriter::finish`] succeeds;
+/// [`ObjectWriter::abort`] discards what was written.
+#[async_trait]
+pub trait ObjectWriter: Send {
+    /// Append `chunk` to the object.
+    ///
+    /// # Errors
+    ///
+    /// Returns a backend error if the chunk cannot be written.
+    async fn write(&mut self, chunk: Bytes) -> Result<(), StorageError>;
+
+    /// Store the object under its key, replacing any existing object.
+    ///
+    /// # Errors
+    ///
+    /// Returns a backend error if the object cannot be completed.
+    async fn finish(self: Box<Self>) -> Result<(), StorageError>;
+
+    /// Discard everything written.
+    ///
+    /// # Errors
+    ///
+    /// Returns a backend error if the partial object cannot be removed.
+    async fn abort(self: Box<Self>) -> Result<(), StorageError>;
+}
+
+/// Content store addressed by object key.
+#[async_trait]
+pub trait Storage: Send + Sync + std::fmt::Debug {
+    /// Open the object stored under `key` for reading.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::NotFound`] when nothing is stored under
+    /// `key`, or a backend error.
+    async fn open(&self, key: &str) -> Result<ObjectReader, StorageError>;
+
+    /// Start writing a new object under `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::InvalidKey`] for a malformed key, or a
+    /// backend error.
+    async fn create(&self, key: &str) -> Result<Box<dyn ObjectWriter>, StorageError>;
+
+    /// Read the whole object stored under `key`; meant for small objects.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::NotFound`] when nothing is stored under
+    /// `key`, or a backend error.
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError> {
+        let mut data = Vec::new();
+        self.open(key).await?.read_to_end(&mut data).await?;
+        Ok(Bytes::from(data))
+    }
+
+    /// Store `data` under `key`, replacing any existing object.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::InvalidKey`] for a malformed key, or a
+    /// backend error.
+    async fn put(&self, key: &str, data: Bytes) -> Result<(), StorageError> {
+        let mut writer = self.create(key).await?;
+        match writer.write(data).await {
+            Ok(()) => writer.finish().await,
+            Err(error) => {
+                if let Err(cleanup) = writer.abort().await {
+                    debug!(%key, error = %cleanup, "partial object not removed");
+                }
+                Err(error)
+            }
+        }
+    }
+
+    /// Return the size in bytes of the object stored under `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::NotFound`] when nothing is stored under
+    /// `key`, or a backend error.
+    async fn size(&self, key: &str) -> Result<u64, StorageError>;
+
+    /// Remove the object stored under `key`. Removing a missing object
+    /// succeeds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::InvalidKey`] for a malformed key, or a
+    /// backend error.
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Check that `key` is a relative path of plain segments and return it as a
+/// path.
+///
+/// # Errors
+///
+/// Returns [`StorageError::InvalidKey`] when `key` is empty, starts with a
+/// slash, contains a backslash, or has an empty, `.`, or `..` segment.
+pub fn validate_key(key: &str) -> Result<&Utf8Path, StorageError> {
+    let valid = !key.is_empty()
+        && !key.contains('\\')
+        && key
+            .split('/')
+            .all(|segment| !matches!(segment, "" | "." | ".."));
+    if valid {
+        Ok(Utf8Path::new(key))
+    } else {
+        Err(StorageError::InvalidKey(key.to_owned()))
+    }
+}
+
+/// Where file content is stored, parsed from `storage_url`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StorageLocation {
+    /// A directory on the local filesystem.
+    Local(String),
+    /// A bucket on an S3-compatible service, with an optional key prefix.
+    S3 {
+        /// Bucket name.
+        bucket: String,
+        /// Prefix added to every object key; empty for none.
+        prefix: String,
+    },
+}
+
+impl StorageLocation {
+    /// Parse a storage URL: `file:///srv/mxd/files`, a plain path, or
+    /// `s3://bucket/prefix`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StorageError::UnsupportedUrl`] for an empty path, an S3 URL
+    /// without a bucket, or any other scheme.
+    pub fn parse(url: &str) -> Result<Self, StorageError> {
+        let unsupported = || StorageError::UnsupportedUrl(url.to_owned());
+        if let Some(rest) = url.strip_prefix("s3://") {
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            if bucket.is_empty() {
+                return Err(unsupported());
+            }
+            return Ok(Self::S3 {
+                bucket: bucket.to_owned(),
+                prefix: prefix.trim_matches('/').to_owned(),
+            });
+        }
+        let path = url.strip_prefix("file://").unwrap_or(url);
+        if path.is_empty() || path.contains("://") {
+            return Err(unsupported());
+        }
+        Ok(Self::Local(path.to_owned()))
+    }
+}
+
+/// Open the backend named by `config.storage_url`, or return `None` when it
+/// is unset.
+///
+/// # Errors
+///
+/// Returns an error if the URL is malformed, names a backend this build
+/// lacks, or the backend cannot be opened.
+pub fn open_storage(config: &AppConfig) -> Result<Option<Arc<dyn Storage>>, StorageError> {
+    let Some(url) = config.storage_url.as_deref() else {
+        return Ok(None);
+    };
+    let backend: Arc<dyn Storage> = match StorageLocation::parse(url)? {
+        StorageLocation::Local(root) => Arc::new(LocalStorage::open(&root)?),
+        #[cfg(feature = "s3")]
+        StorageLocation::S3 { bucket, prefix } => Arc::new(S3Storage::open(&bucket, &prefix)?),
+        #[cfg(not(feature = "s3"))]
+        StorageLocation::S3 { .. } => return Err(StorageError::UnsupportedUrl(url.to_owned())),
+    };
+    Ok(Some(backend))
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for object keys and storage URLs.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case::plain("objects/guide", true)]
+    #[case::single("guide", true)]
+    #[case::empty("", false)]
+    #[case::absolute("/etc/passwd", false)]
+    #[case::parent("objects/../secret", false)]
+    #[case::current("./guide", false)]
+    #[case::empty_segment("objects//guide", false)]
+    #[case::trailing_slash("objects/", false)]
+    #[case::backslash("objects\\guide", false)]
+    fn validates_object_keys(#[case] key: &str, #[case] valid: bool) {
+        assert_eq!(validate_key(key).is_ok(), valid);
+    }
+
+    #[rstest]
+    #[case::file_url("file:///srv/mxd/files", Some(StorageLocation::Local("/srv/mxd/files".to_owned())))]
+    #[case::plain_path("files", Some(StorageLocation::Local("files".to_owned())))]
+    #[case::bucket("s3://mxd", Some(StorageLocation::S3 {
+        bucket: "mxd".to_owned(),
+        prefix: String::new(),
+    }))]
+    #[case::bucket_and_prefix("s3://mxd/hotline/files/", Some(StorageLocation::S3 {
+        bucket: "mxd".to_owned(),
+        prefix: "hotline/files".to_owned(),
+    }))]
+    #[case::no_bucket("s3:///files", None)]
+    #[case::empty_file_url("file://", None)]
+    #[case::other_scheme("gs://mxd", None)]
+    fn parses_storage_urls(#[case] url: &str, #[case] expected: Option<StorageLocation>) {
+        assert_eq!(StorageLocation::parse(url).ok(), expected);
+    }
+}
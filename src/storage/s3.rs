@@ -0,0 +1,119 @@
+//! Storage backend on an S3-compatible object store.
+
+use std::io;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::TryStreamExt;
+use object_store::{
+    ObjectStore,
+    WriteMultipart,
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path,
+    prefix::PrefixStore,
+};
+use tokio_util::io::StreamReader;
+
+use super::{ObjectReader, ObjectWriter, Storage, StorageError, validate_key};
+
+/// Parts of a multipart upload sent at once before a writer waits.
+const PARTS_IN_FLIGHT: usize = 4;
+
+/// Objects stored in an S3 bucket under an optional key prefix.
+///
+/// Credentials, region, and endpoint come from the standard `AWS_*`
+/// environment variables, so S3-compatible services such as `MinIO` work by
+/// setting `AWS_ENDPOINT` (and `AWS_ALLOW_HTTP` for plain HTTP). Objects are
+/// read as a stream and written as multipart uploads.
+#[derive(Debug)]
+pub struct S3Storage {
+    store: PrefixStore<AmazonS3>,
+}
+
+impl S3Storage {
+    /// Connect to `bucket`, storing objects beneath `prefix`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the client cannot be configured from the
+    /// environment.
+    pub fn open(bucket: &str, prefix: &str) -> Result<Self, StorageError> {
+        let client = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()?;
+        Ok(Self {
+            store: PrefixStore::new(client, prefix),
+        })
+    }
+
+    fn path(key: &str) -> Result<Path, StorageError> { Ok(Path::from(validate_key(key)?.as_str())) }
+}
+
+fn not_found(key: &str, error: object_store::Error) -> StorageError {
+    match error {
+        object_store::Error::NotFound { .. } => StorageError::NotFound(key.to_owned()),
+        other => StorageError::ObjectStore(other),
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn open(&self, key: &str) -> Result<ObjectReader, StorageError> {
+        let path = Self::path(key)?;
+        let object = self
+            .store
+            .get(&path)
+            .await
+            .map_err(|error| not_found(key, error))?;
+        let chunks = object.into_stream().map_err(io::Error::other);
+        Ok(Box::pin(StreamReader::new(chunks)))
+    }
+
+    async fn create(&self, key: &str) -> Result<Box<dyn ObjectWriter>, StorageError> {
+        let path = Self::path(key)?;
+        let upload = self.store.put_multipart(&path).await?;
+        Ok(Box::new(S3Writer(WriteMultipart::new(upload))))
+    }
+
+    async fn size(&self, key: &str) -> Result<u64, StorageError> {
+        let path = Self::path(key)?;
+        let meta = self
+            .store
+            .head(&path)
+            .await
+            .map_err(|error| not_found(key, error))?;
+        Ok(meta.size)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let path = Self::path(key)?;
+        match self.store.delete(&path).await {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+/// Object being sent as a multipart upload.
+struct S3Writer(WriteMultipart);
+
+#[async_trait]
+impl ObjectWriter for S3Writer {
+    async fn write(&mut self, chunk: Bytes) -> Result<(), StorageError> {
+        // Wait for earlier parts so a fast sender cannot queue the whole
+        // object in memory.
+        self.0.wait_for_capacity(PARTS_IN_FLIGHT).await?;
+        self.0.put(chunk);
+        Ok(())
+    }
+
+    async fn finish(self: Box<Self>) -> Result<(), StorageError> {
+        self.0.finish().await?;
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) -> Result<(), StorageError> {
+        self.0.abort().await?;
+        Ok(())
+    }
+}
@@ -1,10 +1,14 @@
 //! Password hashing and verification utilities.
 //!
 //! Functions in this module provide a thin wrapper around the `argon2` crate
-//! to hash and verify user passwords for authentication purposes.
+//! to hash and verify user passwords for authentication purposes, and to
+//! spot stored hashes made with settings the server no longer uses.
 
 use argon2::{
+    Algorithm,
     Argon2,
+    Params,
+    Version,
     password_hash::{
         Error,
         PasswordHash,
@@ -38,13 +42,39 @@ pub(crate) fn verify_password(hash: &str, pw: &str) -> bool {
         .is_ok()
 }
 
+/// Report whether `hash` was made with other Argon2 settings than `argon2`
+/// uses, so login should replace it once the password has been verified.
+///
+/// The server always hashes with Argon2id version 0x13, so hashes using
+/// another variant or version are stale as well. Unparsable hashes are not:
+/// they never verify, so there is nothing to replace them with.
+pub(crate) fn needs_rehash(argon2: &Argon2, hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(hash) else {
+        return false;
+    };
+    let Ok(stored) = Params::try_from(&parsed) else {
+        return true;
+    };
+    let current = argon2.params();
+    parsed.algorithm != Algorithm::Argon2id.ident()
+        || parsed.version != Some(u32::from(Version::V0x13))
+        || stored.m_cost() != current.m_cost()
+        || stored.t_cost() != current.t_cost()
+        || stored.p_cost() != current.p_cost()
+}
+
 #[cfg(test)]
 mod tests {
     //! Tests for this module.
-    use argon2::Argon2;
+    use argon2::{Algorithm, Argon2, Params, Version};
     use rstest::{fixture, rstest};
 
-    use super::{hash_password, verify_password};
+    use super::{hash_password, needs_rehash, verify_password};
+
+    fn argon2_with(m_cost: u32, t_cost: u32, p_cost: u32) -> Argon2<'static> {
+        let params = Params::new(m_cost, t_cost, p_cost, None).expect("valid params");
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+    }
 
     #[fixture]
     #[rustfmt::skip]
@@ -67,4 +97,27 @@ mod tests {
     fn test_verify_password_rejects_invalid_hash() {
         assert!(!verify_password("not-a-hash", "secret"));
     }
+
+    #[rstest]
+    #[case(argon2_with(19_456, 2, 1), false)]
+    #[case(argon2_with(19_456, 3, 1), true)]
+    #[case(argon2_with(65_536, 2, 1), true)]
+    #[case(argon2_with(19_456, 2, 2), true)]
+    fn test_needs_rehash_compares_parameters(
+        #[case] current: Argon2<'static>,
+        #[case] expected: bool,
+    ) {
+        let hashed = hash_password(&argon2_with(19_456, 2, 1), "secret").expect("hash password");
+        assert_eq!(needs_rehash(&current, &hashed), expected);
+    }
+
+    #[test]
+    fn test_needs_rehash_flags_other_variants_but_not_garbage() {
+        let params = Params::new(19_456, 2, 1, None).expect("valid params");
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, params);
+        let hashed = hash_password(&argon2i, "secret").expect("hash password");
+
+        assert!(needs_rehash(&argon2_with(19_456, 2, 1), &hashed));
+        assert!(!needs_rehash(&Argon2::default(), "not-a-hash"));
+    }
 }
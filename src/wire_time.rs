@@ -0,0 +1,112 @@
+//! Date and time encodings shared by protocol replies.
+//!
+//! Every timestamp mxd puts on the wire (article `NewsDate` values, category
+//! activity in news listings, and the server clock in login replies) uses the
+//! same unit: a big-endian signed 64-bit count of milliseconds since the Unix
+//! epoch, measured in UTC. Older clients render those values in the server's
+//! zone, so login replies also carry the server's UTC offset in seconds.
+#![expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone};
+
+use crate::field_id::FieldId;
+
+/// Count the milliseconds between the Unix epoch and a UTC timestamp.
+#[must_use]
+pub fn timestamp_millis(timestamp: NaiveDateTime) -> i64 { timestamp.and_utc().timestamp_millis() }
+
+/// Count the milliseconds to an optional UTC timestamp, using zero when the
+/// value is absent.
+#[must_use]
+pub fn optional_timestamp_millis(timestamp: Option<NaiveDateTime>) -> i64 {
+    timestamp.map_or(0, timestamp_millis)
+}
+
+/// Encode a UTC timestamp as big-endian milliseconds since the Unix epoch.
+#[must_use]
+pub fn encode_timestamp(timestamp: NaiveDateTime) -> [u8; 8] {
+    timestamp_millis(timestamp).to_be_bytes()
+}
+
+/// Encode an optional UTC timestamp, using zero when the value is absent.
+#[must_use]
+pub fn encode_optional_timestamp(timestamp: Option<NaiveDateTime>) -> [u8; 8] {
+    optional_timestamp_millis(timestamp).to_be_bytes()
+}
+
+/// Decode big-endian epoch milliseconds produced by [`encode_timestamp`].
+///
+/// Returns `None` when `bytes` is not eight bytes long or the value is out of
+/// range.
+#[must_use]
+pub fn decode_timestamp(bytes: &[u8]) -> Option<NaiveDateTime> {
+    let raw: [u8; 8] = bytes.try_into().ok()?;
+    DateTime::from_timestamp_millis(i64::from_be_bytes(raw)).map(|dt| dt.naive_utc())
+}
+
+/// Build the server clock fields appended to successful login replies.
+///
+/// Returns [`FieldId::ServerTime`] as epoch milliseconds and
+/// [`FieldId::ServerUtcOffset`] as a big-endian `i32` count of seconds east
+/// of UTC.
+#[must_use]
+pub fn server_clock_params<Tz: TimeZone>(now: &DateTime<Tz>) -> [(FieldId, Vec<u8>); 2] {
+    let offset = now.fixed_offset().offset().local_minus_utc();
+    [
+        (
+            FieldId::ServerTime,
+            encode_timestamp(now.naive_utc()).to_vec(),
+        ),
+        (FieldId::ServerUtcOffset, offset.to_be_bytes().to_vec()),
+    ]
+}
+
+/// Return the current server time in the host's local zone.
+#[must_use]
+pub fn server_now() -> DateTime<FixedOffset> { Local::now().fixed_offset() }
+
+#[cfg(test)]
+mod tests {
+    //! Unit tests for wire time encodings.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn timestamp_round_trips_through_wire_encoding() {
+        let Some(timestamp) = DateTime::from_timestamp(2_000, 0).map(|dt| dt.naive_utc()) else {
+            panic!("fixture timestamp in range");
+        };
+
+        let bytes = encode_timestamp(timestamp);
+
+        assert_eq!(bytes, 2_000_000i64.to_be_bytes());
+        assert_eq!(decode_timestamp(&bytes), Some(timestamp));
+    }
+
+    #[rstest]
+    fn missing_timestamp_encodes_as_zero() {
+        assert_eq!(encode_optional_timestamp(None), [0; 8]);
+    }
+
+    #[rstest]
+    #[case::utc(0)]
+    #[case::east(5 * 3600 + 1800)]
+    #[case::west(-8 * 3600)]
+    fn server_clock_reports_utc_instant_and_offset(#[case] offset_secs: i32) {
+        let Some(zone) = FixedOffset::east_opt(offset_secs) else {
+            panic!("valid fixture offset");
+        };
+        let Some(now) = zone.timestamp_millis_opt(1_700_000_000_123).single() else {
+            panic!("valid fixture instant");
+        };
+
+        let [(time_field, time), (offset_field, offset)] = server_clock_params(&now);
+
+        assert_eq!(time_field, FieldId::ServerTime);
+        assert_eq!(time, 1_700_000_000_123i64.to_be_bytes());
+        assert_eq!(offset_field, FieldId::ServerUtcOffset);
+        assert_eq!(offset, offset_secs.to_be_bytes());
+    }
+}
@@ -0,0 +1,135 @@
+//! Server-initiated close for Wireframe connections.
+//!
+//! Wireframe owns each connection's socket, so the handshake hook keeps a
+//! duplicate handle to it in a [`SocketCloser`]. That lets the server end a
+//! connection the way the legacy runtime does (see [`crate::server::disconnect`]):
+//! queue a Disconnect Message, let the connection actor flush everything queued
+//! ahead of it, half-close the write side, and drain input for a short window
+//! so the kernel does not answer unread bytes with a reset.
+//!
+//! The flush is signalled in band. After the notice the server pushes an empty
+//! [close marker](close_marker) on the same low-priority queue. The actor
+//! writes and flushes each frame before taking the next, so when the marker
+//! reaches `HotlineProtocol::before_send` everything queued ahead of it is in
+//! the kernel's send buffer and the write side can be shut down. The encoder
+//! then refuses the marker, which ends the actor.
+
+use std::{
+    fmt,
+    io,
+    net::{Shutdown, TcpStream as StdTcpStream},
+    sync::Arc,
+};
+
+use socket2::{SockRef, Socket};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+use crate::server::disconnect::{DRAIN_WINDOW, drain_inbound};
+
+/// Return the frame that asks a connection's actor to close once everything
+/// queued before it has been written.
+#[must_use]
+pub const fn close_marker() -> Vec<u8> { Vec::new() }
+
+/// Return `true` when `frame` is the [close marker](close_marker).
+///
+/// No transaction is empty, since every one carries a 20-byte header.
+#[must_use]
+pub const fn is_close_marker(frame: &[u8]) -> bool { frame.is_empty() }
+
+/// Duplicate handle to a connection's socket, used to close it from the
+/// server side.
+///
+/// Two closers are equal only when they are the same value, so connection
+/// contexts sharing one [`Arc`] compare equal.
+pub struct SocketCloser {
+    socket: Socket,
+}
+
+impl SocketCloser {
+    /// Duplicate the handle of `stream`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the operating system cannot duplicate the handle.
+    pub fn from_stream(stream: &TcpStream) -> io::Result<Arc<Self>> {
+        let socket = SockRef::from(stream).try_clone()?;
+        Ok(Arc::new(Self { socket }))
+    }
+
+    /// Half-close the write side, then read and discard input for
+    /// [`DRAIN_WINDOW`] in the background before letting go of the socket.
+    ///
+    /// Every step is best-effort: the connection is going away regardless.
+    pub fn close_write(&self) {
+        if let Err(error) = self.socket.shutdown(Shutdown::Write) {
+            debug!(%error, "failed to half-close connection");
+            return;
+        }
+        let drained = self.socket.try_clone().and_then(|socket| {
+            let stream = StdTcpStream::from(socket);
+            stream.set_nonblocking(true)?;
+            TcpStream::from_std(stream)
+        });
+        match drained {
+            Ok(mut stream) => {
+                tokio::spawn(async move { drain_inbound(&mut stream, DRAIN_WINDOW).await });
+            }
+            Err(error) => debug!(%error, "failed to drain closing connection"),
+        }
+    }
+}
+
+impl fmt::Debug for SocketCloser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SocketCloser").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for SocketCloser {
+    fn eq(&self, other: &Self) -> bool { std::ptr::eq(self, other) }
+}
+
+impl Eq for SocketCloser {}
+
+#[cfg(test)]
+mod tests {
+    //! Closing a connection through a duplicated handle.
+
+    use rstest::rstest;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    #[rstest]
+    #[tokio::test]
+    async fn close_write_ends_the_stream_after_written_bytes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+        let addr = listener.local_addr().expect("local address");
+        let mut client = TcpStream::connect(addr).await.expect("connect");
+        let (mut server, _) = listener.accept().await.expect("accept");
+        let closer = SocketCloser::from_stream(&server).expect("duplicate");
+
+        server.write_all(b"notice").await.expect("write");
+        closer.close_write();
+        let mut received = Vec::new();
+        client
+            .read_to_end(&mut received)
+            .await
+            .expect("read to end");
+
+        // The server's own handle is still open; only the write side closed.
+        assert_eq!(received, b"notice");
+        drop(server);
+    }
+
+    #[rstest]
+    fn only_the_empty_frame_is_the_close_marker() {
+        assert!(is_close_marker(&close_marker()));
+        assert!(!is_close_marker(&[0; 20]));
+    }
+}
@@ -4,14 +4,16 @@
 //! interface by converting between raw Hotline transaction bytes and
 //! bincode-encoded `Envelope` payloads. Inbound decoding surfaces physical
 //! Hotline frames to Wireframe's protocol-level `MessageAssembler`, while
-//! outbound encoding preserves the existing logical transaction writer.
+//! outbound encoding preserves the existing logical transaction writer. Both
+//! halves follow the connection's [`FrameDataLimit`] and the server's
+//! [`PayloadLimits`].
 
-use std::{
-    io,
-    time::{Duration, Instant},
-};
+use std::{io, time::Duration};
 
+use bincode::{borrow_decode_from_slice, config};
 use bytes::{Bytes, BytesMut};
+use mxd_proto::codec;
+use tokio::time::Instant;
 use tokio_util::codec::{Decoder, Encoder};
 use wireframe::{
     app::{Envelope, Packet},
@@ -20,15 +22,16 @@ use wireframe::{
     message_assembler::FrameSequence,
 };
 
-use super::{HotlineCodec, HotlineTransaction};
+use super::{FrameDataLimit, HotlineCodec};
 use crate::{
-    transaction::parse_transaction,
+    transaction::{PayloadLimits, READ_TIMEOUT, parse_transaction_ref},
     wireframe::{
+        closer::is_close_marker,
         message_assembly::{
-            HOTLINE_LOGICAL_MESSAGE_BYTES,
             IsLast,
             continuation_frame_payload,
             first_frame_payload,
+            hotline_logical_message_bytes,
             message_key_for,
         },
         route_ids::route_id_for,
@@ -36,25 +39,71 @@ use crate::{
 };
 
 /// Wireframe `FrameCodec` implementation for Hotline transactions.
-#[derive(Clone, Debug, Default)]
-pub struct HotlineFrameCodec;
+#[derive(Clone, Debug)]
+pub struct HotlineFrameCodec {
+    limit: FrameDataLimit,
+    read_timeout: Duration,
+    payload_limits: PayloadLimits,
+}
+
+impl Default for HotlineFrameCodec {
+    fn default() -> Self { Self::with_frame_limit(FrameDataLimit::default()) }
+}
 
 impl HotlineFrameCodec {
-    /// Create a new Hotline frame codec.
+    /// Create a new Hotline frame codec using the protocol's default frame
+    /// size.
     #[must_use]
-    pub const fn new() -> Self { Self }
+    pub fn new() -> Self { Self::default() }
+
+    /// Create a codec that follows `limit`, the frame size negotiated with
+    /// the connection's client.
+    #[must_use]
+    pub const fn with_frame_limit(limit: FrameDataLimit) -> Self {
+        Self {
+            limit,
+            read_timeout: READ_TIMEOUT,
+            payload_limits: PayloadLimits::new(),
+        }
+    }
+
+    /// Give each fragment of a request `timeout` to arrive after the one
+    /// before it, instead of [`READ_TIMEOUT`].
+    #[must_use]
+    pub const fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Hold transactions to `limits` instead of the default limits.
+    #[must_use]
+    pub fn with_payload_limits(mut self, limits: PayloadLimits) -> Self {
+        self.payload_limits = limits;
+        self
+    }
 }
 
 /// Stateful decoder half of `HotlineFrameCodec`, tracking active fragment series.
 #[doc(hidden)]
 pub struct HotlineFrameDecoder {
     series: InboundSeriesTracker,
+    limit: FrameDataLimit,
+    payload_limits: PayloadLimits,
 }
 
 impl HotlineFrameDecoder {
     /// Create a new decoder.
-    #[rustfmt::skip]
-    const fn new() -> Self { Self { series: InboundSeriesTracker::new() } }
+    const fn new(
+        limit: FrameDataLimit,
+        read_timeout: Duration,
+        payload_limits: PayloadLimits,
+    ) -> Self {
+        Self {
+            series: InboundSeriesTracker::new(read_timeout),
+            limit,
+            payload_limits,
+        }
+    }
 }
 
 impl Decoder for HotlineFrameDecoder {
@@ -62,7 +111,9 @@ impl Decoder for HotlineFrameDecoder {
     type Error = io::Error;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        let Some((header, payload)) = super::take_hotline_frame(src)? else {
+        let Some((header, payload)) =
+            codec::take_hotline_frame_limited(src, self.limit.get(), &self.payload_limits)?
+        else {
             return Ok(None);
         };
 
@@ -87,55 +138,99 @@ impl Decoder for HotlineFrameDecoder {
 #[doc(hidden)]
 pub struct HotlineFrameEncoder {
     inner: HotlineCodec,
+    limit: FrameDataLimit,
+    payload_limits: PayloadLimits,
 }
 
 impl HotlineFrameEncoder {
     /// Create a new encoder.
-    #[rustfmt::skip]
-    fn new() -> Self { Self { inner: HotlineCodec::new() } }
+    fn new(limit: FrameDataLimit, payload_limits: PayloadLimits) -> Self {
+        Self {
+            inner: HotlineCodec::new(),
+            limit,
+            payload_limits,
+        }
+    }
 }
 
 impl Encoder<Vec<u8>> for HotlineFrameEncoder {
     type Error = io::Error;
 
     fn encode(&mut self, item: Vec<u8>, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        let (envelope, _) = Envelope::from_bytes(&item)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
-        let payload = envelope.into_parts().into_payload();
-        let parsed = parse_transaction(&payload)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
-        let tx = HotlineTransaction::try_from(parsed)
+        // The transaction is framed straight from the envelope's bytes into
+        // the connection's write buffer, without an intermediate copy.
+        let payload = envelope_payload(&item)?;
+        if is_close_marker(payload) {
+            // The write side is already shut; ending the actor here closes
+            // the connection.
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "connection closed by the server",
+            ));
+        }
+        let (header, body) = parse_transaction_ref(payload, &self.payload_limits)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
-        self.inner.encode(tx, dst)
+        // The limit can rise mid-connection, when the login reply grants it.
+        self.inner.set_max_frame_data(self.limit.get());
+        self.inner.encode_frames(&header, body, dst)
     }
 }
 
+/// Borrow the payload of a bincode-encoded [`Envelope`].
+///
+/// `Envelope::from_bytes` would copy the payload into a fresh vector for every
+/// outbound frame. Decoding the envelope's fields (route id, correlation id,
+/// payload) as a tuple borrows the payload from `bytes` instead.
+fn envelope_payload(bytes: &[u8]) -> io::Result<&[u8]> {
+    let ((_, _, payload), _): ((u32, Option<u64>, &[u8]), usize) =
+        borrow_decode_from_slice(bytes, config::standard())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+    Ok(payload)
+}
+
 impl FrameCodec for HotlineFrameCodec {
     type Frame = Vec<u8>;
     type Decoder = HotlineFrameDecoder;
     type Encoder = HotlineFrameEncoder;
-    fn decoder(&self) -> Self::Decoder { HotlineFrameDecoder::new() }
-    fn encoder(&self) -> Self::Encoder { HotlineFrameEncoder::new() }
+    fn decoder(&self) -> Self::Decoder {
+        HotlineFrameDecoder::new(
+            self.limit.clone(),
+            self.read_timeout,
+            self.payload_limits.clone(),
+        )
+    }
+    fn encoder(&self) -> Self::Encoder {
+        HotlineFrameEncoder::new(self.limit.clone(), self.payload_limits.clone())
+    }
     fn frame_payload(frame: &Self::Frame) -> &[u8] { frame.as_slice() }
     fn wrap_payload(&self, payload: Bytes) -> Self::Frame { payload.to_vec() }
-    fn max_frame_length(&self) -> usize { HOTLINE_LOGICAL_MESSAGE_BYTES }
+    fn max_frame_length(&self) -> usize { hotline_logical_message_bytes(&self.payload_limits) }
 }
 
-/// Timeout for receiving the next physical fragment in one Hotline series.
-///
-/// This mirrors the legacy transaction reader's five-second I/O timeout for
-/// multi-frame payload progress without changing the server's overall idle
-/// connection policy.
-const SERIES_TIMEOUT: Duration = crate::transaction::IO_TIMEOUT;
-
 /// Tracker for one in-progress multi-fragment Hotline series.
 struct InboundSeriesTracker {
     state: Option<InboundSeriesState>,
+    read_timeout: Duration,
 }
 
 impl InboundSeriesTracker {
-    /// Create a tracker with no active series.
-    const fn new() -> Self { Self { state: None } }
+    /// Create a tracker with no active series that gives each fragment
+    /// `read_timeout` to arrive.
+    const fn new(read_timeout: Duration) -> Self {
+        Self {
+            state: None,
+            read_timeout,
+        }
+    }
+
+    /// Deadline for receiving the next physical fragment in one Hotline
+    /// series.
+    ///
+    /// This is the configured read timeout, the same limit the legacy
+    /// transaction reader applies to multi-frame payload progress, without
+    /// changing the server's overall idle connection policy. Deadlines use
+    /// Tokio's clock so tests can pause and jump it.
+    fn series_deadline(&self) -> Instant { Instant::now() + self.read_timeout }
 
     /// Returns `true` when a fragment series is in progress.
     const fn has_active_series(&self) -> bool { self.state.is_some() }
@@ -163,7 +258,7 @@ impl InboundSeriesTracker {
                 message_key,
                 remaining,
                 next_sequence: FrameSequence(1),
-                deadline: Instant::now() + SERIES_TIMEOUT,
+                deadline: self.series_deadline(),
             });
         }
 
@@ -209,10 +304,11 @@ impl InboundSeriesTracker {
                     "fragment sequence overflow while tracking continuation",
                 )
             })?;
+            let deadline = self.series_deadline();
             let active_series_mut = self.active_state_mut()?;
             active_series_mut.remaining -= data_size;
             active_series_mut.next_sequence = next_sequence;
-            active_series_mut.deadline = Instant::now() + SERIES_TIMEOUT;
+            active_series_mut.deadline = deadline;
         }
 
         continuation_frame_payload(message_key, sequence, IsLast(is_last), payload)
@@ -245,7 +341,7 @@ impl InboundSeriesTracker {
     ) -> Result<(), io::Error> {
         let active_series = self.active_state()?;
         // Malformed continuation headers do not consume active-series state.
-        super::validate_fragment_consistency(&active_series.first_header, header)
+        codec::validate_fragment_consistency(&active_series.first_header, header)
             .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
     }
 
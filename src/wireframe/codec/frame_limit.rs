@@ -0,0 +1,61 @@
+//! Per-connection frame size agreed with the client.
+//!
+//! Every connection starts at the protocol's [`MAX_FRAME_DATA`]. A client that
+//! sends the mxd `MaxFrameData` (166) field with its login may be granted a
+//! larger size, up to [`MAX_NEGOTIATED_FRAME_DATA`]; the compatibility policy
+//! records the grant here and the connection's [`super::HotlineFrameCodec`]
+//! reads it for every frame it decodes or encodes.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use crate::transaction::{MAX_FRAME_DATA, MAX_NEGOTIATED_FRAME_DATA};
+
+/// Shared handle to one connection's frame data limit.
+#[derive(Clone, Debug)]
+pub struct FrameDataLimit(Arc<AtomicUsize>);
+
+impl Default for FrameDataLimit {
+    fn default() -> Self { Self(Arc::new(AtomicUsize::new(MAX_FRAME_DATA))) }
+}
+
+impl FrameDataLimit {
+    /// Return the largest data size one frame may carry.
+    #[must_use]
+    pub fn get(&self) -> usize { self.0.load(Ordering::Relaxed) }
+
+    /// Grant a client's `requested` frame size and return what was granted.
+    ///
+    /// Requests are clamped to
+    /// `MAX_FRAME_DATA..=MAX_NEGOTIATED_FRAME_DATA`, so negotiation can only
+    /// raise the limit.
+    pub fn grant(&self, requested: usize) -> usize {
+        let granted = requested.clamp(MAX_FRAME_DATA, MAX_NEGOTIATED_FRAME_DATA);
+        self.0.store(granted, Ordering::Relaxed);
+        granted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Granting negotiated frame sizes.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(0, MAX_FRAME_DATA)]
+    #[case(MAX_FRAME_DATA * 2, MAX_FRAME_DATA * 2)]
+    #[case(usize::MAX, MAX_NEGOTIATED_FRAME_DATA)]
+    fn grants_are_clamped(#[case] requested: usize, #[case] granted: usize) {
+        let limit = FrameDataLimit::default();
+        let shared = limit.clone();
+        assert_eq!(limit.get(), MAX_FRAME_DATA);
+
+        assert_eq!(limit.grant(requested), granted);
+        assert_eq!(shared.get(), granted);
+    }
+}
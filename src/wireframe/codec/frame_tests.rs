@@ -3,6 +3,7 @@
 
 use bytes::{Bytes, BytesMut};
 use rstest::{fixture, rstest};
+use test_util::CLOCK_JUMPS;
 use tokio_util::codec::Decoder as _;
 use wireframe::{
     app::{Envelope, Packet},
@@ -11,9 +12,9 @@ use wireframe::{
     message::Message,
 };
 
-use super::HotlineFrameCodec;
+use super::{FrameDataLimit, HotlineFrameCodec};
 use crate::{
-    transaction::{FrameHeader, HEADER_LEN, MAX_PAYLOAD_SIZE},
+    transaction::{FrameHeader, HEADER_LEN, MAX_FRAME_DATA, MAX_PAYLOAD_SIZE, READ_TIMEOUT},
     wireframe::test_helpers::fragmented_transaction_bytes,
 };
 
@@ -97,6 +98,35 @@ fn decoder_emits_first_then_continuation_payloads_for_fragmented_request() {
     );
     assert_eq!(second_env.correlation_id(), Some(44));
 }
+
+#[test]
+fn decoder_accepts_larger_frames_once_granted() {
+    let data_size = u32::try_from(MAX_FRAME_DATA + 1).expect("frame size fits");
+    let header = FrameHeader {
+        flags: 0,
+        is_reply: 0,
+        ty: 105,
+        id: 45,
+        error: 0,
+        total_size: data_size,
+        data_size,
+    };
+    let mut header_bytes = [0u8; HEADER_LEN];
+    header.write_bytes(&mut header_bytes);
+    let mut frame = header_bytes.to_vec();
+    frame.resize(HEADER_LEN + MAX_FRAME_DATA + 1, 0);
+    let limit = FrameDataLimit::default();
+    let mut decoder = HotlineFrameCodec::with_frame_limit(limit.clone()).decoder();
+
+    assert!(decoder.decode(&mut BytesMut::from(&frame[..])).is_err());
+
+    limit.grant(2 * MAX_FRAME_DATA);
+    let decoded = decoder
+        .decode(&mut BytesMut::from(&frame[..]))
+        .expect("decode granted frame");
+    assert!(decoded.is_some());
+}
+
 fn tracker_with_pending_series() -> (super::InboundSeriesTracker, FrameHeader, FrameHeader) {
     let first_header = FrameHeader {
         flags: 0,
@@ -154,3 +184,28 @@ fn continue_series_clears_active_state_on_zero_progress_fragment() {
         "zero-progress continuation must clear the active series"
     );
 }
+
+#[tokio::test(start_paused = true)]
+async fn series_timeout_survives_clock_jumps() {
+    for jump in CLOCK_JUMPS {
+        let (mut tracker, first_header, _) = tracker_with_pending_series();
+        tokio::time::advance(jump.forward()).await;
+        let result = tracker.continue_series(&first_header, &[0u8; 2]);
+        assert_eq!(result.is_err(), jump.passes(READ_TIMEOUT), "{jump:?}");
+        assert!(!tracker.has_active_series(), "{jump:?}");
+    }
+}
+
+#[rstest]
+#[case::correlated(Some(7))]
+#[case::uncorrelated(None)]
+fn envelope_payload_borrows_the_encoded_payload(#[case] correlation_id: Option<u64>) {
+    let payload = vec![0u8, 1, 2, 3, 0xff];
+    let bytes = Envelope::new(200, correlation_id, payload.clone())
+        .to_bytes()
+        .expect("encode envelope");
+
+    let borrowed = super::envelope_payload(&bytes).expect("decode envelope");
+
+    assert_eq!(borrowed, payload.as_slice());
+}
@@ -4,7 +4,9 @@
 //! clients that obfuscate text parameters by XOR-ing each byte with `0xFF`.
 //! The shim detects XOR-encoded inputs, transparently decodes inbound payloads
 //! and encodes outbound payloads when required, while keeping the domain layer
-//! unaware of the client-specific behaviour.
+//! unaware of the client-specific behaviour. The configured [`XorPolicy`]
+//! decides whether detected clients are translated, refused, or never looked
+//! for.
 
 use std::sync::atomic::{AtomicBool, Ordering};
 
@@ -16,39 +18,43 @@ use crate::{
 
 #[cfg(kani)]
 mod kani;
+mod policy;
+
+pub use policy::{XOR_REJECTED_REASON, XorPolicy, XorPolicyError};
 
 /// Per-connection XOR compatibility state.
 #[derive(Debug)]
 pub struct XorCompatibility {
     enabled: AtomicBool,
+    rejected: AtomicBool,
+    policy: XorPolicy,
 }
 
 impl XorCompatibility {
-    /// Construct a compatibility state seeded from handshake metadata.
+    /// Construct a compatibility state seeded from handshake metadata under
+    /// `policy`.
     ///
     /// This currently ignores the provided metadata and defaults to XOR
     /// disabled. It exists as a placeholder until a reliable handshake-based
     /// XOR detection rule is available.
     #[must_use]
-    pub const fn from_handshake(_handshake: &HandshakeMetadata) -> Self {
-        Self {
-            enabled: AtomicBool::new(false),
-        }
+    pub const fn from_handshake(_handshake: &HandshakeMetadata, policy: XorPolicy) -> Self {
+        Self::new(false, policy)
     }
 
-    /// Construct a compatibility state with XOR disabled.
+    /// Construct a permissive compatibility state with XOR disabled.
     #[must_use]
-    pub const fn disabled() -> Self {
-        Self {
-            enabled: AtomicBool::new(false),
-        }
-    }
+    pub const fn disabled() -> Self { Self::new(false, XorPolicy::Permissive) }
 
-    /// Construct a compatibility state with XOR enabled.
+    /// Construct a permissive compatibility state with XOR enabled.
     #[must_use]
-    pub const fn enabled() -> Self {
+    pub const fn enabled() -> Self { Self::new(true, XorPolicy::Permissive) }
+
+    const fn new(enabled: bool, policy: XorPolicy) -> Self {
         Self {
-            enabled: AtomicBool::new(true),
+            enabled: AtomicBool::new(enabled),
+            rejected: AtomicBool::new(false),
+            policy,
         }
     }
 
@@ -56,40 +62,47 @@ impl XorCompatibility {
     #[must_use]
     pub fn is_enabled(&self) -> bool { self.enabled.load(Ordering::Relaxed) }
 
+    /// Returns `true`, once, after the `strict` policy refused a request.
+    #[must_use]
+    pub fn take_rejection(&self) -> bool { self.rejected.swap(false, Ordering::Relaxed) }
+
     fn enable(&self) { self.enabled.store(true, Ordering::Relaxed); }
 
     /// Decode a parameter payload, transparently XOR-decoding text fields.
     ///
     /// When XOR is already enabled, all text fields are decoded. Otherwise, the
     /// decoder checks whether XOR-ing the text fields yields valid UTF-8 and
-    /// enables the compatibility mode on success.
+    /// enables the compatibility mode on success. Under the `strict` policy a
+    /// detection refuses the connection instead, and under `disabled` no
+    /// detection is attempted.
     ///
     /// # Errors
     ///
     /// Returns an error if the parameter payload cannot be decoded or
-    /// re-encoded.
+    /// re-encoded, or if the `strict` policy refuses XOR-encoded text.
     pub fn decode_payload(&self, payload: &[u8]) -> Result<Vec<u8>, TransactionError> {
-        if payload.is_empty() {
-            return Ok(Vec::new());
+        let enabled = self.is_enabled();
+        if payload.is_empty() || !(enabled || self.policy.detects()) {
+            return Ok(payload.to_vec());
         }
         let params = decode_params(payload)?;
-        if !params.iter().any(|(field, _)| is_text_field(*field)) {
+        let Some(first_text) = params
+            .iter()
+            .map(|(field, _)| *field)
+            .find(|field| is_text_field(*field))
+        else {
+            return Ok(payload.to_vec());
+        };
+        if !enabled && !detect_xor(&params) {
             return Ok(payload.to_vec());
         }
-
-        let enabled = self.is_enabled();
-        let should_xor = if enabled { true } else { detect_xor(&params) };
-
-        if should_xor {
-            let transformed = xor_params(&params);
-            let encoded = encode_params(&transformed)?;
-            if !enabled {
-                self.enable();
-            }
-            Ok(encoded)
-        } else {
-            Ok(payload.to_vec())
+        if !enabled && self.policy == XorPolicy::Strict {
+            self.rejected.store(true, Ordering::Relaxed);
+            return Err(TransactionError::InvalidParamValue(first_text));
         }
+        let encoded = encode_params(&xor_params(&params))?;
+        self.enable();
+        Ok(encoded)
     }
 
     /// Encode a parameter payload, XOR-ing text fields when enabled.
@@ -216,6 +229,34 @@ mod tests {
         assert_eq!(params[1].1, b"secret");
     }
 
+    #[rstest]
+    fn strict_policy_refuses_xor_text() {
+        let compat = XorCompatibility::new(false, XorPolicy::Strict);
+        let payload = build_payload(&[(FieldId::Login, xor_bytes(b"alice").as_slice())]);
+
+        let error = compat.decode_payload(&payload).expect_err("refused");
+
+        assert!(matches!(
+            error,
+            TransactionError::InvalidParamValue(FieldId::Login)
+        ));
+        assert!(compat.take_rejection());
+        assert!(!compat.take_rejection());
+        assert!(!compat.is_enabled());
+        let plain = build_payload(&[(FieldId::Login, b"alice")]);
+        assert_eq!(compat.decode_payload(&plain).expect("plaintext"), plain);
+    }
+
+    #[rstest]
+    fn disabled_policy_passes_xor_text_through() {
+        let compat = XorCompatibility::new(false, XorPolicy::Disabled);
+        let payload = build_payload(&[(FieldId::Login, xor_bytes(b"alice").as_slice())]);
+
+        assert_eq!(compat.decode_payload(&payload).expect("decode"), payload);
+        assert!(!compat.is_enabled());
+        assert!(!compat.take_rejection());
+    }
+
     #[rstest]
     fn decode_payload_keeps_plaintext_when_valid() {
         let compat = XorCompatibility::disabled();
@@ -0,0 +1,84 @@
+//! Whether XOR-obfuscated clients are accepted.
+//!
+//! The `compat_policy` setting chooses an [`XorPolicy`], which each server
+//! keeps in its settings and hands to every Wireframe connection's
+//! [`super::XorCompatibility`]. `permissive`, the default, detects XOR-encoded
+//! text and translates it. `strict` detects it but refuses the request, and the
+//! transaction middleware then asks the client to hang up with
+//! [`XOR_REJECTED_REASON`]. `disabled` skips detection, so XOR-encoded text
+//! reaches handlers as sent and fails like any other malformed text.
+
+use thiserror::Error;
+
+use crate::server::AppConfig;
+
+/// Reason given in the Disconnect Message sent to a client refused by the
+/// `strict` policy.
+pub const XOR_REJECTED_REASON: &str = "XOR-obfuscated clients are not accepted";
+
+/// How the server treats clients that XOR their text fields.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum XorPolicy {
+    /// Detect XOR-encoded text and translate it for the connection.
+    #[default]
+    Permissive,
+    /// Detect XOR-encoded text and refuse the connection.
+    Strict,
+    /// Never look for XOR-encoded text.
+    Disabled,
+}
+
+/// Errors raised while reading the policy from configuration.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum XorPolicyError {
+    /// `compat_policy` named no known policy.
+    #[error("compat_policy must be strict, permissive, or disabled, not {0:?}")]
+    InvalidMode(String),
+}
+
+impl XorPolicy {
+    /// Read the policy from `config`; an unset `compat_policy` selects
+    /// [`Self::Permissive`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`XorPolicyError::InvalidMode`] for an unrecognised policy.
+    pub fn from_config(config: &AppConfig) -> Result<Self, XorPolicyError> {
+        match config.compat_policy.as_deref().map(str::trim) {
+            None | Some("permissive") => Ok(Self::Permissive),
+            Some("strict") => Ok(Self::Strict),
+            Some("disabled") => Ok(Self::Disabled),
+            Some(other) => Err(XorPolicyError::InvalidMode(other.to_owned())),
+        }
+    }
+
+    /// Whether connections look for XOR-encoded text at all.
+    #[must_use]
+    pub const fn detects(self) -> bool { !matches!(self, Self::Disabled) }
+}
+
+#[cfg(test)]
+mod tests {
+    //! Reading the XOR policy from configuration.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(None, Ok(XorPolicy::Permissive))]
+    #[case(Some("permissive"), Ok(XorPolicy::Permissive))]
+    #[case(Some(" strict "), Ok(XorPolicy::Strict))]
+    #[case(Some("disabled"), Ok(XorPolicy::Disabled))]
+    #[case(Some("lenient"), Err(XorPolicyError::InvalidMode("lenient".to_owned())))]
+    fn reads_xor_policy(
+        #[case] mode: Option<&str>,
+        #[case] expected: Result<XorPolicy, XorPolicyError>,
+    ) {
+        let config = AppConfig {
+            compat_policy: mode.map(str::to_owned),
+            ..AppConfig::default()
+        };
+        assert_eq!(XorPolicy::from_config(&config), expected);
+    }
+}
@@ -9,8 +9,8 @@
 use std::net::SocketAddr;
 
 use crate::{
-    commands::{Command, CommandContext, CommandError},
-    transaction::{FrameHeader, Transaction},
+    commands::{Command, CommandContext, CommandError, ERR_INTERNAL_SERVER},
+    transaction::{FrameHeader, Transaction, TransactionError},
     transaction_type::TransactionType,
     wireframe::{
         auth_strategy::AuthStrategy,
@@ -18,12 +18,10 @@ use crate::{
         compat_policy::ClientCompatibility,
         login_reply_augmenter::LoginReplyAugmenter,
         routes::reply_builder::ReplyBuilder,
+        transcode::TextEncoding,
     },
 };
 
-/// Error code for internal server failures.
-const ERR_INTERNAL: u32 = 3;
-
 /// Centralized compatibility hooks for the transaction lifecycle.
 ///
 /// The router constructs this layer after request hooks have run and client
@@ -88,16 +86,41 @@ impl<'a> RequestCompatibility<'a> {
         Self { xor, client }
     }
 
-    /// Decode payload compatibility and record login version metadata.
+    /// Verify any payload checksum, decode payload compatibility, record login
+    /// version metadata, and transcode the client's text to UTF-8.
+    ///
+    /// Transcoding runs after the login version is recorded, so a classic
+    /// client's first login is already decoded from MacRoman.
     pub(crate) fn on_request(
         &self,
         peer: SocketAddr,
         tx_type: TransactionType,
         transaction: Transaction,
     ) -> Result<Transaction, Vec<u8>> {
-        let tx = decode_payload_for_request(self.xor, peer, tx_type, transaction)?;
+        let verified = verify_checksum_for_request(self.client, peer, tx_type, transaction)?;
+        let tx = decode_payload_for_request(self.xor, peer, tx_type, verified)?;
         record_login_version_for_request(self.client, tx_type, &tx.payload);
-        Ok(tx)
+        transcode_request(self.client.text_encoding(), peer, tx_type, tx)
+    }
+}
+
+/// Strip a request's payload checksum, refusing corrupt requests with an
+/// error the client may retry.
+fn verify_checksum_for_request(
+    client: &ClientCompatibility,
+    peer: SocketAddr,
+    tx_type: TransactionType,
+    transaction: Transaction,
+) -> Result<Transaction, Vec<u8>> {
+    if tx_type.bypass_payload_decode() {
+        return Ok(transaction);
+    }
+    let Transaction { header, payload } = transaction;
+    match client.check_request_checksum(tx_type, &payload) {
+        Ok(None) => Ok(Transaction { header, payload }),
+        Ok(Some(stripped)) => with_payload(header.clone(), stripped)
+            .map_err(|error| ReplyBuilder::from_header(peer, &header).command_parse_error(error)),
+        Err(error) => Err(ReplyBuilder::from_header(peer, &header).command_parse_error(error)),
     }
 }
 
@@ -114,8 +137,9 @@ fn decode_payload_for_request(
     let decoded_payload = match xor.decode_payload(&transaction.payload) {
         Ok(payload) => payload,
         Err(error) => {
-            return Err(ReplyBuilder::from_header(peer, &transaction.header)
-                .command_parse_error(error, ERR_INTERNAL));
+            return Err(
+                ReplyBuilder::from_header(peer, &transaction.header).command_parse_error(error)
+            );
         }
     };
     Ok(Transaction {
@@ -124,6 +148,35 @@ fn decode_payload_for_request(
     })
 }
 
+/// Convert a request's text fields from the client's encoding to UTF-8.
+fn transcode_request(
+    encoding: TextEncoding,
+    peer: SocketAddr,
+    tx_type: TransactionType,
+    transaction: Transaction,
+) -> Result<Transaction, Vec<u8>> {
+    if tx_type.bypass_payload_decode() {
+        return Ok(transaction);
+    }
+    let Transaction { header, payload } = transaction;
+    encoding
+        .decode_payload(&payload)
+        .and_then(|decoded| with_payload(header.clone(), decoded))
+        .map_err(|error| ReplyBuilder::from_header(peer, &header).command_parse_error(error))
+}
+
+/// Rebuild a request around `payload`, whose length may differ from the
+/// original.
+fn with_payload(
+    mut header: FrameHeader,
+    payload: Vec<u8>,
+) -> Result<Transaction, TransactionError> {
+    let len = u32::try_from(payload.len()).map_err(|_| TransactionError::PayloadTooLarge)?;
+    header.total_size = len;
+    header.data_size = len;
+    Ok(Transaction { header, payload })
+}
+
 /// Record login version metadata for the compatibility policy.
 fn record_login_version_for_request(
     client: &ClientCompatibility,
@@ -150,7 +203,7 @@ pub(crate) fn finalize_reply(
     compat_layer: &CompatibilityLayer<'_>,
 ) -> Vec<u8> {
     transport.take_reply().map_or_else(
-        || ReplyBuilder::from_header(peer, header).missing_reply(ERR_INTERNAL),
+        || ReplyBuilder::from_header(peer, header).missing_reply(ERR_INTERNAL_SERVER),
         |mut reply| {
             #[cfg(test)]
             crate::wireframe::router::compat_spy::record(
@@ -163,182 +216,5 @@ pub(crate) fn finalize_reply(
 }
 
 #[cfg(test)]
-mod tests {
-    //! Tests for this module.
-    use std::{
-        net::SocketAddr,
-        sync::atomic::{AtomicU32, Ordering},
-    };
-
-    use tokio::runtime::Builder;
-
-    use super::{CompatibilityLayer, decode_payload_for_request};
-    use crate::{
-        commands::{Command, CommandContext},
-        handler::Session,
-        presence::PresenceRegistry,
-        server::outbound::{NoopOutboundMessaging, ReplyBuffer},
-        transaction::{FrameHeader, Transaction},
-        transaction_type::TransactionType,
-        wireframe::{
-            auth_strategy::{AuthStrategy, AuthStrategyFuture},
-            compat::XorCompatibility,
-            login_reply_augmenter::LoginReplyAugmenter,
-            test_helpers::dummy_pool,
-        },
-    };
-
-    struct SpyAuthStrategy {
-        login_calls: AtomicU32,
-    }
-
-    impl SpyAuthStrategy {
-        const fn new() -> Self {
-            Self {
-                login_calls: AtomicU32::new(0),
-            }
-        }
-    }
-
-    impl AuthStrategy for SpyAuthStrategy {
-        fn authenticate<'a>(
-            &self,
-            command: Command,
-            context: CommandContext<'a>,
-        ) -> AuthStrategyFuture<'a> {
-            self.login_calls.fetch_add(1, Ordering::SeqCst);
-            Box::pin(async move { command.process_with_outbound(context).await })
-        }
-    }
-
-    struct SpyReplyAugmenter {
-        calls: AtomicU32,
-    }
-
-    impl SpyReplyAugmenter {
-        const fn new() -> Self {
-            Self {
-                calls: AtomicU32::new(0),
-            }
-        }
-    }
-
-    impl LoginReplyAugmenter for SpyReplyAugmenter {
-        fn augment(&self, _reply: &mut Transaction) { self.calls.fetch_add(1, Ordering::SeqCst); }
-    }
-
-    fn header(tx_type: TransactionType) -> FrameHeader {
-        FrameHeader {
-            flags: 0,
-            is_reply: 0,
-            ty: u16::from(tx_type),
-            id: 1,
-            error: 0,
-            total_size: 0,
-            data_size: 0,
-        }
-    }
-
-    fn run_auth_strategy_test(tx_type: TransactionType, expected_auth_calls: u32) {
-        let auth = SpyAuthStrategy::new();
-        let augmenter = SpyReplyAugmenter::new();
-        let layer = CompatibilityLayer::new(&auth, &augmenter);
-        let mut session = Session::default();
-        let mut transport = ReplyBuffer::new();
-        let messaging = NoopOutboundMessaging;
-        let presence = PresenceRegistry::default();
-        let context = CommandContext {
-            peer: match "127.0.0.1:12345".parse() {
-                Ok(peer) => peer,
-                Err(err) => panic!("valid loopback socket: {err}"),
-            },
-            pool: dummy_pool(),
-            session: &mut session,
-            transport: &mut transport,
-            messaging: &messaging,
-            presence: &presence,
-            presence_connection_id: Some(crate::server::outbound::OutboundConnectionId::new(1)),
-        };
-        let command = Command::Unknown {
-            header: header(tx_type),
-        };
-
-        let runtime = match Builder::new_current_thread().enable_all().build() {
-            Ok(runtime) => runtime,
-            Err(err) => panic!("runtime builds: {err}"),
-        };
-        if let Err(err) = runtime.block_on(layer.process_command(tx_type, command, context)) {
-            panic!("command succeeds: {err}");
-        }
-
-        assert_eq!(
-            auth.login_calls.load(Ordering::SeqCst),
-            expected_auth_calls,
-            "unexpected auth strategy call count for {tx_type:?}"
-        );
-    }
-
-    #[test]
-    fn login_commands_use_auth_strategy() { run_auth_strategy_test(TransactionType::Login, 1); }
-
-    #[test]
-    fn non_login_commands_bypass_auth_strategy() {
-        run_auth_strategy_test(TransactionType::GetFileNameList, 0);
-    }
-
-    #[test]
-    fn file_list_payload_bypasses_param_decode() {
-        let peer: SocketAddr = "127.0.0.1:12345".parse().expect("valid loopback socket");
-        let xor = XorCompatibility::enabled();
-        let transaction = Transaction {
-            header: header(TransactionType::GetFileNameList),
-            payload: vec![0xff, 0x00, 0x01, 0x02, 0x03],
-        };
-
-        let decoded = decode_payload_for_request(
-            &xor,
-            peer,
-            TransactionType::GetFileNameList,
-            transaction.clone(),
-        )
-        .expect("file-list payload should bypass parameter decode");
-        let non_bypassed = decode_payload_for_request(
-            &xor,
-            peer,
-            TransactionType::NewsArticleData,
-            transaction.clone(),
-        );
-
-        assert!(
-            xor.is_enabled(),
-            "xor state should remain non-trivial for the bypass check"
-        );
-        assert!(
-            match non_bypassed.as_ref() {
-                Err(_) => true,
-                Ok(non_bypassed_decoded) => non_bypassed_decoded.payload != transaction.payload,
-            },
-            "non-bypassed transaction should not preserve the opaque payload"
-        );
-        assert_eq!(decoded.payload, transaction.payload);
-    }
-
-    #[test]
-    fn on_reply_calls_login_reply_augmenter() {
-        let auth = SpyAuthStrategy::new();
-        let augmenter = SpyReplyAugmenter::new();
-        let layer = CompatibilityLayer::new(&auth, &augmenter);
-        let mut reply = Transaction {
-            header: header(TransactionType::Login),
-            payload: Vec::new(),
-        };
-
-        layer.on_reply(&mut reply);
-
-        assert_eq!(
-            augmenter.calls.load(Ordering::SeqCst),
-            1,
-            "reply augmenter should run once"
-        );
-    }
-}
+#[path = "compat_layer_tests.rs"]
+mod tests;
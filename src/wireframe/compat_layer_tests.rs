@@ -0,0 +1,219 @@
+//! Tests for the request and reply compatibility hooks.
+use std::{
+    net::SocketAddr,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use tokio::runtime::Builder;
+
+use super::{CompatibilityLayer, decode_payload_for_request, verify_checksum_for_request};
+use crate::{
+    commands::{Command, CommandContext, ERR_CHECKSUM_MISMATCH},
+    field_id::FieldId,
+    handler::Session,
+    presence::PresenceRegistry,
+    server::{
+        outbound::{NoopOutboundMessaging, ReplyBuffer},
+        settings::ServerSettings,
+    },
+    transaction::{FrameHeader, Transaction, append_checksum, encode_params, parse_transaction},
+    transaction_type::TransactionType,
+    wireframe::{
+        auth_strategy::{AuthStrategy, AuthStrategyFuture},
+        compat::XorCompatibility,
+        compat_policy::ClientCompatibility,
+        connection::HandshakeMetadata,
+        login_reply_augmenter::LoginReplyAugmenter,
+        test_helpers::dummy_pool,
+    },
+};
+
+struct SpyAuthStrategy {
+    login_calls: AtomicU32,
+}
+
+impl SpyAuthStrategy {
+    const fn new() -> Self {
+        Self {
+            login_calls: AtomicU32::new(0),
+        }
+    }
+}
+
+impl AuthStrategy for SpyAuthStrategy {
+    fn authenticate<'a>(
+        &self,
+        command: Command,
+        context: CommandContext<'a>,
+    ) -> AuthStrategyFuture<'a> {
+        self.login_calls.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move { command.process_with_outbound(context).await })
+    }
+}
+
+struct SpyReplyAugmenter {
+    calls: AtomicU32,
+}
+
+impl SpyReplyAugmenter {
+    const fn new() -> Self {
+        Self {
+            calls: AtomicU32::new(0),
+        }
+    }
+}
+
+impl LoginReplyAugmenter for SpyReplyAugmenter {
+    fn augment(&self, _reply: &mut Transaction) { self.calls.fetch_add(1, Ordering::SeqCst); }
+}
+
+fn header(tx_type: TransactionType) -> FrameHeader {
+    FrameHeader {
+        flags: 0,
+        is_reply: 0,
+        ty: u16::from(tx_type),
+        id: 1,
+        error: 0,
+        total_size: 0,
+        data_size: 0,
+    }
+}
+
+fn run_auth_strategy_test(tx_type: TransactionType, expected_auth_calls: u32) {
+    let auth = SpyAuthStrategy::new();
+    let augmenter = SpyReplyAugmenter::new();
+    let layer = CompatibilityLayer::new(&auth, &augmenter);
+    let mut session = Session::default();
+    let mut transport = ReplyBuffer::new();
+    let messaging = NoopOutboundMessaging;
+    let presence = PresenceRegistry::default();
+    let settings = ServerSettings::default();
+    let context = CommandContext {
+        peer: match "127.0.0.1:12345".parse() {
+            Ok(peer) => peer,
+            Err(err) => panic!("valid loopback socket: {err}"),
+        },
+        pool: dummy_pool(),
+        session: &mut session,
+        transport: &mut transport,
+        messaging: &messaging,
+        presence: &presence,
+        presence_connection_id: Some(crate::server::outbound::OutboundConnectionId::new(1)),
+        settings: &settings,
+    };
+    let command = Command::Unknown {
+        header: header(tx_type),
+    };
+
+    let runtime = match Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(err) => panic!("runtime builds: {err}"),
+    };
+    if let Err(err) = runtime.block_on(layer.process_command(tx_type, command, context)) {
+        panic!("command succeeds: {err}");
+    }
+
+    assert_eq!(
+        auth.login_calls.load(Ordering::SeqCst),
+        expected_auth_calls,
+        "unexpected auth strategy call count for {tx_type:?}"
+    );
+}
+
+#[test]
+fn login_commands_use_auth_strategy() { run_auth_strategy_test(TransactionType::Login, 1); }
+
+#[test]
+fn non_login_commands_bypass_auth_strategy() {
+    run_auth_strategy_test(TransactionType::GetFileNameList, 0);
+}
+
+#[test]
+fn file_list_payload_bypasses_param_decode() {
+    let peer: SocketAddr = "127.0.0.1:12345".parse().expect("valid loopback socket");
+    let xor = XorCompatibility::enabled();
+    let transaction = Transaction {
+        header: header(TransactionType::GetFileNameList),
+        payload: vec![0xff, 0x00, 0x01, 0x02, 0x03],
+    };
+
+    let decoded = decode_payload_for_request(
+        &xor,
+        peer,
+        TransactionType::GetFileNameList,
+        transaction.clone(),
+    )
+    .expect("file-list payload should bypass parameter decode");
+    let non_bypassed = decode_payload_for_request(
+        &xor,
+        peer,
+        TransactionType::NewsArticleData,
+        transaction.clone(),
+    );
+
+    assert!(
+        xor.is_enabled(),
+        "xor state should remain non-trivial for the bypass check"
+    );
+    assert!(
+        match non_bypassed.as_ref() {
+            Err(_) => true,
+            Ok(non_bypassed_decoded) => non_bypassed_decoded.payload != transaction.payload,
+        },
+        "non-bypassed transaction should not preserve the opaque payload"
+    );
+    assert_eq!(decoded.payload, transaction.payload);
+}
+
+#[test]
+fn checksummed_requests_are_stripped_or_refused() {
+    let peer: SocketAddr = "127.0.0.1:12345".parse().expect("valid loopback socket");
+    let client = ClientCompatibility::from_handshake(&HandshakeMetadata::default());
+    let body = encode_params(&[(FieldId::Data, b"hello".as_slice())]).expect("payload encodes");
+    let mut payload = append_checksum(&body).expect("append checksum");
+    let request = |payload: Vec<u8>| Transaction {
+        header: header(TransactionType::SendChat),
+        payload,
+    };
+
+    let stripped = verify_checksum_for_request(
+        &client,
+        peer,
+        TransactionType::SendChat,
+        request(payload.clone()),
+    )
+    .expect("valid checksum accepted");
+    assert_eq!(stripped.payload, body);
+    assert_eq!(
+        usize::try_from(stripped.header.data_size).expect("size fits usize"),
+        body.len()
+    );
+
+    let last = payload.len() - 1;
+    payload[last] ^= 0xff;
+    let refused =
+        verify_checksum_for_request(&client, peer, TransactionType::SendChat, request(payload))
+            .expect_err("corrupt payload refused");
+    let reply = parse_transaction(&refused).expect("error reply parses");
+    assert_eq!(reply.header.error, ERR_CHECKSUM_MISMATCH);
+    assert_eq!(reply.header.id, 1);
+}
+
+#[test]
+fn on_reply_calls_login_reply_augmenter() {
+    let auth = SpyAuthStrategy::new();
+    let augmenter = SpyReplyAugmenter::new();
+    let layer = CompatibilityLayer::new(&auth, &augmenter);
+    let mut reply = Transaction {
+        header: header(TransactionType::Login),
+        payload: Vec::new(),
+    };
+
+    layer.on_reply(&mut reply);
+
+    assert_eq!(
+        augmenter.calls.load(Ordering::SeqCst),
+        1,
+        "reply augmenter should run once"
+    );
+}
@@ -5,11 +5,21 @@
 //! 160): `151..=189` maps to Hotline 1.8.5 and `>=190` maps to Hotline 1.9.
 //! The adapter uses this policy to decide which login reply fields to include
 //! without leaking quirks into the domain layer.
+//!
+//! A client of any kind may also send the mxd `MaxFrameData` (166) field with
+//! its login to ask for frames larger than 32 KiB. The successful login reply
+//! then carries the size granted, which the connection's codec uses from then
+//! on (see [`FrameDataLimit`]).
+//!
+//! A client that sends its login with a valid `PayloadChecksum` (167) field
+//! offers checksums. Once that login succeeds, every payload in both
+//! directions must carry one (see [`crate::transaction::checksum`]).
 
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 use crate::{
     field_id::FieldId,
+    news_handlers::NewsListingEncoding,
     transaction::{
         Transaction,
         TransactionError,
@@ -17,15 +27,17 @@ use crate::{
         encode_params,
         read_u16,
         read_u32,
+        verify_checksum,
     },
     transaction_type::TransactionType,
-    wireframe::connection::HandshakeMetadata,
+    wireframe::{codec::FrameDataLimit, connection::HandshakeMetadata, transcode::TextEncoding},
 };
 
 #[cfg(kani)]
 mod kani;
 
 const UNKNOWN_LOGIN_VERSION: u32 = u32::MAX;
+const NO_FRAME_REQUEST: u32 = 0;
 const SYNHX_SUB_VERSION: u16 = 2;
 const HOTLINE_85_MIN_VERSION: u16 = 151;
 const HOTLINE_19_MIN_VERSION: u16 = 190;
@@ -50,18 +62,74 @@ pub enum ClientKind {
 pub struct ClientCompatibility {
     handshake_sub_version: u16,
     login_version: AtomicU32,
+    requested_frame_data: AtomicU32,
+    frame_limit: FrameDataLimit,
+    checksums_offered: AtomicBool,
+    checksums: AtomicBool,
 }
 
 impl ClientCompatibility {
     /// Seed a compatibility policy from handshake metadata.
     #[must_use]
-    pub const fn from_handshake(handshake: &HandshakeMetadata) -> Self {
+    pub fn from_handshake(handshake: &HandshakeMetadata) -> Self {
         Self {
             handshake_sub_version: handshake.sub_version,
             login_version: AtomicU32::new(UNKNOWN_LOGIN_VERSION),
+            requested_frame_data: AtomicU32::new(NO_FRAME_REQUEST),
+            frame_limit: FrameDataLimit::default(),
+            checksums_offered: AtomicBool::new(false),
+            checksums: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns true once payloads in both directions carry checksums.
+    #[must_use]
+    pub fn checksums_enabled(&self) -> bool { self.checksums.load(Ordering::Relaxed) }
+
+    /// Verify and strip the checksum of a request payload.
+    ///
+    /// Returns the payload without its checksum, or `None` when it carries
+    /// none and none is required. A checksummed login offers checksums for
+    /// the rest of the connection. Malformed payloads are left for the command
+    /// parser to report until checksums are enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TransactionError::ChecksumMismatch`] for a corrupt payload,
+    /// and [`TransactionError::MissingField`] when checksums are enabled and
+    /// the payload has none.
+    pub fn check_request_checksum(
+        &self,
+        tx_type: TransactionType,
+        payload: &[u8],
+    ) -> Result<Option<Vec<u8>>, TransactionError> {
+        let enabled = self.checksums_enabled();
+        match verify_checksum(payload) {
+            Ok(Some(stripped)) => {
+                if tx_type == TransactionType::Login {
+                    self.checksums_offered.store(true, Ordering::Relaxed);
+                }
+                Ok(Some(stripped))
+            }
+            Ok(None) if enabled => Err(TransactionError::MissingField(FieldId::PayloadChecksum)),
+            Err(error) if enabled || matches!(error, TransactionError::ChecksumMismatch) => {
+                Err(error)
+            }
+            Ok(None) | Err(_) => Ok(None),
         }
     }
 
+    /// Return the connection's frame data limit, for its codec to follow.
+    #[must_use]
+    pub fn frame_limit(&self) -> FrameDataLimit { self.frame_limit.clone() }
+
+    /// Return the frame size the client asked for at login, if any.
+    #[must_use]
+    pub fn requested_frame_data(&self) -> Option<u32> {
+        let requested = self.requested_frame_data.load(Ordering::Relaxed);
+        (requested != NO_FRAME_REQUEST).then_some(requested)
+    }
+
     /// Record the client version observed in the login request.
     pub fn record_login_version(&self, version: u16) {
         self.login_version
@@ -96,19 +164,57 @@ impl ClientCompatibility {
         matches!(self.kind(), ClientKind::Hotline85 | ClientKind::Hotline19)
     }
 
-    /// Capture the login version from the login request payload.
+    /// Select the news category listing encoding for this connection.
+    ///
+    /// Hotline 1.8.5 and 1.9 clients receive typed, count-prefixed entries and
+    /// clients reporting an older login version receive type-byte entries.
+    /// `SynHX` and clients without a recorded login version keep bare names.
+    #[must_use]
+    pub fn news_listing_encoding(&self) -> NewsListingEncoding {
+        match self.kind() {
+            ClientKind::Hotline85 | ClientKind::Hotline19 => NewsListingEncoding::Hotline18,
+            ClientKind::Unknown if self.login_version().is_some() => NewsListingEncoding::Hotline15,
+            ClientKind::SynHx | ClientKind::Unknown => NewsListingEncoding::Names,
+        }
+    }
+
+    /// Select the character set this connection's client uses for text.
+    ///
+    /// Classic Mac OS clients (Hotline 1.8.5, 1.9, and older versions that
+    /// report a login version) use MacRoman. `SynHX` and clients without a
+    /// recorded login version keep UTF-8.
+    #[must_use]
+    pub fn text_encoding(&self) -> TextEncoding {
+        match self.kind() {
+            ClientKind::Hotline85 | ClientKind::Hotline19 => TextEncoding::MacRoman,
+            ClientKind::Unknown if self.login_version().is_some() => TextEncoding::MacRoman,
+            ClientKind::SynHx | ClientKind::Unknown => TextEncoding::Utf8,
+        }
+    }
+
+    /// Capture the login version and any requested frame size from the login
+    /// request payload.
     ///
     /// # Errors
     ///
     /// Returns a [`TransactionError`] when the payload cannot be decoded.
     pub fn record_login_payload(&self, payload: &[u8]) -> Result<(), TransactionError> {
-        if let Some(version) = extract_login_version(payload)? {
+        let params = decode_params(payload)?;
+        if let Some(version) = find_param(&params, FieldId::Version).and_then(parse_login_version) {
             self.record_login_version(version);
         }
+        if let Some(requested) =
+            find_param(&params, FieldId::MaxFrameData).and_then(|data| read_u32(data).ok())
+        {
+            self.requested_frame_data
+                .store(requested, Ordering::Relaxed);
+        }
         Ok(())
     }
 
-    /// Augment a successful login reply with banner fields when required.
+    /// Augment a successful login reply with banner fields when required, and
+    /// with the granted frame size when the client asked for one. Checksums
+    /// offered with the login are enabled from this reply on.
     ///
     /// Returns `true` if the reply was modified.
     ///
@@ -120,23 +226,20 @@ impl ClientCompatibility {
         if !is_successful_login_reply(reply) {
             return Ok(false);
         }
-        if !self.should_include_login_extras() {
+        if self.checksums_offered.load(Ordering::Relaxed) {
+            self.checksums.store(true, Ordering::Relaxed);
+        }
+        let include_extras = self.should_include_login_extras();
+        let requested_frame_data = self.requested_frame_data();
+        if !include_extras && requested_frame_data.is_none() {
             return Ok(false);
         }
         let mut params = decode_params(&reply.payload)?;
-        let has_banner_id = params.iter().any(|(id, _)| *id == FieldId::BannerId);
-        let has_server_name = params.iter().any(|(id, _)| *id == FieldId::ServerName);
-
-        if !has_banner_id {
-            #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
-            let banner_id_bytes = DEFAULT_BANNER_ID.to_be_bytes();
-            params.push((FieldId::BannerId, banner_id_bytes.to_vec()));
-        }
-        if !has_server_name {
-            params.push((FieldId::ServerName, DEFAULT_SERVER_NAME.as_bytes().to_vec()));
+        let mut updated = include_extras && push_login_extras(&mut params);
+        if let Some(requested) = requested_frame_data {
+            updated |= self.push_frame_grant(&mut params, requested);
         }
-
-        if has_banner_id && has_server_name {
+        if !updated {
             return Ok(false);
         }
 
@@ -147,6 +250,43 @@ impl ClientCompatibility {
         reply.header.data_size = payload_len;
         Ok(true)
     }
+
+    /// Grant the client's `requested` frame size and add it to `params`,
+    /// returning `false` if the reply already states a size.
+    fn push_frame_grant(&self, params: &mut Vec<(FieldId, Vec<u8>)>, requested: u32) -> bool {
+        if find_param(params, FieldId::MaxFrameData).is_some() {
+            return false;
+        }
+        let requested_len = usize::try_from(requested).unwrap_or(usize::MAX);
+        let granted = u32::try_from(self.frame_limit.grant(requested_len)).unwrap_or(u32::MAX);
+        #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+        let granted_bytes = granted.to_be_bytes();
+        params.push((FieldId::MaxFrameData, granted_bytes.to_vec()));
+        true
+    }
+}
+
+/// Add the banner id and server name to `params` where missing, returning
+/// `true` if either was added.
+fn push_login_extras(params: &mut Vec<(FieldId, Vec<u8>)>) -> bool {
+    let has_banner_id = find_param(params, FieldId::BannerId).is_some();
+    let has_server_name = find_param(params, FieldId::ServerName).is_some();
+    if !has_banner_id {
+        #[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+        let banner_id_bytes = DEFAULT_BANNER_ID.to_be_bytes();
+        params.push((FieldId::BannerId, banner_id_bytes.to_vec()));
+    }
+    if !has_server_name {
+        params.push((FieldId::ServerName, DEFAULT_SERVER_NAME.as_bytes().to_vec()));
+    }
+    !(has_banner_id && has_server_name)
+}
+
+fn find_param(params: &[(FieldId, Vec<u8>)], field: FieldId) -> Option<&[u8]> {
+    params
+        .iter()
+        .find(|(id, _)| *id == field)
+        .map(|(_, data)| data.as_slice())
 }
 
 fn is_successful_login_reply(reply: &Transaction) -> bool {
@@ -156,16 +296,6 @@ fn is_successful_login_reply(reply: &Transaction) -> bool {
     is_reply && is_successful_login
 }
 
-fn extract_login_version(payload: &[u8]) -> Result<Option<u16>, TransactionError> {
-    let params = decode_params(payload)?;
-    for (field, data) in params {
-        if field == FieldId::Version {
-            return Ok(parse_login_version(&data));
-        }
-    }
-    Ok(None)
-}
-
 fn parse_login_version(data: &[u8]) -> Option<u16> {
     match data.len() {
         2 => read_u16(data).ok(),
@@ -8,7 +8,7 @@ use super::*;
 use crate::{
     commands::ERR_NOT_AUTHENTICATED,
     protocol::VERSION,
-    transaction::{FrameHeader, Transaction, encode_params},
+    transaction::{FrameHeader, Transaction, append_checksum, encode_params},
 };
 
 fn handshake(sub_version: u16) -> HandshakeMetadata {
@@ -246,3 +246,114 @@ fn record_login_payload_ignores_unparsable_version_lengths() {
     assert_eq!(compat.login_version(), None);
     assert_eq!(compat.kind(), ClientKind::Unknown);
 }
+
+#[rstest]
+#[case::synhx(SYNHX_SUB_VERSION, Some(190), NewsListingEncoding::Names)]
+#[case::no_login_version(0, None, NewsListingEncoding::Names)]
+#[case::legacy_hotline(0, Some(150), NewsListingEncoding::Hotline15)]
+#[case::hotline_85(0, Some(151), NewsListingEncoding::Hotline18)]
+#[case::hotline_19(0, Some(190), NewsListingEncoding::Hotline18)]
+fn selects_news_listing_encoding_by_client(
+    #[case] sub_version: u16,
+    #[case] login_version: Option<u16>,
+    #[case] expected: NewsListingEncoding,
+) {
+    let compat = ClientCompatibility::from_handshake(&handshake(sub_version));
+    if let Some(version) = login_version {
+        compat.record_login_version(version);
+    }
+
+    assert_eq!(compat.news_listing_encoding(), expected);
+}
+
+#[rstest]
+#[case::synhx(SYNHX_SUB_VERSION, Some(190), TextEncoding::Utf8)]
+#[case::no_login_version(0, None, TextEncoding::Utf8)]
+#[case::legacy_hotline(0, Some(150), TextEncoding::MacRoman)]
+#[case::hotline_85(0, Some(151), TextEncoding::MacRoman)]
+#[case::hotline_19(0, Some(190), TextEncoding::MacRoman)]
+fn selects_text_encoding_by_client(
+    #[case] sub_version: u16,
+    #[case] login_version: Option<u16>,
+    #[case] expected: TextEncoding,
+) {
+    let compat = ClientCompatibility::from_handshake(&handshake(sub_version));
+    if let Some(version) = login_version {
+        compat.record_login_version(version);
+    }
+
+    assert_eq!(compat.text_encoding(), expected);
+}
+
+#[rstest]
+fn checksummed_login_enables_checksums_once_it_succeeds() {
+    let compat = ClientCompatibility::from_handshake(&handshake(SYNHX_SUB_VERSION));
+    let body = encode_params(&[(FieldId::Login, b"alice".as_slice())]).expect("payload encodes");
+    let payload = append_checksum(&body).expect("append checksum");
+    assert_eq!(
+        compat
+            .check_request_checksum(TransactionType::SendChat, &body)
+            .expect("unchecked payload accepted"),
+        None
+    );
+
+    let stripped = compat
+        .check_request_checksum(TransactionType::Login, &payload)
+        .expect("checksummed login accepted");
+    assert_eq!(stripped, Some(body.clone()));
+    assert!(!compat.checksums_enabled());
+
+    let mut reply = Transaction {
+        header: reply_header(0),
+        payload: Vec::new(),
+    };
+    compat
+        .augment_login_reply(&mut reply)
+        .expect("augment reply");
+    assert!(compat.checksums_enabled());
+    assert!(matches!(
+        compat.check_request_checksum(TransactionType::SendChat, &body),
+        Err(TransactionError::MissingField(FieldId::PayloadChecksum))
+    ));
+}
+
+#[rstest]
+#[case::raised(Some(65_536), Some(65_536))]
+#[case::never_lowered(Some(1_024), Some(32_768))]
+#[case::not_requested(None, None)]
+fn grants_requested_frame_size_in_login_reply(
+    #[case] requested: Option<u32>,
+    #[case] granted: Option<u32>,
+) {
+    // SynHX gets no login extras, so only the frame size can change the reply.
+    let compat = ClientCompatibility::from_handshake(&handshake(SYNHX_SUB_VERSION));
+    #[expect(
+        clippy::big_endian_bytes,
+        reason = "network protocol uses big-endian integers"
+    )]
+    let request: Vec<(FieldId, Vec<u8>)> = requested
+        .map(|size| (FieldId::MaxFrameData, size.to_be_bytes().to_vec()))
+        .into_iter()
+        .collect();
+    compat
+        .record_login_payload(&encode_params(&request).expect("payload encodes"))
+        .expect("record login payload");
+    let mut reply = Transaction {
+        header: reply_header(0),
+        payload: Vec::new(),
+    };
+
+    let updated = compat
+        .augment_login_reply(&mut reply)
+        .expect("augment reply");
+
+    assert_eq!(updated, granted.is_some());
+    let params = decode_params(&reply.payload).expect("decode reply params");
+    let reply_grant = params
+        .iter()
+        .find(|(id, _)| *id == FieldId::MaxFrameData)
+        .map(|(_, data)| read_u32(data).expect("grant is a u32"));
+    assert_eq!(reply_grant, granted);
+    let expected_limit = granted.map_or(32_768, |size| usize::try_from(size).expect("fits"));
+    assert_eq!(compat.frame_limit().get(), expected_limit);
+}
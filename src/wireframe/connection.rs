@@ -16,12 +16,16 @@ use std::{
     cell::RefCell,
     collections::HashMap,
     net::SocketAddr,
-    sync::{Mutex, OnceLock},
+    sync::{Arc, Mutex, OnceLock},
 };
 
 use tokio::task::{self, Id};
 
-use crate::protocol::{Handshake, VERSION};
+use super::closer::SocketCloser;
+use crate::{
+    protocol::{Handshake, VERSION},
+    server::connection_limit::ConnectionSlot,
+};
 
 /// Handshake parameters captured from the Hotline preamble.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -75,6 +79,8 @@ impl From<&Handshake> for HandshakeMetadata {
 pub struct ConnectionContext {
     handshake: HandshakeMetadata,
     peer: Option<SocketAddr>,
+    slot: Option<ConnectionSlot>,
+    closer: Option<Arc<SocketCloser>>,
 }
 
 impl ConnectionContext {
@@ -84,6 +90,8 @@ impl ConnectionContext {
         Self {
             handshake,
             peer: None,
+            slot: None,
+            closer: None,
         }
     }
 
@@ -102,13 +110,37 @@ impl ConnectionContext {
         self
     }
 
-    /// Consume the context and return the handshake metadata and peer address.
+    /// Attach the connection's place within the connection limits.
+    #[must_use]
+    pub fn with_slot(mut self, slot: ConnectionSlot) -> Self {
+        self.slot = Some(slot);
+        self
+    }
+
+    /// Attach the handle the server uses to close the connection itself.
     #[must_use]
-    pub const fn into_parts(self) -> (HandshakeMetadata, Option<SocketAddr>) {
-        (self.handshake, self.peer)
+    pub fn with_closer(mut self, closer: Arc<SocketCloser>) -> Self {
+        self.closer = Some(closer);
+        self
+    }
+
+    /// Consume the context and return the handshake metadata, peer address,
+    /// connection slot, and socket closer.
+    #[must_use]
+    pub fn into_parts(self) -> ConnectionParts {
+        (self.handshake, self.peer, self.slot, self.closer)
     }
 }
 
+/// Parts of a [`ConnectionContext`], as returned by
+/// [`ConnectionContext::into_parts`].
+pub type ConnectionParts = (
+    HandshakeMetadata,
+    Option<SocketAddr>,
+    Option<ConnectionSlot>,
+    Option<Arc<SocketCloser>>,
+);
+
 tokio::task_local! {
     static CONNECTION_CONTEXT: RefCell<Option<ConnectionContext>>;
 }
@@ -206,177 +238,5 @@ pub fn take_current_context() -> Option<ConnectionContext> {
 pub fn has_current_context() -> bool { current_context().is_some() }
 
 #[cfg(test)]
-mod tests {
-    //! Tests for this module.
-    use rstest::rstest;
-    use serial_test::serial;
-    use tokio::{runtime::Builder, sync::Barrier, task};
-
-    use super::*;
-
-    fn metadata(sub_protocol: u32, sub_version: u16) -> HandshakeMetadata {
-        HandshakeMetadata {
-            sub_protocol,
-            version: VERSION,
-            sub_version,
-        }
-    }
-
-    fn registry_count_for_metadata(metadata: &[HandshakeMetadata]) -> usize {
-        registry()
-            .lock()
-            .unwrap_or_else(std::sync::PoisonError::into_inner)
-            .values()
-            .filter(|context| metadata.contains(context.handshake()))
-            .count()
-    }
-
-    #[rstest]
-    #[tokio::test]
-    async fn stores_and_reads_metadata_in_task() {
-        let meta = metadata(u32::from_be_bytes(*b"CHAT"), 7);
-        let context = ConnectionContext::new(meta.clone());
-        scope_current_context(None, async {
-            store_current_context(context.clone());
-            assert_eq!(current_context(), Some(context.clone()));
-            assert_eq!(context.handshake().sub_protocol_tag(), *b"CHAT");
-            let _ = take_current_context();
-            assert!(current_context().is_none());
-            assert!(!has_current_context());
-        })
-        .await;
-    }
-
-    #[rstest]
-    #[tokio::test]
-    async fn isolates_metadata_between_tasks() {
-        let first = task::spawn(async {
-            let meta = metadata(1, 1);
-            let context = ConnectionContext::new(meta.clone());
-            scope_current_context(None, async move {
-                store_current_context(context.clone());
-                let seen = current_context();
-                let _ = take_current_context();
-                seen
-            })
-            .await
-        });
-
-        let second = task::spawn(async {
-            let meta = metadata(2, 2);
-            let context = ConnectionContext::new(meta.clone());
-            scope_current_context(None, async move {
-                store_current_context(context.clone());
-                let seen = current_context();
-                let _ = take_current_context();
-                seen
-            })
-            .await
-        });
-
-        let (first_seen, second_seen) = tokio::join!(first, second);
-        assert_eq!(
-            first_seen
-                .expect("first task panicked")
-                .map(|context| context.handshake),
-            Some(metadata(1, 1))
-        );
-        assert_eq!(
-            second_seen
-                .expect("second task panicked")
-                .map(|context| context.handshake),
-            Some(metadata(2, 2))
-        );
-        assert!(!has_current_context());
-    }
-
-    #[rstest]
-    fn preserves_context_across_await_on_multi_worker_runtime() {
-        let runtime = Builder::new_multi_thread()
-            .worker_threads(2)
-            .enable_all()
-            .build()
-            .expect("build multi-worker runtime");
-        let barrier = std::sync::Arc::new(Barrier::new(2));
-
-        runtime.block_on(async {
-            let meta = metadata(u32::from_be_bytes(*b"CHAT"), 9);
-            let context = ConnectionContext::new(meta.clone());
-            let task_barrier = barrier.clone();
-
-            let seen = task::spawn(async move {
-                scope_current_context(Some(context.clone()), async move {
-                    task_barrier.wait().await;
-                    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
-                    take_current_context()
-                })
-                .await
-            });
-
-            barrier.wait().await;
-            assert_eq!(
-                seen.await.expect("context task panicked"),
-                Some(ConnectionContext::new(meta))
-            );
-        });
-    }
-
-    #[rstest]
-    #[tokio::test]
-    async fn scopes_context_for_post_handshake_handoff() {
-        let context = ConnectionContext::new(metadata(u32::from_be_bytes(*b"CHAT"), 5));
-        let scoped_context = context.clone();
-
-        let handed_off = task::spawn(async move {
-            scope_current_context(Some(scoped_context), async {}).await;
-            take_current_context()
-        })
-        .await
-        .expect("handoff task panicked");
-
-        assert_eq!(handed_off, Some(context));
-    }
-
-    #[rstest]
-    #[tokio::test]
-    #[serial]
-    async fn reports_registry_entry_count() {
-        let tracked_metadata = [metadata(0xfeed_0001, 1), metadata(0xfeed_0002, 2)];
-        let first = ConnectionContext::new(tracked_metadata[0].clone());
-        let second = ConnectionContext::new(tracked_metadata[1].clone());
-        let registered_barrier = std::sync::Arc::new(Barrier::new(3));
-        let release_barrier = std::sync::Arc::new(Barrier::new(3));
-
-        let first_registered_barrier = registered_barrier.clone();
-        let first_release_barrier = release_barrier.clone();
-        let first_task = task::spawn(async move {
-            scope_current_context(None, async move {
-                store_current_context(first);
-                first_registered_barrier.wait().await;
-                first_release_barrier.wait().await;
-                let _ = take_current_context();
-            })
-            .await;
-        });
-
-        let second_registered_barrier = registered_barrier.clone();
-        let second_release_barrier = release_barrier.clone();
-        let second_task = task::spawn(async move {
-            scope_current_context(None, async move {
-                store_current_context(second);
-                second_registered_barrier.wait().await;
-                second_release_barrier.wait().await;
-                let _ = take_current_context();
-            })
-            .await;
-        });
-
-        registered_barrier.wait().await;
-        assert_eq!(registry_count_for_metadata(&tracked_metadata), 2);
-        release_barrier.wait().await;
-
-        first_task.await.expect("first task panicked");
-        second_task.await.expect("second task panicked");
-        assert_eq!(registry_count_for_metadata(&tracked_metadata), 0);
-    }
-}
+#[path = "connection_tests.rs"]
+mod tests;
@@ -2,14 +2,17 @@
 //!
 //! This module wires the Hotline handshake semantics into the Wireframe runtime
 //! by registering preamble callbacks that emit the standard 8-byte reply and
-//! enforce the protocol's idle timeout, with reusable hooks for tests.
+//! enforce the protocol's idle timeout, with reusable hooks for tests. The
+//! success hook also admits the connection: it checks bans and the server's
+//! connection limits itself, or, behind the TLS front end, takes the
+//! admission the front end already made.
 
-use std::{io, time::Duration};
+use std::{io, net::SocketAddr, sync::Arc, time::Duration};
 
 use bincode::error::DecodeError;
 use futures_util::{FutureExt, future::BoxFuture};
 use tokio::net::TcpStream;
-use tracing::warn;
+use tracing::{info, warn};
 use wireframe::{
     app::Packet,
     codec::FrameCodec,
@@ -17,10 +20,11 @@ use wireframe::{
     server::{AppFactory, ServerState, WireframeServer},
 };
 
-use super::preamble::HotlinePreamble;
+use super::{closer::SocketCloser, preamble::HotlinePreamble};
 use crate::{
     protocol::{
         HANDSHAKE_ERR_INVALID,
+        HANDSHAKE_ERR_SERVER_FULL,
         HANDSHAKE_ERR_TIMEOUT,
         HANDSHAKE_ERR_UNSUPPORTED_VERSION,
         HANDSHAKE_INVALID_PROTOCOL_TOKEN,
@@ -28,6 +32,7 @@ use crate::{
         HANDSHAKE_UNSUPPORTED_VERSION_TOKEN,
         write_handshake_reply,
     },
+    server::{connection_limit::ConnectionSlot, settings::ServerSettings, tls::forwarded_client},
     wireframe::connection::{
         ConnectionContext,
         HandshakeMetadata,
@@ -36,16 +41,29 @@ use crate::{
     },
 };
 
+/// How the handshake hook admits a connection.
+#[derive(Clone, Debug)]
+pub enum Admission {
+    /// Clients connect to the listener directly; the hook checks the bans in
+    /// these settings and claims a slot from their limiter itself.
+    Direct(Arc<ServerSettings>),
+    /// The listener sits behind the TLS front end, which has already admitted
+    /// every client it relays; connections it did not open are refused.
+    Relayed,
+}
+
 /// Attach Hotline handshake behaviour to a [`WireframeServer`].
 ///
-/// The returned server writes the Hotline reply on success, returns Hotline
-/// error codes on decode failures, and times out idle sockets after `timeout`.
-/// Tests may call this with a shorter duration, while production code should
-/// use [`crate::protocol::HANDSHAKE_TIMEOUT`].
+/// The returned server admits each connection as `admission` says, writes the
+/// Hotline reply on success, returns Hotline error codes on decode failures,
+/// and times out idle sockets after `timeout`. Tests may call this with a
+/// shorter duration, while production code should use
+/// [`crate::protocol::HANDSHAKE_TIMEOUT`].
 #[must_use]
 pub fn install<F, S, Ser, Ctx, E, Codec>(
     server: WireframeServer<F, HotlinePreamble, S, Ser, Ctx, E, Codec>,
     timeout: Duration,
+    admission: Admission,
 ) -> WireframeServer<F, HotlinePreamble, S, Ser, Ctx, E, Codec>
 where
     F: AppFactory<Ser, Ctx, E, Codec>,
@@ -56,24 +74,37 @@ where
     Codec: FrameCodec,
 {
     server
-        .on_preamble_decode_success(success_handler())
+        .on_preamble_decode_success(success_handler(admission))
         .on_preamble_decode_failure(failure_handler())
         .preamble_timeout(timeout)
 }
 
-fn success_handler()
--> impl for<'a> Fn(&'a HotlinePreamble, &'a mut TcpStream) -> BoxFuture<'a, io::Result<()>> + Send + Sync
+fn success_handler(
+    admission: Admission,
+) -> impl for<'a> Fn(&'a HotlinePreamble, &'a mut TcpStream) -> BoxFuture<'a, io::Result<()>> + Send + Sync
 {
     move |preamble, stream| {
         let mut context = ConnectionContext::new(HandshakeMetadata::from(preamble.handshake()));
-        match stream.peer_addr() {
-            Ok(peer) => {
-                context = context.with_peer(peer);
-            }
+        let peer = match stream.peer_addr() {
+            Ok(peer) => peer,
             Err(error) => {
                 warn!(%error, "failed to retrieve peer address during handshake");
                 return async move { Err(error) }.boxed();
             }
+        };
+        match admit(&admission, peer) {
+            Ok((client, slot)) => {
+                context = context.with_peer(client).with_slot(slot);
+                match SocketCloser::from_stream(stream) {
+                    Ok(closer) => context = context.with_closer(closer),
+                    Err(error) => warn!(%error, "cannot close this connection from the server"),
+                }
+            }
+            Err(Refusal::Full) => return refuse_full(stream).boxed(),
+            Err(Refusal::Closed(refused)) => {
+                // Failing the hook closes the socket without a handshake reply.
+                return async move { Err(refused) }.boxed();
+            }
         }
 
         scope_current_context(Some(context.clone()), async move {
@@ -85,6 +116,57 @@ fn success_handler()
     }
 }
 
+/// Why the success hook turned a connection away.
+enum Refusal {
+    /// The connection limits are reached; the client is told the server is
+    /// full.
+    Full,
+    /// The connection is closed without a reply.
+    Closed(io::Error),
+}
+
+/// Admit the connection from socket peer `peer`, returning the client it
+/// serves and the slot it holds.
+fn admit(admission: &Admission, peer: SocketAddr) -> Result<(SocketAddr, ConnectionSlot), Refusal> {
+    let settings = match admission {
+        Admission::Direct(settings) => settings,
+        Admission::Relayed => {
+            // Behind the TLS front end the socket peer is the relay, and only
+            // connections the front end opened carry an admitted client.
+            let client = forwarded_client(peer).ok_or_else(|| {
+                warn!(%peer, "refused connection that bypassed the TLS front end");
+                Refusal::Closed(io::Error::new(
+                    io::ErrorKind::ConnectionRefused,
+                    "connection bypassed the TLS front end",
+                ))
+            })?;
+            return Ok((client.address, client.slot));
+        }
+    };
+    if settings.address_bans.is_banned(peer.ip()) {
+        info!(%peer, "refused connection from banned address");
+        return Err(Refusal::Closed(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            "address is banned",
+        )));
+    }
+    let Some(slot) = settings.connections.admit(peer.ip()) else {
+        info!(%peer, "refusing connection: connection limit reached");
+        return Err(Refusal::Full);
+    };
+    Ok((peer, slot))
+}
+
+/// Answer the handshake with [`HANDSHAKE_ERR_SERVER_FULL`], then fail the
+/// hook so the socket is closed.
+async fn refuse_full(stream: &mut TcpStream) -> io::Result<()> {
+    write_handshake_reply(stream, HANDSHAKE_ERR_SERVER_FULL).await?;
+    Err(io::Error::new(
+        io::ErrorKind::ConnectionRefused,
+        "connection limit reached",
+    ))
+}
+
 fn failure_handler()
 -> impl for<'a> Fn(&'a DecodeError, &'a mut TcpStream) -> BoxFuture<'a, io::Result<()>> + Send + Sync
 {
@@ -127,135 +209,8 @@ fn error_code_from_str(text: &str) -> Option<u32> {
 }
 
 #[cfg(test)]
-mod tests {
-    //! Tests for this module.
-    use std::time::Duration;
-
-    use rstest::rstest;
-    use tokio::{io::AsyncWriteExt, net::TcpStream, sync::oneshot, time::timeout};
-    use wireframe::{
-        app::{Envelope, WireframeApp},
-        serializer::BincodeSerializer,
-        server::WireframeServer,
-    };
-
-    use super::HotlinePreamble;
-    use crate::{
-        protocol::{
-            HANDSHAKE_ERR_INVALID,
-            HANDSHAKE_ERR_TIMEOUT,
-            HANDSHAKE_ERR_UNSUPPORTED_VERSION,
-            HANDSHAKE_OK,
-            HANDSHAKE_TIMEOUT,
-            PROTOCOL_ID,
-            VERSION,
-        },
-        wireframe::{
-            connection::take_current_context,
-            test_helpers::{preamble_bytes, recv_reply},
-        },
-    };
-
-    pub(super) fn start_server(timeout: Duration) -> (std::net::SocketAddr, oneshot::Sender<()>) {
-        let server = WireframeServer::new(|| {
-            let handshake = take_current_context()
-                .map(|context| context.into_parts().0)
-                .unwrap_or_default();
-            WireframeApp::<BincodeSerializer, (), Envelope>::default().app_data(handshake)
-        })
-        .with_preamble::<HotlinePreamble>();
-        let server = super::install(server, timeout);
-        let bind_addr = match "127.0.0.1:0".parse() {
-            Ok(addr) => addr,
-            Err(err) => panic!("parse socket addr: {err}"),
-        };
-        let server = match server.bind(bind_addr) {
-            Ok(server) => server,
-            Err(err) => panic!("bind: {err}"),
-        };
-        let Some(addr) = server.local_addr() else {
-            panic!("failed to obtain server.local_addr() when setting up handshake");
-        };
-        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
-        tokio::spawn(async move {
-            let _ = server
-                .run_with_shutdown(async {
-                    let _ = shutdown_rx.await;
-                })
-                .await;
-        });
-        (addr, shutdown_tx)
-    }
-
-    #[rstest]
-    #[tokio::test]
-    async fn replies_success() {
-        let (addr, shutdown) = start_server(HANDSHAKE_TIMEOUT);
-        let mut stream = TcpStream::connect(addr).await.expect("connect");
-        let bytes = preamble_bytes(*PROTOCOL_ID, *b"CHAT", VERSION, 7);
-        stream.write_all(&bytes).await.expect("write handshake");
-
-        let reply = recv_reply(&mut stream).await.expect("handshake reply");
-        assert_eq!(&reply[0..4], PROTOCOL_ID);
-        assert_eq!(
-            u32::from_be_bytes(
-                reply[4..8]
-                    .try_into()
-                    .expect("convert reply slice to array (ok)")
-            ),
-            HANDSHAKE_OK
-        );
-        let _ = shutdown.send(());
-    }
-
-    #[rstest]
-    #[case(*b"WRNG", HANDSHAKE_ERR_INVALID)]
-    #[case(*PROTOCOL_ID, HANDSHAKE_ERR_UNSUPPORTED_VERSION)]
-    #[tokio::test]
-    async fn replies_handshake_errors(#[case] protocol: [u8; 4], #[case] expected: u32) {
-        let (addr, shutdown) = start_server(HANDSHAKE_TIMEOUT);
-        let mut stream = TcpStream::connect(addr).await.expect("connect");
-        let version = if expected == HANDSHAKE_ERR_UNSUPPORTED_VERSION {
-            VERSION + 1
-        } else {
-            VERSION
-        };
-        let bytes = preamble_bytes(protocol, *b"CHAT", version, 0);
-        stream.write_all(&bytes).await.expect("write handshake");
-
-        let reply = recv_reply(&mut stream).await.expect("handshake reply");
-        assert_eq!(
-            u32::from_be_bytes(
-                reply[4..8]
-                    .try_into()
-                    .expect("convert reply slice to array (error path)")
-            ),
-            expected
-        );
-        let _ = shutdown.send(());
-    }
-
-    #[rstest]
-    #[tokio::test]
-    async fn replies_timeout_for_idle_socket() {
-        let (addr, shutdown) = start_server(Duration::from_millis(100));
-        let mut stream = TcpStream::connect(addr).await.expect("connect");
-
-        let reply = timeout(Duration::from_secs(1), recv_reply(&mut stream))
-            .await
-            .expect("reply timed out in test")
-            .expect("handshake reply");
-        assert_eq!(
-            u32::from_be_bytes(
-                reply[4..8]
-                    .try_into()
-                    .expect("convert reply slice to array (timeout)")
-            ),
-            HANDSHAKE_ERR_TIMEOUT
-        );
-        let _ = shutdown.send(());
-    }
-}
+#[path = "handshake_tests.rs"]
+mod tests;
 
 #[cfg(test)]
 #[path = "handshake_bdd.rs"]
@@ -0,0 +1,189 @@
+//! Tests for the Wireframe handshake hooks.
+
+use std::{sync::Arc, time::Duration};
+
+use rstest::rstest;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::oneshot,
+    time::timeout,
+};
+use wireframe::{
+    app::{Envelope, WireframeApp},
+    serializer::BincodeSerializer,
+    server::WireframeServer,
+};
+
+use super::{Admission, HotlinePreamble};
+use crate::{
+    protocol::{
+        HANDSHAKE_ERR_INVALID,
+        HANDSHAKE_ERR_TIMEOUT,
+        HANDSHAKE_ERR_UNSUPPORTED_VERSION,
+        HANDSHAKE_OK,
+        HANDSHAKE_TIMEOUT,
+        PROTOCOL_ID,
+        VERSION,
+    },
+    server::{
+        connection_limit::ConnectionLimiter,
+        tls::{ForwardedClient, forward_peer},
+    },
+    wireframe::{
+        connection::take_current_context,
+        test_helpers::{preamble_bytes, recv_reply},
+    },
+};
+
+pub(super) fn start_server(timeout: Duration) -> (std::net::SocketAddr, oneshot::Sender<()>) {
+    start_server_with(timeout, Admission::Direct(Arc::default()))
+}
+
+fn start_server_with(
+    timeout: Duration,
+    admission: Admission,
+) -> (std::net::SocketAddr, oneshot::Sender<()>) {
+    let server = WireframeServer::new(|| {
+        let handshake = take_current_context()
+            .map(|context| context.into_parts().0)
+            .unwrap_or_default();
+        WireframeApp::<BincodeSerializer, (), Envelope>::default().app_data(handshake)
+    })
+    .with_preamble::<HotlinePreamble>();
+    let server = super::install(server, timeout, admission);
+    let bind_addr = match "127.0.0.1:0".parse() {
+        Ok(addr) => addr,
+        Err(err) => panic!("parse socket addr: {err}"),
+    };
+    let server = match server.bind(bind_addr) {
+        Ok(server) => server,
+        Err(err) => panic!("bind: {err}"),
+    };
+    let Some(addr) = server.local_addr() else {
+        panic!("failed to obtain server.local_addr() when setting up handshake");
+    };
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let _ = server
+            .run_with_shutdown(async {
+                let _ = shutdown_rx.await;
+            })
+            .await;
+    });
+    (addr, shutdown_tx)
+}
+
+#[rstest]
+#[tokio::test]
+async fn replies_success() {
+    let (addr, shutdown) = start_server(HANDSHAKE_TIMEOUT);
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let bytes = preamble_bytes(*PROTOCOL_ID, *b"CHAT", VERSION, 7);
+    stream.write_all(&bytes).await.expect("write handshake");
+
+    let reply = recv_reply(&mut stream).await.expect("handshake reply");
+    assert_eq!(&reply[0..4], PROTOCOL_ID);
+    assert_eq!(
+        u32::from_be_bytes(
+            reply[4..8]
+                .try_into()
+                .expect("convert reply slice to array (ok)")
+        ),
+        HANDSHAKE_OK
+    );
+    let _ = shutdown.send(());
+}
+
+#[rstest]
+#[case(*b"WRNG", HANDSHAKE_ERR_INVALID)]
+#[case(*PROTOCOL_ID, HANDSHAKE_ERR_UNSUPPORTED_VERSION)]
+#[tokio::test]
+async fn replies_handshake_errors(#[case] protocol: [u8; 4], #[case] expected: u32) {
+    let (addr, shutdown) = start_server(HANDSHAKE_TIMEOUT);
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let version = if expected == HANDSHAKE_ERR_UNSUPPORTED_VERSION {
+        VERSION + 1
+    } else {
+        VERSION
+    };
+    let bytes = preamble_bytes(protocol, *b"CHAT", version, 0);
+    stream.write_all(&bytes).await.expect("write handshake");
+
+    let reply = recv_reply(&mut stream).await.expect("handshake reply");
+    assert_eq!(
+        u32::from_be_bytes(
+            reply[4..8]
+                .try_into()
+                .expect("convert reply slice to array (error path)")
+        ),
+        expected
+    );
+    let _ = shutdown.send(());
+}
+
+#[rstest]
+#[tokio::test]
+async fn replies_timeout_for_idle_socket() {
+    let (addr, shutdown) = start_server(Duration::from_millis(100));
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+
+    let reply = timeout(Duration::from_secs(1), recv_reply(&mut stream))
+        .await
+        .expect("reply timed out in test")
+        .expect("handshake reply");
+    assert_eq!(
+        u32::from_be_bytes(
+            reply[4..8]
+                .try_into()
+                .expect("convert reply slice to array (timeout)")
+        ),
+        HANDSHAKE_ERR_TIMEOUT
+    );
+    let _ = shutdown.send(());
+}
+
+#[rstest]
+#[tokio::test]
+async fn relayed_listener_refuses_connections_the_front_end_did_not_open() {
+    let (addr, shutdown) = start_server_with(HANDSHAKE_TIMEOUT, Admission::Relayed);
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let bytes = preamble_bytes(*PROTOCOL_ID, *b"CHAT", VERSION, 0);
+    stream.write_all(&bytes).await.expect("write handshake");
+
+    let mut received = Vec::new();
+    timeout(Duration::from_secs(1), stream.read_to_end(&mut received))
+        .await
+        .expect("close timed out in test")
+        .expect("read to end");
+    assert!(received.is_empty(), "bypassing connection got a reply");
+    let _ = shutdown.send(());
+}
+
+#[rstest]
+#[tokio::test]
+async fn relayed_listener_serves_forwarded_clients() {
+    let (addr, shutdown) = start_server_with(HANDSHAKE_TIMEOUT, Admission::Relayed);
+    let mut stream = TcpStream::connect(addr).await.expect("connect");
+    let address = "203.0.113.7:5500".parse().expect("address");
+    let slot = Arc::new(ConnectionLimiter::default())
+        .admit(std::net::IpAddr::from([203, 0, 113, 7]))
+        .expect("slot");
+    let _forwarded = forward_peer(
+        stream.local_addr().expect("local address"),
+        ForwardedClient { address, slot },
+    );
+    let bytes = preamble_bytes(*PROTOCOL_ID, *b"CHAT", VERSION, 0);
+    stream.write_all(&bytes).await.expect("write handshake");
+
+    let reply = recv_reply(&mut stream).await.expect("handshake reply");
+    assert_eq!(
+        u32::from_be_bytes(
+            reply[4..8]
+                .try_into()
+                .expect("convert reply slice to array (relayed)")
+        ),
+        HANDSHAKE_OK
+    );
+    let _ = shutdown.send(());
+}
@@ -18,12 +18,16 @@ use wireframe::message_assembler::{
     ParsedFrameHeader,
 };
 
-use crate::transaction::{FrameHeader, HEADER_LEN, MAX_PAYLOAD_SIZE};
+use crate::transaction::{FrameHeader, HEADER_LEN, PayloadLimits};
 
-/// Maximum logical Hotline transaction size carried through the Wireframe app.
+/// Maximum logical Hotline transaction size carried through the Wireframe app:
+/// the largest payload limit in `limits` plus its header.
 /// This is a logical request budget, not a physical frame ceiling; the extra
 /// headroom lets Wireframe size assembly against the full transaction envelope.
-pub(crate) const HOTLINE_LOGICAL_MESSAGE_BYTES: usize = HEADER_LEN + MAX_PAYLOAD_SIZE;
+#[must_use]
+pub(crate) fn hotline_logical_message_bytes(limits: &PayloadLimits) -> usize {
+    HEADER_LEN + limits.largest()
+}
 
 const FIRST_FRAME_TAG: u8 = 0;
 const CONTINUATION_FRAME_TAG: u8 = 1;
@@ -7,6 +7,7 @@
 //! # Module Structure
 //!
 //! - `auth_strategy`: Login authentication strategy abstractions
+//! - [`closer`]: Server-initiated connection close
 //! - [`codec`]: Transaction framing codec (`HotlineTransaction`, `HotlineCodec`)
 //! - [`compat_policy`]: Client compatibility policy for login reply gating
 //! - [`connection`]: Handshake metadata storage
@@ -18,8 +19,10 @@
 //! - [`protocol`]: `WireframeProtocol` adapter implementation
 //! - [`routes`]: Transaction route handlers
 //! - [`route_ids`]: Route identifiers for transaction types
+//! - [`transcode`]: MacRoman transcoding for classic clients
 
 pub(crate) mod auth_strategy;
+pub mod closer;
 pub mod codec;
 pub mod compat;
 pub(crate) mod compat_layer;
@@ -35,6 +38,7 @@ pub mod protocol;
 pub mod route_ids;
 pub mod router;
 pub mod routes;
+pub mod transcode;
 
 #[cfg(any(test, feature = "test-support"))]
 pub mod test_helpers;
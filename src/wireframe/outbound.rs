@@ -3,10 +3,17 @@
 //! This module implements the outbound messaging trait for the wireframe
 //! transport, mapping domain transactions to wireframe push queues.
 
-use std::sync::{
-    Arc,
-    OnceLock,
-    atomic::{AtomicU64, Ordering},
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        Mutex,
+        OnceLock,
+        PoisonError,
+        Weak,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
 };
 
 use async_trait::async_trait;
@@ -18,20 +25,29 @@ use wireframe::{
 
 use crate::{
     presence::{PresenceRegistry, build_notify_delete_user},
-    server::outbound::{
-        OutboundConnectionId,
-        OutboundError,
-        OutboundMessaging,
-        OutboundPriority,
-        OutboundTarget,
+    server::{
+        chaos::{ChaosPoint, chaos_point},
+        disconnect::build_disconnect_msg,
+        idle::{ActivityClock, IDLE_DISCONNECT_REASON, wait_until_idle},
+        outbound::{
+            ConnectionController,
+            OutboundConnectionId,
+            OutboundError,
+            OutboundMessaging,
+            OutboundPriority,
+            OutboundTarget,
+        },
     },
-    transaction::Transaction,
+    transaction::{Transaction, WRITE_TIMEOUT},
+    wireframe::closer::close_marker,
 };
 
-/// Shared registry for mapping outbound connection identifiers to push handles.
+/// Shared registry for mapping outbound connection identifiers to push handles
+/// and the connections that own them.
 pub struct WireframeOutboundRegistry {
     next_id: AtomicU64,
     sessions: SessionRegistry<Vec<u8>>,
+    connections: Mutex<HashMap<OutboundConnectionId, Weak<WireframeOutboundConnection>>>,
 }
 
 impl Default for WireframeOutboundRegistry {
@@ -39,6 +55,7 @@ impl Default for WireframeOutboundRegistry {
         Self {
             next_id: AtomicU64::new(1),
             sessions: SessionRegistry::default(),
+            connections: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -51,12 +68,29 @@ impl WireframeOutboundRegistry {
         OutboundConnectionId::new(id)
     }
 
-    fn insert(&self, id: OutboundConnectionId, handle: &PushHandle<Vec<u8>>) {
+    fn insert(&self, connection: &Arc<WireframeOutboundConnection>, handle: &PushHandle<Vec<u8>>) {
+        let id = connection.id();
         self.sessions.insert(ConnectionId::new(id.as_u64()), handle);
+        self.lock_connections()
+            .insert(id, Arc::downgrade(connection));
     }
 
     fn remove(&self, id: OutboundConnectionId) {
         self.sessions.remove(&ConnectionId::new(id.as_u64()));
+        self.lock_connections().remove(&id);
+    }
+
+    fn connection_for(&self, id: OutboundConnectionId) -> Option<Arc<WireframeOutboundConnection>> {
+        self.lock_connections().get(&id).and_then(Weak::upgrade)
+    }
+
+    fn lock_connections(
+        &self,
+    ) -> std::sync::MutexGuard<'_, HashMap<OutboundConnectionId, Weak<WireframeOutboundConnection>>>
+    {
+        self.connections
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
     }
 
     fn handle_for(&self, id: OutboundConnectionId) -> Option<PushHandle<Vec<u8>>> {
@@ -70,6 +104,25 @@ impl WireframeOutboundRegistry {
             .map(|(_, handle)| handle)
             .collect()
     }
+
+    /// Queue a Disconnect Message (111) for every active connection, followed
+    /// by a close marker.
+    ///
+    /// The notice is pushed at low priority so it is delivered after frames
+    /// that are already queued; the marker then half-closes each connection
+    /// once the notice is flushed. Failures are logged per connection.
+    pub async fn notify_disconnect(&self, reason: &str) {
+        let bytes = match build_disconnect_msg(reason) {
+            Ok(notice) => notice.to_bytes(),
+            Err(error) => {
+                warn!(?error, "failed to encode disconnect notice");
+                return;
+            }
+        };
+        for handle in self.active_handles() {
+            push_notice_and_close(&handle, bytes.clone()).await;
+        }
+    }
 }
 
 /// Per-connection outbound state for wireframe messaging.
@@ -118,17 +171,51 @@ impl WireframeOutboundConnection {
     #[must_use]
     pub const fn id(&self) -> OutboundConnectionId { self.id }
 
-    /// Register the push handle for this connection.
-    pub fn register_handle(&self, handle: &PushHandle<Vec<u8>>) {
+    /// Register the push handle for this connection, making it reachable
+    /// for pushes and for [`ConnectionController::disconnect`].
+    pub fn register_handle(self: &Arc<Self>, handle: &PushHandle<Vec<u8>>) {
         if self.handle.set(handle.clone()).is_err() {
             warn!("outbound push handle already registered");
             return;
         }
-        self.registry.insert(self.id, handle);
+        self.registry.insert(self, handle);
     }
 
     fn handle(&self) -> Option<PushHandle<Vec<u8>>> { self.handle.get().cloned() }
 
+    /// Send the client a Disconnect Message with `reason` and close the
+    /// connection behind it, then take the connection offline and tell the
+    /// remaining peers it has left.
+    ///
+    /// Frames already queued are flushed before the notice; the write side is
+    /// then shut down and input drained, as the legacy runtime does. Either
+    /// way the connection stops appearing in user lists and receiving
+    /// broadcasts at once.
+    pub async fn evict(&self, reason: &str) {
+        match (self.handle(), build_disconnect_msg(reason)) {
+            (Some(handle), Ok(notice)) => push_notice_and_close(&handle, notice.to_bytes()).await,
+            (None, _) => warn!("no push handle available for disconnect notice"),
+            (_, Err(error)) => warn!(?error, "failed to encode disconnect notice"),
+        }
+        if let Some((_user_id, peer_ids, bytes)) = self.take_disconnect_notification() {
+            push_disconnect_notifications(Arc::clone(&self.registry), peer_ids, bytes).await;
+        }
+    }
+
+    /// Evict the connection once `activity` shows no transactions for
+    /// `window`.
+    ///
+    /// The reaper holds only a weak reference, so it stops quietly when the
+    /// connection closes first.
+    pub fn spawn_idle_reaper(self: &Arc<Self>, activity: Arc<ActivityClock>, window: Duration) {
+        let Some(runtime_handle) = self.runtime_handle.clone() else {
+            warn!("no runtime handle available for idle reaper");
+            return;
+        };
+        let connection = Arc::downgrade(self);
+        runtime_handle.spawn(reap_when_idle(connection, activity, window));
+    }
+
     fn registry(&self) -> &WireframeOutboundRegistry { &self.registry }
 
     fn take_disconnect_notification(&self) -> Option<(i32, Vec<OutboundConnectionId>, Vec<u8>)> {
@@ -169,6 +256,29 @@ impl Drop for WireframeOutboundConnection {
     }
 }
 
+async fn reap_when_idle(
+    connection: Weak<WireframeOutboundConnection>,
+    activity: Arc<ActivityClock>,
+    window: Duration,
+) {
+    wait_until_idle(window, &activity).await;
+    if let Some(live) = connection.upgrade() {
+        live.evict(IDLE_DISCONNECT_REASON).await;
+    }
+}
+
+/// Queue the encoded Disconnect Message `notice` and a close marker behind
+/// it on `handle`'s low-priority queue.
+async fn push_notice_and_close(handle: &PushHandle<Vec<u8>>, notice: Vec<u8>) {
+    if let Err(error) = handle.push_low_priority(notice).await {
+        warn!(?error, "disconnect notice push failed");
+        return;
+    }
+    if let Err(error) = handle.push_low_priority(close_marker()).await {
+        warn!(?error, "close marker push failed");
+    }
+}
+
 async fn push_disconnect_notifications(
     registry: Arc<WireframeOutboundRegistry>,
     peer_ids: Vec<OutboundConnectionId>,
@@ -200,27 +310,50 @@ async fn push_disconnect_notification(
 #[derive(Clone)]
 pub struct WireframeOutboundMessaging {
     connection: Arc<WireframeOutboundConnection>,
+    write_timeout: Duration,
 }
 
 impl WireframeOutboundMessaging {
     /// Create a new wireframe outbound messaging adapter.
     #[must_use]
-    pub const fn new(connection: Arc<WireframeOutboundConnection>) -> Self { Self { connection } }
+    pub const fn new(connection: Arc<WireframeOutboundConnection>) -> Self {
+        Self {
+            connection,
+            write_timeout: WRITE_TIMEOUT,
+        }
+    }
+
+    /// Give pushes to the current connection `timeout` to find room in its
+    /// queue, instead of [`WRITE_TIMEOUT`].
+    #[must_use]
+    pub const fn with_write_timeout(mut self, timeout: Duration) -> Self {
+        self.write_timeout = timeout;
+        self
+    }
 
     /// Return the outbound identifier for the current connection.
     #[must_use]
     pub fn connection_id(&self) -> OutboundConnectionId { self.connection.id() }
 
+    /// Queue `bytes` on `handle`, giving up once the write timeout passes
+    /// so a client that stops reading cannot stall its sender.
     async fn push_bytes(
+        &self,
         handle: &PushHandle<Vec<u8>>,
         bytes: Vec<u8>,
         priority: OutboundPriority,
     ) -> Result<(), OutboundError> {
-        let result = match priority {
-            OutboundPriority::High => handle.push_high_priority(bytes).await,
-            OutboundPriority::Low => handle.push_low_priority(bytes).await,
+        chaos_point(ChaosPoint::OutboundPush).await;
+        let push = async {
+            match priority {
+                OutboundPriority::High => handle.push_high_priority(bytes).await,
+                OutboundPriority::Low => handle.push_low_priority(bytes).await,
+            }
         };
-        result.map_err(map_push_error)
+        tokio::time::timeout(self.write_timeout, push)
+            .await
+            .map_err(|_| OutboundError::QueueFull)?
+            .map_err(map_push_error)
     }
 }
 
@@ -232,14 +365,17 @@ impl OutboundMessaging for WireframeOutboundMessaging {
         message: Transaction,
         priority: OutboundPriority,
     ) -> Result<(), OutboundError> {
-        let maybe_handle = match target {
-            OutboundTarget::Current => self.connection.handle(),
-            OutboundTarget::Connection(id) => self.connection.registry().handle_for(id),
+        let OutboundTarget::Current = target else {
+            return self
+                .connection
+                .registry()
+                .push(target, message, priority)
+                .await;
         };
-        let Some(handle) = maybe_handle else {
+        let Some(handle) = self.connection.handle() else {
             return Err(OutboundError::TargetUnavailable);
         };
-        Self::push_bytes(&handle, message.to_bytes(), priority).await
+        self.push_bytes(&handle, message.to_bytes(), priority).await
     }
 
     async fn broadcast(
@@ -247,14 +383,28 @@ impl OutboundMessaging for WireframeOutboundMessaging {
         message: Transaction,
         priority: OutboundPriority,
     ) -> Result<(), OutboundError> {
-        let handles = self.connection.registry().active_handles();
-        if handles.is_empty() {
-            return Err(OutboundError::TargetUnavailable);
-        }
-        let bytes = message.to_bytes();
-        for handle in handles {
-            Self::push_bytes(&handle, bytes.clone(), priority).await?;
-        }
+        self.connection
+            .registry()
+            .broadcast(message, priority)
+            .await
+    }
+
+    fn controller(&self) -> Option<&dyn ConnectionController> { Some(self) }
+}
+
+#[async_trait]
+impl ConnectionController for WireframeOutboundMessaging {
+    async fn disconnect(
+        &self,
+        target: OutboundConnectionId,
+        reason: &str,
+    ) -> Result<(), OutboundError> {
+        let connection = self
+            .connection
+            .registry()
+            .connection_for(target)
+            .ok_or(OutboundError::TargetUnavailable)?;
+        connection.evict(reason).await;
         Ok(())
     }
 }
@@ -266,6 +416,12 @@ const fn map_push_error(error: PushError) -> OutboundError {
     }
 }
 
+#[path = "outbound_ping.rs"]
+mod pinger;
+
+#[path = "outbound_registry.rs"]
+mod registry_messaging;
+
 #[cfg(test)]
 #[path = "outbound_tests.rs"]
 mod tests;
@@ -0,0 +1,60 @@
+//! Periodic pings for one Wireframe connection.
+//!
+//! The pinger sends the pings described in [`crate::server::ping`] through the
+//! connection's own push handle and evicts the connection once its
+//! [`PingTracker`] says it has stopped answering.
+
+use std::sync::{Arc, Weak};
+
+use tokio::time::sleep;
+use tracing::warn;
+
+use super::WireframeOutboundConnection;
+use crate::server::ping::{PING_DISCONNECT_REASON, PingPolicy, PingStep, PingTracker, build_ping};
+
+impl WireframeOutboundConnection {
+    /// Ping the client as `policy` directs, keeping score in `pings`.
+    ///
+    /// Like the idle reaper, the pinger holds only a weak reference, so it
+    /// stops quietly when the connection closes first.
+    pub fn spawn_pinger(self: &Arc<Self>, pings: Arc<PingTracker>, policy: PingPolicy) {
+        let Some(runtime_handle) = self.runtime_handle.clone() else {
+            warn!("no runtime handle available for pinger");
+            return;
+        };
+        let connection = Arc::downgrade(self);
+        runtime_handle.spawn(ping_until_closed(connection, pings, policy));
+    }
+}
+
+async fn ping_until_closed(
+    connection: Weak<WireframeOutboundConnection>,
+    pings: Arc<PingTracker>,
+    policy: PingPolicy,
+) {
+    loop {
+        sleep(policy.interval).await;
+        let Some(live) = connection.upgrade() else {
+            return;
+        };
+        // The handle is registered once the connection is running; until
+        // then there is nobody to ping.
+        let Some(handle) = live.handle() else {
+            continue;
+        };
+        match pings.next_step(policy.miss_limit) {
+            PingStep::Send(id) => {
+                // High priority keeps queued notifications out of the
+                // measured round trip.
+                if let Err(error) = handle.push_high_priority(build_ping(id).to_bytes()).await {
+                    warn!(?error, "ping push failed");
+                }
+            }
+            PingStep::Stop => return,
+            PingStep::Evict => {
+                live.evict(PING_DISCONNECT_REASON).await;
+                return;
+            }
+        }
+    }
+}
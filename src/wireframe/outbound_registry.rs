@@ -0,0 +1,46 @@
+//! Server-wide messaging through the outbound registry.
+
+use async_trait::async_trait;
+
+use super::{WireframeOutboundMessaging, WireframeOutboundRegistry};
+use crate::{
+    server::outbound::{OutboundError, OutboundMessaging, OutboundPriority, OutboundTarget},
+    transaction::Transaction,
+};
+
+/// Server-wide messaging for tasks that push without a connection of their
+/// own, such as the outbox dispatcher; [`OutboundTarget::Current`] names no
+/// connection here.
+#[async_trait]
+impl OutboundMessaging for WireframeOutboundRegistry {
+    async fn push(
+        &self,
+        target: OutboundTarget,
+        message: Transaction,
+        priority: OutboundPriority,
+    ) -> Result<(), OutboundError> {
+        let OutboundTarget::Connection(id) = target else {
+            return Err(OutboundError::TargetUnavailable);
+        };
+        let handle = self
+            .handle_for(id)
+            .ok_or(OutboundError::TargetUnavailable)?;
+        WireframeOutboundMessaging::push_bytes(&handle, message.to_bytes(), priority).await
+    }
+
+    async fn broadcast(
+        &self,
+        message: Transaction,
+        priority: OutboundPriority,
+    ) -> Result<(), OutboundError> {
+        let handles = self.active_handles();
+        if handles.is_empty() {
+            return Err(OutboundError::TargetUnavailable);
+        }
+        let bytes = message.to_bytes();
+        for handle in handles {
+            WireframeOutboundMessaging::push_bytes(&handle, bytes.clone(), priority).await?;
+        }
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@ use crate::{
     field_id::FieldId,
     presence::{PresenceRegistry, PresenceSnapshot},
     transaction::{FrameHeader, decode_params},
+    wireframe::closer::is_close_marker,
 };
 
 #[fixture]
@@ -114,18 +115,24 @@ fn dropping_connection_broadcasts_notify_delete_user() {
         .upsert(PresenceSnapshot {
             connection_id: departing_id,
             user_id: 7,
+            account_id: 7,
             display_name: "alice".to_owned(),
             icon_id: 0,
             status_flags: 0,
+            cannot_be_disconnected: false,
+            auto_response: None,
         })
         .expect("insert departing presence");
     presence
         .upsert(PresenceSnapshot {
             connection_id: remaining_id,
             user_id: 8,
+            account_id: 8,
             display_name: "bob".to_owned(),
             icon_id: 0,
             status_flags: 0,
+            cannot_be_disconnected: false,
+            auto_response: None,
         })
         .expect("insert remaining presence");
 
@@ -146,3 +153,233 @@ fn dropping_connection_broadcasts_notify_delete_user() {
 
     drop(remaining);
 }
+
+#[rstest]
+fn notify_disconnect_queues_notice_behind_pending_frames() {
+    let rt = Runtime::new().expect("runtime");
+    let registry = Arc::new(WireframeOutboundRegistry::default());
+    let id = registry.allocate_id();
+    let connection = Arc::new(WireframeOutboundConnection::new_with_runtime_handle(
+        id,
+        Arc::clone(&registry),
+        Arc::new(PresenceRegistry::default()),
+        Some(rt.handle().clone()),
+    ));
+    let (mut queues, handle) = PushQueues::<Vec<u8>>::builder()
+        .high_capacity(1)
+        .low_capacity(3)
+        .build()
+        .expect("push queues");
+    connection.register_handle(&handle);
+
+    rt.block_on(async {
+        handle
+            .push_low_priority(b"pending".to_vec())
+            .await
+            .expect("queue pending frame");
+        registry.notify_disconnect("bye").await;
+
+        let (_, pending) = queues.recv().await.expect("pending frame");
+        assert_eq!(pending, b"pending");
+        let (_, frame) = queues.recv().await.expect("disconnect notice");
+        let parsed = crate::transaction::parse_transaction(&frame).expect("parse notice");
+        assert_eq!(parsed.header.ty, 111);
+        let params = decode_params(&parsed.payload).expect("decode params");
+        assert_eq!(params, vec![(FieldId::Data, b"bye".to_vec())]);
+        let (_, marker) = queues.recv().await.expect("close marker");
+        assert!(is_close_marker(&marker));
+    });
+
+    drop(connection);
+}
+
+/// Register an online connection for `user_id` with its own push queues.
+fn connect_online(
+    rt: &Runtime,
+    registry: &Arc<WireframeOutboundRegistry>,
+    presence: &Arc<PresenceRegistry>,
+    user_id: i32,
+) -> (Arc<WireframeOutboundConnection>, PushQueues<Vec<u8>>) {
+    let id = registry.allocate_id();
+    let connection = Arc::new(WireframeOutboundConnection::new_with_runtime_handle(
+        id,
+        Arc::clone(registry),
+        Arc::clone(presence),
+        Some(rt.handle().clone()),
+    ));
+    let (queues, handle) = PushQueues::<Vec<u8>>::builder()
+        .high_capacity(1)
+        .low_capacity(2)
+        .build()
+        .expect("push queues");
+    connection.register_handle(&handle);
+    presence
+        .upsert(PresenceSnapshot {
+            connection_id: id,
+            user_id,
+            account_id: user_id,
+            display_name: format!("user{user_id}"),
+            icon_id: 0,
+            status_flags: 0,
+            cannot_be_disconnected: false,
+            auto_response: None,
+        })
+        .expect("insert presence");
+    (connection, queues)
+}
+
+#[rstest]
+fn idle_reaper_disconnects_client_and_notifies_peers() {
+    let rt = Runtime::new().expect("runtime");
+    let registry = Arc::new(WireframeOutboundRegistry::default());
+    let presence = Arc::new(PresenceRegistry::default());
+    let (idle, mut idle_queues) = connect_online(&rt, &registry, &presence, 7);
+    let (active, mut active_queues) = connect_online(&rt, &registry, &presence, 8);
+
+    rt.block_on(async {
+        idle.spawn_idle_reaper(Arc::new(ActivityClock::new()), Duration::from_millis(10));
+        let (_, notice) = idle_queues.recv().await.expect("disconnect notice");
+        let parsed = crate::transaction::parse_transaction(&notice).expect("parse notice");
+        assert_eq!(parsed.header.ty, 111);
+        let (_, frame) = active_queues.recv().await.expect("notify delete user");
+        let notify = crate::transaction::parse_transaction(&frame).expect("parse notify");
+        assert_eq!(notify.header.ty, 302);
+    });
+
+    assert_eq!(presence.online_snapshots().len(), 1);
+    drop(idle);
+    drop(active);
+}
+
+#[rstest]
+fn controller_disconnects_target_and_notifies_peers() {
+    let rt = Runtime::new().expect("runtime");
+    let registry = Arc::new(WireframeOutboundRegistry::default());
+    let presence = Arc::new(PresenceRegistry::default());
+    let (admin, mut admin_queues) = connect_online(&rt, &registry, &presence, 7);
+    let (target, mut target_queues) = connect_online(&rt, &registry, &presence, 8);
+    let messaging = WireframeOutboundMessaging::new(Arc::clone(&admin));
+    let controller = messaging.controller().expect("wireframe controller");
+
+    rt.block_on(async {
+        controller
+            .disconnect(target.id(), "kicked")
+            .await
+            .expect("disconnect target");
+        let (_, notice) = target_queues.recv().await.expect("disconnect notice");
+        let parsed = crate::transaction::parse_transaction(&notice).expect("parse notice");
+        assert_eq!(parsed.header.ty, 111);
+        let params = decode_params(&parsed.payload).expect("decode params");
+        assert_eq!(params, vec![(FieldId::Data, b"kicked".to_vec())]);
+        let (_, frame) = admin_queues.recv().await.expect("notify delete user");
+        let notify = crate::transaction::parse_transaction(&frame).expect("parse notify");
+        assert_eq!(notify.header.ty, 302);
+
+        let err = controller
+            .disconnect(target.id(), "again")
+            .await
+            .expect_err("target already gone");
+        assert_eq!(err, OutboundError::TargetUnavailable);
+    });
+
+    assert_eq!(presence.online_snapshots().len(), 1);
+    drop(target);
+    drop(admin);
+}
+
+#[rstest]
+#[case(1)]
+#[case(16)]
+#[case(64)]
+#[serial_test::serial(chaos)]
+fn delayed_pushes_through_the_registry_are_never_lost(reply: Transaction, #[case] pushes: u32) {
+    use crate::server::chaos::{ChaosPoint, ChaosSettings, chaos_delays, enable_chaos};
+
+    let rt = Runtime::new().expect("runtime");
+    let registry = Arc::new(WireframeOutboundRegistry::default());
+    let id = registry.allocate_id();
+    let connection = Arc::new(WireframeOutboundConnection::new(
+        id,
+        Arc::clone(&registry),
+        Arc::new(PresenceRegistry::default()),
+    ));
+    let (mut queues, handle) = PushQueues::<Vec<u8>>::builder()
+        .high_capacity(64)
+        .low_capacity(64)
+        .build()
+        .expect("push queues");
+    connection.register_handle(&handle);
+    let messaging = WireframeOutboundMessaging::new(Arc::clone(&connection));
+    let before = chaos_delays(ChaosPoint::OutboundPush);
+    let chaos = enable_chaos(ChaosSettings {
+        max_jitter: std::time::Duration::from_millis(5),
+    });
+
+    let mut received = rt.block_on(async {
+        let sends = (0..pushes).map(|n| {
+            let mut message = reply.clone();
+            message.header.id = n;
+            let priority = if n % 2 == 0 {
+                OutboundPriority::High
+            } else {
+                OutboundPriority::Low
+            };
+            let messaging = messaging.clone();
+            tokio::spawn(async move {
+                messaging
+                    .push(OutboundTarget::Connection(id), message, priority)
+                    .await
+            })
+        });
+        for send in sends.collect::<Vec<_>>() {
+            send.await.expect("push task").expect("push ok");
+        }
+        let mut ids = Vec::new();
+        for _ in 0..pushes {
+            let (_, frame) = queues.recv().await.expect("frame queued");
+            let parsed = crate::transaction::parse_transaction(&frame).expect("parse reply");
+            ids.push(parsed.header.id);
+        }
+        ids
+    });
+    drop(chaos);
+
+    received.sort_unstable();
+    assert_eq!(received, (0..pushes).collect::<Vec<_>>());
+    assert!(chaos_delays(ChaosPoint::OutboundPush) >= before + u64::from(pushes));
+}
+
+#[rstest]
+fn pushes_to_a_stalled_client_time_out(reply: Transaction) {
+    let registry = Arc::new(WireframeOutboundRegistry::default());
+    let connection = Arc::new(WireframeOutboundConnection::new(
+        registry.allocate_id(),
+        Arc::clone(&registry),
+        Arc::new(PresenceRegistry::default()),
+    ));
+    let messaging = WireframeOutboundMessaging::new(Arc::clone(&connection))
+        .with_write_timeout(Duration::from_millis(50));
+    let rt = Runtime::new().expect("runtime");
+    let (_queues, handle) = PushQueues::<Vec<u8>>::builder()
+        .high_capacity(1)
+        .low_capacity(1)
+        .build()
+        .expect("push queues");
+    connection.register_handle(&handle);
+
+    let result = rt.block_on(async {
+        messaging
+            .push(
+                OutboundTarget::Current,
+                reply.clone(),
+                OutboundPriority::Low,
+            )
+            .await
+            .expect("first push fits");
+        messaging
+            .push(OutboundTarget::Current, reply, OutboundPriority::Low)
+            .await
+    });
+
+    assert_eq!(result, Err(OutboundError::QueueFull));
+}
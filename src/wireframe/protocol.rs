@@ -12,7 +12,10 @@
 //! - **Inbound port**: Transaction routing is handled via `WireframeApp::route()` registrations in
 //!   the server bootstrap, not within this trait.
 //! - **Lifecycle hooks**: This trait provides connection setup, frame mutation, and error handling
-//!   callbacks.
+//!   callbacks. `before_send` rewrites every outbound frame, pushes included, into the client's
+//!   text encoding, XOR-encodes it when the client expects that, and finally appends a payload
+//!   checksum once the client has negotiated checksums. It also watches for the close marker queued
+//!   behind a Disconnect Message and half-closes the socket when it arrives.
 //! - **Domain isolation**: The adapter bridges wireframe types to domain types without leaking
 //!   wireframe dependencies into domain code.
 //!
@@ -56,8 +59,14 @@ use wireframe::{
 
 use crate::{
     db::DbPool,
-    transaction::{HEADER_LEN, parse_transaction},
-    wireframe::{compat::XorCompatibility, outbound::WireframeOutboundConnection},
+    transaction::{HEADER_LEN, Transaction, TransactionError, append_checksum, parse_transaction},
+    wireframe::{
+        closer::{SocketCloser, is_close_marker},
+        compat::XorCompatibility,
+        compat_policy::ClientCompatibility,
+        outbound::WireframeOutboundConnection,
+        transcode::TextEncoding,
+    },
 };
 
 /// `WireframeProtocol` implementation for the Hotline protocol.
@@ -77,6 +86,8 @@ pub struct HotlineProtocol {
     argon2: Arc<Argon2<'static>>,
     outbound: Arc<WireframeOutboundConnection>,
     compat: Arc<XorCompatibility>,
+    client: Option<Arc<ClientCompatibility>>,
+    closer: Option<Arc<SocketCloser>>,
 }
 
 impl HotlineProtocol {
@@ -98,9 +109,29 @@ impl HotlineProtocol {
             argon2,
             outbound,
             compat,
+            client: None,
+            closer: None,
         }
     }
 
+    /// Encode outbound text as `client` expects once its login is seen.
+    ///
+    /// Without this, outbound text is sent as UTF-8.
+    #[must_use]
+    pub fn with_client_compat(mut self, client: Arc<ClientCompatibility>) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Half-close the connection through `closer` when a close marker is
+    /// sent; without one, the client is trusted to hang up after a
+    /// Disconnect Message.
+    #[must_use]
+    pub fn with_closer(mut self, closer: Option<Arc<SocketCloser>>) -> Self {
+        self.closer = closer;
+        self
+    }
+
     /// Return a reference to the database pool.
     #[must_use]
     pub const fn pool(&self) -> &DbPool { &self.pool }
@@ -116,6 +147,38 @@ impl HotlineProtocol {
     /// Return the XOR compatibility state for this connection.
     #[must_use]
     pub const fn compat(&self) -> &Arc<XorCompatibility> { &self.compat }
+
+    fn text_encoding(&self) -> TextEncoding {
+        self.client
+            .as_deref()
+            .map_or(TextEncoding::Utf8, ClientCompatibility::text_encoding)
+    }
+
+    fn checksums_enabled(&self) -> bool {
+        self.client
+            .as_deref()
+            .is_some_and(ClientCompatibility::checksums_enabled)
+    }
+
+    /// Apply the client's text encoding, then XOR, to an outbound payload,
+    /// and checksum the result when the client negotiated checksums.
+    fn encode_outbound(
+        &self,
+        encoding: TextEncoding,
+        payload: &[u8],
+    ) -> Result<Vec<u8>, TransactionError> {
+        let transcoded = encoding.encode_payload(payload)?;
+        let encoded = if self.compat.is_enabled() {
+            self.compat.encode_payload(&transcoded)?
+        } else {
+            transcoded
+        };
+        if self.checksums_enabled() {
+            append_checksum(&encoded)
+        } else {
+            Ok(encoded)
+        }
+    }
 }
 
 impl WireframeProtocol for HotlineProtocol {
@@ -132,23 +195,27 @@ impl WireframeProtocol for HotlineProtocol {
     }
 
     fn before_send(&self, frame: &mut Self::Frame, _ctx: &mut ConnectionContext) {
-        if !self.compat.is_enabled() {
+        if is_close_marker(frame) {
+            // Every frame queued ahead of the marker has been flushed.
+            if let Some(closer) = &self.closer {
+                closer.close_write();
+            }
+            return;
+        }
+        let encoding = self.text_encoding();
+        let unchanged = encoding == TextEncoding::Utf8 && !self.compat.is_enabled();
+        if unchanged && !self.checksums_enabled() {
             return;
         }
         let Ok(tx) = parse_transaction(frame) else {
             return;
         };
-        let Ok(encoded) = self.compat.encode_payload(&tx.payload) else {
+        let Ok(encoded) = self.encode_outbound(encoding, &tx.payload) else {
             return;
         };
-        if encoded == tx.payload {
-            return;
+        if encoded != tx.payload {
+            rewrite_frame(frame, tx, encoded);
         }
-        let mut header_buf = [0u8; HEADER_LEN];
-        tx.header.write_bytes(&mut header_buf);
-        frame.clear();
-        frame.extend_from_slice(&header_buf);
-        frame.extend_from_slice(&encoded);
     }
 
     fn on_command_end(&self, _ctx: &mut ConnectionContext) {
@@ -168,140 +235,21 @@ impl WireframeProtocol for HotlineProtocol {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    //! Tests for this module.
-    use rstest::{fixture, rstest};
-    use wireframe::hooks::ConnectionContext;
-
-    use super::*;
-    use crate::{
-        presence::PresenceRegistry,
-        wireframe::{
-            compat::XorCompatibility,
-            outbound::{WireframeOutboundConnection, WireframeOutboundRegistry},
-            test_helpers::{dummy_pool, xor_bytes},
-        },
+/// Replace `frame` with `tx` carrying `payload`, whose length may differ.
+fn rewrite_frame(frame: &mut Vec<u8>, tx: Transaction, payload: Vec<u8>) {
+    let Ok(len) = u32::try_from(payload.len()) else {
+        return;
     };
-
-    #[fixture]
-    fn outbound_connection() -> Arc<WireframeOutboundConnection> {
-        let registry = Arc::new(WireframeOutboundRegistry::default());
-        let id = registry.allocate_id();
-        Arc::new(WireframeOutboundConnection::new(
-            id,
-            registry,
-            Arc::new(PresenceRegistry::default()),
-        ))
-    }
-
-    #[fixture]
-    fn compat() -> Arc<XorCompatibility> {
-        let compat = Arc::new(XorCompatibility::disabled());
-        let _ = compat.is_enabled();
-        compat
-    }
-
-    #[rstest]
-    fn protocol_can_be_created(
-        outbound_connection: Arc<WireframeOutboundConnection>,
-        compat: Arc<XorCompatibility>,
-    ) {
-        let pool = dummy_pool();
-        let argon2 = Arc::new(Argon2::default());
-
-        let protocol = HotlineProtocol::new(pool, argon2, outbound_connection, compat);
-
-        assert!(Arc::strong_count(protocol.argon2()) >= 1);
-    }
-
-    #[rstest]
-    fn protocol_shares_argon2_instance(
-        outbound_connection: Arc<WireframeOutboundConnection>,
-        compat: Arc<XorCompatibility>,
-    ) {
-        let pool = dummy_pool();
-        let argon2 = Arc::new(Argon2::default());
-        let argon2_clone = Arc::clone(&argon2);
-
-        let protocol = HotlineProtocol::new(pool, argon2, outbound_connection, compat);
-
-        assert!(Arc::ptr_eq(protocol.argon2(), &argon2_clone));
-    }
-
-    /// Lifecycle hook identifiers for parameterized testing.
-    #[derive(Debug, Clone, Copy)]
-    enum LifecycleHook {
-        HandleError,
-        StreamEndFrame,
-        OnCommandEnd,
-        BeforeSend,
-    }
-
-    #[rstest]
-    #[case::handle_error(LifecycleHook::HandleError)]
-    #[case::stream_end_frame(LifecycleHook::StreamEndFrame)]
-    #[case::on_command_end(LifecycleHook::OnCommandEnd)]
-    #[case::before_send(LifecycleHook::BeforeSend)]
-    fn lifecycle_hooks_do_not_panic(
-        #[case] hook: LifecycleHook,
-        outbound_connection: Arc<WireframeOutboundConnection>,
-        compat: Arc<XorCompatibility>,
-    ) {
-        let pool = dummy_pool();
-        let argon2 = Arc::new(Argon2::default());
-        let protocol = HotlineProtocol::new(pool, argon2, outbound_connection, compat);
-        let mut ctx = ConnectionContext;
-
-        match hook {
-            LifecycleHook::HandleError => protocol.handle_error((), &mut ctx),
-            LifecycleHook::StreamEndFrame => {
-                let frame = protocol.stream_end_frame(&mut ctx);
-                assert!(frame.is_none());
-            }
-            LifecycleHook::OnCommandEnd => protocol.on_command_end(&mut ctx),
-            LifecycleHook::BeforeSend => {
-                let mut frame = vec![0u8; 20];
-                protocol.before_send(&mut frame, &mut ctx);
-            }
-        }
-    }
-
-    #[rstest]
-    fn before_send_encodes_text_fields_when_enabled(
-        outbound_connection: Arc<WireframeOutboundConnection>,
-    ) {
-        let pool = dummy_pool();
-        let argon2 = Arc::new(Argon2::default());
-        let compat = Arc::new(XorCompatibility::enabled());
-        let protocol = HotlineProtocol::new(pool, argon2, outbound_connection, compat);
-        let mut ctx = ConnectionContext;
-
-        let payload = crate::transaction::encode_params(&[(
-            crate::field_id::FieldId::Data,
-            b"message".as_ref(),
-        )])
-        .expect("payload encodes");
-        let payload_len = u32::try_from(payload.len()).expect("payload length fits u32");
-        let header = crate::transaction::FrameHeader {
-            flags: 0,
-            is_reply: 1,
-            ty: crate::transaction_type::TransactionType::Error.into(),
-            id: 9,
-            error: 0,
-            total_size: payload_len,
-            data_size: payload_len,
-        };
-        let tx = crate::transaction::Transaction { header, payload };
-        let mut frame = tx.to_bytes();
-
-        protocol.before_send(&mut frame, &mut ctx);
-
-        let reply = crate::transaction::parse_transaction(&frame).expect("parse reply");
-        let params = crate::transaction::decode_params(&reply.payload).expect("decode params");
-        assert_eq!(params.len(), 1);
-        assert_eq!(params[0].0, crate::field_id::FieldId::Data);
-        let decoded = xor_bytes(&params[0].1);
-        assert_eq!(decoded, b"message");
-    }
+    let mut header = tx.header;
+    header.total_size = len;
+    header.data_size = len;
+    let mut header_buf = [0u8; HEADER_LEN];
+    header.write_bytes(&mut header_buf);
+    frame.clear();
+    frame.extend_from_slice(&header_buf);
+    frame.extend_from_slice(&payload);
 }
+
+#[cfg(test)]
+#[path = "protocol_tests.rs"]
+mod tests;
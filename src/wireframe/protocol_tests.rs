@@ -0,0 +1,220 @@
+//! Tests for the Hotline protocol adapter and its frame hooks.
+use rstest::{fixture, rstest};
+use wireframe::hooks::ConnectionContext;
+
+use super::*;
+use crate::{
+    presence::PresenceRegistry,
+    wireframe::{
+        compat::XorCompatibility,
+        outbound::{WireframeOutboundConnection, WireframeOutboundRegistry},
+        test_helpers::{dummy_pool, xor_bytes},
+    },
+};
+
+#[fixture]
+fn outbound_connection() -> Arc<WireframeOutboundConnection> {
+    let registry = Arc::new(WireframeOutboundRegistry::default());
+    let id = registry.allocate_id();
+    Arc::new(WireframeOutboundConnection::new(
+        id,
+        registry,
+        Arc::new(PresenceRegistry::default()),
+    ))
+}
+
+#[fixture]
+fn compat() -> Arc<XorCompatibility> {
+    let compat = Arc::new(XorCompatibility::disabled());
+    let _ = compat.is_enabled();
+    compat
+}
+
+#[rstest]
+fn protocol_can_be_created(
+    outbound_connection: Arc<WireframeOutboundConnection>,
+    compat: Arc<XorCompatibility>,
+) {
+    let pool = dummy_pool();
+    let argon2 = Arc::new(Argon2::default());
+
+    let protocol = HotlineProtocol::new(pool, argon2, outbound_connection, compat);
+
+    assert!(Arc::strong_count(protocol.argon2()) >= 1);
+}
+
+#[rstest]
+fn protocol_shares_argon2_instance(
+    outbound_connection: Arc<WireframeOutboundConnection>,
+    compat: Arc<XorCompatibility>,
+) {
+    let pool = dummy_pool();
+    let argon2 = Arc::new(Argon2::default());
+    let argon2_clone = Arc::clone(&argon2);
+
+    let protocol = HotlineProtocol::new(pool, argon2, outbound_connection, compat);
+
+    assert!(Arc::ptr_eq(protocol.argon2(), &argon2_clone));
+}
+
+/// Lifecycle hook identifiers for parameterized testing.
+#[derive(Debug, Clone, Copy)]
+enum LifecycleHook {
+    HandleError,
+    StreamEndFrame,
+    OnCommandEnd,
+    BeforeSend,
+}
+
+#[rstest]
+#[case::handle_error(LifecycleHook::HandleError)]
+#[case::stream_end_frame(LifecycleHook::StreamEndFrame)]
+#[case::on_command_end(LifecycleHook::OnCommandEnd)]
+#[case::before_send(LifecycleHook::BeforeSend)]
+fn lifecycle_hooks_do_not_panic(
+    #[case] hook: LifecycleHook,
+    outbound_connection: Arc<WireframeOutboundConnection>,
+    compat: Arc<XorCompatibility>,
+) {
+    let pool = dummy_pool();
+    let argon2 = Arc::new(Argon2::default());
+    let protocol = HotlineProtocol::new(pool, argon2, outbound_connection, compat);
+    let mut ctx = ConnectionContext;
+
+    match hook {
+        LifecycleHook::HandleError => protocol.handle_error((), &mut ctx),
+        LifecycleHook::StreamEndFrame => {
+            let frame = protocol.stream_end_frame(&mut ctx);
+            assert!(frame.is_none());
+        }
+        LifecycleHook::OnCommandEnd => protocol.on_command_end(&mut ctx),
+        LifecycleHook::BeforeSend => {
+            let mut frame = vec![0u8; 20];
+            protocol.before_send(&mut frame, &mut ctx);
+        }
+    }
+}
+
+#[rstest]
+fn before_send_encodes_text_fields_when_enabled(
+    outbound_connection: Arc<WireframeOutboundConnection>,
+) {
+    let pool = dummy_pool();
+    let argon2 = Arc::new(Argon2::default());
+    let compat = Arc::new(XorCompatibility::enabled());
+    let protocol = HotlineProtocol::new(pool, argon2, outbound_connection, compat);
+    let mut ctx = ConnectionContext;
+
+    let payload =
+        crate::transaction::encode_params(&[(crate::field_id::FieldId::Data, b"message".as_ref())])
+            .expect("payload encodes");
+    let payload_len = u32::try_from(payload.len()).expect("payload length fits u32");
+    let header = crate::transaction::FrameHeader {
+        flags: 0,
+        is_reply: 1,
+        ty: crate::transaction_type::TransactionType::Error.into(),
+        id: 9,
+        error: 0,
+        total_size: payload_len,
+        data_size: payload_len,
+    };
+    let tx = crate::transaction::Transaction { header, payload };
+    let mut frame = tx.to_bytes();
+
+    protocol.before_send(&mut frame, &mut ctx);
+
+    let reply = crate::transaction::parse_transaction(&frame).expect("parse reply");
+    let params = crate::transaction::decode_params(&reply.payload).expect("decode params");
+    assert_eq!(params.len(), 1);
+    assert_eq!(params[0].0, crate::field_id::FieldId::Data);
+    let decoded = xor_bytes(&params[0].1);
+    assert_eq!(decoded, b"message");
+}
+
+#[rstest]
+fn before_send_transcodes_text_for_mac_roman_clients(
+    outbound_connection: Arc<WireframeOutboundConnection>,
+    compat: Arc<XorCompatibility>,
+) {
+    let client = Arc::new(ClientCompatibility::from_handshake(
+        &crate::wireframe::connection::HandshakeMetadata::default(),
+    ));
+    client.record_login_version(151);
+    let protocol = HotlineProtocol::new(
+        dummy_pool(),
+        Arc::new(Argon2::default()),
+        outbound_connection,
+        compat,
+    )
+    .with_client_compat(client);
+    let mut ctx = ConnectionContext;
+    let payload =
+        crate::transaction::encode_params(&[(crate::field_id::FieldId::Data, "Café".as_bytes())])
+            .expect("payload encodes");
+    let mut frame = crate::presence::server_notification(
+        crate::transaction_type::TransactionType::ServerMsg,
+        payload,
+    )
+    .to_bytes();
+
+    protocol.before_send(&mut frame, &mut ctx);
+
+    let sent = crate::transaction::parse_transaction(&frame).expect("parse frame");
+    let params = crate::transaction::decode_params(&sent.payload).expect("decode params");
+    assert_eq!(params[0].1, b"Caf\x8e");
+    assert_eq!(
+        usize::try_from(sent.header.data_size).expect("size fits usize"),
+        sent.payload.len()
+    );
+}
+
+#[rstest]
+fn before_send_checksums_payloads_once_negotiated(
+    outbound_connection: Arc<WireframeOutboundConnection>,
+    compat: Arc<XorCompatibility>,
+) {
+    use crate::{
+        field_id::FieldId,
+        transaction::{FrameHeader, append_checksum, encode_params, verify_checksum},
+        transaction_type::TransactionType,
+    };
+
+    let client = Arc::new(ClientCompatibility::from_handshake(
+        &crate::wireframe::connection::HandshakeMetadata::default(),
+    ));
+    let login = append_checksum(&encode_params(&[(FieldId::Login, b"alice")]).expect("encodes"))
+        .expect("append checksum");
+    client
+        .check_request_checksum(TransactionType::Login, &login)
+        .expect("checksummed login accepted");
+    let mut login_reply = Transaction {
+        header: FrameHeader {
+            flags: 0,
+            is_reply: 1,
+            ty: TransactionType::Login.into(),
+            id: 1,
+            error: 0,
+            total_size: 0,
+            data_size: 0,
+        },
+        payload: Vec::new(),
+    };
+    client
+        .augment_login_reply(&mut login_reply)
+        .expect("augment reply");
+    let protocol = HotlineProtocol::new(
+        dummy_pool(),
+        Arc::new(Argon2::default()),
+        outbound_connection,
+        compat,
+    )
+    .with_client_compat(client);
+    let body = encode_params(&[(FieldId::Data, b"hello")]).expect("payload encodes");
+    let mut frame =
+        crate::presence::server_notification(TransactionType::ServerMsg, body.clone()).to_bytes();
+
+    protocol.before_send(&mut frame, &mut ConnectionContext);
+
+    let sent = parse_transaction(&frame).expect("parse frame");
+    assert_eq!(verify_checksum(&sent.payload).expect("verify"), Some(body));
+}
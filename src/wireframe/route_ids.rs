@@ -7,7 +7,10 @@
 pub const FALLBACK_ROUTE_ID: u32 = 0;
 
 /// Transaction route IDs supported by the wireframe routing layer.
-pub const ROUTE_IDS: [u32; 10] = [107, 121, 200, 300, 303, 304, 370, 371, 400, 410];
+pub const ROUTE_IDS: [u32; 26] = [
+    105, 107, 108, 110, 121, 200, 202, 203, 204, 206, 207, 208, 212, 300, 303, 304, 355, 370, 371,
+    380, 381, 382, 400, 410, 411, 500,
+];
 
 /// Resolve the route ID for a transaction type.
 #[must_use]
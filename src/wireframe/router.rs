@@ -12,8 +12,13 @@ use crate::{
     db::DbPool,
     handler::Session,
     presence::PresenceRegistry,
-    server::outbound::{OutboundConnectionId, OutboundMessaging, ReplyBuffer},
-    transaction::parse_transaction,
+    server::{
+        NetworkRuntime,
+        outbound::{OutboundConnectionId, OutboundMessaging, ReplyBuffer},
+        settings::ServerSettings,
+        transaction_span::{request_span, timed},
+    },
+    transaction::{PayloadLimits, parse_transaction_limited},
     transaction_type::TransactionType,
     wireframe::{
         auth_strategy::{auth_strategy_for_client, auth_strategy_label},
@@ -53,9 +58,18 @@ impl WireframeRouter {
     ///
     /// 1. Parse the frame.
     /// 2. `CompatibilityLayer::on_request` (XOR decode + login version recording).
-    /// 3. `Command::from_transaction` + auth strategy dispatch.
+    /// 3. `Command::from_transaction` + auth strategy dispatch, with the session's news listing
+    ///    encoding refreshed from client metadata.
     /// 4. `CompatibilityLayer::on_reply` via `LoginReplyAugmenter`.
+    ///
+    /// The whole pipeline runs inside a `request` span; see
+    /// [`crate::server::transaction_span`].
     pub async fn route(&self, frame: &[u8], context: RouteContext<'_>) -> Vec<u8> {
+        let span = request_span(NetworkRuntime::Wireframe, context.peer, frame);
+        timed(span, self.route_frame(frame, context)).await
+    }
+
+    async fn route_frame(&self, frame: &[u8], context: RouteContext<'_>) -> Vec<u8> {
         let RouteContext {
             peer,
             pool,
@@ -63,16 +77,20 @@ impl WireframeRouter {
             messaging,
             presence,
             presence_connection_id,
+            settings,
         } = context;
         let request_compat = compat_layer::RequestCompatibility::new(&self.xor, &self.client);
 
-        let (header, tx_type, cmd) = match Self::prepare_command(frame, peer, &request_compat) {
-            Ok(parsed) => parsed,
-            Err(reply) => return reply,
-        };
+        let limits = &settings.transport.payload_limits;
+        let (header, tx_type, cmd) =
+            match Self::prepare_command(frame, peer, &request_compat, limits) {
+                Ok(parsed) => parsed,
+                Err(reply) => return reply,
+            };
         // Select strategy after request hooks so login version metadata affects
         // the first login dispatch.
         let client_kind = self.client.kind();
+        session.news_listing = self.client.news_listing_encoding();
         let auth_strategy = auth_strategy_for_client(client_kind);
         let login_reply_augmenter = ClientCompatibilityLoginReplyAugmenter::new(&self.client);
         let compat_layer = CompatibilityLayer::new(auth_strategy, &login_reply_augmenter);
@@ -95,6 +113,7 @@ impl WireframeRouter {
             messaging,
             presence,
             presence_connection_id: Some(presence_connection_id),
+            settings,
         };
         tracing::trace!(
             auth_strategy = auth_strategy_label(client_kind),
@@ -104,7 +123,7 @@ impl WireframeRouter {
             .process_command(tx_type, cmd, command_context)
             .await
             .map_or_else(
-                |e| handle_process_error(peer, &header, e),
+                |e| handle_process_error(peer, &header, &e),
                 |()| compat_layer::finalize_reply(peer, &header, transport, &compat_layer),
             )
     }
@@ -113,9 +132,10 @@ impl WireframeRouter {
         frame: &[u8],
         peer: SocketAddr,
         request_compat: &compat_layer::RequestCompatibility<'_>,
+        limits: &PayloadLimits,
     ) -> Result<(crate::transaction::FrameHeader, TransactionType, Command), Vec<u8>> {
-        let transaction =
-            parse_transaction(frame).map_err(|e| handle_parse_error(peer, frame, e))?;
+        let transaction = parse_transaction_limited(frame, limits)
+            .map_err(|e| handle_parse_error(peer, frame, e))?;
         let header = transaction.header.clone();
         let tx_type = TransactionType::from(header.ty);
 
@@ -123,7 +143,7 @@ impl WireframeRouter {
         compat_spy::record(compat_spy::HookEvent::OnRequest { tx_type: header.ty });
 
         let request_transaction = request_compat.on_request(peer, tx_type, transaction)?;
-        let command = Command::from_transaction(request_transaction)
+        let command = Command::from_transaction(request_transaction, limits)
             .map_err(|e| handle_command_parse_error(peer, &header, e))?;
         Ok((header, tx_type, command))
     }
@@ -151,6 +171,8 @@ pub struct RouteContext<'a> {
     pub presence: &'a PresenceRegistry,
     /// Adapter-owned connection identifier for presence snapshots.
     pub presence_connection_id: OutboundConnectionId,
+    /// Settings of the server that accepted the connection.
+    pub settings: &'a ServerSettings,
 }
 
 #[cfg(test)]
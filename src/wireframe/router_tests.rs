@@ -14,7 +14,10 @@ use crate::{
     field_id::FieldId,
     handler::Session,
     presence::PresenceRegistry,
-    server::outbound::{NoopOutboundMessaging, OutboundConnectionId},
+    server::{
+        outbound::{NoopOutboundMessaging, OutboundConnectionId},
+        settings::ServerSettings,
+    },
     transaction::parse_transaction,
     transaction_type::TransactionType,
     wireframe::{
@@ -143,6 +146,7 @@ fn execute_login(
             messaging: &setup.messaging,
             presence: &setup.presence,
             presence_connection_id: OutboundConnectionId::new(1),
+            settings: &ServerSettings::default(),
         },
     ));
     let tx = parse_transaction(&reply)?;
@@ -243,6 +247,7 @@ fn non_login_hooks_fire_in_order(
             messaging: &setup.messaging,
             presence: &setup.presence,
             presence_connection_id: OutboundConnectionId::new(1),
+            settings: &ServerSettings::default(),
         },
     ));
     let login_tx = parse_transaction(&login_reply)?;
@@ -266,6 +271,7 @@ fn non_login_hooks_fire_in_order(
             messaging: &setup.messaging,
             presence: &setup.presence,
             presence_connection_id: OutboundConnectionId::new(1),
+            settings: &ServerSettings::default(),
         },
     ));
     let tx = parse_transaction(&reply)?;
@@ -318,6 +324,7 @@ fn parse_failure_does_not_trigger_hooks(
             messaging: &setup.messaging,
             presence: &setup.presence,
             presence_connection_id: OutboundConnectionId::new(1),
+            settings: &ServerSettings::default(),
         },
     ));
 
@@ -21,9 +21,10 @@
 //! the `WireframeApp`. The middleware intercepts all frames, processes them
 //! through the domain command dispatcher, and writes the reply bytes.
 
-use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+use std::{convert::Infallible, net::SocketAddr, sync::Arc, time::Instant};
 
 use async_trait::async_trait;
+use tracing::{debug, warn};
 use wireframe::{
     app::Envelope,
     middleware::{HandlerService, Service, ServiceRequest, ServiceResponse, Transform},
@@ -32,24 +33,35 @@ use wireframe::{
 #[cfg(test)]
 use crate::header_util::reply_header;
 #[cfg(test)]
-use crate::transaction::Transaction;
-#[cfg(test)]
 use crate::wireframe::codec::HotlineTransaction;
 use crate::{
+    commands::CommandError,
     db::DbPool,
     presence::PresenceRegistry,
-    server::outbound::{OutboundConnectionId, OutboundMessaging},
-    transaction::FrameHeader,
-    wireframe::router::{RouteContext as RouterRouteContext, WireframeRouter},
+    server::{
+        NetworkRuntime,
+        agreement::take_agreement_push,
+        connection_limit::ConnectionSlot,
+        disconnect::build_disconnect_msg,
+        idle::ActivityClock,
+        metrics::runtime_metrics,
+        outbound::{OutboundConnectionId, OutboundMessaging, OutboundPriority, OutboundTarget},
+        ping::PingTracker,
+        rate_limit::{ConnectionRateLimiter, throttled_reply},
+        settings::ServerSettings,
+    },
+    transaction::{FrameHeader, HEADER_LEN, Transaction, TransactionError},
+    transaction_type::TransactionType,
+    wireframe::{
+        compat::XOR_REJECTED_REASON,
+        router::{RouteContext as RouterRouteContext, WireframeRouter},
+    },
 };
 
 pub(crate) mod reply_builder;
 
 use reply_builder::ReplyBuilder;
 
-/// Error code for internal server failures.
-const ERR_INTERNAL: u32 = 3;
-
 #[cfg(test)]
 pub(crate) mod dispatch_spy {
     //! Captures dispatch details for transaction routing tests.
@@ -100,30 +112,26 @@ fn error_transaction(header: &FrameHeader, error_code: u32) -> Transaction {
 }
 
 /// Handle transaction parse errors by returning an error reply.
-pub(crate) fn handle_parse_error(
-    peer: SocketAddr,
-    frame: &[u8],
-    e: impl std::fmt::Display,
-) -> Vec<u8> {
-    ReplyBuilder::from_frame(peer, frame).parse_error(e, ERR_INTERNAL)
+pub(crate) fn handle_parse_error(peer: SocketAddr, frame: &[u8], e: TransactionError) -> Vec<u8> {
+    ReplyBuilder::from_frame(peer, frame).parse_error(e)
 }
 
 /// Handle command parsing errors by returning an error reply.
 pub(crate) fn handle_command_parse_error(
     peer: SocketAddr,
     header: &FrameHeader,
-    e: impl std::fmt::Display,
+    e: TransactionError,
 ) -> Vec<u8> {
-    ReplyBuilder::from_header(peer, header).command_parse_error(e, ERR_INTERNAL)
+    ReplyBuilder::from_header(peer, header).command_parse_error(e)
 }
 
 /// Handle command processing errors by returning an error reply.
 pub(crate) fn handle_process_error(
     peer: SocketAddr,
     header: &FrameHeader,
-    e: impl std::fmt::Display,
+    e: &CommandError,
 ) -> Vec<u8> {
-    ReplyBuilder::from_header(peer, header).process_error(e, ERR_INTERNAL)
+    ReplyBuilder::from_header(peer, header).process_error(e)
 }
 
 /// Build an error reply as a `HotlineTransaction`.
@@ -167,6 +175,11 @@ pub(crate) struct TransactionMiddleware {
     messaging: Arc<dyn OutboundMessaging>,
     presence: Arc<PresenceRegistry>,
     presence_connection_id: OutboundConnectionId,
+    activity: Arc<ActivityClock>,
+    pings: Arc<PingTracker>,
+    limiter: Arc<ConnectionRateLimiter>,
+    settings: Arc<ServerSettings>,
+    _slot: Option<ConnectionSlot>,
 }
 
 /// Construction parameters for [`TransactionMiddleware`].
@@ -185,6 +198,15 @@ pub(crate) struct TransactionMiddlewareConfig {
     pub(crate) presence: Arc<PresenceRegistry>,
     /// Adapter-owned connection identifier for presence snapshots.
     pub(crate) presence_connection_id: OutboundConnectionId,
+    /// Time of the connection's latest transaction, read by the idle reaper.
+    pub(crate) activity: Arc<ActivityClock>,
+    /// Ping state, updated when the client answers a server ping.
+    pub(crate) pings: Arc<PingTracker>,
+    /// Settings of the server that accepted the connection.
+    pub(crate) settings: Arc<ServerSettings>,
+    /// Place within the connection limits, held until the connection's app
+    /// is dropped.
+    pub(crate) slot: Option<ConnectionSlot>,
 }
 
 impl TransactionMiddleware {
@@ -199,6 +221,14 @@ impl TransactionMiddleware {
             messaging: config.messaging,
             presence: config.presence,
             presence_connection_id: config.presence_connection_id,
+            activity: config.activity,
+            pings: config.pings,
+            limiter: Arc::new(ConnectionRateLimiter::new(
+                Arc::clone(&config.settings.rate_limits),
+                config.peer.ip(),
+            )),
+            settings: config.settings,
+            _slot: config.slot,
         }
     }
 }
@@ -213,18 +243,62 @@ struct TransactionHandler {
     messaging: Arc<dyn OutboundMessaging>,
     presence: Arc<PresenceRegistry>,
     presence_connection_id: OutboundConnectionId,
+    activity: Arc<ActivityClock>,
+    pings: Arc<PingTracker>,
+    limiter: Arc<ConnectionRateLimiter>,
+    settings: Arc<ServerSettings>,
 }
 
-#[async_trait]
-impl Service for TransactionHandler {
-    type Error = Infallible;
+impl TransactionHandler {
+    /// Queue a Disconnect Message (111) for this connection.
+    ///
+    /// Wireframe gives middleware no way to drop its own connection, so the
+    /// notice asks the client to hang up instead. It is queued at low
+    /// priority, behind any notifications already waiting for the client.
+    async fn request_disconnect(&self, reason: &str) {
+        let notice = match build_disconnect_msg(reason) {
+            Ok(notice) => notice,
+            Err(error) => {
+                warn!(%error, "failed to encode disconnect notice");
+                return;
+            }
+        };
+        self.push_to_self(notice, OutboundPriority::Low).await;
+    }
 
-    async fn call(&self, req: ServiceRequest) -> Result<ServiceResponse, Self::Error> {
-        let reply_bytes = {
+    /// Record `header` as the answer to a server ping if it is one.
+    ///
+    /// The answer still goes through the router like any other frame; the
+    /// keep-alive reply it provokes carries the ping's id, which clients
+    /// already ignore for ids they did not issue.
+    fn record_ping_answer(&self, header: &FrameHeader) {
+        if header.is_reply == 0 || header.ty != u16::from(TransactionType::KeepAlive) {
+            return;
+        }
+        if let Some(round_trip) = self.pings.answer(header.id) {
+            runtime_metrics(NetworkRuntime::Wireframe).record_round_trip(round_trip);
+        }
+    }
+
+    /// Return whether `request` must be refused because the connection or
+    /// its address is over its rate limit.
+    fn over_rate(&self, request: &FrameHeader) -> bool {
+        if self.limiter.admit(request, Instant::now()) {
+            return false;
+        }
+        debug!(peer = %self.peer, ty = request.ty, id = request.id, "request rate limited");
+        true
+    }
+
+    /// Route `frame` and act on what it left in the session, returning the
+    /// reply bytes.
+    async fn process(&self, frame: &[u8]) -> Vec<u8> {
+        let (reply_bytes, disconnect_reason, agreement) = {
             let mut session_guard = self.session.lock().await;
-            self.router
+            let reply_bytes = self
+                .router
                 .route(
-                    req.frame(),
+                    frame,
                     RouterRouteContext {
                         peer: self.peer,
                         pool: self.pool.clone(),
@@ -232,10 +306,63 @@ impl Service for TransactionHandler {
                         messaging: self.messaging.as_ref(),
                         presence: self.presence.as_ref(),
                         presence_connection_id: self.presence_connection_id,
+                        settings: &self.settings,
                     },
                 )
-                .await
+                .await;
+            let agreement = take_agreement_push(&mut session_guard, &self.settings.agreement);
+            // A client refused by the `strict` XOR policy is asked to leave
+            // like one whose session ended.
+            let disconnect_reason = session_guard.take_disconnect_reason().or_else(|| {
+                self.router
+                    .xor()
+                    .take_rejection()
+                    .then_some(XOR_REJECTED_REASON)
+            });
+            (reply_bytes, disconnect_reason, agreement)
         };
+        match agreement {
+            Ok(Some(push)) => self.push_to_self(push, OutboundPriority::High).await,
+            Ok(None) => {}
+            Err(error) => warn!(%error, peer = %self.peer, "failed to encode agreement"),
+        }
+        if let Some(reason) = disconnect_reason {
+            self.request_disconnect(reason).await;
+        }
+        reply_bytes
+    }
+
+    /// Queue a server-initiated transaction for this connection.
+    async fn push_to_self(&self, message: Transaction, priority: OutboundPriority) {
+        let target = OutboundTarget::Connection(self.presence_connection_id);
+        let ty = message.header.ty;
+        if let Err(error) = self.messaging.push(target, message, priority).await {
+            warn!(%error, peer = %self.peer, ty, "push to own connection failed");
+        }
+    }
+}
+
+#[async_trait]
+impl Service for TransactionHandler {
+    type Error = Infallible;
+
+    async fn call(&self, req: ServiceRequest) -> Result<ServiceResponse, Self::Error> {
+        self.activity.touch();
+        let request_header = req
+            .frame()
+            .first_chunk::<HEADER_LEN>()
+            .map(FrameHeader::from_bytes);
+        if let Some(request) = &request_header {
+            self.record_ping_answer(request);
+        }
+        let reply_bytes = match request_header.filter(|request| self.over_rate(request)) {
+            Some(request) => throttled_reply(&request).to_bytes(),
+            None => self.process(req.frame()).await,
+        };
+        if let Some(reply_header) = reply_bytes.first_chunk::<HEADER_LEN>() {
+            runtime_metrics(NetworkRuntime::Wireframe)
+                .record_reply(FrameHeader::from_bytes(reply_header).error);
+        }
 
         // Call inner service to propagate through the chain, then replace the response frame
         let mut response = self.inner.call(req).await?;
@@ -260,6 +387,10 @@ impl Transform<HandlerService<Envelope>> for TransactionMiddleware {
             messaging: Arc::clone(&self.messaging),
             presence: Arc::clone(&self.presence),
             presence_connection_id: self.presence_connection_id,
+            activity: Arc::clone(&self.activity),
+            pings: Arc::clone(&self.pings),
+            limiter: Arc::clone(&self.limiter),
+            settings: Arc::clone(&self.settings),
         };
         HandlerService::from_service(id, wrapped)
     }
@@ -2,11 +2,13 @@
 //!
 //! Centralizes error reply construction and logging so routing error paths
 //! preserve transaction identifiers whenever possible and emit structured
-//! tracing events.
+//! tracing events. Error codes and text come from
+//! [`CommandError::reply_to`], the mapping the legacy runtime uses too.
 
 use std::{fmt::Display, net::SocketAddr};
 
 use crate::{
+    commands::CommandError,
     header_util::reply_header,
     transaction::{FrameHeader, HEADER_LEN, Transaction},
 };
@@ -61,19 +63,29 @@ impl ReplyBuilder {
         }
     }
 
-    pub(crate) fn parse_error<E: Display>(&self, err: E, error_code: u32) -> Vec<u8> {
-        self.log_warn_with_error(err, error_code, "failed to parse transaction from bytes");
-        self.error_bytes(error_code)
+    pub(crate) fn parse_error(&self, err: impl Into<CommandError>) -> Vec<u8> {
+        let error = err.into();
+        self.log_warn_with_error(
+            &error,
+            error.error_code(),
+            "failed to parse transaction from bytes",
+        );
+        self.reply_bytes(&error)
     }
 
-    pub(crate) fn command_parse_error<E: Display>(&self, err: E, error_code: u32) -> Vec<u8> {
-        self.log_warn_with_error(err, error_code, "failed to parse command from transaction");
-        self.error_bytes(error_code)
+    pub(crate) fn command_parse_error(&self, err: impl Into<CommandError>) -> Vec<u8> {
+        let error = err.into();
+        self.log_warn_with_error(
+            &error,
+            error.error_code(),
+            "failed to parse command from transaction",
+        );
+        self.reply_bytes(&error)
     }
 
-    pub(crate) fn process_error<E: Display>(&self, err: E, error_code: u32) -> Vec<u8> {
-        self.log_error_with_error(err, error_code, "command processing failed");
-        self.error_bytes(error_code)
+    pub(crate) fn process_error(&self, error: &CommandError) -> Vec<u8> {
+        self.log_error_with_error(error, error.error_code(), "command processing failed");
+        self.reply_bytes(error)
     }
 
     pub(crate) fn missing_reply(&self, error_code: u32) -> Vec<u8> {
@@ -93,6 +105,10 @@ impl ReplyBuilder {
         self.error_transaction(error_code).to_bytes()
     }
 
+    fn reply_bytes(&self, error: &CommandError) -> Vec<u8> {
+        error.reply_to(&self.request_header_or_default()).to_bytes()
+    }
+
     log_method!(log_warn_with_error, warn, with_error);
     log_method!(log_error_with_error, error, with_error);
     log_method!(log_error_without_error, error, without_error);
@@ -132,7 +148,9 @@ mod tests {
 
     use super::ReplyBuilder;
     use crate::{
-        transaction::FrameHeader,
+        commands::CommandError,
+        field_id::FieldId,
+        transaction::{FrameHeader, HEADER_LEN, TransactionError, decode_params},
         wireframe::test_helpers::{
             tracing::{RecordedEvent, capture_single_event},
             transaction_bytes,
@@ -263,14 +281,14 @@ mod tests {
         header: (200, 42),
         expected: {
             level: Level::WARN,
-            error_code: 3,
+            error_code: 2,
             message: "failed to parse transaction from bytes",
-            err: Some("parse fail")
+            err: Some("transaction error: size mismatch")
         },
         action: |peer, header| {
             let frame = transaction_bytes(header, &[]);
             let builder = ReplyBuilder::from_frame(peer, &frame);
-            let _ = builder.parse_error("parse fail", 3);
+            let _ = builder.parse_error(TransactionError::SizeMismatch);
         },
     );
 
@@ -280,13 +298,13 @@ mod tests {
         header: (201, 43),
         expected: {
             level: Level::WARN,
-            error_code: 4,
+            error_code: 22,
             message: "failed to parse command from transaction",
-            err: Some("command fail")
+            err: Some("transaction error: payload checksum mismatch")
         },
         action: |peer, header| {
             let builder = ReplyBuilder::from_header(peer, header);
-            let _ = builder.command_parse_error("command fail", 4);
+            let _ = builder.command_parse_error(TransactionError::ChecksumMismatch);
         },
     );
 
@@ -296,13 +314,13 @@ mod tests {
         header: (202, 44),
         expected: {
             level: Level::ERROR,
-            error_code: 6,
+            error_code: 3,
             message: "command processing failed",
-            err: Some("process fail")
+            err: Some("invariant violation: process fail")
         },
         action: |peer, header| {
             let builder = ReplyBuilder::from_header(peer, header);
-            let _ = builder.process_error("process fail", 6);
+            let _ = builder.process_error(&CommandError::Invariant("process fail"));
         },
     );
 
@@ -312,20 +330,51 @@ mod tests {
         capture_and_assert_event(
             || {
                 let builder = ReplyBuilder::from_frame(peer, &[]);
-                let _ = builder.parse_error("short", 3);
+                let _ = builder.parse_error(TransactionError::SizeMismatch);
             },
             &ExpectedEvent {
                 level: Level::WARN,
                 peer: "127.0.0.1:9001",
                 ty: None,
                 id: None,
-                error_code: 3,
+                error_code: 2,
                 message: Some("failed to parse transaction from bytes"),
-                err: Some("short"),
+                err: Some("transaction error: size mismatch"),
             },
         );
     }
 
+    #[rstest]
+    fn malformed_requests_are_explained_in_error_text() {
+        let peer: SocketAddr = "127.0.0.1:9005".parse().expect("peer");
+        let builder = ReplyBuilder::from_header(peer, &test_header(107, 45));
+
+        let reply = builder.command_parse_error(TransactionError::MissingField(FieldId::Login));
+
+        let header = FrameHeader::from_bytes(
+            reply
+                .get(..HEADER_LEN)
+                .and_then(|bytes| bytes.try_into().ok())
+                .expect("reply header"),
+        );
+        assert_eq!(header.error, 2);
+        let params = decode_params(reply.get(HEADER_LEN..).expect("payload")).expect("params");
+        assert_eq!(
+            params,
+            vec![(FieldId::ErrorText, b"missing field Login".to_vec())]
+        );
+    }
+
+    #[rstest]
+    fn server_failures_carry_no_error_text() {
+        let peer: SocketAddr = "127.0.0.1:9006".parse().expect("peer");
+        let builder = ReplyBuilder::from_header(peer, &test_header(107, 46));
+
+        let reply = builder.process_error(&CommandError::Invariant("secret detail"));
+
+        assert_eq!(reply.len(), HEADER_LEN);
+    }
+
     test_reply_builder_logging!(
         missing_reply_logs_without_error_field,
         peer: "127.0.0.1:9002",
@@ -0,0 +1,394 @@
+//! Account inspection (348, 352) and administration (350, 351, 353)
+//! routing tests.
+
+use rstest::rstest;
+use test_util::{AnyError, TestDb, build_test_db, setup_login_db};
+use tokio::runtime::Runtime;
+
+use super::helpers::{RouteTestContext, decode_reply_params, issue_token, runtime, token_redeems};
+use crate::{
+    commands::{
+        ERR_ACCOUNT_EXISTS,
+        ERR_ACCOUNT_NOT_FOUND,
+        ERR_INSUFFICIENT_PRIVILEGES,
+        ERR_INVALID_PAYLOAD,
+    },
+    db::{decode_privileges, encode_privileges, get_user_by_name, set_privileges},
+    field_id::FieldId,
+    models::User,
+    privileges::{Privileges, wire},
+    transaction::decode_params,
+    transaction_type::TransactionType,
+    users::verify_password,
+};
+
+fn find_account(rt: &Runtime, test_db: &TestDb, login: &str) -> Result<Option<User>, AnyError> {
+    rt.block_on(async {
+        let mut conn = test_db.pool().get().await?;
+        Ok(get_user_by_name(&mut conn, login).await?)
+    })
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::new_user(TransactionType::NewUser, Privileges::CREATE_USER)]
+#[case::delete_user(TransactionType::DeleteUser, Privileges::DELETE_USER)]
+#[case::set_user(TransactionType::SetUser, Privileges::MODIFY_USER)]
+fn account_changes_require_matching_privilege(
+    #[case] ty: TransactionType,
+    #[case] required: Privileges,
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::admin() - required);
+
+    let reply = rt.block_on(ctx.send(
+        ty,
+        60,
+        &[(FieldId::Login, b"alice"), (FieldId::Password, b"changed")],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    let alice = find_account(&rt, &test_db, "alice")?.expect("alice is untouched");
+    assert!(verify_password(&alice.password, "secret"));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn new_user_hashes_password_and_rejects_duplicates() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::CREATE_USER);
+    let params: [(FieldId, &[u8]); 3] = [
+        (FieldId::Login, b"bob"),
+        (FieldId::Password, b"hunter2"),
+        (FieldId::Name, b"Bob"),
+    ];
+
+    let created = rt.block_on(ctx.send(TransactionType::NewUser, 61, &params))?;
+    let duplicate = rt.block_on(ctx.send(TransactionType::NewUser, 62, &params))?;
+
+    assert_eq!(created.header.error, 0);
+    assert!(created.payload.is_empty());
+    assert_eq!(duplicate.header.error, ERR_ACCOUNT_EXISTS);
+    let bob = find_account(&rt, &test_db, "bob")?.expect("bob was created");
+    assert_ne!(bob.password, "hunter2");
+    assert!(verify_password(&bob.password, "hunter2"));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn set_user_renames_and_keeps_unchanged_password() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let alice = find_account(&rt, &test_db, "alice")?.expect("alice exists");
+    let token = rt.block_on(issue_token(&test_db.pool(), alice.id))?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::MODIFY_USER);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::SetUser,
+        63,
+        &[
+            (FieldId::Data, b"alice"),
+            (FieldId::Login, b"alicia"),
+            (FieldId::Password, &[0]),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    assert!(find_account(&rt, &test_db, "alice")?.is_none());
+    let alicia = find_account(&rt, &test_db, "alicia")?.expect("alice was renamed");
+    assert!(verify_password(&alicia.password, "secret"));
+    assert!(rt.block_on(token_redeems(&test_db.pool(), alicia.id, &token))?);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn set_user_replaces_password() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let before = find_account(&rt, &test_db, "alice")?.expect("alice exists");
+    let token = rt.block_on(issue_token(&test_db.pool(), before.id))?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::MODIFY_USER);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::SetUser,
+        64,
+        &[(FieldId::Login, b"alice"), (FieldId::Password, b"changed")],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let alice = find_account(&rt, &test_db, "alice")?.expect("alice still exists");
+    assert!(verify_password(&alice.password, "changed"));
+    // Tokens issued against the old password no longer log in.
+    assert!(!rt.block_on(token_redeems(&test_db.pool(), alice.id, &token))?);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::delete_user(TransactionType::DeleteUser)]
+#[case::set_user(TransactionType::SetUser)]
+fn account_changes_report_unknown_logins(#[case] ty: TransactionType) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::admin());
+
+    let reply = rt.block_on(ctx.send(ty, 65, &[(FieldId::Login, b"nobody")]))?;
+
+    assert_eq!(reply.header.error, ERR_ACCOUNT_NOT_FOUND);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn delete_user_removes_account() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::DELETE_USER);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteUser,
+        66,
+        &[(FieldId::Login, b"alice")],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    assert!(find_account(&rt, &test_db, "alice")?.is_none());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::get_user(TransactionType::GetUser)]
+#[case::get_accounts(TransactionType::GetAccounts)]
+fn account_inspection_requires_open_user(#[case] ty: TransactionType) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::admin() - Privileges::OPEN_USER);
+    let params: &[(FieldId, &[u8])] = match ty {
+        TransactionType::GetUser => &[(FieldId::Login, b"alice")],
+        _ => &[],
+    };
+
+    let reply = rt.block_on(ctx.send(ty, 67, params))?;
+
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    assert!(reply.payload.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn get_user_describes_account_without_password() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::OPEN_USER);
+
+    let reply =
+        rt.block_on(ctx.send(TransactionType::GetUser, 68, &[(FieldId::Login, b"alice")]))?;
+
+    assert_eq!(reply.header.error, 0);
+    let inverted: Vec<u8> = b"alice".iter().map(|byte| !byte).collect();
+    let access = wire::encode(Privileges::default_user()).to_vec();
+    assert_eq!(
+        decode_reply_params(&reply)?,
+        vec![
+            (FieldId::Name, b"alice".to_vec()),
+            (FieldId::Login, inverted),
+            (FieldId::Password, vec![0]),
+            (FieldId::UserAccess, access),
+        ]
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn get_user_reports_unknown_login() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::OPEN_USER);
+
+    let reply =
+        rt.block_on(ctx.send(TransactionType::GetUser, 69, &[(FieldId::Login, b"nobody")]))?;
+
+    assert_eq!(reply.header.error, ERR_ACCOUNT_NOT_FOUND);
+    assert!(reply.payload.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn get_accounts_lists_each_account_in_a_data_field() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::OPEN_USER);
+
+    let reply = rt.block_on(ctx.send(TransactionType::GetAccounts, 70, &[]))?;
+
+    assert_eq!(reply.header.error, 0);
+    let entries = decode_reply_params(&reply)?;
+    assert_eq!(entries.len(), 1);
+    let (field, data) = entries.first().expect("one account is listed");
+    assert_eq!(*field, FieldId::Data);
+    let account = decode_params(data)?;
+    assert!(account.contains(&(FieldId::Name, b"alice".to_vec())));
+    assert!(account.contains(&(FieldId::Password, vec![0])));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::new_user(TransactionType::NewUser, "carol")]
+#[case::set_user(TransactionType::SetUser, "alice")]
+fn account_changes_store_access_bitmap(
+    #[case] ty: TransactionType,
+    #[case] login: &str,
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::admin());
+    let granted = Privileges::DOWNLOAD_FILE | Privileges::OPEN_USER;
+    let access = wire::encode(granted);
+
+    let reply = rt.block_on(ctx.send(
+        ty,
+        71,
+        &[
+            (FieldId::Login, login.as_bytes()),
+            (FieldId::Password, &[0]),
+            (FieldId::UserAccess, &access),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let account = find_account(&rt, &test_db, login)?.expect("account exists");
+    assert_eq!(decode_privileges(account.privileges), granted);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn account_changes_reject_short_access_bitmap() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::admin());
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::SetUser,
+        72,
+        &[
+            (FieldId::Login, b"alice"),
+            (FieldId::UserAccess, &[0xff; 4]),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_INVALID_PAYLOAD);
+    let alice = find_account(&rt, &test_db, "alice")?.expect("alice still exists");
+    assert_eq!(
+        decode_privileges(alice.privileges),
+        Privileges::default_user()
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn login_grants_stored_privileges() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let granted = Privileges::default_user() | Privileges::BROADCAST | Privileges::NO_AGREEMENT;
+    rt.block_on(async {
+        let mut conn = test_db.pool().get().await?;
+        set_privileges(&mut conn, "alice", granted).await?;
+        Ok::<_, AnyError>(())
+    })?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::Login,
+        73,
+        &[(FieldId::Login, b"alice"), (FieldId::Password, b"secret")],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    assert!(ctx.session.is_authenticated());
+    assert_eq!(ctx.session.privileges, granted);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::new_user(TransactionType::NewUser, Privileges::CREATE_USER, "carol")]
+#[case::set_own_account(TransactionType::SetUser, Privileges::MODIFY_USER, "alice")]
+fn account_changes_refuse_privileges_the_administrator_lacks(
+    #[case] ty: TransactionType,
+    #[case] held: Privileges,
+    #[case] login: &str,
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_login_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, held);
+    let access = wire::encode(Privileges::admin());
+
+    let reply = rt.block_on(ctx.send(
+        ty,
+        73,
+        &[
+            (FieldId::Login, login.as_bytes()),
+            (FieldId::Password, &[0]),
+            (FieldId::UserAccess, &access),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    let stored = find_account(&rt, &test_db, login)?.map(|account| account.privileges);
+    let expected = (login == "alice").then(|| encode_privileges(Privileges::default_user()));
+    assert_eq!(stored, expected);
+    Ok(())
+}
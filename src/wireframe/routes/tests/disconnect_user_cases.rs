@@ -0,0 +1,168 @@
+//! Disconnect User (110) routing tests.
+
+use std::net::IpAddr;
+
+use rstest::rstest;
+use test_util::{AnyError, build_test_db, setup_files_db};
+
+use super::helpers::{RouteTestContext, issue_token, runtime, token_redeems};
+use crate::{
+    commands::{
+        ERR_INSUFFICIENT_PRIVILEGES,
+        ERR_INTERNAL_SERVER,
+        ERR_NOT_AUTHENTICATED,
+        ERR_USER_NOT_ONLINE,
+    },
+    db::{BanTarget, find_active_ban},
+    field_id::FieldId,
+    privileges::Privileges,
+    server::bans::{BAN_OPTION_PERMANENT, ban_clock},
+    transaction_type::TransactionType,
+};
+
+#[expect(clippy::big_endian_bytes, reason = "network protocol")]
+fn user_id_field(user_id: i32) -> Result<[u8; 2], AnyError> {
+    Ok(u16::try_from(user_id)?.to_be_bytes())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::default_user(Privileges::default_user(), false, ERR_INSUFFICIENT_PRIVILEGES)]
+#[case::protected_target(Privileges::DISCONNECT_USER, true, ERR_INSUFFICIENT_PRIVILEGES)]
+#[case::no_controller(Privileges::DISCONNECT_USER, false, ERR_INTERNAL_SERVER)]
+fn disconnect_user_checks_caller_and_target(
+    #[case] privileges: Privileges,
+    #[case] protected: bool,
+    #[case] expected_error: u32,
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, privileges);
+    let target = user_id_field(ctx.add_peer(9, 1, protected))?;
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DisconnectUser,
+        40,
+        &[(FieldId::UserId, target.as_ref())],
+    ))?;
+
+    assert_eq!(reply.header.error, expected_error);
+    assert!(reply.payload.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn disconnect_user_rejects_offline_target() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::DISCONNECT_USER);
+    let target = user_id_field(42)?;
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DisconnectUser,
+        41,
+        &[(FieldId::UserId, target.as_ref())],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_USER_NOT_ONLINE);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn disconnect_user_requires_online_session() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    let target = user_id_field(1)?;
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DisconnectUser,
+        42,
+        &[(FieldId::UserId, target.as_ref())],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_NOT_AUTHENTICATED);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[expect(clippy::big_endian_bytes, reason = "network protocol")]
+#[rstest]
+fn disconnect_user_ban_option_bans_target_account_and_address() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::DISCONNECT_USER);
+    let target = user_id_field(ctx.add_peer(9, 1, false))?;
+    let mapped: IpAddr = "::ffff:198.51.100.9".parse()?;
+    ctx.attach_peer_address(9, mapped);
+    let options = u16::try_from(BAN_OPTION_PERMANENT)?.to_be_bytes();
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DisconnectUser,
+        43,
+        &[
+            (FieldId::UserId, target.as_ref()),
+            (FieldId::Options, options.as_ref()),
+        ],
+    ))?;
+
+    // The route test context cannot reach other connections, but the ban is
+    // recorded before the disconnect is attempted.
+    assert_eq!(reply.header.error, ERR_INTERNAL_SERVER);
+    let (account_ban, address_ban) = rt.block_on(async {
+        let mut conn = test_db.pool().get().await?;
+        let account = BanTarget::Username("alice".to_owned());
+        let address = BanTarget::Address("198.51.100.9".parse()?);
+        let now = ban_clock();
+        Ok::<_, AnyError>((
+            find_active_ban(&mut conn, &account, now).await?,
+            find_active_ban(&mut conn, &address, now).await?,
+        ))
+    })?;
+    assert!(account_ban.is_some_and(|found| found.expires_at.is_none()));
+    assert!(address_ban.is_some_and(|found| found.expires_at.is_none()));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[expect(clippy::big_endian_bytes, reason = "network protocol")]
+#[rstest]
+#[case::kick(None)]
+#[case::ban(Some(BAN_OPTION_PERMANENT))]
+fn disconnect_user_revokes_target_session_tokens(#[case] ban: Option<u32>) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let token = rt.block_on(issue_token(&test_db.pool(), 1))?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::DISCONNECT_USER);
+    let target = user_id_field(ctx.add_peer(9, 1, false))?;
+    let options = ban.map(u16::try_from).transpose()?.map(u16::to_be_bytes);
+    let mut params: Vec<(FieldId, &[u8])> = vec![(FieldId::UserId, target.as_ref())];
+    params.extend(
+        options
+            .as_ref()
+            .map(|bytes| (FieldId::Options, bytes.as_ref())),
+    );
+
+    let reply = rt.block_on(ctx.send(TransactionType::DisconnectUser, 44, &params))?;
+
+    // As above, the tokens are revoked before the disconnect is attempted.
+    assert_eq!(reply.header.error, ERR_INTERNAL_SERVER);
+    assert!(!rt.block_on(token_redeems(&test_db.pool(), 1, &token))?);
+    Ok(())
+}
@@ -11,10 +11,14 @@ use super::super::{
     handle_parse_error,
 };
 use crate::{
+    field_id::FieldId,
     handler::Session,
     presence::PresenceRegistry,
-    server::outbound::{NoopOutboundMessaging, OutboundConnectionId},
-    transaction::{FrameHeader, HEADER_LEN, Transaction},
+    server::{
+        outbound::{NoopOutboundMessaging, OutboundConnectionId},
+        settings::ServerSettings,
+    },
+    transaction::{FrameHeader, HEADER_LEN, Transaction, TransactionError},
     wireframe::{
         compat::XorCompatibility,
         compat_policy::ClientCompatibility,
@@ -26,8 +30,10 @@ use crate::{
 
 /// Error code indicating a permission failure.
 const ERR_PERMISSION: u32 = 1;
-/// Error code for internal failures (mirrors the route module constant).
+/// Error code for internal failures.
 const ERR_INTERNAL: u32 = 3;
+/// Error code for malformed requests.
+const ERR_INVALID_PAYLOAD: u32 = 2;
 /// Error code returned for unknown transaction types (per spec: `ERR_INTERNAL`).
 const ERR_UNKNOWN_TYPE: u32 = 3;
 
@@ -67,11 +73,11 @@ fn error_reply_preserves_header_fields(#[case] ty: u16, #[case] id: u32, #[case]
     assert!(reply.payload().is_empty());
 }
 
-/// Tests that malformed input returns an error with `ERR_INTERNAL`.
+/// Tests that transport failures while parsing return `ERR_INTERNAL`.
 #[rstest]
 fn handle_parse_error_returns_internal_error() {
     let peer = "127.0.0.1:5555".parse().expect("valid peer");
-    let result = handle_parse_error(peer, &[], "simulated parse error");
+    let result = handle_parse_error(peer, &[], TransactionError::Timeout);
 
     // Should produce a valid transaction header + empty payload.
     assert!(
@@ -103,7 +109,11 @@ fn handle_command_parse_error_preserves_id() {
         data_size: 0,
     };
 
-    let result = handle_command_parse_error(peer, &header, "simulated command error");
+    let result = handle_command_parse_error(
+        peer,
+        &header,
+        TransactionError::InvalidParamValue(FieldId::Login),
+    );
 
     let reply_header = FrameHeader::from_bytes(
         result[..HEADER_LEN]
@@ -113,7 +123,7 @@ fn handle_command_parse_error_preserves_id() {
     assert_eq!(reply_header.is_reply, 1);
     assert_eq!(reply_header.ty, 200);
     assert_eq!(reply_header.id, 54321);
-    assert_eq!(reply_header.error, ERR_INTERNAL);
+    assert_eq!(reply_header.error, ERR_INVALID_PAYLOAD);
 }
 
 /// Tests that `transaction_to_bytes` correctly serializes a transaction.
@@ -199,6 +209,7 @@ async fn process_transaction_bytes_truncated_input() {
                 messaging: &messaging,
                 presence: &presence,
                 presence_connection_id: OutboundConnectionId::new(1),
+                settings: &ServerSettings::default(),
             },
         )
         .await;
@@ -210,7 +221,7 @@ async fn process_transaction_bytes_truncated_input() {
             .try_into()
             .expect("header slice should be exact size"),
     );
-    assert_eq!(reply_header.error, ERR_INTERNAL);
+    assert_eq!(reply_header.error, ERR_INVALID_PAYLOAD);
 }
 
 async fn assert_error_reply(header: FrameHeader, payload: &[u8]) -> FrameHeader {
@@ -233,6 +244,7 @@ async fn assert_error_reply(header: FrameHeader, payload: &[u8]) -> FrameHeader
                 messaging: &messaging,
                 presence: &presence,
                 presence_connection_id: OutboundConnectionId::new(1),
+                settings: &ServerSettings::default(),
             },
         )
         .await;
@@ -260,7 +272,7 @@ async fn process_transaction_bytes_preserves_id_on_payload_mismatch() {
     let reply_header = assert_error_reply(header, &[]).await;
     assert_eq!(reply_header.id, 4242);
     assert_eq!(reply_header.ty, 200);
-    assert_eq!(reply_header.error, ERR_INTERNAL);
+    assert_eq!(reply_header.error, ERR_INVALID_PAYLOAD);
 }
 
 /// Tests that unknown transaction type returns error code 3.
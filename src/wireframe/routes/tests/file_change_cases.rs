@@ -0,0 +1,293 @@
+//! Unit tests covering Delete File and Move File routing.
+
+use rstest::rstest;
+use test_util::{AnyError, build_test_db, setup_drop_box_db, setup_file_tree_db};
+use tokio::runtime::Runtime;
+
+use super::helpers::{
+    RouteTestContext,
+    collect_strings,
+    decode_reply_params,
+    folder_path,
+    runtime,
+};
+use crate::{
+    commands::{
+        ERR_INSUFFICIENT_PRIVILEGES,
+        ERR_INVALID_PAYLOAD,
+        FILE_ERR_FOLDER_NOT_EMPTY,
+        FILE_ERR_NAME_TAKEN,
+        FILE_ERR_READ_ONLY,
+    },
+    db::{resolve_file_node_path, snapshot_folder},
+    field_id::FieldId,
+    privileges::Privileges,
+    transaction_type::TransactionType,
+};
+
+fn list_names(
+    rt: &Runtime,
+    ctx: &mut RouteTestContext,
+    segments: &[&str],
+) -> Result<Vec<String>, AnyError> {
+    let path = folder_path(segments)?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileNameList,
+        60,
+        &[(FieldId::FilePath, path.as_slice())],
+    ))?;
+    let params = decode_reply_params(&reply)?;
+    Ok(collect_strings(&params, FieldId::FileName)?)
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_delete_file_removes_nested_file() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | Privileges::DELETE_FILE);
+
+    let docs = folder_path(&["Docs"])?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteFile,
+        61,
+        &[
+            (FieldId::FileItemName, b"guide.txt"),
+            (FieldId::FilePath, docs.as_slice()),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    assert_eq!(list_names(&rt, &mut ctx, &["Docs"])?, vec!["Archive"]);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_delete_file_requires_privilege() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteFile,
+        62,
+        &[(FieldId::FileItemName, b"fileA.txt")],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_delete_file_needs_only_delete_privilege() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::DELETE_FILE);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteFile,
+        62,
+        &[(FieldId::FileItemName, b"fileA.txt")],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_delete_file_keeps_non_empty_folder() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | Privileges::DELETE_FOLDER);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteFile,
+        63,
+        &[(FieldId::FileItemName, b"Docs")],
+    ))?;
+
+    assert_eq!(reply.header.error, FILE_ERR_FOLDER_NOT_EMPTY);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_delete_file_refuses_archived_entry() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let taken_at = chrono::NaiveDate::from_ymd_opt(2026, 10, 16)
+        .and_then(|date| date.and_hms_opt(9, 5, 0))
+        .ok_or_else(|| anyhow::anyhow!("invalid snapshot time"))?;
+    rt.block_on(async {
+        let mut conn = test_db.pool().get().await?;
+        let docs = resolve_file_node_path(&mut conn, "Docs")
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Docs folder missing"))?;
+        snapshot_folder(&mut conn, docs.id, taken_at, 1).await?;
+        Ok::<_, AnyError>(())
+    })?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | Privileges::DELETE_FILE);
+
+    let snapshot = folder_path(&["Docs Archive", "2026-10-16 0905"])?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteFile,
+        62,
+        &[
+            (FieldId::FileItemName, b"guide.txt"),
+            (FieldId::FilePath, snapshot.as_slice()),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, FILE_ERR_READ_ONLY);
+    assert_eq!(
+        list_names(&rt, &mut ctx, &["Docs Archive", "2026-10-16 0905"])?,
+        vec!["Archive", "guide.txt"]
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_move_file_relocates_entry() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | Privileges::MOVE_FILE);
+
+    let docs = folder_path(&["Docs"])?;
+    let archive = folder_path(&["Docs", "Archive"])?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::MoveFile,
+        64,
+        &[
+            (FieldId::FileItemName, b"guide.txt"),
+            (FieldId::FilePath, docs.as_slice()),
+            (FieldId::FileNewPath, archive.as_slice()),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    assert_eq!(list_names(&rt, &mut ctx, &["Docs"])?, vec!["Archive"]);
+    assert_eq!(
+        list_names(&rt, &mut ctx, &["Docs", "Archive"])?,
+        vec!["guide.txt"]
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_move_file_rejects_name_clash() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(
+        1,
+        Privileges::default_user() | Privileges::RENAME_FILE | Privileges::MOVE_FILE,
+    );
+
+    let docs = folder_path(&["Docs"])?;
+    let rename = rt.block_on(ctx.send(
+        TransactionType::SetFileInfo,
+        65,
+        &[
+            (FieldId::FileItemName, b"guide.txt"),
+            (FieldId::FilePath, docs.as_slice()),
+            (FieldId::FileNewName, b"fileA.txt"),
+        ],
+    ))?;
+    assert_eq!(rename.header.error, 0);
+
+    let clash = rt.block_on(ctx.send(
+        TransactionType::MoveFile,
+        66,
+        &[
+            (FieldId::FileItemName, b"fileA.txt"),
+            (FieldId::FilePath, docs.as_slice()),
+        ],
+    ))?;
+    assert_eq!(clash.header.error, FILE_ERR_NAME_TAKEN);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_move_file_rejects_move_into_itself() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | Privileges::MOVE_FOLDER);
+
+    let archive = folder_path(&["Docs", "Archive"])?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::MoveFile,
+        67,
+        &[
+            (FieldId::FileItemName, b"Docs"),
+            (FieldId::FileNewPath, archive.as_slice()),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_INVALID_PAYLOAD);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::get_info(TransactionType::GetFileInfo, None)]
+#[case::rename(TransactionType::SetFileInfo, Some((FieldId::FileNewName, &b"mine.txt"[..])))]
+#[case::delete(TransactionType::DeleteFile, None)]
+#[case::move_out(TransactionType::MoveFile, None)]
+fn process_transaction_bytes_drop_box_entries_need_view_drop_boxes(
+    #[case] ty: TransactionType,
+    #[case] extra: Option<(FieldId, &[u8])>,
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_drop_box_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::admin() - Privileges::VIEW_DROP_BOXES);
+    let uploads = folder_path(&["Uploads"])?;
+    let mut params = vec![
+        (FieldId::FileItemName, &b"deposit.txt"[..]),
+        (FieldId::FilePath, uploads.as_slice()),
+    ];
+    params.extend(extra);
+
+    let reply = rt.block_on(ctx.send(ty, 66, &params))?;
+
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    assert!(reply.payload.is_empty());
+    ctx.authenticate_with_privileges(1, Privileges::admin());
+    assert_eq!(
+        list_names(&rt, &mut ctx, &["Uploads"])?,
+        vec!["Inbox", "deposit.txt"]
+    );
+    assert_eq!(list_names(&rt, &mut ctx, &[])?, vec!["Uploads"]);
+    Ok(())
+}
@@ -0,0 +1,163 @@
+//! Unit tests covering Get File Info and Set File Info routing.
+
+use rstest::rstest;
+use test_util::{AnyError, build_test_db, setup_files_db};
+
+use super::helpers::{RouteTestContext, decode_reply_params, find_string, runtime};
+use crate::{
+    commands::{ERR_INSUFFICIENT_PRIVILEGES, FILE_ERR_NAME_TAKEN, FILE_ERR_NOT_FOUND},
+    field_id::FieldId,
+    privileges::Privileges,
+    transaction_type::TransactionType,
+};
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_get_file_info_for_visible_file() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileInfo,
+        40,
+        &[(FieldId::FileItemName, b"fileA.txt")],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    assert_eq!(find_string(&params, FieldId::FileItemName)?, "fileA.txt");
+    assert_eq!(find_string(&params, FieldId::FileType)?, "    ");
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_get_file_info_hides_invisible_file() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileInfo,
+        41,
+        &[(FieldId::FileItemName, b"fileB.txt")],
+    ))?;
+
+    assert_eq!(reply.header.error, FILE_ERR_NOT_FOUND);
+    assert!(reply.payload.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_set_file_info_requires_comment_privilege() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::SetFileInfo,
+        42,
+        &[
+            (FieldId::FileItemName, b"fileA.txt"),
+            (FieldId::FileComment, b"Read me first"),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_set_file_info_updates_comment_and_name() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(
+        1,
+        Privileges::default_user() | Privileges::SET_FILE_COMMENT | Privileges::RENAME_FILE,
+    );
+
+    let update = rt.block_on(ctx.send(
+        TransactionType::SetFileInfo,
+        43,
+        &[
+            (FieldId::FileItemName, b"fileA.txt"),
+            (FieldId::FileNewName, b"notes.txt"),
+            (FieldId::FileComment, b"Read me first"),
+        ],
+    ))?;
+    assert_eq!(update.header.error, 0);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileInfo,
+        44,
+        &[(FieldId::FileItemName, b"notes.txt")],
+    ))?;
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    assert_eq!(find_string(&params, FieldId::FileComment)?, "Read me first");
+
+    let clash = rt.block_on(ctx.send(
+        TransactionType::SetFileInfo,
+        45,
+        &[
+            (FieldId::FileItemName, b"notes.txt"),
+            (FieldId::FileNewName, b"fileC.txt"),
+        ],
+    ))?;
+    assert_eq!(clash.header.error, FILE_ERR_NAME_TAKEN);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_file_info_needs_no_download_privilege() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::RENAME_FILE);
+
+    let info = rt.block_on(ctx.send(
+        TransactionType::GetFileInfo,
+        46,
+        &[(FieldId::FileItemName, b"fileA.txt")],
+    ))?;
+    assert_eq!(info.header.error, 0);
+
+    let rename = rt.block_on(ctx.send(
+        TransactionType::SetFileInfo,
+        47,
+        &[
+            (FieldId::FileItemName, b"fileA.txt"),
+            (FieldId::FileNewName, b"notes.txt"),
+        ],
+    ))?;
+    assert_eq!(rename.header.error, 0);
+
+    let comment = rt.block_on(ctx.send(
+        TransactionType::SetFileInfo,
+        48,
+        &[
+            (FieldId::FileItemName, b"notes.txt"),
+            (FieldId::FileComment, b"Read me first"),
+        ],
+    ))?;
+    assert_eq!(comment.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    Ok(())
+}
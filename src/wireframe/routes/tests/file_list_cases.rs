@@ -0,0 +1,165 @@
+//! Unit tests covering Get File Name List routing for nested folders.
+
+use rstest::rstest;
+use test_util::{AnyError, build_test_db, setup_drop_box_db, setup_file_tree_db};
+
+use super::helpers::{
+    RouteTestContext,
+    collect_strings,
+    decode_reply_params,
+    folder_path,
+    runtime,
+};
+use crate::{
+    commands::{ERR_INSUFFICIENT_PRIVILEGES, ERR_INVALID_PAYLOAD, FILE_ERR_NOT_FOUND},
+    field_id::FieldId,
+    privileges::Privileges,
+    transaction_type::TransactionType,
+};
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_file_list_root_includes_visible_folders() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let root = folder_path(&[])?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileNameList,
+        50,
+        &[(FieldId::FilePath, root.as_slice())],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    let names = collect_strings(&params, FieldId::FileName)?;
+    assert_eq!(names, vec!["Docs", "fileA.txt", "fileC.txt"]);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_file_list_lists_folder_contents() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let docs = folder_path(&["Docs"])?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileNameList,
+        51,
+        &[(FieldId::FilePath, docs.as_slice())],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    let names = collect_strings(&params, FieldId::FileName)?;
+    assert_eq!(names, vec!["Archive", "guide.txt"]);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::hidden_folder(&["Private"])]
+#[case::missing_folder(&["Nowhere"])]
+#[case::file_not_folder(&["fileA.txt"])]
+#[case::hidden_below_visible(&["Docs", "draft.txt"])]
+fn process_transaction_bytes_file_list_rejects_unlistable_paths(
+    #[case] segments: &[&str],
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let path = folder_path(segments)?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileNameList,
+        52,
+        &[(FieldId::FilePath, path.as_slice())],
+    ))?;
+
+    assert_eq!(reply.header.error, FILE_ERR_NOT_FOUND);
+    assert!(reply.payload.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_file_list_rejects_malformed_path() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let truncated: &[u8] = &[0, 1, 0, 0, 4, b'D'];
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileNameList,
+        53,
+        &[(FieldId::FilePath, truncated)],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_INVALID_PAYLOAD);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::drop_box(&["Uploads"])]
+#[case::folder_inside_drop_box(&["Uploads", "Inbox"])]
+fn process_transaction_bytes_file_list_hides_drop_box_contents(
+    #[case] segments: &[&str],
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_drop_box_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let path = folder_path(segments)?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileNameList,
+        54,
+        &[(FieldId::FilePath, path.as_slice())],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    assert!(reply.payload.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_file_list_shows_drop_boxes_to_viewers() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_drop_box_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | Privileges::VIEW_DROP_BOXES);
+
+    let uploads = folder_path(&["Uploads"])?;
+    let reply = rt.block_on(ctx.send(
+        TransactionType::GetFileNameList,
+        55,
+        &[(FieldId::FilePath, uploads.as_slice())],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    let names = collect_strings(&params, FieldId::FileName)?;
+    assert_eq!(names, vec!["Inbox", "deposit.txt"]);
+    Ok(())
+}
@@ -0,0 +1,361 @@
+//! Unit tests covering Download File and Upload File over the transfer port.
+#![expect(clippy::big_endian_bytes, reason = "network protocol")]
+
+use std::{
+    num::{NonZeroU64, NonZeroUsize},
+    sync::Arc,
+    time::Instant,
+};
+
+use bytes::Bytes;
+use rstest::rstest;
+use test_util::{AnyError, build_test_db, setup_file_tree_db, setup_files_db};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, duplex},
+    join,
+    runtime::Runtime,
+};
+
+use super::helpers::{
+    RouteTestContext,
+    TestStorage,
+    collect_strings,
+    decode_reply_params,
+    find_i32,
+    find_string,
+    folder_path,
+    runtime,
+};
+use crate::{
+    commands::{FILE_ERR_DOWNLOAD_REFUSED, FILE_ERR_NAME_TAKEN, FILE_ERR_QUOTA_EXCEEDED},
+    db::{get_download_credits, set_download_credits, set_quota_bytes},
+    field_id::FieldId,
+    privileges::Privileges,
+    server::{
+        download_policy::{DownloadPolicy, DownloadRules},
+        flat_file::{FlatFileInfo, encode_flat_file_prefix, read_flat_file},
+        transfer_port::{HTXF_MAGIC, TransferKind, TransferRegistry, serve_transfer},
+        transfers::{TransferCounts, TransferLimits},
+    },
+    storage::Storage,
+    transaction_type::TransactionType,
+};
+
+/// Connect to the transfer port of `ctx`'s server with `reference`, send
+/// `upload`, and return what the server sent back.
+fn transfer(
+    rt: &Runtime,
+    ctx: &RouteTestContext,
+    reference: u32,
+    upload: &[u8],
+) -> Result<(TransferKind, Vec<u8>), AnyError> {
+    rt.block_on(serve(&ctx.settings.transfers, reference, upload))
+}
+
+async fn serve(
+    registry: &TransferRegistry,
+    reference: u32,
+    upload: &[u8],
+) -> Result<(TransferKind, Vec<u8>), AnyError> {
+    let (mut client, mut server) = duplex(64 * 1024);
+    let mut handshake = HTXF_MAGIC.to_vec();
+    handshake.extend_from_slice(&reference.to_be_bytes());
+    handshake.extend_from_slice(&u32::try_from(upload.len())?.to_be_bytes());
+    handshake.extend_from_slice(&[0; 4]);
+    client.write_all(&handshake).await?;
+    client.write_all(upload).await?;
+    let kind = serve_transfer(&mut server, registry, Instant::now()).await?;
+    let mut received = Vec::new();
+    client.read_to_end(&mut received).await?;
+    Ok((kind, received))
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn downloads_are_served_from_storage() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let storage = TestStorage::create()?;
+    rt.block_on(storage.backend().put("1", Bytes::from_static(b"hello")))?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.settings.storage = Some(storage.shared());
+    ctx.authenticate(1);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DownloadFile,
+        90,
+        &[(FieldId::FileItemName, b"fileA.txt")],
+    ))?;
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    assert_eq!(find_i32(&params, FieldId::FileSize)?, 5);
+    let transfer_size = find_i32(&params, FieldId::TransferSize)?;
+    let reference = find_i32(&params, FieldId::ReferenceNumber)?.cast_unsigned();
+
+    let (kind, received) = transfer(&rt, &ctx, reference, &[])?;
+    assert_eq!(kind, TransferKind::File);
+    assert_eq!(i32::try_from(received.len())?, transfer_size);
+    let file = rt.block_on(read_flat_file(&mut received.as_slice()))?;
+    assert_eq!(file.info.name, "fileA.txt");
+    assert_eq!(file.data, b"hello");
+    assert_eq!(ctx.transferred().downloaded, 5);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn uploads_are_stored_and_listed() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let storage = TestStorage::create()?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.settings.storage = Some(storage.shared());
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | Privileges::UPLOAD_FILE);
+    let info = FlatFileInfo {
+        name: "notes.txt".to_owned(),
+        type_code: *b"TEXT",
+        creator_code: *b"ttxt",
+        ..FlatFileInfo::default()
+    };
+    let mut upload = encode_flat_file_prefix(&info, 6)?;
+    upload.extend_from_slice(b"jotted");
+    let size = u32::try_from(upload.len())?.to_be_bytes();
+    let docs = folder_path(&["Docs"])?;
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::UploadFile,
+        91,
+        &[
+            (FieldId::FileItemName, b"notes.txt"),
+            (FieldId::FilePath, docs.as_slice()),
+            (FieldId::TransferSize, size.as_ref()),
+        ],
+    ))?;
+    assert_eq!(reply.header.error, 0);
+    let reference = find_i32(&decode_reply_params(&reply)?, FieldId::ReferenceNumber)?;
+    let (kind, _) = transfer(&rt, &ctx, reference.cast_unsigned(), &upload)?;
+    assert_eq!(kind, TransferKind::Upload);
+    assert_eq!(ctx.transferred().uploaded, 6);
+
+    let listing = rt.block_on(ctx.send(
+        TransactionType::GetFileNameList,
+        92,
+        &[(FieldId::FilePath, docs.as_slice())],
+    ))?;
+    let names = collect_strings(&decode_reply_params(&listing)?, FieldId::FileName)?;
+    assert!(names.contains(&"notes.txt".to_owned()));
+
+    let download = rt.block_on(ctx.send(
+        TransactionType::DownloadFile,
+        93,
+        &[
+            (FieldId::FileItemName, b"notes.txt"),
+            (FieldId::FilePath, docs.as_slice()),
+        ],
+    ))?;
+    let reference = find_i32(&decode_reply_params(&download)?, FieldId::ReferenceNumber)?;
+    let (_, received) = transfer(&rt, &ctx, reference.cast_unsigned(), &[])?;
+    let file = rt.block_on(read_flat_file(&mut received.as_slice()))?;
+    assert_eq!(file.data, b"jotted");
+    assert_eq!(file.info.type_code, *b"TEXT");
+
+    let again = rt.block_on(ctx.send(
+        TransactionType::UploadFile,
+        94,
+        &[
+            (FieldId::FileItemName, b"notes.txt"),
+            (FieldId::FilePath, docs.as_slice()),
+            (FieldId::TransferSize, size.as_ref()),
+        ],
+    ))?;
+    assert_eq!(again.header.error, FILE_ERR_NAME_TAKEN);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn downloads_spend_credits_and_stop_when_they_run_out() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let storage = TestStorage::create()?;
+    rt.block_on(storage.backend().put("1", Bytes::from_static(b"hello")))?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.settings.storage = Some(storage.shared());
+    ctx.settings.download_rules = DownloadRules {
+        policy: DownloadPolicy::Credits,
+        exempt: Privileges::empty(),
+    };
+    ctx.authenticate(1);
+    let request = [(FieldId::FileItemName, b"fileA.txt".as_slice())];
+
+    let refused = rt.block_on(ctx.send(TransactionType::DownloadFile, 95, &request))?;
+    assert_eq!(refused.header.error, FILE_ERR_DOWNLOAD_REFUSED);
+    let reason = find_string(&decode_reply_params(&refused)?, FieldId::ErrorText)?;
+    assert!(reason.contains("needs 5 credits and you have 0"));
+
+    rt.block_on(async {
+        let mut conn = test_db.pool().get().await?;
+        set_download_credits(&mut conn, 1, 5).await?;
+        Ok::<_, AnyError>(())
+    })?;
+    let reply = rt.block_on(ctx.send(TransactionType::DownloadFile, 96, &request))?;
+    assert_eq!(reply.header.error, 0);
+    let reference = find_i32(&decode_reply_params(&reply)?, FieldId::ReferenceNumber)?;
+    let (_, received) = transfer(&rt, &ctx, reference.cast_unsigned(), &[])?;
+    assert_eq!(
+        rt.block_on(read_flat_file(&mut received.as_slice()))?.data,
+        b"hello"
+    );
+    let credits = rt.block_on(async {
+        let mut conn = test_db.pool().get().await?;
+        Ok::<_, AnyError>(get_download_credits(&mut conn, 1).await?)
+    })?;
+    assert_eq!(credits, 0);
+
+    let again = rt.block_on(ctx.send(TransactionType::DownloadFile, 97, &request))?;
+    assert_eq!(again.header.error, FILE_ERR_DOWNLOAD_REFUSED);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn queued_downloads_report_their_place_and_wait_their_turn() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let storage = TestStorage::create()?;
+    rt.block_on(storage.backend().put("1", Bytes::from_static(b"first")))?;
+    rt.block_on(storage.backend().put("3", Bytes::from_static(b"third")))?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.settings.storage = Some(storage.shared());
+    ctx.settings.transfers = Arc::new(TransferRegistry::new(TransferLimits {
+        total: NonZeroUsize::new(1),
+        ..TransferLimits::UNLIMITED
+    }));
+    ctx.authenticate(1);
+
+    let running = rt.block_on(ctx.send(
+        TransactionType::DownloadFile,
+        98,
+        &[(FieldId::FileItemName, b"fileA.txt")],
+    ))?;
+    let running_params = decode_reply_params(&running)?;
+    assert_eq!(find_i32(&running_params, FieldId::WaitingCount)?, 0);
+    let queued = rt.block_on(ctx.send(
+        TransactionType::DownloadFile,
+        99,
+        &[(FieldId::FileItemName, b"fileC.txt")],
+    ))?;
+    let queued_params = decode_reply_params(&queued)?;
+    assert_eq!(find_i32(&queued_params, FieldId::WaitingCount)?, 1);
+    assert_eq!(
+        ctx.settings.transfers.manager().counts(),
+        TransferCounts {
+            active: 1,
+            queued: 1,
+        }
+    );
+
+    // The queued download is claimed first and held until the running one
+    // finishes.
+    let running_reference = find_i32(&running_params, FieldId::ReferenceNumber)?;
+    let queued_reference = find_i32(&queued_params, FieldId::ReferenceNumber)?;
+    let transfers = &ctx.settings.transfers;
+    let (later, first) = rt.block_on(async {
+        join!(
+            serve(transfers, queued_reference.cast_unsigned(), &[]),
+            serve(transfers, running_reference.cast_unsigned(), &[]),
+        )
+    });
+    let (_, first_received) = first?;
+    let (_, later_received) = later?;
+    assert_eq!(
+        rt.block_on(read_flat_file(&mut first_received.as_slice()))?
+            .data,
+        b"first"
+    );
+    assert_eq!(
+        rt.block_on(read_flat_file(&mut later_received.as_slice()))?
+            .data,
+        b"third"
+    );
+    assert_eq!(
+        ctx.settings.transfers.manager().counts(),
+        TransferCounts::default()
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn uploads_past_the_quota_are_refused() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let storage = TestStorage::create()?;
+    rt.block_on(async {
+        let mut conn = test_db.pool().get().await?;
+        set_quota_bytes(&mut conn, "alice", Some(10)).await?;
+        Ok::<_, AnyError>(())
+    })?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.settings.storage = Some(storage.shared());
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | Privileges::UPLOAD_FILE);
+    let size = 200u32.to_be_bytes();
+    let docs = folder_path(&["Docs"])?;
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::UploadFile,
+        100,
+        &[
+            (FieldId::FileItemName, b"big.txt"),
+            (FieldId::FilePath, docs.as_slice()),
+            (FieldId::TransferSize, size.as_ref()),
+        ],
+    ))?;
+    assert_eq!(reply.header.error, FILE_ERR_QUOTA_EXCEEDED);
+    let reason = find_string(&decode_reply_params(&reply)?, FieldId::ErrorText)?;
+    assert!(reason.contains("your quota is 10 bytes"));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn uploads_past_the_size_cap_are_refused() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let storage = TestStorage::create()?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.settings.storage = Some(storage.shared());
+    ctx.settings.transfers = Arc::new(TransferRegistry::new(TransferLimits {
+        max_upload: NonZeroU64::new(100),
+        ..TransferLimits::UNLIMITED
+    }));
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | Privileges::UPLOAD_FILE);
+    let size = 200u32.to_be_bytes();
+    let docs = folder_path(&["Docs"])?;
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::UploadFile,
+        101,
+        &[
+            (FieldId::FileItemName, b"big.txt"),
+            (FieldId::FilePath, docs.as_slice()),
+            (FieldId::TransferSize, size.as_ref()),
+        ],
+    ))?;
+    assert_eq!(reply.header.error, FILE_ERR_QUOTA_EXCEEDED);
+    let reason = find_string(&decode_reply_params(&reply)?, FieldId::ErrorText)?;
+    assert!(reason.contains("at most 100 bytes"));
+    Ok(())
+}
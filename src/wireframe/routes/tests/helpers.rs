@@ -1,27 +1,69 @@
 //! Shared helpers for wireframe routing tests.
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
 
+use chrono::{TimeDelta, Utc};
+use tempfile::TempDir;
 use test_util::AnyError;
 use tokio::runtime::{Builder, Runtime};
 
 pub(super) use crate::wireframe::test_helpers::{build_frame, collect_strings};
 use crate::{
-    db::DbPool,
+    db::{DbPool, issue_session_token, redeem_session_token},
     field_id::FieldId,
+    file_handlers::encode_file_path,
     handler::Session,
-    presence::{PresenceRegistry, SessionPhase},
+    presence::{PresenceRegistry, PresenceSnapshot, SessionPhase},
     privileges::Privileges,
-    server::outbound::{NoopOutboundMessaging, OutboundConnectionId},
+    server::{
+        idle::ActivityClock,
+        outbound::{NoopOutboundMessaging, OutboundConnectionId},
+        settings::ServerSettings,
+        transfer_stats::SessionBytes,
+    },
+    storage::{LocalStorage, Storage},
     transaction::{Transaction, decode_params, parse_transaction},
     transaction_type::TransactionType,
     wireframe::{
         compat::XorCompatibility,
         compat_policy::ClientCompatibility,
+        connection::HandshakeMetadata,
         router::{RouteContext, WireframeRouter},
     },
 };
 
+/// A storage backend in a temporary directory, removed on drop.
+pub(super) struct TestStorage {
+    backend: Arc<LocalStorage>,
+    _dir: TempDir,
+}
+
+impl TestStorage {
+    /// Open an empty backend in a temporary directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created or opened.
+    pub(super) fn create() -> Result<Self, AnyError> {
+        let dir = tempfile::tempdir()?;
+        let root = dir
+            .path()
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("temp dir is not UTF-8"))?;
+        let backend = Arc::new(LocalStorage::open(root)?);
+        Ok(Self { backend, _dir: dir })
+    }
+
+    /// The backend.
+    pub(super) fn backend(&self) -> &LocalStorage { &self.backend }
+
+    /// The backend as a server's settings hold it.
+    pub(super) fn shared(&self) -> Arc<dyn Storage> { Arc::clone(&self.backend) }
+}
+
 /// Test harness context that bundles routing state for wireframe handlers.
 pub(super) struct RouteTestContext {
     /// Database pool passed into routing so handlers can query state.
@@ -35,6 +77,8 @@ pub(super) struct RouteTestContext {
     /// Shared presence registry used during routing tests.
     presence: PresenceRegistry,
     presence_connection_id: OutboundConnectionId,
+    /// Settings of the server the requests are routed through.
+    pub(super) settings: ServerSettings,
 }
 
 impl RouteTestContext {
@@ -48,21 +92,43 @@ impl RouteTestContext {
     ///
     /// Returns an error if the fixed peer address literal fails to parse.
     pub(super) fn new(pool: DbPool) -> Result<Self, AnyError> {
+        Self::with_handshake(pool, &HandshakeMetadata::default())
+    }
+
+    /// Create a routing context whose client compatibility is seeded from
+    /// `handshake`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fixed peer address literal fails to parse.
+    pub(super) fn with_handshake(
+        pool: DbPool,
+        handshake: &HandshakeMetadata,
+    ) -> Result<Self, AnyError> {
         let peer = "127.0.0.1:12345".parse()?;
         let router = WireframeRouter::new(
             Arc::new(XorCompatibility::disabled()),
-            Arc::new(ClientCompatibility::from_handshake(
-                &crate::wireframe::connection::HandshakeMetadata::default(),
-            )),
+            Arc::new(ClientCompatibility::from_handshake(handshake)),
         );
-        Ok(Self {
+        let context = Self {
             pool,
             session: Session::default(),
             peer,
             router,
             presence: PresenceRegistry::default(),
             presence_connection_id: OutboundConnectionId::new(1),
-        })
+            settings: ServerSettings::default(),
+        };
+        context.attach(context.presence_connection_id);
+        Ok(context)
+    }
+
+    /// Attach the test peer's address and a fresh activity clock to
+    /// `connection_id`, as the runtimes do on accept.
+    fn attach(&self, connection_id: OutboundConnectionId) {
+        let activity = Arc::new(ActivityClock::new());
+        self.presence
+            .attach_connection(connection_id, self.peer.ip(), activity);
     }
 
     /// Authenticate the session with default user privileges.
@@ -89,8 +155,43 @@ impl RouteTestContext {
         self.refresh_presence(self.presence_connection_id);
     }
 
+    /// Put another online session for `account_id` into the presence
+    /// registry, returning the user id clients see for it.
+    pub(super) fn add_peer(
+        &self,
+        connection_id: u64,
+        account_id: i32,
+        cannot_be_disconnected: bool,
+    ) -> i32 {
+        let snapshot = PresenceSnapshot {
+            connection_id: OutboundConnectionId::new(connection_id),
+            user_id: 0,
+            account_id,
+            display_name: format!("peer-{connection_id}"),
+            icon_id: 0,
+            status_flags: 0,
+            cannot_be_disconnected,
+            auto_response: None,
+        };
+        match self.presence.upsert(snapshot) {
+            Ok(upsert) => upsert.snapshot.user_id,
+            Err(error) => panic!("upsert peer presence: {error}"),
+        }
+    }
+
+    /// Record `address` as the peer address of connection `connection_id`.
+    pub(super) fn attach_peer_address(&self, connection_id: u64, address: IpAddr) {
+        let activity = Arc::new(ActivityClock::new());
+        self.presence.attach_connection(
+            OutboundConnectionId::new(connection_id),
+            address,
+            activity,
+        );
+    }
+
     fn refresh_presence(&self, connection_id: OutboundConnectionId) {
         let _ = self.presence.remove(connection_id);
+        self.attach(connection_id);
         if let Some(snapshot) = self.session.presence_snapshot(connection_id)
             && let Err(error) = self.presence.upsert(snapshot)
         {
@@ -129,6 +230,7 @@ impl RouteTestContext {
                     messaging: &messaging,
                     presence: &self.presence,
                     presence_connection_id: self.presence_connection_id,
+                    settings: &self.settings,
                 },
             )
             .await;
@@ -137,6 +239,14 @@ impl RouteTestContext {
 
     /// Access the XOR compatibility state for assertions.
     pub(super) fn xor(&self) -> &XorCompatibility { self.router.xor() }
+
+    /// Bytes the test connection's tally has counted this login.
+    pub(super) fn transferred(&self) -> SessionBytes {
+        self.presence
+            .connection_details(self.presence_connection_id)
+            .map(|details| details.transfers.session_bytes())
+            .unwrap_or_default()
+    }
 }
 
 /// Build a single-threaded Tokio runtime with all features enabled.
@@ -205,3 +315,38 @@ pub(super) fn find_i32(params: &[(FieldId, Vec<u8>)], field_id: FieldId) -> Resu
     let raw: [u8; 4] = bytes.try_into()?;
     Ok(i32::from_be_bytes(raw))
 }
+
+/// Encode folder names, outermost first, as a field 202 path.
+///
+/// # Errors
+///
+/// Returns an error if a name is too long to encode.
+pub(super) fn folder_path(segments: &[&str]) -> Result<Vec<u8>, AnyError> {
+    encode_file_path(segments).ok_or_else(|| anyhow::anyhow!("path does not encode"))
+}
+
+/// Issue `account_id` a session token that stays valid for an hour.
+///
+/// # Errors
+///
+/// Returns an error if a connection cannot be acquired or the insert fails.
+pub(super) async fn issue_token(pool: &DbPool, account_id: i32) -> Result<String, AnyError> {
+    let mut conn = pool.get().await?;
+    let now = Utc::now().naive_utc();
+    Ok(issue_session_token(&mut conn, account_id, now, now + TimeDelta::hours(1)).await?)
+}
+
+/// Whether `token` still stands in for `account_id`'s password, using it up
+/// if so.
+///
+/// # Errors
+///
+/// Returns an error if a connection cannot be acquired or the query fails.
+pub(super) async fn token_redeems(
+    pool: &DbPool,
+    account_id: i32,
+    token: &str,
+) -> Result<bool, AnyError> {
+    let mut conn = pool.get().await?;
+    Ok(redeem_session_token(&mut conn, account_id, token, Utc::now().naive_utc()).await?)
+}
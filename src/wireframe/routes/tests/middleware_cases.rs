@@ -3,6 +3,7 @@
 use std::{
     collections::HashMap,
     net::SocketAddr,
+    num::NonZeroU32,
     sync::{
         Arc,
         atomic::{AtomicUsize, Ordering},
@@ -21,11 +22,17 @@ use super::{
     helpers::{build_frame, runtime},
 };
 use crate::{
+    commands::ERR_RATE_LIMITED,
     db::DbPool,
     field_id::FieldId,
     handler::Session,
     presence::PresenceRegistry,
-    server::outbound::{NoopOutboundMessaging, OutboundConnectionId},
+    server::{
+        idle::ActivityClock,
+        outbound::{NoopOutboundMessaging, OutboundConnectionId},
+        rate_limit::{RateLimit, RateLimitPolicy, RateLimiter},
+        settings::ServerSettings,
+    },
     transaction::parse_transaction,
     transaction_type::TransactionType,
     wireframe::{
@@ -33,6 +40,7 @@ use crate::{
         compat_policy::ClientCompatibility,
         connection::HandshakeMetadata,
         router::WireframeRouter,
+        test_helpers::dummy_pool,
     },
 };
 
@@ -44,6 +52,20 @@ fn setup_middleware_test(
     impl Service<Error: Into<AnyError>>,
     SocketAddr,
     Arc<AtomicUsize>,
+) {
+    setup_rate_limited_middleware_test(rt, pool, RateLimitPolicy::OFF)
+}
+
+/// Build the wrapped middleware service, peer address, and call counter for
+/// a server applying `rate_limits`.
+fn setup_rate_limited_middleware_test(
+    rt: &Runtime,
+    pool: DbPool,
+    rate_limits: RateLimitPolicy,
+) -> (
+    impl Service<Error: Into<AnyError>>,
+    SocketAddr,
+    Arc<AtomicUsize>,
 ) {
     let session = Arc::new(tokio::sync::Mutex::new(Session::default()));
     let peer: SocketAddr = match "127.0.0.1:12345".parse() {
@@ -66,6 +88,13 @@ fn setup_middleware_test(
         messaging,
         presence,
         presence_connection_id: OutboundConnectionId::new(1),
+        activity: Arc::new(ActivityClock::new()),
+        pings: Arc::default(),
+        settings: Arc::new(ServerSettings {
+            rate_limits: Arc::new(RateLimiter::new(rate_limits)),
+            ..ServerSettings::default()
+        }),
+        slot: None,
     });
 
     let calls = Arc::new(AtomicUsize::new(0));
@@ -173,6 +202,41 @@ fn transaction_middleware_routes_known_types() -> Result<(), AnyError> {
     Ok(())
 }
 
+#[rstest]
+fn transaction_middleware_refuses_requests_over_the_rate_limit() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let one_a_second = RateLimit {
+        per_sec: NonZeroU32::MIN,
+        burst: NonZeroU32::MIN,
+    };
+    let policy = RateLimitPolicy {
+        connection: Some(one_a_second),
+        address: None,
+    };
+    let (wrapped, _peer, calls) = setup_rate_limited_middleware_test(&rt, dummy_pool(), policy);
+
+    let replies: Result<Vec<_>, AnyError> = [901_101, 901_102]
+        .into_iter()
+        .map(|id| {
+            let frame = build_frame(TransactionType::KeepAlive, id, &[])?;
+            let response = rt.block_on(wrapped.call(ServiceRequest::new(frame, None)))?;
+            Ok(parse_transaction(response.frame())?)
+        })
+        .collect();
+
+    let replies = replies?;
+    assert_eq!(
+        replies
+            .iter()
+            .map(|reply| reply.header.error)
+            .collect::<Vec<_>>(),
+        [0, ERR_RATE_LIMITED]
+    );
+    assert_eq!(replies.last().map(|reply| reply.header.id), Some(901_102));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    Ok(())
+}
+
 struct MiddlewareCase {
     label: &'static str,
     ty: TransactionType,
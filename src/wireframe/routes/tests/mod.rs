@@ -1,7 +1,18 @@
 //! Unit tests for wireframe transaction routing.
 
+mod account_cases;
+mod disconnect_user_cases;
 mod error_cases;
+mod file_change_cases;
+mod file_info_cases;
+mod file_list_cases;
+mod file_transfer_cases;
 mod helpers;
 mod middleware_cases;
+mod news_delete_cases;
+mod news_listing_cases;
+mod news_structure_cases;
+mod news_transfer_cases;
 mod presence_routing_cases;
 mod routing_cases;
+mod search_cases;
@@ -0,0 +1,102 @@
+//! Unit tests covering Delete News Article routing.
+#![expect(clippy::big_endian_bytes, reason = "network protocol")]
+
+use rstest::rstest;
+use test_util::{AnyError, build_test_db, setup_news_db};
+
+use super::helpers::{RouteTestContext, collect_strings, decode_reply_params, runtime};
+use crate::{
+    commands::{ERR_INSUFFICIENT_PRIVILEGES, NEWS_ERR_ARTICLE_NOT_FOUND},
+    field_id::FieldId,
+    privileges::Privileges,
+    transaction_type::TransactionType,
+};
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn delete_news_article_requires_privilege() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::default_user());
+
+    let article_id = 2i32.to_be_bytes();
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteNewsArticle,
+        70,
+        &[
+            (FieldId::NewsPath, b"General"),
+            (FieldId::NewsArticleId, article_id.as_ref()),
+        ],
+    ))?;
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn delete_news_article_removes_article_from_listing() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(
+        1,
+        Privileges::default_user() | Privileges::NEWS_DELETE_ARTICLE,
+    );
+
+    let article_id = 2i32.to_be_bytes();
+    let recursive = 1u32.to_be_bytes();
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteNewsArticle,
+        71,
+        &[
+            (FieldId::NewsPath, b"General"),
+            (FieldId::NewsArticleId, article_id.as_ref()),
+            (FieldId::NewsRecursiveDelete, recursive.as_ref()),
+        ],
+    ))?;
+    assert_eq!(reply.header.error, 0);
+    assert_eq!(reply.header.id, 71);
+
+    let listing = rt.block_on(ctx.send(
+        TransactionType::NewsArticleNameList,
+        72,
+        &[(FieldId::NewsPath, b"General")],
+    ))?;
+    let params = decode_reply_params(&listing)?;
+    assert_eq!(
+        collect_strings(&params, FieldId::NewsArticle)?,
+        vec!["First"]
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn delete_news_article_reports_missing_article() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(
+        1,
+        Privileges::default_user() | Privileges::NEWS_DELETE_ARTICLE,
+    );
+
+    let article_id = 999i32.to_be_bytes();
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteNewsArticle,
+        73,
+        &[
+            (FieldId::NewsPath, b"General"),
+            (FieldId::NewsArticleId, article_id.as_ref()),
+        ],
+    ))?;
+    assert_eq!(reply.header.error, NEWS_ERR_ARTICLE_NOT_FOUND);
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! Layout tests for client-specific news category listing encodings.
+//!
+//! Each case logs in as a particular client family, lists the news root seeded
+//! by `setup_news_categories_root_db` (bundle `Bundle`, categories `General`
+//! and `Updates`), and compares the reply payload byte for byte with the
+//! layout expected by that client. Hotline 1.8.5 and later layouts also
+//! carry per-entry item counts. Post times travel in field 172, and only to
+//! clients that ask for them.
+//!
+//! These payloads are written out by hand from the entry layouts in
+//! `docs/protocol.md`; they are not recordings of real client sessions, so
+//! they pin mxd's encoder but cannot prove a client accepts it. Replace each
+//! with bytes recorded from a real session of that client family once one is
+//! available, and note in its doc comment where the recording came from.
+
+use rstest::rstest;
+use test_util::{AnyError, build_test_db, setup_news_categories_root_db, setup_news_db};
+
+use super::helpers::{RouteTestContext, runtime};
+use crate::{
+    field_id::FieldId,
+    transaction_type::TransactionType,
+    wireframe::connection::HandshakeMetadata,
+};
+
+const SYNHX_SUB_VERSION: u16 = 2;
+
+/// `SynHX` and unidentified clients: bare names.
+const NAMES_LAYOUT: &[u8] = b"\x00\x03\
+\x01\x43\x00\x06Bundle\
+\x01\x43\x00\x07General\
+\x01\x43\x00\x07Updates";
+
+/// Pre-1.8.5 Hotline clients: one type byte ahead of each name.
+const HOTLINE_15_LAYOUT: &[u8] = b"\x00\x03\
+\x01\x43\x00\x07\x02Bundle\
+\x01\x43\x00\x08\x03General\
+\x01\x43\x00\x08\x03Updates";
+
+/// Hotline 1.8.5 and 1.9 clients: type code, item count, and sized name.
+const HOTLINE_18_LAYOUT: &[u8] = b"\x00\x03\
+\x01\x43\x00\x0b\x00\x02\x00\x00\x06Bundle\
+\x01\x43\x00\x0c\x00\x03\x00\x00\x07General\
+\x01\x43\x00\x0c\x00\x03\x00\x00\x07Updates";
+
+#[expect(clippy::big_endian_bytes, reason = "network protocol")]
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::synhx(SYNHX_SUB_VERSION, Some(190), NAMES_LAYOUT)]
+#[case::no_login_version(0, None, NAMES_LAYOUT)]
+#[case::hotline_15(0, Some(150), HOTLINE_15_LAYOUT)]
+#[case::hotline_85(0, Some(151), HOTLINE_18_LAYOUT)]
+#[case::hotline_19(0, Some(190), HOTLINE_18_LAYOUT)]
+fn news_category_listing_matches_client_layout(
+    #[case] sub_version: u16,
+    #[case] login_version: Option<u16>,
+    #[case] layout: &[u8],
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_categories_root_db)? else {
+        return Ok(());
+    };
+    let handshake = HandshakeMetadata {
+        sub_version,
+        ..HandshakeMetadata::default()
+    };
+    let mut ctx = RouteTestContext::with_handshake(test_db.pool(), &handshake)?;
+    let version_bytes = login_version.map(u16::to_be_bytes);
+    let mut login_fields: Vec<(FieldId, &[u8])> = vec![
+        (FieldId::Login, b"alice".as_slice()),
+        (FieldId::Password, b"secret".as_slice()),
+    ];
+    if let Some(bytes) = version_bytes.as_ref() {
+        login_fields.push((FieldId::Version, bytes.as_slice()));
+    }
+
+    let login = rt.block_on(ctx.send(TransactionType::Login, 1, &login_fields))?;
+    assert_eq!(login.header.error, 0);
+
+    let reply = rt.block_on(ctx.send(TransactionType::NewsCategoryNameList, 2, &[]))?;
+    assert_eq!(reply.header.error, 0);
+    assert_eq!(reply.payload, layout);
+    Ok(())
+}
+
+#[expect(clippy::big_endian_bytes, reason = "network protocol")]
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::not_asked(
+    &[],
+    b"\x00\x01\x01\x43\x00\x0c\x00\x03\x00\x02\x07General".as_slice()
+)]
+#[case::asked(
+    &[(FieldId::NewsActivity, &[0, 1][..])],
+    b"\x00\x02\x01\x43\x00\x0c\x00\x03\x00\x02\x07General\
+\x00\xac\x00\x08\x00\x00\x00\x00\x00\x1e\x84\x80"
+        .as_slice()
+)]
+fn hotline_18_listing_reports_category_activity_on_request(
+    #[case] request: &[(FieldId, &[u8])],
+    #[case] expected: &[u8],
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    let version = 190u16.to_be_bytes();
+    let login = rt.block_on(ctx.send(
+        TransactionType::Login,
+        1,
+        &[
+            (FieldId::Login, b"alice"),
+            (FieldId::Password, b"secret"),
+            (FieldId::Version, version.as_ref()),
+        ],
+    ))?;
+    assert_eq!(login.header.error, 0);
+
+    let reply = rt.block_on(ctx.send(TransactionType::NewsCategoryNameList, 2, request))?;
+    assert_eq!(reply.header.error, 0);
+    assert_eq!(reply.payload, expected);
+    Ok(())
+}
@@ -0,0 +1,113 @@
+//! Unit tests covering news bundle and category management routing.
+
+use rstest::rstest;
+use test_util::{AnyError, build_test_db, setup_news_db};
+
+use super::helpers::{RouteTestContext, collect_strings, decode_reply_params, runtime};
+use crate::{
+    commands::{ERR_INSUFFICIENT_PRIVILEGES, NEWS_ERR_NAME_TAKEN, NEWS_ERR_PATH_NOT_FOUND},
+    field_id::FieldId,
+    privileges::Privileges,
+    transaction_type::TransactionType,
+};
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn new_news_folder_requires_privilege() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::default_user());
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::NewNewsFolder,
+        80,
+        &[(FieldId::FileItemName, b"Archive")],
+    ))?;
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn new_folder_and_category_appear_in_listing() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(
+        1,
+        Privileges::default_user()
+            | Privileges::NEWS_CREATE_FOLDER
+            | Privileges::NEWS_CREATE_CATEGORY,
+    );
+
+    let folder = rt.block_on(ctx.send(
+        TransactionType::NewNewsFolder,
+        81,
+        &[(FieldId::FileItemName, b"Archive")],
+    ))?;
+    assert_eq!(folder.header.error, 0);
+    let category = rt.block_on(ctx.send(
+        TransactionType::NewNewsCategory,
+        82,
+        &[
+            (FieldId::NewsPath, b"Archive"),
+            (FieldId::NewsCategoryName, b"Old"),
+        ],
+    ))?;
+    assert_eq!(category.header.error, 0);
+    let duplicate = rt.block_on(ctx.send(
+        TransactionType::NewNewsCategory,
+        83,
+        &[(FieldId::NewsCategoryName, b"General")],
+    ))?;
+    assert_eq!(duplicate.header.error, NEWS_ERR_NAME_TAKEN);
+
+    let listing = rt.block_on(ctx.send(
+        TransactionType::NewsCategoryNameList,
+        84,
+        &[(FieldId::NewsPath, b"Archive")],
+    ))?;
+    let params = decode_reply_params(&listing)?;
+    assert_eq!(
+        collect_strings(&params, FieldId::NewsCategory)?,
+        vec!["Old"]
+    );
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::folder_privilege_only(Privileges::NEWS_DELETE_FOLDER, ERR_INSUFFICIENT_PRIVILEGES)]
+#[case::category_privilege(Privileges::NEWS_DELETE_CATEGORY, 0)]
+fn delete_news_item_checks_privilege_for_item_kind(
+    #[case] granted: Privileges,
+    #[case] expected: u32,
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::default_user() | granted);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::DeleteNewsItem,
+        85,
+        &[(FieldId::NewsPath, b"General")],
+    ))?;
+    assert_eq!(reply.header.error, expected);
+    if expected == 0 {
+        let listing = rt.block_on(ctx.send(
+            TransactionType::NewsArticleNameList,
+            86,
+            &[(FieldId::NewsPath, b"General")],
+        ))?;
+        assert_eq!(listing.header.error, NEWS_ERR_PATH_NOT_FOUND);
+    }
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Unit tests covering article bodies sent over the transfer port.
+#![expect(clippy::big_endian_bytes, reason = "network protocol")]
+
+use std::time::Instant;
+
+use rstest::rstest;
+use test_util::{AnyError, build_test_db, setup_news_db};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, duplex};
+
+use super::helpers::{RouteTestContext, decode_reply_params, find_i32, find_string, runtime};
+use crate::{
+    field_id::FieldId,
+    server::transfer_port::{HTXF_MAGIC, TransferKind, serve_transfer},
+    transaction_type::TransactionType,
+};
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn long_article_bodies_move_to_the_transfer_port() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.settings.news_transfer_threshold = Some(0);
+    ctx.authenticate(1);
+
+    let article_id = 1i32.to_be_bytes();
+    let flag = 1i16.to_be_bytes();
+    let reply = rt.block_on(ctx.send(
+        TransactionType::NewsArticleData,
+        80,
+        &[
+            (FieldId::NewsPath, b"General"),
+            (FieldId::NewsArticleId, article_id.as_ref()),
+            (FieldId::NewsDataTransfer, flag.as_ref()),
+        ],
+    ))?;
+    assert_eq!(reply.header.error, 0);
+
+    let params = decode_reply_params(&reply)?;
+    assert_eq!(find_string(&params, FieldId::NewsTitle)?, "First");
+    assert!(find_string(&params, FieldId::NewsArticleData).is_err());
+    assert_eq!(find_i32(&params, FieldId::TransferSize)?, 1);
+    let reference = find_i32(&params, FieldId::ReferenceNumber)?.cast_unsigned();
+
+    let (kind, received) = rt.block_on(async {
+        let (mut client, mut server) = duplex(64);
+        let mut handshake = HTXF_MAGIC.to_vec();
+        handshake.extend_from_slice(&reference.to_be_bytes());
+        handshake.extend_from_slice(&[0; 8]);
+        client.write_all(&handshake).await?;
+        let served = serve_transfer(&mut server, &ctx.settings.transfers, Instant::now()).await?;
+        let mut body = Vec::new();
+        client.read_to_end(&mut body).await?;
+        Ok::<_, AnyError>((served, body))
+    })?;
+    assert_eq!(kind, TransferKind::ArticleData);
+    assert_eq!(received, b"a");
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn article_bodies_stay_inline_unless_requested() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_news_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.settings.news_transfer_threshold = Some(0);
+    ctx.authenticate(1);
+
+    let article_id = 1i32.to_be_bytes();
+    let reply = rt.block_on(ctx.send(
+        TransactionType::NewsArticleData,
+        81,
+        &[
+            (FieldId::NewsPath, b"General"),
+            (FieldId::NewsArticleId, article_id.as_ref()),
+        ],
+    ))?;
+    assert_eq!(reply.header.error, 0);
+
+    let params = decode_reply_params(&reply)?;
+    assert_eq!(find_string(&params, FieldId::NewsArticleData)?, "a");
+    assert!(find_i32(&params, FieldId::ReferenceNumber).is_err());
+    Ok(())
+}
@@ -3,7 +3,14 @@
 use rstest::rstest;
 use test_util::{AnyError, build_test_db, setup_files_db};
 
-use super::helpers::{RouteTestContext, decode_reply_params, find_string, runtime};
+use super::helpers::{
+    RouteTestContext,
+    decode_reply_params,
+    find_string,
+    issue_token,
+    runtime,
+    token_redeems,
+};
 use crate::{field_id::FieldId, privileges::Privileges, transaction_type::TransactionType};
 
 fn decode_user_name_with_info(payload: &[u8]) -> Result<(u16, u16, u16, String), AnyError> {
@@ -49,8 +56,7 @@ fn process_transaction_bytes_user_name_list_returns_online_snapshot() -> Result<
 #[expect(clippy::big_endian_bytes, reason = "network protocol")]
 #[expect(clippy::panic_in_result_fn, reason = "test assertions")]
 #[rstest]
-fn process_transaction_bytes_client_info_text_returns_name_and_blank_info() -> Result<(), AnyError>
-{
+fn process_transaction_bytes_client_info_text_reports_connection_details() -> Result<(), AnyError> {
     let rt = runtime()?;
     let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
         return Ok(());
@@ -72,7 +78,14 @@ fn process_transaction_bytes_client_info_text_returns_name_and_blank_info() -> R
     assert_eq!(reply.header.error, 0);
     let params = decode_reply_params(&reply)?;
     assert_eq!(find_string(&params, FieldId::Name)?, "alice");
-    assert_eq!(find_string(&params, FieldId::Data)?, "");
+    let info = find_string(&params, FieldId::Data)?;
+    let lines: Vec<&str> = info.split('\r').collect();
+    assert_eq!(lines.first(), Some(&"Name: alice"));
+    assert!(lines.contains(&"Address: 127.0.0.1"));
+    assert!(lines.iter().any(|line| line.starts_with("Logged in: ")));
+    assert!(lines.iter().any(|line| line.starts_with("Idle: 0:00:")));
+    assert!(lines.contains(&"Downloaded: 0 bytes (0 files)"));
+    assert_eq!(lines.last(), Some(&"Transfers: none"));
     Ok(())
 }
 
@@ -126,7 +139,7 @@ fn process_transaction_bytes_client_info_text_accepts_mixed_width_user_id_encodi
     assert_eq!(reply_16.header.error, 0);
     let params_16 = decode_reply_params(&reply_16)?;
     assert_eq!(find_string(&params_16, FieldId::Name)?, "alice");
-    assert_eq!(find_string(&params_16, FieldId::Data)?, "");
+    assert!(find_string(&params_16, FieldId::Data)?.starts_with("Name: alice\r"));
 
     let target_user_id_u32 = 1u32.to_be_bytes();
     let reply_32 = rt.block_on(ctx.send(
@@ -137,7 +150,7 @@ fn process_transaction_bytes_client_info_text_accepts_mixed_width_user_id_encodi
     assert_eq!(reply_32.header.error, 0);
     let params_32 = decode_reply_params(&reply_32)?;
     assert_eq!(find_string(&params_32, FieldId::Name)?, "alice");
-    assert_eq!(find_string(&params_32, FieldId::Data)?, "");
+    assert!(find_string(&params_32, FieldId::Data)?.starts_with("Name: alice\r"));
     Ok(())
 }
 
@@ -191,6 +204,38 @@ fn process_transaction_bytes_set_client_user_info_updates_future_user_list_views
     Ok(())
 }
 
+#[expect(clippy::big_endian_bytes, reason = "network protocol")]
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::blank(b"  ".as_slice(), "user-1")]
+#[case::control_characters(b"\r  Mallory\n".as_slice(), "Mallory")]
+fn process_transaction_bytes_set_client_user_info_normalises_nickname(
+    #[case] requested: &[u8],
+    #[case] expected: &str,
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let icon_id = 12u16.to_be_bytes();
+    let update = rt.block_on(ctx.send(
+        TransactionType::SetClientUserInfo,
+        28,
+        &[
+            (FieldId::Name, requested),
+            (FieldId::IconId, icon_id.as_ref()),
+        ],
+    ))?;
+
+    assert_eq!(update.header.error, 0);
+    assert_eq!(ctx.session.display_name, expected);
+    assert_eq!(ctx.session.icon_id, 12);
+    Ok(())
+}
+
 #[expect(clippy::panic_in_result_fn, reason = "test assertions")]
 #[rstest]
 fn process_transaction_bytes_user_name_list_requires_online_session() -> Result<(), AnyError> {
@@ -205,3 +250,151 @@ fn process_transaction_bytes_user_name_list_requires_online_session() -> Result<
     assert_eq!(reply.header.error, crate::commands::ERR_NOT_AUTHENTICATED);
     Ok(())
 }
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_send_chat_acknowledges_online_sender() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    let login = rt.block_on(ctx.send(
+        TransactionType::Login,
+        28,
+        &[(FieldId::Login, b"alice"), (FieldId::Password, b"secret")],
+    ))?;
+    assert_eq!(login.header.error, 0);
+
+    let reply = rt.block_on(ctx.send(TransactionType::SendChat, 29, &[(FieldId::Data, b"hi")]))?;
+
+    assert_eq!(reply.header.error, 0);
+    assert!(reply.payload.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_send_chat_requires_online_session() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+
+    let reply = rt.block_on(ctx.send(TransactionType::SendChat, 30, &[(FieldId::Data, b"hi")]))?;
+
+    assert_eq!(reply.header.error, crate::commands::ERR_NOT_AUTHENTICATED);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_instant_msg_rejects_offline_recipient() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    let login = rt.block_on(ctx.send(
+        TransactionType::Login,
+        31,
+        &[(FieldId::Login, b"alice"), (FieldId::Password, b"secret")],
+    ))?;
+    assert_eq!(login.header.error, 0);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::SendInstantMsg,
+        32,
+        &[(FieldId::UserId, &[0, 99]), (FieldId::Data, b"hi")],
+    ))?;
+
+    assert_eq!(reply.header.error, crate::commands::ERR_USER_NOT_ONLINE);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_instant_msg_requires_online_session() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::SendInstantMsg,
+        33,
+        &[(FieldId::UserId, &[0, 1]), (FieldId::Data, b"hi")],
+    ))?;
+
+    assert_eq!(reply.header.error, crate::commands::ERR_NOT_AUTHENTICATED);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case(
+    Privileges::default_user(),
+    crate::commands::ERR_INSUFFICIENT_PRIVILEGES
+)]
+#[case(Privileges::default_user() | Privileges::BROADCAST, 0)]
+fn process_transaction_bytes_broadcast_requires_broadcast_privilege(
+    #[case] privileges: Privileges,
+    #[case] expected_error: u32,
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, privileges);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::UserBroadcast,
+        34,
+        &[(FieldId::Data, b"Restarting at noon")],
+    ))?;
+
+    assert_eq!(reply.header.error, expected_error);
+    assert!(reply.payload.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_broadcast_requires_online_session() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::UserBroadcast,
+        35,
+        &[(FieldId::Data, b"Restarting at noon")],
+    ))?;
+
+    assert_eq!(reply.header.error, crate::commands::ERR_NOT_AUTHENTICATED);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_logout_revokes_session_tokens() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let token = rt.block_on(issue_token(&test_db.pool(), 1))?;
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let reply = rt.block_on(ctx.send(TransactionType::Logout, 36, &[]))?;
+
+    assert_eq!(reply.header.error, 0);
+    assert_eq!(ctx.session.user_id, None);
+    assert!(!rt.block_on(token_redeems(&test_db.pool(), 1, &token))?);
+    Ok(())
+}
@@ -21,6 +21,7 @@ use crate::{
     field_id::FieldId,
     privileges::Privileges,
     transaction_type::TransactionType,
+    wire_time::decode_timestamp,
     wireframe::test_helpers::xor_bytes,
 };
 
@@ -73,6 +74,37 @@ fn process_transaction_bytes_login_hotline_19_includes_banner_fields() -> Result
     Ok(())
 }
 
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_login_reports_server_clock() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_files_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    let before = chrono::Utc::now().naive_utc();
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::Login,
+        3,
+        &[(FieldId::Login, b"alice"), (FieldId::Password, b"secret")],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    let server_time = params
+        .iter()
+        .find(|(id, _)| *id == FieldId::ServerTime)
+        .and_then(|(_, data)| decode_timestamp(data))
+        .ok_or_else(|| anyhow::anyhow!("missing server time"))?;
+    let after = chrono::Utc::now().naive_utc();
+    let tolerance = chrono::TimeDelta::milliseconds(1);
+    assert!(server_time + tolerance >= before && server_time <= after + tolerance);
+    let offset = find_i32(&params, FieldId::ServerUtcOffset)?;
+    assert_eq!(offset, chrono::Local::now().offset().local_minus_utc());
+    Ok(())
+}
+
 #[expect(clippy::panic_in_result_fn, reason = "test assertions")]
 #[rstest]
 fn process_transaction_bytes_login_success_with_xor_password() -> Result<(), AnyError> {
@@ -0,0 +1,158 @@
+//! Unit tests covering Search routing across files and news.
+
+use rstest::rstest;
+use test_util::{
+    AnyError,
+    build_test_db,
+    setup_drop_box_db,
+    setup_file_tree_db,
+    setup_news_with_article,
+};
+
+use super::helpers::{RouteTestContext, decode_reply_params, runtime};
+use crate::{
+    commands::{ERR_INSUFFICIENT_PRIVILEGES, SearchResultKind, SearchScope, encode_search_result},
+    field_id::FieldId,
+    privileges::Privileges,
+    transaction_type::TransactionType,
+};
+
+fn setup_article(db: test_util::DatabaseUrl) -> Result<(), AnyError> {
+    setup_news_with_article(db).map(drop)
+}
+
+fn result_entries(params: &[(FieldId, Vec<u8>)]) -> Vec<Vec<u8>> {
+    params
+        .iter()
+        .filter(|(id, _)| *id == FieldId::SearchResult)
+        .map(|(_, value)| value.clone())
+        .collect()
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_search_returns_only_reachable_files() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let scope = SearchScope::FILES.bits().to_be_bytes();
+    let reply = rt.block_on(ctx.send(
+        TransactionType::Search,
+        60,
+        &[
+            (FieldId::Data, b"TXT".as_slice()),
+            (FieldId::Options, &scope),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    let expected = [("", "fileA.txt"), ("", "fileC.txt"), ("Docs", "guide.txt")]
+        .into_iter()
+        .map(|(folder, name)| encode_search_result(SearchResultKind::File, 0, folder, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(result_entries(&params), expected);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_search_finds_articles_by_body() -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_article)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate(1);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::Search,
+        61,
+        &[(FieldId::Data, b"Hello".as_slice())],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    let entries = result_entries(&params);
+    let [entry] = entries.as_slice() else {
+        panic!("expected one result, got {}", entries.len());
+    };
+    // The fixture picks the article id, so compare everything around it.
+    let expected = encode_search_result(SearchResultKind::Article, 0, "General", "First")?;
+    assert_eq!(entry.get(..2), expected.get(..2));
+    assert_ne!(entry.get(2..6), Some([0u8; 4].as_slice()));
+    assert_eq!(entry.get(6..), expected.get(6..));
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn process_transaction_bytes_search_refuses_sessions_without_read_privileges()
+-> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_file_tree_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, Privileges::SEND_CHAT);
+
+    let reply = rt.block_on(ctx.send(
+        TransactionType::Search,
+        62,
+        &[(FieldId::Data, b"txt".as_slice())],
+    ))?;
+
+    assert_eq!(reply.header.error, ERR_INSUFFICIENT_PRIVILEGES);
+    assert!(reply.payload.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+#[case::hidden_file(Privileges::default_user(), "deposit", &[])]
+#[case::hidden_folder(Privileges::default_user(), "inbox", &[])]
+#[case::drop_box_itself(
+    Privileges::default_user(),
+    "uploads",
+    &[(SearchResultKind::Folder, "", "Uploads")]
+)]
+#[case::viewer(
+    Privileges::default_user() | Privileges::VIEW_DROP_BOXES,
+    "deposit",
+    &[(SearchResultKind::File, "Uploads", "deposit.txt")]
+)]
+fn process_transaction_bytes_search_hides_drop_box_contents(
+    #[case] privileges: Privileges,
+    #[case] query: &str,
+    #[case] expected: &[(SearchResultKind, &str, &str)],
+) -> Result<(), AnyError> {
+    let rt = runtime()?;
+    let Some(test_db) = build_test_db(&rt, setup_drop_box_db)? else {
+        return Ok(());
+    };
+    let mut ctx = RouteTestContext::new(test_db.pool())?;
+    ctx.authenticate_with_privileges(1, privileges);
+
+    let scope = SearchScope::FILES.bits().to_be_bytes();
+    let reply = rt.block_on(ctx.send(
+        TransactionType::Search,
+        63,
+        &[
+            (FieldId::Data, query.as_bytes()),
+            (FieldId::Options, &scope),
+        ],
+    ))?;
+
+    assert_eq!(reply.header.error, 0);
+    let params = decode_reply_params(&reply)?;
+    let hits = expected
+        .iter()
+        .map(|&(kind, folder, name)| encode_search_result(kind, 0, folder, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(result_entries(&params), hits);
+    Ok(())
+}
@@ -11,13 +11,19 @@
 use std::time::Duration;
 
 use diesel_async::pooled_connection::{AsyncDieselConnectionManager, bb8::Pool};
+pub use mxd_proto::test_support::{
+    FragmentError,
+    fragmented_transaction_bytes,
+    mismatched_continuation_bytes,
+    transaction_bytes,
+};
 use tokio::io::AsyncReadExt;
 
 use crate::{
     db::{DbConnection, DbPool},
     field_id::FieldId,
     protocol::{HANDSHAKE_LEN, REPLY_LEN},
-    transaction::{FrameHeader, HEADER_LEN, TransactionError, encode_params},
+    transaction::{FrameHeader, TransactionError, encode_params},
     transaction_type::TransactionType,
 };
 
@@ -72,20 +78,6 @@ pub async fn recv_reply(
     Ok(buf)
 }
 
-/// Build a transaction frame buffer from a header and payload.
-///
-/// The returned buffer contains the serialized 20-byte header followed by the
-/// payload bytes.
-#[must_use]
-pub fn transaction_bytes(header: &FrameHeader, payload: &[u8]) -> Vec<u8> {
-    let mut buf = Vec::with_capacity(HEADER_LEN + payload.len());
-    let mut hdr_buf = [0u8; HEADER_LEN];
-    header.write_bytes(&mut hdr_buf);
-    buf.extend_from_slice(&hdr_buf);
-    buf.extend_from_slice(payload);
-    buf
-}
-
 /// XOR each byte with `0xFF`, matching the legacy Hotline text obfuscation.
 ///
 /// # Examples
@@ -166,134 +158,6 @@ pub fn collect_strings(
         .collect()
 }
 
-/// Build fragmented transaction frames from a header and payload.
-///
-/// Each fragment contains a copy of the header with `data_size` adjusted to
-/// reflect the chunk size. The first fragment receives the initial portion of
-/// the payload, and subsequent fragments receive the remaining chunks.
-///
-/// # Arguments
-///
-/// * `header` - Base header (`total_size` should match payload length)
-///
-/// Errors returned by wireframe test helper builders.
-#[derive(Debug)]
-pub enum FragmentError {
-    /// Calculated slice bounds exceeded the payload length.
-    SliceOutOfBounds,
-    /// Fragment length could not be represented as `u32`.
-    Length(std::num::TryFromIntError),
-}
-
-impl From<std::num::TryFromIntError> for FragmentError {
-    fn from(err: std::num::TryFromIntError) -> Self { Self::Length(err) }
-}
-
-impl std::fmt::Display for FragmentError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::SliceOutOfBounds => write!(f, "payload slice bounds exceeded payload length"),
-            Self::Length(err) => write!(f, "fragment length overflow: {err}"),
-        }
-    }
-}
-
-impl std::error::Error for FragmentError {}
-
-/// * `payload` - Complete payload to fragment
-/// * `fragment_size` - Maximum data bytes per fragment
-///
-/// # Errors
-///
-/// Returns an error if any chunk length exceeds `u32::MAX` or if calculated
-/// slice bounds fall outside the payload.
-pub fn fragmented_transaction_bytes(
-    header: &FrameHeader,
-    payload: &[u8],
-    fragment_size: usize,
-) -> Result<Vec<Vec<u8>>, FragmentError> {
-    debug_assert_eq!(
-        header.total_size as usize,
-        payload.len(),
-        "header.total_size must match payload.len()"
-    );
-
-    let mut fragments = Vec::new();
-    let mut offset = 0usize;
-
-    while offset < payload.len() {
-        let end = (offset + fragment_size).min(payload.len());
-        let chunk = payload
-            .get(offset..end)
-            .ok_or(FragmentError::SliceOutOfBounds)?;
-
-        let mut frag_header = header.clone();
-        frag_header.data_size = u32::try_from(chunk.len())?;
-
-        fragments.push(transaction_bytes(&frag_header, chunk));
-        offset = end;
-    }
-
-    // Handle empty payload case
-    if fragments.is_empty() {
-        let mut frag_header = header.clone();
-        frag_header.data_size = 0;
-        fragments.push(transaction_bytes(&frag_header, &[]));
-    }
-
-    Ok(fragments)
-}
-
-/// Build fragmented transaction frames where the continuation header has a
-/// mismatched field.
-///
-/// Creates a two-fragment transaction where the second frame has a different
-/// transaction ID than the first, which should be rejected by the decoder.
-///
-/// # Errors
-///
-/// Returns an error if any chunk length exceeds `u32::MAX`.
-pub fn mismatched_continuation_bytes() -> Result<Vec<u8>, FragmentError> {
-    let total_size = 2000u32;
-    let first_chunk = 1000usize;
-
-    let payload = vec![0u8; total_size as usize];
-
-    // First fragment
-    let first_header = FrameHeader {
-        flags: 0,
-        is_reply: 0,
-        ty: 107,
-        id: 1,
-        error: 0,
-        total_size,
-        data_size: u32::try_from(first_chunk)?,
-    };
-
-    // Second fragment with mismatched ID
-    let second_header = FrameHeader {
-        flags: 0,
-        is_reply: 0,
-        ty: 107,
-        id: 999, // Different ID — this should trigger header mismatch
-        error: 0,
-        total_size,
-        data_size: u32::try_from(total_size as usize - first_chunk)?,
-    };
-
-    let first_slice = payload
-        .get(..first_chunk)
-        .ok_or(FragmentError::SliceOutOfBounds)?;
-    let second_slice = payload
-        .get(first_chunk..)
-        .ok_or(FragmentError::SliceOutOfBounds)?;
-
-    let mut bytes = transaction_bytes(&first_header, first_slice);
-    bytes.extend(transaction_bytes(&second_header, second_slice));
-
-    Ok(bytes)
-}
-
 #[cfg(test)]
 mod tests {
     //! Unit tests for wireframe test helpers.
@@ -301,6 +165,7 @@ mod tests {
     use rstest::rstest;
 
     use super::*;
+    use crate::transaction::HEADER_LEN;
 
     #[rstest]
     #[case::login(
@@ -0,0 +1,234 @@
+//! MacRoman transcoding for classic Hotline clients.
+//!
+//! The domain layer works in UTF-8, but classic Mac OS clients send and expect
+//! MacRoman text. Each connection's [`TextEncoding`] comes from
+//! [`crate::wireframe::compat_policy::ClientCompatibility::text_encoding`].
+//! For MacRoman connections, request hooks decode the plain-text fields of a
+//! request before `Command::from_transaction` parses it, and the protocol's
+//! `before_send` hook encodes every outbound frame, pushes included. Bytes
+//! with no MacRoman equivalent are sent as `?`.
+//!
+//! Only fields that hold nothing but text are transcoded, plus the name inside
+//! a User Name With Info (300) entry. Binary fields that embed names, such as
+//! news paths, are passed through unchanged.
+
+use crate::{
+    field_id::FieldId,
+    transaction::{TransactionError, decode_params, encode_params},
+};
+
+/// Byte sent for characters MacRoman cannot represent.
+const UNMAPPABLE: u8 = b'?';
+
+/// Bytes before the name in a User Name With Info (300) entry.
+const USER_ENTRY_HEADER_LEN: usize = 8;
+
+/// Characters for MacRoman bytes `0x80..=0xFF`, per Apple's mapping.
+const MAC_ROMAN_HIGH: [char; 128] = [
+    'Ä', 'Å', 'Ç', 'É', 'Ñ', 'Ö', 'Ü', 'á', 'à', 'â', 'ä', 'ã', 'å', 'ç', 'é', 'è', //
+    'ê', 'ë', 'í', 'ì', 'î', 'ï', 'ñ', 'ó', 'ò', 'ô', 'ö', 'õ', 'ú', 'ù', 'û', 'ü', //
+    '†', '°', '¢', '£', '§', '•', '¶', 'ß', '®', '©', '™', '´', '¨', '≠', 'Æ', 'Ø', //
+    '∞', '±', '≤', '≥', '¥', 'µ', '∂', '∑', '∏', 'π', '∫', 'ª', 'º', 'Ω', 'æ', 'ø', //
+    '¿', '¡', '¬', '√', 'ƒ', '≈', '∆', '«', '»', '…', '\u{a0}', 'À', 'Ã', 'Õ', 'Œ', 'œ', //
+    '–', '—', '“', '”', '‘', '’', '÷', '◊', 'ÿ', 'Ÿ', '⁄', '€', '‹', '›', 'ﬁ', 'ﬂ', //
+    '‡', '·', '‚', '„', '‰', 'Â', 'Ê', 'Á', 'Ë', 'È', 'Í', 'Î', 'Ï', 'Ì', 'Ó', 'Ô', //
+    '\u{f8ff}', 'Ò', 'Ú', 'Û', 'Ù', 'ı', 'ˆ', '˜', '¯', '˘', '˙', '˚', '¸', '˝', '˛', 'ˇ', //
+];
+
+/// Character set a connection's client uses for text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// Text is exchanged as UTF-8, unchanged.
+    #[default]
+    Utf8,
+    /// Text is exchanged as MacRoman.
+    MacRoman,
+}
+
+impl TextEncoding {
+    /// Convert the text fields of a client's request payload to UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload cannot be decoded or re-encoded.
+    pub fn decode_payload(self, payload: &[u8]) -> Result<Vec<u8>, TransactionError> {
+        self.transcode(payload, |field, data| {
+            is_text_field(field).then(|| mac_roman_to_utf8(data).into_bytes())
+        })
+    }
+
+    /// Convert the text fields of an outbound payload to the client's
+    /// encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload cannot be decoded or re-encoded.
+    pub fn encode_payload(self, payload: &[u8]) -> Result<Vec<u8>, TransactionError> {
+        self.transcode(payload, encode_field)
+    }
+
+    fn transcode(
+        self,
+        payload: &[u8],
+        convert: impl Fn(FieldId, &[u8]) -> Option<Vec<u8>>,
+    ) -> Result<Vec<u8>, TransactionError> {
+        if self == Self::Utf8 || payload.is_empty() {
+            return Ok(payload.to_vec());
+        }
+        let mut params = decode_params(payload)?;
+        let mut changed = false;
+        for (field, data) in &mut params {
+            if let Some(converted) = convert(*field, data)
+                && converted != *data
+            {
+                *data = converted;
+                changed = true;
+            }
+        }
+        if changed {
+            encode_params(&params)
+        } else {
+            Ok(payload.to_vec())
+        }
+    }
+}
+
+/// Decode MacRoman `bytes` as a string.
+#[must_use]
+pub fn mac_roman_to_utf8(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| {
+            byte.checked_sub(0x80)
+                .and_then(|high| MAC_ROMAN_HIGH.get(usize::from(high)).copied())
+                .unwrap_or(char::from(byte))
+        })
+        .collect()
+}
+
+/// Encode `text` as MacRoman, replacing unmappable characters with `?`.
+#[must_use]
+pub fn utf8_to_mac_roman(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|ch| {
+            if ch.is_ascii() {
+                return u8::try_from(ch).unwrap_or(UNMAPPABLE);
+            }
+            MAC_ROMAN_HIGH
+                .iter()
+                .position(|&high| high == ch)
+                .and_then(|index| u8::try_from(index).ok())
+                .map_or(UNMAPPABLE, |index| index | 0x80)
+        })
+        .collect()
+}
+
+/// Encode one outbound field, or return `None` to leave it unchanged.
+fn encode_field(field: FieldId, data: &[u8]) -> Option<Vec<u8>> {
+    if field == FieldId::UserNameWithInfo {
+        return encode_user_entry(data);
+    }
+    let text = std::str::from_utf8(data).ok()?;
+    is_text_field(field).then(|| utf8_to_mac_roman(text))
+}
+
+/// Encode the name in a User Name With Info (300) entry, fixing its length.
+#[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+fn encode_user_entry(data: &[u8]) -> Option<Vec<u8>> {
+    let (header, name) = data.split_at_checked(USER_ENTRY_HEADER_LEN)?;
+    let encoded = utf8_to_mac_roman(std::str::from_utf8(name).ok()?);
+    let name_len = u16::try_from(encoded.len()).ok()?;
+    let mut entry = Vec::with_capacity(data.len());
+    entry.extend_from_slice(header.get(..USER_ENTRY_HEADER_LEN - 2)?);
+    entry.extend_from_slice(&name_len.to_be_bytes());
+    entry.extend_from_slice(&encoded);
+    Some(entry)
+}
+
+/// Fields whose whole value is text.
+const fn is_text_field(field: FieldId) -> bool {
+    matches!(
+        field,
+        FieldId::Name
+            | FieldId::Login
+            | FieldId::Password
+            | FieldId::Data
+            | FieldId::ChatSubject
+            | FieldId::ServerName
+            | FieldId::QuotingMsg
+            | FieldId::AutoResponse
+            | FieldId::NewsCategoryName
+            | FieldId::NewsTitle
+            | FieldId::NewsPoster
+            | FieldId::NewsArticleData
+            | FieldId::FileItemName
+            | FieldId::FileComment
+            | FieldId::FileNewName
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    //! MacRoman tables and payload transcoding.
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn every_mac_roman_byte_round_trips() {
+        let bytes: Vec<u8> = (0..=u8::MAX).collect();
+        let text = mac_roman_to_utf8(&bytes);
+        assert_eq!(utf8_to_mac_roman(&text), bytes);
+    }
+
+    #[rstest]
+    #[case("Café", b"Caf\x8e".as_slice())]
+    #[case("“Grüße”", b"\xd2Gr\x9f\xa7e\xd3".as_slice())]
+    #[case("日本", b"??".as_slice())]
+    fn encodes_mac_roman(#[case] text: &str, #[case] expected: &[u8]) {
+        assert_eq!(utf8_to_mac_roman(text), expected);
+    }
+
+    #[rstest]
+    fn decodes_only_text_fields() {
+        let payload = encode_params(&[
+            (FieldId::Data, b"Caf\x8e".as_slice()),
+            (FieldId::UserId, b"\x00\x8e".as_slice()),
+        ])
+        .expect("payload encodes");
+
+        let decoded = TextEncoding::MacRoman
+            .decode_payload(&payload)
+            .expect("decode");
+
+        let params = decode_params(&decoded).expect("params");
+        assert_eq!(params[0], (FieldId::Data, "Café".as_bytes().to_vec()));
+        assert_eq!(params[1], (FieldId::UserId, vec![0x00, 0x8e]));
+        assert_eq!(
+            TextEncoding::Utf8.decode_payload(&payload).expect("decode"),
+            payload
+        );
+    }
+
+    #[rstest]
+    fn encodes_user_list_names_with_their_new_length() {
+        let mut entry = vec![0, 7, 0, 1, 0, 0, 0, 5];
+        entry.extend_from_slice("Zoë".as_bytes());
+        let payload =
+            encode_params(&[(FieldId::UserNameWithInfo, entry)]).expect("payload encodes");
+
+        let encoded = TextEncoding::MacRoman
+            .encode_payload(&payload)
+            .expect("encode");
+
+        let params = decode_params(&encoded).expect("params");
+        assert_eq!(
+            params[0],
+            (
+                FieldId::UserNameWithInfo,
+                vec![0, 7, 0, 1, 0, 0, 0, 3, b'Z', b'o', 0x91]
+            )
+        );
+    }
+}
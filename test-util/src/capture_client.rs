@@ -0,0 +1,371 @@
+//! Test client that records server-initiated frames.
+//!
+//! [`CaptureClient`] drives a connection to a running [`crate::TestServer`]
+//! from a background reader thread. Replies are matched to requests by
+//! transaction id, while every unsolicited frame is recorded so broadcast and
+//! notification features can be asserted on with
+//! [`CaptureClient::expect_push`] instead of ad hoc socket reads.
+
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    net::{Shutdown, SocketAddr, TcpStream},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use mxd::{
+    field_id::FieldId,
+    transaction::{FrameHeader, HEADER_LEN, MAX_PAYLOAD_SIZE, Transaction, encode_params},
+    transaction_type::TransactionType,
+};
+
+use crate::{AnyError, protocol::handshake};
+
+/// Time [`CaptureClient::request`] waits for a reply.
+pub const DEFAULT_REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connection that separates replies from pushed frames and records the
+/// latter.
+pub struct CaptureClient {
+    stream: TcpStream,
+    frames: Receiver<Transaction>,
+    reader: Option<JoinHandle<()>>,
+    replies: VecDeque<Transaction>,
+    unclaimed: VecDeque<Transaction>,
+    pushes: Vec<Transaction>,
+    next_id: u32,
+}
+
+impl CaptureClient {
+    /// Connect to `addr`, complete the handshake, and start recording.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection or handshake fails.
+    pub fn connect(addr: SocketAddr) -> Result<Self, AnyError> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.set_write_timeout(Some(DEFAULT_REPLY_TIMEOUT))?;
+        stream.set_read_timeout(Some(DEFAULT_REPLY_TIMEOUT))?;
+        handshake(&mut stream)?;
+        stream.set_read_timeout(None)?;
+        let reader_stream = stream.try_clone()?;
+        let (tx, frames) = mpsc::channel();
+        let reader = thread::spawn(move || read_frames(reader_stream, &tx));
+        Ok(Self {
+            stream,
+            frames,
+            reader: Some(reader),
+            replies: VecDeque::new(),
+            unclaimed: VecDeque::new(),
+            pushes: Vec::new(),
+            next_id: 1,
+        })
+    }
+
+    /// Log in with the given credentials and return the reply.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the exchange fails or the server rejects the login.
+    pub fn login(&mut self, username: &str, password: &str) -> Result<Transaction, AnyError> {
+        let reply = self.request(
+            TransactionType::Login,
+            &[
+                (FieldId::Login, username.as_bytes()),
+                (FieldId::Password, password.as_bytes()),
+            ],
+        )?;
+        if reply.header.error != 0 {
+            return Err(anyhow::anyhow!(
+                "login failed with error code {}",
+                reply.header.error
+            ));
+        }
+        Ok(reply)
+    }
+
+    /// Send a request and wait up to [`DEFAULT_REPLY_TIMEOUT`] for its reply.
+    /// Pushes that arrive meanwhile are recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request cannot be sent, the connection closes,
+    /// or no reply arrives in time.
+    pub fn request(
+        &mut self,
+        ty: TransactionType,
+        params: &[(FieldId, &[u8])],
+    ) -> Result<Transaction, AnyError> {
+        let id = self.send(ty, params)?;
+        let deadline = Instant::now() + DEFAULT_REPLY_TIMEOUT;
+        loop {
+            if let Some(index) = self.replies.iter().position(|reply| reply.header.id == id) {
+                return self
+                    .replies
+                    .remove(index)
+                    .ok_or_else(|| anyhow::anyhow!("reply queue changed unexpectedly"));
+            }
+            if !self.receive_until(deadline)? {
+                return Err(anyhow::anyhow!("no reply to {ty} (id {id}) before timeout"));
+            }
+        }
+    }
+
+    /// Send a request without waiting for its reply, returning its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parameters cannot be encoded or the write fails.
+    pub fn send(
+        &mut self,
+        ty: TransactionType,
+        params: &[(FieldId, &[u8])],
+    ) -> Result<u32, AnyError> {
+        let payload = encode_params(params)?;
+        let size = u32::try_from(payload.len())?;
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let header = FrameHeader {
+            flags: 0,
+            is_reply: 0,
+            ty: ty.into(),
+            id,
+            error: 0,
+            total_size: size,
+            data_size: size,
+        };
+        self.stream
+            .write_all(&Transaction { header, payload }.to_bytes())?;
+        Ok(id)
+    }
+
+    /// Wait up to `timeout` for a push of type `ty`, returning the oldest one
+    /// not yet claimed. Pushes of other types stay available to later calls.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection closes or no matching push arrives
+    /// in time.
+    pub fn expect_push(
+        &mut self,
+        ty: TransactionType,
+        timeout: Duration,
+    ) -> Result<Transaction, AnyError> {
+        let deadline = Instant::now() + timeout;
+        let wanted = u16::from(ty);
+        loop {
+            if let Some(index) = self
+                .unclaimed
+                .iter()
+                .position(|push| push.header.ty == wanted)
+            {
+                return self
+                    .unclaimed
+                    .remove(index)
+                    .ok_or_else(|| anyhow::anyhow!("push queue changed unexpectedly"));
+            }
+            if !self.receive_until(deadline)? {
+                return Err(anyhow::anyhow!("no {ty} push within {timeout:?}"));
+            }
+        }
+    }
+
+    /// Fail if a push of type `ty` arrives within `window`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a matching push is received.
+    pub fn expect_no_push(
+        &mut self,
+        ty: TransactionType,
+        window: Duration,
+    ) -> Result<(), AnyError> {
+        match self.expect_push(ty, window) {
+            Ok(push) => Err(anyhow::anyhow!(
+                "unexpected {ty} push (id {})",
+                push.header.id
+            )),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Every push received so far, in arrival order, including claimed ones.
+    #[must_use]
+    pub fn pushes(&self) -> &[Transaction] { &self.pushes }
+
+    /// Receive one frame before `deadline`, sorting it into replies or
+    /// pushes. Returns `false` when the deadline passes first.
+    fn receive_until(&mut self, deadline: Instant) -> Result<bool, AnyError> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let frame = match self.frames.recv_timeout(remaining) {
+            Ok(frame) => frame,
+            Err(RecvTimeoutError::Timeout) => return Ok(false),
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(anyhow::anyhow!("server closed the connection"));
+            }
+        };
+        if frame.header.is_reply == 0 {
+            self.pushes.push(frame.clone());
+            self.unclaimed.push_back(frame);
+        } else {
+            self.replies.push_back(frame);
+        }
+        Ok(true)
+    }
+}
+
+impl Drop for CaptureClient {
+    #[expect(
+        clippy::let_underscore_must_use,
+        reason = "best-effort cleanup; Drop cannot propagate errors"
+    )]
+    fn drop(&mut self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Forward reassembled frames until the connection closes or the receiver
+/// is dropped.
+fn read_frames(mut stream: TcpStream, tx: &mpsc::Sender<Transaction>) {
+    while let Ok(frame) = read_transaction(&mut stream) {
+        if tx.send(frame).is_err() {
+            break;
+        }
+    }
+}
+
+/// Read one transaction, joining continuation fragments.
+fn read_transaction(stream: &mut TcpStream) -> Result<Transaction, AnyError> {
+    let mut header = read_header(stream)?;
+    let total = header.total_size as usize;
+    if total > MAX_PAYLOAD_SIZE {
+        return Err(anyhow::anyhow!("frame payload too large: {total}"));
+    }
+    let mut payload = Vec::with_capacity(total);
+    let mut fragment = header;
+    loop {
+        let mut chunk = vec![0u8; fragment.data_size as usize];
+        stream.read_exact(&mut chunk)?;
+        payload.extend_from_slice(&chunk);
+        if payload.len() >= total {
+            break;
+        }
+        fragment = read_header(stream)?;
+        if fragment.id != header.id || fragment.ty != header.ty {
+            return Err(anyhow::anyhow!(
+                "interleaved fragment for id {}",
+                fragment.id
+            ));
+        }
+    }
+    header.data_size = header.total_size;
+    Ok(Transaction { header, payload })
+}
+
+fn read_header(stream: &mut TcpStream) -> Result<FrameHeader, AnyError> {
+    let mut buf = [0u8; HEADER_LEN];
+    stream.read_exact(&mut buf)?;
+    Ok(FrameHeader::from_bytes(&buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use mxd::protocol::{HANDSHAKE_LEN, PROTOCOL_ID, REPLY_LEN};
+
+    use super::*;
+
+    fn frame(ty: TransactionType, id: u32, is_reply: u8, payload: &[u8]) -> Vec<u8> {
+        let size = u32::try_from(payload.len()).unwrap_or(u32::MAX);
+        let header = FrameHeader {
+            flags: 0,
+            is_reply,
+            ty: ty.into(),
+            id,
+            error: 0,
+            total_size: size,
+            data_size: size,
+        };
+        Transaction {
+            header,
+            payload: payload.to_vec(),
+        }
+        .to_bytes()
+    }
+
+    /// Accept one client, answer its handshake, then run `serve`.
+    fn fake_server<F>(serve: F) -> Result<(SocketAddr, JoinHandle<()>), AnyError>
+    where
+        F: FnOnce(TcpStream) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let handle = thread::spawn(move || {
+            let Ok((mut stream, _)) = listener.accept() else {
+                return;
+            };
+            let mut request = [0u8; HANDSHAKE_LEN];
+            if stream.read_exact(&mut request).is_err() {
+                return;
+            }
+            let mut reply = PROTOCOL_ID.to_vec();
+            reply.resize(REPLY_LEN, 0);
+            if stream.write_all(&reply).is_ok() {
+                serve(stream);
+            }
+        });
+        Ok((addr, handle))
+    }
+
+    #[test]
+    fn separates_replies_from_pushes() -> Result<(), AnyError> {
+        let (addr, server) = fake_server(|mut stream| {
+            let mut header = [0u8; HEADER_LEN];
+            if stream.read_exact(&mut header).is_err() {
+                return;
+            }
+            let id = FrameHeader::from_bytes(&header).id;
+            let mut out = frame(TransactionType::ChatMsg, 0, 0, b"pushed");
+            out.extend(frame(TransactionType::KeepAlive, id, 1, &[]));
+            drop(stream.write_all(&out));
+            let mut rest = Vec::new();
+            drop(stream.read_to_end(&mut rest));
+        })?;
+        let mut client = CaptureClient::connect(addr)?;
+        let reply = client.request(TransactionType::KeepAlive, &[])?;
+        assert_eq!(reply.header.is_reply, 1);
+        let push = client.expect_push(TransactionType::ChatMsg, Duration::from_secs(1))?;
+        assert_eq!(push.payload, b"pushed");
+        assert_eq!(client.pushes().len(), 1);
+        client.expect_no_push(TransactionType::ChatMsg, Duration::from_millis(50))?;
+        drop(client);
+        server
+            .join()
+            .map_err(|_| anyhow::anyhow!("fake server panicked"))?;
+        Ok(())
+    }
+
+    #[test]
+    fn expect_push_times_out_without_match() -> Result<(), AnyError> {
+        let (addr, server) = fake_server(|mut stream| {
+            drop(stream.write_all(&frame(TransactionType::NotifyDeleteUser, 0, 0, &[])));
+            let mut rest = Vec::new();
+            drop(stream.read_to_end(&mut rest));
+        })?;
+        let mut client = CaptureClient::connect(addr)?;
+        let missing = client.expect_push(TransactionType::ChatMsg, Duration::from_millis(100));
+        assert!(missing.is_err());
+        client.expect_push(TransactionType::NotifyDeleteUser, Duration::from_secs(1))?;
+        drop(client);
+        server
+            .join()
+            .map_err(|_| anyhow::anyhow!("fake server panicked"))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,57 @@
+//! Simulated clock jumps for timeout tests.
+//!
+//! The server's timeouts read a monotonic clock: code that tracks deadlines
+//! takes the current [`Instant`] as an argument, and code that sleeps uses
+//! Tokio's clock. Tests simulate a skewed clock by handing the former shifted
+//! instants with [`ClockJump::apply`], and by advancing a paused Tokio runtime
+//! by [`ClockJump::forward`] for the latter. [`CLOCK_JUMPS`] lists the jumps
+//! every timeout must survive without panicking or hanging.
+
+use std::time::{Duration, Instant};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+/// A sudden change in the time a timeout sees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockJump {
+    /// The clock moves back, as a wall clock does when daylight saving ends.
+    Backward(Duration),
+    /// The clock moves on, as after a suspend or when daylight saving starts.
+    Forward(Duration),
+}
+
+/// Jumps covering daylight-saving changes, a suspended host, and a clock
+/// that is years out.
+pub const CLOCK_JUMPS: [ClockJump; 5] = [
+    ClockJump::Backward(HOUR),
+    ClockJump::Backward(Duration::from_secs(1)),
+    ClockJump::Forward(HOUR),
+    ClockJump::Forward(Duration::from_secs(60 * 60 * 24 * 3)),
+    ClockJump::Forward(Duration::from_secs(60 * 60 * 24 * 365 * 20)),
+];
+
+impl ClockJump {
+    /// Return `now` after the jump, or `now` itself when the result cannot
+    /// be represented.
+    #[must_use]
+    pub fn apply(self, now: Instant) -> Instant {
+        match self {
+            Self::Backward(by) => now.checked_sub(by).unwrap_or(now),
+            Self::Forward(by) => now.checked_add(by).unwrap_or(now),
+        }
+    }
+
+    /// Distance to advance a paused Tokio clock, which cannot run backwards,
+    /// so backward jumps advance it by nothing.
+    #[must_use]
+    pub const fn forward(self) -> Duration {
+        match self {
+            Self::Backward(_) => Duration::ZERO,
+            Self::Forward(by) => by,
+        }
+    }
+
+    /// Whether the jump moves the clock on by at least `window`.
+    #[must_use]
+    pub fn passes(self, window: Duration) -> bool { self.forward() >= window }
+}
@@ -0,0 +1,143 @@
+//! One-call harness for exercising command handlers against a fixture
+//! database.
+//!
+//! Handler tests otherwise repeat the same plumbing: build a runtime, seed a
+//! temporary database, assemble a [`HandlerContext`], and decode the reply.
+//! [`run_command`] folds those steps into a single call that drives the full
+//! parse → command → reply path via [`handle_request`].
+
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+
+use mxd::{
+    field_id::FieldId,
+    handler::{Context as HandlerContext, Session, handle_request},
+    server::settings::ServerSettings,
+    transaction::{FrameHeader, decode_params_map},
+};
+use tokio::runtime::Builder;
+
+use crate::{AnyError, SetupFn, build_test_db_async};
+
+const HARNESS_PEER: &str = "127.0.0.1:5500";
+
+/// Decoded reply produced by [`run_command`].
+#[derive(Debug, Clone)]
+pub struct CommandReply {
+    /// Reply header, including the error code and echoed transaction id.
+    pub header: FrameHeader,
+    /// Reply parameters grouped by field identifier in wire order.
+    pub params: HashMap<FieldId, Vec<Vec<u8>>>,
+    /// Session state after the command completed.
+    pub session: Session,
+}
+
+impl CommandReply {
+    /// Return the error code carried by the reply header.
+    #[must_use]
+    pub const fn error(&self) -> u32 { self.header.error }
+
+    /// Return every value recorded for `field`.
+    #[must_use]
+    pub fn values(&self, field: FieldId) -> &[Vec<u8>] {
+        self.params.get(&field).map_or(&[], Vec::as_slice)
+    }
+
+    /// Decode every value recorded for `field` as UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any value is not valid UTF-8.
+    pub fn strings(&self, field: FieldId) -> Result<Vec<String>, AnyError> {
+        self.values(field)
+            .iter()
+            .map(|value| Ok(String::from_utf8(value.clone())?))
+            .collect()
+    }
+}
+
+/// Run a single request frame against a freshly seeded database using an
+/// unauthenticated session.
+///
+/// Returns `Ok(None)` when the configured database backend is unavailable,
+/// mirroring [`crate::build_test_db`].
+///
+/// # Errors
+///
+/// Returns an error if the fixture, runtime, or command processing fails, or
+/// if the reply payload cannot be decoded.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mxd::{field_id::FieldId, transaction_type::TransactionType};
+/// use test_util::{build_frame, run_command, setup_news_db};
+///
+/// let frame = build_frame(TransactionType::NewsCategoryNameList, 1, &[])?;
+/// let Some(reply) = run_command(setup_news_db, &frame)? else {
+///     return Ok(());
+/// };
+/// assert_eq!(reply.error(), 1); // authentication required
+/// ```
+pub fn run_command(setup: SetupFn, frame: &[u8]) -> Result<Option<CommandReply>, AnyError> {
+    run_command_with_session(setup, Session::default(), frame)
+}
+
+/// Run a single request frame against a freshly seeded database using the
+/// supplied session state.
+///
+/// Seed `session` with a user id and privileges to exercise authenticated
+/// handlers without replaying the login flow.
+///
+/// # Errors
+///
+/// Returns an error if the fixture, runtime, or command processing fails, or
+/// if the reply payload cannot be decoded.
+pub fn run_command_with_session(
+    setup: SetupFn,
+    session: Session,
+    frame: &[u8],
+) -> Result<Option<CommandReply>, AnyError> {
+    run_command_with_settings(setup, session, ServerSettings::default(), frame)
+}
+
+/// Run a single request frame with `session` against a server configured
+/// with `settings`.
+///
+/// # Errors
+///
+/// Returns an error if the fixture, runtime, or command processing fails, or
+/// if the reply payload cannot be decoded.
+pub fn run_command_with_settings(
+    setup: SetupFn,
+    session: Session,
+    settings: ServerSettings,
+    frame: &[u8],
+) -> Result<Option<CommandReply>, AnyError> {
+    let rt = Builder::new_current_thread().enable_all().build()?;
+    rt.block_on(run_command_async(setup, session, settings, frame))
+}
+
+async fn run_command_async(
+    setup: SetupFn,
+    mut session: Session,
+    settings: ServerSettings,
+    frame: &[u8],
+) -> Result<Option<CommandReply>, AnyError> {
+    let Some(test_db) = build_test_db_async(setup).await? else {
+        return Ok(None);
+    };
+    let peer: SocketAddr = HARNESS_PEER.parse()?;
+    let ctx = HandlerContext::new(peer, test_db.pool(), Arc::new(argon2::Argon2::default()))
+        .with_settings(Arc::new(settings));
+    let reply = handle_request(&ctx, &mut session, frame).await?;
+    let params = if reply.payload.is_empty() {
+        HashMap::new()
+    } else {
+        decode_params_map(&reply.payload)?
+    };
+    Ok(Some(CommandReply {
+        header: reply.header,
+        params,
+        session,
+    }))
+}
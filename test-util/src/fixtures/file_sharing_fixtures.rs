@@ -18,6 +18,7 @@ use mxd::{
     schema::users::dsl as users_dsl,
 };
 
+use super::{DatabaseUrl, ensure_test_user, with_db};
 use crate::AnyError;
 
 /// Resolve a file name to its file-node ID from the lookup map.
@@ -152,17 +153,132 @@ pub(crate) async fn grant_fixture_download_visibility(
 ) -> Result<(), AnyError> {
     for name in ["fileA.txt", "fileC.txt"] {
         let resource_id = resolve_file_node_id(file_node_ids, name)?;
-        grant_resource_permission(
-            conn,
-            &NewResourcePermission {
-                resource_type: "file_node",
-                resource_id,
-                principal_type: "user",
-                principal_id: user_id,
-                permission_id,
-            },
-        )
-        .await?;
+        grant_user_download(conn, user_id, permission_id, resource_id).await?;
     }
     Ok(())
 }
+
+/// Create a test database with the [`super::setup_files_db`] contents plus a
+/// `Docs` folder holding `guide.txt`, `draft.txt`, and an `Archive` subfolder.
+///
+/// `alice` may see `Docs`, `guide.txt`, and `Archive`; `draft.txt` is hidden.
+/// A root `Private` folder exists but is not granted to anyone.
+///
+/// # Errors
+///
+/// Returns an error if database setup fails.
+pub fn setup_file_tree_db(db: DatabaseUrl) -> Result<(), AnyError> {
+    with_db(db, |conn| {
+        Box::pin(async move {
+            ensure_test_user(conn).await?;
+            let user_id = fetch_test_user_id(conn).await?;
+            let permission_id = seed_download_file_permission(conn).await?;
+            ensure_everyone_group_membership(conn, user_id).await?;
+            let file_node_ids = seed_root_file_nodes(conn, user_id).await?;
+            grant_fixture_download_visibility(conn, user_id, permission_id, &file_node_ids).await?;
+            let docs_id = create_folder(conn, "Docs", None, user_id).await?;
+            create_folder(conn, "Private", None, user_id).await?;
+            let archive_id = create_folder(conn, "Archive", Some(docs_id), user_id).await?;
+            let guide_id = create_child_file(conn, "guide.txt", docs_id, user_id).await?;
+            create_child_file(conn, "draft.txt", docs_id, user_id).await?;
+            for resource_id in [docs_id, archive_id, guide_id] {
+                grant_user_download(conn, user_id, permission_id, resource_id).await?;
+            }
+            Ok(())
+        })
+    })
+}
+
+/// Create a test database holding an `Uploads` drop box with `deposit.txt`
+/// and an `Inbox` subfolder inside, all granted to `alice`.
+///
+/// # Errors
+///
+/// Returns an error if database setup fails.
+pub fn setup_drop_box_db(db: DatabaseUrl) -> Result<(), AnyError> {
+    with_db(db, |conn| {
+        Box::pin(async move {
+            ensure_test_user(conn).await?;
+            let user_id = fetch_test_user_id(conn).await?;
+            let permission_id = seed_download_file_permission(conn).await?;
+            let uploads = NewFileNode {
+                kind: FileNodeKind::Folder.as_str(),
+                name: "Uploads",
+                parent_id: None,
+                alias_target_id: None,
+                object_key: None,
+                size: None,
+                comment: None,
+                is_dropbox: true,
+                creator_id: user_id,
+            };
+            let uploads_id = create_file_node(conn, &uploads).await?;
+            let inbox_id = create_folder(conn, "Inbox", Some(uploads_id), user_id).await?;
+            let deposit_id = create_child_file(conn, "deposit.txt", uploads_id, user_id).await?;
+            for resource_id in [uploads_id, inbox_id, deposit_id] {
+                grant_user_download(conn, user_id, permission_id, resource_id).await?;
+            }
+            Ok(())
+        })
+    })
+}
+
+async fn create_folder(
+    conn: &mut DbConnection,
+    name: &str,
+    parent_id: Option<i32>,
+    creator_id: i32,
+) -> Result<i32, AnyError> {
+    let node = NewFileNode {
+        kind: FileNodeKind::Folder.as_str(),
+        name,
+        parent_id,
+        alias_target_id: None,
+        object_key: None,
+        size: None,
+        comment: None,
+        is_dropbox: false,
+        creator_id,
+    };
+    Ok(create_file_node(conn, &node).await?)
+}
+
+async fn create_child_file(
+    conn: &mut DbConnection,
+    name: &str,
+    parent_id: i32,
+    creator_id: i32,
+) -> Result<i32, AnyError> {
+    let node = NewFileNode {
+        kind: FileNodeKind::File.as_str(),
+        name,
+        parent_id: Some(parent_id),
+        alias_target_id: None,
+        object_key: Some(name),
+        size: Some(1),
+        comment: None,
+        is_dropbox: false,
+        creator_id,
+    };
+    Ok(create_file_node(conn, &node).await?)
+}
+
+async fn grant_user_download(
+    conn: &mut DbConnection,
+    user_id: i32,
+    permission_id: i32,
+    resource_id: i32,
+) -> Result<(), AnyError> {
+    grant_resource_permission(
+        conn,
+        &NewResourcePermission {
+            resource_type: "file_node",
+            resource_id,
+            principal_type: "user",
+            principal_id: user_id,
+            permission_id,
+        },
+    )
+    .await?;
+    Ok(())
+}
@@ -15,7 +15,7 @@ compile_error!("Either feature 'sqlite' or 'postgres' must be enabled");
 compile_error!("Choose either sqlite or postgres, not both");
 
 use mxd::{
-    db::{DbConnection, create_bundle},
+    db::{DbConnection, PRIMARY_NEWS_ROOT, create_bundle},
     models::{NewArticle, NewBundle},
 };
 
@@ -36,6 +36,7 @@ pub(super) async fn insert_root_bundle(conn: &mut DbConnection) -> Result<i32, A
             name: "Bundle",
             guid: None,
             created_at: None,
+            root_id: PRIMARY_NEWS_ROOT,
         },
     )
     .await?;
@@ -14,7 +14,14 @@ use diesel_async::{AsyncConnection, RunQueryDsl};
 use futures_util::future::BoxFuture;
 use helpers::{insert_article, insert_root_bundle};
 use mxd::{
-    db::{DbConnection, apply_migrations, create_bundle, create_category, create_user},
+    db::{
+        DbConnection,
+        PRIMARY_NEWS_ROOT,
+        apply_migrations,
+        create_bundle,
+        create_category,
+        create_user,
+    },
     models::{NewArticle, NewBundle, NewCategory, NewUser},
     schema::users::dsl as users_dsl,
     users::hash_password,
@@ -27,6 +34,7 @@ use self::file_sharing_fixtures::{
     seed_download_file_permission,
     seed_root_file_nodes,
 };
+pub use self::file_sharing_fixtures::{setup_drop_box_db, setup_file_tree_db};
 use crate::AnyError;
 
 /// Database URL wrapper to make fixture APIs more explicit.
@@ -179,6 +187,7 @@ async fn create_general_category(conn: &mut DbConnection) -> Result<i32, AnyErro
             add_sn: None,
             delete_sn: None,
             created_at: None,
+            root_id: PRIMARY_NEWS_ROOT,
         },
     )
     .await?)
@@ -300,6 +309,7 @@ pub fn setup_news_categories_root_db(db: DatabaseUrl) -> Result<(), AnyError> {
                     add_sn: None,
                     delete_sn: None,
                     created_at: None,
+                    root_id: PRIMARY_NEWS_ROOT,
                 },
             )
             .await?;
@@ -312,6 +322,7 @@ pub fn setup_news_categories_root_db(db: DatabaseUrl) -> Result<(), AnyError> {
                     add_sn: None,
                     delete_sn: None,
                     created_at: None,
+                    root_id: PRIMARY_NEWS_ROOT,
                 },
             )
             .await?;
@@ -335,6 +346,7 @@ pub fn setup_news_categories_nested_db(db: DatabaseUrl) -> Result<(), AnyError>
                     name: "Sub",
                     guid: None,
                     created_at: None,
+                    root_id: PRIMARY_NEWS_ROOT,
                 },
             )
             .await?;
@@ -348,6 +360,7 @@ pub fn setup_news_categories_nested_db(db: DatabaseUrl) -> Result<(), AnyError>
                     add_sn: None,
                     delete_sn: None,
                     created_at: None,
+                    root_id: PRIMARY_NEWS_ROOT,
                 },
             )
             .await?;
@@ -11,15 +11,29 @@ pub type AnyError = anyhow::Error;
 pub mod postgres;
 
 mod bdd_helpers;
+mod capture_client;
+mod clock;
+mod command_harness;
 mod fixtures;
 mod protocol;
 mod server;
+mod tls;
 mod wireframe_bdd_world;
 
 pub use bdd_helpers::{SetupFn, TestDb, build_test_db, build_test_db_async};
+pub use capture_client::{CaptureClient, DEFAULT_REPLY_TIMEOUT};
+pub use clock::{CLOCK_JUMPS, ClockJump};
+pub use command_harness::{
+    CommandReply,
+    run_command,
+    run_command_with_session,
+    run_command_with_settings,
+};
 pub use fixtures::{
     DatabaseUrl,
     ensure_test_user,
+    setup_drop_box_db,
+    setup_file_tree_db,
     setup_files_db,
     setup_login_db,
     setup_news_categories_nested_db,
@@ -33,5 +47,14 @@ pub use mxd::wireframe::test_helpers::{build_frame, collect_strings};
 #[cfg(feature = "postgres")]
 pub use postgres::{PostgresTestDb, postgres_db};
 pub use protocol::{handshake, login};
-pub use server::{TestServer, ensure_server_binary_env, with_env_var};
+pub use server::{
+    ServerRuntime,
+    TEST_RUNTIME_ENV,
+    TestServer,
+    ensure_runtime_binary_env,
+    ensure_server_binary_env,
+    for_each_runtime,
+    with_env_var,
+};
+pub use tls::{TlsClient, TlsFixture};
 pub use wireframe_bdd_world::WireframeBddWorld;
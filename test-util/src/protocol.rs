@@ -1,11 +1,10 @@
 //! Protocol helpers shared by integration tests.
 //!
 //! Currently provides the client-side handshake and login used by multiple suites.
+//! Both work over any byte stream, including the TLS clients from
+//! [`crate::TlsFixture`].
 
-use std::{
-    io::{self, Read, Write},
-    net::TcpStream,
-};
+use std::io::{self, Read, Write};
 
 use mxd::{
     field_id::FieldId,
@@ -56,7 +55,7 @@ fn validate_handshake_reply(reply: [u8; REPLY_LEN]) -> io::Result<()> {
 /// # Errors
 ///
 /// Returns an I/O error if the handshake fails.
-pub fn handshake(stream: &mut TcpStream) -> std::io::Result<()> {
+pub fn handshake<S: Read + Write>(stream: &mut S) -> std::io::Result<()> {
     handshake_with_sub_version(stream, 0)
 }
 
@@ -66,7 +65,10 @@ pub fn handshake(stream: &mut TcpStream) -> std::io::Result<()> {
 ///
 /// Returns an I/O error if the handshake write/read fails or if the server
 /// reports a non-zero error code.
-pub fn handshake_with_sub_version(stream: &mut TcpStream, sub_version: u16) -> std::io::Result<()> {
+pub fn handshake_with_sub_version<S: Read + Write>(
+    stream: &mut S,
+    sub_version: u16,
+) -> std::io::Result<()> {
     let request = handshake_request(sub_version);
     stream.write_all(&request)?;
     let mut reply = [0u8; REPLY_LEN];
@@ -87,7 +89,11 @@ pub fn handshake_with_sub_version(stream: &mut TcpStream, sub_version: u16) -> s
     clippy::panic_in_result_fn,
     reason = "test helper: panics indicate protocol violations"
 )]
-pub fn login(stream: &mut TcpStream, username: &str, password: &str) -> std::io::Result<()> {
+pub fn login<S: Read + Write>(
+    stream: &mut S,
+    username: &str,
+    password: &str,
+) -> std::io::Result<()> {
     let params: &[(FieldId, &[u8])] = &[
         (FieldId::Login, username.as_bytes()),
         (FieldId::Password, password.as_bytes()),
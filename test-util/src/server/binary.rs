@@ -4,19 +4,19 @@ use std::path::{Path, PathBuf};
 
 use tracing::debug;
 
-use super::env::SERVER_BINARY_ENV;
-
-pub(super) fn resolve_server_binary() -> Option<PathBuf> {
-    let resolution =
-        std::env::var_os(SERVER_BINARY_ENV).map_or(ServerBinaryResolution::EnvMissing, |bin| {
-            let path = PathBuf::from(bin);
-            if path.is_file() {
-                ServerBinaryResolution::Found(path)
-            } else {
-                ServerBinaryResolution::Missing(path)
-            }
-        });
-    resolution.log();
+use super::runtime::ServerRuntime;
+
+pub(super) fn resolve_server_binary(runtime: ServerRuntime) -> Option<PathBuf> {
+    let env_var = runtime.binary_env();
+    let resolution = std::env::var_os(env_var).map_or(ServerBinaryResolution::EnvMissing, |bin| {
+        let path = PathBuf::from(bin);
+        if path.is_file() {
+            ServerBinaryResolution::Found(path)
+        } else {
+            ServerBinaryResolution::Missing(path)
+        }
+    });
+    resolution.log(env_var);
     resolution.into_option()
 }
 
@@ -27,7 +27,7 @@ enum ServerBinaryResolution {
 }
 
 impl ServerBinaryResolution {
-    fn log(&self) {
+    fn log(&self, env_var: &'static str) {
         let (message, binary) = match self {
             Self::EnvMissing => ("env var not set", None),
             Self::Found(path) => ("using prebuilt binary", Some(path.as_path())),
@@ -36,7 +36,7 @@ impl ServerBinaryResolution {
                 Some(path.as_path()),
             ),
         };
-        log_server_binary_resolution(message, binary);
+        log_server_binary_resolution(env_var, message, binary);
     }
 
     fn into_option(self) -> Option<PathBuf> {
@@ -47,10 +47,14 @@ impl ServerBinaryResolution {
     }
 }
 
-fn log_server_binary_resolution(message: &'static str, binary: Option<&Path>) {
+fn log_server_binary_resolution(
+    env_var: &'static str,
+    message: &'static str,
+    binary: Option<&Path>,
+) {
     let binary_display = binary.map(|path| path.display().to_string());
     debug!(
-        env_var = SERVER_BINARY_ENV,
+        env_var,
         binary = ?binary_display,
         "{message}"
     );
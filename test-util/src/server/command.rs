@@ -0,0 +1,90 @@
+//! Commands that start the server binaries under test.
+//!
+//! A prebuilt binary is preferred; otherwise the server is started through
+//! `cargo run` with the feature set the tests were built with.
+
+use std::{
+    ffi::OsString,
+    net::SocketAddr,
+    path::PathBuf,
+    process::{Command, Stdio},
+};
+
+use tracing::debug;
+
+use super::{DbUrl, ManifestPath, ServerRuntime, binary::resolve_server_binary};
+
+/// Constructs the base `cargo run` command for launching the server with the
+/// requested runtime, manifest, bind address, and database URL, enabling the
+/// active backend.
+pub(super) fn build_server_command(
+    runtime: ServerRuntime,
+    manifest_path: &ManifestPath,
+    addr: SocketAddr,
+    db_url: &DbUrl,
+) -> Command {
+    if let Some(bin) = resolve_server_binary(runtime) {
+        return server_binary_command(bin, addr, db_url);
+    }
+    debug!(%runtime, "falling back to cargo run");
+    cargo_run_command(runtime, manifest_path, addr, db_url)
+}
+
+/// Builds a command that executes an already-built server binary bound
+/// to the requested address and database URL, bypassing `cargo run` entirely.
+fn server_binary_command(bin: PathBuf, addr: SocketAddr, db_url: &DbUrl) -> Command {
+    let mut cmd = Command::new(bin);
+    cmd.arg("--bind");
+    cmd.arg(addr.to_string());
+    cmd.arg("--database");
+    cmd.arg(db_url.as_str());
+    cmd.stdout(Stdio::piped()).stderr(Stdio::inherit());
+    cmd
+}
+
+/// Produces a `cargo run` invocation tailored to the active backend, falling
+/// back to this path when no prebuilt binary is available.
+fn cargo_run_command(
+    runtime: ServerRuntime,
+    manifest_path: &ManifestPath,
+    addr: SocketAddr,
+    db_url: &DbUrl,
+) -> Command {
+    let cargo: OsString = std::env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+    let mut cmd = Command::new(cargo);
+    cmd.arg("run");
+    // Always use --no-default-features and explicitly specify required features
+    // to ensure the binary is built with the same feature set as the tests.
+    cmd.arg("--no-default-features");
+    #[cfg(feature = "postgres")]
+    {
+        cmd.args(["--features", "postgres"]);
+    }
+    #[cfg(feature = "sqlite")]
+    {
+        // Keep sqlite builds aligned with default features: Cargo.toml defines
+        // `toml` (figment/toml + dep:toml) for configuration/fixture parsing,
+        // so we pass `--features sqlite,toml` to ensure compilation matches.
+        cmd.args(["--features", "sqlite,toml"]);
+    }
+    // Ensure the server binary matches the feature set used by tests so Cargo
+    // does not trigger a costly rebuild when the harness falls back to
+    // `cargo run` (for example when the prebuilt binary is unavailable). The
+    // legacy binary additionally requires `legacy-networking`.
+    cmd.args(["--features", runtime.cargo_features()]);
+    cmd.args([
+        "--bin",
+        runtime.binary_name(),
+        "--manifest-path",
+        manifest_path.as_str(),
+        "--quiet",
+        "--",
+        "--bind",
+        &addr.to_string(),
+        "--database",
+        db_url.as_str(),
+    ])
+    .stdout(Stdio::piped())
+    .stderr(Stdio::inherit());
+    cmd
+}
@@ -2,11 +2,9 @@
 
 use std::{ffi::OsString, fmt, io, path::Path, sync::Mutex};
 
+use super::runtime::ServerRuntime;
 use crate::AnyError;
 
-/// Environment variable name for the prebuilt server binary path.
-pub(super) const SERVER_BINARY_ENV: &str = "CARGO_BIN_EXE_mxd-wireframe-server";
-
 static ENV_LOCK: Mutex<()> = Mutex::new(());
 
 /// Newtype wrapping the path to a Cargo manifest, providing type-safe handling
@@ -79,13 +77,24 @@ impl fmt::Display for DbUrl {
 ///
 /// Returns an error if the environment mutex is poisoned.
 pub fn ensure_server_binary_env(bin_path: &str) -> Result<(), AnyError> {
+    ensure_runtime_binary_env(ServerRuntime::Wireframe, bin_path)
+}
+
+/// Ensure the prebuilt binary environment variable for `runtime` is populated
+/// from the provided compile-time path.
+///
+/// # Errors
+///
+/// Returns an error if the environment mutex is poisoned.
+pub fn ensure_runtime_binary_env(runtime: ServerRuntime, bin_path: &str) -> Result<(), AnyError> {
     let _guard = ENV_LOCK
         .lock()
         .map_err(|_| io::Error::other("environment mutex poisoned"))?;
-    if std::env::var_os(SERVER_BINARY_ENV).is_none() {
+    let key = runtime.binary_env();
+    if std::env::var_os(key).is_none() {
         // SAFETY: Environment mutation is serialized by `ENV_LOCK`, ensuring no
         // concurrent readers/writers observe a partially updated state.
-        unsafe { std::env::set_var(SERVER_BINARY_ENV, bin_path) };
+        unsafe { std::env::set_var(key, bin_path) };
     }
     Ok(())
 }
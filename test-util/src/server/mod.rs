@@ -1,39 +1,44 @@
 //! Test server harness used by integration suites.
 //!
-//! Provides helpers to launch the `mxd` server binary with either the `SQLite` or
-//! `PostgreSQL` backend, monitor readiness, and tear it down once tests complete.
+//! Provides helpers to launch the `mxd` server binaries with either the `SQLite`
+//! or `PostgreSQL` backend, monitor readiness, and tear them down once tests
+//! complete. [`ServerRuntime`] selects whether the Wireframe or legacy binary
+//! is launched, and [`TestServer::start_with_tls`] serves it over TLS.
 
 use std::{
     ffi::OsString,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener},
-    path::PathBuf,
-    process::{Child, Command, Stdio},
+    process::Child,
 };
 
 mod binary;
+mod command;
 mod env;
 mod readiness;
+mod runtime;
 
-use binary::resolve_server_binary;
-pub use env::{DbUrl, ManifestPath, ensure_server_binary_env, with_env_var};
+use command::build_server_command;
+pub use env::{
+    DbUrl,
+    ManifestPath,
+    ensure_runtime_binary_env,
+    ensure_server_binary_env,
+    with_env_var,
+};
 #[cfg(unix)]
 use nix::{
     sys::signal::{Signal, kill},
     unistd::Pid,
 };
 use readiness::wait_for_server;
+pub use runtime::{ServerRuntime, TEST_RUNTIME_ENV, for_each_runtime};
 use tempfile::TempDir;
 use tracing::{debug, info, warn};
 
-use crate::AnyError;
 #[cfg(feature = "postgres")]
 use crate::postgres::PostgresTestDb;
+use crate::{AnyError, tls::TlsFixture};
 
-/// Name of the server binary to use for integration tests.
-///
-/// The wireframe server (`mxd-wireframe-server`) is the default, as it provides
-/// the production-ready transport layer implementation.
-const SERVER_BINARY_NAME: &str = "mxd-wireframe-server";
 const DEFAULT_BIND_HOST: &str = "127.0.0.1";
 const TEST_BIND_HOST_ENV: &str = "MXD_TEST_BIND_HOST";
 const MAX_SERVER_LAUNCH_ATTEMPTS: u8 = 3;
@@ -72,69 +77,6 @@ where
     Ok(url)
 }
 
-/// Constructs the base `cargo run` command for launching the server with the
-/// requested manifest, bind address, and database URL, enabling the active backend.
-fn build_server_command(manifest_path: &ManifestPath, addr: SocketAddr, db_url: &DbUrl) -> Command {
-    if let Some(bin) = resolve_server_binary() {
-        return server_binary_command(bin, addr, db_url);
-    }
-    debug!("falling back to cargo run");
-    cargo_run_command(manifest_path, addr, db_url)
-}
-
-/// Builds a command that executes an already-built wireframe server binary bound
-/// to the requested address and database URL, bypassing `cargo run` entirely.
-fn server_binary_command(bin: PathBuf, addr: SocketAddr, db_url: &DbUrl) -> Command {
-    let mut cmd = Command::new(bin);
-    cmd.arg("--bind");
-    cmd.arg(addr.to_string());
-    cmd.arg("--database");
-    cmd.arg(db_url.as_str());
-    cmd.stdout(Stdio::piped()).stderr(Stdio::inherit());
-    cmd
-}
-
-/// Produces a `cargo run` invocation tailored to the active backend, falling
-/// back to this path when no prebuilt binary is available.
-fn cargo_run_command(manifest_path: &ManifestPath, addr: SocketAddr, db_url: &DbUrl) -> Command {
-    let cargo: OsString = std::env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
-    let mut cmd = Command::new(cargo);
-    cmd.arg("run");
-    // Always use --no-default-features and explicitly specify required features
-    // to ensure the binary is built with the same feature set as the tests.
-    cmd.arg("--no-default-features");
-    #[cfg(feature = "postgres")]
-    {
-        cmd.args(["--features", "postgres"]);
-    }
-    #[cfg(feature = "sqlite")]
-    {
-        // Keep sqlite builds aligned with default features: Cargo.toml defines
-        // `toml` (figment/toml + dep:toml) for configuration/fixture parsing,
-        // so we pass `--features sqlite,toml` to ensure compilation matches.
-        cmd.args(["--features", "sqlite,toml"]);
-    }
-    // Ensure the server binary matches the feature set used by tests so Cargo
-    // does not trigger a costly rebuild when the harness falls back to
-    // `cargo run` (for example when the prebuilt binary is unavailable).
-    cmd.args(["--features", "test-support"]);
-    cmd.args([
-        "--bin",
-        SERVER_BINARY_NAME,
-        "--manifest-path",
-        manifest_path.as_str(),
-        "--quiet",
-        "--",
-        "--bind",
-        &addr.to_string(),
-        "--database",
-        db_url.as_str(),
-    ])
-    .stdout(Stdio::piped())
-    .stderr(Stdio::inherit());
-    cmd
-}
-
 fn resolve_bind_host() -> Result<String, AnyError> {
     let value =
         std::env::var_os(TEST_BIND_HOST_ENV).unwrap_or_else(|| OsString::from(DEFAULT_BIND_HOST));
@@ -143,6 +85,15 @@ fn resolve_bind_host() -> Result<String, AnyError> {
         .map_err(|_| anyhow::anyhow!("{TEST_BIND_HOST_ENV} must be valid UTF-8"))
 }
 
+/// What to launch: the runtime, how to build it, where to bind, and whether
+/// clients must use TLS.
+struct Launch<'a> {
+    runtime: ServerRuntime,
+    manifest_path: &'a ManifestPath,
+    bind_host: &'a str,
+    tls: Option<&'a TlsFixture>,
+}
+
 /// Spawns the configured server process on an ephemeral port and waits for the
 /// socket to accept connections before returning the child handle and chosen
 /// port.
@@ -154,13 +105,10 @@ fn resolve_bind_host() -> Result<String, AnyError> {
     clippy::let_underscore_must_use,
     reason = "best-effort cleanup; error already being propagated"
 )]
-fn launch_server_process(
-    manifest_path: &ManifestPath,
-    bind_host: &str,
-    db_url: &DbUrl,
-) -> Result<(Child, SocketAddr), AnyError> {
+fn launch_server_process(launch: &Launch, db_url: &DbUrl) -> Result<(Child, SocketAddr), AnyError> {
+    let runtime = launch.runtime;
     for attempt in 1..=MAX_SERVER_LAUNCH_ATTEMPTS {
-        let socket = TcpListener::bind((bind_host, 0))?;
+        let socket = TcpListener::bind((launch.bind_host, 0))?;
         let addr = socket.local_addr()?;
         let readiness_addr = match addr.ip() {
             IpAddr::V4(ip) if ip.is_unspecified() => {
@@ -176,12 +124,17 @@ fn launch_server_process(
         info!(
             port = addr.port(),
             db_url = %db_url,
+            %runtime,
             attempt,
             "launching server"
         );
-        let mut child = build_server_command(manifest_path, addr, db_url).spawn()?;
+        let mut command = build_server_command(runtime, launch.manifest_path, addr, db_url);
+        if let Some(tls) = launch.tls {
+            tls.configure_server(&mut command);
+        }
+        let mut child = command.spawn()?;
         debug!("spawned server process, waiting for readiness");
-        match wait_for_server(&mut child, readiness_addr) {
+        match wait_for_server(&mut child, readiness_addr, launch.tls) {
             Ok(()) => {
                 info!(port = addr.port(), attempt, "server ready");
                 return Ok((child, addr));
@@ -206,7 +159,7 @@ fn launch_server_process(
     ))
 }
 
-/// Integration test server wrapper that spawns the `mxd` process with the
+/// Integration test server wrapper that spawns a server process with the
 /// selected backend, waits for readiness, and tears it down automatically on
 /// drop.
 pub struct TestServer {
@@ -214,6 +167,7 @@ pub struct TestServer {
     port: u16,
     bind_addr: SocketAddr,
     db_url: DbUrl,
+    runtime: ServerRuntime,
     #[cfg(feature = "postgres")]
     db: PostgresTestDb,
     temp_dir: Option<TempDir>,
@@ -233,12 +187,13 @@ impl TestServer {
 
     /// Launches a server and runs the setup callback with the database URL
     /// before starting, useful for seeding data or running migrations; returns
-    /// an error if setup, database initialization, or launch fails.
+    /// an error if setup, database initialization, or launch fails. The
+    /// runtime is read from [`TEST_RUNTIME_ENV`].
     ///
     /// # Errors
     ///
-    /// Returns an error if setup, database initialization, or launch fails.
-    #[expect(clippy::shadow_reuse, reason = "standard Into pattern")]
+    /// Returns an error if the runtime selection is invalid or setup,
+    /// database initialization, or launch fails.
     pub fn start_with_setup<F>(
         manifest_path: impl Into<ManifestPath>,
         setup: F,
@@ -246,25 +201,82 @@ impl TestServer {
     where
         F: FnOnce(&DbUrl) -> Result<(), AnyError>,
     {
+        Self::start_with_runtime(manifest_path, ServerRuntime::from_env()?, setup)
+    }
+
+    /// Launches the given runtime after running the setup callback, ignoring
+    /// [`TEST_RUNTIME_ENV`]; pair with [`for_each_runtime`] to run a suite
+    /// against every runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime is unavailable in this build or setup,
+    /// database initialization, or launch fails.
+    pub fn start_with_runtime<F>(
+        manifest_path: impl Into<ManifestPath>,
+        runtime: ServerRuntime,
+        setup: F,
+    ) -> Result<Self, AnyError>
+    where
+        F: FnOnce(&DbUrl) -> Result<(), AnyError>,
+    {
+        Self::start_with_options(manifest_path, runtime, None, setup)
+    }
+
+    /// Launches the given runtime serving clients over TLS with the
+    /// certificate and ALPN mode of `tls`; connect with
+    /// [`TlsFixture::connect`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the runtime is unavailable in this build or setup,
+    /// database initialization, or launch fails.
+    pub fn start_with_tls<F>(
+        manifest_path: impl Into<ManifestPath>,
+        runtime: ServerRuntime,
+        tls: &TlsFixture,
+        setup: F,
+    ) -> Result<Self, AnyError>
+    where
+        F: FnOnce(&DbUrl) -> Result<(), AnyError>,
+    {
+        Self::start_with_options(manifest_path, runtime, Some(tls), setup)
+    }
+
+    #[expect(clippy::shadow_reuse, reason = "standard Into pattern")]
+    fn start_with_options<F>(
+        manifest_path: impl Into<ManifestPath>,
+        runtime: ServerRuntime,
+        tls: Option<&TlsFixture>,
+        setup: F,
+    ) -> Result<Self, AnyError>
+    where
+        F: FnOnce(&DbUrl) -> Result<(), AnyError>,
+    {
+        runtime.ensure_available()?;
         let manifest_path = manifest_path.into();
         let bind_host = resolve_bind_host()?;
+        let launch = Launch {
+            runtime,
+            manifest_path: &manifest_path,
+            bind_host: &bind_host,
+            tls,
+        };
         ensure_single_backend();
         #[cfg(all(feature = "sqlite", not(feature = "postgres")))]
         {
             let temp = TempDir::new()?;
             let db_url = setup_sqlite(&temp, setup)?;
-            Self::launch_with(
-                &manifest_path,
-                &bind_host,
-                db_url,
-                move |child, bind_addr, db_url_value| Self {
+            Self::launch_with(&launch, db_url, move |child, bind_addr, db_url_value| {
+                Self {
                     child,
                     port: bind_addr.port(),
                     bind_addr,
                     db_url: db_url_value,
+                    runtime,
                     temp_dir: Some(temp),
-                },
-            )
+                }
+            })
         }
 
         #[cfg(feature = "postgres")]
@@ -272,32 +284,25 @@ impl TestServer {
             let db = crate::postgres::PostgresTestDb::new()?;
             let db_url = DbUrl::from(db.url.as_ref());
             setup(&db_url)?;
-            Self::launch_with(
-                &manifest_path,
-                &bind_host,
-                db_url,
-                move |child, bind_addr, db_url_value| Self {
+            Self::launch_with(&launch, db_url, move |child, bind_addr, db_url_value| {
+                Self {
                     child,
                     port: bind_addr.port(),
                     bind_addr,
                     db_url: db_url_value,
+                    runtime,
                     db,
                     temp_dir: None,
-                },
-            )
+                }
+            })
         }
     }
 
-    fn launch_with<F>(
-        manifest_path: &ManifestPath,
-        bind_host: &str,
-        db_url: DbUrl,
-        build_self: F,
-    ) -> Result<Self, AnyError>
+    fn launch_with<F>(launch: &Launch, db_url: DbUrl, build_self: F) -> Result<Self, AnyError>
     where
         F: FnOnce(Child, SocketAddr, DbUrl) -> Self,
     {
-        let (child, bind_addr) = launch_server_process(manifest_path, bind_host, &db_url)?;
+        let (child, bind_addr) = launch_server_process(launch, &db_url)?;
         Ok(build_self(child, bind_addr, db_url))
     }
 
@@ -309,6 +314,10 @@ impl TestServer {
     #[must_use]
     pub const fn db_url(&self) -> &DbUrl { &self.db_url }
 
+    /// Returns the runtime serving this instance.
+    #[must_use]
+    pub const fn runtime(&self) -> ServerRuntime { self.runtime }
+
     /// Returns the bind address used by the server.
     #[must_use]
     pub const fn bind_addr(&self) -> SocketAddr { self.bind_addr }
@@ -11,23 +11,28 @@ use std::{
 use tracing::warn;
 use wait_timeout::ChildExt;
 
-use crate::{AnyError, protocol::handshake};
+use crate::{AnyError, protocol::handshake, tls::TlsFixture};
 
 const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
 const CONNECT_TIMEOUT: Duration = Duration::from_millis(200);
 const POLL_INTERVAL: Duration = Duration::from_millis(50);
 
-/// Wait for a spawned server to accept connections on the provided address.
+/// Wait for a spawned server to accept connections on the provided address,
+/// over TLS when `tls` is set.
 ///
 /// # Errors
 ///
 /// Returns an error if the server exits early or fails to start listening
 /// before the startup timeout elapses.
-pub(super) fn wait_for_server(child: &mut Child, addr: SocketAddr) -> Result<(), AnyError> {
+pub(super) fn wait_for_server(
+    child: &mut Child,
+    addr: SocketAddr,
+    tls: Option<&TlsFixture>,
+) -> Result<(), AnyError> {
     let start = Instant::now();
     loop {
         check_child_alive(child)?;
-        if is_protocol_ready(addr) {
+        if is_protocol_ready(addr, tls) {
             return verify_ready_server(child);
         }
         check_timeout(&start, addr)?;
@@ -70,7 +75,7 @@ fn is_listening(addr: SocketAddr) -> bool {
     TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok()
 }
 
-fn is_protocol_ready(addr: SocketAddr) -> bool {
+fn is_protocol_ready(addr: SocketAddr, tls: Option<&TlsFixture>) -> bool {
     let Ok(mut stream) = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) else {
         return false;
     };
@@ -79,7 +84,12 @@ fn is_protocol_ready(addr: SocketAddr) -> bool {
     {
         return false;
     }
-    handshake(&mut stream).is_ok()
+    match tls {
+        Some(fixture) => fixture
+            .wrap(addr, stream)
+            .is_ok_and(|mut client| handshake(&mut client).is_ok()),
+        None => handshake(&mut stream).is_ok(),
+    }
 }
 
 #[cfg(test)]
@@ -142,7 +152,7 @@ mod tests {
             .expect("rustc should spawn");
         child.wait().expect("rustc should exit");
 
-        let result = wait_for_server(&mut child, addr);
+        let result = wait_for_server(&mut child, addr, None);
 
         assert!(
             result.is_err(),
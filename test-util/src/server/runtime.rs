@@ -0,0 +1,186 @@
+//! Selection of the server runtime launched by the integration harness.
+//!
+//! Suites default to the Wireframe server. Setting `MXD_TEST_RUNTIME=legacy`
+//! runs them against the legacy `mxd` binary instead, and
+//! [`for_each_runtime`] runs one check against every runtime this build can
+//! launch so divergence between the two code paths shows up as a failure.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::Context as _;
+
+use crate::AnyError;
+
+/// Environment variable selecting the runtime launched by [`super::TestServer`].
+pub const TEST_RUNTIME_ENV: &str = "MXD_TEST_RUNTIME";
+
+/// Server runtime launched by the integration harness.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ServerRuntime {
+    /// The Wireframe transport (`mxd-wireframe-server`).
+    #[default]
+    Wireframe,
+    /// The legacy Tokio loop (`mxd`), built with `legacy-networking`.
+    Legacy,
+}
+
+impl ServerRuntime {
+    /// Runtimes this build of the harness can launch.
+    #[cfg(feature = "legacy-networking")]
+    #[must_use]
+    pub const fn available() -> &'static [Self] { &[Self::Wireframe, Self::Legacy] }
+
+    /// Runtimes this build of the harness can launch.
+    #[cfg(not(feature = "legacy-networking"))]
+    #[must_use]
+    pub const fn available() -> &'static [Self] { &[Self::Wireframe] }
+
+    /// Read the runtime from [`TEST_RUNTIME_ENV`], defaulting to Wireframe.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable is not valid UTF-8, names an unknown
+    /// runtime, or names one this build cannot launch.
+    pub fn from_env() -> Result<Self, AnyError> {
+        let Some(value) = std::env::var_os(TEST_RUNTIME_ENV) else {
+            return Ok(Self::default());
+        };
+        let text = value
+            .into_string()
+            .map_err(|_| anyhow::anyhow!("{TEST_RUNTIME_ENV} must be valid UTF-8"))?;
+        let runtime = text.parse::<Self>()?;
+        runtime.ensure_available()?;
+        Ok(runtime)
+    }
+
+    /// Fail unless this build of the harness can launch the runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for [`Self::Legacy`] when this crate's
+    /// `legacy-networking` feature is disabled.
+    pub fn ensure_available(self) -> Result<(), AnyError> {
+        if Self::available().contains(&self) {
+            return Ok(());
+        }
+        Err(anyhow::anyhow!(
+            "the {self} runtime requires the test-util/legacy-networking feature"
+        ))
+    }
+
+    /// Name of the Cargo binary target serving this runtime.
+    #[must_use]
+    pub const fn binary_name(self) -> &'static str {
+        match self {
+            Self::Wireframe => "mxd-wireframe-server",
+            Self::Legacy => "mxd",
+        }
+    }
+
+    /// Environment variable holding the prebuilt binary path for this runtime.
+    pub(super) const fn binary_env(self) -> &'static str {
+        match self {
+            Self::Wireframe => "CARGO_BIN_EXE_mxd-wireframe-server",
+            Self::Legacy => "CARGO_BIN_EXE_mxd",
+        }
+    }
+
+    /// Extra Cargo features the `cargo run` fallback needs for this runtime.
+    pub(super) const fn cargo_features(self) -> &'static str {
+        match self {
+            Self::Wireframe => "test-support",
+            Self::Legacy => "test-support,legacy-networking",
+        }
+    }
+}
+
+impl FromStr for ServerRuntime {
+    type Err = AnyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "wireframe" => Ok(Self::Wireframe),
+            "legacy" => Ok(Self::Legacy),
+            other => Err(anyhow::anyhow!(
+                "unknown {TEST_RUNTIME_ENV} value {other:?}; expected wireframe or legacy"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ServerRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Wireframe => "wireframe",
+            Self::Legacy => "legacy",
+        })
+    }
+}
+
+/// Run `check` once for every runtime in [`ServerRuntime::available`].
+///
+/// Failures are tagged with the runtime that produced them.
+///
+/// # Errors
+///
+/// Returns the first error raised by `check`.
+pub fn for_each_runtime<F>(mut check: F) -> Result<(), AnyError>
+where
+    F: FnMut(ServerRuntime) -> Result<(), AnyError>,
+{
+    for &runtime in ServerRuntime::available() {
+        check(runtime).with_context(|| format!("{runtime} runtime"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("wireframe", ServerRuntime::Wireframe)]
+    #[case("legacy", ServerRuntime::Legacy)]
+    #[case(" Legacy ", ServerRuntime::Legacy)]
+    fn parses_runtime_names(#[case] input: &str, #[case] expected: ServerRuntime) {
+        assert_eq!(input.parse::<ServerRuntime>().ok(), Some(expected));
+        assert_eq!(
+            expected.to_string().parse::<ServerRuntime>().ok(),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_runtime() {
+        assert!("hotline".parse::<ServerRuntime>().is_err());
+    }
+
+    #[test]
+    fn wireframe_is_always_available() {
+        assert_eq!(
+            ServerRuntime::available().first(),
+            Some(&ServerRuntime::Wireframe)
+        );
+        assert!(ServerRuntime::Wireframe.ensure_available().is_ok());
+    }
+
+    #[cfg(not(feature = "legacy-networking"))]
+    #[test]
+    fn legacy_requires_feature() {
+        assert!(ServerRuntime::Legacy.ensure_available().is_err());
+    }
+
+    #[test]
+    fn for_each_runtime_tags_failures() {
+        let mut seen = Vec::new();
+        let result = for_each_runtime(|runtime| {
+            seen.push(runtime);
+            Err(anyhow::anyhow!("boom"))
+        });
+        let message = result.err().map(|error| error.to_string());
+        assert_eq!(message.as_deref(), Some("wireframe runtime"));
+        assert_eq!(seen, vec![ServerRuntime::Wireframe]);
+    }
+}
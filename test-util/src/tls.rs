@@ -0,0 +1,147 @@
+//! Self-signed TLS material and clients for servers started with TLS.
+//!
+//! A [`TlsFixture`] writes a fresh certificate and key for `localhost`,
+//! `127.0.0.1`, and `::1` into a temporary directory, passes them to the
+//! server through [`crate::TestServer::start_with_tls`], and opens client
+//! connections that trust only that certificate.
+
+use std::{
+    net::{SocketAddr, TcpStream},
+    path::PathBuf,
+    process::Command,
+    sync::Arc,
+};
+
+use mxd::server::tls::HOTLINE_ALPN;
+use rustls::{
+    ClientConfig,
+    ClientConnection,
+    RootCertStore,
+    StreamOwned,
+    crypto::ring,
+    pki_types::{CertificateDer, ServerName},
+};
+use tempfile::TempDir;
+
+use crate::AnyError;
+
+/// Blocking TLS client connected to a test server.
+pub type TlsClient = StreamOwned<ClientConnection, TcpStream>;
+
+/// Certificate, key, and ALPN mode for a server started with TLS.
+pub struct TlsFixture {
+    dir: TempDir,
+    cert: CertificateDer<'static>,
+    alpn: bool,
+}
+
+impl TlsFixture {
+    /// Generate a self-signed certificate for the loopback addresses; the
+    /// server offers [`HOTLINE_ALPN`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the certificate cannot be generated or written.
+    pub fn new() -> Result<Self, AnyError> {
+        let names = ["localhost", "127.0.0.1", "::1"].map(str::to_owned);
+        let generated = rcgen::generate_simple_self_signed(names)?;
+        let dir = TempDir::new()?;
+        std::fs::write(dir.path().join("cert.pem"), generated.cert.pem())?;
+        std::fs::write(
+            dir.path().join("key.pem"),
+            generated.signing_key.serialize_pem(),
+        )?;
+        Ok(Self {
+            dir,
+            cert: generated.cert.der().clone(),
+            alpn: true,
+        })
+    }
+
+    /// Start the server without ALPN, as "Hotline over TLS" proxies expect.
+    #[must_use]
+    pub const fn without_alpn(mut self) -> Self {
+        self.alpn = false;
+        self
+    }
+
+    /// Returns the PEM certificate path passed as `--tls-cert`.
+    #[must_use]
+    pub fn cert_path(&self) -> PathBuf { self.dir.path().join("cert.pem") }
+
+    /// Returns the PEM private key path passed as `--tls-key`.
+    #[must_use]
+    pub fn key_path(&self) -> PathBuf { self.dir.path().join("key.pem") }
+
+    /// Append the TLS options to a server command line.
+    pub(crate) fn configure_server(&self, command: &mut Command) {
+        command.arg("--tls-cert").arg(self.cert_path());
+        command.arg("--tls-key").arg(self.key_path());
+        if !self.alpn {
+            command.arg("--tls-no-alpn");
+        }
+    }
+
+    /// Connect to `addr` and complete the TLS handshake, offering
+    /// [`HOTLINE_ALPN`] when the server negotiates it and no protocols
+    /// otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection or TLS handshake fails.
+    pub fn connect(&self, addr: SocketAddr) -> Result<TlsClient, AnyError> {
+        self.wrap(addr, TcpStream::connect(addr)?)
+    }
+
+    /// Connect to `addr` offering exactly `protocols` through ALPN.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection or TLS handshake fails.
+    pub fn connect_offering(
+        &self,
+        addr: SocketAddr,
+        protocols: &[&[u8]],
+    ) -> Result<TlsClient, AnyError> {
+        handshake_over(
+            self.client_config(protocols)?,
+            addr,
+            TcpStream::connect(addr)?,
+        )
+    }
+
+    /// Complete the TLS handshake over an already connected `stream`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the TLS handshake fails.
+    pub(crate) fn wrap(&self, addr: SocketAddr, stream: TcpStream) -> Result<TlsClient, AnyError> {
+        let protocols: &[&[u8]] = if self.alpn { &[HOTLINE_ALPN] } else { &[] };
+        handshake_over(self.client_config(protocols)?, addr, stream)
+    }
+
+    fn client_config(&self, protocols: &[&[u8]]) -> Result<ClientConfig, AnyError> {
+        let mut roots = RootCertStore::empty();
+        roots.add(self.cert.clone())?;
+        let mut config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        config.alpn_protocols = protocols.iter().map(|protocol| protocol.to_vec()).collect();
+        Ok(config)
+    }
+}
+
+/// Complete the client side of the TLS handshake with the server at `addr`.
+fn handshake_over(
+    config: ClientConfig,
+    addr: SocketAddr,
+    stream: TcpStream,
+) -> Result<TlsClient, AnyError> {
+    let connection = ClientConnection::new(Arc::new(config), ServerName::from(addr.ip()))?;
+    let mut client = StreamOwned::new(connection, stream);
+    while client.conn.is_handshaking() {
+        client.conn.complete_io(&mut client.sock)?;
+    }
+    Ok(client)
+}
@@ -0,0 +1,89 @@
+//! Ban list enforcement integration tests.
+//!
+//! Each test runs against every runtime the build can launch, because both
+//! must refuse banned addresses at accept time and banned accounts at login.
+
+use std::time::Duration;
+
+use mxd::{
+    commands::ERR_BANNED,
+    db::{BanTarget, create_ban},
+    field_id::FieldId,
+    transaction_type::TransactionType,
+};
+use test_util::{
+    AnyError,
+    CaptureClient,
+    DatabaseUrl,
+    ServerRuntime,
+    for_each_runtime,
+    setup_login_db,
+    with_db,
+};
+
+#[expect(
+    dead_code,
+    reason = "this suite starts each runtime explicitly rather than via the environment"
+)]
+mod common;
+
+const PUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn setup_with_ban(db: DatabaseUrl, target: BanTarget) -> Result<(), AnyError> {
+    setup_login_db(db.clone())?;
+    with_db(db, |conn| {
+        Box::pin(async move {
+            create_ban(conn, &target, Some("integration test"), None).await?;
+            Ok(())
+        })
+    })
+}
+
+#[test]
+fn banned_account_cannot_log_in() -> Result<(), AnyError> {
+    for_each_runtime(banned_account_cannot_log_in_with)
+}
+
+fn banned_account_cannot_log_in_with(runtime: ServerRuntime) -> Result<(), AnyError> {
+    let account = BanTarget::Username("alice".to_owned());
+    let Some(server) = common::start_runtime_or_skip(runtime, |db| setup_with_ban(db, account))?
+    else {
+        return Ok(());
+    };
+    let mut client = CaptureClient::connect(server.bind_addr())?;
+
+    let reply = client.request(
+        TransactionType::Login,
+        &[
+            (FieldId::Login, b"alice".as_slice()),
+            (FieldId::Password, b"secret".as_slice()),
+        ],
+    )?;
+
+    if reply.header.error != ERR_BANNED {
+        return Err(anyhow::anyhow!(
+            "expected ban error, got {}",
+            reply.header.error
+        ));
+    }
+    client.expect_push(TransactionType::DisconnectMsg, PUSH_TIMEOUT)?;
+    Ok(())
+}
+
+#[test]
+fn banned_address_is_refused_before_handshake() -> Result<(), AnyError> {
+    for_each_runtime(banned_address_is_refused_with)
+}
+
+fn banned_address_is_refused_with(runtime: ServerRuntime) -> Result<(), AnyError> {
+    let address = BanTarget::Address("127.0.0.1".parse()?);
+    let Some(server) = common::start_runtime_or_skip(runtime, |db| setup_with_ban(db, address))?
+    else {
+        return Ok(());
+    };
+
+    if CaptureClient::connect(server.bind_addr()).is_ok() {
+        return Err(anyhow::anyhow!("banned address completed the handshake"));
+    }
+    Ok(())
+}
@@ -0,0 +1,204 @@
+//! Integration tests for the one-call command harness.
+//!
+//! Exercises `test_util::run_command` against seeded fixtures to confirm the
+//! harness drives the full parse → command → reply path and decodes replies.
+
+use std::{sync::Arc, time::Instant};
+
+use mxd::{
+    SessionPhase,
+    commands::{
+        ERR_INTERNAL_SERVER,
+        ERR_NOT_AUTHENTICATED,
+        NEWS_ERR_PATH_NOT_FOUND,
+        NEWS_ERR_PATH_UNSUPPORTED,
+    },
+    field_id::FieldId,
+    handler::Session,
+    privileges::Privileges,
+    server::{agreement::ServerAgreement, settings::ServerSettings, transfer_port::TransferKind},
+    transaction_type::TransactionType,
+};
+use rstest::rstest;
+use test_util::{
+    AnyError,
+    build_frame,
+    run_command,
+    run_command_with_session,
+    run_command_with_settings,
+    setup_login_db,
+    setup_news_categories_root_db,
+};
+
+fn news_reader_session() -> Session {
+    Session {
+        user_id: Some(1),
+        privileges: Privileges::default_user(),
+        phase: SessionPhase::Online,
+        ..Session::default()
+    }
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn login_updates_returned_session() -> Result<(), AnyError> {
+    let frame = build_frame(
+        TransactionType::Login,
+        7,
+        &[(FieldId::Login, b"alice"), (FieldId::Password, b"secret")],
+    )?;
+    let Some(reply) = run_command(setup_login_db, &frame)? else {
+        return Ok(());
+    };
+
+    assert_eq!(reply.error(), 0);
+    assert_eq!(reply.header.id, 7);
+    assert!(reply.session.is_authenticated());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn unauthenticated_news_listing_is_rejected() -> Result<(), AnyError> {
+    let frame = build_frame(TransactionType::NewsCategoryNameList, 1, &[])?;
+    let Some(reply) = run_command(setup_news_categories_root_db, &frame)? else {
+        return Ok(());
+    };
+
+    assert_eq!(reply.error(), ERR_NOT_AUTHENTICATED);
+    assert!(reply.params.is_empty());
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn authenticated_news_listing_returns_decoded_names() -> Result<(), AnyError> {
+    let frame = build_frame(TransactionType::NewsCategoryNameList, 2, &[])?;
+    let Some(reply) =
+        run_command_with_session(setup_news_categories_root_db, news_reader_session(), &frame)?
+    else {
+        return Ok(());
+    };
+
+    assert_eq!(reply.error(), 0);
+    let mut names = reply.strings(FieldId::NewsCategory)?;
+    names.sort_unstable();
+    assert_eq!(names, vec!["Bundle", "General", "Updates"]);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn unknown_news_path_maps_to_error_code() -> Result<(), AnyError> {
+    let frame = build_frame(
+        TransactionType::NewsCategoryNameList,
+        3,
+        &[(FieldId::NewsPath, b"Missing")],
+    )?;
+    let Some(reply) =
+        run_command_with_session(setup_news_categories_root_db, news_reader_session(), &frame)?
+    else {
+        return Ok(());
+    };
+
+    assert_eq!(reply.error(), NEWS_ERR_PATH_NOT_FOUND);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn malformed_news_path_maps_to_error_code() -> Result<(), AnyError> {
+    let frame = build_frame(
+        TransactionType::NewsCategoryNameList,
+        4,
+        &[(FieldId::NewsPath, b"Bundle//General")],
+    )?;
+    let Some(reply) =
+        run_command_with_session(setup_news_categories_root_db, news_reader_session(), &frame)?
+    else {
+        return Ok(());
+    };
+
+    assert_eq!(reply.error(), NEWS_ERR_PATH_UNSUPPORTED);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn unknown_transaction_is_counted_on_the_session() -> Result<(), AnyError> {
+    let frame = build_frame(TransactionType::Other(9_999), 5, &[])?;
+    let Some(reply) =
+        run_command_with_session(setup_news_categories_root_db, news_reader_session(), &frame)?
+    else {
+        return Ok(());
+    };
+
+    assert_eq!(reply.error(), ERR_INTERNAL_SERVER);
+    assert_eq!(reply.session.unknown_transactions, 1);
+    assert_eq!(reply.session.disconnect_reason, None);
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn agreed_brings_a_pending_session_online() -> Result<(), AnyError> {
+    let mut session = Session::default();
+    session.apply_login(1, "alice", Privileges::default_user());
+    let frame = build_frame(TransactionType::Agreed, 8, &[(FieldId::Name, b"Alice")])?;
+    let Some(reply) = run_command_with_session(setup_login_db, session, &frame)? else {
+        return Ok(());
+    };
+
+    assert_eq!(reply.error(), 0);
+    assert!(reply.session.is_online());
+    assert_eq!(reply.session.privileges, Privileges::default_user());
+    assert_eq!(reply.session.display_name, "Alice");
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[expect(clippy::big_endian_bytes, reason = "network protocol uses big-endian")]
+#[rstest]
+fn download_banner_files_the_configured_image_for_transfer() -> Result<(), AnyError> {
+    let settings = ServerSettings {
+        agreement: ServerAgreement::new(None, Some(b"GIF89a".to_vec())),
+        ..ServerSettings::default()
+    };
+    let transfers = Arc::clone(&settings.transfers);
+    let frame = build_frame(TransactionType::DownloadBanner, 9, &[])?;
+    let Some(reply) =
+        run_command_with_settings(setup_login_db, news_reader_session(), settings, &frame)?
+    else {
+        return Ok(());
+    };
+
+    assert_eq!(reply.error(), 0);
+    assert_eq!(
+        reply.values(FieldId::TransferSize),
+        [6u32.to_be_bytes().to_vec()]
+    );
+    let [reference] = reply.values(FieldId::ReferenceNumber) else {
+        return Err(anyhow::anyhow!("expected one reference number"));
+    };
+    let reference = u32::from_be_bytes(reference.as_slice().try_into()?);
+    let transfer = transfers
+        .claim(reference, Instant::now())
+        .ok_or_else(|| anyhow::anyhow!("banner transfer not filed"))?;
+    assert_eq!(transfer.kind, TransferKind::Banner);
+    assert_eq!(&*transfer.data, b"GIF89a");
+    Ok(())
+}
+
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+#[rstest]
+fn keep_alive_is_acknowledged_before_login() -> Result<(), AnyError> {
+    let frame = build_frame(TransactionType::KeepAlive, 11, &[])?;
+    let Some(reply) = run_command(setup_login_db, &frame)? else {
+        return Ok(());
+    };
+
+    assert_eq!(reply.error(), 0);
+    assert_eq!(reply.header.id, 11);
+    assert!(reply.params.is_empty());
+    Ok(())
+}
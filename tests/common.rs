@@ -2,22 +2,44 @@
 
 #[cfg(feature = "postgres")]
 use test_util::postgres::PostgresTestDbError;
-use test_util::{AnyError, DatabaseUrl, TestServer, ensure_server_binary_env};
+use test_util::{AnyError, DatabaseUrl, ServerRuntime, TestServer, ensure_server_binary_env};
 
 /// Start the server for a test or skip if prerequisites are unavailable.
 ///
 /// Runs the provided setup callback, returning a started `TestServer` on success or `None` when the
-/// environment indicates the test should be skipped (e.g., embedded Postgres not available).
+/// environment indicates the test should be skipped (e.g., embedded Postgres not available). The
+/// runtime is taken from `MXD_TEST_RUNTIME`.
 ///
 /// # Errors
 ///
 /// Returns any error produced by the setup callback or while launching the server.
 pub fn start_server_or_skip<F>(setup: F) -> Result<Option<TestServer>, AnyError>
+where
+    F: FnOnce(DatabaseUrl) -> Result<(), AnyError>,
+{
+    start_runtime_or_skip(ServerRuntime::from_env()?, setup)
+}
+
+/// Start the given runtime for a test or skip if prerequisites are unavailable.
+///
+/// Pair with `test_util::for_each_runtime` to run one test against every runtime.
+///
+/// # Errors
+///
+/// Returns any error produced by the setup callback or while launching the server.
+pub fn start_runtime_or_skip<F>(
+    runtime: ServerRuntime,
+    setup: F,
+) -> Result<Option<TestServer>, AnyError>
 where
     F: FnOnce(DatabaseUrl) -> Result<(), AnyError>,
 {
     ensure_server_binary_env(env!("CARGO_BIN_EXE_mxd-wireframe-server"))?;
-    match TestServer::start_with_setup("./Cargo.toml", |db| setup(DatabaseUrl::from(db))) {
+    #[cfg(feature = "legacy-networking")]
+    test_util::ensure_runtime_binary_env(ServerRuntime::Legacy, env!("CARGO_BIN_EXE_mxd"))?;
+    let launched =
+        TestServer::start_with_runtime("./Cargo.toml", runtime, |db| setup(DatabaseUrl::from(db)));
+    match launched {
         Ok(s) => Ok(Some(s)),
         Err(e) => {
             #[cfg(feature = "postgres")]
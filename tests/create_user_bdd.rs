@@ -84,6 +84,7 @@ impl CreateUserWorld {
             argon2_m_cost: Params::DEFAULT_M_COST,
             argon2_t_cost: Params::DEFAULT_T_COST,
             argon2_p_cost: Params::DEFAULT_P_COST,
+            ..AppConfig::default()
         };
         Ok(Self {
             _temp_dir: temp_dir,
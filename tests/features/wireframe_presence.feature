@@ -13,3 +13,26 @@ Feature: Wireframe presence notifications
     Then client "bob-client" receives a notify change user for user 2 with name "Alice A." and icon 9
     When client "alice-client" disconnects
     Then client "bob-client" receives a notify delete user for user 2
+
+  Scenario: Logging out takes a user offline but keeps the connection
+    Given a wireframe server with two presence test users
+    And client "bob-client" is connected and logged in as "bob"
+    And client "alice-client" is connected and logged in as "alice"
+    Then client "bob-client" receives a notify change user for user 2 with name "alice" and icon 0
+    When client "alice-client" logs out
+    Then the reply has error 0
+    And client "bob-client" receives a notify delete user for user 2
+    When client "alice-client" requests the user name list
+    Then the reply has error 1
+
+  Scenario: Private messages to an away user are answered with the away message
+    Given a wireframe server with two presence test users
+    And client "bob-client" is connected and logged in as "bob"
+    And client "alice-client" is connected and logged in as "alice"
+    Then client "bob-client" receives a notify change user for user 2 with name "alice" and icon 0
+    When client "alice-client" sets the away message "Back at five"
+    Then client "bob-client" receives a notify change user for user 2 with flags 1
+    When client "bob-client" sends the private message "hello" to user 2
+    Then the reply has error 0
+    And client "alice-client" receives the message "hello" from user 1
+    And client "bob-client" receives the message "Back at five" from user 2
@@ -6,7 +6,13 @@ Feature: Wireframe server bootstrap
     Given a wireframe configuration binding to "127.0.0.1:0"
     When I bootstrap the wireframe server
     Then bootstrap succeeds
-    And the resolved bind address is "127.0.0.1:0"
+    And the resolved bind addresses are "127.0.0.1:0"
+
+  Scenario: Accepts a list of bind addresses
+    Given a wireframe configuration binding to "127.0.0.1:0,[::1]:0"
+    When I bootstrap the wireframe server
+    Then bootstrap succeeds
+    And the resolved bind addresses are "127.0.0.1:0, [::1]:0"
 
   Scenario: Rejects invalid bind addresses
     Given a wireframe configuration binding to "invalid-bind"
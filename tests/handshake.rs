@@ -1,4 +1,7 @@
 //! Handshake integration tests for the Hotline server.
+//!
+//! The handshake runs against every runtime the build can launch, so the
+//! legacy and Wireframe listeners must agree on it.
 #![expect(clippy::big_endian_bytes, reason = "network protocol")]
 
 use std::{
@@ -7,13 +10,19 @@ use std::{
     time::Duration,
 };
 
-use test_util::{AnyError, DatabaseUrl};
+use test_util::{AnyError, DatabaseUrl, ServerRuntime, for_each_runtime};
 
+#[expect(
+    dead_code,
+    reason = "this suite starts each runtime explicitly rather than via the environment"
+)]
 mod common;
 
 #[test]
-fn handshake() -> Result<(), AnyError> {
-    let Some(server) = common::start_server_or_skip(|_: DatabaseUrl| Ok(()))? else {
+fn handshake() -> Result<(), AnyError> { for_each_runtime(handshake_with) }
+
+fn handshake_with(runtime: ServerRuntime) -> Result<(), AnyError> {
+    let Some(server) = common::start_runtime_or_skip(runtime, |_: DatabaseUrl| Ok(()))? else {
         return Ok(());
     };
     let addr = server.bind_addr();
@@ -5,6 +5,10 @@
 #[path = "integration/admin_postgres.rs"]
 mod admin_postgres;
 
+#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
+#[path = "integration/migrate_postgres.rs"]
+mod migrate_postgres;
+
 #[cfg(feature = "legacy-networking")]
 #[path = "integration/server_legacy.rs"]
 mod server_legacy;
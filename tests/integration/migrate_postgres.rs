@@ -0,0 +1,80 @@
+//! Integration tests for `migrate --online` against embedded `PostgreSQL`.
+//!
+//! The scenario migrates an empty database with the online runner, then
+//! checks that the migrations with online plans were recorded as applied and
+//! that the indexes they built concurrently are valid. Like
+//! `admin_postgres`, it skips when the embedded cluster is unavailable or an
+//! external `POSTGRES_TEST_URL` is configured.
+
+use anyhow::Result;
+use diesel::{
+    dsl::sql,
+    sql_types::{BigInt, Text},
+};
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use mxd::{
+    db::{DbConnection, OnlineStep, online_plans},
+    server::{AppConfig, Commands, MigrateArgs, run_command},
+};
+use rstest::rstest;
+use test_util::postgres::{PostgresTestDb, PostgresTestDbError};
+use tokio::runtime::Builder;
+
+async fn count(conn: &mut DbConnection, query: &str, name: &str) -> Result<i64> {
+    let found = diesel::select(sql::<BigInt>(query).bind::<Text, _>(name).sql(")"))
+        .get_result(conn)
+        .await?;
+    Ok(found)
+}
+
+#[rstest]
+fn online_migration_records_plans_and_builds_valid_indexes() -> Result<()> {
+    if std::env::var_os("POSTGRES_TEST_URL").is_some() {
+        tracing::warn!("SKIP-TEST-CLUSTER: POSTGRES_TEST_URL set, skipping embedded postgres test");
+        return Ok(());
+    }
+    let pg = match PostgresTestDb::new() {
+        Ok(db) => db,
+        Err(PostgresTestDbError::Unavailable(_)) => {
+            tracing::warn!("SKIP-TEST-CLUSTER: PostgreSQL unavailable");
+            return Ok(());
+        }
+        Err(err) => anyhow::bail!("Failed to initialize PostgreSQL test database: {err}"),
+    };
+    let rt = Builder::new_current_thread().enable_all().build()?;
+
+    rt.block_on(async {
+        let cfg = AppConfig {
+            database: pg.url.to_string(),
+            ..AppConfig::default()
+        };
+        run_command(Commands::Migrate(MigrateArgs { online: true }), &cfg).await?;
+        // A second run finds nothing pending.
+        run_command(Commands::Migrate(MigrateArgs { online: true }), &cfg).await?;
+
+        let mut conn = DbConnection::establish(&cfg.database).await?;
+        for plan in online_plans() {
+            let recorded = count(
+                &mut conn,
+                "(SELECT COUNT(*) FROM __diesel_schema_migrations WHERE version = ",
+                plan.version,
+            )
+            .await?;
+            assert_eq!(recorded, 1, "migration {} recorded once", plan.version);
+            for step in plan.steps {
+                let OnlineStep::ConcurrentIndex { name, .. } = *step else {
+                    continue;
+                };
+                let valid = count(
+                    &mut conn,
+                    "(SELECT COUNT(*) FROM pg_index i JOIN pg_class c ON c.oid = i.indexrelid \
+                     WHERE i.indisvalid AND c.relname = ",
+                    name,
+                )
+                .await?;
+                assert_eq!(valid, 1, "index {name} is valid");
+            }
+        }
+        Ok(())
+    })
+}
@@ -42,6 +42,7 @@ fn accept_context() -> AcceptContext {
         pool,
         argon2: Arc::clone(&argon2),
         presence: Arc::new(PresenceRegistry::default()),
+        settings: Arc::default(),
     };
     AcceptContext {
         resources,
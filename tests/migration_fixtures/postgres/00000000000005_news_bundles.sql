@@ -0,0 +1,99 @@
+-- Golden postgres snapshot with migrations up to 00000000000005 applied.
+-- Regenerate only when adding a new snapshot; existing files are frozen.
+
+CREATE TABLE __diesel_schema_migrations (
+    version VARCHAR(50) PRIMARY KEY NOT NULL,
+    run_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 00000000000000_create_users
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    username TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL
+);
+
+-- 00000000000001_create_news
+CREATE TABLE news_categories (
+    id INTEGER PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    name TEXT NOT NULL UNIQUE
+);
+
+-- 00000000000002_add_bundles
+CREATE TABLE news_bundles (
+    id INTEGER PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    parent_bundle_id INTEGER REFERENCES news_bundles(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    UNIQUE(name, parent_bundle_id)
+);
+
+ALTER TABLE news_categories
+    ADD COLUMN bundle_id INTEGER REFERENCES news_bundles(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_bundles_parent ON news_bundles(parent_bundle_id);
+CREATE INDEX idx_categories_bundle ON news_categories(bundle_id);
+
+-- 00000000000003_add_articles
+CREATE TABLE news_articles (
+    id                     INTEGER PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    category_id            INTEGER NOT NULL REFERENCES news_categories(id) ON DELETE CASCADE,
+    parent_article_id      INTEGER REFERENCES news_articles(id),
+    prev_article_id        INTEGER REFERENCES news_articles(id),
+    next_article_id        INTEGER REFERENCES news_articles(id),
+    first_child_article_id INTEGER REFERENCES news_articles(id),
+    title       TEXT    NOT NULL,
+    poster      TEXT,
+    posted_at   TIMESTAMP NOT NULL,
+    flags       INTEGER DEFAULT 0,
+    data_flavor TEXT    DEFAULT 'text/plain',
+    data        TEXT,
+    CHECK (category_id IS NOT NULL)
+);
+
+CREATE INDEX idx_articles_category ON news_articles(category_id);
+
+-- 00000000000004_create_files
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY GENERATED BY DEFAULT AS IDENTITY,
+    name TEXT NOT NULL UNIQUE,
+    object_key TEXT NOT NULL,
+    size BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE TABLE file_acl (
+    file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    PRIMARY KEY (file_id, user_id)
+);
+
+CREATE INDEX idx_file_acl_user_file ON file_acl (user_id, file_id);
+
+-- 00000000000005_add_bundle_name_parent_index
+CREATE INDEX idx_bundles_name_parent ON news_bundles(name, parent_bundle_id);
+
+INSERT INTO __diesel_schema_migrations (version) VALUES
+    ('00000000000000'),
+    ('00000000000001'),
+    ('00000000000002'),
+    ('00000000000003'),
+    ('00000000000004'),
+    ('00000000000005');
+
+-- Seed data
+INSERT INTO users (id, username, password) VALUES (1, 'alice', '$argon2id$v=19$m=19456,t=2,p=1$c2VlZHNhbHQ$placeholderhash');
+INSERT INTO news_bundles (id, parent_bundle_id, name) VALUES (1, NULL, 'Announcements');
+INSERT INTO news_bundles (id, parent_bundle_id, name) VALUES (2, 1, 'Archive');
+INSERT INTO news_categories (id, name, bundle_id) VALUES (1, 'Lobby', NULL);
+INSERT INTO news_categories (id, name, bundle_id) VALUES (2, 'Releases', 1);
+INSERT INTO news_categories (id, name, bundle_id) VALUES (3, 'Old', 2);
+INSERT INTO news_articles (id, category_id, parent_article_id, prev_article_id, next_article_id, first_child_article_id, title, poster, posted_at, flags, data_flavor, data) VALUES (1, 2, NULL, NULL, NULL, NULL, 'Welcome', 'alice', '2024-03-01 09:30:00', 0, 'text/plain', 'First post');
+INSERT INTO news_articles (id, category_id, parent_article_id, prev_article_id, next_article_id, first_child_article_id, title, poster, posted_at, flags, data_flavor, data) VALUES (2, 2, NULL, 1, NULL, NULL, 'Second', 'alice', '2024-03-02 10:00:00', 0, 'text/plain', 'Follow-up');
+UPDATE news_articles SET next_article_id = 2 WHERE id = 1;
+INSERT INTO files (id, name, object_key, size) VALUES (1, 'readme.txt', 'objects/readme', 12);
+INSERT INTO file_acl (file_id, user_id) VALUES (1, 1);
+
+SELECT setval(pg_get_serial_sequence('users', 'id'), 1);
+SELECT setval(pg_get_serial_sequence('news_bundles', 'id'), 2);
+SELECT setval(pg_get_serial_sequence('news_categories', 'id'), 3);
+SELECT setval(pg_get_serial_sequence('news_articles', 'id'), 2);
+SELECT setval(pg_get_serial_sequence('files', 'id'), 1);
@@ -0,0 +1,263 @@
+-- Golden sqlite snapshot with migrations up to 00000000000006 applied.
+-- Regenerate only when adding a new snapshot; existing files are frozen.
+
+CREATE TABLE __diesel_schema_migrations (
+    version VARCHAR(50) PRIMARY KEY NOT NULL,
+    run_on TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 00000000000000_create_users
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL UNIQUE,
+    password TEXT NOT NULL
+);
+
+-- 00000000000001_create_news
+CREATE TABLE news_categories (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE
+);
+
+-- 00000000000002_add_bundles
+CREATE TABLE news_bundles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    parent_bundle_id INTEGER REFERENCES news_bundles(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    UNIQUE(name, parent_bundle_id)
+);
+
+ALTER TABLE news_categories
+    ADD COLUMN bundle_id INTEGER REFERENCES news_bundles(id) ON DELETE CASCADE;
+
+CREATE INDEX idx_bundles_parent ON news_bundles(parent_bundle_id);
+CREATE INDEX idx_categories_bundle ON news_categories(bundle_id);
+
+-- 00000000000003_add_articles
+CREATE TABLE news_articles (
+    id                     INTEGER PRIMARY KEY AUTOINCREMENT,
+    category_id            INTEGER NOT NULL REFERENCES news_categories(id) ON DELETE CASCADE,
+    parent_article_id      INTEGER REFERENCES news_articles(id),
+    prev_article_id        INTEGER REFERENCES news_articles(id),
+    next_article_id        INTEGER REFERENCES news_articles(id),
+    first_child_article_id INTEGER REFERENCES news_articles(id),
+    title       TEXT    NOT NULL,
+    poster      TEXT,
+    posted_at   DATETIME NOT NULL,
+    flags       INTEGER DEFAULT 0,
+    data_flavor TEXT    DEFAULT 'text/plain',
+    data        TEXT,
+    CHECK (category_id IS NOT NULL)
+);
+
+CREATE INDEX idx_articles_category ON news_articles(category_id);
+
+-- 00000000000004_create_files
+CREATE TABLE files (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    object_key TEXT NOT NULL,
+    size INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE file_acl (
+    file_id INTEGER NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    PRIMARY KEY (file_id, user_id)
+);
+
+CREATE INDEX idx_file_acl_user_file ON file_acl (user_id, file_id);
+
+-- 00000000000005_add_bundle_name_parent_index
+CREATE INDEX idx_bundles_name_parent ON news_bundles(name, parent_bundle_id);
+
+-- 00000000000006_add_file_nodes_and_permissions
+CREATE TABLE permissions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    code INTEGER NOT NULL UNIQUE,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL
+);
+
+CREATE TABLE user_permissions (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    permission_id INTEGER NOT NULL REFERENCES permissions(id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, permission_id)
+);
+
+CREATE TABLE groups (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE user_groups (
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    group_id INTEGER NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+    PRIMARY KEY (user_id, group_id)
+);
+
+CREATE TABLE file_nodes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL CHECK (kind IN ('file', 'folder', 'alias')),
+    name TEXT NOT NULL CHECK (name <> '' AND instr(name, '/') = 0),
+    parent_id INTEGER REFERENCES file_nodes(id) ON DELETE CASCADE,
+    alias_target_id INTEGER REFERENCES file_nodes(id) ON DELETE RESTRICT,
+    object_key TEXT,
+    size INTEGER,
+    comment TEXT,
+    is_dropbox BOOLEAN NOT NULL DEFAULT 0,
+    creator_id INTEGER NOT NULL REFERENCES users(id) ON DELETE RESTRICT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (parent_id IS NULL OR parent_id <> id),
+    CHECK (alias_target_id IS NULL OR alias_target_id <> id),
+    CHECK (
+        (kind = 'file'
+         AND object_key IS NOT NULL
+         AND alias_target_id IS NULL
+         AND size IS NOT NULL
+         AND size >= 0
+         AND is_dropbox = 0)
+        OR
+        (kind = 'folder'
+         AND object_key IS NULL
+         AND alias_target_id IS NULL
+         AND size IS NULL)
+        OR
+        (kind = 'alias'
+         AND object_key IS NULL
+         AND alias_target_id IS NOT NULL
+         AND size IS NULL
+         AND is_dropbox = 0)
+    )
+);
+
+CREATE UNIQUE INDEX idx_file_nodes_root_name
+    ON file_nodes(name)
+    WHERE parent_id IS NULL;
+
+CREATE UNIQUE INDEX idx_file_nodes_child_name
+    ON file_nodes(parent_id, name)
+    WHERE parent_id IS NOT NULL;
+
+CREATE UNIQUE INDEX idx_file_nodes_object_key
+    ON file_nodes(object_key)
+    WHERE object_key IS NOT NULL;
+
+CREATE INDEX idx_file_nodes_parent_name
+    ON file_nodes(parent_id, name);
+
+CREATE INDEX idx_file_nodes_alias_target
+    ON file_nodes(alias_target_id);
+
+CREATE INDEX idx_file_nodes_creator
+    ON file_nodes(creator_id);
+
+CREATE TABLE resource_permissions (
+    resource_type TEXT NOT NULL CHECK (resource_type = 'file_node'),
+    resource_id INTEGER NOT NULL REFERENCES file_nodes(id) ON DELETE CASCADE,
+    principal_type TEXT NOT NULL CHECK (principal_type IN ('user', 'group')),
+    principal_id INTEGER NOT NULL,
+    permission_id INTEGER NOT NULL REFERENCES permissions(id) ON DELETE CASCADE,
+    PRIMARY KEY (
+        resource_type,
+        resource_id,
+        principal_type,
+        principal_id,
+        permission_id
+    )
+);
+
+CREATE INDEX idx_resource_permissions_lookup
+    ON resource_permissions(
+        resource_type,
+        principal_type,
+        principal_id,
+        permission_id,
+        resource_id
+    );
+
+CREATE INDEX idx_resource_permissions_resource
+    ON resource_permissions(resource_type, resource_id);
+
+CREATE TRIGGER validate_resource_permissions_principal_insert
+BEFORE INSERT ON resource_permissions
+FOR EACH ROW
+BEGIN
+    SELECT CASE
+        WHEN NEW.principal_type = 'user'
+             AND NOT EXISTS (
+                 SELECT 1 FROM users WHERE id = NEW.principal_id
+             )
+        THEN RAISE(ABORT, 'resource_permissions principal is not a valid user')
+        WHEN NEW.principal_type = 'group'
+             AND NOT EXISTS (
+                 SELECT 1 FROM groups WHERE id = NEW.principal_id
+             )
+        THEN RAISE(ABORT, 'resource_permissions principal is not a valid group')
+    END;
+END;
+
+CREATE TRIGGER validate_resource_permissions_principal_update
+BEFORE UPDATE ON resource_permissions
+FOR EACH ROW
+BEGIN
+    SELECT CASE
+        WHEN NEW.principal_type = 'user'
+             AND NOT EXISTS (
+                 SELECT 1 FROM users WHERE id = NEW.principal_id
+             )
+        THEN RAISE(ABORT, 'resource_permissions principal is not a valid user')
+        WHEN NEW.principal_type = 'group'
+             AND NOT EXISTS (
+                 SELECT 1 FROM groups WHERE id = NEW.principal_id
+             )
+        THEN RAISE(ABORT, 'resource_permissions principal is not a valid group')
+    END;
+END;
+
+CREATE TRIGGER cleanup_resource_permissions_after_user_delete
+AFTER DELETE ON users
+FOR EACH ROW
+BEGIN
+    DELETE FROM resource_permissions
+    WHERE principal_type = 'user'
+      AND principal_id = OLD.id;
+END;
+
+CREATE TRIGGER cleanup_resource_permissions_after_group_delete
+AFTER DELETE ON groups
+FOR EACH ROW
+BEGIN
+    DELETE FROM resource_permissions
+    WHERE principal_type = 'group'
+      AND principal_id = OLD.id;
+END;
+
+INSERT INTO __diesel_schema_migrations (version) VALUES
+    ('00000000000000'),
+    ('00000000000001'),
+    ('00000000000002'),
+    ('00000000000003'),
+    ('00000000000004'),
+    ('00000000000005'),
+    ('00000000000006');
+
+-- Seed data
+INSERT INTO users (id, username, password) VALUES (1, 'alice', '$argon2id$v=19$m=19456,t=2,p=1$c2VlZHNhbHQ$placeholderhash');
+INSERT INTO news_bundles (id, parent_bundle_id, name) VALUES (1, NULL, 'Announcements');
+INSERT INTO news_bundles (id, parent_bundle_id, name) VALUES (2, 1, 'Archive');
+INSERT INTO news_categories (id, name, bundle_id) VALUES (1, 'Lobby', NULL);
+INSERT INTO news_categories (id, name, bundle_id) VALUES (2, 'Releases', 1);
+INSERT INTO news_categories (id, name, bundle_id) VALUES (3, 'Old', 2);
+INSERT INTO news_articles (id, category_id, parent_article_id, prev_article_id, next_article_id, first_child_article_id, title, poster, posted_at, flags, data_flavor, data) VALUES (1, 2, NULL, NULL, NULL, NULL, 'Welcome', 'alice', '2024-03-01 09:30:00', 0, 'text/plain', 'First post');
+INSERT INTO news_articles (id, category_id, parent_article_id, prev_article_id, next_article_id, first_child_article_id, title, poster, posted_at, flags, data_flavor, data) VALUES (2, 2, NULL, 1, NULL, NULL, 'Second', 'alice', '2024-03-02 10:00:00', 0, 'text/plain', 'Follow-up');
+UPDATE news_articles SET next_article_id = 2 WHERE id = 1;
+INSERT INTO files (id, name, object_key, size) VALUES (1, 'readme.txt', 'objects/readme', 12);
+INSERT INTO file_acl (file_id, user_id) VALUES (1, 1);
+
+INSERT INTO permissions (id, code, name, description) VALUES (1, 2, 'download_file', 'Download files');
+INSERT INTO groups (id, name) VALUES (1, 'everyone');
+INSERT INTO user_groups (user_id, group_id) VALUES (1, 1);
+INSERT INTO file_nodes (id, kind, name, parent_id, alias_target_id, object_key, size, comment, is_dropbox, creator_id) VALUES (1, 'file', 'readme.txt', NULL, NULL, 'objects/readme', 12, NULL, 0, 1);
+INSERT INTO resource_permissions (resource_type, resource_id, principal_type, principal_id, permission_id) VALUES ('file_node', 1, 'user', 1, 1);
@@ -0,0 +1,117 @@
+//! Golden migration tests that upgrade frozen schema snapshots.
+//!
+//! Each file under `tests/migration_fixtures/<backend>/` captures the schema,
+//! the `__diesel_schema_migrations` ledger, and representative data exactly as
+//! a released server left them. The tests load a snapshot, run
+//! `apply_migrations`, and assert that domain queries still surface the seeded
+//! content, so upgrades for long-lived deployments stay lossless.
+
+use diesel_async::SimpleAsyncConnection;
+use mxd::db::{
+    DbConnection,
+    apply_migrations,
+    get_article,
+    get_user_by_name,
+    list_article_titles,
+    list_names_at_path,
+    list_visible_root_file_nodes_for_user,
+};
+use rstest::rstest;
+use test_util::AnyError;
+
+const RELEASES_PATH: &str = "Announcements/Releases";
+
+async fn listed_names(
+    conn: &mut DbConnection,
+    path: &str,
+) -> Result<Vec<String>, AnyError> {
+    let rows = list_names_at_path(conn, path).await?;
+    Ok(rows.into_iter().map(|row| row.name).collect())
+}
+
+/// Assert that every row seeded by the golden snapshots survives migration.
+#[expect(clippy::panic_in_result_fn, reason = "test assertions")]
+async fn assert_seeded_content(conn: &mut DbConnection) -> Result<(), AnyError> {
+    let alice = get_user_by_name(conn, "alice")
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("seeded user missing after upgrade"))?;
+    assert_eq!(alice.id, 1);
+
+    let mut root = listed_names(conn, "").await?;
+    root.sort_unstable();
+    assert_eq!(root, vec!["Announcements", "Lobby"]);
+
+    let mut nested = listed_names(conn, "Announcements").await?;
+    nested.sort_unstable();
+    assert_eq!(nested, vec!["Archive", "Releases"]);
+
+    let titles = list_article_titles(conn, RELEASES_PATH).await?;
+    assert_eq!(titles, vec!["Welcome", "Second"]);
+
+    let article = get_article(conn, RELEASES_PATH, 1)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("seeded article missing after upgrade"))?;
+    assert_eq!(article.data.as_deref(), Some("First post"));
+    assert_eq!(article.poster.as_deref(), Some("alice"));
+    assert_eq!(article.next_article_id, Some(2));
+
+    let files = list_visible_root_file_nodes_for_user(conn, alice.id).await?;
+    let names: Vec<_> = files.iter().map(|node| node.name.as_str()).collect();
+    assert_eq!(names, vec!["readme.txt"]);
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    //! Golden upgrades against in-memory `SQLite` databases.
+
+    use diesel_async::AsyncConnection;
+
+    use super::*;
+
+    #[rstest]
+    #[case::news_bundles(include_str!(
+        "migration_fixtures/sqlite/00000000000005_news_bundles.sql"
+    ))]
+    #[case::file_nodes(include_str!("migration_fixtures/sqlite/00000000000006_file_nodes.sql"))]
+    #[tokio::test]
+    async fn snapshot_upgrades_preserve_content(#[case] snapshot: &str) -> Result<(), AnyError> {
+        let mut conn = DbConnection::establish(":memory:").await?;
+        conn.batch_execute(snapshot).await?;
+        apply_migrations(&mut conn, "", None).await?;
+        assert_seeded_content(&mut conn).await
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    //! Golden upgrades against throwaway `PostgreSQL` databases.
+
+    use diesel_async::AsyncConnection;
+    use test_util::PostgresTestDb;
+
+    use super::*;
+
+    #[rstest]
+    #[case::news_bundles(include_str!(
+        "migration_fixtures/postgres/00000000000005_news_bundles.sql"
+    ))]
+    #[case::file_nodes(include_str!(
+        "migration_fixtures/postgres/00000000000006_file_nodes.sql"
+    ))]
+    #[tokio::test]
+    async fn snapshot_upgrades_preserve_content(#[case] snapshot: &str) -> Result<(), AnyError> {
+        let db = match PostgresTestDb::new_async().await {
+            Ok(db) => db,
+            Err(err) if err.is_unavailable() => {
+                tracing::warn!("skipping test: {err}");
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let mut conn = DbConnection::establish(db.url.as_ref()).await?;
+        conn.batch_execute(snapshot).await?;
+        apply_migrations(&mut conn, db.url.as_ref(), None).await?;
+        assert_seeded_content(&mut conn).await
+    }
+}
@@ -13,8 +13,8 @@ use std::{
 use diesel::prelude::*;
 use diesel_async::{AsyncConnection, RunQueryDsl};
 use mxd::{
-    commands::NEWS_ERR_PATH_UNSUPPORTED,
-    db::{DbConnection, create_category},
+    commands::NEWS_ERR_PATH_NOT_FOUND,
+    db::{DbConnection, PRIMARY_NEWS_ROOT, create_category},
     field_id::FieldId,
     models::NewCategory,
     transaction::{FrameHeader, Transaction, decode_params, encode_params},
@@ -122,7 +122,7 @@ fn verify_article_titles(db_url: &str, expected: &[&str]) -> Result<(), AnyError
 
 #[expect(clippy::panic_in_result_fn, reason = "test assertions")]
 #[test]
-fn list_news_articles_invalid_path() -> Result<(), AnyError> {
+fn list_news_articles_missing_path() -> Result<(), AnyError> {
     let Some(server) = common::start_server_or_skip(|db| {
         with_db(db, |conn| {
             Box::pin(async move {
@@ -138,6 +138,7 @@ fn list_news_articles_invalid_path() -> Result<(), AnyError> {
                         add_sn: None,
                         delete_sn: None,
                         created_at: None,
+                        root_id: PRIMARY_NEWS_ROOT,
                     },
                 )
                 .await?;
@@ -159,7 +160,7 @@ fn list_news_articles_invalid_path() -> Result<(), AnyError> {
     )?;
 
     let (hdr, _) = receive_transaction(&mut stream)?;
-    assert_eq!(hdr.error, NEWS_ERR_PATH_UNSUPPORTED);
+    assert_eq!(hdr.error, NEWS_ERR_PATH_NOT_FOUND);
     Ok(())
 }
 
@@ -251,6 +252,7 @@ fn post_news_article_root() -> Result<(), AnyError> {
                         add_sn: None,
                         delete_sn: None,
                         created_at: None,
+                        root_id: PRIMARY_NEWS_ROOT,
                     },
                 )
                 .await?;
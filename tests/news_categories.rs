@@ -2,7 +2,7 @@
 //!
 //! Validates that the server correctly returns category hierarchies at various
 //! paths (root, nested bundles, trailing slashes) and handles edge cases such
-//! as invalid paths and empty databases.
+//! as missing paths and empty databases.
 
 use std::{
     io::{Read, Write},
@@ -11,8 +11,8 @@ use std::{
 
 use diesel_async::AsyncConnection;
 use mxd::{
-    commands::NEWS_ERR_PATH_UNSUPPORTED,
-    db::{DbConnection, apply_migrations, create_category},
+    commands::NEWS_ERR_PATH_NOT_FOUND,
+    db::{DbConnection, PRIMARY_NEWS_ROOT, apply_migrations, create_category},
     field_id::FieldId,
     models::NewCategory,
     transaction::{FrameHeader, Transaction, decode_params, encode_params},
@@ -138,17 +138,16 @@ mod rstest_tests {
     }
 }
 
-/// Tests that requesting news categories with an invalid path returns the expected unsupported path
-/// error.
+/// Tests that requesting news categories under a missing path returns the path-not-found error.
 ///
-/// Sets up a database with a single category, sends a transaction with an invalid path parameter,
-/// and asserts that the server responds with the `NEWS_ERR_PATH_UNSUPPORTED` error code.
+/// Sets up a database with a single category, sends a transaction with a path naming no bundle,
+/// and asserts that the server responds with the `NEWS_ERR_PATH_NOT_FOUND` error code.
 ///
 /// # Returns
 /// Returns `Ok(())` if the test passes; otherwise, returns an error if any step fails.
 #[expect(clippy::panic_in_result_fn, reason = "test assertions")]
 #[test]
-fn list_news_categories_invalid_path() -> Result<(), AnyError> {
+fn list_news_categories_missing_path() -> Result<(), AnyError> {
     let Some(server) = common::start_server_or_skip(|db: DatabaseUrl| {
         let rt = tokio::runtime::Runtime::new()?;
         rt.block_on(async {
@@ -167,6 +166,7 @@ fn list_news_categories_invalid_path() -> Result<(), AnyError> {
                     add_sn: None,
                     delete_sn: None,
                     created_at: None,
+                    root_id: PRIMARY_NEWS_ROOT,
                 },
             )
             .await?;
@@ -179,7 +179,7 @@ fn list_news_categories_invalid_path() -> Result<(), AnyError> {
 
     let addr = server.bind_addr();
     let (hdr, _) = list_categories(addr, Some("some/path"))?;
-    assert_eq!(hdr.error, NEWS_ERR_PATH_UNSUPPORTED);
+    assert_eq!(hdr.error, NEWS_ERR_PATH_NOT_FOUND);
     Ok(())
 }
 
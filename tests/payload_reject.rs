@@ -54,7 +54,7 @@ fn download_banner_reject_payload() -> Result<(), AnyError> {
     stream.set_write_timeout(Some(Duration::from_secs(20)))?;
     handshake(&mut stream)?;
 
-    let params = encode_params(&[(FieldId::Other(1), b"bogus".as_ref())])?;
+    let params = encode_params(&[(FieldId::Unknown(1), b"bogus".as_ref())])?;
     let size = u32::try_from(params.len()).expect("params fit in u32");
     let header = FrameHeader {
         flags: 0,
@@ -87,7 +87,7 @@ fn user_name_list_reject_payload() -> Result<(), AnyError> {
     let mut stream = TcpStream::connect(addr)?;
     handshake(&mut stream)?;
 
-    let params = encode_params(&[(FieldId::Other(1), b"bogus".as_ref())])?;
+    let params = encode_params(&[(FieldId::Unknown(1), b"bogus".as_ref())])?;
     let size = u32::try_from(params.len()).expect("params fit in u32");
     let header = FrameHeader {
         flags: 0,
@@ -0,0 +1,69 @@
+//! Integration tests for pushed frames observed through `CaptureClient`.
+//!
+//! Only the Wireframe runtime delivers server-initiated frames, so these
+//! tests always launch it regardless of `MXD_TEST_RUNTIME`.
+
+use std::time::Duration;
+
+use mxd::{field_id::FieldId, transaction::decode_params_map, transaction_type::TransactionType};
+use test_util::{AnyError, CaptureClient, ServerRuntime, setup_login_db};
+
+#[expect(
+    dead_code,
+    reason = "these tests pin the runtime rather than reading the environment"
+)]
+mod common;
+
+const PUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[test]
+fn peers_are_notified_when_a_user_comes_online() -> Result<(), AnyError> {
+    let Some(server) = common::start_runtime_or_skip(ServerRuntime::Wireframe, setup_login_db)?
+    else {
+        return Ok(());
+    };
+    let mut watcher = CaptureClient::connect(server.bind_addr())?;
+    watcher.login("alice", "secret")?;
+    let mut arrival = CaptureClient::connect(server.bind_addr())?;
+    arrival.login("alice", "secret")?;
+
+    let notice = watcher.expect_push(TransactionType::NotifyChangeUser, PUSH_TIMEOUT)?;
+    let params = decode_params_map(&notice.payload)?;
+    if !params.contains_key(&FieldId::UserId) {
+        return Err(anyhow::anyhow!("presence notice lacks a user id"));
+    }
+    Ok(())
+}
+
+#[test]
+fn chat_is_pushed_to_every_online_client() -> Result<(), AnyError> {
+    let Some(server) = common::start_runtime_or_skip(ServerRuntime::Wireframe, setup_login_db)?
+    else {
+        return Ok(());
+    };
+    let mut listener = CaptureClient::connect(server.bind_addr())?;
+    listener.login("alice", "secret")?;
+    let mut speaker = CaptureClient::connect(server.bind_addr())?;
+    speaker.login("alice", "secret")?;
+
+    let reply = speaker.request(
+        TransactionType::SendChat,
+        &[(FieldId::Data, b"hello".as_slice())],
+    )?;
+    if reply.header.error != 0 {
+        return Err(anyhow::anyhow!("chat rejected with {}", reply.header.error));
+    }
+    for client in [&mut listener, &mut speaker] {
+        let line = client.expect_push(TransactionType::ChatMsg, PUSH_TIMEOUT)?;
+        let params = decode_params_map(&line.payload)?;
+        let text = params
+            .get(&FieldId::Data)
+            .and_then(|values| values.first())
+            .ok_or_else(|| anyhow::anyhow!("chat push lacks text"))?;
+        if !text.ends_with(b"hello") {
+            return Err(anyhow::anyhow!("unexpected chat text {text:?}"));
+        }
+    }
+    listener.expect_no_push(TransactionType::ChatMsg, Duration::from_millis(200))?;
+    Ok(())
+}
@@ -17,7 +17,10 @@ use mxd::{
     handler::Session,
     privileges::Privileges,
     schema::users::dsl as users_dsl,
-    server::outbound::{NoopOutboundMessaging, OutboundConnectionId},
+    server::{
+        outbound::{NoopOutboundMessaging, OutboundConnectionId},
+        settings::ServerSettings,
+    },
     transaction::{Transaction, parse_transaction},
     transaction_type::TransactionType,
     wireframe::{
@@ -127,6 +130,7 @@ impl PrivilegeWorld {
                 messaging: &messaging,
                 presence: &self.presence,
                 presence_connection_id: OutboundConnectionId::new(1),
+                settings: &ServerSettings::default(),
             },
         ));
         self.session.replace(session);
@@ -0,0 +1,101 @@
+//! TLS termination integration tests.
+//!
+//! Each test runs against every runtime the build can launch: the legacy
+//! runtime terminates TLS per connection and the Wireframe runtime in front
+//! of its listener, and both must then serve the Hotline handshake and login
+//! unchanged inside the encrypted stream.
+
+use mxd::server::tls::HOTLINE_ALPN;
+use test_util::{
+    AnyError,
+    DatabaseUrl,
+    ServerRuntime,
+    TestServer,
+    TlsFixture,
+    ensure_server_binary_env,
+    for_each_runtime,
+    handshake,
+    login,
+    setup_login_db,
+};
+
+/// Start `runtime` over TLS with a login fixture, or `None` when embedded
+/// `PostgreSQL` is unavailable.
+fn start_tls_server(
+    runtime: ServerRuntime,
+    tls: &TlsFixture,
+) -> Result<Option<TestServer>, AnyError> {
+    ensure_server_binary_env(env!("CARGO_BIN_EXE_mxd-wireframe-server"))?;
+    #[cfg(feature = "legacy-networking")]
+    test_util::ensure_runtime_binary_env(ServerRuntime::Legacy, env!("CARGO_BIN_EXE_mxd"))?;
+    match TestServer::start_with_tls("./Cargo.toml", runtime, tls, |db| {
+        setup_login_db(DatabaseUrl::from(db))
+    }) {
+        Ok(server) => Ok(Some(server)),
+        #[cfg(feature = "postgres")]
+        Err(error)
+            if error
+                .downcast_ref::<test_util::postgres::PostgresTestDbError>()
+                .is_some_and(test_util::postgres::PostgresTestDbError::is_unavailable) =>
+        {
+            tracing::warn!("skipping test: {error}");
+            Ok(None)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+#[test]
+fn clients_log_in_over_tls_with_alpn() -> Result<(), AnyError> {
+    for_each_runtime(|runtime| {
+        let tls = TlsFixture::new()?;
+        let Some(server) = start_tls_server(runtime, &tls)? else {
+            return Ok(());
+        };
+        let mut client = tls.connect(server.bind_addr())?;
+
+        if client.conn.alpn_protocol() != Some(HOTLINE_ALPN) {
+            return Err(anyhow::anyhow!(
+                "server did not negotiate the Hotline ALPN id"
+            ));
+        }
+        handshake(&mut client)?;
+        login(&mut client, "alice", "secret")?;
+        Ok(())
+    })
+}
+
+#[test]
+fn proxies_log_in_over_tls_without_alpn() -> Result<(), AnyError> {
+    for_each_runtime(|runtime| {
+        let tls = TlsFixture::new()?.without_alpn();
+        let Some(server) = start_tls_server(runtime, &tls)? else {
+            return Ok(());
+        };
+        let mut client = tls.connect_offering(server.bind_addr(), &[b"x-hotline-proxy"])?;
+
+        if client.conn.alpn_protocol().is_some() {
+            return Err(anyhow::anyhow!("server negotiated ALPN in no-ALPN mode"));
+        }
+        handshake(&mut client)?;
+        login(&mut client, "alice", "secret")?;
+        Ok(())
+    })
+}
+
+#[test]
+fn clients_offering_other_protocols_are_refused() -> Result<(), AnyError> {
+    for_each_runtime(|runtime| {
+        let tls = TlsFixture::new()?;
+        let Some(server) = start_tls_server(runtime, &tls)? else {
+            return Ok(());
+        };
+
+        if tls.connect_offering(server.bind_addr(), &[b"h2"]).is_ok() {
+            return Err(anyhow::anyhow!(
+                "server accepted a client without the Hotline ALPN id"
+            ));
+        }
+        Ok(())
+    })
+}
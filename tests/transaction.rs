@@ -268,11 +268,28 @@ fn display_field_and_type() {
     use field_id::FieldId;
     use transaction_type::TransactionType;
     assert_eq!(FieldId::Login.to_string(), "Login");
-    assert_eq!(FieldId::Other(42).to_string(), "Other(42)");
+    assert_eq!(FieldId::Unknown(42).to_string(), "Unknown(42)");
     assert_eq!(TransactionType::Login.to_string(), "Login");
     assert_eq!(TransactionType::Other(99).to_string(), "Other(99)");
 }
 
+#[test]
+fn unknown_fields_survive_decode_encode_round_trip() {
+    use field_id::FieldId;
+    let payload = encode_params(&[
+        (FieldId::Login, b"alice".as_ref()),
+        (FieldId::Unknown(4242), [0xde, 0xad].as_ref()),
+        (FieldId::Unknown(65_000), b"".as_ref()),
+    ])
+    .unwrap();
+
+    let decoded = decode_params(&payload).expect("decode");
+
+    assert_eq!(decoded[1].0, FieldId::Unknown(4242));
+    assert_eq!(decoded[2].0, FieldId::Unknown(65_000));
+    assert_eq!(encode_params(&decoded).unwrap(), payload);
+}
+
 #[test]
 fn duplicate_news_category_fields_allowed() {
     use field_id::FieldId;
@@ -309,3 +326,55 @@ fn duplicate_news_category_fields_allowed() {
         .collect();
     assert_eq!(names, ["General", "Updates"]);
 }
+
+/// Build a frame whose payload holds `count` empty fields with distinct ids.
+fn tiny_fields_frame(count: u16) -> Vec<u8> {
+    let mut payload = count.to_be_bytes().to_vec();
+    for id in 1..=count {
+        payload.extend_from_slice(&id.to_be_bytes());
+        payload.extend_from_slice(&0u16.to_be_bytes());
+    }
+    let payload_len =
+        u32::try_from(payload.len()).expect("payload length fits within the 32-bit header field");
+    let header = FrameHeader {
+        flags: 0,
+        is_reply: 0,
+        ty: 9,
+        id: 6,
+        error: 0,
+        total_size: payload_len,
+        data_size: payload_len,
+    };
+    Transaction { header, payload }.to_bytes()
+}
+
+/// Fuzz regression: a payload claiming tens of thousands of four-byte fields
+/// must be refused before any of them is decoded.
+#[rstest]
+#[case(u16::MAX)]
+#[case(u16::try_from(DEFAULT_MAX_PARAM_COUNT + 1).expect("cap fits in u16"))]
+fn parse_transaction_rejects_excess_params(#[case] count: u16) {
+    let frame = tiny_fields_frame(count);
+
+    match parse_transaction(&frame).unwrap_err() {
+        TransactionError::TooManyParams(declared) => assert_eq!(declared, count),
+        e => panic!("unexpected {e:?}"),
+    }
+    assert!(matches!(
+        PayloadLimits::new().check_params(&frame[HEADER_LEN..]),
+        Err(TransactionError::TooManyParams(_))
+    ));
+}
+
+#[test]
+fn parse_transaction_accepts_params_up_to_the_cap() {
+    let count = u16::try_from(DEFAULT_MAX_PARAM_COUNT).expect("cap fits in u16");
+    let frame = tiny_fields_frame(count);
+
+    let tx = parse_transaction(&frame).expect("parse");
+
+    assert_eq!(
+        decode_params(&tx.payload).expect("decode").len(),
+        DEFAULT_MAX_PARAM_COUNT
+    );
+}
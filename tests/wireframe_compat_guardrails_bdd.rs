@@ -16,7 +16,10 @@ use mxd::{
     db::DbPool,
     field_id::FieldId,
     handler::Session,
-    server::outbound::{NoopOutboundMessaging, OutboundConnectionId},
+    server::{
+        outbound::{NoopOutboundMessaging, OutboundConnectionId},
+        settings::ServerSettings,
+    },
     transaction::{Transaction, decode_params, parse_transaction},
     transaction_type::TransactionType,
     wireframe::{
@@ -122,6 +125,7 @@ impl GuardrailWorld {
                 messaging: &messaging,
                 presence: &self.presence,
                 presence_connection_id: OutboundConnectionId::new(1),
+                settings: &ServerSettings::default(),
             },
         ));
         self.session.replace(session);
@@ -103,7 +103,11 @@ impl MetadataWorld {
         .workers(1)
         .with_preamble::<HotlinePreamble>();
 
-        let handshake_server = handshake::install(app_server, Duration::from_millis(200));
+        let handshake_server = handshake::install(
+            app_server,
+            Duration::from_millis(200),
+            handshake::Admission::Direct(Arc::default()),
+        );
         let bind_addr: SocketAddr = match "127.0.0.1:0".parse() {
             Ok(bind_addr) => bind_addr,
             Err(err) => panic!("failed to parse bind address: {err}"),
@@ -14,9 +14,9 @@ use mxd::{
     transaction::{
         FrameHeader,
         HEADER_LEN,
-        IO_TIMEOUT,
         MAX_FRAME_DATA,
         MAX_PAYLOAD_SIZE,
+        READ_TIMEOUT,
         encode_params,
     },
     transaction_type::TransactionType,
@@ -202,7 +202,7 @@ fn stalled_fragmented_request_is_disconnected_when_continuation_resumes() -> Res
     };
 
     stream.write_all(first_fragment)?;
-    sleep(IO_TIMEOUT + Duration::from_millis(250));
+    sleep(READ_TIMEOUT + Duration::from_millis(250));
 
     match stream.write_all(second_fragment) {
         Ok(()) => assert_connection_closed(&mut stream),
@@ -12,50 +12,14 @@ use test_util::AnyError;
 #[path = "wireframe_presence_bdd/wireframe_presence_support.rs"]
 mod wireframe_presence_support;
 
-use wireframe_presence_support::{PresenceWorld, RequestSpec};
-
-fn decode_protocol_u32(bytes: &[u8]) -> Result<u32, AnyError> {
-    match bytes.len() {
-        2 => Ok(u32::from(u16::from_be_bytes(bytes.try_into()?))),
-        4 => Ok(u32::from_be_bytes(bytes.try_into()?)),
-        _ => Err(anyhow!("unexpected integer width {}", bytes.len())),
-    }
-}
-
-fn decode_user_name_with_info(bytes: &[u8]) -> Result<(u16, String), AnyError> {
-    let user_id = decode_u16_slice(bytes, 0..2)?;
-    let name_len = usize::from(decode_u16_slice(bytes, 6..8)?);
-    let name_bytes = bytes
-        .get(8..8 + name_len)
-        .ok_or_else(|| anyhow!("field 300 name exceeds payload length"))?;
-    let name = std::str::from_utf8(name_bytes)?.to_owned();
-    Ok((user_id, name))
-}
-
-fn decode_u16_slice(bytes: &[u8], range: std::ops::Range<usize>) -> Result<u16, AnyError> {
-    let slice = bytes
-        .get(range)
-        .ok_or_else(|| anyhow!("payload shorter than expected"))?;
-    Ok(u16::from_be_bytes(slice.try_into()?))
-}
-
-fn find_param(params: &[(FieldId, Vec<u8>)], field_id: FieldId) -> Result<&[u8], AnyError> {
-    params
-        .iter()
-        .find(|(candidate, _)| *candidate == field_id)
-        .map(|(_, bytes)| bytes.as_slice())
-        .ok_or_else(|| anyhow!("missing {field_id}"))
-}
-
-type FieldParam = (FieldId, Vec<u8>);
-
-fn read_u32_param(params: &[(FieldId, Vec<u8>)], field_id: FieldId) -> Result<u32, AnyError> {
-    decode_protocol_u32(find_param(params, field_id)?)
-}
-
-fn read_string_param(params: &[(FieldId, Vec<u8>)], field_id: FieldId) -> Result<String, AnyError> {
-    Ok(std::str::from_utf8(find_param(params, field_id)?)?.to_owned())
-}
+use wireframe_presence_support::{
+    FieldParam,
+    PresenceWorld,
+    RequestSpec,
+    decode_user_name_with_info,
+    read_string_param,
+    read_u32_param,
+};
 
 #[fixture]
 #[rustfmt::skip]
@@ -136,6 +100,68 @@ fn when_update_user_info(
     )
 }
 
+#[when("client \"{label}\" logs out")]
+fn when_log_out(world: &PresenceWorld, label: String) -> Result<(), AnyError> {
+    if world.is_skipped() {
+        return Ok(());
+    }
+    world.send(
+        &label,
+        RequestSpec {
+            ty: TransactionType::Logout,
+            id: 13,
+            params: &[],
+        },
+    )
+}
+
+#[when("client \"{label}\" sets the away message \"{message}\"")]
+fn when_set_away_message(
+    world: &PresenceWorld,
+    label: String,
+    message: String,
+) -> Result<(), AnyError> {
+    if world.is_skipped() {
+        return Ok(());
+    }
+    let options = 4u16.to_be_bytes();
+    world.send(
+        &label,
+        RequestSpec {
+            ty: TransactionType::SetClientUserInfo,
+            id: 14,
+            params: &[
+                (FieldId::Options, options.as_ref()),
+                (FieldId::AutoResponse, message.as_bytes()),
+            ],
+        },
+    )
+}
+
+#[when("client \"{label}\" sends the private message \"{text}\" to user {user_id}")]
+fn when_send_private_message(
+    world: &PresenceWorld,
+    label: String,
+    text: String,
+    user_id: u16,
+) -> Result<(), AnyError> {
+    if world.is_skipped() {
+        return Ok(());
+    }
+    let target = user_id.to_be_bytes();
+    world.send(
+        &label,
+        RequestSpec {
+            ty: TransactionType::SendInstantMsg,
+            id: 15,
+            params: &[
+                (FieldId::UserId, target.as_ref()),
+                (FieldId::Data, text.as_bytes()),
+            ],
+        },
+    )
+}
+
 #[when("client \"{label}\" disconnects")]
 fn when_disconnect(world: &PresenceWorld, label: String) -> Result<(), AnyError> {
     if world.is_skipped() {
@@ -240,6 +266,68 @@ fn then_notify_delete_user(
     Ok(())
 }
 
+#[then("client \"{label}\" receives a notify change user for user {user_id} with flags {flags}")]
+fn then_notify_change_user_flags(
+    world: &PresenceWorld,
+    label: String,
+    user_id: u32,
+    flags: u32,
+) -> Result<(), AnyError> {
+    if world.is_skipped() {
+        return Ok(());
+    }
+    let params = world.observe_params(&label, TransactionType::NotifyChangeUser)?;
+    let actual = (
+        read_u32_param(&params, FieldId::UserId)?,
+        read_u32_param(&params, FieldId::UserFlags)?,
+    );
+    if actual != (user_id, flags) {
+        return Err(anyhow!(
+            "expected user {user_id} with flags {flags}, got {actual:?}"
+        ));
+    }
+    Ok(())
+}
+
+#[then("client \"{label}\" receives the message \"{text}\" from user {user_id}")]
+fn then_receives_message(
+    world: &PresenceWorld,
+    label: String,
+    text: String,
+    user_id: u32,
+) -> Result<(), AnyError> {
+    if world.is_skipped() {
+        return Ok(());
+    }
+    let params = world.observe_params(&label, TransactionType::ServerMsg)?;
+    let actual = (
+        read_u32_param(&params, FieldId::UserId)?,
+        read_string_param(&params, FieldId::Data)?,
+    );
+    if actual != (user_id, text.clone()) {
+        return Err(anyhow!(
+            "expected {text:?} from user {user_id}, got {actual:?}"
+        ));
+    }
+    Ok(())
+}
+
+#[then("the reply has error {error}")]
+fn then_reply_has_error(world: &PresenceWorld, error: u32) -> Result<(), AnyError> {
+    if world.is_skipped() {
+        return Ok(());
+    }
+    world.with_last_transaction(|transaction| {
+        if transaction.header.error != error {
+            return Err(anyhow!(
+                "expected reply error {error}, got {}",
+                transaction.header.error
+            ));
+        }
+        Ok(())
+    })
+}
+
 #[then("the reply lists online users \"{first}\" and \"{second}\"")]
 fn then_reply_lists_online_users(
     world: &PresenceWorld,
@@ -17,14 +17,17 @@ use mxd::{
     field_id::FieldId,
     handler::Session,
     models::NewUser,
-    server::outbound::{
-        OutboundConnectionId,
-        OutboundError,
-        OutboundMessaging,
-        OutboundPriority,
-        OutboundTarget,
+    server::{
+        outbound::{
+            OutboundConnectionId,
+            OutboundError,
+            OutboundMessaging,
+            OutboundPriority,
+            OutboundTarget,
+        },
+        settings::ServerSettings,
     },
-    transaction::{Transaction, parse_transaction},
+    transaction::{Transaction, decode_params, parse_transaction},
     transaction_type::TransactionType,
     users::hash_password,
     wireframe::{
@@ -124,6 +127,7 @@ impl PresenceWorld {
                 messaging: &self.messaging,
                 presence: &self.presence,
                 presence_connection_id: connection_id_for_label(label),
+                settings: &ServerSettings::default(),
             },
         ));
         let parsed = parse_transaction(&reply).map_err(|error| error.to_string());
@@ -166,6 +170,23 @@ impl PresenceWorld {
             .ok_or_else(|| anyhow!("missing queued notification for {label}"))
     }
 
+    /// Take the next push queued for `label`, which must be of type `ty`,
+    /// and decode its parameters.
+    pub(super) fn observe_params(
+        &self,
+        label: &str,
+        ty: TransactionType,
+    ) -> Result<Vec<(FieldId, Vec<u8>)>, AnyError> {
+        let notification = self.observe_notification(label)?;
+        if notification.header.ty != u16::from(ty) {
+            return Err(anyhow!(
+                "expected {ty}, got transaction {}",
+                notification.header.ty
+            ));
+        }
+        Ok(decode_params(&notification.payload)?)
+    }
+
     pub(super) fn with_last_transaction<T>(
         &self,
         f: impl FnOnce(&Transaction) -> Result<T, AnyError>,
@@ -265,3 +286,52 @@ fn setup_presence_db(db: DatabaseUrl) -> Result<(), AnyError> {
         })
     })
 }
+
+fn decode_protocol_u32(bytes: &[u8]) -> Result<u32, AnyError> {
+    match bytes.len() {
+        2 => Ok(u32::from(u16::from_be_bytes(bytes.try_into()?))),
+        4 => Ok(u32::from_be_bytes(bytes.try_into()?)),
+        _ => Err(anyhow!("unexpected integer width {}", bytes.len())),
+    }
+}
+
+pub(super) fn decode_user_name_with_info(bytes: &[u8]) -> Result<(u16, String), AnyError> {
+    let user_id = decode_u16_slice(bytes, 0..2)?;
+    let name_len = usize::from(decode_u16_slice(bytes, 6..8)?);
+    let name_bytes = bytes
+        .get(8..8 + name_len)
+        .ok_or_else(|| anyhow!("field 300 name exceeds payload length"))?;
+    let name = std::str::from_utf8(name_bytes)?.to_owned();
+    Ok((user_id, name))
+}
+
+fn decode_u16_slice(bytes: &[u8], range: std::ops::Range<usize>) -> Result<u16, AnyError> {
+    let slice = bytes
+        .get(range)
+        .ok_or_else(|| anyhow!("payload shorter than expected"))?;
+    Ok(u16::from_be_bytes(slice.try_into()?))
+}
+
+fn find_param(params: &[(FieldId, Vec<u8>)], field_id: FieldId) -> Result<&[u8], AnyError> {
+    params
+        .iter()
+        .find(|(candidate, _)| *candidate == field_id)
+        .map(|(_, bytes)| bytes.as_slice())
+        .ok_or_else(|| anyhow!("missing {field_id}"))
+}
+
+pub(super) type FieldParam = (FieldId, Vec<u8>);
+
+pub(super) fn read_u32_param(
+    params: &[(FieldId, Vec<u8>)],
+    field_id: FieldId,
+) -> Result<u32, AnyError> {
+    decode_protocol_u32(find_param(params, field_id)?)
+}
+
+pub(super) fn read_string_param(
+    params: &[(FieldId, Vec<u8>)],
+    field_id: FieldId,
+) -> Result<String, AnyError> {
+    Ok(std::str::from_utf8(find_param(params, field_id)?)?.to_owned())
+}
@@ -157,7 +157,7 @@ fn oversized_params() -> Result<Vec<(FieldId, Vec<u8>)>, String> {
     (0..params_needed)
         .map(|idx| {
             let raw = u16::try_from(9000 + idx).map_err(|_| "field id overflows u16".to_owned())?;
-            Ok((FieldId::Other(raw), vec![0u8; per_param_len]))
+            Ok((FieldId::Unknown(raw), vec![0u8; per_param_len]))
         })
         .collect::<Result<Vec<_>, String>>()
 }
@@ -278,7 +278,7 @@ fn given_parameter_transaction(world: &EncodingWorld, count: usize) {
 
 #[given("a parameter transaction with a {size}-byte field value")]
 fn given_large_parameter_transaction(world: &EncodingWorld, size: usize) {
-    world.set_params(vec![(FieldId::Other(999), vec![0u8; size])]);
+    world.set_params(vec![(FieldId::Unknown(999), vec![0u8; size])]);
 }
 
 #[given("a transaction with mismatched header and payload sizes")]
@@ -9,6 +9,7 @@ mod config;
 mod harness;
 mod hx_client;
 mod policy;
+mod screen;
 mod server_binary;
 
 pub use config::{
@@ -38,4 +39,12 @@ pub use policy::{
     ValidatorRunPolicy,
     ValidatorRunPolicyError,
 };
+pub use screen::{
+    SCREEN_COLUMNS,
+    SCREEN_ROWS,
+    TerminalScreen,
+    expect_news_title,
+    expect_screen,
+    expect_user_listed,
+};
 pub use server_binary::{ServerBinaryError, VALIDATOR_SERVER_BINARY_ENV_VAR, ValidatorBackend};
@@ -0,0 +1,208 @@
+//! Terminal-state model for asserting on rendered `SynHX` output.
+//!
+//! Matching raw PTY bytes ties a test to colour codes, cursor motion, and
+//! column padding that `hx` is free to change. [`TerminalScreen`] instead
+//! feeds the byte stream through a VT100 parser and exposes the logical rows a
+//! user would see, so assertions such as "the user list shows alice" keep
+//! passing when only the presentation changes.
+//!
+//! The model only sees bytes drained through [`expect_screen`]; output already
+//! consumed by the regular-expression helpers in `harness` is not replayed.
+//! Tests should therefore switch to the screen helpers before issuing the
+//! command whose output they assert on.
+
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use expectrl::{Regex, session::OsSession as Session};
+use test_util::AnyError;
+
+/// Rows in the screen model, matching the PTY window `expectrl` opens.
+pub const SCREEN_ROWS: u16 = 24;
+/// Columns in the screen model, matching the PTY window `expectrl` opens.
+pub const SCREEN_COLUMNS: u16 = 80;
+/// Rows retained once they scroll off the top of the screen.
+const SCROLLBACK_ROWS: usize = 1000;
+/// Delay between polls of the PTY while waiting for the screen to settle.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// How long [`expect_screen`] waits for the screen to match.
+const SCREEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Logical view of a terminal driven by `hx` output.
+pub struct TerminalScreen {
+    parser: vt100::Parser,
+}
+
+impl Default for TerminalScreen {
+    fn default() -> Self { Self::new() }
+}
+
+impl TerminalScreen {
+    /// Create a blank screen matching the PTY size used for `hx` sessions.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            parser: vt100::Parser::new(SCREEN_ROWS, SCREEN_COLUMNS, SCROLLBACK_ROWS),
+        }
+    }
+
+    /// Apply raw terminal output to the screen.
+    pub fn feed(&mut self, bytes: &[u8]) { self.parser.process(bytes); }
+
+    /// Return every row written so far, scrollback first, with trailing
+    /// blanks removed and attributes discarded.
+    #[must_use]
+    pub fn rows(&mut self) -> Vec<String> {
+        let (_, width) = self.parser.screen().size();
+        self.parser.set_scrollback(usize::MAX);
+        let depth = self.parser.screen().scrollback();
+        let mut rows = Vec::new();
+        for offset in (1..=depth).rev() {
+            self.parser.set_scrollback(offset);
+            rows.extend(self.parser.screen().rows(0, width).next());
+        }
+        self.parser.set_scrollback(0);
+        rows.extend(self.parser.screen().rows(0, width));
+        while rows.last().is_some_and(|row| row.trim().is_empty()) {
+            rows.pop();
+        }
+        rows.iter().map(|row| row.trim_end().to_owned()).collect()
+    }
+
+    /// Return `true` when some row contains `text`, ignoring differences in
+    /// the amount of whitespace between words.
+    #[must_use]
+    pub fn contains_text(&mut self, text: &str) -> bool {
+        let wanted = collapse_whitespace(text);
+        self.rows()
+            .iter()
+            .any(|row| collapse_whitespace(row).contains(&wanted))
+    }
+
+    /// Return `true` when a user list entry for `name` is on screen.
+    ///
+    /// The name must appear as a whole whitespace-delimited word, so `al`
+    /// does not match a row listing `alice`.
+    #[must_use]
+    pub fn shows_user(&mut self, name: &str) -> bool {
+        self.rows()
+            .iter()
+            .any(|row| row.split_whitespace().any(|word| word == name))
+    }
+
+    /// Return `true` when a news listing shows an entry titled `title`.
+    #[must_use]
+    pub fn shows_news_title(&mut self, title: &str) -> bool { self.contains_text(title) }
+
+    /// Render the rows for failure messages.
+    #[must_use]
+    pub fn dump(&mut self) -> String { self.rows().join("\n") }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Drain `session` output into `screen` until `predicate` holds.
+///
+/// # Errors
+///
+/// Returns an error naming `context` and showing the rendered screen if the
+/// predicate does not hold within ten seconds, or if reading the PTY fails.
+pub fn expect_screen(
+    session: &mut Session,
+    screen: &mut TerminalScreen,
+    context: &str,
+    mut predicate: impl FnMut(&mut TerminalScreen) -> bool,
+) -> Result<(), AnyError> {
+    let deadline = Instant::now() + SCREEN_TIMEOUT;
+    loop {
+        let pending = session.check(Regex("(?s-u).+"))?;
+        screen.feed(pending.as_bytes());
+        if predicate(screen) {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(AnyError::msg(format!(
+                "{context}; screen:\n{}",
+                screen.dump()
+            )));
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
+
+/// Wait until the user list on `screen` shows `name`.
+///
+/// # Errors
+///
+/// Returns an error if the user does not appear within ten seconds.
+pub fn expect_user_listed(
+    session: &mut Session,
+    screen: &mut TerminalScreen,
+    name: &str,
+) -> Result<(), AnyError> {
+    let context = format!("user list did not show {name}");
+    expect_screen(session, screen, &context, |view| view.shows_user(name))
+}
+
+/// Wait until a news listing on `screen` shows `title`.
+///
+/// # Errors
+///
+/// Returns an error if the title does not appear within ten seconds.
+pub fn expect_news_title(
+    session: &mut Session,
+    screen: &mut TerminalScreen,
+    title: &str,
+) -> Result<(), AnyError> {
+    let context = format!("news listing did not show {title:?}");
+    expect_screen(session, screen, &context, |view| {
+        view.shows_news_title(title)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TerminalScreen;
+
+    fn screen_with(bytes: &[u8]) -> TerminalScreen {
+        let mut screen = TerminalScreen::new();
+        screen.feed(bytes);
+        screen
+    }
+
+    #[test]
+    fn attributes_and_padding_do_not_affect_rows() {
+        let mut screen = screen_with(b"\x1b[1;32malice\x1b[0m     \x1b[7midle\x1b[0m\r\n");
+
+        assert_eq!(screen.rows(), vec!["alice     idle".to_owned()]);
+        assert!(screen.shows_user("alice"));
+        assert!(!screen.shows_user("al"));
+        assert!(screen.contains_text("alice idle"));
+    }
+
+    #[test]
+    fn overwritten_text_is_not_reported() {
+        let mut screen = screen_with(b"loading...\r\x1b[2KFirst post\r\n");
+
+        assert!(screen.shows_news_title("First post"));
+        assert!(!screen.contains_text("loading"));
+    }
+
+    #[test]
+    fn rows_include_scrollback() {
+        let mut output = Vec::new();
+        for line in 0..40 {
+            output.extend_from_slice(format!("line {line}\r\n").as_bytes());
+        }
+        let mut screen = screen_with(&output);
+
+        let rows = screen.rows();
+        assert_eq!(rows.len(), 40);
+        assert_eq!(rows.first().map(String::as_str), Some("line 0"));
+        assert!(screen.contains_text("line 39"));
+    }
+}
@@ -0,0 +1,59 @@
+//! Manual pages and shell completions generated from `cli_defs::Cli`.
+//!
+//! The build script renders `mxd.1` into `OUT_DIR` for local use, but the
+//! file's location there depends on a build hash. Rendering it again here
+//! from the same definitions gives packaging a stable source and lets the
+//! completions share it.
+
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use clap_complete::Shell;
+use clap_mangen::Man;
+use cli_defs::Cli;
+
+/// Render the manual page for binary `name`.
+///
+/// # Errors
+///
+/// Returns an error if `roff` rendering fails.
+pub(crate) fn man_page(name: &'static str) -> Result<Vec<u8>> {
+    let mut page = Vec::new();
+    Man::new(Cli::command().name(name))
+        .render(&mut page)
+        .with_context(|| format!("failed to render man page for {name}"))?;
+    Ok(page)
+}
+
+/// Render the `shell` completion script for binary `name`.
+#[must_use]
+pub(crate) fn completion(shell: Shell, name: &'static str) -> Vec<u8> {
+    let mut command = Cli::command();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for generated documentation.
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn man_page_is_titled_after_the_binary() {
+        let page = String::from_utf8(man_page("mxd-wireframe-server").expect("render"))
+            .expect("roff is UTF-8");
+        assert!(page.contains(".TH mxd-wireframe-server 1"), "{page}");
+        assert!(page.contains("\\-\\-bind"), "{page}");
+    }
+
+    #[rstest]
+    #[case(Shell::Bash)]
+    #[case(Shell::Zsh)]
+    #[case(Shell::Fish)]
+    fn completions_offer_subcommands(#[case] shell: Shell) {
+        let script = String::from_utf8(completion(shell, "mxd")).expect("script is UTF-8");
+        assert!(script.contains("create-user"), "{script}");
+    }
+}
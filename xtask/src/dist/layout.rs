@@ -0,0 +1,206 @@
+//! File layout shared by the tarball and Debian package.
+//!
+//! Both artefacts unpack onto `/`, so one staging tree serves both: the
+//! tarball archives it as is and the Debian package adds a `DEBIAN/control`
+//! file. Keeping the paths here, away from the filesystem and `cargo`, lets
+//! the layout be tested without building anything.
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap_complete::Shell;
+
+/// Name of the package and of the binary the systemd unit starts.
+pub(crate) const PACKAGE_NAME: &str = "mxd";
+
+/// Binaries shipped in every artefact. Both parse `cli_defs::Cli`.
+pub(crate) const BINARIES: [&str; 2] = ["mxd", "mxd-wireframe-server"];
+
+/// Shells that receive generated completion scripts.
+pub(crate) const SHELLS: [Shell; 3] = [Shell::Bash, Shell::Zsh, Shell::Fish];
+
+/// Installed location of the systemd unit.
+pub(crate) const UNIT_PATH: &str = "lib/systemd/system/mxd.service";
+
+/// Description used for the Debian package summary.
+const DESCRIPTION: &str = "Marrakesh Express Hotline server";
+
+/// Debian architectures with a supported Rust target.
+const ARCHITECTURES: [(&str, &str); 2] = [
+    ("amd64", "x86_64-unknown-linux-gnu"),
+    ("arm64", "aarch64-unknown-linux-gnu"),
+];
+
+/// Identity of one release build.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Release {
+    /// Package version, taken from the `mxd` manifest unless overridden.
+    pub(crate) version: String,
+    /// Debian architecture name, such as `amd64`.
+    pub(crate) arch: String,
+}
+
+impl Release {
+    /// Directory under the output directory that holds the staging tree.
+    #[must_use]
+    pub(crate) fn stage_name(&self) -> String {
+        format!("{PACKAGE_NAME}_{}_linux_{}", self.version, self.arch)
+    }
+
+    /// File name of the compressed tarball.
+    #[must_use]
+    pub(crate) fn tarball_name(&self) -> String {
+        format!("{PACKAGE_NAME}-{}-linux-{}.tar.gz", self.version, self.arch)
+    }
+
+    /// File name of the Debian package.
+    #[must_use]
+    pub(crate) fn deb_name(&self) -> String {
+        format!("{PACKAGE_NAME}_{}_{}.deb", self.version, self.arch)
+    }
+
+    /// Render `DEBIAN/control` for a tree of `installed_kib` kibibytes.
+    #[must_use]
+    pub(crate) fn control(&self, maintainer: &str, installed_kib: u64) -> String {
+        format!(
+            "Package: {PACKAGE_NAME}\nVersion: {}\nArchitecture: {}\nMaintainer: \
+             {maintainer}\nInstalled-Size: {installed_kib}\nSection: net\nPriority: \
+             optional\nDepends: libc6\nDescription: {DESCRIPTION}\n",
+            self.version, self.arch
+        )
+    }
+}
+
+/// Rust target triple that builds binaries for the Debian `arch`.
+///
+/// # Errors
+///
+/// Returns an error listing the supported architectures when `arch` is not
+/// one of them.
+pub(crate) fn rust_target(arch: &str) -> Result<&'static str> {
+    if let Some((_, target)) = ARCHITECTURES.iter().find(|(name, _)| *name == arch) {
+        return Ok(target);
+    }
+    let known: Vec<_> = ARCHITECTURES.iter().map(|(name, _)| *name).collect();
+    bail!("unsupported architecture {arch:?}; expected one of {known:?}")
+}
+
+/// Installed location of binary `name`.
+#[must_use]
+pub(crate) fn binary_path(name: &str) -> PathBuf { PathBuf::from(format!("usr/bin/{name}")) }
+
+/// Installed location of the manual page for `name`.
+#[must_use]
+pub(crate) fn man_path(name: &str) -> PathBuf {
+    PathBuf::from(format!("usr/share/man/man1/{name}.1"))
+}
+
+/// Installed location of the `shell` completion script for `name`, following
+/// each shell's vendor directory convention.
+#[must_use]
+pub(crate) fn completion_path(shell: Shell, name: &str) -> PathBuf {
+    let path = match shell {
+        Shell::Zsh => format!("usr/share/zsh/vendor-completions/_{name}"),
+        Shell::Fish => format!("usr/share/fish/vendor_completions.d/{name}.fish"),
+        Shell::Elvish => format!("usr/share/elvish/lib/{name}.elv"),
+        Shell::PowerShell => format!("usr/share/powershell/{name}.ps1"),
+        _ => format!("usr/share/bash-completion/completions/{name}"),
+    };
+    PathBuf::from(path)
+}
+
+/// Read the `[package]` version from a `Cargo.toml` body.
+///
+/// Only the plain `version = "x.y.z"` form is recognised; the `mxd`
+/// manifest does not inherit its version from the workspace.
+#[must_use]
+pub(crate) fn package_version(manifest: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+            continue;
+        }
+        if !in_package {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() == "version" {
+            return value
+                .trim()
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .map(str::to_owned);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for artefact names and installed paths.
+    use rstest::rstest;
+
+    use super::*;
+
+    fn release() -> Release {
+        Release {
+            version: "0.1.0".to_owned(),
+            arch: "arm64".to_owned(),
+        }
+    }
+
+    #[rstest]
+    fn names_include_version_and_architecture() {
+        let release = release();
+        assert_eq!(release.stage_name(), "mxd_0.1.0_linux_arm64");
+        assert_eq!(release.tarball_name(), "mxd-0.1.0-linux-arm64.tar.gz");
+        assert_eq!(release.deb_name(), "mxd_0.1.0_arm64.deb");
+    }
+
+    #[rstest]
+    fn control_lists_required_fields() {
+        let control = release().control("Example <ops@example.org>", 42);
+        for field in [
+            "Package: mxd\n",
+            "Version: 0.1.0\n",
+            "Architecture: arm64\n",
+            "Maintainer: Example <ops@example.org>\n",
+            "Installed-Size: 42\n",
+        ] {
+            assert!(control.contains(field), "missing {field:?} in {control}");
+        }
+        assert!(control.ends_with('\n'));
+    }
+
+    #[rstest]
+    #[case("amd64", "x86_64-unknown-linux-gnu")]
+    #[case("arm64", "aarch64-unknown-linux-gnu")]
+    fn maps_architectures_to_targets(#[case] arch: &str, #[case] target: &str) {
+        assert_eq!(rust_target(arch).expect("supported"), target);
+    }
+
+    #[rstest]
+    fn rejects_unknown_architectures() {
+        let error = rust_target("riscv64").expect_err("riscv64 is unsupported");
+        assert!(error.to_string().contains("unsupported architecture"));
+    }
+
+    #[rstest]
+    #[case(Shell::Bash, "usr/share/bash-completion/completions/mxd")]
+    #[case(Shell::Zsh, "usr/share/zsh/vendor-completions/_mxd")]
+    #[case(Shell::Fish, "usr/share/fish/vendor_completions.d/mxd.fish")]
+    fn completions_use_vendor_directories(#[case] shell: Shell, #[case] expected: &str) {
+        assert_eq!(completion_path(shell, "mxd"), PathBuf::from(expected));
+    }
+
+    #[rstest]
+    fn reads_package_version_only() {
+        let manifest = "[workspace]\nversion = \"9.9.9\"\n\n[package]\nname = \"mxd\"\nversion = \
+                        \"0.1.0\"\n\n[dependencies]\nversion = \"1\"\n";
+        assert_eq!(package_version(manifest).as_deref(), Some("0.1.0"));
+        assert_eq!(package_version("[dependencies]\nversion = \"1\"\n"), None);
+    }
+}
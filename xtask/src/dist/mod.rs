@@ -0,0 +1,287 @@
+//! `cargo xtask dist`: build release binaries and package them.
+//!
+//! The task stages one tree holding the binaries, their manual pages, shell
+//! completions, and the systemd unit from `packaging/systemd`, then archives
+//! it as a tarball and builds a Debian package from it with `dpkg-deb`. The
+//! release workflow runs the same command, so packaging can be reproduced
+//! and debugged locally.
+
+mod docs;
+mod layout;
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, ensure};
+use layout::{BINARIES, PACKAGE_NAME, Release, SHELLS, UNIT_PATH};
+
+use crate::{run_cargo, workspace_root};
+
+/// systemd unit installed alongside the binaries.
+const SYSTEMD_UNIT: &str = include_str!("../../../packaging/systemd/mxd.service");
+
+/// Artefact formats `dist` can produce.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+pub(crate) enum Format {
+    /// Compressed tarball of the staging tree.
+    Tar,
+    /// Debian package built with `dpkg-deb`.
+    Deb,
+}
+
+/// Options for `cargo xtask dist`.
+#[derive(Debug, clap::Args)]
+pub(crate) struct DistArgs {
+    /// Debian architecture to package (`amd64` or `arm64`).
+    #[arg(long, default_value = "amd64")]
+    pub(crate) arch: String,
+    /// Rust target triple; defaults to the triple matching `--arch`.
+    #[arg(long)]
+    pub(crate) target: Option<String>,
+    /// Package version; defaults to the version in the `mxd` manifest.
+    #[arg(long = "package-version", value_name = "VERSION")]
+    pub(crate) version: Option<String>,
+    /// Artefact to produce; repeat to select several. Defaults to all.
+    #[arg(long = "format", value_enum)]
+    pub(crate) formats: Vec<Format>,
+    /// Directory receiving the staging tree and artefacts.
+    #[arg(long, default_value = "dist")]
+    pub(crate) out_dir: PathBuf,
+    /// Package binaries already built for `--target` instead of building.
+    #[arg(long)]
+    pub(crate) skip_build: bool,
+    /// `Maintainer` field of the Debian package.
+    #[arg(long, default_value = "mxd maintainers")]
+    pub(crate) maintainer: String,
+}
+
+/// Build, stage, and package a release.
+///
+/// # Errors
+///
+/// Returns an error if the build fails, if the version or architecture
+/// cannot be resolved, or if staging or archiving fails.
+pub(crate) fn run(args: &DistArgs) -> Result<()> {
+    let root = workspace_root();
+    let release = Release {
+        version: resolve_version(&root, args.version.as_deref())?,
+        arch: args.arch.clone(),
+    };
+    let target = match &args.target {
+        Some(target) => target.clone(),
+        None => layout::rust_target(&args.arch)?.to_owned(),
+    };
+    if !args.skip_build {
+        run_cargo(&build_args(&target))?;
+    }
+    let out_dir = root.join(&args.out_dir);
+    let stage = out_dir.join(release.stage_name());
+    let binaries = root.join("target").join(&target).join("release");
+    stage_tree(&stage, &binaries)?;
+
+    let formats: BTreeSet<Format> = if args.formats.is_empty() {
+        [Format::Tar, Format::Deb].into()
+    } else {
+        args.formats.iter().copied().collect()
+    };
+    // Archive before `DEBIAN/` is added so the tarball holds only files
+    // destined for `/`.
+    for format in formats {
+        let artefact = match format {
+            Format::Tar => {
+                let path = out_dir.join(release.tarball_name());
+                archive_tar(&stage, &path)?;
+                path
+            }
+            Format::Deb => {
+                let path = out_dir.join(release.deb_name());
+                build_deb(&stage, &path, &release, &args.maintainer)?;
+                path
+            }
+        };
+        writeln!(io::stdout().lock(), "{}", artefact.display())?;
+    }
+    Ok(())
+}
+
+/// Arguments building every shipped binary for `target` in release mode.
+#[must_use]
+pub(crate) fn build_args(target: &str) -> Vec<String> {
+    let mut args = vec![
+        "build".to_owned(),
+        "--release".to_owned(),
+        "-p".to_owned(),
+        PACKAGE_NAME.to_owned(),
+        "--target".to_owned(),
+        target.to_owned(),
+    ];
+    for binary in BINARIES {
+        args.push("--bin".to_owned());
+        args.push(binary.to_owned());
+    }
+    args
+}
+
+fn resolve_version(root: &Path, requested: Option<&str>) -> Result<String> {
+    if let Some(version) = requested {
+        return Ok(version.to_owned());
+    }
+    let manifest_path = root.join("Cargo.toml");
+    let manifest = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("failed to read {}", manifest_path.display()))?;
+    layout::package_version(&manifest)
+        .with_context(|| format!("no [package] version in {}", manifest_path.display()))
+}
+
+/// Recreate `stage` with binaries from `binaries` and generated files.
+fn stage_tree(stage: &Path, binaries: &Path) -> Result<()> {
+    if stage.exists() {
+        fs::remove_dir_all(stage)
+            .with_context(|| format!("failed to clear {}", stage.display()))?;
+    }
+    for binary in BINARIES {
+        let source = binaries.join(binary);
+        let dest = stage.join(layout::binary_path(binary));
+        create_parent(&dest)?;
+        fs::copy(&source, &dest).with_context(|| format!("failed to copy {}", source.display()))?;
+        write_file(
+            &stage.join(layout::man_path(binary)),
+            &docs::man_page(binary)?,
+        )?;
+    }
+    for shell in SHELLS {
+        for binary in BINARIES {
+            let script = docs::completion(shell, binary);
+            write_file(&stage.join(layout::completion_path(shell, binary)), &script)?;
+        }
+    }
+    write_file(&stage.join(UNIT_PATH), SYSTEMD_UNIT.as_bytes())
+}
+
+fn archive_tar(stage: &Path, dest: &Path) -> Result<()> {
+    let mut command = Command::new("tar");
+    command
+        .arg("--owner=0")
+        .arg("--group=0")
+        .arg("-czf")
+        .arg(dest)
+        .arg("-C")
+        .arg(stage)
+        .arg(".");
+    run_tool(&mut command)
+}
+
+fn build_deb(stage: &Path, dest: &Path, release: &Release, maintainer: &str) -> Result<()> {
+    let installed_kib = tree_size(stage)?.div_ceil(1024);
+    write_file(
+        &stage.join("DEBIAN/control"),
+        release.control(maintainer, installed_kib).as_bytes(),
+    )?;
+    let mut command = Command::new("dpkg-deb");
+    command
+        .arg("--root-owner-group")
+        .arg("--build")
+        .arg(stage)
+        .arg(dest);
+    run_tool(&mut command)
+}
+
+/// Total size in bytes of the regular files under `dir`.
+fn tree_size(dir: &Path) -> Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let metadata = fs::metadata(&path)?;
+        total += if metadata.is_dir() {
+            tree_size(&path)?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(total)
+}
+
+fn create_parent(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    create_parent(path)?;
+    fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn run_tool(command: &mut Command) -> Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let status = command
+        .status()
+        .with_context(|| format!("failed to launch {program}"))?;
+    ensure!(status.success(), "`{program}` {status}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for staging and build arguments.
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn build_args_select_every_binary() {
+        assert_eq!(
+            build_args("aarch64-unknown-linux-gnu"),
+            [
+                "build",
+                "--release",
+                "-p",
+                "mxd",
+                "--target",
+                "aarch64-unknown-linux-gnu",
+                "--bin",
+                "mxd",
+                "--bin",
+                "mxd-wireframe-server",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn unit_starts_the_packaged_binary() {
+        assert!(SYSTEMD_UNIT.contains("ExecStart=/usr/bin/mxd\n"));
+        assert!(SYSTEMD_UNIT.contains("[Install]"));
+    }
+
+    #[rstest]
+    fn stages_binaries_docs_completions_and_unit() {
+        let scratch = std::env::temp_dir().join(format!("xtask-dist-{}", std::process::id()));
+        let binaries = scratch.join("release");
+        for binary in BINARIES {
+            write_file(&binaries.join(binary), b"#!/bin/sh\n").expect("fake binary");
+        }
+        let stage = scratch.join("stage");
+        write_file(&stage.join("stale"), b"old").expect("stale file");
+
+        stage_tree(&stage, &binaries).expect("stage");
+
+        assert!(!stage.join("stale").exists());
+        for path in [
+            PathBuf::from("usr/bin/mxd-wireframe-server"),
+            PathBuf::from("usr/share/man/man1/mxd.1"),
+            PathBuf::from("usr/share/zsh/vendor-completions/_mxd"),
+            PathBuf::from(UNIT_PATH),
+        ] {
+            assert!(stage.join(&path).is_file(), "missing {}", path.display());
+        }
+        assert!(tree_size(&stage).expect("size") > 0);
+        fs::remove_dir_all(&scratch).expect("clean up");
+    }
+}
@@ -0,0 +1,67 @@
+//! Workspace automation tasks for mxd.
+//!
+//! Invoke through the `cargo xtask` alias defined in `.cargo/config.toml`,
+//! for example `cargo xtask check-matrix` or `cargo xtask dist`.
+
+mod dist;
+mod matrix;
+
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result, ensure};
+use clap::{Parser, Subcommand};
+
+/// Command-line entry point for workspace tasks.
+#[derive(Debug, Parser)]
+#[command(name = "xtask", about = "Workspace automation tasks for mxd")]
+struct Cli {
+    #[command(subcommand)]
+    task: Task,
+}
+
+/// Available tasks.
+#[derive(Debug, Subcommand)]
+enum Task {
+    /// Build and unit-test every supported feature combination.
+    CheckMatrix(matrix::CheckMatrixArgs),
+    /// Build release binaries and package them as a tarball and `.deb`.
+    Dist(dist::DistArgs),
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().task {
+        Task::CheckMatrix(args) => matrix::run(&args),
+        Task::Dist(args) => dist::run(&args),
+    }
+}
+
+/// Run the `cargo` that launched the task from the workspace root.
+///
+/// # Errors
+///
+/// Returns an error if `cargo` cannot be launched or exits unsuccessfully.
+pub(crate) fn run_cargo(args: &[String]) -> Result<()> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+    writeln!(io::stderr(), "==> cargo {}", args.join(" "))?;
+    let status = Command::new(cargo)
+        .args(args)
+        .current_dir(workspace_root())
+        .status()
+        .context("failed to launch cargo")?;
+    ensure!(status.success(), "`cargo {}` {status}", args.join(" "));
+    Ok(())
+}
+
+/// Directory holding the workspace `Cargo.toml`.
+pub(crate) fn workspace_root() -> PathBuf {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    manifest_dir
+        .parent()
+        .map_or_else(|| manifest_dir.to_path_buf(), Path::to_path_buf)
+}
@@ -0,0 +1,250 @@
+//! `cargo xtask check-matrix`: build and unit-test each feature combination.
+//!
+//! The `sqlite` and `postgres` backends are mutually exclusive, so no single
+//! `cargo` invocation covers the whole tree; the `lint` feature only silences
+//! the guard that enforces the exclusion. This task runs one build and one
+//! unit-test pass per supported combination so code that compiles under the
+//! default features but not under another is caught before CI.
+
+use std::io::{self, Write};
+
+use anyhow::{Context, Result, bail};
+
+use crate::run_cargo;
+
+/// Package whose feature combinations are checked.
+const PACKAGE: &str = "mxd";
+
+/// Target directory for postgres builds, shared with the `Makefile`, so
+/// switching backends does not invalidate the sqlite build cache.
+const POSTGRES_TARGET_DIR: &str = "target/postgres";
+
+/// A feature combination checked by the matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct FeatureSet {
+    /// Name used with `--only` and in reports.
+    pub(crate) name: &'static str,
+    /// Whether the package's default features stay enabled.
+    pub(crate) default_features: bool,
+    /// Features enabled on top of (or instead of) the defaults.
+    pub(crate) features: &'static [&'static str],
+    /// Separate target directory for this combination, if any.
+    pub(crate) target_dir: Option<&'static str>,
+}
+
+/// Every combination the project supports.
+pub(crate) static MATRIX: [FeatureSet; 6] = [
+    FeatureSet {
+        name: "sqlite",
+        default_features: true,
+        features: &["sqlite", "test-support", "test-util/legacy-networking"],
+        target_dir: None,
+    },
+    FeatureSet {
+        name: "postgres",
+        default_features: false,
+        features: &[
+            "postgres",
+            "legacy-networking",
+            "test-support",
+            "test-util/legacy-networking",
+        ],
+        target_dir: Some(POSTGRES_TARGET_DIR),
+    },
+    FeatureSet {
+        name: "wireframe-only-sqlite",
+        default_features: false,
+        features: &["sqlite", "toml", "test-support"],
+        target_dir: None,
+    },
+    FeatureSet {
+        name: "wireframe-only-postgres",
+        default_features: false,
+        features: &["postgres", "test-support"],
+        target_dir: Some(POSTGRES_TARGET_DIR),
+    },
+    FeatureSet {
+        name: "config-formats",
+        default_features: true,
+        features: &["sqlite", "json5", "yaml", "test-support"],
+        target_dir: None,
+    },
+    FeatureSet {
+        name: "s3-storage",
+        default_features: true,
+        features: &["sqlite", "s3", "test-support"],
+        target_dir: None,
+    },
+    FeatureSet {
+        name: "profiling",
+        default_features: true,
+        features: &["sqlite", "profiling", "test-support"],
+        target_dir: None,
+    },
+];
+
+impl FeatureSet {
+    /// Arguments selecting this combination for any `cargo` subcommand.
+    fn feature_args(&self) -> Vec<String> {
+        let mut args = vec!["-p".to_owned(), PACKAGE.to_owned()];
+        if !self.default_features {
+            args.push("--no-default-features".to_owned());
+        }
+        if !self.features.is_empty() {
+            args.push("--features".to_owned());
+            args.push(self.features.join(","));
+        }
+        if let Some(dir) = self.target_dir {
+            args.push("--target-dir".to_owned());
+            args.push(dir.to_owned());
+        }
+        args
+    }
+
+    /// Arguments for building every target under this combination.
+    #[must_use]
+    pub(crate) fn build_args(&self) -> Vec<String> {
+        let mut args = vec!["build".to_owned(), "--all-targets".to_owned()];
+        args.extend(self.feature_args());
+        args
+    }
+
+    /// Arguments for running the library's unit tests.
+    #[must_use]
+    pub(crate) fn test_args(&self) -> Vec<String> {
+        let mut args = vec!["test".to_owned(), "--lib".to_owned()];
+        args.extend(self.feature_args());
+        args
+    }
+}
+
+/// Options for `cargo xtask check-matrix`.
+#[derive(Debug, clap::Args)]
+pub(crate) struct CheckMatrixArgs {
+    /// Check only the named combination; repeat to select several.
+    #[arg(long = "only", value_name = "NAME")]
+    pub(crate) only: Vec<String>,
+    /// Build each combination without running its unit tests.
+    #[arg(long)]
+    pub(crate) build_only: bool,
+    /// Print the combinations and exit.
+    #[arg(long)]
+    pub(crate) list: bool,
+}
+
+/// Run the matrix, reporting every failing combination rather than stopping
+/// at the first.
+///
+/// # Errors
+///
+/// Returns an error naming each combination that failed to build or test, or
+/// if `--only` names an unknown combination.
+pub(crate) fn run(args: &CheckMatrixArgs) -> Result<()> {
+    let selected = select(&args.only)?;
+    if args.list {
+        let mut stdout = io::stdout().lock();
+        for set in selected {
+            writeln!(stdout, "{}\t{}", set.name, set.feature_args().join(" "))?;
+        }
+        return Ok(());
+    }
+    let mut failures = Vec::new();
+    for set in selected {
+        if let Err(error) = check(set, args.build_only) {
+            failures.push(format!("{}: {error:#}", set.name));
+        }
+    }
+    if failures.is_empty() {
+        return Ok(());
+    }
+    bail!("feature matrix failed:\n  {}", failures.join("\n  "))
+}
+
+/// Resolve `--only` names against [`MATRIX`]; no names selects everything.
+///
+/// # Errors
+///
+/// Returns an error listing the valid names when one is not recognised.
+pub(crate) fn select(only: &[String]) -> Result<Vec<&'static FeatureSet>> {
+    if only.is_empty() {
+        return Ok(MATRIX.iter().collect());
+    }
+    only.iter()
+        .map(|name| {
+            MATRIX
+                .iter()
+                .find(|set| set.name == name.as_str())
+                .with_context(|| {
+                    let known: Vec<_> = MATRIX.iter().map(|set| set.name).collect();
+                    format!("unknown combination {name:?}; expected one of {known:?}")
+                })
+        })
+        .collect()
+}
+
+fn check(set: &FeatureSet, build_only: bool) -> Result<()> {
+    run_cargo(&set.build_args())?;
+    if !build_only {
+        run_cargo(&set.test_args())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    //! Tests for matrix selection and argument construction.
+    use std::collections::HashSet;
+
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn names_are_unique() {
+        let names: HashSet<_> = MATRIX.iter().map(|set| set.name).collect();
+        assert_eq!(names.len(), MATRIX.len());
+    }
+
+    #[rstest]
+    fn every_combination_picks_exactly_one_backend() {
+        for set in &MATRIX {
+            let backends = set
+                .features
+                .iter()
+                .filter(|feature| matches!(**feature, "sqlite" | "postgres"))
+                .count();
+            assert_eq!(backends, 1, "{}", set.name);
+        }
+    }
+
+    #[rstest]
+    fn postgres_args_disable_defaults_and_use_separate_target() {
+        let postgres = select(&["postgres".to_owned()]).expect("select postgres");
+        let args = postgres.first().expect("one combination").test_args();
+        assert_eq!(
+            args,
+            [
+                "test",
+                "--lib",
+                "-p",
+                "mxd",
+                "--no-default-features",
+                "--features",
+                "postgres,legacy-networking,test-support,test-util/legacy-networking",
+                "--target-dir",
+                "target/postgres",
+            ]
+        );
+    }
+
+    #[rstest]
+    fn empty_selection_checks_everything() {
+        assert_eq!(select(&[]).expect("select all").len(), MATRIX.len());
+    }
+
+    #[rstest]
+    fn unknown_names_are_rejected() {
+        let error = select(&["tls".to_owned()]).expect_err("tls is not a combination");
+        assert!(error.to_string().contains("unknown combination \"tls\""));
+    }
+}